@@ -7,7 +7,7 @@ use haze::config::Config;
 use haze::state::StateManager;
 use haze::consensus::ConsensusEngine;
 use haze::crypto::KeyPair;
-use haze::types::{Block, BlockHeader, Address};
+use haze::types::{Block, BlockHeader, Address, Transaction, TxEnvelope, PROTOCOL_VERSION, STATE_VERSION};
 use std::sync::Arc;
 
 fn config_with_temp_db() -> (tempfile::TempDir, Config) {
@@ -29,8 +29,11 @@ fn empty_block_height_1(validator: Address) -> Block {
         state_root: [0u8; 32],
         wave_number: 0,
         committee_id: 0,
+        protocol_version: PROTOCOL_VERSION,
+        state_version: STATE_VERSION,
+        signals: 0,
     };
-    header.hash = header.compute_hash();
+    header.hash = header.compute_hash(&haze::types::network_id(0, &[]));
     Block {
         header,
         transactions: vec![],
@@ -44,6 +47,24 @@ fn bench_compute_state_root(c: &mut Criterion) {
     c.bench_function("compute_state_root_empty", |b| {
         b.iter(|| black_box(state.compute_state_root()))
     });
+
+    // Root after one account write on top of 10k accounts (only one bucket is rehashed)
+    let (_temp, config) = config_with_temp_db();
+    let state = StateManager::new(&config).unwrap();
+    for i in 0..10_000u32 {
+        let mut address = [0u8; 32];
+        address[..4].copy_from_slice(&i.to_le_bytes());
+        state.create_test_account(address, 1_000, 0);
+    }
+    state.compute_state_root();
+    let mut balance = 0u64;
+    c.bench_function("compute_state_root_10k_accounts_one_change", |b| {
+        b.iter(|| {
+            balance += 1;
+            state.create_test_account([7u8; 32], balance, 0);
+            black_box(state.compute_state_root())
+        })
+    });
 }
 
 fn bench_apply_block(c: &mut Criterion) {
@@ -83,5 +104,51 @@ fn bench_process_block(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, bench_compute_state_root, bench_apply_block, bench_process_block);
+/// Transactions in the large-block hashing benchmarks
+const LARGE_BLOCK_TXS: u64 = 5_000;
+
+fn large_block_transactions() -> Vec<Transaction> {
+    (0..LARGE_BLOCK_TXS)
+        .map(|nonce| Transaction::Transfer {
+            from: [1u8; 32],
+            to: [2u8; 32],
+            amount: 1000,
+            fee: 10,
+            nonce,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![0u8; 64],
+        })
+        .collect()
+}
+
+/// Hashing done while a block moves through the pool: admission, merkle root, pool removal.
+/// Uncached, every step rehashes; with `TxEnvelope` each transaction is hashed once.
+fn bench_tx_hash_caching(c: &mut Criterion) {
+    let transactions = large_block_transactions();
+    c.bench_function("tx_hash_uncached_large_block", |b| {
+        b.iter(|| {
+            for _pass in 0..3 {
+                for tx in &transactions {
+                    black_box(tx.hash());
+                }
+            }
+        })
+    });
+    c.bench_function("tx_hash_envelope_large_block", |b| {
+        b.iter_with_setup(
+            || transactions.iter().cloned().map(TxEnvelope::new).collect::<Vec<_>>(),
+            |envelopes| {
+                for _pass in 0..3 {
+                    for tx in &envelopes {
+                        black_box(tx.hash());
+                    }
+                }
+                envelopes
+            },
+        )
+    });
+}
+
+criterion_group!(benches, bench_compute_state_root, bench_apply_block, bench_process_block, bench_tx_hash_caching);
 criterion_main!(benches);
@@ -36,6 +36,7 @@ function Create-NodeConfig {
     
     $Config = @{
         node_id = "node-$NodeId"
+        chain_id = 4242
         network = @{
             listen_addr = "/ip4/127.0.0.1/tcp/$NetworkPort"
             bootstrap_nodes = ($BootstrapNodes | ConvertFrom-Json)
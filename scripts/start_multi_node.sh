@@ -36,6 +36,7 @@ create_node_config() {
     cat > "$config_file" <<EOF
 {
   "node_id": "node-${node_id}",
+  "chain_id": 4242,
   "network": {
     "listen_addr": "/ip4/127.0.0.1/tcp/${network_port}",
     "bootstrap_nodes": ${bootstrap_nodes},
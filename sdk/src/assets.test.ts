@@ -64,6 +64,21 @@ describe('MistbornAsset', () => {
       expect(tx.nonce).toBe(0);
     });
 
+    it('uses the given nonce', () => {
+      const owner = bytes32(1);
+      const assetId = MistbornAsset.createAssetId('asset1');
+      const tx = MistbornAsset.createCreateTransaction(
+        assetId,
+        owner,
+        DensityLevel.Ethereal,
+        {},
+        [],
+        undefined,
+        4
+      );
+      expect(tx.nonce).toBe(4);
+    });
+
     it('data.owner equals from', () => {
       const owner = bytes32(1);
       const assetId = MistbornAsset.createAssetId('asset1');
@@ -143,6 +158,24 @@ describe('MistbornAsset', () => {
     });
   });
 
+  describe('private fields', () => {
+    it('commits to salt || plaintext and reveals with hex salts', () => {
+      const salt = new Uint8Array(16).fill(7);
+      expect(MistbornAsset.commitPrivateField('legendary sword', salt)).toBe(
+        '98f464158c4dfac87fde0b48eaa81a7ac7b3176b82a6f204deaceb6da6e72763'
+      );
+      const tx = MistbornAsset.createRevealTransaction(
+        MistbornAsset.createAssetId('chest'),
+        bytes32(1),
+        { loot: 'legendary sword' },
+        { loot: salt }
+      );
+      expect(tx.action).toBe(AssetAction.Reveal);
+      expect(tx.data.metadata.loot).toBe('legendary sword');
+      expect(JSON.parse(tx.data.metadata._salts)).toEqual({ loot: '07'.repeat(16) });
+    });
+  });
+
   describe('assetIdToHex / hexToAssetId', () => {
     it('roundtrip preserves asset id', () => {
       const id = MistbornAsset.createAssetId('roundtrip');
@@ -56,7 +56,8 @@ export class MistbornAsset {
     density: DensityLevel,
     metadata: Record<string, string>,
     attributes: Attribute[] = [],
-    gameId?: string
+    gameId?: string,
+    nonce: number = 0
   ): MistbornAssetTransaction {
     const data = this.createAssetData(owner, density, metadata, attributes, gameId);
     
@@ -67,7 +68,7 @@ export class MistbornAsset {
       asset_id: assetId,
       data,
       fee: 0n,
-      nonce: 0,
+      nonce,
       signature: new Uint8Array(0), // Will be set when signing
     };
   }
@@ -79,7 +80,8 @@ export class MistbornAsset {
     assetId: Hash,
     owner: Address,
     metadata: Record<string, string>,
-    attributes: Attribute[] = []
+    attributes: Attribute[] = [],
+    nonce: number = 0
   ): MistbornAssetTransaction {
     const data: AssetData = {
       density: DensityLevel.Ethereal, // Will be updated by backend
@@ -95,7 +97,7 @@ export class MistbornAsset {
       asset_id: assetId,
       data,
       fee: 0n,
-      nonce: 0,
+      nonce,
       signature: new Uint8Array(0), // Will be set when signing
     };
   }
@@ -108,7 +110,8 @@ export class MistbornAsset {
     owner: Address,
     newDensity: DensityLevel,
     additionalMetadata: Record<string, string> = {},
-    additionalAttributes: Attribute[] = []
+    additionalAttributes: Attribute[] = [],
+    nonce: number = 0
   ): MistbornAssetTransaction {
     const data: AssetData = {
       density: newDensity,
@@ -124,7 +127,7 @@ export class MistbornAsset {
       asset_id: assetId,
       data,
       fee: 0n,
-      nonce: 0,
+      nonce,
       signature: new Uint8Array(0), // Will be set when signing
     };
   }
@@ -135,7 +138,8 @@ export class MistbornAsset {
   static createEvaporateTransaction(
     assetId: Hash,
     owner: Address,
-    newDensity: DensityLevel
+    newDensity: DensityLevel,
+    nonce: number = 0
   ): MistbornAssetTransaction {
     const data: AssetData = {
       density: newDensity,
@@ -151,7 +155,49 @@ export class MistbornAsset {
       asset_id: assetId,
       data,
       fee: 0n,
-      nonce: 0,
+      nonce,
+      signature: new Uint8Array(0), // Will be set when signing
+    };
+  }
+
+  /**
+   * Commitment to a private metadata value: hex sha256(salt || plaintext).
+   * Use a random salt of at least 16 bytes and keep it for the reveal.
+   */
+  static commitPrivateField(plaintext: string, salt: Uint8Array): string {
+    const plain = Buffer.from(plaintext, 'utf-8');
+    return bytesToHex(sha256(Buffer.concat([Buffer.from(salt), plain])));
+  }
+
+  /**
+   * Create a transaction revealing committed private fields
+   */
+  static createRevealTransaction(
+    assetId: Hash,
+    owner: Address,
+    plaintexts: Record<string, string>,
+    salts: Record<string, Uint8Array>,
+    nonce: number = 0
+  ): MistbornAssetTransaction {
+    const saltsHex: Record<string, string> = {};
+    for (const [field, salt] of Object.entries(salts)) {
+      saltsHex[field] = bytesToHex(salt);
+    }
+    const data: AssetData = {
+      density: DensityLevel.Ethereal, // Ignored by the backend
+      metadata: { ...plaintexts, _salts: JSON.stringify(saltsHex) },
+      attributes: [],
+      owner,
+    };
+
+    return {
+      type: 'MistbornAsset',
+      from: owner,
+      action: AssetAction.Reveal,
+      asset_id: assetId,
+      data,
+      fee: 0n,
+      nonce,
       signature: new Uint8Array(0), // Will be set when signing
     };
   }
@@ -161,9 +207,10 @@ export class MistbornAsset {
    */
   static async sign(
     transaction: MistbornAssetTransaction,
-    keyPair: KeyPair
+    keyPair: KeyPair,
+    network?: Hash
   ): Promise<MistbornAssetTransaction> {
-    const signature = await signTransaction(transaction, keyPair);
+    const signature = await signTransaction(transaction, keyPair, network);
     return {
       ...transaction,
       signature,
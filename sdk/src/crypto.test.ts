@@ -2,9 +2,12 @@
  * Tests for getTransactionDataForSigning (canonical payload for node verification)
  */
 import { describe, it, expect } from 'vitest';
-import { getTransactionDataForSigning } from './crypto';
+import { getTransactionDataForSigning, networkId } from './crypto';
 import { AssetAction, DensityLevel } from './types';
 
+/** Network id prefix before the variant tag */
+const NET = 32;
+
 function bytes32(n: number): Uint8Array {
   const a = new Uint8Array(32);
   a.fill(n & 0xff);
@@ -12,6 +15,57 @@ function bytes32(n: number): Uint8Array {
 }
 
 describe('getTransactionDataForSigning', () => {
+  describe('network id', () => {
+    it('matches the Rust network id of chain 0', () => {
+      expect(Buffer.from(networkId(0)).toString('hex')).toBe(
+        '2dd8af478b6de3abd3c8a0b4aa571e211d004a679b9e3e98295d2e29b215f532'
+      );
+    });
+
+    it('prefixes the payload with the network id of the transaction chain', () => {
+      const tx = {
+        type: 'Transfer' as const,
+        from: bytes32(1),
+        to: bytes32(2),
+        amount: 1n,
+        fee: 0n,
+        nonce: 0,
+        signature: new Uint8Array(0),
+      };
+      expect(getTransactionDataForSigning(tx).subarray(0, NET)).toEqual(networkId(0));
+      expect(getTransactionDataForSigning({ ...tx, chain_id: 7 }).subarray(0, NET)).toEqual(networkId(7));
+    });
+
+    it('commits to the genesis allocations in address order', () => {
+      const genesis = [
+        { address: bytes32(2), amount: 300n },
+        { address: bytes32(1), amount: 500n },
+      ];
+      const id = networkId(7, genesis);
+      expect(Buffer.from(id).toString('hex')).toBe(
+        '10074addc32043298dc104eb20997a3b3132b5fcb9d1c1d70da3961709211940'
+      );
+      expect(id).not.toEqual(networkId(7));
+      expect(networkId(7, [...genesis].reverse())).toEqual(id);
+      expect(networkId(7, [genesis[0], { ...genesis[1], amount: 501n }])).not.toEqual(id);
+    });
+
+    it('prefixes the payload with an explicit network id', () => {
+      const tx = {
+        type: 'Transfer' as const,
+        from: bytes32(1),
+        to: bytes32(2),
+        amount: 1n,
+        fee: 0n,
+        nonce: 0,
+        chain_id: 7,
+        signature: new Uint8Array(0),
+      };
+      const network = networkId(7, [{ address: bytes32(3), amount: 1n }]);
+      expect(getTransactionDataForSigning(tx, network).subarray(0, NET)).toEqual(network);
+    });
+  });
+
   describe('Transfer', () => {
     it('produces payload starting with Transfer tag', () => {
       const payload = getTransactionDataForSigning({
@@ -23,7 +77,7 @@ describe('getTransactionDataForSigning', () => {
         nonce: 0,
         signature: new Uint8Array(0),
       });
-      expect(new TextDecoder().decode(payload.subarray(0, 8))).toBe('Transfer');
+      expect(new TextDecoder().decode(payload.subarray(NET, NET + 8))).toBe('Transfer');
     });
 
     it('payload length is 8 + 32 + 32 + 8 + 8 + 8', () => {
@@ -36,7 +90,7 @@ describe('getTransactionDataForSigning', () => {
         nonce: 0,
         signature: new Uint8Array(0),
       });
-      expect(payload.length).toBe(8 + 32 + 32 + 8 + 8 + 8);
+      expect(payload.length).toBe(NET + 8 + 32 + 32 + 8 + 8 + 8);
     });
 
     it('changing nonce changes payload', () => {
@@ -81,7 +135,7 @@ describe('getTransactionDataForSigning', () => {
         nonce: 1,
         signature: new Uint8Array(0),
       });
-      expect(new TextDecoder().decode(payload.subarray(0, 5))).toBe('Stake');
+      expect(new TextDecoder().decode(payload.subarray(NET, NET + 5))).toBe('Stake');
     });
 
     it('changing nonce changes payload', () => {
@@ -115,7 +169,7 @@ describe('getTransactionDataForSigning', () => {
         nonce: 0,
         signature: new Uint8Array(0),
       });
-      expect(new TextDecoder().decode(payload.subarray(0, CONTRACTCALL_TAG_LEN))).toBe('ContractCall');
+      expect(new TextDecoder().decode(payload.subarray(NET, NET + CONTRACTCALL_TAG_LEN))).toBe('ContractCall');
     });
 
     it('includes from, fee, nonce in payload', () => {
@@ -132,7 +186,7 @@ describe('getTransactionDataForSigning', () => {
         signature: new Uint8Array(0),
       });
       // After "ContractCall" (12 bytes) comes from (32 bytes)
-      const fromInPayload = payload.subarray(CONTRACTCALL_TAG_LEN, CONTRACTCALL_TAG_LEN + 32);
+      const fromInPayload = payload.subarray(NET + CONTRACTCALL_TAG_LEN, NET + CONTRACTCALL_TAG_LEN + 32);
       expect(fromInPayload).toEqual(from);
     });
   });
@@ -154,7 +208,7 @@ describe('getTransactionDataForSigning', () => {
         nonce: 0,
         signature: new Uint8Array(0),
       });
-      expect(new TextDecoder().decode(payload.subarray(0, 13))).toBe('MistbornAsset');
+      expect(new TextDecoder().decode(payload.subarray(NET, NET + 13))).toBe('MistbornAsset');
     });
 
     it('changing nonce changes payload', () => {
@@ -215,8 +269,8 @@ describe('getTransactionDataForSigning', () => {
         nonce: 0,
         signature: new Uint8Array(0),
       });
-      // After "MistbornAsset"(13) + from(32) = 45, next byte is action
-      expect(payload[45]).toBe(0);
+      // After network id(32) + "MistbornAsset"(13) + from(32) = 77, next byte is action
+      expect(payload[NET + 45]).toBe(0);
     });
 
     it('Merge action includes other_asset_id in payload when in metadata', () => {
@@ -5,7 +5,7 @@
 
 import * as ed25519 from '@noble/ed25519';
 import { Address, Hash, Transaction, AssetAction, DensityLevel } from './types';
-import { bytesToHex, hexToBytes } from './utils';
+import { bytesToHex, hexToBytes, sha256 } from './utils';
 
 /**
  * Key pair for signing transactions
@@ -120,9 +120,10 @@ export class KeyPair {
  */
 export async function signTransaction(
   transaction: Transaction,
-  keyPair: KeyPair
+  keyPair: KeyPair,
+  network?: Hash
 ): Promise<Uint8Array> {
-  const message = getTransactionDataForSigning(transaction);
+  const message = getTransactionDataForSigning(transaction, network);
   return await keyPair.sign(message);
 }
 
@@ -158,6 +159,9 @@ function actionToByte(action: AssetAction): number {
     case AssetAction.Evaporate: return 3;
     case AssetAction.Merge: return 4;
     case AssetAction.Split: return 5;
+    case AssetAction.Reveal: return 6;
+    case AssetAction.Breed: return 7;
+    case AssetAction.Upgrade: return 8;
   }
 }
 
@@ -180,10 +184,52 @@ function appendChainFields(parts: Uint8Array[], chainId?: number, validUntilHeig
   }
 }
 
+/** Genesis supply committed to by the network id (must match Rust `tokenomics::INITIAL_SUPPLY`). */
+const INITIAL_SUPPLY = 1_000_000_000_000_000_000n;
+
+/** Genesis allocation of a network's governance token, committed to by the network id */
+export interface GenesisAllocation {
+  address: Address;
+  amount: bigint;
+}
+
+/**
+ * Network id of a chain: sha256("haze_genesis" || chain_id u64 LE || initial supply u64 LE
+ * || for each genesis allocation, sorted by address: address || amount u64 LE).
+ * Must match Rust `types::network_id`.
+ */
+export function networkId(chainId: number = 0, genesis: GenesisAllocation[] = []): Hash {
+  const allocations = [...genesis].sort(
+    (a, b) => compareBytes(a.address, b.address) || (a.amount < b.amount ? -1 : a.amount > b.amount ? 1 : 0)
+  );
+  return sha256(concatBytes([
+    new TextEncoder().encode('haze_genesis'),
+    u64le(BigInt(chainId)),
+    u64le(INITIAL_SUPPLY),
+    ...allocations.flatMap((a) => [a.address, u64le(a.amount)]),
+  ]));
+}
+
+function compareBytes(a: Uint8Array, b: Uint8Array): number {
+  for (let i = 0; i < Math.min(a.length, b.length); i++) {
+    if (a[i] !== b[i]) return a[i] - b[i];
+  }
+  return a.length - b.length;
+}
+
 /**
  * Must exactly match Rust `ConsensusEngine::get_transaction_data_for_signing`.
+ *
+ * The payload starts with `network` when given, otherwise with the network id of
+ * `tx.chain_id` (chain 0 when unset) without genesis allocations. Pass the `network_id`
+ * from `GET /api/v1/blockchain/info` when signing for a network with governance token
+ * genesis allocations.
  */
-export function getTransactionDataForSigning(tx: Transaction): Uint8Array {
+export function getTransactionDataForSigning(tx: Transaction, network?: Hash): Uint8Array {
+  return concatBytes([network ?? networkId(tx.chain_id ?? 0), transactionPayload(tx)]);
+}
+
+function transactionPayload(tx: Transaction): Uint8Array {
   const enc = new TextEncoder();
 
   switch (tx.type) {
@@ -248,6 +294,12 @@ export function getTransactionDataForSigning(tx: Transaction): Uint8Array {
             parts.push(otherBytes);
           }
         }
+        // Conflict policy, tagged with its key
+        const policy = tx.data.metadata['_merge_policy'];
+        if (policy) {
+          parts.push(enc.encode('_merge_policy'));
+          parts.push(enc.encode(policy));
+        }
       }
 
       // For Split: include components in signature
@@ -256,6 +308,33 @@ export function getTransactionDataForSigning(tx: Transaction): Uint8Array {
         if (componentsStr) {
           parts.push(enc.encode(componentsStr));
         }
+        // Explicit component manifest, tagged with its key
+        const manifest = tx.data.metadata['_component_manifest'];
+        if (manifest) {
+          parts.push(enc.encode('_component_manifest'));
+          parts.push(enc.encode(manifest));
+        }
+      }
+
+      // Economic zone, tagged with its key; an empty value (leave the zone) is signed too
+      const zone = tx.data.metadata['_zone'];
+      if (zone !== undefined) {
+        parts.push(enc.encode('_zone'));
+        parts.push(enc.encode(zone));
+      }
+
+      // Paymaster sponsoring the gas, tagged with its key
+      const paymaster = tx.data.metadata['_paymaster'];
+      if (paymaster !== undefined) {
+        parts.push(enc.encode('_paymaster'));
+        parts.push(enc.encode(paymaster));
+      }
+
+      // Commitments to encrypted fields, tagged with their key
+      const commitments = tx.data.metadata['_commitments'];
+      if (commitments !== undefined) {
+        parts.push(enc.encode('_commitments'));
+        parts.push(enc.encode(commitments));
       }
 
       // Common fee/nonce fields for MistbornAsset
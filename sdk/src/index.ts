@@ -12,7 +12,7 @@ export * from './types';
 export * from './utils';
 
 // Cryptography
-export { KeyPair } from './crypto';
+export { KeyPair, networkId, GenesisAllocation } from './crypto';
 
 // API Client
 export { HazeClient, HazeClientConfig } from './client';
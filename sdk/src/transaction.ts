@@ -3,7 +3,7 @@
  */
 
 import { KeyPair, signTransaction } from './crypto';
-import { Transaction, TransferTransaction, StakeTransaction, Address } from './types';
+import { Transaction, TransferTransaction, StakeTransaction, Address, Hash } from './types';
 import { sha256, bytesToHex } from './utils';
 
 /**
@@ -86,8 +86,8 @@ export class TransactionBuilder {
   /**
    * Sign a transaction
    */
-  static async sign(transaction: Transaction, keyPair: KeyPair): Promise<Transaction> {
-    const signature = await signTransaction(transaction, keyPair);
+  static async sign(transaction: Transaction, keyPair: KeyPair, network?: Hash): Promise<Transaction> {
+    const signature = await signTransaction(transaction, keyPair, network);
     
     // Create a copy with signature
     if (transaction.type === 'Transfer') {
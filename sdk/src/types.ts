@@ -36,14 +36,23 @@ export enum AssetAction {
   Evaporate = "Evaporate", // Decrease density
   Merge = "Merge",
   Split = "Split",
+  Reveal = "Reveal", // Publish committed private metadata
+  Breed = "Breed", // Child hatched from two parents (asset history only; use the Breed transaction)
+  Upgrade = "Upgrade", // Upgrade outcome (asset history only; use the Upgrade transaction)
 }
 
+/**
+ * Typed attribute value
+ */
+export type AttributeValue = number | boolean | string | { enum: string };
+
 /**
  * Attribute for NFT
  */
 export interface Attribute {
   name: string;
-  value: string;
+  /** Integer, number, boolean, string or `{ enum: label }` */
+  value: AttributeValue;
   rarity?: number;
 }
 
@@ -153,6 +162,12 @@ export interface BlockHeader {
   state_root: Hash;
   wave_number: number;
   committee_id: number;
+  /** Peer protocol the block was produced under (1 for blocks from before version 2) */
+  protocol_version: number;
+  /** State format the state root was computed with */
+  state_version: number;
+  /** Upgrade signal bits of features the producer is ready for (0 before protocol 3) */
+  signals: number;
 }
 
 /**
@@ -183,6 +198,28 @@ export interface AssetInfo {
   density: string;
   created_at: number;
   updated_at: number;
+  /** Private metadata fields by name */
+  private_fields?: Record<string, PrivateFieldInfo>;
+  /** Metadata namespaces (`<game_id>:<name>` keys) by game id */
+  namespaces?: Record<string, MetadataNamespaceInfo>;
+}
+
+/**
+ * Metadata namespace of an asset: its keys and the grantees that may write them
+ */
+export interface MetadataNamespaceInfo {
+  keys: string[];
+  writers: string[];
+}
+
+/**
+ * Private metadata field: ciphertext until revealed
+ */
+export interface PrivateFieldInfo {
+  status: 'committed' | 'revealed';
+  /** Hex sha256(salt || plaintext) */
+  commitment: string;
+  revealed_at: number | null;
 }
 
 /**
@@ -202,6 +239,9 @@ export interface BlockInfo {
  * Blockchain information
  */
 export interface BlockchainInfo {
+  chain_id: number;
+  /** Hex network id prefixed to every signing payload and block hash */
+  network_id: string;
   current_height: number;
   total_supply: bigint;
   current_wave: number;
@@ -239,3 +279,61 @@ export interface ApiResponse<T> {
   data?: T;
   error?: string;
 }
+
+/**
+ * WebSocket event schema version (connect with `/api/v1/ws?event_version=2`)
+ */
+export const EVENT_VERSION = 2;
+
+/**
+ * Density level in v2 events: numeric level with a string tag
+ */
+export interface DensityTag {
+  level: 0 | 1 | 2 | 3;
+  name: "ethereal" | "light" | "dense" | "core";
+}
+
+/**
+ * v2 WebSocket event payloads by type
+ */
+export interface WsEventData {
+  asset_created: { asset_id: string; owner: string; density: DensityTag };
+  asset_updated: { asset_id: string; owner: string };
+  asset_condensed: { asset_id: string; new_density: DensityTag };
+  asset_evaporated: { asset_id: string; new_density: DensityTag };
+  asset_merged: { asset_id: string; merged_asset_id: string };
+  asset_split: { asset_id: string; created_assets: string[] };
+  asset_permission_changed: { asset_id: string; owner: string };
+  asset_attribute_updated: { asset_id: string; owner: string; attributes: string[] };
+  asset_version_created: { asset_id: string; version: number; owner: string };
+  payment_request_settled: { request_id: string; requester: string; payer: string; amount: number };
+  season_started: { season_id: string; game_id: string; season: string; start_height: number; end_height: number };
+  season_ended: { season_id: string; game_id: string; season: string; end_height: number; frozen_collections: string[] };
+  asset_upgraded: {
+    asset_id: string;
+    owner: string;
+    upgrade_id: string;
+    game_id: string;
+    outcome: "success" | "fail" | "destroy";
+    attribute: string;
+    value: number | null;
+    catalysts: string[];
+  };
+  asset_finalized: { asset_id: string; height: number; wave: number };
+  collection_price_changed: {
+    game_id: string;
+    floor_price: number | null;
+    previous_floor_price: number | null;
+    last_price: number | null;
+    previous_last_price: number | null;
+    height: number;
+  };
+  error: { message: string };
+}
+
+/**
+ * v2 WebSocket event envelope
+ */
+export type WsEvent = {
+  [K in keyof WsEventData]: { event_version: 2; type: K; data: WsEventData[K] };
+}[keyof WsEventData];
@@ -20,21 +20,67 @@ use axum::{
 use axum::extract::ws::Message;
 use serde::{Deserialize, Serialize};
 use tokio::sync::broadcast;
-use crate::assets::BlobStorage;
 use crate::config::Config;
 use crate::consensus::ConsensusEngine;
+use crate::error::HazeError;
+use crate::http_cache::{self, CachedResponse, ResponseCache};
+use crate::watchlist::{NewWatchlist, Watchlist, WatchlistStore};
+use crate::custody::{CustodialAccount, CustodyStore};
+use crate::sign_in::SignInService;
+use crate::messaging::{Mailbox, SealedMessage};
+use crate::tenant::{NewTenant, Tenant, TenantStore};
+use crate::metering::UsageMeter;
+use crate::telemetry::{TelemetryAggregator, TelemetryReport};
+use crate::dev::{Faucet, FaucetRequest, FaucetTransfer};
 use crate::state::StateManager;
-use crate::types::{Transaction, AssetAction, Hash, AssetPermission, PermissionLevel, hash_to_hex, address_to_hex};
+use crate::types::{Transaction, AssetAction, Hash, AssetPermission, PermissionLevel, OrderSide, hash_to_hex, address_to_hex};
 use crate::state::AssetState;
 pub use crate::ws_events::WsEvent;
 
 // Use std::result::Result for API handlers to avoid conflict with crate::error::Result
-type ApiResult<T> = std::result::Result<T, StatusCode>;
+type ApiResult<T> = std::result::Result<T, ApiError>;
+
+/// Handler error: a bare status code, or a `HazeError` returned as 400 with its
+/// error code and parameters in the body
+#[derive(Debug)]
+pub enum ApiError {
+    Status(StatusCode),
+    Haze(HazeError),
+}
+
+impl From<StatusCode> for ApiError {
+    fn from(status: StatusCode) -> Self {
+        ApiError::Status(status)
+    }
+}
+
+impl From<HazeError> for ApiError {
+    fn from(error: HazeError) -> Self {
+        ApiError::Haze(error)
+    }
+}
+
+impl axum::response::IntoResponse for ApiError {
+    fn into_response(self) -> axum::response::Response {
+        match self {
+            ApiError::Status(status) => status.into_response(),
+            ApiError::Haze(error) => {
+                let status = match error {
+                    HazeError::Maintenance(_) => StatusCode::SERVICE_UNAVAILABLE,
+                    _ => StatusCode::BAD_REQUEST,
+                };
+                (status, Json(ApiResponse::<()>::from_error(&error))).into_response()
+            }
+        }
+    }
+}
 
 /// WebSocket subscription request
 #[derive(Debug, Deserialize)]
 pub struct WsSubscribeRequest {
     pub subscribe: Vec<WsSubscription>,
+    /// Switch the connection to this event schema version
+    pub event_version: Option<u32>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -46,6 +92,21 @@ pub struct WsSubscription {
     pub game_id: Option<String>,
 }
 
+impl WsSubscription {
+    /// Whether the event has the subscribed type and matches the asset_id / owner filters
+    /// (the owner filter is ignored for events that carry no owner)
+    fn matches(&self, event: &WsEvent) -> bool {
+        let hex_matches = |filter: &Option<String>, value: Option<&[u8; 32]>| match (filter, value) {
+            (Some(filter), Some(value)) => filter.eq_ignore_ascii_case(&hex::encode(value)),
+            _ => true,
+        };
+        self.sub_type == event.event_type()
+            && event.event_type() != "error"
+            && hex_matches(&self.asset_id, event.asset_id())
+            && hex_matches(&self.owner, event.owner())
+    }
+}
+
 /// API state shared across handlers
 #[derive(Clone)]
 pub struct ApiState {
@@ -55,6 +116,24 @@ pub struct ApiState {
     pub ws_tx: broadcast::Sender<WsEvent>,
     /// Shared counter of connected P2P peers (updated by network layer)
     pub connected_peers: Arc<std::sync::atomic::AtomicUsize>,
+    /// Cached responses of hot read endpoints (assets, blockchain info)
+    pub response_cache: Arc<ResponseCache>,
+    /// Asset watchlists by API key
+    pub watchlists: Arc<WatchlistStore>,
+    /// Custodial player accounts (`None` unless `custody.enabled`)
+    pub custody: Option<Arc<CustodyStore>>,
+    /// Sign-in-with-HAZE challenges and sessions (`None` unless `sign_in.enabled`)
+    pub sign_in: Option<Arc<SignInService>>,
+    /// Mailboxes of encrypted direct messages (`None` unless `messaging.enabled`)
+    pub mailbox: Option<Arc<Mailbox>>,
+    /// Tenants of a multi-tenant node (`None` unless `tenants.enabled`)
+    pub tenants: Option<Arc<TenantStore>>,
+    /// Per-API-key usage metering (`None` unless `metering.enabled`)
+    pub metering: Option<Arc<UsageMeter>>,
+    /// Reports of other nodes (`None` unless `telemetry.aggregator`)
+    pub telemetry: Option<Arc<TelemetryAggregator>>,
+    /// Test funds of a dev node (`None` unless `dev.enabled`)
+    pub faucet: Option<Arc<Faucet>>,
 }
 
 /// API response wrapper
@@ -63,6 +142,15 @@ pub struct ApiResponse<T> {
     pub success: bool,
     pub data: Option<T>,
     pub error: Option<String>,
+    /// Stable `HazeError` code (see docs/ERROR_CODES.md)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<u32>,
+    /// Machine-readable error parameters (e.g. expected/actual nonce)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_params: Option<std::collections::BTreeMap<String, u64>>,
+    /// Block height a multi-entity query was answered at (all entities read from one snapshot)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u64>,
 }
 
 impl<T> ApiResponse<T> {
@@ -71,14 +159,36 @@ impl<T> ApiResponse<T> {
             success: true,
             data: Some(data),
             error: None,
+            error_code: None,
+            error_params: None,
+            height: None,
         }
     }
     
+    /// Successful response read from the state snapshot at `height`
+    pub fn success_at(data: T, height: u64) -> Self {
+        Self { height: Some(height), ..Self::success(data) }
+    }
+
     pub fn error(error: String) -> Self {
         Self {
             success: false,
             data: None,
             error: Some(error),
+            error_code: None,
+            error_params: None,
+            height: None,
+        }
+    }
+
+    pub fn from_error(error: &HazeError) -> Self {
+        Self {
+            success: false,
+            data: None,
+            error: Some(error.to_string()),
+            error_code: Some(error.code()),
+            error_params: Some(error.params()),
+            height: None,
         }
     }
 }
@@ -164,7 +274,7 @@ fn asset_data_from_value(v: &serde_json::Value) -> Result<crate::types::AssetDat
                     let o = x.as_object()?;
                     Some(crate::types::Attribute {
                         name: o.get("name")?.as_str()?.to_string(),
-                        value: o.get("value")?.as_str()?.to_string(),
+                        value: serde_json::from_value(o.get("value")?.clone()).ok()?,
                         rarity: o.get("rarity").and_then(|r| r.as_f64()),
                     })
                 })
@@ -188,6 +298,96 @@ fn asset_data_from_value(v: &serde_json::Value) -> Result<crate::types::AssetDat
     })
 }
 
+fn loot_outcomes_from_value(v: &serde_json::Value) -> Result<Vec<crate::types::LootOutcome>, String> {
+    let outcomes = v.as_array().ok_or("expected array for outcomes")?;
+    outcomes
+        .iter()
+        .map(|outcome| {
+            let obj = outcome.as_object().ok_or("expected object for outcome")?;
+            let weight = u64_from_value(obj.get("weight").ok_or("missing outcome weight")?)?;
+            let weight = u32::try_from(weight).map_err(|_| "outcome weight does not fit in u32".to_string())?;
+            // Same density/metadata/attributes shape as asset data, without owner or game_id
+            let mut data = obj.clone();
+            data.insert("owner".to_string(), serde_json::Value::String(hex::encode([0u8; 32])));
+            let data = asset_data_from_value(&serde_json::Value::Object(data))?;
+            Ok(crate::types::LootOutcome {
+                weight,
+                density: data.density,
+                metadata: data.metadata.into_iter().collect(),
+                attributes: data.attributes,
+            })
+        })
+        .collect()
+}
+
+fn season_hooks_from_value(v: &serde_json::Value) -> Result<crate::types::SeasonHooks, String> {
+    let obj = v.as_object().ok_or("expected object for hooks")?;
+    let freeze_collections = match obj.get("freeze_collections") {
+        Some(v) if !v.is_null() => v
+            .as_array()
+            .ok_or("expected array for freeze_collections")?
+            .iter()
+            .map(|c| c.as_str().map(String::from).ok_or_else(|| "expected string collection".to_string()))
+            .collect::<Result<Vec<_>, _>>()?,
+        _ => Vec::new(),
+    };
+    let snapshot_holders = obj.get("snapshot_holders").and_then(|b| b.as_bool()).unwrap_or(false);
+    let leaderboard_capacity = match obj.get("leaderboard_capacity") {
+        Some(v) if !v.is_null() => Some(
+            u32::try_from(u64_from_value(v)?).map_err(|_| "leaderboard_capacity does not fit in u32".to_string())?,
+        ),
+        _ => None,
+    };
+    let leaderboard_submitters = match obj.get("leaderboard_submitters") {
+        Some(v) if !v.is_null() => v
+            .as_array()
+            .ok_or("expected array for leaderboard_submitters")?
+            .iter()
+            .map(bytes32_from_value)
+            .collect::<Result<Vec<_>, _>>()?,
+        _ => Vec::new(),
+    };
+    Ok(crate::types::SeasonHooks { freeze_collections, snapshot_holders, leaderboard_capacity, leaderboard_submitters })
+}
+
+fn asset_hook_from_value(v: &serde_json::Value) -> Result<Option<crate::types::AssetHook>, String> {
+    if v.is_null() {
+        return Ok(None);
+    }
+    let obj = v.as_object().ok_or("expected object or null for hook")?;
+    let contract = bytes32_from_value(obj.get("contract").ok_or("missing hook contract")?)?;
+    let method = obj
+        .get("method")
+        .and_then(|m| m.as_str())
+        .ok_or("missing hook method")?
+        .to_string();
+    let gas_limit = u64_from_value(obj.get("gas_limit").ok_or("missing hook gas_limit")?)?;
+    let on_failure = match obj.get("on_failure").and_then(|f| f.as_str()) {
+        Some("Warn") => crate::types::HookFailureMode::Warn,
+        Some("Revert") => crate::types::HookFailureMode::Revert,
+        _ => return Err("invalid hook on_failure (expected Warn or Revert)".to_string()),
+    };
+    Ok(Some(crate::types::AssetHook {
+        contract,
+        method,
+        gas_limit,
+        on_failure,
+    }))
+}
+
+fn airdrop_claims_from_value(v: &serde_json::Value) -> Result<Vec<crate::types::AirdropClaim>, String> {
+    let arr = v.as_array().ok_or("expected array for claims")?;
+    arr.iter()
+        .map(|claim| {
+            let obj = claim.as_object().ok_or("expected object for claim")?;
+            Ok(crate::types::AirdropClaim {
+                address: bytes32_from_value(obj.get("address").ok_or("missing claim address")?)?,
+                amount: u64_from_value(obj.get("amount").ok_or("missing claim amount")?)?,
+            })
+        })
+        .collect()
+}
+
 fn parse_transaction_from_value(v: &serde_json::Value) -> Result<Transaction, String> {
     let obj = v.as_object().ok_or("transaction must be an object")?;
     if obj.len() != 1 {
@@ -272,6 +472,7 @@ fn parse_transaction_from_value(v: &serde_json::Value) -> Result<Transaction, St
                 Some("Evaporate") => AssetAction::Evaporate,
                 Some("Merge") => AssetAction::Merge,
                 Some("Split") => AssetAction::Split,
+                Some("Reveal") => AssetAction::Reveal,
                 _ => return Err("invalid MistbornAsset action".to_string()),
             };
             let asset_id = bytes32_from_value(inner.get("asset_id").ok_or("missing asset_id")?)?;
@@ -313,1271 +514,4645 @@ fn parse_transaction_from_value(v: &serde_json::Value) -> Result<Transaction, St
                 signature,
             })
         }
-        _ => Err(format!("unknown transaction variant: {}", tag)),
-    }
-}
-
-/// Transaction response
-#[derive(Debug, Serialize)]
-pub struct TransactionResponse {
-    pub hash: String,
-    pub status: String,
-}
-
-/// Account info response
-#[derive(Debug, Serialize)]
-pub struct AccountInfo {
-    pub address: String,
-    pub balance: u64,
-    pub nonce: u64,
-    pub staked: u64,
-}
-
-/// Block info response
-#[derive(Debug, Serialize)]
-pub struct BlockInfo {
-    pub hash: String,
-    pub parent_hash: String,
-    pub height: u64,
-    pub timestamp: i64,
-    pub validator: String,
-    pub transaction_count: usize,
-    pub wave_number: u64,
-}
-
-/// Blockchain info response
-#[derive(Debug, Serialize, Clone)]
-pub struct BlockchainInfo {
-    pub current_height: u64,
-    pub total_supply: u64,
-    pub current_wave: u64,
-    pub state_root: String, // Hex-encoded state root hash
-    pub last_finalized_height: u64,
-    pub last_finalized_wave: u64,
-}
-
-/// Create API router
-pub fn create_router(state: ApiState) -> Router {
-    let enable_cors = state.config.api.enable_cors;
-    
-    let router = Router::new()
-        .route("/health", get(health_check))
-        .route("/api/v1/blockchain/info", get(get_blockchain_info))
-        .route("/api/v1/metrics/basic", get(get_basic_metrics))
-        .route("/api/v1/metrics/prometheus", get(get_prometheus_metrics))
-        .route("/api/v1/transactions", post(send_transaction))
-        .route("/api/v1/transactions/:hash", get(get_transaction))
-        .route("/api/v1/blocks/:hash", get(get_block_by_hash))
-        .route("/api/v1/blocks/height/:height", get(get_block_by_height))
-        .route("/api/v1/accounts/:address", get(get_account))
-        .route("/api/v1/accounts/:address/balance", get(get_balance))
-        .route("/api/v1/assets/:asset_id", get(get_asset))
-        .route("/api/v1/assets/:asset_id/blob/:blob_key", get(get_asset_blob))
-        .route("/api/v1/assets/:asset_id/history", get(get_asset_history))
-        .route("/api/v1/assets/:asset_id/versions", get(get_asset_versions))
-        .route("/api/v1/assets/:asset_id/versions/:version", get(get_asset_version))
-        .route("/api/v1/assets/:asset_id/snapshot", post(create_asset_snapshot))
-        .route("/api/v1/assets", post(create_asset))
-        .route("/api/v1/assets/search", get(search_assets))
-        .route("/api/v1/assets/:asset_id/condense", post(condense_asset))
-        .route("/api/v1/assets/:asset_id/evaporate", post(evaporate_asset))
-        .route("/api/v1/assets/:asset_id/merge", post(merge_assets))
-        .route("/api/v1/assets/:asset_id/split", post(split_asset))
-        .route("/api/v1/assets/estimate-gas", post(estimate_asset_gas))
-        .route("/api/v1/assets/:asset_id/permissions", get(get_asset_permissions))
-        .route("/api/v1/assets/:asset_id/permissions", post(set_asset_permissions))
-        .route("/api/v1/assets/:asset_id/export", get(export_asset))
-        .route("/api/v1/assets/import", post(import_asset))
-        .route("/api/v1/economy/pools", get(get_liquidity_pools))
-        .route("/api/v1/economy/pools", post(create_liquidity_pool))
-        .route("/api/v1/economy/pools/:pool_id", get(get_liquidity_pool))
-        .route("/api/v1/ws", get(ws_handler))
-        .route("/api/v1/sync/start", post(start_sync))
-        .route("/api/v1/sync/status", get(get_sync_status))
-        .with_state(state);
-    
-    // Add CORS if enabled
-    if enable_cors {
-        router.layer(
-            tower_http::cors::CorsLayer::new()
-                .allow_origin(tower_http::cors::Any)
-                .allow_methods(tower_http::cors::Any)
-                .allow_headers(tower_http::cors::Any)
-        )
-    } else {
-        router
-    }
-}
-
-/// Health check endpoint
-async fn health_check() -> Json<ApiResponse<&'static str>> {
-    Json(ApiResponse::success("OK"))
-}
-
-/// Get blockchain info
-async fn get_blockchain_info(
-    State(api_state): State<ApiState>,
-) -> ApiResult<Json<ApiResponse<BlockchainInfo>>> {
-    let height = api_state.state.current_height();
-    let total_supply = api_state.state.tokenomics().total_supply();
-    
-    // Get current wave from consensus
-    let current_wave = api_state.consensus.get_current_wave();
-    
-    // Compute state root
-    let state_root = api_state.state.compute_state_root();
-    
-    // Get finalized checkpoint info
-    let last_finalized_height = api_state.consensus.get_last_finalized_height();
-    let last_finalized_wave = api_state.consensus.get_last_finalized_wave();
-    
-    let info = BlockchainInfo {
-        current_height: height,
-        total_supply,
-        current_wave,
-        state_root: hash_to_hex(&state_root),
-        last_finalized_height,
-        last_finalized_wave,
-    };
-    
-    Ok(Json(ApiResponse::success(info)))
-}
-
-/// Send transaction
-async fn send_transaction(
-    State(api_state): State<ApiState>,
-    Json(request): Json<SendTransactionRequest>,
-) -> ApiResult<Json<ApiResponse<TransactionResponse>>> {
-    let tx = request.transaction;
-    let tx_hash = tx.hash();
-    
-    match api_state.consensus.add_transaction(tx.clone()) {
-        Ok(()) => {
-            // Broadcast transaction to network (async, don't wait)
-            // For now, transactions will be broadcast when blocks are created
-            // In future, we can add direct transaction broadcasting here
-            tracing::debug!("Transaction added to pool, will be broadcast with next block");
-            
-            let response = TransactionResponse {
-                hash: hash_to_hex(&tx_hash),
-                status: "pending".to_string(),
-            };
-            Ok(Json(ApiResponse::success(response)))
+        "Unjail" => {
+            let from = bytes32_from_value(inner.get("from").ok_or("missing from")?)?;
+            let fee = u64_from_value(inner.get("fee").ok_or("missing fee")?)?;
+            let nonce = u64_from_value(inner.get("nonce").ok_or("missing nonce")?)?;
+            let chain_id = inner.get("chain_id").and_then(|c| u64_from_value(c).ok());
+            let valid_until_height = inner.get("valid_until_height").and_then(|h| u64_from_value(h).ok());
+            let signature = bytes_from_value(inner.get("signature").ok_or("missing signature")?)?;
+            Ok(Transaction::Unjail {
+                from,
+                fee,
+                nonce,
+                chain_id,
+                valid_until_height,
+                signature,
+            })
         }
-        Err(_e) => {
-            Err(StatusCode::BAD_REQUEST)
+        "SetAssetHook" => {
+            let from = bytes32_from_value(inner.get("from").ok_or("missing from")?)?;
+            let game_id = inner
+                .get("game_id")
+                .and_then(|g| g.as_str())
+                .ok_or("missing game_id")?
+                .to_string();
+            let hook = asset_hook_from_value(inner.get("hook").unwrap_or(&serde_json::Value::Null))?;
+            let fee = u64_from_value(inner.get("fee").ok_or("missing fee")?)?;
+            let nonce = u64_from_value(inner.get("nonce").ok_or("missing nonce")?)?;
+            let chain_id = inner.get("chain_id").and_then(|c| u64_from_value(c).ok());
+            let valid_until_height = inner.get("valid_until_height").and_then(|h| u64_from_value(h).ok());
+            let signature = bytes_from_value(inner.get("signature").ok_or("missing signature")?)?;
+            Ok(Transaction::SetAssetHook {
+                from,
+                game_id,
+                hook,
+                fee,
+                nonce,
+                chain_id,
+                valid_until_height,
+                signature,
+            })
         }
-    }
-}
-
-/// Get transaction by hash
-async fn get_transaction(
-    State(api_state): State<ApiState>,
-    Path(hash_str): Path<String>,
-) -> ApiResult<Json<ApiResponse<TransactionResponse>>> {
-    let hash = crate::types::hex_to_hash(&hash_str)
-        .ok_or(StatusCode::BAD_REQUEST)?;
-    
-    // Try to get from transaction pool
-    if api_state.consensus.get_transaction(&hash).is_some() {
-        let response = TransactionResponse {
-            hash: hash_to_hex(&hash),
-            status: "pending".to_string(),
-        };
-        return Ok(Json(ApiResponse::success(response)));
-    }
-    
-    // Check in executed blocks
-    // Iterate through blocks to find the transaction
-    // Note: In production, this should use an index for better performance
-    for entry in api_state.state.blocks().iter() {
-        let block = entry.value();
-        for tx in &block.transactions {
-            if tx.hash() == hash {
-                let response = TransactionResponse {
-                    hash: hash_to_hex(&hash),
-                    status: "executed".to_string(),
-                };
-                return Ok(Json(ApiResponse::success(response)));
-            }
+        "CreateAirdrop" => {
+            let from = bytes32_from_value(inner.get("from").ok_or("missing from")?)?;
+            let claims = airdrop_claims_from_value(inner.get("claims").ok_or("missing claims")?)?;
+            let claims_root = bytes32_from_value(inner.get("claims_root").ok_or("missing claims_root")?)?;
+            let fee = u64_from_value(inner.get("fee").ok_or("missing fee")?)?;
+            let nonce = u64_from_value(inner.get("nonce").ok_or("missing nonce")?)?;
+            let chain_id = inner.get("chain_id").and_then(|c| u64_from_value(c).ok());
+            let valid_until_height = inner.get("valid_until_height").and_then(|h| u64_from_value(h).ok());
+            let signature = bytes_from_value(inner.get("signature").ok_or("missing signature")?)?;
+            Ok(Transaction::CreateAirdrop {
+                from,
+                claims,
+                claims_root,
+                fee,
+                nonce,
+                chain_id,
+                valid_until_height,
+                signature,
+            })
         }
-    }
-    
-    Err(StatusCode::NOT_FOUND)
-}
-
-/// Get block by hash
+        "CreatePaymentRequest" => {
+            let from = bytes32_from_value(inner.get("from").ok_or("missing from")?)?;
+            let payer = match inner.get("payer") {
+                Some(v) if !v.is_null() => Some(bytes32_from_value(v)?),
+                _ => None,
+            };
+            let amount = u64_from_value(inner.get("amount").ok_or("missing amount")?)?;
+            let memo = inner.get("memo")
+                .map(|m| m.as_str().map(str::to_string).ok_or("expected string for memo"))
+                .transpose()?
+                .unwrap_or_default();
+            let expires_at_height = u64_from_value(inner.get("expires_at_height").ok_or("missing expires_at_height")?)?;
+            let fee = u64_from_value(inner.get("fee").ok_or("missing fee")?)?;
+            let nonce = u64_from_value(inner.get("nonce").ok_or("missing nonce")?)?;
+            let chain_id = inner.get("chain_id").and_then(|c| u64_from_value(c).ok());
+            let valid_until_height = inner.get("valid_until_height").and_then(|h| u64_from_value(h).ok());
+            let signature = bytes_from_value(inner.get("signature").ok_or("missing signature")?)?;
+            Ok(Transaction::CreatePaymentRequest {
+                from,
+                payer,
+                amount,
+                memo,
+                expires_at_height,
+                fee,
+                nonce,
+                chain_id,
+                valid_until_height,
+                signature,
+            })
+        }
+        "PayPaymentRequest" => {
+            let from = bytes32_from_value(inner.get("from").ok_or("missing from")?)?;
+            let request_id = bytes32_from_value(inner.get("request_id").ok_or("missing request_id")?)?;
+            let amount = u64_from_value(inner.get("amount").ok_or("missing amount")?)?;
+            let fee = u64_from_value(inner.get("fee").ok_or("missing fee")?)?;
+            let nonce = u64_from_value(inner.get("nonce").ok_or("missing nonce")?)?;
+            let chain_id = inner.get("chain_id").and_then(|c| u64_from_value(c).ok());
+            let valid_until_height = inner.get("valid_until_height").and_then(|h| u64_from_value(h).ok());
+            let signature = bytes_from_value(inner.get("signature").ok_or("missing signature")?)?;
+            Ok(Transaction::PayPaymentRequest {
+                from,
+                request_id,
+                amount,
+                fee,
+                nonce,
+                chain_id,
+                valid_until_height,
+                signature,
+            })
+        }
+        "PlaceOrder" => {
+            let from = bytes32_from_value(inner.get("from").ok_or("missing from")?)?;
+            let game_id = inner.get("game_id").and_then(|g| g.as_str()).ok_or("missing game_id")?.to_string();
+            let side = match inner.get("side").and_then(|s| s.as_str()).ok_or("missing side")? {
+                "Buy" => OrderSide::Buy,
+                "Sell" => OrderSide::Sell,
+                other => return Err(format!("unknown order side: {}", other)),
+            };
+            let price = u64_from_value(inner.get("price").ok_or("missing price")?)?;
+            let quantity = u64_from_value(inner.get("quantity").ok_or("missing quantity")?)?;
+            let assets = match inner.get("assets") {
+                Some(serde_json::Value::Array(items)) => items
+                    .iter()
+                    .map(bytes32_from_value)
+                    .collect::<Result<Vec<_>, _>>()?,
+                Some(v) if !v.is_null() => return Err("expected array for assets".to_string()),
+                _ => Vec::new(),
+            };
+            let fee = u64_from_value(inner.get("fee").ok_or("missing fee")?)?;
+            let nonce = u64_from_value(inner.get("nonce").ok_or("missing nonce")?)?;
+            let chain_id = inner.get("chain_id").and_then(|c| u64_from_value(c).ok());
+            let valid_until_height = inner.get("valid_until_height").and_then(|h| u64_from_value(h).ok());
+            let signature = bytes_from_value(inner.get("signature").ok_or("missing signature")?)?;
+            Ok(Transaction::PlaceOrder {
+                from,
+                game_id,
+                side,
+                price,
+                quantity,
+                assets,
+                fee,
+                nonce,
+                chain_id,
+                valid_until_height,
+                signature,
+            })
+        }
+        "CancelOrder" => {
+            let from = bytes32_from_value(inner.get("from").ok_or("missing from")?)?;
+            let order_id = bytes32_from_value(inner.get("order_id").ok_or("missing order_id")?)?;
+            let fee = u64_from_value(inner.get("fee").ok_or("missing fee")?)?;
+            let nonce = u64_from_value(inner.get("nonce").ok_or("missing nonce")?)?;
+            let chain_id = inner.get("chain_id").and_then(|c| u64_from_value(c).ok());
+            let valid_until_height = inner.get("valid_until_height").and_then(|h| u64_from_value(h).ok());
+            let signature = bytes_from_value(inner.get("signature").ok_or("missing signature")?)?;
+            Ok(Transaction::CancelOrder {
+                from,
+                order_id,
+                fee,
+                nonce,
+                chain_id,
+                valid_until_height,
+                signature,
+            })
+        }
+        "CreateSubscription" => {
+            let from = bytes32_from_value(inner.get("from").ok_or("missing from")?)?;
+            let payee = bytes32_from_value(inner.get("payee").ok_or("missing payee")?)?;
+            let amount = u64_from_value(inner.get("amount").ok_or("missing amount")?)?;
+            let interval_blocks = u64_from_value(inner.get("interval_blocks").ok_or("missing interval_blocks")?)?;
+            let end_height = match inner.get("end_height") {
+                Some(v) if !v.is_null() => Some(u64_from_value(v)?),
+                _ => None,
+            };
+            let fee = u64_from_value(inner.get("fee").ok_or("missing fee")?)?;
+            let nonce = u64_from_value(inner.get("nonce").ok_or("missing nonce")?)?;
+            let chain_id = inner.get("chain_id").and_then(|c| u64_from_value(c).ok());
+            let valid_until_height = inner.get("valid_until_height").and_then(|h| u64_from_value(h).ok());
+            let signature = bytes_from_value(inner.get("signature").ok_or("missing signature")?)?;
+            Ok(Transaction::CreateSubscription {
+                from,
+                payee,
+                amount,
+                interval_blocks,
+                end_height,
+                fee,
+                nonce,
+                chain_id,
+                valid_until_height,
+                signature,
+            })
+        }
+        "CancelSubscription" => {
+            let from = bytes32_from_value(inner.get("from").ok_or("missing from")?)?;
+            let subscription_id = bytes32_from_value(inner.get("subscription_id").ok_or("missing subscription_id")?)?;
+            let fee = u64_from_value(inner.get("fee").ok_or("missing fee")?)?;
+            let nonce = u64_from_value(inner.get("nonce").ok_or("missing nonce")?)?;
+            let chain_id = inner.get("chain_id").and_then(|c| u64_from_value(c).ok());
+            let valid_until_height = inner.get("valid_until_height").and_then(|h| u64_from_value(h).ok());
+            let signature = bytes_from_value(inner.get("signature").ok_or("missing signature")?)?;
+            Ok(Transaction::CancelSubscription {
+                from,
+                subscription_id,
+                fee,
+                nonce,
+                chain_id,
+                valid_until_height,
+                signature,
+            })
+        }
+        "SetReferrer" => {
+            let from = bytes32_from_value(inner.get("from").ok_or("missing from")?)?;
+            let game_id = inner.get("game_id").and_then(|g| g.as_str()).ok_or("missing game_id")?.to_string();
+            let referrer = bytes32_from_value(inner.get("referrer").ok_or("missing referrer")?)?;
+            let fee = u64_from_value(inner.get("fee").ok_or("missing fee")?)?;
+            let nonce = u64_from_value(inner.get("nonce").ok_or("missing nonce")?)?;
+            let chain_id = inner.get("chain_id").and_then(|c| u64_from_value(c).ok());
+            let valid_until_height = inner.get("valid_until_height").and_then(|h| u64_from_value(h).ok());
+            let signature = bytes_from_value(inner.get("signature").ok_or("missing signature")?)?;
+            Ok(Transaction::SetReferrer {
+                from,
+                game_id,
+                referrer,
+                fee,
+                nonce,
+                chain_id,
+                valid_until_height,
+                signature,
+            })
+        }
+        "CommitLootBox" => {
+            let from = bytes32_from_value(inner.get("from").ok_or("missing from")?)?;
+            let player = bytes32_from_value(inner.get("player").ok_or("missing player")?)?;
+            let game_id = inner.get("game_id").and_then(|g| g.as_str()).ok_or("missing game_id")?.to_string();
+            let commitment = bytes32_from_value(inner.get("commitment").ok_or("missing commitment")?)?;
+            let bond = u64_from_value(inner.get("bond").ok_or("missing bond")?)?;
+            let fee = u64_from_value(inner.get("fee").ok_or("missing fee")?)?;
+            let nonce = u64_from_value(inner.get("nonce").ok_or("missing nonce")?)?;
+            let chain_id = inner.get("chain_id").and_then(|c| u64_from_value(c).ok());
+            let valid_until_height = inner.get("valid_until_height").and_then(|h| u64_from_value(h).ok());
+            let signature = bytes_from_value(inner.get("signature").ok_or("missing signature")?)?;
+            Ok(Transaction::CommitLootBox {
+                from,
+                player,
+                game_id,
+                commitment,
+                bond,
+                fee,
+                nonce,
+                chain_id,
+                valid_until_height,
+                signature,
+            })
+        }
+        "OpenLootBox" | "ForfeitLootBox" => {
+            let from = bytes32_from_value(inner.get("from").ok_or("missing from")?)?;
+            let box_id = bytes32_from_value(inner.get("box_id").ok_or("missing box_id")?)?;
+            let fee = u64_from_value(inner.get("fee").ok_or("missing fee")?)?;
+            let nonce = u64_from_value(inner.get("nonce").ok_or("missing nonce")?)?;
+            let chain_id = inner.get("chain_id").and_then(|c| u64_from_value(c).ok());
+            let valid_until_height = inner.get("valid_until_height").and_then(|h| u64_from_value(h).ok());
+            let signature = bytes_from_value(inner.get("signature").ok_or("missing signature")?)?;
+            if tag == "OpenLootBox" {
+                Ok(Transaction::OpenLootBox { from, box_id, fee, nonce, chain_id, valid_until_height, signature })
+            } else {
+                Ok(Transaction::ForfeitLootBox { from, box_id, fee, nonce, chain_id, valid_until_height, signature })
+            }
+        }
+        "RevealLootBox" => {
+            let from = bytes32_from_value(inner.get("from").ok_or("missing from")?)?;
+            let box_id = bytes32_from_value(inner.get("box_id").ok_or("missing box_id")?)?;
+            let outcomes = loot_outcomes_from_value(inner.get("outcomes").ok_or("missing outcomes")?)?;
+            let salt = bytes_from_value(inner.get("salt").ok_or("missing salt")?)?;
+            let fee = u64_from_value(inner.get("fee").ok_or("missing fee")?)?;
+            let nonce = u64_from_value(inner.get("nonce").ok_or("missing nonce")?)?;
+            let chain_id = inner.get("chain_id").and_then(|c| u64_from_value(c).ok());
+            let valid_until_height = inner.get("valid_until_height").and_then(|h| u64_from_value(h).ok());
+            let signature = bytes_from_value(inner.get("signature").ok_or("missing signature")?)?;
+            Ok(Transaction::RevealLootBox {
+                from,
+                box_id,
+                outcomes,
+                salt,
+                fee,
+                nonce,
+                chain_id,
+                valid_until_height,
+                signature,
+            })
+        }
+        "CreateLeaderboard" => {
+            let from = bytes32_from_value(inner.get("from").ok_or("missing from")?)?;
+            let game_id = inner.get("game_id").and_then(|g| g.as_str()).ok_or("missing game_id")?.to_string();
+            let season = inner.get("season").and_then(|g| g.as_str()).ok_or("missing season")?.to_string();
+            let capacity = u64_from_value(inner.get("capacity").ok_or("missing capacity")?)?;
+            let capacity = u32::try_from(capacity).map_err(|_| "capacity does not fit in u32".to_string())?;
+            let end_height = u64_from_value(inner.get("end_height").ok_or("missing end_height")?)?;
+            let submitters = match inner.get("submitters") {
+                Some(v) if !v.is_null() => v
+                    .as_array()
+                    .ok_or("expected array for submitters")?
+                    .iter()
+                    .map(bytes32_from_value)
+                    .collect::<Result<Vec<_>, _>>()?,
+                _ => Vec::new(),
+            };
+            let fee = u64_from_value(inner.get("fee").ok_or("missing fee")?)?;
+            let nonce = u64_from_value(inner.get("nonce").ok_or("missing nonce")?)?;
+            let chain_id = inner.get("chain_id").and_then(|c| u64_from_value(c).ok());
+            let valid_until_height = inner.get("valid_until_height").and_then(|h| u64_from_value(h).ok());
+            let signature = bytes_from_value(inner.get("signature").ok_or("missing signature")?)?;
+            Ok(Transaction::CreateLeaderboard {
+                from,
+                game_id,
+                season,
+                capacity,
+                end_height,
+                submitters,
+                fee,
+                nonce,
+                chain_id,
+                valid_until_height,
+                signature,
+            })
+        }
+        "SubmitScore" => {
+            let from = bytes32_from_value(inner.get("from").ok_or("missing from")?)?;
+            let board_id = bytes32_from_value(inner.get("board_id").ok_or("missing board_id")?)?;
+            let player = bytes32_from_value(inner.get("player").ok_or("missing player")?)?;
+            let score = u64_from_value(inner.get("score").ok_or("missing score")?)?;
+            let fee = u64_from_value(inner.get("fee").ok_or("missing fee")?)?;
+            let nonce = u64_from_value(inner.get("nonce").ok_or("missing nonce")?)?;
+            let chain_id = inner.get("chain_id").and_then(|c| u64_from_value(c).ok());
+            let valid_until_height = inner.get("valid_until_height").and_then(|h| u64_from_value(h).ok());
+            let signature = bytes_from_value(inner.get("signature").ok_or("missing signature")?)?;
+            Ok(Transaction::SubmitScore {
+                from,
+                board_id,
+                player,
+                score,
+                fee,
+                nonce,
+                chain_id,
+                valid_until_height,
+                signature,
+            })
+        }
+        "RegisterSeason" => {
+            let from = bytes32_from_value(inner.get("from").ok_or("missing from")?)?;
+            let game_id = inner.get("game_id").and_then(|g| g.as_str()).ok_or("missing game_id")?.to_string();
+            let season = inner.get("season").and_then(|g| g.as_str()).ok_or("missing season")?.to_string();
+            let start_height = u64_from_value(inner.get("start_height").ok_or("missing start_height")?)?;
+            let end_height = u64_from_value(inner.get("end_height").ok_or("missing end_height")?)?;
+            let hooks = match inner.get("hooks") {
+                Some(v) if !v.is_null() => season_hooks_from_value(v)?,
+                _ => crate::types::SeasonHooks::default(),
+            };
+            let fee = u64_from_value(inner.get("fee").ok_or("missing fee")?)?;
+            let nonce = u64_from_value(inner.get("nonce").ok_or("missing nonce")?)?;
+            let chain_id = inner.get("chain_id").and_then(|c| u64_from_value(c).ok());
+            let valid_until_height = inner.get("valid_until_height").and_then(|h| u64_from_value(h).ok());
+            let signature = bytes_from_value(inner.get("signature").ok_or("missing signature")?)?;
+            Ok(Transaction::RegisterSeason {
+                from,
+                game_id,
+                season,
+                start_height,
+                end_height,
+                hooks,
+                fee,
+                nonce,
+                chain_id,
+                valid_until_height,
+                signature,
+            })
+        }
+        "CreateAssetPool" => {
+            let from = bytes32_from_value(inner.get("from").ok_or("missing from")?)?;
+            let game_id = inner.get("game_id").and_then(|g| g.as_str()).ok_or("missing game_id")?.to_string();
+            let reward_per_block = u64_from_value(inner.get("reward_per_block").ok_or("missing reward_per_block")?)?;
+            let lock_blocks = u64_from_value(inner.get("lock_blocks").ok_or("missing lock_blocks")?)?;
+            let deposit = u64_from_value(inner.get("deposit").ok_or("missing deposit")?)?;
+            let fee = u64_from_value(inner.get("fee").ok_or("missing fee")?)?;
+            let nonce = u64_from_value(inner.get("nonce").ok_or("missing nonce")?)?;
+            let chain_id = inner.get("chain_id").and_then(|c| u64_from_value(c).ok());
+            let valid_until_height = inner.get("valid_until_height").and_then(|h| u64_from_value(h).ok());
+            let signature = bytes_from_value(inner.get("signature").ok_or("missing signature")?)?;
+            Ok(Transaction::CreateAssetPool {
+                from,
+                game_id,
+                reward_per_block,
+                lock_blocks,
+                deposit,
+                fee,
+                nonce,
+                chain_id,
+                valid_until_height,
+                signature,
+            })
+        }
+        "FundAssetPool" => {
+            let from = bytes32_from_value(inner.get("from").ok_or("missing from")?)?;
+            let pool_id = bytes32_from_value(inner.get("pool_id").ok_or("missing pool_id")?)?;
+            let amount = u64_from_value(inner.get("amount").ok_or("missing amount")?)?;
+            let fee = u64_from_value(inner.get("fee").ok_or("missing fee")?)?;
+            let nonce = u64_from_value(inner.get("nonce").ok_or("missing nonce")?)?;
+            let chain_id = inner.get("chain_id").and_then(|c| u64_from_value(c).ok());
+            let valid_until_height = inner.get("valid_until_height").and_then(|h| u64_from_value(h).ok());
+            let signature = bytes_from_value(inner.get("signature").ok_or("missing signature")?)?;
+            Ok(Transaction::FundAssetPool { from, pool_id, amount, fee, nonce, chain_id, valid_until_height, signature })
+        }
+        "StakeAsset" => {
+            let from = bytes32_from_value(inner.get("from").ok_or("missing from")?)?;
+            let pool_id = bytes32_from_value(inner.get("pool_id").ok_or("missing pool_id")?)?;
+            let asset_id = bytes32_from_value(inner.get("asset_id").ok_or("missing asset_id")?)?;
+            let fee = u64_from_value(inner.get("fee").ok_or("missing fee")?)?;
+            let nonce = u64_from_value(inner.get("nonce").ok_or("missing nonce")?)?;
+            let chain_id = inner.get("chain_id").and_then(|c| u64_from_value(c).ok());
+            let valid_until_height = inner.get("valid_until_height").and_then(|h| u64_from_value(h).ok());
+            let signature = bytes_from_value(inner.get("signature").ok_or("missing signature")?)?;
+            Ok(Transaction::StakeAsset { from, pool_id, asset_id, fee, nonce, chain_id, valid_until_height, signature })
+        }
+        "UnstakeAsset" => {
+            let from = bytes32_from_value(inner.get("from").ok_or("missing from")?)?;
+            let asset_id = bytes32_from_value(inner.get("asset_id").ok_or("missing asset_id")?)?;
+            let fee = u64_from_value(inner.get("fee").ok_or("missing fee")?)?;
+            let nonce = u64_from_value(inner.get("nonce").ok_or("missing nonce")?)?;
+            let chain_id = inner.get("chain_id").and_then(|c| u64_from_value(c).ok());
+            let valid_until_height = inner.get("valid_until_height").and_then(|h| u64_from_value(h).ok());
+            let signature = bytes_from_value(inner.get("signature").ok_or("missing signature")?)?;
+            Ok(Transaction::UnstakeAsset { from, asset_id, fee, nonce, chain_id, valid_until_height, signature })
+        }
+        "ClaimAssetRewards" => {
+            let from = bytes32_from_value(inner.get("from").ok_or("missing from")?)?;
+            let pool_id = bytes32_from_value(inner.get("pool_id").ok_or("missing pool_id")?)?;
+            let fee = u64_from_value(inner.get("fee").ok_or("missing fee")?)?;
+            let nonce = u64_from_value(inner.get("nonce").ok_or("missing nonce")?)?;
+            let chain_id = inner.get("chain_id").and_then(|c| u64_from_value(c).ok());
+            let valid_until_height = inner.get("valid_until_height").and_then(|h| u64_from_value(h).ok());
+            let signature = bytes_from_value(inner.get("signature").ok_or("missing signature")?)?;
+            Ok(Transaction::ClaimAssetRewards { from, pool_id, fee, nonce, chain_id, valid_until_height, signature })
+        }
+        "Breed" => {
+            let from = bytes32_from_value(inner.get("from").ok_or("missing from")?)?;
+            let parent_a = bytes32_from_value(inner.get("parent_a").ok_or("missing parent_a")?)?;
+            let parent_b = bytes32_from_value(inner.get("parent_b").ok_or("missing parent_b")?)?;
+            let fee = u64_from_value(inner.get("fee").ok_or("missing fee")?)?;
+            let nonce = u64_from_value(inner.get("nonce").ok_or("missing nonce")?)?;
+            let chain_id = inner.get("chain_id").and_then(|c| u64_from_value(c).ok());
+            let valid_until_height = inner.get("valid_until_height").and_then(|h| u64_from_value(h).ok());
+            let signature = bytes_from_value(inner.get("signature").ok_or("missing signature")?)?;
+            Ok(Transaction::Breed { from, parent_a, parent_b, fee, nonce, chain_id, valid_until_height, signature })
+        }
+        "Upgrade" => {
+            let from = bytes32_from_value(inner.get("from").ok_or("missing from")?)?;
+            let asset_id = bytes32_from_value(inner.get("asset_id").ok_or("missing asset_id")?)?;
+            let catalysts = match inner.get("catalysts") {
+                Some(v) if !v.is_null() => v
+                    .as_array()
+                    .ok_or("expected array for catalysts")?
+                    .iter()
+                    .map(bytes32_from_value)
+                    .collect::<Result<Vec<_>, _>>()?,
+                _ => Vec::new(),
+            };
+            let fee = u64_from_value(inner.get("fee").ok_or("missing fee")?)?;
+            let nonce = u64_from_value(inner.get("nonce").ok_or("missing nonce")?)?;
+            let chain_id = inner.get("chain_id").and_then(|c| u64_from_value(c).ok());
+            let valid_until_height = inner.get("valid_until_height").and_then(|h| u64_from_value(h).ok());
+            let signature = bytes_from_value(inner.get("signature").ok_or("missing signature")?)?;
+            Ok(Transaction::Upgrade { from, asset_id, catalysts, fee, nonce, chain_id, valid_until_height, signature })
+        }
+        "TransferGovernance" => {
+            let from = bytes32_from_value(inner.get("from").ok_or("missing from")?)?;
+            let to = bytes32_from_value(inner.get("to").ok_or("missing to")?)?;
+            let amount = u64_from_value(inner.get("amount").ok_or("missing amount")?)?;
+            let fee = u64_from_value(inner.get("fee").ok_or("missing fee")?)?;
+            let nonce = u64_from_value(inner.get("nonce").ok_or("missing nonce")?)?;
+            let chain_id = inner.get("chain_id").and_then(|c| u64_from_value(c).ok());
+            let valid_until_height = inner.get("valid_until_height").and_then(|h| u64_from_value(h).ok());
+            let signature = bytes_from_value(inner.get("signature").ok_or("missing signature")?)?;
+            Ok(Transaction::TransferGovernance { from, to, amount, fee, nonce, chain_id, valid_until_height, signature })
+        }
+        "StakeGovernance" | "UnstakeGovernance" => {
+            let from = bytes32_from_value(inner.get("from").ok_or("missing from")?)?;
+            let amount = u64_from_value(inner.get("amount").ok_or("missing amount")?)?;
+            let fee = u64_from_value(inner.get("fee").ok_or("missing fee")?)?;
+            let nonce = u64_from_value(inner.get("nonce").ok_or("missing nonce")?)?;
+            let chain_id = inner.get("chain_id").and_then(|c| u64_from_value(c).ok());
+            let valid_until_height = inner.get("valid_until_height").and_then(|h| u64_from_value(h).ok());
+            let signature = bytes_from_value(inner.get("signature").ok_or("missing signature")?)?;
+            if tag == "StakeGovernance" {
+                Ok(Transaction::StakeGovernance { from, amount, fee, nonce, chain_id, valid_until_height, signature })
+            } else {
+                Ok(Transaction::UnstakeGovernance { from, amount, fee, nonce, chain_id, valid_until_height, signature })
+            }
+        }
+        "SetCommission" => {
+            let from = bytes32_from_value(inner.get("from").ok_or("missing from")?)?;
+            let rate_bps = u64_from_value(inner.get("rate_bps").ok_or("missing rate_bps")?)?;
+            let fee = u64_from_value(inner.get("fee").ok_or("missing fee")?)?;
+            let nonce = u64_from_value(inner.get("nonce").ok_or("missing nonce")?)?;
+            let chain_id = inner.get("chain_id").and_then(|c| u64_from_value(c).ok());
+            let valid_until_height = inner.get("valid_until_height").and_then(|h| u64_from_value(h).ok());
+            let signature = bytes_from_value(inner.get("signature").ok_or("missing signature")?)?;
+            Ok(Transaction::SetCommission { from, rate_bps, fee, nonce, chain_id, valid_until_height, signature })
+        }
+        "VoteCommitteeParams" => {
+            let from = bytes32_from_value(inner.get("from").ok_or("missing from")?)?;
+            let min_stake = u64_from_value(inner.get("min_stake").ok_or("missing min_stake")?)?;
+            let max_weight_bps = u64_from_value(inner.get("max_weight_bps").ok_or("missing max_weight_bps")?)?;
+            let fee = u64_from_value(inner.get("fee").ok_or("missing fee")?)?;
+            let nonce = u64_from_value(inner.get("nonce").ok_or("missing nonce")?)?;
+            let chain_id = inner.get("chain_id").and_then(|c| u64_from_value(c).ok());
+            let valid_until_height = inner.get("valid_until_height").and_then(|h| u64_from_value(h).ok());
+            let signature = bytes_from_value(inner.get("signature").ok_or("missing signature")?)?;
+            Ok(Transaction::VoteCommitteeParams { from, min_stake, max_weight_bps, fee, nonce, chain_id, valid_until_height, signature })
+        }
+        _ => Err(format!("unknown transaction variant: {}", tag)),
+    }
+}
+
+/// Transaction response
+#[derive(Debug, Serialize)]
+pub struct TransactionResponse {
+    pub hash: String,
+    pub status: String,
+}
+
+/// Validator info response
+#[derive(Debug, Serialize)]
+pub struct ValidatorResponse {
+    pub address: String,
+    pub total_staked: u64,
+    pub self_stake: u64,
+    pub delegator_count: u64,
+    pub reputation_score: u64,
+    /// "active" or "jailed"
+    pub status: String,
+    pub jailed_until: Option<i64>,
+    pub jail_count: u64,
+    pub missed_slots: u64,
+    /// Share of delegator rewards the validator keeps (basis points)
+    pub commission_bps: u64,
+    /// Announced commission increase and the height it takes effect
+    pub pending_commission: Option<crate::tokenomics::PendingCommission>,
+}
+
+impl ValidatorResponse {
+    fn new(info: &crate::tokenomics::ValidatorInfo, missed_slots: u64) -> Self {
+        Self {
+            address: address_to_hex(&info.address),
+            total_staked: info.total_staked,
+            self_stake: info.self_stake,
+            delegator_count: info.delegator_count,
+            reputation_score: info.reputation_score,
+            status: if info.jailed_until.is_some() { "jailed" } else { "active" }.to_string(),
+            jailed_until: info.jailed_until,
+            jail_count: info.jail_count,
+            missed_slots,
+            commission_bps: info.commission_bps,
+            pending_commission: info.pending_commission,
+        }
+    }
+}
+
+/// Account info response
+#[derive(Debug, Serialize)]
+pub struct AccountInfo {
+    pub address: String,
+    pub balance: u64,
+    /// Balance reserved by this account's transactions waiting in the pool
+    pub reserved: u64,
+    /// Balance new transactions can still spend (`balance - reserved`)
+    pub available: u64,
+    pub nonce: u64,
+    pub staked: u64,
+    /// Next sequence of each nonce lane in use (lane id -> sequence); empty unless the
+    /// account is one of `consensus.nonce_lane_accounts`
+    #[serde(skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub nonce_lanes: std::collections::BTreeMap<u16, u64>,
+}
+
+/// Governance token response
+#[derive(Debug, Serialize)]
+pub struct GovernanceTokenInfo {
+    pub enabled: bool,
+    /// Ticker of the governance token (`null` when it is disabled)
+    pub symbol: Option<String>,
+    pub total_supply: u64,
+    pub total_staked: u64,
+}
+
+/// Governance holdings of one account
+#[derive(Debug, Serialize)]
+pub struct GovernanceAccountInfo {
+    pub address: String,
+    pub balance: u64,
+    pub staked: u64,
+    /// Voting weight: staked governance tokens, or HAZE stake when the token is disabled
+    pub weight: u64,
+}
+
+/// Block info response
+#[derive(Debug, Serialize)]
+pub struct BlockInfo {
+    pub hash: String,
+    pub parent_hash: String,
+    pub height: u64,
+    pub timestamp: i64,
+    pub validator: String,
+    pub transaction_count: usize,
+    pub wave_number: u64,
+}
+
+/// Blockchain info response
+#[derive(Debug, Serialize, Clone)]
+pub struct BlockchainInfo {
+    pub chain_id: u64,
+    /// Hex network id prefixed to every signing payload and block hash
+    pub network_id: String,
+    pub current_height: u64,
+    pub total_supply: u64,
+    pub current_wave: u64,
+    pub state_root: String, // Hex-encoded state root hash
+    pub last_finalized_height: u64,
+    pub last_finalized_wave: u64,
+    /// Largest transaction (wire encoding, bytes) the node accepts
+    pub max_transaction_bytes: usize,
+}
+
+/// Largest request body: room for a maximal transaction with hex-encoded fields
+fn max_request_body(config: &Config) -> usize {
+    config.consensus.max_transaction_bytes.saturating_mul(2).saturating_add(64 * 1024)
+}
+
+/// Create API router
+pub fn create_router(state: ApiState) -> Router {
+    let enable_cors = state.config.api.enable_cors;
+    
+    let router = Router::new()
+        .route("/health", get(health_check))
+        .route("/api/v1/health", get(detailed_health_check))
+        .route("/api/v1/blockchain/info", get(get_blockchain_info))
+        .route("/api/v1/metrics/basic", get(get_basic_metrics))
+        .route("/api/v1/metrics/prometheus", get(get_prometheus_metrics))
+        .route("/api/v1/stats/fees", get(get_fee_stats))
+        .route("/api/v1/stats/shards", get(get_shard_stats))
+        .route("/api/v1/stats/storage", get(get_storage_stats))
+        .route("/api/v1/consensus/dag", get(get_dag))
+        .route("/api/v1/consensus/waves", get(get_waves))
+        .route("/api/v1/consensus/committee", get(get_committee))
+        .route("/api/v1/transactions", post(send_transaction))
+        .route("/api/v1/transactions/estimate-gas", post(estimate_gas))
+        .route("/api/v1/transactions/estimate-gas/batch", post(estimate_gas_batch))
+        .route("/api/v1/transactions/dead-letter", get(get_dead_letters))
+        .route("/api/v1/transactions/dead-letter/:hash/retry", post(retry_dead_letter))
+        .route("/api/v1/transactions/:hash", get(get_transaction))
+        .route("/api/v1/transactions/:hash/logs", get(get_transaction_logs))
+        .route("/api/v1/transactions/:hash/receipt", get(get_transaction_receipt))
+        .route("/api/v1/transactions/:hash/trace", get(get_transaction_trace))
+        .route("/api/v1/logs", get(get_logs))
+        .route("/api/v1/blocks/:hash", get(get_block_by_hash))
+        .route(
+            "/api/v1/blocks/:hash/full",
+            get(get_full_block).layer(tower_http::compression::CompressionLayer::new()),
+        )
+        .route("/api/v1/blocks/height/:height", get(get_block_by_height))
+        .route("/api/v1/accounts", get(list_accounts))
+        .route("/api/v1/accounts/:address", get(get_account))
+        .route("/api/v1/accounts/:address/balance", get(get_balance))
+        .route("/api/v1/proof/account/:address", get(get_account_proof))
+        .route("/api/v1/proof/asset/:asset_id", get(get_asset_proof))
+        .route("/api/v1/contracts/:address", get(get_contract))
+        .route("/api/v1/contracts/:address/code", get(get_contract_code))
+        .route("/api/v1/contracts/:address/call", post(call_contract))
+        .route("/api/v1/collections/:game_id/hook", get(get_collection_hook))
+        .route("/api/v1/collections/:game_id/traits", get(get_collection_traits))
+        .route("/api/v1/collections/:game_id/holders", get(get_collection_holders))
+        .route("/api/v1/collections/:game_id/referrals/:player", get(get_referral))
+        .route("/api/v1/referrers/:address/earnings", get(get_referrer_earnings))
+        .route("/api/v1/payment-requests/:request_id", get(get_payment_request))
+        .route("/api/v1/subscriptions/:subscription_id", get(get_subscription))
+        .route("/api/v1/loot-boxes/:box_id", get(get_loot_box))
+        .route("/api/v1/leaderboards/:board_id", get(get_leaderboard))
+        .route("/api/v1/collections/:game_id/leaderboards", get(get_collection_leaderboards))
+        .route("/api/v1/collections/:game_id/seasons", get(get_game_seasons))
+        .route("/api/v1/seasons/:season_id", get(get_season))
+        .route("/api/v1/asset-pools/:pool_id", get(get_asset_pool))
+        .route("/api/v1/breedings/:breeding_id", get(get_breeding))
+        .route("/api/v1/upgrades/:upgrade_id", get(get_upgrade))
+        .route("/api/v1/collections/:game_id/asset-pools", get(get_collection_asset_pools))
+        .route("/api/v1/accounts/:address/asset-stakes", get(get_account_asset_stakes))
+        .route("/api/v1/accounts/:address/subscriptions", get(get_account_subscriptions))
+        .route("/api/v1/governance", get(get_governance_token))
+        .route("/api/v1/governance/:address", get(get_governance_account))
+        .route("/api/v1/validators", get(get_validators))
+        .route("/api/v1/validators/:address", get(get_validator))
+        .route("/api/v1/assets/:asset_id", get(get_asset))
+        .route("/api/v1/assets/:asset_id/blob/:blob_key", get(get_asset_blob))
+        .route("/api/v1/assets/:asset_id/history", get(get_asset_history))
+        .route("/api/v1/assets/:asset_id/rarity", get(get_asset_rarity))
+        .route("/api/v1/assets/:asset_id/lineage", get(get_asset_lineage))
+        .route("/api/v1/assets/:asset_id/breeding", get(get_asset_breeding))
+        .route("/api/v1/assets/:asset_id/upgrades", get(get_asset_upgrades))
+        .route("/api/v1/assets/:asset_id/versions", get(get_asset_versions))
+        .route("/api/v1/assets/:asset_id/versions/:version", get(get_asset_version))
+        .route("/api/v1/assets/:asset_id/snapshot", post(create_asset_snapshot))
+        .route("/api/v1/assets", post(create_asset))
+        .route("/api/v1/assets/search", get(search_assets))
+        .route("/api/v1/assets/:asset_id/condense", post(condense_asset))
+        .route("/api/v1/assets/:asset_id/evaporate", post(evaporate_asset))
+        .route("/api/v1/assets/:asset_id/merge", post(merge_assets))
+        .route("/api/v1/assets/:asset_id/split", post(split_asset))
+        .route("/api/v1/assets/estimate-gas", post(estimate_gas))
+        .route("/api/v1/gas/estimate-priority", get(estimate_priority_fee))
+        .route("/api/v1/assets/:asset_id/permissions", get(get_asset_permissions))
+        .route("/api/v1/assets/:asset_id/permissions", post(set_asset_permissions))
+        .route("/api/v1/assets/:asset_id/export", get(export_asset))
+        .route("/api/v1/assets/import", post(import_asset))
+        .route("/api/v1/economy/pools", get(get_liquidity_pools))
+        .route("/api/v1/economy/pools", post(create_liquidity_pool))
+        .route("/api/v1/economy/pools/:pool_id", get(get_liquidity_pool))
+        .route("/api/v1/economy/pools/:pool_id/twap", get(get_pool_twap))
+        .route("/api/v1/economy/zones", get(get_economic_zones))
+        .route("/api/v1/economy/zones/:game_id/:zone_id", get(get_economic_zone))
+        .route("/api/v1/economy/orderbooks/:game_id", get(get_order_book))
+        .route("/api/v1/economy/orderbooks/:game_id/trades", get(get_order_book_trades))
+        .route("/api/v1/market/collections/:id/stats", get(get_collection_market_stats))
+        .route("/api/v1/economy/orders/:order_id", get(get_order))
+        .route("/api/v1/watchlists", get(list_watchlists))
+        .route("/api/v1/watchlists", post(create_watchlist))
+        .route("/api/v1/watchlists/:id", axum::routing::delete(delete_watchlist))
+        .route("/api/v1/auth/challenge", post(create_sign_in_challenge))
+        .route("/api/v1/auth/session", post(create_session))
+        .route("/api/v1/auth/session", get(get_session))
+        .route("/api/v1/auth/session", axum::routing::delete(delete_session))
+        .route("/api/v1/messages", post(send_direct_message))
+        .route("/api/v1/messages/:address", get(list_direct_messages))
+        .route("/api/v1/messages/:address/:id", axum::routing::delete(delete_direct_message))
+        .route("/api/v1/custody/players/:player_id", get(get_custodial_account))
+        .route("/api/v1/custody/players/:player_id/transactions", post(send_custodial_transaction))
+        .route("/api/v1/custody/players/:player_id/export", post(export_custodial_key))
+        .route("/api/v1/tenant", get(get_own_tenant))
+        .route("/api/v1/admin/tenants", get(list_tenants))
+        .route("/api/v1/admin/tenants", post(create_tenant))
+        .route("/api/v1/admin/tenants/:id", get(get_tenant))
+        .route("/api/v1/admin/tenants/:id", axum::routing::delete(delete_tenant))
+        .route("/api/v1/admin/tenants/:id/quota", axum::routing::put(set_tenant_quota))
+        .route("/api/v1/admin/tenants/:id/keys", post(issue_tenant_key))
+        .route("/api/v1/admin/tenants/:id/keys/:key_hash", axum::routing::delete(revoke_tenant_key))
+        .route("/api/v1/admin/metering", get(export_metering))
+        .route("/api/v1/admin/divergence", get(get_divergence_incidents))
+        .route("/api/v1/admin/consensus/pause", post(pause_block_production))
+        .route("/api/v1/admin/consensus/resume", post(resume_block_production))
+        .route("/api/v1/telemetry", post(submit_telemetry))
+        .route("/api/v1/telemetry/nodes", get(list_telemetry_nodes))
+        .route("/api/v1/dev/faucet", post(dev_faucet))
+        .route("/api/v1/ws", get(ws_handler))
+        .route("/api/v1/sync/start", post(start_sync))
+        .route("/api/v1/sync/status", get(get_sync_status))
+        .route("/api/v1/node/version", get(get_node_version))
+        .route("/api/v1/node/info", get(get_node_info))
+        .route("/api/v1/node/peers", get(get_node_peers))
+        .route("/api/v1/features", get(get_features))
+        .layer(axum::extract::DefaultBodyLimit::max(max_request_body(&state.config)))
+        .layer(axum::middleware::from_fn_with_state(state.clone(), tenant_gate))
+        .layer(axum::middleware::from_fn_with_state(state.clone(), replica_gate))
+        .layer(axum::middleware::from_fn_with_state(state.clone(), role_gate))
+        .layer(axum::middleware::from_fn_with_state(state.clone(), meter_requests))
+        .layer(axum::middleware::from_fn_with_state(state.clone(), time_requests))
+        .with_state(state);
+    
+    // Add CORS if enabled
+    if enable_cors {
+        router.layer(
+            tower_http::cors::CorsLayer::new()
+                .allow_origin(tower_http::cors::Any)
+                .allow_methods(tower_http::cors::Any)
+                .allow_headers(tower_http::cors::Any)
+        )
+    } else {
+        router
+    }
+}
+
+/// Health check endpoint
+async fn health_check() -> Json<ApiResponse<&'static str>> {
+    Json(ApiResponse::success("OK"))
+}
+
+/// Health check with clock drift against peers, plus the lag behind the primary on a read
+/// replica; `status` is "degraded" while drifting or lagging
+async fn detailed_health_check(
+    State(api_state): State<ApiState>,
+) -> Json<ApiResponse<serde_json::Value>> {
+    let clock = api_state.consensus.clock_status();
+    let replica = api_state.config.replica.enabled.then(|| api_state.consensus.replica_status());
+    let lagging = replica.as_ref().is_some_and(|replica| replica.lagging);
+    let pause = api_state.consensus.control().current(chrono::Utc::now().timestamp());
+    let maintenance = pause.as_ref().is_some_and(|pause| pause.maintenance);
+    let mut health = serde_json::json!({
+        "status": if clock.drifting || lagging || maintenance { "degraded" } else { "ok" },
+        "clock": clock,
+        "pause": pause,
+    });
+    if let Some(replica) = replica {
+        health["replica"] = serde_json::json!(replica);
+    }
+    Json(ApiResponse::success(health))
+}
+
+/// Identity of this node: its role and the subsystems it runs
+async fn get_node_info(
+    State(api_state): State<ApiState>,
+) -> Json<ApiResponse<serde_json::Value>> {
+    let config = &api_state.config;
+    let (role, subsystems) = config.subsystems();
+    Json(ApiResponse::success(serde_json::json!({
+        "node_id": config.node_id,
+        "chain_id": config.chain_id,
+        "role": role,
+        "subsystems": subsystems,
+        "node_type": config.network.node_type,
+        "read_replica": config.replica.enabled,
+        "dev": config.dev.enabled,
+        "version": crate::version::NodeVersion::current(config.chain_id),
+        "current_height": api_state.state.current_height(),
+    })))
+}
+
+/// Binary, protocol and state versions of this node, plus the versions connected peers
+/// announced in the handshake and how this node talks to each
+async fn get_node_version(
+    State(api_state): State<ApiState>,
+) -> Json<ApiResponse<serde_json::Value>> {
+    let mut version = serde_json::json!(crate::version::NodeVersion::current(api_state.config.chain_id));
+    version["peers"] = serde_json::json!(api_state.consensus.peer_versions().peers());
+    Json(ApiResponse::success(version))
+}
+
+/// Connection health of peers: keep-alive round trips, last-seen times and missed pings,
+/// plus dead peer disconnects and the redial schedule
+async fn get_node_peers(
+    State(api_state): State<ApiState>,
+) -> Json<ApiResponse<serde_json::Value>> {
+    let health = api_state.consensus.peer_health();
+    let now = crate::time_sync::now_ms();
+    let peers: Vec<serde_json::Value> = health
+        .peers()
+        .into_iter()
+        .map(|peer| {
+            let mut entry = serde_json::json!(peer);
+            entry["last_seen_ms_ago"] = serde_json::json!((now - peer.last_seen_ms).max(0));
+            entry
+        })
+        .collect();
+    let mut body = serde_json::json!(health.status());
+    body["peers"] = serde_json::json!(peers);
+    Json(ApiResponse::success(body))
+}
+
+/// Feature flags with this node's signaling, the current window's signal count and the
+/// activation height of each locked-in or pinned feature
+async fn get_features(
+    State(api_state): State<ApiState>,
+) -> Json<ApiResponse<serde_json::Value>> {
+    let height = api_state.state.current_height();
+    let features = &api_state.config.features;
+    Json(ApiResponse::success(serde_json::json!({
+        "height": height,
+        "window_blocks": features.window_blocks,
+        "threshold_percent": features.threshold_percent,
+        "features": api_state.state.features().status(height),
+    })))
+}
+
+/// Get blockchain info (cached until the state or the consensus checkpoint changes)
+async fn get_blockchain_info(
+    State(api_state): State<ApiState>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<axum::response::Response> {
+    let state_version = api_state.state.state_version();
+    let cache_key = format!(
+        "blockchain_info:{}:{}:{}",
+        api_state.consensus.get_current_wave(),
+        api_state.consensus.get_last_finalized_height(),
+        api_state.consensus.get_last_finalized_wave(),
+    );
+    let cached = match api_state.response_cache.get(&cache_key, state_version) {
+        Some(cached) => cached,
+        None => {
+            let info = build_blockchain_info(&api_state);
+            let body = serde_json::to_vec(&ApiResponse::success(info))
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            let cached = CachedResponse {
+                etag: http_cache::etag_for(&state_version.to_string(), &body),
+                body: body.into(),
+                state_version,
+            };
+            api_state.response_cache.insert(cache_key, cached.clone());
+            cached
+        }
+    };
+    Ok(http_cache::json_response(&headers, &cached.etag, http_cache::REVALIDATE, cached.body))
+}
+
+fn build_blockchain_info(api_state: &ApiState) -> BlockchainInfo {
+    let height = api_state.state.current_height();
+    let total_supply = api_state.state.tokenomics().total_supply();
+    
+    // Get current wave from consensus
+    let current_wave = api_state.consensus.get_current_wave();
+    
+    // Compute state root
+    let state_root = api_state.state.compute_state_root();
+    
+    // Get finalized checkpoint info
+    let last_finalized_height = api_state.consensus.get_last_finalized_height();
+    let last_finalized_wave = api_state.consensus.get_last_finalized_wave();
+    
+    BlockchainInfo {
+        chain_id: api_state.config.chain_id,
+        network_id: hash_to_hex(&api_state.consensus.network_id()),
+        current_height: height,
+        total_supply,
+        current_wave,
+        state_root: hash_to_hex(&state_root),
+        last_finalized_height,
+        last_finalized_wave,
+        max_transaction_bytes: api_state.config.consensus.max_transaction_bytes,
+    }
+}
+
+/// Send transaction
+async fn send_transaction(
+    State(api_state): State<ApiState>,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<SendTransactionRequest>,
+) -> ApiResult<Json<ApiResponse<TransactionResponse>>> {
+    let tx = request.transaction;
+    let tx_hash = tx.hash();
+    
+    authorize_submission(&api_state, &headers, &tx)?;
+    match api_state.consensus.add_transaction(tx.clone()) {
+        Ok(()) => {
+            // Broadcast transaction to network (async, don't wait)
+            // For now, transactions will be broadcast when blocks are created
+            // In future, we can add direct transaction broadcasting here
+            tracing::debug!("Transaction added to pool, will be broadcast with next block");
+            
+            let response = TransactionResponse {
+                hash: hash_to_hex(&tx_hash),
+                status: "pending".to_string(),
+            };
+            Ok(Json(ApiResponse::success(response)))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Get transaction by hash
+async fn get_transaction(
+    State(api_state): State<ApiState>,
+    Path(hash_str): Path<String>,
+) -> ApiResult<Json<ApiResponse<TransactionResponse>>> {
+    let hash = crate::types::hex_to_hash(&hash_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    
+    // Try to get from transaction pool
+    if api_state.consensus.get_transaction(&hash).is_some() {
+        let response = TransactionResponse {
+            hash: hash_to_hex(&hash),
+            status: "pending".to_string(),
+        };
+        return Ok(Json(ApiResponse::success(response)));
+    }
+    
+    // Failed during block application (see /api/v1/transactions/dead-letter)
+    if api_state.state.get_dead_letter(&hash).is_some() {
+        let response = TransactionResponse {
+            hash: hash_to_hex(&hash),
+            status: "failed".to_string(),
+        };
+        return Ok(Json(ApiResponse::success(response)));
+    }
+    
+    // Check in executed blocks (indexed by hash when the block is applied)
+    if api_state.state.get_transaction_height(&hash).is_some() {
+        let response = TransactionResponse {
+            hash: hash_to_hex(&hash),
+            status: "executed".to_string(),
+        };
+        return Ok(Json(ApiResponse::success(response)));
+    }
+    
+    Err(StatusCode::NOT_FOUND.into())
+}
+
+/// Receipt of an applied or failed transaction (404 while it is pending or unknown)
+async fn get_transaction_receipt(
+    State(api_state): State<ApiState>,
+    Path(hash_str): Path<String>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let hash = crate::types::hex_to_hash(&hash_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let receipt = api_state.state.get_receipt(&hash).ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(ApiResponse::success(receipt_to_json(&receipt))))
+}
+
+/// Maximum block range scanned by one `/api/v1/logs` query
+const MAX_LOG_QUERY_RANGE: u64 = 10_000;
+
+/// Log filter query parameters (topics are positional; omitted = any)
+#[derive(Debug, Deserialize)]
+pub struct LogsQuery {
+    pub address: Option<String>,
+    pub topic0: Option<String>,
+    pub topic1: Option<String>,
+    pub topic2: Option<String>,
+    pub topic3: Option<String>,
+    pub from_height: Option<u64>,
+    pub to_height: Option<u64>,
+    pub limit: Option<usize>,
+}
+
+fn log_entry_to_json(entry: &crate::state::LogEntry) -> serde_json::Value {
+    serde_json::json!({
+        "address": address_to_hex(&entry.log.address),
+        "topics": entry.log.topics.iter().map(hash_to_hex).collect::<Vec<_>>(),
+        "data": hex::encode(&entry.log.data),
+        "block_height": entry.block_height,
+        "block_hash": hash_to_hex(&entry.block_hash),
+        "transaction_hash": hash_to_hex(&entry.tx_hash),
+        "transaction_index": entry.tx_index,
+        "log_index": entry.log_index,
+    })
+}
+
+/// Query contract logs by emitting contract and topics
+async fn get_logs(
+    State(api_state): State<ApiState>,
+    axum::extract::Query(query): axum::extract::Query<LogsQuery>,
+) -> ApiResult<Json<ApiResponse<Vec<serde_json::Value>>>> {
+    let address = match query.address.as_deref() {
+        Some(a) => Some(crate::types::hex_to_address(a).ok_or(StatusCode::BAD_REQUEST)?),
+        None => None,
+    };
+    let mut topics = Vec::new();
+    for topic in [&query.topic0, &query.topic1, &query.topic2, &query.topic3] {
+        topics.push(match topic.as_deref() {
+            Some(t) => Some(crate::types::hex_to_hash(t).ok_or(StatusCode::BAD_REQUEST)?),
+            None => None,
+        });
+    }
+    while topics.last() == Some(&None) {
+        topics.pop();
+    }
+
+    let to_height = query.to_height.unwrap_or_else(|| api_state.state.current_height());
+    let from_height = query.from_height.unwrap_or(to_height.saturating_sub(MAX_LOG_QUERY_RANGE - 1));
+    if from_height > to_height || to_height - from_height >= MAX_LOG_QUERY_RANGE {
+        return Err(StatusCode::BAD_REQUEST.into());
+    }
+
+    let filter = crate::state::LogFilter {
+        address,
+        topics,
+        from_height,
+        to_height: Some(to_height),
+        limit: query.limit.unwrap_or(1000).min(10_000),
+    };
+    let logs = api_state.state.get_logs(&filter).iter().map(log_entry_to_json).collect();
+    Ok(Json(ApiResponse::success(logs)))
+}
+
+/// Logs emitted by an executed transaction
+async fn get_transaction_logs(
+    State(api_state): State<ApiState>,
+    Path(hash_str): Path<String>,
+) -> ApiResult<Json<ApiResponse<Vec<serde_json::Value>>>> {
+    let hash = crate::types::hex_to_hash(&hash_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let logs = api_state.state.get_transaction_logs(&hash).iter().map(log_entry_to_json).collect();
+    Ok(Json(ApiResponse::success(logs)))
+}
+
+/// Re-execute a transaction step by step to show which check fails
+///
+/// Pending transactions run against the current state, dead-lettered ones against the
+/// state before their block, executed ones against the state before them in their block.
+async fn get_transaction_trace(
+    State(api_state): State<ApiState>,
+    Path(hash_str): Path<String>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let hash = crate::types::hex_to_hash(&hash_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let state = &api_state.state;
+
+    let (status, tx, height, preceding) = if let Some(tx) = api_state.consensus.get_transaction(&hash) {
+        ("pending", tx, state.current_height(), Vec::new())
+    } else if let Some(entry) = state.get_dead_letter(&hash) {
+        ("failed", entry.transaction, entry.block_height.saturating_sub(1), Vec::new())
+    } else if let Some(height) = state.get_transaction_height(&hash) {
+        let block = state.get_block_by_height(height).ok_or(StatusCode::NOT_FOUND)?;
+        let index = block.transactions.iter().position(|tx| tx.hash() == hash).ok_or(StatusCode::NOT_FOUND)?;
+        let tx = block.transactions[index].clone();
+        let preceding = block.transactions[..index].to_vec();
+        ("executed", tx, height - 1, preceding)
+    } else {
+        return Err(StatusCode::NOT_FOUND.into());
+    };
+
+    match state.trace_transaction(&tx, height, &preceding) {
+        Ok(trace) => Ok(Json(ApiResponse::success(serde_json::json!({
+            "hash": hash_to_hex(&hash),
+            "status": status,
+            "state_height": trace.state_height,
+            "success": trace.error.is_none(),
+            "error": trace.error,
+            "error_code": trace.error_code,
+            "error_params": trace.error_params,
+            "steps": trace.steps,
+        })))),
+        Err(e) => Ok(Json(ApiResponse::from_error(&e))),
+    }
+}
+
+/// Dead-letter list query parameters
+#[derive(Debug, Deserialize)]
+pub struct DeadLetterQuery {
+    pub limit: Option<usize>,
+}
+
+/// List transactions that failed block application
+async fn get_dead_letters(
+    State(api_state): State<ApiState>,
+    axum::extract::Query(query): axum::extract::Query<DeadLetterQuery>,
+) -> ApiResult<Json<ApiResponse<Vec<serde_json::Value>>>> {
+    let limit = query.limit.unwrap_or(100);
+    let entries: Vec<serde_json::Value> = api_state.state.get_dead_letters(limit)
+        .iter()
+        .map(|entry| {
+            serde_json::json!({
+                "hash": hash_to_hex(&entry.tx_hash),
+                "error": entry.error,
+                "error_code": entry.error_code,
+                "error_params": entry.error_params,
+                "block_height": entry.block_height,
+                "block_hash": hash_to_hex(&entry.block_hash),
+                "tx_index": entry.tx_index,
+                "failed_at": entry.failed_at,
+                "attempts": entry.attempts,
+            })
+        })
+        .collect();
+    Ok(Json(ApiResponse::success(entries)))
+}
+
+/// Resubmit a dead-lettered transaction to the pool
+async fn retry_dead_letter(
+    State(api_state): State<ApiState>,
+    Path(hash_str): Path<String>,
+) -> ApiResult<Json<ApiResponse<TransactionResponse>>> {
+    let hash = crate::types::hex_to_hash(&hash_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    
+    if api_state.state.get_dead_letter(&hash).is_none() {
+        return Err(StatusCode::NOT_FOUND.into());
+    }
+    
+    match api_state.consensus.retry_dead_letter(&hash) {
+        Ok(()) => {
+            let response = TransactionResponse {
+                hash: hash_to_hex(&hash),
+                status: "pending".to_string(),
+            };
+            Ok(Json(ApiResponse::success(response)))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Block response with the block hash as ETag
+fn block_response(
+    headers: &axum::http::HeaderMap,
+    block: &crate::types::Block,
+    cache_control: &'static str,
+) -> ApiResult<axum::response::Response> {
+    let info = BlockInfo {
+        hash: hash_to_hex(&block.header.hash),
+        parent_hash: hash_to_hex(&block.header.parent_hash),
+        height: block.header.height,
+        timestamp: block.header.timestamp,
+        validator: address_to_hex(&block.header.validator),
+        transaction_count: block.transactions.len(),
+        wave_number: block.header.wave_number,
+    };
+    let etag = format!("\"{}\"", info.hash);
+    let body = serde_json::to_vec(&ApiResponse::success(info))
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(http_cache::json_response(headers, &etag, cache_control, body.into()))
+}
+
+/// Get block by hash
 async fn get_block_by_hash(
     State(api_state): State<ApiState>,
-    Path(hash_str): Path<String>,
-) -> ApiResult<Json<ApiResponse<BlockInfo>>> {
-    let hash = crate::types::hex_to_hash(&hash_str)
+    Path(hash_str): Path<String>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<axum::response::Response> {
+    let hash = crate::types::hex_to_hash(&hash_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    
+    if let Some(block) = api_state.state.get_block(&hash) {
+        // A block never changes once it has a hash
+        block_response(&headers, &block, http_cache::IMMUTABLE)
+    } else {
+        Err(StatusCode::NOT_FOUND.into())
+    }
+}
+
+/// Byte fields of transactions that are hex-encoded even when empty
+const TX_BYTE_FIELDS: [&str; 3] = ["signature", "code", "args"];
+
+/// Transaction as JSON with byte fields hex-encoded (the shape `POST /api/v1/transactions` accepts)
+fn transaction_to_json(tx: &Transaction) -> serde_json::Value {
+    fn hex_bytes(value: serde_json::Value, byte_field: bool) -> serde_json::Value {
+        match value {
+            serde_json::Value::Array(items)
+                if (byte_field || !items.is_empty())
+                    && items.iter().all(|v| v.as_u64().is_some_and(|n| n <= 255)) =>
+            {
+                let bytes: Vec<u8> = items.iter().filter_map(|v| v.as_u64()).map(|n| n as u8).collect();
+                hex::encode(bytes).into()
+            }
+            serde_json::Value::Array(items) => items.into_iter().map(|v| hex_bytes(v, false)).collect(),
+            serde_json::Value::Object(fields) => fields
+                .into_iter()
+                .map(|(key, v)| {
+                    let byte_field = TX_BYTE_FIELDS.contains(&key.as_str());
+                    (key, hex_bytes(v, byte_field))
+                })
+                .collect(),
+            other => other,
+        }
+    }
+    hex_bytes(serde_json::to_value(tx).unwrap_or_default(), false)
+}
+
+/// JSON of a transaction receipt
+fn receipt_to_json(receipt: &crate::state::TxReceipt) -> serde_json::Value {
+    let execution = &receipt.execution;
+    let events: Vec<serde_json::Value> = execution
+        .events
+        .iter()
+        .filter_map(|event| {
+            serde_json::to_value(crate::ws_events::WsEventEnvelope {
+                event_version: crate::ws_events::EVENT_VERSION,
+                event,
+            })
+            .ok()
+        })
+        .collect();
+    serde_json::json!({
+        "tx_hash": hash_to_hex(&receipt.tx_hash),
+        "status": receipt.status.as_str(),
+        "block_hash": hash_to_hex(&receipt.block_hash),
+        "block_height": receipt.block_height,
+        "tx_index": receipt.tx_index,
+        "gas_used": execution.gas_used,
+        "fees_paid": execution.fees_paid,
+        "gas_discount": execution.gas_discount,
+        "sponsored_by": execution.sponsorship.map(|(paymaster, _)| address_to_hex(&paymaster)),
+        "sponsored_gas": execution.sponsorship.map_or(0, |(_, amount)| amount),
+        "error": receipt.error,
+        "error_code": receipt.error_code,
+        "logs": receipt.logs.iter().map(log_entry_to_json).collect::<Vec<_>>(),
+        "events": events,
+    })
+}
+
+/// Get a block with its transactions, receipts, events and DAG references
+///
+/// Blocks are applied atomically, so every transaction of a stored block executed.
+async fn get_full_block(
+    State(api_state): State<ApiState>,
+    Path(hash_str): Path<String>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<axum::response::Response> {
+    let hash = crate::types::hex_to_hash(&hash_str).ok_or(StatusCode::BAD_REQUEST)?;
+    let block = api_state.state.get_block(&hash).ok_or(StatusCode::NOT_FOUND)?;
+    let receipts = api_state.state.get_block_receipts(&block);
+
+    let transactions: Vec<serde_json::Value> = block
+        .transactions
+        .iter()
+        .zip(&receipts)
+        .enumerate()
+        .map(|(index, (tx, receipt))| {
+            serde_json::json!({
+                "hash": hash_to_hex(&receipt.tx_hash),
+                "index": index,
+                "transaction": transaction_to_json(tx),
+                "receipt": receipt_to_json(receipt),
+            })
+        })
+        .collect();
+
+    let header = &block.header;
+    let full = serde_json::json!({
+        "header": {
+            "hash": hash_to_hex(&header.hash),
+            "parent_hash": hash_to_hex(&header.parent_hash),
+            "height": header.height,
+            "timestamp": header.timestamp,
+            "validator": address_to_hex(&header.validator),
+            "merkle_root": hash_to_hex(&header.merkle_root),
+            "state_root": hash_to_hex(&header.state_root),
+            "wave_number": header.wave_number,
+            "committee_id": header.committee_id,
+        },
+        "transactions": transactions,
+        "dag_references": block.dag_references.iter().map(hash_to_hex).collect::<Vec<_>>(),
+    });
+    let body = serde_json::to_vec(&ApiResponse::success(full))
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let etag = format!("\"{}-full\"", hash_to_hex(&header.hash));
+    Ok(http_cache::json_response(&headers, &etag, http_cache::IMMUTABLE, body.into()))
+}
+
+/// Get block by height
+async fn get_block_by_height(
+    State(api_state): State<ApiState>,
+    Path(height): Path<u64>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<axum::response::Response> {
+    if let Some(block) = api_state.state.get_block_by_height(height) {
+        block_response(&headers, &block, http_cache::REVALIDATE)
+    } else {
+        Err(StatusCode::NOT_FOUND.into())
+    }
+}
+
+/// Get account info
+async fn get_account(
+    State(api_state): State<ApiState>,
+    Path(address_str): Path<String>,
+) -> ApiResult<Json<ApiResponse<AccountInfo>>> {
+    let address = crate::types::hex_to_address(&address_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    
+    if let Some(account) = api_state.state.get_account(&address) {
+        let reserved = api_state.consensus.reserved_balance(&address);
+        let info = AccountInfo {
+            address: address_to_hex(&address),
+            balance: account.balance,
+            reserved,
+            available: account.balance.saturating_sub(reserved),
+            nonce: account.nonce,
+            staked: account.staked,
+            nonce_lanes: account.nonce_lanes,
+        };
+        Ok(Json(ApiResponse::success(info)))
+    } else {
+        Err(StatusCode::NOT_FOUND.into())
+    }
+}
+
+/// JSON of a state proof; `encoded` is the bincode `StateProof` for `haze::state::verify_proof`
+fn state_proof_to_json(proof: &crate::state::StateProof) -> serde_json::Value {
+    serde_json::json!({
+        "kind": proof.kind,
+        "key": hash_to_hex(&proof.key),
+        "exists": proof.value.is_some(),
+        "height": proof.height,
+        "state_root": hash_to_hex(&proof.state_root()),
+        "committed_in_block": proof.height + 1,
+        "value": proof.value.as_deref().map(hex::encode),
+        "siblings": proof.trie.siblings.iter().map(hash_to_hex).collect::<Vec<_>>(),
+        "leaf": proof.trie.leaf.map(|(key, entry_hash)| serde_json::json!({
+            "key": hash_to_hex(&key),
+            "entry_hash": hash_to_hex(&entry_hash),
+        })),
+        "account_root": hash_to_hex(&proof.account_root),
+        "shard_roots": proof.shard_roots.iter().map(hash_to_hex).collect::<Vec<_>>(),
+        "shard": proof.shard,
+        "encoded": hex::encode(bincode::serialize(proof).unwrap_or_default()),
+    })
+}
+
+/// Merkle proof of an account (or of its absence) against the current state root
+async fn get_account_proof(
+    State(api_state): State<ApiState>,
+    Path(address_str): Path<String>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let address = crate::types::hex_to_address(&address_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let proof = api_state.state.prove_account(&address);
+    Ok(Json(ApiResponse::success_at(state_proof_to_json(&proof), proof.height)))
+}
+
+/// Merkle proof of an asset against the current state root (404 for unknown assets)
+async fn get_asset_proof(
+    State(api_state): State<ApiState>,
+    Path(asset_id_str): Path<String>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let asset_id = crate::types::hex_to_hash(&asset_id_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let proof = api_state.state.prove_asset(&asset_id).ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(ApiResponse::success_at(state_proof_to_json(&proof), proof.height)))
+}
+
+/// Default and maximum page size of `/api/v1/accounts`
+const ACCOUNT_PAGE_DEFAULT: usize = 100;
+const ACCOUNT_PAGE_MAX: usize = 1000;
+
+/// Account listing query parameters
+#[derive(Debug, Deserialize)]
+pub struct ListAccountsQuery {
+    /// `next_cursor` of the previous page (an address)
+    pub cursor: Option<String>,
+    pub limit: Option<usize>,
+    pub min_balance: Option<u64>,
+    pub has_stake: Option<bool>,
+}
+
+/// Accounts in address order, paginated by cursor
+#[derive(Debug, Serialize)]
+pub struct AccountList {
+    pub accounts: Vec<AccountInfo>,
+    pub next_cursor: Option<String>,
+    /// Number of accounts on the node (not only those matching the filters)
+    pub total: usize,
+}
+
+/// List accounts from the persisted account index
+async fn list_accounts(
+    State(api_state): State<ApiState>,
+    axum::extract::Query(query): axum::extract::Query<ListAccountsQuery>,
+) -> ApiResult<Json<ApiResponse<AccountList>>> {
+    let cursor = query
+        .cursor
+        .as_deref()
+        .map(|cursor| crate::types::hex_to_address(cursor).ok_or(StatusCode::BAD_REQUEST))
+        .transpose()?;
+    let limit = query.limit.unwrap_or(ACCOUNT_PAGE_DEFAULT).clamp(1, ACCOUNT_PAGE_MAX);
+    let filter = crate::state::AccountFilter {
+        min_balance: query.min_balance,
+        has_stake: query.has_stake,
+    };
+    let page = api_state.state.list_accounts(cursor.as_ref(), limit, &filter)?;
+    Ok(Json(ApiResponse::success(AccountList {
+        accounts: page
+            .accounts
+            .into_iter()
+            .map(|(address, account)| {
+                let reserved = api_state.consensus.reserved_balance(&address);
+                AccountInfo {
+                    address: address_to_hex(&address),
+                    balance: account.balance,
+                    reserved,
+                    available: account.balance.saturating_sub(reserved),
+                    nonce: account.nonce,
+                    staked: account.staked,
+                    nonce_lanes: account.nonce_lanes,
+                }
+            })
+            .collect(),
+        next_cursor: page.next_cursor.map(|address| address_to_hex(&address)),
+        total: api_state.state.account_count(),
+    })))
+}
+
+fn contract_info_to_json(info: &crate::state::ContractInfo) -> serde_json::Value {
+    serde_json::json!({
+        "address": address_to_hex(&info.address),
+        "deployer": address_to_hex(&info.deployer),
+        "deploy_nonce": info.deploy_nonce,
+        "code_hash": hash_to_hex(&info.code_hash),
+        "code_size": info.code_size,
+        "deployed_at_height": info.deployed_at_height,
+    })
+}
+
+/// Get deployed contract metadata
+async fn get_contract(
+    State(api_state): State<ApiState>,
+    Path(address_str): Path<String>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let address = crate::types::hex_to_address(&address_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let info = api_state.state.get_contract(&address)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(ApiResponse::success(contract_info_to_json(&info))))
+}
+
+/// Get deployed contract bytecode (hex) with its metadata
+async fn get_contract_code(
+    State(api_state): State<ApiState>,
+    Path(address_str): Path<String>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let address = crate::types::hex_to_address(&address_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let info = api_state.state.get_contract(&address)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let code = api_state.state.get_contract_code(&address)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let mut response = contract_info_to_json(&info);
+    response["code"] = serde_json::Value::String(hex::encode(code));
+    Ok(Json(ApiResponse::success(response)))
+}
+
+/// Read-only contract call request
+#[derive(Debug, Deserialize)]
+pub struct ContractCallRequest {
+    pub method: String,
+    /// Hex-encoded call arguments
+    pub args: Option<String>,
+    /// Gas cap (defaults to, and is bounded by, the node's VM gas limit)
+    pub gas_limit: Option<u64>,
+    /// Caller address seen by the contract (defaults to the zero address)
+    pub caller: Option<String>,
+}
+
+/// Execute a contract method against current state without submitting a transaction.
+/// Execution failures (traps, out of gas) are reported in the response error field.
+async fn call_contract(
+    State(api_state): State<ApiState>,
+    Path(address_str): Path<String>,
+    Json(request): Json<ContractCallRequest>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let address = crate::types::hex_to_address(&address_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    if api_state.state.get_contract(&address).is_none() {
+        return Err(StatusCode::NOT_FOUND.into());
+    }
+    let args = match request.args.as_deref() {
+        Some(args) => hex::decode(args.trim_start_matches("0x")).map_err(|_| StatusCode::BAD_REQUEST)?,
+        None => Vec::new(),
+    };
+    let caller = match request.caller.as_deref() {
+        Some(caller) => crate::types::hex_to_address(caller).ok_or(StatusCode::BAD_REQUEST)?,
+        None => [0u8; 32],
+    };
+    let gas_limit = request.gas_limit.unwrap_or(api_state.config.vm.gas_limit);
+
+    match api_state.state.call_contract_readonly(caller, &address, &request.method, &args, gas_limit) {
+        Ok(output) => Ok(Json(ApiResponse::success(serde_json::json!({
+            "return_values": output.return_values,
+            "return_data": hex::encode(&output.return_data),
+            "gas_used": output.gas_used,
+            "logs": output.logs.iter().map(|log| serde_json::json!({
+                "address": address_to_hex(&log.address),
+                "topics": log.topics.iter().map(hash_to_hex).collect::<Vec<_>>(),
+                "data": hex::encode(&log.data),
+            })).collect::<Vec<_>>(),
+        })))),
+        Err(e) => Ok(Json(ApiResponse::from_error(&e))),
+    }
+}
+
+/// Get the asset lifecycle hook registered for a collection (game_id)
+async fn get_collection_hook(
+    State(api_state): State<ApiState>,
+    Path(game_id): Path<String>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let registered = api_state.state.get_asset_hook(&game_id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(ApiResponse::success(serde_json::json!({
+        "game_id": game_id,
+        "registrant": address_to_hex(&registered.registrant),
+        "contract": address_to_hex(&registered.hook.contract),
+        "method": registered.hook.method,
+        "gas_limit": registered.hook.gas_limit,
+        "on_failure": format!("{:?}", registered.hook.on_failure),
+    }))))
+}
+
+/// Trait distribution of a collection (game_id): asset count per attribute value
+async fn get_collection_traits(
+    State(api_state): State<ApiState>,
+    Path(game_id): Path<String>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let snapshot = api_state.state.read_snapshot();
+    let (total_assets, traits) = api_state.state.get_collection_traits(&game_id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let height = snapshot.height;
+    drop(snapshot);
+    let traits: serde_json::Map<String, serde_json::Value> = traits
+        .into_iter()
+        .map(|(name, values)| {
+            let values: Vec<serde_json::Value> = values
+                .into_iter()
+                .map(|(value, count)| serde_json::json!({
+                    "value": value,
+                    "count": count,
+                    "frequency": count as f64 / total_assets as f64,
+                }))
+                .collect();
+            (name, serde_json::Value::Array(values))
+        })
+        .collect();
+    Ok(Json(ApiResponse::success_at(serde_json::json!({
+        "game_id": game_id,
+        "total_assets": total_assets,
+        "traits": traits,
+    }), height)))
+}
+
+/// Collection holders query parameters
+#[derive(Debug, Deserialize)]
+pub struct CollectionHoldersQuery {
+    /// Snapshot height (default: current height)
+    pub at_height: Option<u64>,
+}
+
+/// Holders of a collection (game_id) at a height: address -> number of assets held
+async fn get_collection_holders(
+    State(api_state): State<ApiState>,
+    Path(game_id): Path<String>,
+    axum::extract::Query(query): axum::extract::Query<CollectionHoldersQuery>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let current_height = api_state.state.current_height();
+    let height = query.at_height.unwrap_or(current_height);
+    if height > current_height {
+        return Err(StatusCode::BAD_REQUEST.into());
+    }
+    let holders = api_state.state.get_collection_holders(&game_id, height)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let total_assets: u64 = holders.values().sum();
+    let holders: serde_json::Map<String, serde_json::Value> = holders
+        .into_iter()
+        .map(|(owner, count)| (address_to_hex(&owner), serde_json::Value::from(count)))
+        .collect();
+    Ok(Json(ApiResponse::success(serde_json::json!({
+        "game_id": game_id,
+        "at_height": height,
+        "total_assets": total_assets,
+        "holders": holders,
+    }))))
+}
+
+/// Query of `GET /api/v1/market/collections/:id/stats`
+#[derive(Debug, Deserialize)]
+pub struct MarketStatsQuery {
+    /// History granularity, `hour` (default) or `day`
+    pub bucket: Option<crate::economy::MarketBucketSize>,
+    /// Recent sales to return (default 20)
+    pub limit: Option<usize>,
+}
+
+/// Market statistics of a collection: floor (lowest open ask), volume, sales, recent sales
+/// and time-bucketed history, all built from order book trades
+async fn get_collection_market_stats(
+    State(api_state): State<ApiState>,
+    Path(game_id): Path<String>,
+    axum::extract::Query(query): axum::extract::Query<MarketStatsQuery>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let bucket = query.bucket.unwrap_or(crate::economy::MarketBucketSize::Hour);
+    let snapshot = api_state.state.read_snapshot();
+    let height = snapshot.height;
+    let floor = api_state.state.get_floor_price(&game_id);
+    let stats = api_state.state.get_market_stats(&game_id).unwrap_or_default();
+    let recent = api_state.state.get_trades(&game_id, query.limit.unwrap_or(20));
+    drop(snapshot);
+    Ok(Json(ApiResponse::success_at(serde_json::json!({
+        "game_id": game_id,
+        "floor_price": floor,
+        "last_price": stats.last_price,
+        "volume": stats.volume.to_string(),
+        "sales": stats.sales,
+        "fees": stats.fees.to_string(),
+        "last_sale_height": stats.last_sale_height,
+        "recent_sales": recent.iter().map(|trade| serde_json::json!({
+            "asset_id": hash_to_hex(&trade.asset_id),
+            "price": trade.price,
+            "buyer": address_to_hex(&trade.buyer),
+            "seller": address_to_hex(&trade.seller),
+            "height": trade.height,
+        })).collect::<Vec<_>>(),
+        "bucket": bucket,
+        "history": stats.buckets(bucket).iter().map(|b| serde_json::json!({
+            "start": b.start,
+            "open": b.open,
+            "close": b.close,
+            "low": b.low,
+            "high": b.high,
+            "volume": b.volume.to_string(),
+            "sales": b.sales,
+        })).collect::<Vec<_>>(),
+    }), height)))
+}
+
+/// Referrer of a player in a collection's referral program
+async fn get_referral(
+    State(api_state): State<ApiState>,
+    Path((game_id, player_str)): Path<(String, String)>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let player = crate::types::hex_to_address(&player_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let referral = api_state.state.get_referral(&game_id, &player)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(ApiResponse::success(serde_json::json!({
+        "game_id": game_id,
+        "player": address_to_hex(&player),
+        "referrer": address_to_hex(&referral.referrer),
+        "bound_at_height": referral.bound_at_height,
+    }))))
+}
+
+/// Referral earnings of a referrer, per collection and in total
+async fn get_referrer_earnings(
+    State(api_state): State<ApiState>,
+    Path(address_str): Path<String>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let referrer = crate::types::hex_to_address(&address_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let by_game = api_state.state.get_referral_earnings(&referrer);
+    let mut total = crate::economy::ReferralEarnings::default();
+    let games: Vec<serde_json::Value> = by_game
+        .iter()
+        .map(|(game_id, earnings)| {
+            total.add(earnings);
+            serde_json::json!({
+                "game_id": game_id,
+                "players": earnings.players,
+                "marketplace": earnings.marketplace,
+                "crafting": earnings.crafting,
+                "total": earnings.total(),
+            })
+        })
+        .collect();
+    Ok(Json(ApiResponse::success(serde_json::json!({
+        "referrer": address_to_hex(&referrer),
+        "players": total.players,
+        "total": total.total(),
+        "games": games,
+    }))))
+}
+
+/// Get a payment request and its status
+async fn get_payment_request(
+    State(api_state): State<ApiState>,
+    Path(request_id_str): Path<String>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let request_id = crate::types::hex_to_hash(&request_id_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let request = api_state.state.get_payment_request(&request_id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    // As seen by the next block, the earliest one a payment can land in
+    let status = request.status(api_state.state.current_height() + 1);
+    Ok(Json(ApiResponse::success(serde_json::json!({
+        "request_id": hash_to_hex(&request.id),
+        "requester": address_to_hex(&request.requester),
+        "payer": request.payer.as_ref().map(address_to_hex),
+        "amount": request.amount,
+        "memo": request.memo,
+        "created_at_height": request.created_at_height,
+        "expires_at_height": request.expires_at_height,
+        "status": status,
+        "paid_by": request.settlement.as_ref().map(|s| address_to_hex(&s.payer)),
+        "paid_at_height": request.settlement.as_ref().map(|s| s.height),
+    }))))
+}
+
+/// Loot box by id, with its status as seen by the next block
+async fn get_loot_box(
+    State(api_state): State<ApiState>,
+    Path(box_id_str): Path<String>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let box_id = crate::types::hex_to_hash(&box_id_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let loot_box = api_state.state.get_loot_box(&box_id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let status = loot_box.status(api_state.state.current_height() + 1);
+    let (outcome, revealed_at_height, forfeited_at_height) = match loot_box.settlement {
+        Some(crate::economy::LootSettlement::Revealed { height, outcome }) => (Some(outcome), Some(height), None),
+        Some(crate::economy::LootSettlement::Forfeited { height }) => (None, None, Some(height)),
+        None => (None, None, None),
+    };
+    Ok(Json(ApiResponse::success(serde_json::json!({
+        "box_id": hash_to_hex(&loot_box.id),
+        "operator": address_to_hex(&loot_box.operator),
+        "player": address_to_hex(&loot_box.player),
+        "game_id": loot_box.game_id,
+        "commitment": hash_to_hex(&loot_box.commitment),
+        "bond": loot_box.bond,
+        "sealed_at_height": loot_box.sealed_at_height,
+        "opened_at_height": loot_box.opened_at_height,
+        "beacon_height": loot_box.beacon_height,
+        "reveal_deadline": loot_box.reveal_deadline,
+        "status": status,
+        "outcome": outcome,
+        "revealed_at_height": revealed_at_height,
+        "forfeited_at_height": forfeited_at_height,
+        // The minted asset reuses the box id
+        "asset_id": revealed_at_height.map(|_| hash_to_hex(&loot_box.id)),
+    }))))
+}
+
+/// Leaderboard fields, with its status as seen by the next block
+fn leaderboard_json(board: &crate::leaderboard::Leaderboard, height: u64) -> serde_json::Value {
+    serde_json::json!({
+        "board_id": hash_to_hex(&board.id),
+        "creator": address_to_hex(&board.creator),
+        "game_id": board.game_id,
+        "season": board.season,
+        "capacity": board.capacity,
+        "end_height": board.end_height,
+        "submitters": board.submitters.iter().map(address_to_hex).collect::<Vec<_>>(),
+        "created_at_height": board.created_at_height,
+        "submissions": board.submissions,
+        "status": board.status(height),
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct LeaderboardQuery {
+    /// Rank of the first entry returned (1-based, default 1)
+    offset: Option<usize>,
+    /// Entries returned (default 100)
+    limit: Option<usize>,
+    /// Also report this player's rank
+    player: Option<String>,
+}
+
+/// Leaderboard standings, best first (final once the season has ended)
+async fn get_leaderboard(
+    State(api_state): State<ApiState>,
+    Path(board_id_str): Path<String>,
+    axum::extract::Query(query): axum::extract::Query<LeaderboardQuery>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let board_id = crate::types::hex_to_hash(&board_id_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let board = api_state.state.get_leaderboard(&board_id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let player = match query.player.as_deref() {
+        Some(player) => Some(crate::types::hex_to_address(player).ok_or(StatusCode::BAD_REQUEST)?),
+        None => None,
+    };
+    let offset = query.offset.unwrap_or(1).max(1);
+    let standings: Vec<serde_json::Value> = board.entries
+        .iter()
+        .enumerate()
+        .skip(offset - 1)
+        .take(query.limit.unwrap_or(100))
+        .map(|(index, entry)| serde_json::json!({
+            "rank": index + 1,
+            "player": address_to_hex(&entry.player),
+            "score": entry.score,
+            "submitted_at_height": entry.submitted_at_height,
+        }))
+        .collect();
+    let mut body = leaderboard_json(&board, api_state.state.current_height() + 1);
+    body["entries"] = serde_json::Value::from(board.entries.len());
+    body["standings"] = serde_json::Value::from(standings);
+    if let Some(player) = player {
+        body["player_rank"] = serde_json::json!(board.rank_of(&player));
+    }
+    Ok(Json(ApiResponse::success(body)))
+}
+
+/// Leaderboards of a collection, oldest first
+async fn get_collection_leaderboards(
+    State(api_state): State<ApiState>,
+    Path(game_id): Path<String>,
+) -> ApiResult<Json<ApiResponse<Vec<serde_json::Value>>>> {
+    let height = api_state.state.current_height() + 1;
+    let boards = api_state.state.get_collection_leaderboards(&game_id)
+        .iter()
+        .map(|board| leaderboard_json(board, height))
+        .collect();
+    Ok(Json(ApiResponse::success(boards)))
+}
+
+/// Asset pool fields, with rewards emitted up to `height`
+fn asset_pool_json(pool: &crate::economy::AssetPool, height: u64) -> serde_json::Value {
+    let mut pool = pool.clone();
+    pool.accrue(height);
+    serde_json::json!({
+        "pool_id": hash_to_hex(&pool.id),
+        "creator": address_to_hex(&pool.creator),
+        "game_id": pool.game_id,
+        "reward_per_block": pool.reward_per_block,
+        "lock_blocks": pool.lock_blocks,
+        "created_at_height": pool.created_at_height,
+        "reserve": pool.reserve,
+        "staked": pool.staked,
+        "paid": pool.paid,
+    })
+}
+
+/// Asset pool by id
+async fn get_asset_pool(
+    State(api_state): State<ApiState>,
+    Path(pool_id_str): Path<String>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let pool_id = crate::types::hex_to_hash(&pool_id_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let pool = api_state.state.get_asset_pool(&pool_id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(ApiResponse::success(asset_pool_json(&pool, api_state.state.current_height()))))
+}
+
+/// Asset pools of a collection, oldest first
+async fn get_collection_asset_pools(
+    State(api_state): State<ApiState>,
+    Path(game_id): Path<String>,
+) -> ApiResult<Json<ApiResponse<Vec<serde_json::Value>>>> {
+    let height = api_state.state.current_height();
+    let pools = api_state.state.get_collection_asset_pools(&game_id)
+        .iter()
+        .map(|pool| asset_pool_json(pool, height))
+        .collect();
+    Ok(Json(ApiResponse::success(pools)))
+}
+
+/// Assets an account has staked, with the rewards they can claim now
+async fn get_account_asset_stakes(
+    State(api_state): State<ApiState>,
+    Path(address_str): Path<String>,
+) -> ApiResult<Json<ApiResponse<Vec<serde_json::Value>>>> {
+    let address = crate::types::hex_to_address(&address_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let height = api_state.state.current_height();
+    let stakes = api_state.state.get_account_asset_stakes(&address)
+        .iter()
+        .map(|stake| {
+            let pending = api_state.state.get_asset_pool(&stake.pool_id).map_or(0, |mut pool| {
+                pool.accrue(height);
+                stake.pending(&pool)
+            });
+            serde_json::json!({
+                "asset_id": hash_to_hex(&stake.asset_id),
+                "pool_id": hash_to_hex(&stake.pool_id),
+                "staked_at_height": stake.staked_at_height,
+                "unlock_height": stake.unlock_height,
+                "locked": height + 1 < stake.unlock_height,
+                "pending_rewards": pending,
+                "claimed": stake.claimed,
+            })
+        })
+        .collect();
+    Ok(Json(ApiResponse::success(stakes)))
+}
+
+/// Breeding by id, with its child once hatched
+async fn get_breeding(
+    State(api_state): State<ApiState>,
+    Path(breeding_id_str): Path<String>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let breeding_id = crate::types::hex_to_hash(&breeding_id_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let breeding = api_state.state.get_breeding(&breeding_id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let child = breeding.hatched_at_height.map(|_| hash_to_hex(&breeding.id));
+    Ok(Json(ApiResponse::success(serde_json::json!({
+        "breeding_id": hash_to_hex(&breeding.id),
+        "breeder": address_to_hex(&breeding.breeder),
+        "game_id": breeding.game_id,
+        "parents": breeding.parents.iter().map(hash_to_hex).collect::<Vec<_>>(),
+        "status": breeding.status(),
+        "bred_at_height": breeding.bred_at_height,
+        "hatch_height": breeding.hatch_height,
+        "hatched_at_height": breeding.hatched_at_height,
+        "child": child,
+    }))))
+}
+
+/// Breeding rule of an asset's collection and when the asset can breed next
+async fn get_asset_breeding(
+    State(api_state): State<ApiState>,
+    Path(asset_id_str): Path<String>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let asset_id = crate::types::hex_to_hash(&asset_id_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let asset = api_state.state.get_asset(&asset_id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let rule = asset.data.game_id.as_deref()
+        .and_then(|game_id| api_state.config.economy.breeding_rule(game_id));
+    let last_bred = api_state.state.get_last_bred(&asset_id);
+    let ready_at = rule.map(|rule| last_bred.map_or(0, |bred| bred.saturating_add(rule.cooldown_blocks)));
+    Ok(Json(ApiResponse::success(serde_json::json!({
+        "asset_id": hash_to_hex(&asset_id),
+        "game_id": asset.data.game_id,
+        "breedable": rule.is_some(),
+        "fee": rule.map(|rule| rule.fee),
+        "cooldown_blocks": rule.map(|rule| rule.cooldown_blocks),
+        "hatch_delay": rule.map(|rule| rule.hatch_delay),
+        "last_bred_at_height": last_bred,
+        "ready_at_height": ready_at,
+    }))))
+}
+
+fn upgrade_json(upgrade: &crate::upgrade::Upgrade) -> serde_json::Value {
+    serde_json::json!({
+        "upgrade_id": hash_to_hex(&upgrade.id),
+        "owner": address_to_hex(&upgrade.owner),
+        "asset_id": hash_to_hex(&upgrade.asset_id),
+        "game_id": upgrade.game_id,
+        "attribute": upgrade.attribute,
+        "catalysts": upgrade.catalysts.iter().map(hash_to_hex).collect::<Vec<_>>(),
+        "success_bps": upgrade.success_bps,
+        "destroy_bps": upgrade.destroy_bps,
+        "boost": upgrade.boost,
+        "requested_at_height": upgrade.requested_at_height,
+        "roll_height": upgrade.roll_height,
+        "outcome": upgrade.outcome,
+        "resolved_at_height": upgrade.resolved_at_height,
+        "value_before": upgrade.value_before,
+        "value_after": upgrade.value_after,
+    })
+}
+
+/// Upgrade by id, with its outcome once rolled
+async fn get_upgrade(
+    State(api_state): State<ApiState>,
+    Path(upgrade_id_str): Path<String>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let upgrade_id = crate::types::hex_to_hash(&upgrade_id_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let upgrade = api_state.state.get_upgrade(&upgrade_id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(ApiResponse::success(upgrade_json(&upgrade))))
+}
+
+/// Upgrade rule of an asset's collection and the asset's past upgrades (also for burned
+/// assets)
+async fn get_asset_upgrades(
+    State(api_state): State<ApiState>,
+    Path(asset_id_str): Path<String>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let asset_id = crate::types::hex_to_hash(&asset_id_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let upgrades = api_state.state.get_asset_upgrades(&asset_id);
+    let game_id = match api_state.state.get_asset(&asset_id) {
+        Some(asset) => asset.data.game_id,
+        None => Some(upgrades.first().ok_or(StatusCode::NOT_FOUND)?.game_id.clone()),
+    };
+    let rule = game_id.as_deref().and_then(|game_id| api_state.config.economy.upgrade_rule(game_id));
+    Ok(Json(ApiResponse::success(serde_json::json!({
+        "asset_id": hash_to_hex(&asset_id),
+        "game_id": game_id,
+        "rule": rule.map(|rule| serde_json::json!({
+            "attribute": rule.attribute,
+            "boost": rule.boost,
+            "fee": rule.fee,
+            "catalysts": rule.catalysts,
+            "success_bps": rule.success_bps,
+            "destroy_bps": rule.destroy_bps,
+            "roll_delay": rule.roll_delay,
+        })),
+        "upgrades": upgrades.iter().map(upgrade_json).collect::<Vec<_>>(),
+    }))))
+}
+
+/// Season fields, with its status as seen by the next block
+fn season_json(season: &crate::season::Season, height: u64) -> serde_json::Value {
+    serde_json::json!({
+        "season_id": hash_to_hex(&season.id),
+        "game_id": season.game_id,
+        "season": season.name,
+        "operator": address_to_hex(&season.operator),
+        "start_height": season.start_height,
+        "end_height": season.end_height,
+        "status": season.status(height),
+        "hooks": {
+            "freeze_collections": season.hooks.freeze_collections,
+            "snapshot_holders": season.hooks.snapshot_holders,
+            "leaderboard_capacity": season.hooks.leaderboard_capacity,
+            "leaderboard_submitters": season.hooks.leaderboard_submitters.iter().map(address_to_hex).collect::<Vec<_>>(),
+        },
+        "registered_at_height": season.registered_at_height,
+        "leaderboard": season.leaderboard.as_ref().map(hash_to_hex),
+        "holders_snapshot_height": season.holders_snapshot_height,
+    })
+}
+
+/// Seasons of a game in order, with the game's frozen collections
+async fn get_game_seasons(
+    State(api_state): State<ApiState>,
+    Path(game_id): Path<String>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let height = api_state.state.current_height() + 1;
+    let seasons: Vec<serde_json::Value> = api_state.state.get_game_seasons(&game_id)
+        .iter()
+        .map(|season| season_json(season, height))
+        .collect();
+    let frozen: Vec<serde_json::Value> = api_state.state.get_frozen_collections(&game_id)
+        .into_iter()
+        .map(|(collection, season_id)| serde_json::json!({
+            "collection": collection,
+            "frozen_by": hash_to_hex(&season_id),
+        }))
+        .collect();
+    Ok(Json(ApiResponse::success(serde_json::json!({
+        "game_id": game_id,
+        "seasons": seasons,
+        "frozen_collections": frozen,
+    }))))
+}
+
+/// Season by id, with the holder snapshot taken when it ended
+async fn get_season(
+    State(api_state): State<ApiState>,
+    Path(season_id_str): Path<String>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let season_id = crate::types::hex_to_hash(&season_id_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let season = api_state.state.get_season(&season_id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let mut body = season_json(&season, api_state.state.current_height() + 1);
+    if let Some(height) = season.holders_snapshot_height {
+        let holders: serde_json::Map<String, serde_json::Value> = api_state.state
+            .get_collection_holders(&season.game_id, height)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(owner, count)| (address_to_hex(&owner), serde_json::Value::from(count)))
+            .collect();
+        body["holders"] = serde_json::Value::Object(holders);
+    }
+    Ok(Json(ApiResponse::success(body)))
+}
+
+fn subscription_json(subscription: &crate::state::Subscription) -> serde_json::Value {
+    serde_json::json!({
+        "subscription_id": hash_to_hex(&subscription.id),
+        "payer": address_to_hex(&subscription.payer),
+        "payee": address_to_hex(&subscription.payee),
+        "amount": subscription.amount,
+        "interval_blocks": subscription.interval_blocks,
+        "end_height": subscription.end_height,
+        "created_at_height": subscription.created_at_height,
+        "next_charge_height": subscription.is_live().then_some(subscription.next_charge_height),
+        "past_due_since": subscription.past_due_since,
+        "charges": subscription.charges,
+        "total_paid": subscription.total_paid,
+        "status": subscription.status,
+        "cancelled_by": subscription.cancelled_by.as_ref().map(address_to_hex),
+    })
+}
+
+/// Get a subscription and its billing status
+async fn get_subscription(
+    State(api_state): State<ApiState>,
+    Path(subscription_id_str): Path<String>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let subscription_id = crate::types::hex_to_hash(&subscription_id_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let subscription = api_state.state.get_subscription(&subscription_id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(ApiResponse::success(subscription_json(&subscription))))
+}
+
+/// Subscriptions an account pays or is paid by
+async fn get_account_subscriptions(
+    State(api_state): State<ApiState>,
+    Path(address_str): Path<String>,
+) -> ApiResult<Json<ApiResponse<Vec<serde_json::Value>>>> {
+    let address = crate::types::hex_to_address(&address_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let subscriptions = api_state.state.get_account_subscriptions(&address)
+        .iter()
+        .map(subscription_json)
+        .collect();
+    Ok(Json(ApiResponse::success(subscriptions)))
+}
+
+/// List validators (including jailed ones) by stake
+async fn get_validators(
+    State(api_state): State<ApiState>,
+) -> ApiResult<Json<ApiResponse<Vec<ValidatorResponse>>>> {
+    let validators = api_state.state.tokenomics().get_all_validators()
+        .iter()
+        .map(|info| ValidatorResponse::new(info, api_state.consensus.get_missed_slots(&info.address)))
+        .collect();
+    Ok(Json(ApiResponse::success(validators)))
+}
+
+/// Get validator info, including jail status
+async fn get_validator(
+    State(api_state): State<ApiState>,
+    Path(address_str): Path<String>,
+) -> ApiResult<Json<ApiResponse<ValidatorResponse>>> {
+    let address = crate::types::hex_to_address(&address_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let info = api_state.state.tokenomics().get_validator(&address)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let missed_slots = api_state.consensus.get_missed_slots(&address);
+    Ok(Json(ApiResponse::success(ValidatorResponse::new(&info, missed_slots))))
+}
+
+/// Governance token supply and total stake
+async fn get_governance_token(
+    State(api_state): State<ApiState>,
+) -> ApiResult<Json<ApiResponse<GovernanceTokenInfo>>> {
+    let config = &api_state.config.governance_token;
+    Ok(Json(ApiResponse::success(GovernanceTokenInfo {
+        enabled: config.enabled,
+        symbol: config.enabled.then(|| config.symbol.clone()),
+        total_supply: api_state.state.tokenomics().governance_supply(),
+        total_staked: api_state.state.total_governance_staked(),
+    })))
+}
+
+/// Governance token balance, stake and voting weight of an account
+async fn get_governance_account(
+    State(api_state): State<ApiState>,
+    Path(address_str): Path<String>,
+) -> ApiResult<Json<ApiResponse<GovernanceAccountInfo>>> {
+    let address = crate::types::hex_to_address(&address_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let account = api_state.state.get_account(&address).unwrap_or_default();
+    Ok(Json(ApiResponse::success(GovernanceAccountInfo {
+        address: address_to_hex(&address),
+        balance: account.governance_balance,
+        staked: account.governance_staked,
+        weight: api_state.state.governance_weight(&address),
+    })))
+}
+
+/// Get account balance
+async fn get_balance(
+    State(api_state): State<ApiState>,
+    Path(address_str): Path<String>,
+) -> ApiResult<Json<ApiResponse<u64>>> {
+    let address = crate::types::hex_to_address(&address_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    
+    if let Some(account) = api_state.state.get_account(&address) {
+        Ok(Json(ApiResponse::success(account.balance)))
+    } else {
+        Ok(Json(ApiResponse::success(0))) // New account has 0 balance
+    }
+}
+
+/// Private metadata fields of an asset: `{name: {status: "committed" | "revealed", commitment, revealed_at}}`
+fn private_fields_json(asset_state: &crate::state::AssetState) -> serde_json::Value {
+    asset_state.private_fields.iter()
+        .map(|(name, field)| {
+            let status = if field.revealed_at.is_some() { "revealed" } else { "committed" };
+            (name.clone(), serde_json::json!({
+                "status": status,
+                "commitment": hex::encode(field.commitment),
+                "revealed_at": field.revealed_at,
+            }))
+        })
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
+
+/// Metadata namespaces of an asset: `{namespace: {keys, writers}}`
+fn namespaces_json(asset_state: &crate::state::AssetState) -> serde_json::Value {
+    crate::state::metadata_namespaces(asset_state, chrono::Utc::now().timestamp()).into_iter()
+        .map(|(namespace, entry)| {
+            (namespace, serde_json::json!({
+                "keys": entry.keys,
+                "writers": entry.writers.iter().map(address_to_hex).collect::<Vec<_>>(),
+            }))
+        })
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
+
+/// Get asset info (cached until the state changes; ETag from `updated_at` and the body)
+async fn get_asset(
+    State(api_state): State<ApiState>,
+    Path(asset_id_str): Path<String>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<axum::response::Response> {
+    let asset_id = crate::types::hex_to_hash(&asset_id_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    
+    let state_version = api_state.state.state_version();
+    let cache_key = format!("asset:{}", hash_to_hex(&asset_id));
+    if let Some(cached) = api_state.response_cache.get(&cache_key, state_version) {
+        return Ok(http_cache::json_response(&headers, &cached.etag, http_cache::REVALIDATE, cached.body));
+    }
+    
+    if let Some(asset_state) = api_state.state.get_asset(&asset_id) {
+        // Convert blob_refs to hex strings for JSON
+        let blob_refs_json: std::collections::HashMap<String, String> = asset_state.blob_refs.iter()
+            .map(|(k, v)| (k.clone(), hex::encode(v)))
+            .collect();
+        
+        let permissions_json: Vec<serde_json::Value> = asset_state.permissions.iter().map(|p| {
+            serde_json::json!({
+                "grantee": address_to_hex(&p.grantee),
+                "level": format!("{:?}", p.level),
+                "game_id": p.game_id,
+                "expires_at": p.expires_at,
+            })
+        }).collect();
+        let asset_json = serde_json::json!({
+            "asset_id": hash_to_hex(&asset_id),
+            "owner": address_to_hex(&asset_state.owner),
+            "density": format!("{:?}", asset_state.data.density),
+            "metadata": asset_state.data.metadata,
+            "attributes": asset_state.data.attributes,
+            "game_id": asset_state.data.game_id,
+            "created_at": asset_state.created_at,
+            "updated_at": asset_state.updated_at,
+            "blob_refs": blob_refs_json,
+            "history_count": asset_state.history.len(),
+            "permissions": permissions_json,
+            "public_read": asset_state.public_read,
+            "private_fields": private_fields_json(&asset_state),
+            "namespaces": namespaces_json(&asset_state),
+        });
+        let body = serde_json::to_vec(&ApiResponse::success(asset_json))
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let cached = CachedResponse {
+            etag: http_cache::etag_for(&asset_state.updated_at.to_string(), &body),
+            body: body.into(),
+            state_version,
+        };
+        api_state.response_cache.insert(cache_key, cached.clone());
+        Ok(http_cache::json_response(&headers, &cached.etag, http_cache::REVALIDATE, cached.body))
+    } else {
+        Err(StatusCode::NOT_FOUND.into())
+    }
+}
+
+/// Stream blob data for an asset by blob key (Core density). Serves a single `Range` (206,
+/// or 416 past the end) and answers `If-None-Match` on the blob hash with 304.
+async fn get_asset_blob(
+    State(api_state): State<ApiState>,
+    Path((asset_id_str, blob_key)): Path<(String, String)>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<axum::response::Response> {
+    use axum::http::header;
+    use axum::response::IntoResponse;
+    use futures_util::StreamExt;
+
+    let asset_id = crate::types::hex_to_hash(&asset_id_str).ok_or(StatusCode::BAD_REQUEST)?;
+    let asset_state = api_state.state.get_asset(&asset_id).ok_or(StatusCode::NOT_FOUND)?;
+    let blob_hash = *asset_state.blob_refs.get(&blob_key).ok_or(StatusCode::NOT_FOUND)?;
+    let etag = format!("\"{}\"", crate::types::hash_to_hex(&blob_hash));
+    if http_cache::not_modified(&headers, &etag) {
+        return Ok((StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response());
+    }
+    let blob_storage = api_state.state.blob_storage();
+    let reader = tokio::task::spawn_blocking(move || blob_storage.open_blob(&blob_key, &blob_hash))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+
+    let (status, start, end) = match http_cache::byte_range(&headers, &etag, reader.size) {
+        http_cache::ByteRange::Full => (StatusCode::OK, 0, reader.size),
+        http_cache::ByteRange::Partial(start, end) => (StatusCode::PARTIAL_CONTENT, start, end),
+        http_cache::ByteRange::Unsatisfiable => {
+            return Ok((
+                StatusCode::RANGE_NOT_SATISFIABLE,
+                [(header::CONTENT_RANGE, format!("bytes */{}", reader.size))],
+            )
+                .into_response());
+        }
+    };
+    // One chunk is read from disk at a time, as the client consumes the body
+    let body = futures::stream::iter(reader.pieces(start, end)).then(|piece| async move {
+        match tokio::task::spawn_blocking(move || piece.read()).await {
+            Ok(Ok(data)) => Ok(data),
+            Ok(Err(e)) => Err(std::io::Error::other(e)),
+            Err(e) => Err(std::io::Error::other(e)),
+        }
+    });
+    let mut response = axum::response::Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, &reader.content_type)
+        .header(header::CONTENT_LENGTH, end - start)
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::ETAG, &etag)
+        .header(header::CACHE_CONTROL, http_cache::REVALIDATE);
+    if status == StatusCode::PARTIAL_CONTENT {
+        response = response.header(header::CONTENT_RANGE, format!("bytes {}-{}/{}", start, end - 1, reader.size));
+    }
+    response
+        .body(axum::body::Body::from_stream(body))
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR.into())
+}
+
+/// Get asset history query parameters
+#[derive(Debug, Deserialize)]
+pub struct AssetHistoryQuery {
+    pub limit: Option<usize>,
+}
+
+/// Create asset snapshot
+async fn create_asset_snapshot(
+    State(api_state): State<ApiState>,
+    Path(asset_id_str): Path<String>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let asset_id = crate::types::hex_to_hash(&asset_id_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    
+    match api_state.state.create_asset_snapshot(&asset_id) {
+        Ok(version) => {
+            let response = serde_json::json!({
+                "asset_id": hash_to_hex(&asset_id),
+                "version": version,
+                "status": "created",
+            });
+            Ok(Json(ApiResponse::success(response)))
+        }
+        Err(_) => Err(StatusCode::NOT_FOUND.into()),
+    }
+}
+
+/// Get asset versions
+async fn get_asset_versions(
+    State(api_state): State<ApiState>,
+    Path(asset_id_str): Path<String>,
+) -> ApiResult<Json<ApiResponse<Vec<serde_json::Value>>>> {
+    let asset_id = crate::types::hex_to_hash(&asset_id_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    
+    if let Some(versions) = api_state.state.get_asset_versions(&asset_id) {
+        let versions_json: Vec<serde_json::Value> = versions.iter()
+            .map(|v| {
+                let blob_refs_json: std::collections::HashMap<String, String> = v.blob_refs.iter()
+                    .map(|(k, h)| (k.clone(), hex::encode(h)))
+                    .collect();
+                
+                serde_json::json!({
+                    "version": v.version,
+                    "timestamp": v.timestamp,
+                    "density": format!("{:?}", v.data.density),
+                    "metadata": v.data.metadata,
+                    "attributes": v.data.attributes,
+                    "game_id": v.data.game_id,
+                    "blob_refs": blob_refs_json,
+                })
+            })
+            .collect();
+        Ok(Json(ApiResponse::success(versions_json)))
+    } else {
+        Err(StatusCode::NOT_FOUND.into())
+    }
+}
+
+/// Get asset version by version number
+async fn get_asset_version(
+    State(api_state): State<ApiState>,
+    Path((asset_id_str, version_str)): Path<(String, String)>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let asset_id = crate::types::hex_to_hash(&asset_id_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    
+    let version = version_str.parse::<u64>()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    
+    if let Some(asset_version) = api_state.state.get_asset_version(&asset_id, version) {
+        let blob_refs_json: std::collections::HashMap<String, String> = asset_version.blob_refs.iter()
+            .map(|(k, h)| (k.clone(), hex::encode(h)))
+            .collect();
+        
+        let version_json = serde_json::json!({
+            "asset_id": hash_to_hex(&asset_id),
+            "version": asset_version.version,
+            "timestamp": asset_version.timestamp,
+            "density": format!("{:?}", asset_version.data.density),
+            "metadata": asset_version.data.metadata,
+            "attributes": asset_version.data.attributes,
+            "game_id": asset_version.data.game_id,
+            "blob_refs": blob_refs_json,
+        });
+        Ok(Json(ApiResponse::success(version_json)))
+    } else {
+        Err(StatusCode::NOT_FOUND.into())
+    }
+}
+
+/// Get collection-relative rarity score and rank of an asset
+async fn get_asset_rarity(
+    State(api_state): State<ApiState>,
+    Path(asset_id_str): Path<String>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let asset_id = crate::types::hex_to_hash(&asset_id_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let snapshot = api_state.state.read_snapshot();
+    let asset = api_state.state.get_asset(&asset_id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let rarity = api_state.state.get_asset_rarity(&asset_id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let height = snapshot.height;
+    drop(snapshot);
+    Ok(Json(ApiResponse::success_at(serde_json::json!({
+        "asset_id": hex::encode(asset_id),
+        "game_id": asset.data.game_id,
+        "score": rarity.score,
+        "rank": rarity.rank,
+        "collection_size": rarity.collection_size,
+    }), height)))
+}
+
+fn lineage_edge_to_json(edge: &crate::state::LineageEdge) -> serde_json::Value {
+    serde_json::json!({
+        "parent": hash_to_hex(&edge.parent),
+        "child": hash_to_hex(&edge.child),
+        "action": format!("{:?}", edge.action),
+        "height": edge.height,
+    })
+}
+
+/// Composition lineage of an asset: every merge/split/breed/upgrade edge leading to it
+/// (ancestors) and derived from it (descendants). Works for merged-away and split assets too.
+async fn get_asset_lineage(
+    State(api_state): State<ApiState>,
+    Path(asset_id_str): Path<String>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let asset_id = crate::types::hex_to_hash(&asset_id_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let snapshot = api_state.state.read_snapshot();
+    let exists = api_state.state.get_asset(&asset_id).is_some();
+    if !exists && api_state.state.get_asset_lineage(&asset_id).is_none() {
+        return Err(StatusCode::NOT_FOUND.into());
+    }
+    let ancestors = api_state.state.lineage_closure(&asset_id, true);
+    let descendants = api_state.state.lineage_closure(&asset_id, false);
+    let lineage_hash = api_state.state.lineage_hash(&asset_id);
+    let height = snapshot.height;
+    drop(snapshot);
+    Ok(Json(ApiResponse::success_at(serde_json::json!({
+        "asset_id": hash_to_hex(&asset_id),
+        "exists": exists,
+        "ancestors": ancestors.iter().map(lineage_edge_to_json).collect::<Vec<_>>(),
+        "descendants": descendants.iter().map(lineage_edge_to_json).collect::<Vec<_>>(),
+        "lineage_hash": lineage_hash.as_ref().map(hash_to_hex),
+    }), height)))
+}
+
+/// Get asset history
+async fn get_asset_history(
+    State(api_state): State<ApiState>,
+    Path(asset_id_str): Path<String>,
+    axum::extract::Query(query): axum::extract::Query<AssetHistoryQuery>,
+) -> ApiResult<Json<ApiResponse<Vec<serde_json::Value>>>> {
+    let asset_id = crate::types::hex_to_hash(&asset_id_str)
         .ok_or(StatusCode::BAD_REQUEST)?;
     
-    if let Some(block) = api_state.state.get_block(&hash) {
-        let info = BlockInfo {
-            hash: hash_to_hex(&block.header.hash),
-            parent_hash: hash_to_hex(&block.header.parent_hash),
-            height: block.header.height,
-            timestamp: block.header.timestamp,
-            validator: address_to_hex(&block.header.validator),
-            transaction_count: block.transactions.len(),
-            wave_number: block.header.wave_number,
-        };
-        Ok(Json(ApiResponse::success(info)))
+    let limit = query.limit.unwrap_or(0); // 0 = all
+    
+    if let Some(history) = api_state.state.get_asset_history(&asset_id, limit) {
+        let history_json: Vec<serde_json::Value> = history.iter()
+            .map(|entry| {
+                serde_json::json!({
+                    "timestamp": entry.timestamp,
+                    "action": format!("{:?}", entry.action),
+                    "changes": entry.changes,
+                })
+            })
+            .collect();
+        Ok(Json(ApiResponse::success(history_json)))
+    } else {
+        Err(StatusCode::NOT_FOUND.into())
+    }
+}
+
+/// Create asset
+///
+/// Expects a **signed** `Transaction::MistbornAsset { action: Create, ... }`.
+/// The server does not sign transactions on behalf of clients.
+async fn create_asset(
+    State(api_state): State<ApiState>,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<SendTransactionRequest>,
+) -> ApiResult<Json<ApiResponse<TransactionResponse>>> {
+    let tx = request.transaction;
+
+    // Must be a signed Create tx
+    let (action, asset_id, signature) = match &tx {
+        Transaction::MistbornAsset { action, asset_id, signature, .. } => (action, asset_id, signature),
+        _ => return Err(StatusCode::BAD_REQUEST.into()),
+    };
+
+    if !matches!(action, AssetAction::Create) {
+        return Err(StatusCode::BAD_REQUEST.into());
+    }
+    if signature.is_empty() {
+        return Err(StatusCode::BAD_REQUEST.into());
+    }
+    if api_state.state.get_asset(asset_id).is_some() {
+        return Err(StatusCode::CONFLICT.into());
+    }
+
+    authorize_submission(&api_state, &headers, &tx)?;
+    let tx_hash = tx.hash();
+    match api_state.consensus.add_transaction(tx) {
+        Ok(()) => Ok(Json(ApiResponse::success(TransactionResponse {
+            hash: hash_to_hex(&tx_hash),
+            status: "pending".to_string(),
+        }))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Search assets query parameters
+#[derive(Debug, Deserialize)]
+pub struct SearchAssetsQuery {
+    pub owner: Option<String>,
+    pub game_id: Option<String>,
+    pub density: Option<String>,
+    pub q: Option<String>, // Full-text search query
+    pub zone: Option<String>, // Economic zone within `game_id` (requires game_id)
+    pub attr: Option<String>, // Comma-separated attribute conditions (`level>=5,class=mage`)
+    pub sort_by: Option<String>, // created_at, updated_at, rarity (computed collection rarity score)
+    pub sort_order: Option<String>, // asc, desc
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+/// Condense asset (increase density)
+///
+/// Expects a **signed** `Transaction::MistbornAsset { action: Condense, asset_id: <path>, ... }`.
+async fn condense_asset(
+    State(api_state): State<ApiState>,
+    Path(asset_id_str): Path<String>,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<SendTransactionRequest>,
+) -> ApiResult<Json<ApiResponse<TransactionResponse>>> {
+    let path_asset_id = crate::types::hex_to_hash(&asset_id_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+
+    let tx = request.transaction;
+    let (action, asset_id, signature) = match &tx {
+        Transaction::MistbornAsset { action, asset_id, signature, .. } => (action, asset_id, signature),
+        _ => return Err(StatusCode::BAD_REQUEST.into()),
+    };
+
+    if !matches!(action, AssetAction::Condense) {
+        return Err(StatusCode::BAD_REQUEST.into());
+    }
+    if *asset_id != path_asset_id {
+        return Err(StatusCode::BAD_REQUEST.into());
+    }
+    if signature.is_empty() {
+        return Err(StatusCode::BAD_REQUEST.into());
+    }
+    if api_state.state.get_asset(&path_asset_id).is_none() {
+        return Err(StatusCode::NOT_FOUND.into());
+    }
+
+    authorize_submission(&api_state, &headers, &tx)?;
+    let tx_hash = tx.hash();
+    match api_state.consensus.add_transaction(tx) {
+        Ok(()) => Ok(Json(ApiResponse::success(TransactionResponse {
+            hash: hash_to_hex(&tx_hash),
+            status: "pending".to_string(),
+        }))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Evaporate asset (decrease density)
+///
+/// Expects a **signed** `Transaction::MistbornAsset { action: Evaporate, asset_id: <path>, ... }`.
+async fn evaporate_asset(
+    State(api_state): State<ApiState>,
+    Path(asset_id_str): Path<String>,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<SendTransactionRequest>,
+) -> ApiResult<Json<ApiResponse<TransactionResponse>>> {
+    let path_asset_id = crate::types::hex_to_hash(&asset_id_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+
+    let tx = request.transaction;
+    let (action, asset_id, signature) = match &tx {
+        Transaction::MistbornAsset { action, asset_id, signature, .. } => (action, asset_id, signature),
+        _ => return Err(StatusCode::BAD_REQUEST.into()),
+    };
+
+    if !matches!(action, AssetAction::Evaporate) {
+        return Err(StatusCode::BAD_REQUEST.into());
+    }
+    if *asset_id != path_asset_id {
+        return Err(StatusCode::BAD_REQUEST.into());
+    }
+    if signature.is_empty() {
+        return Err(StatusCode::BAD_REQUEST.into());
+    }
+    if api_state.state.get_asset(&path_asset_id).is_none() {
+        return Err(StatusCode::NOT_FOUND.into());
+    }
+
+    authorize_submission(&api_state, &headers, &tx)?;
+    let tx_hash = tx.hash();
+    match api_state.consensus.add_transaction(tx) {
+        Ok(()) => Ok(Json(ApiResponse::success(TransactionResponse {
+            hash: hash_to_hex(&tx_hash),
+            status: "pending".to_string(),
+        }))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Merge two assets
+///
+/// Expects a **signed** `Transaction::MistbornAsset { action: Merge, asset_id: <path>, data: { metadata: { "_other_asset_id": "<hex>" } }, ... }`.
+async fn merge_assets(
+    State(api_state): State<ApiState>,
+    Path(asset_id_str): Path<String>,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<SendTransactionRequest>,
+) -> ApiResult<Json<ApiResponse<TransactionResponse>>> {
+    let path_asset_id = crate::types::hex_to_hash(&asset_id_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    
+    let tx = request.transaction;
+    let (action, asset_id, signature, data) = match &tx {
+        Transaction::MistbornAsset { action, asset_id, signature, data, .. } => (action, asset_id, signature, data),
+        _ => return Err(StatusCode::BAD_REQUEST.into()),
+    };
+    
+    if !matches!(action, AssetAction::Merge) {
+        return Err(StatusCode::BAD_REQUEST.into());
+    }
+    if *asset_id != path_asset_id {
+        return Err(StatusCode::BAD_REQUEST.into());
+    }
+    if signature.is_empty() {
+        return Err(StatusCode::BAD_REQUEST.into());
+    }
+    
+    // Verify _other_asset_id is present in metadata
+    let other_asset_id_str = data.metadata.get("_other_asset_id")
+        .ok_or_else(|| StatusCode::BAD_REQUEST)?;
+    
+    let other_asset_id = crate::types::hex_to_hash(other_asset_id_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    
+    // Verify both assets exist
+    if api_state.state.get_asset(&path_asset_id).is_none() {
+        return Err(StatusCode::NOT_FOUND.into());
+    }
+    if api_state.state.get_asset(&other_asset_id).is_none() {
+        return Err(StatusCode::NOT_FOUND.into());
+    }
+    
+    authorize_submission(&api_state, &headers, &tx)?;
+    let tx_hash = tx.hash();
+    match api_state.consensus.add_transaction(tx) {
+        Ok(()) => Ok(Json(ApiResponse::success(TransactionResponse {
+            hash: hash_to_hex(&tx_hash),
+            status: "pending".to_string(),
+        }))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Split asset into components
+///
+/// Expects a **signed** `Transaction::MistbornAsset { action: Split, asset_id: <path>, data: { metadata: { "_components": "component1,component2,..." } }, ... }`
+/// or with a `_component_manifest` instead of `_components` (validated when the block is applied).
+async fn split_asset(
+    State(api_state): State<ApiState>,
+    Path(asset_id_str): Path<String>,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<SendTransactionRequest>,
+) -> ApiResult<Json<ApiResponse<TransactionResponse>>> {
+    let path_asset_id = crate::types::hex_to_hash(&asset_id_str)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    
+    let tx = request.transaction;
+    let (action, asset_id, signature, data) = match &tx {
+        Transaction::MistbornAsset { action, asset_id, signature, data, .. } => (action, asset_id, signature, data),
+        _ => return Err(StatusCode::BAD_REQUEST.into()),
+    };
+    
+    if !matches!(action, AssetAction::Split) {
+        return Err(StatusCode::BAD_REQUEST.into());
+    }
+    if *asset_id != path_asset_id {
+        return Err(StatusCode::BAD_REQUEST.into());
+    }
+    if signature.is_empty() {
+        return Err(StatusCode::BAD_REQUEST.into());
+    }
+    
+    // Verify a component manifest or _components is present in metadata
+    if !data.metadata.contains_key(crate::assets::SPLIT_MANIFEST_KEY) {
+        let components_str = data.metadata.get("_components")
+            .ok_or(StatusCode::BAD_REQUEST)?;
+        
+        let components: Vec<&str> = components_str.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
+        if components.is_empty() {
+            return Err(StatusCode::BAD_REQUEST.into());
+        }
+    }
+    
+    if api_state.state.get_asset(&path_asset_id).is_none() {
+        return Err(StatusCode::NOT_FOUND.into());
+    }
+    
+    authorize_submission(&api_state, &headers, &tx)?;
+    let tx_hash = tx.hash();
+    match api_state.consensus.add_transaction(tx) {
+        Ok(()) => Ok(Json(ApiResponse::success(TransactionResponse {
+            hash: hash_to_hex(&tx_hash),
+            status: "pending".to_string(),
+        }))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Estimate gas cost for a transaction (accepts hex strings for byte fields in JSON)
+#[derive(Debug, Deserialize, Serialize)]
+pub struct EstimateGasRequest {
+    #[serde(deserialize_with = "de_transaction_from_json")]
+    pub transaction: Transaction,
+}
+
+/// Estimate gas cost for transactions applied in order (accepts hex strings for byte fields in JSON)
+#[derive(Debug, Deserialize, Serialize)]
+pub struct EstimateGasBatchRequest {
+    #[serde(deserialize_with = "de_transactions_from_json")]
+    pub transactions: Vec<Transaction>,
+}
+
+fn de_transactions_from_json<'de, D>(d: D) -> Result<Vec<Transaction>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let values = Vec::<serde_json::Value>::deserialize(d)?;
+    values
+        .iter()
+        .map(|v| parse_transaction_from_value(v).map_err(serde::de::Error::custom))
+        .collect()
+}
+
+/// Gas estimate response
+#[derive(Debug, Serialize)]
+pub struct GasEstimateResponse {
+    /// Asset operation gas, or the contract gas a dry run used
+    pub gas_cost: u64,
+    /// Fee charged: `gas_cost * gas_price` less `stake_discount`
+    pub gas_fee: u64,
+    pub gas_price: u64,
+    /// Discount tier of the payer's stake (basis points)
+    pub stake_discount_bps: u64,
+    /// Part of the gas fee waived by the stake discount
+    pub stake_discount: u64,
+    /// Everything the transaction is charged: its fee (for types that pay one) plus `gas_fee`
+    pub total_fee: u64,
+    /// Line items summing to `gas_cost`
+    pub breakdown: Vec<crate::assets::GasLineItem>,
+}
+
+/// Batch gas estimate: one estimate per transaction up to the first failure
+#[derive(Debug, Serialize)]
+pub struct GasBatchEstimateResponse {
+    pub estimates: Vec<GasEstimateResponse>,
+    /// Sum of `gas_cost` over `estimates`
+    pub total_gas_cost: u64,
+    /// Sum of `total_fee` over `estimates`
+    pub total_fee: u64,
+    /// Index of the first transaction that would fail (later ones were not priced)
+    pub failed_index: Option<usize>,
+    pub error: Option<String>,
+    pub error_code: Option<u32>,
+}
+
+/// Asset operations are priced by the gas schedule alone, so they can be estimated before
+/// the owner is funded
+fn asset_gas_estimate(api_state: &ApiState, action: &AssetAction, data: &crate::types::AssetData) -> GasEstimateResponse {
+    let breakdown = crate::assets::asset_operation_gas_breakdown(
+        &api_state.config,
+        action,
+        data,
+        Some(&data.metadata),
+    );
+    let gas_cost = breakdown.iter().map(|line| line.gas).sum::<u64>();
+    
+    // Calculate gas fee (gas_cost * gas_price, less the owner's stake discount)
+    let vm_config = &api_state.config.vm;
+    let staked = api_state.state.get_account(&data.owner).map(|a| a.staked).unwrap_or(0);
+    let (gas_fee, stake_discount) = vm_config.discounted_gas_fee(gas_cost * vm_config.gas_price, staked);
+    
+    GasEstimateResponse {
+        gas_cost,
+        gas_fee,
+        gas_price: vm_config.gas_price,
+        stake_discount_bps: vm_config.stake_discount_bps(staked),
+        stake_discount,
+        total_fee: gas_fee,
+        breakdown,
+    }
+}
+
+/// Estimate for a transaction from what a dry run of it charged
+fn executed_gas_estimate(api_state: &ApiState, tx: &Transaction, execution: &crate::state::TxExecution) -> GasEstimateResponse {
+    let vm_config = &api_state.config.vm;
+    let staked = api_state.state.get_account(tx.sender()).map(|a| a.staked).unwrap_or(0);
+    let breakdown = if execution.gas_used > 0 {
+        vec![crate::assets::GasLineItem { item: "execution", quantity: 1, gas: execution.gas_used }]
     } else {
-        Err(StatusCode::NOT_FOUND)
+        Vec::new()
+    };
+    GasEstimateResponse {
+        gas_cost: execution.gas_used,
+        gas_fee: execution.fees_paid.saturating_sub(tx.fee()),
+        gas_price: vm_config.gas_price,
+        stake_discount_bps: vm_config.stake_discount_bps(staked),
+        stake_discount: execution.gas_discount,
+        total_fee: execution.fees_paid,
+        breakdown,
     }
 }
 
-/// Get block by height
-async fn get_block_by_height(
+/// Estimate gas and fees of one transaction: asset operations from the gas schedule, other
+/// types by a dry run on the current state (a contract call runs in the VM)
+async fn estimate_gas(
     State(api_state): State<ApiState>,
-    Path(height): Path<u64>,
-) -> ApiResult<Json<ApiResponse<BlockInfo>>> {
-    if let Some(block) = api_state.state.get_block_by_height(height) {
-        let info = BlockInfo {
-            hash: hash_to_hex(&block.header.hash),
-            parent_hash: hash_to_hex(&block.header.parent_hash),
-            height: block.header.height,
-            timestamp: block.header.timestamp,
-            validator: address_to_hex(&block.header.validator),
-            transaction_count: block.transactions.len(),
-            wave_number: block.header.wave_number,
-        };
-        Ok(Json(ApiResponse::success(info)))
+    Json(request): Json<EstimateGasRequest>,
+) -> ApiResult<Json<ApiResponse<GasEstimateResponse>>> {
+    let tx = request.transaction;
+    if let Transaction::MistbornAsset { action, data, .. } = &tx {
+        return Ok(Json(ApiResponse::success(asset_gas_estimate(&api_state, action, data))));
+    }
+    let dry_run = api_state.state.dry_run(std::slice::from_ref(&tx));
+    if let Some((_, e)) = dry_run.failure {
+        return Err(e.into());
+    }
+    let execution = dry_run.executions.first().ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(ApiResponse::success(executed_gas_estimate(&api_state, &tx, execution))))
+}
+
+/// Estimate gas and fees of transactions applied in order on top of the current state, so
+/// later ones see the effects of earlier ones (at most `consensus.max_transactions_per_block`)
+async fn estimate_gas_batch(
+    State(api_state): State<ApiState>,
+    Json(request): Json<EstimateGasBatchRequest>,
+) -> ApiResult<Json<ApiResponse<GasBatchEstimateResponse>>> {
+    let txs = request.transactions;
+    let max = api_state.config.consensus.max_transactions_per_block;
+    if txs.is_empty() || txs.len() > max {
+        return Err(HazeError::InvalidTransaction(
+            format!("A batch holds 1 to {} transactions, got {}", max, txs.len())
+        ).into());
+    }
+    let dry_run = api_state.state.dry_run(&txs);
+    let estimates: Vec<GasEstimateResponse> = txs
+        .iter()
+        .zip(&dry_run.executions)
+        .map(|(tx, execution)| match tx {
+            Transaction::MistbornAsset { action, data, .. } => asset_gas_estimate(&api_state, action, data),
+            _ => executed_gas_estimate(&api_state, tx, execution),
+        })
+        .collect();
+    let (failed_index, error, error_code) = match dry_run.failure {
+        Some((index, e)) => (Some(index), Some(e.to_string()), Some(e.code())),
+        None => (None, None, None),
+    };
+    Ok(Json(ApiResponse::success(GasBatchEstimateResponse {
+        total_gas_cost: estimates.iter().map(|e| e.gas_cost).sum(),
+        total_fee: estimates.iter().map(|e| e.total_fee).sum(),
+        estimates,
+        failed_index,
+        error,
+        error_code,
+    })))
+}
+
+/// Get asset permissions
+async fn get_asset_permissions(
+    State(api_state): State<ApiState>,
+    Path(asset_id_str): Path<String>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let asset_id = crate::types::hex_to_hash(&asset_id_str).ok_or(StatusCode::BAD_REQUEST)?;
+
+    if let Some(asset_state) = api_state.state.get_asset(&asset_id) {
+        let permissions_json: Vec<serde_json::Value> = asset_state.permissions.iter().map(|p| {
+            serde_json::json!({
+                "grantee": address_to_hex(&p.grantee),
+                "level": format!("{:?}", p.level),
+                "game_id": p.game_id,
+                "expires_at": p.expires_at,
+            })
+        }).collect();
+        let response = serde_json::json!({
+            "asset_id": hash_to_hex(&asset_id),
+            "owner": address_to_hex(&asset_state.owner),
+            "permissions": permissions_json,
+            "public_read": asset_state.public_read,
+            "namespaces": namespaces_json(&asset_state),
+        });
+        Ok(Json(ApiResponse::success(response)))
     } else {
-        Err(StatusCode::NOT_FOUND)
+        Err(StatusCode::NOT_FOUND.into())
     }
 }
 
-/// Get account info
-async fn get_account(
+/// Permission DTO for API (grantee as hex string)
+#[derive(Debug, Deserialize)]
+pub struct PermissionDto {
+    pub grantee: String,
+    pub level: String,
+    pub game_id: Option<String>,
+    pub expires_at: Option<i64>,
+}
+
+/// Set asset permissions request
+#[derive(Debug, Deserialize)]
+pub struct SetPermissionsRequest {
+    pub permissions: Vec<PermissionDto>,
+    pub public_read: bool,
+    /// Owner address (hex string); the owner signs and pays the fee
+    pub owner: String,
+    #[serde(default)]
+    pub fee: u64,
+    /// Owner's next nonce
+    #[serde(default)]
+    pub nonce: u64,
+    #[serde(default)]
+    pub chain_id: Option<u64>,
+    #[serde(default)]
+    pub valid_until_height: Option<u64>,
+    pub signature: Vec<u8>,
+}
+
+/// Set asset permissions (owner only)
+async fn set_asset_permissions(
     State(api_state): State<ApiState>,
-    Path(address_str): Path<String>,
-) -> ApiResult<Json<ApiResponse<AccountInfo>>> {
-    let address = crate::types::hex_to_address(&address_str)
-        .ok_or(StatusCode::BAD_REQUEST)?;
-    
-    if let Some(account) = api_state.state.get_account(&address) {
-        let info = AccountInfo {
-            address: address_to_hex(&address),
-            balance: account.balance,
-            nonce: account.nonce,
-            staked: account.staked,
+    Path(asset_id_str): Path<String>,
+    headers: axum::http::HeaderMap,
+    Json(req): Json<SetPermissionsRequest>,
+) -> ApiResult<Json<ApiResponse<TransactionResponse>>> {
+    let asset_id = crate::types::hex_to_hash(&asset_id_str).ok_or(StatusCode::BAD_REQUEST)?;
+
+    let owner = crate::types::hex_to_address(&req.owner).ok_or(StatusCode::BAD_REQUEST)?;
+
+    if api_state.state.get_asset(&asset_id).is_none() {
+        return Err(StatusCode::NOT_FOUND.into());
+    }
+    if req.signature.is_empty() {
+        return Err(StatusCode::BAD_REQUEST.into());
+    }
+
+    let mut permissions = Vec::with_capacity(req.permissions.len());
+    for p in req.permissions {
+        let grantee = crate::types::hex_to_address(&p.grantee).ok_or(StatusCode::BAD_REQUEST)?;
+        let level = match p.level.as_str() {
+            "GameContract" => PermissionLevel::GameContract,
+            "PublicRead" => PermissionLevel::PublicRead,
+            _ => return Err(StatusCode::BAD_REQUEST.into()),
         };
-        Ok(Json(ApiResponse::success(info)))
-    } else {
-        Err(StatusCode::NOT_FOUND)
+        permissions.push(AssetPermission {
+            grantee,
+            level,
+            game_id: p.game_id,
+            expires_at: p.expires_at,
+        });
+    }
+
+    let tx = Transaction::SetAssetPermissions {
+        from: owner,
+        asset_id,
+        permissions,
+        public_read: req.public_read,
+        owner,
+        fee: req.fee,
+        nonce: req.nonce,
+        chain_id: req.chain_id,
+        valid_until_height: req.valid_until_height,
+        signature: req.signature,
+    };
+    authorize_submission(&api_state, &headers, &tx)?;
+    let tx_hash = tx.hash();
+    match api_state.consensus.add_transaction(tx) {
+        Ok(()) => Ok(Json(ApiResponse::success(TransactionResponse {
+            hash: hash_to_hex(&tx_hash),
+            status: "pending".to_string(),
+        }))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Export asset as JSON (full state: metadata, attributes, blob_refs, history, versions, permissions)
+async fn export_asset(
+    State(api_state): State<ApiState>,
+    Path(asset_id_str): Path<String>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let asset_id = crate::types::hex_to_hash(&asset_id_str).ok_or(StatusCode::BAD_REQUEST)?;
+
+    let snapshot = api_state.state.read_snapshot();
+    let asset_state = api_state.state.get_asset(&asset_id).ok_or(StatusCode::NOT_FOUND)?;
+    let lineage = api_state.state.get_asset_lineage(&asset_id).unwrap_or_default();
+    let lineage_hash = api_state.state.lineage_hash(&asset_id);
+    let height = snapshot.height;
+    drop(snapshot);
+
+    let blob_refs_json: std::collections::HashMap<String, String> = asset_state
+        .blob_refs
+        .iter()
+        .map(|(k, v)| (k.clone(), hex::encode(v)))
+        .collect();
+    let permissions_json: Vec<serde_json::Value> = asset_state
+        .permissions
+        .iter()
+        .map(|p| {
+            serde_json::json!({
+                "grantee": address_to_hex(&p.grantee),
+                "level": format!("{:?}", p.level),
+                "game_id": p.game_id,
+                "expires_at": p.expires_at,
+            })
+        })
+        .collect();
+    let history_json: Vec<serde_json::Value> = asset_state
+        .history
+        .iter()
+        .map(|e| {
+            serde_json::json!({
+                "timestamp": e.timestamp,
+                "action": format!("{:?}", e.action),
+                "changes": e.changes,
+            })
+        })
+        .collect();
+    let versions_json: Vec<serde_json::Value> = asset_state
+        .versions
+        .iter()
+        .map(|v| {
+            serde_json::json!({
+                "version": v.version,
+                "timestamp": v.timestamp,
+            })
+        })
+        .collect();
+
+    let export_json = serde_json::json!({
+        "asset_id": hash_to_hex(&asset_id),
+        "owner": address_to_hex(&asset_state.owner),
+        "density": format!("{:?}", asset_state.data.density),
+        "metadata": asset_state.data.metadata,
+        "attributes": asset_state.data.attributes,
+        "game_id": asset_state.data.game_id,
+        "created_at": asset_state.created_at,
+        "updated_at": asset_state.updated_at,
+        "blob_refs": blob_refs_json,
+        "history": history_json,
+        "versions": versions_json,
+        "current_version": asset_state.current_version,
+        "permissions": permissions_json,
+        "public_read": asset_state.public_read,
+        "private_fields": private_fields_json(&asset_state),
+        "namespaces": namespaces_json(&asset_state),
+        "lineage": {
+            "parents": lineage.parents.iter().map(|e| hash_to_hex(&e.parent)).collect::<Vec<_>>(),
+            "children": lineage.children.iter().map(|e| hash_to_hex(&e.child)).collect::<Vec<_>>(),
+            "lineage_hash": lineage_hash.as_ref().map(hash_to_hex),
+        },
+    });
+    Ok(Json(ApiResponse::success_at(export_json, height)))
+}
+
+/// Import asset request (export-like JSON + signature for Create tx)
+#[derive(Debug, Deserialize)]
+pub struct ImportAssetRequest {
+    pub asset_id: String,
+    pub owner: String,
+    pub density: String,
+    pub metadata: std::collections::HashMap<String, String>,
+    pub attributes: Vec<crate::types::Attribute>,
+    pub game_id: Option<String>,
+    #[serde(default)]
+    pub blob_refs: std::collections::HashMap<String, String>,
+    /// Owner's next nonce
+    #[serde(default)]
+    pub nonce: u64,
+    /// Signature hex string
+    pub signature: String,
+}
+
+/// Import asset from JSON (creates asset via Create transaction)
+async fn import_asset(
+    State(api_state): State<ApiState>,
+    headers: axum::http::HeaderMap,
+    Json(req): Json<ImportAssetRequest>,
+) -> ApiResult<Json<ApiResponse<TransactionResponse>>> {
+    let asset_id = crate::types::hex_to_hash(&req.asset_id).ok_or(StatusCode::BAD_REQUEST)?;
+
+    let owner = crate::types::hex_to_address(&req.owner).ok_or(StatusCode::BAD_REQUEST)?;
+
+    if api_state.state.get_asset(&asset_id).is_some() {
+        return Err(StatusCode::CONFLICT.into());
+    }
+    let signature = hex::decode(&req.signature).map_err(|_| StatusCode::BAD_REQUEST)?;
+    if signature.is_empty() {
+        return Err(StatusCode::BAD_REQUEST.into());
+    }
+
+    let density = match req.density.as_str() {
+        "Ethereal" => crate::types::DensityLevel::Ethereal,
+        "Light" => crate::types::DensityLevel::Light,
+        "Dense" => crate::types::DensityLevel::Dense,
+        "Core" => crate::types::DensityLevel::Core,
+        _ => return Err(StatusCode::BAD_REQUEST.into()),
+    };
+
+    let mut metadata = req.metadata;
+    if !req.blob_refs.is_empty() {
+        let blob_refs_json = serde_json::to_string(&req.blob_refs).map_err(|_| StatusCode::BAD_REQUEST)?;
+        metadata.insert("_blob_refs".to_string(), blob_refs_json);
+    }
+
+    let data = crate::types::AssetData {
+        density,
+        metadata,
+        attributes: req.attributes,
+        game_id: req.game_id,
+        owner,
+    };
+
+    let tx = Transaction::MistbornAsset {
+        from: owner,
+        action: AssetAction::Create,
+        asset_id,
+        data,
+        fee: 0,
+        nonce: req.nonce,
+        chain_id: None,
+        valid_until_height: None,
+        signature,
+    };
+    authorize_submission(&api_state, &headers, &tx)?;
+    let tx_hash = tx.hash();
+    match api_state.consensus.add_transaction(tx) {
+        Ok(()) => Ok(Json(ApiResponse::success(TransactionResponse {
+            hash: hash_to_hex(&tx_hash),
+            status: "pending".to_string(),
+        }))),
+        Err(e) => Err(e.into()),
     }
 }
 
-/// Get account balance
-async fn get_balance(
+/// Search assets
+async fn search_assets(
     State(api_state): State<ApiState>,
-    Path(address_str): Path<String>,
-) -> ApiResult<Json<ApiResponse<u64>>> {
-    let address = crate::types::hex_to_address(&address_str)
+    axum::extract::Query(query): axum::extract::Query<SearchAssetsQuery>,
+) -> ApiResult<Json<ApiResponse<Vec<serde_json::Value>>>> {
+    let limit = query.limit.unwrap_or(100).min(1000);
+    let offset = query.offset.unwrap_or(0);
+    let attribute_filters = query.attr.as_deref()
+        .filter(|conditions| !conditions.is_empty())
+        .map(|conditions| conditions.split(',').map(crate::state::AttributeFilter::parse).collect::<Option<Vec<_>>>())
+        .unwrap_or(Some(Vec::new()))
         .ok_or(StatusCode::BAD_REQUEST)?;
+    // Indexes and asset states below are all read at one height
+    let snapshot = api_state.state.read_snapshot();
+    let mut candidate_ids: Vec<Hash> = Vec::new();
     
-    if let Some(account) = api_state.state.get_account(&address) {
-        Ok(Json(ApiResponse::success(account.balance)))
+    // Use indexes for efficient filtering
+    if let Some(ref owner_filter) = query.owner {
+        if let Some(owner) = crate::types::hex_to_address(owner_filter) {
+            candidate_ids = api_state.state.search_assets_by_owner(&owner);
+        }
+    } else if let Some(ref game_id_filter) = query.game_id {
+        candidate_ids = api_state.state.search_assets_by_game_id(game_id_filter);
+    } else if let Some(ref density_filter) = query.density {
+        // Parse density level
+        let density = match density_filter.as_str() {
+            "Ethereal" => crate::types::DensityLevel::Ethereal,
+            "Light" => crate::types::DensityLevel::Light,
+            "Dense" => crate::types::DensityLevel::Dense,
+            "Core" => crate::types::DensityLevel::Core,
+            _ => return Err(StatusCode::BAD_REQUEST.into()),
+        };
+        candidate_ids = api_state.state.search_assets_by_density(density);
     } else {
-        Ok(Json(ApiResponse::success(0))) // New account has 0 balance
+        // No specific filter, use all assets
+        candidate_ids = api_state.state.assets().iter().map(|e| *e.key()).collect();
     }
-}
-
-/// Get asset info
-async fn get_asset(
-    State(api_state): State<ApiState>,
-    Path(asset_id_str): Path<String>,
-) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
-    let asset_id = crate::types::hex_to_hash(&asset_id_str)
-        .ok_or(StatusCode::BAD_REQUEST)?;
     
-    if let Some(asset_state) = api_state.state.get_asset(&asset_id) {
-        // Convert blob_refs to hex strings for JSON
-        let blob_refs_json: std::collections::HashMap<String, String> = asset_state.blob_refs.iter()
-            .map(|(k, v)| (k.clone(), hex::encode(v)))
+    // Restrict to an economic zone of the game_id collection
+    if let Some(ref zone) = query.zone {
+        let game_id = query.game_id.as_deref().ok_or(StatusCode::BAD_REQUEST)?;
+        let in_zone: std::collections::HashSet<Hash> = api_state.state.search_assets_by_zone(game_id, zone)
+            .into_iter()
             .collect();
-        
-        let permissions_json: Vec<serde_json::Value> = asset_state.permissions.iter().map(|p| {
-            serde_json::json!({
-                "grantee": address_to_hex(&p.grantee),
-                "level": format!("{:?}", p.level),
-                "game_id": p.game_id,
-                "expires_at": p.expires_at,
+        candidate_ids.retain(|id| in_zone.contains(id));
+    }
+    
+    // Apply full-text search if provided
+    if let Some(ref search_query) = query.q {
+        if !search_query.is_empty() {
+            let text_search_results = api_state.state.search_assets_by_metadata(search_query);
+            // Intersect with candidate_ids
+            let text_search_set: std::collections::HashSet<Hash> = text_search_results.into_iter().collect();
+            candidate_ids.retain(|id| text_search_set.contains(id));
+        }
+    }
+    
+    // Build results (with collection-relative rarity, computed once per collection), keeping
+    // assets whose typed attributes meet every condition
+    let mut collection_rarity = std::collections::HashMap::new();
+    let mut results: Vec<(Hash, AssetState, Option<crate::state::RarityScore>)> = candidate_ids.iter()
+        .filter_map(|id| {
+            let asset = api_state.state.get_asset(id)
+                .filter(|state| attribute_filters.iter().all(|filter| filter.matches(&state.data)));
+            asset.map(|state| {
+                let rarity = state.data.game_id.as_ref().and_then(|game_id| {
+                    collection_rarity
+                        .entry(game_id.clone())
+                        .or_insert_with(|| api_state.state.get_collection_rarity(game_id))
+                        .as_ref()
+                        .and_then(|scores| scores.get(id).copied())
+                });
+                (*id, state, rarity)
             })
-        }).collect();
-        let asset_json = serde_json::json!({
-            "asset_id": hash_to_hex(&asset_id),
-            "owner": address_to_hex(&asset_state.owner),
-            "density": format!("{:?}", asset_state.data.density),
-            "metadata": asset_state.data.metadata,
-            "attributes": asset_state.data.attributes,
-            "game_id": asset_state.data.game_id,
-            "created_at": asset_state.created_at,
-            "updated_at": asset_state.updated_at,
-            "blob_refs": blob_refs_json,
-            "history_count": asset_state.history.len(),
-            "permissions": permissions_json,
-            "public_read": asset_state.public_read,
-        });
-        Ok(Json(ApiResponse::success(asset_json)))
-    } else {
-        Err(StatusCode::NOT_FOUND)
+        })
+        .collect();
+    let height = snapshot.height;
+    drop(snapshot);
+    
+    // Sort results
+    let sort_by = query.sort_by.as_deref().unwrap_or("created_at");
+    let sort_order = query.sort_order.as_deref().unwrap_or("desc");
+    let ascending = sort_order == "asc";
+    
+    match sort_by {
+        "created_at" => {
+            results.sort_by(|a, b| {
+                if ascending {
+                    a.1.created_at.cmp(&b.1.created_at)
+                } else {
+                    b.1.created_at.cmp(&a.1.created_at)
+                }
+            });
+        }
+        "updated_at" => {
+            results.sort_by(|a, b| {
+                if ascending {
+                    a.1.updated_at.cmp(&b.1.updated_at)
+                } else {
+                    b.1.updated_at.cmp(&a.1.updated_at)
+                }
+            });
+        }
+        "rarity" => {
+            results.sort_by(|a, b| {
+                let rarity_a = a.2.map_or(0.0, |rarity| rarity.score);
+                let rarity_b = b.2.map_or(0.0, |rarity| rarity.score);
+                if ascending {
+                    rarity_a.partial_cmp(&rarity_b).unwrap_or(std::cmp::Ordering::Equal)
+                } else {
+                    rarity_b.partial_cmp(&rarity_a).unwrap_or(std::cmp::Ordering::Equal)
+                }
+            });
+        }
+        _ => {
+            // Default: sort by created_at desc
+            results.sort_by(|a, b| b.1.created_at.cmp(&a.1.created_at));
+        }
     }
+    
+    // Apply pagination
+    let paginated_results: Vec<serde_json::Value> = results
+        .into_iter()
+        .skip(offset)
+        .take(limit)
+        .map(|(asset_id, asset_state, rarity)| {
+            let blob_refs_json: std::collections::HashMap<String, String> = asset_state.blob_refs.iter()
+                .map(|(k, v)| (k.clone(), hex::encode(v)))
+                .collect();
+            
+            serde_json::json!({
+                "asset_id": hex::encode(asset_id),
+                "owner": hex::encode(asset_state.owner),
+                "density": format!("{:?}", asset_state.data.density),
+                "metadata": asset_state.data.metadata,
+                "attributes": asset_state.data.attributes,
+                "game_id": asset_state.data.game_id,
+                "created_at": asset_state.created_at,
+                "updated_at": asset_state.updated_at,
+                "blob_refs": blob_refs_json,
+                "history_count": asset_state.history.len(),
+                "private_fields": private_fields_json(&asset_state),
+                "namespaces": namespaces_json(&asset_state),
+                "rarity_score": rarity.map(|rarity| rarity.score),
+                "rarity_rank": rarity.map(|rarity| rarity.rank),
+            })
+        })
+        .collect();
+    
+    Ok(Json(ApiResponse::success_at(paginated_results, height)))
 }
 
-/// Get blob data for an asset by blob key (Core density). Returns raw bytes.
-async fn get_asset_blob(
-    State(api_state): State<ApiState>,
-    Path((asset_id_str, blob_key)): Path<(String, String)>,
-) -> ApiResult<impl axum::response::IntoResponse> {
-    let asset_id = crate::types::hex_to_hash(&asset_id_str).ok_or(StatusCode::BAD_REQUEST)?;
-    let asset_state = api_state.state.get_asset(&asset_id).ok_or(StatusCode::NOT_FOUND)?;
-    let blob_hash = asset_state.blob_refs.get(&blob_key).ok_or(StatusCode::NOT_FOUND)?;
-    let blob_storage = BlobStorage::new(&api_state.config).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let data = blob_storage.get_blob(&blob_key, blob_hash).map_err(|_| StatusCode::NOT_FOUND)?;
-    Ok((
-        [(axum::http::header::CONTENT_TYPE, "application/octet-stream")],
-        data,
-    ))
+/// Create liquidity pool request
+#[derive(Debug, Deserialize)]
+pub struct CreatePoolRequest {
+    pub asset1: String,
+    pub asset2: String,
+    pub reserve1: u64,
+    pub reserve2: u64,
+    pub fee_rate: u64,
 }
 
-/// Get asset history query parameters
-#[derive(Debug, Deserialize)]
-pub struct AssetHistoryQuery {
-    pub limit: Option<usize>,
+/// Get liquidity pools
+async fn get_liquidity_pools(
+    State(api_state): State<ApiState>,
+) -> ApiResult<Json<ApiResponse<Vec<serde_json::Value>>>> {
+    let economy = api_state.state.economy();
+    let pools: Vec<serde_json::Value> = economy.liquidity_pools()
+        .iter()
+        .map(|entry| {
+            let pool = entry.value();
+            serde_json::json!({
+                "pool_id": pool.pool_id,
+                "asset1": pool.asset1,
+                "asset2": pool.asset2,
+                "reserve1": pool.reserve1,
+                "reserve2": pool.reserve2,
+                "fee_rate": pool.fee_rate,
+                "total_liquidity": pool.total_liquidity,
+            })
+        })
+        .collect();
+    
+    Ok(Json(ApiResponse::success(pools)))
 }
 
-/// Create asset snapshot
-async fn create_asset_snapshot(
+/// Create liquidity pool
+async fn create_liquidity_pool(
     State(api_state): State<ApiState>,
-    Path(asset_id_str): Path<String>,
+    Json(request): Json<CreatePoolRequest>,
 ) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
-    let asset_id = crate::types::hex_to_hash(&asset_id_str)
-        .ok_or(StatusCode::BAD_REQUEST)?;
+    let economy = api_state.state.economy();
     
-    match api_state.state.create_asset_snapshot(&asset_id) {
-        Ok(version) => {
+    match economy.create_liquidity_pool(
+        request.asset1,
+        request.asset2,
+        request.reserve1,
+        request.reserve2,
+        request.fee_rate,
+    ) {
+        Ok(pool_id) => {
             let response = serde_json::json!({
-                "asset_id": hash_to_hex(&asset_id),
-                "version": version,
+                "pool_id": pool_id,
                 "status": "created",
             });
-            Ok(Json(ApiResponse::success(response)))
-        }
-        Err(_) => Err(StatusCode::NOT_FOUND),
-    }
-}
-
-/// Get asset versions
-async fn get_asset_versions(
-    State(api_state): State<ApiState>,
-    Path(asset_id_str): Path<String>,
-) -> ApiResult<Json<ApiResponse<Vec<serde_json::Value>>>> {
-    let asset_id = crate::types::hex_to_hash(&asset_id_str)
-        .ok_or(StatusCode::BAD_REQUEST)?;
-    
-    if let Some(versions) = api_state.state.get_asset_versions(&asset_id) {
-        let versions_json: Vec<serde_json::Value> = versions.iter()
-            .map(|v| {
-                let blob_refs_json: std::collections::HashMap<String, String> = v.blob_refs.iter()
-                    .map(|(k, h)| (k.clone(), hex::encode(h)))
-                    .collect();
-                
-                serde_json::json!({
-                    "version": v.version,
-                    "timestamp": v.timestamp,
-                    "density": format!("{:?}", v.data.density),
-                    "metadata": v.data.metadata,
-                    "attributes": v.data.attributes,
-                    "game_id": v.data.game_id,
-                    "blob_refs": blob_refs_json,
-                })
-            })
-            .collect();
-        Ok(Json(ApiResponse::success(versions_json)))
-    } else {
-        Err(StatusCode::NOT_FOUND)
+            Ok(Json(ApiResponse::success(response)))
+        }
+        Err(e) => Err(e.into()),
     }
 }
 
-/// Get asset version by version number
-async fn get_asset_version(
+/// Get liquidity pool by ID
+async fn get_liquidity_pool(
     State(api_state): State<ApiState>,
-    Path((asset_id_str, version_str)): Path<(String, String)>,
+    Path(pool_id): Path<String>,
 ) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
-    let asset_id = crate::types::hex_to_hash(&asset_id_str)
-        .ok_or(StatusCode::BAD_REQUEST)?;
-    
-    let version = version_str.parse::<u64>()
-        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    let economy = api_state.state.economy();
     
-    if let Some(asset_version) = api_state.state.get_asset_version(&asset_id, version) {
-        let blob_refs_json: std::collections::HashMap<String, String> = asset_version.blob_refs.iter()
-            .map(|(k, h)| (k.clone(), hex::encode(h)))
-            .collect();
-        
-        let version_json = serde_json::json!({
-            "asset_id": hash_to_hex(&asset_id),
-            "version": asset_version.version,
-            "timestamp": asset_version.timestamp,
-            "density": format!("{:?}", asset_version.data.density),
-            "metadata": asset_version.data.metadata,
-            "attributes": asset_version.data.attributes,
-            "game_id": asset_version.data.game_id,
-            "blob_refs": blob_refs_json,
+    if let Some(pool) = economy.get_liquidity_pool(&pool_id) {
+        let pool_json = serde_json::json!({
+            "pool_id": pool.pool_id,
+            "asset1": pool.asset1,
+            "asset2": pool.asset2,
+            "reserve1": pool.reserve1,
+            "reserve2": pool.reserve2,
+            "fee_rate": pool.fee_rate,
+            "total_liquidity": pool.total_liquidity,
         });
-        Ok(Json(ApiResponse::success(version_json)))
+        Ok(Json(ApiResponse::success(pool_json)))
     } else {
-        Err(StatusCode::NOT_FOUND)
+        Err(StatusCode::NOT_FOUND.into())
     }
 }
 
-/// Get asset history
-async fn get_asset_history(
-    State(api_state): State<ApiState>,
-    Path(asset_id_str): Path<String>,
-    axum::extract::Query(query): axum::extract::Query<AssetHistoryQuery>,
-) -> ApiResult<Json<ApiResponse<Vec<serde_json::Value>>>> {
-    let asset_id = crate::types::hex_to_hash(&asset_id_str)
-        .ok_or(StatusCode::BAD_REQUEST)?;
-    
-    let limit = query.limit.unwrap_or(0); // 0 = all
-    
-    if let Some(history) = api_state.state.get_asset_history(&asset_id, limit) {
-        let history_json: Vec<serde_json::Value> = history.iter()
-            .map(|entry| {
-                serde_json::json!({
-                    "timestamp": entry.timestamp,
-                    "action": format!("{:?}", entry.action),
-                    "changes": entry.changes,
-                })
-            })
-            .collect();
-        Ok(Json(ApiResponse::success(history_json)))
-    } else {
-        Err(StatusCode::NOT_FOUND)
-    }
+/// TWAP query parameters
+#[derive(Debug, Deserialize)]
+pub struct TwapQuery {
+    /// Window in blocks (defaults to `economy.twap_default_window`)
+    pub window: Option<u64>,
 }
 
-/// Create asset
-///
-/// Expects a **signed** `Transaction::MistbornAsset { action: Create, ... }`.
-/// The server does not sign transactions on behalf of clients.
-async fn create_asset(
+/// Time-weighted average price of a pool over the last `window` blocks
+async fn get_pool_twap(
     State(api_state): State<ApiState>,
-    Json(request): Json<SendTransactionRequest>,
-) -> ApiResult<Json<ApiResponse<TransactionResponse>>> {
-    let tx = request.transaction;
-
-    // Must be a signed Create tx
-    let (action, asset_id, signature) = match &tx {
-        Transaction::MistbornAsset { action, asset_id, signature, .. } => (action, asset_id, signature),
-        _ => return Err(StatusCode::BAD_REQUEST),
-    };
-
-    if !matches!(action, AssetAction::Create) {
-        return Err(StatusCode::BAD_REQUEST);
-    }
-    if signature.is_empty() {
-        return Err(StatusCode::BAD_REQUEST);
-    }
-    if api_state.state.get_asset(asset_id).is_some() {
-        return Err(StatusCode::CONFLICT);
-    }
-
-    let tx_hash = tx.hash();
-    match api_state.consensus.add_transaction(tx) {
-        Ok(()) => Ok(Json(ApiResponse::success(TransactionResponse {
-            hash: hash_to_hex(&tx_hash),
-            status: "pending".to_string(),
-        }))),
-        Err(_) => Err(StatusCode::BAD_REQUEST),
+    Path(pool_id): Path<String>,
+    axum::extract::Query(query): axum::extract::Query<TwapQuery>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let economy_config = &api_state.config.economy;
+    let window = query.window.unwrap_or(economy_config.twap_default_window);
+    if window == 0 || window >= economy_config.twap_max_observations as u64 {
+        return Err(StatusCode::BAD_REQUEST.into());
     }
+    let economy = api_state.state.economy();
+    let pool = economy.get_liquidity_pool(&pool_id).ok_or(StatusCode::NOT_FOUND)?;
+    let twap = economy.twap(&pool_id, window);
+    let scale = crate::economy::TWAP_PRICE_SCALE as f64;
+    Ok(Json(ApiResponse::success(serde_json::json!({
+        "pool_id": pool.pool_id,
+        "asset1": pool.asset1,
+        "asset2": pool.asset2,
+        "requested_window": window,
+        "window_blocks": twap.map(|t| t.window_blocks).unwrap_or(0),
+        "full_window": twap.is_some_and(|t| t.window_blocks >= window),
+        "from_height": twap.map(|t| t.from_height),
+        "to_height": twap.map(|t| t.to_height),
+        "price1": twap.map(|t| t.price1 as f64 / scale),
+        "price2": twap.map(|t| t.price2 as f64 / scale),
+        "price1_scaled": twap.map(|t| t.price1.to_string()),
+        "price2_scaled": twap.map(|t| t.price2.to_string()),
+        "scale": crate::economy::TWAP_PRICE_SCALE.to_string(),
+    }))))
 }
 
-/// Search assets query parameters
-#[derive(Debug, Deserialize)]
-pub struct SearchAssetsQuery {
-    pub owner: Option<String>,
-    pub game_id: Option<String>,
-    pub density: Option<String>,
-    pub q: Option<String>, // Full-text search query
-    pub sort_by: Option<String>, // created_at, updated_at, rarity
-    pub sort_order: Option<String>, // asc, desc
-    pub limit: Option<usize>,
-    pub offset: Option<usize>,
+/// Configured zone with its treasury balance and number of assets
+fn zone_json(state: &StateManager, zone: &crate::config::ZoneConfig) -> serde_json::Value {
+    let treasury = crate::economy::zone_treasury_address(&zone.game_id, &zone.zone_id);
+    serde_json::json!({
+        "game_id": zone.game_id,
+        "zone_id": zone.zone_id,
+        "entry_fee": zone.entry_fee,
+        "exit_fee": zone.exit_fee,
+        "sale_fee_modifier_bps": zone.sale_fee_modifier_bps,
+        "treasury": address_to_hex(&treasury),
+        "treasury_balance": state.get_account(&treasury).map(|a| a.balance).unwrap_or(0),
+        "asset_count": state.search_assets_by_zone(&zone.game_id, &zone.zone_id).len(),
+    })
 }
 
-/// Condense asset (increase density)
-///
-/// Expects a **signed** `Transaction::MistbornAsset { action: Condense, asset_id: <path>, ... }`.
-async fn condense_asset(
+/// List configured economic zones
+async fn get_economic_zones(
     State(api_state): State<ApiState>,
-    Path(asset_id_str): Path<String>,
-    Json(request): Json<SendTransactionRequest>,
-) -> ApiResult<Json<ApiResponse<TransactionResponse>>> {
-    let path_asset_id = crate::types::hex_to_hash(&asset_id_str)
-        .ok_or(StatusCode::BAD_REQUEST)?;
-
-    let tx = request.transaction;
-    let (action, asset_id, signature) = match &tx {
-        Transaction::MistbornAsset { action, asset_id, signature, .. } => (action, asset_id, signature),
-        _ => return Err(StatusCode::BAD_REQUEST),
-    };
+) -> ApiResult<Json<ApiResponse<Vec<serde_json::Value>>>> {
+    let snapshot = api_state.state.read_snapshot();
+    let zones = api_state.config.economy.zones
+        .iter()
+        .map(|zone| zone_json(&api_state.state, zone))
+        .collect();
+    let height = snapshot.height;
+    drop(snapshot);
+    Ok(Json(ApiResponse::success_at(zones, height)))
+}
 
-    if !matches!(action, AssetAction::Condense) {
-        return Err(StatusCode::BAD_REQUEST);
-    }
-    if *asset_id != path_asset_id {
-        return Err(StatusCode::BAD_REQUEST);
-    }
-    if signature.is_empty() {
-        return Err(StatusCode::BAD_REQUEST);
-    }
-    if api_state.state.get_asset(&path_asset_id).is_none() {
-        return Err(StatusCode::NOT_FOUND);
-    }
+/// Get an economic zone (list its assets with `GET /api/v1/assets/search?game_id=..&zone=..`)
+async fn get_economic_zone(
+    State(api_state): State<ApiState>,
+    Path((game_id, zone_id)): Path<(String, String)>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let zone = api_state.config.economy.zone(&game_id, &zone_id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let snapshot = api_state.state.read_snapshot();
+    let zone = zone_json(&api_state.state, zone);
+    let height = snapshot.height;
+    drop(snapshot);
+    Ok(Json(ApiResponse::success_at(zone, height)))
+}
 
-    let tx_hash = tx.hash();
-    match api_state.consensus.add_transaction(tx) {
-        Ok(()) => Ok(Json(ApiResponse::success(TransactionResponse {
-            hash: hash_to_hex(&tx_hash),
-            status: "pending".to_string(),
-        }))),
-        Err(_) => Err(StatusCode::BAD_REQUEST),
+fn order_side_str(side: OrderSide) -> &'static str {
+    match side {
+        OrderSide::Buy => "buy",
+        OrderSide::Sell => "sell",
     }
 }
 
-/// Evaporate asset (decrease density)
-///
-/// Expects a **signed** `Transaction::MistbornAsset { action: Evaporate, asset_id: <path>, ... }`.
-async fn evaporate_asset(
-    State(api_state): State<ApiState>,
-    Path(asset_id_str): Path<String>,
-    Json(request): Json<SendTransactionRequest>,
-) -> ApiResult<Json<ApiResponse<TransactionResponse>>> {
-    let path_asset_id = crate::types::hex_to_hash(&asset_id_str)
-        .ok_or(StatusCode::BAD_REQUEST)?;
-
-    let tx = request.transaction;
-    let (action, asset_id, signature) = match &tx {
-        Transaction::MistbornAsset { action, asset_id, signature, .. } => (action, asset_id, signature),
-        _ => return Err(StatusCode::BAD_REQUEST),
-    };
+fn order_json(order: &crate::economy::Order) -> serde_json::Value {
+    serde_json::json!({
+        "order_id": hash_to_hex(&order.id),
+        "owner": address_to_hex(&order.owner),
+        "game_id": order.game_id,
+        "side": order_side_str(order.side),
+        "price": order.price,
+        "quantity": order.quantity,
+        "remaining": order.remaining,
+        "assets": order.assets.iter().map(hash_to_hex).collect::<Vec<_>>(),
+        "placed_at_height": order.placed_at_height,
+        "status": order.status,
+    })
+}
 
-    if !matches!(action, AssetAction::Evaporate) {
-        return Err(StatusCode::BAD_REQUEST);
-    }
-    if *asset_id != path_asset_id {
-        return Err(StatusCode::BAD_REQUEST);
+/// Price levels of one side of a book: `(price, quantity, orders)` in the given price order
+fn order_book_levels(orders: &[crate::economy::Order], side: OrderSide) -> Vec<serde_json::Value> {
+    let mut levels: std::collections::BTreeMap<u64, (u64, usize)> = std::collections::BTreeMap::new();
+    for order in orders.iter().filter(|o| o.side == side) {
+        let level = levels.entry(order.price).or_default();
+        level.0 += order.remaining;
+        level.1 += 1;
     }
-    if signature.is_empty() {
-        return Err(StatusCode::BAD_REQUEST);
-    }
-    if api_state.state.get_asset(&path_asset_id).is_none() {
-        return Err(StatusCode::NOT_FOUND);
+    let level_json = |(price, (quantity, orders)): (&u64, &(u64, usize))| serde_json::json!({
+        "price": price,
+        "quantity": quantity,
+        "orders": orders,
+    });
+    match side {
+        OrderSide::Buy => levels.iter().rev().map(level_json).collect(),
+        OrderSide::Sell => levels.iter().map(level_json).collect(),
     }
+}
 
-    let tx_hash = tx.hash();
-    match api_state.consensus.add_transaction(tx) {
-        Ok(()) => Ok(Json(ApiResponse::success(TransactionResponse {
-            hash: hash_to_hex(&tx_hash),
-            status: "pending".to_string(),
-        }))),
-        Err(_) => Err(StatusCode::BAD_REQUEST),
-    }
+/// Order book of a collection aggregated by price level (bids best first, asks best first)
+async fn get_order_book(
+    State(api_state): State<ApiState>,
+    Path(game_id): Path<String>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let snapshot = api_state.state.read_snapshot();
+    let orders = api_state.state.get_open_orders(&game_id);
+    let height = snapshot.height;
+    drop(snapshot);
+    let bids = order_book_levels(&orders, OrderSide::Buy);
+    let asks = order_book_levels(&orders, OrderSide::Sell);
+    Ok(Json(ApiResponse::success_at(serde_json::json!({
+        "game_id": game_id,
+        "best_bid": bids.first().map(|level| level["price"].clone()),
+        "best_ask": asks.first().map(|level| level["price"].clone()),
+        "bids": bids,
+        "asks": asks,
+        "orders": orders.iter().map(order_json).collect::<Vec<_>>(),
+    }), height)))
 }
 
-/// Merge two assets
-///
-/// Expects a **signed** `Transaction::MistbornAsset { action: Merge, asset_id: <path>, data: { metadata: { "_other_asset_id": "<hex>" } }, ... }`.
-async fn merge_assets(
+/// Recent trades of a collection, newest first
+async fn get_order_book_trades(
     State(api_state): State<ApiState>,
-    Path(asset_id_str): Path<String>,
-    Json(request): Json<SendTransactionRequest>,
-) -> ApiResult<Json<ApiResponse<TransactionResponse>>> {
-    let path_asset_id = crate::types::hex_to_hash(&asset_id_str)
-        .ok_or(StatusCode::BAD_REQUEST)?;
-    
-    let tx = request.transaction;
-    let (action, asset_id, signature, data) = match &tx {
-        Transaction::MistbornAsset { action, asset_id, signature, data, .. } => (action, asset_id, signature, data),
-        _ => return Err(StatusCode::BAD_REQUEST),
-    };
-    
-    if !matches!(action, AssetAction::Merge) {
-        return Err(StatusCode::BAD_REQUEST);
-    }
-    if *asset_id != path_asset_id {
-        return Err(StatusCode::BAD_REQUEST);
-    }
-    if signature.is_empty() {
-        return Err(StatusCode::BAD_REQUEST);
-    }
-    
-    // Verify _other_asset_id is present in metadata
-    let other_asset_id_str = data.metadata.get("_other_asset_id")
-        .ok_or_else(|| StatusCode::BAD_REQUEST)?;
-    
-    let other_asset_id = crate::types::hex_to_hash(other_asset_id_str)
-        .ok_or(StatusCode::BAD_REQUEST)?;
-    
-    // Verify both assets exist
-    if api_state.state.get_asset(&path_asset_id).is_none() {
-        return Err(StatusCode::NOT_FOUND);
-    }
-    if api_state.state.get_asset(&other_asset_id).is_none() {
-        return Err(StatusCode::NOT_FOUND);
-    }
-    
-    let tx_hash = tx.hash();
-    match api_state.consensus.add_transaction(tx) {
-        Ok(()) => Ok(Json(ApiResponse::success(TransactionResponse {
-            hash: hash_to_hex(&tx_hash),
-            status: "pending".to_string(),
-        }))),
-        Err(_) => Err(StatusCode::BAD_REQUEST),
-    }
+    Path(game_id): Path<String>,
+    axum::extract::Query(query): axum::extract::Query<AssetHistoryQuery>,
+) -> ApiResult<Json<ApiResponse<Vec<serde_json::Value>>>> {
+    let trades = api_state.state.get_trades(&game_id, query.limit.unwrap_or(100))
+        .iter()
+        .map(|trade| serde_json::json!({
+            "asset_id": hash_to_hex(&trade.asset_id),
+            "price": trade.price,
+            "fee": trade.fee,
+            "buyer": address_to_hex(&trade.buyer),
+            "seller": address_to_hex(&trade.seller),
+            "buy_order": hash_to_hex(&trade.buy_order),
+            "sell_order": hash_to_hex(&trade.sell_order),
+            "height": trade.height,
+        }))
+        .collect();
+    Ok(Json(ApiResponse::success(trades)))
 }
 
-/// Split asset into components
-///
-/// Expects a **signed** `Transaction::MistbornAsset { action: Split, asset_id: <path>, data: { metadata: { "_components": "component1,component2,..." } }, ... }`.
-async fn split_asset(
+/// Get an order book order by id
+async fn get_order(
     State(api_state): State<ApiState>,
-    Path(asset_id_str): Path<String>,
-    Json(request): Json<SendTransactionRequest>,
-) -> ApiResult<Json<ApiResponse<TransactionResponse>>> {
-    let path_asset_id = crate::types::hex_to_hash(&asset_id_str)
+    Path(order_id_str): Path<String>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let order_id = crate::types::hex_to_hash(&order_id_str)
         .ok_or(StatusCode::BAD_REQUEST)?;
-    
-    let tx = request.transaction;
-    let (action, asset_id, signature, data) = match &tx {
-        Transaction::MistbornAsset { action, asset_id, signature, data, .. } => (action, asset_id, signature, data),
-        _ => return Err(StatusCode::BAD_REQUEST),
-    };
-    
-    if !matches!(action, AssetAction::Split) {
-        return Err(StatusCode::BAD_REQUEST);
-    }
-    if *asset_id != path_asset_id {
-        return Err(StatusCode::BAD_REQUEST);
+    let order = api_state.state.get_order(&order_id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(ApiResponse::success(order_json(&order))))
+}
+
+/// WebSocket query parameters
+#[derive(Debug, Deserialize)]
+pub struct WsQuery {
+    /// Event schema version (defaults to the legacy v1 shape)
+    pub event_version: Option<u32>,
+    /// API key whose watchlists the connection follows (alternative to `x-api-key`)
+    pub api_key: Option<String>,
+}
+
+/// WebSocket handler
+async fn ws_handler(
+    ws: WebSocketUpgrade,
+    State(api_state): State<ApiState>,
+    axum::extract::Query(query): axum::extract::Query<WsQuery>,
+    headers: axum::http::HeaderMap,
+) -> axum::response::Response {
+    let event_version = query.event_version.unwrap_or(crate::ws_events::MIN_EVENT_VERSION);
+    if !crate::ws_events::is_supported_event_version(event_version) {
+        return axum::response::IntoResponse::into_response(StatusCode::BAD_REQUEST);
     }
-    if signature.is_empty() {
-        return Err(StatusCode::BAD_REQUEST);
+    let api_key = api_key_from_headers(&headers).or(query.api_key);
+    // A tenant's connection follows the tenant's watchlists and only sees its collections
+    let tenant = api_key.as_deref().and_then(|key| tenant_caller(&api_state, key));
+    if let (Some(tenants), Some(tenant)) = (&api_state.tenants, &tenant) {
+        tenants.usage(&tenant.id).ws_connections.fetch_add(1, Ordering::Relaxed);
     }
-    
-    // Verify _components is present in metadata
-    let components_str = data.metadata.get("_components")
-        .ok_or_else(|| StatusCode::BAD_REQUEST)?;
-    
-    let components: Vec<&str> = components_str.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
-    if components.is_empty() {
-        return Err(StatusCode::BAD_REQUEST);
+    let api_key_hash = match &tenant {
+        Some(tenant) => Some(tenant.watchlist_owner()),
+        None => api_key.as_deref().map(crate::watchlist::hash_api_key),
+    };
+    let tenant_id = tenant.map(|tenant| tenant.id);
+    let metered = api_state.metering.clone().zip(api_key);
+    ws.on_upgrade(move |socket| async move {
+        let connected_at = std::time::Instant::now();
+        handle_socket(socket, api_state, event_version, api_key_hash, tenant_id).await;
+        if let Some((meter, api_key)) = metered {
+            meter.record_ws(&api_key, connected_at.elapsed().as_secs());
+        }
+    })
+}
+
+/// Whether an event is sent on a connection: it matches an explicit subscription or one
+/// of the connection's watchlists; with neither, every event is sent. A tenant's connection
+/// only gets events about the tenant's collections.
+fn ws_should_send(
+    state: &ApiState,
+    subs: &[WsSubscription],
+    api_key_hash: Option<&str>,
+    tenant_id: Option<&str>,
+    event: &WsEvent,
+) -> bool {
+    if let Some(tenant_id) = tenant_id {
+        let game_id = crate::watchlist::event_collection(&state.state, event);
+        if !game_id.is_some_and(|game_id| crate::tenant::in_namespace(tenant_id, &game_id)) {
+            return false;
+        }
     }
-    
-    if api_state.state.get_asset(&path_asset_id).is_none() {
-        return Err(StatusCode::NOT_FOUND);
+    if subs.iter().any(|sub| sub.matches(event)) {
+        return true;
     }
-    
-    let tx_hash = tx.hash();
-    match api_state.consensus.add_transaction(tx) {
-        Ok(()) => Ok(Json(ApiResponse::success(TransactionResponse {
-            hash: hash_to_hex(&tx_hash),
-            status: "pending".to_string(),
-        }))),
-        Err(_) => Err(StatusCode::BAD_REQUEST),
+    let Some(key) = api_key_hash.filter(|key| state.watchlists.has_watchlists(key)) else {
+        return subs.is_empty();
+    };
+    if matches!(event, WsEvent::Error { .. }) {
+        return false;
     }
+    let game_id = crate::watchlist::event_collection(&state.state, event);
+    state.watchlists.key_matches(key, event, game_id.as_deref())
 }
 
-/// Estimate gas cost for asset operation (accepts hex strings for byte fields in JSON)
-#[derive(Debug, Deserialize, Serialize)]
-pub struct EstimateGasRequest {
-    #[serde(deserialize_with = "de_transaction_from_json")]
-    pub transaction: Transaction,
+/// Handle WebSocket connection
+async fn handle_socket(
+    socket: axum::extract::ws::WebSocket,
+    state: ApiState,
+    event_version: u32,
+    api_key_hash: Option<String>,
+    tenant_id: Option<String>,
+) {
+    use futures_util::{SinkExt, StreamExt};
+    let (mut sender, mut receiver) = socket.split();
+    let mut rx = state.ws_tx.subscribe();
+    let subscriptions = Arc::new(tokio::sync::Mutex::new(Vec::<WsSubscription>::new()));
+    // Schema version for this connection; a subscribe message may change it
+    let event_version = Arc::new(std::sync::atomic::AtomicU32::new(event_version));
+    let event_version_send = Arc::clone(&event_version);
+
+    // Clone Arc for send task
+    let subscriptions_send = Arc::clone(&subscriptions);
+    // Spawn task to send events to client
+    let mut send_task = tokio::spawn(async move {
+        while let Ok(event) = rx.recv().await {
+            // Check if event matches any subscription or watchlist
+            let subs = subscriptions_send.lock().await;
+            let should_send = ws_should_send(&state, &subs, api_key_hash.as_deref(), tenant_id.as_deref(), &event);
+            drop(subs); // Release lock before potential await
+
+            if should_send {
+                let event_version = event_version_send.load(Ordering::Relaxed);
+                if let Ok(json) = event.to_json(event_version)
+                    && sender.send(Message::Text(json)).await.is_err()
+                {
+                    break;
+                }
+            }
+        }
+    });
+
+    // Clone Arc for receive task
+    let subscriptions_recv = Arc::clone(&subscriptions);
+    // Spawn task to receive messages from client
+    let mut recv_task = tokio::spawn(async move {
+        while let Some(Ok(msg)) = receiver.next().await {
+            if let Message::Text(text) = msg {
+                if let Ok(request) = serde_json::from_str::<WsSubscribeRequest>(&text) {
+                    if let Some(version) = request.event_version.filter(|v| crate::ws_events::is_supported_event_version(*v)) {
+                        event_version.store(version, Ordering::Relaxed);
+                    }
+                    let mut subs = subscriptions_recv.lock().await;
+                    *subs = request.subscribe;
+                }
+            }
+        }
+    });
+
+    // Wait for either task to complete
+    tokio::select! {
+        _ = (&mut send_task) => recv_task.abort(),
+        _ = (&mut recv_task) => send_task.abort(),
+    };
 }
 
-/// Gas estimate response
-#[derive(Debug, Serialize)]
-pub struct GasEstimateResponse {
-    pub gas_cost: u64,
-    pub gas_fee: u64,
-    pub gas_price: u64,
+/// API key from the `x-api-key` header
+fn api_key_from_headers(headers: &axum::http::HeaderMap) -> Option<String> {
+    headers
+        .get("x-api-key")
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+        .map(str::to_string)
 }
 
-async fn estimate_asset_gas(
-    State(api_state): State<ApiState>,
-    Json(request): Json<EstimateGasRequest>,
-) -> ApiResult<Json<ApiResponse<GasEstimateResponse>>> {
-    let tx = request.transaction;
-    
-    // Extract asset operation data
-    let (action, data) = match &tx {
-        Transaction::MistbornAsset { action, data, .. } => (action, data),
-        _ => return Err(StatusCode::BAD_REQUEST),
-    };
-    
-    // Calculate gas cost
-    let gas_cost = crate::assets::calculate_asset_operation_gas(
-        &api_state.config,
-        action,
-        data,
-        Some(&data.metadata),
-    );
-    
-    // Calculate gas fee (gas_cost * gas_price)
-    let gas_fee = gas_cost * api_state.config.vm.gas_price;
-    
-    Ok(Json(ApiResponse::success(GasEstimateResponse {
-        gas_cost,
-        gas_fee,
-        gas_price: api_state.config.vm.gas_price,
-    })))
+/// Session token from an `Authorization: Bearer` header
+fn session_token_from_headers(headers: &axum::http::HeaderMap) -> Option<String> {
+    headers
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+        .map(str::to_string)
 }
 
-/// Get asset permissions
-async fn get_asset_permissions(
-    State(api_state): State<ApiState>,
-    Path(asset_id_str): Path<String>,
-) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
-    let asset_id = crate::types::hex_to_hash(&asset_id_str).ok_or(StatusCode::BAD_REQUEST)?;
+/// Who a request is from: an API key (`x-api-key`), or an account signed in with
+/// Sign-in-with-HAZE (`Authorization: Bearer <session token>`)
+enum Caller {
+    ApiKey(String),
+    Account(crate::types::Address),
+}
 
-    if let Some(asset_state) = api_state.state.get_asset(&asset_id) {
-        let permissions_json: Vec<serde_json::Value> = asset_state.permissions.iter().map(|p| {
-            serde_json::json!({
-                "grantee": address_to_hex(&p.grantee),
-                "level": format!("{:?}", p.level),
-                "game_id": p.game_id,
-                "expires_at": p.expires_at,
-            })
-        }).collect();
-        let response = serde_json::json!({
-            "asset_id": hash_to_hex(&asset_id),
-            "owner": address_to_hex(&asset_state.owner),
-            "permissions": permissions_json,
-            "public_read": asset_state.public_read,
-        });
-        Ok(Json(ApiResponse::success(response)))
-    } else {
-        Err(StatusCode::NOT_FOUND)
+impl Caller {
+    /// Key the caller's watchlists and custodial accounts belong to
+    fn key(&self) -> String {
+        match self {
+            Caller::ApiKey(api_key) => api_key.clone(),
+            Caller::Account(address) => format!("account:{}", address_to_hex(address)),
+        }
     }
 }
 
-/// Permission DTO for API (grantee as hex string)
-#[derive(Debug, Deserialize)]
-pub struct PermissionDto {
-    pub grantee: String,
-    pub level: String,
-    pub game_id: Option<String>,
-    pub expires_at: Option<i64>,
+/// Caller of a request; an API key takes precedence over a session token, and an expired
+/// or unknown token counts as no caller
+fn request_caller(api_state: &ApiState, headers: &axum::http::HeaderMap) -> Option<Caller> {
+    if let Some(api_key) = api_key_from_headers(headers) {
+        return Some(Caller::ApiKey(api_key));
+    }
+    let token = session_token_from_headers(headers)?;
+    let session = api_state.sign_in.as_ref()?.session(&token, chrono::Utc::now().timestamp())?;
+    Some(Caller::Account(session.address))
 }
 
-/// Set asset permissions request
-#[derive(Debug, Deserialize)]
-pub struct SetPermissionsRequest {
-    pub permissions: Vec<PermissionDto>,
-    pub public_read: bool,
-    /// Owner address (hex string)
-    pub owner: String,
-    pub signature: Vec<u8>,
+/// Tenant owning `api_key`, if the node is multi-tenant
+fn tenant_caller(api_state: &ApiState, api_key: &str) -> Option<Tenant> {
+    api_state.tenants.as_ref().and_then(|tenants| tenants.tenant_for_key(api_key))
 }
 
-/// Set asset permissions (owner only)
-async fn set_asset_permissions(
+/// Check a submitted transaction's size, then check one submitted with a tenant API key
+/// against the tenant's namespace and quotas (see `TenantStore::authorize_transaction`),
+/// and meter it for the key
+fn authorize_submission(api_state: &ApiState, headers: &axum::http::HeaderMap, tx: &Transaction) -> ApiResult<()> {
+    api_state.consensus.check_transaction_size(tx)?;
+    let Some(api_key) = api_key_from_headers(headers) else {
+        return Ok(());
+    };
+    if let (Some(tenants), Some(tenant)) = (&api_state.tenants, tenant_caller(api_state, &api_key)) {
+        tenants.authorize_transaction(&tenant, &api_state.state, tx)?;
+    }
+    if let Some(meter) = &api_state.metering {
+        meter.record_transaction(&api_key, &api_state.config, tx);
+    }
+    Ok(())
+}
+
+/// Read-only API surface (`role.api = "read"`): writes outside `/api/v1/admin/` answer `405`
+async fn role_gate(
     State(api_state): State<ApiState>,
-    Path(asset_id_str): Path<String>,
-    Json(req): Json<SetPermissionsRequest>,
-) -> ApiResult<Json<ApiResponse<TransactionResponse>>> {
-    let asset_id = crate::types::hex_to_hash(&asset_id_str).ok_or(StatusCode::BAD_REQUEST)?;
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    use axum::response::IntoResponse;
+    let read = matches!(
+        *request.method(),
+        axum::http::Method::GET | axum::http::Method::HEAD | axum::http::Method::OPTIONS
+    );
+    let (role, subsystems) = api_state.config.subsystems();
+    if subsystems.api == crate::config::ApiSurface::Full || read || request.uri().path().starts_with("/api/v1/admin/") {
+        return next.run(request).await;
+    }
+    (
+        StatusCode::METHOD_NOT_ALLOWED,
+        [(axum::http::header::ALLOW, "GET, HEAD, OPTIONS")],
+        Json(ApiResponse::<()>::error(format!("A {} node serves a read-only API", role.as_str()))),
+    )
+        .into_response()
+}
 
-    let owner = crate::types::hex_to_address(&req.owner).ok_or(StatusCode::BAD_REQUEST)?;
+/// Largest write request body forwarded from a read replica to its primary
+const REPLICA_PROXY_MAX_BODY: usize = 16 * 1024 * 1024;
+/// Time a read replica waits for the primary to answer a forwarded write
+const REPLICA_PROXY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(15);
 
-    if api_state.state.get_asset(&asset_id).is_none() {
-        return Err(StatusCode::NOT_FOUND);
+/// Read replica mode: serve reads locally and forward writes to the primary
+/// (`replica.primary_api_url`), or reject them with `405` when no primary URL is set
+async fn replica_gate(
+    State(api_state): State<ApiState>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    use axum::response::IntoResponse;
+    let read = matches!(
+        *request.method(),
+        axum::http::Method::GET | axum::http::Method::HEAD | axum::http::Method::OPTIONS
+    );
+    if !api_state.config.replica.enabled || read {
+        return next.run(request).await;
     }
-    if req.signature.is_empty() {
-        return Err(StatusCode::BAD_REQUEST);
+    match &api_state.config.replica.primary_api_url {
+        Some(primary) => forward_to_primary(primary, request).await.unwrap_or_else(|e| {
+            tracing::warn!("Failed to forward write to primary {}: {}", primary, e);
+            StatusCode::BAD_GATEWAY.into_response()
+        }),
+        None => (
+            StatusCode::METHOD_NOT_ALLOWED,
+            [(axum::http::header::ALLOW, "GET, HEAD, OPTIONS")],
+            Json(ApiResponse::<()>::error("Read replica: send writes to the primary node".to_string())),
+        )
+            .into_response(),
     }
+}
 
-    let mut permissions = Vec::with_capacity(req.permissions.len());
-    for p in req.permissions {
-        let grantee = crate::types::hex_to_address(&p.grantee).ok_or(StatusCode::BAD_REQUEST)?;
-        let level = match p.level.as_str() {
-            "GameContract" => PermissionLevel::GameContract,
-            "PublicRead" => PermissionLevel::PublicRead,
-            _ => return Err(StatusCode::BAD_REQUEST),
-        };
-        permissions.push(AssetPermission {
-            grantee,
-            level,
-            game_id: p.game_id,
-            expires_at: p.expires_at,
-        });
+/// Send a request to the primary's API unchanged and relay its answer
+async fn forward_to_primary(
+    primary: &str,
+    request: axum::extract::Request,
+) -> std::result::Result<axum::response::Response, String> {
+    static CLIENT: std::sync::OnceLock<reqwest::Client> = std::sync::OnceLock::new();
+    let client = CLIENT.get_or_init(|| {
+        reqwest::Client::builder().timeout(REPLICA_PROXY_TIMEOUT).build().unwrap_or_default()
+    });
+
+    let (parts, body) = request.into_parts();
+    let path = parts.uri.path_and_query().map(|p| p.as_str()).unwrap_or("/");
+    let url = format!("{}{}", primary.trim_end_matches('/'), path);
+    let body = axum::body::to_bytes(body, REPLICA_PROXY_MAX_BODY).await.map_err(|e| e.to_string())?;
+    let mut headers = parts.headers;
+    for hop in [axum::http::header::HOST, axum::http::header::CONNECTION, axum::http::header::CONTENT_LENGTH] {
+        headers.remove(hop);
     }
 
-    let tx = Transaction::SetAssetPermissions {
-        from: owner,
-        asset_id,
-        permissions,
-        public_read: req.public_read,
-        owner,
-        fee: 0,
-        nonce: 0,
-        chain_id: None,
-        valid_until_height: None,
-        signature: req.signature,
-    };
-    let tx_hash = tx.hash();
-    match api_state.consensus.add_transaction(tx) {
-        Ok(()) => Ok(Json(ApiResponse::success(TransactionResponse {
-            hash: hash_to_hex(&tx_hash),
-            status: "pending".to_string(),
-        }))),
-        Err(_) => Err(StatusCode::BAD_REQUEST),
+    let response = client
+        .request(parts.method, url)
+        .headers(headers)
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut relayed = axum::response::Response::builder().status(response.status());
+    if let Some(content_type) = response.headers().get(reqwest::header::CONTENT_TYPE) {
+        relayed = relayed.header(axum::http::header::CONTENT_TYPE, content_type);
     }
+    let body = response.bytes().await.map_err(|e| e.to_string())?;
+    relayed.body(axum::body::Body::from(body)).map_err(|e| e.to_string())
 }
 
-/// Export asset as JSON (full state: metadata, attributes, blob_refs, history, versions, permissions)
-async fn export_asset(
+/// Count requests per API key for usage metering
+async fn meter_requests(
     State(api_state): State<ApiState>,
-    Path(asset_id_str): Path<String>,
-) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
-    let asset_id = crate::types::hex_to_hash(&asset_id_str).ok_or(StatusCode::BAD_REQUEST)?;
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    if let (Some(meter), Some(api_key)) = (&api_state.metering, api_key_from_headers(request.headers())) {
+        meter.record_request(&api_key);
+    }
+    next.run(request).await
+}
 
-    let asset_state = api_state.state.get_asset(&asset_id).ok_or(StatusCode::NOT_FOUND)?;
+/// Time requests per method and route for `haze_api_request_seconds`. Requests matching no
+/// route are not timed, so arbitrary paths do not add time series.
+async fn time_requests(
+    State(api_state): State<ApiState>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    let Some(route) = request.extensions().get::<axum::extract::MatchedPath>() else {
+        return next.run(request).await;
+    };
+    let operation = format!("{} {}", request.method(), route.as_str());
+    let started = std::time::Instant::now();
+    let response = next.run(request).await;
+    api_state.state.latency().api_requests.observe(&operation, started.elapsed());
+    response
+}
 
-    let blob_refs_json: std::collections::HashMap<String, String> = asset_state
-        .blob_refs
-        .iter()
-        .map(|(k, v)| (k.clone(), hex::encode(v)))
-        .collect();
-    let permissions_json: Vec<serde_json::Value> = asset_state
-        .permissions
-        .iter()
-        .map(|p| {
-            serde_json::json!({
-                "grantee": address_to_hex(&p.grantee),
-                "level": format!("{:?}", p.level),
-                "game_id": p.game_id,
-                "expires_at": p.expires_at,
-            })
-        })
-        .collect();
-    let history_json: Vec<serde_json::Value> = asset_state
-        .history
-        .iter()
-        .map(|e| {
-            serde_json::json!({
-                "timestamp": e.timestamp,
-                "action": format!("{:?}", e.action),
-                "changes": e.changes,
-            })
-        })
-        .collect();
-    let versions_json: Vec<serde_json::Value> = asset_state
-        .versions
-        .iter()
-        .map(|v| {
-            serde_json::json!({
-                "version": v.version,
-                "timestamp": v.timestamp,
-            })
-        })
-        .collect();
+/// Multi-tenant mode: count each tenant's requests and require a tenant (or admin) API key
+/// for write endpoints. Admin endpoints check their own key; sign-in and direct messages are
+/// authenticated by account signatures instead.
+async fn tenant_gate(
+    State(api_state): State<ApiState>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    let Some(tenants) = api_state.tenants.as_ref() else {
+        return next.run(request).await;
+    };
+    let api_key = api_key_from_headers(request.headers());
+    let tenant = api_key.as_deref().and_then(|key| tenants.tenant_for_key(key));
+    if let Some(tenant) = &tenant {
+        tenants.usage(&tenant.id).requests.fetch_add(1, Ordering::Relaxed);
+    }
+    let read = matches!(
+        *request.method(),
+        axum::http::Method::GET | axum::http::Method::HEAD | axum::http::Method::OPTIONS
+    );
+    let path = request.uri().path();
+    if read || tenant.is_some() || path.starts_with("/api/v1/admin/")
+        || path.starts_with("/api/v1/auth/")
+        || path.starts_with("/api/v1/messages")
+    {
+        return next.run(request).await;
+    }
+    match api_key {
+        Some(key) if tenants.is_admin(&key) => next.run(request).await,
+        Some(_) => axum::response::IntoResponse::into_response(StatusCode::FORBIDDEN),
+        None => axum::response::IntoResponse::into_response(StatusCode::UNAUTHORIZED),
+    }
+}
 
-    let export_json = serde_json::json!({
-        "asset_id": hash_to_hex(&asset_id),
-        "owner": address_to_hex(&asset_state.owner),
-        "density": format!("{:?}", asset_state.data.density),
-        "metadata": asset_state.data.metadata,
-        "attributes": asset_state.data.attributes,
-        "game_id": asset_state.data.game_id,
-        "created_at": asset_state.created_at,
-        "updated_at": asset_state.updated_at,
-        "blob_refs": blob_refs_json,
-        "history": history_json,
-        "versions": versions_json,
-        "current_version": asset_state.current_version,
-        "permissions": permissions_json,
-        "public_read": asset_state.public_read,
-    });
-    Ok(Json(ApiResponse::success(export_json)))
+fn tenant_json(api_state: &ApiState, tenants: &TenantStore, tenant: &Tenant) -> serde_json::Value {
+    let usage = tenants.usage(&tenant.id);
+    let collections = tenants.collections(tenant, &api_state.state);
+    let watchlists = api_state.watchlists.list_owned(&tenant.watchlist_owner()).len();
+    serde_json::json!({
+        "id": tenant.id,
+        "name": tenant.name,
+        "namespace": tenant.namespace(),
+        "key_hashes": tenant.key_hashes,
+        "quota": tenant.quota,
+        "created_at": tenant.created_at,
+        "collections": collections
+            .iter()
+            .map(|(game_id, assets)| serde_json::json!({ "game_id": game_id, "assets": assets }))
+            .collect::<Vec<_>>(),
+        "assets": collections.iter().map(|(_, assets)| assets).sum::<usize>(),
+        "watchlists": watchlists,
+        "usage": {
+            "requests": usage.requests.load(Ordering::Relaxed),
+            "transactions": usage.transactions.load(Ordering::Relaxed),
+            "assets_created": usage.assets_created.load(Ordering::Relaxed),
+            "ws_connections": usage.ws_connections.load(Ordering::Relaxed),
+        },
+    })
+}
+
+/// Tenant store, if the node is multi-tenant and the caller holds an admin API key
+fn admin_caller(api_state: &ApiState, headers: &axum::http::HeaderMap) -> ApiResult<Arc<TenantStore>> {
+    let tenants = api_state.tenants.clone().ok_or(StatusCode::NOT_FOUND)?;
+    let api_key = api_key_from_headers(headers).ok_or(StatusCode::UNAUTHORIZED)?;
+    if !tenants.is_admin(&api_key) {
+        return Err(StatusCode::FORBIDDEN.into());
+    }
+    Ok(tenants)
+}
+
+/// The calling tenant with its quotas, collections and usage
+async fn get_own_tenant(
+    State(api_state): State<ApiState>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let tenants = api_state.tenants.clone().ok_or(StatusCode::NOT_FOUND)?;
+    let api_key = api_key_from_headers(&headers).ok_or(StatusCode::UNAUTHORIZED)?;
+    let tenant = tenants.tenant_for_key(&api_key).ok_or(StatusCode::FORBIDDEN)?;
+    Ok(Json(ApiResponse::success(tenant_json(&api_state, &tenants, &tenant))))
 }
 
-/// Import asset request (export-like JSON + signature for Create tx)
-#[derive(Debug, Deserialize)]
-pub struct ImportAssetRequest {
-    pub asset_id: String,
-    pub owner: String,
-    pub density: String,
-    pub metadata: std::collections::HashMap<String, String>,
-    pub attributes: Vec<crate::types::Attribute>,
-    pub game_id: Option<String>,
-    #[serde(default)]
-    pub blob_refs: std::collections::HashMap<String, String>,
-    /// Signature hex string
-    pub signature: String,
+/// List tenants with their usage (admin)
+async fn list_tenants(
+    State(api_state): State<ApiState>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<Json<ApiResponse<Vec<serde_json::Value>>>> {
+    let tenants = admin_caller(&api_state, &headers)?;
+    let list = tenants.list().iter().map(|tenant| tenant_json(&api_state, &tenants, tenant)).collect();
+    Ok(Json(ApiResponse::success(list)))
 }
 
-/// Import asset from JSON (creates asset via Create transaction)
-async fn import_asset(
+/// Create a tenant (admin); the response holds its first API key, which is not shown again
+async fn create_tenant(
     State(api_state): State<ApiState>,
-    Json(req): Json<ImportAssetRequest>,
-) -> ApiResult<Json<ApiResponse<TransactionResponse>>> {
-    let asset_id = crate::types::hex_to_hash(&req.asset_id).ok_or(StatusCode::BAD_REQUEST)?;
+    headers: axum::http::HeaderMap,
+    Json(request): Json<NewTenant>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let tenants = admin_caller(&api_state, &headers)?;
+    let (tenant, api_key) = tenants.create(request)?;
+    tracing::info!("Created tenant {}", tenant.id);
+    let mut body = tenant_json(&api_state, &tenants, &tenant);
+    body["api_key"] = api_key.into();
+    Ok(Json(ApiResponse::success(body)))
+}
 
-    let owner = crate::types::hex_to_address(&req.owner).ok_or(StatusCode::BAD_REQUEST)?;
+/// One tenant with its usage (admin)
+async fn get_tenant(
+    State(api_state): State<ApiState>,
+    Path(id): Path<String>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let tenants = admin_caller(&api_state, &headers)?;
+    let tenant = tenants.get(&id).ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(ApiResponse::success(tenant_json(&api_state, &tenants, &tenant))))
+}
 
-    if api_state.state.get_asset(&asset_id).is_some() {
-        return Err(StatusCode::CONFLICT);
+/// Delete a tenant and revoke its keys (admin)
+async fn delete_tenant(
+    State(api_state): State<ApiState>,
+    Path(id): Path<String>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<Json<ApiResponse<&'static str>>> {
+    let tenants = admin_caller(&api_state, &headers)?;
+    if tenants.delete(&id)? {
+        tracing::info!("Deleted tenant {}", id);
+        Ok(Json(ApiResponse::success("deleted")))
+    } else {
+        Err(StatusCode::NOT_FOUND.into())
     }
-    let signature = hex::decode(&req.signature).map_err(|_| StatusCode::BAD_REQUEST)?;
-    if signature.is_empty() {
-        return Err(StatusCode::BAD_REQUEST);
+}
+
+/// Replace a tenant's quota (admin)
+async fn set_tenant_quota(
+    State(api_state): State<ApiState>,
+    Path(id): Path<String>,
+    headers: axum::http::HeaderMap,
+    Json(quota): Json<crate::config::TenantQuota>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let tenants = admin_caller(&api_state, &headers)?;
+    let tenant = tenants.set_quota(&id, quota)?.ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(ApiResponse::success(tenant_json(&api_state, &tenants, &tenant))))
+}
+
+/// Issue another API key for a tenant (admin); the key is not shown again
+async fn issue_tenant_key(
+    State(api_state): State<ApiState>,
+    Path(id): Path<String>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let tenants = admin_caller(&api_state, &headers)?;
+    let api_key = tenants.issue_key(&id)?.ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(ApiResponse::success(serde_json::json!({
+        "api_key": api_key,
+        "key_hash": crate::watchlist::hash_api_key(&api_key),
+    }))))
+}
+
+/// Revoke a tenant API key by its hash (admin)
+async fn revoke_tenant_key(
+    State(api_state): State<ApiState>,
+    Path((id, key_hash)): Path<(String, String)>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<Json<ApiResponse<&'static str>>> {
+    let tenants = admin_caller(&api_state, &headers)?;
+    if tenants.revoke_key(&id, &key_hash)? {
+        Ok(Json(ApiResponse::success("revoked")))
+    } else {
+        Err(StatusCode::NOT_FOUND.into())
     }
+}
 
-    let density = match req.density.as_str() {
-        "Ethereal" => crate::types::DensityLevel::Ethereal,
-        "Light" => crate::types::DensityLevel::Light,
-        "Dense" => crate::types::DensityLevel::Dense,
-        "Core" => crate::types::DensityLevel::Core,
-        _ => return Err(StatusCode::BAD_REQUEST),
-    };
+/// Query of the usage export
+#[derive(Debug, Deserialize)]
+pub struct MeteringQuery {
+    /// First day (`YYYY-MM-DD`, UTC), inclusive
+    pub from: Option<String>,
+    /// Last day (`YYYY-MM-DD`, UTC), inclusive
+    pub to: Option<String>,
+    /// Only this API key (hex SHA-256)
+    pub key_hash: Option<String>,
+    /// `json` (default) or `csv`
+    pub format: Option<String>,
+}
 
-    let mut metadata = req.metadata;
-    if !req.blob_refs.is_empty() {
-        let blob_refs_json = serde_json::to_string(&req.blob_refs).map_err(|_| StatusCode::BAD_REQUEST)?;
-        metadata.insert("_blob_refs".to_string(), blob_refs_json);
+/// Require an API key listed in `api.admin_key_hashes` (or a tenant admin key)
+fn require_node_admin(api_state: &ApiState, headers: &axum::http::HeaderMap) -> ApiResult<()> {
+    let api_key = api_key_from_headers(headers).ok_or(StatusCode::UNAUTHORIZED)?;
+    let key_hash = crate::watchlist::hash_api_key(&api_key);
+    let node_admin = api_state.config.api.admin_key_hashes.iter().any(|hash| hash.eq_ignore_ascii_case(&key_hash));
+    let tenant_admin = api_state.tenants.as_ref().is_some_and(|tenants| tenants.is_admin(&api_key));
+    if !node_admin && !tenant_admin {
+        return Err(StatusCode::FORBIDDEN.into());
     }
+    Ok(())
+}
 
-    let data = crate::types::AssetData {
-        density,
-        metadata,
-        attributes: req.attributes,
-        game_id: req.game_id,
-        owner,
-    };
+/// State divergence incidents and the waves they keep from finalizing (admin)
+async fn get_divergence_incidents(
+    State(api_state): State<ApiState>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    require_node_admin(&api_state, &headers)?;
+    let incidents = api_state.consensus.get_divergence_incidents();
+    Ok(Json(ApiResponse::success(serde_json::json!({
+        "halted_waves": api_state.consensus.divergent_waves(),
+        "incidents": incidents.iter().map(|incident| serde_json::json!({
+            "height": incident.height,
+            "block_hash": hash_to_hex(&incident.block_hash),
+            "wave": incident.wave,
+            "validator": address_to_hex(&incident.validator),
+            "expected_state_root": hash_to_hex(&incident.expected),
+            "local_state_root": hash_to_hex(&incident.local),
+            "detected_at": incident.detected_at,
+        })).collect::<Vec<_>>(),
+    }))))
+}
 
-    let tx = Transaction::MistbornAsset {
-        from: owner,
-        action: AssetAction::Create,
-        asset_id,
-        data,
-        fee: 0,
-        nonce: 0,
-        chain_id: None,
-        valid_until_height: None,
-        signature,
+/// Body of `POST /api/v1/admin/consensus/pause`
+#[derive(Debug, Default, Deserialize)]
+pub struct PauseRequest {
+    /// Seconds until production resumes on its own (default and maximum: `consensus.max_pause_secs`)
+    pub timeout_secs: Option<u64>,
+    /// Also refuse new transactions
+    #[serde(default)]
+    pub maintenance: bool,
+    pub reason: Option<String>,
+}
+
+/// Pause block production, optionally in maintenance mode (admin)
+async fn pause_block_production(
+    State(api_state): State<ApiState>,
+    headers: axum::http::HeaderMap,
+    request: Option<Json<PauseRequest>>,
+) -> ApiResult<Json<ApiResponse<crate::maintenance::Pause>>> {
+    require_node_admin(&api_state, &headers)?;
+    let request = request.map(|Json(request)| request).unwrap_or_default();
+    let pause = api_state.consensus.control().pause(
+        chrono::Utc::now().timestamp(),
+        request.timeout_secs,
+        request.maintenance,
+        request.reason,
+    )?;
+    Ok(Json(ApiResponse::success(pause)))
+}
+
+/// Resume block production and leave maintenance mode (admin); returns the pause that ended
+async fn resume_block_production(
+    State(api_state): State<ApiState>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<Json<ApiResponse<Option<crate::maintenance::Pause>>>> {
+    require_node_admin(&api_state, &headers)?;
+    Ok(Json(ApiResponse::success(api_state.consensus.resume_production())))
+}
+
+/// Daily usage rollups per API key for billing (admin), as JSON or CSV
+async fn export_metering(
+    State(api_state): State<ApiState>,
+    axum::extract::Query(query): axum::extract::Query<MeteringQuery>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<axum::response::Response> {
+    let meter = api_state.metering.clone().ok_or(StatusCode::NOT_FOUND)?;
+    let api_key = api_key_from_headers(&headers).ok_or(StatusCode::UNAUTHORIZED)?;
+    let tenant_admin = api_state.tenants.as_ref().is_some_and(|tenants| tenants.is_admin(&api_key));
+    if !meter.is_admin(&api_key) && !tenant_admin {
+        return Err(StatusCode::FORBIDDEN.into());
+    }
+    let valid_day = |day: &Option<String>| {
+        day.as_deref()
+            .is_none_or(|day| chrono::NaiveDate::parse_from_str(day, crate::metering::DAY_FORMAT).is_ok())
     };
-    let tx_hash = tx.hash();
-    match api_state.consensus.add_transaction(tx) {
-        Ok(()) => Ok(Json(ApiResponse::success(TransactionResponse {
-            hash: hash_to_hex(&tx_hash),
-            status: "pending".to_string(),
-        }))),
-        Err(_) => Err(StatusCode::BAD_REQUEST),
+    if !valid_day(&query.from) || !valid_day(&query.to) {
+        return Err(StatusCode::BAD_REQUEST.into());
+    }
+    let key_hash = query.key_hash.map(|hash| hash.to_lowercase());
+    let mut rollups = meter.rollups(query.from.as_deref(), query.to.as_deref(), key_hash.as_deref())?;
+    if let Some(tenants) = &api_state.tenants {
+        let owners: std::collections::HashMap<String, String> = tenants
+            .list()
+            .into_iter()
+            .flat_map(|tenant| tenant.key_hashes.into_iter().map(move |hash| (hash, tenant.id.clone())))
+            .collect();
+        for rollup in &mut rollups {
+            rollup.tenant = owners.get(&rollup.key_hash).cloned();
+        }
+    }
+
+    match query.format.as_deref().unwrap_or("json") {
+        "json" => Ok(axum::response::IntoResponse::into_response(Json(ApiResponse::success(rollups)))),
+        "csv" => Ok(axum::response::IntoResponse::into_response((
+            [
+                (axum::http::header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+                (axum::http::header::CONTENT_DISPOSITION, "attachment; filename=\"haze-usage.csv\""),
+            ],
+            crate::metering::rollups_to_csv(&rollups),
+        ))),
+        _ => Err(StatusCode::BAD_REQUEST.into()),
     }
 }
 
-/// Search assets
-async fn search_assets(
+/// Accept a node's telemetry report (aggregator nodes only)
+async fn submit_telemetry(
     State(api_state): State<ApiState>,
-    axum::extract::Query(query): axum::extract::Query<SearchAssetsQuery>,
+    Json(report): Json<TelemetryReport>,
+) -> ApiResult<Json<ApiResponse<&'static str>>> {
+    let aggregator = api_state.telemetry.clone().ok_or(StatusCode::NOT_FOUND)?;
+    aggregator.record(report, chrono::Utc::now().timestamp())?;
+    Ok(Json(ApiResponse::success("recorded")))
+}
+
+/// Nodes that reported recently, for a network map (aggregator nodes only)
+async fn list_telemetry_nodes(
+    State(api_state): State<ApiState>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let aggregator = api_state.telemetry.clone().ok_or(StatusCode::NOT_FOUND)?;
+    let nodes = aggregator.nodes(chrono::Utc::now().timestamp());
+    Ok(Json(ApiResponse::success(serde_json::json!({
+        "count": nodes.len(),
+        "nodes": nodes,
+    }))))
+}
+
+/// Send test funds to an address, once the transfer is sealed (dev nodes only)
+async fn dev_faucet(
+    State(api_state): State<ApiState>,
+    Json(request): Json<FaucetRequest>,
+) -> ApiResult<Json<ApiResponse<FaucetTransfer>>> {
+    let faucet = api_state.faucet.clone().ok_or(StatusCode::NOT_FOUND)?;
+    let to = crate::types::hex_to_address(&request.address).ok_or(StatusCode::BAD_REQUEST)?;
+    let transfer = faucet.send(to, request.amount).await?;
+    Ok(Json(ApiResponse::success(transfer)))
+}
+
+fn watchlist_json(watchlist: &Watchlist) -> serde_json::Value {
+    serde_json::json!({
+        "id": watchlist.id,
+        "name": watchlist.name,
+        "assets": watchlist.assets.iter().map(hash_to_hex).collect::<Vec<_>>(),
+        "collections": watchlist.collections,
+        "accounts": watchlist.accounts.iter().map(address_to_hex).collect::<Vec<_>>(),
+        "webhook_url": watchlist.webhook_url,
+        "created_at": watchlist.created_at,
+    })
+}
+
+/// List the caller's watchlists (requires `x-api-key`)
+async fn list_watchlists(
+    State(api_state): State<ApiState>,
+    headers: axum::http::HeaderMap,
 ) -> ApiResult<Json<ApiResponse<Vec<serde_json::Value>>>> {
-    let limit = query.limit.unwrap_or(100).min(1000);
-    let offset = query.offset.unwrap_or(0);
-    let mut candidate_ids: Vec<Hash> = Vec::new();
-    
-    // Use indexes for efficient filtering
-    if let Some(ref owner_filter) = query.owner {
-        if let Some(owner) = crate::types::hex_to_address(owner_filter) {
-            candidate_ids = api_state.state.search_assets_by_owner(&owner);
+    let api_key = request_caller(&api_state, &headers).ok_or(StatusCode::UNAUTHORIZED)?.key();
+    let watchlists = match tenant_caller(&api_state, &api_key) {
+        Some(tenant) => api_state.watchlists.list_owned(&tenant.watchlist_owner()),
+        None => api_state.watchlists.list(&api_key),
+    };
+    let watchlists = watchlists.iter().map(watchlist_json).collect();
+    Ok(Json(ApiResponse::success(watchlists)))
+}
+
+/// Create a watchlist for the caller's API key
+async fn create_watchlist(
+    State(api_state): State<ApiState>,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<NewWatchlist>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let api_key = request_caller(&api_state, &headers).ok_or(StatusCode::UNAUTHORIZED)?.key();
+    let watchlist = match tenant_caller(&api_state, &api_key) {
+        Some(tenant) => {
+            if let Some(game_id) = request.collections.iter().find(|game_id| !tenant.owns_collection(game_id)) {
+                return Err(HazeError::AccessDenied(format!(
+                    "Collection '{}' is outside tenant namespace '{}'", game_id, tenant.namespace()
+                )).into());
+            }
+            api_state.watchlists.create_owned(tenant.watchlist_owner(), request, tenant.quota.max_watchlists)?
         }
-    } else if let Some(ref game_id_filter) = query.game_id {
-        candidate_ids = api_state.state.search_assets_by_game_id(game_id_filter);
-    } else if let Some(ref density_filter) = query.density {
-        // Parse density level
-        let density = match density_filter.as_str() {
-            "Ethereal" => crate::types::DensityLevel::Ethereal,
-            "Light" => crate::types::DensityLevel::Light,
-            "Dense" => crate::types::DensityLevel::Dense,
-            "Core" => crate::types::DensityLevel::Core,
-            _ => return Err(StatusCode::BAD_REQUEST),
-        };
-        candidate_ids = api_state.state.search_assets_by_density(density);
+        None => api_state.watchlists.create(&api_key, request)?,
+    };
+    Ok(Json(ApiResponse::success(watchlist_json(&watchlist))))
+}
+
+/// Delete one of the caller's watchlists
+async fn delete_watchlist(
+    State(api_state): State<ApiState>,
+    Path(id): Path<String>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<Json<ApiResponse<&'static str>>> {
+    let api_key = request_caller(&api_state, &headers).ok_or(StatusCode::UNAUTHORIZED)?.key();
+    let deleted = match tenant_caller(&api_state, &api_key) {
+        Some(tenant) => api_state.watchlists.delete_owned(&tenant.watchlist_owner(), &id)?,
+        None => api_state.watchlists.delete(&api_key, &id)?,
+    };
+    if deleted {
+        Ok(Json(ApiResponse::success("deleted")))
     } else {
-        // No specific filter, use all assets
-        candidate_ids = api_state.state.assets().iter().map(|e| *e.key()).collect();
-    }
-    
-    // Apply full-text search if provided
-    if let Some(ref search_query) = query.q {
-        if !search_query.is_empty() {
-            let text_search_results = api_state.state.search_assets_by_metadata(search_query);
-            // Intersect with candidate_ids
-            let text_search_set: std::collections::HashSet<Hash> = text_search_results.into_iter().collect();
-            candidate_ids.retain(|id| text_search_set.contains(id));
-        }
+        Err(StatusCode::NOT_FOUND.into())
     }
-    
-    // Build results
-    let mut results: Vec<(Hash, AssetState)> = candidate_ids.iter()
-        .filter_map(|id| {
-            api_state.state.get_asset(id).map(|state| (*id, state))
-        })
-        .collect();
-    
-    // Sort results
-    let sort_by = query.sort_by.as_deref().unwrap_or("created_at");
-    let sort_order = query.sort_order.as_deref().unwrap_or("desc");
-    let ascending = sort_order == "asc";
-    
-    match sort_by {
-        "created_at" => {
-            results.sort_by(|a, b| {
-                if ascending {
-                    a.1.created_at.cmp(&b.1.created_at)
-                } else {
-                    b.1.created_at.cmp(&a.1.created_at)
-                }
-            });
-        }
-        "updated_at" => {
-            results.sort_by(|a, b| {
-                if ascending {
-                    a.1.updated_at.cmp(&b.1.updated_at)
-                } else {
-                    b.1.updated_at.cmp(&a.1.updated_at)
-                }
-            });
-        }
-        "rarity" => {
-            results.sort_by(|a, b| {
-                let rarity_a = a.1.data.attributes.iter()
-                    .find(|attr| attr.name == "rarity")
-                    .and_then(|attr| attr.rarity)
-                    .unwrap_or(0.0);
-                let rarity_b = b.1.data.attributes.iter()
-                    .find(|attr| attr.name == "rarity")
-                    .and_then(|attr| attr.rarity)
-                    .unwrap_or(0.0);
-                if ascending {
-                    rarity_a.partial_cmp(&rarity_b).unwrap_or(std::cmp::Ordering::Equal)
-                } else {
-                    rarity_b.partial_cmp(&rarity_a).unwrap_or(std::cmp::Ordering::Equal)
-                }
-            });
-        }
-        _ => {
-            // Default: sort by created_at desc
-            results.sort_by(|a, b| b.1.created_at.cmp(&a.1.created_at));
-        }
+}
+
+/// Custody store and the caller's key, if custody is enabled and the caller (API key or
+/// signed-in account) is authorized
+fn custody_caller(api_state: &ApiState, headers: &axum::http::HeaderMap) -> ApiResult<(Arc<CustodyStore>, String)> {
+    let custody = api_state.custody.clone().ok_or(StatusCode::NOT_FOUND)?;
+    let caller = request_caller(api_state, headers).ok_or(StatusCode::UNAUTHORIZED)?;
+    let authorized = match &caller {
+        Caller::ApiKey(api_key) => custody.is_authorized(api_key),
+        Caller::Account(address) => custody.is_authorized_account(address),
+    };
+    if !authorized {
+        return Err(StatusCode::FORBIDDEN.into());
     }
-    
-    // Apply pagination
-    let paginated_results: Vec<serde_json::Value> = results
-        .into_iter()
-        .skip(offset)
-        .take(limit)
-        .map(|(asset_id, asset_state)| {
-            let blob_refs_json: std::collections::HashMap<String, String> = asset_state.blob_refs.iter()
-                .map(|(k, v)| (k.clone(), hex::encode(v)))
-                .collect();
-            
-            serde_json::json!({
-                "asset_id": hex::encode(asset_id),
-                "owner": hex::encode(asset_state.owner),
-                "density": format!("{:?}", asset_state.data.density),
-                "metadata": asset_state.data.metadata,
-                "attributes": asset_state.data.attributes,
-                "game_id": asset_state.data.game_id,
-                "created_at": asset_state.created_at,
-                "updated_at": asset_state.updated_at,
-                "blob_refs": blob_refs_json,
-                "history_count": asset_state.history.len(),
-            })
+    Ok((custody, caller.key()))
+}
+
+/// Sign-in challenge request
+#[derive(Debug, Deserialize)]
+pub struct SignInChallengeRequest {
+    pub address: String,
+}
+
+/// Signed challenge redeemed for a session
+#[derive(Debug, Deserialize)]
+pub struct SessionRequest {
+    /// Challenge text exactly as issued
+    pub message: String,
+    /// Hex Ed25519 signature of `message` by the account key
+    pub signature: String,
+}
+
+fn sign_in_service(api_state: &ApiState) -> ApiResult<Arc<SignInService>> {
+    Ok(api_state.sign_in.clone().ok_or(StatusCode::NOT_FOUND)?)
+}
+
+/// Issue a Sign-in-with-HAZE challenge for an address
+async fn create_sign_in_challenge(
+    State(api_state): State<ApiState>,
+    Json(request): Json<SignInChallengeRequest>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let sign_in = sign_in_service(&api_state)?;
+    let address = crate::types::hex_to_address(&request.address).ok_or(StatusCode::BAD_REQUEST)?;
+    let challenge = sign_in.challenge(address, chrono::Utc::now().timestamp());
+    Ok(Json(ApiResponse::success(serde_json::json!({
+        "message": challenge.to_text(),
+        "nonce": challenge.nonce,
+        "expires_at": challenge.expires_at,
+    }))))
+}
+
+/// Exchange a signed challenge for a session token
+async fn create_session(
+    State(api_state): State<ApiState>,
+    Json(request): Json<SessionRequest>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let sign_in = sign_in_service(&api_state)?;
+    let signature = hex::decode(&request.signature).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let (token, session) = sign_in.sign_in(&request.message, &signature, chrono::Utc::now().timestamp())?;
+    Ok(Json(ApiResponse::success(serde_json::json!({
+        "token": token,
+        "address": address_to_hex(&session.address),
+        "expires_at": session.expires_at,
+    }))))
+}
+
+/// Account and expiry of the bearer's session
+async fn get_session(
+    State(api_state): State<ApiState>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let sign_in = sign_in_service(&api_state)?;
+    let token = session_token_from_headers(&headers).ok_or(StatusCode::UNAUTHORIZED)?;
+    let session = sign_in.session(&token, chrono::Utc::now().timestamp()).ok_or(StatusCode::UNAUTHORIZED)?;
+    Ok(Json(ApiResponse::success(serde_json::json!({
+        "address": address_to_hex(&session.address),
+        "expires_at": session.expires_at,
+    }))))
+}
+
+/// End the bearer's session
+async fn delete_session(
+    State(api_state): State<ApiState>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<Json<ApiResponse<&'static str>>> {
+    let sign_in = sign_in_service(&api_state)?;
+    let token = session_token_from_headers(&headers).ok_or(StatusCode::UNAUTHORIZED)?;
+    if sign_in.sign_out(&token) {
+        Ok(Json(ApiResponse::success("signed out")))
+    } else {
+        Err(StatusCode::UNAUTHORIZED.into())
+    }
+}
+
+/// Sealed direct message with hex-encoded byte fields (see `haze::messaging::seal`)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SealedMessageDto {
+    pub sender: String,
+    pub recipient: String,
+    pub ephemeral_key: String,
+    pub nonce: String,
+    pub ciphertext: String,
+    pub sent_at: i64,
+    pub signature: String,
+}
+
+impl SealedMessageDto {
+    fn into_message(self) -> Option<SealedMessage> {
+        Some(SealedMessage {
+            sender: crate::types::hex_to_address(&self.sender)?,
+            recipient: crate::types::hex_to_address(&self.recipient)?,
+            ephemeral_key: hex::decode(&self.ephemeral_key).ok()?.try_into().ok()?,
+            nonce: hex::decode(&self.nonce).ok()?.try_into().ok()?,
+            ciphertext: hex::decode(&self.ciphertext).ok()?,
+            sent_at: self.sent_at,
+            signature: hex::decode(&self.signature).ok()?,
         })
-        .collect();
-    
-    Ok(Json(ApiResponse::success(paginated_results)))
+    }
 }
 
-/// Create liquidity pool request
+fn sealed_message_json(message: &SealedMessage) -> serde_json::Value {
+    serde_json::json!({
+        "id": hash_to_hex(&message.id()),
+        "sender": address_to_hex(&message.sender),
+        "recipient": address_to_hex(&message.recipient),
+        "ephemeral_key": hex::encode(message.ephemeral_key),
+        "nonce": hex::encode(message.nonce),
+        "ciphertext": hex::encode(&message.ciphertext),
+        "sent_at": message.sent_at,
+        "signature": hex::encode(&message.signature),
+    })
+}
+
+/// Mailbox request signed by the recipient (alternative to a Sign-in-with-HAZE session)
 #[derive(Debug, Deserialize)]
-pub struct CreatePoolRequest {
-    pub asset1: String,
-    pub asset2: String,
-    pub reserve1: u64,
-    pub reserve2: u64,
-    pub fee_rate: u64,
+pub struct MailboxAuthQuery {
+    /// Unix time the request was signed
+    pub timestamp: Option<i64>,
+    /// Hex Ed25519 signature of `haze::messaging::mailbox_auth_payload(address, timestamp)`
+    pub signature: Option<String>,
 }
 
-/// Get liquidity pools
-async fn get_liquidity_pools(
-    State(api_state): State<ApiState>,
-) -> ApiResult<Json<ApiResponse<Vec<serde_json::Value>>>> {
-    let economy = api_state.state.economy();
-    let pools: Vec<serde_json::Value> = economy.liquidity_pools()
-        .iter()
-        .map(|entry| {
-            let pool = entry.value();
-            serde_json::json!({
-                "pool_id": pool.pool_id,
-                "asset1": pool.asset1,
-                "asset2": pool.asset2,
-                "reserve1": pool.reserve1,
-                "reserve2": pool.reserve2,
-                "fee_rate": pool.fee_rate,
-                "total_liquidity": pool.total_liquidity,
-            })
-        })
-        .collect();
-    
-    Ok(Json(ApiResponse::success(pools)))
+/// Mailbox of `address`, if messaging is enabled and the request is signed by `address` or
+/// carries a session of it
+fn mailbox_owner(
+    api_state: &ApiState,
+    headers: &axum::http::HeaderMap,
+    address: &str,
+    query: &MailboxAuthQuery,
+) -> ApiResult<(Arc<Mailbox>, crate::types::Address)> {
+    let mailbox = api_state.mailbox.clone().ok_or(StatusCode::NOT_FOUND)?;
+    let address = crate::types::hex_to_address(address).ok_or(StatusCode::BAD_REQUEST)?;
+    let now = chrono::Utc::now().timestamp();
+    if let (Some(timestamp), Some(signature)) = (query.timestamp, &query.signature) {
+        let signature = hex::decode(signature).map_err(|_| StatusCode::BAD_REQUEST)?;
+        mailbox.authenticate(&address, timestamp, &signature, now)?;
+        return Ok((mailbox, address));
+    }
+    let session = session_token_from_headers(headers)
+        .and_then(|token| api_state.sign_in.as_ref()?.session(&token, now))
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    if session.address != address {
+        return Err(StatusCode::FORBIDDEN.into());
+    }
+    Ok((mailbox, address))
 }
 
-/// Create liquidity pool
-async fn create_liquidity_pool(
+/// Submit a sealed direct message for relaying and mailbox storage
+async fn send_direct_message(
     State(api_state): State<ApiState>,
-    Json(request): Json<CreatePoolRequest>,
+    Json(request): Json<SealedMessageDto>,
 ) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
-    let economy = api_state.state.economy();
-    
-    match economy.create_liquidity_pool(
-        request.asset1,
-        request.asset2,
-        request.reserve1,
-        request.reserve2,
-        request.fee_rate,
-    ) {
-        Ok(pool_id) => {
-            let response = serde_json::json!({
-                "pool_id": pool_id,
-                "status": "created",
-            });
-            Ok(Json(ApiResponse::success(response)))
-        }
-        Err(_) => Err(StatusCode::BAD_REQUEST),
-    }
+    let mailbox = api_state.mailbox.clone().ok_or(StatusCode::NOT_FOUND)?;
+    let message = request.into_message().ok_or(StatusCode::BAD_REQUEST)?;
+    let id = mailbox.send(message, chrono::Utc::now().timestamp())?;
+    Ok(Json(ApiResponse::success(serde_json::json!({ "id": hash_to_hex(&id) }))))
 }
 
-/// Get liquidity pool by ID
-async fn get_liquidity_pool(
+/// Messages waiting in an account's mailbox, oldest first
+async fn list_direct_messages(
     State(api_state): State<ApiState>,
-    Path(pool_id): Path<String>,
-) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
-    let economy = api_state.state.economy();
-    
-    if let Some(pool) = economy.get_liquidity_pool(&pool_id) {
-        let pool_json = serde_json::json!({
-            "pool_id": pool.pool_id,
-            "asset1": pool.asset1,
-            "asset2": pool.asset2,
-            "reserve1": pool.reserve1,
-            "reserve2": pool.reserve2,
-            "fee_rate": pool.fee_rate,
-            "total_liquidity": pool.total_liquidity,
-        });
-        Ok(Json(ApiResponse::success(pool_json)))
-    } else {
-        Err(StatusCode::NOT_FOUND)
-    }
+    Path(address): Path<String>,
+    headers: axum::http::HeaderMap,
+    axum::extract::Query(query): axum::extract::Query<MailboxAuthQuery>,
+) -> ApiResult<Json<ApiResponse<Vec<serde_json::Value>>>> {
+    let (mailbox, address) = mailbox_owner(&api_state, &headers, &address, &query)?;
+    let messages = mailbox.messages(&address, chrono::Utc::now().timestamp())?;
+    Ok(Json(ApiResponse::success(messages.iter().map(sealed_message_json).collect())))
 }
 
-/// WebSocket handler
-async fn ws_handler(
-    ws: WebSocketUpgrade,
+/// Delete a read message from an account's mailbox
+async fn delete_direct_message(
     State(api_state): State<ApiState>,
-) -> axum::response::Response {
-    ws.on_upgrade(|socket| handle_socket(socket, api_state))
+    Path((address, id)): Path<(String, String)>,
+    headers: axum::http::HeaderMap,
+    axum::extract::Query(query): axum::extract::Query<MailboxAuthQuery>,
+) -> ApiResult<Json<ApiResponse<&'static str>>> {
+    let (mailbox, address) = mailbox_owner(&api_state, &headers, &address, &query)?;
+    let id = crate::types::hex_to_hash(&id).ok_or(StatusCode::BAD_REQUEST)?;
+    if mailbox.delete(&address, &id)? {
+        Ok(Json(ApiResponse::success("deleted")))
+    } else {
+        Err(StatusCode::NOT_FOUND.into())
+    }
 }
 
-/// Handle WebSocket connection
-async fn handle_socket(socket: axum::extract::ws::WebSocket, state: ApiState) {
-    use futures_util::{SinkExt, StreamExt};
-    let (mut sender, mut receiver) = socket.split();
-    let mut rx = state.ws_tx.subscribe();
-    let subscriptions = Arc::new(tokio::sync::Mutex::new(Vec::<WsSubscription>::new()));
+fn custodial_account_json(account: &CustodialAccount) -> serde_json::Value {
+    serde_json::json!({
+        "player_id": account.player_id,
+        "address": address_to_hex(&account.address),
+        "exported_at": account.exported_at,
+    })
+}
 
-    // Clone Arc for send task
-    let subscriptions_send = Arc::clone(&subscriptions);
-    // Spawn task to send events to client
-    let mut send_task = tokio::spawn(async move {
-        while let Ok(event) = rx.recv().await {
-            // Check if event matches any subscription
-            let subs = subscriptions_send.lock().await;
-            let should_send = subs.is_empty() || subs.iter().any(|sub| {
-                match (&sub.sub_type[..], &event) {
-                    ("asset_created", WsEvent::AssetCreated { asset_id, owner, .. }) => {
-                        sub.asset_id.as_ref().map(|id| id == asset_id).unwrap_or(true) &&
-                        sub.owner.as_ref().map(|o| o == owner).unwrap_or(true)
-                    }
-                    ("asset_updated", WsEvent::AssetUpdated { asset_id, owner, .. }) => {
-                        sub.asset_id.as_ref().map(|id| id == asset_id).unwrap_or(true) &&
-                        sub.owner.as_ref().map(|o| o == owner).unwrap_or(true)
-                    }
-                    ("asset_condensed", WsEvent::AssetCondensed { asset_id, .. }) => {
-                        sub.asset_id.as_ref().map(|id| id == asset_id).unwrap_or(true)
-                    }
-                    ("asset_evaporated", WsEvent::AssetEvaporated { asset_id, .. }) => {
-                        sub.asset_id.as_ref().map(|id| id == asset_id).unwrap_or(true)
-                    }
-                    ("asset_merged", WsEvent::AssetMerged { asset_id, .. }) => {
-                        sub.asset_id.as_ref().map(|id| id == asset_id).unwrap_or(true)
-                    }
-                    ("asset_split", WsEvent::AssetSplit { asset_id, .. }) => {
-                        sub.asset_id.as_ref().map(|id| id == asset_id).unwrap_or(true)
-                    }
-                    ("asset_permission_changed", WsEvent::AssetPermissionChanged { asset_id, owner, .. }) => {
-                        sub.asset_id.as_ref().map(|id| id == asset_id).unwrap_or(true) &&
-                        sub.owner.as_ref().map(|o| o == owner).unwrap_or(true)
-                    }
-                    ("asset_attribute_updated", WsEvent::AssetAttributeUpdated { asset_id, owner, .. }) => {
-                        sub.asset_id.as_ref().map(|id| id == asset_id).unwrap_or(true) &&
-                        sub.owner.as_ref().map(|o| o == owner).unwrap_or(true)
-                    }
-                    ("asset_version_created", WsEvent::AssetVersionCreated { asset_id, owner, .. }) => {
-                        sub.asset_id.as_ref().map(|id| id == asset_id).unwrap_or(true) &&
-                        sub.owner.as_ref().map(|o| o == owner).unwrap_or(true)
-                    }
-                    _ => false,
-                }
-            });
-            drop(subs); // Release lock before potential await
+/// Custodial account of a player (address is derived, nothing is created on chain)
+async fn get_custodial_account(
+    State(api_state): State<ApiState>,
+    Path(player_id): Path<String>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let (custody, api_key) = custody_caller(&api_state, &headers)?;
+    let account = custody.account(&api_key, &player_id)?;
+    Ok(Json(ApiResponse::success(custodial_account_json(&account))))
+}
 
-            if should_send {
-                if let Ok(json) = serde_json::to_string(&event) {
-                    if sender.send(Message::Text(json)).await.is_err() {
-                        break;
-                    }
-                }
-            }
-        }
-    });
+/// Sign a transaction with the player's custodial key and submit it
+///
+/// Same body as `POST /api/v1/transactions`; `from` defaults to the player's address and
+/// `signature` may be omitted.
+async fn send_custodial_transaction(
+    State(api_state): State<ApiState>,
+    Path(player_id): Path<String>,
+    headers: axum::http::HeaderMap,
+    Json(mut request): Json<serde_json::Value>,
+) -> ApiResult<Json<ApiResponse<TransactionResponse>>> {
+    let (custody, api_key) = custody_caller(&api_state, &headers)?;
+    let account = custody.account(&api_key, &player_id)?;
+    let inner = request
+        .get_mut("transaction")
+        .and_then(|tx| tx.as_object_mut())
+        .and_then(|tx| tx.values_mut().next())
+        .and_then(|inner| inner.as_object_mut())
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    inner.entry("from").or_insert_with(|| address_to_hex(&account.address).into());
+    inner.entry("signature").or_insert_with(|| "".into());
+    let mut tx = parse_transaction_from_value(&request["transaction"]).map_err(|e| {
+        tracing::debug!("Invalid custodial transaction: {}", e);
+        StatusCode::BAD_REQUEST
+    })?;
+    custody.sign_transaction(&api_key, &player_id, &api_state.consensus, &mut tx)?;
+    authorize_submission(&api_state, &headers, &tx)?;
 
-    // Clone Arc for receive task
-    let subscriptions_recv = Arc::clone(&subscriptions);
-    // Spawn task to receive messages from client
-    let mut recv_task = tokio::spawn(async move {
-        while let Some(Ok(msg)) = receiver.next().await {
-            if let Message::Text(text) = msg {
-                if let Ok(request) = serde_json::from_str::<WsSubscribeRequest>(&text) {
-                    let mut subs = subscriptions_recv.lock().await;
-                    *subs = request.subscribe;
-                }
-            }
-        }
-    });
+    let tx_hash = tx.hash();
+    api_state.consensus.add_transaction(tx)?;
+    Ok(Json(ApiResponse::success(TransactionResponse {
+        hash: hash_to_hex(&tx_hash),
+        status: "pending".to_string(),
+    })))
+}
 
-    // Wait for either task to complete
-    tokio::select! {
-        _ = (&mut send_task) => recv_task.abort(),
-        _ = (&mut recv_task) => send_task.abort(),
-    };
+/// Export the player's secret key for migration to their own wallet
+///
+/// The node stops signing for the player afterwards.
+async fn export_custodial_key(
+    State(api_state): State<ApiState>,
+    Path(player_id): Path<String>,
+    headers: axum::http::HeaderMap,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let (custody, api_key) = custody_caller(&api_state, &headers)?;
+    let (account, secret_key) = custody.export(&api_key, &player_id)?;
+    let mut body = custodial_account_json(&account);
+    body["secret_key"] = hex::encode(secret_key.as_ref()).into();
+    tracing::info!("Exported custodial key for {}", address_to_hex(&account.address));
+    Ok(Json(ApiResponse::success(body)))
 }
 
 /// Broadcast asset event to all WebSocket clients
@@ -1604,6 +5179,7 @@ pub struct BasicMetrics {
     pub tx_pool_size: usize,
     pub connected_peers: usize,
     pub block_time_avg_ms: Option<u64>, // Average block time in ms (if available)
+    pub total_accounts: usize,
 }
 
 /// Start sync with peers
@@ -1636,6 +5212,309 @@ async fn get_sync_status(
     Ok(Json(ApiResponse::success(status)))
 }
 
+/// Default and maximum number of DAG vertices per page
+const DAG_PAGE_DEFAULT: usize = 500;
+const DAG_PAGE_MAX: usize = 2000;
+
+/// DAG query parameters
+#[derive(Debug, Deserialize)]
+pub struct DagQuery {
+    pub from_wave: Option<u64>,
+    pub to_wave: Option<u64>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+/// DAG vertices and edges for rendering, paginated in (wave, height, hash) order
+///
+/// Edges are `[from, to]` pairs of the returned vertices' references; targets may lie
+/// outside the page (or be the zero genesis hash).
+async fn get_dag(
+    State(api_state): State<ApiState>,
+    axum::extract::Query(query): axum::extract::Query<DagQuery>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let from_wave = query.from_wave.unwrap_or(0);
+    let to_wave = query.to_wave.unwrap_or(u64::MAX);
+    if from_wave > to_wave {
+        return Err(StatusCode::BAD_REQUEST.into());
+    }
+    let limit = query.limit.unwrap_or(DAG_PAGE_DEFAULT).clamp(1, DAG_PAGE_MAX);
+    let offset = query.offset.unwrap_or(0);
+
+    let vertices = api_state.consensus.dag_vertices(from_wave, to_wave);
+    let total = vertices.len();
+    let page = &vertices[offset.min(total)..(offset.saturating_add(limit)).min(total)];
+    let next_offset = (offset.saturating_add(limit) < total).then(|| offset + limit);
+
+    let edges: Vec<[String; 2]> = page
+        .iter()
+        .flat_map(|vertex| {
+            vertex
+                .references
+                .iter()
+                .map(|target| [hash_to_hex(&vertex.hash), hash_to_hex(target)])
+        })
+        .collect();
+    let vertices: Vec<serde_json::Value> = page
+        .iter()
+        .map(|vertex| {
+            serde_json::json!({
+                "hash": hash_to_hex(&vertex.hash),
+                "height": vertex.height,
+                "wave": vertex.wave,
+                "validator": address_to_hex(&vertex.validator),
+                "timestamp": vertex.timestamp,
+                "finalized": vertex.finalized,
+            })
+        })
+        .collect();
+
+    Ok(Json(ApiResponse::success(serde_json::json!({
+        "vertices": vertices,
+        "edges": edges,
+        "total": total,
+        "offset": offset,
+        "limit": limit,
+        "next_offset": next_offset,
+    }))))
+}
+
+/// Default and maximum number of waves per page
+const WAVE_PAGE_DEFAULT: usize = 100;
+const WAVE_PAGE_MAX: usize = 1000;
+
+/// Wave history query parameters
+#[derive(Debug, Deserialize)]
+pub struct WavesQuery {
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+    pub limit: Option<usize>,
+}
+
+/// Block counts, finalization times and certificates of waves, oldest first. Waves pruned
+/// from consensus memory are served from their archived summaries.
+async fn get_waves(
+    State(api_state): State<ApiState>,
+    axum::extract::Query(query): axum::extract::Query<WavesQuery>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let from = query.from.unwrap_or(0);
+    let to = query.to.unwrap_or(u64::MAX);
+    if from > to {
+        return Err(StatusCode::BAD_REQUEST.into());
+    }
+    let limit = query.limit.unwrap_or(WAVE_PAGE_DEFAULT).clamp(1, WAVE_PAGE_MAX);
+    let summaries = api_state.consensus.wave_history(from, to, limit);
+    let next_from = (summaries.len() == limit)
+        .then(|| summaries.last().map(|summary| summary.wave + 1))
+        .flatten()
+        .filter(|next| *next <= to);
+    let waves: Vec<serde_json::Value> = summaries
+        .iter()
+        .map(|summary| {
+            serde_json::json!({
+                "wave": summary.wave,
+                "block_count": summary.block_count,
+                "finalized": summary.finalized,
+                "opened_at_ms": summary.opened_at_ms,
+                "finalized_at_ms": summary.finalized_at_ms,
+                "finalization_ms": summary.finalization_ms(),
+                "certificate": summary.certificate.as_ref().map(|certificate| serde_json::json!({
+                    "checkpoint_height": certificate.checkpoint_height,
+                    "committee_id": certificate.committee_id,
+                    "proposers": certificate.proposers.iter().map(address_to_hex).collect::<Vec<_>>(),
+                })),
+            })
+        })
+        .collect();
+
+    Ok(Json(ApiResponse::success(serde_json::json!({
+        "waves": waves,
+        "next_from": next_from,
+        "last_finalized_wave": api_state.consensus.get_last_finalized_wave(),
+        "live_waves": api_state.consensus.live_wave_count(),
+    }))))
+}
+
+/// The current committee with its members' weights, the committee parameters in force and
+/// the open governance votes to change them
+async fn get_committee(
+    State(api_state): State<ApiState>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let committee = api_state.consensus.current_committee().ok_or(StatusCode::NOT_FOUND)?;
+    let tokenomics = api_state.state.tokenomics();
+    let params = tokenomics.committee_params();
+    let members: Vec<serde_json::Value> = committee
+        .members
+        .iter()
+        .map(|(address, weight_bps)| {
+            serde_json::json!({
+                "address": address_to_hex(address),
+                "stake": tokenomics.get_validator(address).map_or(0, |v| v.total_staked),
+                "weight_bps": weight_bps,
+            })
+        })
+        .collect();
+    let mut votes = tokenomics.committee_votes();
+    votes.sort_by_key(|(voter, _)| *voter);
+    let votes: Vec<serde_json::Value> = votes
+        .iter()
+        .map(|(voter, vote)| {
+            serde_json::json!({
+                "voter": address_to_hex(voter),
+                "min_stake": vote.min_stake,
+                "max_weight_bps": vote.max_weight_bps,
+                "weight": api_state.state.governance_weight(voter),
+            })
+        })
+        .collect();
+
+    Ok(Json(ApiResponse::success(serde_json::json!({
+        "id": committee.id,
+        "expires_at": committee.expires_at,
+        "members": members,
+        "min_stake": params.min_stake,
+        "max_weight_bps": params.max_weight_bps,
+        "votes": votes,
+        "total_governance_weight": api_state.state.total_governance_weight(),
+    }))))
+}
+
+/// Shard stats query parameters
+#[derive(Debug, Deserialize)]
+pub struct ShardStatsQuery {
+    /// Also report the shard holding this collection
+    pub game_id: Option<String>,
+}
+
+/// Asset shards with their sizes, activity and roots (the asset part of the state root is
+/// the root of these roots)
+async fn get_shard_stats(
+    State(api_state): State<ApiState>,
+    axum::extract::Query(query): axum::extract::Query<ShardStatsQuery>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let roots = api_state.state.asset_shard_roots();
+    let shards: Vec<serde_json::Value> = api_state.state.asset_shard_stats()
+        .into_iter()
+        .zip(&roots)
+        .map(|(stats, root)| serde_json::json!({
+            "shard": stats.shard,
+            "assets": stats.assets,
+            "collections": stats.collections,
+            "reads": stats.reads,
+            "writes": stats.writes,
+            "root": hash_to_hex(root),
+        }))
+        .collect();
+    let asset_root = crate::asset_shards::root_of(&roots);
+    let mut response = serde_json::json!({
+        "shard_count": crate::asset_shards::ASSET_SHARD_COUNT,
+        "asset_root": hash_to_hex(&asset_root),
+        "shards": shards,
+    });
+    if let Some(game_id) = query.game_id {
+        response["game_id_shard"] = crate::asset_shards::shard_for(Some(&game_id)).into();
+    }
+    Ok(Json(ApiResponse::success(response)))
+}
+
+/// Database size per tree, flush timing and blob deduplication, from the last maintenance run
+async fn get_storage_stats(
+    State(api_state): State<ApiState>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let stats = api_state.state.storage_stats();
+    let trees: Vec<serde_json::Value> = stats
+        .trees
+        .iter()
+        .map(|tree| serde_json::json!({
+            "name": tree.name,
+            "entries": tree.entries,
+            "bytes": tree.bytes,
+        }))
+        .collect();
+    Ok(Json(ApiResponse::success(serde_json::json!({
+        "size_on_disk": stats.size_on_disk,
+        "trees": trees,
+        "last_flush_seconds": stats.last_flush_seconds,
+        "flushes": stats.flushes,
+        "last_clean_height": api_state.state.last_clean_height(),
+        "journal_recoveries": api_state.state.journal_recoveries(),
+        "blobs": api_state.state.blob_stats(),
+        "last_run": stats.last_run,
+    }))))
+}
+
+/// Fee stats query parameters
+#[derive(Debug, Deserialize)]
+pub struct FeeStatsQuery {
+    pub from_wave: Option<u64>,
+    pub limit: Option<usize>,
+}
+
+/// Priority fee estimate: fee percentiles of recent blocks and of the pool, with suggested
+/// fees (see `fee_estimate`)
+async fn estimate_priority_fee(
+    State(api_state): State<ApiState>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let (recent, blocks) = api_state.state.recent_fees();
+    let pool = api_state.consensus.pool_fees();
+    let capacity = api_state.config.consensus.max_transactions_per_block;
+    Ok(Json(ApiResponse::success(serde_json::json!({
+        "height": api_state.state.current_height(),
+        "blocks": blocks,
+        "recent": recent.summary(),
+        "pool": pool.summary(),
+        "block_capacity": capacity,
+        // More waiting than the next block can take
+        "congested": pool.len() > capacity as u64,
+        "suggested": crate::fee_estimate::suggest(&recent, &pool),
+    }))))
+}
+
+/// Get per-wave fee collection/burn statistics (deflation dashboard)
+async fn get_fee_stats(
+    State(api_state): State<ApiState>,
+    axum::extract::Query(query): axum::extract::Query<FeeStatsQuery>,
+) -> ApiResult<Json<ApiResponse<serde_json::Value>>> {
+    let tokenomics = api_state.state.tokenomics();
+    let from_wave = query.from_wave.unwrap_or(0);
+    let limit = query.limit.unwrap_or(1000);
+    
+    let waves: Vec<serde_json::Value> = tokenomics.get_wave_fee_stats(from_wave, limit)
+        .iter()
+        .map(|stats| {
+            serde_json::json!({
+                "wave": stats.wave,
+                "blocks": stats.blocks,
+                "transactions": stats.transactions,
+                "fees_collected": stats.fees_collected,
+                "fees_burned": stats.fees_burned,
+                "fees_redistributed": stats.fees_redistributed,
+                "minted": stats.minted,
+                "net_issuance": stats.net_issuance(),
+            })
+        })
+        .collect();
+    
+    let totals = tokenomics.fee_totals();
+    let summary = serde_json::json!({
+        "blocks": totals.blocks,
+        "transactions": totals.transactions,
+        "fees_collected": totals.fees_collected,
+        "fees_burned": totals.fees_burned,
+        "fees_redistributed": totals.fees_redistributed,
+        "minted": totals.minted,
+        "net_issuance": totals.net_issuance(),
+        "deflationary": totals.net_issuance() < 0,
+        "burned_supply": tokenomics.burned_supply(),
+        "circulating_supply": tokenomics.circulating_supply(),
+    });
+    
+    Ok(Json(ApiResponse::success(serde_json::json!({
+        "summary": summary,
+        "waves": waves,
+    }))))
+}
+
 /// Get basic metrics for observability
 async fn get_basic_metrics(
     State(api_state): State<ApiState>,
@@ -1651,8 +5530,8 @@ async fn get_basic_metrics(
         let mut timestamps = Vec::new();
         let start_height = current_height.saturating_sub(10);
         for h in start_height..=current_height {
-            if let Some(block) = api_state.state.get_block_by_height(h) {
-                timestamps.push(block.header.timestamp);
+            if let Some(header) = api_state.state.get_block_header_by_height(h) {
+                timestamps.push(header.timestamp);
             }
         }
         if timestamps.len() >= 2 {
@@ -1677,6 +5556,7 @@ async fn get_basic_metrics(
         tx_pool_size,
         connected_peers,
         block_time_avg_ms,
+        total_accounts: api_state.state.account_count(),
     };
     
     Ok(Json(ApiResponse::success(metrics)))
@@ -1699,8 +5579,8 @@ async fn get_prometheus_metrics(
         let mut timestamps = Vec::new();
         let start_height = current_height.saturating_sub(10);
         for h in start_height..=current_height {
-            if let Some(block) = api_state.state.get_block_by_height(h) {
-                timestamps.push(block.header.timestamp);
+            if let Some(header) = api_state.state.get_block_header_by_height(h) {
+                timestamps.push(header.timestamp);
             }
         }
         if timestamps.len() >= 2 {
@@ -1718,6 +5598,8 @@ async fn get_prometheus_metrics(
         None
     };
 
+    let clock = api_state.consensus.clock_status();
+
     let mut out = String::new();
     out.push_str("# HELP haze_blockchain_height Current blockchain height\n");
     out.push_str("# TYPE haze_blockchain_height gauge\n");
@@ -1734,12 +5616,127 @@ async fn get_prometheus_metrics(
     out.push_str("# HELP haze_connected_peers Number of connected P2P peers\n");
     out.push_str("# TYPE haze_connected_peers gauge\n");
     out.push_str(&format!("haze_connected_peers {}\n", connected_peers));
+    let peer_health = api_state.consensus.peer_health();
+    let peer_status = peer_health.status();
+    let now_ms = crate::time_sync::now_ms();
+    out.push_str("# HELP haze_peer_rtt_ms Latest keep-alive round trip to each peer in milliseconds\n");
+    out.push_str("# TYPE haze_peer_rtt_ms gauge\n");
+    let peers = peer_health.peers();
+    for peer in peers.iter().filter(|peer| peer.rtt_ms.is_some()) {
+        out.push_str(&format!("haze_peer_rtt_ms{{peer=\"{}\"}} {}\n", peer.peer_id, peer.rtt_ms.unwrap_or(0)));
+    }
+    out.push_str("# HELP haze_peer_last_seen_seconds Seconds since anything was received from each peer\n");
+    out.push_str("# TYPE haze_peer_last_seen_seconds gauge\n");
+    for peer in &peers {
+        let seconds = (now_ms - peer.last_seen_ms).max(0) as f64 / 1000.0;
+        out.push_str(&format!("haze_peer_last_seen_seconds{{peer=\"{}\"}} {}\n", peer.peer_id, seconds));
+    }
+    out.push_str("# HELP haze_dead_peers_disconnected_total Peers disconnected for not answering keep-alive pings\n");
+    out.push_str("# TYPE haze_dead_peers_disconnected_total counter\n");
+    out.push_str(&format!("haze_dead_peers_disconnected_total {}\n", peer_status.dead_peers_disconnected));
+    out.push_str("# HELP haze_peer_reconnect_attempts_total Redials of dropped peers\n");
+    out.push_str("# TYPE haze_peer_reconnect_attempts_total counter\n");
+    out.push_str(&format!("haze_peer_reconnect_attempts_total {}\n", peer_status.reconnect_attempts));
+    out.push_str("# HELP haze_peer_pending_reconnects Dialed peer addresses waiting to be redialed\n");
+    out.push_str("# TYPE haze_peer_pending_reconnects gauge\n");
+    out.push_str(&format!("haze_peer_pending_reconnects {}\n", peer_status.pending_reconnects.len()));
+    out.push_str("# HELP haze_accounts_total Number of accounts\n");
+    out.push_str("# TYPE haze_accounts_total gauge\n");
+    out.push_str(&format!("haze_accounts_total {}\n", api_state.state.account_count()));
     out.push_str("# HELP haze_block_time_seconds Average block time in seconds (last 10 blocks)\n");
     out.push_str("# TYPE haze_block_time_seconds gauge\n");
     match block_time_avg_sec {
         Some(sec) => out.push_str(&format!("haze_block_time_seconds {}\n", sec)),
         None => out.push_str("haze_block_time_seconds 0\n"),
     }
+    out.push_str("# HELP haze_clock_offset_ms Median peer clock minus local clock in milliseconds\n");
+    out.push_str("# TYPE haze_clock_offset_ms gauge\n");
+    out.push_str(&format!("haze_clock_offset_ms {}\n", clock.median_offset_ms.unwrap_or(0)));
+    out.push_str("# HELP haze_clock_drifting 1 if the local clock drifts more than consensus.max_clock_drift_ms from peers\n");
+    out.push_str("# TYPE haze_clock_drifting gauge\n");
+    out.push_str(&format!("haze_clock_drifting {}\n", u8::from(clock.drifting)));
+    if api_state.config.replica.enabled {
+        let replica = api_state.consensus.replica_status();
+        out.push_str("# HELP haze_replica_lag_blocks Blocks this read replica trails the primary (-1 while unknown)\n");
+        out.push_str("# TYPE haze_replica_lag_blocks gauge\n");
+        let lag = replica.lag_blocks.map_or(-1, |lag| lag as i64);
+        out.push_str(&format!("haze_replica_lag_blocks {}\n", lag));
+    }
+    out.push_str("# HELP haze_equivocations_total Conflicting blocks rejected from validators that proposed twice at one height\n");
+    out.push_str("# TYPE haze_equivocations_total counter\n");
+    out.push_str(&format!("haze_equivocations_total {}\n", api_state.consensus.get_equivocation_evidence().len()));
+    out.push_str("# HELP haze_state_divergences_total Processed blocks whose state root differed from the one computed locally\n");
+    out.push_str("# TYPE haze_state_divergences_total counter\n");
+    out.push_str(&format!("haze_state_divergences_total {}\n", api_state.consensus.get_divergence_incidents().len()));
+    out.push_str("# HELP haze_divergent_waves Waves held back from finalization by a state divergence\n");
+    out.push_str("# TYPE haze_divergent_waves gauge\n");
+    out.push_str(&format!("haze_divergent_waves {}\n", api_state.consensus.divergent_waves().len()));
+    out.push_str("# HELP haze_api_cache_entries Responses held in the API response cache\n");
+    out.push_str("# TYPE haze_api_cache_entries gauge\n");
+    out.push_str(&format!("haze_api_cache_entries {}\n", api_state.response_cache.entry_count()));
+    let shards = api_state.state.asset_shard_stats();
+    out.push_str("# HELP haze_asset_shard_assets Assets held by each asset shard\n");
+    out.push_str("# TYPE haze_asset_shard_assets gauge\n");
+    for stats in &shards {
+        out.push_str(&format!("haze_asset_shard_assets{{shard=\"{}\"}} {}\n", stats.shard, stats.assets));
+    }
+    out.push_str("# HELP haze_asset_shard_collections Collections (game_ids) held by each asset shard\n");
+    out.push_str("# TYPE haze_asset_shard_collections gauge\n");
+    for stats in &shards {
+        out.push_str(&format!("haze_asset_shard_collections{{shard=\"{}\"}} {}\n", stats.shard, stats.collections));
+    }
+    out.push_str("# HELP haze_asset_shard_reads_total Asset lookups served by each asset shard\n");
+    out.push_str("# TYPE haze_asset_shard_reads_total counter\n");
+    for stats in &shards {
+        out.push_str(&format!("haze_asset_shard_reads_total{{shard=\"{}\"}} {}\n", stats.shard, stats.reads));
+    }
+    out.push_str("# HELP haze_asset_shard_writes_total Asset writes applied to each asset shard\n");
+    out.push_str("# TYPE haze_asset_shard_writes_total counter\n");
+    for stats in &shards {
+        out.push_str(&format!("haze_asset_shard_writes_total{{shard=\"{}\"}} {}\n", stats.shard, stats.writes));
+    }
+    // Sampled by the database maintenance task (empty until its first run)
+    let storage = api_state.state.storage_stats();
+    out.push_str("# HELP haze_db_size_bytes Size of the database files on disk\n");
+    out.push_str("# TYPE haze_db_size_bytes gauge\n");
+    out.push_str(&format!("haze_db_size_bytes {}\n", storage.size_on_disk));
+    out.push_str("# HELP haze_db_tree_bytes Key and value bytes stored in each database tree\n");
+    out.push_str("# TYPE haze_db_tree_bytes gauge\n");
+    for tree in &storage.trees {
+        out.push_str(&format!("haze_db_tree_bytes{{tree=\"{}\"}} {}\n", tree.name, tree.bytes));
+    }
+    out.push_str("# HELP haze_db_tree_entries Entries in each database tree\n");
+    out.push_str("# TYPE haze_db_tree_entries gauge\n");
+    for tree in &storage.trees {
+        out.push_str(&format!("haze_db_tree_entries{{tree=\"{}\"}} {}\n", tree.name, tree.entries));
+    }
+    out.push_str("# HELP haze_db_flush_seconds Duration of the last scheduled database flush\n");
+    out.push_str("# TYPE haze_db_flush_seconds gauge\n");
+    out.push_str(&format!("haze_db_flush_seconds {}\n", storage.last_flush_seconds));
+    out.push_str("# HELP haze_db_flushes_total Scheduled database flushes\n");
+    out.push_str("# TYPE haze_db_flushes_total counter\n");
+    out.push_str(&format!("haze_db_flushes_total {}\n", storage.flushes));
+    out.push_str("# HELP haze_db_last_clean_height Height of the last block whose database writes all completed\n");
+    out.push_str("# TYPE haze_db_last_clean_height gauge\n");
+    out.push_str(&format!("haze_db_last_clean_height {}\n", api_state.state.last_clean_height()));
+    let blobs = api_state.state.blob_stats();
+    out.push_str("# HELP haze_blob_logical_bytes Sum of the sizes of the stored blobs\n");
+    out.push_str("# TYPE haze_blob_logical_bytes gauge\n");
+    out.push_str(&format!("haze_blob_logical_bytes {}\n", blobs.logical_bytes));
+    out.push_str("# HELP haze_blob_unique_bytes Bytes of the distinct blob chunks stored\n");
+    out.push_str("# TYPE haze_blob_unique_bytes gauge\n");
+    out.push_str(&format!("haze_blob_unique_bytes {}\n", blobs.unique_bytes));
+    out.push_str("# HELP haze_blob_dedup_ratio Logical blob bytes per stored byte\n");
+    out.push_str("# TYPE haze_blob_dedup_ratio gauge\n");
+    out.push_str(&format!("haze_blob_dedup_ratio {}\n", blobs.dedup_ratio));
+    let webhooks = &api_state.watchlists.webhook_stats;
+    out.push_str("# HELP haze_webhook_deliveries_total Watchlist webhook POSTs answered with a success status\n");
+    out.push_str("# TYPE haze_webhook_deliveries_total counter\n");
+    out.push_str(&format!("haze_webhook_deliveries_total {}\n", webhooks.delivered.load(Ordering::Relaxed)));
+    out.push_str("# HELP haze_webhook_failures_total Watchlist webhook deliveries that failed or were dropped\n");
+    out.push_str("# TYPE haze_webhook_failures_total counter\n");
+    out.push_str(&format!("haze_webhook_failures_total {}\n", webhooks.failed.load(Ordering::Relaxed)));
+    api_state.state.latency().render(&mut out);
 
     Ok((
         [
@@ -1752,6 +5749,11 @@ async fn get_prometheus_metrics(
 /// Start API server
 pub async fn start_api_server(state: ApiState) -> std::result::Result<(), Box<dyn std::error::Error>> {
     let app = create_router(state.clone());
+    tokio::spawn(crate::watchlist::run_webhook_dispatcher(
+        state.watchlists.clone(),
+        state.state.clone(),
+        state.ws_tx.subscribe(),
+    ));
     
     let listener = tokio::net::TcpListener::bind(&state.config.api.listen_addr).await?;
     tracing::info!("API server listening on http://{}", state.config.api.listen_addr);
@@ -1782,10 +5784,19 @@ mod tests {
         let (ws_tx, _) = tokio::sync::broadcast::channel(100);
         ApiState {
             consensus,
+            watchlists: Arc::new(WatchlistStore::open(&state).unwrap()),
+            custody: None,
+            sign_in: None,
+            mailbox: None,
+            tenants: None,
+            metering: None,
+            telemetry: None,
+            faucet: None,
             state,
             config,
             ws_tx,
             connected_peers: Arc::new(std::sync::atomic::AtomicUsize::new(0)),
+            response_cache: Arc::new(ResponseCache::default()),
         }
     }
     
@@ -0,0 +1,292 @@
+//! Asset state sharded by collection (game_id)
+//!
+//! Assets live in `ASSET_SHARD_COUNT` shards, each with its own maps (and so its own locks)
+//! for the assets and the collection index of the game_ids it holds. An asset's shard follows
+//! from its game_id, so one collection never spans shards and collection reads touch a
+//! single shard. A directory maps asset ids to shards for lookups by id.
+//!
+//! The asset part of the state root is the root of the shard roots, which lets a future
+//! cross-node setup verify one shard without the others. Each shard keeps a `RootTree` (sparse
+//! merkle trie) of its asset hashes, updated on every write, so a shard root costs only the
+//! paths changed since the last one. The shard count is part of the protocol: changing it
+//! changes every state root.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use dashmap::DashMap;
+use dashmap::mapref::multiple::RefMulti;
+use dashmap::mapref::one::Ref;
+use crate::state::AssetState;
+use crate::state::trie::{RootTree, TrieProof};
+use crate::types::{sha256, Hash};
+
+/// Number of asset shards
+pub const ASSET_SHARD_COUNT: usize = 16;
+
+/// Shard holding the assets of collection `game_id` (assets without one share a shard)
+pub fn shard_for(game_id: Option<&str>) -> usize {
+    let digest = sha256(game_id.unwrap_or_default().as_bytes());
+    let mut prefix = [0u8; 8];
+    prefix.copy_from_slice(&digest[..8]);
+    (u64::from_le_bytes(prefix) % ASSET_SHARD_COUNT as u64) as usize
+}
+
+/// Root over shard roots given in shard order
+pub fn root_of(shard_roots: &[Hash]) -> Hash {
+    sha256(&bincode::serialize(shard_roots).unwrap_or_default())
+}
+
+#[derive(Debug, Default)]
+struct AssetShard {
+    assets: DashMap<Hash, AssetState>,
+    /// Asset ids by game_id, for the collections in this shard
+    collections: DashMap<String, Vec<Hash>>,
+    /// Hashes of the shard's assets
+    hashes: RootTree,
+    reads: AtomicU64,
+    writes: AtomicU64,
+}
+
+impl AssetShard {
+    fn root(&self) -> Hash {
+        self.hashes.root()
+    }
+}
+
+/// Size and activity of one shard (for metrics)
+#[derive(Debug, Clone)]
+pub struct ShardStats {
+    pub shard: usize,
+    pub assets: usize,
+    pub collections: usize,
+    /// Asset lookups since node start
+    pub reads: u64,
+    /// Asset inserts and removals since node start
+    pub writes: u64,
+}
+
+/// Asset states and collection index split into shards by game_id
+#[derive(Debug)]
+pub struct ShardedAssets {
+    shards: Vec<AssetShard>,
+    /// Shard of each stored asset
+    directory: DashMap<Hash, usize>,
+}
+
+impl Default for ShardedAssets {
+    fn default() -> Self {
+        Self {
+            shards: (0..ASSET_SHARD_COUNT).map(|_| AssetShard::default()).collect(),
+            directory: DashMap::new(),
+        }
+    }
+}
+
+impl ShardedAssets {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn shard_of(&self, asset_id: &Hash) -> Option<&AssetShard> {
+        let index = *self.directory.get(asset_id)?;
+        Some(&self.shards[index])
+    }
+
+    pub fn get(&self, asset_id: &Hash) -> Option<Ref<'_, Hash, AssetState>> {
+        let shard = self.shard_of(asset_id)?;
+        shard.reads.fetch_add(1, Ordering::Relaxed);
+        shard.assets.get(asset_id)
+    }
+
+    /// Change an asset in place; changing its game_id this way does not move it between shards
+    pub fn update<R>(&self, asset_id: &Hash, f: impl FnOnce(&mut AssetState) -> R) -> Option<R> {
+        let shard = self.shard_of(asset_id)?;
+        shard.writes.fetch_add(1, Ordering::Relaxed);
+        let mut asset = shard.assets.get_mut(asset_id)?;
+        let result = f(&mut asset);
+        shard.hashes.set(asset_id, &*asset);
+        Some(result)
+    }
+
+    pub fn contains_key(&self, asset_id: &Hash) -> bool {
+        self.directory.contains_key(asset_id)
+    }
+
+    /// Store an asset in the shard of its game_id, moving it if the game_id changed
+    pub fn insert(&self, asset_id: Hash, asset: AssetState) -> Option<AssetState> {
+        let index = shard_for(asset.data.game_id.as_deref());
+        let previous = match self.directory.insert(asset_id, index) {
+            Some(old_index) if old_index != index => {
+                let old_shard = &self.shards[old_index];
+                old_shard.hashes.remove(&asset_id);
+                old_shard.assets.remove(&asset_id).map(|(_, a)| a)
+            }
+            _ => None,
+        };
+        let shard = &self.shards[index];
+        shard.writes.fetch_add(1, Ordering::Relaxed);
+        shard.hashes.set(&asset_id, &asset);
+        shard.assets.insert(asset_id, asset).or(previous)
+    }
+
+    pub fn remove(&self, asset_id: &Hash) -> Option<(Hash, AssetState)> {
+        let (_, index) = self.directory.remove(asset_id)?;
+        let shard = &self.shards[index];
+        shard.writes.fetch_add(1, Ordering::Relaxed);
+        shard.hashes.remove(asset_id);
+        shard.assets.remove(asset_id)
+    }
+
+    /// All assets, shard by shard
+    pub fn iter(&self) -> impl Iterator<Item = RefMulti<'_, Hash, AssetState>> {
+        self.shards.iter().flat_map(|shard| shard.assets.iter())
+    }
+
+    pub fn len(&self) -> usize {
+        self.directory.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.directory.is_empty()
+    }
+
+    /// Add an asset id to its collection's index
+    pub fn index_collection(&self, game_id: &str, asset_id: Hash) {
+        self.shards[shard_for(Some(game_id))]
+            .collections
+            .entry(game_id.to_string())
+            .or_default()
+            .push(asset_id);
+    }
+
+    /// Remove an asset id from its collection's index
+    pub fn unindex_collection(&self, game_id: &str, asset_id: &Hash) {
+        let collections = &self.shards[shard_for(Some(game_id))].collections;
+        if let Some(mut ids) = collections.get_mut(game_id) {
+            ids.retain(|id| id != asset_id);
+            if ids.is_empty() {
+                drop(ids);
+                collections.remove(game_id);
+            }
+        }
+    }
+
+    /// Asset ids of a collection, `None` if it has no assets
+    pub fn collection(&self, game_id: &str) -> Option<Vec<Hash>> {
+        self.shards[shard_for(Some(game_id))].collections.get(game_id).map(|ids| ids.clone())
+    }
+
+    /// Number of assets in a collection, `None` if it has none
+    pub fn collection_len(&self, game_id: &str) -> Option<usize> {
+        self.shards[shard_for(Some(game_id))].collections.get(game_id).map(|ids| ids.len())
+    }
+
+    /// Every collection with its asset count
+    pub fn collections(&self) -> Vec<(String, usize)> {
+        self.shards
+            .iter()
+            .flat_map(|shard| shard.collections.iter().map(|entry| (entry.key().clone(), entry.value().len())))
+            .collect()
+    }
+
+    /// Root of each shard, in shard order (only shards changed since the last call are rehashed)
+    pub fn shard_roots(&self) -> Vec<Hash> {
+        self.shards.iter().map(AssetShard::root).collect()
+    }
+
+    /// Root of the shard roots (the asset part of the state root)
+    pub fn root(&self) -> Hash {
+        root_of(&self.shard_roots())
+    }
+
+    /// Shard of a stored asset and the proof of its hash under that shard's root
+    pub fn prove(&self, asset_id: &Hash) -> Option<(usize, TrieProof)> {
+        let index = *self.directory.get(asset_id)?;
+        Some((index, self.shards[index].hashes.prove(asset_id)))
+    }
+
+    pub fn stats(&self) -> Vec<ShardStats> {
+        self.shards
+            .iter()
+            .enumerate()
+            .map(|(shard_index, shard)| ShardStats {
+                shard: shard_index,
+                assets: shard.assets.len(),
+                collections: shard.collections.len(),
+                reads: shard.reads.load(Ordering::Relaxed),
+                writes: shard.writes.load(Ordering::Relaxed),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{AssetData, DensityLevel};
+
+    fn asset(game_id: Option<&str>) -> AssetState {
+        AssetState {
+            owner: [1u8; 32],
+            data: AssetData {
+                density: DensityLevel::Ethereal,
+                metadata: Default::default(),
+                attributes: vec![],
+                game_id: game_id.map(str::to_string),
+                owner: [1u8; 32],
+            },
+            created_at: 0,
+            updated_at: 0,
+            blob_refs: Default::default(),
+            history: vec![],
+            versions: vec![],
+            current_version: 0,
+            permissions: vec![],
+            public_read: false,
+            private_fields: Default::default(),
+        }
+    }
+
+    /// Two game_ids that land in different shards
+    fn split_collections() -> (&'static str, &'static str) {
+        let names = ["racing", "puzzle", "shooter", "cards", "chess", "farming"];
+        let first = names[0];
+        let second = names.iter().find(|name| shard_for(Some(name)) != shard_for(Some(first))).unwrap();
+        (first, second)
+    }
+
+    #[test]
+    fn test_assets_follow_their_collection_shard() {
+        let (racing, other) = split_collections();
+        let assets = ShardedAssets::new();
+        let id = sha256(b"car");
+        assets.insert(id, asset(Some(racing)));
+        assets.index_collection(racing, id);
+        let stats = assets.stats();
+        assert_eq!(stats[shard_for(Some(racing))].assets, 1);
+        assert_eq!(stats.iter().map(|s| s.assets).sum::<usize>(), 1);
+        assert_eq!(assets.collection(racing), Some(vec![id]));
+        assert!(assets.get(&id).is_some());
+        assert_eq!(assets.stats()[shard_for(Some(racing))].reads, 1);
+
+        // Moving to another collection moves the asset to that collection's shard
+        let root_before = assets.root();
+        let shard_roots_before = assets.shard_roots();
+        assert!(assets.insert(id, asset(Some(other))).is_some());
+        assets.unindex_collection(racing, &id);
+        assets.index_collection(other, id);
+        let stats = assets.stats();
+        assert_eq!((stats[shard_for(Some(racing))].assets, stats[shard_for(Some(other))].assets), (0, 1));
+        assert_eq!(assets.collection(racing), None);
+        assert_eq!(assets.collections(), vec![(other.to_string(), 1)]);
+        assert_eq!(assets.len(), 1);
+        assert_ne!(assets.root(), root_before);
+
+        // Only the shards touched by a change get a new root
+        let changed = assets.shard_roots().iter().zip(&shard_roots_before).filter(|(a, b)| a != b).count();
+        assert_eq!(changed, 2);
+
+        assert_eq!(assets.remove(&id).map(|(removed, _)| removed), Some(id));
+        assert!(assets.is_empty() && assets.get(&id).is_none());
+        assert_eq!(assets.root(), ShardedAssets::new().root());
+    }
+}
@@ -6,13 +6,18 @@
 //! - Blob storage for large files (Core density)
 //! - WASM contract integration
 
-use crate::types::{Hash, Address, AssetData, DensityLevel, AssetAction};
+use crate::types::{Hash, Address, AssetData, AttributeValue, DensityLevel, AssetAction};
 use crate::error::{HazeError, Result};
 use crate::vm::{HazeVM, ExecutionContext};
-use crate::config::Config;
-use std::collections::HashMap;
+use crate::blob_s3::S3Store;
+use crate::config::{BlobBackend, BlobChunking, Config};
+use sha2::{Digest, Sha256};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::fs;
+use std::io::{Read, Seek, SeekFrom};
 
 /// Mistborn Asset manager
 pub struct MistbornAsset {
@@ -31,31 +36,284 @@ pub struct AssetHistoryEntry {
     pub changes: HashMap<String, String>,
 }
 
+/// Size assumed for a referenced blob this node does not hold (quota accounting)
+pub const BLOB_SIZE_ESTIMATE: u64 = 1024 * 1024;
+
 /// Blob storage for large files (Core density assets)
+///
+/// Blobs are cut into chunks (content-defined by default, see `BlobChunking`) and every chunk
+/// is stored once under its sha256 in `chunks/`, so near-identical files share most of their
+/// bytes on disk. A manifest per blob in `manifests/` lists its chunks and the blob keys that
+/// reference it. Blobs written before deduplication (`{key}_{hash}` files and `.chunks`
+/// directories) are still read.
+///
+/// With the S3 backend, chunks and manifests are also written to the bucket, and `chunks/`
+/// becomes a read-through cache trimmed to `blob_s3.cache_max_bytes`. A blob fetched from the
+/// bucket is checked against its on-chain hash before its manifest is kept locally, and every
+/// chunk fetched later is checked against its own hash.
 pub struct BlobStorage {
     storage_path: PathBuf,
     chunk_size: usize,
     max_size: usize,
+    chunking: BlobChunking,
+    remote: Option<Arc<S3Store>>,
+    cache_max_bytes: u64,
+}
+
+/// Chunk list of a stored blob
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct BlobManifest {
+    size: u64,
+    /// Chunk hashes (hex) and sizes, in blob order
+    chunks: Vec<(String, u64)>,
+    /// Blob keys referencing this blob; the blob is deleted with the last one
+    keys: Vec<String>,
+    /// MIME type hint given when the blob was stored
+    #[serde(default)]
+    content_type: Option<String>,
+}
+
+/// Served for blobs with no stored MIME hint and no recognised signature
+pub const DEFAULT_CONTENT_TYPE: &str = "application/octet-stream";
+
+/// MIME type from the signature at the start of a file, for common game asset formats
+pub fn sniff_content_type(head: &[u8]) -> Option<&'static str> {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF8", "image/gif"),
+        (b"glTF", "model/gltf-binary"),
+        (b"OggS", "audio/ogg"),
+        (b"ID3", "audio/mpeg"),
+        (b"fLaC", "audio/flac"),
+        (b"\0asm", "application/wasm"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+    ];
+    if head.len() >= 12 && head.starts_with(b"RIFF") {
+        return match &head[8..12] {
+            b"WEBP" => Some("image/webp"),
+            b"WAVE" => Some("audio/wav"),
+            _ => None,
+        };
+    }
+    SIGNATURES
+        .iter()
+        .find(|(signature, _)| head.starts_with(signature))
+        .map(|(_, content_type)| *content_type)
+}
+
+/// A stored blob located for reading (see `BlobStorage::open_blob`)
+#[derive(Debug, Clone)]
+pub struct BlobReader {
+    pub size: u64,
+    /// The MIME hint stored with the blob, else detected from its first bytes
+    pub content_type: String,
+    /// Files holding the blob in order: path, sha256 hex of content-addressed chunks, length
+    files: Vec<(PathBuf, Option<String>, u64)>,
+    /// Largest piece read from a legacy single-file blob
+    piece_size: u64,
+    /// Object store chunks missing from the local cache are fetched from
+    remote: Option<Arc<S3Store>>,
+}
+
+impl BlobReader {
+    /// Pieces covering bytes `start..end` of the blob, in order. Each piece lies within one
+    /// file and is at most one chunk long, so reading a range holds one chunk in memory at a time.
+    pub fn pieces(&self, start: u64, end: u64) -> Vec<BlobPiece> {
+        let mut pieces = Vec::new();
+        let mut file_start = 0u64;
+        for (path, checksum, len) in &self.files {
+            let file_end = file_start + len;
+            let (from, to) = (start.max(file_start), end.min(file_end));
+            let mut offset = from;
+            while offset < to {
+                // Content-addressed chunks are verified whole; other files are read in slices
+                let piece_end = if checksum.is_some() { to } else { to.min(offset + self.piece_size) };
+                pieces.push(BlobPiece {
+                    path: path.clone(),
+                    checksum: checksum.clone(),
+                    start: offset - file_start,
+                    end: piece_end - file_start,
+                    remote: self.remote.clone(),
+                });
+                offset = piece_end;
+            }
+            file_start = file_end;
+        }
+        pieces
+    }
+}
+
+/// Bytes `start..end` of one file of a blob
+#[derive(Debug, Clone)]
+pub struct BlobPiece {
+    path: PathBuf,
+    checksum: Option<String>,
+    start: u64,
+    end: u64,
+    remote: Option<Arc<S3Store>>,
+}
+
+impl BlobPiece {
+    /// Read the piece (blocking). Chunks are read whole and checked against their hash; with
+    /// an object store, chunks missing from the cache are fetched into it.
+    pub fn read(&self) -> Result<Vec<u8>> {
+        if let Some(chunk_hex) = &self.checksum {
+            let data = match (&self.remote, self.path.exists()) {
+                (Some(remote), false) => fetch_chunk(remote, &self.path, chunk_hex)?,
+                (remote, _) => {
+                    let data = fs::read(&self.path).map_err(|e| io_error("Failed to read chunk", e))?;
+                    if remote.is_some() {
+                        // Recently read chunks are the last evicted from the cache
+                        fs::File::options()
+                            .write(true)
+                            .open(&self.path)
+                            .and_then(|file| file.set_modified(std::time::SystemTime::now()))
+                            .ok();
+                    }
+                    data
+                }
+            };
+            if crate::types::hash_to_hex(&crate::types::sha256(&data)) != *chunk_hex {
+                return Err(HazeError::Asset(format!("Chunk {} does not match its hash", chunk_hex)));
+            }
+            return data
+                .get(self.start as usize..self.end as usize)
+                .map(<[u8]>::to_vec)
+                .ok_or_else(|| HazeError::Asset(format!("Chunk {} is shorter than its manifest says", chunk_hex)));
+        }
+        let mut file = fs::File::open(&self.path).map_err(|e| io_error("Failed to read blob", e))?;
+        file.seek(SeekFrom::Start(self.start)).map_err(|e| io_error("Failed to read blob", e))?;
+        let mut data = vec![0u8; (self.end - self.start) as usize];
+        file.read_exact(&mut data).map_err(|e| io_error("Failed to read blob", e))?;
+        Ok(data)
+    }
+}
+
+/// Deduplication of the blob store at the last maintenance run
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct BlobStats {
+    pub blobs: u64,
+    pub chunks: u64,
+    /// Sum of blob sizes
+    pub logical_bytes: u64,
+    /// Bytes of the distinct chunks actually stored
+    pub unique_bytes: u64,
+    /// `logical_bytes / unique_bytes` (1.0 for an empty store)
+    pub dedup_ratio: f64,
+}
+
+/// Gear table of the content-defined chunker (fixed: changing it changes every chunk boundary)
+const GEAR: [u64; 256] = gear_table();
+
+const fn gear_table() -> [u64; 256] {
+    // splitmix64
+    let mut table = [0u64; 256];
+    let mut state = 0u64;
+    let mut i = 0;
+    while i < 256 {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        table[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    table
+}
+
+/// Length of the next chunk at the start of `data`
+fn next_chunk_len(data: &[u8], chunking: BlobChunking, max: usize) -> usize {
+    let end = data.len().min(max);
+    // Content-defined chunks average max / 16 bytes and are at least a quarter of that
+    let avg = max / 16;
+    if chunking == BlobChunking::Fixed || avg < 64 {
+        return end;
+    }
+    let min = avg / 4;
+    if end <= min {
+        return end;
+    }
+    // Cut where the top bits of the gear hash (which covers the last 64 bytes) are all zero
+    let shift = 64 - avg.ilog2();
+    let mut hash = 0u64;
+    for (i, byte) in data.iter().enumerate().take(end).skip(min) {
+        hash = (hash << 1).wrapping_add(GEAR[*byte as usize]);
+        if hash >> shift == 0 {
+            return i + 1;
+        }
+    }
+    end
+}
+
+fn io_error(context: &str, e: std::io::Error) -> HazeError {
+    HazeError::Asset(format!("{}: {}", context, e))
+}
+
+/// Write a file aside and rename it into place, so it is never seen partially written
+fn write_file(path: &std::path::Path, data: &[u8]) -> std::io::Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, data)?;
+    fs::rename(&tmp_path, path)
+}
+
+/// Fetch a chunk from the object store into the local cache at `path`, checking its hash
+fn fetch_chunk(remote: &S3Store, path: &std::path::Path, chunk_hex: &str) -> Result<Vec<u8>> {
+    let data = remote
+        .get(&format!("chunks/{}", chunk_hex))?
+        .ok_or_else(|| HazeError::Asset(format!("Chunk {} is missing from the object store", chunk_hex)))?;
+    if crate::types::hash_to_hex(&crate::types::sha256(&data)) != chunk_hex {
+        return Err(HazeError::Asset(format!("Chunk {} from the object store does not match its hash", chunk_hex)));
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| io_error("Failed to create chunk directory", e))?;
+    }
+    write_file(path, &data).map_err(|e| io_error("Failed to cache chunk", e))?;
+    Ok(data)
 }
 
 impl BlobStorage {
     /// Create new blob storage
     pub fn new(config: &Config) -> Result<Self> {
-        let storage_path = config.storage.blob_storage_path.clone();
+        let storage = Self::open(config)?;
         
         // Create blob storage directory if it doesn't exist
-        fs::create_dir_all(&storage_path)
+        fs::create_dir_all(&storage.storage_path)
             .map_err(|e| HazeError::Asset(format!("Failed to create blob storage: {}", e)))?;
         
+        Ok(storage)
+    }
+
+    /// Blob storage at the configured path, without touching the disk (for read-only use).
+    /// Fails when the S3 backend is selected and its credentials are not set.
+    pub fn open(config: &Config) -> Result<Self> {
+        let remote = match config.storage.blob_backend {
+            BlobBackend::Local => None,
+            BlobBackend::S3 => Some(Arc::new(S3Store::from_config(&config.storage.blob_s3)?)),
+        };
         Ok(Self {
-            storage_path,
-            chunk_size: config.storage.blob_chunk_size,
+            storage_path: config.storage.blob_storage_path.clone(),
+            chunk_size: config.storage.blob_chunk_size.max(1),
             max_size: config.storage.max_blob_size,
+            chunking: config.storage.blob_chunking,
+            remote,
+            cache_max_bytes: config.storage.blob_s3.cache_max_bytes,
         })
     }
     
-    /// Store blob data and return hash
+    /// Store blob data under `blob_key` and return its hash. Chunks already stored are not written again.
     pub fn store_blob(&self, blob_key: &str, data: &[u8]) -> Result<Hash> {
+        self.store_blob_with_content_type(blob_key, data, None)
+    }
+
+    /// Store blob data with a MIME type to serve it with (detected from the data when `None`)
+    pub fn store_blob_with_content_type(&self, blob_key: &str, data: &[u8], content_type: Option<&str>) -> Result<Hash> {
+        if let Some(content_type) = content_type
+            && !(content_type.contains('/') && content_type.bytes().all(|b| b.is_ascii_graphic() || b == b' '))
+        {
+            return Err(HazeError::Asset(format!("Invalid blob content type '{}'", content_type)));
+        }
         if data.len() > self.max_size {
             return Err(HazeError::Asset(format!(
                 "Blob size {} exceeds maximum {} bytes",
@@ -67,95 +325,216 @@ impl BlobStorage {
         // Compute hash of blob data
         let blob_hash = crate::types::sha256(data);
         
-        // Ensure storage directory exists
-        fs::create_dir_all(&self.storage_path)
-            .map_err(|e| HazeError::Asset(format!("Failed to create storage directory: {}", e)))?;
-        
-        // Store blob in chunks if it's large
-        if data.len() > self.chunk_size {
-            self.store_blob_chunked(blob_key, data, &blob_hash)?;
-        } else {
-            let blob_path = self.get_blob_path(blob_key, &blob_hash);
-            // Ensure parent directory exists
-            if let Some(parent) = blob_path.parent() {
-                fs::create_dir_all(parent)
-                    .map_err(|e| HazeError::Asset(format!("Failed to create blob directory: {}", e)))?;
-            }
-            fs::write(&blob_path, data)
-                .map_err(|e| HazeError::Asset(format!("Failed to write blob: {}", e)))?;
+        let mut manifest = match self.read_manifest(&blob_hash)? {
+            Some(manifest) => manifest,
+            None => BlobManifest {
+                size: data.len() as u64,
+                chunks: self.store_chunks(data)?,
+                keys: Vec::new(),
+                content_type: None,
+            },
+        };
+        if let Some(content_type) = content_type {
+            manifest.content_type = Some(content_type.to_string());
+        }
+        if !manifest.keys.iter().any(|key| key == blob_key) {
+            manifest.keys.push(blob_key.to_string());
         }
+        self.write_manifest(&blob_hash, &manifest)?;
         
         Ok(blob_hash)
     }
-    
-    /// Store blob in chunks for large files
-    fn store_blob_chunked(&self, blob_key: &str, data: &[u8], blob_hash: &Hash) -> Result<()> {
-        let base_path = self.get_blob_path(blob_key, blob_hash);
-        let chunk_dir = base_path.with_extension("chunks");
-        
-        // Ensure parent directory exists
-        if let Some(parent) = chunk_dir.parent() {
-            fs::create_dir_all(parent)
-                .map_err(|e| HazeError::Asset(format!("Failed to create parent directory: {}", e)))?;
-        }
-        
+
+    /// Cut `data` into chunks and store the ones not stored yet
+    fn store_chunks(&self, data: &[u8]) -> Result<Vec<(String, u64)>> {
+        let chunk_dir = self.storage_path.join("chunks");
         fs::create_dir_all(&chunk_dir)
-            .map_err(|e| HazeError::Asset(format!("Failed to create chunk directory: {}", e)))?;
+            .map_err(|e| io_error("Failed to create chunk directory", e))?;
         
+        let mut chunks = Vec::new();
         let mut offset = 0;
-        let mut chunk_index = 0;
-        
         while offset < data.len() {
-            let chunk_end = std::cmp::min(offset + self.chunk_size, data.len());
-            let chunk_data = &data[offset..chunk_end];
-            
-            let chunk_path = chunk_dir.join(format!("chunk_{:08}", chunk_index));
-            fs::write(&chunk_path, chunk_data)
-                .map_err(|e| HazeError::Asset(format!("Failed to write chunk: {}", e)))?;
-            
-            offset = chunk_end;
-            chunk_index += 1;
+            let len = next_chunk_len(&data[offset..], self.chunking, self.chunk_size);
+            let chunk = &data[offset..offset + len];
+            let chunk_hex = crate::types::hash_to_hex(&crate::types::sha256(chunk));
+            let chunk_path = chunk_dir.join(&chunk_hex);
+            // A cached chunk is already in the object store: it was uploaded or fetched
+            if !chunk_path.exists() {
+                if let Some(remote) = &self.remote {
+                    remote.put(&format!("chunks/{}", chunk_hex), chunk)?;
+                }
+                write_file(&chunk_path, chunk).map_err(|e| io_error("Failed to write chunk", e))?;
+            }
+            chunks.push((chunk_hex, len as u64));
+            offset += len;
         }
-        
-        Ok(())
+        Ok(chunks)
     }
     
-    /// Retrieve blob data
+    /// Retrieve blob data, checked against its hash
     pub fn get_blob(&self, blob_key: &str, blob_hash: &Hash) -> Result<Vec<u8>> {
-        let blob_path = self.get_blob_path(blob_key, blob_hash);
-        
-        // Check if it's chunked
-        let chunk_dir = blob_path.with_extension("chunks");
-        if chunk_dir.exists() {
-            self.get_blob_chunked(&chunk_dir)
+        let reader = self.open_blob(blob_key, blob_hash)?;
+        let mut data = Vec::with_capacity(reader.size as usize);
+        for piece in reader.pieces(0, reader.size) {
+            data.extend_from_slice(&piece.read()?);
+        }
+        if crate::types::sha256(&data) != *blob_hash {
+            return Err(HazeError::Asset(format!(
+                "Blob {} does not match its hash",
+                crate::types::hash_to_hex(blob_hash)
+            )));
+        }
+        Ok(data)
+    }
+
+    /// Locate a stored blob for reading it in pieces, without reading its data. With an object
+    /// store, a blob not known locally is fetched and checked against `blob_hash` first.
+    pub fn open_blob(&self, blob_key: &str, blob_hash: &Hash) -> Result<BlobReader> {
+        let manifest = match self.read_manifest(blob_hash)? {
+            Some(manifest) => Some(manifest),
+            None => self.fetch_blob(blob_hash)?,
+        };
+        let (files, hint) = if let Some(manifest) = manifest {
+            let chunk_dir = self.storage_path.join("chunks");
+            let files = manifest.chunks
+                .into_iter()
+                .map(|(chunk_hex, len)| (chunk_dir.join(&chunk_hex), Some(chunk_hex), len))
+                .collect();
+            (files, manifest.content_type)
         } else {
-            fs::read(&blob_path)
-                .map_err(|e| HazeError::Asset(format!("Failed to read blob: {}", e)))
+            let blob_path = self.get_blob_path(blob_key, blob_hash);
+            let chunk_dir = blob_path.with_extension("chunks");
+            let mut files = Vec::new();
+            if chunk_dir.exists() {
+                let mut chunk_index = 0;
+                loop {
+                    let chunk_path = chunk_dir.join(format!("chunk_{:08}", chunk_index));
+                    let Ok(metadata) = fs::metadata(&chunk_path) else {
+                        break;
+                    };
+                    files.push((chunk_path, None, metadata.len()));
+                    chunk_index += 1;
+                }
+            } else {
+                let metadata = fs::metadata(&blob_path)
+                    .map_err(|e| HazeError::Asset(format!("Failed to read blob: {}", e)))?;
+                files.push((blob_path, None, metadata.len()));
+            }
+            (files, None)
+        };
+
+        let mut reader = BlobReader {
+            size: files.iter().map(|(_, _, len)| len).sum(),
+            content_type: String::new(),
+            files,
+            piece_size: self.chunk_size as u64,
+            remote: self.remote.clone(),
+        };
+        reader.content_type = match hint {
+            Some(content_type) => content_type,
+            None => {
+                let head = match reader.pieces(0, reader.size.min(16)).first() {
+                    Some(piece) => piece.read()?,
+                    None => Vec::new(),
+                };
+                sniff_content_type(&head).unwrap_or(DEFAULT_CONTENT_TYPE).to_string()
+            }
+        };
+        Ok(reader)
+    }
+
+    /// Fetch a blob's manifest and chunks from the object store into the local cache. The
+    /// manifest is kept only if the chunks hash to `blob_hash`, the hash recorded on chain.
+    fn fetch_blob(&self, blob_hash: &Hash) -> Result<Option<BlobManifest>> {
+        let Some(remote) = &self.remote else {
+            return Ok(None);
+        };
+        let blob_hex = crate::types::hash_to_hex(blob_hash);
+        let Some(bytes) = remote.get(&format!("manifests/{}", blob_hex))? else {
+            return Ok(None);
+        };
+        let manifest: BlobManifest = serde_json::from_slice(&bytes)
+            .map_err(|e| HazeError::Asset(format!("Damaged manifest of blob {} in the object store: {}", blob_hex, e)))?;
+        let chunk_dir = self.storage_path.join("chunks");
+        let mut hasher = Sha256::new();
+        for (chunk_hex, _) in &manifest.chunks {
+            let chunk_path = chunk_dir.join(chunk_hex);
+            let data = match fs::read(&chunk_path) {
+                Ok(data) => data,
+                Err(_) => fetch_chunk(remote, &chunk_path, chunk_hex)?,
+            };
+            hasher.update(&data);
+        }
+        if hasher.finalize()[..] != blob_hash[..] {
+            return Err(HazeError::Asset(format!("Blob {} from the object store does not match its hash", blob_hex)));
         }
+        self.write_local_manifest(blob_hash, &manifest)?;
+        Ok(Some(manifest))
     }
-    
-    /// Retrieve chunked blob
-    fn get_blob_chunked(&self, chunk_dir: &PathBuf) -> Result<Vec<u8>> {
-        let mut data = Vec::new();
-        let mut chunk_index = 0;
-        
-        loop {
-            let chunk_path = chunk_dir.join(format!("chunk_{:08}", chunk_index));
-            if !chunk_path.exists() {
+
+    /// Evict the least recently used chunks until the local cache fits `blob_s3.cache_max_bytes`.
+    /// Only with an object store; returns the bytes evicted.
+    pub fn trim_cache(&self) -> Result<u64> {
+        if self.remote.is_none() {
+            return Ok(0);
+        }
+        let chunk_dir = self.storage_path.join("chunks");
+        let Ok(entries) = fs::read_dir(&chunk_dir) else {
+            return Ok(0);
+        };
+        let mut cached = Vec::new();
+        for entry in entries {
+            let entry = entry.map_err(|e| io_error("Failed to list chunk cache", e))?;
+            let metadata = entry.metadata().map_err(|e| io_error("Failed to list chunk cache", e))?;
+            if entry.file_name().to_string_lossy().ends_with(".tmp") {
+                continue;
+            }
+            let used = metadata.modified().unwrap_or(std::time::UNIX_EPOCH);
+            cached.push((used, metadata.len(), entry.path()));
+        }
+        let mut total: u64 = cached.iter().map(|(_, len, _)| len).sum();
+        cached.sort();
+        let mut evicted = 0u64;
+        for (_, len, path) in cached {
+            if total <= self.cache_max_bytes {
                 break;
             }
-            
-            let mut chunk_data = fs::read(&chunk_path)
-                .map_err(|e| HazeError::Asset(format!("Failed to read chunk: {}", e)))?;
-            data.append(&mut chunk_data);
-            chunk_index += 1;
+            fs::remove_file(&path).map_err(|e| io_error("Failed to evict chunk", e))?;
+            total -= len;
+            evicted += len;
         }
-        
-        Ok(data)
+        Ok(evicted)
     }
     
-    /// Delete blob
+    /// Delete blob. Once no key references it, chunks no other blob uses are removed
+    /// (scans every manifest).
     pub fn delete_blob(&self, blob_key: &str, blob_hash: &Hash) -> Result<()> {
+        if let Some(mut manifest) = self.read_manifest(blob_hash)? {
+            manifest.keys.retain(|key| key != blob_key);
+            if !manifest.keys.is_empty() {
+                return self.write_manifest(blob_hash, &manifest);
+            }
+            // Chunks in the object store are kept: other nodes sharing the bucket may use them
+            if let Some(remote) = &self.remote {
+                remote.delete(&format!("manifests/{}", crate::types::hash_to_hex(blob_hash)))?;
+            }
+            fs::remove_file(self.manifest_path(blob_hash))
+                .map_err(|e| io_error("Failed to remove manifest", e))?;
+            let mut in_use = HashSet::new();
+            for (_, other) in self.manifests()? {
+                in_use.extend(other.chunks.into_iter().map(|(chunk_hex, _)| chunk_hex));
+            }
+            for (chunk_hex, _) in manifest.chunks {
+                if !in_use.contains(&chunk_hex) {
+                    let chunk_path = self.storage_path.join("chunks").join(&chunk_hex);
+                    if chunk_path.exists() {
+                        fs::remove_file(&chunk_path).map_err(|e| io_error("Failed to remove chunk", e))?;
+                    }
+                    in_use.insert(chunk_hex);
+                }
+            }
+        }
+
         let blob_path = self.get_blob_path(blob_key, blob_hash);
         let chunk_dir = blob_path.with_extension("chunks");
         
@@ -171,6 +550,100 @@ impl BlobStorage {
         
         Ok(())
     }
+
+    /// Bytes of the distinct chunks of the given blobs. Blobs this node does not hold (or
+    /// stored before deduplication) count as `BLOB_SIZE_ESTIMATE`.
+    pub fn unique_bytes<'a>(&self, blob_hashes: impl IntoIterator<Item = &'a Hash>) -> u64 {
+        let mut seen_blobs = HashSet::new();
+        let mut chunks = HashMap::new();
+        let mut estimated = 0u64;
+        for blob_hash in blob_hashes {
+            if !seen_blobs.insert(*blob_hash) {
+                continue;
+            }
+            match self.read_manifest(blob_hash) {
+                Ok(Some(manifest)) => chunks.extend(manifest.chunks),
+                _ => estimated += BLOB_SIZE_ESTIMATE,
+            }
+        }
+        estimated + chunks.values().sum::<u64>()
+    }
+
+    /// Count blobs and chunks and measure deduplication. Blocking: reads every manifest.
+    pub fn stats(&self) -> Result<BlobStats> {
+        let mut stats = BlobStats::default();
+        let mut chunks = HashMap::new();
+        for (_, manifest) in self.manifests()? {
+            stats.blobs += 1;
+            stats.logical_bytes += manifest.size;
+            chunks.extend(manifest.chunks);
+        }
+        stats.chunks = chunks.len() as u64;
+        stats.unique_bytes = chunks.values().sum();
+        stats.dedup_ratio = if stats.unique_bytes == 0 {
+            1.0
+        } else {
+            stats.logical_bytes as f64 / stats.unique_bytes as f64
+        };
+        Ok(stats)
+    }
+
+    fn manifest_path(&self, blob_hash: &Hash) -> PathBuf {
+        self.storage_path.join("manifests").join(crate::types::hash_to_hex(blob_hash))
+    }
+
+    fn read_manifest(&self, blob_hash: &Hash) -> Result<Option<BlobManifest>> {
+        let path = self.manifest_path(blob_hash);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let bytes = fs::read(&path).map_err(|e| io_error("Failed to read manifest", e))?;
+        serde_json::from_slice(&bytes)
+            .map(Some)
+            .map_err(|e| HazeError::Asset(format!("Damaged manifest {}: {}", path.display(), e)))
+    }
+
+    /// Write a manifest locally and to the object store
+    fn write_manifest(&self, blob_hash: &Hash, manifest: &BlobManifest) -> Result<()> {
+        if let Some(remote) = &self.remote {
+            let bytes = serde_json::to_vec(manifest)
+                .map_err(|e| HazeError::Serialization(format!("Failed to encode manifest: {}", e)))?;
+            remote.put(&format!("manifests/{}", crate::types::hash_to_hex(blob_hash)), &bytes)?;
+        }
+        self.write_local_manifest(blob_hash, manifest)
+    }
+
+    fn write_local_manifest(&self, blob_hash: &Hash, manifest: &BlobManifest) -> Result<()> {
+        let path = self.manifest_path(blob_hash);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| io_error("Failed to create manifest directory", e))?;
+        }
+        let bytes = serde_json::to_vec(manifest)
+            .map_err(|e| HazeError::Serialization(format!("Failed to encode manifest: {}", e)))?;
+        write_file(&path, &bytes).map_err(|e| io_error("Failed to write manifest", e))
+    }
+
+    /// Every readable manifest, by file name
+    fn manifests(&self) -> Result<Vec<(String, BlobManifest)>> {
+        let dir = self.storage_path.join("manifests");
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut manifests = Vec::new();
+        for entry in fs::read_dir(&dir).map_err(|e| io_error("Failed to list manifests", e))? {
+            let entry = entry.map_err(|e| io_error("Failed to list manifests", e))?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.ends_with(".tmp") {
+                continue;
+            }
+            let bytes = fs::read(entry.path()).map_err(|e| io_error("Failed to read manifest", e))?;
+            match serde_json::from_slice(&bytes) {
+                Ok(manifest) => manifests.push((name, manifest)),
+                Err(e) => tracing::warn!("Skipping damaged blob manifest {}: {}", name, e),
+            }
+        }
+        Ok(manifests)
+    }
     
     /// Get blob path
     fn get_blob_path(&self, blob_key: &str, blob_hash: &Hash) -> PathBuf {
@@ -563,7 +1036,7 @@ impl MistbornAsset {
     }
 
     /// Add or update an attribute
-    pub fn add_attribute(&mut self, name: String, value: String, rarity: Option<f64>) {
+    pub fn add_attribute(&mut self, name: String, value: AttributeValue, rarity: Option<f64>) {
         // Check if attribute already exists
         if let Some(existing) = self.data.attributes.iter_mut().find(|a| a.name == name) {
             existing.value = value;
@@ -578,7 +1051,7 @@ impl MistbornAsset {
     }
 
     /// Update an existing attribute's value
-    pub fn update_attribute(&mut self, name: &str, value: String) -> Result<()> {
+    pub fn update_attribute(&mut self, name: &str, value: AttributeValue) -> Result<()> {
         let attr = self.data.attributes.iter_mut()
             .find(|a| a.name == name)
             .ok_or_else(|| HazeError::Asset(format!("Attribute '{}' not found", name)))?;
@@ -608,6 +1081,371 @@ impl MistbornAsset {
     }
 }
 
+/// Reserved metadata key carrying an explicit Split manifest (JSON array of `SplitComponent`)
+pub const SPLIT_MANIFEST_KEY: &str = "_component_manifest";
+
+/// Maximum number of components one Split may create
+pub const MAX_SPLIT_COMPONENTS: usize = 100;
+
+/// One component of an explicit Split manifest
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct SplitComponent {
+    /// Component name; the component asset id is `sha256(source_asset_id || name)`
+    pub name: String,
+    /// Source metadata keys copied into the component
+    #[serde(default)]
+    pub metadata_keys: Vec<String>,
+    /// Names of source attributes copied into the component
+    #[serde(default)]
+    pub attributes: Vec<String>,
+    /// Component density (default Ethereal); may not exceed the source density
+    #[serde(default)]
+    pub density: Option<DensityLevel>,
+}
+
+impl SplitComponent {
+    pub fn density(&self) -> DensityLevel {
+        self.density.unwrap_or(DensityLevel::Ethereal)
+    }
+
+    /// Asset data of this component cut from the source asset
+    pub fn component_data(&self, source: &AssetData) -> AssetData {
+        AssetData {
+            density: self.density(),
+            metadata: self.metadata_keys.iter()
+                .filter_map(|key| source.metadata.get(key).map(|value| (key.clone(), value.clone())))
+                .collect(),
+            attributes: source.attributes.iter()
+                .filter(|attr| self.attributes.contains(&attr.name))
+                .cloned()
+                .collect(),
+            game_id: source.game_id.clone(),
+            owner: source.owner,
+        }
+    }
+}
+
+/// Parse a Split manifest and validate it against the source asset
+///
+/// Components must have unique non-empty names, reference only metadata keys and attributes
+/// the source has, and fit their density. Every non-reserved source metadata key and every
+/// source attribute must be assigned to at least one component.
+pub fn parse_split_manifest(json: &str, source: &AssetData) -> Result<Vec<SplitComponent>> {
+    let invalid = |msg: String| HazeError::InvalidTransaction(format!("Invalid split manifest: {}", msg));
+    let components: Vec<SplitComponent> = serde_json::from_str(json).map_err(|e| invalid(e.to_string()))?;
+    if components.is_empty() || components.len() > MAX_SPLIT_COMPONENTS {
+        return Err(invalid(format!("expected 1..={} components, got {}", MAX_SPLIT_COMPONENTS, components.len())));
+    }
+
+    let mut names = std::collections::HashSet::new();
+    let mut assigned_keys = std::collections::HashSet::new();
+    let mut assigned_attributes = std::collections::HashSet::new();
+    for component in &components {
+        if component.name.trim().is_empty() {
+            return Err(invalid("component name is empty".to_string()));
+        }
+        if !names.insert(component.name.as_str()) {
+            return Err(invalid(format!("duplicate component '{}'", component.name)));
+        }
+        if component.density() as u8 > source.density as u8 {
+            return Err(invalid(format!(
+                "component '{}' density {:?} exceeds source density {:?}",
+                component.name, component.density(), source.density
+            )));
+        }
+        for key in &component.metadata_keys {
+            if key.starts_with('_') || !source.metadata.contains_key(key) {
+                return Err(invalid(format!("component '{}' references unknown metadata key '{}'", component.name, key)));
+            }
+            assigned_keys.insert(key.as_str());
+        }
+        for name in &component.attributes {
+            if !source.attributes.iter().any(|attr| attr.name == *name) {
+                return Err(invalid(format!("component '{}' references unknown attribute '{}'", component.name, name)));
+            }
+            assigned_attributes.insert(name.as_str());
+        }
+        let size: usize = component.metadata_keys.iter()
+            .filter_map(|key| source.metadata.get(key))
+            .map(|value| value.len())
+            .sum();
+        if size > component.density().max_size() {
+            return Err(HazeError::AssetSizeExceeded(size, component.density().max_size()));
+        }
+    }
+
+    let mut unassigned_keys: Vec<&String> = source.metadata.keys()
+        .filter(|key| !key.starts_with('_') && !assigned_keys.contains(key.as_str()))
+        .collect();
+    if !unassigned_keys.is_empty() {
+        unassigned_keys.sort();
+        return Err(invalid(format!("metadata keys not assigned to any component: {:?}", unassigned_keys)));
+    }
+    if let Some(attr) = source.attributes.iter().find(|attr| !assigned_attributes.contains(attr.name.as_str())) {
+        return Err(invalid(format!("attribute '{}' not assigned to any component", attr.name)));
+    }
+    Ok(components)
+}
+
+/// Separator of namespaced metadata keys (`game1:durability`)
+pub const NAMESPACE_SEPARATOR: char = ':';
+
+/// Namespace of a metadata key: the game id before the first `NAMESPACE_SEPARATOR`
+/// (`None` for plain and reserved keys)
+///
+/// Only the owner and GameContract grantees for that game may write a namespaced key, so
+/// games sharing an asset cannot overwrite each other's fields.
+pub fn metadata_namespace(key: &str) -> Option<&str> {
+    if key.starts_with('_') {
+        return None;
+    }
+    key.split_once(NAMESPACE_SEPARATOR).map(|(namespace, _)| namespace)
+}
+
+/// Reserved metadata key committing encrypted fields on Create and Update: a JSON object
+/// mapping each field to the hex `metadata_commitment` of its plaintext
+pub const COMMITMENTS_KEY: &str = "_commitments";
+
+/// Reserved metadata key of a Reveal: a JSON object mapping each revealed field to the hex
+/// salt its commitment was made with
+pub const REVEAL_SALTS_KEY: &str = "_salts";
+
+/// Minimum salt length in bytes, so short plaintexts cannot be guessed from the commitment
+pub const MIN_REVEAL_SALT_LEN: usize = 16;
+
+/// Commitment to a private metadata value: `sha256(salt || plaintext)`
+///
+/// The client encrypts the value, stores the ciphertext as the field's metadata and the
+/// commitment under `COMMITMENTS_KEY`; a later Reveal publishes the plaintext and salt.
+pub fn metadata_commitment(salt: &[u8], plaintext: &str) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update(salt);
+    hasher.update(plaintext.as_bytes());
+    hasher.finalize().into()
+}
+
+fn hex_map(key: &str, json: &str) -> Result<std::collections::BTreeMap<String, Vec<u8>>> {
+    let invalid = |detail: String| HazeError::InvalidMetadataFormat(format!("Invalid {}: {}", key, detail));
+    let map: std::collections::BTreeMap<String, String> = serde_json::from_str(json).map_err(|e| invalid(e.to_string()))?;
+    if map.is_empty() {
+        return Err(invalid("no fields".to_string()));
+    }
+    map.into_iter()
+        .map(|(field, value)| {
+            if field.is_empty() || field.starts_with('_') {
+                return Err(invalid(format!("'{}' is not a metadata field", field)));
+            }
+            let bytes = hex::decode(&value).map_err(|_| invalid(format!("value for '{}' is not hex", field)))?;
+            Ok((field, bytes))
+        })
+        .collect()
+}
+
+/// Commitments declared by a Create or Update (field -> commitment; empty without
+/// `_commitments`). Every committed field must be written by the same transaction.
+pub fn metadata_commitments(metadata: &HashMap<String, String>) -> Result<std::collections::BTreeMap<String, Hash>> {
+    let Some(json) = metadata.get(COMMITMENTS_KEY) else {
+        return Ok(Default::default());
+    };
+    hex_map(COMMITMENTS_KEY, json)?
+        .into_iter()
+        .map(|(field, bytes)| {
+            let invalid = |detail: &str| {
+                HazeError::InvalidMetadataFormat(format!("Invalid {}: {} '{}'", COMMITMENTS_KEY, detail, field))
+            };
+            if !metadata.contains_key(&field) {
+                return Err(invalid("no encrypted value for"));
+            }
+            let commitment = Hash::try_from(bytes.as_slice()).map_err(|_| invalid("commitment is not 32 bytes for"))?;
+            Ok((field, commitment))
+        })
+        .collect()
+}
+
+/// Salts of a Reveal (field -> salt); the revealed fields are exactly the transaction's
+/// metadata fields, and each needs a salt
+pub fn reveal_salts(metadata: &HashMap<String, String>) -> Result<std::collections::BTreeMap<String, Vec<u8>>> {
+    let json = metadata.get(REVEAL_SALTS_KEY).ok_or_else(|| {
+        HazeError::InvalidTransaction(format!("Reveal requires '{}'", REVEAL_SALTS_KEY))
+    })?;
+    let salts = hex_map(REVEAL_SALTS_KEY, json)?;
+    let invalid = |detail: String| HazeError::InvalidMetadataFormat(format!("Invalid {}: {}", REVEAL_SALTS_KEY, detail));
+    if let Some((field, _)) = salts.iter().find(|(_, salt)| salt.len() < MIN_REVEAL_SALT_LEN) {
+        return Err(invalid(format!("salt for '{}' is shorter than {} bytes", field, MIN_REVEAL_SALT_LEN)));
+    }
+    if let Some(field) = salts.keys().find(|field| !metadata.contains_key(*field)) {
+        return Err(invalid(format!("no plaintext for '{}'", field)));
+    }
+    if let Some(field) = metadata.keys().find(|key| !key.starts_with('_') && !salts.contains_key(*key)) {
+        return Err(invalid(format!("no salt for '{}'", field)));
+    }
+    Ok(salts)
+}
+
+/// Reserved metadata key selecting the Merge conflict policy (`MergePolicy`)
+pub const MERGE_POLICY_KEY: &str = "_merge_policy";
+
+/// Separator between the two values of a conflict resolved by `MergePolicy::Concatenate`
+pub const MERGE_CONCAT_SEPARATOR: &str = "|";
+
+/// How Merge resolves a metadata key, attribute or blob ref present in both assets with
+/// different values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MergePolicy {
+    /// Keep the source asset's metadata and blob refs; for attributes the higher rarity wins
+    /// (ties keep the source). Used when no policy is given.
+    #[default]
+    HigherRarity,
+    /// Keep the source asset's value
+    PreferSource,
+    /// Take the other asset's value
+    PreferOther,
+    /// Join both values with `MERGE_CONCAT_SEPARATOR` (joined attribute values are strings
+    /// and keep the higher rarity; blob refs keep the source's)
+    Concatenate,
+    /// Reject the merge
+    FailOnConflict,
+}
+
+impl MergePolicy {
+    /// Parse the `_merge_policy` metadata value
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "higher-rarity" => Ok(Self::HigherRarity),
+            "prefer-source" => Ok(Self::PreferSource),
+            "prefer-other" => Ok(Self::PreferOther),
+            "concatenate" => Ok(Self::Concatenate),
+            "fail-on-conflict" => Ok(Self::FailOnConflict),
+            _ => Err(HazeError::InvalidTransaction(format!(
+                "Unknown merge policy '{}' (expected higher-rarity, prefer-source, prefer-other, concatenate or fail-on-conflict)",
+                value
+            ))),
+        }
+    }
+
+    /// Policy requested by a Merge transaction's metadata
+    pub fn from_metadata(metadata: &HashMap<String, String>) -> Result<Self> {
+        metadata.get(MERGE_POLICY_KEY).map_or(Ok(Self::default()), |value| Self::parse(value))
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::HigherRarity => "higher-rarity",
+            Self::PreferSource => "prefer-source",
+            Self::PreferOther => "prefer-other",
+            Self::Concatenate => "concatenate",
+            Self::FailOnConflict => "fail-on-conflict",
+        }
+    }
+}
+
+/// Merge `other` into `target` under `policy`, returning the conflicting keys
+/// (`metadata.<key>`, `attribute.<name>`, `blob.<key>`)
+///
+/// Reserved `_` metadata keys of `other` are skipped. Fails with the conflicts listed when
+/// `policy` is `FailOnConflict`; `target` is left untouched in that case.
+pub fn merge_asset_contents(
+    target: &mut AssetData,
+    target_blobs: &mut HashMap<String, Hash>,
+    other: &AssetData,
+    other_blobs: &HashMap<String, Hash>,
+    policy: MergePolicy,
+) -> Result<Vec<String>> {
+    let mut conflicts: Vec<String> = other.metadata.iter()
+        .filter(|(key, value)| !key.starts_with('_') && target.metadata.get(*key).is_some_and(|v| v != *value))
+        .map(|(key, _)| format!("metadata.{}", key))
+        .chain(other.attributes.iter()
+            .filter(|attr| target.attributes.iter()
+                .any(|a| a.name == attr.name && (a.value != attr.value || a.rarity != attr.rarity)))
+            .map(|attr| format!("attribute.{}", attr.name)))
+        .chain(other_blobs.iter()
+            .filter(|(key, hash)| target_blobs.get(*key).is_some_and(|h| h != *hash))
+            .map(|(key, _)| format!("blob.{}", key)))
+        .collect();
+    conflicts.sort();
+    if policy == MergePolicy::FailOnConflict && !conflicts.is_empty() {
+        return Err(HazeError::InvalidTransaction(format!(
+            "Merge conflicts with policy fail-on-conflict: {}", conflicts.join(", ")
+        )));
+    }
+
+    for (key, value) in &other.metadata {
+        if key.starts_with('_') {
+            continue;
+        }
+        match target.metadata.get_mut(key) {
+            None => {
+                target.metadata.insert(key.clone(), value.clone());
+            }
+            Some(existing) if existing != value => match policy {
+                MergePolicy::PreferOther => *existing = value.clone(),
+                MergePolicy::Concatenate => {
+                    *existing = format!("{}{}{}", existing, MERGE_CONCAT_SEPARATOR, value);
+                }
+                _ => {}
+            },
+            Some(_) => {}
+        }
+    }
+
+    for other_attr in &other.attributes {
+        let Some(existing) = target.attributes.iter_mut().find(|a| a.name == other_attr.name) else {
+            target.attributes.push(other_attr.clone());
+            continue;
+        };
+        // Higher rarity wins; with equal or missing rarity the source attribute is kept
+        let other_rarer = match (existing.rarity, other_attr.rarity) {
+            (Some(existing_rarity), Some(other_rarity)) => other_rarity > existing_rarity,
+            (None, Some(_)) => true,
+            (Some(_), None) | (None, None) => false,
+        };
+        match policy {
+            MergePolicy::HigherRarity if other_rarer => {
+                existing.value = other_attr.value.clone();
+                existing.rarity = other_attr.rarity;
+            }
+            MergePolicy::PreferOther => {
+                existing.value = other_attr.value.clone();
+                existing.rarity = other_attr.rarity;
+            }
+            MergePolicy::Concatenate => {
+                if existing.value != other_attr.value {
+                    existing.value = format!("{}{}{}", existing.value, MERGE_CONCAT_SEPARATOR, other_attr.value).into();
+                }
+                if other_rarer {
+                    existing.rarity = other_attr.rarity;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for (key, hash) in other_blobs {
+        if policy == MergePolicy::PreferOther || !target_blobs.contains_key(key) {
+            target_blobs.insert(key.clone(), *hash);
+        }
+    }
+
+    Ok(conflicts)
+}
+
+/// One charged component of an asset operation's gas cost
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct GasLineItem {
+    /// What is charged (`base`, `metadata_kb`, `metadata_bytes`, `blobs`, `components`, ...; `execution`
+    /// for the contract gas of a dry run)
+    pub item: &'static str,
+    /// Units charged (KB, bytes, blobs, components; the density multiplier for `base`)
+    pub quantity: u64,
+    pub gas: u64,
+}
+
+impl GasLineItem {
+    fn new(item: &'static str, quantity: u64, unit_gas: u64) -> Self {
+        Self { item, quantity, gas: quantity * unit_gas }
+    }
+}
+
 /// Calculate gas cost for asset operations
 pub fn calculate_asset_operation_gas(
     config: &crate::config::Config,
@@ -615,23 +1453,33 @@ pub fn calculate_asset_operation_gas(
     data: &AssetData,
     additional_data: Option<&HashMap<String, String>>,
 ) -> u64 {
+    asset_operation_gas_breakdown(config, action, data, additional_data)
+        .iter()
+        .map(|line| line.gas)
+        .sum()
+}
+
+/// Line items making up `calculate_asset_operation_gas` (their gas sums to the total)
+pub fn asset_operation_gas_breakdown(
+    config: &crate::config::Config,
+    action: &AssetAction,
+    data: &AssetData,
+    additional_data: Option<&HashMap<String, String>>,
+) -> Vec<GasLineItem> {
     let gas_config = &config.asset_gas;
+    let metadata_size: usize = data.metadata.values().map(|v| v.len()).sum();
+    let metadata_kb = (metadata_size as u64).div_ceil(1024);
     
     match action {
-        AssetAction::Create => {
-            let metadata_size: usize = data.metadata.values().map(|v| v.len()).sum();
-            let metadata_kb = (metadata_size as u64 + 1023) / 1024; // Round up
-            gas_config.create_base + (gas_config.create_per_kb * metadata_kb)
-        }
-        AssetAction::Update => {
-            let metadata_size: usize = data.metadata.values().map(|v| v.len()).sum();
-            let metadata_kb = (metadata_size as u64 + 1023) / 1024; // Round up
-            gas_config.update_base + (gas_config.update_per_kb * metadata_kb)
-        }
+        AssetAction::Create | AssetAction::Breed => vec![
+            GasLineItem::new("base", 1, gas_config.create_base),
+            GasLineItem::new("metadata_kb", metadata_kb, gas_config.create_per_kb),
+        ],
+        AssetAction::Update | AssetAction::Reveal | AssetAction::Upgrade => vec![
+            GasLineItem::new("base", 1, gas_config.update_base),
+            GasLineItem::new("metadata_kb", metadata_kb, gas_config.update_per_kb),
+        ],
         AssetAction::Condense => {
-            let metadata_size: usize = data.metadata.values().map(|v| v.len()).sum();
-            let metadata_kb = (metadata_size as u64 + 1023) / 1024; // Round up
-            
             // Calculate density multiplier
             let density_multiplier = match data.density {
                 DensityLevel::Light => 1,   // Ethereal -> Light
@@ -639,22 +1487,33 @@ pub fn calculate_asset_operation_gas(
                 DensityLevel::Core => 5,     // Dense -> Core
                 DensityLevel::Ethereal => 1, // Shouldn't happen, but default to 1
             };
+            // Bytes written into the asset's metadata (reserved `_` keys are instructions)
+            let new_metadata_bytes: usize = data.metadata.iter()
+                .filter(|(k, _)| !k.starts_with('_'))
+                .map(|(_, v)| v.len())
+                .sum();
+            // Blobs declared in `_blob_refs`, each stored by every node holding the asset
+            let blob_count = data.metadata.get("_blob_refs")
+                .and_then(|json| serde_json::from_str::<HashMap<String, String>>(json).ok())
+                .map_or(0, |refs| refs.len() as u64);
             
-            gas_config.condense_base * density_multiplier + (gas_config.condense_per_kb * metadata_kb)
+            vec![
+                GasLineItem::new("base", density_multiplier, gas_config.condense_base),
+                GasLineItem::new("metadata_kb", metadata_kb, gas_config.condense_per_kb),
+                GasLineItem::new("metadata_bytes", new_metadata_bytes as u64, gas_config.condense_per_byte),
+                GasLineItem::new("blobs", blob_count, gas_config.condense_per_blob),
+            ]
         }
         AssetAction::Evaporate => {
-            gas_config.evaporate_base // Minimal cost for archiving
+            vec![GasLineItem::new("base", 1, gas_config.evaporate_base)] // Minimal cost for archiving
         }
         AssetAction::Merge => {
-            // Calculate combined size from current asset and other asset
-            let current_size: usize = data.metadata.values().map(|v| v.len()).sum();
-            
             // Try to get other asset size from additional_data
             let other_size = if let Some(additional) = additional_data {
                 if additional.get("_other_asset_id").is_some() {
                     // We can't access the other asset here, so use a conservative estimate
                     // based on current asset size
-                    current_size
+                    metadata_size
                 } else {
                     0
                 }
@@ -662,13 +1521,22 @@ pub fn calculate_asset_operation_gas(
                 0
             };
             
-            let combined_size = current_size + other_size;
-            let combined_kb = (combined_size as u64 + 1023) / 1024; // Round up
-            gas_config.merge_base + (gas_config.merge_per_kb * combined_kb)
+            let combined_size = metadata_size + other_size;
+            let combined_kb = (combined_size as u64).div_ceil(1024);
+            vec![
+                GasLineItem::new("base", 1, gas_config.merge_base),
+                GasLineItem::new("combined_kb", combined_kb, gas_config.merge_per_kb),
+            ]
         }
         AssetAction::Split => {
-            // Get number of components from additional_data
-            let component_count = if let Some(additional) = additional_data {
+            // Get number of components from additional_data (an explicit manifest wins)
+            let manifest_count = additional_data
+                .and_then(|additional| additional.get(SPLIT_MANIFEST_KEY))
+                .and_then(|json| serde_json::from_str::<Vec<serde_json::Value>>(json).ok())
+                .map(|components| components.len() as u64);
+            let component_count = if let Some(count) = manifest_count {
+                count
+            } else if let Some(additional) = additional_data {
                 if let Some(components_str) = additional.get("_components") {
                     components_str.split(',').filter(|s| !s.trim().is_empty()).count() as u64
                 } else {
@@ -679,13 +1547,14 @@ pub fn calculate_asset_operation_gas(
             };
             
             // Estimate component size (split current asset size by component count)
-            let current_size: usize = data.metadata.values().map(|v| v.len()).sum();
-            let estimated_component_size = current_size / component_count.max(1) as usize;
-            let component_kb = (estimated_component_size as u64 + 1023) / 1024; // Round up
+            let estimated_component_size = metadata_size / component_count.max(1) as usize;
+            let component_kb = (estimated_component_size as u64).div_ceil(1024);
             
-            gas_config.split_base 
-                + (gas_config.split_per_component * component_count)
-                + (gas_config.split_per_kb * component_kb * component_count)
+            vec![
+                GasLineItem::new("base", 1, gas_config.split_base),
+                GasLineItem::new("components", component_count, gas_config.split_per_component),
+                GasLineItem::new("component_kb", component_kb * component_count, gas_config.split_per_kb),
+            ]
         }
     }
 }
@@ -732,6 +1601,105 @@ mod tests {
         std::fs::remove_dir_all(&config.storage.blob_storage_path).ok();
     }
     
+    /// Pseudo-random bytes, so content-defined chunk boundaries fall where the data says
+    fn noise(len: usize, seed: u64) -> Vec<u8> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                (state >> 56) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_near_identical_blobs_share_chunks() {
+        let mut config = create_test_config();
+        config.storage.blob_chunk_size = 64 * 1024;
+        let blob_storage = BlobStorage::new(&config).unwrap();
+
+        let original = noise(256 * 1024, 1);
+        // Same file with a few bytes inserted near the start: only the chunks around the edit differ
+        let mut edited = original.clone();
+        edited.splice(1000..1000, b"patch".iter().copied());
+        let first = blob_storage.store_blob("level", &original).unwrap();
+        let second = blob_storage.store_blob("level_v2", &edited).unwrap();
+
+        assert_eq!(blob_storage.get_blob("level", &first).unwrap(), original);
+        assert_eq!(blob_storage.get_blob("level_v2", &second).unwrap(), edited);
+        let stats = blob_storage.stats().unwrap();
+        assert_eq!(stats.blobs, 2);
+        assert_eq!(stats.logical_bytes, (original.len() + edited.len()) as u64);
+        assert!(stats.unique_bytes < original.len() as u64 + 64 * 1024);
+        assert!(stats.dedup_ratio > 1.5);
+        assert_eq!(blob_storage.unique_bytes([&first, &second]), stats.unique_bytes);
+        // Blobs this node does not hold are estimated
+        assert_eq!(blob_storage.unique_bytes([&first, &[7u8; 32]]), original.len() as u64 + BLOB_SIZE_ESTIMATE);
+
+        // Shared chunks survive deleting one blob; the last delete removes every chunk
+        blob_storage.delete_blob("level", &first).unwrap();
+        assert_eq!(blob_storage.get_blob("level_v2", &second).unwrap(), edited);
+        blob_storage.delete_blob("level_v2", &second).unwrap();
+        assert_eq!(blob_storage.stats().unwrap(), BlobStats { dedup_ratio: 1.0, ..BlobStats::default() });
+        assert_eq!(std::fs::read_dir(config.storage.blob_storage_path.join("chunks")).unwrap().count(), 0);
+
+        std::fs::remove_dir_all(&config.storage.blob_storage_path).ok();
+    }
+
+    #[test]
+    fn test_blob_keys_share_one_copy() {
+        let mut config = create_test_config();
+        config.storage.blob_chunk_size = 4 * 1024;
+        config.storage.blob_chunking = BlobChunking::Fixed;
+        let blob_storage = BlobStorage::new(&config).unwrap();
+
+        let data = noise(10 * 1024, 2);
+        let hash = blob_storage.store_blob("texture", &data).unwrap();
+        assert_eq!(blob_storage.store_blob("texture_copy", &data).unwrap(), hash);
+        let stats = blob_storage.stats().unwrap();
+        assert_eq!((stats.blobs, stats.chunks, stats.unique_bytes), (1, 3, data.len() as u64));
+
+        // Still readable through the remaining key
+        blob_storage.delete_blob("texture", &hash).unwrap();
+        assert_eq!(blob_storage.get_blob("texture_copy", &hash).unwrap(), data);
+
+        // A damaged chunk is detected on read
+        let chunk_dir = config.storage.blob_storage_path.join("chunks");
+        let chunk = std::fs::read_dir(&chunk_dir).unwrap().next().unwrap().unwrap().path();
+        std::fs::write(&chunk, b"damaged").unwrap();
+        assert!(blob_storage.get_blob("texture_copy", &hash).is_err());
+
+        std::fs::remove_dir_all(&config.storage.blob_storage_path).ok();
+    }
+    
+    #[test]
+    fn test_blob_pieces_and_content_type() {
+        let mut config = create_test_config();
+        config.storage.blob_chunk_size = 1024;
+        let blob_storage = BlobStorage::new(&config).unwrap();
+
+        // Legacy single-file blob: read in slices, type detected from its signature
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        png.extend(noise(3000, 3));
+        let legacy_hash = sha256(&png);
+        std::fs::write(blob_storage.get_blob_path("icon", &legacy_hash), &png).unwrap();
+        let reader = blob_storage.open_blob("icon", &legacy_hash).unwrap();
+        assert_eq!((reader.size, reader.content_type.as_str()), (3008, "image/png"));
+        let pieces = reader.pieces(500, 2600);
+        assert_eq!(pieces.len(), 3);
+        let range: Vec<u8> = pieces.iter().flat_map(|piece| piece.read().unwrap()).collect();
+        assert_eq!(range, png[500..2600]);
+
+        // The stored hint wins over detection
+        let hash = blob_storage.store_blob_with_content_type("sheet", &png, Some("image/x-sprite-sheet")).unwrap();
+        assert_eq!(blob_storage.open_blob("sheet", &hash).unwrap().content_type, "image/x-sprite-sheet");
+        let hash = blob_storage.store_blob("data", b"plain bytes").unwrap();
+        assert_eq!(blob_storage.open_blob("data", &hash).unwrap().content_type, DEFAULT_CONTENT_TYPE);
+        assert!(blob_storage.store_blob_with_content_type("bad", b"x", Some("text\nhtml")).is_err());
+
+        std::fs::remove_dir_all(&config.storage.blob_storage_path).ok();
+    }
+    
     #[test]
     fn test_condense_with_blob_storage() {
         let config = create_test_config();
@@ -827,10 +1795,10 @@ mod tests {
             HashMap::new(),
         );
 
-        asset.add_attribute("damage".to_string(), "10".to_string(), Some(0.5));
+        asset.add_attribute("damage".to_string(), "10".into(), Some(0.5));
         
         let attr = asset.get_attribute("damage").unwrap();
-        assert_eq!(attr.value, "10");
+        assert_eq!(attr.value.to_string(), "10");
         assert_eq!(attr.rarity, Some(0.5));
     }
 
@@ -845,11 +1813,11 @@ mod tests {
             HashMap::new(),
         );
 
-        asset.add_attribute("damage".to_string(), "10".to_string(), None);
-        asset.update_attribute("damage", "15".to_string()).unwrap();
+        asset.add_attribute("damage".to_string(), "10".into(), None);
+        asset.update_attribute("damage", "15".into()).unwrap();
         
         let attr = asset.get_attribute("damage").unwrap();
-        assert_eq!(attr.value, "15");
+        assert_eq!(attr.value.to_string(), "15");
     }
 
     #[test]
@@ -863,7 +1831,7 @@ mod tests {
             HashMap::new(),
         );
 
-        asset.add_attribute("damage".to_string(), "10".to_string(), None);
+        asset.add_attribute("damage".to_string(), "10".into(), None);
         assert!(asset.get_attribute("damage").is_some());
         
         asset.remove_attribute("damage").unwrap();
@@ -882,7 +1850,7 @@ mod tests {
             DensityLevel::Ethereal,
             HashMap::new(),
         );
-        asset1.add_attribute("power".to_string(), "10".to_string(), Some(0.3));
+        asset1.add_attribute("power".to_string(), "10".into(), Some(0.3));
 
         let mut asset2 = MistbornAsset::create(
             asset_id_2,
@@ -890,13 +1858,13 @@ mod tests {
             DensityLevel::Ethereal,
             HashMap::new(),
         );
-        asset2.add_attribute("power".to_string(), "20".to_string(), Some(0.8)); // Higher rarity
+        asset2.add_attribute("power".to_string(), "20".into(), Some(0.8)); // Higher rarity
 
         asset1.merge(&asset2).unwrap();
         
         // Should keep attribute with higher rarity
         let attr = asset1.get_attribute("power").unwrap();
-        assert_eq!(attr.value, "20");
+        assert_eq!(attr.value.to_string(), "20");
         assert_eq!(attr.rarity, Some(0.8));
     }
 
@@ -911,9 +1879,9 @@ mod tests {
             HashMap::new(),
         );
 
-        asset.add_attribute("component1_power".to_string(), "10".to_string(), None);
-        asset.add_attribute("shared_rarity".to_string(), "epic".to_string(), Some(0.9));
-        asset.add_attribute("power".to_string(), "100".to_string(), None);
+        asset.add_attribute("component1_power".to_string(), "10".into(), None);
+        asset.add_attribute("shared_rarity".to_string(), "epic".into(), Some(0.9));
+        asset.add_attribute("power".to_string(), "100".into(), None);
 
         let components = asset.split(vec!["component1".to_string(), "component2".to_string()]).unwrap();
         
@@ -954,4 +1922,81 @@ mod tests {
         add.insert("_components".to_string(), "a,b".to_string());
         assert!(calculate_asset_operation_gas(&config, &AssetAction::Split, &data, Some(&add)) > 0);
     }
+
+    #[test]
+    fn test_merge_policies() {
+        use crate::types::{AssetData, Attribute, DensityLevel};
+        use std::collections::HashMap;
+
+        let asset = |lore: &str, power: &str, rarity: f64, blob: u8| {
+            let data = AssetData {
+                density: DensityLevel::Ethereal,
+                metadata: HashMap::from([("lore".to_string(), lore.to_string())]),
+                attributes: vec![Attribute { name: "power".to_string(), value: power.into(), rarity: Some(rarity) }],
+                game_id: None,
+                owner: [1u8; 32],
+            };
+            (data, HashMap::from([("model".to_string(), [blob; 32])]))
+        };
+        let (other, other_blobs) = asset("dragon", "9", 0.9, 2);
+        let merge = |policy: MergePolicy| {
+            let (mut data, mut blobs) = asset("knight", "5", 0.5, 1);
+            merge_asset_contents(&mut data, &mut blobs, &other, &other_blobs, policy)
+                .map(|conflicts| (data.metadata["lore"].clone(), data.attributes[0].value.to_string(), blobs["model"][0], conflicts))
+        };
+
+        let (lore, power, blob, conflicts) = merge(MergePolicy::HigherRarity).unwrap();
+        assert_eq!((lore.as_str(), power.as_str(), blob), ("knight", "9", 1));
+        assert_eq!(conflicts, ["attribute.power", "blob.model", "metadata.lore"]);
+
+        let (lore, power, blob, _) = merge(MergePolicy::PreferSource).unwrap();
+        assert_eq!((lore.as_str(), power.as_str(), blob), ("knight", "5", 1));
+
+        let (lore, power, blob, _) = merge(MergePolicy::PreferOther).unwrap();
+        assert_eq!((lore.as_str(), power.as_str(), blob), ("dragon", "9", 2));
+
+        let (lore, power, blob, _) = merge(MergePolicy::Concatenate).unwrap();
+        assert_eq!((lore.as_str(), power.as_str(), blob), ("knight|dragon", "5|9", 1));
+
+        let err = merge(MergePolicy::FailOnConflict).unwrap_err().to_string();
+        assert!(err.contains("metadata.lore"), "got: {}", err);
+
+        assert_eq!(MergePolicy::parse("prefer-other").unwrap(), MergePolicy::PreferOther);
+        assert!(MergePolicy::parse("newest").is_err());
+    }
+
+    #[test]
+    fn test_condense_gas_charges_blobs_and_new_bytes() {
+        use crate::types::{AssetAction, AssetData, DensityLevel};
+        use std::collections::HashMap;
+
+        let config = Config::default();
+        let gas = &config.asset_gas;
+        let mut metadata = HashMap::new();
+        metadata.insert("lore".to_string(), "x".repeat(100));
+        let mut data = AssetData {
+            density: DensityLevel::Dense,
+            metadata,
+            attributes: vec![],
+            game_id: None,
+            owner: [1u8; 32],
+        };
+        let without_blobs = calculate_asset_operation_gas(&config, &AssetAction::Condense, &data, None);
+
+        data.metadata.insert(
+            "_blob_refs".to_string(),
+            serde_json::json!({ "model": hex::encode([1u8; 32]), "texture": hex::encode([2u8; 32]) }).to_string(),
+        );
+        let breakdown = asset_operation_gas_breakdown(&config, &AssetAction::Condense, &data, None);
+        let line = |item: &str| breakdown.iter().find(|l| l.item == item).unwrap().clone();
+        assert_eq!(line("base").gas, gas.condense_base * 2);
+        assert_eq!(line("metadata_bytes").quantity, 100);
+        assert_eq!(line("metadata_bytes").gas, 100 * gas.condense_per_byte);
+        assert_eq!(line("blobs").quantity, 2);
+        assert_eq!(line("blobs").gas, 2 * gas.condense_per_blob);
+
+        let total = calculate_asset_operation_gas(&config, &AssetAction::Condense, &data, None);
+        assert_eq!(total, breakdown.iter().map(|l| l.gas).sum::<u64>());
+        assert_eq!(total, without_blobs + 2 * gas.condense_per_blob);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,175 @@
+//! Block storage backed by the node database
+//!
+//! Blocks are kept only in the database, in their wire encoding in the `blocks` tree keyed by
+//! height. Lookups read the stored bytes as an `IVec`, which shares sled's page cache instead
+//! of copying (other backends hand out their own buffer), and decode only what is asked for:
+//! the header is the first field of an encoded block, so header reads never touch the
+//! transactions. Full blocks are decoded into `Arc<Block>`, and the most recently read ones
+//! are cached so hot paths (sync requests, explorers polling the tip) share one decoded copy
+//! instead of cloning it per query.
+//!
+//! The height key is the height index: a block is found by height with one keyed read, and a
+//! run of heights with one ordered range scan. Hashes map to heights through `heights` (and
+//! the `indexes` tree it is rebuilt from).
+
+use std::collections::VecDeque;
+use std::ops::Bound;
+use std::sync::Arc;
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use sled::IVec;
+use crate::error::Result;
+use crate::storage::StorageTree;
+use crate::types::{Block, BlockHeader, Hash, WireFormat, WIRE_MAGIC};
+
+/// Number of decoded blocks kept for repeated reads
+pub const BLOCK_CACHE_SIZE: usize = 32;
+
+/// Key of the block at `height` in the `blocks` tree (big-endian, so keys sort by height)
+pub(crate) fn block_key(height: u64) -> [u8; 8] {
+    height.to_be_bytes()
+}
+
+/// Decode a stored block, wire encoded or from before the wire format existed (plain bincode
+/// in the version-1 layout, rewritten by the `blocks_current_wire` migration)
+pub(crate) fn decode_block(bytes: &[u8]) -> Result<Block> {
+    if bytes.starts_with(&WIRE_MAGIC) {
+        Block::from_wire(bytes)
+    } else {
+        Block::decode_body(1, bytes)
+    }
+}
+
+/// Decode only the header of a stored block
+pub(crate) fn decode_header(bytes: &[u8]) -> Result<BlockHeader> {
+    if bytes.starts_with(&WIRE_MAGIC) {
+        Block::header_from_wire(bytes)
+    } else {
+        // Legacy bincode: the header is a prefix
+        Block::header_from_body(1, bytes)
+    }
+}
+
+/// Applied blocks, read lazily from the database
+#[derive(Debug)]
+pub struct BlockStore {
+    tree: Arc<dyn StorageTree>,
+    /// Height of each applied block, by hash
+    heights: DashMap<Hash, u64>,
+    /// Recently decoded blocks, most recent last
+    cache: Mutex<VecDeque<Arc<Block>>>,
+}
+
+impl BlockStore {
+    pub fn new(tree: Arc<dyn StorageTree>) -> Self {
+        Self {
+            tree,
+            heights: DashMap::new(),
+            cache: Mutex::new(VecDeque::with_capacity(BLOCK_CACHE_SIZE)),
+        }
+    }
+
+    /// Index a block whose encoding has been written under `block_key(height)`
+    pub fn record(&self, hash: Hash, height: u64) {
+        self.heights.insert(hash, height);
+    }
+
+    /// Height of a block, if it was applied
+    pub fn height_of(&self, hash: &Hash) -> Option<u64> {
+        self.heights.get(hash).map(|height| *height)
+    }
+
+    pub fn contains(&self, hash: &Hash) -> bool {
+        self.heights.contains_key(hash)
+    }
+
+    /// Stored encoding of the block at `height`, without copying it out of the database cache
+    pub fn raw_by_height(&self, height: u64) -> Option<IVec> {
+        match self.tree.get(&block_key(height)) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                tracing::warn!("Failed to read block {}: {}", height, e);
+                None
+            }
+        }
+    }
+
+    pub fn get(&self, hash: &Hash) -> Option<Arc<Block>> {
+        let height = self.height_of(hash)?;
+        self.get_by_height(height).filter(|block| block.header.hash == *hash)
+    }
+
+    pub fn get_by_height(&self, height: u64) -> Option<Arc<Block>> {
+        if let Some(block) = self.cache.lock().iter().find(|block| block.header.height == height) {
+            return Some(block.clone());
+        }
+        let bytes = self.raw_by_height(height)?;
+        let block = match decode_block(&bytes) {
+            Ok(block) => Arc::new(block),
+            Err(e) => {
+                tracing::warn!("Stored block {} does not decode: {}", height, e);
+                return None;
+            }
+        };
+        let mut cache = self.cache.lock();
+        if cache.len() == BLOCK_CACHE_SIZE {
+            cache.pop_front();
+        }
+        cache.push_back(block.clone());
+        Some(block)
+    }
+
+    /// Blocks with heights in `start..=end`, in height order, read in one range scan. Blocks
+    /// found in the cache are shared; the others are decoded without being cached, so a long
+    /// range does not evict the tip.
+    pub fn range_by_height(&self, start: u64, end: u64) -> Vec<Arc<Block>> {
+        if start > end {
+            return Vec::new();
+        }
+        let cached: Vec<Arc<Block>> = self.cache.lock().iter()
+            .filter(|block| (start..=end).contains(&block.header.height))
+            .cloned()
+            .collect();
+        let (from, to) = (block_key(start), block_key(end));
+        let mut blocks = Vec::new();
+        for item in self.tree.range(Bound::Included(&from[..]), Bound::Included(&to[..])) {
+            let (key, bytes) = match item {
+                Ok(entry) => entry,
+                Err(e) => {
+                    tracing::warn!("Failed to read blocks {}-{}: {}", start, end, e);
+                    break;
+                }
+            };
+            let height = key.as_ref().try_into().map(u64::from_be_bytes).unwrap_or_default();
+            if let Some(block) = cached.iter().find(|block| block.header.height == height) {
+                blocks.push(block.clone());
+                continue;
+            }
+            match decode_block(&bytes) {
+                Ok(block) => blocks.push(Arc::new(block)),
+                Err(e) => tracing::warn!("Stored block {} does not decode: {}", height, e),
+            }
+        }
+        blocks
+    }
+
+    /// Header of a block, decoded without its transactions
+    pub fn header(&self, hash: &Hash) -> Option<BlockHeader> {
+        let height = self.height_of(hash)?;
+        self.header_by_height(height).filter(|header| header.hash == *hash)
+    }
+
+    pub fn header_by_height(&self, height: u64) -> Option<BlockHeader> {
+        if let Some(block) = self.cache.lock().iter().find(|block| block.header.height == height) {
+            return Some(block.header.clone());
+        }
+        let bytes = self.raw_by_height(height)?;
+        match decode_header(&bytes) {
+            Ok(header) => Some(header),
+            Err(e) => {
+                tracing::warn!("Stored block header {} does not decode: {}", height, e);
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,127 @@
+//! Asset breeding
+//!
+//! Two assets of a collection with a breeding rule (`economy.breeding`) produce a child with
+//! a `Breed` transaction. The parents stay with the breeder, and each has to wait the rule's
+//! `cooldown_blocks` before it breeds again.
+//!
+//! The child hatches at the end of block `hatch_height`, `hatch_delay` blocks after the
+//! breeding. Its attributes are drawn from the parents' with that block's hash as the
+//! randomness, which nobody knows when the breeding is signed. A child whose collection is
+//! frozen by a season when it is due hatches in the first block after the collection thaws.
+//! The child is minted to the breeder and linked to both parents in the composition graph.
+
+use serde::{Deserialize, Serialize};
+use crate::types::{Address, Attribute, DensityLevel, Hash};
+
+/// Status of a breeding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreedingStatus {
+    /// Waiting for its hatch block
+    Pending,
+    /// The child was minted
+    Hatched,
+}
+
+/// Breeding of two parents, pending until its child hatches
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Breeding {
+    /// Also the id of the child asset
+    pub id: Hash,
+    pub breeder: Address,
+    pub game_id: String,
+    pub parents: [Hash; 2],
+    /// Attributes of the parents when they were bred (the child is drawn from these)
+    pub parent_attributes: [Vec<Attribute>; 2],
+    /// Density of the child: the lower of the parents'
+    pub density: DensityLevel,
+    pub bred_at_height: u64,
+    /// Block whose hash decides the child (moved past a season freeze)
+    pub hatch_height: u64,
+    pub hatched_at_height: Option<u64>,
+}
+
+impl Breeding {
+    pub fn status(&self) -> BreedingStatus {
+        match self.hatched_at_height {
+            Some(_) => BreedingStatus::Hatched,
+            None => BreedingStatus::Pending,
+        }
+    }
+}
+
+/// Lower of two densities
+pub fn lower_density(a: DensityLevel, b: DensityLevel) -> DensityLevel {
+    if a.max_size() <= b.max_size() { a } else { b }
+}
+
+/// Attributes of the child of a breeding
+///
+/// Attribute names are taken from the first parent, then the second, in order (a name
+/// repeated within a parent counts once). For the `i`-th name, the first byte of
+/// sha256("haze_breed_roll" || beacon || breeding_id || i as u32 LE) decides: with an even
+/// byte the child inherits the first parent's value, with an odd one the second's. A name
+/// only one parent has is inherited on an even byte and dropped on an odd one.
+pub fn derive_child_attributes(parents: &[Vec<Attribute>; 2], beacon: &Hash, breeding_id: &Hash) -> Vec<Attribute> {
+    let mut names: Vec<&str> = Vec::new();
+    for attribute in parents.iter().flatten() {
+        if !names.contains(&attribute.name.as_str()) {
+            names.push(&attribute.name);
+        }
+    }
+    let find = |parent: &[Attribute], name: &str| parent.iter().find(|attribute| attribute.name == name).cloned();
+    names
+        .iter()
+        .enumerate()
+        .filter_map(|(i, name)| {
+            let mut data = b"haze_breed_roll".to_vec();
+            data.extend_from_slice(beacon);
+            data.extend_from_slice(breeding_id);
+            data.extend_from_slice(&(i as u32).to_le_bytes());
+            let first = crate::types::sha256(&data)[0].is_multiple_of(2);
+            match (find(&parents[0], name), find(&parents[1], name)) {
+                (Some(a), Some(b)) => Some(if first { a } else { b }),
+                (Some(only), None) | (None, Some(only)) => first.then_some(only),
+                (None, None) => None,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attribute(name: &str, value: &str) -> Attribute {
+        Attribute { name: name.to_string(), value: value.into(), rarity: None }
+    }
+
+    #[test]
+    fn test_child_attributes_come_from_parents() {
+        let parents = [
+            vec![attribute("color", "red"), attribute("wings", "yes"), attribute("color", "blue")],
+            vec![attribute("color", "green"), attribute("horn", "long")],
+        ];
+        let derive = |beacon: u8| derive_child_attributes(&parents, &[beacon; 32], &[7u8; 32]);
+
+        let mut colors = std::collections::HashSet::new();
+        for beacon in 0..32u8 {
+            let child = derive(beacon);
+            // Same inputs, same child
+            assert_eq!(format!("{:?}", child), format!("{:?}", derive(beacon)));
+            let color = child.iter().find(|a| a.name == "color").expect("shared attributes are always inherited");
+            let color = color.value.to_string();
+            assert!(color == "red" || color == "green");
+            colors.insert(color);
+            assert!(child.iter().all(|a| ["color", "wings", "horn"].contains(&a.name.as_str())));
+            assert!(child.len() <= 3);
+        }
+        assert_eq!(colors.len(), 2);
+    }
+
+    #[test]
+    fn test_lower_density() {
+        assert_eq!(lower_density(DensityLevel::Dense, DensityLevel::Light), DensityLevel::Light);
+        assert_eq!(lower_density(DensityLevel::Ethereal, DensityLevel::Core), DensityLevel::Ethereal);
+    }
+}
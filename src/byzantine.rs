@@ -0,0 +1,203 @@
+//! Byzantine fault injection (test mode) and misbehavior evidence
+//!
+//! A node can be switched into a Byzantine behavior from tests to check that honest
+//! nodes notice and reject it:
+//! - **Equivocate**: propose a second, conflicting block at the same height
+//! - **WithholdBlocks**: stop gossiping blocks and answer sync requests with nothing
+//! - **InvalidReferences**: reference DAG vertices that do not exist
+//!
+//! The behavior is never read from the config file; it is only set through
+//! `ConsensusEngine::set_byzantine_behavior`. Honest nodes record equivocation as
+//! evidence so it can be punished once slashing exists.
+//!
+//! Nodes also record state divergence: a block whose header commits to a state root other
+//! than the one this node computed for the same parent state. The waves holding such blocks
+//! are not finalized (see `ConsensusEngine::finalize_wave`).
+
+use std::collections::{BTreeSet, HashMap};
+use parking_lot::RwLock;
+use serde::Serialize;
+use crate::types::{Address, Hash};
+
+/// Misbehavior injected into a node (tests only)
+#[allow(dead_code)] // Constructed by tests through the library, never by the node binary
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ByzantineBehavior {
+    #[default]
+    Honest,
+    /// Produce a conflicting twin for every proposed block
+    Equivocate,
+    /// Neither gossip blocks nor serve them to syncing peers
+    WithholdBlocks,
+    /// Add a reference to an unknown DAG vertex to every proposed block
+    InvalidReferences,
+}
+
+/// Two different blocks proposed by the same validator at the same height
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EquivocationEvidence {
+    pub validator: Address,
+    pub height: u64,
+    /// Block accepted first
+    pub first: Hash,
+    /// Conflicting block that was rejected
+    pub second: Hash,
+}
+
+/// Tracks the block each validator proposed per height
+#[derive(Debug, Default)]
+pub struct EquivocationDetector {
+    proposals: RwLock<HashMap<(Address, u64), Hash>>,
+    evidence: RwLock<Vec<EquivocationEvidence>>,
+}
+
+impl EquivocationDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a proposal; returns evidence if the validator already proposed a
+    /// different block at this height
+    pub fn observe(&self, validator: Address, height: u64, hash: Hash) -> Option<EquivocationEvidence> {
+        let first = *self.proposals.write().entry((validator, height)).or_insert(hash);
+        if first == hash {
+            return None;
+        }
+        let evidence = EquivocationEvidence { validator, height, first, second: hash };
+        let mut recorded = self.evidence.write();
+        if !recorded.contains(&evidence) {
+            recorded.push(evidence.clone());
+        }
+        Some(evidence)
+    }
+
+    /// Forget a proposal whose block was rejected, so the validator can propose another
+    /// block at that height
+    pub fn forget(&self, validator: Address, height: u64, hash: Hash) {
+        let mut proposals = self.proposals.write();
+        if proposals.get(&(validator, height)) == Some(&hash) {
+            proposals.remove(&(validator, height));
+        }
+    }
+
+    /// Forget proposals at or below the finalized height (those blocks are rejected anyway)
+    pub fn prune(&self, finalized_height: u64) {
+        self.proposals.write().retain(|(_, height), _| *height > finalized_height);
+    }
+
+    /// All equivocations seen so far
+    pub fn evidence(&self) -> Vec<EquivocationEvidence> {
+        self.evidence.read().clone()
+    }
+}
+
+/// A block whose recorded state root differs from the one this node computed before applying it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DivergenceIncident {
+    pub height: u64,
+    pub block_hash: Hash,
+    pub wave: u64,
+    /// Validator that produced the block
+    pub validator: Address,
+    /// State root in the block header (the producer's parent state)
+    pub expected: Hash,
+    /// Root of this node's state at `height - 1`
+    pub local: Hash,
+    /// Unix time the incident was recorded
+    pub detected_at: i64,
+}
+
+/// State divergence incidents and the waves they halt
+#[derive(Debug, Default)]
+pub struct DivergenceMonitor {
+    incidents: RwLock<Vec<DivergenceIncident>>,
+    halted_waves: RwLock<BTreeSet<u64>>,
+}
+
+impl DivergenceMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a divergent block and halt finalization of its wave
+    pub fn record(&self, incident: DivergenceIncident) {
+        self.halted_waves.write().insert(incident.wave);
+        let mut incidents = self.incidents.write();
+        if !incidents.iter().any(|recorded| recorded.block_hash == incident.block_hash) {
+            incidents.push(incident);
+        }
+    }
+
+    /// Whether a wave holds a divergent block
+    pub fn is_halted(&self, wave: u64) -> bool {
+        self.halted_waves.read().contains(&wave)
+    }
+
+    /// Waves not finalized because of divergence, ascending
+    pub fn halted_waves(&self) -> Vec<u64> {
+        self.halted_waves.read().iter().copied().collect()
+    }
+
+    /// All incidents seen so far, oldest first
+    pub fn incidents(&self) -> Vec<DivergenceIncident> {
+        self.incidents.read().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_conflicting_proposals() {
+        let detector = EquivocationDetector::new();
+        let validator = [1u8; 32];
+
+        assert!(detector.observe(validator, 5, [0xaa; 32]).is_none());
+        // Same block again is not equivocation
+        assert!(detector.observe(validator, 5, [0xaa; 32]).is_none());
+        // Other validators and heights are independent
+        assert!(detector.observe([2u8; 32], 5, [0xbb; 32]).is_none());
+        assert!(detector.observe(validator, 6, [0xcc; 32]).is_none());
+
+        let evidence = detector.observe(validator, 5, [0xdd; 32]).unwrap();
+        assert_eq!(evidence.first, [0xaa; 32]);
+        assert_eq!(evidence.second, [0xdd; 32]);
+        detector.observe(validator, 5, [0xdd; 32]);
+        assert_eq!(detector.evidence().len(), 1);
+
+        detector.prune(5);
+        assert!(detector.observe(validator, 5, [0xee; 32]).is_none());
+
+        // Forgetting a rejected block frees its height; other blocks are kept
+        detector.forget(validator, 6, [0xff; 32]);
+        assert!(detector.observe(validator, 6, [0xff; 32]).is_some());
+        detector.forget(validator, 5, [0xee; 32]);
+        assert!(detector.observe(validator, 5, [0x11; 32]).is_none());
+    }
+
+    #[test]
+    fn test_divergence_halts_wave() {
+        let monitor = DivergenceMonitor::new();
+        let incident = DivergenceIncident {
+            height: 4,
+            block_hash: [4u8; 32],
+            wave: 2,
+            validator: [1u8; 32],
+            expected: [0xaa; 32],
+            local: [0xbb; 32],
+            detected_at: 0,
+        };
+        assert!(!monitor.is_halted(2));
+        monitor.record(incident.clone());
+        // The same block seen again is one incident
+        monitor.record(incident.clone());
+        monitor.record(DivergenceIncident { block_hash: [5u8; 32], height: 5, ..incident });
+
+        assert!(monitor.is_halted(2));
+        assert!(!monitor.is_halted(3));
+        assert_eq!(monitor.halted_waves(), vec![2]);
+        assert_eq!(monitor.incidents().len(), 2);
+    }
+}
@@ -1,6 +1,7 @@
 //! Configuration for HAZE node
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::path::PathBuf;
 use crate::error::{HazeError, Result};
 
@@ -14,6 +15,11 @@ pub struct ApiConfig {
     
     /// Enable WebSocket support
     pub enable_websocket: bool,
+
+    /// Hex SHA-256 hashes of the API keys allowed to use node admin endpoints such as
+    /// `/api/v1/admin/divergence` (tenant admin keys are accepted as well)
+    #[serde(default)]
+    pub admin_key_hashes: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,7 +27,9 @@ pub struct Config {
     /// Node identity
     pub node_id: String,
 
-    /// Chain identifier; transactions with chain_id set are only accepted when they match.
+    /// Chain identifier; the network id every signature and block hash commits to is derived
+    /// from it (see `types::network_id`). Must be non-zero outside dev nodes: a new config file
+    /// gets a random one, to be shared by every node of the network.
     #[serde(default)]
     pub chain_id: u64,
 
@@ -48,6 +56,1090 @@ pub struct Config {
     
     /// Logging level
     pub log_level: String,
+
+    /// Custodial player accounts (disabled by default)
+    #[serde(default)]
+    pub custody: CustodyConfig,
+
+    /// Sign-in-with-HAZE challenges and session tokens
+    #[serde(default)]
+    pub sign_in: SignInConfig,
+
+    /// Encrypted direct messages between accounts (disabled by default)
+    #[serde(default)]
+    pub messaging: MessagingConfig,
+
+    /// Second native token carrying governance weight (disabled by default)
+    #[serde(default)]
+    pub governance_token: GovernanceTokenConfig,
+
+    /// Multi-tenant node mode (disabled by default)
+    #[serde(default)]
+    pub tenants: TenantsConfig,
+
+    /// Per-API-key usage metering for billing (disabled by default)
+    #[serde(default)]
+    pub metering: MeteringConfig,
+
+    /// Read replica mode (disabled by default)
+    #[serde(default)]
+    pub replica: ReplicaConfig,
+
+    /// Economic zones assets can be assigned to
+    #[serde(default)]
+    pub economy: EconomyConfig,
+
+    /// Opt-in reporting to a network dashboard (disabled by default)
+    #[serde(default)]
+    pub telemetry: TelemetryConfig,
+
+    /// Upgrade signaling and feature activation heights
+    #[serde(default)]
+    pub features: FeaturesConfig,
+
+    /// Local development node settings (disabled by default; see `haze --dev`)
+    #[serde(default)]
+    pub dev: DevConfig,
+
+    /// Role of the node and the subsystems it runs (a validator by default)
+    #[serde(default)]
+    pub role: RoleConfig,
+}
+
+/// Economic zones, their fees and the pool price oracle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EconomyConfig {
+    /// Zones assets of a collection can be migrated into (see docs/API_TRANSACTIONS.md#economic-zones)
+    pub zones: Vec<ZoneConfig>,
+
+    /// Price observations kept per liquidity pool, one per block (bounds the TWAP window)
+    pub twap_max_observations: usize,
+
+    /// TWAP window (blocks) used when a request does not name one
+    pub twap_default_window: u64,
+
+    /// Per-block, per-account limits of the order books
+    pub order_book: OrderBookLimits,
+
+    /// Marketplace fee on order book trades, deducted from the seller's proceeds (basis points)
+    pub trade_fee_bps: u64,
+
+    /// Referral programs sharing fees of a collection with the referrers of its players
+    pub referral_programs: Vec<ReferralProgram>,
+
+    /// Blocks a past-due subscription keeps retrying its charge before it lapses
+    pub subscription_grace_blocks: u64,
+
+    /// Blocks between opening a loot box and the block whose hash decides its outcome
+    pub loot_beacon_delay: u64,
+
+    /// Blocks after the beacon block the operator has to reveal a loot box before its
+    /// bond can be claimed by the player
+    pub loot_reveal_window: u64,
+
+    /// Collections whose assets can be bred, with their fees and cooldowns
+    pub breeding: Vec<BreedingRule>,
+
+    /// Collections whose assets can be upgraded, with their costs and odds
+    pub upgrades: Vec<UpgradeRule>,
+}
+
+impl Default for EconomyConfig {
+    fn default() -> Self {
+        Self {
+            zones: Vec::new(),
+            twap_max_observations: crate::economy::DEFAULT_TWAP_MAX_OBSERVATIONS,
+            twap_default_window: 30,
+            order_book: OrderBookLimits::default(),
+            trade_fee_bps: 0,
+            referral_programs: Vec::new(),
+            subscription_grace_blocks: 100,
+            loot_beacon_delay: 1,
+            loot_reveal_window: 100,
+            breeding: Vec::new(),
+            upgrades: Vec::new(),
+        }
+    }
+}
+
+/// Referral program of a collection (game_id)
+///
+/// Players bind themselves to a referrer with a `SetReferrer` transaction. From then on
+/// `share_bps` of the selected fees they pay is credited to the referrer instead of going
+/// to the fee pool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReferralProgram {
+    pub game_id: String,
+    /// Share of each fee paid to the referrer (basis points, at most `MAX_REFERRAL_SHARE_BPS`)
+    pub share_bps: u64,
+    /// Share marketplace fees (`trade_fee_bps` of order book sales, paid by the seller)
+    #[serde(default = "default_true")]
+    pub marketplace: bool,
+    /// Share crafting fees (gas of condense, evaporate, merge and split, and breeding and
+    /// upgrade fees)
+    #[serde(default = "default_true")]
+    pub crafting: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Breeding rule of a collection (game_id)
+///
+/// Two assets of the collection can produce a child with a `Breed` transaction (see
+/// `crate::breeding`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BreedingRule {
+    pub game_id: String,
+    /// HAZE the breeder pays per breeding on top of the transaction fee (a crafting fee)
+    #[serde(default)]
+    pub fee: u64,
+    /// Blocks a parent waits after breeding before it can breed again
+    #[serde(default)]
+    pub cooldown_blocks: u64,
+    /// Blocks between a breeding and the block whose hash decides the child
+    #[serde(default = "default_hatch_delay")]
+    pub hatch_delay: u64,
+}
+
+fn default_hatch_delay() -> u64 {
+    1
+}
+
+/// Upgrade rule of a collection (game_id)
+///
+/// An asset of the collection can have a numeric attribute boosted with an `Upgrade`
+/// transaction, at the risk of losing the asset (see `crate::upgrade`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpgradeRule {
+    pub game_id: String,
+    /// Attribute the upgrade boosts (a missing attribute counts as 0)
+    pub attribute: String,
+    /// Added to the attribute on success
+    #[serde(default = "default_upgrade_boost")]
+    pub boost: u64,
+    /// HAZE the owner pays per upgrade on top of the transaction fee (a crafting fee)
+    #[serde(default)]
+    pub fee: u64,
+    /// Assets of the collection burned per upgrade
+    #[serde(default)]
+    pub catalysts: u32,
+    /// Chance of success, in basis points
+    pub success_bps: u64,
+    /// Chance the asset is destroyed, in basis points (the rest fails harmlessly)
+    #[serde(default)]
+    pub destroy_bps: u64,
+    /// Blocks between an upgrade and the block whose hash decides its outcome
+    #[serde(default = "default_roll_delay")]
+    pub roll_delay: u64,
+}
+
+fn default_upgrade_boost() -> u64 {
+    1
+}
+
+fn default_roll_delay() -> u64 {
+    1
+}
+
+/// Upper bound for `EconomyConfig::trade_fee_bps` (10%)
+pub const MAX_TRADE_FEE_BPS: u64 = 1_000;
+
+/// Upper bound for `ReferralProgram::share_bps` (50%)
+pub const MAX_REFERRAL_SHARE_BPS: u64 = 5_000;
+
+/// Per-block, per-account limits enforced while a block is applied
+///
+/// They stop single-block round trips that only pay off because nothing else can
+/// happen in between (flash-loan style), e.g. buying an asset and re-listing it, or
+/// posting liquidity to the book and pulling it again before anyone can trade on it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OrderBookLimits {
+    /// Reject a sell order listing an asset its owner bought on a book in the same block
+    pub deny_relist_in_block: bool,
+    /// Reject cancelling an order in the block it was placed in
+    pub deny_cancel_in_block: bool,
+    /// Orders one account may place per block (0 = unlimited)
+    pub max_orders_per_block: u32,
+}
+
+impl Default for OrderBookLimits {
+    fn default() -> Self {
+        Self {
+            deny_relist_in_block: true,
+            deny_cancel_in_block: true,
+            max_orders_per_block: 0,
+        }
+    }
+}
+
+/// An economic zone of a collection (game_id)
+///
+/// Entry and exit fees are paid by the asset owner when an asset moves into or out of
+/// the zone and credited to the zone treasury (`economy::zone_treasury_address`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZoneConfig {
+    pub game_id: String,
+    pub zone_id: String,
+    /// Fee charged when an asset enters the zone
+    #[serde(default)]
+    pub entry_fee: u64,
+    /// Fee charged when an asset leaves the zone (for another zone or no zone)
+    #[serde(default)]
+    pub exit_fee: u64,
+    /// Multiplier applied to marketplace fees on sales of assets in the zone
+    /// (basis points, 10000 = unchanged)
+    #[serde(default = "default_sale_fee_modifier_bps")]
+    pub sale_fee_modifier_bps: u64,
+}
+
+fn default_sale_fee_modifier_bps() -> u64 {
+    10_000
+}
+
+/// Upper bound for `ZoneConfig::sale_fee_modifier_bps` (10x)
+pub const MAX_SALE_FEE_MODIFIER_BPS: u64 = 100_000;
+
+impl EconomyConfig {
+    /// Configured zone `zone_id` of collection `game_id`
+    pub fn zone(&self, game_id: &str, zone_id: &str) -> Option<&ZoneConfig> {
+        self.zones.iter().find(|z| z.game_id == game_id && z.zone_id == zone_id)
+    }
+
+    /// Referral program of collection `game_id`
+    pub fn referral_program(&self, game_id: &str) -> Option<&ReferralProgram> {
+        self.referral_programs.iter().find(|p| p.game_id == game_id)
+    }
+
+    /// Breeding rule of collection `game_id`
+    pub fn breeding_rule(&self, game_id: &str) -> Option<&BreedingRule> {
+        self.breeding.iter().find(|r| r.game_id == game_id)
+    }
+
+    /// Upgrade rule of collection `game_id`
+    pub fn upgrade_rule(&self, game_id: &str) -> Option<&UpgradeRule> {
+        self.upgrades.iter().find(|r| r.game_id == game_id)
+    }
+
+    /// Reject empty or duplicate zone ids, out-of-range fee modifiers and shares, TWAP
+    /// windows longer than the kept price history, zero loot box, hatch and roll delays,
+    /// and upgrade odds over 100% or with too many catalysts
+    pub fn validate(&self) -> Result<()> {
+        if self.trade_fee_bps > MAX_TRADE_FEE_BPS {
+            return Err(HazeError::Config(format!(
+                "economy.trade_fee_bps must be at most {}, got {}",
+                MAX_TRADE_FEE_BPS, self.trade_fee_bps
+            )));
+        }
+        for (i, program) in self.referral_programs.iter().enumerate() {
+            if program.game_id.is_empty() || self.referral_programs[..i].iter().any(|p| p.game_id == program.game_id) {
+                return Err(HazeError::Config(format!(
+                    "economy.referral_programs needs one entry per non-empty game_id, got '{}'",
+                    program.game_id
+                )));
+            }
+            if program.share_bps > MAX_REFERRAL_SHARE_BPS {
+                return Err(HazeError::Config(format!(
+                    "economy.referral_programs '{}': share_bps must be at most {}, got {}",
+                    program.game_id, MAX_REFERRAL_SHARE_BPS, program.share_bps
+                )));
+            }
+        }
+        if self.twap_max_observations < 2 {
+            return Err(HazeError::Config(format!(
+                "economy.twap_max_observations must be at least 2, got {}",
+                self.twap_max_observations
+            )));
+        }
+        if self.twap_default_window == 0 || self.twap_default_window >= self.twap_max_observations as u64 {
+            return Err(HazeError::Config(format!(
+                "economy.twap_default_window must be between 1 and twap_max_observations - 1 ({}), got {}",
+                self.twap_max_observations - 1, self.twap_default_window
+            )));
+        }
+        for (i, zone) in self.zones.iter().enumerate() {
+            if zone.game_id.is_empty() || zone.zone_id.is_empty() {
+                return Err(HazeError::Config(
+                    "economy.zones entries need a game_id and a zone_id".to_string()
+                ));
+            }
+            if self.zones[..i].iter().any(|z| z.game_id == zone.game_id && z.zone_id == zone.zone_id) {
+                return Err(HazeError::Config(format!(
+                    "economy.zones has duplicate zone '{}' in game '{}'",
+                    zone.zone_id, zone.game_id
+                )));
+            }
+            if zone.sale_fee_modifier_bps > MAX_SALE_FEE_MODIFIER_BPS {
+                return Err(HazeError::Config(format!(
+                    "economy.zones '{}': sale_fee_modifier_bps must be at most {}, got {}",
+                    zone.zone_id, MAX_SALE_FEE_MODIFIER_BPS, zone.sale_fee_modifier_bps
+                )));
+            }
+        }
+        if self.loot_beacon_delay == 0 || self.loot_reveal_window == 0 {
+            return Err(HazeError::Config(format!(
+                "economy.loot_beacon_delay and loot_reveal_window must be at least 1, got {} and {}",
+                self.loot_beacon_delay, self.loot_reveal_window
+            )));
+        }
+        for (i, rule) in self.breeding.iter().enumerate() {
+            if rule.game_id.is_empty() || self.breeding[..i].iter().any(|r| r.game_id == rule.game_id) {
+                return Err(HazeError::Config(format!(
+                    "economy.breeding needs one entry per non-empty game_id, got '{}'",
+                    rule.game_id
+                )));
+            }
+            if rule.hatch_delay == 0 {
+                return Err(HazeError::Config(format!(
+                    "economy.breeding '{}': hatch_delay must be at least 1", rule.game_id
+                )));
+            }
+        }
+        for (i, rule) in self.upgrades.iter().enumerate() {
+            if rule.game_id.is_empty() || self.upgrades[..i].iter().any(|r| r.game_id == rule.game_id) {
+                return Err(HazeError::Config(format!(
+                    "economy.upgrades needs one entry per non-empty game_id, got '{}'",
+                    rule.game_id
+                )));
+            }
+            if rule.attribute.is_empty() || rule.roll_delay == 0 {
+                return Err(HazeError::Config(format!(
+                    "economy.upgrades '{}': attribute must not be empty and roll_delay must be at least 1",
+                    rule.game_id
+                )));
+            }
+            if rule.catalysts as usize > crate::types::MAX_UPGRADE_CATALYSTS {
+                return Err(HazeError::Config(format!(
+                    "economy.upgrades '{}': catalysts must be at most {}, got {}",
+                    rule.game_id, crate::types::MAX_UPGRADE_CATALYSTS, rule.catalysts
+                )));
+            }
+            if rule.success_bps.saturating_add(rule.destroy_bps) > crate::upgrade::UPGRADE_ODDS_TOTAL {
+                return Err(HazeError::Config(format!(
+                    "economy.upgrades '{}': success_bps + destroy_bps must be at most {}, got {}",
+                    rule.game_id, crate::upgrade::UPGRADE_ODDS_TOTAL, rule.success_bps.saturating_add(rule.destroy_bps)
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Custodial sub-accounts for free-to-play onboarding
+///
+/// Player keys are derived from a node-held master seed stored in an encrypted
+/// keystore; the passphrase is read from `passphrase_env` at startup.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CustodyConfig {
+    /// Enable the `/api/v1/custody` endpoints
+    pub enabled: bool,
+
+    /// Encrypted keystore holding the master seed (created on first start)
+    pub keystore_path: PathBuf,
+
+    /// Environment variable holding the keystore passphrase
+    pub passphrase_env: String,
+
+    /// Hex SHA-256 hashes of the API keys allowed to use custodial accounts
+    pub api_key_hashes: Vec<String>,
+
+    /// Accounts (hex addresses) allowed to use custodial accounts with a Sign-in-with-HAZE
+    /// session token instead of an API key
+    pub account_addresses: Vec<String>,
+}
+
+impl Default for CustodyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            keystore_path: PathBuf::from("./haze_db/custody.keystore"),
+            passphrase_env: "HAZE_CUSTODY_PASSPHRASE".to_string(),
+            api_key_hashes: Vec::new(),
+            account_addresses: Vec::new(),
+        }
+    }
+}
+
+/// Sign-in-with-HAZE: wallet login by signing a node-issued challenge
+///
+/// A signed challenge is exchanged for a short-lived session token, accepted as
+/// `Authorization: Bearer <token>` by the watchlist and custody endpoints.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SignInConfig {
+    /// Serve the `/api/v1/auth` endpoints
+    pub enabled: bool,
+
+    /// Domain named in challenges (the site or game clients sign in to)
+    pub domain: String,
+
+    /// Seconds a challenge can be signed and redeemed
+    pub challenge_ttl_secs: u64,
+
+    /// Seconds a session token stays valid
+    pub session_ttl_secs: u64,
+
+    /// Unredeemed challenges kept at once (older ones are dropped first)
+    pub max_pending_challenges: usize,
+}
+
+impl Default for SignInConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            domain: "haze".to_string(),
+            challenge_ttl_secs: 300,
+            session_ttl_secs: 3600,
+            max_pending_challenges: 10_000,
+        }
+    }
+}
+
+impl SignInConfig {
+    pub fn validate(&self) -> Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if self.domain.is_empty() || self.domain.contains('\n') {
+            return Err(HazeError::Config("sign_in.domain must be a non-empty single line".to_string()));
+        }
+        if self.challenge_ttl_secs == 0 || self.session_ttl_secs == 0 {
+            return Err(HazeError::Config(
+                "sign_in.challenge_ttl_secs and sign_in.session_ttl_secs must be at least 1".to_string()
+            ));
+        }
+        if self.max_pending_challenges == 0 {
+            return Err(HazeError::Config("sign_in.max_pending_challenges must be at least 1".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Largest `messaging.max_message_bytes` accepted
+pub const MAX_MESSAGE_BYTES: usize = 64 * 1024;
+
+/// Off-chain encrypted direct messages between accounts
+///
+/// Messages are sealed to the recipient's address key, gossiped between nodes and kept in
+/// a mailbox until the recipient fetches and deletes them (see docs/MESSAGING.md).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MessagingConfig {
+    /// Accept, relay and store messages, and serve the `/api/v1/messages` endpoints
+    pub enabled: bool,
+
+    /// Largest plaintext of one message (bytes)
+    pub max_message_bytes: usize,
+
+    /// Messages kept per recipient; the oldest are dropped first
+    pub max_messages_per_recipient: usize,
+
+    /// Seconds a message is kept before it is dropped unread
+    pub retention_secs: u64,
+
+    /// Seconds a signed mailbox request, or a message's send time, may be off the node clock
+    pub auth_window_secs: u64,
+}
+
+impl Default for MessagingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_message_bytes: 4096,
+            max_messages_per_recipient: 100,
+            retention_secs: 7 * 24 * 3600,
+            auth_window_secs: 300,
+        }
+    }
+}
+
+impl MessagingConfig {
+    pub fn validate(&self) -> Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if self.max_message_bytes == 0 || self.max_message_bytes > MAX_MESSAGE_BYTES {
+            return Err(HazeError::Config(format!(
+                "messaging.max_message_bytes must be between 1 and {}", MAX_MESSAGE_BYTES
+            )));
+        }
+        if self.max_messages_per_recipient == 0 {
+            return Err(HazeError::Config("messaging.max_messages_per_recipient must be at least 1".to_string()));
+        }
+        if self.retention_secs == 0 || self.auth_window_secs == 0 {
+            return Err(HazeError::Config(
+                "messaging.retention_secs and messaging.auth_window_secs must be at least 1".to_string()
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Governance token: a second native token, next to HAZE, that carries governance weight
+///
+/// Its whole supply is allocated at genesis. Holders transfer it and stake it for voting
+/// weight; every fee is still paid in HAZE. This is part of the chain's rules: all nodes of
+/// a network must use the same settings (see docs/GOVERNANCE_TOKEN.md).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GovernanceTokenConfig {
+    /// Enable the token and its transactions; without it governance weight is HAZE stake
+    pub enabled: bool,
+
+    /// Ticker shown by the API
+    pub symbol: String,
+
+    /// Balances credited on an empty chain
+    pub genesis: Vec<GenesisAllocation>,
+}
+
+/// Governance tokens credited to one account at genesis
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GenesisAllocation {
+    /// Hex address
+    pub address: String,
+    pub amount: u64,
+}
+
+impl Default for GovernanceTokenConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            symbol: "HZG".to_string(),
+            genesis: Vec::new(),
+        }
+    }
+}
+
+impl GovernanceTokenConfig {
+    pub fn validate(&self) -> Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        let symbol = self.symbol.trim();
+        if symbol.is_empty() || symbol.eq_ignore_ascii_case("HAZE") {
+            return Err(HazeError::Config(
+                "governance_token.symbol must be set and differ from HAZE".to_string()
+            ));
+        }
+        let mut seen = HashSet::new();
+        let mut total: u64 = 0;
+        for allocation in &self.genesis {
+            let address = crate::types::hex_to_address(&allocation.address).ok_or_else(|| HazeError::Config(format!(
+                "governance_token.genesis: '{}' is not a hex address", allocation.address
+            )))?;
+            if !seen.insert(address) {
+                return Err(HazeError::Config(format!(
+                    "governance_token.genesis: '{}' is allocated twice", allocation.address
+                )));
+            }
+            total = total.checked_add(allocation.amount).ok_or_else(|| HazeError::Config(
+                "governance_token.genesis: total supply overflows u64".to_string()
+            ))?;
+        }
+        Ok(())
+    }
+
+    /// Genesis allocations as addresses (entries that are not hex addresses are skipped;
+    /// `validate` rejects them)
+    pub fn allocations(&self) -> Vec<(crate::types::Address, u64)> {
+        self.genesis
+            .iter()
+            .filter_map(|a| crate::types::hex_to_address(&a.address).map(|address| (address, a.amount)))
+            .collect()
+    }
+
+    /// Supply of the token: the sum of the genesis allocations (it is never minted or burned)
+    pub fn total_supply(&self) -> u64 {
+        self.genesis.iter().fold(0u64, |total, a| total.saturating_add(a.amount))
+    }
+}
+
+/// Multi-tenant node mode for infrastructure providers serving several studios
+///
+/// Tenants are managed through the `/api/v1/admin/tenants` endpoints and persisted in the
+/// node database; each gets its own API keys, collection namespace and quotas.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TenantsConfig {
+    /// Require a tenant API key for write endpoints and namespace collections per tenant
+    pub enabled: bool,
+
+    /// Hex SHA-256 hashes of the API keys allowed to use the admin endpoints
+    pub admin_key_hashes: Vec<String>,
+
+    /// Quota given to tenants created without one
+    pub default_quota: TenantQuota,
+}
+
+/// Limits of one tenant
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TenantQuota {
+    /// Collections (game_ids) in the tenant's namespace
+    pub max_collections: usize,
+    /// Assets across the tenant's collections
+    pub max_assets: usize,
+    /// Watchlists (and so webhooks) across the tenant's API keys
+    pub max_watchlists: usize,
+}
+
+impl Default for TenantQuota {
+    fn default() -> Self {
+        Self {
+            max_collections: 10,
+            max_assets: 100_000,
+            max_watchlists: 100,
+        }
+    }
+}
+
+/// Usage metering per API key, rolled up per UTC day for billing
+///
+/// Rollups are kept in the node database and exported through `/api/v1/admin/metering`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MeteringConfig {
+    /// Record requests, submitted gas, stored bytes and WebSocket time per API key
+    pub enabled: bool,
+
+    /// Hex SHA-256 hashes of the API keys allowed to export usage
+    /// (tenant admin keys are accepted as well)
+    pub admin_key_hashes: Vec<String>,
+
+    /// How often in-memory counters are written to the database, in seconds
+    pub flush_interval_secs: u64,
+
+    /// Daily rollups older than this many days are deleted (0 keeps them forever)
+    pub retention_days: u32,
+}
+
+impl Default for MeteringConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            admin_key_hashes: Vec::new(),
+            flush_interval_secs: 60,
+            retention_days: 400,
+        }
+    }
+}
+
+impl MeteringConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.enabled && self.flush_interval_secs == 0 {
+            return Err(HazeError::Config("metering.flush_interval_secs must be at least 1".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Read replica that follows a primary node for read traffic
+///
+/// The replica syncs blocks from its `network.bootstrap_nodes` (the primary), never
+/// produces blocks or keeps a mempool, and serves the read endpoints. Writes are proxied to
+/// `primary_api_url` or rejected.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReplicaConfig {
+    /// Run as a read replica
+    pub enabled: bool,
+
+    /// HTTP API of the primary (e.g. `http://10.0.0.1:8080`); write requests are forwarded
+    /// there. Without it, write requests are rejected.
+    pub primary_api_url: Option<String>,
+
+    /// How often the replica asks the primary for its chain tip, in milliseconds
+    pub poll_interval_ms: u64,
+
+    /// Health reports `degraded` when the replica is more blocks than this behind
+    pub max_lag_blocks: u64,
+}
+
+impl Default for ReplicaConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            primary_api_url: None,
+            poll_interval_ms: 2000,
+            max_lag_blocks: 10,
+        }
+    }
+}
+
+impl ReplicaConfig {
+    pub fn validate(&self, network: &NetworkConfig) -> Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if network.bootstrap_nodes.is_empty() {
+            return Err(HazeError::Config(
+                "replica.enabled requires the primary's address in network.bootstrap_nodes".to_string()
+            ));
+        }
+        if self.poll_interval_ms < 100 {
+            return Err(HazeError::Config(format!(
+                "replica.poll_interval_ms must be at least 100, got {}", self.poll_interval_ms
+            )));
+        }
+        let valid_url = self.primary_api_url.as_deref().is_none_or(|url| {
+            url.starts_with("http://") || url.starts_with("https://")
+        });
+        if !valid_url {
+            return Err(HazeError::Config("replica.primary_api_url must be an http(s) URL".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Opt-in telemetry for a public map of the network's nodes
+///
+/// Reporting sends the node's id, name, region, version, height, peer count and peer
+/// latency to `endpoint`; it is off unless `enabled` is set. `aggregator` makes this node
+/// collect other nodes' reports (see `src/telemetry.rs`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TelemetryConfig {
+    /// Report this node to `endpoint`
+    pub enabled: bool,
+
+    /// Aggregator URL reports are POSTed to (e.g. `https://telemetry.example/api/v1/telemetry`)
+    pub endpoint: Option<String>,
+
+    /// Seconds between reports
+    pub interval_secs: u64,
+
+    /// Name shown on the map (defaults to `node_id`)
+    pub node_name: Option<String>,
+
+    /// Location shown on the map, as the operator wants it known (e.g. `eu-west`)
+    pub region: Option<String>,
+
+    /// Accept reports of other nodes and list them
+    pub aggregator: bool,
+
+    /// Nodes that have not reported for this long drop off the list
+    pub aggregator_ttl_secs: u64,
+
+    /// Most nodes the aggregator tracks at once
+    pub aggregator_max_nodes: usize,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: None,
+            interval_secs: 60,
+            node_name: None,
+            region: None,
+            aggregator: false,
+            aggregator_ttl_secs: 300,
+            aggregator_max_nodes: 10_000,
+        }
+    }
+}
+
+impl TelemetryConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.enabled {
+            let valid_url = self.endpoint.as_deref().is_some_and(|url| {
+                reqwest::Url::parse(url).is_ok_and(|url| matches!(url.scheme(), "http" | "https"))
+            });
+            if !valid_url {
+                return Err(HazeError::Config("telemetry.enabled requires an http(s) telemetry.endpoint".to_string()));
+            }
+            if self.interval_secs < 10 {
+                return Err(HazeError::Config(format!(
+                    "telemetry.interval_secs must be at least 10, got {}", self.interval_secs
+                )));
+            }
+        }
+        let too_long = [&self.node_name, &self.region].into_iter().flatten()
+            .any(|field| field.len() > crate::telemetry::MAX_TELEMETRY_FIELD_LEN);
+        if too_long {
+            return Err(HazeError::Config(format!(
+                "telemetry.node_name and telemetry.region must be at most {} bytes", crate::telemetry::MAX_TELEMETRY_FIELD_LEN
+            )));
+        }
+        if self.aggregator && (self.aggregator_ttl_secs == 0 || self.aggregator_max_nodes == 0) {
+            return Err(HazeError::Config(
+                "telemetry.aggregator requires a non-zero aggregator_ttl_secs and aggregator_max_nodes".to_string()
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Upgrade signaling and feature activation (see `src/features.rs`)
+///
+/// `window_blocks`, `threshold_percent` and `activation_heights` are consensus rules: every
+/// node of a network must use the same values.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FeaturesConfig {
+    /// Features this node signals readiness for in the blocks it produces (default: all)
+    pub signal: Vec<String>,
+
+    /// Blocks per signaling window
+    pub window_blocks: u64,
+
+    /// Share of a window's blocks (percent) that must signal a feature to lock it in
+    pub threshold_percent: u8,
+
+    /// Features switched on at a fixed height regardless of signaling
+    pub activation_heights: std::collections::BTreeMap<String, u64>,
+}
+
+impl Default for FeaturesConfig {
+    fn default() -> Self {
+        Self {
+            signal: crate::features::Feature::ALL.iter().map(|feature| feature.name().to_string()).collect(),
+            window_blocks: 1000,
+            threshold_percent: 80,
+            activation_heights: std::collections::BTreeMap::new(),
+        }
+    }
+}
+
+impl FeaturesConfig {
+    pub fn validate(&self) -> Result<()> {
+        if let Some(unknown) = self.signal.iter().chain(self.activation_heights.keys())
+            .find(|name| crate::features::Feature::from_name(name).is_none())
+        {
+            return Err(HazeError::Config(format!("Unknown feature in features config: {}", unknown)));
+        }
+        if self.window_blocks == 0 {
+            return Err(HazeError::Config("features.window_blocks must be greater than 0".to_string()));
+        }
+        if !(1..=100).contains(&self.threshold_percent) {
+            return Err(HazeError::Config(format!(
+                "features.threshold_percent must be between 1 and 100, got {}", self.threshold_percent
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Ephemeral development node (see `src/dev.rs`)
+///
+/// Blocks are sealed as soon as a transaction arrives, deterministic dev accounts are funded on
+/// the empty chain, and `POST /api/v1/dev/faucet` sends test funds. Never enable on a node
+/// that holds real value.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DevConfig {
+    pub enabled: bool,
+
+    /// Pre-funded dev accounts, whose private keys are printed at startup
+    pub accounts: usize,
+
+    /// Starting balance of each dev account
+    pub account_balance: u64,
+
+    /// Starting balance of the faucet account
+    pub faucet_balance: u64,
+
+    /// Amount the faucet sends when a request names none
+    pub faucet_amount: u64,
+
+    /// Accept transactions without checking their signatures (any non-empty signature passes)
+    pub relaxed_signatures: bool,
+}
+
+impl Default for DevConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            accounts: 10,
+            account_balance: 1_000_000_000,
+            faucet_balance: 1_000_000_000_000,
+            faucet_amount: 1_000_000,
+            relaxed_signatures: false,
+        }
+    }
+}
+
+/// Most dev accounts a dev node funds
+pub const MAX_DEV_ACCOUNTS: usize = 1000;
+
+impl DevConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.relaxed_signatures && !self.enabled {
+            return Err(HazeError::Config("dev.relaxed_signatures requires dev.enabled".to_string()));
+        }
+        if self.accounts > MAX_DEV_ACCOUNTS {
+            return Err(HazeError::Config(format!(
+                "dev.accounts must be at most {}, got {}", MAX_DEV_ACCOUNTS, self.accounts
+            )));
+        }
+        if self.enabled && self.faucet_amount == 0 {
+            return Err(HazeError::Config("dev.faucet_amount must be greater than 0".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// What a node is for; each role is a preset of the subsystems it runs
+///
+/// | role | block production | mempool | full history | indexer sink | API |
+/// |------|------------------|---------|--------------|--------------|-----|
+/// | validator | yes | yes | no | no | full |
+/// | full | no | yes | no | no | full |
+/// | light | no | no | no | no | read |
+/// | archive | no | yes | yes | no | full |
+/// | indexer | no | no | yes | yes | read |
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeRole {
+    Validator,
+    Full,
+    Light,
+    Archive,
+    Indexer,
+}
+
+impl NodeRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NodeRole::Validator => "validator",
+            NodeRole::Full => "full",
+            NodeRole::Light => "light",
+            NodeRole::Archive => "archive",
+            NodeRole::Indexer => "indexer",
+        }
+    }
+
+    /// Subsystems the role runs before `role` overrides
+    pub fn subsystems(&self) -> Subsystems {
+        let (block_production, mempool, full_history, indexer, api) = match self {
+            NodeRole::Validator => (true, true, false, false, ApiSurface::Full),
+            NodeRole::Full => (false, true, false, false, ApiSurface::Full),
+            NodeRole::Light => (false, false, false, false, ApiSurface::Read),
+            NodeRole::Archive => (false, true, true, false, ApiSurface::Full),
+            NodeRole::Indexer => (false, false, true, true, ApiSurface::Read),
+        };
+        Subsystems { block_production, mempool, full_history, indexer, api }
+    }
+}
+
+/// Endpoints a node serves
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiSurface {
+    /// Every endpoint
+    Full,
+    /// Reads and `/api/v1/admin/*`; other writes answer `405`
+    Read,
+}
+
+/// Subsystems a node runs, resolved from its role (see `Config::subsystems`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Subsystems {
+    /// Seal blocks from the transaction pool
+    pub block_production: bool,
+    /// Accept transactions from the API and peers
+    pub mempool: bool,
+    /// Keep every consensus wave in full instead of pruning to `consensus.wave_retention`
+    pub full_history: bool,
+    /// Append every chain event to `role.indexer_sink_path`
+    pub indexer: bool,
+    pub api: ApiSurface,
+}
+
+/// Node role preset and per-subsystem overrides
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RoleConfig {
+    /// Role preset; unset means `validator`, or `full` on a read replica
+    pub preset: Option<NodeRole>,
+
+    /// Overrides of the preset's subsystems
+    pub block_production: Option<bool>,
+    pub mempool: Option<bool>,
+    pub full_history: Option<bool>,
+    pub indexer: Option<bool>,
+    pub api: Option<ApiSurface>,
+
+    /// File the indexer sink appends events to, one JSON object per line
+    pub indexer_sink_path: PathBuf,
+}
+
+impl Default for RoleConfig {
+    fn default() -> Self {
+        Self {
+            preset: None,
+            block_production: None,
+            mempool: None,
+            full_history: None,
+            indexer: None,
+            api: None,
+            indexer_sink_path: PathBuf::from("./haze_events.ndjson"),
+        }
+    }
+}
+
+impl RoleConfig {
+    /// Active role and subsystems; a read replica never produces blocks or keeps a mempool
+    pub fn resolve(&self, replica: &ReplicaConfig) -> (NodeRole, Subsystems) {
+        let role = self.preset.unwrap_or(if replica.enabled { NodeRole::Full } else { NodeRole::Validator });
+        let preset = role.subsystems();
+        let subsystems = Subsystems {
+            block_production: self.block_production.unwrap_or(preset.block_production) && !replica.enabled,
+            mempool: self.mempool.unwrap_or(preset.mempool) && !replica.enabled,
+            full_history: self.full_history.unwrap_or(preset.full_history),
+            indexer: self.indexer.unwrap_or(preset.indexer),
+            api: self.api.unwrap_or(preset.api),
+        };
+        (role, subsystems)
+    }
+
+    /// Reject subsystems the rest of the configuration contradicts
+    pub fn validate(&self, config: &Config) -> Result<()> {
+        let (role, subsystems) = self.resolve(&config.replica);
+        if config.replica.enabled
+            && (self.preset == Some(NodeRole::Validator) || self.block_production == Some(true) || self.mempool == Some(true))
+        {
+            return Err(HazeError::Config(
+                "replica.enabled cannot be combined with block production or a mempool (role.preset full, light or archive)".to_string()
+            ));
+        }
+        if subsystems.block_production && !subsystems.mempool {
+            return Err(HazeError::Config(format!(
+                "role.block_production needs role.mempool: a {} node would seal empty blocks", role.as_str()
+            )));
+        }
+        if subsystems.indexer && self.indexer_sink_path.as_os_str().is_empty() {
+            return Err(HazeError::Config("role.indexer_sink_path must be set when the indexer runs".to_string()));
+        }
+        if config.dev.enabled && !subsystems.block_production {
+            return Err(HazeError::Config(format!(
+                "dev.enabled needs block production, which a {} node does not run (role.preset validator)", role.as_str()
+            )));
+        }
+        // Features whose endpoints write
+        for (enabled, feature) in [
+            (config.custody.enabled, "custody.enabled"),
+            (config.messaging.enabled, "messaging.enabled"),
+            (config.tenants.enabled, "tenants.enabled"),
+        ] {
+            if enabled && subsystems.api == ApiSurface::Read {
+                return Err(HazeError::Config(format!(
+                    "{} needs the full API, which a {} node does not serve (role.api full)", feature, role.as_str()
+                )));
+            }
+        }
+        if config.custody.enabled && !subsystems.mempool {
+            return Err(HazeError::Config(format!(
+                "custody.enabled submits transactions, which a {} node does not accept (role.mempool)", role.as_str()
+            )));
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -66,6 +1158,69 @@ pub struct NetworkConfig {
     
     /// Minimum stake for edge nodes
     pub min_edge_stake: u64,
+
+    /// Interval between keep-alive pings to each connected peer (ms)
+    #[serde(default = "default_ping_interval_ms")]
+    pub ping_interval_ms: u64,
+
+    /// A peer not heard from for this long is disconnected (ms)
+    #[serde(default = "default_dead_peer_timeout_ms")]
+    pub dead_peer_timeout_ms: u64,
+
+    /// A peer that misses this many keep-alive pings in a row is disconnected
+    #[serde(default = "default_max_missed_pings")]
+    pub max_missed_pings: u32,
+
+    /// First delay before redialing a dropped peer this node dialed (ms); doubles per failed attempt
+    #[serde(default = "default_reconnect_base_ms")]
+    pub reconnect_base_ms: u64,
+
+    /// Longest delay between redial attempts (ms)
+    #[serde(default = "default_reconnect_max_ms")]
+    pub reconnect_max_ms: u64,
+}
+
+fn default_ping_interval_ms() -> u64 {
+    15_000
+}
+
+fn default_dead_peer_timeout_ms() -> u64 {
+    60_000
+}
+
+fn default_max_missed_pings() -> u32 {
+    3
+}
+
+fn default_reconnect_base_ms() -> u64 {
+    1_000
+}
+
+fn default_reconnect_max_ms() -> u64 {
+    60_000
+}
+
+impl NetworkConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.ping_interval_ms == 0 || self.max_missed_pings == 0 || self.reconnect_base_ms == 0 {
+            return Err(HazeError::Config(
+                "network.ping_interval_ms, max_missed_pings and reconnect_base_ms must be greater than 0".to_string()
+            ));
+        }
+        if self.dead_peer_timeout_ms <= self.ping_interval_ms {
+            return Err(HazeError::Config(format!(
+                "network.dead_peer_timeout_ms ({}) must exceed ping_interval_ms ({}), or peers are dropped between pings",
+                self.dead_peer_timeout_ms, self.ping_interval_ms
+            )));
+        }
+        if self.reconnect_max_ms < self.reconnect_base_ms {
+            return Err(HazeError::Config(format!(
+                "network.reconnect_max_ms ({}) is below reconnect_base_ms ({})",
+                self.reconnect_max_ms, self.reconnect_base_ms
+            )));
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -89,6 +1244,307 @@ pub struct ConsensusConfig {
     /// (relative to current local height). Used only when
     /// `strict_block_validation` is enabled.
     pub max_future_block_height_delta: u64,
+    
+    /// Number of validators selected (by stake) into each committee. Must be odd.
+    #[serde(default = "default_committee_size")]
+    pub committee_size: usize,
+    
+    /// Minimum number of validators required to rotate into a new committee;
+    /// with fewer eligible validators the current committee is kept.
+    #[serde(default = "default_min_committee_validators")]
+    pub min_committee_validators: usize,
+    
+    /// Consecutive waves a committee member may miss proposing before it is jailed
+    #[serde(default = "default_max_missed_slots")]
+    pub max_missed_slots: u64,
+    
+    /// How long a jailed validator must wait before it can send `Unjail` (seconds)
+    #[serde(default = "default_jail_duration_secs")]
+    pub jail_duration_secs: u64,
+    
+    /// Number of recent blocks whose median timestamp a new block must not precede
+    #[serde(default = "default_median_time_window")]
+    pub median_time_window: usize,
+    
+    /// How far (seconds) a block timestamp may be ahead of the local clock
+    #[serde(default = "default_max_block_time_drift_secs")]
+    pub max_block_time_drift_secs: u64,
+    
+    /// Median offset from peer clocks (ms) above which the local clock is flagged as drifting
+    #[serde(default = "default_max_clock_drift_ms")]
+    pub max_clock_drift_ms: u64,
+
+    /// Number of recent blocks whose transaction fees priority fee estimates are drawn from
+    #[serde(default = "default_fee_estimate_blocks")]
+    pub fee_estimate_blocks: usize,
+    /// Accounts (hex addresses) whose transactions may use nonce lanes: the top 16 bits of
+    /// the nonce pick a lane and each lane keeps its own sequence (see `types::nonce_lane`)
+    #[serde(default)]
+    pub nonce_lane_accounts: Vec<String>,
+    /// Lanes a designated account may use (lane ids `0..max_nonce_lanes`)
+    #[serde(default = "default_max_nonce_lanes")]
+    pub max_nonce_lanes: u32,
+    /// Largest transaction (canonical wire encoding, bytes) accepted by the API, the pool and
+    /// block validation
+    #[serde(default = "default_max_transaction_bytes")]
+    pub max_transaction_bytes: usize,
+    /// Blocks per commission epoch; a validator changes its commission at most once per epoch
+    #[serde(default = "default_commission_epoch_blocks")]
+    pub commission_epoch_blocks: u64,
+    /// Largest change (basis points) of one `SetCommission`
+    #[serde(default = "default_max_commission_change_bps")]
+    pub max_commission_change_bps: u64,
+    /// Blocks a commission increase waits before it applies (decreases apply at once)
+    #[serde(default = "default_commission_notice_blocks")]
+    pub commission_notice_blocks: u64,
+    /// Waves kept in full behind the last finalized wave; older ones are pruned to summaries
+    #[serde(default = "default_wave_retention")]
+    pub wave_retention: u64,
+    /// Summaries of pruned waves kept for `/api/v1/consensus/waves`
+    #[serde(default = "default_wave_history_limit")]
+    pub wave_history_limit: usize,
+    /// Least HAZE stake a validator needs to be selected into a committee. Starting value:
+    /// governance can change it with `VoteCommitteeParams`.
+    #[serde(default = "default_committee_min_stake")]
+    pub committee_min_stake: u64,
+    /// Largest normalized weight (basis points of the committee's weight) of one committee
+    /// member; the excess goes to the other members. Starting value, like `committee_min_stake`.
+    #[serde(default = "default_committee_max_weight_bps")]
+    pub committee_max_weight_bps: u64,
+    /// Longest an operator pause of block production lasts before it ends on its own (seconds)
+    #[serde(default = "default_max_pause_secs")]
+    pub max_pause_secs: u64,
+}
+
+/// Upper bound for `consensus.committee_size` (keeps per-wave vote traffic bounded)
+pub const MAX_COMMITTEE_SIZE: usize = 1001;
+
+/// Random non-zero chain id for a new network
+fn new_chain_id() -> u64 {
+    loop {
+        let chain_id = rand::random::<u64>();
+        if chain_id != 0 {
+            return chain_id;
+        }
+    }
+}
+
+fn default_committee_size() -> usize {
+    21 // Typical BFT committee size
+}
+
+fn default_min_committee_validators() -> usize {
+    1
+}
+
+fn default_max_missed_slots() -> u64 {
+    50
+}
+
+fn default_jail_duration_secs() -> u64 {
+    3600 // 1 hour
+}
+
+fn default_median_time_window() -> usize {
+    11
+}
+
+fn default_max_clock_drift_ms() -> u64 {
+    2_000
+}
+
+fn default_fee_estimate_blocks() -> usize {
+    20
+}
+
+fn default_max_nonce_lanes() -> u32 {
+    64
+}
+
+fn default_max_transaction_bytes() -> usize {
+    1024 * 1024
+}
+
+fn default_commission_epoch_blocks() -> u64 {
+    10_000
+}
+
+fn default_max_commission_change_bps() -> u64 {
+    100 // 1%
+}
+
+fn default_commission_notice_blocks() -> u64 {
+    10_000
+}
+
+fn default_committee_min_stake() -> u64 {
+    1_000
+}
+
+fn default_committee_max_weight_bps() -> u64 {
+    3_333 // Below a third, so no single member can block a quorum
+}
+
+fn default_max_pause_secs() -> u64 {
+    3600 // 1 hour
+}
+
+fn default_wave_retention() -> u64 {
+    1_000
+}
+
+fn default_wave_history_limit() -> usize {
+    10_000
+}
+
+fn default_max_block_time_drift_secs() -> u64 {
+    15
+}
+
+fn default_condense_per_blob() -> u64 {
+    2_000
+}
+
+fn default_condense_per_byte() -> u64 {
+    1
+}
+
+fn default_split_heuristic_fallback() -> bool {
+    true
+}
+
+/// Upper bound for `consensus.median_time_window`
+pub const MAX_MEDIAN_TIME_WINDOW: usize = 1000;
+
+/// Upper bound for `consensus.fee_estimate_blocks`
+pub const MAX_FEE_ESTIMATE_BLOCKS: usize = 1000;
+
+/// Upper bound for `consensus.max_nonce_lanes` (lane ids are 16 bits)
+pub const MAX_NONCE_LANES: u32 = 1 << 16;
+
+/// Bounds for `consensus.max_transaction_bytes` (the upper one fits a Core asset's metadata)
+pub const MIN_TRANSACTION_BYTES: usize = 1024;
+pub const MAX_TRANSACTION_BYTES: usize = 64 * 1024 * 1024;
+
+impl ConsensusConfig {
+    /// BFT quorum for a committee of `n` validators: more than two thirds
+    pub fn quorum_size(n: usize) -> usize {
+        n * 2 / 3 + 1
+    }
+
+    /// Byzantine validators a committee of `n` can tolerate (n >= 3f + 1)
+    pub fn fault_tolerance(n: usize) -> usize {
+        n.saturating_sub(1) / 3
+    }
+
+    /// Validate committee parameters; rejects configs where quorum can never be reached
+    pub fn validate(&self) -> Result<()> {
+        if self.committee_size == 0 || self.committee_size > MAX_COMMITTEE_SIZE {
+            return Err(HazeError::Config(format!(
+                "consensus.committee_size must be between 1 and {}, got {}",
+                MAX_COMMITTEE_SIZE, self.committee_size
+            )));
+        }
+        if self.committee_size.is_multiple_of(2) {
+            return Err(HazeError::Config(format!(
+                "consensus.committee_size must be odd to avoid tied votes, got {}",
+                self.committee_size
+            )));
+        }
+        if self.min_committee_validators == 0 {
+            return Err(HazeError::Config(
+                "consensus.min_committee_validators must be at least 1".to_string()
+            ));
+        }
+        if self.min_committee_validators > self.committee_size {
+            return Err(HazeError::Config(format!(
+                "consensus.min_committee_validators ({}) exceeds committee_size ({}); a committee could never form",
+                self.min_committee_validators, self.committee_size
+            )));
+        }
+        if self.max_missed_slots == 0 {
+            return Err(HazeError::Config(
+                "consensus.max_missed_slots must be greater than 0".to_string()
+            ));
+        }
+        if self.median_time_window == 0 || self.median_time_window > MAX_MEDIAN_TIME_WINDOW {
+            return Err(HazeError::Config(format!(
+                "consensus.median_time_window must be between 1 and {}, got {}",
+                MAX_MEDIAN_TIME_WINDOW, self.median_time_window
+            )));
+        }
+        if self.fee_estimate_blocks == 0 || self.fee_estimate_blocks > MAX_FEE_ESTIMATE_BLOCKS {
+            return Err(HazeError::Config(format!(
+                "consensus.fee_estimate_blocks must be between 1 and {}, got {}",
+                MAX_FEE_ESTIMATE_BLOCKS, self.fee_estimate_blocks
+            )));
+        }
+        if self.committee_rotation_interval == 0 {
+            return Err(HazeError::Config(
+                "consensus.committee_rotation_interval must be greater than 0".to_string()
+            ));
+        }
+        if !(MIN_TRANSACTION_BYTES..=MAX_TRANSACTION_BYTES).contains(&self.max_transaction_bytes) {
+            return Err(HazeError::Config(format!(
+                "consensus.max_transaction_bytes must be between {} and {}, got {}",
+                MIN_TRANSACTION_BYTES, MAX_TRANSACTION_BYTES, self.max_transaction_bytes
+            )));
+        }
+        if self.max_nonce_lanes == 0 || self.max_nonce_lanes > MAX_NONCE_LANES {
+            return Err(HazeError::Config(format!(
+                "consensus.max_nonce_lanes must be between 1 and {}, got {}",
+                MAX_NONCE_LANES, self.max_nonce_lanes
+            )));
+        }
+        if let Some(account) = self.nonce_lane_accounts.iter().find(|a| crate::types::hex_to_address(a).is_none()) {
+            return Err(HazeError::Config(format!(
+                "consensus.nonce_lane_accounts: '{}' is not a hex address", account
+            )));
+        }
+        if self.commission_epoch_blocks == 0 {
+            return Err(HazeError::Config(
+                "consensus.commission_epoch_blocks must be greater than 0".to_string()
+            ));
+        }
+        if self.max_commission_change_bps == 0 || self.max_commission_change_bps > crate::tokenomics::MAX_COMMISSION_BPS {
+            return Err(HazeError::Config(format!(
+                "consensus.max_commission_change_bps must be between 1 and {}", crate::tokenomics::MAX_COMMISSION_BPS
+            )));
+        }
+        if self.committee_max_weight_bps == 0 || self.committee_max_weight_bps > crate::tokenomics::MAX_COMMITTEE_WEIGHT_BPS {
+            return Err(HazeError::Config(format!(
+                "consensus.committee_max_weight_bps must be between 1 and {}", crate::tokenomics::MAX_COMMITTEE_WEIGHT_BPS
+            )));
+        }
+        if self.max_pause_secs == 0 {
+            return Err(HazeError::Config("consensus.max_pause_secs must be greater than 0".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Committee eligibility and weight cap the chain starts with
+    pub fn committee_params(&self) -> crate::tokenomics::CommitteeParams {
+        crate::tokenomics::CommitteeParams {
+            min_stake: self.committee_min_stake,
+            max_weight_bps: self.committee_max_weight_bps,
+        }
+    }
+
+    /// Limits on validator commission changes
+    pub fn commission_policy(&self) -> crate::tokenomics::CommissionPolicy {
+        crate::tokenomics::CommissionPolicy {
+            epoch_blocks: self.commission_epoch_blocks,
+            max_change_bps: self.max_commission_change_bps,
+            notice_blocks: self.commission_notice_blocks,
+        }
+    }
+
+    /// Whether `address` is designated to use nonce lanes
+    pub fn has_nonce_lanes(&self, address: &crate::types::Address) -> bool {
+        self.nonce_lane_accounts
+            .iter()
+            .any(|a| crate::types::hex_to_address(a).as_ref() == Some(address))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -101,6 +1557,91 @@ pub struct VMConfig {
     
     /// Gas price
     pub gas_price: u64,
+
+    /// Gas discount tiers by staked balance; the highest tier reached applies
+    #[serde(default)]
+    pub stake_discounts: Vec<StakeDiscountTier>,
+
+    /// Gas limit of a paymaster's sponsorship decision call
+    #[serde(default = "default_paymaster_gas_limit")]
+    pub paymaster_gas_limit: u64,
+
+    /// Length in blocks of the window over which a paymaster's sponsored gas is reported
+    /// to its decision call (for daily caps)
+    #[serde(default = "default_paymaster_window_blocks")]
+    pub paymaster_window_blocks: u64,
+}
+
+fn default_paymaster_gas_limit() -> u64 {
+    50_000
+}
+
+fn default_paymaster_window_blocks() -> u64 {
+    14_400 // about a day of 6s blocks
+}
+
+/// Gas discount for accounts with at least `min_stake` staked
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StakeDiscountTier {
+    pub min_stake: u64,
+    /// Discount on gas fees in basis points (at most `MAX_STAKE_DISCOUNT_BPS`)
+    pub discount_bps: u64,
+}
+
+/// Upper bound for `StakeDiscountTier::discount_bps` (25%)
+pub const MAX_STAKE_DISCOUNT_BPS: u64 = 2_500;
+
+impl VMConfig {
+    /// Gas discount (basis points) of an account with `staked` at stake
+    pub fn stake_discount_bps(&self, staked: u64) -> u64 {
+        self.stake_discounts
+            .iter()
+            .filter(|tier| staked >= tier.min_stake)
+            .map(|tier| tier.discount_bps)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Split a gas fee into (charged, discount) for an account with `staked` at stake
+    pub fn discounted_gas_fee(&self, gas_fee: u64, staked: u64) -> (u64, u64) {
+        let discount = (gas_fee as u128 * self.stake_discount_bps(staked) as u128 / 10_000) as u64;
+        (gas_fee - discount, discount)
+    }
+
+    /// Reject tiers above the discount cap, without a stake threshold, or out of order
+    pub fn validate(&self) -> Result<()> {
+        if self.paymaster_gas_limit == 0 || self.paymaster_gas_limit > self.gas_limit {
+            return Err(HazeError::Config(format!(
+                "vm.paymaster_gas_limit must be between 1 and vm.gas_limit ({}), got {}",
+                self.gas_limit, self.paymaster_gas_limit
+            )));
+        }
+        if self.paymaster_window_blocks == 0 {
+            return Err(HazeError::Config("vm.paymaster_window_blocks must be above 0".to_string()));
+        }
+        for (i, tier) in self.stake_discounts.iter().enumerate() {
+            if tier.min_stake == 0 {
+                return Err(HazeError::Config(
+                    "vm.stake_discounts tiers need a min_stake above 0".to_string()
+                ));
+            }
+            if tier.discount_bps > MAX_STAKE_DISCOUNT_BPS {
+                return Err(HazeError::Config(format!(
+                    "vm.stake_discounts: discount_bps must be at most {}, got {}",
+                    MAX_STAKE_DISCOUNT_BPS, tier.discount_bps
+                )));
+            }
+            if i > 0 {
+                let previous = &self.stake_discounts[i - 1];
+                if tier.min_stake <= previous.min_stake || tier.discount_bps <= previous.discount_bps {
+                    return Err(HazeError::Config(
+                        "vm.stake_discounts tiers must raise both min_stake and discount_bps".to_string()
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -117,8 +1658,163 @@ pub struct StorageConfig {
     /// Maximum blob size (bytes) - for Core density assets
     pub max_blob_size: usize,
     
-    /// Chunk size for streaming large files (bytes)
+    /// Chunk size for streaming large files (bytes); the largest chunk a blob is cut into
     pub blob_chunk_size: usize,
+
+    /// How blobs are cut into deduplicated chunks
+    #[serde(default)]
+    pub blob_chunking: BlobChunking,
+
+    /// Where blob chunks are kept; with `s3`, `blob_storage_path` is a read-through cache
+    #[serde(default)]
+    pub blob_backend: BlobBackend,
+
+    /// Object store used when `blob_backend` is `s3`
+    #[serde(default)]
+    pub blob_s3: BlobS3Config,
+
+    /// Seconds between database maintenance runs (flush, size sampling for metrics); 0 disables
+    #[serde(default = "default_maintenance_interval_secs")]
+    pub maintenance_interval_secs: u64,
+
+    /// Storage engine holding the database at `db_path`
+    #[serde(default)]
+    pub backend: StorageBackend,
+
+    /// Copy the database to `<db_path>.schema-v<N>.backup` before schema migrations run
+    #[serde(default = "default_true")]
+    pub migration_backup: bool,
+}
+
+/// Storage engine of the node database
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    #[default]
+    Sled,
+    /// Requires a node built with the `rocksdb` feature
+    RocksDb,
+    /// Kept in memory and discarded on exit (dev nodes); `db_path` is unused
+    Memory,
+}
+
+impl std::fmt::Display for StorageBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            StorageBackend::Sled => "sled",
+            StorageBackend::RocksDb => "rocksdb",
+            StorageBackend::Memory => "memory",
+        })
+    }
+}
+
+impl std::str::FromStr for StorageBackend {
+    type Err = HazeError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "sled" => Ok(StorageBackend::Sled),
+            "rocksdb" => Ok(StorageBackend::RocksDb),
+            "memory" => Ok(StorageBackend::Memory),
+            other => Err(HazeError::Config(format!("Unknown storage backend '{}' (sled, rocksdb or memory)", other))),
+        }
+    }
+}
+
+/// How blobs are cut into chunks, which are stored once however many blobs contain them
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BlobChunking {
+    /// Content-defined boundaries (averaging `blob_chunk_size / 16`), so an insertion only
+    /// changes the chunks around it
+    #[default]
+    Cdc,
+    /// Chunks of exactly `blob_chunk_size`; only identical prefixes deduplicate
+    Fixed,
+}
+
+/// Where blob chunks are kept
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BlobBackend {
+    /// Under `blob_storage_path`
+    #[default]
+    Local,
+    /// In an S3-compatible bucket (`storage.blob_s3`)
+    S3,
+}
+
+/// S3-compatible object store holding blobs (`storage.blob_backend = "s3"`)
+///
+/// Credentials are read at startup from the environment variables named here.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BlobS3Config {
+    /// Endpoint URL, e.g. `https://s3.eu-west-1.amazonaws.com` or `http://minio:9000`
+    pub endpoint: String,
+
+    pub bucket: String,
+
+    /// Signing region (`us-east-1` for most S3-compatible stores)
+    pub region: String,
+
+    /// Prefix of every object key (e.g. `haze/`)
+    pub prefix: String,
+
+    /// Address the bucket in the path (`endpoint/bucket/key`, as MinIO expects) rather than
+    /// in the host name
+    pub path_style: bool,
+
+    /// Environment variable holding the access key id
+    pub access_key_env: String,
+
+    /// Environment variable holding the secret access key
+    pub secret_key_env: String,
+
+    /// Bytes of chunks kept in the local cache; trimmed by the maintenance task
+    pub cache_max_bytes: u64,
+
+    /// Timeout of one object store request, in seconds
+    pub timeout_secs: u64,
+}
+
+impl Default for BlobS3Config {
+    fn default() -> Self {
+        Self {
+            endpoint: String::new(),
+            bucket: String::new(),
+            region: "us-east-1".to_string(),
+            prefix: String::new(),
+            path_style: false,
+            access_key_env: "AWS_ACCESS_KEY_ID".to_string(),
+            secret_key_env: "AWS_SECRET_ACCESS_KEY".to_string(),
+            cache_max_bytes: 10 * 1024 * 1024 * 1024,
+            timeout_secs: 30,
+        }
+    }
+}
+
+impl StorageConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.blob_backend != BlobBackend::S3 {
+            return Ok(());
+        }
+        let s3 = &self.blob_s3;
+        if !(s3.endpoint.starts_with("http://") || s3.endpoint.starts_with("https://")) {
+            return Err(HazeError::Config("storage.blob_s3.endpoint must be an http(s) URL".to_string()));
+        }
+        if s3.bucket.is_empty() || s3.region.is_empty() {
+            return Err(HazeError::Config("storage.blob_s3 needs a bucket and a region".to_string()));
+        }
+        if s3.timeout_secs == 0 {
+            return Err(HazeError::Config("storage.blob_s3.timeout_secs must be at least 1".to_string()));
+        }
+        Ok(())
+    }
+}
+
+fn default_maintenance_interval_secs() -> u64 {
+    300
 }
 
 /// Gas costs configuration for asset operations
@@ -146,6 +1842,14 @@ pub struct AssetGasConfig {
     /// Gas cost per KB of new data for condense
     pub condense_per_kb: u64,
     
+    /// Gas cost per blob declared in condense `_blob_refs`
+    #[serde(default = "default_condense_per_blob")]
+    pub condense_per_blob: u64,
+    
+    /// Gas cost per byte of new (non-reserved) metadata written by condense
+    #[serde(default = "default_condense_per_byte")]
+    pub condense_per_byte: u64,
+    
     /// Base gas cost for evaporating (decreasing density)
     pub evaporate_base: u64,
     
@@ -177,6 +1881,11 @@ pub struct AssetLimits {
     /// Maximum number of blob files per asset
     pub max_blob_files_per_asset: u64,
     
+    /// Allow Split without a `_component_manifest`, distributing metadata and attributes by
+    /// name matching (see docs/API_TRANSACTIONS.md). When false every Split needs a manifest.
+    #[serde(default = "default_split_heuristic_fallback")]
+    pub split_heuristic_fallback: bool,
+    
     /// Quotas for different node types
     pub quotas: NodeQuotas,
 }
@@ -213,26 +1922,70 @@ pub struct NodeQuota {
     pub max_blob_storage_per_account: u64,
 }
 
+/// Config file read by `Config::load`, in the working directory
+pub const CONFIG_FILE: &str = "haze_config.json";
+
+/// Prefix of the environment variables overriding config fields (`HAZE__API__LISTEN_ADDR`)
+pub const ENV_OVERRIDE_PREFIX: &str = "HAZE__";
+
 impl Config {
-    /// Load configuration from file or create default
+    /// Load configuration from file or create default, then apply `HAZE__*` environment
+    /// overrides (see `apply_env_overrides`) and validate the result
     pub fn load() -> Result<Self> {
-        let default_config = Self::default();
-        
-        // Try to load from config file
-        let config_path = PathBuf::from("haze_config.json");
-        if config_path.exists() {
-            let content = std::fs::read_to_string(&config_path)
-                .map_err(|e| HazeError::Config(format!("Failed to read config: {}", e)))?;
-            serde_json::from_str(&content)
-                .map_err(|e| HazeError::Config(format!("Failed to parse config: {}", e)))
-        } else {
-            // Save default config
-            let content = serde_json::to_string_pretty(&default_config)
+        let config_path = PathBuf::from(CONFIG_FILE);
+        if !config_path.exists() {
+            // Save default config (overrides stay out of the file)
+            let content = serde_json::to_string_pretty(&Self::new_network())
                 .map_err(|e| HazeError::Config(format!("Failed to serialize config: {}", e)))?;
             std::fs::write(&config_path, content)
                 .map_err(|e| HazeError::Config(format!("Failed to write config: {}", e)))?;
-            Ok(default_config)
         }
+        let config = Self::read_file(&config_path)?.apply_env_overrides(std::env::vars())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Parse a config file, without overrides or validation
+    pub fn read_file(path: &std::path::Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| HazeError::Config(format!("Failed to read {}: {}", path.display(), e)))?;
+        serde_json::from_str(&content)
+            .map_err(|e| HazeError::Config(format!("Failed to parse {}: {}", path.display(), e)))
+    }
+
+    /// Override fields from `HAZE__<SECTION>__<FIELD>=<value>` variables (any depth, case
+    /// insensitive: `HAZE__API__LISTEN_ADDR` sets `api.listen_addr`). Values are JSON
+    /// (`HAZE__CONSENSUS__COMMITTEE_SIZE=7`, `HAZE__NETWORK__BOOTSTRAP_NODES='["/ip4/..."]'`);
+    /// string fields take the raw value. Every unknown field or mistyped value is reported.
+    pub fn apply_env_overrides(self, vars: impl IntoIterator<Item = (String, String)>) -> Result<Self> {
+        let mut value = serde_json::to_value(&self)
+            .map_err(|e| HazeError::Config(format!("Failed to serialize config: {}", e)))?;
+        let mut errors = Vec::new();
+        for (name, raw) in vars {
+            let Some(path) = name.strip_prefix(ENV_OVERRIDE_PREFIX) else { continue };
+            let path: Vec<String> = path.split("__").map(str::to_lowercase).collect();
+            if let Err(e) = override_field(&mut value, &path, &raw) {
+                errors.push(format!("{}: {}", name, e));
+            }
+        }
+        if !errors.is_empty() {
+            return Err(HazeError::Config(aggregate("invalid environment override", errors)));
+        }
+        serde_json::from_value(value)
+            .map_err(|e| HazeError::Config(format!("environment overrides do not form a valid config: {}", e)))
+    }
+
+    /// Default config of the first node of a new network, with a random `chain_id` (and so
+    /// a network id of its own)
+    pub fn new_network() -> Self {
+        Self { chain_id: new_chain_id(), ..Self::default() }
+    }
+
+    /// Network id of the configured chain: its `chain_id` and the genesis allocations of the
+    /// governance token (when enabled), see `types::network_id`
+    pub fn network_id(&self) -> crate::types::Hash {
+        let genesis = if self.governance_token.enabled { self.governance_token.allocations() } else { Vec::new() };
+        crate::types::network_id(self.chain_id, &genesis)
     }
 
     pub fn default() -> Self {
@@ -245,6 +1998,11 @@ impl Config {
                 node_type: "light".to_string(),
                 min_core_stake: 1000,
                 min_edge_stake: 100,
+                ping_interval_ms: default_ping_interval_ms(),
+                dead_peer_timeout_ms: default_dead_peer_timeout_ms(),
+                max_missed_pings: default_max_missed_pings(),
+                reconnect_base_ms: default_reconnect_base_ms(),
+                reconnect_max_ms: default_reconnect_max_ms(),
             },
             consensus: ConsensusConfig {
                 committee_rotation_interval: 900, // 15 minutes
@@ -253,11 +2011,33 @@ impl Config {
                 max_transactions_per_block: 10000,
                 strict_block_validation: false,
                 max_future_block_height_delta: 2,
+                committee_size: default_committee_size(),
+                min_committee_validators: default_min_committee_validators(),
+                max_missed_slots: default_max_missed_slots(),
+                jail_duration_secs: default_jail_duration_secs(),
+                median_time_window: default_median_time_window(),
+                max_block_time_drift_secs: default_max_block_time_drift_secs(),
+                max_clock_drift_ms: default_max_clock_drift_ms(),
+                fee_estimate_blocks: default_fee_estimate_blocks(),
+                nonce_lane_accounts: Vec::new(),
+                max_nonce_lanes: default_max_nonce_lanes(),
+                max_transaction_bytes: default_max_transaction_bytes(),
+                commission_epoch_blocks: default_commission_epoch_blocks(),
+                max_commission_change_bps: default_max_commission_change_bps(),
+                commission_notice_blocks: default_commission_notice_blocks(),
+                wave_retention: default_wave_retention(),
+                wave_history_limit: default_wave_history_limit(),
+                committee_min_stake: default_committee_min_stake(),
+                committee_max_weight_bps: default_committee_max_weight_bps(),
+                max_pause_secs: default_max_pause_secs(),
             },
             vm: VMConfig {
                 wasm_cache_size: 512,
                 gas_limit: 10_000_000,
                 gas_price: 1,
+                stake_discounts: Vec::new(),
+                paymaster_gas_limit: default_paymaster_gas_limit(),
+                paymaster_window_blocks: default_paymaster_window_blocks(),
             },
             storage: StorageConfig {
                 db_path: PathBuf::from("./haze_db"),
@@ -265,11 +2045,18 @@ impl Config {
                 blob_storage_path: PathBuf::from("./haze_db/blobs"),
                 max_blob_size: 100 * 1024 * 1024, // 100MB for Core density
                 blob_chunk_size: 1024 * 1024, // 1MB chunks
+                blob_chunking: BlobChunking::default(),
+                blob_backend: BlobBackend::default(),
+                blob_s3: BlobS3Config::default(),
+                maintenance_interval_secs: default_maintenance_interval_secs(),
+                backend: StorageBackend::default(),
+                migration_backup: true,
             },
             api: ApiConfig {
                 listen_addr: "127.0.0.1:8080".to_string(),
                 enable_cors: true,
                 enable_websocket: true,
+                admin_key_hashes: Vec::new(),
             },
             asset_gas: AssetGasConfig {
                 create_base: 10_000,
@@ -279,6 +2066,8 @@ impl Config {
                 condense_base: 15_000,
                 condense_density_multiplier: 1, // Base multiplier
                 condense_per_kb: 200,
+                condense_per_blob: default_condense_per_blob(),
+                condense_per_byte: default_condense_per_byte(),
                 evaporate_base: 2_000, // Minimal cost for archiving
                 merge_base: 20_000,
                 merge_per_kb: 150,
@@ -290,6 +2079,7 @@ impl Config {
                 max_assets_per_account: 10_000,
                 max_metadata_size: 50 * 1024 * 1024, // 50MB (Core density max)
                 max_blob_files_per_asset: 100,
+                split_heuristic_fallback: default_split_heuristic_fallback(),
                 quotas: NodeQuotas {
                     core: NodeQuota {
                         max_assets_per_account: 100_000,
@@ -318,9 +2108,143 @@ impl Config {
                 },
             },
             log_level: "info".to_string(),
+            custody: CustodyConfig::default(),
+            sign_in: SignInConfig::default(),
+            messaging: MessagingConfig::default(),
+            governance_token: GovernanceTokenConfig::default(),
+            tenants: TenantsConfig::default(),
+            metering: MeteringConfig::default(),
+            replica: ReplicaConfig::default(),
+            economy: EconomyConfig::default(),
+            telemetry: TelemetryConfig::default(),
+            features: FeaturesConfig::default(),
+            dev: DevConfig::default(),
+            role: RoleConfig::default(),
+        }
+    }
+    
+    /// Validate configuration values that cannot be expressed in the type system, reporting
+    /// every problem at once
+    pub fn validate(&self) -> Result<()> {
+        let errors = self.validation_errors();
+        match errors.len() {
+            0 => Ok(()),
+            1 => Err(HazeError::Config(errors.into_iter().next().unwrap_or_default())),
+            _ => Err(HazeError::Config(aggregate("invalid configuration", errors))),
+        }
+    }
+
+    /// Every problem with the configuration, each naming the field it concerns
+    pub fn validation_errors(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.chain_id == 0 && !self.dev.enabled {
+            errors.push(
+                "chain_id must be set: every network left at chain_id 0 shares one network id, so \
+                 transactions and blocks would replay between them. Use this network's chain_id on \
+                 all of its nodes (a new config file gets a random one)".to_string()
+            );
+        }
+        if self.node_id.trim().is_empty() {
+            errors.push("node_id must not be empty".to_string());
+        }
+        if !["trace", "debug", "info", "warn", "error", "off"].contains(&self.log_level.as_str()) {
+            errors.push(format!("log_level must be trace, debug, info, warn, error or off, got '{}'", self.log_level));
+        }
+
+        // Listen addresses: both must parse, and the API and p2p ports must differ
+        let p2p_port = match self.network.listen_addr.parse::<multiaddr::Multiaddr>() {
+            _ if self.network.listen_addr.trim().is_empty() => {
+                errors.push("network.listen_addr must not be empty".to_string());
+                None
+            }
+            Ok(addr) => addr.iter().find_map(|protocol| match protocol {
+                multiaddr::Protocol::Tcp(port) => Some(port),
+                _ => None,
+            }),
+            Err(e) => {
+                errors.push(format!("network.listen_addr '{}' is not a multiaddr: {}", self.network.listen_addr, e));
+                None
+            }
+        };
+        let api_port = match self.api.listen_addr.rsplit_once(':') {
+            _ if self.api.listen_addr.trim().is_empty() => {
+                errors.push("api.listen_addr must not be empty".to_string());
+                None
+            }
+            Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok() => port.parse::<u16>().ok(),
+            _ => {
+                errors.push(format!("api.listen_addr '{}' must be <host>:<port>", self.api.listen_addr));
+                None
+            }
+        };
+        if let (Some(api), Some(p2p)) = (api_port, p2p_port)
+            && api == p2p
+            && api != 0
+        {
+            errors.push(format!("api.listen_addr and network.listen_addr both use port {}", api));
+        }
+        if !["core", "edge", "light", "mobile"].contains(&self.network.node_type.as_str()) {
+            errors.push(format!(
+                "network.node_type must be core, edge, light or mobile, got '{}'", self.network.node_type
+            ));
+        }
+
+        let mut positive = |field: &str, value: u64| {
+            if value == 0 {
+                errors.push(format!("{} must be at least 1", field));
+            }
+        };
+        positive("consensus.max_transactions_per_block", self.consensus.max_transactions_per_block as u64);
+        positive("storage.max_blob_size", self.storage.max_blob_size as u64);
+        positive("storage.blob_chunk_size", self.storage.blob_chunk_size as u64);
+        positive("asset_limits.max_assets_per_account", self.asset_limits.max_assets_per_account);
+        positive("asset_limits.max_metadata_size", self.asset_limits.max_metadata_size as u64);
+        for (node_type, quota) in [
+            ("core", &self.asset_limits.quotas.core),
+            ("edge", &self.asset_limits.quotas.edge),
+            ("light", &self.asset_limits.quotas.light),
+            ("mobile", &self.asset_limits.quotas.mobile),
+        ] {
+            let field = |name: &str| format!("asset_limits.quotas.{}.{}", node_type, name);
+            positive(&field("max_assets_per_account"), quota.max_assets_per_account);
+            positive(&field("max_metadata_size"), quota.max_metadata_size as u64);
+            positive(&field("max_blob_storage_per_account"), quota.max_blob_storage_per_account);
         }
+        if self.storage.backend != StorageBackend::Memory && self.storage.db_path.as_os_str().is_empty() {
+            errors.push("storage.db_path must not be empty".to_string());
+        }
+
+        // Section validators report the first problem of their section
+        for result in [
+            self.network.validate(),
+            self.consensus.validate(),
+            self.vm.validate(),
+            self.storage.validate(),
+            self.metering.validate(),
+            self.sign_in.validate(),
+            self.messaging.validate(),
+            self.governance_token.validate(),
+            self.replica.validate(&self.network),
+            self.telemetry.validate(),
+            self.features.validate(),
+            self.dev.validate(),
+            self.economy.validate(),
+            self.role.validate(self),
+        ] {
+            match result {
+                Ok(()) => {}
+                Err(HazeError::Config(message)) => errors.push(message),
+                Err(other) => errors.push(other.to_string()),
+            }
+        }
+        errors
     }
     
+    /// Active role and the subsystems it runs
+    pub fn subsystems(&self) -> (NodeRole, Subsystems) {
+        self.role.resolve(&self.replica)
+    }
+
     /// Get quota for current node type
     pub fn get_node_quota(&self) -> &NodeQuota {
         match self.network.node_type.as_str() {
@@ -331,4 +2255,200 @@ impl Config {
             _ => &self.asset_limits.quotas.light, // Default to light
         }
     }
-}
\ No newline at end of file
+}
+/// One message listing every error, one per line
+fn aggregate(what: &str, errors: Vec<String>) -> String {
+    format!("{} ({} errors):\n  - {}", what, errors.len(), errors.join("\n  - "))
+}
+
+/// Set the field at `path` of a serialized config to `raw`, keeping its JSON type
+fn override_field(config: &mut serde_json::Value, path: &[String], raw: &str) -> std::result::Result<(), String> {
+    let dotted = path.join(".");
+    let mut field = config;
+    for key in path {
+        field = match field.as_object_mut().and_then(|section| section.get_mut(key)) {
+            Some(next) => next,
+            None => return Err(format!("no config field {}", dotted)),
+        };
+    }
+    let value = match (&*field, serde_json::from_str::<serde_json::Value>(raw)) {
+        (serde_json::Value::String(_), _) => serde_json::Value::String(raw.to_string()),
+        // Optional fields left unset take JSON, or a plain string
+        (serde_json::Value::Null, parsed) => parsed.unwrap_or_else(|_| serde_json::Value::String(raw.to_string())),
+        (current, Ok(parsed)) if std::mem::discriminant(current) == std::mem::discriminant(&parsed) => parsed,
+        (current, _) => {
+            let expected = match current {
+                serde_json::Value::Bool(_) => "true or false",
+                serde_json::Value::Number(_) => "a number",
+                serde_json::Value::Array(_) => "a JSON array",
+                _ => "a JSON object",
+            };
+            return Err(format!("{} expects {}, got '{}'", dotted, expected, raw));
+        }
+    };
+    *field = value;
+    Ok(())
+}
+
+/// `haze config validate [--config <path>]`: check a config file, with the environment
+/// overrides the node would apply, without starting the node
+pub fn run_cli(args: &[String]) -> Result<()> {
+    let usage = "usage: haze config validate [--config <haze_config.json>]";
+    if args.first().map(String::as_str) != Some("validate") {
+        return Err(HazeError::Config(format!("Unknown config command ({})", usage)));
+    }
+    let mut path = PathBuf::from(CONFIG_FILE);
+    let mut args = args[1..].iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().cloned()
+            .ok_or_else(|| HazeError::Config(format!("Missing value for {}", arg)));
+        match arg.as_str() {
+            "--config" => path = PathBuf::from(value()?),
+            other => return Err(HazeError::Config(format!("Unknown argument '{}' ({})", other, usage))),
+        }
+    }
+    let config = Config::read_file(&path)?.apply_env_overrides(std::env::vars())?;
+    let errors = config.validation_errors();
+    if errors.is_empty() {
+        println!("{}: configuration is valid", path.display());
+        return Ok(());
+    }
+    for error in &errors {
+        eprintln!("{}: {}", path.display(), error);
+    }
+    Err(HazeError::Config(format!("{} is invalid ({} errors)", path.display(), errors.len())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validation_aggregates_field_errors() {
+        let mut config = Config::new_network();
+        config.validate().unwrap();
+
+        config.consensus.max_transactions_per_block = 0;
+        config.api.listen_addr = String::new();
+        config.network.listen_addr = "/ip4/0.0.0.0/tcp/8080".to_string();
+        config.asset_limits.quotas.edge.max_metadata_size = 0;
+        assert_eq!(config.validation_errors(), [
+            "api.listen_addr must not be empty",
+            "consensus.max_transactions_per_block must be at least 1",
+            "asset_limits.quotas.edge.max_metadata_size must be at least 1",
+        ]);
+
+        config.api.listen_addr = "127.0.0.1:8080".to_string();
+        let errors = config.validation_errors();
+        assert!(errors.contains(&"api.listen_addr and network.listen_addr both use port 8080".to_string()));
+        let message = config.validate().unwrap_err().to_string();
+        assert!(message.contains("invalid configuration (3 errors)"), "{}", message);
+        assert!(message.contains("asset_limits.quotas.edge.max_metadata_size"), "{}", message);
+    }
+
+    #[test]
+    fn test_role_presets_and_incompatible_settings() {
+        let mut config = Config::new_network();
+        assert_eq!(config.subsystems(), (NodeRole::Validator, NodeRole::Validator.subsystems()));
+        config.replica.enabled = true;
+        config.network.bootstrap_nodes = vec!["/ip4/10.0.0.1/tcp/9000".to_string()];
+        let (role, subsystems) = config.subsystems();
+        assert_eq!(role, NodeRole::Full);
+        assert!(!subsystems.block_production && !subsystems.mempool);
+        config.validate().unwrap();
+
+        // Overrides apply on top of the preset
+        config.replica.enabled = false;
+        config.role.preset = Some(NodeRole::Light);
+        config.role.full_history = Some(true);
+        let (_, subsystems) = config.subsystems();
+        assert!(subsystems.full_history && !subsystems.mempool);
+        assert_eq!(subsystems.api, ApiSurface::Read);
+        config.validate().unwrap();
+
+        config.custody.enabled = true;
+        assert!(config.validate().unwrap_err().to_string().contains("custody.enabled needs the full API"));
+        config.custody.enabled = false;
+        config.role = RoleConfig { preset: Some(NodeRole::Full), block_production: Some(true), mempool: Some(false), ..RoleConfig::default() };
+        assert!(config.validate().unwrap_err().to_string().contains("role.block_production needs role.mempool"));
+        config.role = RoleConfig { preset: Some(NodeRole::Archive), ..RoleConfig::default() };
+        config.dev.enabled = true;
+        assert!(config.validate().unwrap_err().to_string().contains("dev.enabled needs block production"));
+        config.dev.enabled = false;
+        config.replica.enabled = true;
+        config.role.preset = Some(NodeRole::Validator);
+        assert!(config.validate().unwrap_err().to_string().contains("replica.enabled cannot be combined"));
+    }
+
+    #[test]
+    fn test_env_overrides_set_typed_fields() {
+        let vars = [
+            ("HAZE__API__LISTEN_ADDR", "0.0.0.0:9000"),
+            ("HAZE__CONSENSUS__MAX_TRANSACTIONS_PER_BLOCK", "250"),
+            ("HAZE__API__ENABLE_CORS", "false"),
+            ("HAZE__NETWORK__BOOTSTRAP_NODES", r#"["/ip4/10.0.0.1/tcp/9000"]"#),
+            ("HAZE__ASSET_LIMITS__QUOTAS__CORE__MAX_ASSETS_PER_ACCOUNT", "42"),
+            ("PATH", "/usr/bin"),
+        ];
+        let config = Config::new_network()
+            .apply_env_overrides(vars.iter().map(|(k, v)| (k.to_string(), v.to_string())))
+            .unwrap();
+        assert_eq!(config.api.listen_addr, "0.0.0.0:9000");
+        assert_eq!(config.consensus.max_transactions_per_block, 250);
+        assert!(!config.api.enable_cors);
+        assert_eq!(config.network.bootstrap_nodes, ["/ip4/10.0.0.1/tcp/9000"]);
+        assert_eq!(config.asset_limits.quotas.core.max_assets_per_account, 42);
+    }
+
+    #[test]
+    fn test_env_overrides_report_unknown_and_mistyped_fields() {
+        let vars = [
+            ("HAZE__API__LISTEN_PORT", "9000"),
+            ("HAZE__CONSENSUS__MAX_TRANSACTIONS_PER_BLOCK", "many"),
+        ];
+        let message = Config::new_network()
+            .apply_env_overrides(vars.iter().map(|(k, v)| (k.to_string(), v.to_string())))
+            .unwrap_err()
+            .to_string();
+        assert!(message.contains("HAZE__API__LISTEN_PORT: no config field api.listen_port"), "{}", message);
+        assert!(message.contains("consensus.max_transactions_per_block expects a number, got 'many'"), "{}", message);
+    }
+
+    #[test]
+    fn test_new_networks_get_distinct_network_ids() {
+        // Two networks set up from scratch never share a network id
+        let (first, second) = (Config::new_network(), Config::new_network());
+        first.validate().unwrap();
+        second.validate().unwrap();
+        assert_ne!(first.chain_id, second.chain_id);
+        assert_ne!(first.network_id(), second.network_id());
+
+        // The unconfigured chain id is shared by every default config, so only dev nodes may use it
+        let mut unconfigured = Config::default();
+        assert!(unconfigured.validate().unwrap_err().to_string().contains("chain_id must be set"));
+        unconfigured.dev.enabled = true;
+        unconfigured.validate().unwrap();
+    }
+
+    #[test]
+    fn test_network_id_commits_to_genesis_allocations() {
+        let mut config = Config::new_network();
+        let plain = config.network_id();
+        assert_eq!(plain, crate::types::network_id(config.chain_id, &[]));
+
+        let allocation = |address: u8, amount| GenesisAllocation { address: hex::encode([address; 32]), amount };
+        config.governance_token.genesis = vec![allocation(1, 500), allocation(2, 300)];
+        // Allocations of a disabled token are never credited, so they do not change the network
+        assert_eq!(config.network_id(), plain);
+
+        config.governance_token.enabled = true;
+        let allocated = config.network_id();
+        assert_ne!(allocated, plain);
+
+        // The order of the entries does not matter, their addresses and amounts do
+        config.governance_token.genesis.reverse();
+        assert_eq!(config.network_id(), allocated);
+        config.governance_token.genesis[0].amount += 1;
+        assert_ne!(config.network_id(), allocated);
+    }
+}
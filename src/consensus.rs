@@ -6,11 +6,19 @@
 //! - Haze Weights (reputation system)
 
 use std::sync::Arc;
-use std::collections::{HashMap, HashSet};
-use parking_lot::RwLock;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use parking_lot::{Mutex, RwLock};
 use dashmap::DashMap;
-use crate::types::{Block, BlockHeader, Hash, Address, Transaction};
+use crate::types::{Block, BlockHeader, Hash, Address, Transaction, TxEnvelope, WireFormat, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION, STATE_VERSION};
+use crate::time_sync::{ClockStatus, PeerClock, PeerOffset};
+use crate::version::PeerVersions;
+use crate::peer_health::{LivenessLimits, PeerHealth};
+use crate::features::Feature;
+use crate::fee_estimate::FeeHistogram;
+use crate::maintenance::{NodeControl, Pause};
+use crate::byzantine::{ByzantineBehavior, DivergenceIncident, DivergenceMonitor, EquivocationDetector, EquivocationEvidence};
 use crate::state::StateManager;
+use crate::ws_events::WsEvent;
 use crate::config::Config;
 use crate::error::Result;
 use crate::crypto::verify_signature;
@@ -19,6 +27,8 @@ use chrono::Utc;
 /// Consensus engine implementing Fog Consensus
 pub struct ConsensusEngine {
     config: Config,
+    /// Network id derived from genesis (see `types::network_id`)
+    network_id: Hash,
     state: Arc<StateManager>,
     
     // DAG structure
@@ -30,13 +40,51 @@ pub struct ConsensusEngine {
     
     // Wave finalization
     waves: Arc<RwLock<HashMap<u64, Wave>>>,
+    // Summaries of waves pruned from `waves` (at most `consensus.wave_history_limit`)
+    wave_archive: Arc<RwLock<BTreeMap<u64, WaveSummary>>>,
     current_wave: Arc<RwLock<u64>>,
     // Last finalized wave and height (checkpointing)
     last_finalized_wave: Arc<RwLock<u64>>,
     last_finalized_height: Arc<RwLock<u64>>,
     
     // Transaction pool
-    tx_pool: Arc<DashMap<Hash, Transaction>>,
+    tx_pool: Arc<DashMap<Hash, TxEnvelope>>,
+    // Woken when a transaction enters the pool (instant sealing on dev nodes)
+    tx_added: Arc<tokio::sync::Notify>,
+    
+    // Consecutive finalized waves each committee member went without proposing
+    missed_slots: Arc<DashMap<Address, u64>>,
+    
+    // Clock offsets reported by peers (drift detection)
+    peer_clock: Arc<PeerClock>,
+
+    // Chain heights reported by peers (read replica lag)
+    peer_heights: Arc<DashMap<String, u64>>,
+
+    // Versions announced by peers in the handshake
+    peer_versions: Arc<PeerVersions>,
+
+    // Keep-alive round trips, last-seen times and redial schedule of peers
+    peer_health: Arc<PeerHealth>,
+    
+    // Injected misbehavior (tests only) and equivocation seen from other validators
+    byzantine: Arc<RwLock<ByzantineBehavior>>,
+    equivocations: Arc<EquivocationDetector>,
+
+    // Blocks whose state root this node did not reproduce, and the waves they halt
+    divergences: Arc<DivergenceMonitor>,
+
+    // Fees of the transactions in the pool (priority fee estimates)
+    pool_fees: Arc<Mutex<FeeHistogram>>,
+
+    // Balance pending transactions in the pool will spend, per sender
+    reserved: Arc<DashMap<Address, u64>>,
+
+    // Transactions waiting in the pool, per sender and nonce lane
+    pending_nonces: Arc<DashMap<(Address, u16), u64>>,
+
+    // Operator pause of block production and maintenance mode
+    control: Arc<NodeControl>,
 }
 
 /// DAG structure for Fog Consensus
@@ -54,6 +102,33 @@ struct DagVertex {
     processed: bool,
 }
 
+/// A DAG vertex as exposed to operators (see `ConsensusEngine::dag_vertices`)
+#[derive(Debug, Clone)]
+pub struct DagVertexInfo {
+    pub hash: Hash,
+    pub height: u64,
+    pub wave: u64,
+    pub validator: Address,
+    pub timestamp: i64,
+    /// The vertex's wave is finalized, or it is at or below the finalized height
+    pub finalized: bool,
+    /// Blocks this vertex references (outgoing edges)
+    pub references: Vec<Hash>,
+}
+
+/// How far a read replica trails the primary (see `ConsensusEngine::replica_status`)
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ReplicaStatus {
+    /// Highest block height reported by a peer, `None` before the primary has answered
+    pub primary_height: Option<u64>,
+    pub height: u64,
+    /// Blocks behind the primary, `None` while its height is unknown
+    pub lag_blocks: Option<u64>,
+    pub max_lag_blocks: u64,
+    /// Lag above `max_lag_blocks`, or no height from the primary yet
+    pub lagging: bool,
+}
+
 /// Haze Committee - dynamic validator group
 #[allow(dead_code)] // Fields will be used in full implementation
 struct Committee {
@@ -70,13 +145,73 @@ struct Wave {
     number: u64,
     blocks: HashSet<Hash>,
     finalized: bool,
-    created_at: i64,
+    /// Unix time (ms) the wave's first block arrived
+    created_at_ms: i64,
+    finalized_at_ms: Option<i64>,
+    certificate: Option<FinalityCertificate>,
+}
+
+impl Wave {
+    fn summary(&self) -> WaveSummary {
+        WaveSummary {
+            wave: self.number,
+            block_count: self.blocks.len(),
+            finalized: self.finalized,
+            opened_at_ms: self.created_at_ms,
+            finalized_at_ms: self.finalized_at_ms,
+            certificate: self.certificate.clone(),
+        }
+    }
+}
+
+/// What finalized a wave: the checkpoint it set and the committee members that proposed in it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FinalityCertificate {
+    /// Highest block height in the wave
+    pub checkpoint_height: u64,
+    /// Committee in office when the wave finalized
+    pub committee_id: u64,
+    /// Validators that proposed blocks in the wave, sorted
+    pub proposers: Vec<Address>,
+}
+
+/// A wave as reported by `ConsensusEngine::wave_history`
+#[derive(Debug, Clone)]
+pub struct WaveSummary {
+    pub wave: u64,
+    pub block_count: usize,
+    pub finalized: bool,
+    /// Unix time (ms) the wave's first block arrived
+    pub opened_at_ms: i64,
+    pub finalized_at_ms: Option<i64>,
+    /// `None` until the wave is finalized
+    pub certificate: Option<FinalityCertificate>,
+}
+
+/// The current committee as reported by `ConsensusEngine::current_committee`
+#[derive(Debug, Clone)]
+pub struct CommitteeSummary {
+    pub id: u64,
+    /// Members by stake with their normalized weights (basis points)
+    pub members: Vec<(Address, u64)>,
+    /// Unix time (seconds) of the next rotation
+    pub expires_at: i64,
+}
+
+impl WaveSummary {
+    /// Time from the wave's first block to its finalization (ms)
+    pub fn finalization_ms(&self) -> Option<i64> {
+        self.finalized_at_ms.map(|at| at - self.opened_at_ms)
+    }
 }
 
 impl ConsensusEngine {
     pub fn new(config: Config, state: Arc<StateManager>) -> Result<Self> {
-        let mut engine = Self {
+        config.consensus.validate()?;
+
+        let engine = Self {
             config: config.clone(),
+            network_id: config.network_id(),
             state,
             dag: Arc::new(RwLock::new(Dag {
                 vertices: HashMap::new(),
@@ -86,10 +221,24 @@ impl ConsensusEngine {
             committees: Arc::new(RwLock::new(HashMap::new())),
             current_committee_id: Arc::new(RwLock::new(0)),
             waves: Arc::new(RwLock::new(HashMap::new())),
+            wave_archive: Arc::new(RwLock::new(BTreeMap::new())),
             current_wave: Arc::new(RwLock::new(0)),
             tx_pool: Arc::new(DashMap::new()),
+            tx_added: Arc::new(tokio::sync::Notify::new()),
             last_finalized_wave: Arc::new(RwLock::new(0)),
             last_finalized_height: Arc::new(RwLock::new(0)),
+            missed_slots: Arc::new(DashMap::new()),
+            peer_clock: Arc::new(PeerClock::new(config.consensus.max_clock_drift_ms)),
+            peer_heights: Arc::new(DashMap::new()),
+            peer_versions: Arc::new(PeerVersions::new(config.chain_id)),
+            peer_health: Arc::new(PeerHealth::new(LivenessLimits::from_config(&config.network))),
+            byzantine: Arc::new(RwLock::new(ByzantineBehavior::Honest)),
+            equivocations: Arc::new(EquivocationDetector::new()),
+            divergences: Arc::new(DivergenceMonitor::new()),
+            pool_fees: Arc::new(Mutex::new(FeeHistogram::new())),
+            reserved: Arc::new(DashMap::new()),
+            pending_nonces: Arc::new(DashMap::new()),
+            control: Arc::new(NodeControl::new(config.consensus.max_pause_secs)),
         };
 
         // Initialize first committee
@@ -99,22 +248,45 @@ impl ConsensusEngine {
     }
 
     /// Initialize a new Haze Committee
-    fn initialize_committee(&mut self) -> Result<()> {
-        let committee_id = *self.current_committee_id.read() + 1;
+    ///
+    /// Selects the top `consensus.committee_size` validators by stake among those with at least
+    /// the committee minimum stake, and caps their normalized weights (both set by governance,
+    /// see `Tokenomics::committee_params`). If fewer than `consensus.min_committee_validators`
+    /// are eligible, the current committee is kept for another rotation interval (at startup an
+    /// undersized committee is bootstrapped).
+    fn initialize_committee(&self) -> Result<()> {
+        let current_id = *self.current_committee_id.read();
+        let committee_id = current_id + 1;
         let now = Utc::now().timestamp();
-        let expires_at = now + self.config.consensus.committee_rotation_interval as i64;
+        let consensus_config = &self.config.consensus;
+        let expires_at = now + consensus_config.committee_rotation_interval as i64;
 
         // Select validators based on stake (top validators)
-        const COMMITTEE_SIZE: usize = 21; // Typical BFT committee size
-        let top_validators = self.state.tokenomics().get_top_validators(COMMITTEE_SIZE);
+        let params = self.state.tokenomics().committee_params();
+        let top_validators = self.state.tokenomics()
+            .get_committee_candidates(consensus_config.committee_size, params.min_stake);
         let validator_count = top_validators.len();
+        if validator_count < consensus_config.min_committee_validators {
+            if let Some(committee) = self.committees.write().get_mut(&current_id) {
+                committee.expires_at = expires_at;
+                tracing::warn!(
+                    "Only {} eligible validators (minimum {}); keeping committee {}",
+                    validator_count, consensus_config.min_committee_validators, current_id
+                );
+                return Ok(());
+            }
+            tracing::warn!(
+                "Bootstrapping committee with {} validators (minimum {})",
+                validator_count, consensus_config.min_committee_validators
+            );
+        }
         let validators: Vec<Address> = top_validators.iter().map(|v| v.address).collect();
         
-        // Calculate weights (stake-based)
-        let mut weights = HashMap::new();
-        for validator_info in top_validators {
-            weights.insert(validator_info.address, validator_info.total_staked);
-        }
+        // Stake-based weights in basis points, none above the cap
+        let stakes: Vec<u64> = top_validators.iter().map(|v| v.total_staked).collect();
+        let weights: HashMap<Address, u64> = validators.iter().copied()
+            .zip(crate::tokenomics::normalize_committee_weights(&stakes, params.max_weight_bps))
+            .collect();
 
         let committee = Committee {
             id: committee_id,
@@ -127,7 +299,13 @@ impl ConsensusEngine {
         self.committees.write().insert(committee_id, committee);
         *self.current_committee_id.write() = committee_id;
 
-        tracing::info!("Initialized committee {} with {} validators", committee_id, validator_count);
+        tracing::info!(
+            "Initialized committee {} with {} validators (quorum {}, tolerates {} faulty)",
+            committee_id,
+            validator_count,
+            crate::config::ConsensusConfig::quorum_size(validator_count),
+            crate::config::ConsensusConfig::fault_tolerance(validator_count)
+        );
 
         Ok(())
     }
@@ -160,7 +338,29 @@ impl ConsensusEngine {
     /// # Errors
     /// Returns an error if the transaction is invalid (duplicate, invalid signature, etc.)
     pub fn add_transaction(&self, tx: Transaction) -> Result<()> {
+        let (type_name, started) = (tx.type_name(), std::time::Instant::now());
+        let result = self.admit_transaction(tx);
+        self.state.latency().mempool_admission.observe(type_name, started.elapsed());
+        result
+    }
+
+    fn admit_transaction(&self, tx: Transaction) -> Result<()> {
+        if self.config.replica.enabled {
+            return Err(crate::error::HazeError::Consensus(
+                "Read replica does not accept transactions; submit them to the primary".to_string()
+            ));
+        }
+        let (role, subsystems) = self.config.subsystems();
+        if !subsystems.mempool {
+            return Err(crate::error::HazeError::Consensus(format!(
+                "A {} node keeps no mempool; submit transactions to a full or validator node", role.as_str()
+            )));
+        }
+
+        self.control.check_accepting(Utc::now().timestamp())?;
+
         // Check if transaction already exists in pool
+        let tx = TxEnvelope::new(tx);
         let tx_hash = tx.hash();
         if self.tx_pool.contains_key(&tx_hash) {
             return Err(crate::error::HazeError::InvalidTransaction(
@@ -169,31 +369,198 @@ impl ConsensusEngine {
         }
 
         // Basic validation
+        self.check_transaction_size(tx.transaction())?;
         self.validate_transaction(&tx)?;
 
+        // Reserve what it spends so pending transactions never commit more than the balance
+        let sender = *tx.transaction().sender();
+        let spend = tx.transaction().spend();
+        self.reserve_balance(&sender, spend)?;
+
         // Add to pool
-        self.tx_pool.insert(tx_hash, tx);
+        let fee = tx.transaction().fee();
+        let nonce = tx.transaction().nonce();
+        if self.tx_pool.insert(tx_hash, tx).is_none() {
+            self.pool_fees.lock().insert(fee);
+            *self.pending_nonces.entry((sender, crate::types::nonce_lane(nonce))).or_insert(0) += 1;
+        } else {
+            self.release_balance(&sender, spend);
+        }
+        self.tx_added.notify_one();
+        Ok(())
+    }
+
+    /// Reject a transaction whose wire encoding is over `consensus.max_transaction_bytes`
+    pub fn check_transaction_size(&self, tx: &Transaction) -> Result<()> {
+        let size = tx.to_wire().len();
+        let limit = self.config.consensus.max_transaction_bytes;
+        if size > limit {
+            return Err(crate::error::HazeError::TransactionTooLarge(size, limit));
+        }
+        Ok(())
+    }
+
+    /// Reserve `amount` of `address`'s balance for a pending transaction; fails when the
+    /// balance not yet reserved by other pending transactions does not cover it
+    fn reserve_balance(&self, address: &Address, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+        let balance = self.state.get_account(address).map(|account| account.balance).unwrap_or(0);
+        let mut reserved = self.reserved.entry(*address).or_insert(0);
+        let available = balance.saturating_sub(*reserved);
+        if amount > available {
+            let unused = *reserved == 0;
+            drop(reserved);
+            if unused {
+                self.reserved.remove_if(address, |_, reserved| *reserved == 0);
+            }
+            return Err(crate::error::HazeError::InsufficientBalance { required: amount, available });
+        }
+        *reserved += amount;
         Ok(())
     }
+
+    fn release_balance(&self, address: &Address, amount: u64) {
+        if amount == 0 {
+            return;
+        }
+        self.reserved.remove_if_mut(address, |_, reserved| {
+            *reserved = reserved.saturating_sub(amount);
+            *reserved == 0
+        });
+    }
+
+    fn release_nonce(&self, tx: &Transaction) {
+        let key = (*tx.sender(), crate::types::nonce_lane(tx.nonce()));
+        self.pending_nonces.remove_if_mut(&key, |_, pending| {
+            *pending = pending.saturating_sub(1);
+            *pending == 0
+        });
+    }
+
+    /// Balance of `address` reserved by its transactions waiting in the pool
+    pub fn reserved_balance(&self, address: &Address) -> u64 {
+        self.reserved.get(address).map(|reserved| *reserved).unwrap_or(0)
+    }
+
+    /// Wait until a transaction enters the pool (returns at once if one arrived since the
+    /// last call)
+    pub async fn transaction_added(&self) {
+        self.tx_added.notified().await;
+    }
+
+    /// Pause and maintenance controls of this node
+    pub fn control(&self) -> &NodeControl {
+        &self.control
+    }
+
+    /// Whether block production is paused by an operator (block producers check this before
+    /// each block; blocks from peers are still processed)
+    pub fn production_paused(&self) -> bool {
+        self.control.is_paused(Utc::now().timestamp())
+    }
+
+    /// End an operator pause and wake producers waiting for transactions
+    pub fn resume_production(&self) -> Option<Pause> {
+        let ended = self.control.resume();
+        self.tx_added.notify_one();
+        ended
+    }
+
+    /// Nonce the next transaction from `address` must carry, counting its transactions still in the pool
+    pub fn next_nonce(&self, address: &Address) -> u64 {
+        self.next_lane_nonce(address, 0)
+    }
+
+    /// Nonce the next transaction from `address` on nonce lane `lane` must carry (lane 0 is the
+    /// main sequence; other lanes only count for `consensus.nonce_lane_accounts`)
+    pub fn next_lane_nonce(&self, address: &Address, lane: u16) -> u64 {
+        let account = self.state.get_account(address).unwrap_or_default();
+        let lane_start = if lane == 0 || !self.config.consensus.has_nonce_lanes(address) {
+            account.nonce
+        } else {
+            account.expected_nonce(crate::types::lane_nonce(lane, 0))
+        };
+        self.get_expected_nonce(address, lane_start)
+    }
     
     /// Get transaction from pool by hash
     pub fn get_transaction(&self, tx_hash: &Hash) -> Option<Transaction> {
-        self.tx_pool.get(tx_hash).map(|tx| tx.clone())
+        self.tx_pool.get(tx_hash).map(|tx| tx.transaction().clone())
     }
     
     /// Remove transactions from pool (after they've been included in a block)
-    pub fn remove_transactions_from_pool(&self, transactions: &[Transaction]) {
-        for tx in transactions {
-            let tx_hash = tx.hash();
-            self.tx_pool.remove(&tx_hash);
+    pub fn remove_transactions_from_pool(&self, transactions: &[TxEnvelope]) {
+        self.remove_hashes_from_pool(transactions.iter().map(TxEnvelope::hash));
+    }
+
+    fn remove_hashes_from_pool(&self, hashes: impl IntoIterator<Item = Hash>) {
+        for hash in hashes {
+            if let Some((_, removed)) = self.tx_pool.remove(&hash) {
+                self.pool_fees.lock().remove(removed.transaction().fee());
+                self.release_balance(removed.transaction().sender(), removed.transaction().spend());
+                self.release_nonce(removed.transaction());
+            }
         }
     }
+
+    /// Drop pool transactions that can no longer be included in the next block, with the
+    /// sender's later transactions on the same nonce lane, which would wait for the dropped
+    /// nonce forever. Returns how many were dropped.
+    pub fn evict_expired_transactions(&self) -> usize {
+        let next_height = self.state.current_height() + 1;
+        let mut first_expired: HashMap<(Address, u16), u64> = HashMap::new();
+        for entry in self.tx_pool.iter() {
+            let tx = entry.value().transaction();
+            if tx.is_expired_at(next_height) {
+                let nonce = first_expired.entry((*tx.sender(), crate::types::nonce_lane(tx.nonce()))).or_insert(u64::MAX);
+                *nonce = (*nonce).min(tx.nonce());
+            }
+        }
+        if first_expired.is_empty() {
+            return 0;
+        }
+        let evicted: Vec<TxEnvelope> = self.tx_pool.iter()
+            .filter(|entry| {
+                let tx = entry.value().transaction();
+                first_expired.get(&(*tx.sender(), crate::types::nonce_lane(tx.nonce()))).is_some_and(|first| tx.nonce() >= *first)
+            })
+            .map(|entry| entry.value().clone())
+            .collect();
+        self.remove_transactions_from_pool(&evicted);
+        tracing::debug!("Evicted {} expired transactions from the pool", evicted.len());
+        evicted.len()
+    }
+
+    /// Fee distribution of the transactions waiting in the pool
+    pub fn pool_fees(&self) -> FeeHistogram {
+        self.pool_fees.lock().clone()
+    }
     
+    /// Resubmit a dead-lettered transaction to the pool.
+    ///
+    /// The transaction is re-validated like any new submission; it leaves the
+    /// dead-letter store only once it has been accepted back into the pool.
+    pub fn retry_dead_letter(&self, tx_hash: &Hash) -> Result<()> {
+        let entry = self.state.get_dead_letter(tx_hash).ok_or_else(|| {
+            crate::error::HazeError::InvalidTransaction("Transaction not in dead-letter store".to_string())
+        })?;
+        self.add_transaction(entry.transaction)?;
+        self.state.remove_dead_letter(tx_hash)?;
+        Ok(())
+    }
+
     /// Get transaction pool size
     pub fn tx_pool_size(&self) -> usize {
         self.tx_pool.len()
     }
     
+    /// Network id this node signs and hashes blocks with
+    pub fn network_id(&self) -> Hash {
+        self.network_id
+    }
+
     /// Get current wave number (read access)
     pub fn get_current_wave(&self) -> u64 {
         *self.current_wave.read()
@@ -214,6 +581,122 @@ impl ConsensusEngine {
         *self.last_finalized_height.read()
     }
 
+    /// Record a peer's clock offset measured by the network layer
+    pub fn record_peer_clock_offset(&self, peer: String, offset: PeerOffset) {
+        if self.peer_clock.record(peer, offset) {
+            let status = self.peer_clock.status();
+            if status.drifting {
+                tracing::warn!(
+                    "Local clock drifts {}ms from {} peer(s) (limit {}ms); widening consensus timing windows",
+                    status.median_offset_ms.unwrap_or(0), status.peers, status.max_drift_ms
+                );
+            } else {
+                tracing::info!("Local clock back within {}ms of peers", status.max_drift_ms);
+            }
+        }
+    }
+
+    /// Drop the clock offset of a disconnected peer
+    pub fn remove_peer_clock(&self, peer: &str) {
+        self.peer_clock.remove(peer);
+    }
+
+    /// Clock drift relative to peers (for health checks)
+    pub fn clock_status(&self) -> ClockStatus {
+        self.peer_clock.status()
+    }
+
+    /// Record a peer's chain height, seen in sync responses and gossiped blocks
+    pub fn record_peer_height(&self, peer: String, height: u64) {
+        let mut known = self.peer_heights.entry(peer).or_insert(0);
+        *known = (*known).max(height);
+    }
+
+    /// Drop the height of a disconnected peer
+    pub fn remove_peer_height(&self, peer: &str) {
+        self.peer_heights.remove(peer);
+    }
+
+    /// Versions announced by peers in the handshake
+    pub fn peer_versions(&self) -> &PeerVersions {
+        &self.peer_versions
+    }
+
+    /// Liveness of connected peers (keep-alive pings, dead peer detection, redials)
+    pub fn peer_health(&self) -> &PeerHealth {
+        &self.peer_health
+    }
+
+    /// Lag behind the highest peer (the primary, for a read replica)
+    pub fn replica_status(&self) -> ReplicaStatus {
+        let primary_height = self.peer_heights.iter().map(|entry| *entry.value()).max();
+        let height = self.state.current_height();
+        let lag_blocks = primary_height.map(|primary| primary.saturating_sub(height));
+        let max_lag_blocks = self.config.replica.max_lag_blocks;
+        ReplicaStatus {
+            primary_height,
+            height,
+            lag_blocks,
+            max_lag_blocks,
+            lagging: lag_blocks.is_none_or(|lag| lag > max_lag_blocks),
+        }
+    }
+
+    /// Switch the node into a Byzantine behavior (fault injection for tests)
+    #[doc(hidden)]
+    pub fn set_byzantine_behavior(&self, behavior: ByzantineBehavior) {
+        tracing::warn!("Byzantine test mode: {:?}", behavior);
+        *self.byzantine.write() = behavior;
+    }
+
+    pub fn byzantine_behavior(&self) -> ByzantineBehavior {
+        *self.byzantine.read()
+    }
+
+    /// Whether blocks may be gossiped and served to syncing peers
+    pub fn shares_blocks(&self) -> bool {
+        self.byzantine_behavior() != ByzantineBehavior::WithholdBlocks
+    }
+
+    /// Conflicting twin of a block this node proposed, while equivocating
+    ///
+    /// The twin drops the block's transactions, so it has the same validator and
+    /// height but a different hash.
+    pub fn create_equivocating_block(&self, block: &Block) -> Option<Block> {
+        if self.byzantine_behavior() != ByzantineBehavior::Equivocate {
+            return None;
+        }
+        let mut header = BlockHeader {
+            hash: [0; 32],
+            merkle_root: crate::types::merkle_root(vec![]),
+            ..block.header.clone()
+        };
+        if header.merkle_root == block.header.merkle_root {
+            header.timestamp += 1;
+        }
+        header.hash = header.compute_hash(&self.network_id);
+        Some(Block {
+            header,
+            transactions: vec![],
+            dag_references: block.dag_references.clone(),
+        })
+    }
+
+    /// Equivocations detected in processed blocks (for slashing once it exists)
+    pub fn get_equivocation_evidence(&self) -> Vec<EquivocationEvidence> {
+        self.equivocations.evidence()
+    }
+
+    /// Processed blocks whose state root differed from this node's
+    pub fn get_divergence_incidents(&self) -> Vec<DivergenceIncident> {
+        self.divergences.incidents()
+    }
+
+    /// Waves left unfinalized because they hold a divergent block
+    pub fn divergent_waves(&self) -> Vec<u64> {
+        self.divergences.halted_waves()
+    }
+
     /// Validate transaction
     ///
     /// Performs basic validation checks on a transaction.
@@ -235,17 +718,12 @@ impl ConsensusEngine {
                 // Check that sender has sufficient balance (if account exists)
                 if let Some(account) = self.state.get_account(from) {
                     if account.balance < *amount + *fee {
-                        return Err(crate::error::HazeError::InvalidTransaction(
-                            "Insufficient balance".to_string()
-                        ));
+                        return Err(crate::error::HazeError::InsufficientBalance { required: *amount + *fee, available: account.balance });
                     }
                 }
 
                 // Verify signature
                 self.verify_transaction_signature(tx, from)?;
-                
-                // Check nonce
-                self.validate_nonce(tx)?;
             }
             Transaction::Stake { from, amount, signature, .. } => {
                 if *amount == 0 {
@@ -294,7 +772,12 @@ impl ConsensusEngine {
                 }
                 self.verify_transaction_signature(tx, from)?;
             }
-            Transaction::MistbornAsset { data, signature, .. } => {
+            Transaction::MistbornAsset { from, action, data, signature, .. } => {
+                if *from != data.owner {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "MistbornAsset must be sent by the asset owner".to_string()
+                    ));
+                }
                 // Verify signature
                 if signature.is_empty() {
                     return Err(crate::error::HazeError::InvalidTransaction(
@@ -302,11 +785,75 @@ impl ConsensusEngine {
                     ));
                 }
                 self.verify_transaction_signature(tx, &data.owner)?;
+                if matches!(action, crate::types::AssetAction::Breed) {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Assets are bred with the Breed transaction".to_string()
+                    ));
+                }
+                if matches!(action, crate::types::AssetAction::Upgrade) {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Assets are upgraded with the Upgrade transaction".to_string()
+                    ));
+                }
 
                 // Validate asset data
                 self.validate_asset_data(data)?;
+
+                if let Some(policy) = data.metadata.get(crate::assets::MERGE_POLICY_KEY) {
+                    if !matches!(action, crate::types::AssetAction::Merge) {
+                        return Err(crate::error::HazeError::InvalidTransaction(format!(
+                            "'{}' is only valid for Merge", crate::assets::MERGE_POLICY_KEY
+                        )));
+                    }
+                    crate::assets::MergePolicy::parse(policy)?;
+                }
+
+                if let Some(zone) = data.metadata.get(crate::economy::ZONE_KEY) {
+                    if !matches!(action, crate::types::AssetAction::Create | crate::types::AssetAction::Update) {
+                        return Err(crate::error::HazeError::InvalidTransaction(format!(
+                            "'{}' is only valid for Create and Update", crate::economy::ZONE_KEY
+                        )));
+                    }
+                    // Update may omit game_id; the zone is then checked against the asset's
+                    // collection when the transaction is applied
+                    let unknown = data.game_id.as_deref()
+                        .filter(|game_id| !zone.is_empty() && self.config.economy.zone(game_id, zone).is_none());
+                    if let Some(game_id) = unknown {
+                        return Err(crate::error::HazeError::InvalidTransaction(format!(
+                            "Unknown economic zone '{}' for game '{}'", zone, game_id
+                        )));
+                    }
+                }
+
+                let paymaster = data.metadata.get(crate::vm::PAYMASTER_KEY);
+                if paymaster.is_some_and(|paymaster| crate::types::hex_to_address(paymaster).is_none()) {
+                    return Err(crate::error::HazeError::InvalidTransaction(format!(
+                        "'{}' must be a hex-encoded 32-byte contract address", crate::vm::PAYMASTER_KEY
+                    )));
+                }
+
+                if data.metadata.contains_key(crate::assets::COMMITMENTS_KEY)
+                    && !matches!(action, crate::types::AssetAction::Create | crate::types::AssetAction::Update)
+                {
+                    return Err(crate::error::HazeError::InvalidTransaction(format!(
+                        "'{}' is only valid for Create and Update", crate::assets::COMMITMENTS_KEY
+                    )));
+                }
+                crate::assets::metadata_commitments(&data.metadata)?;
+                if matches!(action, crate::types::AssetAction::Reveal) {
+                    crate::assets::reveal_salts(&data.metadata)?;
+                } else if data.metadata.contains_key(crate::assets::REVEAL_SALTS_KEY) {
+                    return Err(crate::error::HazeError::InvalidTransaction(format!(
+                        "'{}' is only valid for Reveal", crate::assets::REVEAL_SALTS_KEY
+                    )));
+                }
             }
-            Transaction::SetAssetPermissions { owner, signature, .. } => {
+            Transaction::SetAssetPermissions { from, owner, signature, .. } => {
+                if from != owner {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "SetAssetPermissions must be sent by the asset owner".to_string()
+                    ));
+                }
                 if signature.is_empty() {
                     return Err(crate::error::HazeError::InvalidTransaction(
                         "Transaction signature is empty".to_string()
@@ -314,92 +861,596 @@ impl ConsensusEngine {
                 }
                 self.verify_transaction_signature(tx, owner)?;
             }
-        }
-
-        // Replay & chain boundaries
-        let current_height = self.state.current_height();
-        let (chain_id, valid_until_height) = match tx {
-            Transaction::Transfer { chain_id, valid_until_height, .. } => (chain_id.as_ref(), valid_until_height.as_ref()),
-            Transaction::DeployContract { chain_id, valid_until_height, .. } => (chain_id.as_ref(), valid_until_height.as_ref()),
-            Transaction::ContractCall { chain_id, valid_until_height, .. } => (chain_id.as_ref(), valid_until_height.as_ref()),
-            Transaction::MistbornAsset { chain_id, valid_until_height, .. } => (chain_id.as_ref(), valid_until_height.as_ref()),
-            Transaction::Stake { chain_id, valid_until_height, .. } => (chain_id.as_ref(), valid_until_height.as_ref()),
-            Transaction::SetAssetPermissions { chain_id, valid_until_height, .. } => (chain_id.as_ref(), valid_until_height.as_ref()),
-        };
-        if let Some(cid) = chain_id {
-            if *cid != self.config.chain_id {
-                return Err(crate::error::HazeError::InvalidTransaction(
-                    "Transaction chain_id does not match node".to_string()
-                ));
+            Transaction::Unjail { from, signature, .. } => {
+                if signature.is_empty() {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Transaction signature is empty".to_string()
+                    ));
+                }
+                self.verify_transaction_signature(tx, from)?;
+                self.state.tokenomics()
+                    .check_unjail(from, Utc::now().timestamp())
+                    .map_err(|e| crate::error::HazeError::InvalidTransaction(e.to_string()))?;
             }
-        }
-        if let Some(vuh) = valid_until_height {
-            if current_height > *vuh {
-                return Err(crate::error::HazeError::InvalidTransaction(
-                    "Transaction valid_until_height has passed".to_string()
-                ));
+            Transaction::SetAssetHook { from, game_id, hook, signature, .. } => {
+                if game_id.is_empty() || game_id.len() > 128 {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Game ID must be 1-128 bytes".to_string()
+                    ));
+                }
+                if let Some(hook) = hook {
+                    if hook.method.is_empty() {
+                        return Err(crate::error::HazeError::InvalidTransaction(
+                            "Hook method cannot be empty".to_string()
+                        ));
+                    }
+                    if hook.gas_limit == 0 || hook.gas_limit > self.config.vm.gas_limit {
+                        return Err(crate::error::HazeError::InvalidTransaction(
+                            format!("Hook gas limit must be between 1 and {}", self.config.vm.gas_limit)
+                        ));
+                    }
+                }
+                if signature.is_empty() {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Transaction signature is empty".to_string()
+                    ));
+                }
+                self.verify_transaction_signature(tx, from)?;
             }
-        }
-
-        Ok(())
-    }
-
-    /// Verify transaction signature
-    ///
-    /// Verifies that the transaction signature is valid for the signer's address.
-    /// In HAZE, the address is the first 32 bytes of the ED25519 public key.
-    ///
-    /// NOTE: With the unified transaction model, the logical signer is always `from`
-    /// for all user-initiated transactions. The `signer_address` argument is kept
-    /// for backward compatibility but is no longer used.
-    fn verify_transaction_signature(&self, tx: &Transaction, _signer_address: &Address) -> Result<()> {
-        // Determine signer (always `from`) and signature
-        let (signer_address, signature) = match tx {
-            Transaction::Transfer { from, signature, .. } => (from, signature),
-            Transaction::DeployContract { from, signature, .. } => (from, signature),
-            Transaction::ContractCall { from, signature, .. } => (from, signature),
-            Transaction::MistbornAsset { from, signature, .. } => (from, signature),
-            Transaction::Stake { from, signature, .. } => (from, signature),
-            Transaction::SetAssetPermissions { from, signature, .. } => (from, signature),
-        };
-
-        // Get transaction data for signing (transaction without signature field)
-        let tx_data = self.get_transaction_data_for_signing(tx);
-
-        // Verify signature using `from` address as public key (32-byte ED25519 pubkey)
-        let is_valid = verify_signature(signer_address, &tx_data, signature)
-            .map_err(|e| crate::error::HazeError::InvalidTransaction(
-                format!("Signature verification error: {}", e)
-            ))?;
-
-        if !is_valid {
-            return Err(crate::error::HazeError::InvalidTransaction(
-                "Invalid transaction signature".to_string()
-            ));
-        }
-
-        Ok(())
-    }
-
-    /// Validate transaction nonce
-    ///
-    /// Checks that the transaction nonce is correct for the sender account.
-    /// Nonce must be sequential: for existing accounts it must be current_nonce + 1,
-    /// considering pending transactions in the pool. For new accounts it must be 0.
-    ///
-    /// # Arguments
-    /// * `tx` - The transaction to validate
-    ///
-    /// # Errors
-    /// Returns an error if the nonce is invalid (too low, too high, or duplicate).
-    /// Validate asset data
-    ///
-    /// Validates that asset data conforms to HAZE rules:
-    /// - Data size matches density level limits
-    /// - Owner address is valid (non-zero)
-    /// - Metadata is not empty for new assets
-    /// - Attributes are valid
-    fn validate_asset_data(&self, data: &crate::types::AssetData) -> Result<()> {
+            Transaction::CreateAirdrop { from, claims, claims_root, signature, .. } => {
+                if claims.is_empty() || claims.len() > crate::types::MAX_AIRDROP_CLAIMS {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        format!("Airdrop must have 1-{} claims", crate::types::MAX_AIRDROP_CLAIMS)
+                    ));
+                }
+                let mut recipients = HashSet::with_capacity(claims.len());
+                for claim in claims {
+                    if claim.amount == 0 {
+                        return Err(crate::error::HazeError::InvalidTransaction(
+                            "Airdrop claim amount cannot be zero".to_string()
+                        ));
+                    }
+                    if !recipients.insert(claim.address) {
+                        return Err(crate::error::HazeError::InvalidTransaction(
+                            "Airdrop claims contain a duplicate address".to_string()
+                        ));
+                    }
+                }
+                if crate::types::airdrop_claims_root(claims) != *claims_root {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Airdrop claims do not match claims_root".to_string()
+                    ));
+                }
+                if signature.is_empty() {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Transaction signature is empty".to_string()
+                    ));
+                }
+                self.verify_transaction_signature(tx, from)?;
+            }
+            Transaction::CreatePaymentRequest { from, payer, amount, memo, expires_at_height, signature, .. } => {
+                if *amount == 0 {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Payment request amount cannot be zero".to_string()
+                    ));
+                }
+                if memo.len() > crate::types::MAX_PAYMENT_MEMO_LEN {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        format!("Payment request memo exceeds {} bytes", crate::types::MAX_PAYMENT_MEMO_LEN)
+                    ));
+                }
+                if *payer == Some(*from) {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Payment request payer cannot be the requester".to_string()
+                    ));
+                }
+                if *expires_at_height <= self.state.current_height() {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Payment request already expired".to_string()
+                    ));
+                }
+                if signature.is_empty() {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Transaction signature is empty".to_string()
+                    ));
+                }
+                self.verify_transaction_signature(tx, from)?;
+            }
+            Transaction::PayPaymentRequest { from, request_id, amount, fee, signature, .. } => {
+                let request = self.state.get_payment_request(request_id)
+                    .ok_or_else(|| crate::error::HazeError::InvalidTransaction(
+                        "Payment request not found".to_string()
+                    ))?;
+                request.check_payable(from, *amount, self.state.current_height() + 1)?;
+                if let Some(account) = self.state.get_account(from) {
+                    let required = amount.saturating_add(*fee);
+                    if account.balance < required {
+                        return Err(crate::error::HazeError::InsufficientBalance { required, available: account.balance });
+                    }
+                }
+                if signature.is_empty() {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Transaction signature is empty".to_string()
+                    ));
+                }
+                self.verify_transaction_signature(tx, from)?;
+            }
+            Transaction::PlaceOrder { from, game_id, side, price, quantity, assets, signature, .. } => {
+                if game_id.is_empty() || game_id.len() > 128 {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Game ID must be 1-128 bytes".to_string()
+                    ));
+                }
+                if *price == 0 {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Order price cannot be zero".to_string()
+                    ));
+                }
+                if *quantity == 0 || *quantity > crate::types::MAX_ORDER_QUANTITY {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        format!("Order quantity must be 1-{}", crate::types::MAX_ORDER_QUANTITY)
+                    ));
+                }
+                match side {
+                    crate::types::OrderSide::Buy => {
+                        if !assets.is_empty() {
+                            return Err(crate::error::HazeError::InvalidTransaction(
+                                "Buy order cannot list assets".to_string()
+                            ));
+                        }
+                        if price.checked_mul(*quantity).is_none() {
+                            return Err(crate::error::HazeError::InvalidTransaction(
+                                "Order total overflows".to_string()
+                            ));
+                        }
+                    }
+                    crate::types::OrderSide::Sell => {
+                        let unique: std::collections::HashSet<&Hash> = assets.iter().collect();
+                        if assets.len() as u64 != *quantity || unique.len() != assets.len() {
+                            return Err(crate::error::HazeError::InvalidTransaction(
+                                "Sell order must list `quantity` distinct assets".to_string()
+                            ));
+                        }
+                    }
+                }
+                if signature.is_empty() {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Transaction signature is empty".to_string()
+                    ));
+                }
+                self.verify_transaction_signature(tx, from)?;
+            }
+            Transaction::CreateSubscription { from, payee, amount, interval_blocks, end_height, signature, .. } => {
+                if *amount == 0 {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Subscription amount cannot be zero".to_string()
+                    ));
+                }
+                if *interval_blocks == 0 {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Subscription interval must be at least one block".to_string()
+                    ));
+                }
+                if payee == from {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Subscription payee cannot be the payer".to_string()
+                    ));
+                }
+                if end_height.is_some_and(|end| end <= self.state.current_height()) {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Subscription already ended".to_string()
+                    ));
+                }
+                if signature.is_empty() {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Transaction signature is empty".to_string()
+                    ));
+                }
+                self.verify_transaction_signature(tx, from)?;
+            }
+            Transaction::CancelSubscription { from, signature, .. } => {
+                if signature.is_empty() {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Transaction signature is empty".to_string()
+                    ));
+                }
+                self.verify_transaction_signature(tx, from)?;
+            }
+            Transaction::SetReferrer { from, game_id, referrer, signature, .. } => {
+                if game_id.is_empty() || game_id.len() > 128 {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Game ID must be 1-128 bytes".to_string()
+                    ));
+                }
+                if referrer == from {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "A player cannot refer themselves".to_string()
+                    ));
+                }
+                if self.config.economy.referral_program(game_id).is_none() {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        format!("No referral program for '{}'", game_id)
+                    ));
+                }
+                if signature.is_empty() {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Transaction signature is empty".to_string()
+                    ));
+                }
+                self.verify_transaction_signature(tx, from)?;
+            }
+            Transaction::CancelOrder { from, signature, .. } => {
+                if signature.is_empty() {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Transaction signature is empty".to_string()
+                    ));
+                }
+                self.verify_transaction_signature(tx, from)?;
+            }
+            Transaction::CommitLootBox { from, player, game_id, signature, .. } => {
+                if game_id.is_empty() || game_id.len() > 128 {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Game ID must be 1-128 bytes".to_string()
+                    ));
+                }
+                if player == from {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Loot box player cannot be the operator".to_string()
+                    ));
+                }
+                if signature.is_empty() {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Transaction signature is empty".to_string()
+                    ));
+                }
+                self.verify_transaction_signature(tx, from)?;
+            }
+            Transaction::RevealLootBox { from, outcomes, salt, signature, .. } => {
+                if outcomes.is_empty() || outcomes.len() > crate::types::MAX_LOOT_OUTCOMES {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        format!("Loot box must have 1-{} outcomes", crate::types::MAX_LOOT_OUTCOMES)
+                    ));
+                }
+                if outcomes.iter().all(|outcome| outcome.weight == 0) {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Loot box outcomes need a non-zero weight".to_string()
+                    ));
+                }
+                if salt.len() < crate::types::MIN_LOOT_SALT_LEN {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        format!("Loot box salt must be at least {} bytes", crate::types::MIN_LOOT_SALT_LEN)
+                    ));
+                }
+                if signature.is_empty() {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Transaction signature is empty".to_string()
+                    ));
+                }
+                self.verify_transaction_signature(tx, from)?;
+            }
+            Transaction::OpenLootBox { from, signature, .. } | Transaction::ForfeitLootBox { from, signature, .. } => {
+                if signature.is_empty() {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Transaction signature is empty".to_string()
+                    ));
+                }
+                self.verify_transaction_signature(tx, from)?;
+            }
+            Transaction::CreateLeaderboard { from, game_id, season, capacity, submitters, signature, .. } => {
+                if game_id.is_empty() || game_id.len() > 128 {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Game ID must be 1-128 bytes".to_string()
+                    ));
+                }
+                if season.is_empty() || season.len() > 64 {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Season must be 1-64 bytes".to_string()
+                    ));
+                }
+                if *capacity == 0 || *capacity > crate::types::MAX_LEADERBOARD_CAPACITY {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        format!("Leaderboard capacity must be 1-{}", crate::types::MAX_LEADERBOARD_CAPACITY)
+                    ));
+                }
+                if submitters.len() > crate::types::MAX_LEADERBOARD_SUBMITTERS {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        format!("Leaderboard can have at most {} submitters", crate::types::MAX_LEADERBOARD_SUBMITTERS)
+                    ));
+                }
+                if signature.is_empty() {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Transaction signature is empty".to_string()
+                    ));
+                }
+                self.verify_transaction_signature(tx, from)?;
+            }
+            Transaction::SubmitScore { from, signature, .. } => {
+                if signature.is_empty() {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Transaction signature is empty".to_string()
+                    ));
+                }
+                self.verify_transaction_signature(tx, from)?;
+            }
+            Transaction::RegisterSeason { from, game_id, season, start_height, end_height, hooks, signature, .. } => {
+                if game_id.is_empty() || game_id.len() > 128 {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Game ID must be 1-128 bytes".to_string()
+                    ));
+                }
+                if season.is_empty() || season.len() > 64 {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Season must be 1-64 bytes".to_string()
+                    ));
+                }
+                if end_height < start_height {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Season must end at or after its start height".to_string()
+                    ));
+                }
+                if hooks.freeze_collections.len() > crate::types::MAX_SEASON_FREEZES {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        format!("Season can freeze at most {} collections", crate::types::MAX_SEASON_FREEZES)
+                    ));
+                }
+                if let Some(collection) = hooks.freeze_collections.iter().find(|c| !crate::season::can_freeze(game_id, c)) {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        format!("Season of '{}' cannot freeze collection '{}'", game_id, collection)
+                    ));
+                }
+                if hooks.leaderboard_capacity.is_some_and(|capacity| capacity == 0 || capacity > crate::types::MAX_LEADERBOARD_CAPACITY) {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        format!("Leaderboard capacity must be 1-{}", crate::types::MAX_LEADERBOARD_CAPACITY)
+                    ));
+                }
+                if hooks.leaderboard_submitters.len() > crate::types::MAX_LEADERBOARD_SUBMITTERS {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        format!("Leaderboard can have at most {} submitters", crate::types::MAX_LEADERBOARD_SUBMITTERS)
+                    ));
+                }
+                if signature.is_empty() {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Transaction signature is empty".to_string()
+                    ));
+                }
+                self.verify_transaction_signature(tx, from)?;
+            }
+            Transaction::CreateAssetPool { from, game_id, reward_per_block, signature, .. } => {
+                if game_id.is_empty() || game_id.len() > 128 {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Game ID must be 1-128 bytes".to_string()
+                    ));
+                }
+                if *reward_per_block == 0 {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Asset pool reward per block must be positive".to_string()
+                    ));
+                }
+                if signature.is_empty() {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Transaction signature is empty".to_string()
+                    ));
+                }
+                self.verify_transaction_signature(tx, from)?;
+            }
+            Transaction::FundAssetPool { from, amount, signature, .. } => {
+                if *amount == 0 {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Asset pool funding must be positive".to_string()
+                    ));
+                }
+                if signature.is_empty() {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Transaction signature is empty".to_string()
+                    ));
+                }
+                self.verify_transaction_signature(tx, from)?;
+            }
+            Transaction::StakeAsset { from, signature, .. }
+            | Transaction::UnstakeAsset { from, signature, .. }
+            | Transaction::ClaimAssetRewards { from, signature, .. } => {
+                if signature.is_empty() {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Transaction signature is empty".to_string()
+                    ));
+                }
+                self.verify_transaction_signature(tx, from)?;
+            }
+            Transaction::Breed { from, parent_a, parent_b, signature, .. } => {
+                if parent_a == parent_b {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "An asset cannot breed with itself".to_string()
+                    ));
+                }
+                if signature.is_empty() {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Transaction signature is empty".to_string()
+                    ));
+                }
+                self.verify_transaction_signature(tx, from)?;
+            }
+            Transaction::Upgrade { from, asset_id, catalysts, signature, .. } => {
+                if catalysts.len() > crate::types::MAX_UPGRADE_CATALYSTS {
+                    return Err(crate::error::HazeError::InvalidTransaction(format!(
+                        "An upgrade burns at most {} catalysts", crate::types::MAX_UPGRADE_CATALYSTS
+                    )));
+                }
+                let distinct: HashSet<&Hash> = catalysts.iter().collect();
+                if distinct.len() != catalysts.len() || distinct.contains(asset_id) {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Catalysts must be distinct assets other than the upgraded one".to_string()
+                    ));
+                }
+                if signature.is_empty() {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Transaction signature is empty".to_string()
+                    ));
+                }
+                self.verify_transaction_signature(tx, from)?;
+            }
+            Transaction::TransferGovernance { from, amount, signature, .. }
+            | Transaction::StakeGovernance { from, amount, signature, .. }
+            | Transaction::UnstakeGovernance { from, amount, signature, .. } => {
+                if !self.config.governance_token.enabled {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "The governance token is not enabled on this chain".to_string()
+                    ));
+                }
+                if *amount == 0 {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Governance token amount cannot be zero".to_string()
+                    ));
+                }
+                if signature.is_empty() {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Transaction signature is empty".to_string()
+                    ));
+                }
+                self.verify_transaction_signature(tx, from)?;
+            }
+            Transaction::SetCommission { from, rate_bps, signature, .. } => {
+                if *rate_bps > crate::tokenomics::MAX_COMMISSION_BPS {
+                    return Err(crate::error::HazeError::InvalidTransaction(format!(
+                        "Commission cannot exceed {} bps", crate::tokenomics::MAX_COMMISSION_BPS
+                    )));
+                }
+                if signature.is_empty() {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Transaction signature is empty".to_string()
+                    ));
+                }
+                self.verify_transaction_signature(tx, from)?;
+            }
+            Transaction::VoteCommitteeParams { from, max_weight_bps, signature, .. } => {
+                if *max_weight_bps == 0 || *max_weight_bps > crate::tokenomics::MAX_COMMITTEE_WEIGHT_BPS {
+                    return Err(crate::error::HazeError::InvalidTransaction(format!(
+                        "Committee weight cap must be between 1 and {} bps", crate::tokenomics::MAX_COMMITTEE_WEIGHT_BPS
+                    )));
+                }
+                if signature.is_empty() {
+                    return Err(crate::error::HazeError::InvalidTransaction(
+                        "Transaction signature is empty".to_string()
+                    ));
+                }
+                self.verify_transaction_signature(tx, from)?;
+            }
+        }
+
+        // Replay & chain boundaries
+        self.check_chain_id(tx)?;
+        // The transaction must still fit in the next block
+        if tx.is_expired_at(self.state.current_height() + 1) {
+            return Err(crate::error::HazeError::InvalidTransaction(
+                "Transaction valid_until_height has passed".to_string()
+            ));
+        }
+
+        // Every transaction takes the next nonce of its sender
+        self.validate_nonce(tx)?;
+
+        Ok(())
+    }
+
+    /// Reject a transaction bound to another chain
+    fn check_chain_id(&self, tx: &Transaction) -> Result<()> {
+        if tx.chain_id().is_some_and(|chain_id| chain_id != self.config.chain_id) {
+            return Err(crate::error::HazeError::InvalidTransaction(
+                "Transaction chain_id does not match node".to_string()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Verify transaction signature
+    ///
+    /// Verifies that the transaction signature is valid for the signer's address.
+    /// In HAZE, the address is the first 32 bytes of the ED25519 public key.
+    ///
+    /// NOTE: With the unified transaction model, the logical signer is always `from`
+    /// for all user-initiated transactions. The `signer_address` argument is kept
+    /// for backward compatibility but is no longer used.
+    fn verify_transaction_signature(&self, tx: &Transaction, _signer_address: &Address) -> Result<()> {
+        // Dev nodes may accept unsigned test traffic
+        if self.config.dev.relaxed_signatures {
+            return Ok(());
+        }
+
+        // Determine signer (always `from`) and signature
+        let (signer_address, signature) = match tx {
+            Transaction::Transfer { from, signature, .. } => (from, signature),
+            Transaction::DeployContract { from, signature, .. } => (from, signature),
+            Transaction::ContractCall { from, signature, .. } => (from, signature),
+            Transaction::MistbornAsset { from, signature, .. } => (from, signature),
+            Transaction::Stake { from, signature, .. } => (from, signature),
+            Transaction::SetAssetPermissions { from, signature, .. } => (from, signature),
+            Transaction::Unjail { from, signature, .. } => (from, signature),
+            Transaction::SetAssetHook { from, signature, .. } => (from, signature),
+            Transaction::CreateAirdrop { from, signature, .. } => (from, signature),
+            Transaction::CreatePaymentRequest { from, signature, .. } => (from, signature),
+            Transaction::PayPaymentRequest { from, signature, .. } => (from, signature),
+            Transaction::PlaceOrder { from, signature, .. } => (from, signature),
+            Transaction::CancelOrder { from, signature, .. } => (from, signature),
+            Transaction::SetReferrer { from, signature, .. } => (from, signature),
+            Transaction::CreateSubscription { from, signature, .. } => (from, signature),
+            Transaction::CancelSubscription { from, signature, .. } => (from, signature),
+            Transaction::CommitLootBox { from, signature, .. } => (from, signature),
+            Transaction::OpenLootBox { from, signature, .. } => (from, signature),
+            Transaction::RevealLootBox { from, signature, .. } => (from, signature),
+            Transaction::ForfeitLootBox { from, signature, .. } => (from, signature),
+            Transaction::CreateLeaderboard { from, signature, .. } => (from, signature),
+            Transaction::SubmitScore { from, signature, .. } => (from, signature),
+            Transaction::RegisterSeason { from, signature, .. } => (from, signature),
+            Transaction::CreateAssetPool { from, signature, .. } => (from, signature),
+            Transaction::FundAssetPool { from, signature, .. } => (from, signature),
+            Transaction::StakeAsset { from, signature, .. } => (from, signature),
+            Transaction::UnstakeAsset { from, signature, .. } => (from, signature),
+            Transaction::ClaimAssetRewards { from, signature, .. } => (from, signature),
+            Transaction::Breed { from, signature, .. } => (from, signature),
+            Transaction::Upgrade { from, signature, .. } => (from, signature),
+            Transaction::TransferGovernance { from, signature, .. } => (from, signature),
+            Transaction::StakeGovernance { from, signature, .. } => (from, signature),
+            Transaction::UnstakeGovernance { from, signature, .. } => (from, signature),
+            Transaction::SetCommission { from, signature, .. } => (from, signature),
+            Transaction::VoteCommitteeParams { from, signature, .. } => (from, signature),
+        };
+
+        // Get transaction data for signing (transaction without signature field)
+        let tx_data = self.get_transaction_data_for_signing(tx);
+
+        // Verify signature using `from` address as public key (32-byte ED25519 pubkey)
+        let is_valid = verify_signature(signer_address, &tx_data, signature)
+            .map_err(|e| crate::error::HazeError::InvalidTransaction(
+                format!("Signature verification error: {}", e)
+            ))?;
+
+        if !is_valid {
+            return Err(crate::error::HazeError::InvalidTransaction(
+                "Invalid transaction signature".to_string()
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Validate transaction nonce
+    ///
+    /// Checks that the transaction nonce is correct for the sender account.
+    /// Nonce must be sequential: for existing accounts it must be current_nonce + 1,
+    /// considering pending transactions in the pool. For new accounts it must be 0.
+    ///
+    /// # Arguments
+    /// * `tx` - The transaction to validate
+    ///
+    /// # Errors
+    /// Returns an error if the nonce is invalid (too low, too high, or duplicate).
+    /// Validate asset data
+    ///
+    /// Validates that asset data conforms to HAZE rules:
+    /// - Data size matches density level limits
+    /// - Owner address is valid (non-zero)
+    /// - Metadata is not empty for new assets
+    /// - Attributes are valid
+    fn validate_asset_data(&self, data: &crate::types::AssetData) -> Result<()> {
 
         // Validate owner address (must be non-zero)
         if data.owner == [0u8; 32] {
@@ -411,7 +1462,7 @@ impl ConsensusEngine {
         // Calculate total data size
         let metadata_size: usize = data.metadata.values().map(|v| v.len()).sum();
         let attributes_size: usize = data.attributes.iter()
-            .map(|attr| attr.name.len() + attr.value.len())
+            .map(|attr| attr.name.len() + attr.value.encode().len())
             .sum();
         let total_size = metadata_size + attributes_size;
 
@@ -438,6 +1489,13 @@ impl ConsensusEngine {
                     "Asset metadata key too long (max 256 bytes)".to_string()
                 ));
             }
+            if let Some(namespace) = crate::assets::metadata_namespace(key)
+                && (namespace.is_empty() || key.len() == namespace.len() + 1)
+            {
+                return Err(crate::error::HazeError::InvalidTransaction(format!(
+                    "Asset metadata key '{}' must be '<namespace>{}<name>'", key, crate::assets::NAMESPACE_SEPARATOR
+                )));
+            }
             if value.len() > 1024 * 1024 {
                 return Err(crate::error::HazeError::InvalidTransaction(
                     "Asset metadata value too long (max 1MB)".to_string()
@@ -457,11 +1515,16 @@ impl ConsensusEngine {
                     "Asset attribute name too long (max 128 bytes)".to_string()
                 ));
             }
-            if attr.value.len() > 1024 {
+            if attr.value.encode().len() > 1024 {
                 return Err(crate::error::HazeError::InvalidTransaction(
                     "Asset attribute value too long (max 1024 bytes)".to_string()
                 ));
             }
+            if let Err(e) = attr.value.validate() {
+                return Err(crate::error::HazeError::InvalidTransaction(
+                    format!("Asset attribute '{}': {}", attr.name, e)
+                ));
+            }
             // Validate rarity if present (should be between 0.0 and 1.0)
             if let Some(rarity) = attr.rarity {
                 if rarity < 0.0 || rarity > 1.0 {
@@ -489,31 +1552,19 @@ impl ConsensusEngine {
         Ok(())
     }
 
-    fn validate_nonce(&self, tx: &Transaction) -> Result<()> {
-        let (from_address, tx_nonce) = match tx {
-            Transaction::Transfer { from, nonce, .. } => (*from, *nonce),
-            _ => {
-                // Nonce validation only applies to Transfer transactions
-                return Ok(());
-            }
-        };
-
-        // Get current account nonce (0 for new accounts)
-        let current_nonce = self.state
-            .get_account(&from_address)
-            .map(|acc| acc.nonce)
-            .unwrap_or(0);
+    fn validate_nonce(&self, tx: &Transaction) -> Result<()> {
+        let from_address = *tx.sender();
+        let tx_nonce = tx.nonce();
+
+        // Get the next nonce on the transaction's lane (0 for new accounts)
+        let account = self.state.get_account(&from_address).unwrap_or_default();
+        let current_nonce = self.state.expected_nonce(&account, &from_address, tx_nonce)?;
 
         // Get expected nonce considering pending transactions in pool
         let expected_nonce = self.get_expected_nonce(&from_address, current_nonce);
 
         if tx_nonce != expected_nonce {
-            return Err(crate::error::HazeError::InvalidTransaction(
-                format!(
-                    "Invalid nonce: expected {}, got {}",
-                    expected_nonce, tx_nonce
-                )
-            ));
+            return Err(crate::error::HazeError::InvalidNonce { expected: expected_nonce, actual: tx_nonce });
         }
 
         Ok(())
@@ -526,22 +1577,13 @@ impl ConsensusEngine {
     ///
     /// # Arguments
     /// * `address` - The account address
-    /// * `current_nonce` - The current nonce from state (0 for new accounts)
+    /// * `current_nonce` - The current nonce from state on the lane to check (0 for new accounts)
     ///
     /// # Returns
-    /// The next expected nonce (current_nonce + number_of_pending_txs + 1)
+    /// The next expected nonce (current_nonce + number_of_pending_txs on the same lane)
     fn get_expected_nonce(&self, address: &Address, current_nonce: u64) -> u64 {
-        // Count pending transactions from this address in the pool
-        let mut pending_count = 0u64;
-        for entry in self.tx_pool.iter() {
-            if let Transaction::Transfer { from, .. } = entry.value() {
-                if from == address {
-                    pending_count += 1;
-                }
-            }
-        }
-
-        // Expected nonce is current nonce plus pending transactions
+        let lane = crate::types::nonce_lane(current_nonce);
+        let pending_count = self.pending_nonces.get(&(*address, lane)).map(|pending| *pending).unwrap_or(0);
         current_nonce + pending_count
     }
 
@@ -558,8 +1600,16 @@ impl ConsensusEngine {
     /// Get transaction data for signing (transaction without signature field)
     ///
     /// Creates a serialized representation of the transaction without the signature
-    /// for use in signature verification. The data format matches what was signed.
-    fn get_transaction_data_for_signing(&self, tx: &Transaction) -> Vec<u8> {
+    /// for use in signature verification: the node's network id followed by the
+    /// per-variant payload. The data format matches what was signed.
+    pub fn get_transaction_data_for_signing(&self, tx: &Transaction) -> Vec<u8> {
+        let mut data = self.network_id.to_vec();
+        data.extend_from_slice(&Self::transaction_payload(tx));
+        data
+    }
+
+    /// Per-variant signing payload, without the network id prefix
+    fn transaction_payload(tx: &Transaction) -> Vec<u8> {
         match tx {
             Transaction::Transfer { from, to, amount, fee, nonce, chain_id, valid_until_height, .. } => {
                 let mut data = Vec::new();
@@ -610,6 +1660,9 @@ impl ConsensusEngine {
                     crate::types::AssetAction::Evaporate => 3,
                     crate::types::AssetAction::Merge => 4,
                     crate::types::AssetAction::Split => 5,
+                    crate::types::AssetAction::Reveal => 6,
+                    crate::types::AssetAction::Breed => 7,
+                    crate::types::AssetAction::Upgrade => 8,
                 });
                 serialized.extend_from_slice(asset_id);
                 serialized.extend_from_slice(&data.owner);
@@ -630,6 +1683,10 @@ impl ConsensusEngine {
                             }
                         }
                     }
+                    if let Some(policy) = data.metadata.get(crate::assets::MERGE_POLICY_KEY) {
+                        serialized.extend_from_slice(crate::assets::MERGE_POLICY_KEY.as_bytes());
+                        serialized.extend_from_slice(policy.as_bytes());
+                    }
                 }
 
                 // For Split: include components in signature
@@ -637,6 +1694,31 @@ impl ConsensusEngine {
                     if let Some(components_str) = data.metadata.get("_components") {
                         serialized.extend_from_slice(components_str.as_bytes());
                     }
+                    // The manifest decides what each component receives; tag it so it cannot
+                    // be confused with a components list
+                    if let Some(manifest) = data.metadata.get(crate::assets::SPLIT_MANIFEST_KEY) {
+                        serialized.extend_from_slice(crate::assets::SPLIT_MANIFEST_KEY.as_bytes());
+                        serialized.extend_from_slice(manifest.as_bytes());
+                    }
+                }
+
+                // The zone decides which fees are charged; an empty value (leave the zone)
+                // is signed too
+                if let Some(zone) = data.metadata.get(crate::economy::ZONE_KEY) {
+                    serialized.extend_from_slice(crate::economy::ZONE_KEY.as_bytes());
+                    serialized.extend_from_slice(zone.as_bytes());
+                }
+
+                // The paymaster decides who pays the gas
+                if let Some(paymaster) = data.metadata.get(crate::vm::PAYMASTER_KEY) {
+                    serialized.extend_from_slice(crate::vm::PAYMASTER_KEY.as_bytes());
+                    serialized.extend_from_slice(paymaster.as_bytes());
+                }
+
+                // Commitments bind the encrypted fields to the plaintext a Reveal must publish
+                if let Some(commitments) = data.metadata.get(crate::assets::COMMITMENTS_KEY) {
+                    serialized.extend_from_slice(crate::assets::COMMITMENTS_KEY.as_bytes());
+                    serialized.extend_from_slice(commitments.as_bytes());
                 }
 
                 // Common fee/nonce fields for MistbornAsset
@@ -672,6 +1754,391 @@ impl ConsensusEngine {
                 Self::append_chain_fields(&mut data, *chain_id, *valid_until_height);
                 data
             }
+            Transaction::Unjail { from, fee, nonce, chain_id, valid_until_height, .. } => {
+                let mut data = Vec::new();
+                data.extend_from_slice(b"Unjail");
+                data.extend_from_slice(from);
+                data.extend_from_slice(&fee.to_le_bytes());
+                data.extend_from_slice(&nonce.to_le_bytes());
+                Self::append_chain_fields(&mut data, *chain_id, *valid_until_height);
+                data
+            }
+            Transaction::SetAssetHook { from, game_id, hook, fee, nonce, chain_id, valid_until_height, .. } => {
+                let mut data = Vec::new();
+                data.extend_from_slice(b"SetAssetHook");
+                data.extend_from_slice(from);
+                data.extend_from_slice(game_id.as_bytes());
+                data.push(0); // Null terminator for game_id
+                match hook {
+                    Some(hook) => {
+                        data.push(1);
+                        data.extend_from_slice(&hook.contract);
+                        data.extend_from_slice(hook.method.as_bytes());
+                        data.push(0); // Null terminator for method
+                        data.extend_from_slice(&hook.gas_limit.to_le_bytes());
+                        data.push(match hook.on_failure {
+                            crate::types::HookFailureMode::Warn => 0,
+                            crate::types::HookFailureMode::Revert => 1,
+                        });
+                    }
+                    None => data.push(0),
+                }
+                data.extend_from_slice(&fee.to_le_bytes());
+                data.extend_from_slice(&nonce.to_le_bytes());
+                Self::append_chain_fields(&mut data, *chain_id, *valid_until_height);
+                data
+            }
+            Transaction::CreateAirdrop { from, claims, claims_root, fee, nonce, chain_id, valid_until_height, .. } => {
+                let mut data = Vec::new();
+                data.extend_from_slice(b"CreateAirdrop");
+                data.extend_from_slice(from);
+                data.extend_from_slice(claims_root);
+                data.extend_from_slice(&(claims.len() as u32).to_le_bytes());
+                data.extend_from_slice(&fee.to_le_bytes());
+                data.extend_from_slice(&nonce.to_le_bytes());
+                Self::append_chain_fields(&mut data, *chain_id, *valid_until_height);
+                data
+            }
+            Transaction::CreatePaymentRequest { from, payer, amount, memo, expires_at_height, fee, nonce, chain_id, valid_until_height, .. } => {
+                let mut data = Vec::new();
+                data.extend_from_slice(b"CreatePaymentRequest");
+                data.extend_from_slice(from);
+                match payer {
+                    Some(payer) => {
+                        data.push(1);
+                        data.extend_from_slice(payer);
+                    }
+                    None => data.push(0),
+                }
+                data.extend_from_slice(&amount.to_le_bytes());
+                // Length-prefixed: the memo is free text and may contain zero bytes
+                data.extend_from_slice(&(memo.len() as u32).to_le_bytes());
+                data.extend_from_slice(memo.as_bytes());
+                data.extend_from_slice(&expires_at_height.to_le_bytes());
+                data.extend_from_slice(&fee.to_le_bytes());
+                data.extend_from_slice(&nonce.to_le_bytes());
+                Self::append_chain_fields(&mut data, *chain_id, *valid_until_height);
+                data
+            }
+            Transaction::PayPaymentRequest { from, request_id, amount, fee, nonce, chain_id, valid_until_height, .. } => {
+                let mut data = Vec::new();
+                data.extend_from_slice(b"PayPaymentRequest");
+                data.extend_from_slice(from);
+                data.extend_from_slice(request_id);
+                data.extend_from_slice(&amount.to_le_bytes());
+                data.extend_from_slice(&fee.to_le_bytes());
+                data.extend_from_slice(&nonce.to_le_bytes());
+                Self::append_chain_fields(&mut data, *chain_id, *valid_until_height);
+                data
+            }
+            Transaction::PlaceOrder { from, game_id, side, price, quantity, assets, fee, nonce, chain_id, valid_until_height, .. } => {
+                let mut data = Vec::new();
+                data.extend_from_slice(b"PlaceOrder");
+                data.extend_from_slice(from);
+                data.extend_from_slice(&(game_id.len() as u32).to_le_bytes());
+                data.extend_from_slice(game_id.as_bytes());
+                data.push(match side {
+                    crate::types::OrderSide::Buy => 0,
+                    crate::types::OrderSide::Sell => 1,
+                });
+                data.extend_from_slice(&price.to_le_bytes());
+                data.extend_from_slice(&quantity.to_le_bytes());
+                data.extend_from_slice(&(assets.len() as u32).to_le_bytes());
+                for asset_id in assets {
+                    data.extend_from_slice(asset_id);
+                }
+                data.extend_from_slice(&fee.to_le_bytes());
+                data.extend_from_slice(&nonce.to_le_bytes());
+                Self::append_chain_fields(&mut data, *chain_id, *valid_until_height);
+                data
+            }
+            Transaction::CancelOrder { from, order_id, fee, nonce, chain_id, valid_until_height, .. } => {
+                let mut data = Vec::new();
+                data.extend_from_slice(b"CancelOrder");
+                data.extend_from_slice(from);
+                data.extend_from_slice(order_id);
+                data.extend_from_slice(&fee.to_le_bytes());
+                data.extend_from_slice(&nonce.to_le_bytes());
+                Self::append_chain_fields(&mut data, *chain_id, *valid_until_height);
+                data
+            }
+            Transaction::CreateSubscription { from, payee, amount, interval_blocks, end_height, fee, nonce, chain_id, valid_until_height, .. } => {
+                let mut data = Vec::new();
+                data.extend_from_slice(b"CreateSubscription");
+                data.extend_from_slice(from);
+                data.extend_from_slice(payee);
+                data.extend_from_slice(&amount.to_le_bytes());
+                data.extend_from_slice(&interval_blocks.to_le_bytes());
+                match end_height {
+                    Some(end_height) => {
+                        data.push(1);
+                        data.extend_from_slice(&end_height.to_le_bytes());
+                    }
+                    None => data.push(0),
+                }
+                data.extend_from_slice(&fee.to_le_bytes());
+                data.extend_from_slice(&nonce.to_le_bytes());
+                Self::append_chain_fields(&mut data, *chain_id, *valid_until_height);
+                data
+            }
+            Transaction::CancelSubscription { from, subscription_id, fee, nonce, chain_id, valid_until_height, .. } => {
+                let mut data = Vec::new();
+                data.extend_from_slice(b"CancelSubscription");
+                data.extend_from_slice(from);
+                data.extend_from_slice(subscription_id);
+                data.extend_from_slice(&fee.to_le_bytes());
+                data.extend_from_slice(&nonce.to_le_bytes());
+                Self::append_chain_fields(&mut data, *chain_id, *valid_until_height);
+                data
+            }
+            Transaction::SetReferrer { from, game_id, referrer, fee, nonce, chain_id, valid_until_height, .. } => {
+                let mut data = Vec::new();
+                data.extend_from_slice(b"SetReferrer");
+                data.extend_from_slice(from);
+                data.extend_from_slice(&(game_id.len() as u32).to_le_bytes());
+                data.extend_from_slice(game_id.as_bytes());
+                data.extend_from_slice(referrer);
+                data.extend_from_slice(&fee.to_le_bytes());
+                data.extend_from_slice(&nonce.to_le_bytes());
+                Self::append_chain_fields(&mut data, *chain_id, *valid_until_height);
+                data
+            }
+            Transaction::CommitLootBox { from, player, game_id, commitment, bond, fee, nonce, chain_id, valid_until_height, .. } => {
+                let mut data = Vec::new();
+                data.extend_from_slice(b"CommitLootBox");
+                data.extend_from_slice(from);
+                data.extend_from_slice(player);
+                data.extend_from_slice(&(game_id.len() as u32).to_le_bytes());
+                data.extend_from_slice(game_id.as_bytes());
+                data.extend_from_slice(commitment);
+                data.extend_from_slice(&bond.to_le_bytes());
+                data.extend_from_slice(&fee.to_le_bytes());
+                data.extend_from_slice(&nonce.to_le_bytes());
+                Self::append_chain_fields(&mut data, *chain_id, *valid_until_height);
+                data
+            }
+            Transaction::OpenLootBox { from, box_id, fee, nonce, chain_id, valid_until_height, .. } => {
+                let mut data = Vec::new();
+                data.extend_from_slice(b"OpenLootBox");
+                data.extend_from_slice(from);
+                data.extend_from_slice(box_id);
+                data.extend_from_slice(&fee.to_le_bytes());
+                data.extend_from_slice(&nonce.to_le_bytes());
+                Self::append_chain_fields(&mut data, *chain_id, *valid_until_height);
+                data
+            }
+            Transaction::RevealLootBox { from, box_id, outcomes, salt, fee, nonce, chain_id, valid_until_height, .. } => {
+                let mut data = Vec::new();
+                data.extend_from_slice(b"RevealLootBox");
+                data.extend_from_slice(from);
+                data.extend_from_slice(box_id);
+                // The commitment covers the outcomes and the salt
+                data.extend_from_slice(&crate::types::loot_commitment(salt, outcomes));
+                data.extend_from_slice(&fee.to_le_bytes());
+                data.extend_from_slice(&nonce.to_le_bytes());
+                Self::append_chain_fields(&mut data, *chain_id, *valid_until_height);
+                data
+            }
+            Transaction::ForfeitLootBox { from, box_id, fee, nonce, chain_id, valid_until_height, .. } => {
+                let mut data = Vec::new();
+                data.extend_from_slice(b"ForfeitLootBox");
+                data.extend_from_slice(from);
+                data.extend_from_slice(box_id);
+                data.extend_from_slice(&fee.to_le_bytes());
+                data.extend_from_slice(&nonce.to_le_bytes());
+                Self::append_chain_fields(&mut data, *chain_id, *valid_until_height);
+                data
+            }
+            Transaction::CreateLeaderboard {
+                from, game_id, season, capacity, end_height, submitters, fee, nonce, chain_id, valid_until_height, ..
+            } => {
+                let mut data = Vec::new();
+                data.extend_from_slice(b"CreateLeaderboard");
+                data.extend_from_slice(from);
+                data.extend_from_slice(&(game_id.len() as u32).to_le_bytes());
+                data.extend_from_slice(game_id.as_bytes());
+                data.extend_from_slice(&(season.len() as u32).to_le_bytes());
+                data.extend_from_slice(season.as_bytes());
+                data.extend_from_slice(&capacity.to_le_bytes());
+                data.extend_from_slice(&end_height.to_le_bytes());
+                data.extend_from_slice(&(submitters.len() as u32).to_le_bytes());
+                for submitter in submitters {
+                    data.extend_from_slice(submitter);
+                }
+                data.extend_from_slice(&fee.to_le_bytes());
+                data.extend_from_slice(&nonce.to_le_bytes());
+                Self::append_chain_fields(&mut data, *chain_id, *valid_until_height);
+                data
+            }
+            Transaction::SubmitScore { from, board_id, player, score, fee, nonce, chain_id, valid_until_height, .. } => {
+                let mut data = Vec::new();
+                data.extend_from_slice(b"SubmitScore");
+                data.extend_from_slice(from);
+                data.extend_from_slice(board_id);
+                data.extend_from_slice(player);
+                data.extend_from_slice(&score.to_le_bytes());
+                data.extend_from_slice(&fee.to_le_bytes());
+                data.extend_from_slice(&nonce.to_le_bytes());
+                Self::append_chain_fields(&mut data, *chain_id, *valid_until_height);
+                data
+            }
+            Transaction::RegisterSeason {
+                from, game_id, season, start_height, end_height, hooks, fee, nonce, chain_id, valid_until_height, ..
+            } => {
+                let mut data = Vec::new();
+                data.extend_from_slice(b"RegisterSeason");
+                data.extend_from_slice(from);
+                data.extend_from_slice(&(game_id.len() as u32).to_le_bytes());
+                data.extend_from_slice(game_id.as_bytes());
+                data.extend_from_slice(&(season.len() as u32).to_le_bytes());
+                data.extend_from_slice(season.as_bytes());
+                data.extend_from_slice(&start_height.to_le_bytes());
+                data.extend_from_slice(&end_height.to_le_bytes());
+                data.extend_from_slice(&(hooks.freeze_collections.len() as u32).to_le_bytes());
+                for collection in &hooks.freeze_collections {
+                    data.extend_from_slice(&(collection.len() as u32).to_le_bytes());
+                    data.extend_from_slice(collection.as_bytes());
+                }
+                data.push(hooks.snapshot_holders as u8);
+                // 0 = no leaderboard (capacity 0 is rejected)
+                data.extend_from_slice(&hooks.leaderboard_capacity.unwrap_or(0).to_le_bytes());
+                data.extend_from_slice(&(hooks.leaderboard_submitters.len() as u32).to_le_bytes());
+                for submitter in &hooks.leaderboard_submitters {
+                    data.extend_from_slice(submitter);
+                }
+                data.extend_from_slice(&fee.to_le_bytes());
+                data.extend_from_slice(&nonce.to_le_bytes());
+                Self::append_chain_fields(&mut data, *chain_id, *valid_until_height);
+                data
+            }
+            Transaction::CreateAssetPool { from, game_id, reward_per_block, lock_blocks, deposit, fee, nonce, chain_id, valid_until_height, .. } => {
+                let mut data = Vec::new();
+                data.extend_from_slice(b"CreateAssetPool");
+                data.extend_from_slice(from);
+                data.extend_from_slice(&(game_id.len() as u32).to_le_bytes());
+                data.extend_from_slice(game_id.as_bytes());
+                data.extend_from_slice(&reward_per_block.to_le_bytes());
+                data.extend_from_slice(&lock_blocks.to_le_bytes());
+                data.extend_from_slice(&deposit.to_le_bytes());
+                data.extend_from_slice(&fee.to_le_bytes());
+                data.extend_from_slice(&nonce.to_le_bytes());
+                Self::append_chain_fields(&mut data, *chain_id, *valid_until_height);
+                data
+            }
+            Transaction::FundAssetPool { from, pool_id, amount, fee, nonce, chain_id, valid_until_height, .. } => {
+                let mut data = Vec::new();
+                data.extend_from_slice(b"FundAssetPool");
+                data.extend_from_slice(from);
+                data.extend_from_slice(pool_id);
+                data.extend_from_slice(&amount.to_le_bytes());
+                data.extend_from_slice(&fee.to_le_bytes());
+                data.extend_from_slice(&nonce.to_le_bytes());
+                Self::append_chain_fields(&mut data, *chain_id, *valid_until_height);
+                data
+            }
+            Transaction::StakeAsset { from, pool_id, asset_id, fee, nonce, chain_id, valid_until_height, .. } => {
+                let mut data = Vec::new();
+                data.extend_from_slice(b"StakeAsset");
+                data.extend_from_slice(from);
+                data.extend_from_slice(pool_id);
+                data.extend_from_slice(asset_id);
+                data.extend_from_slice(&fee.to_le_bytes());
+                data.extend_from_slice(&nonce.to_le_bytes());
+                Self::append_chain_fields(&mut data, *chain_id, *valid_until_height);
+                data
+            }
+            Transaction::UnstakeAsset { from, asset_id, fee, nonce, chain_id, valid_until_height, .. } => {
+                let mut data = Vec::new();
+                data.extend_from_slice(b"UnstakeAsset");
+                data.extend_from_slice(from);
+                data.extend_from_slice(asset_id);
+                data.extend_from_slice(&fee.to_le_bytes());
+                data.extend_from_slice(&nonce.to_le_bytes());
+                Self::append_chain_fields(&mut data, *chain_id, *valid_until_height);
+                data
+            }
+            Transaction::ClaimAssetRewards { from, pool_id, fee, nonce, chain_id, valid_until_height, .. } => {
+                let mut data = Vec::new();
+                data.extend_from_slice(b"ClaimAssetRewards");
+                data.extend_from_slice(from);
+                data.extend_from_slice(pool_id);
+                data.extend_from_slice(&fee.to_le_bytes());
+                data.extend_from_slice(&nonce.to_le_bytes());
+                Self::append_chain_fields(&mut data, *chain_id, *valid_until_height);
+                data
+            }
+            Transaction::Breed { from, parent_a, parent_b, fee, nonce, chain_id, valid_until_height, .. } => {
+                let mut data = Vec::new();
+                data.extend_from_slice(b"Breed");
+                data.extend_from_slice(from);
+                data.extend_from_slice(parent_a);
+                data.extend_from_slice(parent_b);
+                data.extend_from_slice(&fee.to_le_bytes());
+                data.extend_from_slice(&nonce.to_le_bytes());
+                Self::append_chain_fields(&mut data, *chain_id, *valid_until_height);
+                data
+            }
+            Transaction::Upgrade { from, asset_id, catalysts, fee, nonce, chain_id, valid_until_height, .. } => {
+                let mut data = Vec::new();
+                data.extend_from_slice(b"Upgrade");
+                data.extend_from_slice(from);
+                data.extend_from_slice(asset_id);
+                data.extend_from_slice(&(catalysts.len() as u32).to_le_bytes());
+                for catalyst in catalysts {
+                    data.extend_from_slice(catalyst);
+                }
+                data.extend_from_slice(&fee.to_le_bytes());
+                data.extend_from_slice(&nonce.to_le_bytes());
+                Self::append_chain_fields(&mut data, *chain_id, *valid_until_height);
+                data
+            }
+            Transaction::TransferGovernance { from, to, amount, fee, nonce, chain_id, valid_until_height, .. } => {
+                let mut data = Vec::new();
+                data.extend_from_slice(b"TransferGovernance");
+                data.extend_from_slice(from);
+                data.extend_from_slice(to);
+                data.extend_from_slice(&amount.to_le_bytes());
+                data.extend_from_slice(&fee.to_le_bytes());
+                data.extend_from_slice(&nonce.to_le_bytes());
+                Self::append_chain_fields(&mut data, *chain_id, *valid_until_height);
+                data
+            }
+            Transaction::StakeGovernance { from, amount, fee, nonce, chain_id, valid_until_height, .. }
+            | Transaction::UnstakeGovernance { from, amount, fee, nonce, chain_id, valid_until_height, .. } => {
+                let mut data = Vec::new();
+                data.extend_from_slice(if matches!(tx, Transaction::StakeGovernance { .. }) {
+                    b"StakeGovernance".as_slice()
+                } else {
+                    b"UnstakeGovernance".as_slice()
+                });
+                data.extend_from_slice(from);
+                data.extend_from_slice(&amount.to_le_bytes());
+                data.extend_from_slice(&fee.to_le_bytes());
+                data.extend_from_slice(&nonce.to_le_bytes());
+                Self::append_chain_fields(&mut data, *chain_id, *valid_until_height);
+                data
+            }
+            Transaction::SetCommission { from, rate_bps, fee, nonce, chain_id, valid_until_height, .. } => {
+                let mut data = Vec::new();
+                data.extend_from_slice(b"SetCommission");
+                data.extend_from_slice(from);
+                data.extend_from_slice(&rate_bps.to_le_bytes());
+                data.extend_from_slice(&fee.to_le_bytes());
+                data.extend_from_slice(&nonce.to_le_bytes());
+                Self::append_chain_fields(&mut data, *chain_id, *valid_until_height);
+                data
+            }
+            Transaction::VoteCommitteeParams { from, min_stake, max_weight_bps, fee, nonce, chain_id, valid_until_height, .. } => {
+                let mut data = Vec::new();
+                data.extend_from_slice(b"VoteCommitteeParams");
+                data.extend_from_slice(from);
+                data.extend_from_slice(&min_stake.to_le_bytes());
+                data.extend_from_slice(&max_weight_bps.to_le_bytes());
+                data.extend_from_slice(&fee.to_le_bytes());
+                data.extend_from_slice(&nonce.to_le_bytes());
+                Self::append_chain_fields(&mut data, *chain_id, *valid_until_height);
+                data
+            }
         }
     }
 
@@ -680,13 +2147,21 @@ impl ConsensusEngine {
         // Check committee rotation (using interior mutability)
         self.check_and_rotate_committee()?;
         
-        // Collect transactions from pool
-        let mut transactions = Vec::new();
+        // Expired transactions cannot be included
+        self.evict_expired_transactions();
+
+        // Collect transactions from pool (envelopes keep the hash computed at admission), lowest
+        // nonces first so each sender's transactions are included in order and without gaps
         let max_txs = self.config.consensus.max_transactions_per_block;
-        
-        for entry in self.tx_pool.iter().take(max_txs) {
-            transactions.push(entry.value().clone());
-        }
+        let mut queue: Vec<(u64, Address, Hash)> = self.tx_pool.iter()
+            .map(|entry| (entry.value().transaction().nonce(), *entry.value().transaction().sender(), *entry.key()))
+            .collect();
+        queue.sort_unstable();
+        let envelopes: Vec<TxEnvelope> = queue.into_iter()
+            .filter_map(|(_, _, hash)| self.tx_pool.get(&hash).map(|entry| entry.value().clone()))
+            .take(max_txs)
+            .collect();
+        let tx_hashes: Vec<Hash> = envelopes.iter().map(|tx| tx.hash()).collect();
         
         // If no transactions, don't create empty block (for MVP, we can create empty blocks)
         // But for better UX, we'll still create blocks even if empty
@@ -695,7 +2170,10 @@ impl ConsensusEngine {
         let height = self.state.current_height();
         
         // Get DAG references (parent blocks)
-        let dag_refs = self.get_dag_references()?;
+        let mut dag_refs = self.get_dag_references()?;
+        if self.byzantine_behavior() == ByzantineBehavior::InvalidReferences {
+            dag_refs.push(crate::types::sha256(format!("byzantine-ref-{}", height + 1).as_bytes()));
+        }
         
         // Create block header
         let parent_hash = self.get_parent_hash()?;
@@ -703,28 +2181,66 @@ impl ConsensusEngine {
             hash: [0; 32], // Will be computed
             parent_hash,
             height: height + 1,
-            timestamp: Utc::now().timestamp(),
+            timestamp: self.next_block_timestamp(),
             validator,
-            merkle_root: self.compute_merkle_root(&transactions)?,
+            merkle_root: crate::types::merkle_root(tx_hashes),
             state_root: self.state.compute_state_root(),
             wave_number: *self.current_wave.read(),
             committee_id: *self.current_committee_id.read(),
+            protocol_version: PROTOCOL_VERSION,
+            state_version: STATE_VERSION,
+            signals: self.state.features().signal_bits(),
         };
         
-        header.hash = header.compute_hash();
+        header.hash = header.compute_hash(&self.network_id);
 
+        // The transactions stay in the pool until `process_block` has applied the block, so
+        // a block that fails to apply loses none of them
         let block = Block {
             header,
-            transactions: transactions.clone(),
+            transactions: envelopes.into_iter().map(TxEnvelope::into_transaction).collect(),
             dag_references: dag_refs,
         };
-        
-        // Remove transactions from pool after creating block
-        self.remove_transactions_from_pool(&transactions);
 
         Ok(block)
     }
 
+    /// Local time, raised to the median of recent blocks if the local clock lags the chain
+    fn next_block_timestamp(&self) -> i64 {
+        let now = Utc::now().timestamp();
+        self.state.median_time_past().map_or(now, |median| now.max(median))
+    }
+
+    /// Reject blocks timestamped before the median of the last `median_time_window` blocks,
+    /// or more than `max_block_time_drift_secs` ahead of the local clock (plus the
+    /// measured drift while the local clock is flagged as drifting from peers)
+    fn validate_block_timestamp(&self, block: &Block) -> Result<()> {
+        let timestamp = block.header.timestamp;
+        let max_drift_secs = self.config.consensus.max_block_time_drift_secs
+            + self.peer_clock.drift_allowance_ms().div_ceil(1000);
+        let max_allowed = Utc::now().timestamp() + max_drift_secs as i64;
+        if timestamp > max_allowed {
+            return Err(crate::error::HazeError::InvalidBlock(
+                format!(
+                    "Block timestamp {} is more than {}s ahead of local time",
+                    timestamp,
+                    max_drift_secs
+                )
+            ));
+        }
+        if let Some(median) = self.state.median_time_past().filter(|median| timestamp < *median) {
+            return Err(crate::error::HazeError::InvalidBlock(
+                format!(
+                    "Block timestamp {} is before the median time {} of the last {} blocks",
+                    timestamp,
+                    median,
+                    self.config.consensus.median_time_window
+                )
+            ));
+        }
+        Ok(())
+    }
+
     /// Get DAG references for new block (smart referencing)
     fn get_dag_references(&self) -> Result<Vec<Hash>> {
         let dag = self.dag.read();
@@ -826,44 +2342,60 @@ impl ConsensusEngine {
         };
         
         if should_rotate {
-            // Use a workaround: we can't mutate self, so we'll skip rotation here
-            // In a real implementation, this would need to be handled differently
-            // For now, we'll just log a warning
-            tracing::warn!("Committee rotation needed but create_block is not mutable");
+            self.initialize_committee()?;
         }
         
         Ok(())
     }
 
-    /// Compute merkle root
-    fn compute_merkle_root(&self, transactions: &[Transaction]) -> Result<Hash> {
-        if transactions.is_empty() {
-            return Ok([0; 32]);
+    /// Process block (add to DAG)
+    pub fn process_block(&self, block: &Block) -> Result<()> {
+        let block_hash = block.header.hash;
+        let block_height = block.header.height;
+        
+        // Blocks of a protocol this build does not speak, or whose state root was computed
+        // with another state format, cannot be validated here
+        let header = &block.header;
+        if !(MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(&header.protocol_version) {
+            return Err(crate::error::HazeError::InvalidBlock(format!(
+                "Block protocol version {} is outside the supported range {}-{}",
+                header.protocol_version, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION
+            )));
+        }
+        if header.state_version != STATE_VERSION {
+            return Err(crate::error::HazeError::InvalidBlock(format!(
+                "Block state version {} does not match this node's state version {}",
+                header.state_version, STATE_VERSION
+            )));
+        }
+        // Hashes of protocol 1 and 2 headers do not cover upgrade signals
+        if header.protocol_version < 3 && header.signals != 0 {
+            return Err(crate::error::HazeError::InvalidBlock(format!(
+                "Protocol {} blocks cannot carry upgrade signals", header.protocol_version
+            )));
         }
         
-        let mut hashes: Vec<Hash> = transactions.iter().map(|tx| tx.hash()).collect();
+        // The hash commits to the network id, so blocks from another network never match
+        if block.header.compute_hash(&self.network_id) != block_hash {
+            return Err(crate::error::HazeError::InvalidBlock(format!(
+                "Block hash {} does not match its header on this network",
+                crate::types::hash_to_hex(&block_hash)
+            )));
+        }
         
-        while hashes.len() > 1 {
-            let mut next_level = Vec::new();
-            for chunk in hashes.chunks(2) {
-                if chunk.len() == 2 {
-                    let combined = [chunk[0].as_ref(), chunk[1].as_ref()].concat();
-                    next_level.push(crate::types::sha256(&combined));
-                } else {
-                    next_level.push(chunk[0]);
-                }
+        // Transactions are checked like at pool admission: a peer's block may carry ones that
+        // never went through a pool, e.g. unsigned or signed for another network
+        for tx in &block.transactions {
+            let checked = self.check_transaction_size(tx)
+                .and_then(|()| self.check_chain_id(tx))
+                .and_then(|()| self.verify_transaction_signature(tx, tx.sender()));
+            if let Err(e) = checked {
+                return Err(crate::error::HazeError::InvalidBlock(format!(
+                    "Transaction {}: {}", crate::types::hash_to_hex(&tx.hash()), e
+                )));
             }
-            hashes = next_level;
         }
         
-        Ok(hashes[0])
-    }
-
-    /// Process block (add to DAG)
-    pub fn process_block(&self, block: &Block) -> Result<()> {
-        let block_hash = block.header.hash;
-        let block_height = block.header.height;
-        
         // Check if block already exists
         {
             let dag = self.dag.read();
@@ -873,6 +2405,13 @@ impl ConsensusEngine {
             }
         }
         
+        // The DAG is in-memory and pruned; the persisted applied-blocks set catches
+        // blocks seen before a restart or prune (e.g. re-sent by a peer)
+        if self.state.is_block_applied(&block_hash)? {
+            tracing::debug!("Block {} already applied to state, skipping", crate::types::hash_to_hex(&block_hash));
+            return Ok(());
+        }
+        
         // Reject blocks at or below finalized height (no revert of finalized blocks)
         let last_finalized = self.get_last_finalized_height();
         if block_height <= last_finalized {
@@ -885,8 +2424,12 @@ impl ConsensusEngine {
             ));
         }
         
-        // Optional strict validation, controlled via config
-        if self.config.consensus.strict_block_validation {
+        self.validate_block_timestamp(block)?;
+        
+        // Strict validation, enabled via config or network-wide by the strict_blocks feature
+        if self.config.consensus.strict_block_validation
+            || self.state.features().is_active(Feature::StrictBlocks, block_height)
+        {
             let current_height = self.state.current_height();
             
             // Reject blocks that are too far in the future
@@ -908,7 +2451,7 @@ impl ConsensusEngine {
                 let parent_known_in_dag = dag.vertices.contains_key(&block.header.parent_hash);
                 drop(dag);
                 
-                let parent_known_in_state = self.state.get_block(&block.header.parent_hash).is_some();
+                let parent_known_in_state = self.state.has_block(&block.header.parent_hash);
                 
                 if !parent_known_in_dag && !parent_known_in_state {
                     return Err(crate::error::HazeError::InvalidBlock(
@@ -925,15 +2468,67 @@ impl ConsensusEngine {
         // Validate DAG references exist
         self.validate_dag_references(block)?;
         
+        // A validator must not propose two different blocks at one height
+        if let Some(evidence) = self.equivocations.observe(block.header.validator, block_height, block_hash) {
+            tracing::warn!(
+                "Equivocation by validator {} at height {}: {} conflicts with {}",
+                crate::types::hash_to_hex(&evidence.validator),
+                evidence.height,
+                crate::types::hash_to_hex(&evidence.second),
+                crate::types::hash_to_hex(&evidence.first)
+            );
+            return Err(crate::error::HazeError::InvalidBlock(
+                format!(
+                    "Validator {} already proposed block {} at height {}",
+                    crate::types::hash_to_hex(&evidence.validator),
+                    crate::types::hash_to_hex(&evidence.first),
+                    block_height
+                )
+            ));
+        }
+        
+        // The header commits to the state its parent left: compare with ours before applying
+        if !self.state.is_block_applied(&block_hash)? {
+            self.check_state_root(block);
+        }
+
+        // Apply to state; the block joins the DAG only once applied, so a block that failed
+        // can be sent again (e.g. after its failing transaction was fixed)
+        match self.state.apply_block(block) {
+            Ok(()) => {
+                tracing::info!("Block applied to state: height={}, hash={}", 
+                    block_height, crate::types::hash_to_hex(&block_hash));
+                self.remove_hashes_from_pool(block.transactions.iter().map(Transaction::hash));
+                self.evict_expired_transactions();
+            }
+            Err(e) => {
+                tracing::error!("Failed to apply block to state: {}", e);
+                // A rejected block is no proposal: the validator's next block at this height
+                // is not equivocation
+                self.equivocations.forget(block.header.validator, block_height, block_hash);
+                // The failing transaction was dead-lettered; the others stay pending for the
+                // next block
+                self.remove_hashes_from_pool(
+                    block.transactions.iter()
+                        .map(Transaction::hash)
+                        .filter(|hash| self.state.get_dead_letter(hash).is_some_and(|entry| entry.block_hash == block_hash))
+                );
+                return Err(e);
+            }
+        }
+        
         // Add to DAG
         {
             let mut dag = self.dag.write();
+            if dag.vertices.contains_key(&block_hash) {
+                return Ok(());
+            }
             let vertex = DagVertex {
                 block: block.clone(),
                 references: block.dag_references.clone(),
                 wave: block.header.wave_number,
                 timestamp: block.header.timestamp,
-                processed: false,
+                processed: true,
             };
             dag.vertices.insert(block_hash, vertex);
             dag.edges.insert(block_hash, block.dag_references.clone());
@@ -955,43 +2550,20 @@ impl ConsensusEngine {
                 number: wave_num,
                 blocks: HashSet::new(),
                 finalized: false,
-                created_at: Utc::now().timestamp(),
+                created_at_ms: Utc::now().timestamp_millis(),
+                finalized_at_ms: None,
+                certificate: None,
             });
             wave.blocks.insert(block_hash);
         }
 
-        // Apply to state
-        // Note: In multi-node setup, we need to ensure state consistency
-        match self.state.apply_block(block) {
-            Ok(()) => {
-                tracing::info!("Block applied to state: height={}, hash={}", 
-                    block_height, crate::types::hash_to_hex(&block_hash));
-            }
-            Err(e) => {
-                tracing::error!("Failed to apply block to state: {}", e);
-                return Err(e);
-            }
-        }
-        
-        // Mark as processed
-        {
-            let mut dag = self.dag.write();
-            if let Some(vertex) = dag.vertices.get_mut(&block_hash) {
-                vertex.processed = true;
-            }
-        }
-        
-        // Verify state consistency after applying block
-        let state_root = self.state.compute_state_root();
-        if state_root != block.header.state_root {
-            tracing::warn!("State root mismatch after applying block: expected {}, got {}", 
-                crate::types::hash_to_hex(&block.header.state_root), crate::types::hash_to_hex(&state_root));
-            // For MVP, we'll log but continue - in production this should be an error
-        }
-
-        // Automatic wave finalization & checkpointing
+        // Automatic wave finalization & checkpointing; a block joining a wave that already
+        // finalized is final with it
         let wave_num = block.header.wave_number;
-        if self.check_wave_finalization(wave_num)? {
+        let joined_finalized = self.waves.read().get(&wave_num).is_some_and(|wave| wave.finalized);
+        if joined_finalized {
+            self.announce_wave_assets(wave_num, &HashSet::from([block_hash]));
+        } else if self.check_wave_finalization(wave_num)? {
             self.finalize_wave(wave_num)?;
         }
 
@@ -1014,37 +2586,84 @@ impl ConsensusEngine {
         Ok(())
     }
 
-    /// Check wave finalization (Golden Wave)
+    /// Record a divergence incident if `block` (at the next height) commits to a state root
+    /// other than the local state's. The block is still applied; its wave is not finalized.
+    fn check_state_root(&self, block: &Block) {
+        // Blocks out of sequence are rejected when applied; there is no parent state to compare
+        if block.header.height != self.state.current_height() + 1 {
+            return;
+        }
+        let local = self.state.compute_state_root();
+        if local == block.header.state_root {
+            return;
+        }
+        tracing::error!(
+            "State divergence at block {} ({}) from validator {}: header state root {}, local state root {}; wave {} will not finalize",
+            block.header.height,
+            crate::types::hash_to_hex(&block.header.hash),
+            crate::types::hash_to_hex(&block.header.validator),
+            crate::types::hash_to_hex(&block.header.state_root),
+            crate::types::hash_to_hex(&local),
+            block.header.wave_number
+        );
+        self.divergences.record(DivergenceIncident {
+            height: block.header.height,
+            block_hash: block.header.hash,
+            wave: block.header.wave_number,
+            validator: block.header.validator,
+            expected: block.header.state_root,
+            local,
+            detected_at: Utc::now().timestamp(),
+        });
+    }
+
+    /// Check wave finalization (Golden Wave); waves holding a divergent block never finalize
     pub fn check_wave_finalization(&self, wave_num: u64) -> Result<bool> {
+        if self.divergences.is_halted(wave_num) {
+            return Ok(false);
+        }
         let waves = self.waves.read();
         if let Some(wave) = waves.get(&wave_num) {
             if wave.finalized {
                 return Ok(true);
             }
             
-            let now = Utc::now().timestamp();
-            let elapsed = (now - wave.created_at) * 1000; // Convert to ms
+            let elapsed = Utc::now().timestamp_millis() - wave.created_at_ms;
+            // A drifting clock must not finalize early: wait out the drift too
+            let threshold = self.config.consensus.golden_wave_threshold + self.peer_clock.drift_allowance_ms();
             
             // Check if wave has enough blocks and time has passed
             let min_blocks = 2; // Minimum blocks for finalization
             if wave.blocks.len() >= min_blocks && 
-               elapsed >= self.config.consensus.golden_wave_threshold as i64 {
+               elapsed >= threshold as i64 {
                 return Ok(true);
             }
         }
         Ok(false)
     }
     
-    /// Finalize wave (mark as finalized)
+    /// Finalize wave (mark as finalized); refused while the wave holds a divergent block
     pub fn finalize_wave(&self, wave_num: u64) -> Result<()> {
+        if self.divergences.is_halted(wave_num) {
+            return Err(crate::error::HazeError::Consensus(format!(
+                "Wave {} holds a block with a diverging state root and cannot be finalized",
+                wave_num
+            )));
+        }
         let mut waves = self.waves.write();
+        let mut proposers = HashSet::new();
+        // Latest block timestamp of the wave: the clock jailing runs on, the same on every node
+        let mut wave_timestamp = 0;
+        let blocks;
         if let Some(wave) = waves.get_mut(&wave_num) {
             if wave.finalized {
                 // Already finalized, nothing to do
                 return Ok(());
             }
             wave.finalized = true;
+            wave.finalized_at_ms = Some(Utc::now().timestamp_millis());
             tracing::info!("Wave {} finalized with {} blocks", wave_num, wave.blocks.len());
+            blocks = wave.blocks.clone();
 
             // Compute checkpoint height: max block height in this wave
             let dag = self.dag.read();
@@ -1055,9 +2674,18 @@ impl ConsensusEngine {
                     if h > max_height {
                         max_height = h;
                     }
+                    wave_timestamp = wave_timestamp.max(vertex.block.header.timestamp);
+                    proposers.insert(vertex.block.header.validator);
                 }
             }
             drop(dag);
+            let mut certificate_proposers: Vec<Address> = proposers.iter().copied().collect();
+            certificate_proposers.sort();
+            wave.certificate = Some(FinalityCertificate {
+                checkpoint_height: max_height,
+                committee_id: *self.current_committee_id.read(),
+                proposers: certificate_proposers,
+            });
 
             // Update checkpoint fields
             {
@@ -1066,6 +2694,7 @@ impl ConsensusEngine {
                 if wave_num > *last_wave || max_height > *last_height {
                     *last_wave = wave_num;
                     *last_height = max_height;
+                    self.equivocations.prune(max_height);
                     tracing::info!(
                         "Checkpoint updated: last_finalized_wave={}, last_finalized_height={}",
                         *last_wave,
@@ -1073,9 +2702,157 @@ impl ConsensusEngine {
                     );
                 }
             }
+        } else {
+            return Ok(());
+        }
+        drop(waves);
+        self.announce_wave_assets(wave_num, &blocks);
+        self.prune_waves();
+
+        // Wave boundary: account for committee members that did not propose
+        self.update_missed_slots(&proposers, wave_timestamp)?;
+        Ok(())
+    }
+
+    /// Move waves more than `consensus.wave_retention` waves behind the last finalized wave,
+    /// finalized or not, out of the live wave map into the summary archive (never on nodes
+    /// keeping full history)
+    fn prune_waves(&self) -> usize {
+        if self.config.subsystems().1.full_history {
+            return 0;
+        }
+        let Some(cutoff) = self.get_last_finalized_wave().checked_sub(self.config.consensus.wave_retention) else {
+            return 0;
+        };
+        let mut waves = self.waves.write();
+        let stale: Vec<u64> = waves.keys().copied().filter(|number| *number < cutoff).collect();
+        if stale.is_empty() {
+            return 0;
+        }
+        let mut archive = self.wave_archive.write();
+        for number in &stale {
+            if let Some(wave) = waves.remove(number) {
+                archive.insert(*number, wave.summary());
+            }
+        }
+        drop(waves);
+        while archive.len() > self.config.consensus.wave_history_limit {
+            archive.pop_first();
+        }
+        drop(archive);
+        tracing::debug!("Pruned {} stale waves (before wave {})", stale.len(), cutoff);
+        stale.len()
+    }
+
+    /// Send `AssetFinalized` for every asset the blocks of the finalized wave `wave` changed,
+    /// in block order
+    fn announce_wave_assets(&self, wave: u64, blocks: &HashSet<Hash>) {
+        let mut heights: Vec<u64> = blocks
+            .iter()
+            .filter_map(|hash| self.state.get_block_header(hash))
+            .map(|header| header.height)
+            .collect();
+        heights.sort_unstable();
+        for height in heights {
+            let mut announced = HashSet::new();
+            for execution in self.state.get_block_executions(height) {
+                for asset_id in execution.events.iter().flat_map(WsEvent::changed_assets) {
+                    if !announced.insert(asset_id) {
+                        continue;
+                    }
+                    self.state.broadcast_event(WsEvent::AssetFinalized { asset_id, height, wave });
+                }
+            }
+        }
+    }
+
+    /// Waves with `from <= wave <= to`, archived and live, oldest first (at most `limit`)
+    pub fn wave_history(&self, from: u64, to: u64, limit: usize) -> Vec<WaveSummary> {
+        if from > to {
+            return Vec::new();
+        }
+        let mut summaries: Vec<WaveSummary> = self.wave_archive
+            .read()
+            .range(from..=to)
+            .map(|(_, summary)| summary.clone())
+            .collect();
+        summaries.extend(
+            self.waves
+                .read()
+                .values()
+                .filter(|wave| wave.number >= from && wave.number <= to)
+                .map(Wave::summary),
+        );
+        summaries.sort_by_key(|summary| summary.wave);
+        summaries.truncate(limit);
+        summaries
+    }
+
+    /// Number of waves held in the live wave map
+    pub fn live_wave_count(&self) -> usize {
+        self.waves.read().len()
+    }
+
+    /// Members and weights of the current committee
+    pub fn current_committee(&self) -> Option<CommitteeSummary> {
+        let current_id = *self.current_committee_id.read();
+        self.committees.read().get(&current_id).map(|committee| CommitteeSummary {
+            id: committee.id,
+            members: committee.validators.iter()
+                .map(|validator| (*validator, committee.weights.get(validator).copied().unwrap_or(0)))
+                .collect(),
+            expires_at: committee.expires_at,
+        })
+    }
+
+    /// Update missed-slot counters for the current committee after a wave and
+    /// jail members that reached `consensus.max_missed_slots` consecutive misses,
+    /// until `consensus.jail_duration_secs` after `wave_timestamp` (the wave's latest block).
+    /// If anyone was jailed the committee is re-formed without them.
+    fn update_missed_slots(&self, proposers: &HashSet<Address>, wave_timestamp: i64) -> Result<()> {
+        let members = {
+            let current_id = *self.current_committee_id.read();
+            match self.committees.read().get(&current_id) {
+                Some(committee) => committee.validators.clone(),
+                None => return Ok(()),
+            }
+        };
+
+        let tokenomics = self.state.tokenomics();
+        let jail_until = wave_timestamp + self.config.consensus.jail_duration_secs as i64;
+        let mut jailed_any = false;
+        for member in members {
+            if proposers.contains(&member) {
+                self.missed_slots.remove(&member);
+                continue;
+            }
+            let missed = {
+                let mut missed = self.missed_slots.entry(member).or_insert(0);
+                *missed += 1;
+                *missed
+            };
+            if missed >= self.config.consensus.max_missed_slots && !tokenomics.is_jailed(&member) {
+                tokenomics.jail(&member, jail_until)?;
+                self.missed_slots.remove(&member);
+                jailed_any = true;
+                tracing::warn!(
+                    "Validator {} jailed after missing {} consecutive slots",
+                    crate::types::address_to_hex(&member),
+                    missed
+                );
+            }
+        }
+
+        if jailed_any {
+            self.initialize_committee()?;
         }
         Ok(())
     }
+
+    /// Consecutive missed proposal slots for a validator
+    pub fn get_missed_slots(&self, validator: &Address) -> u64 {
+        self.missed_slots.get(validator).map(|m| *m).unwrap_or(0)
+    }
     
     /// Get all ancestors of a block (transitive closure of references)
     pub fn get_ancestors(&self, block_hash: &Hash) -> HashSet<Hash> {
@@ -1214,6 +2991,30 @@ impl ConsensusEngine {
         Ok(removed)
     }
     
+    /// DAG vertices with `from_wave <= wave <= to_wave`, ordered by wave, height and hash
+    pub fn dag_vertices(&self, from_wave: u64, to_wave: u64) -> Vec<DagVertexInfo> {
+        let dag = self.dag.read();
+        let waves = self.waves.read();
+        let finalized_height = self.get_last_finalized_height();
+        let mut vertices: Vec<DagVertexInfo> = dag
+            .vertices
+            .iter()
+            .filter(|(_, vertex)| vertex.wave >= from_wave && vertex.wave <= to_wave)
+            .map(|(hash, vertex)| DagVertexInfo {
+                hash: *hash,
+                height: vertex.block.header.height,
+                wave: vertex.wave,
+                validator: vertex.block.header.validator,
+                timestamp: vertex.timestamp,
+                finalized: waves.get(&vertex.wave).is_some_and(|wave| wave.finalized)
+                    || vertex.block.header.height <= finalized_height,
+                references: vertex.references.clone(),
+            })
+            .collect();
+        vertices.sort_by_key(|vertex| (vertex.wave, vertex.height, vertex.hash));
+        vertices
+    }
+
     /// Check DAG consistency
     pub fn check_dag_consistency(&self) -> Result<()> {
         let dag = self.dag.read();
@@ -1253,15 +3054,30 @@ impl Clone for ConsensusEngine {
     fn clone(&self) -> Self {
         Self {
             config: self.config.clone(),
+            network_id: self.network_id,
             state: self.state.clone(),
             dag: self.dag.clone(),
             committees: self.committees.clone(),
             current_committee_id: self.current_committee_id.clone(),
             waves: self.waves.clone(),
+            wave_archive: self.wave_archive.clone(),
             current_wave: self.current_wave.clone(),
             tx_pool: self.tx_pool.clone(),
+            tx_added: self.tx_added.clone(),
             last_finalized_wave: self.last_finalized_wave.clone(),
             last_finalized_height: self.last_finalized_height.clone(),
+            missed_slots: self.missed_slots.clone(),
+            peer_clock: self.peer_clock.clone(),
+            peer_heights: self.peer_heights.clone(),
+            peer_versions: self.peer_versions.clone(),
+            peer_health: self.peer_health.clone(),
+            byzantine: self.byzantine.clone(),
+            equivocations: self.equivocations.clone(),
+            divergences: self.divergences.clone(),
+            pool_fees: self.pool_fees.clone(),
+            reserved: self.reserved.clone(),
+            pending_nonces: self.pending_nonces.clone(),
+            control: self.control.clone(),
         }
     }
 }
@@ -1279,6 +3095,43 @@ mod tests {
         config
     }
 
+    #[test]
+    fn test_read_replica_rejects_transactions_and_reports_lag() {
+        let mut config = create_test_config("read_replica");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        config.replica.enabled = true;
+        config.replica.max_lag_blocks = 2;
+        let state = std::sync::Arc::new(crate::state::StateManager::new(&config).unwrap());
+        let consensus = ConsensusEngine::new(config, state.clone()).unwrap();
+
+        let tx = Transaction::Transfer {
+            from: [1u8; 32],
+            to: [2u8; 32],
+            amount: 1,
+            fee: 1,
+            nonce: 0,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        assert!(consensus.add_transaction(tx).is_err());
+        assert_eq!(consensus.tx_pool_size(), 0);
+
+        // Unknown primary height counts as lagging
+        let status = consensus.replica_status();
+        assert_eq!((status.primary_height, status.lag_blocks, status.lagging), (None, None, true));
+
+        let height = state.current_height();
+        consensus.record_peer_height("primary".to_string(), height + 2);
+        consensus.record_peer_height("primary".to_string(), height + 1);
+        let status = consensus.replica_status();
+        assert_eq!((status.primary_height, status.lag_blocks, status.lagging), (Some(height + 2), Some(2), false));
+        consensus.record_peer_height("other".to_string(), height + 5);
+        assert!(consensus.replica_status().lagging);
+        consensus.remove_peer_height("other");
+        assert_eq!(consensus.replica_status().lag_blocks, Some(2));
+    }
+
     #[test]
     fn test_add_transaction_duplicate() {
         let config = create_test_config("duplicate");
@@ -1296,7 +3149,7 @@ mod tests {
         
         // Create transaction with nonce 0 for new account
         let tx_data = {
-            let mut data = Vec::new();
+            let mut data = crate::types::network_id(0, &[]).to_vec();
             data.extend_from_slice(b"Transfer");
             data.extend_from_slice(&from);
             data.extend_from_slice(&to);
@@ -1344,7 +3197,7 @@ mod tests {
         
         // Create transaction data for signing with nonce 0 for new account
         let tx_data = {
-            let mut data = Vec::new();
+            let mut data = crate::types::network_id(0, &[]).to_vec();
             data.extend_from_slice(b"Transfer");
             data.extend_from_slice(&from);
             data.extend_from_slice(&to);
@@ -1374,6 +3227,130 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_create_airdrop_validation() {
+        let config = create_test_config("airdrop_validation");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        let state = crate::state::StateManager::new(&config).unwrap();
+        let keypair = KeyPair::generate();
+        let from = keypair.address();
+        state.create_test_account(from, 10_000, 0);
+        let consensus = ConsensusEngine::new(config, std::sync::Arc::new(state)).unwrap();
+
+        let airdrop = |claims: Vec<crate::types::AirdropClaim>, claims_root: Hash| {
+            let mut tx = Transaction::CreateAirdrop {
+                from,
+                claims,
+                claims_root,
+                fee: 10,
+                nonce: 0,
+                chain_id: None,
+                valid_until_height: None,
+                signature: vec![],
+            };
+            let payload = consensus.get_transaction_data_for_signing(&tx);
+            if let Transaction::CreateAirdrop { signature, .. } = &mut tx {
+                *signature = keypair.sign(&payload);
+            }
+            tx
+        };
+        let claim = |seed: u8, amount: u64| crate::types::AirdropClaim { address: [seed; 32], amount };
+
+        let claims = vec![claim(1, 100), claim(2, 200), claim(3, 300)];
+        let root = crate::types::airdrop_claims_root(&claims);
+        assert!(consensus.validate_transaction(&airdrop(claims.clone(), root)).is_ok());
+
+        // Claim list must match the committed root
+        let mut tampered = claims.clone();
+        tampered[1].amount = 2_000;
+        assert!(consensus.validate_transaction(&airdrop(tampered, root)).is_err());
+
+        let duplicate = vec![claim(1, 100), claim(1, 100)];
+        let duplicate_root = crate::types::airdrop_claims_root(&duplicate);
+        assert!(consensus.validate_transaction(&airdrop(duplicate, duplicate_root)).is_err());
+        assert!(consensus.validate_transaction(&airdrop(vec![], [0; 32])).is_err());
+    }
+
+    #[test]
+    fn test_payment_request_validation() {
+        let config = create_test_config("payment_request_validation");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        let state = std::sync::Arc::new(crate::state::StateManager::new(&config).unwrap());
+        let studio = KeyPair::generate();
+        let player = KeyPair::generate();
+        state.create_test_account(studio.address(), 1_000, 0);
+        state.create_test_account(player.address(), 30, 0);
+        let consensus = ConsensusEngine::new(config, state.clone()).unwrap();
+
+        let sign = |keypair: &KeyPair, mut tx: Transaction| {
+            let payload = consensus.get_transaction_data_for_signing(&tx);
+            match &mut tx {
+                Transaction::CreatePaymentRequest { signature, .. }
+                | Transaction::PayPaymentRequest { signature, .. } => *signature = keypair.sign(&payload),
+                _ => unreachable!(),
+            }
+            tx
+        };
+        let request = |amount: u64, memo: &str, expires_at_height: u64| Transaction::CreatePaymentRequest {
+            from: studio.address(),
+            payer: Some(player.address()),
+            amount,
+            memo: memo.to_string(),
+            expires_at_height,
+            fee: 1,
+            nonce: 0,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![],
+        };
+
+        let create = sign(&studio, request(50, "Sword of Fog", 100));
+        assert!(consensus.validate_transaction(&create).is_ok());
+        // Signed by someone other than the requester
+        assert!(consensus.validate_transaction(&sign(&player, request(50, "Sword of Fog", 100))).is_err());
+        assert!(consensus.validate_transaction(&sign(&studio, request(0, "", 100))).is_err());
+        assert!(consensus.validate_transaction(&sign(&studio, request(50, &"x".repeat(300), 100))).is_err());
+        assert!(consensus.validate_transaction(&sign(&studio, request(50, "", 0))).is_err());
+
+        let request_id = crate::types::payment_request_id(&studio.address(), 0);
+        let pay = Transaction::PayPaymentRequest {
+            from: player.address(),
+            request_id,
+            amount: 50,
+            fee: 1,
+            nonce: 0,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![],
+        };
+        // Unknown request
+        assert!(consensus.validate_transaction(&sign(&player, pay.clone())).is_err());
+
+        state.apply_block(&Block {
+            header: BlockHeader {
+                hash: [1; 32],
+                parent_hash: [0; 32],
+                height: 1,
+                timestamp: 0,
+                validator: studio.address(),
+                merkle_root: [0; 32],
+                state_root: [0; 32],
+                wave_number: 0,
+                committee_id: 0,
+                protocol_version: PROTOCOL_VERSION,
+                state_version: STATE_VERSION,
+                signals: 0,
+            },
+            transactions: vec![create],
+            dag_references: vec![],
+        }).unwrap();
+        // Player holds 30 but owes 51
+        let err = consensus.validate_transaction(&sign(&player, pay.clone())).unwrap_err();
+        assert_eq!(err.code(), 2002);
+        state.create_test_account(player.address(), 100, 0);
+        assert!(consensus.validate_transaction(&sign(&player, pay)).is_ok());
+    }
+
     #[test]
     fn test_add_transaction_empty_signature() {
         let config = create_test_config("empty_sig");
@@ -1390,38 +3367,406 @@ mod tests {
             valid_until_height: None,
             signature: vec![], // Empty signature
         };
-        
-        let result = consensus.add_transaction(tx);
-        assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains("empty"));
+        
+        let result = consensus.add_transaction(tx);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("empty"));
+    }
+
+    #[test]
+    fn test_validate_set_asset_permissions_empty_signature() {
+        let config = create_test_config("set_perms_empty_sig");
+        let state = crate::state::StateManager::new(&config).unwrap();
+        let consensus = ConsensusEngine::new(config, std::sync::Arc::new(state)).unwrap();
+
+        let owner = create_test_address_for_asset(1);
+        let asset_id = crate::types::sha256(b"perm_asset");
+
+        let tx = Transaction::SetAssetPermissions {
+            from: owner,
+            asset_id,
+            permissions: vec![],
+            public_read: false,
+            owner,
+            fee: 0,
+            nonce: 0,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![], // Empty signature
+        };
+
+        let result = consensus.add_transaction(tx);
+        assert!(result.is_err());
+        let err = result.unwrap_err().to_string();
+        assert!(err.contains("empty") || err.contains("signature"));
+    }
+
+    #[test]
+    fn test_validate_set_asset_permissions_signer_and_nonce() {
+        let config = create_test_config("set_perms_signer_nonce");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        let state = crate::state::StateManager::new(&config).unwrap();
+        let keypair = KeyPair::generate();
+        let owner = keypair.address();
+        state.create_test_account(owner, 1_000, 0);
+        let consensus = ConsensusEngine::new(config, std::sync::Arc::new(state)).unwrap();
+
+        let set_permissions = |from: Address, nonce: u64, signer: &KeyPair| {
+            let mut tx = Transaction::SetAssetPermissions {
+                from,
+                asset_id: crate::types::sha256(b"perm_asset"),
+                permissions: vec![],
+                public_read: true,
+                owner,
+                fee: 5,
+                nonce,
+                chain_id: None,
+                valid_until_height: None,
+                signature: vec![],
+            };
+            let payload = consensus.get_transaction_data_for_signing(&tx);
+            tx.set_signature(signer.sign(&payload));
+            tx
+        };
+
+        // Signed by someone other than the owner
+        let other = KeyPair::generate();
+        assert!(consensus.validate_transaction(&set_permissions(owner, 0, &other)).is_err());
+        // Sent from another account on the owner's behalf
+        assert!(consensus.validate_transaction(&set_permissions(other.address(), 0, &other)).is_err());
+
+        // Nonces are sequenced with the owner's pending transactions
+        assert!(matches!(
+            consensus.validate_transaction(&set_permissions(owner, 1, &keypair)),
+            Err(crate::error::HazeError::InvalidNonce { expected: 0, actual: 1 })
+        ));
+        consensus.add_transaction(set_permissions(owner, 0, &keypair)).unwrap();
+        assert!(consensus.validate_transaction(&set_permissions(owner, 0, &keypair)).is_err());
+        assert!(consensus.validate_transaction(&set_permissions(owner, 1, &keypair)).is_ok());
+    }
+
+    #[test]
+    fn test_pool_sequences_nonces_of_every_transaction_type() {
+        let config = create_test_config("pool_nonces_all_types");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        let state = std::sync::Arc::new(crate::state::StateManager::new(&config).unwrap());
+        let keypair = KeyPair::generate();
+        let owner = keypair.address();
+        state.create_test_account(owner, 100_000, 0);
+        let consensus = ConsensusEngine::new(config, state.clone()).unwrap();
+
+        let sign = |mut tx: Transaction| {
+            let payload = consensus.get_transaction_data_for_signing(&tx);
+            tx.set_signature(keypair.sign(&payload));
+            tx
+        };
+        let transfer = |nonce: u64| sign(Transaction::Transfer {
+            from: owner,
+            to: [9; 32],
+            amount: 10,
+            fee: 1,
+            nonce,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![],
+        });
+        let create = |from: Address, seed: u8, nonce: u64| sign(Transaction::MistbornAsset {
+            from,
+            action: crate::types::AssetAction::Create,
+            asset_id: [seed; 32],
+            data: crate::types::AssetData {
+                density: crate::types::DensityLevel::Ethereal,
+                metadata: std::collections::HashMap::new(),
+                attributes: vec![],
+                game_id: None,
+                owner,
+            },
+            fee: 0,
+            nonce,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![],
+        });
+        let stake = |nonce: u64| sign(Transaction::Stake {
+            from: owner,
+            validator: owner,
+            amount: 1_000,
+            fee: 0,
+            nonce,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![],
+        });
+
+        // Asset and stake transactions take the next nonce after the pending transfer
+        consensus.add_transaction(transfer(0)).unwrap();
+        assert!(matches!(
+            consensus.add_transaction(create(owner, 1, 0)),
+            Err(crate::error::HazeError::InvalidNonce { expected: 1, actual: 0 })
+        ));
+        consensus.add_transaction(create(owner, 1, 1)).unwrap();
+        consensus.add_transaction(stake(2)).unwrap();
+        assert_eq!(consensus.next_nonce(&owner), 3);
+        // Asset transactions are sent by their owner
+        assert!(consensus.add_transaction(create([7; 32], 2, 0)).is_err());
+
+        // The block applies them in nonce order and the pool forgets them
+        let block = consensus.create_block(owner).unwrap();
+        consensus.process_block(&block).unwrap();
+        assert_eq!(consensus.tx_pool_size(), 0);
+        assert_eq!(state.get_account(&owner).unwrap().nonce, 3);
+        assert_eq!(consensus.next_nonce(&owner), 3);
+        assert!(state.get_asset(&[1; 32]).is_some());
+
+        // A sealed asset transaction cannot be replayed
+        assert!(matches!(
+            consensus.add_transaction(create(owner, 1, 1)),
+            Err(crate::error::HazeError::InvalidNonce { expected: 3, actual: 1 })
+        ));
+    }
+
+    #[test]
+    fn test_chain_id_and_expiry_enforced_in_pool_and_blocks() {
+        use crate::state::tests::test_block;
+        let mut config = create_test_config("chain_id_and_expiry");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        config.chain_id = 7;
+        config.features.activation_heights.insert("transaction_bounds".to_string(), 2);
+        let state = std::sync::Arc::new(crate::state::StateManager::new(&config).unwrap());
+        let (alice, bob, carol) = (KeyPair::generate(), KeyPair::generate(), KeyPair::generate());
+        for keypair in [&alice, &bob, &carol] {
+            state.create_test_account(keypair.address(), 1_000, 0);
+        }
+        let consensus = ConsensusEngine::new(config, state.clone()).unwrap();
+
+        let transfer = |keypair: &KeyPair, nonce: u64, chain_id: Option<u64>, valid_until_height: Option<u64>| {
+            let mut tx = Transaction::Transfer {
+                from: keypair.address(),
+                to: [9; 32],
+                amount: 10,
+                fee: 1,
+                nonce,
+                chain_id,
+                valid_until_height,
+                signature: vec![],
+            };
+            let payload = consensus.get_transaction_data_for_signing(&tx);
+            tx.set_signature(keypair.sign(&payload));
+            tx
+        };
+
+        // Bound to another chain, or too late for the next block (height 1)
+        assert!(consensus.add_transaction(transfer(&alice, 0, Some(8), None)).is_err());
+        assert!(consensus.add_transaction(transfer(&alice, 0, Some(7), Some(0))).is_err());
+        consensus.add_transaction(transfer(&alice, 0, Some(7), Some(1))).unwrap();
+        consensus.add_transaction(transfer(&alice, 1, None, None)).unwrap();
+        consensus.add_transaction(transfer(&bob, 0, None, None)).unwrap();
+
+        // Once height 1 is taken, alice's first transfer expires and her second waits on it.
+        // Blocks below the feature's activation height are not checked, so stored chains replay
+        state.apply_block(&test_block(1, vec![transfer(&carol, 0, Some(8), Some(0))])).unwrap();
+        assert_eq!(consensus.evict_expired_transactions(), 2);
+        assert_eq!(consensus.tx_pool_size(), 1);
+        assert_eq!(consensus.next_nonce(&alice.address()), 0);
+        let next = consensus.create_block(bob.address()).unwrap();
+        assert_eq!(next.transactions.len(), 1);
+        assert_eq!(next.transactions[0].sender(), &bob.address());
+
+        // From its activation, blocks carrying them are rejected too
+        assert!(state.apply_block(&test_block(2, vec![transfer(&alice, 0, Some(7), Some(1))])).is_err());
+        assert!(state.apply_block(&test_block(2, vec![transfer(&alice, 0, Some(8), None)])).is_err());
+        state.apply_block(&test_block(2, vec![transfer(&alice, 0, Some(7), Some(2))])).unwrap();
+    }
+
+    #[test]
+    fn test_failed_block_keeps_other_transactions_pending() {
+        let config = create_test_config("failed_block_pool");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        let state = std::sync::Arc::new(crate::state::StateManager::new(&config).unwrap());
+        let (alice, bob, carol) = (KeyPair::generate(), KeyPair::generate(), KeyPair::generate());
+        for keypair in [&alice, &bob, &carol] {
+            state.create_test_account(keypair.address(), 1_000, 0);
+        }
+        let consensus = ConsensusEngine::new(config, state.clone()).unwrap();
+        let transfer = |keypair: &KeyPair, amount: u64| {
+            let mut tx = Transaction::Transfer {
+                from: keypair.address(),
+                to: [9; 32],
+                amount,
+                fee: 1,
+                nonce: 0,
+                chain_id: None,
+                valid_until_height: None,
+                signature: vec![],
+            };
+            let payload = consensus.get_transaction_data_for_signing(&tx);
+            tx.set_signature(keypair.sign(&payload));
+            tx
+        };
+        let (good_a, good_b, bad) = (transfer(&alice, 10), transfer(&bob, 10), transfer(&carol, 500));
+        for tx in [&good_a, &good_b, &bad] {
+            consensus.add_transaction(tx.clone()).unwrap();
+        }
+        // Carol's funds go away after admission, so her transfer fails in the block
+        state.create_test_account(carol.address(), 0, 0);
+
+        let failed = consensus.create_block(alice.address()).unwrap();
+        assert_eq!(failed.transactions.len(), 3);
+        assert!(consensus.process_block(&failed).is_err());
+        assert_eq!(state.current_height(), 0);
+        assert!(state.get_dead_letter(&bad.hash()).is_some());
+        assert!(consensus.get_transaction(&bad.hash()).is_none());
+        assert!(consensus.get_transaction(&good_a.hash()).is_some());
+        assert!(consensus.get_transaction(&good_b.hash()).is_some());
+        // The failed block never joined the DAG, so sending it again applies (and fails) it again
+        assert!(consensus.process_block(&failed).is_err());
+        assert_eq!(state.current_height(), 0);
+
+        let next = consensus.create_block(bob.address()).unwrap();
+        assert_eq!(next.transactions.len(), 2);
+        consensus.process_block(&next).unwrap();
+        assert_eq!(state.current_height(), 1);
+        assert_eq!(consensus.tx_pool_size(), 0);
+        assert_eq!(state.get_account(&[9; 32]).unwrap().balance, 20);
     }
 
     #[test]
-    fn test_validate_set_asset_permissions_empty_signature() {
-        let config = create_test_config("set_perms_empty_sig");
-        let state = crate::state::StateManager::new(&config).unwrap();
-        let consensus = ConsensusEngine::new(config, std::sync::Arc::new(state)).unwrap();
+    fn test_dead_lettered_transaction_applies_after_retry() {
+        let config = create_test_config("dead_letter_retry");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        let state = std::sync::Arc::new(crate::state::StateManager::new(&config).unwrap());
+        let (alice, carol) = (KeyPair::generate(), KeyPair::generate());
+        state.create_test_account(alice.address(), 1_000, 0);
+        state.create_test_account(carol.address(), 1_000, 0);
+        let consensus = ConsensusEngine::new(config, state.clone()).unwrap();
+        let transfer = |keypair: &KeyPair, amount: u64| {
+            let mut tx = Transaction::Transfer {
+                from: keypair.address(),
+                to: [9; 32],
+                amount,
+                fee: 1,
+                nonce: 0,
+                chain_id: None,
+                valid_until_height: None,
+                signature: vec![],
+            };
+            let payload = consensus.get_transaction_data_for_signing(&tx);
+            tx.set_signature(keypair.sign(&payload));
+            tx
+        };
+        let (good, failing) = (transfer(&alice, 10), transfer(&carol, 500));
+        consensus.add_transaction(good.clone()).unwrap();
+        consensus.add_transaction(failing.clone()).unwrap();
+        state.create_test_account(carol.address(), 0, 0);
 
-        let owner = create_test_address_for_asset(1);
-        let asset_id = crate::types::sha256(b"perm_asset");
+        let block = consensus.create_block([4u8; 32]).unwrap();
+        assert!(consensus.process_block(&block).is_err());
+        assert_eq!(state.get_dead_letter(&failing.hash()).unwrap().attempts, 1);
 
-        let tx = Transaction::SetAssetPermissions {
-            from: owner,
-            asset_id,
-            permissions: vec![],
-            public_read: false,
-            owner,
-            fee: 0,
+        // Once carol is funded again, the retried transfer goes through with the one left pending
+        state.create_test_account(carol.address(), 1_000, 0);
+        consensus.retry_dead_letter(&failing.hash()).unwrap();
+        assert!(state.get_dead_letter(&failing.hash()).is_none());
+        let block = consensus.create_block([4u8; 32]).unwrap();
+        assert_eq!(block.transactions.len(), 2);
+        consensus.process_block(&block).unwrap();
+        assert_eq!(state.current_height(), 1);
+        assert_eq!(state.get_account(&carol.address()).unwrap().nonce, 1);
+        assert_eq!(state.get_account(&[9; 32]).unwrap().balance, 510);
+        assert_eq!(state.get_receipt(&failing.hash()).unwrap().status, crate::state::ReceiptStatus::Executed);
+    }
+
+    #[test]
+    fn test_rejected_block_is_not_equivocation() {
+        let config = create_test_config("rejected_block_equivocation");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        let state = std::sync::Arc::new(crate::state::StateManager::new(&config).unwrap());
+        let (alice, broke) = (KeyPair::generate(), KeyPair::generate());
+        state.create_test_account(alice.address(), 1_000, 0);
+        let consensus = ConsensusEngine::new(config, state.clone()).unwrap();
+        let transfer = |keypair: &KeyPair| {
+            let mut tx = Transaction::Transfer {
+                from: keypair.address(),
+                to: [9; 32],
+                amount: 10,
+                fee: 1,
+                nonce: 0,
+                chain_id: None,
+                valid_until_height: None,
+                signature: vec![],
+            };
+            let payload = consensus.get_transaction_data_for_signing(&tx);
+            tx.set_signature(keypair.sign(&payload));
+            tx
+        };
+        let validator = [4u8; 32];
+        consensus.add_transaction(transfer(&alice)).unwrap();
+
+        // A block carrying a transfer from an account without funds fails to apply
+        let mut rejected = consensus.create_block(validator).unwrap();
+        rejected.transactions.push(transfer(&broke));
+        rejected.header.merkle_root = crate::types::merkle_root(rejected.transactions.iter().map(Transaction::hash).collect());
+        rejected.header.hash = rejected.header.compute_hash(&consensus.network_id());
+        assert!(consensus.process_block(&rejected).is_err());
+
+        // The same validator's next block at that height is accepted
+        let block = consensus.create_block(validator).unwrap();
+        assert_eq!(block.header.height, rejected.header.height);
+        consensus.process_block(&block).unwrap();
+        assert_eq!(state.current_height(), 1);
+        assert!(consensus.get_equivocation_evidence().is_empty());
+    }
+
+    #[test]
+    fn test_block_transactions_are_verified() {
+        let config = create_test_config("block_tx_verification");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        let state = std::sync::Arc::new(crate::state::StateManager::new(&config).unwrap());
+        let alice = KeyPair::generate();
+        state.create_test_account(alice.address(), 1_000, 0);
+        let consensus = ConsensusEngine::new(config.clone(), state.clone()).unwrap();
+        let transfer = |chain_id: Option<u64>| Transaction::Transfer {
+            from: alice.address(),
+            to: [9; 32],
+            amount: 10,
+            fee: 1,
             nonce: 0,
-            chain_id: None,
+            chain_id,
             valid_until_height: None,
-            signature: vec![], // Empty signature
+            signature: vec![],
         };
+        let sign_for = |consensus: &ConsensusEngine, mut tx: Transaction| {
+            let payload = consensus.get_transaction_data_for_signing(&tx);
+            tx.set_signature(alice.sign(&payload));
+            tx
+        };
+        let mut other_config = config.clone();
+        other_config.chain_id += 1;
+        other_config.storage.db_path = config.storage.db_path.with_extension("other");
+        let _ = std::fs::remove_dir_all(&other_config.storage.db_path);
+        let other_state = std::sync::Arc::new(crate::state::StateManager::new(&other_config).unwrap());
+        let other = ConsensusEngine::new(other_config, other_state).unwrap();
 
-        let result = consensus.add_transaction(tx);
-        assert!(result.is_err());
-        let err = result.unwrap_err().to_string();
-        assert!(err.contains("empty") || err.contains("signature"));
+        let candidates = [
+            transfer(None),
+            sign_for(&other, transfer(None)),
+            sign_for(&consensus, transfer(Some(consensus.config.chain_id + 1))),
+        ];
+        for tx in candidates {
+            let mut block = consensus.create_block([4u8; 32]).unwrap();
+            block.transactions.push(tx);
+            block.header.merkle_root = crate::types::merkle_root(block.transactions.iter().map(Transaction::hash).collect());
+            block.header.hash = block.header.compute_hash(&consensus.network_id());
+            assert!(matches!(consensus.process_block(&block), Err(crate::error::HazeError::InvalidBlock(_))));
+            assert_eq!(state.current_height(), 0);
+        }
+
+        let mut block = consensus.create_block([4u8; 32]).unwrap();
+        block.transactions.push(sign_for(&consensus, transfer(None)));
+        block.header.merkle_root = crate::types::merkle_root(block.transactions.iter().map(Transaction::hash).collect());
+        block.header.hash = block.header.compute_hash(&consensus.network_id());
+        consensus.process_block(&block).unwrap();
+        assert_eq!(state.current_height(), 1);
     }
 
     #[test]
@@ -1436,7 +3781,7 @@ mod tests {
         
         // Create transaction with nonce 0 for new account
         let tx_data = {
-            let mut data = Vec::new();
+            let mut data = crate::types::network_id(0, &[]).to_vec();
             data.extend_from_slice(b"Transfer");
             data.extend_from_slice(&from);
             data.extend_from_slice(&to);
@@ -1481,7 +3826,7 @@ mod tests {
         
         // Create first transaction with nonce 0
         let tx_data_1 = {
-            let mut data = Vec::new();
+            let mut data = crate::types::network_id(0, &[]).to_vec();
             data.extend_from_slice(b"Transfer");
             data.extend_from_slice(&from);
             data.extend_from_slice(&to);
@@ -1508,7 +3853,7 @@ mod tests {
         
         // Second transaction with same nonce should fail
         let tx_data_2 = {
-            let mut data = Vec::new();
+            let mut data = crate::types::network_id(0, &[]).to_vec();
             data.extend_from_slice(b"Transfer");
             data.extend_from_slice(&from);
             data.extend_from_slice(&to);
@@ -1553,7 +3898,7 @@ mod tests {
         // Create transaction with nonce 1 (matches current account nonce)
         // Expected nonce = current_nonce (1) + pending_count (0) = 1
         let tx_data = {
-            let mut data = Vec::new();
+            let mut data = crate::types::network_id(0, &[]).to_vec();
             data.extend_from_slice(b"Transfer");
             data.extend_from_slice(&from);
             data.extend_from_slice(&to);
@@ -1596,7 +3941,7 @@ mod tests {
         
         // Transaction with nonce 5 when account has nonce 0 (should be 0)
         let tx_data = {
-            let mut data = Vec::new();
+            let mut data = crate::types::network_id(0, &[]).to_vec();
             data.extend_from_slice(b"Transfer");
             data.extend_from_slice(&from);
             data.extend_from_slice(&to);
@@ -1617,12 +3962,157 @@ mod tests {
             valid_until_height: None,
             signature,
         };
-        
+
         let result = consensus.add_transaction(tx);
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("nonce"));
     }
 
+    #[test]
+    fn test_nonce_lanes_validated_per_lane() {
+        use crate::types::lane_nonce;
+        let mut config = create_test_config("nonce_lanes");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        let server = KeyPair::generate();
+        let player = KeyPair::generate();
+        config.consensus.nonce_lane_accounts = vec![crate::types::address_to_hex(&server.address())];
+        config.consensus.max_nonce_lanes = 4;
+        let state = std::sync::Arc::new(crate::state::StateManager::new(&config).unwrap());
+        state.create_test_account(server.address(), 10_000, 0);
+        state.create_test_account(player.address(), 10_000, 0);
+        let consensus = ConsensusEngine::new(config, state.clone()).unwrap();
+
+        let transfer = |keypair: &KeyPair, nonce: u64| {
+            let mut tx = Transaction::Transfer {
+                from: keypair.address(),
+                to: [2u8; 32],
+                amount: 10,
+                fee: 1,
+                nonce,
+                chain_id: None,
+                valid_until_height: None,
+                signature: vec![],
+            };
+            let payload = consensus.get_transaction_data_for_signing(&tx);
+            if let Transaction::Transfer { signature, .. } = &mut tx {
+                *signature = keypair.sign(&payload);
+            }
+            tx
+        };
+
+        // Lanes progress independently, each counting its own pending transactions
+        let accepted = vec![
+            transfer(&server, 0),
+            transfer(&server, lane_nonce(1, 0)),
+            transfer(&server, lane_nonce(1, 1)),
+            transfer(&server, lane_nonce(3, 0)),
+        ];
+        for tx in &accepted {
+            consensus.add_transaction(tx.clone()).unwrap();
+        }
+        assert_eq!(consensus.next_nonce(&server.address()), 1);
+        assert_eq!(consensus.next_lane_nonce(&server.address(), 1), lane_nonce(1, 2));
+        assert!(consensus.add_transaction(transfer(&server, lane_nonce(2, 1))).is_err());
+        assert!(consensus.add_transaction(transfer(&server, lane_nonce(4, 0))).is_err());
+        // Accounts not designated keep a single sequence
+        assert!(consensus.add_transaction(transfer(&player, lane_nonce(1, 0))).is_err());
+
+        state.apply_block(&Block {
+            header: BlockHeader {
+                hash: [1; 32],
+                parent_hash: [0; 32],
+                height: 1,
+                timestamp: 0,
+                validator: server.address(),
+                merkle_root: [0; 32],
+                state_root: [0; 32],
+                wave_number: 0,
+                committee_id: 0,
+                protocol_version: PROTOCOL_VERSION,
+                state_version: STATE_VERSION,
+                signals: 0,
+            },
+            transactions: accepted,
+            dag_references: vec![],
+        }).unwrap();
+        let account = state.get_account(&server.address()).unwrap();
+        assert_eq!(account.nonce, 1);
+        assert_eq!(account.nonce_lanes, std::collections::BTreeMap::from([(1, 2), (3, 1)]));
+        assert_eq!(account.balance, 10_000 - 4 * 11);
+    }
+
+    #[test]
+    fn test_pending_transactions_reserve_balance() {
+        let config = create_test_config("reserved_balance");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        let sender = KeyPair::generate();
+        let state = std::sync::Arc::new(crate::state::StateManager::new(&config).unwrap());
+        state.create_test_account(sender.address(), 100, 0);
+        let consensus = ConsensusEngine::new(config, state).unwrap();
+
+        let transfer = |amount: u64, nonce: u64| {
+            let mut tx = Transaction::Transfer {
+                from: sender.address(),
+                to: [2u8; 32],
+                amount,
+                fee: 1,
+                nonce,
+                chain_id: None,
+                valid_until_height: None,
+                signature: vec![],
+            };
+            let payload = consensus.get_transaction_data_for_signing(&tx);
+            if let Transaction::Transfer { signature, .. } = &mut tx {
+                *signature = sender.sign(&payload);
+            }
+            tx
+        };
+
+        let first = transfer(60, 0);
+        consensus.add_transaction(first.clone()).unwrap();
+        assert_eq!(consensus.reserved_balance(&sender.address()), 61);
+        // Covered by the balance, but not by what the first transfer left
+        match consensus.add_transaction(transfer(50, 1)) {
+            Err(crate::error::HazeError::InsufficientBalance { required, available }) => {
+                assert_eq!((required, available), (51, 39));
+            }
+            other => panic!("expected insufficient balance, got {:?}", other),
+        }
+        consensus.add_transaction(transfer(38, 1)).unwrap();
+        assert_eq!(consensus.reserved_balance(&sender.address()), 100);
+
+        consensus.remove_transactions_from_pool(&[TxEnvelope::new(first)]);
+        assert_eq!(consensus.reserved_balance(&sender.address()), 39);
+    }
+
+    #[test]
+    fn test_oversized_transactions_rejected_by_pool_and_blocks() {
+        let mut config = create_test_config("max_transaction_bytes");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        config.consensus.max_transaction_bytes = 1024;
+        let state = std::sync::Arc::new(crate::state::StateManager::new(&config).unwrap());
+        let consensus = ConsensusEngine::new(config, state).unwrap();
+
+        let oversized = Transaction::Transfer {
+            from: [1u8; 32],
+            to: [2u8; 32],
+            amount: 1,
+            fee: 1,
+            nonce: 0,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![0u8; 2048],
+        };
+        match consensus.add_transaction(oversized.clone()) {
+            Err(crate::error::HazeError::TransactionTooLarge(size, 1024)) => assert!(size > 2048),
+            other => panic!("expected transaction too large, got {:?}", other),
+        }
+
+        let mut block = consensus.create_block(KeyPair::generate().address()).unwrap();
+        block.transactions.push(oversized);
+        assert!(matches!(consensus.process_block(&block), Err(crate::error::HazeError::InvalidBlock(_))));
+    }
+
     // Asset validation tests
     use crate::types::{AssetData, DensityLevel, Attribute};
     
@@ -1648,7 +4138,7 @@ mod tests {
             attributes: vec![
                 Attribute {
                     name: "power".to_string(),
-                    value: "100".to_string(),
+                    value: "100".into(),
                     rarity: Some(0.5),
                 }
             ],
@@ -1740,7 +4230,7 @@ mod tests {
             attributes: vec![
                 Attribute {
                     name: "power".to_string(),
-                    value: "100".to_string(),
+                    value: "100".into(),
                     rarity: Some(1.5), // Invalid: > 1.0
                 }
             ],
@@ -1766,7 +4256,7 @@ mod tests {
             attributes: vec![
                 Attribute {
                     name: "power".to_string(),
-                    value: "100".to_string(),
+                    value: "100".into(),
                     rarity: Some(0.75), // Valid: between 0.0 and 1.0
                 }
             ],
@@ -1906,6 +4396,315 @@ mod tests {
         assert!(is_finalized);
     }
 
+    #[test]
+    fn test_stale_waves_pruned_to_history() {
+        let mut config = create_test_config("wave_history");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        config.consensus.wave_retention = 3;
+        config.consensus.wave_history_limit = 4;
+        let state = std::sync::Arc::new(crate::state::StateManager::new(&config).unwrap());
+        let consensus = ConsensusEngine::new(config, state).unwrap();
+
+        let block = consensus.create_block(KeyPair::generate().address()).unwrap();
+        consensus.process_block(&block).unwrap();
+        let first = block.header.wave_number;
+        {
+            let mut waves = consensus.waves.write();
+            for number in first + 1..first + 10 {
+                waves.insert(number, Wave {
+                    number,
+                    blocks: HashSet::new(),
+                    finalized: false,
+                    created_at_ms: 0,
+                    finalized_at_ms: None,
+                    certificate: None,
+                });
+            }
+        }
+        consensus.finalize_wave(first).unwrap();
+        let summary = &consensus.wave_history(first, first, 10)[0];
+        assert!(summary.finalized);
+        assert!(summary.finalization_ms().is_some());
+        let certificate = summary.certificate.as_ref().unwrap();
+        assert_eq!(certificate.checkpoint_height, block.header.height);
+        assert_eq!(certificate.proposers, vec![block.header.validator]);
+
+        // Waves more than 3 behind the last finalized one leave memory; 4 summaries are kept
+        consensus.finalize_wave(first + 9).unwrap();
+        assert_eq!(consensus.live_wave_count(), 4);
+        let history = consensus.wave_history(0, u64::MAX, 100);
+        let numbers: Vec<u64> = history.iter().map(|summary| summary.wave).collect();
+        assert_eq!(numbers, (first + 2..first + 10).collect::<Vec<_>>());
+        assert!(!history[0].finalized, "stale unfinalized waves are pruned too");
+        assert_eq!(consensus.wave_history(first + 3, first + 7, 2).len(), 2);
+    }
+
+    #[test]
+    fn test_asset_finality_events() {
+        let mut config = create_test_config("asset_finality");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        config.dev.enabled = true;
+        config.dev.relaxed_signatures = true;
+        config.consensus.wave_retention = 1;
+        let state = std::sync::Arc::new(crate::state::StateManager::new(&config).unwrap());
+        let (ws_tx, mut ws_rx) = tokio::sync::broadcast::channel(64);
+        state.set_ws_tx(ws_tx);
+        let owner = KeyPair::generate().address();
+        state.create_test_account(owner, 100_000, 0);
+        let consensus = ConsensusEngine::new(config, state).unwrap();
+        let create = |asset_id: Hash| Transaction::MistbornAsset {
+            from: owner,
+            action: crate::types::AssetAction::Create,
+            asset_id,
+            data: crate::types::AssetData {
+                density: crate::types::DensityLevel::Ethereal,
+                metadata: std::collections::HashMap::new(),
+                attributes: vec![],
+                game_id: None,
+                owner,
+            },
+            fee: 0,
+            nonce: consensus.next_nonce(&owner),
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        let seal = |asset_id: Hash| {
+            consensus.add_transaction(create(asset_id)).unwrap();
+            let block = consensus.create_block(owner).unwrap();
+            consensus.process_block(&block).unwrap();
+            block.header
+        };
+
+        // Finalizing the wave confirms the asset
+        let first = seal([0xa1; 32]);
+        assert!(matches!(ws_rx.try_recv().unwrap(), WsEvent::AssetCreated { .. }));
+        consensus.finalize_wave(first.wave_number).unwrap();
+        match ws_rx.try_recv().unwrap() {
+            WsEvent::AssetFinalized { asset_id, height, wave } => {
+                assert_eq!((asset_id, height, wave), ([0xa1; 32], first.height, first.wave_number));
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+
+        // A block joining the finalized wave is final right away
+        let joined = seal([0xa3; 32]);
+        assert!(matches!(ws_rx.try_recv().unwrap(), WsEvent::AssetCreated { .. }));
+        assert!(matches!(
+            ws_rx.try_recv().unwrap(),
+            WsEvent::AssetFinalized { asset_id: [0xa3, ..], height, .. } if height == joined.height
+        ));
+
+        // A wave dropped before finalizing sends nothing: no state is undone, so the asset
+        // is still there
+        *consensus.current_wave.write() = first.wave_number + 1;
+        let second = seal([0xa2; 32]);
+        assert!(matches!(ws_rx.try_recv().unwrap(), WsEvent::AssetCreated { .. }));
+        {
+            let mut waves = consensus.waves.write();
+            for number in second.wave_number + 1..second.wave_number + 4 {
+                waves.insert(number, Wave {
+                    number,
+                    blocks: HashSet::new(),
+                    finalized: false,
+                    created_at_ms: 0,
+                    finalized_at_ms: None,
+                    certificate: None,
+                });
+            }
+        }
+        consensus.finalize_wave(second.wave_number + 3).unwrap();
+        assert!(!consensus.waves.read().contains_key(&second.wave_number));
+        assert!(ws_rx.try_recv().is_err());
+        assert!(consensus.state.get_asset(&[0xa2; 32]).is_some());
+    }
+
+    #[test]
+    fn test_node_roles_toggle_mempool_and_wave_pruning() {
+        // An archive node keeps every wave in full
+        let mut config = create_test_config("role_archive");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        config.consensus.wave_retention = 1;
+        config.role.preset = Some(crate::config::NodeRole::Archive);
+        let state = std::sync::Arc::new(crate::state::StateManager::new(&config).unwrap());
+        let consensus = ConsensusEngine::new(config, state).unwrap();
+        {
+            let mut waves = consensus.waves.write();
+            for number in 1..6 {
+                waves.insert(number, Wave {
+                    number,
+                    blocks: HashSet::new(),
+                    finalized: false,
+                    created_at_ms: 0,
+                    finalized_at_ms: None,
+                    certificate: None,
+                });
+            }
+        }
+        let live = consensus.live_wave_count();
+        consensus.finalize_wave(5).unwrap();
+        assert_eq!(consensus.live_wave_count(), live);
+
+        // A light node refuses transactions
+        let mut config = create_test_config("role_light");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        config.role.preset = Some(crate::config::NodeRole::Light);
+        let state = std::sync::Arc::new(crate::state::StateManager::new(&config).unwrap());
+        let consensus = ConsensusEngine::new(config, state).unwrap();
+        let keypair = KeyPair::generate();
+        let tx = Transaction::Transfer {
+            from: keypair.address(),
+            to: [2u8; 32],
+            amount: 1,
+            fee: 1,
+            nonce: 0,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![0u8; 64],
+        };
+        let error = consensus.add_transaction(tx).unwrap_err().to_string();
+        assert!(error.contains("light node keeps no mempool"), "{}", error);
+    }
+
+    #[test]
+    fn test_state_divergence_halts_wave() {
+        let config = create_test_config("consensus_divergence");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        let state = std::sync::Arc::new(crate::state::StateManager::new(&config).unwrap());
+        let consensus = ConsensusEngine::new(config, state.clone()).unwrap();
+        let validator = KeyPair::generate().address();
+
+        // A peer's block committing to a parent state this node does not have
+        let mut block = consensus.create_block(validator).unwrap();
+        let local = state.compute_state_root();
+        block.header.state_root = [9u8; 32];
+        block.header.hash = block.header.compute_hash(&consensus.network_id());
+        consensus.process_block(&block).unwrap();
+        assert_eq!(state.current_height(), 1);
+
+        let incidents = consensus.get_divergence_incidents();
+        assert_eq!(incidents.len(), 1);
+        assert_eq!((incidents[0].height, incidents[0].expected, incidents[0].local), (1, [9u8; 32], local));
+        let wave = block.header.wave_number;
+        assert_eq!(consensus.divergent_waves(), vec![wave]);
+        assert!(!consensus.check_wave_finalization(wave).unwrap());
+        assert!(consensus.finalize_wave(wave).is_err());
+        assert_eq!(consensus.get_last_finalized_height(), 0);
+
+        // Blocks built on the same state as ours raise nothing
+        let next = consensus.create_block(validator).unwrap();
+        consensus.process_block(&next).unwrap();
+        assert_eq!(consensus.get_divergence_incidents().len(), 1);
+    }
+
+    /// Two nodes applying the same asset blocks at different wall-clock times end up with the
+    /// same asset state and state roots
+    #[test]
+    fn test_asset_blocks_keep_nodes_in_agreement() {
+        let owner = KeyPair::generate().address();
+        let node = |name: &str| {
+            let mut config = create_test_config(name);
+            let _ = std::fs::remove_dir_all(&config.storage.db_path);
+            config.dev.enabled = true;
+            config.dev.relaxed_signatures = true;
+            let state = std::sync::Arc::new(crate::state::StateManager::new(&config).unwrap());
+            state.create_test_account(owner, 100_000, 0);
+            (ConsensusEngine::new(config, state.clone()).unwrap(), state)
+        };
+        let (producer, producer_state) = node("agreement_producer");
+        let (follower, follower_state) = node("agreement_follower");
+        let asset_id = [0xa1; 32];
+        let asset_tx = |action: crate::types::AssetAction, key: &str| Transaction::MistbornAsset {
+            from: owner,
+            action,
+            asset_id,
+            data: crate::types::AssetData {
+                density: crate::types::DensityLevel::Ethereal,
+                metadata: [(key.to_string(), "1".to_string()), (format!("{}_max", key), "9".to_string())].into(),
+                attributes: vec![],
+                game_id: None,
+                owner,
+            },
+            fee: 0,
+            nonce: producer.next_nonce(&owner),
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+
+        for (action, key) in [(crate::types::AssetAction::Create, "level"), (crate::types::AssetAction::Update, "xp")] {
+            producer.add_transaction(asset_tx(action, key)).unwrap();
+            let block = producer.create_block(owner).unwrap();
+            producer.process_block(&block).unwrap();
+            // The follower sees the block on a later second of its own clock
+            std::thread::sleep(std::time::Duration::from_millis(1100));
+            follower.process_block(&block).unwrap();
+
+            let asset = follower_state.get_asset(&asset_id).unwrap();
+            assert_eq!(asset.updated_at, block.header.timestamp);
+            assert_eq!(asset.history.last().unwrap().timestamp, block.header.timestamp);
+            assert_eq!(follower_state.compute_state_root(), producer_state.compute_state_root());
+        }
+        // The next block commits to the producer's state root, which the follower shares
+        let block = producer.create_block(owner).unwrap();
+        follower.process_block(&block).unwrap();
+        assert!(follower.get_divergence_incidents().is_empty());
+    }
+
+    /// The same block processed twice (by the local producer and again from a peer,
+    /// here a second engine over the same state) is applied only once.
+    #[test]
+    fn test_process_same_block_twice() {
+        let config = create_test_config("process_twice");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        let state = std::sync::Arc::new(crate::state::StateManager::new(&config).unwrap());
+
+        let keypair = KeyPair::generate();
+        let from = keypair.address();
+        let to = [2u8; 32];
+        state.create_test_account(from, 10_000, 0);
+
+        let consensus = ConsensusEngine::new(config.clone(), state.clone()).unwrap();
+        let tx_data = {
+            let mut data = crate::types::network_id(0, &[]).to_vec();
+            data.extend_from_slice(b"Transfer");
+            data.extend_from_slice(&from);
+            data.extend_from_slice(&to);
+            data.extend_from_slice(&1000u64.to_le_bytes());
+            data.extend_from_slice(&10u64.to_le_bytes());
+            data.extend_from_slice(&0u64.to_le_bytes());
+            data
+        };
+        let tx = Transaction::Transfer {
+            from,
+            to,
+            amount: 1000,
+            fee: 10,
+            nonce: 0,
+            chain_id: None,
+            valid_until_height: None,
+            signature: keypair.sign(&tx_data),
+        };
+        consensus.add_transaction(tx).unwrap();
+
+        let block = consensus.create_block(from).unwrap();
+        consensus.process_block(&block).unwrap();
+        let supply = state.tokenomics().total_supply();
+        let burned = state.tokenomics().burned_supply();
+
+        // Same engine: caught by the DAG
+        consensus.process_block(&block).unwrap();
+        // Fresh engine with an empty DAG: caught by the applied-blocks set
+        let peer = ConsensusEngine::new(config, state.clone()).unwrap();
+        peer.process_block(&block).unwrap();
+
+        assert_eq!(state.current_height(), 1);
+        assert_eq!(state.get_account(&from).unwrap().balance, 10_000 - 1010);
+        assert_eq!(state.get_account(&to).unwrap().balance, 1000);
+        assert_eq!(state.tokenomics().total_supply(), supply);
+        assert_eq!(state.tokenomics().burned_supply(), burned);
+    }
+
     /// Finalized blocks must not be reverted: processing a competing block at or below
     /// last_finalized_height must be rejected.
     #[test]
@@ -1926,7 +4725,7 @@ mod tests {
         // Competing block at same height (different hash) must be rejected
         let mut header_b = block_a.header.clone();
         header_b.parent_hash = [2u8; 32]; // different parent -> different hash
-        header_b.hash = header_b.compute_hash();
+        header_b.hash = header_b.compute_hash(&consensus.network_id());
         let block_b = crate::types::Block {
             header: header_b,
             transactions: vec![],
@@ -1937,4 +4736,201 @@ mod tests {
         let err_msg = result.unwrap_err().to_string();
         assert!(err_msg.contains("finalized") || err_msg.contains("Finalized"), "expected finalized-related error, got: {}", err_msg);
     }
-}
\ No newline at end of file
+    #[test]
+    fn test_new_rejects_invalid_committee_config() {
+        let base = create_test_config("committee_invalid");
+        let state = std::sync::Arc::new(crate::state::StateManager::new(&base).unwrap());
+
+        for (size, min) in [(0, 1), (4, 1), (3, 5), (3, 0)] {
+            let mut config = base.clone();
+            config.consensus.committee_size = size;
+            config.consensus.min_committee_validators = min;
+            assert!(
+                ConsensusEngine::new(config, state.clone()).is_err(),
+                "committee_size={} min={} should be rejected", size, min
+            );
+        }
+    }
+
+    #[test]
+    fn test_committee_respects_configured_size() {
+        let mut config = create_test_config("committee_size");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        config.consensus.committee_size = 3;
+        let state = crate::state::StateManager::new(&config).unwrap();
+        for seed in 1..=5u8 {
+            let validator = [seed; 32];
+            state.tokenomics().stake(validator, validator, seed as u64 * 1000).unwrap();
+        }
+        let consensus = ConsensusEngine::new(config, std::sync::Arc::new(state)).unwrap();
+
+        let current_id = *consensus.current_committee_id.read();
+        let committees = consensus.committees.read();
+        let committee = committees.get(&current_id).unwrap();
+        assert_eq!(committee.validators.len(), 3);
+        // Highest stakes are selected
+        assert!(committee.validators.iter().all(|v| v[0] >= 3));
+    }
+
+    #[test]
+    fn test_committee_min_stake_and_weight_cap() {
+        let mut config = create_test_config("committee_weights");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        config.consensus.committee_size = 5;
+        let state = crate::state::StateManager::new(&config).unwrap();
+        for (seed, amount) in [(1u8, 6_000u64), (2, 2_000), (3, 1_000), (4, 500)] {
+            state.tokenomics().stake([seed; 32], [seed; 32], amount).unwrap();
+        }
+        let consensus = ConsensusEngine::new(config, std::sync::Arc::new(state)).unwrap();
+
+        // Below `committee_min_stake` (1000) is left out; every weight is capped at 33.33%
+        let committee = consensus.current_committee().unwrap();
+        assert_eq!(committee.members, vec![([1u8; 32], 3_333), ([2u8; 32], 3_333), ([3u8; 32], 3_333)]);
+
+        // Parameters changed by governance apply from the next committee
+        consensus.state.tokenomics().set_committee_params(crate::tokenomics::CommitteeParams {
+            min_stake: 2_000,
+            max_weight_bps: 10_000,
+        });
+        consensus.initialize_committee().unwrap();
+        let committee = consensus.current_committee().unwrap();
+        assert_eq!(committee.members, vec![([1u8; 32], 7_500), ([2u8; 32], 2_500)]);
+    }
+
+    #[test]
+    fn test_committee_kept_when_too_few_validators() {
+        let mut config = create_test_config("committee_min");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        config.consensus.min_committee_validators = 2;
+        let state = crate::state::StateManager::new(&config).unwrap();
+        let consensus = ConsensusEngine::new(config, std::sync::Arc::new(state)).unwrap();
+        assert_eq!(*consensus.current_committee_id.read(), 1);
+
+        // No eligible validators: rotation keeps the bootstrap committee
+        consensus.initialize_committee().unwrap();
+        assert_eq!(*consensus.current_committee_id.read(), 1);
+        assert_eq!(consensus.committees.read().len(), 1);
+    }
+
+    #[test]
+    fn test_validator_jailed_after_missed_slots() {
+        let mut config = create_test_config("jail_missed_slots");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        config.consensus.max_missed_slots = 2;
+        let state = crate::state::StateManager::new(&config).unwrap();
+        let proposer = [1u8; 32];
+        let idle = [2u8; 32];
+        state.tokenomics().stake(proposer, proposer, 2000).unwrap();
+        state.tokenomics().stake(idle, idle, 1000).unwrap();
+        let consensus = ConsensusEngine::new(config, std::sync::Arc::new(state)).unwrap();
+
+        let proposers: HashSet<Address> = [proposer].into_iter().collect();
+        consensus.update_missed_slots(&proposers, 1_000).unwrap();
+        assert_eq!(consensus.get_missed_slots(&idle), 1);
+        assert_eq!(consensus.get_missed_slots(&proposer), 0);
+        assert!(!consensus.state.tokenomics().is_jailed(&idle));
+
+        consensus.update_missed_slots(&proposers, 1_000).unwrap();
+        assert!(consensus.state.tokenomics().is_jailed(&idle));
+        // Jailed for the configured duration from the wave's block time, not the local clock
+        let jailed_until = consensus.state.tokenomics().get_validator(&idle).unwrap().jailed_until;
+        assert_eq!(jailed_until, Some(1_000 + consensus.config.consensus.jail_duration_secs as i64));
+        assert!(!consensus.state.tokenomics().is_jailed(&proposer));
+
+        // Committee is re-formed without the jailed validator
+        let current_id = *consensus.current_committee_id.read();
+        let committees = consensus.committees.read();
+        assert_eq!(committees.get(&current_id).unwrap().validators, vec![proposer]);
+    }
+
+    #[test]
+    fn test_block_timestamp_median_time_rule() {
+        let mut config = create_test_config("median_time");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        config.consensus.median_time_window = 3;
+        let state = crate::state::StateManager::new(&config).unwrap();
+        let consensus = ConsensusEngine::new(config, std::sync::Arc::new(state)).unwrap();
+        let validator = KeyPair::generate().address();
+
+        let restamp = |block: &mut Block, timestamp: i64| {
+            block.header.timestamp = timestamp;
+            block.header.hash = block.header.compute_hash(&consensus.network_id());
+        };
+
+        // Too far in the future
+        let mut block = consensus.create_block(validator).unwrap();
+        let now = block.header.timestamp;
+        restamp(&mut block, now + 3600);
+        let err = consensus.process_block(&block).unwrap_err().to_string();
+        assert!(err.contains("ahead of local time"), "unexpected error: {}", err);
+
+        // Build a history with timestamps now-30, now-20, now-10 (median now-20)
+        for offset in [30, 20, 10] {
+            let mut block = consensus.create_block(validator).unwrap();
+            restamp(&mut block, now - offset);
+            consensus.process_block(&block).unwrap();
+        }
+        assert_eq!(consensus.state.median_time_past(), Some(now - 20));
+
+        // Before the median is rejected; at or after it is accepted
+        let mut block = consensus.create_block(validator).unwrap();
+        restamp(&mut block, now - 21);
+        let err = consensus.process_block(&block).unwrap_err().to_string();
+        assert!(err.contains("median time"), "unexpected error: {}", err);
+        restamp(&mut block, now - 20);
+        consensus.process_block(&block).unwrap();
+
+        // Blocks created locally never precede the median
+        let block = consensus.create_block(validator).unwrap();
+        assert!(block.header.timestamp >= consensus.state.median_time_past().unwrap());
+    }
+
+    #[test]
+    fn test_no_replay_across_networks() {
+        let mainnet_config = create_test_config("replay_mainnet");
+        let mut testnet_config = create_test_config("replay_testnet");
+        testnet_config.chain_id = 2;
+        let _ = std::fs::remove_dir_all(&mainnet_config.storage.db_path);
+        let _ = std::fs::remove_dir_all(&testnet_config.storage.db_path);
+        let mainnet_state = std::sync::Arc::new(crate::state::StateManager::new(&mainnet_config).unwrap());
+        let mainnet = ConsensusEngine::new(mainnet_config, mainnet_state.clone()).unwrap();
+        let testnet_state = std::sync::Arc::new(crate::state::StateManager::new(&testnet_config).unwrap());
+        let testnet = ConsensusEngine::new(testnet_config, testnet_state.clone()).unwrap();
+        assert_ne!(mainnet.network_id(), testnet.network_id());
+
+        // A transfer signed for testnet (without an explicit chain_id) does not verify on mainnet
+        let keypair = KeyPair::generate();
+        mainnet_state.create_test_account(keypair.address(), 1_000, 0);
+        testnet_state.create_test_account(keypair.address(), 1_000, 0);
+        let mut tx = Transaction::Transfer {
+            from: keypair.address(),
+            to: [2u8; 32],
+            amount: 10,
+            fee: 1,
+            nonce: 0,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![],
+        };
+        tx.set_signature(keypair.sign(&testnet.get_transaction_data_for_signing(&tx)));
+        assert!(testnet.validate_transaction(&tx).is_ok());
+        let err = mainnet.validate_transaction(&tx).unwrap_err().to_string();
+        assert!(err.contains("Invalid transaction signature"), "unexpected error: {}", err);
+
+        // A testnet block hashes differently on mainnet and is rejected there
+        let block = testnet.create_block(KeyPair::generate().address()).unwrap();
+        let err = mainnet.process_block(&block).unwrap_err().to_string();
+        assert!(err.contains("does not match"), "unexpected error: {}", err);
+        testnet.process_block(&block).unwrap();
+    }
+
+    #[test]
+    fn test_signing_payload_golden() {
+        let config = create_test_config("signing_golden");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        let state = crate::state::StateManager::new(&config).unwrap();
+        let consensus = ConsensusEngine::new(config, std::sync::Arc::new(state)).unwrap();
+        let payload = consensus.get_transaction_data_for_signing(&crate::types::golden_transfer());
+        crate::types::check_golden("transfer_signing", &payload);
+    }
+}
@@ -31,6 +31,16 @@ impl KeyPair {
         Self { signing_key }
     }
 
+    /// Wrap an existing signing key (e.g. one derived or imported from bytes)
+    pub fn from_signing_key(signing_key: SigningKey) -> Self {
+        Self { signing_key }
+    }
+
+    /// Underlying signing key; export with `signing_key_to_bytes`
+    pub fn signing_key(&self) -> &SigningKey {
+        &self.signing_key
+    }
+
     /// Get public key as address
     ///
     /// Returns a 32-byte address derived from the public key.
@@ -0,0 +1,285 @@
+//! Custodial player accounts for free-to-play onboarding
+//!
+//! A game backend holding an authorized API key gets one account per external player id
+//! without the player ever managing a key. Player keys are derived deterministically from a
+//! node-held master seed:
+//!
+//! `secret = HMAC-SHA256(master_seed, "haze_custody_v1" || sha256_hex(api_key) || 0x00 || player_id)`
+//!
+//! so the same (API key, player id) pair always maps to the same address and nothing per-player
+//! has to be stored. The master seed lives in an encrypted keystore file (Argon2id key
+//! derivation, ChaCha20-Poly1305) created on first start.
+//!
+//! The node signs transactions on behalf of a player until the player's key is exported to their
+//! own wallet; exports are recorded in the `custody_exports` tree and custodial signing is refused
+//! for that player afterwards.
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use hmac::{Hmac, Mac};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use zeroize::Zeroizing;
+use crate::config::CustodyConfig;
+use crate::consensus::ConsensusEngine;
+use crate::crypto::{signing_key_from_bytes, signing_key_to_bytes, KeyPair};
+use crate::error::{HazeError, Result};
+use crate::state::StateManager;
+use crate::storage::StorageTree;
+use crate::types::{hex_to_address, sha256, Address, Transaction};
+use crate::watchlist::hash_api_key;
+
+/// Maximum length of an external player id (bytes)
+pub const MAX_PLAYER_ID_LEN: usize = 128;
+
+const KEYSTORE_VERSION: u32 = 1;
+const DERIVATION_DOMAIN: &[u8] = b"haze_custody_v1";
+
+/// Encrypted master seed as stored on disk (JSON, byte fields hex-encoded)
+#[derive(Debug, Serialize, Deserialize)]
+struct KeystoreFile {
+    version: u32,
+    kdf: String,
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// Custodial account of one player
+#[derive(Debug, Clone)]
+pub struct CustodialAccount {
+    pub player_id: String,
+    pub address: Address,
+    /// Unix time the key was exported; the node no longer signs for this player
+    pub exported_at: Option<i64>,
+}
+
+/// Record of a key export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ExportRecord {
+    exported_at: i64,
+}
+
+/// Master seed plus export bookkeeping
+pub struct CustodyStore {
+    master_seed: Zeroizing<[u8; 32]>,
+    authorized_keys: HashSet<String>,
+    authorized_accounts: HashSet<Address>,
+    exports: Arc<dyn StorageTree>,
+}
+
+impl CustodyStore {
+    /// Unlock (or create) the keystore at `config.keystore_path` and open the export tree
+    pub fn open(config: &CustodyConfig, passphrase: &str, state: &StateManager) -> Result<Self> {
+        let master_seed = load_or_create_keystore(&config.keystore_path, passphrase)?;
+        Ok(Self {
+            master_seed,
+            authorized_keys: config.api_key_hashes.iter().map(|h| h.to_lowercase()).collect(),
+            authorized_accounts: config.account_addresses.iter().filter_map(|a| hex_to_address(a)).collect(),
+            exports: state.open_tree("custody_exports")?,
+        })
+    }
+
+    /// Whether `api_key` may manage custodial accounts
+    pub fn is_authorized(&self, api_key: &str) -> bool {
+        self.authorized_keys.contains(&hash_api_key(api_key))
+    }
+
+    /// Whether an account signed in with Sign-in-with-HAZE may manage custodial accounts
+    pub fn is_authorized_account(&self, address: &Address) -> bool {
+        self.authorized_accounts.contains(address)
+    }
+
+    /// Custodial account for `player_id` under `api_key`
+    pub fn account(&self, api_key: &str, player_id: &str) -> Result<CustodialAccount> {
+        let keypair = self.player_keypair(api_key, player_id)?;
+        Ok(CustodialAccount {
+            player_id: player_id.to_string(),
+            address: keypair.address(),
+            exported_at: self.export_record(api_key, player_id)?.map(|r| r.exported_at),
+        })
+    }
+
+    /// Sign `tx` as the player; `tx.sender()` must be the player's address
+    pub fn sign_transaction(
+        &self,
+        api_key: &str,
+        player_id: &str,
+        consensus: &ConsensusEngine,
+        tx: &mut Transaction,
+    ) -> Result<()> {
+        if self.export_record(api_key, player_id)?.is_some() {
+            return Err(HazeError::AccessDenied(format!(
+                "Key for player '{}' was exported; sign with the player's wallet", player_id
+            )));
+        }
+        let keypair = self.player_keypair(api_key, player_id)?;
+        if *tx.sender() != keypair.address() {
+            return Err(HazeError::AccessDenied(
+                "Transaction sender is not the player's custodial account".to_string()
+            ));
+        }
+        let signature = keypair.sign(&consensus.get_transaction_data_for_signing(tx));
+        tx.set_signature(signature);
+        Ok(())
+    }
+
+    /// Export the player's secret key and stop custodial signing for them
+    ///
+    /// Repeated exports return the same key (it is derived, not stored).
+    pub fn export(&self, api_key: &str, player_id: &str) -> Result<(CustodialAccount, Zeroizing<[u8; 32]>)> {
+        let keypair = self.player_keypair(api_key, player_id)?;
+        let record = match self.export_record(api_key, player_id)? {
+            Some(record) => record,
+            None => {
+                let record = ExportRecord { exported_at: chrono::Utc::now().timestamp() };
+                let value = bincode::serialize(&record)
+                    .map_err(|e| HazeError::Serialization(e.to_string()))?;
+                self.exports.insert(&export_key(api_key, player_id), &value)?;
+                self.exports.flush()?;
+                record
+            }
+        };
+        let account = CustodialAccount {
+            player_id: player_id.to_string(),
+            address: keypair.address(),
+            exported_at: Some(record.exported_at),
+        };
+        Ok((account, signing_key_to_bytes(keypair.signing_key())))
+    }
+
+    fn player_keypair(&self, api_key: &str, player_id: &str) -> Result<KeyPair> {
+        validate_player_id(player_id)?;
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(self.master_seed.as_ref())
+            .map_err(|e| HazeError::Crypto(e.to_string()))?;
+        mac.update(DERIVATION_DOMAIN);
+        mac.update(hash_api_key(api_key).as_bytes());
+        mac.update(&[0u8]);
+        mac.update(player_id.as_bytes());
+        let mut secret = Zeroizing::new([0u8; 32]);
+        secret.copy_from_slice(&mac.finalize().into_bytes());
+        Ok(KeyPair::from_signing_key(signing_key_from_bytes(secret.as_ref())?))
+    }
+
+    fn export_record(&self, api_key: &str, player_id: &str) -> Result<Option<ExportRecord>> {
+        let Some(value) = self.exports.get(&export_key(api_key, player_id))? else {
+            return Ok(None);
+        };
+        bincode::deserialize(&value)
+            .map(Some)
+            .map_err(|e| HazeError::Serialization(e.to_string()))
+    }
+}
+
+fn export_key(api_key: &str, player_id: &str) -> [u8; 32] {
+    let mut data = hash_api_key(api_key).into_bytes();
+    data.push(0);
+    data.extend_from_slice(player_id.as_bytes());
+    sha256(&data)
+}
+
+fn validate_player_id(player_id: &str) -> Result<()> {
+    if player_id.is_empty() || player_id.len() > MAX_PLAYER_ID_LEN {
+        return Err(HazeError::InvalidTransaction(format!(
+            "Player id must be 1..={} bytes", MAX_PLAYER_ID_LEN
+        )));
+    }
+    Ok(())
+}
+
+fn keystore_cipher(passphrase: &str, salt: &[u8], params: Params) -> Result<ChaCha20Poly1305> {
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, key.as_mut())
+        .map_err(|e| HazeError::Crypto(format!("Keystore key derivation failed: {}", e)))?;
+    Ok(ChaCha20Poly1305::new(Key::from_slice(key.as_ref())))
+}
+
+/// Decrypt the master seed at `path`, creating a new keystore if the file does not exist
+pub fn load_or_create_keystore(path: &Path, passphrase: &str) -> Result<Zeroizing<[u8; 32]>> {
+    if passphrase.is_empty() {
+        return Err(HazeError::Config("Custody keystore passphrase is empty".to_string()));
+    }
+    if path.exists() {
+        let file: KeystoreFile = serde_json::from_slice(&std::fs::read(path)?)
+            .map_err(|e| HazeError::Serialization(format!("Invalid custody keystore: {}", e)))?;
+        if file.version != KEYSTORE_VERSION || file.kdf != "argon2id" {
+            return Err(HazeError::Config(format!(
+                "Unsupported custody keystore (version {}, kdf {})", file.version, file.kdf
+            )));
+        }
+        let decode = |field: &str| {
+            hex::decode(field).map_err(|e| HazeError::Serialization(format!("Invalid custody keystore: {}", e)))
+        };
+        let (salt, nonce, ciphertext) = (decode(&file.salt)?, decode(&file.nonce)?, decode(&file.ciphertext)?);
+        if nonce.len() != 12 {
+            return Err(HazeError::Serialization("Invalid custody keystore nonce".to_string()));
+        }
+        let params = Params::new(file.m_cost, file.t_cost, file.p_cost, None)
+            .map_err(|e| HazeError::Config(format!("Invalid custody keystore parameters: {}", e)))?;
+        let plaintext = Zeroizing::new(
+            keystore_cipher(passphrase, &salt, params)?
+                .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+                .map_err(|_| HazeError::Crypto("Wrong custody keystore passphrase".to_string()))?,
+        );
+        let seed: [u8; 32] = plaintext
+            .as_slice()
+            .try_into()
+            .map_err(|_| HazeError::Crypto("Invalid custody master seed length".to_string()))?;
+        return Ok(Zeroizing::new(seed));
+    }
+
+    let mut seed = Zeroizing::new([0u8; 32]);
+    let mut salt = [0u8; 16];
+    let mut nonce = [0u8; 12];
+    OsRng.fill_bytes(seed.as_mut());
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce);
+
+    let params = Params::default();
+    let ciphertext = keystore_cipher(passphrase, &salt, params.clone())?
+        .encrypt(Nonce::from_slice(&nonce), seed.as_ref())
+        .map_err(|e| HazeError::Crypto(format!("Keystore encryption failed: {}", e)))?;
+    let file = KeystoreFile {
+        version: KEYSTORE_VERSION,
+        kdf: "argon2id".to_string(),
+        m_cost: params.m_cost(),
+        t_cost: params.t_cost(),
+        p_cost: params.p_cost(),
+        salt: hex::encode(salt),
+        nonce: hex::encode(nonce),
+        ciphertext: hex::encode(ciphertext),
+    };
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let json = serde_json::to_vec_pretty(&file).map_err(|e| HazeError::Serialization(e.to_string()))?;
+    std::fs::write(path, json)?;
+    tracing::info!("Created custody keystore at {}", path.display());
+    Ok(seed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keystore_roundtrip_and_wrong_passphrase() {
+        let dir = std::env::temp_dir().join(format!("haze_custody_keystore_{}", uuid::Uuid::new_v4()));
+        let path = dir.join("custody.keystore");
+        let seed = load_or_create_keystore(&path, "correct horse").unwrap();
+        let reopened = load_or_create_keystore(&path, "correct horse").unwrap();
+        assert_eq!(*seed, *reopened);
+        assert!(load_or_create_keystore(&path, "wrong").is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
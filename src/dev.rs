@@ -0,0 +1,263 @@
+//! Ephemeral development node (`haze --dev`)
+//!
+//! One command starts a throwaway local chain for game integration tests: the database lives
+//! in memory, a block is sealed as soon as a transaction arrives (one transaction per block),
+//! dev accounts are funded on the empty chain with their private keys printed, and
+//! `POST /api/v1/dev/faucet` sends test funds to any address. `--relaxed-signatures` also
+//! skips signature checks, so test clients can submit unsigned transactions.
+//!
+//! Dev keys are derived from fixed seeds, so the same accounts come back on every run and
+//! test fixtures can hard-code them. They are public: never fund them on a real network.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use ed25519_dalek::SigningKey;
+use serde::{Deserialize, Serialize};
+use crate::config::{Config, DevConfig, StorageBackend};
+use crate::consensus::ConsensusEngine;
+use crate::crypto::{signing_key_to_bytes, KeyPair};
+use crate::error::{HazeError, Result};
+use crate::state::StateManager;
+use crate::types::{address_to_hex, hash_to_hex, sha256, Address, Transaction};
+
+/// Chain id of dev nodes, so their transactions are never valid on a real network
+pub const DEV_CHAIN_ID: u64 = 1337;
+
+/// Fee of faucet transfers
+const FAUCET_FEE: u64 = 1;
+
+/// How long a faucet request waits for its transfer to be sealed
+const FAUCET_SEAL_TIMEOUT: Duration = Duration::from_secs(10);
+
+const FAUCET_SEAL_POLL: Duration = Duration::from_millis(20);
+
+/// Scratch directory of this dev node (blob files), removed on shutdown
+pub fn scratch_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("haze-dev-{}", std::process::id()))
+}
+
+/// Config of `haze --dev [--accounts <n>] [--relaxed-signatures] [--api <addr>]`; no config
+/// file is read or written
+pub fn config_from_args(args: &[String]) -> Result<Config> {
+    let usage = "usage: haze --dev [--accounts <n>] [--relaxed-signatures] [--api <addr>]";
+    let mut config = Config::default();
+    config.node_id = "dev".to_string();
+    config.chain_id = DEV_CHAIN_ID;
+    config.dev.enabled = true;
+    config.storage.backend = StorageBackend::Memory;
+    config.storage.db_path = scratch_dir().join("db");
+    config.storage.blob_storage_path = scratch_dir().join("blobs");
+    config.storage.maintenance_interval_secs = 0;
+    config.network.listen_addr = "/ip4/127.0.0.1/tcp/0".to_string();
+    config.consensus.max_transactions_per_block = 1;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().cloned()
+            .ok_or_else(|| HazeError::Config(format!("Missing value for {}", arg)));
+        match arg.as_str() {
+            "--accounts" => {
+                config.dev.accounts = value()?.parse()
+                    .map_err(|_| HazeError::Config(format!("--accounts takes a number ({})", usage)))?;
+            }
+            "--relaxed-signatures" => config.dev.relaxed_signatures = true,
+            "--api" => config.api.listen_addr = value()?,
+            other => return Err(HazeError::Config(format!("Unknown argument '{}' ({})", other, usage))),
+        }
+    }
+    config.validate()?;
+    Ok(config)
+}
+
+fn derived_key(seed: &str) -> KeyPair {
+    KeyPair::from_signing_key(SigningKey::from_bytes(&sha256(seed.as_bytes())))
+}
+
+/// Key of dev account `index`: the ed25519 key whose secret is sha256(`haze-dev-account-<index>`)
+pub fn dev_account_key(index: usize) -> KeyPair {
+    derived_key(&format!("haze-dev-account-{}", index))
+}
+
+/// Key of the account the faucet pays from: secret sha256(`haze-dev-faucet`)
+pub fn faucet_key() -> KeyPair {
+    derived_key("haze-dev-faucet")
+}
+
+/// A pre-funded dev account
+#[derive(Debug, Clone, Serialize)]
+pub struct DevAccount {
+    pub address: String,
+    /// Hex ed25519 secret key
+    pub private_key: String,
+    pub balance: u64,
+}
+
+/// Fund the faucet and the dev accounts on an empty chain. On a chain that already has blocks
+/// (a dev node on a persistent database) the accounts are only listed.
+pub fn fund_accounts(state: &StateManager, config: &DevConfig) -> Result<Vec<DevAccount>> {
+    let fund = state.current_height() == 0;
+    if fund {
+        state.seed_dev_account(faucet_key().address(), config.faucet_balance)?;
+    }
+    let mut accounts = Vec::with_capacity(config.accounts);
+    for index in 0..config.accounts {
+        let key = dev_account_key(index);
+        let address = key.address();
+        if fund {
+            state.seed_dev_account(address, config.account_balance)?;
+        }
+        accounts.push(DevAccount {
+            address: address_to_hex(&address),
+            private_key: hex::encode(*signing_key_to_bytes(key.signing_key())),
+            balance: state.get_account(&address).map_or(0, |account| account.balance),
+        });
+    }
+    Ok(accounts)
+}
+
+/// Print the dev accounts and endpoints; keys go to stdout whatever the log level
+pub fn print_banner(config: &Config, accounts: &[DevAccount]) {
+    println!("HAZE dev node (chain id {}): in-memory chain, one block per transaction", config.chain_id);
+    println!("  API:    http://{}", config.api.listen_addr);
+    println!("  Faucet: POST http://{}/api/v1/dev/faucet {{\"address\": \"<hex>\"}}", config.api.listen_addr);
+    if config.dev.relaxed_signatures {
+        println!("  Signatures are NOT checked (--relaxed-signatures)");
+    }
+    println!();
+    println!("Dev accounts (public test keys, never use them on a real network):");
+    for (index, account) in accounts.iter().enumerate() {
+        println!("  ({}) {}  balance {}", index, account.address, account.balance);
+        println!("      private key {}", account.private_key);
+    }
+}
+
+/// Seal a block whenever transactions are waiting, instead of on a timer
+pub async fn run_sealer(consensus: Arc<ConsensusEngine>, validator: Address) {
+    loop {
+        consensus.transaction_added().await;
+        while consensus.tx_pool_size() > 0 && !consensus.production_paused() {
+            let pending = consensus.tx_pool_size();
+            if let Err(e) = seal_block(&consensus, validator) {
+                // A transaction failing in the block is dead-lettered and leaves the pool, so
+                // the next block seals the ones behind it
+                tracing::error!("Failed to seal dev block: {}", e);
+                if consensus.tx_pool_size() >= pending {
+                    // Nothing left the pool: sealing again now would fail the same way
+                    break;
+                }
+            }
+        }
+    }
+}
+
+fn seal_block(consensus: &ConsensusEngine, validator: Address) -> Result<()> {
+    let block = consensus.create_block(validator)?;
+    consensus.process_block(&block)?;
+    tracing::info!(
+        "Sealed block {} ({} txs): {}",
+        block.header.height, block.transactions.len(), &hash_to_hex(&block.header.hash)[..16]
+    );
+    Ok(())
+}
+
+/// Body of `POST /api/v1/dev/faucet`
+#[derive(Debug, Clone, Deserialize)]
+pub struct FaucetRequest {
+    /// Hex address to fund
+    pub address: String,
+    /// Defaults to `dev.faucet_amount`
+    #[serde(default)]
+    pub amount: Option<u64>,
+}
+
+/// A sealed faucet transfer
+#[derive(Debug, Clone, Serialize)]
+pub struct FaucetTransfer {
+    pub tx_hash: String,
+    pub to: String,
+    pub amount: u64,
+}
+
+/// Sends test funds from the faucet account as signed transfers
+pub struct Faucet {
+    consensus: Arc<ConsensusEngine>,
+    key: KeyPair,
+    chain_id: u64,
+    amount: u64,
+    /// One transfer at a time, so each is sealed before the next takes a nonce
+    sending: tokio::sync::Mutex<()>,
+}
+
+impl Faucet {
+    pub fn new(consensus: Arc<ConsensusEngine>, config: &Config) -> Self {
+        Self {
+            consensus,
+            key: faucet_key(),
+            chain_id: config.chain_id,
+            amount: config.dev.faucet_amount,
+            sending: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Send `amount` (default `dev.faucet_amount`) to `to` and wait until it is sealed
+    pub async fn send(&self, to: Address, amount: Option<u64>) -> Result<FaucetTransfer> {
+        let amount = amount.unwrap_or(self.amount);
+        let _sending = self.sending.lock().await;
+        let from = self.key.address();
+        let nonce = self.consensus.next_nonce(&from);
+        let mut tx = Transaction::Transfer {
+            from,
+            to,
+            amount,
+            fee: FAUCET_FEE,
+            nonce,
+            chain_id: Some(self.chain_id),
+            valid_until_height: None,
+            signature: Vec::new(),
+        };
+        let signed = self.key.sign(&self.consensus.get_transaction_data_for_signing(&tx));
+        if let Transaction::Transfer { signature, .. } = &mut tx {
+            *signature = signed;
+        }
+        let tx_hash = tx.hash();
+        self.consensus.add_transaction(tx)?;
+
+        let state = self.consensus.state();
+        let deadline = Instant::now() + FAUCET_SEAL_TIMEOUT;
+        while state.get_account(&from).is_none_or(|account| account.nonce <= nonce) {
+            if let Some(failed) = state.get_dead_letter(&tx_hash) {
+                return Err(HazeError::InvalidTransaction(format!("Faucet transfer failed: {}", failed.error)));
+            }
+            if Instant::now() >= deadline {
+                return Err(HazeError::Consensus(format!(
+                    "Faucet transfer {} was not sealed within {}s", hash_to_hex(&tx_hash), FAUCET_SEAL_TIMEOUT.as_secs()
+                )));
+            }
+            tokio::time::sleep(FAUCET_SEAL_POLL).await;
+        }
+        Ok(FaucetTransfer { tx_hash: hash_to_hex(&tx_hash), to: address_to_hex(&to), amount })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dev_keys_are_deterministic() {
+        assert_eq!(dev_account_key(0).address(), dev_account_key(0).address());
+        assert_ne!(dev_account_key(0).address(), dev_account_key(1).address());
+        assert_ne!(faucet_key().address(), dev_account_key(0).address());
+    }
+
+    #[test]
+    fn test_config_from_args() {
+        let args = ["--accounts", "3", "--relaxed-signatures"].map(String::from);
+        let config = config_from_args(&args).unwrap();
+        assert!(config.dev.enabled && config.dev.relaxed_signatures);
+        assert_eq!((config.dev.accounts, config.chain_id), (3, DEV_CHAIN_ID));
+        assert_eq!(config.storage.backend, StorageBackend::Memory);
+        assert!(config_from_args(&["--bogus".to_string()]).is_err());
+    }
+}
@@ -4,13 +4,40 @@
 //! - Fog liquidity (dynamic liquidity based on gaming activity)
 //! - Vortex markets (spontaneous trading points)
 //! - Fog treasury (automatic revenue distribution)
+//! - Collection market statistics (volume, sales and time-bucketed price history)
+//! - Pool TWAP oracle (time-weighted prices sampled once per block)
+//! - Order books (limit orders per collection, matched during block application)
+//! - Referral programs (fee shares for the referrers of a collection's players)
+//! - Asset staking pools (HAZE yield for the staked assets of a collection)
 
+use std::collections::VecDeque;
 use std::sync::Arc;
 use dashmap::DashMap;
 use chrono::{DateTime, Utc, Duration};
-use crate::types::Address;
+use crate::types::{Address, AssetData, Hash, LootOutcome, OrderSide};
 use crate::error::{HazeError, Result};
 
+/// Reserved metadata key holding the economic zone of an asset (within its game_id).
+/// Create with it to place a new asset in a zone; Update with it to migrate the asset
+/// (an empty value leaves the current zone).
+pub const ZONE_KEY: &str = "_zone";
+
+/// Zone an asset is in (`None` if unassigned)
+pub fn asset_zone(data: &AssetData) -> Option<&str> {
+    data.metadata.get(ZONE_KEY).map(String::as_str).filter(|zone| !zone.is_empty())
+}
+
+/// Treasury receiving a zone's entry and exit fees:
+/// sha256("haze_zone_treasury" || game_id || 0x00 || zone_id)
+pub fn zone_treasury_address(game_id: &str, zone_id: &str) -> Address {
+    let mut data = Vec::with_capacity(18 + game_id.len() + 1 + zone_id.len());
+    data.extend_from_slice(b"haze_zone_treasury");
+    data.extend_from_slice(game_id.as_bytes());
+    data.push(0);
+    data.extend_from_slice(zone_id.as_bytes());
+    crate::types::sha256(&data)
+}
+
 /// Fog Economics manager
 pub struct FogEconomy {
     /// Regional economic zones (by game ID)
@@ -24,10 +51,69 @@ pub struct FogEconomy {
     
     /// Game activity tracking
     game_activity: Arc<DashMap<String, GameActivity>>,
+
+    /// Cumulative price observations by pool id
+    price_observations: Arc<DashMap<String, PoolObservations>>,
+
+    /// Observations kept per pool (the longest TWAP window in blocks)
+    max_observations: usize,
+}
+
+/// Fixed-point scale of TWAP prices (1.0 = `TWAP_PRICE_SCALE`)
+pub const TWAP_PRICE_SCALE: u128 = 1_000_000_000;
+
+/// Observations kept per pool unless configured otherwise
+pub const DEFAULT_TWAP_MAX_OBSERVATIONS: usize = 1024;
+
+/// Cumulative pool prices at the end of a block
+///
+/// `price1_cumulative` sums, over every block since the first observation, the price of
+/// `asset1` in `asset2` (scaled by `TWAP_PRICE_SCALE`) that held when the block started.
+/// A price moved within a block only counts from the next block on, so a manipulation has
+/// to be held across blocks to shift the average.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PriceObservation {
+    pub height: u64,
+    pub timestamp: i64,
+    pub price1_cumulative: u128,
+    pub price2_cumulative: u128,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+struct PoolObservations {
+    observations: VecDeque<PriceObservation>,
+    /// Prices at the end of the last observed block
+    price1: u128,
+    price2: u128,
+}
+
+/// Time-weighted average pool price over a window of blocks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub struct Twap {
+    pub from_height: u64,
+    pub to_height: u64,
+    /// Blocks actually covered (less than requested while history is short)
+    pub window_blocks: u64,
+    /// Average price of asset1 in asset2, scaled by `TWAP_PRICE_SCALE`
+    pub price1: u128,
+    /// Average price of asset2 in asset1, scaled by `TWAP_PRICE_SCALE`
+    pub price2: u128,
+}
+
+/// Spot prices of a pool (asset1 in asset2, asset2 in asset1), scaled by `TWAP_PRICE_SCALE`
+fn spot_prices(pool: &LiquidityPool) -> (u128, u128) {
+    let price = |numerator: u64, denominator: u64| {
+        if denominator == 0 {
+            0
+        } else {
+            numerator as u128 * TWAP_PRICE_SCALE / denominator as u128
+        }
+    };
+    (price(pool.reserve2, pool.reserve1), price(pool.reserve1, pool.reserve2))
 }
 
 /// Economic zone within a game
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct EconomicZone {
     pub game_id: String,
     pub zone_id: String,
@@ -39,7 +125,7 @@ pub struct EconomicZone {
 }
 
 /// Vortex market - spontaneous trading point
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct VortexMarket {
     pub market_id: String,
     pub game_id: String,
@@ -52,7 +138,7 @@ pub struct VortexMarket {
 }
 
 /// Market conditions for vortex markets
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub enum MarketConditions {
     ArbitrageOpportunity { discount: u64 }, // Percentage discount
     LimitedTimeAuction,
@@ -61,7 +147,7 @@ pub enum MarketConditions {
 }
 
 /// Liquidity pool for assets
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct LiquidityPool {
     pub pool_id: String,
     pub asset1: String,
@@ -74,7 +160,7 @@ pub struct LiquidityPool {
 }
 
 /// Game activity tracking
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct GameActivity {
     pub game_id: String,
     pub transactions_24h: u64,
@@ -91,9 +177,71 @@ impl FogEconomy {
             vortex_markets: Arc::new(DashMap::new()),
             liquidity_pools: Arc::new(DashMap::new()),
             game_activity: Arc::new(DashMap::new()),
+            price_observations: Arc::new(DashMap::new()),
+            max_observations: DEFAULT_TWAP_MAX_OBSERVATIONS,
         }
     }
 
+    /// Economy keeping `max_observations` price observations per pool (at least 2)
+    pub fn with_twap_capacity(max_observations: usize) -> Self {
+        Self {
+            max_observations: max_observations.max(2),
+            ..Self::new()
+        }
+    }
+
+    /// Record the end-of-block prices of every pool (call once per applied block)
+    pub fn record_price_observations(&self, height: u64, timestamp: i64) {
+        for pool in self.liquidity_pools.iter() {
+            let (price1, price2) = spot_prices(pool.value());
+            let mut entry = self.price_observations.entry(pool.key().clone()).or_default();
+            let observation = match entry.observations.back() {
+                Some(last) if height <= last.height => continue,
+                Some(last) => {
+                    let blocks = (height - last.height) as u128;
+                    PriceObservation {
+                        height,
+                        timestamp,
+                        price1_cumulative: last.price1_cumulative.saturating_add(entry.price1.saturating_mul(blocks)),
+                        price2_cumulative: last.price2_cumulative.saturating_add(entry.price2.saturating_mul(blocks)),
+                    }
+                }
+                None => PriceObservation { height, timestamp, price1_cumulative: 0, price2_cumulative: 0 },
+            };
+            entry.observations.push_back(observation);
+            while entry.observations.len() > self.max_observations {
+                entry.observations.pop_front();
+            }
+            entry.price1 = price1;
+            entry.price2 = price2;
+        }
+    }
+
+    /// Average price of a pool over the last `window_blocks` observed blocks
+    ///
+    /// Uses the newest observation at least `window_blocks` older than the latest, or the
+    /// oldest one while history is shorter. `None` for an unknown pool or fewer than two
+    /// observations.
+    pub fn twap(&self, pool_id: &str, window_blocks: u64) -> Option<Twap> {
+        let entry = self.price_observations.get(pool_id)?;
+        let latest = *entry.observations.back()?;
+        let target = latest.height.saturating_sub(window_blocks.max(1));
+        let start = entry.observations
+            .iter()
+            .rev()
+            .find(|o| o.height <= target)
+            .or_else(|| entry.observations.front())
+            .copied()?;
+        let blocks = latest.height.checked_sub(start.height).filter(|b| *b > 0)?;
+        Some(Twap {
+            from_height: start.height,
+            to_height: latest.height,
+            window_blocks: blocks,
+            price1: (latest.price1_cumulative - start.price1_cumulative) / blocks as u128,
+            price2: (latest.price2_cumulative - start.price2_cumulative) / blocks as u128,
+        })
+    }
+
     /// Update game activity
     pub fn update_game_activity(
         &self,
@@ -335,6 +483,392 @@ impl FogEconomy {
     pub fn get_game_activity(&self, game_id: &str) -> Option<GameActivity> {
         self.game_activity.get(game_id).map(|a| a.clone())
     }
+
+    /// Copy of everything tracked here, for the state checkpoint written on shutdown
+    pub(crate) fn checkpoint(&self) -> FogEconomyCheckpoint {
+        fn entries<V: Clone>(map: &DashMap<String, V>) -> Vec<(String, V)> {
+            map.iter().map(|entry| (entry.key().clone(), entry.value().clone())).collect()
+        }
+        FogEconomyCheckpoint {
+            economic_zones: entries(&self.economic_zones),
+            vortex_markets: entries(&self.vortex_markets),
+            liquidity_pools: entries(&self.liquidity_pools),
+            game_activity: entries(&self.game_activity),
+            price_observations: entries(&self.price_observations),
+        }
+    }
+
+    /// Replace everything tracked here with a checkpoint (keeping this economy's observation
+    /// capacity)
+    pub(crate) fn restore(&self, checkpoint: FogEconomyCheckpoint) {
+        self.economic_zones.clear();
+        for (key, value) in checkpoint.economic_zones {
+            self.economic_zones.insert(key, value);
+        }
+        self.vortex_markets.clear();
+        for (key, value) in checkpoint.vortex_markets {
+            self.vortex_markets.insert(key, value);
+        }
+        self.liquidity_pools.clear();
+        for (key, value) in checkpoint.liquidity_pools {
+            self.liquidity_pools.insert(key, value);
+        }
+        self.game_activity.clear();
+        for (key, value) in checkpoint.game_activity {
+            self.game_activity.insert(key, value);
+        }
+        self.price_observations.clear();
+        for (pool_id, mut entry) in checkpoint.price_observations {
+            while entry.observations.len() > self.max_observations {
+                entry.observations.pop_front();
+            }
+            self.price_observations.insert(pool_id, entry);
+        }
+    }
+}
+
+/// Contents of a `FogEconomy` (see `FogEconomy::checkpoint`)
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub(crate) struct FogEconomyCheckpoint {
+    economic_zones: Vec<(String, EconomicZone)>,
+    vortex_markets: Vec<(String, VortexMarket)>,
+    liquidity_pools: Vec<(String, LiquidityPool)>,
+    game_activity: Vec<(String, GameActivity)>,
+    price_observations: Vec<(String, PoolObservations)>,
+}
+
+/// Trades kept per collection for the trade history
+pub const MAX_TRADE_HISTORY: usize = 1000;
+
+/// Account holding the HAZE and assets escrowed by open orders: sha256("haze_order_escrow")
+pub fn order_escrow_address() -> Address {
+    crate::types::sha256(b"haze_order_escrow")
+}
+
+/// Status of an order book order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderStatus {
+    Open,
+    Filled,
+    Cancelled,
+}
+
+/// Limit order on the order book of a collection, priced in HAZE per asset
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Order {
+    pub id: Hash,
+    pub owner: Address,
+    pub game_id: String,
+    pub side: OrderSide,
+    pub price: u64,
+    pub quantity: u64,
+    /// Quantity not filled yet
+    pub remaining: u64,
+    /// Escrowed assets not sold yet (sell orders), in the order they are sold
+    pub assets: Vec<Hash>,
+    pub placed_at_height: u64,
+    /// Chain-wide placement counter; earlier orders win ties at the same price
+    pub sequence: u64,
+    pub status: OrderStatus,
+}
+
+/// One asset changing hands when an incoming order matches a resting (maker) order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Fill {
+    pub maker: Hash,
+    pub asset_id: Hash,
+    /// Execution price: the maker's limit price
+    pub price: u64,
+}
+
+/// Executed trade of one asset
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Trade {
+    pub game_id: String,
+    pub asset_id: Hash,
+    pub price: u64,
+    /// Marketplace fee deducted from the seller's proceeds
+    pub fee: u64,
+    pub buyer: Address,
+    pub seller: Address,
+    pub buy_order: Hash,
+    pub sell_order: Hash,
+    pub height: u64,
+}
+
+/// Fees a referral program can share
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ReferralFee {
+    /// Marketplace fee on order book sales
+    Marketplace,
+    /// Gas of condense, evaporate, merge and split
+    Crafting,
+}
+
+/// Referrer of a player in one collection
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Referral {
+    pub referrer: Address,
+    pub bound_at_height: u64,
+}
+
+/// What a referrer earned in one collection
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ReferralEarnings {
+    /// Players bound to the referrer
+    pub players: u64,
+    pub marketplace: u64,
+    pub crafting: u64,
+}
+
+impl ReferralEarnings {
+    pub fn total(&self) -> u64 {
+        self.marketplace + self.crafting
+    }
+
+    pub fn add(&mut self, other: &ReferralEarnings) {
+        self.players += other.players;
+        self.marketplace += other.marketplace;
+        self.crafting += other.crafting;
+    }
+}
+
+/// Account holding loot box bonds until they are returned or forfeited:
+/// sha256("haze_loot_escrow")
+pub fn loot_escrow_address() -> Address {
+    crate::types::sha256(b"haze_loot_escrow")
+}
+
+/// Status of a loot box at a given height
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LootBoxStatus {
+    /// Waiting for the player to open it
+    Sealed,
+    /// Opened; the operator may reveal it until the deadline
+    Opened,
+    /// The drawn outcome was minted to the player
+    Revealed,
+    /// The operator missed the reveal deadline; the player may claim the bond
+    Expired,
+    /// The bond was paid to the player
+    Forfeited,
+}
+
+/// How a loot box was settled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum LootSettlement {
+    /// Outcome `outcome` (index into the sealed outcomes) was drawn and minted
+    Revealed { height: u64, outcome: usize },
+    /// The bond went to the player at `height`
+    Forfeited { height: u64 },
+}
+
+/// Loot box sealed by a game operator for one player (see `Transaction::CommitLootBox`)
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct LootBox {
+    pub id: Hash,
+    pub operator: Address,
+    pub player: Address,
+    pub game_id: String,
+    pub commitment: Hash,
+    pub bond: u64,
+    pub sealed_at_height: u64,
+    pub opened_at_height: Option<u64>,
+    /// Block whose hash is the box's randomness (set when opened)
+    pub beacon_height: Option<u64>,
+    /// Last height the box can be revealed at (set when opened)
+    pub reveal_deadline: Option<u64>,
+    pub settlement: Option<LootSettlement>,
+}
+
+impl LootBox {
+    pub fn status(&self, height: u64) -> LootBoxStatus {
+        match (self.settlement, self.reveal_deadline) {
+            (Some(LootSettlement::Revealed { .. }), _) => LootBoxStatus::Revealed,
+            (Some(LootSettlement::Forfeited { .. }), _) => LootBoxStatus::Forfeited,
+            (None, Some(deadline)) if height > deadline => LootBoxStatus::Expired,
+            (None, Some(_)) => LootBoxStatus::Opened,
+            (None, None) => LootBoxStatus::Sealed,
+        }
+    }
+}
+
+/// Index of the outcome a loot box draws (`None` when every weight is 0)
+///
+/// The roll is the first 8 bytes (LE) of sha256("haze_loot_roll" || beacon || salt || box_id)
+/// modulo the total weight; outcomes own consecutive ranges of that total in list order.
+/// The beacon (a block hash) is unknown to both sides when the box is sealed and opened,
+/// and the salt keeps the block producer from predicting the result.
+pub fn draw_loot_outcome(outcomes: &[LootOutcome], beacon: &Hash, salt: &[u8], box_id: &Hash) -> Option<usize> {
+    let total: u64 = outcomes.iter().map(|outcome| outcome.weight as u64).sum();
+    if total == 0 {
+        return None;
+    }
+    let mut data = b"haze_loot_roll".to_vec();
+    data.extend_from_slice(beacon);
+    data.extend_from_slice(salt);
+    data.extend_from_slice(box_id);
+    let digest = crate::types::sha256(&data);
+    let mut roll = u64::from_le_bytes(digest[..8].try_into().expect("8 bytes")) % total;
+    outcomes.iter().position(|outcome| {
+        if roll < outcome.weight as u64 {
+            return true;
+        }
+        roll -= outcome.weight as u64;
+        false
+    })
+}
+
+/// Account holding the assets staked in asset pools and the pools' HAZE:
+/// sha256("haze_asset_pool_escrow")
+pub fn asset_pool_escrow_address() -> Address {
+    crate::types::sha256(b"haze_asset_pool_escrow")
+}
+
+/// Account holding assets while their upgrade outcome is pending:
+/// sha256("haze_upgrade_escrow")
+pub fn upgrade_escrow_address() -> Address {
+    crate::types::sha256(b"haze_upgrade_escrow")
+}
+
+/// Fixed-point scale of `AssetPool::reward_per_asset`
+pub const ASSET_POOL_REWARD_SCALE: u128 = 1_000_000_000_000;
+
+/// Staking pool paying HAZE to the staked assets of one collection
+///
+/// Every block emits `reward_per_block` out of the reserve, shared equally by the assets
+/// staked at that time. Blocks with nothing staked emit nothing, and emission stops when
+/// the reserve runs out until the pool is funded again. Rewards are accounted lazily:
+/// `reward_per_asset` sums what one asset earned since the pool opened, and each stake
+/// keeps the value it has already been paid up to.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct AssetPool {
+    pub id: Hash,
+    pub creator: Address,
+    pub game_id: String,
+    pub reward_per_block: u64,
+    /// Blocks a staked asset stays locked
+    pub lock_blocks: u64,
+    pub created_at_height: u64,
+    /// HAZE not emitted yet
+    pub reserve: u64,
+    /// Assets staked
+    pub staked: u64,
+    /// HAZE earned by one asset staked since the pool opened, times `ASSET_POOL_REWARD_SCALE`
+    pub reward_per_asset: u128,
+    /// Last height rewards were emitted for
+    pub accrued_at_height: u64,
+    /// HAZE paid out to stakers
+    pub paid: u64,
+}
+
+impl AssetPool {
+    /// Emit the rewards of the blocks after `accrued_at_height` up to `height`
+    pub fn accrue(&mut self, height: u64) {
+        if height <= self.accrued_at_height {
+            return;
+        }
+        if self.staked > 0 {
+            let blocks = height - self.accrued_at_height;
+            let emitted = self.reward_per_block.saturating_mul(blocks).min(self.reserve);
+            self.reserve -= emitted;
+            self.reward_per_asset = self.reward_per_asset
+                .saturating_add(emitted as u128 * ASSET_POOL_REWARD_SCALE / self.staked as u128);
+        }
+        self.accrued_at_height = height;
+    }
+}
+
+/// Asset locked in an asset pool
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct AssetStake {
+    pub asset_id: Hash,
+    pub pool_id: Hash,
+    /// Account the asset returns to
+    pub owner: Address,
+    pub staked_at_height: u64,
+    /// First height the asset can be unstaked at
+    pub unlock_height: u64,
+    /// `reward_per_asset` of the pool when the stake was last paid
+    pub reward_checkpoint: u128,
+    /// HAZE paid to the owner so far
+    pub claimed: u64,
+}
+
+impl AssetStake {
+    /// Unclaimed rewards, given the pool accrued to the current height (rounded down)
+    pub fn pending(&self, pool: &AssetPool) -> u64 {
+        (pool.reward_per_asset.saturating_sub(self.reward_checkpoint) / ASSET_POOL_REWARD_SCALE) as u64
+    }
+
+    /// Mark the unclaimed rewards paid and return them. The fraction of a HAZE left over
+    /// stays with the stake.
+    pub fn take_pending(&mut self, pool: &AssetPool) -> u64 {
+        let pending = self.pending(pool);
+        self.reward_checkpoint += pending as u128 * ASSET_POOL_REWARD_SCALE;
+        self.claimed += pending;
+        pending
+    }
+}
+
+/// Match an incoming order against resting orders with price-time priority
+///
+/// Asks are taken lowest price first, bids highest price first, and orders at the same
+/// price by `sequence`. Every fill executes at the maker's price. Orders never match
+/// orders of their own owner. `taker` and the matched entries of `book` are updated in
+/// place (remaining quantity, unsold assets, status); fills are returned in execution order.
+pub fn match_order(taker: &mut Order, book: &mut [Order]) -> Vec<Fill> {
+    let crosses = |maker: &Order| match taker.side {
+        OrderSide::Buy => maker.price <= taker.price,
+        OrderSide::Sell => maker.price >= taker.price,
+    };
+    let mut makers: Vec<usize> = (0..book.len())
+        .filter(|&i| {
+            let maker = &book[i];
+            maker.status == OrderStatus::Open
+                && maker.side != taker.side
+                && maker.game_id == taker.game_id
+                && maker.owner != taker.owner
+                && crosses(maker)
+        })
+        .collect();
+    match taker.side {
+        OrderSide::Buy => makers.sort_by_key(|&i| (book[i].price, book[i].sequence)),
+        OrderSide::Sell => makers.sort_by_key(|&i| (std::cmp::Reverse(book[i].price), book[i].sequence)),
+    }
+
+    let mut fills = Vec::new();
+    for i in makers {
+        if taker.remaining == 0 {
+            break;
+        }
+        let maker = &mut book[i];
+        let units = taker.remaining.min(maker.remaining);
+        let seller_assets = match taker.side {
+            OrderSide::Buy => &mut maker.assets,
+            OrderSide::Sell => &mut taker.assets,
+        };
+        for asset_id in seller_assets.drain(..units as usize) {
+            fills.push(Fill { maker: maker.id, asset_id, price: maker.price });
+        }
+        taker.remaining -= units;
+        maker.remaining -= units;
+        if maker.remaining == 0 {
+            maker.status = OrderStatus::Filled;
+        }
+    }
+    if taker.remaining == 0 {
+        taker.status = OrderStatus::Filled;
+    }
+    fills
+}
+
+impl crate::vm::PriceOracle for FogEconomy {
+    fn twap(&self, pool_id: &str, window_blocks: u64) -> Option<Twap> {
+        FogEconomy::twap(self, pool_id, window_blocks)
+    }
 }
 
 impl Default for FogEconomy {
@@ -342,3 +876,100 @@ impl Default for FogEconomy {
         Self::new()
     }
 }
+
+/// Hourly buckets kept per collection (one week)
+pub const MAX_HOURLY_BUCKETS: usize = 24 * 7;
+
+/// Daily buckets kept per collection (one year)
+pub const MAX_DAILY_BUCKETS: usize = 365;
+
+/// Granularity of collection market history
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarketBucketSize {
+    Hour,
+    Day,
+}
+
+impl MarketBucketSize {
+    pub fn seconds(self) -> i64 {
+        match self {
+            MarketBucketSize::Hour => 3600,
+            MarketBucketSize::Day => 86_400,
+        }
+    }
+}
+
+/// Sales of a collection in one time bucket (block time)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct MarketBucket {
+    /// Unix timestamp the bucket starts at
+    pub start: i64,
+    pub open: u64,
+    pub close: u64,
+    pub low: u64,
+    pub high: u64,
+    pub volume: u128,
+    pub sales: u64,
+}
+
+/// Sale statistics of a collection, updated incrementally as sales are applied
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CollectionMarketStats {
+    pub volume: u128,
+    pub sales: u64,
+    pub fees: u128,
+    pub last_price: Option<u64>,
+    pub last_sale_height: Option<u64>,
+    pub hourly: VecDeque<MarketBucket>,
+    pub daily: VecDeque<MarketBucket>,
+}
+
+impl CollectionMarketStats {
+    /// Count a sale at `price` (paying `fee`) made in the block at `height` with `timestamp`
+    pub fn record_sale(&mut self, price: u64, fee: u64, height: u64, timestamp: i64) {
+        self.volume += price as u128;
+        self.sales += 1;
+        self.fees += fee as u128;
+        self.last_price = Some(price);
+        self.last_sale_height = Some(height);
+        Self::record_bucket(&mut self.hourly, MarketBucketSize::Hour, MAX_HOURLY_BUCKETS, price, timestamp);
+        Self::record_bucket(&mut self.daily, MarketBucketSize::Day, MAX_DAILY_BUCKETS, price, timestamp);
+    }
+
+    fn record_bucket(buckets: &mut VecDeque<MarketBucket>, size: MarketBucketSize, max: usize, price: u64, timestamp: i64) {
+        let start = timestamp - timestamp.rem_euclid(size.seconds());
+        match buckets.back_mut() {
+            // Block timestamps may step back a little; such a sale joins the latest bucket
+            Some(bucket) if start <= bucket.start => {
+                bucket.close = price;
+                bucket.low = bucket.low.min(price);
+                bucket.high = bucket.high.max(price);
+                bucket.volume += price as u128;
+                bucket.sales += 1;
+            }
+            _ => {
+                buckets.push_back(MarketBucket {
+                    start,
+                    open: price,
+                    close: price,
+                    low: price,
+                    high: price,
+                    volume: price as u128,
+                    sales: 1,
+                });
+                while buckets.len() > max {
+                    buckets.pop_front();
+                }
+            }
+        }
+    }
+
+    /// History at `size` granularity, oldest first
+    pub fn buckets(&self, size: MarketBucketSize) -> &VecDeque<MarketBucket> {
+        match size {
+            MarketBucketSize::Hour => &self.hourly,
+            MarketBucketSize::Day => &self.daily,
+        }
+    }
+}
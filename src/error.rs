@@ -1,6 +1,12 @@
 //! Error types for HAZE blockchain
+//!
+//! Every variant has a stable numeric code (`HazeError::code`) and machine-readable
+//! parameters (`HazeError::params`) so clients do not have to parse messages.
+//! The catalog is in `docs/ERROR_CODES.md`; codes are never renumbered or reused.
 
+use std::collections::BTreeMap;
 use thiserror::Error;
+use crate::types::DensityLevel;
 
 #[derive(Error, Debug)]
 pub enum HazeError {
@@ -25,8 +31,8 @@ pub enum HazeError {
     #[error("Invalid metadata format: {0}")]
     InvalidMetadataFormat(String),
 
-    #[error("Invalid density transition: cannot transition from {0} to {1}")]
-    InvalidDensityTransition(String, String),
+    #[error("Invalid density transition: cannot transition from {0:?} to {1:?}")]
+    InvalidDensityTransition(DensityLevel, DensityLevel),
 
     #[error("Access denied: {0}")]
     AccessDenied(String),
@@ -43,6 +49,15 @@ pub enum HazeError {
     #[error("Invalid transaction: {0}")]
     InvalidTransaction(String),
 
+    #[error("Invalid nonce: expected {expected}, got {actual}")]
+    InvalidNonce { expected: u64, actual: u64 },
+
+    #[error("Insufficient balance: need {required}, have {available}")]
+    InsufficientBalance { required: u64, available: u64 },
+
+    #[error("Transaction too large: {0} bytes exceeds limit of {1} bytes")]
+    TransactionTooLarge(usize, usize),
+
     #[error("Invalid block: {0}")]
     InvalidBlock(String),
 
@@ -51,6 +66,112 @@ pub enum HazeError {
 
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
+
+    #[error("Database corruption: {0}")]
+    Corruption(String),
+
+    #[error("Maintenance: {0}")]
+    Maintenance(String),
+}
+
+pub type Result<T> = std::result::Result<T, HazeError>;
+
+impl HazeError {
+    /// Stable numeric error code
+    ///
+    /// 1xxx node internals, 2xxx transactions, 3xxx assets, 4xxx blocks, 5xxx contracts.
+    pub fn code(&self) -> u32 {
+        match self {
+            HazeError::Consensus(_) => 1001,
+            HazeError::Network(_) => 1002,
+            HazeError::State(_) => 1003,
+            HazeError::Database(_) => 1004,
+            HazeError::Serialization(_) => 1005,
+            HazeError::Io(_) => 1006,
+            HazeError::Config(_) => 1007,
+            HazeError::Crypto(_) => 1008,
+            HazeError::Corruption(_) => 1009,
+            HazeError::Maintenance(_) => 1010,
+            HazeError::InvalidTransaction(_) => 2000,
+            HazeError::InvalidNonce { .. } => 2001,
+            HazeError::InsufficientBalance { .. } => 2002,
+            HazeError::AccessDenied(_) => 2003,
+            HazeError::TransactionTooLarge(..) => 2004,
+            HazeError::Asset(_) => 3000,
+            HazeError::AssetSizeExceeded(..) => 3001,
+            HazeError::InvalidMetadataFormat(_) => 3002,
+            HazeError::InvalidDensityTransition(..) => 3003,
+            HazeError::InvalidBlock(_) => 4000,
+            HazeError::VM(_) => 5000,
+        }
+    }
+
+    /// Stable snake_case name of the error kind
+    pub fn name(&self) -> &'static str {
+        match self {
+            HazeError::Consensus(_) => "consensus",
+            HazeError::Network(_) => "network",
+            HazeError::State(_) => "state",
+            HazeError::Database(_) => "database",
+            HazeError::Serialization(_) => "serialization",
+            HazeError::Io(_) => "io",
+            HazeError::Config(_) => "config",
+            HazeError::Crypto(_) => "crypto",
+            HazeError::Corruption(_) => "corruption",
+            HazeError::Maintenance(_) => "maintenance",
+            HazeError::InvalidTransaction(_) => "invalid_transaction",
+            HazeError::InvalidNonce { .. } => "invalid_nonce",
+            HazeError::InsufficientBalance { .. } => "insufficient_balance",
+            HazeError::AccessDenied(_) => "access_denied",
+            HazeError::TransactionTooLarge(..) => "transaction_too_large",
+            HazeError::Asset(_) => "asset",
+            HazeError::AssetSizeExceeded(..) => "asset_size_exceeded",
+            HazeError::InvalidMetadataFormat(_) => "invalid_metadata_format",
+            HazeError::InvalidDensityTransition(..) => "invalid_density_transition",
+            HazeError::InvalidBlock(_) => "invalid_block",
+            HazeError::VM(_) => "vm",
+        }
+    }
+
+    /// Machine-readable parameters (densities as levels: 0 ethereal .. 3 core)
+    pub fn params(&self) -> BTreeMap<String, u64> {
+        let params: Vec<(&str, u64)> = match self {
+            HazeError::InvalidNonce { expected, actual } => vec![("expected", *expected), ("actual", *actual)],
+            HazeError::InsufficientBalance { required, available } => {
+                vec![("required", *required), ("available", *available)]
+            }
+            HazeError::AssetSizeExceeded(size, limit) | HazeError::TransactionTooLarge(size, limit) => {
+                vec![("size", *size as u64), ("limit", *limit as u64)]
+            }
+            HazeError::InvalidDensityTransition(from, to) => vec![("from", *from as u64), ("to", *to as u64)],
+            _ => Vec::new(),
+        };
+        params.into_iter().map(|(name, value)| (name.to_string(), value)).collect()
+    }
 }
 
-pub type Result<T> = std::result::Result<T, HazeError>;
\ No newline at end of file
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes_and_params() {
+        let err = HazeError::InvalidNonce { expected: 3, actual: 5 };
+        assert_eq!(err.code(), 2001);
+        assert_eq!(err.name(), "invalid_nonce");
+        assert_eq!(err.to_string(), "Invalid nonce: expected 3, got 5");
+        assert_eq!(err.params().get("expected"), Some(&3));
+        assert_eq!(err.params().get("actual"), Some(&5));
+
+        let err = HazeError::InvalidDensityTransition(DensityLevel::Ethereal, DensityLevel::Core);
+        assert_eq!(err.code(), 3003);
+        assert_eq!(err.to_string(), "Invalid density transition: cannot transition from Ethereal to Core");
+        assert_eq!(err.params().get("from"), Some(&0));
+        assert_eq!(err.params().get("to"), Some(&3));
+
+        assert_eq!(HazeError::AssetSizeExceeded(2048, 1024).params().get("limit"), Some(&1024));
+        assert_eq!(HazeError::TransactionTooLarge(2048, 1024).code(), 2004);
+        assert_eq!(HazeError::TransactionTooLarge(2048, 1024).params().get("size"), Some(&2048));
+        assert!(HazeError::InvalidBlock("x".to_string()).params().is_empty());
+    }
+}
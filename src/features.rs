@@ -0,0 +1,266 @@
+//! Upgrade signaling and coordinated feature activation
+//!
+//! Protocol changes every node must switch on at the same height are features. Each has a
+//! signal bit: validators set the bits of the features they are ready for in `signals` of the
+//! blocks they produce (`features.signal`, by default every feature this build knows). Blocks
+//! are counted in windows of `features.window_blocks` heights; when at least
+//! `features.threshold_percent` of a window's blocks signal a feature, it locks in at the last
+//! height of that window and activates one window later, which leaves the remaining nodes time
+//! to upgrade. `features.activation_heights` pins a feature to a height instead (a coordinated
+//! hard fork, or a testnet). Window settings are consensus rules shared by the whole network.
+//!
+//! The tally is rebuilt from the chain: every applied block goes through `record_block`,
+//! including the blocks replayed at startup. Consensus and state code ask `is_active`.
+
+use std::collections::{BTreeMap, HashMap};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use crate::config::FeaturesConfig;
+
+/// Protocol features switched on by signaling or at a pinned height
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Feature {
+    /// Every node checks block height deltas and parents, as with
+    /// `consensus.strict_block_validation`
+    StrictBlocks,
+    /// Blocks carrying a transaction for another `chain_id`, or past its
+    /// `valid_until_height`, are rejected
+    TransactionBounds,
+}
+
+impl Feature {
+    /// Every feature this build knows
+    pub const ALL: [Feature; 2] = [Feature::StrictBlocks, Feature::TransactionBounds];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Feature::StrictBlocks => "strict_blocks",
+            Feature::TransactionBounds => "transaction_bounds",
+        }
+    }
+
+    /// Bit in `BlockHeader::signals`; a bit is never reused for another feature
+    pub fn bit(self) -> u32 {
+        match self {
+            Feature::StrictBlocks => 0,
+            Feature::TransactionBounds => 1,
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Feature::StrictBlocks => "Reject blocks too far ahead of the local height or with an unknown parent",
+            Feature::TransactionBounds => "Reject blocks with a transaction for another chain_id or past its valid_until_height",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|feature| feature.name() == name)
+    }
+
+    fn mask(self) -> u32 {
+        1 << self.bit()
+    }
+}
+
+/// How a feature got (or will get) its activation height
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivationSource {
+    /// Locked in by signaling at the end of a window
+    Signaled { locked_in_at: u64 },
+    /// `features.activation_heights`
+    Pinned,
+}
+
+/// Activation of a feature
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Activation {
+    /// First height the feature applies to
+    pub height: u64,
+    pub source: ActivationSource,
+}
+
+/// A feature as listed by `GET /api/v1/features`
+#[derive(Debug, Clone, Serialize)]
+pub struct FeatureStatus {
+    pub feature: Feature,
+    pub bit: u32,
+    pub description: &'static str,
+    /// This node signals readiness in the blocks it produces
+    pub signaling: bool,
+    /// Blocks of the current window that signal the feature
+    pub window_signals: u64,
+    pub activation: Option<Activation>,
+    /// Active at the current height
+    pub active: bool,
+}
+
+/// Signal counts of the current window and the features locked in so far
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct Tally {
+    window_signals: HashMap<Feature, u64>,
+    locked_in: BTreeMap<Feature, Activation>,
+}
+
+/// Feature flags consulted by consensus and state code
+#[derive(Debug)]
+pub struct FeatureRegistry {
+    window_blocks: u64,
+    threshold_percent: u64,
+    signal_bits: u32,
+    pinned: HashMap<Feature, u64>,
+    tally: RwLock<Tally>,
+}
+
+impl FeatureRegistry {
+    /// Registry for validated `features` config (unknown names are rejected by `validate`)
+    pub fn new(config: &FeaturesConfig) -> Self {
+        Self {
+            window_blocks: config.window_blocks,
+            threshold_percent: config.threshold_percent as u64,
+            signal_bits: config.signal.iter().filter_map(|name| Feature::from_name(name)).fold(0, |bits, f| bits | f.mask()),
+            pinned: config.activation_heights
+                .iter()
+                .filter_map(|(name, height)| Some((Feature::from_name(name)?, *height)))
+                .collect(),
+            tally: RwLock::new(Tally::default()),
+        }
+    }
+
+    /// Signal bits for blocks this node produces
+    pub fn signal_bits(&self) -> u32 {
+        self.signal_bits
+    }
+
+    /// Count the signals of an applied block; at the end of a window, lock in the features
+    /// signaled by enough of its blocks
+    pub fn record_block(&self, height: u64, signals: u32) {
+        let mut tally = self.tally.write();
+        for feature in Feature::ALL {
+            if signals & feature.mask() != 0 {
+                *tally.window_signals.entry(feature).or_insert(0) += 1;
+            }
+        }
+        if height == 0 || !height.is_multiple_of(self.window_blocks) {
+            return;
+        }
+        let window_signals = std::mem::take(&mut tally.window_signals);
+        for feature in Feature::ALL {
+            if self.pinned.contains_key(&feature) || tally.locked_in.contains_key(&feature) {
+                continue;
+            }
+            let signaled = window_signals.get(&feature).copied().unwrap_or(0);
+            if signaled * 100 >= self.threshold_percent * self.window_blocks {
+                let activation = Activation {
+                    height: height + self.window_blocks,
+                    source: ActivationSource::Signaled { locked_in_at: height },
+                };
+                tracing::info!(
+                    "Feature {} locked in at height {} ({} of {} blocks signaled); active from height {}",
+                    feature.name(), height, signaled, self.window_blocks, activation.height
+                );
+                tally.locked_in.insert(feature, activation);
+            }
+        }
+    }
+
+    /// Signal tally, for the state checkpoint written on shutdown
+    pub(crate) fn checkpoint(&self) -> Tally {
+        self.tally.read().clone()
+    }
+
+    /// Replace the signal tally with a checkpoint
+    pub(crate) fn restore(&self, tally: Tally) {
+        *self.tally.write() = tally;
+    }
+
+    /// Activation of a feature, if pinned or locked in
+    pub fn activation(&self, feature: Feature) -> Option<Activation> {
+        if let Some(height) = self.pinned.get(&feature) {
+            return Some(Activation { height: *height, source: ActivationSource::Pinned });
+        }
+        self.tally.read().locked_in.get(&feature).copied()
+    }
+
+    /// Whether `feature` applies to the block at `height`
+    pub fn is_active(&self, feature: Feature, height: u64) -> bool {
+        self.activation(feature).is_some_and(|activation| height >= activation.height)
+    }
+
+    /// Every feature with its signaling and activation, as of `height`
+    pub fn status(&self, height: u64) -> Vec<FeatureStatus> {
+        Feature::ALL
+            .into_iter()
+            .map(|feature| {
+                let activation = self.activation(feature);
+                FeatureStatus {
+                    feature,
+                    bit: feature.bit(),
+                    description: feature.description(),
+                    signaling: self.signal_bits & feature.mask() != 0,
+                    window_signals: self.tally.read().window_signals.get(&feature).copied().unwrap_or(0),
+                    activation,
+                    active: activation.is_some_and(|activation| height >= activation.height),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry(configure: impl FnOnce(&mut FeaturesConfig)) -> FeatureRegistry {
+        let mut config = FeaturesConfig { window_blocks: 10, threshold_percent: 80, ..FeaturesConfig::default() };
+        configure(&mut config);
+        config.validate().unwrap();
+        FeatureRegistry::new(&config)
+    }
+
+    #[test]
+    fn test_signaling_locks_in_and_activates_a_window_later() {
+        let features = registry(|_| {});
+        assert_eq!(features.signal_bits(), Feature::StrictBlocks.mask() | Feature::TransactionBounds.mask());
+        let bit = Feature::StrictBlocks.mask();
+
+        // 7 of 10 blocks signal: below the threshold
+        for height in 1..=10 {
+            features.record_block(height, if height <= 7 { bit } else { 0 });
+        }
+        assert_eq!(features.activation(Feature::StrictBlocks), None);
+
+        // 8 of 10 lock it in at 20, active from 30
+        for height in 11..=20 {
+            features.record_block(height, if height <= 18 { bit } else { 0 });
+        }
+        let activation = features.activation(Feature::StrictBlocks).unwrap();
+        assert_eq!(activation, Activation { height: 30, source: ActivationSource::Signaled { locked_in_at: 20 } });
+        assert!(!features.is_active(Feature::StrictBlocks, 29));
+        assert!(features.is_active(Feature::StrictBlocks, 30));
+
+        // Later windows do not move it
+        for height in 21..=30 {
+            features.record_block(height, bit);
+        }
+        assert_eq!(features.activation(Feature::StrictBlocks), Some(activation));
+    }
+
+    #[test]
+    fn test_pinned_activation_ignores_signals() {
+        let features = registry(|config| {
+            config.signal.clear();
+            config.activation_heights.insert("strict_blocks".to_string(), 5);
+        });
+        assert_eq!(features.signal_bits(), 0);
+        for height in 1..=10 {
+            features.record_block(height, Feature::StrictBlocks.mask());
+        }
+        assert_eq!(features.activation(Feature::StrictBlocks), Some(Activation { height: 5, source: ActivationSource::Pinned }));
+        let status = features.status(4);
+        assert!(!status[0].active && !status[0].signaling);
+        assert!(features.is_active(Feature::StrictBlocks, 5));
+    }
+}
@@ -0,0 +1,187 @@
+//! Priority fee estimation
+//!
+//! `GET /api/v1/gas/estimate-priority` answers from two fee distributions kept up to date as
+//! the chain moves, so a request only reads percentiles:
+//! - the fees of the transactions in the last `consensus.fee_estimate_blocks` blocks
+//!   (`RecentBlockFees`, fed by `StateManager` as blocks are applied and replayed), and
+//! - the fees of the transactions waiting in the pool (a `FeeHistogram` kept by
+//!   `ConsensusEngine` as transactions enter and leave it).
+//!
+//! Both are multisets of fees (fee -> count), so adding or dropping a transaction is a map
+//! update and a percentile walks the distinct fees only.
+
+use std::collections::{BTreeMap, VecDeque};
+use serde::Serialize;
+
+/// Fees as a multiset
+#[derive(Debug, Clone, Default)]
+pub struct FeeHistogram {
+    counts: BTreeMap<u64, u64>,
+    total: u64,
+}
+
+impl FeeHistogram {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, fee: u64) {
+        *self.counts.entry(fee).or_default() += 1;
+        self.total += 1;
+    }
+
+    /// Drop one occurrence of `fee` (nothing if it is not there)
+    pub fn remove(&mut self, fee: u64) {
+        if let Some(count) = self.counts.get_mut(&fee) {
+            *count -= 1;
+            if *count == 0 {
+                self.counts.remove(&fee);
+            }
+            self.total -= 1;
+        }
+    }
+
+    pub fn len(&self) -> u64 {
+        self.total
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total == 0
+    }
+
+    /// Nearest-rank percentile (`p` in 0..=100); `None` when empty
+    pub fn percentile(&self, p: u8) -> Option<u64> {
+        if self.total == 0 {
+            return None;
+        }
+        let rank = (self.total * p.min(100) as u64).div_ceil(100).max(1);
+        let mut seen = 0;
+        for (fee, count) in &self.counts {
+            seen += count;
+            if seen >= rank {
+                return Some(*fee);
+            }
+        }
+        self.counts.keys().next_back().copied()
+    }
+
+    pub fn summary(&self) -> FeeSummary {
+        FeeSummary {
+            count: self.total,
+            min: self.counts.keys().next().copied(),
+            p10: self.percentile(10),
+            p25: self.percentile(25),
+            p50: self.percentile(50),
+            p75: self.percentile(75),
+            p90: self.percentile(90),
+            max: self.counts.keys().next_back().copied(),
+        }
+    }
+}
+
+/// Percentiles of a fee distribution (all `None` when it is empty)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct FeeSummary {
+    pub count: u64,
+    pub min: Option<u64>,
+    pub p10: Option<u64>,
+    pub p25: Option<u64>,
+    pub p50: Option<u64>,
+    pub p75: Option<u64>,
+    pub p90: Option<u64>,
+    pub max: Option<u64>,
+}
+
+/// Fees of the transactions in the last `window` blocks
+#[derive(Debug, Clone)]
+pub struct RecentBlockFees {
+    window: usize,
+    blocks: VecDeque<Vec<u64>>,
+    histogram: FeeHistogram,
+}
+
+impl RecentBlockFees {
+    pub fn new(window: usize) -> Self {
+        Self { window: window.max(1), blocks: VecDeque::new(), histogram: FeeHistogram::new() }
+    }
+
+    /// Add the fees of an applied block, dropping the oldest block past the window
+    pub fn record_block(&mut self, fees: Vec<u64>) {
+        for fee in &fees {
+            self.histogram.insert(*fee);
+        }
+        self.blocks.push_back(fees);
+        while self.blocks.len() > self.window {
+            for fee in self.blocks.pop_front().unwrap_or_default() {
+                self.histogram.remove(fee);
+            }
+        }
+    }
+
+    /// Blocks currently in the window
+    pub fn blocks(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Fees of each block in the window, oldest first
+    pub fn block_fees(&self) -> impl Iterator<Item = &[u64]> {
+        self.blocks.iter().map(Vec::as_slice)
+    }
+
+    pub fn histogram(&self) -> &FeeHistogram {
+        &self.histogram
+    }
+}
+
+/// Suggested fees for a transaction to be picked up soon
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SuggestedFees {
+    pub low: u64,
+    pub medium: u64,
+    pub high: u64,
+}
+
+/// Suggest fees from the 25th/50th/90th percentiles of recent blocks, falling back to the pool
+/// before any transaction was included, and to the minimum fee of 1 without either
+pub fn suggest(recent: &FeeHistogram, pool: &FeeHistogram) -> SuggestedFees {
+    let source = if recent.is_empty() { pool } else { recent };
+    let at = |p: u8| source.percentile(p).unwrap_or(1).max(1);
+    SuggestedFees { low: at(25), medium: at(50), high: at(90) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentiles_follow_inserts_and_removals() {
+        let mut fees = FeeHistogram::new();
+        assert_eq!(fees.summary(), FeeSummary::default());
+        for fee in 1..=10 {
+            fees.insert(fee * 10);
+        }
+        let summary = fees.summary();
+        assert_eq!((summary.min, summary.p10, summary.p50, summary.p90, summary.max), (Some(10), Some(10), Some(50), Some(90), Some(100)));
+
+        fees.remove(100);
+        fees.remove(100);
+        fees.remove(7);
+        assert_eq!(fees.len(), 9);
+        assert_eq!(fees.percentile(100), Some(90));
+    }
+
+    #[test]
+    fn test_recent_window_drops_old_blocks() {
+        let mut recent = RecentBlockFees::new(2);
+        recent.record_block(vec![100, 100]);
+        recent.record_block(vec![5]);
+        assert_eq!(recent.histogram().percentile(50), Some(100));
+        recent.record_block(vec![1, 9]);
+        assert_eq!(recent.blocks(), 2);
+        assert_eq!(recent.histogram().summary().max, Some(9));
+
+        let pool = FeeHistogram::new();
+        assert_eq!(suggest(recent.histogram(), &pool), SuggestedFees { low: 1, medium: 5, high: 9 });
+        assert_eq!(suggest(&FeeHistogram::new(), &pool), SuggestedFees { low: 1, medium: 1, high: 1 });
+    }
+}
@@ -0,0 +1,248 @@
+//! HTTP caching for read endpoints
+//!
+//! Responses carry an `ETag` and are answered with `304 Not Modified` when the
+//! client's `If-None-Match` still matches. Hot responses (assets, blockchain info)
+//! are kept in a small in-process cache keyed by request and tagged with the
+//! `StateManager::state_version` they were built from; any state change makes
+//! them stale, so they are rebuilt on the next read. Blob downloads are tagged
+//! with the blob hash and also serve single byte ranges (see `byte_range`).
+
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use dashmap::DashMap;
+
+/// Default number of cached responses
+pub const DEFAULT_CACHE_CAPACITY: usize = 1024;
+
+/// Cache-Control for responses that can change (clients must revalidate)
+pub const REVALIDATE: &str = "no-cache";
+/// Cache-Control for content addressed by hash
+pub const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+
+/// Serialized JSON response with its ETag
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    pub etag: String,
+    pub body: bytes::Bytes,
+    /// State version the body was built from
+    pub state_version: u64,
+}
+
+/// Bounded response cache for hot read endpoints
+#[derive(Debug)]
+pub struct ResponseCache {
+    entries: DashMap<String, CachedResponse>,
+    capacity: usize,
+}
+
+impl Default for ResponseCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CACHE_CAPACITY)
+    }
+}
+
+impl ResponseCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: DashMap::new(),
+            capacity,
+        }
+    }
+
+    /// Cached response for `key`, if it was built from `state_version`
+    pub fn get(&self, key: &str, state_version: u64) -> Option<CachedResponse> {
+        self.entries
+            .get(key)
+            .filter(|entry| entry.state_version == state_version)
+            .map(|entry| entry.clone())
+    }
+
+    /// Cache a response; stale entries are dropped first when the cache is full
+    pub fn insert(&self, key: String, response: CachedResponse) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&key) {
+            let current = response.state_version;
+            self.entries.retain(|_, entry| entry.state_version == current);
+            if self.entries.len() >= self.capacity {
+                let victim = self.entries.iter().next().map(|entry| entry.key().clone());
+                if let Some(victim) = victim {
+                    self.entries.remove(&victim);
+                }
+            }
+        }
+        self.entries.insert(key, response);
+    }
+
+    /// Number of cached responses
+    pub fn entry_count(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+/// Strong ETag from a prefix (e.g. `updated_at`) and a digest of the body
+pub fn etag_for(prefix: &str, body: &[u8]) -> String {
+    let digest = crate::types::sha256(body);
+    format!("\"{}-{}\"", prefix, hex::encode(&digest[..8]))
+}
+
+/// Whether the request's `If-None-Match` matches `etag`
+pub fn not_modified(headers: &HeaderMap, etag: &str) -> bool {
+    let Some(value) = headers.get(header::IF_NONE_MATCH).and_then(|v| v.to_str().ok()) else {
+        return false;
+    };
+    value.split(',').map(str::trim).any(|tag| {
+        // Weak comparison, as for GET requests
+        tag == "*" || tag.trim_start_matches("W/") == etag.trim_start_matches("W/")
+    })
+}
+
+/// What to serve for a request's `Range` header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteRange {
+    /// The whole body: no `Range`, several ranges, a unit other than bytes, an unparsable
+    /// header, or an `If-Range` that no longer matches
+    Full,
+    /// Bytes `start..end` (206)
+    Partial(u64, u64),
+    /// The range starts past the end of the body (416)
+    Unsatisfiable,
+}
+
+/// Single byte range requested for a body of `size` bytes whose current ETag is `etag`
+pub fn byte_range(headers: &HeaderMap, etag: &str, size: u64) -> ByteRange {
+    let Some(value) = headers.get(header::RANGE).and_then(|v| v.to_str().ok()) else {
+        return ByteRange::Full;
+    };
+    // If-Range only applies the range to the representation the client already holds part of
+    if let Some(if_range) = headers.get(header::IF_RANGE)
+        && if_range.to_str().ok() != Some(etag)
+    {
+        return ByteRange::Full;
+    }
+    let Some(spec) = value.trim().strip_prefix("bytes=") else {
+        return ByteRange::Full;
+    };
+    let Some((first, last)) = spec.trim().split_once('-') else {
+        return ByteRange::Full;
+    };
+    if spec.contains(',') {
+        return ByteRange::Full;
+    }
+    let (first, last) = (first.trim(), last.trim());
+    if first.is_empty() {
+        // Suffix range: the last `n` bytes
+        return match last.parse::<u64>() {
+            Ok(0) => ByteRange::Unsatisfiable,
+            Ok(n) if size > 0 => ByteRange::Partial(size.saturating_sub(n), size),
+            Ok(_) => ByteRange::Unsatisfiable,
+            Err(_) => ByteRange::Full,
+        };
+    }
+    let Ok(start) = first.parse::<u64>() else {
+        return ByteRange::Full;
+    };
+    let end = if last.is_empty() {
+        size
+    } else {
+        match last.parse::<u64>() {
+            Ok(last) if last >= start => last.saturating_add(1).min(size),
+            _ => return ByteRange::Full,
+        }
+    };
+    if start >= size {
+        return ByteRange::Unsatisfiable;
+    }
+    ByteRange::Partial(start, end)
+}
+
+/// JSON response with caching headers, or 304 when the client's copy is current
+pub fn json_response(headers: &HeaderMap, etag: &str, cache_control: &'static str, body: bytes::Bytes) -> Response {
+    let etag_value = HeaderValue::from_str(etag).unwrap_or_else(|_| HeaderValue::from_static("\"\""));
+    let cache_headers = [
+        (header::ETAG, etag_value),
+        (header::CACHE_CONTROL, HeaderValue::from_static(cache_control)),
+    ];
+    if not_modified(headers, etag) {
+        return (StatusCode::NOT_MODIFIED, cache_headers).into_response();
+    }
+    (
+        cache_headers,
+        [(header::CONTENT_TYPE, HeaderValue::from_static("application/json"))],
+        body,
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cached(state_version: u64) -> CachedResponse {
+        CachedResponse {
+            etag: "\"x\"".to_string(),
+            body: bytes::Bytes::from_static(b"{}"),
+            state_version,
+        }
+    }
+
+    #[test]
+    fn test_stale_entries_are_ignored_and_evicted() {
+        let cache = ResponseCache::new(2);
+        cache.insert("a".to_string(), cached(1));
+        assert!(cache.get("a", 1).is_some());
+        assert!(cache.get("a", 2).is_none());
+
+        cache.insert("b".to_string(), cached(1));
+        cache.insert("c".to_string(), cached(2));
+        // Both version 1 entries were dropped to make room
+        assert_eq!(cache.entry_count(), 1);
+        assert!(cache.get("c", 2).is_some());
+
+        cache.insert("d".to_string(), cached(2));
+        cache.insert("e".to_string(), cached(2));
+        assert_eq!(cache.entry_count(), 2);
+    }
+
+    #[test]
+    fn test_if_none_match() {
+        let etag = etag_for("10", b"body");
+        let mut headers = HeaderMap::new();
+        assert!(!not_modified(&headers, &etag));
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_str(&format!("\"other\", W/{}", etag)).unwrap());
+        assert!(not_modified(&headers, &etag));
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static("*"));
+        assert!(not_modified(&headers, &etag));
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static("\"other\""));
+        assert!(!not_modified(&headers, &etag));
+    }
+
+    #[test]
+    fn test_byte_range() {
+        let range = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::RANGE, HeaderValue::from_str(value).unwrap());
+            byte_range(&headers, "\"tag\"", 100)
+        };
+        assert_eq!(byte_range(&HeaderMap::new(), "\"tag\"", 100), ByteRange::Full);
+        assert_eq!(range("bytes=0-9"), ByteRange::Partial(0, 10));
+        assert_eq!(range("bytes=90-"), ByteRange::Partial(90, 100));
+        assert_eq!(range("bytes=50-500"), ByteRange::Partial(50, 100));
+        assert_eq!(range("bytes=-10"), ByteRange::Partial(90, 100));
+        assert_eq!(range("bytes=-500"), ByteRange::Partial(0, 100));
+        assert_eq!(range("bytes=100-"), ByteRange::Unsatisfiable);
+        assert_eq!(range("bytes=-0"), ByteRange::Unsatisfiable);
+        // Served whole: several ranges, other units, malformed ranges
+        assert_eq!(range("bytes=0-1,5-6"), ByteRange::Full);
+        assert_eq!(range("items=0-1"), ByteRange::Full);
+        assert_eq!(range("bytes=9-1"), ByteRange::Full);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::RANGE, HeaderValue::from_static("bytes=0-9"));
+        headers.insert(header::IF_RANGE, HeaderValue::from_static("\"old\""));
+        assert_eq!(byte_range(&headers, "\"tag\"", 100), ByteRange::Full);
+        headers.insert(header::IF_RANGE, HeaderValue::from_static("\"tag\""));
+        assert_eq!(byte_range(&headers, "\"tag\"", 100), ByteRange::Partial(0, 10));
+    }
+}
@@ -0,0 +1,62 @@
+//! Indexer sink: every event the node emits, appended to a file as JSON lines
+//!
+//! Runs when the node's role enables the indexer (`role.preset = "indexer"`, or
+//! `role.indexer = true`). Lines are the objects WebSocket clients receive, in the current
+//! schema (`ws_events::EVENT_VERSION`), so an external indexer can tail the file.
+
+use std::path::Path;
+
+use tokio::io::AsyncWriteExt;
+use tokio::sync::broadcast;
+
+use crate::error::{HazeError, Result};
+use crate::ws_events::{WsEvent, EVENT_VERSION};
+
+/// Append events to `path` until the broadcaster closes
+pub async fn run(mut events: broadcast::Receiver<WsEvent>, path: &Path) -> Result<()> {
+    let mut file = tokio::fs::OpenOptions::new().create(true).append(true).open(path).await?;
+    loop {
+        match events.recv().await {
+            Ok(event) => {
+                let mut line = event.to_json(EVENT_VERSION)
+                    .map_err(|e| HazeError::Serialization(e.to_string()))?;
+                line.push('\n');
+                file.write_all(line.as_bytes()).await?;
+            }
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                tracing::warn!("Indexer sink fell behind and missed {} events", missed);
+            }
+            Err(broadcast::error::RecvError::Closed) => {
+                file.flush().await?;
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_sink_appends_one_line_per_event() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.ndjson");
+        let (tx, rx) = broadcast::channel(16);
+        for asset in [1u8, 2] {
+            tx.send(WsEvent::AssetCreated {
+                asset_id: [asset; 32],
+                owner: [9u8; 32],
+                density: crate::types::DensityLevel::Ethereal,
+            }).unwrap();
+        }
+        drop(tx);
+        run(rx, &path).await.unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<serde_json::Value> = content.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["type"], "asset_created");
+        assert_eq!(lines[1]["data"]["asset_id"], hex::encode([2u8; 32]));
+    }
+}
@@ -0,0 +1,235 @@
+//! Startup integrity check of the node database
+//!
+//! A node killed mid-write (or a damaged disk) can leave the trees disagreeing with each
+//! other. Before blocks are replayed, `check_and_repair` verifies the primary records and
+//! repairs the trees that can be rebuilt from them:
+//!
+//! - `blocks` (primary): every block must decode, be stored under its own height, match the
+//!   checksum recorded in `block_checksums` when it was written, and heights must run from 1
+//!   without gaps. Blocks written before checksums existed get one recorded now.
+//! - `indexes`: applied markers pointing at a missing block or a block with another hash are
+//!   removed. Missing markers need no repair: replay writes the marker of every stored block.
+//! - `contract_code`: bytecode whose sha256 does not match its key is removed; replay stores
+//!   the code of every deployed contract again.
+//!
+//! Unreadable dead letters are dropped when they are loaded, and the account index is checked
+//! against the replayed accounts afterwards (see `StateManager::new`).
+//!
+//! Damaged primary data cannot be rebuilt: the node refuses to start with
+//! `HazeError::Corruption`, and the database has to be restored from a backup or resynced.
+
+use std::ops::Bound;
+use serde::Serialize;
+use crate::block_store::decode_header;
+use crate::error::{HazeError, Result};
+use crate::storage::DbTrees;
+use crate::types::{sha256, Hash};
+
+/// What the startup integrity check found and repaired
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct IntegrityReport {
+    pub blocks_checked: u64,
+    /// Blocks written before checksums were recorded, checksummed by this check
+    pub checksums_backfilled: u64,
+    pub index_entries_removed: u64,
+    pub contract_code_removed: u64,
+    pub dead_letters_removed: u64,
+    /// Account index entries rewritten, added or removed to match the replayed accounts
+    pub accounts_repaired: u64,
+    /// Asset index entries rewritten, added or removed to match the replayed assets
+    pub assets_repaired: u64,
+}
+
+impl IntegrityReport {
+    /// Entries changed to make the database consistent (backfilled checksums not included)
+    pub fn repairs(&self) -> u64 {
+        self.index_entries_removed
+            + self.contract_code_removed
+            + self.dead_letters_removed
+            + self.accounts_repaired
+            + self.assets_repaired
+    }
+}
+
+/// Checksum of an encoded block, stored in `block_checksums` under the block's key
+pub fn block_checksum(encoded: &[u8]) -> Hash {
+    sha256(encoded)
+}
+
+fn corruption(detail: String) -> HazeError {
+    HazeError::Corruption(format!(
+        "{}; restore the database from a backup or resync the node into an empty database",
+        detail
+    ))
+}
+
+/// Verify primary records and repair rebuildable trees (see the module docs)
+pub fn check_and_repair(trees: &DbTrees) -> Result<IntegrityReport> {
+    let mut report = IntegrityReport::default();
+    let block_hashes = check_blocks(trees, &mut report)?;
+    repair_indexes(trees, &block_hashes, &mut report)?;
+    repair_contract_code(trees, &mut report)?;
+    Ok(report)
+}
+
+/// Hash of every stored block, indexed by height - 1
+fn check_blocks(trees: &DbTrees, report: &mut IntegrityReport) -> Result<Vec<Hash>> {
+    let mut hashes: Vec<Hash> = Vec::new();
+    for item in trees.blocks.iter() {
+        let (key, value) = item?;
+        let height = <[u8; 8]>::try_from(&key[..])
+            .map(u64::from_be_bytes)
+            .map_err(|_| corruption(format!("Block key {} is not a height", hex::encode(&key))))?;
+        let expected = hashes.len() as u64 + 1;
+        if height != expected {
+            return Err(corruption(format!("Block {} is missing (next stored block is {})", expected, height)));
+        }
+        let header = decode_header(&value)
+            .map_err(|e| corruption(format!("Block {} does not decode: {}", height, e)))?;
+        if header.height != height {
+            return Err(corruption(format!("Block {} is stored under height {}", header.height, height)));
+        }
+        let checksum = block_checksum(&value);
+        match trees.block_checksums.get(&key)? {
+            Some(stored) if stored[..] == checksum[..] => {}
+            Some(_) => return Err(corruption(format!("Block {} does not match its checksum", height))),
+            None => {
+                trees.block_checksums.insert(&key, &checksum)?;
+                report.checksums_backfilled += 1;
+            }
+        }
+        hashes.push(header.hash);
+        report.blocks_checked += 1;
+    }
+    // A checksum past the last block means the block itself was lost
+    let last = (hashes.len() as u64).to_be_bytes();
+    if let Some(item) = trees.block_checksums.range(Bound::Excluded(&last[..]), Bound::Unbounded).next() {
+        let (key, _) = item?;
+        return Err(corruption(format!("Block with key {} is missing but has a checksum", hex::encode(&key))));
+    }
+    Ok(hashes)
+}
+
+fn repair_indexes(trees: &DbTrees, block_hashes: &[Hash], report: &mut IntegrityReport) -> Result<()> {
+    let mut dangling = Vec::new();
+    for item in trees.indexes.iter() {
+        let (key, value) = item?;
+        let points_at_block = <[u8; 8]>::try_from(&value[..])
+            .map(u64::from_be_bytes)
+            .ok()
+            .and_then(|height| height.checked_sub(1))
+            .and_then(|index| block_hashes.get(index as usize))
+            .is_some_and(|hash| hash[..] == key[..]);
+        if !points_at_block {
+            dangling.push(key);
+        }
+    }
+    for key in dangling {
+        trees.indexes.remove(&key)?;
+        report.index_entries_removed += 1;
+    }
+    Ok(())
+}
+
+fn repair_contract_code(trees: &DbTrees, report: &mut IntegrityReport) -> Result<()> {
+    let mut damaged = Vec::new();
+    for item in trees.contract_code.iter() {
+        let (key, value) = item?;
+        if sha256(&value)[..] != key[..] {
+            damaged.push(key);
+        }
+    }
+    for key in damaged {
+        tracing::warn!("Removing damaged bytecode {}; replay stores it again", hex::encode(&key));
+        trees.contract_code.remove(&key)?;
+        report.contract_code_removed += 1;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{SledStorage, Storage, WriteBatch, BLOCK_CHECKSUMS_TREE, BLOCKS_TREE};
+    use crate::types::{Block, BlockHeader, WireFormat, PROTOCOL_VERSION, STATE_VERSION};
+
+    fn block(height: u64) -> Block {
+        Block {
+            header: BlockHeader {
+                hash: [height as u8; 32],
+                parent_hash: [0u8; 32],
+                height,
+                timestamp: 0,
+                validator: [0u8; 32],
+                merkle_root: [0u8; 32],
+                state_root: [0u8; 32],
+                wave_number: 0,
+                committee_id: 0,
+                protocol_version: PROTOCOL_VERSION,
+                state_version: STATE_VERSION,
+                signals: 0,
+            },
+            transactions: vec![],
+            dag_references: vec![],
+        }
+    }
+
+    fn store(storage: &dyn Storage, height: u64, with_checksum: bool) {
+        let encoded = block(height).to_wire();
+        let mut blocks = WriteBatch::default();
+        let mut checksums = WriteBatch::default();
+        blocks.insert(height.to_be_bytes(), encoded.clone());
+        if with_checksum {
+            checksums.insert(height.to_be_bytes(), block_checksum(&encoded).to_vec());
+        }
+        storage.apply_atomic(&[(BLOCKS_TREE, &blocks), (BLOCK_CHECKSUMS_TREE, &checksums)]).unwrap();
+    }
+
+    #[test]
+    fn test_rebuildable_trees_are_repaired() {
+        let storage = SledStorage::temporary().unwrap();
+        store(&storage, 1, true);
+        store(&storage, 2, false);
+        let trees = DbTrees::open(&storage).unwrap();
+        trees.indexes.insert(&[1u8; 32], &1u64.to_be_bytes()).unwrap();
+        // Marker of a block that was never stored, and of a hash that is not the stored block's
+        trees.indexes.insert(&[9u8; 32], &9u64.to_be_bytes()).unwrap();
+        trees.indexes.insert(&[8u8; 32], &2u64.to_be_bytes()).unwrap();
+        trees.contract_code.insert(&sha256(b"code"), b"code").unwrap();
+        trees.contract_code.insert(&sha256(b"other"), b"damaged").unwrap();
+
+        let report = check_and_repair(&trees).unwrap();
+        assert_eq!(report.blocks_checked, 2);
+        assert_eq!(report.checksums_backfilled, 1);
+        assert_eq!((report.index_entries_removed, report.contract_code_removed), (2, 1));
+        assert!(trees.indexes.contains_key(&[1u8; 32]).unwrap());
+        assert_eq!(trees.contract_code.len(), 1);
+
+        // Nothing left to repair
+        assert_eq!(check_and_repair(&trees).unwrap().repairs(), 0);
+    }
+
+    #[test]
+    fn test_damaged_blocks_are_unrecoverable() {
+        let storage = SledStorage::temporary().unwrap();
+        store(&storage, 1, true);
+        store(&storage, 2, true);
+        let trees = DbTrees::open(&storage).unwrap();
+        trees.block_checksums.insert(&2u64.to_be_bytes(), &[0u8; 32]).unwrap();
+        let err = check_and_repair(&trees).unwrap_err();
+        assert_eq!(err.code(), 1009);
+        assert!(err.to_string().contains("Block 2 does not match its checksum"));
+
+        let storage = SledStorage::temporary().unwrap();
+        store(&storage, 1, true);
+        store(&storage, 3, true);
+        let err = check_and_repair(&DbTrees::open(&storage).unwrap()).unwrap_err();
+        assert!(err.to_string().contains("Block 2 is missing"));
+
+        let storage = SledStorage::temporary().unwrap();
+        store(&storage, 1, true);
+        let trees = DbTrees::open(&storage).unwrap();
+        trees.block_checksums.insert(&2u64.to_be_bytes(), &[0u8; 32]).unwrap();
+        assert!(check_and_repair(&trees).is_err());
+    }
+}
@@ -0,0 +1,303 @@
+//! Write-ahead journal of block application
+//!
+//! Applying a block writes to several trees (the block, its checksum and applied marker, the
+//! account index, new contract bytecode). Before any of them is touched, `BlockJournal::apply`
+//! records every intended write, with the value it replaces, as one entry in the `journal`
+//! tree. The writes, the removal of that entry and the new last cleanly applied height then go
+//! to the database in one atomic batch. Both engines log writes in order, so whenever the
+//! writes are on disk, the entry written before them is too.
+//!
+//! An entry still present at startup belongs to a block whose application was interrupted.
+//! `recover` rolls it forward (re-applies its writes) when the block continues the stored
+//! chain or is already stored, and rolls it back (restores the previous values) otherwise.
+//! Blocks are replayed only after recovery, so in-memory state is rebuilt from the result.
+//!
+//! The last cleanly applied height is kept next to the entries and served by
+//! `GET /api/v1/stats/storage`; after a crash, the startup log shows it before recovery runs.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use crate::block_store::decode_header;
+use crate::error::{HazeError, Result};
+use crate::storage::{Storage, StorageTree, WriteBatch, BLOCKS_TREE, JOURNAL_TREE};
+use crate::types::Hash;
+
+/// Key of the last cleanly applied height in the `journal` tree (entries are keyed by height)
+const LAST_CLEAN_HEIGHT_KEY: &[u8] = b"last_clean_height";
+
+/// One write of a journaled block, with the value it replaces
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct JournalWrite {
+    tree: String,
+    key: Vec<u8>,
+    /// `None` removes the key
+    value: Option<Vec<u8>>,
+    previous: Option<Vec<u8>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct JournalEntry {
+    height: u64,
+    block_hash: Hash,
+    writes: Vec<JournalWrite>,
+}
+
+/// What startup recovery did with an interrupted block
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecoveryAction {
+    /// Writes re-applied: the block is part of the stored chain
+    RolledForward,
+    /// Previous values restored: the block did not fit the stored chain
+    RolledBack,
+    /// The entry did not decode and was dropped
+    Discarded,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct JournalRecovery {
+    pub height: u64,
+    pub action: RecoveryAction,
+}
+
+/// Journal of the node database (see the module docs)
+#[derive(Debug)]
+pub struct BlockJournal {
+    storage: Arc<dyn Storage>,
+    tree: Arc<dyn StorageTree>,
+    last_clean_height: AtomicU64,
+    recovered: RwLock<Vec<JournalRecovery>>,
+}
+
+fn height_of(value: &[u8]) -> Option<u64> {
+    <[u8; 8]>::try_from(value).ok().map(u64::from_be_bytes)
+}
+
+impl BlockJournal {
+    pub fn open(storage: Arc<dyn Storage>) -> Result<Self> {
+        let tree = storage.open_tree(JOURNAL_TREE)?;
+        let last_clean_height = tree
+            .get(LAST_CLEAN_HEIGHT_KEY)?
+            .and_then(|value| height_of(&value))
+            .unwrap_or(0);
+        Ok(Self {
+            storage,
+            tree,
+            last_clean_height: AtomicU64::new(last_clean_height),
+            recovered: RwLock::new(Vec::new()),
+        })
+    }
+
+    /// Height of the last block whose writes all reached the database
+    pub fn last_clean_height(&self) -> u64 {
+        self.last_clean_height.load(Ordering::SeqCst)
+    }
+
+    /// Interrupted blocks handled when the node started
+    pub fn recovered(&self) -> Vec<JournalRecovery> {
+        self.recovered.read().clone()
+    }
+
+    /// Journal the writes of the block at `height`, then apply them atomically
+    pub fn apply(&self, height: u64, block_hash: Hash, batches: &[(&str, &WriteBatch)]) -> Result<()> {
+        let mut writes = Vec::new();
+        for (tree_name, batch) in batches {
+            let tree = self.storage.open_tree(tree_name)?;
+            for (key, value) in batch.ops() {
+                writes.push(JournalWrite {
+                    tree: tree_name.to_string(),
+                    key: key.to_vec(),
+                    value: value.map(<[u8]>::to_vec),
+                    previous: tree.get(key)?.map(|previous| previous.to_vec()),
+                });
+            }
+        }
+        let entry = JournalEntry { height, block_hash, writes };
+        let encoded = bincode::serialize(&entry).map_err(|e| HazeError::Serialization(e.to_string()))?;
+        self.tree.insert(&height.to_be_bytes(), &encoded)?;
+
+        let mut journal = WriteBatch::default();
+        journal.remove(height.to_be_bytes());
+        journal.insert(LAST_CLEAN_HEIGHT_KEY, height.to_be_bytes().to_vec());
+        let mut all: Vec<(&str, &WriteBatch)> = batches.to_vec();
+        all.push((JOURNAL_TREE, &journal));
+        self.storage.apply_atomic(&all)?;
+        self.last_clean_height.store(height, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Roll forward or back every entry left by an interrupted block application
+    pub fn recover(&self) -> Result<Vec<JournalRecovery>> {
+        let mut pending = Vec::new();
+        for item in self.tree.iter() {
+            let (key, value) = item?;
+            if let Some(height) = height_of(&key) {
+                pending.push((height, value));
+            }
+        }
+        if !pending.is_empty() {
+            tracing::warn!(
+                "Found {} interrupted block application(s); last cleanly applied height {}",
+                pending.len(),
+                self.last_clean_height()
+            );
+        }
+
+        let blocks = self.storage.open_tree(BLOCKS_TREE)?;
+        let mut recovered = Vec::new();
+        for (height, value) in pending {
+            let mut journal = WriteBatch::default();
+            journal.remove(height.to_be_bytes());
+            let Ok(entry) = bincode::deserialize::<JournalEntry>(&value) else {
+                tracing::warn!("Discarding unreadable journal entry for block {}", height);
+                self.storage.apply_atomic(&[(JOURNAL_TREE, &journal)])?;
+                recovered.push(JournalRecovery { height, action: RecoveryAction::Discarded });
+                continue;
+            };
+
+            let tip = blocks.last()?.and_then(|(key, _)| height_of(&key)).unwrap_or(0);
+            let stored_hash = blocks
+                .get(&height.to_be_bytes())?
+                .map(|bytes| decode_header(&bytes).map(|header| header.hash).ok());
+            let continues_chain = match stored_hash {
+                Some(hash) => hash == Some(entry.block_hash),
+                None => height == tip + 1,
+            };
+
+            let mut batches: Vec<(String, WriteBatch)> = Vec::new();
+            for write in entry.writes {
+                let target = if continues_chain { write.value } else { write.previous };
+                let index = match batches.iter().position(|(tree, _)| *tree == write.tree) {
+                    Some(index) => index,
+                    None => {
+                        batches.push((write.tree, WriteBatch::default()));
+                        batches.len() - 1
+                    }
+                };
+                match target {
+                    Some(value) => batches[index].1.insert(&write.key, value),
+                    None => batches[index].1.remove(&write.key),
+                }
+            }
+            let action = if continues_chain {
+                journal.insert(LAST_CLEAN_HEIGHT_KEY, height.to_be_bytes().to_vec());
+                RecoveryAction::RolledForward
+            } else {
+                RecoveryAction::RolledBack
+            };
+            let mut all: Vec<(&str, &WriteBatch)> = batches.iter().map(|(tree, batch)| (tree.as_str(), batch)).collect();
+            all.push((JOURNAL_TREE, &journal));
+            self.storage.apply_atomic(&all)?;
+            if continues_chain {
+                self.last_clean_height.store(height, Ordering::SeqCst);
+            }
+            tracing::warn!("Block {} from the journal: {:?}", height, action);
+            recovered.push(JournalRecovery { height, action });
+        }
+
+        // Databases written before the journal existed: every stored block was applied cleanly
+        if self.tree.get(LAST_CLEAN_HEIGHT_KEY)?.is_none()
+            && let Some(tip) = blocks.last()?.and_then(|(key, _)| height_of(&key))
+        {
+            self.tree.insert(LAST_CLEAN_HEIGHT_KEY, &tip.to_be_bytes())?;
+            self.last_clean_height.store(tip, Ordering::SeqCst);
+        }
+        *self.recovered.write() = recovered.clone();
+        Ok(recovered)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{SledStorage, ACCOUNTS_TREE};
+    use crate::types::{Block, BlockHeader, WireFormat, PROTOCOL_VERSION, STATE_VERSION};
+
+    fn encoded_block(height: u64, hash: Hash) -> Vec<u8> {
+        Block {
+            header: BlockHeader {
+                hash,
+                parent_hash: [0u8; 32],
+                height,
+                timestamp: 0,
+                validator: [0u8; 32],
+                merkle_root: [0u8; 32],
+                state_root: [0u8; 32],
+                wave_number: 0,
+                committee_id: 0,
+                protocol_version: PROTOCOL_VERSION,
+                state_version: STATE_VERSION,
+                signals: 0,
+            },
+            transactions: vec![],
+            dag_references: vec![],
+        }
+        .to_wire()
+    }
+
+    /// Leave a journal entry behind as if the node died before applying it
+    fn interrupt(journal: &BlockJournal, height: u64, hash: Hash, batches: &[(&str, &WriteBatch)]) {
+        let mut writes = Vec::new();
+        for (tree, batch) in batches {
+            for (key, value) in batch.ops() {
+                let previous = journal.storage.open_tree(tree).unwrap().get(key).unwrap().map(|v| v.to_vec());
+                writes.push(JournalWrite {
+                    tree: tree.to_string(),
+                    key: key.to_vec(),
+                    value: value.map(<[u8]>::to_vec),
+                    previous,
+                });
+            }
+        }
+        let entry = JournalEntry { height, block_hash: hash, writes };
+        journal.tree.insert(&height.to_be_bytes(), &bincode::serialize(&entry).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_apply_records_last_clean_height() {
+        let storage: Arc<dyn Storage> = Arc::new(SledStorage::temporary().unwrap());
+        let journal = BlockJournal::open(storage.clone()).unwrap();
+        let mut blocks = WriteBatch::default();
+        blocks.insert(1u64.to_be_bytes(), encoded_block(1, [1u8; 32]));
+        journal.apply(1, [1u8; 32], &[(BLOCKS_TREE, &blocks)]).unwrap();
+        assert_eq!(journal.last_clean_height(), 1);
+        assert_eq!(journal.tree.len(), 1); // only the clean height, no pending entry
+        assert_eq!(BlockJournal::open(storage).unwrap().last_clean_height(), 1);
+    }
+
+    #[test]
+    fn test_recovery_rolls_forward_and_back() {
+        let storage: Arc<dyn Storage> = Arc::new(SledStorage::temporary().unwrap());
+        let journal = BlockJournal::open(storage.clone()).unwrap();
+        let accounts = storage.open_tree(ACCOUNTS_TREE).unwrap();
+        accounts.insert(b"alice", b"10").unwrap();
+
+        // Block 1 continues the (empty) chain: its writes are re-applied
+        let mut blocks = WriteBatch::default();
+        blocks.insert(1u64.to_be_bytes(), encoded_block(1, [1u8; 32]));
+        let mut index = WriteBatch::default();
+        index.insert(b"alice", b"7".to_vec());
+        interrupt(&journal, 1, [1u8; 32], &[(BLOCKS_TREE, &blocks), (ACCOUNTS_TREE, &index)]);
+        // Block 5 leaves a gap: whatever it wrote is undone
+        let mut index = WriteBatch::default();
+        index.insert(b"bob", b"3".to_vec());
+        interrupt(&journal, 5, [5u8; 32], &[(ACCOUNTS_TREE, &index)]);
+        accounts.insert(b"bob", b"3").unwrap();
+        journal.tree.insert(&9u64.to_be_bytes(), b"torn").unwrap();
+
+        let journal = BlockJournal::open(storage.clone()).unwrap();
+        let actions: Vec<_> = journal.recover().unwrap().into_iter().map(|r| (r.height, r.action)).collect();
+        assert_eq!(actions, vec![
+            (1, RecoveryAction::RolledForward),
+            (5, RecoveryAction::RolledBack),
+            (9, RecoveryAction::Discarded),
+        ]);
+        assert_eq!(accounts.get(b"alice").unwrap().unwrap(), b"7".as_slice());
+        assert!(accounts.get(b"bob").unwrap().is_none());
+        assert!(storage.open_tree(BLOCKS_TREE).unwrap().contains_key(&1u64.to_be_bytes()).unwrap());
+        assert_eq!(journal.last_clean_height(), 1);
+        assert!(journal.recover().unwrap().is_empty());
+    }
+}
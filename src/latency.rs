@@ -0,0 +1,178 @@
+//! Latency histograms
+//!
+//! Block application, transaction application (per transaction type), mempool admission
+//! (per transaction type) and API requests (per method and route) are timed into Prometheus
+//! histograms with fixed buckets, one time series per operation. `StateManager` owns them;
+//! `GET /api/v1/metrics/prometheus` renders them in the text exposition format:
+//!
+//! | Family | `operation` |
+//! |--------|-------------|
+//! | `haze_block_apply_seconds` | `apply` (new blocks) or `replay` (stored blocks) |
+//! | `haze_tx_apply_seconds` | transaction type, e.g. `Transfer` |
+//! | `haze_mempool_admission_seconds` | transaction type |
+//! | `haze_api_request_seconds` | method and route, e.g. `GET /api/v1/assets/:asset_id` |
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use dashmap::DashMap;
+
+/// Upper bounds of the buckets in seconds (the `+Inf` bucket is implicit)
+pub const BUCKETS: [f64; 14] = [
+    0.0001, 0.00025, 0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5,
+];
+
+/// Observations of one operation
+#[derive(Debug, Default)]
+pub struct Histogram {
+    /// Observations per bucket (not cumulative); the last slot is `+Inf`
+    buckets: [AtomicU64; BUCKETS.len() + 1],
+    sum_nanos: AtomicU64,
+}
+
+impl Histogram {
+    pub fn observe(&self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        let bucket = BUCKETS.iter().position(|bound| seconds <= *bound).unwrap_or(BUCKETS.len());
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.sum_nanos.fetch_add(duration.as_nanos().min(u64::MAX as u128) as u64, Ordering::Relaxed);
+    }
+
+    /// Observations at or below each bound of `BUCKETS`, then the total
+    pub fn cumulative(&self) -> Vec<u64> {
+        self.buckets
+            .iter()
+            .scan(0u64, |total, bucket| {
+                *total += bucket.load(Ordering::Relaxed);
+                Some(*total)
+            })
+            .collect()
+    }
+
+    pub fn sum_seconds(&self) -> f64 {
+        self.sum_nanos.load(Ordering::Relaxed) as f64 / 1e9
+    }
+}
+
+/// Histograms of one metric, by operation
+#[derive(Debug)]
+pub struct HistogramFamily {
+    name: &'static str,
+    help: &'static str,
+    operations: DashMap<String, Histogram>,
+}
+
+impl HistogramFamily {
+    pub fn new(name: &'static str, help: &'static str) -> Self {
+        Self { name, help, operations: DashMap::new() }
+    }
+
+    pub fn observe(&self, operation: &str, duration: Duration) {
+        if let Some(histogram) = self.operations.get(operation) {
+            histogram.observe(duration);
+            return;
+        }
+        self.operations.entry(operation.to_string()).or_default().observe(duration);
+    }
+
+    /// Append the family in Prometheus text format, operations in name order
+    pub fn render(&self, out: &mut String) {
+        out.push_str(&format!("# HELP {} {}\n", self.name, self.help));
+        out.push_str(&format!("# TYPE {} histogram\n", self.name));
+        let mut operations: Vec<String> = self.operations.iter().map(|entry| entry.key().clone()).collect();
+        operations.sort();
+        for operation in operations {
+            let Some(histogram) = self.operations.get(&operation) else {
+                continue;
+            };
+            let label = operation.replace('\\', "\\\\").replace('"', "\\\"");
+            let cumulative = histogram.cumulative();
+            for (bound, count) in BUCKETS.iter().zip(&cumulative) {
+                out.push_str(&format!("{}_bucket{{operation=\"{}\",le=\"{}\"}} {}\n", self.name, label, bound, count));
+            }
+            let total = cumulative.last().copied().unwrap_or(0);
+            out.push_str(&format!("{}_bucket{{operation=\"{}\",le=\"+Inf\"}} {}\n", self.name, label, total));
+            out.push_str(&format!("{}_sum{{operation=\"{}\"}} {}\n", self.name, label, histogram.sum_seconds()));
+            out.push_str(&format!("{}_count{{operation=\"{}\"}} {}\n", self.name, label, total));
+        }
+    }
+}
+
+/// Every latency histogram of the node
+#[derive(Debug)]
+pub struct LatencyMetrics {
+    pub block_apply: HistogramFamily,
+    pub tx_apply: HistogramFamily,
+    pub mempool_admission: HistogramFamily,
+    pub api_requests: HistogramFamily,
+}
+
+impl Default for LatencyMetrics {
+    fn default() -> Self {
+        Self {
+            block_apply: HistogramFamily::new("haze_block_apply_seconds", "Time to apply a block to state"),
+            tx_apply: HistogramFamily::new(
+                "haze_tx_apply_seconds",
+                "Time to apply one transaction of a block, by transaction type",
+            ),
+            mempool_admission: HistogramFamily::new(
+                "haze_mempool_admission_seconds",
+                "Time to validate and admit a transaction to the pool, by transaction type",
+            ),
+            api_requests: HistogramFamily::new(
+                "haze_api_request_seconds",
+                "Time to answer an API request, by method and route",
+            ),
+        }
+    }
+}
+
+impl LatencyMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append every family in Prometheus text format
+    pub fn render(&self, out: &mut String) {
+        for family in [&self.block_apply, &self.tx_apply, &self.mempool_admission, &self.api_requests] {
+            family.render(out);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_buckets_are_cumulative() {
+        let histogram = Histogram::default();
+        histogram.observe(Duration::from_micros(50));
+        histogram.observe(Duration::from_millis(3));
+        histogram.observe(Duration::from_secs(10));
+        let cumulative = histogram.cumulative();
+        assert_eq!(cumulative[0], 1);
+        assert_eq!(cumulative[BUCKETS.iter().position(|bound| *bound == 0.005).unwrap()], 2);
+        assert_eq!(cumulative[BUCKETS.len() - 1], 2);
+        assert_eq!(cumulative[BUCKETS.len()], 3);
+        assert!((histogram.sum_seconds() - 10.00305).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_family_renders_prometheus_histograms() {
+        let family = HistogramFamily::new("haze_test_seconds", "Test latency");
+        family.observe("Transfer", Duration::from_millis(2));
+        family.observe("Transfer", Duration::from_millis(20));
+        family.observe("GET /a", Duration::from_millis(1));
+        let mut out = String::new();
+        family.render(&mut out);
+
+        assert!(out.starts_with("# HELP haze_test_seconds Test latency\n# TYPE haze_test_seconds histogram\n"));
+        assert!(out.contains("haze_test_seconds_bucket{operation=\"Transfer\",le=\"0.0025\"} 1\n"));
+        assert!(out.contains("haze_test_seconds_bucket{operation=\"Transfer\",le=\"0.025\"} 2\n"));
+        assert!(out.contains("haze_test_seconds_bucket{operation=\"Transfer\",le=\"+Inf\"} 2\n"));
+        assert!(out.contains("haze_test_seconds_count{operation=\"Transfer\"} 2\n"));
+        assert!(out.contains("haze_test_seconds_sum{operation=\"GET /a\"} 0.001\n"));
+        // Operations in name order
+        assert!(out.find("operation=\"GET /a\"").unwrap() < out.find("operation=\"Transfer\"").unwrap());
+    }
+}
@@ -0,0 +1,164 @@
+//! Seasonal leaderboards
+//!
+//! A leaderboard ranks the players of one collection (game_id) for one season by their best
+//! score. It is opened with `CreateLeaderboard` and fed with `SubmitScore`, signed by the
+//! board's creator or one of the game contracts it lists as submitters.
+//!
+//! Storage is bounded: only the best `capacity` entries are kept, so a score that does not
+//! make the cut is dropped and a player pushed off the bottom loses their entry (a later,
+//! better score can bring them back). Entries rank by score, highest first; ties go to the
+//! score submitted first, then to the lower address, so every node orders them the same way.
+//!
+//! The season ends with block `end_height`. Later submissions are rejected, so the entries
+//! left at that block are the board's final standings.
+
+use serde::{Deserialize, Serialize};
+use crate::types::{Address, Hash};
+
+/// One player's best score on a leaderboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+    pub player: Address,
+    pub score: u64,
+    /// Height of the block that recorded the score (earlier wins ties)
+    pub submitted_at_height: u64,
+}
+
+impl LeaderboardEntry {
+    /// Sort key: higher scores first, then earlier submissions, then lower addresses
+    fn rank_key(&self) -> (std::cmp::Reverse<u64>, u64, Address) {
+        (std::cmp::Reverse(self.score), self.submitted_at_height, self.player)
+    }
+}
+
+/// Status of a leaderboard at a given height
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LeaderboardStatus {
+    /// Accepting scores
+    Active,
+    /// Past `end_height`; the entries are the final standings
+    Final,
+}
+
+/// What a score submission did to the board
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScoreOutcome {
+    /// The player is now at `rank` (1-based)
+    Ranked { rank: usize },
+    /// The player already has this score or a better one
+    NotImproved,
+    /// The board is full and the score does not beat the last entry
+    BelowCutoff,
+}
+
+/// Top-N standings of one collection's season
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Leaderboard {
+    pub id: Hash,
+    pub creator: Address,
+    pub game_id: String,
+    pub season: String,
+    /// Most entries kept
+    pub capacity: u32,
+    /// Last height scores are accepted at
+    pub end_height: u64,
+    /// Game contracts allowed to submit scores besides the creator
+    pub submitters: Vec<Address>,
+    pub created_at_height: u64,
+    /// Ranked entries, best first (at most `capacity`)
+    pub entries: Vec<LeaderboardEntry>,
+    /// Scores submitted so far, including ones that did not rank
+    pub submissions: u64,
+}
+
+impl Leaderboard {
+    /// Whether `address` may submit scores
+    pub fn can_submit(&self, address: &Address) -> bool {
+        self.creator == *address || self.submitters.contains(address)
+    }
+
+    pub fn status(&self, height: u64) -> LeaderboardStatus {
+        if height > self.end_height {
+            LeaderboardStatus::Final
+        } else {
+            LeaderboardStatus::Active
+        }
+    }
+
+    /// 1-based rank of `player`, if they are on the board
+    pub fn rank_of(&self, player: &Address) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.player == *player).map(|index| index + 1)
+    }
+
+    /// Record `score` for `player`, keeping their best score and at most `capacity` entries
+    pub fn submit(&mut self, player: Address, score: u64, height: u64) -> ScoreOutcome {
+        self.submissions += 1;
+        if let Some(index) = self.entries.iter().position(|entry| entry.player == player) {
+            if self.entries[index].score >= score {
+                return ScoreOutcome::NotImproved;
+            }
+            self.entries.remove(index);
+        }
+        let entry = LeaderboardEntry { player, score, submitted_at_height: height };
+        let index = self.entries.partition_point(|ranked| ranked.rank_key() < entry.rank_key());
+        if index >= self.capacity as usize {
+            return ScoreOutcome::BelowCutoff;
+        }
+        self.entries.insert(index, entry);
+        self.entries.truncate(self.capacity as usize);
+        ScoreOutcome::Ranked { rank: index + 1 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board(capacity: u32) -> Leaderboard {
+        Leaderboard {
+            id: [0u8; 32],
+            creator: [1u8; 32],
+            game_id: "my_game".to_string(),
+            season: "s1".to_string(),
+            capacity,
+            end_height: 100,
+            submitters: vec![[2u8; 32]],
+            created_at_height: 1,
+            entries: Vec::new(),
+            submissions: 0,
+        }
+    }
+
+    #[test]
+    fn test_submit_keeps_best_scores_in_rank_order() {
+        let mut board = board(3);
+        let (a, b, c, d) = ([10u8; 32], [11u8; 32], [12u8; 32], [13u8; 32]);
+        assert_eq!(board.submit(a, 50, 1), ScoreOutcome::Ranked { rank: 1 });
+        assert_eq!(board.submit(b, 70, 2), ScoreOutcome::Ranked { rank: 1 });
+        // Ties go to the earlier score
+        assert_eq!(board.submit(c, 50, 3), ScoreOutcome::Ranked { rank: 3 });
+        assert_eq!(board.submit(a, 40, 4), ScoreOutcome::NotImproved);
+
+        // A full board drops scores below the cutoff and prunes the last entry otherwise
+        assert_eq!(board.submit(d, 50, 5), ScoreOutcome::BelowCutoff);
+        assert_eq!(board.submit(d, 60, 5), ScoreOutcome::Ranked { rank: 2 });
+        assert_eq!(board.entries.iter().map(|e| e.player).collect::<Vec<_>>(), vec![b, d, a]);
+        assert_eq!(board.rank_of(&c), None);
+
+        // A better score moves a player up without growing the board
+        assert_eq!(board.submit(a, 80, 6), ScoreOutcome::Ranked { rank: 1 });
+        assert_eq!(board.entries.len(), 3);
+        assert_eq!((board.rank_of(&a), board.rank_of(&b)), (Some(1), Some(2)));
+        assert_eq!(board.submissions, 7);
+    }
+
+    #[test]
+    fn test_submitters_and_status() {
+        let board = board(10);
+        assert!(board.can_submit(&[1u8; 32]) && board.can_submit(&[2u8; 32]));
+        assert!(!board.can_submit(&[3u8; 32]));
+        assert_eq!(board.status(100), LeaderboardStatus::Active);
+        assert_eq!(board.status(101), LeaderboardStatus::Final);
+    }
+}
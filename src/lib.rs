@@ -16,6 +16,37 @@ pub mod tokenomics;
 pub mod economy;
 pub mod api;
 pub mod ws_events;
+pub mod time_sync;
+pub mod byzantine;
+pub mod http_cache;
+pub mod watchlist;
+pub mod custody;
+pub mod tenant;
+pub mod metering;
+pub mod asset_shards;
+pub mod block_store;
+pub mod storage;
+pub mod integrity;
+pub mod journal;
+pub mod blob_s3;
+pub mod simulation;
+pub mod leaderboard;
+pub mod season;
+pub mod breeding;
+pub mod upgrade;
+pub mod telemetry;
+pub mod version;
+pub mod features;
+pub mod migrations;
+pub mod dev;
+pub mod replay;
+pub mod sign_in;
+pub mod messaging;
+pub mod fee_estimate;
+pub mod maintenance;
+pub mod indexer;
+pub mod peer_health;
+pub mod latency;
 
 // Re-export commonly used types
 pub use types::{Block, Transaction, Address, Hash, AssetAction, AssetData, DensityLevel, AssetPermission, PermissionLevel, sha256, hash_to_hex, hex_to_hash};
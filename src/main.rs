@@ -16,9 +16,40 @@ mod tokenomics;
 mod economy;
 mod api;
 mod ws_events;
+mod time_sync;
+mod byzantine;
+mod http_cache;
+mod watchlist;
+mod custody;
+mod tenant;
+mod metering;
+mod asset_shards;
+mod block_store;
+mod storage;
+mod integrity;
+mod journal;
+mod blob_s3;
+mod simulation;
+mod leaderboard;
+mod season;
+mod breeding;
+mod upgrade;
+mod telemetry;
+mod version;
+mod features;
+mod migrations;
+mod dev;
+mod replay;
+mod sign_in;
+mod messaging;
+mod fee_estimate;
+mod maintenance;
+mod indexer;
+mod peer_health;
+mod latency;
 
 use anyhow::Result;
-use tracing::{info, error};
+use tracing::{debug, info, error};
 use crate::types::{address_to_hex, hash_to_hex};
 
 use std::sync::Arc;
@@ -33,6 +64,29 @@ use crate::crypto::KeyPair;
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    // Offline tools run instead of the node
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("simulate-economy") {
+        simulation::run_cli(&args[2..])?;
+        return Ok(());
+    }
+    if args.get(1).map(String::as_str) == Some("migrate-storage") {
+        storage::run_migrate_cli(&args[2..])?;
+        return Ok(());
+    }
+    if args.get(1).map(String::as_str) == Some("migrate-schema") {
+        migrations::run_cli(&args[2..])?;
+        return Ok(());
+    }
+    if args.get(1).map(String::as_str) == Some("replay") {
+        replay::run_cli(&args[2..])?;
+        return Ok(());
+    }
+    if args.get(1).map(String::as_str) == Some("config") {
+        config::run_cli(&args[2..])?;
+        return Ok(());
+    }
+
     // Initialize logging
     tracing_subscriber::fmt()
         .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
@@ -42,9 +96,24 @@ async fn main() -> Result<()> {
     info!("  HAZE Blockchain - High-performance Asset Zone Engine");
     info!("═══════════════════════════════════════════════════════════");
 
-    // Load configuration
-    let config = Config::load()?;
-    info!("✓ Configuration loaded from: haze_config.json");
+    // Load configuration (`haze --dev` builds its own and leaves the config file alone)
+    let dev_mode = args.get(1).map(String::as_str) == Some("--dev");
+    let config = if dev_mode {
+        let config = dev::config_from_args(&args[2..])?;
+        info!("✓ Dev node configuration (in-memory database)");
+        config
+    } else {
+        let config = match Config::load() {
+            Ok(config) => config,
+            Err(e) => {
+                // Validation lists every problem on its own line
+                error!("{}", e);
+                std::process::exit(1);
+            }
+        };
+        info!("✓ Configuration loaded from: {}", config::CONFIG_FILE);
+        config
+    };
     info!("  Node ID: {}", config.node_id);
     info!("  Database: {:?}", config.storage.db_path);
     info!("  Network listen: {}", config.network.listen_addr);
@@ -88,7 +157,113 @@ async fn main() -> Result<()> {
     // Set WebSocket broadcaster in state manager
     state_manager.set_ws_tx(ws_tx.clone());
     info!("✓ WebSocket event broadcaster initialized");
+
+    // Indexer sink: every event appended to a file (indexer role)
+    let (role, subsystems) = config.subsystems();
+    if subsystems.indexer {
+        let events = ws_tx.subscribe();
+        let path = config.role.indexer_sink_path.clone();
+        info!("✓ Indexer sink writing events to {:?}", path);
+        tokio::spawn(async move {
+            if let Err(e) = crate::indexer::run(events, &path).await {
+                error!("Indexer sink stopped: {}", e);
+            }
+        });
+    }
     
+    // Custodial player accounts (keystore unlocked with a passphrase from the environment)
+    let custody = if config.custody.enabled {
+        let passphrase = std::env::var(&config.custody.passphrase_env).map_err(|_| {
+            crate::error::HazeError::Config(format!(
+                "custody.enabled requires the keystore passphrase in ${}", config.custody.passphrase_env
+            ))
+        })?;
+        let store = crate::custody::CustodyStore::open(&config.custody, &passphrase, &state_manager)?;
+        info!("✓ Custodial accounts enabled ({} API keys)", config.custody.api_key_hashes.len());
+        Some(Arc::new(store))
+    } else {
+        None
+    };
+
+    // Encrypted direct messages: mailbox storage and relaying between nodes
+    let mailbox = if config.messaging.enabled {
+        let mailbox = Arc::new(crate::messaging::Mailbox::open(config.messaging.clone(), &state_manager)?);
+        network.set_mailbox(mailbox.clone());
+        info!("✓ Direct messaging enabled");
+        Some(mailbox)
+    } else {
+        None
+    };
+
+    // Multi-tenant mode (tenants are managed through the admin endpoints)
+    let tenants = if config.tenants.enabled {
+        let store = crate::tenant::TenantStore::open(&config.tenants, &state_manager)?;
+        info!("✓ Multi-tenant mode enabled ({} tenants)", store.list().len());
+        Some(Arc::new(store))
+    } else {
+        None
+    };
+
+    // Database maintenance: scheduled flushes and per-tree size sampling for metrics
+    if config.storage.maintenance_interval_secs > 0 {
+        let state_for_maintenance = state_manager.clone();
+        let maintenance_interval = Duration::from_secs(config.storage.maintenance_interval_secs);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(maintenance_interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            loop {
+                interval.tick().await;
+                let state = state_for_maintenance.clone();
+                match tokio::task::spawn_blocking(move || state.run_storage_maintenance()).await {
+                    Ok(Ok(stats)) => debug!(
+                        "Database maintenance: {} bytes on disk, flush took {:.3}s",
+                        stats.size_on_disk, stats.last_flush_seconds
+                    ),
+                    Ok(Err(e)) => error!("Database maintenance failed: {}", e),
+                    Err(e) => error!("Database maintenance task panicked: {}", e),
+                }
+            }
+        });
+    }
+
+    // Usage metering for billing, flushed to daily rollups in the background
+    let metering = if config.metering.enabled {
+        let meter = Arc::new(crate::metering::UsageMeter::open(&config.metering, &state_manager)?);
+        let meter_for_flush = meter.clone();
+        let flush_interval = Duration::from_secs(config.metering.flush_interval_secs);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(flush_interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            loop {
+                interval.tick().await;
+                if let Err(e) = meter_for_flush.flush() {
+                    error!("Failed to flush usage metering: {}", e);
+                }
+            }
+        });
+        info!("✓ Usage metering enabled (flushed every {}s)", config.metering.flush_interval_secs);
+        Some(meter)
+    } else {
+        None
+    };
+
+    // Opt-in telemetry reports to the configured dashboard
+    if config.telemetry.enabled {
+        tokio::spawn(crate::telemetry::run_reporter(
+            config.clone(),
+            state_manager.clone(),
+            consensus.clone(),
+            connected_peers.clone(),
+        ));
+        info!(
+            "✓ Telemetry enabled: reporting to {} every {}s",
+            config.telemetry.endpoint.as_deref().unwrap_or_default(), config.telemetry.interval_secs
+        );
+    }
+    if config.telemetry.aggregator {
+        info!("✓ Telemetry aggregator enabled (GET /api/v1/telemetry/nodes)");
+    }
+
     // Initialize API server
     let api_state = crate::api::ApiState {
         consensus: consensus.clone(),
@@ -96,9 +271,27 @@ async fn main() -> Result<()> {
         config: config.clone(),
         ws_tx: ws_tx.clone(),
         connected_peers: connected_peers.clone(),
+        response_cache: Arc::new(crate::http_cache::ResponseCache::default()),
+        watchlists: Arc::new(crate::watchlist::WatchlistStore::open(&state_manager)?),
+        custody,
+        sign_in: config.sign_in.enabled
+            .then(|| Arc::new(crate::sign_in::SignInService::new(config.sign_in.clone(), config.chain_id))),
+        mailbox,
+        tenants,
+        metering: metering.clone(),
+        telemetry: config.telemetry.aggregator
+            .then(|| Arc::new(crate::telemetry::TelemetryAggregator::new(&config))),
+        faucet: config.dev.enabled
+            .then(|| Arc::new(crate::dev::Faucet::new(consensus.clone(), &config))),
     };
     info!("✓ API server state initialized");
 
+    // Dev node: pre-funded accounts, printed with their keys
+    if config.dev.enabled {
+        let accounts = crate::dev::fund_accounts(&state_manager, &config.dev)?;
+        crate::dev::print_banner(&config, &accounts);
+    }
+
     // Start the node
     info!("═══════════════════════════════════════════════════════════");
     info!("  HAZE node is running!");
@@ -110,14 +303,36 @@ async fn main() -> Result<()> {
     // Clone consensus and validator address for block production task
     let consensus_for_blocks = consensus.clone();
     let validator_addr = validator_address;
+    let produce_blocks = subsystems.block_production;
+    info!(
+        "✓ Role: {} (block production: {}, mempool: {}, full history: {}, indexer: {}, API: {:?})",
+        role.as_str(), subsystems.block_production, subsystems.mempool, subsystems.full_history,
+        subsystems.indexer, subsystems.api
+    );
+    if config.replica.enabled {
+        info!("✓ Read replica mode: following the primary, block production disabled");
+    }
+    let instant_sealing = config.dev.enabled;
     
-    // Start block production task (MVP: create blocks periodically)
+    // Start block production task (MVP: create blocks periodically; only on roles producing blocks;
+    // dev nodes seal a block as soon as a transaction arrives)
     let block_production_handle = tokio::spawn(async move {
+        if !produce_blocks {
+            return;
+        }
+        if instant_sealing {
+            crate::dev::run_sealer(consensus_for_blocks, validator_addr).await;
+            return;
+        }
         let mut interval = tokio::time::interval(Duration::from_secs(5)); // Create block every 5 seconds
         interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
         
         loop {
             interval.tick().await;
+            if consensus_for_blocks.production_paused() {
+                tracing::debug!("Block production paused by operator, skipping block creation");
+                continue;
+            }
             
             // Check if there are transactions in the pool
             let tx_pool_size = consensus_for_blocks.tx_pool_size();
@@ -181,11 +396,9 @@ async fn main() -> Result<()> {
                 let mut total_txs = 0u64;
                 let mut block_count = 0u64;
                 let start_height = height.saturating_sub(10);
-                for h in start_height..=height {
-                    if let Some(block) = state_for_metrics.get_block_by_height(h) {
-                        total_txs += block.transactions.len() as u64;
-                        block_count += 1;
-                    }
+                for block in state_for_metrics.get_blocks_by_height_range(start_height, height) {
+                    total_txs += block.transactions.len() as u64;
+                    block_count += 1;
                 }
                 if block_count > 0 {
                     // Estimate: assume ~5 second block time for MVP
@@ -231,6 +444,18 @@ async fn main() -> Result<()> {
     metrics_handle.abort();
     network_handle.abort();
     api_handle.abort();
+    // Let the next start load the state instead of replaying every block
+    if let Err(e) = state_manager.write_checkpoint() {
+        error!("Failed to write the state checkpoint: {}", e);
+    }
+    if let Some(meter) = metering
+        && let Err(e) = meter.flush()
+    {
+        error!("Failed to flush usage metering: {}", e);
+    }
+    if dev_mode {
+        let _ = std::fs::remove_dir_all(crate::dev::scratch_dir());
+    }
 
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,131 @@
+//! Operator controls for upgrades: pausing block production and maintenance mode
+//!
+//! A paused node stops producing blocks but keeps serving reads, following the network and
+//! filling its pool. In maintenance mode it also refuses new transactions with a `maintenance`
+//! error (code 1010), so clients learn to retry elsewhere or later instead of having their
+//! transactions sit in the pool. Both end on `resume`, or on their own once the pause runs out
+//! (`consensus.max_pause_secs` at most), so a forgotten pause cannot stall the node for good.
+
+use parking_lot::RwLock;
+use serde::Serialize;
+use crate::error::{HazeError, Result};
+
+/// A pause in force
+#[derive(Debug, Clone, Serialize)]
+pub struct Pause {
+    /// Unix time (seconds) the pause started
+    pub paused_at: i64,
+    /// Unix time (seconds) block production resumes on its own
+    pub resume_at: i64,
+    /// New transactions are refused while set
+    pub maintenance: bool,
+    pub reason: Option<String>,
+}
+
+/// Pause and maintenance state of this node
+#[derive(Debug)]
+pub struct NodeControl {
+    pause: RwLock<Option<Pause>>,
+    max_pause_secs: u64,
+}
+
+impl NodeControl {
+    pub fn new(max_pause_secs: u64) -> Self {
+        Self {
+            pause: RwLock::new(None),
+            max_pause_secs,
+        }
+    }
+
+    /// Pause block production at `now` for `timeout_secs` (default and upper bound:
+    /// `max_pause_secs`). Pausing again replaces the current pause.
+    pub fn pause(&self, now: i64, timeout_secs: Option<u64>, maintenance: bool, reason: Option<String>) -> Result<Pause> {
+        let timeout_secs = timeout_secs.unwrap_or(self.max_pause_secs);
+        if timeout_secs == 0 || timeout_secs > self.max_pause_secs {
+            return Err(HazeError::Consensus(format!(
+                "Pause timeout must be between 1 and {} seconds, got {}", self.max_pause_secs, timeout_secs
+            )));
+        }
+        let pause = Pause {
+            paused_at: now,
+            resume_at: now.saturating_add(timeout_secs as i64),
+            maintenance,
+            reason,
+        };
+        tracing::warn!(
+            "Block production paused until {}{}{}",
+            pause.resume_at,
+            if maintenance { " (maintenance mode: refusing transactions)" } else { "" },
+            pause.reason.as_deref().map(|reason| format!(": {}", reason)).unwrap_or_default()
+        );
+        *self.pause.write() = Some(pause.clone());
+        Ok(pause)
+    }
+
+    /// End the pause, if any; returns it
+    pub fn resume(&self) -> Option<Pause> {
+        let ended = self.pause.write().take();
+        if ended.is_some() {
+            tracing::info!("Block production resumed by operator");
+        }
+        ended
+    }
+
+    /// The pause in force at `now`; a pause past its `resume_at` is ended here
+    pub fn current(&self, now: i64) -> Option<Pause> {
+        let pause = self.pause.read().clone()?;
+        if now < pause.resume_at {
+            return Some(pause);
+        }
+        let mut current = self.pause.write();
+        if current.as_ref().is_some_and(|p| p.paused_at == pause.paused_at) {
+            *current = None;
+            tracing::warn!("Pause timed out after {}s; block production resumed", now - pause.paused_at);
+        }
+        None
+    }
+
+    /// Whether block production is paused at `now`
+    pub fn is_paused(&self, now: i64) -> bool {
+        self.current(now).is_some()
+    }
+
+    /// Refuse new transactions in maintenance mode
+    pub fn check_accepting(&self, now: i64) -> Result<()> {
+        match self.current(now) {
+            Some(pause) if pause.maintenance => Err(HazeError::Maintenance(format!(
+                "Node is in maintenance mode until {}{}; reads are served, transactions are refused",
+                pause.resume_at,
+                pause.reason.map(|reason| format!(" ({})", reason)).unwrap_or_default()
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pause_resume_and_timeout() {
+        let control = NodeControl::new(600);
+        assert!(control.pause(100, Some(601), false, None).is_err());
+        assert!(control.pause(100, Some(0), false, None).is_err());
+
+        // Production pause: transactions still accepted
+        let pause = control.pause(100, Some(60), false, Some("upgrade".to_string())).unwrap();
+        assert_eq!(pause.resume_at, 160);
+        assert!(control.is_paused(159));
+        assert!(control.check_accepting(120).is_ok());
+        assert!(control.resume().is_some());
+        assert!(!control.is_paused(120));
+        assert!(control.resume().is_none());
+
+        // Maintenance mode refuses transactions until it times out
+        assert_eq!(control.pause(200, None, true, None).unwrap().resume_at, 800);
+        assert_eq!(control.check_accepting(300).unwrap_err().code(), 1010);
+        assert!(control.check_accepting(800).is_ok());
+        assert!(control.current(300).is_none(), "a timed-out pause is cleared");
+    }
+}
@@ -0,0 +1,393 @@
+//! Encrypted direct messages between accounts
+//!
+//! Players negotiating a trade (e.g. before an escrow) message each other off-chain. A message
+//! is sealed to the recipient's address key, so only the holder of that key can read it:
+//!
+//! 1. The recipient's address (an Ed25519 public key) is mapped to its X25519 form.
+//! 2. The sender draws an ephemeral X25519 key and computes the shared secret with it.
+//! 3. `key = HKDF-SHA256(ikm = shared, info = "haze_dm_v1" || ephemeral_key || recipient)`
+//! 4. The plaintext is encrypted with ChaCha20-Poly1305 under that key and a random nonce.
+//! 5. The sender signs the sealed message with its own account key, so nodes can check who
+//!    sent it without reading it.
+//!
+//! Nodes with `messaging.enabled` relay messages to their peers and keep them in the
+//! `mailbox` tree until the recipient fetches and deletes them, or `retention_secs` pass.
+//! Mailbox reads are authenticated by a signature of the recipient (`mailbox_auth_payload`).
+
+use std::collections::{HashSet, VecDeque};
+use std::ops::Bound;
+use std::sync::Arc;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use curve25519_dalek::montgomery::MontgomeryPoint;
+use ed25519_dalek::VerifyingKey;
+use hkdf::Hkdf;
+use parking_lot::Mutex;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tokio::sync::broadcast;
+use zeroize::Zeroizing;
+use crate::config::MessagingConfig;
+use crate::crypto::{verify_signature, KeyPair};
+use crate::error::{HazeError, Result};
+use crate::state::StateManager;
+use crate::storage::{StorageTree, WriteBatch};
+use crate::types::{sha256, Address, Hash};
+
+const KDF_INFO: &[u8] = b"haze_dm_v1";
+const SIGNING_DOMAIN: &[u8] = b"haze_dm_sig_v1";
+const MAILBOX_AUTH_DOMAIN: &[u8] = b"haze_mailbox_v1";
+/// Poly1305 tag appended to every ciphertext
+const TAG_LEN: usize = 16;
+/// Message ids remembered to drop relayed duplicates
+const SEEN_CAPACITY: usize = 10_000;
+
+/// Message sealed to a recipient, as relayed between nodes and stored in mailboxes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SealedMessage {
+    pub sender: Address,
+    pub recipient: Address,
+    /// Sender's ephemeral X25519 public key
+    pub ephemeral_key: [u8; 32],
+    pub nonce: [u8; 12],
+    pub ciphertext: Vec<u8>,
+    /// Unix time the message was sealed
+    pub sent_at: i64,
+    /// Ed25519 signature of `signing_payload` by the sender
+    pub signature: Vec<u8>,
+}
+
+impl SealedMessage {
+    /// Bytes the sender signs: every field but the signature
+    pub fn signing_payload(&self) -> Vec<u8> {
+        let mut payload = Vec::with_capacity(SIGNING_DOMAIN.len() + 116 + self.ciphertext.len());
+        payload.extend_from_slice(SIGNING_DOMAIN);
+        payload.extend_from_slice(&self.sender);
+        payload.extend_from_slice(&self.recipient);
+        payload.extend_from_slice(&self.ephemeral_key);
+        payload.extend_from_slice(&self.nonce);
+        payload.extend_from_slice(&self.sent_at.to_be_bytes());
+        payload.extend_from_slice(&self.ciphertext);
+        payload
+    }
+
+    /// Message id: SHA-256 of the signing payload
+    pub fn id(&self) -> Hash {
+        sha256(&self.signing_payload())
+    }
+
+    /// Whether the signature is the sender's
+    pub fn verify(&self) -> bool {
+        verify_signature(&self.sender, &self.signing_payload(), &self.signature).unwrap_or(false)
+    }
+}
+
+/// Seal `plaintext` from `sender` to `recipient` at Unix time `now`
+pub fn seal(sender: &KeyPair, recipient: &Address, plaintext: &[u8], now: i64) -> Result<SealedMessage> {
+    let recipient_point = VerifyingKey::from_bytes(recipient)
+        .map_err(|_| HazeError::Crypto("Recipient is not a valid account key".to_string()))?
+        .to_montgomery();
+    let mut ephemeral_secret = Zeroizing::new([0u8; 32]);
+    OsRng.fill_bytes(ephemeral_secret.as_mut());
+    let ephemeral_key = MontgomeryPoint::mul_base_clamped(*ephemeral_secret).to_bytes();
+    let shared = Zeroizing::new(recipient_point.mul_clamped(*ephemeral_secret).to_bytes());
+    let cipher = message_cipher(&shared, &ephemeral_key, recipient)?;
+
+    let mut nonce = [0u8; 12];
+    OsRng.fill_bytes(&mut nonce);
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|_| HazeError::Crypto("Message encryption failed".to_string()))?;
+    let mut message = SealedMessage {
+        sender: sender.address(),
+        recipient: *recipient,
+        ephemeral_key,
+        nonce,
+        ciphertext,
+        sent_at: now,
+        signature: Vec::new(),
+    };
+    message.signature = sender.sign(&message.signing_payload());
+    Ok(message)
+}
+
+/// Decrypt a message with the recipient's key pair
+pub fn open(recipient: &KeyPair, message: &SealedMessage) -> Result<Vec<u8>> {
+    if message.recipient != recipient.address() {
+        return Err(HazeError::Crypto("Message is sealed to another account".to_string()));
+    }
+    if !message.verify() {
+        return Err(HazeError::Crypto("Invalid message signature".to_string()));
+    }
+    let secret = Zeroizing::new(recipient.signing_key().to_scalar_bytes());
+    let shared = Zeroizing::new(MontgomeryPoint(message.ephemeral_key).mul_clamped(*secret).to_bytes());
+    let cipher = message_cipher(&shared, &message.ephemeral_key, &message.recipient)?;
+    cipher
+        .decrypt(Nonce::from_slice(&message.nonce), message.ciphertext.as_slice())
+        .map_err(|_| HazeError::Crypto("Message decryption failed".to_string()))
+}
+
+fn message_cipher(shared: &[u8; 32], ephemeral_key: &[u8; 32], recipient: &Address) -> Result<ChaCha20Poly1305> {
+    // A low-order ephemeral key gives an all-zero secret that anyone can compute
+    if shared.iter().all(|b| *b == 0) {
+        return Err(HazeError::Crypto("Invalid ephemeral key".to_string()));
+    }
+    let mut info = Vec::with_capacity(KDF_INFO.len() + 64);
+    info.extend_from_slice(KDF_INFO);
+    info.extend_from_slice(ephemeral_key);
+    info.extend_from_slice(recipient);
+    let mut key = Zeroizing::new([0u8; 32]);
+    Hkdf::<Sha256>::new(None, shared)
+        .expand(&info, key.as_mut())
+        .map_err(|_| HazeError::Crypto("Message key derivation failed".to_string()))?;
+    Ok(ChaCha20Poly1305::new(Key::from_slice(key.as_ref())))
+}
+
+/// Bytes a recipient signs to read or delete its mailbox at Unix time `timestamp`
+pub fn mailbox_auth_payload(address: &Address, timestamp: i64) -> Vec<u8> {
+    let mut payload = Vec::with_capacity(MAILBOX_AUTH_DOMAIN.len() + 40);
+    payload.extend_from_slice(MAILBOX_AUTH_DOMAIN);
+    payload.extend_from_slice(address);
+    payload.extend_from_slice(&timestamp.to_be_bytes());
+    payload
+}
+
+#[derive(Default)]
+struct SeenIds {
+    ids: HashSet<Hash>,
+    order: VecDeque<Hash>,
+}
+
+impl SeenIds {
+    /// Remember `id`; false if it was already seen
+    fn insert(&mut self, id: Hash) -> bool {
+        if !self.ids.insert(id) {
+            return false;
+        }
+        self.order.push_back(id);
+        if self.order.len() > SEEN_CAPACITY
+            && let Some(oldest) = self.order.pop_front()
+        {
+            self.ids.remove(&oldest);
+        }
+        true
+    }
+}
+
+/// Mailboxes of all recipients, kept by this node
+pub struct Mailbox {
+    config: MessagingConfig,
+    tree: Arc<dyn StorageTree>,
+    /// Also serializes writes so per-recipient caps hold
+    seen: Mutex<SeenIds>,
+    /// Messages accepted through the API, for the network layer to relay
+    outbound: broadcast::Sender<SealedMessage>,
+}
+
+impl Mailbox {
+    /// Open the mailbox in the node database's `mailbox` tree
+    pub fn open(config: MessagingConfig, state: &StateManager) -> Result<Self> {
+        Ok(Self::new(config, state.open_tree("mailbox")?))
+    }
+
+    pub fn new(config: MessagingConfig, tree: Arc<dyn StorageTree>) -> Self {
+        let (outbound, _) = broadcast::channel(256);
+        Self { config, tree, seen: Mutex::new(SeenIds::default()), outbound }
+    }
+
+    /// Messages submitted to this node, to be relayed to peers
+    pub fn subscribe(&self) -> broadcast::Receiver<SealedMessage> {
+        self.outbound.subscribe()
+    }
+
+    /// Accept a message submitted by a client and queue it for relaying
+    pub fn send(&self, message: SealedMessage, now: i64) -> Result<Hash> {
+        let id = message.id();
+        if self.accept(message.clone(), now)? {
+            let _ = self.outbound.send(message);
+        }
+        Ok(id)
+    }
+
+    /// Check and store a message; false if it was already seen (relayed duplicates are dropped)
+    pub fn accept(&self, message: SealedMessage, now: i64) -> Result<bool> {
+        if message.ciphertext.len() > self.config.max_message_bytes + TAG_LEN {
+            return Err(HazeError::Network(format!(
+                "Message exceeds {} bytes", self.config.max_message_bytes
+            )));
+        }
+        if message.sent_at > now.saturating_add(self.config.auth_window_secs as i64)
+            || message.sent_at < now.saturating_sub(self.config.retention_secs as i64)
+        {
+            return Err(HazeError::Network("Message send time is out of range".to_string()));
+        }
+        if !message.verify() {
+            return Err(HazeError::Crypto("Invalid message signature".to_string()));
+        }
+        let id = message.id();
+        let mut seen = self.seen.lock();
+        if !seen.insert(id) {
+            return Ok(false);
+        }
+
+        let mut batch = WriteBatch::default();
+        let keys = self.keys_of(&message.recipient)?;
+        // Make room: expired messages first, then the oldest
+        let cutoff = now.saturating_sub(self.config.retention_secs as i64);
+        let mut kept = keys.len();
+        for (key, sent_at) in &keys {
+            if *sent_at >= cutoff && kept < self.config.max_messages_per_recipient {
+                break;
+            }
+            batch.remove(key);
+            kept -= 1;
+        }
+        let value = bincode::serialize(&message)
+            .map_err(|e| HazeError::Serialization(e.to_string()))?;
+        batch.insert(mailbox_key(&message.recipient, message.sent_at, &id), value);
+        self.tree.apply_batch(batch)?;
+        Ok(true)
+    }
+
+    /// Unexpired messages of `recipient`, oldest first
+    pub fn messages(&self, recipient: &Address, now: i64) -> Result<Vec<SealedMessage>> {
+        let cutoff = now.saturating_sub(self.config.retention_secs as i64);
+        let mut messages = Vec::new();
+        for item in self.tree.range(Bound::Included(&recipient[..]), Bound::Unbounded) {
+            let (key, value) = item?;
+            if !key.starts_with(recipient) {
+                break;
+            }
+            let message: SealedMessage = bincode::deserialize(&value)
+                .map_err(|e| HazeError::Serialization(e.to_string()))?;
+            if message.sent_at >= cutoff {
+                messages.push(message);
+            }
+        }
+        Ok(messages)
+    }
+
+    /// Delete a message of `recipient`; false if there is none with that id
+    pub fn delete(&self, recipient: &Address, id: &Hash) -> Result<bool> {
+        let _guard = self.seen.lock();
+        let Some((key, _)) = self.keys_of(recipient)?.into_iter().find(|(key, _)| key.ends_with(id)) else {
+            return Ok(false);
+        };
+        self.tree.remove(&key)?;
+        Ok(true)
+    }
+
+    /// Check a mailbox request signed by `address` (see `mailbox_auth_payload`)
+    pub fn authenticate(&self, address: &Address, timestamp: i64, signature: &[u8], now: i64) -> Result<()> {
+        if timestamp.abs_diff(now) > self.config.auth_window_secs {
+            return Err(HazeError::AccessDenied("Mailbox request timestamp is out of range".to_string()));
+        }
+        if !verify_signature(address, &mailbox_auth_payload(address, timestamp), signature)? {
+            return Err(HazeError::AccessDenied("Invalid mailbox signature".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Keys and send times of `recipient`'s stored messages, oldest first
+    fn keys_of(&self, recipient: &Address) -> Result<Vec<(Vec<u8>, i64)>> {
+        let mut keys = Vec::new();
+        for item in self.tree.range(Bound::Included(&recipient[..]), Bound::Unbounded) {
+            let (key, _) = item?;
+            if !key.starts_with(recipient) {
+                break;
+            }
+            let sent_at = u64::from_be_bytes(key[32..40].try_into().unwrap_or_default());
+            keys.push((key.to_vec(), sent_at as i64));
+        }
+        Ok(keys)
+    }
+}
+
+/// `recipient || sent_at (big-endian) || id`, so a recipient's messages sort by send time
+fn mailbox_key(recipient: &Address, sent_at: i64, id: &Hash) -> Vec<u8> {
+    let mut key = Vec::with_capacity(72);
+    key.extend_from_slice(recipient);
+    key.extend_from_slice(&(sent_at.max(0) as u64).to_be_bytes());
+    key.extend_from_slice(id);
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{SledStorage, Storage};
+
+    fn test_mailbox(config: MessagingConfig) -> Mailbox {
+        let storage = SledStorage::temporary().unwrap();
+        Mailbox::new(config, storage.open_tree("mailbox").unwrap())
+    }
+
+    #[test]
+    fn test_sealed_message_opens_only_for_recipient() {
+        let alice = KeyPair::generate();
+        let bob = KeyPair::generate();
+        let message = seal(&alice, &bob.address(), b"10 gold for the sword?", 1_000).unwrap();
+        assert!(message.verify());
+        assert_eq!(open(&bob, &message).unwrap(), b"10 gold for the sword?");
+
+        // Another key cannot read it, even when the recipient field is rewritten
+        let eve = KeyPair::generate();
+        assert!(open(&eve, &message).is_err());
+        let mut redirected = message.clone();
+        redirected.recipient = eve.address();
+        redirected.signature = eve.sign(&redirected.signing_payload());
+        redirected.sender = eve.address();
+        assert!(open(&eve, &redirected).is_err());
+
+        let mut tampered = message;
+        tampered.ciphertext[0] ^= 1;
+        assert!(!tampered.verify());
+    }
+
+    #[test]
+    fn test_mailbox_stores_dedups_and_caps_messages() {
+        let config = MessagingConfig { enabled: true, max_messages_per_recipient: 2, ..MessagingConfig::default() };
+        let mailbox = test_mailbox(config.clone());
+        let alice = KeyPair::generate();
+        let bob = KeyPair::generate();
+
+        let first = seal(&alice, &bob.address(), b"one", 1_000).unwrap();
+        assert!(mailbox.accept(first.clone(), 1_000).unwrap());
+        assert!(!mailbox.accept(first.clone(), 1_001).unwrap());
+        for (i, text) in [b"two", b"thr"].iter().enumerate() {
+            let message = seal(&alice, &bob.address(), *text, 1_001 + i as i64).unwrap();
+            mailbox.accept(message, 1_002).unwrap();
+        }
+        let stored = mailbox.messages(&bob.address(), 1_002).unwrap();
+        assert_eq!(stored.len(), 2);
+        assert_eq!(open(&bob, &stored[0]).unwrap(), b"two");
+        assert!(mailbox.messages(&alice.address(), 1_002).unwrap().is_empty());
+
+        assert!(mailbox.delete(&bob.address(), &stored[0].id()).unwrap());
+        assert!(!mailbox.delete(&bob.address(), &stored[0].id()).unwrap());
+        assert_eq!(mailbox.messages(&bob.address(), 1_002).unwrap().len(), 1);
+        assert!(mailbox.messages(&bob.address(), 1_003 + config.retention_secs as i64).unwrap().is_empty());
+
+        // Oversized, forged or far-future messages are refused
+        let big = seal(&alice, &bob.address(), &vec![0u8; config.max_message_bytes + 1], 1_000).unwrap();
+        assert!(mailbox.accept(big, 1_000).is_err());
+        let mut forged = seal(&alice, &bob.address(), b"hi", 1_000).unwrap();
+        forged.sender = bob.address();
+        assert!(mailbox.accept(forged, 1_000).is_err());
+        let future = seal(&alice, &bob.address(), b"hi", 1_000 + 2 * config.auth_window_secs as i64).unwrap();
+        assert!(mailbox.accept(future, 1_000).is_err());
+    }
+
+    #[test]
+    fn test_mailbox_requests_are_signed_by_the_recipient() {
+        let mailbox = test_mailbox(MessagingConfig { enabled: true, ..MessagingConfig::default() });
+        let bob = KeyPair::generate();
+        let signature = bob.sign(&mailbox_auth_payload(&bob.address(), 1_000));
+        assert!(mailbox.authenticate(&bob.address(), 1_000, &signature, 1_010).is_ok());
+        assert!(mailbox.authenticate(&bob.address(), 1_000, &signature, 2_000).is_err());
+        let other = KeyPair::generate().sign(&mailbox_auth_payload(&bob.address(), 1_000));
+        assert!(mailbox.authenticate(&bob.address(), 1_000, &other, 1_010).is_err());
+    }
+}
@@ -0,0 +1,317 @@
+//! Usage metering for billing
+//!
+//! Counts, per API key (`x-api-key`, identified by its SHA-256 hash) and UTC day:
+//! - API requests;
+//! - gas of submitted transactions (asset operation gas, or the gas limit of contract calls);
+//! - bytes stored: the encoded size of submitted transactions;
+//! - WebSocket time, added when a connection closes.
+//!
+//! Counters are kept in memory and added to daily rollups in the node database's `metering`
+//! tree every `metering.flush_interval_secs`. Operators export the rollups as JSON or CSV.
+
+use std::collections::{HashMap, HashSet};
+use std::ops::Bound;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use crate::config::{Config, MeteringConfig};
+use crate::error::{HazeError, Result};
+use crate::state::StateManager;
+use crate::storage::StorageTree;
+use crate::types::Transaction;
+use crate::watchlist::hash_api_key;
+
+/// Format of the `day` of a rollup
+pub const DAY_FORMAT: &str = "%Y-%m-%d";
+
+/// Live counters of one key on one day, not yet flushed
+#[derive(Debug, Default)]
+struct KeyUsage {
+    requests: AtomicU64,
+    gas_submitted: AtomicU64,
+    bytes_stored: AtomicU64,
+    ws_seconds: AtomicU64,
+}
+
+impl KeyUsage {
+    /// Reset the counters, returning what they held
+    fn take(&self) -> StoredUsage {
+        StoredUsage {
+            requests: self.requests.swap(0, Ordering::Relaxed),
+            gas_submitted: self.gas_submitted.swap(0, Ordering::Relaxed),
+            bytes_stored: self.bytes_stored.swap(0, Ordering::Relaxed),
+            ws_seconds: self.ws_seconds.swap(0, Ordering::Relaxed),
+        }
+    }
+}
+
+/// Persisted totals of one key on one day
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct StoredUsage {
+    requests: u64,
+    gas_submitted: u64,
+    bytes_stored: u64,
+    ws_seconds: u64,
+}
+
+impl StoredUsage {
+    fn is_zero(&self) -> bool {
+        *self == Self::default()
+    }
+
+    fn add(&mut self, other: &StoredUsage) {
+        self.requests = self.requests.saturating_add(other.requests);
+        self.gas_submitted = self.gas_submitted.saturating_add(other.gas_submitted);
+        self.bytes_stored = self.bytes_stored.saturating_add(other.bytes_stored);
+        self.ws_seconds = self.ws_seconds.saturating_add(other.ws_seconds);
+    }
+}
+
+/// Usage of one API key on one UTC day
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UsageRollup {
+    /// `YYYY-MM-DD`
+    pub day: String,
+    pub key_hash: String,
+    /// Tenant owning the key, on multi-tenant nodes (filled in by the API)
+    pub tenant: Option<String>,
+    pub requests: u64,
+    pub gas_submitted: u64,
+    pub bytes_stored: u64,
+    pub ws_seconds: u64,
+    /// WebSocket time rounded up to whole minutes
+    pub ws_minutes: u64,
+}
+
+/// Daily usage rollups per API key
+#[derive(Debug)]
+pub struct UsageMeter {
+    tree: Arc<dyn StorageTree>,
+    admin_keys: HashSet<String>,
+    retention_days: u32,
+    live: RwLock<HashMap<(String, String), Arc<KeyUsage>>>,
+}
+
+impl UsageMeter {
+    /// Open the meter in the node database's `metering` tree
+    pub fn open(config: &MeteringConfig, state: &StateManager) -> Result<Self> {
+        Ok(Self {
+            tree: state.open_tree("metering")?,
+            admin_keys: config.admin_key_hashes.iter().map(|h| h.to_lowercase()).collect(),
+            retention_days: config.retention_days,
+            live: RwLock::new(HashMap::new()),
+        })
+    }
+
+    /// Whether `api_key` may export usage
+    pub fn is_admin(&self, api_key: &str) -> bool {
+        self.admin_keys.contains(&hash_api_key(api_key))
+    }
+
+    /// Count one API request
+    pub fn record_request(&self, api_key: &str) {
+        self.counters(&today(), api_key).requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count a transaction submitted with `api_key`
+    pub fn record_transaction(&self, api_key: &str, config: &Config, tx: &Transaction) {
+        let usage = self.counters(&today(), api_key);
+        usage.gas_submitted.fetch_add(submitted_gas(config, tx), Ordering::Relaxed);
+        let bytes = bincode::serialized_size(tx).unwrap_or(0);
+        usage.bytes_stored.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Add the duration of a closed WebSocket connection
+    pub fn record_ws(&self, api_key: &str, seconds: u64) {
+        self.counters(&today(), api_key).ws_seconds.fetch_add(seconds, Ordering::Relaxed);
+    }
+
+    /// Add the live counters to the stored rollups and delete rollups past retention
+    pub fn flush(&self) -> Result<()> {
+        let today = today();
+        let mut live = self.live.write();
+        let mut idle = Vec::new();
+        for ((day, key_hash), usage) in live.iter() {
+            let delta = usage.take();
+            if delta.is_zero() {
+                // Past days are dropped one flush after their last update
+                if *day != today {
+                    idle.push((day.clone(), key_hash.clone()));
+                }
+                continue;
+            }
+            let key = rollup_key(day, key_hash);
+            let mut stored = self.load(&key)?;
+            stored.add(&delta);
+            let value = bincode::serialize(&stored)
+                .map_err(|e| HazeError::Serialization(e.to_string()))?;
+            self.tree.insert(key.as_bytes(), &value)?;
+        }
+        for key in idle {
+            live.remove(&key);
+        }
+        drop(live);
+
+        if self.retention_days > 0 {
+            let cutoff = (chrono::Utc::now() - chrono::Duration::days(self.retention_days as i64))
+                .format(DAY_FORMAT)
+                .to_string();
+            for item in self.tree.range(Bound::Unbounded, Bound::Excluded(cutoff.as_bytes())) {
+                let (key, _) = item?;
+                self.tree.remove(&key)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Rollups from day `from` to day `to` (inclusive, `YYYY-MM-DD`), optionally for one key,
+    /// ordered by day and key hash. Flushes first so the current day is up to date.
+    pub fn rollups(&self, from: Option<&str>, to: Option<&str>, key_hash: Option<&str>) -> Result<Vec<UsageRollup>> {
+        self.flush()?;
+        let mut rollups = Vec::new();
+        for item in self.tree.range(Bound::Included(from.unwrap_or("").as_bytes()), Bound::Unbounded) {
+            let (key, value) = item?;
+            let key = String::from_utf8_lossy(&key);
+            let Some((day, hash)) = key.split_once('/') else {
+                continue;
+            };
+            if to.is_some_and(|to| day > to) {
+                break;
+            }
+            if key_hash.is_some_and(|wanted| wanted != hash) {
+                continue;
+            }
+            let usage: StoredUsage = bincode::deserialize(&value)
+                .map_err(|e| HazeError::Serialization(e.to_string()))?;
+            rollups.push(UsageRollup {
+                day: day.to_string(),
+                key_hash: hash.to_string(),
+                tenant: None,
+                requests: usage.requests,
+                gas_submitted: usage.gas_submitted,
+                bytes_stored: usage.bytes_stored,
+                ws_seconds: usage.ws_seconds,
+                ws_minutes: usage.ws_seconds.div_ceil(60),
+            });
+        }
+        Ok(rollups)
+    }
+
+    fn counters(&self, day: &str, api_key: &str) -> Arc<KeyUsage> {
+        let id = (day.to_string(), hash_api_key(api_key));
+        if let Some(usage) = self.live.read().get(&id) {
+            return usage.clone();
+        }
+        self.live.write().entry(id).or_default().clone()
+    }
+
+    fn load(&self, key: &str) -> Result<StoredUsage> {
+        match self.tree.get(key.as_bytes())? {
+            Some(value) => bincode::deserialize(&value).map_err(|e| HazeError::Serialization(e.to_string())),
+            None => Ok(StoredUsage::default()),
+        }
+    }
+}
+
+/// Gas a transaction asks for: the asset operation gas of `MistbornAsset`, the gas limit of
+/// `ContractCall`, zero for other transactions
+pub fn submitted_gas(config: &Config, tx: &Transaction) -> u64 {
+    match tx {
+        Transaction::MistbornAsset { action, data, .. } => {
+            crate::assets::asset_operation_gas_breakdown(config, action, data, Some(&data.metadata))
+                .iter()
+                .map(|line| line.gas)
+                .sum()
+        }
+        Transaction::ContractCall { gas_limit, .. } => *gas_limit,
+        _ => 0,
+    }
+}
+
+/// CSV export of rollups, with a header row
+pub fn rollups_to_csv(rollups: &[UsageRollup]) -> String {
+    let mut csv = String::from("day,key_hash,tenant,requests,gas_submitted,bytes_stored,ws_seconds,ws_minutes\n");
+    for r in rollups {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{}\n",
+            r.day,
+            r.key_hash,
+            r.tenant.as_deref().unwrap_or(""),
+            r.requests,
+            r.gas_submitted,
+            r.bytes_stored,
+            r.ws_seconds,
+            r.ws_minutes
+        ));
+    }
+    csv
+}
+
+fn today() -> String {
+    chrono::Utc::now().format(DAY_FORMAT).to_string()
+}
+
+fn rollup_key(day: &str, key_hash: &str) -> String {
+    format!("{}/{}", day, key_hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_daily_rollups_flush_and_export() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage.db_path = dir.path().join("db");
+        config.metering.retention_days = 30;
+        let state = StateManager::new(&config).unwrap();
+        let meter = UsageMeter::open(&config.metering, &state).unwrap();
+
+        let tx = Transaction::ContractCall {
+            from: [1u8; 32],
+            contract: [2u8; 32],
+            method: "run".to_string(),
+            args: vec![],
+            gas_limit: 5_000,
+            fee: 0,
+            nonce: 0,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        meter.record_request("studio");
+        meter.record_request("studio");
+        meter.record_transaction("studio", &config, &tx);
+        meter.record_ws("studio", 61);
+        meter.record_request("other");
+        // Yesterday's counters are still written on the next flush
+        let yesterday = (chrono::Utc::now() - chrono::Duration::days(1)).format(DAY_FORMAT).to_string();
+        meter.counters(&yesterday, "studio").requests.fetch_add(3, Ordering::Relaxed);
+        // Rollups past retention are deleted
+        let expired = (chrono::Utc::now() - chrono::Duration::days(31)).format(DAY_FORMAT).to_string();
+        meter.counters(&expired, "studio").requests.fetch_add(1, Ordering::Relaxed);
+
+        let studio = hash_api_key("studio");
+        let rollups = meter.rollups(None, None, Some(&studio)).unwrap();
+        assert_eq!(rollups.len(), 2);
+        assert_eq!((rollups[0].day.as_str(), rollups[0].requests), (yesterday.as_str(), 3));
+        let day = &rollups[1];
+        assert_eq!((day.day.clone(), day.requests, day.gas_submitted), (today(), 2, 5_000));
+        assert_eq!(day.bytes_stored, bincode::serialized_size(&tx).unwrap());
+        assert_eq!((day.ws_seconds, day.ws_minutes), (61, 2));
+        assert_eq!(meter.rollups(Some(&today()), None, None).unwrap().len(), 2);
+        assert_eq!(meter.rollups(None, Some(&yesterday), None).unwrap().len(), 1);
+
+        // Flushing again adds to the stored rollup, and rollups survive a restart
+        meter.record_request("studio");
+        meter.flush().unwrap();
+        let reopened = UsageMeter::open(&config.metering, &state).unwrap();
+        let rollups = reopened.rollups(Some(&today()), None, Some(&studio)).unwrap();
+        assert_eq!(rollups[0].requests, 3);
+        let csv = rollups_to_csv(&rollups);
+        assert!(csv.starts_with("day,key_hash,tenant,"));
+        assert!(csv.contains(&format!("{},{},,3,5000,", today(), studio)));
+    }
+}
@@ -0,0 +1,423 @@
+//! Storage schema migrations
+//!
+//! The layout of persisted values changes between builds (a new field in a bincode struct, a
+//! new block encoding). Rather than every reader decoding every layout ever written, the
+//! database records its schema version under `schema_version` in the `meta` tree, and
+//! `StateManager::new` upgrades it before anything is loaded: each step above the recorded
+//! version runs in order, and the version is recorded as each step completes, so an
+//! interrupted upgrade resumes at the step that did not finish. Steps only rewrite entries
+//! still in the old layout, so running one twice is harmless.
+//!
+//! Before the first step the database is copied to `<db_path>.schema-v<N>.backup`, unless
+//! `storage.migration_backup` is off. A database with a newer schema than this build knows is
+//! refused rather than misread. `haze migrate-schema --db <path> --dry-run` reports what each
+//! step would rewrite without writing anything.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+use crate::block_store::decode_block;
+use crate::config::{StorageBackend, StorageConfig};
+use crate::error::{HazeError, Result};
+use crate::integrity::block_checksum;
+use crate::state::DeadLetterEntry;
+use crate::storage::{
+    copy_storage, open_backend, Storage, WriteBatch, BLOCKS_TREE, BLOCK_CHECKSUMS_TREE, DEAD_LETTERS_TREE,
+    META_TREE,
+};
+use crate::types::{Hash, Transaction, WireFormat, WIRE_MAGIC, WIRE_VERSION};
+
+/// Schema version written by this build
+pub const SCHEMA_VERSION: u32 = 2;
+
+const SCHEMA_VERSION_KEY: &[u8] = b"schema_version";
+
+/// Entries rewritten per atomic batch
+const MIGRATION_BATCH_SIZE: usize = 1_000;
+
+/// One upgrade step
+pub struct Migration {
+    /// Schema version the step upgrades to
+    pub version: u32,
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Rewrite entries still in the old layout (only count them in a dry run)
+    run: fn(&dyn Storage, bool) -> Result<u64>,
+}
+
+/// Every step, in order; step `n` upgrades schema `n - 1` to `n`
+pub const MIGRATIONS: [Migration; SCHEMA_VERSION as usize] = [
+    Migration {
+        version: 1,
+        name: "dead_letter_error_codes",
+        description: "Add error codes to dead letters recorded before they existed",
+        run: dead_letter_error_codes,
+    },
+    Migration {
+        version: 2,
+        name: "blocks_current_wire",
+        description: "Re-encode stored blocks in the current wire format",
+        run: blocks_current_wire,
+    },
+];
+
+/// Result of one step
+#[derive(Debug, Clone, Serialize)]
+pub struct StepReport {
+    pub version: u32,
+    pub name: &'static str,
+    /// Entries rewritten (or, in a dry run, that would be)
+    pub entries: u64,
+}
+
+/// Result of `migrate`
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationReport {
+    pub from_version: u32,
+    pub to_version: u32,
+    pub steps: Vec<StepReport>,
+    /// Copy of the database taken before the first step
+    pub backup: Option<PathBuf>,
+    pub dry_run: bool,
+}
+
+/// Schema version recorded in the database: `None` if the database is empty, 0 if it holds
+/// data from before versions were recorded
+pub fn schema_version(storage: &dyn Storage) -> Result<Option<u32>> {
+    if let Some(value) = storage.open_tree(META_TREE)?.get(SCHEMA_VERSION_KEY)? {
+        let bytes: [u8; 4] = value.as_ref().try_into()
+            .map_err(|_| HazeError::Corruption("Malformed schema version".to_string()))?;
+        return Ok(Some(u32::from_be_bytes(bytes)));
+    }
+    for name in storage.tree_names() {
+        if name != META_TREE && !storage.open_tree(&name)?.is_empty() {
+            return Ok(Some(0));
+        }
+    }
+    Ok(None)
+}
+
+fn set_schema_version(storage: &dyn Storage, version: u32) -> Result<()> {
+    storage.open_tree(META_TREE)?.insert(SCHEMA_VERSION_KEY, &version.to_be_bytes())?;
+    storage.flush()
+}
+
+/// Bring the database up to `SCHEMA_VERSION`. A dry run writes nothing, not even the backup.
+pub fn migrate(storage: &dyn Storage, config: &StorageConfig, dry_run: bool) -> Result<MigrationReport> {
+    let Some(from_version) = schema_version(storage)? else {
+        // Nothing to upgrade: a new database starts at the current schema
+        if !dry_run {
+            set_schema_version(storage, SCHEMA_VERSION)?;
+        }
+        return Ok(MigrationReport {
+            from_version: SCHEMA_VERSION,
+            to_version: SCHEMA_VERSION,
+            steps: Vec::new(),
+            backup: None,
+            dry_run,
+        });
+    };
+    if from_version > SCHEMA_VERSION {
+        return Err(HazeError::Database(format!(
+            "Database schema version {} is newer than this build supports ({}); run a newer node or restore a backup",
+            from_version, SCHEMA_VERSION
+        )));
+    }
+    let pending: Vec<&Migration> = MIGRATIONS.iter().filter(|m| m.version > from_version).collect();
+    let backup = if !pending.is_empty() && !dry_run && config.migration_backup {
+        Some(backup(storage, config, from_version)?)
+    } else {
+        None
+    };
+    let mut steps = Vec::new();
+    for migration in pending {
+        let entries = (migration.run)(storage, dry_run)?;
+        if !dry_run {
+            set_schema_version(storage, migration.version)?;
+            tracing::info!(
+                "Migrated database to schema {} ({}): {} entries rewritten",
+                migration.version, migration.name, entries
+            );
+        }
+        steps.push(StepReport { version: migration.version, name: migration.name, entries });
+    }
+    Ok(MigrationReport { from_version, to_version: SCHEMA_VERSION, steps, backup, dry_run })
+}
+
+/// Path of the copy taken before migrating from `version`
+pub fn backup_path(db_path: &Path, version: u32) -> PathBuf {
+    let mut path = db_path.as_os_str().to_owned();
+    path.push(format!(".schema-v{}.backup", version));
+    PathBuf::from(path)
+}
+
+/// Copy the database before the first step. A backup left by an interrupted run already holds
+/// the data as it was before migrating, so it is kept.
+fn backup(storage: &dyn Storage, config: &StorageConfig, version: u32) -> Result<PathBuf> {
+    let path = backup_path(&config.db_path, version);
+    let target = open_backend(config.backend, &path)?;
+    if schema_version(target.as_ref())?.is_some() {
+        tracing::info!("Keeping existing database backup at {}", path.display());
+        return Ok(path);
+    }
+    let copied = copy_storage(storage, target.as_ref())?;
+    tracing::info!("Backed up database ({} entries) to {}", copied, path.display());
+    Ok(path)
+}
+
+/// Dead-letter entry as persisted before error codes were added
+#[derive(serde::Deserialize)]
+struct LegacyDeadLetterEntry {
+    tx_hash: Hash,
+    transaction: Transaction,
+    error: String,
+    block_height: u64,
+    block_hash: Hash,
+    tx_index: usize,
+    failed_at: i64,
+    attempts: u32,
+}
+
+impl From<LegacyDeadLetterEntry> for DeadLetterEntry {
+    fn from(entry: LegacyDeadLetterEntry) -> Self {
+        Self {
+            tx_hash: entry.tx_hash,
+            transaction: entry.transaction,
+            error: entry.error,
+            block_height: entry.block_height,
+            block_hash: entry.block_hash,
+            tx_index: entry.tx_index,
+            failed_at: entry.failed_at,
+            attempts: entry.attempts,
+            error_code: 0,
+            error_params: BTreeMap::new(),
+        }
+    }
+}
+
+/// Schema 1: rewrite dead letters without `error_code`/`error_params`. Entries readable in
+/// neither layout are left for startup to drop.
+fn dead_letter_error_codes(storage: &dyn Storage, dry_run: bool) -> Result<u64> {
+    let tree = storage.open_tree(DEAD_LETTERS_TREE)?;
+    let mut batch = WriteBatch::default();
+    for item in tree.iter() {
+        let (key, value) = item?;
+        if bincode::deserialize::<DeadLetterEntry>(&value).is_ok() {
+            continue;
+        }
+        if let Ok(legacy) = bincode::deserialize::<LegacyDeadLetterEntry>(&value) {
+            let entry = DeadLetterEntry::from(legacy);
+            batch.insert(key, bincode::serialize(&entry).map_err(|e| HazeError::Serialization(e.to_string()))?);
+        }
+    }
+    let rewritten = batch.len() as u64;
+    if !dry_run && !batch.is_empty() {
+        tree.apply_batch(batch)?;
+    }
+    Ok(rewritten)
+}
+
+/// Schema 2: re-encode blocks stored as plain bincode or in an older wire version, with their
+/// checksums. A block that does not match its checksum or does not decode is left for the
+/// integrity check to report.
+fn blocks_current_wire(storage: &dyn Storage, dry_run: bool) -> Result<u64> {
+    let blocks = storage.open_tree(BLOCKS_TREE)?;
+    let checksums = storage.open_tree(BLOCK_CHECKSUMS_TREE)?;
+    let mut block_batch = WriteBatch::default();
+    let mut checksum_batch = WriteBatch::default();
+    let mut rewritten = 0u64;
+    for item in blocks.iter() {
+        let (key, value) = item?;
+        if value.starts_with(&WIRE_MAGIC) && value.get(WIRE_MAGIC.len()) == Some(&WIRE_VERSION) {
+            continue;
+        }
+        if checksums.get(&key)?.is_some_and(|sum| sum[..] != block_checksum(&value)[..]) {
+            continue;
+        }
+        let Ok(block) = decode_block(&value) else {
+            continue;
+        };
+        rewritten += 1;
+        if dry_run {
+            continue;
+        }
+        let encoded = block.to_wire();
+        checksum_batch.insert(&key, block_checksum(&encoded).to_vec());
+        block_batch.insert(&key, encoded);
+        if block_batch.len() == MIGRATION_BATCH_SIZE {
+            storage.apply_atomic(&[(BLOCKS_TREE, &block_batch), (BLOCK_CHECKSUMS_TREE, &checksum_batch)])?;
+            block_batch = WriteBatch::default();
+            checksum_batch = WriteBatch::default();
+        }
+    }
+    if !block_batch.is_empty() {
+        storage.apply_atomic(&[(BLOCKS_TREE, &block_batch), (BLOCK_CHECKSUMS_TREE, &checksum_batch)])?;
+    }
+    Ok(rewritten)
+}
+
+/// `haze migrate-schema`: upgrade a node database offline, or report what would change
+pub fn run_cli(args: &[String]) -> Result<()> {
+    let usage = "usage: haze migrate-schema --db <db_path> [--backend sled|rocksdb] [--dry-run] [--no-backup]";
+    let mut db_path: Option<PathBuf> = None;
+    let mut backend = StorageBackend::Sled;
+    let mut dry_run = false;
+    let mut migration_backup = true;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().cloned()
+            .ok_or_else(|| HazeError::Config(format!("Missing value for {}", arg)));
+        match arg.as_str() {
+            "--db" => db_path = Some(PathBuf::from(value()?)),
+            "--backend" => backend = value()?.parse()?,
+            "--dry-run" => dry_run = true,
+            "--no-backup" => migration_backup = false,
+            other => return Err(HazeError::Config(format!("Unknown argument '{}' ({})", other, usage))),
+        }
+    }
+    let Some(db_path) = db_path else {
+        return Err(HazeError::Config(format!("--db is required ({})", usage)));
+    };
+    let config = StorageConfig {
+        db_path,
+        backend,
+        migration_backup,
+        ..crate::config::Config::default().storage
+    };
+    let storage = open_backend(config.backend, &config.db_path)?;
+    let report = migrate(storage.as_ref(), &config, dry_run)?;
+    if let Some(backup) = &report.backup {
+        println!("Backed up {} to {}", config.db_path.display(), backup.display());
+    }
+    for step in &report.steps {
+        println!(
+            "{} schema {} ({}: {}): {} entries",
+            if dry_run { "Would migrate to" } else { "Migrated to" },
+            step.version,
+            step.name,
+            MIGRATIONS[step.version as usize - 1].description,
+            step.entries
+        );
+    }
+    println!("Schema version {} -> {}", report.from_version, report.to_version);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_store::block_key;
+    use crate::storage::SledStorage;
+    use crate::types::{Block, WIRE_HEADER_SIZE};
+
+    fn config(dir: &Path) -> StorageConfig {
+        StorageConfig { db_path: dir.join("db"), ..crate::config::Config::default().storage }
+    }
+
+    /// Block stored as plain bincode in the version-1 layout, as before the wire format
+    fn pre_wire_block() -> Vec<u8> {
+        let golden = hex::decode(include_str!("../tests/golden/block_v1.hex").trim()).unwrap();
+        golden[WIRE_HEADER_SIZE..].to_vec()
+    }
+
+    fn legacy_dead_letter() -> Vec<u8> {
+        #[derive(Serialize)]
+        struct Legacy {
+            tx_hash: Hash,
+            transaction: Transaction,
+            error: String,
+            block_height: u64,
+            block_hash: Hash,
+            tx_index: usize,
+            failed_at: i64,
+            attempts: u32,
+        }
+        bincode::serialize(&Legacy {
+            tx_hash: [1; 32],
+            transaction: Transaction::Transfer {
+                from: [3; 32],
+                to: [4; 32],
+                amount: 5,
+                fee: 1,
+                nonce: 0,
+                chain_id: None,
+                valid_until_height: None,
+                signature: Vec::new(),
+            },
+            error: "insufficient balance".to_string(),
+            block_height: 3,
+            block_hash: [2; 32],
+            tx_index: 0,
+            failed_at: 100,
+            attempts: 1,
+        })
+        .unwrap()
+    }
+
+    fn legacy_database(storage: &dyn Storage) -> Vec<u8> {
+        let block = pre_wire_block();
+        storage.open_tree(BLOCKS_TREE).unwrap().insert(&block_key(1), &block).unwrap();
+        storage.open_tree(BLOCK_CHECKSUMS_TREE).unwrap().insert(&block_key(1), &block_checksum(&block)).unwrap();
+        storage.open_tree(DEAD_LETTERS_TREE).unwrap().insert(&[1; 32], &legacy_dead_letter()).unwrap();
+        block
+    }
+
+    #[test]
+    fn test_steps_are_consecutive() {
+        for (i, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version, i as u32 + 1);
+        }
+    }
+
+    #[test]
+    fn test_migrate_legacy_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = SledStorage::temporary().unwrap();
+        let old_block = legacy_database(&storage);
+        assert_eq!(schema_version(&storage).unwrap(), Some(0));
+
+        // A dry run counts what would change and writes nothing
+        let report = migrate(&storage, &config(dir.path()), true).unwrap();
+        assert_eq!(report.steps.iter().map(|s| s.entries).collect::<Vec<_>>(), [1, 1]);
+        assert!(report.backup.is_none());
+        assert_eq!(schema_version(&storage).unwrap(), Some(0));
+        assert_eq!(storage.open_tree(BLOCKS_TREE).unwrap().get(&block_key(1)).unwrap().unwrap(), old_block);
+
+        let report = migrate(&storage, &config(dir.path()), false).unwrap();
+        assert_eq!((report.from_version, report.to_version), (0, SCHEMA_VERSION));
+        assert_eq!(schema_version(&storage).unwrap(), Some(SCHEMA_VERSION));
+
+        let stored = storage.open_tree(BLOCKS_TREE).unwrap().get(&block_key(1)).unwrap().unwrap();
+        assert_eq!(stored[WIRE_MAGIC.len()], WIRE_VERSION);
+        let (migrated, old) = (Block::from_wire(&stored).unwrap(), decode_block(&old_block).unwrap());
+        assert_eq!(migrated.header.hash, old.header.hash);
+        assert_eq!((migrated.header.height, migrated.header.protocol_version), (old.header.height, 1));
+        let checksum = storage.open_tree(BLOCK_CHECKSUMS_TREE).unwrap().get(&block_key(1)).unwrap().unwrap();
+        assert_eq!(checksum[..], block_checksum(&stored)[..]);
+        let letter = storage.open_tree(DEAD_LETTERS_TREE).unwrap().get(&[1; 32]).unwrap().unwrap();
+        let letter: DeadLetterEntry = bincode::deserialize(&letter).unwrap();
+        assert_eq!((letter.attempts, letter.error_code), (1, 0));
+
+        // The backup holds the database as it was
+        let backup = open_backend(StorageBackend::Sled, report.backup.as_ref().unwrap()).unwrap();
+        assert_eq!(schema_version(backup.as_ref()).unwrap(), Some(0));
+        assert_eq!(backup.open_tree(BLOCKS_TREE).unwrap().get(&block_key(1)).unwrap().unwrap(), old_block);
+
+        // Nothing left to do
+        assert!(migrate(&storage, &config(dir.path()), false).unwrap().steps.is_empty());
+    }
+
+    #[test]
+    fn test_new_database_is_stamped_and_newer_schema_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = SledStorage::temporary().unwrap();
+        assert_eq!(schema_version(&storage).unwrap(), None);
+        let report = migrate(&storage, &config(dir.path()), false).unwrap();
+        assert!(report.steps.is_empty() && report.backup.is_none());
+        assert_eq!(schema_version(&storage).unwrap(), Some(SCHEMA_VERSION));
+
+        set_schema_version(&storage, SCHEMA_VERSION + 1).unwrap();
+        let err = migrate(&storage, &config(dir.path()), false).unwrap_err();
+        assert!(err.to_string().contains("newer than this build supports"));
+    }
+}
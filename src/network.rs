@@ -7,13 +7,13 @@
 
 use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::time::Duration;
 use tokio::sync::mpsc;
 use futures::StreamExt;
 use libp2p::{
     identity,
-    swarm::{Swarm, SwarmEvent, NetworkBehaviour},
+    swarm::{dial_opts::DialOpts, ConnectionId, Swarm, SwarmEvent, NetworkBehaviour},
     SwarmBuilder,
     PeerId, Multiaddr,
     noise,
@@ -22,12 +22,14 @@ use libp2p::{
 };
 use libp2p_request_response::{
     Behaviour as RequestResponse, Config as RequestResponseConfig, Codec as RequestResponseCodec, 
-    ProtocolSupport,
+    OutboundRequestId, ProtocolSupport,
 };
 use crate::config::Config;
 use crate::consensus::ConsensusEngine;
 use crate::error::{HazeError, Result as HazeResult};
-use crate::types::{Block, Transaction, Hash, hash_to_hex};
+use crate::messaging::{Mailbox, SealedMessage};
+use crate::types::{Block, Transaction, Hash, WireFormat, WIRE_MAGIC, hash_to_hex};
+use crate::version::{PeerCompatibility, PeerVersion};
 
 /// Network event
 #[derive(Debug, Clone)]
@@ -42,6 +44,10 @@ pub enum NetworkEvent {
 const BLOCKS_PROTOCOL_NAME: &[u8] = b"/haze/blocks/1.0.0";
 /// Protocol name for transactions
 const TRANSACTIONS_PROTOCOL_NAME: &[u8] = b"/haze/transactions/1.0.0";
+/// Protocol name for encrypted direct messages
+const MESSAGES_PROTOCOL_NAME: &[u8] = b"/haze/messages/1.0.0";
+/// Protocol name for keep-alive pings (see `peer_health`)
+const KEEPALIVE_PROTOCOL_NAME: &[u8] = b"/haze/keepalive/1.0.0";
 
 /// Batch size for catch-up sync (blocks per request)
 const SYNC_BATCH_SIZE: u64 = 100;
@@ -57,6 +63,14 @@ pub enum HazeRequest {
     RequestBlockByHash(Hash),
     /// Request blockchain info (for light sync)
     RequestBlockchainInfo,
+    /// Request the peer's wall clock (for drift detection)
+    RequestTime,
+    /// Request the peer's protocol and state versions (handshake)
+    RequestVersion,
+    /// Encrypted direct message to relay and store (messages protocol)
+    Message(SealedMessage),
+    /// Keep-alive ping carrying a nonce to echo (keep-alive protocol)
+    Ping(u64),
 }
 
 /// Blockchain info for P2P (lightweight version)
@@ -74,18 +88,26 @@ pub enum HazeResponse {
     BlockAck,
     TransactionAck,
     /// Response with blocks for sync
-    Blocks(Vec<Block>),
+    Blocks(#[serde(with = "crate::types::wire_bytes_vec")] Vec<Block>),
     /// Response with single block
-    Block(Block),
+    Block(#[serde(with = "crate::types::wire_bytes")] Block),
     /// Response with blockchain info (for light sync)
     BlockchainInfo(PeerBlockchainInfo),
     Error(String),
+    /// Peer wall clock in milliseconds since the Unix epoch
+    Time(i64),
+    /// Peer protocol and state versions
+    Version(PeerVersion),
+    MessageAck,
+    /// Answer to a keep-alive ping with its nonce
+    Pong(u64),
 }
 
-/// Codec for blocks and transactions using bincode
+/// Codec for blocks and transactions
 /// 
-/// Implements RequestResponseCodec for serialization/deserialization
-/// using bincode format with length-prefixed encoding
+/// Implements RequestResponseCodec with length-prefixed messages. Blocks and
+/// transactions use the canonical wire format (`types::WireFormat`); other
+/// messages use tag bytes or bincode
 #[derive(Clone, Default)]
 pub struct HazeCodec {
     protocol: Vec<u8>,
@@ -107,7 +129,7 @@ impl RequestResponseCodec for HazeCodec {
     type Request = HazeRequest;
     type Response = HazeResponse;
 
-    async fn read_request<T>(&mut self, _: &Self::Protocol, io: &mut T) -> std::io::Result<Self::Request>
+    async fn read_request<T>(&mut self, protocol: &Self::Protocol, io: &mut T) -> std::io::Result<Self::Request>
     where
         T: futures::AsyncRead + Unpin + Send,
     {
@@ -123,10 +145,22 @@ impl RequestResponseCodec for HazeCodec {
         io.read_exact(&mut buffer).await?;
         
         // Deserialize based on protocol
+        if protocol.as_bytes() == MESSAGES_PROTOCOL_NAME {
+            let message = bincode::deserialize(&buffer)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+            return Ok(HazeRequest::Message(message));
+        }
+        if protocol.as_bytes() == KEEPALIVE_PROTOCOL_NAME {
+            let nonce = buffer.as_slice().try_into()
+                .map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidData, "Invalid ping format"))?;
+            return Ok(HazeRequest::Ping(u64::from_le_bytes(nonce)));
+        }
         let protocol_str = String::from_utf8_lossy(&self.protocol);
         if protocol_str.as_ref() == String::from_utf8_lossy(BLOCKS_PROTOCOL_NAME).as_ref() {
-            // Try to deserialize as Block first (for backward compatibility)
-            if let Ok(block) = bincode::deserialize::<Block>(&buffer) {
+            // Gossiped blocks use the wire format; sync requests are tagged by their first byte
+            if buffer.starts_with(&WIRE_MAGIC) {
+                let block = Block::from_wire(&buffer)
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))?;
                 Ok(HazeRequest::Block(block))
             } else {
                 // Try sync request format: first byte is request type
@@ -158,6 +192,14 @@ impl RequestResponseCodec for HazeCodec {
                         // RequestBlockchainInfo: (3u8)
                         Ok(HazeRequest::RequestBlockchainInfo)
                     }
+                    4 => {
+                        // RequestTime: (4u8)
+                        Ok(HazeRequest::RequestTime)
+                    }
+                    5 => {
+                        // RequestVersion: (5u8)
+                        Ok(HazeRequest::RequestVersion)
+                    }
                     _ => {
                         // Fallback: try Block again
                         Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "Unknown request type"))
@@ -165,8 +207,8 @@ impl RequestResponseCodec for HazeCodec {
                 }
             }
         } else if protocol_str.as_ref() == String::from_utf8_lossy(TRANSACTIONS_PROTOCOL_NAME).as_ref() {
-            let tx: Transaction = bincode::deserialize(&buffer)
-                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+            let tx = Transaction::from_wire(&buffer)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))?;
             Ok(HazeRequest::Transaction(tx))
         } else {
             Err(std::io::Error::new(
@@ -203,10 +245,8 @@ impl RequestResponseCodec for HazeCodec {
         use futures::AsyncWriteExt;
         
         let data = match request {
-            HazeRequest::Block(block) => bincode::serialize(&block)
-                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?,
-            HazeRequest::Transaction(tx) => bincode::serialize(&tx)
-                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?,
+            HazeRequest::Block(block) => block.to_wire(),
+            HazeRequest::Transaction(tx) => tx.to_wire(),
             HazeRequest::RequestBlocksByHeight { start_height, end_height } => {
                 // Serialize as (1u8, start_height: u64, end_height: u64)
                 let mut data = vec![1u8];
@@ -224,6 +264,17 @@ impl RequestResponseCodec for HazeCodec {
                 // Serialize as (3u8)
                 vec![3u8]
             }
+            HazeRequest::RequestTime => {
+                // Serialize as (4u8)
+                vec![4u8]
+            }
+            HazeRequest::RequestVersion => {
+                // Serialize as (5u8)
+                vec![5u8]
+            }
+            HazeRequest::Message(message) => bincode::serialize(&message)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?,
+            HazeRequest::Ping(nonce) => nonce.to_le_bytes().to_vec(),
         };
         
         // Write length prefix
@@ -265,6 +316,8 @@ pub struct HazeBehaviour {
     pub ping: libp2p::ping::Behaviour,
     pub blocks: RequestResponse<HazeCodec>,
     pub transactions: RequestResponse<HazeCodec>,
+    pub messages: RequestResponse<HazeCodec>,
+    pub keepalive: RequestResponse<HazeCodec>,
 }
 
 impl HazeBehaviour {
@@ -287,6 +340,20 @@ impl HazeBehaviour {
             transactions_config,
         );
 
+        // Configure messages protocol
+        let messages_protocol_name = String::from_utf8_lossy(MESSAGES_PROTOCOL_NAME).to_string();
+        let messages: RequestResponse<HazeCodec> = RequestResponse::new(
+            [(messages_protocol_name, ProtocolSupport::Full)],
+            RequestResponseConfig::default(),
+        );
+
+        // Configure keep-alive protocol (peers without it are tracked through transport pings)
+        let keepalive_protocol_name = String::from_utf8_lossy(KEEPALIVE_PROTOCOL_NAME).to_string();
+        let keepalive: RequestResponse<HazeCodec> = RequestResponse::new(
+            [(keepalive_protocol_name, ProtocolSupport::Full)],
+            RequestResponseConfig::default(),
+        );
+
         Self {
             ping: libp2p::ping::Behaviour::new(
                 libp2p::ping::Config::new()
@@ -295,6 +362,8 @@ impl HazeBehaviour {
             ),
             blocks,
             transactions,
+            messages,
+            keepalive,
         }
     }
 }
@@ -313,6 +382,22 @@ pub struct Network {
     sync_target_height: Option<u64>,
     /// Peer we're requesting blocks from during catch-up (same or fallback)
     sync_peer_id: Option<PeerId>,
+    /// Local send time (ms) of outstanding time requests, for clock offset estimates
+    pending_time_requests: HashMap<OutboundRequestId, i64>,
+    /// Outstanding version handshakes; a failed one means the peer predates the handshake
+    pending_version_requests: HashMap<OutboundRequestId, PeerId>,
+    /// Peers that cannot decode our blocks (older protocol or state format)
+    degraded_peers: HashSet<PeerId>,
+    /// Mailbox storing relayed direct messages (`None` unless `messaging.enabled`)
+    mailbox: Option<Arc<Mailbox>>,
+    /// Messages submitted through the API, to relay to peers
+    outbound_messages: Option<tokio::sync::broadcast::Receiver<SealedMessage>>,
+    /// Outstanding keep-alive pings: nonce and local send time (ms)
+    pending_pings: HashMap<OutboundRequestId, (u64, i64)>,
+    /// Nonce of the last keep-alive ping sent
+    ping_nonce: u64,
+    /// Addresses of dials in progress, to redial them if they fail or later disconnect
+    dialed_addresses: HashMap<ConnectionId, String>,
 }
 
 impl Network {
@@ -368,6 +453,14 @@ impl Network {
             connected_peers_shared,
             sync_target_height: None,
             sync_peer_id: None,
+            pending_time_requests: HashMap::new(),
+            pending_version_requests: HashMap::new(),
+            degraded_peers: HashSet::new(),
+            mailbox: None,
+            outbound_messages: None,
+            pending_pings: HashMap::new(),
+            ping_nonce: 0,
+            dialed_addresses: HashMap::new(),
         };
 
         // Start listening
@@ -378,17 +471,9 @@ impl Network {
         if !config.network.bootstrap_nodes.is_empty() {
             tracing::info!("Connecting to {} bootstrap node(s)...", config.network.bootstrap_nodes.len());
             for bootstrap_addr_str in &config.network.bootstrap_nodes {
-                match bootstrap_addr_str.parse::<Multiaddr>() {
-                    Ok(addr) => {
-                        if let Err(e) = network.swarm.dial(addr) {
-                            tracing::warn!("Failed to dial bootstrap node {}: {}", bootstrap_addr_str, e);
-                        } else {
-                            tracing::info!("Dialing bootstrap node: {}", bootstrap_addr_str);
-                        }
-                    }
-                    Err(e) => {
-                        tracing::warn!("Invalid bootstrap address {}: {}", bootstrap_addr_str, e);
-                    }
+                match network.dial_address(bootstrap_addr_str) {
+                    Ok(()) => tracing::info!("Dialing bootstrap node: {}", bootstrap_addr_str),
+                    Err(e) => tracing::warn!("Failed to dial bootstrap node {}: {}", bootstrap_addr_str, e),
                 }
             }
         }
@@ -397,13 +482,27 @@ impl Network {
         Ok(network)
     }
 
+    /// Store and relay direct messages through `mailbox`
+    pub fn set_mailbox(&mut self, mailbox: Arc<Mailbox>) {
+        self.outbound_messages = Some(mailbox.subscribe());
+        self.mailbox = Some(mailbox);
+    }
+
     /// Start network event loop
     pub async fn run(&mut self) -> HazeResult<()> {
         tracing::info!("Network event loop started");
         
-        // Periodic light sync check (every 30 seconds)
-        let mut light_sync_interval = tokio::time::interval(Duration::from_secs(30));
+        // Periodic light sync check (every 30 seconds; read replicas follow the primary's tip
+        // every `replica.poll_interval_ms`)
+        let light_sync_period = if self.config.replica.enabled {
+            Duration::from_millis(self.config.replica.poll_interval_ms)
+        } else {
+            Duration::from_secs(30)
+        };
+        let mut light_sync_interval = tokio::time::interval(light_sync_period);
         light_sync_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        let mut keepalive_interval = tokio::time::interval(Duration::from_millis(self.config.network.ping_interval_ms));
+        keepalive_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
         
         loop {
             tokio::select! {
@@ -418,11 +517,20 @@ impl Network {
                         break;
                     }
                 }
+                message = next_outbound_message(&mut self.outbound_messages) => {
+                    if let Some(message) = message {
+                        self.relay_message(&message, None);
+                    }
+                }
                 _ = light_sync_interval.tick() => {
                     // Periodic light sync check
                     if let Err(e) = self.perform_light_sync().await {
                         tracing::warn!("Light sync check failed: {}", e);
                     }
+                    self.request_peer_times();
+                }
+                _ = keepalive_interval.tick() => {
+                    self.keep_alive();
                 }
                 _ = tokio::signal::ctrl_c() => {
                     tracing::info!("Shutdown signal received");
@@ -444,17 +552,42 @@ impl Network {
             SwarmEvent::NewListenAddr { address, .. } => {
                 tracing::info!("Listening on {}", address);
             }
-            SwarmEvent::ConnectionEstablished { peer_id, .. } => {
+            SwarmEvent::ConnectionEstablished { peer_id, connection_id, endpoint, num_established, .. } => {
+                let dialed_address = self.dialed_addresses.remove(&connection_id);
+                if num_established.get() > 1 {
+                    tracing::debug!("Additional connection to peer {}", peer_id);
+                    return Ok(());
+                }
                 tracing::info!("Connected to peer: {}", peer_id);
                 self.connected_peers.insert(peer_id);
                 if let Some(ref c) = self.connected_peers_shared {
-                    c.fetch_add(1, Ordering::Relaxed);
+                    c.store(self.connected_peers.len(), Ordering::Relaxed);
                 }
+                let address = dialed_address.or_else(|| Some(endpoint.get_remote_address().to_string()));
+                self.consensus.peer_health().connected(
+                    peer_id.to_string(),
+                    address,
+                    endpoint.is_dialer(),
+                    crate::time_sync::now_ms(),
+                );
+                let request_id = self.swarm.behaviour_mut().blocks.send_request(&peer_id, HazeRequest::RequestVersion);
+                self.pending_version_requests.insert(request_id, peer_id);
                 let _ = self.event_sender.send(NetworkEvent::PeerConnected(peer_id.to_string()));
             }
-            SwarmEvent::ConnectionClosed { peer_id, .. } => {
+            SwarmEvent::ConnectionClosed { peer_id, num_established, .. } => {
+                if num_established > 0 {
+                    tracing::debug!("Closed one of several connections to peer {}", peer_id);
+                    return Ok(());
+                }
                 tracing::info!("Disconnected from peer: {}", peer_id);
+                if let Some(due_ms) = self.consensus.peer_health().disconnected(&peer_id.to_string(), crate::time_sync::now_ms()) {
+                    tracing::info!("Redialing peer {} in {}ms", peer_id, due_ms - crate::time_sync::now_ms());
+                }
                 self.connected_peers.remove(&peer_id);
+                self.degraded_peers.remove(&peer_id);
+                self.consensus.remove_peer_clock(&peer_id.to_string());
+                self.consensus.remove_peer_height(&peer_id.to_string());
+                self.consensus.peer_versions().remove(&peer_id.to_string());
                 if self.sync_peer_id == Some(peer_id) {
                     self.sync_peer_id = self.connected_peers.iter().next().cloned();
                     if self.sync_peer_id.is_none() {
@@ -462,7 +595,7 @@ impl Network {
                     }
                 }
                 if let Some(ref c) = self.connected_peers_shared {
-                    c.fetch_sub(1, Ordering::Relaxed);
+                    c.store(self.connected_peers.len(), Ordering::Relaxed);
                 }
                 let _ = self.event_sender.send(NetworkEvent::PeerDisconnected(peer_id.to_string()));
             }
@@ -472,8 +605,12 @@ impl Network {
             SwarmEvent::IncomingConnectionError { error, .. } => {
                 tracing::warn!("Incoming connection error: {}", error);
             }
-            SwarmEvent::OutgoingConnectionError { peer_id, error, .. } => {
+            SwarmEvent::OutgoingConnectionError { peer_id, connection_id, error } => {
                 tracing::warn!("Outgoing connection error to {}: {}", peer_id.map(|p| p.to_string()).unwrap_or_else(|| "unknown".to_string()), error);
+                if let Some(address) = self.dialed_addresses.remove(&connection_id) {
+                    let due_ms = self.consensus.peer_health().schedule_reconnect(address.clone(), crate::time_sync::now_ms());
+                    tracing::info!("Redialing {} in {}ms", address, due_ms - crate::time_sync::now_ms());
+                }
             }
             SwarmEvent::Dialing { peer_id, .. } => {
                 tracing::debug!("Dialing peer: {}", peer_id.map(|p| p.to_string()).unwrap_or_else(|| "unknown".to_string()));
@@ -491,11 +628,31 @@ impl Network {
 
     /// Handle behaviour events (ping, request-response)
     async fn handle_behaviour_event(&mut self, event: HazeBehaviourEvent) -> HazeResult<()> {
+        if let Some(peer) = message_peer(&event) {
+            self.consensus.peer_health().seen(&peer.to_string(), crate::time_sync::now_ms());
+        }
         match event {
-            HazeBehaviourEvent::Ping(ping_event) => {
-                // Handle ping events if needed
-                tracing::debug!("Ping event: {:?}", ping_event);
+            HazeBehaviourEvent::Ping(libp2p::ping::Event { peer, result, .. }) => {
+                // Transport pings keep the connection open; they measure liveness only for
+                // peers without the keep-alive protocol
+                let health = self.consensus.peer_health();
+                let peer = peer.to_string();
+                match result {
+                    Ok(rtt) => {
+                        health.seen(&peer, crate::time_sync::now_ms());
+                        if !health.uses_keepalive(&peer) {
+                            health.record_pong(&peer, rtt.as_millis() as u64, crate::time_sync::now_ms());
+                        }
+                    }
+                    Err(e) => {
+                        tracing::debug!("Transport ping to {} failed: {}", peer, e);
+                        if !health.uses_keepalive(&peer) {
+                            health.record_missed_ping(&peer);
+                        }
+                    }
+                }
             }
+            HazeBehaviourEvent::Keepalive(event) => self.handle_keepalive_event(event),
             HazeBehaviourEvent::Blocks(libp2p::request_response::Event::Message { message, peer }) => {
                 match message {
                     libp2p::request_response::Message::Request { request, channel, .. } => {
@@ -505,6 +662,7 @@ impl Network {
                                 let block_hash = hash_to_hex(&block.header.hash);
                                 tracing::info!("Received block from peer: height={}, hash={}", 
                                     block_height, &block_hash[..16]);
+                                self.consensus.record_peer_height(peer.to_string(), block_height);
                                 
                                 // Forward to consensus engine
                                 match self.consensus.process_block(&block) {
@@ -519,12 +677,16 @@ impl Network {
                                         
                                         // Broadcast to other peers (gossip protocol)
                                         let block_for_broadcast = block.clone();
-                                        let peers_to_broadcast: Vec<_> = self.connected_peers.iter().collect();
+                                        let peers_to_broadcast: Vec<_> = if self.consensus.shares_blocks() {
+                                            self.block_peers()
+                                        } else {
+                                            Vec::new()
+                                        };
                                         if !peers_to_broadcast.is_empty() {
                                             tracing::debug!("Broadcasting block to {} peer(s)", peers_to_broadcast.len());
                                             for peer_id in peers_to_broadcast {
                                                 let request = HazeRequest::Block(block_for_broadcast.clone());
-                                                let _ = self.swarm.behaviour_mut().blocks.send_request(peer_id, request);
+                                                let _ = self.swarm.behaviour_mut().blocks.send_request(&peer_id, request);
                                             }
                                         }
                                     }
@@ -574,16 +736,26 @@ impl Network {
                                     }
                                 }
                             }
+                            HazeRequest::RequestBlocksByHeight { .. } | HazeRequest::RequestBlockByHash(_)
+                                if self.degraded_peers.contains(&peer) =>
+                            {
+                                let _ = self.swarm.behaviour_mut().blocks.send_response(
+                                    channel,
+                                    HazeResponse::Error("Block format not supported by your protocol version".to_string()),
+                                );
+                            }
                             HazeRequest::RequestBlocksByHeight { start_height, end_height } => {
                                 tracing::info!("Sync request: blocks from height {} to {}", start_height, end_height);
                                 
-                                // Get blocks from state
+                                // Get blocks from state (none while withholding blocks)
                                 let mut blocks = Vec::new();
                                 let state = self.consensus.state();
-                                for height in start_height..=end_height.min(state.current_height()) {
-                                    if let Some(block) = state.get_block_by_height(height) {
-                                        blocks.push(block);
-                                    }
+                                if self.consensus.shares_blocks() {
+                                    blocks = state
+                                        .get_blocks_by_height_range(start_height, end_height.min(state.current_height()))
+                                        .into_iter()
+                                        .map(Arc::unwrap_or_clone)
+                                        .collect();
                                 }
                                 
                                 tracing::info!("Sending {} blocks for sync (heights {}-{})", blocks.len(), start_height, end_height);
@@ -596,10 +768,10 @@ impl Network {
                                 tracing::debug!("Sync request: block by hash {}", hash_to_hex(&hash));
                                 
                                 let state = self.consensus.state();
-                                if let Some(block) = state.get_block(&hash) {
+                                if let Some(block) = state.get_block(&hash).filter(|_| self.consensus.shares_blocks()) {
                                     let _ = self.swarm.behaviour_mut().blocks.send_response(
                                         channel,
-                                        HazeResponse::Block(block),
+                                        HazeResponse::Block(Arc::unwrap_or_clone(block)),
                                     );
                                 } else {
                                     let _ = self.swarm.behaviour_mut().blocks.send_response(
@@ -629,9 +801,25 @@ impl Network {
                                     HazeResponse::BlockchainInfo(info),
                                 );
                             }
+                            HazeRequest::RequestTime => {
+                                let _ = self.swarm.behaviour_mut().blocks.send_response(
+                                    channel,
+                                    HazeResponse::Time(crate::time_sync::now_ms()),
+                                );
+                            }
+                            HazeRequest::RequestVersion => {
+                                let local = self.consensus.peer_versions().local().clone();
+                                let _ = self.swarm.behaviour_mut().blocks.send_response(
+                                    channel,
+                                    HazeResponse::Version(local),
+                                );
+                            }
+                            HazeRequest::Message(_) | HazeRequest::Ping(_) => {
+                                tracing::warn!("Received direct message or ping on blocks protocol; ignoring");
+                            }
                         }
                     }
-                    libp2p::request_response::Message::Response { response, .. } => {
+                    libp2p::request_response::Message::Response { request_id, response } => {
                         match response {
                             HazeResponse::BlockAck => {
                                 tracing::debug!("Received block acknowledgment");
@@ -641,6 +829,9 @@ impl Network {
                             }
                             HazeResponse::Blocks(blocks) => {
                                 tracing::info!("Received {} blocks for sync", blocks.len());
+                                if let Some(last) = blocks.last() {
+                                    self.consensus.record_peer_height(peer.to_string(), last.header.height);
+                                }
                                 // Process received blocks
                                 for block in &blocks {
                                     if let Err(e) = self.consensus.process_block(block) {
@@ -680,6 +871,7 @@ impl Network {
                             HazeResponse::BlockchainInfo(info) => {
                                 tracing::debug!("Received blockchain info from {}: height={}, finalized_height={}, finalized_wave={}", 
                                     peer, info.current_height, info.last_finalized_height, info.last_finalized_wave);
+                                self.consensus.record_peer_height(peer.to_string(), info.current_height);
                                 
                                 // Perform light sync comparison
                                 let state = self.consensus.state();
@@ -704,8 +896,8 @@ impl Network {
                                 // Compare state roots at finalized checkpoint
                                 if info.last_finalized_height > 0 && local_finalized_height > 0 {
                                     // Get state root at finalized height from local state
-                                    if let Some(finalized_block) = state.get_block_by_height(local_finalized_height) {
-                                        let local_checkpoint_state_root = finalized_block.header.state_root;
+                                    if let Some(finalized_header) = state.get_block_header_by_height(local_finalized_height) {
+                                        let local_checkpoint_state_root = finalized_header.state_root;
                                         
                                         // If peer's finalized height matches ours, compare state roots
                                         if info.last_finalized_height == local_finalized_height {
@@ -726,8 +918,30 @@ impl Network {
                                 }
                             }
                             HazeResponse::Error(msg) => {
+                                self.pending_time_requests.remove(&request_id);
                                 tracing::warn!("Received error response: {}", msg);
                             }
+                            HazeResponse::Time(peer_time_ms) => {
+                                if let Some(sent_ms) = self.pending_time_requests.remove(&request_id) {
+                                    let offset = crate::time_sync::estimate_offset(
+                                        sent_ms,
+                                        peer_time_ms,
+                                        crate::time_sync::now_ms(),
+                                    );
+                                    tracing::debug!("Clock offset to {}: {}ms (rtt {}ms)", peer, offset.offset_ms, offset.rtt_ms);
+                                    self.consensus.record_peer_clock_offset(peer.to_string(), offset);
+                                }
+                            }
+                            HazeResponse::Version(version) => {
+                                self.pending_version_requests.remove(&request_id);
+                                self.record_peer_version(peer, version);
+                            }
+                            HazeResponse::MessageAck => {
+                                tracing::debug!("Received message acknowledgment");
+                            }
+                            HazeResponse::Pong(_) => {
+                                tracing::warn!("Received pong on blocks protocol; ignoring");
+                            }
                         }
                     }
                 }
@@ -792,7 +1006,11 @@ impl Network {
                             // Sync-related requests should not arrive on transactions protocol; ignore
                             HazeRequest::RequestBlocksByHeight { .. } 
                             | HazeRequest::RequestBlockByHash(_) 
-                            | HazeRequest::RequestBlockchainInfo => {
+                            | HazeRequest::RequestBlockchainInfo
+                            | HazeRequest::RequestTime
+                            | HazeRequest::RequestVersion
+                            | HazeRequest::Message(_)
+                            | HazeRequest::Ping(_) => {
                                 tracing::warn!("Received sync request on transactions protocol; ignoring");
                             }
                         }
@@ -808,7 +1026,11 @@ impl Network {
                             // Sync-related responses should not arrive on transactions protocol; ignore
                             HazeResponse::Blocks(_) 
                             | HazeResponse::Block(_) 
-                            | HazeResponse::BlockchainInfo(_) => {
+                            | HazeResponse::BlockchainInfo(_)
+                            | HazeResponse::Time(_)
+                            | HazeResponse::Version(_)
+                            | HazeResponse::MessageAck
+                            | HazeResponse::Pong(_) => {
                                 tracing::warn!("Received sync response on transactions protocol; ignoring");
                             }
                             HazeResponse::Error(msg) => {
@@ -818,7 +1040,27 @@ impl Network {
                     }
                 }
             }
+            HazeBehaviourEvent::Messages(libp2p::request_response::Event::Message { message, peer }) => {
+                if let libp2p::request_response::Message::Request { request, channel, .. } = message {
+                    let response = match request {
+                        HazeRequest::Message(message) => self.receive_message(message, peer),
+                        _ => HazeResponse::Error("Only direct messages are accepted on this protocol".to_string()),
+                    };
+                    let _ = self.swarm.behaviour_mut().messages.send_response(channel, response);
+                }
+            }
+            HazeBehaviourEvent::Messages(libp2p::request_response::Event::OutboundFailure { error, .. }) => {
+                tracing::debug!("Messages outbound failure: {:?}", error);
+            }
+            HazeBehaviourEvent::Messages(_) => {}
             HazeBehaviourEvent::Blocks(libp2p::request_response::Event::OutboundFailure { request_id, error, .. }) => {
+                self.pending_time_requests.remove(&request_id);
+                if let Some(peer) = self.pending_version_requests.remove(&request_id)
+                    && self.connected_peers.contains(&peer)
+                {
+                    // Nodes before the handshake reject the request
+                    self.record_peer_version(peer, PeerVersion::legacy(self.config.chain_id));
+                }
                 tracing::warn!("Blocks outbound failure (request {}): {:?}", request_id, error);
             }
             HazeBehaviourEvent::Transactions(libp2p::request_response::Event::OutboundFailure { request_id, error, .. }) => {
@@ -861,9 +1103,13 @@ impl Network {
 
     /// Broadcast block to all connected peers
     pub fn broadcast_block(&mut self, block: &Block) -> HazeResult<()> {
+        if !self.consensus.shares_blocks() {
+            tracing::debug!("Withholding block at height {} (byzantine test mode)", block.header.height);
+            return Ok(());
+        }
+        
         // Serialize block
-        let block_data = bincode::serialize(block)
-            .map_err(|e| HazeError::Serialization(format!("Failed to serialize block: {e}")))?;
+        let block_data = block.to_wire();
         
         tracing::debug!(
             "Broadcasting block: height = {}, size = {} bytes, peers = {}",
@@ -872,10 +1118,10 @@ impl Network {
             self.connected_peers.len()
         );
         
-        // Send to all connected peers using request-response protocol
+        // Send to all peers that can decode it using request-response protocol
         let request = HazeRequest::Block(block.clone());
-        for peer_id in &self.connected_peers {
-            let _request_id = self.swarm.behaviour_mut().blocks.send_request(peer_id, request.clone());
+        for peer_id in self.block_peers() {
+            let _request_id = self.swarm.behaviour_mut().blocks.send_request(&peer_id, request.clone());
             tracing::debug!("Sent block request to {}: request_id = {:?}", peer_id, _request_id);
         }
         
@@ -885,8 +1131,7 @@ impl Network {
     /// Broadcast transaction to all connected peers
     pub fn broadcast_transaction(&mut self, tx: &Transaction) -> HazeResult<()> {
         // Serialize transaction
-        let tx_data = bincode::serialize(tx)
-            .map_err(|e| HazeError::Serialization(format!("Failed to serialize transaction: {e}")))?;
+        let tx_data = tx.to_wire();
         
         tracing::debug!(
             "Broadcasting transaction: size = {} bytes, peers = {}",
@@ -904,6 +1149,147 @@ impl Network {
         Ok(())
     }
 
+    /// Store a direct message from `peer` and relay it on if it is new
+    fn receive_message(&mut self, message: SealedMessage, peer: PeerId) -> HazeResponse {
+        let Some(mailbox) = self.mailbox.clone() else {
+            return HazeResponse::Error("Messaging is disabled on this node".to_string());
+        };
+        match mailbox.accept(message.clone(), chrono::Utc::now().timestamp()) {
+            Ok(true) => {
+                self.relay_message(&message, Some(peer));
+                HazeResponse::MessageAck
+            }
+            Ok(false) => HazeResponse::MessageAck,
+            Err(e) => {
+                tracing::debug!("Rejected direct message from {}: {}", peer, e);
+                HazeResponse::Error(format!("Rejected message: {}", e))
+            }
+        }
+    }
+
+    /// Send a direct message to every connected peer but `from`
+    fn relay_message(&mut self, message: &SealedMessage, from: Option<PeerId>) {
+        let peers: Vec<PeerId> = self.connected_peers.iter().filter(|p| Some(**p) != from).cloned().collect();
+        for peer_id in peers {
+            self.swarm.behaviour_mut().messages.send_request(&peer_id, HazeRequest::Message(message.clone()));
+        }
+    }
+
+    /// Answer keep-alive pings and record the round trips of ours
+    fn handle_keepalive_event(&mut self, event: libp2p::request_response::Event<HazeRequest, HazeResponse>) {
+        use libp2p::request_response::{Event, Message, OutboundFailure};
+        let health = self.consensus.peer_health();
+        match event {
+            Event::Message { message: Message::Request { request, channel, .. }, .. } => {
+                let response = match request {
+                    HazeRequest::Ping(nonce) => HazeResponse::Pong(nonce),
+                    _ => HazeResponse::Error("Only pings are accepted on this protocol".to_string()),
+                };
+                let _ = self.swarm.behaviour_mut().keepalive.send_response(channel, response);
+            }
+            Event::Message { peer, message: Message::Response { request_id, response } } => {
+                let Some((nonce, sent_ms)) = self.pending_pings.remove(&request_id) else {
+                    return;
+                };
+                let now = crate::time_sync::now_ms();
+                match response {
+                    HazeResponse::Pong(echoed) if echoed == nonce => {
+                        health.record_pong(&peer.to_string(), (now - sent_ms).max(0) as u64, now);
+                    }
+                    other => tracing::debug!("Unexpected keep-alive answer from {}: {:?}", peer, other),
+                }
+            }
+            Event::OutboundFailure { peer, request_id, error } => {
+                self.pending_pings.remove(&request_id);
+                if matches!(error, OutboundFailure::UnsupportedProtocols) {
+                    tracing::debug!("Peer {} predates the keep-alive protocol; using transport pings", peer);
+                    health.disable_keepalive(&peer.to_string());
+                } else {
+                    tracing::debug!("Keep-alive ping to {} failed: {:?}", peer, error);
+                    health.record_missed_ping(&peer.to_string());
+                }
+            }
+            Event::InboundFailure { .. } | Event::ResponseSent { .. } => {}
+        }
+    }
+
+    /// Disconnect peers that stopped answering, ping the others and redial dropped
+    /// addresses that are due
+    fn keep_alive(&mut self) {
+        let consensus = self.consensus.clone();
+        let health = consensus.peer_health();
+        let now = crate::time_sync::now_ms();
+        for peer in health.dead_peers(now) {
+            let Ok(peer_id) = peer.parse::<PeerId>() else {
+                continue;
+            };
+            tracing::warn!("Disconnecting unresponsive peer {}", peer);
+            health.record_dead_disconnect();
+            if let Some(due_ms) = health.disconnected(&peer, now) {
+                tracing::info!("Redialing peer {} in {}ms", peer, due_ms - now);
+            }
+            let _ = self.swarm.disconnect_peer_id(peer_id);
+        }
+
+        let peers: Vec<PeerId> = self.connected_peers
+            .iter()
+            .filter(|peer| health.uses_keepalive(&peer.to_string()))
+            .cloned()
+            .collect();
+        for peer_id in peers {
+            self.ping_nonce += 1;
+            let request_id = self.swarm.behaviour_mut().keepalive.send_request(&peer_id, HazeRequest::Ping(self.ping_nonce));
+            self.pending_pings.insert(request_id, (self.ping_nonce, now));
+        }
+
+        for address in health.due_reconnects(now) {
+            tracing::info!("Redialing {}", address);
+            if let Err(e) = self.dial_address(&address) {
+                tracing::warn!("Failed to redial {}: {}", address, e);
+                health.schedule_reconnect(address, now);
+            }
+        }
+    }
+
+    /// Dial `address`, remembering it so the peer is redialed if the dial fails or the
+    /// connection later drops
+    fn dial_address(&mut self, address: &str) -> HazeResult<()> {
+        let addr: Multiaddr = address.parse()
+            .map_err(|e| HazeError::Network(format!("Invalid address {}: {}", address, e)))?;
+        let opts = DialOpts::unknown_peer_id().address(addr).build();
+        let connection_id = opts.connection_id();
+        self.swarm.dial(opts)
+            .map_err(|e| HazeError::Network(format!("Failed to dial peer: {}", e)))?;
+        self.dialed_addresses.insert(connection_id, address.to_string());
+        Ok(())
+    }
+
+    /// Connected peers that blocks are sent to
+    fn block_peers(&self) -> Vec<PeerId> {
+        self.connected_peers.difference(&self.degraded_peers).cloned().collect()
+    }
+
+    /// Apply a peer's handshake result: disconnect it, or stop sending it blocks
+    fn record_peer_version(&mut self, peer: PeerId, version: PeerVersion) {
+        match self.consensus.peer_versions().record(peer.to_string(), version.clone()) {
+            PeerCompatibility::Full => {
+                tracing::debug!("Peer {} speaks protocol {}", peer, version.protocol_version);
+                self.degraded_peers.remove(&peer);
+            }
+            PeerCompatibility::Degraded => {
+                tracing::warn!(
+                    "Peer {} speaks protocol {} (state version {}); not sending it blocks",
+                    peer, version.protocol_version, version.state_version
+                );
+                self.degraded_peers.insert(peer);
+            }
+            PeerCompatibility::Incompatible(reason) => {
+                tracing::warn!("Disconnecting incompatible peer {}: {}", peer, reason);
+                let _ = self.swarm.disconnect_peer_id(peer);
+            }
+        }
+    }
+
     /// Connect to a peer
     pub fn dial(&mut self, addr: Multiaddr) -> HazeResult<()> {
         self.swarm.dial(addr)
@@ -937,6 +1323,15 @@ impl Network {
         Ok(())
     }
     
+    /// Ask every connected peer for its clock (responses update the drift estimate)
+    fn request_peer_times(&mut self) {
+        let peers: Vec<PeerId> = self.connected_peers.iter().cloned().collect();
+        for peer_id in peers {
+            let request_id = self.swarm.behaviour_mut().blocks.send_request(&peer_id, HazeRequest::RequestTime);
+            self.pending_time_requests.insert(request_id, crate::time_sync::now_ms());
+        }
+    }
+
     /// Request blockchain info from a peer (for light sync)
     pub fn request_blockchain_info(&mut self, peer_id: &PeerId) -> HazeResult<()> {
         let request = HazeRequest::RequestBlockchainInfo;
@@ -984,4 +1379,34 @@ impl Network {
     }
 }
 
+/// Peer that sent a request or response
+fn message_peer(event: &HazeBehaviourEvent) -> Option<PeerId> {
+    use libp2p::request_response::Event;
+    match event {
+        HazeBehaviourEvent::Blocks(Event::Message { peer, .. })
+        | HazeBehaviourEvent::Transactions(Event::Message { peer, .. })
+        | HazeBehaviourEvent::Messages(Event::Message { peer, .. })
+        | HazeBehaviourEvent::Keepalive(Event::Message { peer, .. }) => Some(*peer),
+        _ => None,
+    }
+}
+
+/// Next message submitted through the API; pending forever without a mailbox
+async fn next_outbound_message(
+    receiver: &mut Option<tokio::sync::broadcast::Receiver<SealedMessage>>,
+) -> Option<SealedMessage> {
+    let Some(receiver) = receiver else {
+        return std::future::pending().await;
+    };
+    loop {
+        match receiver.recv().await {
+            Ok(message) => return Some(message),
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                tracing::warn!("Dropped {} outbound direct messages", skipped);
+            }
+            Err(tokio::sync::broadcast::error::RecvError::Closed) => return std::future::pending().await,
+        }
+    }
+}
+
 // Network cannot be cloned - use Arc<Mutex<Network>> if needed
@@ -0,0 +1,329 @@
+//! Peer liveness: keep-alive pings, dead peer detection and redial backoff
+//!
+//! The network layer pings every connected peer each `network.ping_interval_ms` on the
+//! keep-alive protocol and records the round trip. Any message from a peer also counts as a
+//! sign of life. Peers that do not speak the keep-alive protocol (older nodes) are tracked
+//! through transport-level pings instead. A peer that has not been heard from for
+//! `network.dead_peer_timeout_ms`, or missed `network.max_missed_pings` pings in a row, is
+//! disconnected, so the peer count and sync peer selection do not keep counting dead
+//! connections. Addresses this node dialed are redialed after a disconnect or a failed dial,
+//! with a delay doubling from `network.reconnect_base_ms` up to `network.reconnect_max_ms`.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use parking_lot::RwLock;
+use serde::Serialize;
+
+use crate::config::NetworkConfig;
+
+/// Liveness of one connected peer
+#[derive(Debug, Clone, Serialize)]
+pub struct PeerLiveness {
+    pub peer_id: String,
+    /// Dialed address for outbound connections, remote address for inbound ones
+    pub address: Option<String>,
+    /// Whether this node dialed the peer
+    pub outbound: bool,
+    /// Local time the connection was established (ms)
+    pub connected_at_ms: i64,
+    /// Local time anything was last received from the peer (ms)
+    pub last_seen_ms: i64,
+    /// Latest ping round trip (ms)
+    pub rtt_ms: Option<u64>,
+    /// Smoothed round trip (ms), weighting each new sample 1/8
+    pub avg_rtt_ms: Option<u64>,
+    pub pings_answered: u64,
+    /// Pings missed in a row
+    pub missed_pings: u32,
+    /// Whether the peer answers keep-alive pings (otherwise transport pings are used)
+    pub keepalive: bool,
+}
+
+/// Peer health summary for the peers API and metrics
+#[derive(Debug, Clone, Serialize)]
+pub struct PeerHealthStatus {
+    pub connected: usize,
+    /// Median of the peers' latest round trips (ms), `None` without measurements
+    pub median_rtt_ms: Option<u64>,
+    /// Peers disconnected for being unresponsive since startup
+    pub dead_peers_disconnected: u64,
+    /// Redial attempts since startup
+    pub reconnect_attempts: u64,
+    /// Dialed addresses waiting to be redialed
+    pub pending_reconnects: Vec<PendingReconnect>,
+}
+
+/// A dialed address waiting to be redialed
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingReconnect {
+    pub address: String,
+    /// Failed attempts so far
+    pub attempts: u32,
+    /// Local time of the next attempt (ms); `None` while a dial is in progress
+    pub due_at_ms: Option<i64>,
+}
+
+/// Keep-alive and redial limits (from `NetworkConfig`)
+#[derive(Debug, Clone, Copy)]
+pub struct LivenessLimits {
+    pub dead_peer_timeout_ms: u64,
+    pub max_missed_pings: u32,
+    pub reconnect_base_ms: u64,
+    pub reconnect_max_ms: u64,
+}
+
+impl LivenessLimits {
+    pub fn from_config(network: &NetworkConfig) -> Self {
+        Self {
+            dead_peer_timeout_ms: network.dead_peer_timeout_ms,
+            max_missed_pings: network.max_missed_pings,
+            reconnect_base_ms: network.reconnect_base_ms,
+            reconnect_max_ms: network.reconnect_max_ms,
+        }
+    }
+
+    /// Delay before redial attempt `attempt` (1-based)
+    fn reconnect_delay_ms(&self, attempt: u32) -> u64 {
+        let doublings = attempt.saturating_sub(1).min(32);
+        self.reconnect_base_ms.saturating_mul(1 << doublings).min(self.reconnect_max_ms)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Reconnect {
+    attempts: u32,
+    /// `None` while the redial is in progress
+    due_at_ms: Option<i64>,
+}
+
+/// Liveness of connected peers and redial schedule of dropped ones
+#[derive(Debug)]
+pub struct PeerHealth {
+    limits: LivenessLimits,
+    peers: RwLock<HashMap<String, PeerLiveness>>,
+    reconnects: RwLock<HashMap<String, Reconnect>>,
+    dead_peers_disconnected: AtomicU64,
+    reconnect_attempts: AtomicU64,
+}
+
+impl PeerHealth {
+    pub fn new(limits: LivenessLimits) -> Self {
+        Self {
+            limits,
+            peers: RwLock::new(HashMap::new()),
+            reconnects: RwLock::new(HashMap::new()),
+            dead_peers_disconnected: AtomicU64::new(0),
+            reconnect_attempts: AtomicU64::new(0),
+        }
+    }
+
+    /// Start tracking a newly connected peer; a pending redial of its address is done
+    pub fn connected(&self, peer: String, address: Option<String>, outbound: bool, now_ms: i64) {
+        if let Some(address) = address.as_ref().filter(|_| outbound) {
+            self.reconnects.write().remove(address);
+        }
+        self.peers.write().insert(peer.clone(), PeerLiveness {
+            peer_id: peer,
+            address,
+            outbound,
+            connected_at_ms: now_ms,
+            last_seen_ms: now_ms,
+            rtt_ms: None,
+            avg_rtt_ms: None,
+            pings_answered: 0,
+            missed_pings: 0,
+            keepalive: true,
+        });
+    }
+
+    /// Something was received from the peer
+    pub fn seen(&self, peer: &str, now_ms: i64) {
+        if let Some(liveness) = self.peers.write().get_mut(peer) {
+            liveness.last_seen_ms = liveness.last_seen_ms.max(now_ms);
+        }
+    }
+
+    /// The peer answered a ping after `rtt_ms`
+    pub fn record_pong(&self, peer: &str, rtt_ms: u64, now_ms: i64) {
+        if let Some(liveness) = self.peers.write().get_mut(peer) {
+            liveness.last_seen_ms = liveness.last_seen_ms.max(now_ms);
+            liveness.rtt_ms = Some(rtt_ms);
+            liveness.avg_rtt_ms = Some(match liveness.avg_rtt_ms {
+                Some(avg) => (avg * 7 + rtt_ms) / 8,
+                None => rtt_ms,
+            });
+            liveness.pings_answered += 1;
+            liveness.missed_pings = 0;
+        }
+    }
+
+    /// The peer did not answer a ping in time
+    pub fn record_missed_ping(&self, peer: &str) {
+        if let Some(liveness) = self.peers.write().get_mut(peer) {
+            liveness.missed_pings += 1;
+        }
+    }
+
+    /// The peer does not speak the keep-alive protocol
+    pub fn disable_keepalive(&self, peer: &str) {
+        if let Some(liveness) = self.peers.write().get_mut(peer) {
+            liveness.keepalive = false;
+        }
+    }
+
+    /// Whether the peer is pinged on the keep-alive protocol
+    pub fn uses_keepalive(&self, peer: &str) -> bool {
+        self.peers.read().get(peer).is_some_and(|liveness| liveness.keepalive)
+    }
+
+    /// Peers to disconnect: silent for `dead_peer_timeout_ms` or missing too many pings in a row
+    pub fn dead_peers(&self, now_ms: i64) -> Vec<String> {
+        let mut dead: Vec<String> = self.peers
+            .read()
+            .values()
+            .filter(|liveness| {
+                now_ms - liveness.last_seen_ms > self.limits.dead_peer_timeout_ms as i64
+                    || liveness.missed_pings >= self.limits.max_missed_pings
+            })
+            .map(|liveness| liveness.peer_id.clone())
+            .collect();
+        dead.sort();
+        dead
+    }
+
+    /// Count a peer disconnected by dead peer detection
+    pub fn record_dead_disconnect(&self) {
+        self.dead_peers_disconnected.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Forget a disconnected peer; if this node dialed it, its address is scheduled for
+    /// redial and the time of the attempt returned
+    pub fn disconnected(&self, peer: &str, now_ms: i64) -> Option<i64> {
+        let liveness = self.peers.write().remove(peer)?;
+        match liveness.address {
+            Some(address) if liveness.outbound => Some(self.schedule_reconnect(address, now_ms)),
+            _ => None,
+        }
+    }
+
+    /// Schedule a redial of `address` after a disconnect or failed dial, backing off with
+    /// each failed attempt; returns the time of the attempt
+    pub fn schedule_reconnect(&self, address: String, now_ms: i64) -> i64 {
+        let mut reconnects = self.reconnects.write();
+        let reconnect = reconnects.entry(address).or_insert(Reconnect { attempts: 0, due_at_ms: None });
+        reconnect.attempts += 1;
+        let due_at_ms = now_ms + self.limits.reconnect_delay_ms(reconnect.attempts) as i64;
+        reconnect.due_at_ms = Some(due_at_ms);
+        due_at_ms
+    }
+
+    /// Addresses due for a redial; each stays pending until it connects or fails again
+    pub fn due_reconnects(&self, now_ms: i64) -> Vec<String> {
+        let mut due = Vec::new();
+        for (address, reconnect) in self.reconnects.write().iter_mut() {
+            if reconnect.due_at_ms.is_some_and(|at| at <= now_ms) {
+                reconnect.due_at_ms = None;
+                due.push(address.clone());
+            }
+        }
+        self.reconnect_attempts.fetch_add(due.len() as u64, Ordering::Relaxed);
+        due.sort();
+        due
+    }
+
+    /// Connected peers by peer id
+    pub fn peers(&self) -> Vec<PeerLiveness> {
+        let mut peers: Vec<PeerLiveness> = self.peers.read().values().cloned().collect();
+        peers.sort_by(|a, b| a.peer_id.cmp(&b.peer_id));
+        peers
+    }
+
+    pub fn status(&self) -> PeerHealthStatus {
+        let peers = self.peers.read();
+        let mut rtts: Vec<u64> = peers.values().filter_map(|liveness| liveness.rtt_ms).collect();
+        rtts.sort_unstable();
+        let mut pending_reconnects: Vec<PendingReconnect> = self.reconnects
+            .read()
+            .iter()
+            .map(|(address, reconnect)| PendingReconnect {
+                address: address.clone(),
+                attempts: reconnect.attempts,
+                due_at_ms: reconnect.due_at_ms,
+            })
+            .collect();
+        pending_reconnects.sort_by(|a, b| a.address.cmp(&b.address));
+        PeerHealthStatus {
+            connected: peers.len(),
+            median_rtt_ms: rtts.get(rtts.len() / 2).copied(),
+            dead_peers_disconnected: self.dead_peers_disconnected.load(Ordering::Relaxed),
+            reconnect_attempts: self.reconnect_attempts.load(Ordering::Relaxed),
+            pending_reconnects,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits() -> LivenessLimits {
+        LivenessLimits {
+            dead_peer_timeout_ms: 1_000,
+            max_missed_pings: 2,
+            reconnect_base_ms: 100,
+            reconnect_max_ms: 350,
+        }
+    }
+
+    #[test]
+    fn test_silent_or_unanswering_peers_are_dead() {
+        let health = PeerHealth::new(limits());
+        health.connected("a".to_string(), Some("/ip4/10.0.0.1/tcp/9000".to_string()), true, 0);
+        health.connected("b".to_string(), None, false, 0);
+        health.connected("c".to_string(), None, false, 0);
+
+        health.record_pong("a", 40, 500);
+        health.record_pong("a", 80, 900);
+        health.seen("b", 900);
+        assert_eq!(health.dead_peers(1_200), ["c"]);
+        assert_eq!(health.disconnected("c", 1_200), None);
+
+        // Missed pings in a row, reset by an answer
+        health.record_missed_ping("b");
+        health.record_pong("b", 10, 1_300);
+        health.record_missed_ping("b");
+        assert!(health.dead_peers(1_400).is_empty());
+        health.record_missed_ping("b");
+        assert_eq!(health.dead_peers(1_400), ["b"]);
+
+        let a = health.peers().into_iter().find(|p| p.peer_id == "a").unwrap();
+        assert_eq!((a.rtt_ms, a.avg_rtt_ms, a.pings_answered), (Some(80), Some(45), 2));
+        assert_eq!(health.status().median_rtt_ms, Some(80));
+    }
+
+    #[test]
+    fn test_dialed_peers_are_redialed_with_backoff() {
+        let health = PeerHealth::new(limits());
+        let address = "/ip4/10.0.0.1/tcp/9000".to_string();
+        health.connected("a".to_string(), Some(address.clone()), true, 0);
+        health.connected("b".to_string(), Some("/ip4/10.0.0.2/tcp/5555".to_string()), false, 0);
+
+        // Only the dialed peer is redialed
+        assert_eq!(health.disconnected("a", 1_000), Some(1_100));
+        assert_eq!(health.disconnected("b", 1_000), None);
+        assert!(health.due_reconnects(1_099).is_empty());
+        assert_eq!(health.due_reconnects(1_100), vec![address.clone()]);
+        assert!(health.due_reconnects(5_000).is_empty());
+
+        // Failed dials back off, capped at reconnect_max_ms
+        assert_eq!(health.schedule_reconnect(address.clone(), 2_000), 2_200);
+        assert_eq!(health.schedule_reconnect(address.clone(), 3_000), 3_350);
+        let status = health.status();
+        assert_eq!(status.reconnect_attempts, 1);
+        assert_eq!(status.pending_reconnects[0].attempts, 3);
+
+        // Connecting again clears the schedule
+        health.connected("a".to_string(), Some(address), true, 4_000);
+        assert!(health.status().pending_reconnects.is_empty());
+    }
+}
@@ -0,0 +1,347 @@
+//! Deterministic replay of a block archive
+//!
+//! `haze replay` re-executes the blocks stored in a node database into a fresh, empty state and
+//! checks the state root after every block against the one the next block's producer recorded:
+//! a block header carries the root of the state its parent left (see
+//! `ConsensusEngine::create_block`), so block `h` is checked before it is applied, against the
+//! replayed state at height `h - 1`. Blocks below `--from` are replayed without being checked.
+//!
+//! The archive only stores blocks and the account index, so the report diffs what it can: when
+//! the replay ends at the archive's last block, every account whose replayed record differs from
+//! the one the archive stored is listed. State written outside blocks (e.g. accounts funded by a
+//! dev node) shows up there, as does any non-deterministic execution.
+//!
+//! The archive is opened read-only in spirit: nothing is migrated, repaired or written. Stop the
+//! node first, or replay a copy of its database.
+
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+use serde::Serialize;
+use crate::block_store::{block_key, decode_block};
+use crate::config::{Config, StorageBackend};
+use crate::error::{HazeError, Result};
+use crate::state::{AccountState, StateManager};
+use crate::storage::{open_backend, DbTrees, SledStorage, Storage, StorageTree};
+use crate::types::{address_to_hex, hash_to_hex, Address, Hash};
+
+/// First block whose recorded state root the replay did not reproduce
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Divergence {
+    pub height: u64,
+    pub block_hash: Hash,
+    /// State root in the block header
+    pub expected: Hash,
+    /// State root of the replayed state the block was applied to
+    pub computed: Hash,
+}
+
+/// An account that differs between the archive and the replay (`None`: absent on that side)
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountDiff {
+    pub address: Address,
+    pub archived: Option<AccountState>,
+    pub replayed: Option<AccountState>,
+}
+
+/// Outcome of a replay
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReplayReport {
+    /// Checked range (the blocks below `from` are replayed unchecked)
+    pub from: u64,
+    pub to: u64,
+    pub blocks_replayed: u64,
+    pub blocks_checked: u64,
+    pub divergent_blocks: u64,
+    pub first_divergence: Option<Divergence>,
+    /// Accounts differing from the archive's index; `None` unless the replay reached the
+    /// archive's last block (before that, the index holds later state)
+    pub account_diff: Option<Vec<AccountDiff>>,
+}
+
+/// Height of the last block stored in an archive (0 when it has none)
+fn last_height(trees: &DbTrees) -> Result<u64> {
+    match trees.blocks.last()? {
+        Some((key, _)) => <[u8; 8]>::try_from(&key[..])
+            .map(u64::from_be_bytes)
+            .map_err(|_| HazeError::Database(format!("Block key {} is not a height", hex::encode(&key)))),
+        None => Ok(0),
+    }
+}
+
+/// Replay the blocks of `archive` up to `to` (default: its last block) into a fresh state,
+/// checking state roots from block `from` on
+pub fn replay(archive: &dyn Storage, config: &Config, from: u64, to: Option<u64>) -> Result<ReplayReport> {
+    let trees = DbTrees::open(archive)?;
+    let last = last_height(&trees)?;
+    let to = to.unwrap_or(last);
+    if to > last {
+        return Err(HazeError::Config(format!("--to {} is past the archive's last block {}", to, last)));
+    }
+    if from > to {
+        return Err(HazeError::Config(format!("--from {} is after --to {}", from, to)));
+    }
+
+    let scratch: Arc<dyn Storage> = Arc::new(SledStorage::temporary()?);
+    let state = StateManager::with_db(scratch.clone(), config)?;
+    let mut report = ReplayReport { from, to, ..ReplayReport::default() };
+    for height in 1..=to {
+        let bytes = trees.blocks.get(&block_key(height))?
+            .ok_or_else(|| HazeError::Database(format!("Block {} is missing from the archive", height)))?;
+        let block = decode_block(&bytes)?;
+        if height >= from {
+            let computed = state.compute_state_root();
+            report.blocks_checked += 1;
+            if computed != block.header.state_root {
+                report.divergent_blocks += 1;
+                report.first_divergence.get_or_insert(Divergence {
+                    height,
+                    block_hash: block.header.hash,
+                    expected: block.header.state_root,
+                    computed,
+                });
+            }
+        }
+        state.replay_block(&block)
+            .map_err(|e| HazeError::InvalidBlock(format!("Block {} failed to replay: {}", height, e)))?;
+        report.blocks_replayed += 1;
+    }
+    if to == last {
+        report.account_diff = Some(diff_accounts(&trees.accounts, &DbTrees::open(scratch.as_ref())?.accounts)?);
+    }
+    Ok(report)
+}
+
+fn decode_account(bytes: Option<sled::IVec>) -> Result<Option<AccountState>> {
+    bytes.map(|bytes| bincode::deserialize(&bytes).map_err(|e| HazeError::Serialization(e.to_string())))
+        .transpose()
+}
+
+/// Accounts whose stored records differ between two account indexes, by address
+fn diff_accounts(archived: &Arc<dyn StorageTree>, replayed: &Arc<dyn StorageTree>) -> Result<Vec<AccountDiff>> {
+    let mut keys = BTreeSet::new();
+    for tree in [archived, replayed] {
+        for item in tree.iter() {
+            keys.insert(item?.0.to_vec());
+        }
+    }
+    let mut diff = Vec::new();
+    for key in keys {
+        let (a, b) = (archived.get(&key)?, replayed.get(&key)?);
+        if a == b {
+            continue;
+        }
+        let Ok(address) = Address::try_from(&key[..]) else { continue };
+        diff.push(AccountDiff { address, archived: decode_account(a)?, replayed: decode_account(b)? });
+    }
+    Ok(diff)
+}
+
+fn describe(account: &Option<AccountState>) -> String {
+    match account {
+        Some(account) => format!("balance={} nonce={} staked={}", account.balance, account.nonce, account.staked),
+        None => "absent".to_string(),
+    }
+}
+
+/// `haze replay` entry point
+pub fn run_cli(args: &[String]) -> Result<()> {
+    let usage = "usage: haze replay --db <db_path> [--backend sled|rocksdb] [--config <haze_config.json>] [--from <height>] [--to <height>] [--format text|json]";
+    let mut db_path: Option<PathBuf> = None;
+    let mut backend = StorageBackend::Sled;
+    let mut config_path: Option<PathBuf> = None;
+    let mut from = 0u64;
+    let mut to: Option<u64> = None;
+    let mut json = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().cloned()
+            .ok_or_else(|| HazeError::Config(format!("Missing value for {}", arg)));
+        let height = |value: String| value.parse::<u64>()
+            .map_err(|_| HazeError::Config(format!("{} must be a block height, got '{}'", arg, value)));
+        match arg.as_str() {
+            "--db" => db_path = Some(PathBuf::from(value()?)),
+            "--backend" => backend = value()?.parse()?,
+            "--config" => config_path = Some(PathBuf::from(value()?)),
+            "--from" => from = height(value()?)?,
+            "--to" => to = Some(height(value()?)?),
+            "--format" => json = match value()?.as_str() {
+                "text" => false,
+                "json" => true,
+                other => return Err(HazeError::Config(format!("Unknown format '{}' ({})", other, usage))),
+            },
+            other => return Err(HazeError::Config(format!("Unknown argument '{}' ({})", other, usage))),
+        }
+    }
+    let Some(db_path) = db_path else {
+        return Err(HazeError::Config(format!("--db is required ({})", usage)));
+    };
+    // Execution depends on the chain's parameters, so replay with the config the node ran with
+    let config = match config_path {
+        Some(path) => {
+            let config = Config::read_file(&path)?;
+            config.validate()?;
+            config
+        }
+        None => Config::default(),
+    };
+    let archive = open_backend(backend, &db_path)?;
+    let report = replay(archive.as_ref(), &config, from, to)?;
+
+    if json {
+        let output = serde_json::to_string_pretty(&report).map_err(|e| HazeError::Serialization(e.to_string()))?;
+        println!("{}", output);
+    } else {
+        println!(
+            "Replayed {} blocks; checked state roots of blocks {}..={} ({} blocks)",
+            report.blocks_replayed, report.from.max(1), report.to, report.blocks_checked
+        );
+        match &report.first_divergence {
+            Some(divergence) => {
+                println!(
+                    "First divergence at block {} ({}): header state root {}, replay computed {}",
+                    divergence.height,
+                    hash_to_hex(&divergence.block_hash),
+                    hash_to_hex(&divergence.expected),
+                    hash_to_hex(&divergence.computed)
+                );
+                println!("{} of {} checked blocks diverge", report.divergent_blocks, report.blocks_checked);
+            }
+            None => println!("All state roots match"),
+        }
+        match &report.account_diff {
+            Some(diff) if diff.is_empty() => println!("Account index matches the replayed accounts"),
+            Some(diff) => {
+                println!("{} accounts differ from the archive's account index:", diff.len());
+                for entry in diff {
+                    println!(
+                        "  {}  archive: {}  replay: {}",
+                        address_to_hex(&entry.address), describe(&entry.archived), describe(&entry.replayed)
+                    );
+                }
+            }
+            None => println!("Account index not compared (replay stopped before the archive's last block)"),
+        }
+    }
+    match report.first_divergence {
+        Some(divergence) => Err(HazeError::State(format!("State diverges from block {}", divergence.height))),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Block, BlockHeader, Transaction, PROTOCOL_VERSION, STATE_VERSION};
+
+    fn block(height: u64, state_root: Hash, transactions: Vec<Transaction>) -> Block {
+        Block {
+            header: BlockHeader {
+                hash: [height as u8; 32],
+                parent_hash: [height as u8 - 1; 32],
+                height,
+                timestamp: height as i64,
+                validator: [0u8; 32],
+                merkle_root: [0u8; 32],
+                state_root,
+                wave_number: 0,
+                committee_id: 0,
+                protocol_version: PROTOCOL_VERSION,
+                state_version: STATE_VERSION,
+                signals: 0,
+            },
+            transactions,
+            dag_references: vec![],
+        }
+    }
+
+    /// Blocks produced by `node`, each recording the root of the state it was built on
+    fn produce(node: &StateManager, heights: std::ops::RangeInclusive<u64>) {
+        for height in heights {
+            node.apply_block(&block(height, node.compute_state_root(), vec![])).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_replay_reproduces_state_roots() {
+        let config = Config::default();
+        let archive: Arc<dyn Storage> = Arc::new(SledStorage::temporary().unwrap());
+        let node = StateManager::with_db(archive.clone(), &config).unwrap();
+        produce(&node, 1..=4);
+
+        let report = replay(archive.as_ref(), &config, 0, None).unwrap();
+        assert_eq!((report.to, report.blocks_replayed, report.blocks_checked), (4, 4, 4));
+        assert_eq!(report.first_divergence, None);
+        assert!(report.account_diff.unwrap().is_empty());
+
+        let report = replay(archive.as_ref(), &config, 3, Some(3)).unwrap();
+        assert_eq!((report.blocks_replayed, report.blocks_checked), (3, 1));
+        assert!(report.account_diff.is_none());
+        assert!(replay(archive.as_ref(), &config, 0, Some(5)).is_err());
+    }
+
+    #[test]
+    fn test_replay_reproduces_asset_blocks() {
+        use crate::types::{AssetAction, AssetData, DensityLevel};
+        // Free gas, so an account the archive never funded can create assets
+        let mut config = Config::default();
+        config.vm.gas_price = 0;
+        let archive: Arc<dyn Storage> = Arc::new(SledStorage::temporary().unwrap());
+        let node = StateManager::with_db(archive.clone(), &config).unwrap();
+        let owner = [5u8; 32];
+        let asset_tx = |action: AssetAction, level: &str, nonce: u64| Transaction::MistbornAsset {
+            from: owner,
+            action,
+            asset_id: [0xa1; 32],
+            data: AssetData {
+                density: DensityLevel::Ethereal,
+                metadata: [("level".to_string(), level.to_string()), ("class".to_string(), "mage".to_string())].into(),
+                attributes: vec![],
+                game_id: Some("game".to_string()),
+                owner,
+            },
+            fee: 0,
+            nonce,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        produce(&node, 1..=1);
+        node.apply_block(&block(2, node.compute_state_root(), vec![asset_tx(AssetAction::Create, "1", 0)])).unwrap();
+        node.apply_block(&block(3, node.compute_state_root(), vec![asset_tx(AssetAction::Update, "2", 1)])).unwrap();
+        produce(&node, 4..=5);
+        assert_eq!(node.get_asset(&[0xa1; 32]).unwrap().data.metadata["level"], "2");
+
+        // Replayed on a later second of the clock, the assets still hash to the recorded roots
+        std::thread::sleep(std::time::Duration::from_millis(1100));
+        let report = replay(archive.as_ref(), &config, 0, None).unwrap();
+        assert_eq!((report.blocks_replayed, report.blocks_checked), (5, 5));
+        assert_eq!(report.first_divergence, None);
+    }
+
+    #[test]
+    fn test_replay_reports_first_divergence_and_account_diff() {
+        let config = Config::default();
+        let archive: Arc<dyn Storage> = Arc::new(SledStorage::temporary().unwrap());
+        let node = StateManager::with_db(archive.clone(), &config).unwrap();
+        produce(&node, 1..=2);
+        // State written outside any block changes the roots the next blocks record
+        node.create_test_account([7u8; 32], 500, 0);
+        produce(&node, 3..=4);
+
+        let report = replay(archive.as_ref(), &config, 0, None).unwrap();
+        let divergence = report.first_divergence.unwrap();
+        assert_eq!(divergence.height, 3);
+        assert_ne!(divergence.expected, divergence.computed);
+        assert_eq!(report.divergent_blocks, 2);
+        let diff = report.account_diff.unwrap();
+        assert_eq!(diff.len(), 1);
+        assert_eq!(diff[0].address, [7u8; 32]);
+        assert_eq!(diff[0].archived.as_ref().map(|a| a.balance), Some(500));
+        assert!(diff[0].replayed.is_none());
+
+        // Checking only the blocks before the write finds nothing
+        let report = replay(archive.as_ref(), &config, 1, Some(2)).unwrap();
+        assert_eq!(report.first_divergence, None);
+    }
+}
@@ -0,0 +1,95 @@
+//! Game seasons
+//!
+//! A game registers its seasons on chain with `RegisterSeason`. The first season of a game
+//! makes the signer the game's season operator; later seasons must be registered by the
+//! operator, must start in a later block and must not overlap earlier ones.
+//!
+//! Seasons change state at their boundaries, during block application:
+//! - at the start of block `start_height` (before its transactions): the collections frozen
+//!   by the game's previous season are thawed, the season's leaderboard is opened and a
+//!   `season_started` event is sent;
+//! - at the end of block `end_height` (after its transactions): the season's collections are
+//!   frozen, the holders of the game's collection are snapshotted and a `season_ended` event
+//!   is sent.
+//!
+//! Assets of a frozen collection cannot be created, changed or moved, by transactions,
+//! order book orders or contracts.
+
+use serde::{Deserialize, Serialize};
+use crate::types::{Address, Hash, SeasonHooks};
+
+/// Status of a season at a given height
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SeasonStatus {
+    /// Before `start_height`
+    Scheduled,
+    Active,
+    /// Past `end_height`
+    Ended,
+}
+
+/// Season of a game, with what its boundary hooks did
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Season {
+    pub id: Hash,
+    pub game_id: String,
+    pub name: String,
+    pub operator: Address,
+    /// First block of the season
+    pub start_height: u64,
+    /// Last block of the season
+    pub end_height: u64,
+    pub hooks: SeasonHooks,
+    pub registered_at_height: u64,
+    /// Leaderboard opened when the season started
+    pub leaderboard: Option<Hash>,
+    /// Height of the holder snapshot taken when the season ended
+    pub holders_snapshot_height: Option<u64>,
+}
+
+impl Season {
+    pub fn status(&self, height: u64) -> SeasonStatus {
+        if height < self.start_height {
+            SeasonStatus::Scheduled
+        } else if height <= self.end_height {
+            SeasonStatus::Active
+        } else {
+            SeasonStatus::Ended
+        }
+    }
+}
+
+/// Whether a season of `game_id` may freeze `collection`: the game's own collection or
+/// one in its namespace
+pub fn can_freeze(game_id: &str, collection: &str) -> bool {
+    collection == game_id || crate::tenant::in_namespace(game_id, collection)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_and_freezable_collections() {
+        let season = Season {
+            id: [0u8; 32],
+            game_id: "my_game".to_string(),
+            name: "s1".to_string(),
+            operator: [1u8; 32],
+            start_height: 10,
+            end_height: 20,
+            hooks: SeasonHooks::default(),
+            registered_at_height: 1,
+            leaderboard: None,
+            holders_snapshot_height: None,
+        };
+        assert_eq!(season.status(9), SeasonStatus::Scheduled);
+        assert_eq!((season.status(10), season.status(20)), (SeasonStatus::Active, SeasonStatus::Active));
+        assert_eq!(season.status(21), SeasonStatus::Ended);
+
+        assert!(can_freeze("my_game", "my_game") && can_freeze("my_game", "my_game/skins"));
+        assert!(!can_freeze("my_game", "my_game_2") && !can_freeze("my_game", "other/skins"));
+        assert!(!can_freeze("my_game", "my_game/"));
+    }
+}
@@ -0,0 +1,240 @@
+//! Sign-in-with-HAZE: wallet login for games
+//!
+//! 1. The client asks for a challenge for its address (`POST /api/v1/auth/challenge`).
+//! 2. It signs the challenge message with its account key (Ed25519 over the UTF-8 text).
+//! 3. It sends message and signature back (`POST /api/v1/auth/session`). The node checks the
+//!    signature against the address (an address is its Ed25519 public key), that it issued
+//!    the challenge, and that the challenge is neither expired nor already used. It then
+//!    returns a session token, sent as `Authorization: Bearer <token>` afterwards.
+//!
+//! Challenge message (timestamps are Unix seconds):
+//!
+//! ```text
+//! {domain} wants you to sign in with your HAZE account:
+//! {address}
+//!
+//! Chain ID: {chain_id}
+//! Nonce: {nonce}
+//! Issued At: {issued_at}
+//! Expiration Time: {expires_at}
+//! ```
+//!
+//! `verify_sign_in` checks a signed message without a node, so a game backend can accept
+//! the same login itself. Challenges and sessions live in memory: only the SHA-256 of a
+//! session token is kept, and tokens end with the node process.
+
+use std::collections::{HashMap, VecDeque};
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use crate::config::SignInConfig;
+use crate::crypto::verify_signature;
+use crate::error::{HazeError, Result};
+use crate::types::{address_to_hex, hex_to_address, sha256, Address, Hash};
+
+const STATEMENT: &str = " wants you to sign in with your HAZE account:";
+
+/// Sign-in challenge, as signed by the client
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignInMessage {
+    pub domain: String,
+    pub address: Address,
+    pub chain_id: u64,
+    /// Random hex string issued by the node (single use)
+    pub nonce: String,
+    pub issued_at: i64,
+    pub expires_at: i64,
+}
+
+impl SignInMessage {
+    /// Text the client signs
+    pub fn to_text(&self) -> String {
+        format!(
+            "{}{}\n{}\n\nChain ID: {}\nNonce: {}\nIssued At: {}\nExpiration Time: {}",
+            self.domain,
+            STATEMENT,
+            address_to_hex(&self.address),
+            self.chain_id,
+            self.nonce,
+            self.issued_at,
+            self.expires_at,
+        )
+    }
+
+    /// Parse a message produced by `to_text`
+    pub fn parse(text: &str) -> Result<Self> {
+        let invalid = |what: &str| HazeError::AccessDenied(format!("Malformed sign-in message: {}", what));
+        let mut lines = text.split('\n');
+        let domain = lines
+            .next()
+            .and_then(|line| line.strip_suffix(STATEMENT))
+            .filter(|domain| !domain.is_empty())
+            .ok_or_else(|| invalid("statement"))?;
+        let address = lines.next().and_then(hex_to_address).ok_or_else(|| invalid("address"))?;
+        if lines.next() != Some("") {
+            return Err(invalid("blank line"));
+        }
+        let mut field = |name: &str| {
+            lines
+                .next()
+                .and_then(|line| line.strip_prefix(name))
+                .and_then(|line| line.strip_prefix(": "))
+                .ok_or_else(|| invalid(name))
+        };
+        let chain_id = field("Chain ID")?.parse().map_err(|_| invalid("Chain ID"))?;
+        let nonce = field("Nonce")?.to_string();
+        let issued_at = field("Issued At")?.parse().map_err(|_| invalid("Issued At"))?;
+        let expires_at = field("Expiration Time")?.parse().map_err(|_| invalid("Expiration Time"))?;
+        if lines.next().is_some() {
+            return Err(invalid("trailing lines"));
+        }
+        Ok(Self { domain: domain.to_string(), address, chain_id, nonce, issued_at, expires_at })
+    }
+}
+
+/// Check a signed sign-in message: its format, the signature by the address it names, and
+/// that it has not expired at `now`. Domain, chain id and nonce are left to the caller.
+pub fn verify_sign_in(text: &str, signature: &[u8], now: i64) -> Result<SignInMessage> {
+    let message = SignInMessage::parse(text)?;
+    if !verify_signature(&message.address, text.as_bytes(), signature)? {
+        return Err(HazeError::AccessDenied("Invalid sign-in signature".to_string()));
+    }
+    if now > message.expires_at {
+        return Err(HazeError::AccessDenied("Sign-in challenge expired".to_string()));
+    }
+    Ok(message)
+}
+
+/// Signed-in account behind a session token
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub address: Address,
+    pub expires_at: i64,
+}
+
+#[derive(Default)]
+struct Challenges {
+    by_nonce: HashMap<String, SignInMessage>,
+    /// Nonces in issue order, to drop the oldest past `max_pending_challenges`
+    order: VecDeque<String>,
+}
+
+/// Challenges issued by this node and the sessions they were redeemed for
+pub struct SignInService {
+    config: SignInConfig,
+    chain_id: u64,
+    challenges: Mutex<Challenges>,
+    /// SHA-256 of the token -> session
+    sessions: DashMap<Hash, Session>,
+}
+
+impl SignInService {
+    pub fn new(config: SignInConfig, chain_id: u64) -> Self {
+        Self { config, chain_id, challenges: Mutex::new(Challenges::default()), sessions: DashMap::new() }
+    }
+
+    /// Issue a challenge for `address`
+    pub fn challenge(&self, address: Address, now: i64) -> SignInMessage {
+        let mut nonce = [0u8; 16];
+        OsRng.fill_bytes(&mut nonce);
+        let message = SignInMessage {
+            domain: self.config.domain.clone(),
+            address,
+            chain_id: self.chain_id,
+            nonce: hex::encode(nonce),
+            issued_at: now,
+            expires_at: now.saturating_add(self.config.challenge_ttl_secs as i64),
+        };
+        let mut challenges = self.challenges.lock();
+        while challenges.order.len() >= self.config.max_pending_challenges
+            || challenges.order.front().is_some_and(|oldest| {
+                challenges.by_nonce.get(oldest).is_none_or(|issued| issued.expires_at < now)
+            })
+        {
+            let Some(oldest) = challenges.order.pop_front() else { break };
+            challenges.by_nonce.remove(&oldest);
+        }
+        challenges.order.push_back(message.nonce.clone());
+        challenges.by_nonce.insert(message.nonce.clone(), message.clone());
+        message
+    }
+
+    /// Redeem a signed challenge for a session token
+    pub fn sign_in(&self, text: &str, signature: &[u8], now: i64) -> Result<(String, Session)> {
+        let message = verify_sign_in(text, signature, now)?;
+        let mut challenges = self.challenges.lock();
+        if challenges.by_nonce.get(&message.nonce) != Some(&message) {
+            return Err(HazeError::AccessDenied("Unknown or already used sign-in challenge".to_string()));
+        }
+        challenges.by_nonce.remove(&message.nonce);
+        drop(challenges);
+
+        let mut bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut bytes);
+        let token = format!("hzs_{}", hex::encode(bytes));
+        let session = Session {
+            address: message.address,
+            expires_at: now.saturating_add(self.config.session_ttl_secs as i64),
+        };
+        self.sessions.retain(|_, session| session.expires_at >= now);
+        self.sessions.insert(sha256(token.as_bytes()), session.clone());
+        Ok((token, session))
+    }
+
+    /// Session of a token, if it is live
+    pub fn session(&self, token: &str, now: i64) -> Option<Session> {
+        let key = sha256(token.as_bytes());
+        let session = self.sessions.get(&key).map(|session| session.clone())?;
+        if session.expires_at < now {
+            self.sessions.remove(&key);
+            return None;
+        }
+        Some(session)
+    }
+
+    /// End a session; false if the token was not live
+    pub fn sign_out(&self, token: &str) -> bool {
+        self.sessions.remove(&sha256(token.as_bytes())).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::KeyPair;
+
+    #[test]
+    fn test_signed_challenge_is_redeemed_once() {
+        let service = SignInService::new(SignInConfig::default(), 7);
+        let player = KeyPair::generate();
+        let message = service.challenge(player.address(), 1_000);
+        let text = message.to_text();
+        assert_eq!(SignInMessage::parse(&text).unwrap(), message);
+        let signature = player.sign(text.as_bytes());
+
+        // Signed by another key, or too late
+        assert!(service.sign_in(&text, &KeyPair::generate().sign(text.as_bytes()), 1_001).is_err());
+        assert!(service.sign_in(&text, &signature, message.expires_at + 1).is_err());
+
+        let (token, session) = service.sign_in(&text, &signature, 1_001).unwrap();
+        assert_eq!(session.address, player.address());
+        assert!(service.sign_in(&text, &signature, 1_002).is_err());
+        assert_eq!(service.session(&token, 1_002).unwrap().address, player.address());
+        assert!(service.session(&token, session.expires_at + 1).is_none());
+    }
+
+    #[test]
+    fn test_messages_not_issued_by_the_node_are_rejected() {
+        let service = SignInService::new(SignInConfig::default(), 7);
+        let player = KeyPair::generate();
+        let mut forged = service.challenge(player.address(), 1_000);
+        forged.expires_at += 3_600;
+        let text = forged.to_text();
+        let signature = player.sign(text.as_bytes());
+        // A correctly signed message still verifies on its own
+        assert!(verify_sign_in(&text, &signature, 1_001).is_ok());
+        assert!(service.sign_in(&text, &signature, 1_001).is_err());
+        assert!(SignInMessage::parse(&format!("{}\nextra", text)).is_err());
+    }
+}
@@ -0,0 +1,334 @@
+//! Economy simulation - backtest tokenomics and market parameters
+//!
+//! Runs the node's own `Tokenomics` (inflation, reward split, fee burn) and `FogEconomy`
+//! (constant-product pool) models over a synthetic workload for a number of waves, without a
+//! database or network, and samples supply, burn, pool reserves and price after every wave.
+//!
+//! Started from the command line as `haze simulate-economy [--params <file.json>] [--waves <n>]
+//! [--format csv|json] [--out <file>]`; see docs/ECONOMY_SIMULATION.md.
+
+use std::path::PathBuf;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use crate::economy::FogEconomy;
+use crate::error::{HazeError, Result};
+use crate::tokenomics::{Tokenomics, INITIAL_INFLATION_RATE};
+
+const POOL_HAZE: &str = "HAZE";
+const POOL_ASSET: &str = "ASSET";
+
+/// Workload and parameters of a simulation run (every field has a default)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SimulationParams {
+    /// Number of waves to simulate
+    pub waves: u64,
+    /// Blocks applied per wave
+    pub blocks_per_wave: u64,
+    /// Height before the first simulated block (inflation decays per `BLOCKS_PER_YEAR` blocks)
+    pub start_height: u64,
+    /// Starting inflation rate (basis points)
+    pub inflation_rate_bps: u64,
+    /// Transactions per block in the first wave
+    pub txs_per_block: u64,
+    /// Relative change of transactions per block from one wave to the next (0.01 = +1%)
+    pub tx_growth_per_wave: f64,
+    /// Average gas fee per transaction (base units)
+    pub avg_fee: u64,
+    /// Fees are drawn uniformly from `avg_fee * (1 ± fee_jitter)`
+    pub fee_jitter: f64,
+    /// Initial HAZE reserve of the simulated pool
+    pub pool_reserve_haze: u64,
+    /// Initial asset reserve of the simulated pool
+    pub pool_reserve_asset: u64,
+    /// Pool swap fee (basis points)
+    pub pool_fee_bps: u64,
+    /// Swaps against the pool per wave
+    pub swaps_per_wave: u64,
+    /// Average swap size in HAZE (sells are sized at the current price)
+    pub avg_swap: u64,
+    /// Share of swaps buying the asset with HAZE (the rest sell it)
+    pub buy_ratio: f64,
+    /// Random seed; equal parameters and seed give identical trajectories
+    pub seed: u64,
+}
+
+impl Default for SimulationParams {
+    fn default() -> Self {
+        Self {
+            waves: 1_000,
+            blocks_per_wave: 1,
+            start_height: 0,
+            inflation_rate_bps: INITIAL_INFLATION_RATE * 100,
+            txs_per_block: 100,
+            tx_growth_per_wave: 0.0,
+            avg_fee: 21_000,
+            fee_jitter: 0.2,
+            pool_reserve_haze: 1_000_000_000,
+            pool_reserve_asset: 1_000_000,
+            pool_fee_bps: 30,
+            swaps_per_wave: 10,
+            avg_swap: 1_000_000,
+            buy_ratio: 0.5,
+            seed: 42,
+        }
+    }
+}
+
+impl SimulationParams {
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |msg: &str| Err(HazeError::Config(format!("Invalid simulation parameters: {}", msg)));
+        if self.waves == 0 || self.blocks_per_wave == 0 {
+            return invalid("waves and blocks_per_wave must be positive");
+        }
+        if self.pool_reserve_haze == 0 || self.pool_reserve_asset == 0 {
+            return invalid("pool reserves must be positive");
+        }
+        if self.pool_fee_bps >= 10_000 {
+            return invalid("pool_fee_bps must be below 10000");
+        }
+        if !(0.0..=1.0).contains(&self.buy_ratio) || !(0.0..=1.0).contains(&self.fee_jitter) {
+            return invalid("buy_ratio and fee_jitter must be within 0..=1");
+        }
+        if self.tx_growth_per_wave <= -1.0 {
+            return invalid("tx_growth_per_wave must be above -1");
+        }
+        Ok(())
+    }
+}
+
+/// State of the simulated economy after one wave
+#[derive(Debug, Clone, Serialize)]
+pub struct WaveSample {
+    pub wave: u64,
+    /// Height of the wave's last block
+    pub height: u64,
+    pub total_supply: u64,
+    pub circulating_supply: u64,
+    pub burned_supply: u64,
+    pub treasury: u64,
+    pub inflation_rate_bps: u64,
+    pub transactions: u64,
+    pub fees_collected: u64,
+    pub fees_burned: u64,
+    pub minted: u64,
+    /// `minted - fees_burned` for the wave (negative = deflationary)
+    pub net_issuance: i128,
+    pub pool_reserve_haze: u64,
+    pub pool_reserve_asset: u64,
+    /// Pool price of one asset unit in HAZE
+    pub price: f64,
+    /// HAZE-denominated volume of the wave's executed swaps
+    pub swap_volume: u64,
+    /// Swaps rejected by the pool (e.g. insufficient liquidity)
+    pub failed_swaps: u64,
+}
+
+/// Run the tokenomics and pool models over the workload in `params`
+pub fn simulate_economy(params: &SimulationParams) -> Result<Vec<WaveSample>> {
+    params.validate()?;
+    let tokenomics = Tokenomics::with_inflation_rate(params.inflation_rate_bps);
+    let economy = FogEconomy::new();
+    let pool_id = economy.create_liquidity_pool(
+        POOL_HAZE.to_string(),
+        POOL_ASSET.to_string(),
+        params.pool_reserve_haze,
+        params.pool_reserve_asset,
+        params.pool_fee_bps,
+    )?;
+    let validator = [1u8; 32];
+    let mut rng = StdRng::seed_from_u64(params.seed);
+
+    let mut samples = Vec::with_capacity(params.waves as usize);
+    let mut height = params.start_height;
+    for wave in 0..params.waves {
+        let txs_per_block = (params.txs_per_block as f64 * (1.0 + params.tx_growth_per_wave).powi(wave as i32)).round() as u64;
+        let mut sample = WaveSample {
+            wave,
+            height,
+            total_supply: 0,
+            circulating_supply: 0,
+            burned_supply: 0,
+            treasury: 0,
+            inflation_rate_bps: 0,
+            transactions: 0,
+            fees_collected: 0,
+            fees_burned: 0,
+            minted: 0,
+            net_issuance: 0,
+            pool_reserve_haze: 0,
+            pool_reserve_asset: 0,
+            price: 0.0,
+            swap_volume: 0,
+            failed_swaps: 0,
+        };
+
+        // Same order as `StateManager::apply_block`: rewards, then the block's fees
+        for _ in 0..params.blocks_per_wave {
+            height += 1;
+            let minted = tokenomics.process_block_rewards(height)?;
+            if minted > 0 {
+                tokenomics.distribute_rewards(minted, validator)?;
+            }
+            let mut fees_collected = 0u64;
+            let mut fees_burned = 0u64;
+            for _ in 0..txs_per_block {
+                let factor = 1.0 + params.fee_jitter * rng.gen_range(-1.0..=1.0);
+                let fee = (params.avg_fee as f64 * factor).round() as u64;
+                tokenomics.process_gas_fee(fee)?;
+                fees_collected += fee;
+                fees_burned += Tokenomics::gas_burn_amount(fee);
+            }
+            tokenomics.record_block_fees(wave, txs_per_block, fees_collected, fees_burned, minted);
+            sample.transactions += txs_per_block;
+            sample.fees_collected += fees_collected;
+            sample.fees_burned += fees_burned;
+            sample.minted += minted;
+        }
+
+        for _ in 0..params.swaps_per_wave {
+            let size = (params.avg_swap as f64 * rng.gen_range(0.5..=1.5)).round() as u64;
+            let pool = economy.get_liquidity_pool(&pool_id)
+                .ok_or_else(|| HazeError::State("Simulated pool disappeared".to_string()))?;
+            let swap = if rng.gen_bool(params.buy_ratio) {
+                economy.swap_assets(&pool_id, POOL_HAZE, size).map(|_| size)
+            } else {
+                let asset_in = (size as u128 * pool.reserve2 as u128 / pool.reserve1 as u128) as u64;
+                economy.swap_assets(&pool_id, POOL_ASSET, asset_in.max(1))
+            };
+            match swap {
+                Ok(volume) => sample.swap_volume += volume,
+                Err(_) => sample.failed_swaps += 1,
+            }
+        }
+
+        let pool = economy.get_liquidity_pool(&pool_id)
+            .ok_or_else(|| HazeError::State("Simulated pool disappeared".to_string()))?;
+        sample.height = height;
+        sample.total_supply = tokenomics.total_supply();
+        sample.circulating_supply = tokenomics.circulating_supply();
+        sample.burned_supply = tokenomics.burned_supply();
+        sample.treasury = tokenomics.treasury_balance();
+        sample.inflation_rate_bps = tokenomics.inflation_rate();
+        sample.net_issuance = sample.minted as i128 - sample.fees_burned as i128;
+        sample.pool_reserve_haze = pool.reserve1;
+        sample.pool_reserve_asset = pool.reserve2;
+        sample.price = pool.reserve1 as f64 / pool.reserve2 as f64;
+        samples.push(sample);
+    }
+    Ok(samples)
+}
+
+/// Samples as CSV (header row plus one row per wave)
+pub fn samples_to_csv(samples: &[WaveSample]) -> String {
+    let mut csv = String::from(
+        "wave,height,total_supply,circulating_supply,burned_supply,treasury,inflation_rate_bps,\
+         transactions,fees_collected,fees_burned,minted,net_issuance,pool_reserve_haze,\
+         pool_reserve_asset,price,swap_volume,failed_swaps\n",
+    );
+    for s in samples {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}\n",
+            s.wave, s.height, s.total_supply, s.circulating_supply, s.burned_supply, s.treasury,
+            s.inflation_rate_bps, s.transactions, s.fees_collected, s.fees_burned, s.minted,
+            s.net_issuance, s.pool_reserve_haze, s.pool_reserve_asset, s.price, s.swap_volume,
+            s.failed_swaps,
+        ));
+    }
+    csv
+}
+
+/// Entry point of `haze simulate-economy`; `args` are the arguments after the subcommand
+pub fn run_cli(args: &[String]) -> Result<()> {
+    let mut params_path: Option<PathBuf> = None;
+    let mut waves: Option<u64> = None;
+    let mut format = "csv".to_string();
+    let mut out: Option<PathBuf> = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().cloned()
+            .ok_or_else(|| HazeError::Config(format!("Missing value for {}", arg)));
+        match arg.as_str() {
+            "--params" => params_path = Some(PathBuf::from(value()?)),
+            "--waves" => {
+                let v = value()?;
+                waves = Some(v.parse().map_err(|_| HazeError::Config(format!("Invalid --waves '{}'", v)))?);
+            }
+            "--format" => format = value()?,
+            "--out" => out = Some(PathBuf::from(value()?)),
+            other => {
+                return Err(HazeError::Config(format!(
+                    "Unknown argument '{}' (usage: haze simulate-economy [--params <file.json>] [--waves <n>] [--format csv|json] [--out <file>])",
+                    other
+                )));
+            }
+        }
+    }
+
+    let mut params = match params_path {
+        Some(path) => serde_json::from_slice(&std::fs::read(&path)?)
+            .map_err(|e| HazeError::Config(format!("Invalid simulation parameters in {}: {}", path.display(), e)))?,
+        None => SimulationParams::default(),
+    };
+    if let Some(waves) = waves {
+        params.waves = waves;
+    }
+
+    let samples = simulate_economy(&params)?;
+    let output = match format.as_str() {
+        "csv" => samples_to_csv(&samples),
+        "json" => serde_json::to_string_pretty(&serde_json::json!({ "params": params, "waves": samples }))
+            .map_err(|e| HazeError::Serialization(e.to_string()))?,
+        other => return Err(HazeError::Config(format!("Unknown format '{}' (csv or json)", other))),
+    };
+    match out {
+        Some(path) => std::fs::write(path, output)?,
+        None => print!("{}", output),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simulation_trajectories() {
+        let params = SimulationParams { waves: 20, buy_ratio: 1.0, ..Default::default() };
+        let samples = simulate_economy(&params).unwrap();
+        assert_eq!(samples.len(), 20);
+        assert_eq!(samples[19].height, 20);
+
+        let first = &samples[0];
+        assert!(first.minted > 0, "block rewards should be issued");
+        assert_eq!(first.transactions, 100);
+        // Burn is rounded down per transaction, so it can trail half the fees by < 1 per tx.
+        assert!(first.fees_burned <= first.fees_collected / 2);
+        assert!(first.fees_collected / 2 - first.fees_burned <= first.transactions);
+        let last = &samples[19];
+        let minted: u64 = samples.iter().map(|s| s.minted).sum();
+        let burned: u64 = samples.iter().map(|s| s.fees_burned).sum();
+        assert_eq!(last.burned_supply, burned);
+        assert_eq!(last.circulating_supply, crate::tokenomics::INITIAL_SUPPLY + minted - burned);
+        // Nobody staked with the simulated validator, so the staker share also goes to the treasury
+        let treasury: u64 = samples.iter()
+            .map(|s| s.minted * crate::tokenomics::TREASURY_RATIO / 100 + s.minted * crate::tokenomics::STAKER_REWARD_RATIO / 100)
+            .sum();
+        assert_eq!(last.treasury, treasury);
+
+        // Only buys: the asset gets more expensive every wave
+        assert!(samples.windows(2).all(|w| w[1].price > w[0].price));
+
+        // Deterministic for a given seed
+        let again = simulate_economy(&params).unwrap();
+        assert_eq!(samples_to_csv(&samples), samples_to_csv(&again));
+        assert_eq!(samples_to_csv(&samples).lines().count(), 21);
+    }
+
+    #[test]
+    fn test_simulation_rejects_invalid_params() {
+        assert!(simulate_economy(&SimulationParams { waves: 0, ..Default::default() }).is_err());
+        assert!(simulate_economy(&SimulationParams { buy_ratio: 1.5, ..Default::default() }).is_err());
+    }
+}
@@ -1,888 +1,4103 @@
 //! State management for HAZE blockchain
 
+pub mod trie;
+mod checkpoint;
+
 use std::sync::Arc;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use parking_lot::RwLock;
-use sled::Db;
 
-/// Sled key prefix for persisted blocks (block height index). Key = PREFIX + height.to_be_bytes().
-const BLOCK_HEIGHT_PREFIX: &[u8] = b"block_h";
 use tokio::sync::broadcast;
-use crate::types::{Address, Hash, Block, Transaction, AssetAction, AssetPermission, PermissionLevel};
+use crate::types::{Address, Hash, Block, BlockHeader, Transaction, WireFormat, AssetAction, AssetHook, AttributeValue, AssetPermission, HookFailureMode, Log, LogsBloom, OrderSide, PermissionLevel};
 use crate::config::Config;
-use crate::vm::{HazeVM, ExecutionContext};
+use crate::vm::{HazeVM, ExecutionContext, ContractValue, AssetHost, PAYMASTER_KEY, PAYMASTER_METHOD};
 use crate::error::{HazeError, Result};
 use crate::tokenomics::Tokenomics;
-use crate::economy::FogEconomy;
+use crate::features::{Feature, FeatureRegistry};
+use crate::fee_estimate::{FeeHistogram, RecentBlockFees};
+use crate::latency::LatencyMetrics;
+use crate::economy::{
+    asset_pool_escrow_address, asset_zone, draw_loot_outcome, loot_escrow_address, match_order, order_escrow_address, upgrade_escrow_address,
+    zone_treasury_address, AssetPool, AssetStake, CollectionMarketStats, FogEconomy, LootBox, LootBoxStatus, LootSettlement, Order, OrderStatus, Referral, ReferralEarnings, ReferralFee,
+    Trade, MAX_TRADE_HISTORY, ZONE_KEY,
+};
 use crate::ws_events::WsEvent;
+use crate::asset_shards::{ShardStats, ShardedAssets};
+use trie::{RootTree, TrieProof};
+use crate::block_store::{block_key, decode_block, BlockStore};
+use crate::storage::{
+    bind_chain_id, open_storage, DbTrees, SledStorage, Storage, StorageMaintenance, StorageStats, StorageTree, WriteBatch,
+    ACCOUNTS_TREE, ASSETS_TREE, BLOCKS_TREE, BLOCK_CHECKSUMS_TREE, BLOCK_LOGS_TREE, CONTRACT_CODE_TREE, INDEXES_TREE,
+    RECEIPTS_TREE, TX_LOCATIONS_TREE,
+};
+use crate::integrity::{block_checksum, check_and_repair};
+use crate::journal::{BlockJournal, JournalRecovery};
+use crate::migrations::migrate;
+use crate::leaderboard::{Leaderboard, ScoreOutcome};
+use crate::season::Season;
+use crate::breeding::{derive_child_attributes, Breeding};
+use crate::upgrade::{attribute_level, roll_outcome, Upgrade, UpgradeOutcome};
+use crate::assets::{BlobStats, BlobStorage};
 use dashmap::DashMap;
 use hex;
 
 /// State manager for blockchain state
 pub struct StateManager {
-    db: Arc<Db>,
+    db: Arc<dyn Storage>,
+    /// Named trees holding the persisted chain data
+    trees: DbTrees,
+    /// Scheduled flushes and size sampling of the database
+    storage: Arc<StorageMaintenance>,
+    /// Write-ahead journal of block application
+    journal: Arc<BlockJournal>,
+    /// Deduplicated blob store (read for quota accounting and blob stats)
+    blobs: Arc<BlobStorage>,
+    /// Blob store deduplication at the last maintenance run
+    blob_stats: Arc<RwLock<BlobStats>>,
     config: Arc<Config>,
     accounts: Arc<DashMap<Address, AccountState>>,
-    assets: Arc<DashMap<Hash, AssetState>>,
-    blocks: Arc<DashMap<Hash, Block>>,
+    /// Account hashes for the state root, updated with every account write
+    account_hashes: Arc<RootTree>,
+    /// Asset states and collection index, sharded by game_id
+    assets: Arc<ShardedAssets>,
+    /// Applied blocks, read from the database on demand
+    blocks: Arc<BlockStore>,
     current_height: Arc<RwLock<u64>>,
     tokenomics: Arc<Tokenomics>,
     economy: Arc<FogEconomy>,
+    /// Upgrade signal tally and feature activations, rebuilt from applied blocks
+    features: Arc<FeatureRegistry>,
     ws_tx: Arc<RwLock<Option<broadcast::Sender<WsEvent>>>>,
     
     // Indexes for fast asset search
     asset_index_by_owner: Arc<DashMap<Address, Vec<Hash>>>,
-    asset_index_by_game_id: Arc<DashMap<String, Vec<Hash>>>,
     asset_index_by_density: Arc<DashMap<u8, Vec<Hash>>>, // Using u8 for density level
+    /// Assets by economic zone, keyed "game_id:zone_id"
+    asset_index_by_zone: Arc<DashMap<String, Vec<Hash>>>,
+    /// Attribute value histograms by collection (game_id)
+    collection_traits: Arc<DashMap<String, TraitCounts>>,
+    /// Rarity scores by collection; dropped when the collection changes, rebuilt on next read
+    collection_rarity: Arc<DashMap<String, Arc<HashMap<Hash, RarityScore>>>>,
+    /// Bumped on every collection change so scores computed from older state are not cached
+    rarity_generation: Arc<std::sync::atomic::AtomicU64>,
+    /// Asset ownership changes by collection, in height order (for holder snapshots)
+    collection_ownership: Arc<DashMap<String, Vec<OwnershipChange>>>,
+    /// Sale statistics per collection (game_id), fed by the trades of applied blocks
+    market_stats: Arc<DashMap<String, CollectionMarketStats>>,
     
     // Cache for frequently accessed assets (LRU-like with access counter)
     asset_access_count: Arc<DashMap<Hash, u64>>, // Track access frequency
 
-    /// Deployed WASM contract code by address (address = sha256(code) for DeployContract)
-    contracts: Arc<DashMap<Address, Vec<u8>>>,
+    /// Deployed contracts by address; bytecode is stored in the database under its code hash
+    contracts: Arc<DashMap<Address, ContractInfo>>,
+
+    /// Transactions that failed during block application, keyed by tx hash
+    dead_letters: Arc<DashMap<Hash, DeadLetterEntry>>,
+
+    /// Asset lifecycle hooks by collection (game_id)
+    asset_hooks: Arc<DashMap<String, RegisteredAssetHook>>,
+
+    /// Timestamps of the last `consensus.median_time_window` applied blocks (oldest first)
+    recent_block_timestamps: Arc<RwLock<std::collections::VecDeque<i64>>>,
+    /// Transaction fees of the last `consensus.fee_estimate_blocks` applied blocks
+    recent_fees: Arc<RwLock<RecentBlockFees>>,
+    /// Latency histograms of block and transaction application, mempool admission and API requests
+    latency: Arc<LatencyMetrics>,
+    /// Bumped on every change to accounts, assets or height (invalidates API response caches)
+    state_version: Arc<std::sync::atomic::AtomicU64>,
+    /// Held for writing while a commit mutates live state; `read_snapshot` holds it for reading
+    snapshot_lock: Arc<RwLock<()>>,
+    /// Payment requests by id
+    payment_requests: Arc<DashMap<Hash, PaymentRequest>>,
+    /// Asset composition graph (merge/split parents and children)
+    asset_lineage: Arc<DashMap<Hash, AssetLineage>>,
+    /// Order book orders by id (open, filled and cancelled)
+    orders: Arc<DashMap<Hash, Order>>,
+    /// Ids of open orders by collection (game_id)
+    open_orders: Arc<DashMap<String, Vec<Hash>>>,
+    /// Latest trades by collection, oldest first (at most `MAX_TRADE_HISTORY`)
+    trades: Arc<DashMap<String, std::collections::VecDeque<Trade>>>,
+    /// Orders placed so far (next order's `sequence`)
+    order_sequence: Arc<std::sync::atomic::AtomicU64>,
+    /// Referrer of each (game_id, player)
+    referrals: Arc<DashMap<(String, Address), Referral>>,
+    /// Referral earnings of each referrer by game_id
+    referral_earnings: Arc<DashMap<Address, BTreeMap<String, ReferralEarnings>>>,
+    subscriptions: Arc<DashMap<Hash, Subscription>>,
+    /// Subscription ids by the height of their next charge (may hold stale ids)
+    subscriptions_due: Arc<DashMap<u64, Vec<Hash>>>,
+    /// Subscription ids by payer and by payee
+    subscriptions_by_account: Arc<DashMap<Address, Vec<Hash>>>,
+    /// Gas sponsored by each paymaster as (window index, amount) for its current window
+    paymaster_spend: Arc<DashMap<Address, (u64, u64)>>,
+    /// Loot boxes by id (sealed, opened and settled)
+    loot_boxes: Arc<DashMap<Hash, LootBox>>,
+    leaderboards: Arc<DashMap<Hash, Leaderboard>>,
+    /// Leaderboard ids by game_id, in creation order
+    leaderboards_by_game: Arc<DashMap<String, Vec<Hash>>>,
+    seasons: Arc<DashMap<Hash, Season>>,
+    /// Season ids by game_id, in season order
+    seasons_by_game: Arc<DashMap<String, Vec<Hash>>>,
+    /// Season ids by start height and by end height (entries are dropped once applied)
+    seasons_starting: Arc<DashMap<u64, Vec<Hash>>>,
+    seasons_ending: Arc<DashMap<u64, Vec<Hash>>>,
+    /// Frozen collections, with the season that froze them
+    frozen_collections: Arc<DashMap<String, Hash>>,
+    asset_pools: Arc<DashMap<Hash, AssetPool>>,
+    /// Asset pool ids by game_id, in creation order
+    asset_pools_by_game: Arc<DashMap<String, Vec<Hash>>>,
+    /// Staked assets by asset id
+    asset_stakes: Arc<DashMap<Hash, AssetStake>>,
+    /// Ids of the assets each account has staked
+    asset_stakes_by_owner: Arc<DashMap<Address, Vec<Hash>>>,
+    /// Breedings by id (pending and hatched)
+    breedings: Arc<DashMap<Hash, Breeding>>,
+    /// Pending breeding ids by hatch height (may hold stale ids; dropped once applied)
+    breedings_due: Arc<DashMap<u64, Vec<Hash>>>,
+    /// Height each asset last bred at
+    last_bred: Arc<DashMap<Hash, u64>>,
+    /// Upgrades by id (pending and resolved)
+    upgrades: Arc<DashMap<Hash, Upgrade>>,
+    /// Pending upgrade ids by roll height (may hold stale ids; dropped once applied)
+    upgrades_due: Arc<DashMap<u64, Vec<Hash>>>,
+    /// Ids of the upgrades of each asset, oldest first
+    upgrades_by_asset: Arc<DashMap<Hash, Vec<Hash>>>,
 }
 
-#[derive(Debug, Clone, serde::Serialize)]
-pub struct AccountState {
-    pub balance: u64,
-    pub nonce: u64,
-    pub staked: u64,
+/// Consistent read view returned by `StateManager::read_snapshot`
+pub struct ReadSnapshot<'a> {
+    /// Height of the last applied block the view reflects
+    pub height: u64,
+    _guard: parking_lot::RwLockReadGuard<'a, ()>,
 }
 
-/// History entry for asset (same as in assets.rs but needed here for serialization)
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct AssetHistoryEntry {
-    pub timestamp: i64,
-    pub action: AssetAction,
-    pub changes: HashMap<String, String>,
+/// What a `StateProof` proves
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProofKind {
+    Account,
+    Asset,
 }
 
-/// Asset version snapshot
+/// Merkle proof of an account or asset (or of an account's absence) against a state root.
+///
+/// The state root of height `height` is `sha256(account_root || root_of(shard_roots) ||
+/// bincode(height))`; it is the `state_root` in the header of block `height + 1`. Check a
+/// proof with `verify_proof`.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct AssetVersion {
-    pub version: u64,
-    pub timestamp: i64,
-    pub data: crate::types::AssetData,
-    pub blob_refs: HashMap<String, Hash>,
+pub struct StateProof {
+    pub kind: ProofKind,
+    /// Account address or asset id
+    pub key: Hash,
+    pub height: u64,
+    /// bincode encoding of the account or asset, `None` if absent; its trie entry hash is
+    /// `sha256(key || value)`
+    pub value: Option<Vec<u8>>,
+    /// Path from the account root, or from the asset's shard root, to the entry
+    pub trie: TrieProof,
+    pub account_root: Hash,
+    /// Asset shard roots in shard order
+    pub shard_roots: Vec<Hash>,
+    /// Shard holding the asset (asset proofs)
+    pub shard: Option<usize>,
 }
 
-/// Quota usage information for an account
+/// Whether `proof` shows its account or asset (or the account's absence) under `state_root`
+pub fn verify_proof(proof: &StateProof, state_root: &Hash) -> bool {
+    let entry = proof.value.as_ref().map(|value| {
+        let mut data = proof.key.to_vec();
+        data.extend_from_slice(value);
+        crate::types::sha256(&data)
+    });
+    let Some(trie_root) = proof.trie.compute_root(&proof.key) else {
+        return false;
+    };
+    if proof.trie.entry(&proof.key) != entry || proof.shard_roots.len() != crate::asset_shards::ASSET_SHARD_COUNT {
+        return false;
+    }
+    let proven = match (proof.kind, proof.shard) {
+        (ProofKind::Account, None) => trie_root == proof.account_root,
+        (ProofKind::Asset, Some(shard)) => entry.is_some() && proof.shard_roots.get(shard) == Some(&trie_root),
+        _ => false,
+    };
+    proven && proof.state_root() == *state_root
+}
+
+impl StateProof {
+    /// State root the proof's roots combine to (the proof itself is checked by `verify_proof`)
+    pub fn state_root(&self) -> Hash {
+        combine_state_root(self.account_root, crate::asset_shards::root_of(&self.shard_roots), self.height)
+    }
+}
+
+/// State root of height `height` from the account root and the asset root
+fn combine_state_root(account_root: Hash, asset_root: Hash, height: u64) -> Hash {
+    let mut combined = Vec::with_capacity(72);
+    combined.extend(account_root);
+    combined.extend(asset_root);
+    combined.extend(bincode::serialize(&height).unwrap_or_default());
+    crate::types::sha256(&combined)
+}
+
+/// State root of height `height` computed from scratch over every account and asset
+fn full_state_root(accounts: &[(Address, AccountState)], assets: &[(Hash, AssetState)], height: u64) -> Hash {
+    use crate::asset_shards::{root_of, shard_for, ASSET_SHARD_COUNT};
+    use trie::entry_hash;
+    let account_root = RootTree::full_root(accounts.iter().map(|(address, account)| (*address, entry_hash(address, account))));
+    let mut shard_entries: Vec<Vec<(Hash, Hash)>> = vec![Vec::new(); ASSET_SHARD_COUNT];
+    for (asset_id, asset) in assets {
+        let shard = shard_for(asset.data.game_id.as_deref());
+        shard_entries[shard].push((*asset_id, entry_hash(asset_id, asset)));
+    }
+    let shard_roots: Vec<Hash> = shard_entries.into_iter().map(RootTree::full_root).collect();
+    combine_state_root(account_root, root_of(&shard_roots), height)
+}
+
+/// Value of a transaction in the `tx_locations` tree: block height and index in the block
+/// (both u64 big-endian)
+fn tx_location(height: u64, tx_index: usize) -> [u8; 16] {
+    let mut value = [0u8; 16];
+    value[..8].copy_from_slice(&height.to_be_bytes());
+    value[8..].copy_from_slice(&(tx_index as u64).to_be_bytes());
+    value
+}
+
+/// Highest state height `trace_transaction` rebuilds by replaying blocks
+const MAX_TRACE_REPLAY_HEIGHT: u64 = 10_000;
+
+/// One check evaluated while tracing a transaction
 #[derive(Debug, Clone, serde::Serialize)]
-pub struct QuotaUsage {
-    pub assets_count: u64,
-    pub assets_limit: u64,
-    pub blob_files_count: u64,
-    pub blob_files_limit: u64,
-    pub blob_storage_estimate: u64,
-    pub blob_storage_limit: u64,
-    pub metadata_size_limit: usize,
+pub struct TraceStep {
+    pub check: String,
+    pub detail: String,
+    pub passed: bool,
+}
+
+/// Step-by-step re-execution of a transaction (nothing is committed)
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TransactionTrace {
+    /// Height of the state the transaction was executed against
+    pub state_height: u64,
+    pub steps: Vec<TraceStep>,
+    /// Error of the failing check; `None` if the transaction succeeds
+    pub error: Option<String>,
+    /// `HazeError::code` of the failing check
+    pub error_code: Option<u32>,
+    pub error_params: std::collections::BTreeMap<String, u64>,
+}
+
+/// Number of assets per attribute value, by attribute name (both sorted)
+pub type TraitCounts = std::collections::BTreeMap<String, std::collections::BTreeMap<String, u64>>;
+
+/// Key of an asset in the zone index (`None` if it has no game_id or zone)
+fn zone_index_key(data: &crate::types::AssetData) -> Option<String> {
+    Some(format!("{}:{}", data.game_id.as_ref()?, asset_zone(data)?))
+}
+
+/// Distinct (name, value) attribute pairs of an asset, values as text; duplicates count once
+fn asset_traits(asset_state: &AssetState) -> std::collections::BTreeSet<(&str, String)> {
+    asset_state.data.attributes
+        .iter()
+        .map(|attr| (attr.name.as_str(), attr.value.to_string()))
+        .collect()
 }
 
+/// An asset entering, leaving or changing owner within a collection
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct AssetState {
-    pub owner: Address,
-    pub data: crate::types::AssetData,
-    pub created_at: i64,
-    pub updated_at: i64,
-    /// Blob references for large files (Core density)
-    /// Maps blob key to blob hash
-    #[serde(default)]
-    pub blob_refs: HashMap<String, Hash>,
-    /// History of asset changes (limited to last 100 entries)
-    #[serde(default)]
-    pub history: Vec<AssetHistoryEntry>,
-    /// Version snapshots (limited to last 10 versions)
-    #[serde(default)]
-    pub versions: Vec<AssetVersion>,
-    /// Current version number (increments on each snapshot)
-    #[serde(default)]
-    pub current_version: u64,
-    /// Permission grants (GameContract, PublicRead)
-    #[serde(default)]
-    pub permissions: Vec<AssetPermission>,
-    /// If true, anyone can read the asset
-    #[serde(default)]
-    pub public_read: bool,
+struct OwnershipChange {
+    height: u64,
+    asset_id: Hash,
+    /// `None` when the asset left the collection (burned, merged away, moved to another game_id)
+    owner: Option<Address>,
 }
 
-impl StateManager {
-    /// Create a new StateManager
-    ///
-    /// Initializes the state manager with an empty state, default tokenomics, and economy.
-    ///
-    /// # Arguments
-    /// * `config` - Configuration for the node, including database path
-    ///
-    /// # Errors
-    /// Returns an error if the database cannot be opened.
-    ///
-    /// # Example
-    /// ```no_run
-    /// use haze::config::Config;
-    /// use haze::state::StateManager;
-    /// use std::path::PathBuf;
-    ///
-    /// let mut config = Config::default();
-    /// // Use a unique database path to avoid conflicts
-    /// config.storage.db_path = PathBuf::from("./haze_db_doctest");
-    /// let state_manager = StateManager::new(&config).unwrap();
-    /// assert_eq!(state_manager.current_height(), 0);
-    /// ```
-    pub fn new(config: &Config) -> Result<Self> {
-        let db = sled::open(&config.storage.db_path)
-            .map_err(|e| HazeError::Database(format!("Failed to open database: {}", e)))?;
+/// Collection-relative rarity of an asset
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RarityScore {
+    /// Sum over the asset's traits of `collection_size / assets_with_trait`
+    pub score: f64,
+    /// 1 for the rarest asset; equal scores share a rank
+    pub rank: usize,
+    pub collection_size: usize,
+}
 
-        let state = Self {
-            db: Arc::new(db),
-            config: Arc::new(config.clone()),
-            accounts: Arc::new(DashMap::new()),
-            assets: Arc::new(DashMap::new()),
-            blocks: Arc::new(DashMap::new()),
-            current_height: Arc::new(RwLock::new(0)),
-            tokenomics: Arc::new(Tokenomics::new()),
-            economy: Arc::new(FogEconomy::new()),
-            ws_tx: Arc::new(RwLock::new(None)),
-            asset_index_by_owner: Arc::new(DashMap::new()),
-            asset_index_by_game_id: Arc::new(DashMap::new()),
-            asset_index_by_density: Arc::new(DashMap::new()),
-            asset_access_count: Arc::new(DashMap::new()),
-            contracts: Arc::new(DashMap::new()),
-        };
-        state.replay_blocks_from_db()?;
-        Ok(state)
+/// Statistical rarity scores for every asset of a collection
+fn compute_rarity_scores<'a>(
+    collection_size: usize,
+    traits: &TraitCounts,
+    assets: impl Iterator<Item = (Hash, &'a AssetState)>,
+) -> HashMap<Hash, RarityScore> {
+    let mut scored: Vec<(Hash, f64)> = assets
+        .map(|(asset_id, asset_state)| {
+            let score = asset_traits(asset_state)
+                .into_iter()
+                .filter_map(|(name, value)| traits.get(name).and_then(|values| values.get(&value)))
+                .filter(|count| **count > 0)
+                .map(|count| collection_size as f64 / *count as f64)
+                .sum();
+            (asset_id, score)
+        })
+        .collect();
+    scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mut scores = HashMap::with_capacity(scored.len());
+    let mut rank = 0;
+    let mut previous = None;
+    for (position, (asset_id, score)) in scored.into_iter().enumerate() {
+        if previous != Some(score) {
+            rank = position + 1;
+            previous = Some(score);
+        }
+        scores.insert(asset_id, RarityScore { score, rank, collection_size });
     }
+    scores
+}
 
-    /// Replay blocks persisted in sled to restore state after restart (blob_refs, history, etc.).
-    fn replay_blocks_from_db(&self) -> Result<()> {
-        let mut entries: Vec<(u64, Block)> = Vec::new();
-        for item in self.db.scan_prefix(BLOCK_HEIGHT_PREFIX) {
-            let (key, value) = item.map_err(|e| HazeError::Database(e.to_string()))?;
-            if key.len() != BLOCK_HEIGHT_PREFIX.len() + 8 {
-                continue;
-            }
-            let height_bytes: [u8; 8] = key[BLOCK_HEIGHT_PREFIX.len()..]
-                .try_into()
-                .map_err(|_| HazeError::Database("Invalid block key length".to_string()))?;
-            let height = u64::from_be_bytes(height_bytes);
-            let block: Block = bincode::deserialize(&value).map_err(|e| HazeError::Serialization(e.to_string()))?;
-            entries.push((height, block));
+/// Uncommitted writes made while applying a block (or a single transaction).
+///
+/// Reads fall through to the committed maps of the owning `StateManager`, so a
+/// transaction sees the effects of earlier transactions in the same block. Nothing
+/// becomes visible to other readers until `StateManager::commit_overlay` runs;
+/// dropping the overlay discards every write. Gas fee burns, stakes and WebSocket
+/// events are queued the same way so a rejected block leaves no trace.
+pub struct StateOverlay<'a> {
+    base: &'a StateManager,
+    /// Height of the block being applied
+    height: u64,
+    /// Timestamp of the block being applied, stamped on asset changes and their history
+    timestamp: i64,
+    accounts: HashMap<Address, AccountState>,
+    /// `None` marks an asset removed in this overlay (merge source, split parent)
+    assets: HashMap<Hash, Option<AssetState>>,
+    contracts: HashMap<Address, (ContractInfo, Vec<u8>)>,
+    /// `None` marks a hook removed in this overlay
+    asset_hooks: HashMap<String, Option<RegisteredAssetHook>>,
+    payment_requests: HashMap<Hash, PaymentRequest>,
+    /// Composition graph edges created by merges and splits
+    lineage: Vec<LineageEdge>,
+    orders: HashMap<Hash, Order>,
+    trades: Vec<Trade>,
+    /// Orders placed in this overlay
+    orders_placed: u64,
+    /// Ids of the orders each account placed in this overlay (one block)
+    block_orders: HashMap<Address, Vec<Hash>>,
+    /// (buyer, asset) pairs bought on an order book in this overlay
+    block_acquired: HashSet<(Address, Hash)>,
+    referrals: HashMap<(String, Address), Referral>,
+    /// Earnings to add per (referrer, game_id)
+    referral_earnings: HashMap<(Address, String), ReferralEarnings>,
+    subscriptions: HashMap<Hash, Subscription>,
+    loot_boxes: HashMap<Hash, LootBox>,
+    leaderboards: HashMap<Hash, Leaderboard>,
+    seasons: HashMap<Hash, Season>,
+    /// Collections frozen (`Some(season)`) or thawed (`None`)
+    frozen_collections: HashMap<String, Option<Hash>>,
+    asset_pools: HashMap<Hash, AssetPool>,
+    /// Staked (`Some`) and unstaked (`None`) assets
+    asset_stakes: HashMap<Hash, Option<AssetStake>>,
+    breedings: HashMap<Hash, Breeding>,
+    last_bred: HashMap<Hash, u64>,
+    upgrades: HashMap<Hash, Upgrade>,
+    gas_fees: Vec<u64>,
+    /// Gas fees waived by stake discounts
+    gas_discount: u64,
+    /// Contract gas used (contract calls, asset hooks, paymaster decisions)
+    gas_used: u64,
+    /// Gas paid by paymasters as (paymaster, window index, amount)
+    sponsorships: Vec<(Address, u64, u64)>,
+    /// Self-stakes (validator, amount) to register in tokenomics
+    stakes: Vec<(Address, u64)>,
+    /// Contract logs emitted by the transaction being applied
+    logs: Vec<Log>,
+    /// Validators to release from jail in tokenomics
+    unjails: Vec<Address>,
+    /// Commission changes (validator, rate, block height) to record in tokenomics
+    commissions: Vec<(Address, u64, u64)>,
+    /// Committee parameter votes (voter, parameters) to record in tokenomics
+    committee_votes: Vec<(Address, crate::tokenomics::CommitteeParams)>,
+    events: Vec<WsEvent>,
+    /// Steps recorded while tracing (`None` when not tracing)
+    trace: Option<Vec<TraceStep>>,
+}
+
+impl<'a> StateOverlay<'a> {
+    /// Overlay for transactions applied outside a block: they count as the next block's,
+    /// stamped with the local clock
+    pub fn new(base: &'a StateManager) -> Self {
+        Self::at(base, base.current_height() + 1, chrono::Utc::now().timestamp())
+    }
+
+    /// Overlay for the block at `height` with header timestamp `timestamp`, so every node
+    /// stores the same state
+    pub fn at(base: &'a StateManager, height: u64, timestamp: i64) -> Self {
+        Self {
+            base,
+            height,
+            timestamp,
+            accounts: HashMap::new(),
+            assets: HashMap::new(),
+            contracts: HashMap::new(),
+            asset_hooks: HashMap::new(),
+            payment_requests: HashMap::new(),
+            lineage: Vec::new(),
+            orders: HashMap::new(),
+            trades: Vec::new(),
+            orders_placed: 0,
+            block_orders: HashMap::new(),
+            block_acquired: HashSet::new(),
+            referrals: HashMap::new(),
+            referral_earnings: HashMap::new(),
+            subscriptions: HashMap::new(),
+            loot_boxes: HashMap::new(),
+            leaderboards: HashMap::new(),
+            seasons: HashMap::new(),
+            frozen_collections: HashMap::new(),
+            asset_pools: HashMap::new(),
+            asset_stakes: HashMap::new(),
+            breedings: HashMap::new(),
+            last_bred: HashMap::new(),
+            upgrades: HashMap::new(),
+            gas_fees: Vec::new(),
+            gas_discount: 0,
+            gas_used: 0,
+            sponsorships: Vec::new(),
+            stakes: Vec::new(),
+            logs: Vec::new(),
+            unjails: Vec::new(),
+            commissions: Vec::new(),
+            committee_votes: Vec::new(),
+            events: Vec::new(),
+            trace: None,
         }
-        entries.sort_by_key(|(h, _)| *h);
-        for (_, block) in entries {
-            self.apply_block(&block)?;
+    }
+
+    /// Get account as seen by this overlay (default empty account if missing)
+    pub fn account(&self, address: &Address) -> AccountState {
+        if let Some(account) = self.accounts.get(address) {
+            return account.clone();
         }
-        Ok(())
+        self.base.accounts.get(address).map(|a| a.clone()).unwrap_or_default()
     }
 
-    /// Set WebSocket broadcaster for real-time event notifications
-    pub fn set_ws_tx(&self, tx: broadcast::Sender<WsEvent>) {
-        *self.ws_tx.write() = Some(tx);
+    /// Get asset as seen by this overlay
+    pub fn asset(&self, asset_id: &Hash) -> Option<AssetState> {
+        match self.assets.get(asset_id) {
+            Some(asset) => asset.clone(),
+            None => self.base.assets.get(asset_id).map(|a| a.clone()),
+        }
     }
 
-    /// Broadcast WebSocket event if broadcaster is available
-    fn broadcast_event(&self, event: WsEvent) {
-        if let Some(ref tx) = *self.ws_tx.read() {
-            let _ = tx.send(event);
+    /// Get contract code as seen by this overlay
+    pub fn contract(&self, address: &Address) -> Result<Option<Vec<u8>>> {
+        match self.contracts.get(address) {
+            Some((_, code)) => Ok(Some(code.clone())),
+            None => self.base.get_contract_code(address),
         }
     }
 
-    /// Add history entry to asset state (limited to last 100 entries)
-    fn add_asset_history(asset_state: &mut AssetState, action: AssetAction, changes: HashMap<String, String>) {
-        let history_entry = AssetHistoryEntry {
-            timestamp: chrono::Utc::now().timestamp(),
-            action,
-            changes,
-        };
-        
-        asset_state.history.push(history_entry);
-        
-        // Limit history to last 100 entries
-        if asset_state.history.len() > 100 {
-            asset_state.history.remove(0);
+    /// Get contract metadata as seen by this overlay
+    pub fn contract_info(&self, address: &Address) -> Option<ContractInfo> {
+        match self.contracts.get(address) {
+            Some((info, _)) => Some(info.clone()),
+            None => self.base.get_contract(address),
         }
     }
 
-    /// Create a version snapshot from asset state
-    fn create_version_from_state(asset_state: &AssetState) -> AssetVersion {
-        AssetVersion {
-            version: asset_state.current_version + 1,
-            timestamp: chrono::Utc::now().timestamp(),
-            data: asset_state.data.clone(),
-            blob_refs: asset_state.blob_refs.clone(),
+    /// Asset hook of a collection as seen by this overlay
+    pub fn asset_hook(&self, game_id: &str) -> Option<RegisteredAssetHook> {
+        match self.asset_hooks.get(game_id) {
+            Some(hook) => hook.clone(),
+            None => self.base.asset_hooks.get(game_id).map(|h| h.clone()),
         }
     }
 
-    /// Add snapshot to asset state (limited to last 10 versions)
-    fn add_asset_snapshot(asset_state: &mut AssetState) {
-        let snapshot = Self::create_version_from_state(asset_state);
-        asset_state.current_version = snapshot.version;
-        asset_state.versions.push(snapshot);
-        
-        // Limit versions to last 10
-        if asset_state.versions.len() > 10 {
-            asset_state.versions.remove(0);
+    /// Payment request as seen by this overlay
+    pub fn payment_request(&self, id: &Hash) -> Option<PaymentRequest> {
+        match self.payment_requests.get(id) {
+            Some(request) => Some(request.clone()),
+            None => self.base.payment_requests.get(id).map(|r| r.clone()),
         }
     }
 
-    /// Add asset to indexes (only if not already present)
-    fn add_asset_to_indexes(&self, asset_id: &Hash, asset_state: &AssetState) {
-        // Index by owner (optimized: check before adding to avoid unnecessary clone)
-        self.asset_index_by_owner
-            .entry(asset_state.owner)
-            .or_insert_with(Vec::new)
-            .push(*asset_id);
-        
-        // Index by game_id
-        if let Some(ref game_id) = asset_state.data.game_id {
-            self.asset_index_by_game_id
-                .entry(game_id.clone())
-                .or_insert_with(Vec::new)
-                .push(*asset_id);
+    /// Order as seen by this overlay
+    pub fn order(&self, id: &Hash) -> Option<Order> {
+        match self.orders.get(id) {
+            Some(order) => Some(order.clone()),
+            None => self.base.orders.get(id).map(|o| o.clone()),
         }
-        
-        // Index by density
-        let density_level = asset_state.data.density as u8;
-        let mut density_assets = self.asset_index_by_density
-            .entry(density_level)
-            .or_insert_with(Vec::new);
-        if !density_assets.contains(asset_id) {
-            density_assets.push(*asset_id);
+    }
+
+    /// Referrer of `player` in collection `game_id` as seen by this overlay
+    pub fn referral(&self, game_id: &str, player: &Address) -> Option<Referral> {
+        let key = (game_id.to_string(), *player);
+        match self.referrals.get(&key) {
+            Some(referral) => Some(referral.clone()),
+            None => self.base.referrals.get(&key).map(|r| r.clone()),
         }
     }
 
-    /// Remove asset from indexes
-    fn remove_asset_from_indexes(&self, asset_id: &Hash, asset_state: &AssetState) {
-        // Remove from owner index
-        if let Some(mut owner_assets) = self.asset_index_by_owner.get_mut(&asset_state.owner) {
-            owner_assets.retain(|&id| id != *asset_id);
-            if owner_assets.is_empty() {
-                drop(owner_assets);
-                self.asset_index_by_owner.remove(&asset_state.owner);
-            }
+    /// Subscription as seen by this overlay
+    pub fn subscription(&self, id: &Hash) -> Option<Subscription> {
+        match self.subscriptions.get(id) {
+            Some(subscription) => Some(subscription.clone()),
+            None => self.base.subscriptions.get(id).map(|s| s.clone()),
         }
-        
-        // Remove from game_id index
-        if let Some(ref game_id) = asset_state.data.game_id {
-            if let Some(mut game_assets) = self.asset_index_by_game_id.get_mut(game_id) {
-                game_assets.retain(|&id| id != *asset_id);
-                if game_assets.is_empty() {
-                    drop(game_assets);
-                    self.asset_index_by_game_id.remove(game_id);
-                }
-            }
+    }
+
+    /// Loot box as seen by this overlay
+    pub fn loot_box(&self, id: &Hash) -> Option<LootBox> {
+        match self.loot_boxes.get(id) {
+            Some(loot_box) => Some(loot_box.clone()),
+            None => self.base.loot_boxes.get(id).map(|b| b.clone()),
         }
-        
-        // Remove from density index
-        let density_level = asset_state.data.density as u8;
-        if let Some(mut density_assets) = self.asset_index_by_density.get_mut(&density_level) {
-            density_assets.retain(|&id| id != *asset_id);
-            if density_assets.is_empty() {
-                drop(density_assets);
-                self.asset_index_by_density.remove(&density_level);
-            }
+    }
+
+    /// Leaderboard as seen by this overlay
+    pub fn leaderboard(&self, id: &Hash) -> Option<Leaderboard> {
+        match self.leaderboards.get(id) {
+            Some(board) => Some(board.clone()),
+            None => self.base.leaderboards.get(id).map(|b| b.clone()),
         }
     }
 
-    /// Get account state by address
-    ///
-    /// # Arguments
-    /// * `address` - The account address
-    ///
-    /// # Returns
-    /// `Some(AccountState)` if the account exists, `None` otherwise.
-    ///
-    /// # Example
-    /// ```
-    /// use haze::crypto::KeyPair;
-    /// use haze::state::StateManager;
-    /// use haze::config::Config;
-    ///
-    /// let config = Config::default();
-    /// let state = StateManager::new(&config)?;
-    /// let keypair = KeyPair::generate();
-    /// let address = keypair.address();
-    ///
-    /// // New account doesn't exist yet
-    /// assert!(state.get_account(&address).is_none());
-    /// # Ok::<(), haze::error::HazeError>(())
-    /// ```
-    pub fn get_account(&self, address: &Address) -> Option<AccountState> {
-        self.accounts.get(address).map(|v| v.clone())
+    /// Asset pool as seen by this overlay
+    pub fn asset_pool(&self, id: &Hash) -> Option<AssetPool> {
+        match self.asset_pools.get(id) {
+            Some(pool) => Some(pool.clone()),
+            None => self.base.asset_pools.get(id).map(|p| p.clone()),
+        }
     }
 
-    /// Get asset state by asset ID
-    ///
-    /// # Arguments
-    /// * `asset_id` - The asset identifier (hash)
-    ///
-    /// # Returns
-    /// `Some(AssetState)` if the asset exists, `None` otherwise.
-    ///
-    /// # Performance
-    /// This method tracks access frequency for cache optimization.
-    pub fn get_asset(&self, asset_id: &Hash) -> Option<AssetState> {
-        let result = self.assets.get(asset_id).map(|v| v.clone());
-        
-        // Track access frequency for cache optimization
-        if result.is_some() {
-            *self.asset_access_count.entry(*asset_id).or_insert(0) += 1;
+    /// Stake of an asset as seen by this overlay
+    pub fn asset_stake(&self, asset_id: &Hash) -> Option<AssetStake> {
+        match self.asset_stakes.get(asset_id) {
+            Some(stake) => stake.clone(),
+            None => self.base.asset_stakes.get(asset_id).map(|s| s.clone()),
         }
-        
-        result
     }
-    
-    /// Get asset state without blob data (lazy loading)
-    ///
-    /// # Arguments
-    /// * `asset_id` - The asset identifier (hash)
-    ///
-    /// # Returns
-    /// `Some(AssetState)` if the asset exists, `None` otherwise.
-    /// Blob references are included but blob data is not loaded.
-    pub fn get_asset_lightweight(&self, asset_id: &Hash) -> Option<AssetState> {
-        // Same as get_asset, but explicitly indicates lazy blob loading
-        self.get_asset(asset_id)
+
+    /// Stakes of `owner` in an asset pool as seen by this overlay, by asset id
+    fn owner_stakes(&self, owner: &Address, pool_id: &Hash) -> Vec<AssetStake> {
+        let mut ids = self.base.asset_stakes_by_owner.get(owner).map(|ids| ids.clone()).unwrap_or_default();
+        ids.extend(self.asset_stakes.keys().copied());
+        ids.sort();
+        ids.dedup();
+        ids.iter()
+            .filter_map(|id| self.asset_stake(id))
+            .filter(|stake| stake.owner == *owner && stake.pool_id == *pool_id)
+            .collect()
     }
-    
-    /// Get most frequently accessed assets
-    ///
-    /// # Arguments
-    /// * `limit` - Maximum number of assets to return
-    ///
-    /// # Returns
-    /// Vector of (asset_id, access_count) tuples sorted by access frequency
-    pub fn get_most_accessed_assets(&self, limit: usize) -> Vec<(Hash, u64)> {
-        let mut access_list: Vec<(Hash, u64)> = self.asset_access_count
-            .iter()
-            .map(|entry| (*entry.key(), *entry.value()))
-            .collect();
-        
-        access_list.sort_by(|a, b| b.1.cmp(&a.1)); // Sort by access count descending
-        access_list.truncate(limit);
-        access_list
+
+    /// Breeding as seen by this overlay
+    pub fn breeding(&self, id: &Hash) -> Option<Breeding> {
+        match self.breedings.get(id) {
+            Some(breeding) => Some(breeding.clone()),
+            None => self.base.breedings.get(id).map(|b| b.clone()),
+        }
     }
 
-    /// Get asset history by asset ID
-    ///
-    /// # Arguments
-    /// * `asset_id` - The asset identifier (hash)
-    /// * `limit` - Maximum number of history entries to return (0 = all)
-    ///
-    /// # Returns
-    /// `Some(Vec<AssetHistoryEntry>)` if the asset exists, `None` otherwise.
-    pub fn get_asset_history(&self, asset_id: &Hash, limit: usize) -> Option<Vec<AssetHistoryEntry>> {
-        self.assets.get(asset_id).map(|asset_state| {
-            let history = asset_state.history.clone();
-            if limit > 0 && history.len() > limit {
-                history.into_iter().rev().take(limit).rev().collect()
-            } else {
-                history
-            }
-        })
+    /// Height an asset last bred at as seen by this overlay
+    pub fn last_bred(&self, asset_id: &Hash) -> Option<u64> {
+        match self.last_bred.get(asset_id) {
+            Some(height) => Some(*height),
+            None => self.base.last_bred.get(asset_id).map(|h| *h),
+        }
     }
 
-    /// Get asset version by version number
-    ///
-    /// # Arguments
-    /// * `asset_id` - The asset identifier (hash)
-    /// * `version` - The version number (0 = current version)
-    ///
-    /// # Returns
-    /// `Some(AssetVersion)` if the version exists, `None` otherwise.
-    pub fn get_asset_version(&self, asset_id: &Hash, version: u64) -> Option<AssetVersion> {
-        self.assets.get(asset_id).and_then(|asset_state| {
-            if version == 0 || version == asset_state.current_version {
-                // Return current version
-                Some(AssetVersion {
-                    version: asset_state.current_version,
-                    timestamp: asset_state.updated_at,
-                    data: asset_state.data.clone(),
-                    blob_refs: asset_state.blob_refs.clone(),
-                })
+    /// Upgrade as seen by this overlay
+    pub fn upgrade(&self, id: &Hash) -> Option<Upgrade> {
+        match self.upgrades.get(id) {
+            Some(upgrade) => Some(upgrade.clone()),
+            None => self.base.upgrades.get(id).map(|u| u.clone()),
+        }
+    }
+
+    /// Season as seen by this overlay
+    pub fn season(&self, id: &Hash) -> Option<Season> {
+        match self.seasons.get(id) {
+            Some(season) => Some(season.clone()),
+            None => self.base.seasons.get(id).map(|s| s.clone()),
+        }
+    }
+
+    /// Seasons of a game as seen by this overlay, in season order
+    fn game_seasons(&self, game_id: &str) -> Vec<Season> {
+        let mut ids: Vec<Hash> = self.base.seasons_by_game.get(game_id).map(|ids| ids.clone()).unwrap_or_default();
+        ids.extend(self.seasons.values().filter(|s| s.game_id == game_id).map(|s| s.id));
+        ids.sort();
+        ids.dedup();
+        let mut seasons: Vec<Season> = ids.iter().filter_map(|id| self.season(id)).collect();
+        seasons.sort_by_key(|s| s.start_height);
+        seasons
+    }
+
+    /// Season that froze `collection`, if it is frozen
+    fn frozen_by(&self, collection: &str) -> Option<Hash> {
+        match self.frozen_collections.get(collection) {
+            Some(season) => *season,
+            None => self.base.frozen_collections.get(collection).map(|s| *s),
+        }
+    }
+
+    /// Every frozen collection as seen by this overlay
+    fn frozen_collection_names(&self) -> HashSet<String> {
+        let mut frozen: HashSet<String> = self.base.frozen_collections.iter().map(|e| e.key().clone()).collect();
+        for (collection, season) in &self.frozen_collections {
+            if season.is_some() {
+                frozen.insert(collection.clone());
             } else {
-                // Find version in history
-                asset_state.versions.iter()
-                    .find(|v| v.version == version)
-                    .cloned()
+                frozen.remove(collection);
             }
-        })
+        }
+        frozen
     }
 
-    /// Get all versions of an asset
-    pub fn get_asset_versions(&self, asset_id: &Hash) -> Option<Vec<AssetVersion>> {
-        self.assets.get(asset_id).map(|asset_state| {
-            let mut versions = asset_state.versions.clone();
-            // Add current version only if it's not already in versions
-            let current_exists = versions.iter().any(|v| v.version == asset_state.current_version);
-            if !current_exists {
-                versions.push(AssetVersion {
-                    version: asset_state.current_version,
-                    timestamp: asset_state.updated_at,
-                    data: asset_state.data.clone(),
-                    blob_refs: asset_state.blob_refs.clone(),
-                });
-            }
-            versions.sort_by_key(|v| v.version);
-            versions
-        })
+    /// Gas a paymaster sponsored in window `window`, including this overlay
+    fn paymaster_spent(&self, paymaster: &Address, window: u64) -> u64 {
+        let base = self.base.paymaster_spend.get(paymaster)
+            .filter(|spend| spend.0 == window)
+            .map_or(0, |spend| spend.1);
+        let pending: u64 = self.sponsorships.iter()
+            .filter(|(address, w, _)| address == paymaster && *w == window)
+            .map(|(_, _, amount)| amount)
+            .sum();
+        base + pending
     }
 
-    /// Create a manual snapshot of an asset
-    pub fn create_asset_snapshot(&self, asset_id: &Hash) -> Result<u64> {
-        let mut asset_state = self.assets.get_mut(asset_id)
-            .ok_or_else(|| HazeError::InvalidTransaction(
-                "Asset not found".to_string()
-            ))?;
-        
-        Self::add_asset_snapshot(&mut asset_state);
-        let version = asset_state.current_version;
-        let owner = asset_state.owner;
-        self.broadcast_event(WsEvent::AssetVersionCreated {
-            asset_id: crate::types::hash_to_hex(asset_id),
-            version,
-            owner: crate::types::address_to_hex(&owner),
-        });
-        Ok(version)
+    /// Live subscriptions whose next charge is at `height`, by id
+    fn due_subscriptions(&self, height: u64) -> Vec<Subscription> {
+        let mut ids: Vec<Hash> = self.base.subscriptions_due.get(&height).map(|ids| ids.clone()).unwrap_or_default();
+        ids.extend(self.subscriptions.keys().copied());
+        ids.sort();
+        ids.dedup();
+        ids.iter()
+            .filter_map(|id| self.subscription(id))
+            .filter(|s| s.is_live() && s.next_charge_height == height)
+            .collect()
     }
 
-    /// Search assets by owner
-    ///
-    /// # Returns
-    /// Vector of asset IDs owned by the address (sorted by creation time, most recent first)
-    pub fn search_assets_by_owner(&self, owner: &Address) -> Vec<Hash> {
-        let mut assets = self.asset_index_by_owner
+    /// Open orders of a collection as seen by this overlay, by sequence
+    pub fn open_orders(&self, game_id: &str) -> Vec<Order> {
+        let mut ids: Vec<Hash> = self.base.open_orders.get(game_id).map(|ids| ids.clone()).unwrap_or_default();
+        ids.extend(self.orders.values().filter(|o| o.game_id == game_id).map(|o| o.id));
+        ids.sort();
+        ids.dedup();
+        let mut orders: Vec<Order> = ids
+            .iter()
+            .filter_map(|id| self.order(id))
+            .filter(|o| o.status == OrderStatus::Open)
+            .collect();
+        orders.sort_by_key(|o| o.sequence);
+        orders
+    }
+
+    /// Asset IDs owned by `owner` as seen by this overlay (unordered)
+    pub fn asset_ids_by_owner(&self, owner: &Address) -> Vec<Hash> {
+        let mut ids: Vec<Hash> = self.base.asset_index_by_owner
             .get(owner)
-            .map(|v| v.clone())
+            .map(|v| v.iter().filter(|id| !self.assets.contains_key(*id)).copied().collect())
             .unwrap_or_default();
-        
-        // Sort by creation time (most recent first) for better UX
-        assets.sort_by(|a, b| {
-            let time_a = self.assets.get(a).map(|s| s.created_at).unwrap_or(0);
-            let time_b = self.assets.get(b).map(|s| s.created_at).unwrap_or(0);
-            time_b.cmp(&time_a) // Descending order
-        });
-        
-        assets
+        for (asset_id, asset) in &self.assets {
+            if matches!(asset, Some(a) if a.owner == *owner) {
+                ids.push(*asset_id);
+            }
+        }
+        ids
     }
 
-    /// Search assets by game_id
-    ///
-    /// # Returns
-    /// Vector of asset IDs for the game (sorted by creation time, most recent first)
-    pub fn search_assets_by_game_id(&self, game_id: &str) -> Vec<Hash> {
-        let mut assets = self.asset_index_by_game_id
-            .get(game_id)
-            .map(|v| v.clone())
-            .unwrap_or_default();
-        
-        // Sort by creation time (most recent first) for better UX
-        assets.sort_by(|a, b| {
-            let time_a = self.assets.get(a).map(|s| s.created_at).unwrap_or(0);
-            let time_b = self.assets.get(b).map(|s| s.created_at).unwrap_or(0);
-            time_b.cmp(&time_a) // Descending order
-        });
-        
-        assets
+    fn put_account(&mut self, address: Address, account: AccountState) {
+        self.accounts.insert(address, account);
     }
 
-    /// Search assets by density level
-    ///
-    /// # Returns
-    /// Vector of asset IDs with the specified density (sorted by creation time, most recent first)
-    pub fn search_assets_by_density(&self, density: crate::types::DensityLevel) -> Vec<Hash> {
-        let density_level = density as u8;
-        let mut assets = self.asset_index_by_density
-            .get(&density_level)
-            .map(|v| v.clone())
-            .unwrap_or_default();
-        
-        // Sort by creation time (most recent first) for better UX
-        assets.sort_by(|a, b| {
-            let time_a = self.assets.get(a).map(|s| s.created_at).unwrap_or(0);
-            let time_b = self.assets.get(b).map(|s| s.created_at).unwrap_or(0);
-            time_b.cmp(&time_a) // Descending order
-        });
-        
-        assets
+    fn put_asset(&mut self, asset_id: Hash, asset: AssetState) {
+        self.assets.insert(asset_id, Some(asset));
     }
 
-    /// Full-text search in metadata (simple substring matching)
-    pub fn search_assets_by_metadata(&self, query: &str) -> Vec<Hash> {
-        let query_lower = query.to_lowercase();
-        let mut results = Vec::new();
-        
-        for entry in self.assets.iter() {
-            let asset_state = entry.value();
-            // Search in metadata values
-            for value in asset_state.data.metadata.values() {
-                if value.to_lowercase().contains(&query_lower) {
-                    results.push(*entry.key());
-                    break; // Found match, no need to check other values for this asset
-                }
-            }
-        }
-        
-        results
+    fn remove_asset(&mut self, asset_id: Hash) {
+        self.assets.insert(asset_id, None);
     }
 
-    /// Check if account has reached asset limit
-    ///
-    /// # Arguments
-    /// * `owner` - Account address
-    ///
-    /// # Returns
-    /// `Ok(())` if within limits, `Err(HazeError)` if limit exceeded
-    fn check_asset_count_limit(&self, owner: &Address) -> Result<()> {
-        let quota = self.config.get_node_quota();
-        let current_count = self.search_assets_by_owner(owner).len() as u64;
-        
-        if current_count >= quota.max_assets_per_account {
-            return Err(HazeError::InvalidTransaction(
-                format!(
-                    "Asset limit exceeded: {} >= {} (node type: {})",
-                    current_count,
-                    quota.max_assets_per_account,
-                    self.config.network.node_type
-                )
-            ));
-        }
-        
-        Ok(())
+    /// Lend the staged assets to a contract call's asset precompiles
+    fn lend_assets(&mut self) -> Box<dyn AssetHost> {
+        let frozen = self.frozen_collection_names();
+        Box::new(ContractAssetView::new(self.base.assets.clone(), std::mem::take(&mut self.assets), frozen, self.timestamp))
     }
 
-    /// Check if metadata size is within limits
-    ///
-    /// # Arguments
-    /// * `metadata_size` - Size of metadata in bytes
-    ///
-    /// # Returns
-    /// `Ok(())` if within limits, `Err(HazeError)` if limit exceeded
-    fn check_metadata_size_limit(&self, metadata_size: usize) -> Result<()> {
-        let quota = self.config.get_node_quota();
-        
-        if metadata_size > quota.max_metadata_size {
-            return Err(HazeError::AssetSizeExceeded(
-                metadata_size,
-                quota.max_metadata_size
-            ));
-        }
-        
+    /// Take back assets lent by `lend_assets`, keeping the call's writes only if it succeeded
+    fn return_assets(&mut self, host: Option<Box<dyn AssetHost>>, keep_writes: bool) -> Result<()> {
+        let host: Box<dyn std::any::Any> = host
+            .ok_or_else(|| HazeError::VM("Asset host was not returned by the VM".to_string()))?;
+        let view = host
+            .downcast::<ContractAssetView>()
+            .map_err(|_| HazeError::VM("Unexpected asset host".to_string()))?;
+        self.assets = view.into_staged(keep_writes);
         Ok(())
     }
 
-    /// Check if asset has reached blob file limit
-    ///
-    /// # Arguments
-    /// * `asset_id` - Asset identifier
-    /// * `additional_blobs` - Number of additional blob files to add
-    ///
-    /// # Returns
-    /// `Ok(())` if within limits, `Err(HazeError)` if limit exceeded
-    fn check_blob_files_limit(&self, asset_id: &Hash, additional_blobs: u64) -> Result<()> {
-        let quota = self.config.get_node_quota();
-        
-        let current_blob_count = if let Some(asset_state) = self.assets.get(asset_id) {
-            asset_state.blob_refs.len() as u64
-        } else {
-            0
-        };
-        
-        let new_blob_count = current_blob_count + additional_blobs;
-        
-        if new_blob_count > quota.max_blob_files_per_asset {
-            return Err(HazeError::InvalidTransaction(
-                format!(
-                    "Blob files limit exceeded: {} > {} (node type: {})",
-                    new_blob_count,
-                    quota.max_blob_files_per_asset,
-                    self.config.network.node_type
-                )
-            ));
-        }
-        
-        Ok(())
+    fn put_contract(&mut self, info: ContractInfo, code: Vec<u8>) {
+        self.contracts.insert(info.address, (info, code));
     }
 
-    /// Check if account has reached blob storage limit
-    ///
-    /// # Arguments
-    /// * `owner` - Account address
-    /// * `additional_size` - Additional blob storage size in bytes
-    ///
-    /// # Returns
-    /// `Ok(())` if within limits, `Err(HazeError)` if limit exceeded
-    fn check_blob_storage_limit(&self, owner: &Address, additional_size: u64) -> Result<()> {
-        let quota = self.config.get_node_quota();
-        
-        // Calculate current blob storage for this account
-        let mut current_storage: u64 = 0;
-        let owner_assets = self.search_assets_by_owner(owner);
-        
-        for asset_id in &owner_assets {
-            if let Some(asset_state) = self.assets.get(asset_id) {
-                // Estimate blob storage size (we don't have exact sizes, so use blob count * average)
-                // This is a conservative estimate
-                let blob_count = asset_state.blob_refs.len() as u64;
-                current_storage += blob_count * 1024 * 1024; // Estimate 1MB per blob
-            }
-        }
-        
-        let new_storage = current_storage + additional_size;
-        
-        if new_storage > quota.max_blob_storage_per_account {
-            return Err(HazeError::InvalidTransaction(
-                format!(
-                    "Blob storage limit exceeded: {} > {} bytes (node type: {})",
-                    new_storage,
-                    quota.max_blob_storage_per_account,
-                    self.config.network.node_type
-                )
-            ));
+    fn put_asset_hook(&mut self, game_id: String, hook: Option<RegisteredAssetHook>) {
+        self.asset_hooks.insert(game_id, hook);
+    }
+
+    fn put_payment_request(&mut self, request: PaymentRequest) {
+        self.payment_requests.insert(request.id, request);
+    }
+
+    fn put_order(&mut self, order: Order) {
+        self.orders.insert(order.id, order);
+    }
+
+    /// Time priority of the next order placed
+    fn next_order_sequence(&mut self) -> u64 {
+        let sequence = self.base.order_sequence.load(std::sync::atomic::Ordering::SeqCst) + self.orders_placed;
+        self.orders_placed += 1;
+        sequence
+    }
+
+    fn link_assets(&mut self, parent: Hash, child: Hash, action: AssetAction) {
+        let height = self.base.current_height() + 1;
+        self.lineage.push(LineageEdge { parent, child, action, height });
+    }
+
+    fn process_gas_fee(&mut self, fee: u64) {
+        self.gas_fees.push(fee);
+    }
+
+    fn stake(&mut self, validator: Address, amount: u64) {
+        self.stakes.push((validator, amount));
+    }
+
+    fn push_logs(&mut self, logs: Vec<Log>) {
+        self.logs.extend(logs);
+    }
+
+    fn unjail(&mut self, validator: Address) {
+        self.unjails.push(validator);
+    }
+
+    fn set_commission(&mut self, validator: Address, rate_bps: u64, height: u64) {
+        self.commissions.push((validator, rate_bps, height));
+    }
+
+    fn vote_committee_params(&mut self, voter: Address, params: crate::tokenomics::CommitteeParams) {
+        self.committee_votes.push((voter, params));
+    }
+
+    fn emit(&mut self, event: WsEvent) {
+        self.events.push(event);
+    }
+
+    /// Record a passed trace step (`detail` is only built while tracing)
+    fn trace(&mut self, check: &str, detail: impl FnOnce() -> String) {
+        if let Some(steps) = self.trace.as_mut() {
+            steps.push(TraceStep { check: check.to_string(), detail: detail(), passed: true });
         }
-        
-        Ok(())
     }
 
-    /// Check if caller has write permission for an asset (Update, Condense, Evaporate, Merge, Split).
-    /// Owner always has full access. GameContract grantees have limited access when game_id matches.
-    fn check_asset_write_permission(
-        &self,
-        asset_state: &AssetState,
-        caller: &Address,
-    ) -> Result<()> {
-        if asset_state.owner == *caller {
-            return Ok(());
+    /// Record the outcome of a check while tracing and pass the result through
+    fn check<T>(&mut self, check: &str, detail: impl FnOnce() -> String, result: Result<T>) -> Result<T> {
+        if let Some(steps) = self.trace.as_mut() {
+            let detail = match &result {
+                Ok(_) => detail(),
+                Err(e) => format!("{}: {}", detail(), e),
+            };
+            steps.push(TraceStep { check: check.to_string(), detail, passed: result.is_ok() });
         }
-        let now = chrono::Utc::now().timestamp();
-        for p in &asset_state.permissions {
-            if p.grantee != *caller {
-                continue;
-            }
-            if p.level != PermissionLevel::GameContract {
-                continue;
+        result
+    }
+}
+
+/// GameContract grants of an asset unexpired at `now`, the timestamp of the block being
+/// applied so every validator reaches the same decision
+fn active_game_grants(asset_state: &AssetState, now: i64) -> impl Iterator<Item = &crate::types::AssetPermission> {
+    asset_state.permissions.iter().filter(move |p| {
+        p.level == PermissionLevel::GameContract && p.expires_at.is_none_or(|exp| now <= exp)
+    })
+}
+
+/// Owner always has write access; GameContract grantees when the grant is unexpired
+/// and its game_id (if any) matches the asset's.
+fn has_asset_write_permission(asset_state: &AssetState, caller: &Address, now: i64) -> bool {
+    if asset_state.owner == *caller {
+        return true;
+    }
+    active_game_grants(asset_state, now).any(|p| {
+        p.grantee == *caller
+            && match (&p.game_id, &asset_state.data.game_id) {
+                (Some(perm_gid), Some(asset_gid)) => perm_gid == asset_gid,
+                (None, _) => true, // No game restriction: allow any game
+                _ => false,
             }
-            if let Some(ref exp) = p.expires_at {
-                if now > *exp {
-                    continue;
-                }
+    })
+}
+
+/// Metadata write of an Update or Condense: the owner writes any key; other callers write
+/// plain and reserved keys with `has_asset_write_permission`, and namespaced keys
+/// (`game1:durability`) only with an unexpired GameContract grant for that game, whatever
+/// the asset's collection. A caller holding only namespace grants cannot change attributes
+/// or write reserved keys other than `_paymaster`.
+fn check_metadata_write_permission(
+    asset_state: &AssetState,
+    caller: &Address,
+    metadata: &HashMap<String, String>,
+    changes_attributes: bool,
+    now: i64,
+) -> Result<()> {
+    if asset_state.owner == *caller {
+        return Ok(());
+    }
+    let namespaces: HashSet<&str> = active_game_grants(asset_state, now)
+        .filter(|p| p.grantee == *caller)
+        .filter_map(|p| p.game_id.as_deref())
+        .collect();
+    let plain = has_asset_write_permission(asset_state, caller, now);
+    if !plain && namespaces.is_empty() {
+        return Err(HazeError::AccessDenied(
+            "Caller is not owner and has no GameContract permission".to_string(),
+        ));
+    }
+    let mut keys: Vec<&String> = metadata.keys().filter(|key| *key != crate::vm::PAYMASTER_KEY).collect();
+    keys.sort();
+    for key in keys {
+        match crate::assets::metadata_namespace(key) {
+            Some(namespace) if !namespaces.contains(namespace) => {
+                return Err(HazeError::AccessDenied(format!(
+                    "Caller has no GameContract grant for namespace '{}' (key '{}')", namespace, key
+                )));
             }
-            match (&p.game_id, &asset_state.data.game_id) {
-                (Some(perm_gid), Some(asset_gid)) if perm_gid == asset_gid => return Ok(()),
-                (None, _) => return Ok(()), // No game restriction: allow any game
-                _ => {}
+            None if !plain => {
+                return Err(HazeError::AccessDenied(format!(
+                    "Caller may only write keys of its namespaces, not '{}'", key
+                )));
             }
+            _ => {}
         }
-        Err(HazeError::AccessDenied(
-            "Caller is not owner and has no GameContract permission".to_string(),
-        ))
     }
+    if changes_attributes && !plain {
+        return Err(HazeError::AccessDenied(
+            "Caller may only write keys of its namespaces, not attributes".to_string(),
+        ));
+    }
+    Ok(())
+}
 
-    /// Get quota usage for an account
-    ///
-    /// # Arguments
-    /// * `owner` - Account address
-    ///
-    /// # Returns
-    /// Quota usage information
-    pub fn get_quota_usage(&self, owner: &Address) -> QuotaUsage {
-        let quota = self.config.get_node_quota();
-        let owner_assets = self.search_assets_by_owner(owner);
-        
-        let mut total_blob_files = 0;
-        let mut total_blob_storage_estimate = 0u64;
-        
-        for asset_id in &owner_assets {
-            if let Some(asset_state) = self.assets.get(asset_id) {
-                let blob_count = asset_state.blob_refs.len() as u64;
-                total_blob_files += blob_count;
-                total_blob_storage_estimate += blob_count * 1024 * 1024; // Estimate 1MB per blob
-            }
+/// Keys and writers of each metadata namespace of an asset
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MetadataNamespace {
+    /// Namespaced keys held by the asset, in order
+    pub keys: Vec<String>,
+    /// Grantees with an unexpired GameContract grant for the namespace (the owner writes
+    /// every namespace)
+    pub writers: Vec<Address>,
+}
+
+/// Namespaces of an asset, from its keys and its GameContract grants unexpired at `now`
+pub fn metadata_namespaces(asset_state: &AssetState, now: i64) -> BTreeMap<String, MetadataNamespace> {
+    let mut namespaces: BTreeMap<String, MetadataNamespace> = BTreeMap::new();
+    for key in asset_state.data.metadata.keys() {
+        if let Some(namespace) = crate::assets::metadata_namespace(key) {
+            namespaces.entry(namespace.to_string()).or_default().keys.push(key.clone());
         }
-        
-        QuotaUsage {
-            assets_count: owner_assets.len() as u64,
-            assets_limit: quota.max_assets_per_account,
-            blob_files_count: total_blob_files,
-            blob_files_limit: quota.max_blob_files_per_asset, // Per asset, but we show total
-            blob_storage_estimate: total_blob_storage_estimate,
-            blob_storage_limit: quota.max_blob_storage_per_account,
-            metadata_size_limit: quota.max_metadata_size,
+    }
+    for grant in active_game_grants(asset_state, now) {
+        if let Some(game_id) = &grant.game_id {
+            namespaces.entry(game_id.clone()).or_default().writers.push(grant.grantee);
+        }
+    }
+    for namespace in namespaces.values_mut() {
+        namespace.keys.sort();
+        namespace.writers.sort();
+        namespace.writers.dedup();
+    }
+    namespaces
+}
+
+/// Apply the commitments (`assets::COMMITMENTS_KEY`) and plain writes of a Create, Update or
+/// Condense to the asset's private fields: a committed field can only be overwritten by a new
+/// commitment, and a plain write to a revealed field makes it an ordinary field again
+fn update_private_fields(asset_state: &mut AssetState, metadata: &HashMap<String, String>) -> Result<()> {
+    let commitments = crate::assets::metadata_commitments(metadata)?;
+    for key in metadata.keys().filter(|key| !key.starts_with('_') && !commitments.contains_key(*key)) {
+        match asset_state.private_fields.get(key) {
+            Some(field) if field.revealed_at.is_none() => {
+                return Err(HazeError::InvalidTransaction(format!(
+                    "Field '{}' is committed; reveal it or commit the new value", key
+                )));
+            }
+            Some(_) => {
+                asset_state.private_fields.remove(key);
+            }
+            None => {}
         }
     }
+    for (field, commitment) in commitments {
+        asset_state.private_fields.insert(field, PrivateField { commitment, revealed_at: None });
+    }
+    Ok(())
+}
 
-    /// Get block by hash
-    pub fn get_block(&self, hash: &Hash) -> Option<Block> {
-        self.blocks.get(hash).map(|v| v.clone())
+/// Merge and Split move metadata between assets, which would separate a committed value
+/// from its commitment
+fn check_no_committed_fields(asset_state: &AssetState, asset_id: &Hash) -> Result<()> {
+    match asset_state.private_fields.iter().find(|(_, field)| field.revealed_at.is_none()) {
+        Some((name, _)) => Err(HazeError::InvalidTransaction(format!(
+            "Asset {} has committed field '{}'; reveal it first", hex::encode(asset_id), name
+        ))),
+        None => Ok(()),
     }
-    
-    /// Get block by height
-    /// Note: This is O(n) operation. In production, use an index for O(1) lookup.
-    pub fn get_block_by_height(&self, height: u64) -> Option<Block> {
-        for entry in self.blocks.iter() {
-            if entry.value().header.height == height {
-                return Some(entry.value().clone());
+}
+
+/// Make a persisted index tree hold exactly the bincode encoding of the live entries
+/// (`current` looks one up by key, `live_keys` lists them all): rewrite outdated values, add
+/// missing ones and remove the rest. Returns entries changed.
+fn repair_index<V: serde::Serialize>(
+    tree: &dyn StorageTree,
+    current: impl Fn(&[u8]) -> Option<V>,
+    live_keys: impl Iterator<Item = [u8; 32]>,
+) -> Result<u64> {
+    let encode = |value: &V| bincode::serialize(value).map_err(|e| HazeError::Serialization(e.to_string()));
+    let mut stale = Vec::new();
+    let mut rewrite = WriteBatch::default();
+    for item in tree.iter() {
+        let (key, value) = item?;
+        match current(&key) {
+            Some(expected) => {
+                let expected = encode(&expected)?;
+                if expected[..] != value[..] {
+                    rewrite.insert(&key, expected);
+                }
             }
+            None => stale.push(key),
+        }
+    }
+    for key in live_keys {
+        if !tree.contains_key(&key)?
+            && let Some(value) = current(&key)
+        {
+            rewrite.insert(key, encode(&value)?);
         }
-        None
     }
+    let repaired = (stale.len() + rewrite.len()) as u64;
+    for key in &stale {
+        tree.remove(key)?;
+    }
+    if !rewrite.is_empty() {
+        tree.apply_batch(rewrite)?;
+    }
+    Ok(repaired)
+}
 
-    /// Get current height
-    pub fn current_height(&self) -> u64 {
-        *self.current_height.read()
+fn frozen_collection_error(game_id: &str) -> HazeError {
+    HazeError::InvalidTransaction(format!(
+        "Collection '{}' is frozen until the game's next season starts", game_id
+    ))
+}
+
+/// Why `caller` may or may not write to an asset (for transaction traces)
+fn describe_write_permission(asset_state: &AssetState, caller: &Address) -> String {
+    if asset_state.owner == *caller {
+        return format!("caller {} is the owner", hex::encode(caller));
     }
+    let grants = asset_state.permissions.iter().filter(|p| p.grantee == *caller).count();
+    format!(
+        "caller {} is not the owner {}; {} permission grant(s) to caller",
+        hex::encode(caller), hex::encode(asset_state.owner), grants
+    )
+}
 
-    /// Apply block to state
-    /// Blocks must be applied in strict sequential order: height == current_height + 1.
-    /// This prevents reorgs that would decrease current_height.
-    pub fn apply_block(&self, block: &Block) -> Result<()> {
-        let current = *self.current_height.read();
-        let next_height = current.checked_add(1).ok_or_else(|| {
-            HazeError::InvalidBlock("Block height overflow".to_string())
+/// Assets lent to a contract call for the asset precompiles. Holds the overlay's
+/// staged assets during the call so precompile writes are visible to later reads;
+/// `undo` keeps the pre-call entries so a failed call can be rolled back.
+struct ContractAssetView {
+    base: Arc<ShardedAssets>,
+    staged: HashMap<Hash, Option<AssetState>>,
+    undo: HashMap<Hash, Option<Option<AssetState>>>,
+    /// Collections frozen by a season (their assets are read-only)
+    frozen: HashSet<String>,
+    /// Timestamp stamped on the assets the contract changes
+    timestamp: i64,
+}
+
+impl ContractAssetView {
+    fn new(
+        base: Arc<ShardedAssets>,
+        staged: HashMap<Hash, Option<AssetState>>,
+        frozen: HashSet<String>,
+        timestamp: i64,
+    ) -> Self {
+        Self { base, staged, undo: HashMap::new(), frozen, timestamp }
+    }
+
+    /// Asset the actor may modify, or an error explaining why not
+    fn writable(&self, actor: &Address, asset_id: &Hash) -> Result<AssetState> {
+        let asset = self.asset(asset_id).ok_or_else(|| {
+            HazeError::InvalidTransaction(format!("Asset not found: {}", hex::encode(asset_id)))
         })?;
-        if block.header.height != next_height {
-            return Err(HazeError::InvalidBlock(
-                format!(
-                    "Block height {} must be current_height+1 ({}); strict sequential apply, no reorg",
-                    block.header.height,
-                    next_height
-                )
+        if !has_asset_write_permission(&asset, actor, self.timestamp) {
+            return Err(HazeError::AccessDenied(
+                "Contract is not owner and has no GameContract permission".to_string(),
             ));
         }
+        if let Some(game_id) = asset.data.game_id.as_ref().filter(|game_id| self.frozen.contains(*game_id)) {
+            return Err(frozen_collection_error(game_id));
+        }
+        Ok(asset)
+    }
 
-        // Process block rewards and inflation
-        let block_reward = self.tokenomics.process_block_rewards(block.header.height)?;
-        
-        // Distribute rewards to validator
-        if block_reward > 0 {
-            self.tokenomics.distribute_rewards(block_reward, block.header.validator)?;
+    fn put(&mut self, asset_id: Hash, asset: AssetState) {
+        if !self.undo.contains_key(&asset_id) {
+            self.undo.insert(asset_id, self.staged.get(&asset_id).cloned());
         }
-        
-        // Validate block
-        // Apply transactions
-        for tx in &block.transactions {
-            self.apply_transaction(tx)?;
+        self.staged.insert(asset_id, Some(asset));
+    }
+
+    /// Staged assets with (`keep_writes`) or without the call's writes
+    fn into_staged(mut self, keep_writes: bool) -> HashMap<Hash, Option<AssetState>> {
+        if !keep_writes {
+            for (asset_id, previous) in self.undo {
+                match previous {
+                    Some(previous) => self.staged.insert(asset_id, previous),
+                    None => self.staged.remove(&asset_id),
+                };
+            }
         }
+        self.staged
+    }
+}
 
-        // Store block in memory and persist to sled for recovery on restart
-        let block_hash = block.header.hash;
-        self.blocks.insert(block_hash, block.clone());
-        let height = block.header.height;
-        let key: Vec<u8> = BLOCK_HEIGHT_PREFIX
-            .iter()
-            .chain(height.to_be_bytes().iter())
-            .copied()
-            .collect();
-        let serialized = bincode::serialize(block).map_err(|e| HazeError::Serialization(e.to_string()))?;
-        self.db
-            .insert(key, serialized)
-            .map_err(|e| HazeError::Database(e.to_string()))?;
+impl AssetHost for ContractAssetView {
+    fn asset(&self, asset_id: &Hash) -> Option<AssetState> {
+        match self.staged.get(asset_id) {
+            Some(asset) => asset.clone(),
+            None => self.base.get(asset_id).map(|a| a.clone()),
+        }
+    }
 
-        // Update height
-        *self.current_height.write() = height;
+    fn can_write(&self, asset_id: &Hash, actor: &Address) -> bool {
+        self.writable(actor, asset_id).is_ok()
+    }
 
+    fn set_attribute(&mut self, actor: &Address, asset_id: &Hash, name: String, value: AttributeValue) -> Result<()> {
+        let mut asset = self.writable(actor, asset_id)?;
+        match asset.data.attributes.iter_mut().find(|a| a.name == name) {
+            Some(attribute) => attribute.value = value.clone(),
+            None => asset.data.attributes.push(crate::types::Attribute {
+                name: name.clone(),
+                value: value.clone(),
+                rarity: None,
+            }),
+        }
+        asset.updated_at = self.timestamp;
+        let mut changes = HashMap::new();
+        changes.insert(format!("attribute:{}", name), value.to_string());
+        StateManager::add_asset_history(&mut asset, AssetAction::Update, changes, self.timestamp);
+        self.put(*asset_id, asset);
         Ok(())
     }
 
-    /// Apply transaction to state
-    fn apply_transaction(&self, tx: &Transaction) -> Result<()> {
-        match tx {
-            Transaction::Transfer { from, to, amount, fee, nonce, .. } => {
-                let mut from_account = self.accounts
-                    .entry(*from)
-                    .or_insert_with(|| AccountState {
-                        balance: 0,
-                        nonce: 0,
-                        staked: 0,
-                    });
-                
-                // Verify nonce is sequential
-                let expected_nonce = from_account.nonce;
-                if *nonce != expected_nonce {
-                    return Err(HazeError::InvalidTransaction(
-                        format!(
-                            "Invalid nonce in transaction: expected {}, got {}",
-                            expected_nonce, nonce
-                        )
-                    ));
-                }
-                
-                if from_account.balance < *amount + *fee {
-                    return Err(HazeError::InvalidTransaction("Insufficient balance".to_string()));
-                }
+    fn transfer(&mut self, actor: &Address, asset_id: &Hash, to: Address) -> Result<()> {
+        let mut asset = self.writable(actor, asset_id)?;
+        asset.owner = to;
+        asset.data.owner = to;
+        // Grants were made by the previous owner
+        asset.permissions.clear();
+        asset.updated_at = self.timestamp;
+        let mut changes = HashMap::new();
+        changes.insert("owner".to_string(), crate::types::address_to_hex(&to));
+        StateManager::add_asset_history(&mut asset, AssetAction::Update, changes, self.timestamp);
+        self.put(*asset_id, asset);
+        Ok(())
+    }
+}
 
-                from_account.balance -= amount + fee;
-                from_account.nonce = *nonce + 1; // Update to next expected nonce
-
-                let mut to_account = self.accounts
-                    .entry(*to)
-                    .or_insert_with(|| AccountState {
-                        balance: 0,
-                        nonce: 0,
-                        staked: 0,
-                    });
-                
-                to_account.balance += amount;
-                
-                // Process gas fee (burn 50%)
-                let _remaining_fee = self.tokenomics.process_gas_fee(*fee)?;
+/// Deployed contract metadata
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ContractInfo {
+    pub address: Address,
+    pub deployer: Address,
+    /// Deployer nonce the address was derived from
+    pub deploy_nonce: u64,
+    pub code_hash: Hash,
+    pub code_size: usize,
+    pub deployed_at_height: u64,
+}
+
+/// Result of a read-only contract call
+#[derive(Debug, Clone)]
+pub struct ContractCallOutput {
+    pub return_values: Vec<ContractValue>,
+    /// Return values concatenated as little-endian bytes
+    pub return_data: Vec<u8>,
+    pub gas_used: u64,
+    /// Logs the call would have emitted (not recorded)
+    pub logs: Vec<Log>,
+}
+
+/// What applying one transaction of a block did, besides its contract logs
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct TxExecution {
+    /// Contract gas used by the transaction: its contract call, asset hooks and paymaster
+    /// decisions (before gas price and discounts)
+    pub gas_used: u64,
+    /// Fees charged (the transaction fee plus gas for contract calls and asset hooks)
+    pub fees_paid: u64,
+    /// Gas fees waived by the payers' stake discount (not included in `fees_paid`)
+    pub gas_discount: u64,
+    /// Paymaster that paid the transaction's gas and the amount it paid (included in `fees_paid`)
+    pub sponsorship: Option<(Address, u64)>,
+    /// Events emitted by the transaction
+    #[serde(
+        serialize_with = "crate::ws_events::serialize_events_json",
+        deserialize_with = "crate::ws_events::deserialize_events_json"
+    )]
+    pub events: Vec<WsEvent>,
+}
+
+/// Outcome of `StateManager::dry_run`
+#[derive(Debug)]
+pub struct DryRun {
+    /// What each transaction before the failure (or all of them) did
+    pub executions: Vec<TxExecution>,
+    /// Index and error of the first transaction that failed; later ones were not run
+    pub failure: Option<(usize, HazeError)>,
+}
+
+/// Whether a transaction executed or failed block application
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReceiptStatus {
+    Executed,
+    /// The block containing it was rejected and the transaction dead-lettered
+    Failed,
+}
+
+impl ReceiptStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReceiptStatus::Executed => "executed",
+            ReceiptStatus::Failed => "failed",
+        }
+    }
+}
+
+/// Outcome of a transaction in a block (see `StateManager::get_receipt`)
+#[derive(Debug, Clone)]
+pub struct TxReceipt {
+    pub tx_hash: Hash,
+    pub status: ReceiptStatus,
+    /// Block that applied the transaction, or the rejected block it failed in
+    pub block_hash: Hash,
+    pub block_height: u64,
+    pub tx_index: usize,
+    /// Gas, fees and events (all zero or empty for a failed transaction, which was not charged)
+    pub execution: TxExecution,
+    pub logs: Vec<LogEntry>,
+    pub error: Option<String>,
+    /// `HazeError::code` of the failure
+    pub error_code: Option<u32>,
+}
+
+/// A contract log with its position in the chain
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct LogEntry {
+    pub log: Log,
+    pub block_height: u64,
+    pub block_hash: Hash,
+    pub tx_hash: Hash,
+    pub tx_index: usize,
+    /// Index of the log within its block
+    pub log_index: usize,
+}
+
+/// Logs of one block with a bloom filter over their addresses and topics
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct BlockLogs {
+    pub bloom: LogsBloom,
+    pub logs: Vec<LogEntry>,
+}
+
+/// Log query. Each entry of `topics` constrains the topic at that position
+/// (`None` matches anything); an empty `topics` matches all logs.
+#[derive(Debug, Clone, Default)]
+pub struct LogFilter {
+    pub address: Option<Address>,
+    pub topics: Vec<Option<Hash>>,
+    pub from_height: u64,
+    /// Defaults to the current height
+    pub to_height: Option<u64>,
+    /// 0 = unlimited
+    pub limit: usize,
+}
+
+impl LogFilter {
+    fn matches_bloom(&self, bloom: &LogsBloom) -> bool {
+        self.address.is_none_or(|address| bloom.contains(&address))
+            && self.topics.iter().flatten().all(|topic| bloom.contains(topic))
+    }
+
+    fn matches(&self, log: &Log) -> bool {
+        self.address.is_none_or(|address| log.address == address)
+            && self.topics.iter().enumerate().all(|(i, topic)| match topic {
+                Some(topic) => log.topics.get(i) == Some(topic),
+                None => true,
+            })
+    }
+}
+
+/// Asset hook registered for a collection; only `registrant` may replace or remove it
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RegisteredAssetHook {
+    pub registrant: Address,
+    pub hook: AssetHook,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct AccountState {
+    pub balance: u64,
+    /// Next nonce of the main sequence (lane 0)
+    pub nonce: u64,
+    pub staked: u64,
+    /// Next sequence of each other nonce lane used so far (accounts with
+    /// `consensus.nonce_lane_accounts` only)
+    #[serde(default)]
+    pub nonce_lanes: BTreeMap<u16, u64>,
+    /// Unstaked governance tokens (`governance_token` config)
+    #[serde(default)]
+    pub governance_balance: u64,
+    /// Governance tokens staked for governance weight
+    #[serde(default)]
+    pub governance_staked: u64,
+}
+
+impl AccountState {
+    /// Next nonce expected on the lane `nonce` belongs to
+    pub fn expected_nonce(&self, nonce: u64) -> u64 {
+        match crate::types::nonce_lane(nonce) {
+            0 => self.nonce,
+            lane => crate::types::lane_nonce(lane, self.nonce_lanes.get(&lane).copied().unwrap_or(0)),
+        }
+    }
+
+    /// Move the lane of an accepted `nonce` past it
+    pub fn advance_nonce(&mut self, nonce: u64) {
+        match crate::types::nonce_lane(nonce) {
+            0 => self.nonce = nonce + 1,
+            lane => {
+                self.nonce_lanes.insert(lane, crate::types::nonce_sequence(nonce) + 1);
             }
-            Transaction::MistbornAsset { from: _owner, action, asset_id, data, .. } => {
-                // Calculate gas cost for this operation
-                let gas_cost = crate::assets::calculate_asset_operation_gas(
-                    &self.config,
-                    action,
-                    data,
-                    Some(&data.metadata),
-                );
-                
-                // Calculate gas fee (gas_cost * gas_price)
-                let gas_fee = gas_cost * self.config.vm.gas_price;
-                
-                // Check owner balance and deduct gas fee
-                let mut owner_account = self.accounts
-                    .entry(data.owner)
-                    .or_insert_with(|| AccountState {
-                        balance: 0,
-                        nonce: 0,
-                        staked: 0,
-                    });
-                
-                if owner_account.balance < gas_fee {
-                    return Err(HazeError::InvalidTransaction(
-                        format!("Insufficient balance for gas fee: need {}, have {}", gas_fee, owner_account.balance)
-                    ));
-                }
-                
-                owner_account.balance -= gas_fee;
-                
-                // Process gas fee (burn 50%)
-                let _remaining_fee = self.tokenomics.process_gas_fee(gas_fee)?;
+        }
+    }
+}
+
+/// Conditions for listing accounts
+#[derive(Debug, Clone, Default)]
+pub struct AccountFilter {
+    pub min_balance: Option<u64>,
+    /// `Some(true)`: only accounts with stake; `Some(false)`: only accounts without
+    pub has_stake: Option<bool>,
+}
+
+impl AccountFilter {
+    fn matches(&self, account: &AccountState) -> bool {
+        self.min_balance.is_none_or(|min| account.balance >= min)
+            && self.has_stake.is_none_or(|staked| (account.staked > 0) == staked)
+    }
+}
+
+/// Comparison of an `AttributeFilter`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// Attribute condition of an asset search, written `<name><op><literal>` with `op` one of
+/// `=`, `!=`, `<`, `<=`, `>`, `>=`. The literal is compared by the type of the asset's value
+/// (see `AttributeValue::compare`), so `level>=5` compares ints numerically. An asset
+/// without the attribute, or whose value cannot be compared with the literal, does not match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttributeFilter {
+    pub name: String,
+    pub op: AttributeOp,
+    pub literal: String,
+}
+
+impl AttributeFilter {
+    pub fn parse(condition: &str) -> Option<Self> {
+        let at = condition.find(['=', '!', '<', '>'])?;
+        let (name, rest) = condition.split_at(at);
+        let operators = [
+            ("!=", AttributeOp::Ne),
+            ("<=", AttributeOp::Le),
+            (">=", AttributeOp::Ge),
+            ("=", AttributeOp::Eq),
+            ("<", AttributeOp::Lt),
+            (">", AttributeOp::Gt),
+        ];
+        let (op, literal) = operators
+            .into_iter()
+            .find_map(|(token, op)| rest.strip_prefix(token).map(|literal| (op, literal)))?;
+        if name.is_empty() {
+            return None;
+        }
+        Some(Self { name: name.to_string(), op, literal: literal.to_string() })
+    }
+
+    pub fn matches(&self, data: &crate::types::AssetData) -> bool {
+        data.attributes
+            .iter()
+            .filter(|attribute| attribute.name == self.name)
+            .filter_map(|attribute| attribute.value.compare(&self.literal))
+            .any(|ordering| match self.op {
+                AttributeOp::Eq => ordering.is_eq(),
+                AttributeOp::Ne => ordering.is_ne(),
+                AttributeOp::Lt => ordering.is_lt(),
+                AttributeOp::Le => ordering.is_le(),
+                AttributeOp::Gt => ordering.is_gt(),
+                AttributeOp::Ge => ordering.is_ge(),
+            })
+    }
+}
+
+/// One page of accounts in address order
+#[derive(Debug, Clone)]
+pub struct AccountPage {
+    pub accounts: Vec<(Address, AccountState)>,
+    /// Pass as `cursor` to get the next page; `None` on the last page
+    pub next_cursor: Option<Address>,
+}
+
+/// History entry for asset (same as in assets.rs but needed here for serialization)
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AssetHistoryEntry {
+    pub timestamp: i64,
+    pub action: AssetAction,
+    #[serde(serialize_with = "crate::types::serialize_sorted_map")]
+    pub changes: HashMap<String, String>,
+}
+
+/// Asset version snapshot
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AssetVersion {
+    pub version: u64,
+    pub timestamp: i64,
+    pub data: crate::types::AssetData,
+    #[serde(serialize_with = "crate::types::serialize_sorted_map")]
+    pub blob_refs: HashMap<String, Hash>,
+}
+
+/// Private metadata field: the value is ciphertext until a Reveal publishes the plaintext
+/// matching `commitment` (see `assets::metadata_commitment`)
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PrivateField {
+    pub commitment: Hash,
+    /// Height of the block that revealed the field (`None` while committed)
+    pub revealed_at: Option<u64>,
+}
+
+/// Quota usage information for an account
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct QuotaUsage {
+    pub assets_count: u64,
+    pub assets_limit: u64,
+    pub blob_files_count: u64,
+    pub blob_files_limit: u64,
+    /// Unique bytes of the account's blobs (see `BlobStorage::unique_bytes`)
+    pub blob_storage_estimate: u64,
+    pub blob_storage_limit: u64,
+    pub metadata_size_limit: usize,
+}
+
+/// Receipt for a transaction that failed while its block was being applied.
+///
+/// Payment request (invoice) created by `CreatePaymentRequest`
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct PaymentRequest {
+    pub id: Hash,
+    pub requester: Address,
+    /// Only this account may pay (anyone when `None`)
+    pub payer: Option<Address>,
+    pub amount: u64,
+    pub memo: String,
+    pub created_at_height: u64,
+    /// Last block height at which the request can be paid
+    pub expires_at_height: u64,
+    /// Set once the request has been paid
+    pub settlement: Option<PaymentSettlement>,
+}
+
+/// Recurring payment authorized once by its payer with `CreateSubscription`
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Subscription {
+    pub id: Hash,
+    pub payer: Address,
+    pub payee: Address,
+    pub amount: u64,
+    pub interval_blocks: u64,
+    /// Last height a period can be charged at (`None` = until cancelled)
+    pub end_height: Option<u64>,
+    pub created_at_height: u64,
+    /// Height of the next charge (retried every block while past due)
+    pub next_charge_height: u64,
+    /// Height of the first failed attempt to charge the current period
+    pub past_due_since: Option<u64>,
+    /// Periods charged so far
+    pub charges: u64,
+    pub total_paid: u64,
+    pub status: SubscriptionStatus,
+    /// Payer or payee that cancelled the subscription
+    pub cancelled_by: Option<Address>,
+}
+
+/// Status of a subscription
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriptionStatus {
+    Active,
+    /// The last charge failed; retried every block until the grace period runs out
+    PastDue,
+    Cancelled,
+    /// Not paid within the grace period
+    Lapsed,
+    /// Every period up to `end_height` was charged
+    Ended,
+}
+
+impl Subscription {
+    /// Whether the subscription still has periods to charge
+    pub fn is_live(&self) -> bool {
+        matches!(self.status, SubscriptionStatus::Active | SubscriptionStatus::PastDue)
+    }
+}
+
+/// Edge of the asset composition graph: `child` was derived from `parent` by `action`
+/// (Merge: the surviving asset absorbed `parent`; Split: `parent` produced the component `child`;
+/// Breed: `parent` is one of the two parents `child` hatched from; Upgrade: `parent` was
+/// burned as a catalyst of an upgrade of `child`)
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct LineageEdge {
+    pub parent: Hash,
+    pub child: Hash,
+    pub action: AssetAction,
+    /// Height of the block that applied the operation
+    pub height: u64,
+}
+
+/// Direct composition edges of one asset
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct AssetLineage {
+    /// Edges to the assets this one was composed from
+    pub parents: Vec<LineageEdge>,
+    /// Edges to the assets derived from this one
+    pub children: Vec<LineageEdge>,
+}
+
+/// Payment of a payment request
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct PaymentSettlement {
+    pub payer: Address,
+    pub height: u64,
+}
+
+/// Status of a payment request at a given height
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaymentRequestStatus {
+    Open,
+    Paid,
+    Expired,
+}
+
+impl PaymentRequest {
+    pub fn status(&self, height: u64) -> PaymentRequestStatus {
+        if self.settlement.is_some() {
+            PaymentRequestStatus::Paid
+        } else if height > self.expires_at_height {
+            PaymentRequestStatus::Expired
+        } else {
+            PaymentRequestStatus::Open
+        }
+    }
+
+    /// Check that `payer` may pay `amount` in a block at `height`
+    pub fn check_payable(&self, payer: &Address, amount: u64, height: u64) -> Result<()> {
+        match self.status(height) {
+            PaymentRequestStatus::Open => {}
+            PaymentRequestStatus::Paid => {
+                return Err(HazeError::InvalidTransaction("Payment request already paid".to_string()));
+            }
+            PaymentRequestStatus::Expired => {
+                return Err(HazeError::InvalidTransaction("Payment request expired".to_string()));
+            }
+        }
+        if self.payer.is_some_and(|expected| expected != *payer) {
+            return Err(HazeError::AccessDenied("Payment request is addressed to another payer".to_string()));
+        }
+        if amount != self.amount {
+            return Err(HazeError::InvalidTransaction(
+                format!("Payment amount {} does not match requested {}", amount, self.amount)
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A block containing a failing transaction is rejected; the failing transaction
+/// is kept here so clients can see why it never executed and retry it.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct DeadLetterEntry {
+    pub tx_hash: Hash,
+    pub transaction: Transaction,
+    pub error: String,
+    pub block_height: u64,
+    pub block_hash: Hash,
+    /// Index of the transaction inside the rejected block
+    pub tx_index: usize,
+    pub failed_at: i64,
+    /// Number of times this transaction failed block application
+    pub attempts: u32,
+    /// `HazeError::code` of the failure (0 for entries recorded before error codes)
+    pub error_code: u32,
+    /// `HazeError::params` of the failure
+    pub error_params: std::collections::BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AssetState {
+    pub owner: Address,
+    pub data: crate::types::AssetData,
+    pub created_at: i64,
+    pub updated_at: i64,
+    /// Blob references for large files (Core density)
+    /// Maps blob key to blob hash; serialized in key order like the metadata, since
+    /// the state trie hashes the encoded asset
+    #[serde(default, serialize_with = "crate::types::serialize_sorted_map")]
+    pub blob_refs: HashMap<String, Hash>,
+    /// History of asset changes (limited to last 100 entries)
+    #[serde(default)]
+    pub history: Vec<AssetHistoryEntry>,
+    /// Version snapshots (limited to last 10 versions)
+    #[serde(default)]
+    pub versions: Vec<AssetVersion>,
+    /// Current version number (increments on each snapshot)
+    #[serde(default)]
+    pub current_version: u64,
+    /// Permission grants (GameContract, PublicRead)
+    #[serde(default)]
+    pub permissions: Vec<AssetPermission>,
+    /// If true, anyone can read the asset
+    #[serde(default)]
+    pub public_read: bool,
+    /// Private metadata fields by name, committed or revealed
+    #[serde(default)]
+    pub private_fields: BTreeMap<String, PrivateField>,
+}
+
+impl StateManager {
+    /// Create a new StateManager
+    ///
+    /// Initializes the state manager with an empty state, default tokenomics, and economy.
+    ///
+    /// # Arguments
+    /// * `config` - Configuration for the node, including database path
+    ///
+    /// # Errors
+    /// Returns an error if the database cannot be opened.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use haze::config::Config;
+    /// use haze::state::StateManager;
+    /// use std::path::PathBuf;
+    ///
+    /// let mut config = Config::default();
+    /// // Use a unique database path to avoid conflicts
+    /// config.storage.db_path = PathBuf::from("./haze_db_doctest");
+    /// let state_manager = StateManager::new(&config).unwrap();
+    /// assert_eq!(state_manager.current_height(), 0);
+    /// ```
+    pub fn new(config: &Config) -> Result<Self> {
+        let db = open_storage(&config.storage)?;
+
+        let state = Self::with_db(db, config)?;
+        // Finish or undo a block application cut short by a crash
+        let recovered = state.journal.recover()?;
+        // Upgrade values written by older builds before they are read
+        let migration = migrate(state.db.as_ref(), &config.storage, false)?;
+        // Refuse a database created for another chain
+        bind_chain_id(state.db.as_ref(), config.chain_id)?;
+        // Verify blocks and repair what is rebuilt from them before anything is loaded
+        let mut integrity = check_and_repair(&state.trees)?;
+        integrity.dead_letters_removed = state.load_dead_letters_from_db()?;
+        // After a clean shutdown, load the persisted state and replay only newer blocks
+        let unchanged = recovered.is_empty() && migration.steps.is_empty() && integrity.repairs() == 0;
+        state.restore_checkpoint(unchanged)?;
+        state.replay_blocks_from_db()?;
+        integrity.accounts_repaired = state.repair_account_index()?;
+        integrity.assets_repaired = state.repair_asset_index()?;
+        state.seed_governance_genesis()?;
+        if integrity.repairs() > 0 {
+            tracing::warn!("Database integrity check repaired {} entries: {:?}", integrity.repairs(), integrity);
+        } else {
+            tracing::info!("Database integrity check passed ({} blocks)", integrity.blocks_checked);
+        }
+        Ok(state)
+    }
+
+    /// Empty state on top of an open database (nothing loaded or replayed)
+    pub(crate) fn with_db(db: Arc<dyn Storage>, config: &Config) -> Result<Self> {
+        let trees = DbTrees::open(db.as_ref())?;
+        let blocks = Arc::new(BlockStore::new(trees.blocks.clone()));
+        let storage = Arc::new(StorageMaintenance::new(db.clone()));
+        let journal = Arc::new(BlockJournal::open(db.clone())?);
+        Ok(Self {
+            db,
+            trees,
+            storage,
+            journal,
+            blobs: Arc::new(BlobStorage::open(config)?),
+            blob_stats: Arc::new(RwLock::new(BlobStats::default())),
+            config: Arc::new(config.clone()),
+            accounts: Arc::new(DashMap::new()),
+            account_hashes: Arc::new(RootTree::new()),
+            assets: Arc::new(ShardedAssets::new()),
+            blocks,
+            current_height: Arc::new(RwLock::new(0)),
+            tokenomics: Arc::new({
+                let tokenomics = Tokenomics::new();
+                tokenomics.set_committee_params(config.consensus.committee_params());
+                tokenomics
+            }),
+            economy: Arc::new(FogEconomy::with_twap_capacity(config.economy.twap_max_observations)),
+            features: Arc::new(FeatureRegistry::new(&config.features)),
+            ws_tx: Arc::new(RwLock::new(None)),
+            asset_index_by_owner: Arc::new(DashMap::new()),
+            asset_index_by_density: Arc::new(DashMap::new()),
+            asset_index_by_zone: Arc::new(DashMap::new()),
+            collection_traits: Arc::new(DashMap::new()),
+            collection_rarity: Arc::new(DashMap::new()),
+            rarity_generation: Arc::new(std::sync::atomic::AtomicU64::new(0)),
+            collection_ownership: Arc::new(DashMap::new()),
+            market_stats: Arc::new(DashMap::new()),
+            asset_access_count: Arc::new(DashMap::new()),
+            contracts: Arc::new(DashMap::new()),
+            dead_letters: Arc::new(DashMap::new()),
+            asset_hooks: Arc::new(DashMap::new()),
+            recent_block_timestamps: Arc::new(RwLock::new(std::collections::VecDeque::new())),
+            recent_fees: Arc::new(RwLock::new(RecentBlockFees::new(config.consensus.fee_estimate_blocks))),
+            latency: Arc::new(LatencyMetrics::new()),
+            state_version: Arc::new(std::sync::atomic::AtomicU64::new(0)),
+            snapshot_lock: Arc::new(RwLock::new(())),
+            payment_requests: Arc::new(DashMap::new()),
+            asset_lineage: Arc::new(DashMap::new()),
+            orders: Arc::new(DashMap::new()),
+            open_orders: Arc::new(DashMap::new()),
+            trades: Arc::new(DashMap::new()),
+            order_sequence: Arc::new(std::sync::atomic::AtomicU64::new(0)),
+            referrals: Arc::new(DashMap::new()),
+            referral_earnings: Arc::new(DashMap::new()),
+            subscriptions: Arc::new(DashMap::new()),
+            loot_boxes: Arc::new(DashMap::new()),
+            leaderboards: Arc::new(DashMap::new()),
+            leaderboards_by_game: Arc::new(DashMap::new()),
+            seasons: Arc::new(DashMap::new()),
+            seasons_by_game: Arc::new(DashMap::new()),
+            seasons_starting: Arc::new(DashMap::new()),
+            seasons_ending: Arc::new(DashMap::new()),
+            frozen_collections: Arc::new(DashMap::new()),
+            asset_pools: Arc::new(DashMap::new()),
+            asset_pools_by_game: Arc::new(DashMap::new()),
+            asset_stakes: Arc::new(DashMap::new()),
+            asset_stakes_by_owner: Arc::new(DashMap::new()),
+            breedings: Arc::new(DashMap::new()),
+            breedings_due: Arc::new(DashMap::new()),
+            last_bred: Arc::new(DashMap::new()),
+            upgrades: Arc::new(DashMap::new()),
+            upgrades_due: Arc::new(DashMap::new()),
+            upgrades_by_asset: Arc::new(DashMap::new()),
+            subscriptions_due: Arc::new(DashMap::new()),
+            subscriptions_by_account: Arc::new(DashMap::new()),
+            paymaster_spend: Arc::new(DashMap::new()),
+        })
+    }
+
+    /// Load dead-lettered transactions persisted in the database, dropping unreadable ones.
+    /// Returns the number dropped.
+    fn load_dead_letters_from_db(&self) -> Result<u64> {
+        let mut unreadable = Vec::new();
+        for item in self.trees.dead_letters.iter() {
+            let (key, value) = item?;
+            let entry: DeadLetterEntry = match bincode::deserialize(&value) {
+                Ok(entry) => entry,
+                Err(e) => {
+                    tracing::warn!("Dropping unreadable dead letter {}: {}", hex::encode(&key), e);
+                    unreadable.push(key);
+                    continue;
+                }
+            };
+            self.dead_letters.insert(entry.tx_hash, entry);
+        }
+        for key in &unreadable {
+            self.trees.dead_letters.remove(key)?;
+        }
+        Ok(unreadable.len() as u64)
+    }
+
+    /// Make the persisted account index match the replayed accounts. Returns entries changed.
+    fn repair_account_index(&self) -> Result<u64> {
+        repair_index(
+            self.trees.accounts.as_ref(),
+            |key| Address::try_from(key).ok().and_then(|address| self.accounts.get(&address).map(|a| a.clone())),
+            self.accounts.iter().map(|entry| *entry.key()).collect::<Vec<_>>().into_iter(),
+        )
+    }
+
+    /// Make the persisted asset index match the replayed assets. Returns entries changed.
+    fn repair_asset_index(&self) -> Result<u64> {
+        repair_index(
+            self.trees.assets.as_ref(),
+            |key| Hash::try_from(key).ok().and_then(|asset_id| self.assets.get(&asset_id).map(|a| a.clone())),
+            self.assets.iter().map(|entry| *entry.key()).collect::<Vec<_>>().into_iter(),
+        )
+    }
+
+    /// Replay the blocks persisted in the database above the current height, to restore the
+    /// state they built after a restart
+    fn replay_blocks_from_db(&self) -> Result<()> {
+        let mut entries: Vec<(u64, Block)> = Vec::new();
+        let from = block_key(self.current_height());
+        for item in self.trees.blocks.range(std::ops::Bound::Excluded(&from[..]), std::ops::Bound::Unbounded) {
+            let (key, value) = item?;
+            let height_bytes: [u8; 8] = key[..]
+                .try_into()
+                .map_err(|_| HazeError::Database("Invalid block key length".to_string()))?;
+            let height = u64::from_be_bytes(height_bytes);
+            entries.push((height, decode_block(&value)?));
+        }
+        entries.sort_by_key(|(h, _)| *h);
+        for (_, block) in entries {
+            // Persisted blocks are already in the applied set; rebuild in-memory state only
+            self.replay_block(&block)?;
+        }
+        Ok(())
+    }
+
+    /// Set WebSocket broadcaster for real-time event notifications
+    pub fn set_ws_tx(&self, tx: broadcast::Sender<WsEvent>) {
+        *self.ws_tx.write() = Some(tx);
+    }
+
+    /// Broadcast WebSocket event if broadcaster is available
+    pub fn broadcast_event(&self, event: WsEvent) {
+        if let Some(ref tx) = *self.ws_tx.read() {
+            let _ = tx.send(event);
+        }
+    }
+
+    /// Split component without a manifest: the metadata value under the component's name,
+    /// attributes whose name contains it plus shared ones (`shared_*`, `rarity`, `power`), or
+    /// every attribute when none match
+    fn heuristic_split_component(source: &crate::types::AssetData, component_name: &str) -> crate::types::AssetData {
+        let mut component_data = crate::types::AssetData {
+            density: crate::types::DensityLevel::Ethereal, // Start with minimum density
+            metadata: std::collections::HashMap::new(),
+            attributes: vec![],
+            game_id: source.game_id.clone(),
+            owner: source.owner,
+        };
+        
+        // Extract component-specific metadata
+        if let Some(value) = source.metadata.get(component_name) {
+            component_data.metadata.insert(component_name.to_string(), value.clone());
+        }
+        
+        for attr in &source.attributes {
+            if attr.name.contains(component_name) || attr.name.starts_with("shared_") || attr.name == "rarity" || attr.name == "power" {
+                component_data.attributes.push(attr.clone());
+            }
+        }
+        
+        // If no component-specific attributes were found, copy all attributes
+        // This ensures components have at least some attributes
+        if component_data.attributes.is_empty() {
+            component_data.attributes = source.attributes.clone();
+        }
+        component_data
+    }
+
+    /// Add history entry to asset state (limited to last 100 entries)
+    fn add_asset_history(asset_state: &mut AssetState, action: AssetAction, changes: HashMap<String, String>, timestamp: i64) {
+        let history_entry = AssetHistoryEntry {
+            timestamp,
+            action,
+            changes,
+        };
+        
+        asset_state.history.push(history_entry);
+        
+        // Limit history to last 100 entries
+        if asset_state.history.len() > 100 {
+            asset_state.history.remove(0);
+        }
+    }
+
+    /// Create a version snapshot from asset state
+    fn create_version_from_state(asset_state: &AssetState, timestamp: i64) -> AssetVersion {
+        AssetVersion {
+            version: asset_state.current_version + 1,
+            timestamp,
+            data: asset_state.data.clone(),
+            blob_refs: asset_state.blob_refs.clone(),
+        }
+    }
+
+    /// Add snapshot to asset state (limited to last 10 versions)
+    fn add_asset_snapshot(asset_state: &mut AssetState, timestamp: i64) {
+        let snapshot = Self::create_version_from_state(asset_state, timestamp);
+        asset_state.current_version = snapshot.version;
+        asset_state.versions.push(snapshot);
+        
+        // Limit versions to last 10
+        if asset_state.versions.len() > 10 {
+            asset_state.versions.remove(0);
+        }
+    }
+
+    /// Add asset to indexes (only if not already present)
+    fn add_asset_to_indexes(&self, asset_id: &Hash, asset_state: &AssetState) {
+        // Index by owner (optimized: check before adding to avoid unnecessary clone)
+        self.asset_index_by_owner
+            .entry(asset_state.owner)
+            .or_insert_with(Vec::new)
+            .push(*asset_id);
+        
+        // Index by game_id (in the collection's shard)
+        if let Some(ref game_id) = asset_state.data.game_id {
+            self.assets.index_collection(game_id, *asset_id);
+        }
+        
+        // Index by density
+        let density_level = asset_state.data.density as u8;
+        let mut density_assets = self.asset_index_by_density
+            .entry(density_level)
+            .or_insert_with(Vec::new);
+        if !density_assets.contains(asset_id) {
+            density_assets.push(*asset_id);
+        }
+        drop(density_assets);
+        
+        // Index by economic zone
+        if let Some(key) = zone_index_key(&asset_state.data) {
+            self.asset_index_by_zone.entry(key).or_default().push(*asset_id);
+        }
+    }
+
+    /// Remove asset from indexes
+    fn remove_asset_from_indexes(&self, asset_id: &Hash, asset_state: &AssetState) {
+        // Remove from owner index
+        if let Some(mut owner_assets) = self.asset_index_by_owner.get_mut(&asset_state.owner) {
+            owner_assets.retain(|&id| id != *asset_id);
+            if owner_assets.is_empty() {
+                drop(owner_assets);
+                self.asset_index_by_owner.remove(&asset_state.owner);
+            }
+        }
+        
+        // Remove from game_id index
+        if let Some(ref game_id) = asset_state.data.game_id {
+            self.assets.unindex_collection(game_id, asset_id);
+        }
+        
+        // Remove from density index
+        let density_level = asset_state.data.density as u8;
+        if let Some(mut density_assets) = self.asset_index_by_density.get_mut(&density_level) {
+            density_assets.retain(|&id| id != *asset_id);
+            if density_assets.is_empty() {
+                drop(density_assets);
+                self.asset_index_by_density.remove(&density_level);
+            }
+        }
+        
+        // Remove from zone index
+        let zone_key = zone_index_key(&asset_state.data);
+        if let Some(mut zone_assets) = zone_key.as_ref().and_then(|key| self.asset_index_by_zone.get_mut(key)) {
+            zone_assets.retain(|&id| id != *asset_id);
+            if zone_assets.is_empty() {
+                drop(zone_assets);
+                self.asset_index_by_zone.remove(zone_key.as_deref().unwrap_or_default());
+            }
+        }
+    }
+
+    fn invalidate_collection_rarity(&self, game_id: &str) {
+        self.rarity_generation.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        self.collection_rarity.remove(game_id);
+    }
+
+    /// Log an owner/collection change; overlays commit as part of the next block
+    fn record_ownership_change(&self, asset_id: &Hash, old: Option<&AssetState>, new: Option<&AssetState>) {
+        let height = self.current_height() + 1;
+        let old_game = old.and_then(|asset| asset.data.game_id.as_ref());
+        let new_game = new.and_then(|asset| asset.data.game_id.as_ref());
+        if let Some(game_id) = old_game.filter(|game_id| Some(*game_id) != new_game) {
+            self.collection_ownership
+                .entry(game_id.clone())
+                .or_default()
+                .push(OwnershipChange { height, asset_id: *asset_id, owner: None });
+        }
+        if let (Some(game_id), Some(new)) = (new_game, new) {
+            self.collection_ownership
+                .entry(game_id.clone())
+                .or_default()
+                .push(OwnershipChange { height, asset_id: *asset_id, owner: Some(new.owner) });
+        }
+    }
+
+    /// Count the asset's attributes in its collection's trait histogram
+    fn add_asset_traits(&self, asset_state: &AssetState) {
+        let Some(ref game_id) = asset_state.data.game_id else {
+            return;
+        };
+        self.invalidate_collection_rarity(game_id);
+        let mut traits = self.collection_traits.entry(game_id.clone()).or_default();
+        for (name, value) in asset_traits(asset_state) {
+            *traits
+                .entry(name.to_string())
+                .or_default()
+                .entry(value.to_string())
+                .or_insert(0) += 1;
+        }
+    }
+
+    /// Remove the asset's attributes from its collection's trait histogram
+    fn remove_asset_traits(&self, asset_state: &AssetState) {
+        let Some(ref game_id) = asset_state.data.game_id else {
+            return;
+        };
+        self.invalidate_collection_rarity(game_id);
+        let Some(mut traits) = self.collection_traits.get_mut(game_id) else {
+            return;
+        };
+        for (name, value) in asset_traits(asset_state) {
+            if let Some(values) = traits.get_mut(name) {
+                if let Some(count) = values.get_mut(&value) {
+                    *count = count.saturating_sub(1);
+                    if *count == 0 {
+                        values.remove(&value);
+                    }
+                }
+                if values.is_empty() {
+                    traits.remove(name);
+                }
+            }
+        }
+        if traits.is_empty() {
+            drop(traits);
+            self.collection_traits.remove(game_id);
+        }
+    }
+
+    /// Get account state by address
+    ///
+    /// # Arguments
+    /// * `address` - The account address
+    ///
+    /// # Returns
+    /// `Some(AccountState)` if the account exists, `None` otherwise.
+    ///
+    /// # Example
+    /// ```
+    /// use haze::crypto::KeyPair;
+    /// use haze::state::StateManager;
+    /// use haze::config::Config;
+    ///
+    /// let config = Config::default();
+    /// let state = StateManager::new(&config)?;
+    /// let keypair = KeyPair::generate();
+    /// let address = keypair.address();
+    ///
+    /// // New account doesn't exist yet
+    /// assert!(state.get_account(&address).is_none());
+    /// # Ok::<(), haze::error::HazeError>(())
+    /// ```
+    pub fn get_account(&self, address: &Address) -> Option<AccountState> {
+        self.accounts.get(address).map(|v| v.clone())
+    }
+
+    /// Number of accounts
+    pub fn account_count(&self) -> usize {
+        self.accounts.len()
+    }
+
+    /// Accounts in address order after `cursor` (exclusive), up to `limit` matching `filter`,
+    /// read from the persisted account index
+    pub fn list_accounts(&self, cursor: Option<&Address>, limit: usize, filter: &AccountFilter) -> Result<AccountPage> {
+        let start = match cursor {
+            Some(address) => std::ops::Bound::Excluded(&address[..]),
+            None => std::ops::Bound::Unbounded,
+        };
+        let mut accounts: Vec<(Address, AccountState)> = Vec::new();
+        let mut next_cursor = None;
+        for item in self.trees.accounts.range(start, std::ops::Bound::Unbounded) {
+            let (key, value) = item?;
+            let address: Address = key[..]
+                .try_into()
+                .map_err(|_| HazeError::Database("Invalid account key length".to_string()))?;
+            let account: AccountState = bincode::deserialize(&value)
+                .map_err(|e| HazeError::Serialization(e.to_string()))?;
+            if !filter.matches(&account) {
+                continue;
+            }
+            if accounts.len() == limit {
+                // Another match exists past this page
+                next_cursor = accounts.last().map(|(address, _)| *address);
+                break;
+            }
+            accounts.push((address, account));
+        }
+        Ok(AccountPage { accounts, next_cursor })
+    }
+
+    /// Get asset state by asset ID
+    ///
+    /// # Arguments
+    /// * `asset_id` - The asset identifier (hash)
+    ///
+    /// # Returns
+    /// `Some(AssetState)` if the asset exists, `None` otherwise.
+    ///
+    /// # Performance
+    /// This method tracks access frequency for cache optimization.
+    pub fn get_asset(&self, asset_id: &Hash) -> Option<AssetState> {
+        let result = self.assets.get(asset_id).map(|v| v.clone());
+        
+        // Track access frequency for cache optimization
+        if result.is_some() {
+            *self.asset_access_count.entry(*asset_id).or_insert(0) += 1;
+        }
+        
+        result
+    }
+    
+    /// Get asset state without blob data (lazy loading)
+    ///
+    /// # Arguments
+    /// * `asset_id` - The asset identifier (hash)
+    ///
+    /// # Returns
+    /// `Some(AssetState)` if the asset exists, `None` otherwise.
+    /// Blob references are included but blob data is not loaded.
+    pub fn get_asset_lightweight(&self, asset_id: &Hash) -> Option<AssetState> {
+        // Same as get_asset, but explicitly indicates lazy blob loading
+        self.get_asset(asset_id)
+    }
+    
+    /// Get most frequently accessed assets
+    ///
+    /// # Arguments
+    /// * `limit` - Maximum number of assets to return
+    ///
+    /// # Returns
+    /// Vector of (asset_id, access_count) tuples sorted by access frequency
+    pub fn get_most_accessed_assets(&self, limit: usize) -> Vec<(Hash, u64)> {
+        let mut access_list: Vec<(Hash, u64)> = self.asset_access_count
+            .iter()
+            .map(|entry| (*entry.key(), *entry.value()))
+            .collect();
+        
+        access_list.sort_by(|a, b| b.1.cmp(&a.1)); // Sort by access count descending
+        access_list.truncate(limit);
+        access_list
+    }
+
+    /// Get asset history by asset ID
+    ///
+    /// # Arguments
+    /// * `asset_id` - The asset identifier (hash)
+    /// * `limit` - Maximum number of history entries to return (0 = all)
+    ///
+    /// # Returns
+    /// `Some(Vec<AssetHistoryEntry>)` if the asset exists, `None` otherwise.
+    pub fn get_asset_history(&self, asset_id: &Hash, limit: usize) -> Option<Vec<AssetHistoryEntry>> {
+        self.assets.get(asset_id).map(|asset_state| {
+            let history = asset_state.history.clone();
+            if limit > 0 && history.len() > limit {
+                history.into_iter().rev().take(limit).rev().collect()
+            } else {
+                history
+            }
+        })
+    }
+
+    /// Get asset version by version number
+    ///
+    /// # Arguments
+    /// * `asset_id` - The asset identifier (hash)
+    /// * `version` - The version number (0 = current version)
+    ///
+    /// # Returns
+    /// `Some(AssetVersion)` if the version exists, `None` otherwise.
+    pub fn get_asset_version(&self, asset_id: &Hash, version: u64) -> Option<AssetVersion> {
+        self.assets.get(asset_id).and_then(|asset_state| {
+            if version == 0 || version == asset_state.current_version {
+                // Return current version
+                Some(AssetVersion {
+                    version: asset_state.current_version,
+                    timestamp: asset_state.updated_at,
+                    data: asset_state.data.clone(),
+                    blob_refs: asset_state.blob_refs.clone(),
+                })
+            } else {
+                // Find version in history
+                asset_state.versions.iter()
+                    .find(|v| v.version == version)
+                    .cloned()
+            }
+        })
+    }
+
+    /// Get all versions of an asset
+    pub fn get_asset_versions(&self, asset_id: &Hash) -> Option<Vec<AssetVersion>> {
+        self.assets.get(asset_id).map(|asset_state| {
+            let mut versions = asset_state.versions.clone();
+            // Add current version only if it's not already in versions
+            let current_exists = versions.iter().any(|v| v.version == asset_state.current_version);
+            if !current_exists {
+                versions.push(AssetVersion {
+                    version: asset_state.current_version,
+                    timestamp: asset_state.updated_at,
+                    data: asset_state.data.clone(),
+                    blob_refs: asset_state.blob_refs.clone(),
+                });
+            }
+            versions.sort_by_key(|v| v.version);
+            versions
+        })
+    }
+
+    /// Create a manual snapshot of an asset
+    pub fn create_asset_snapshot(&self, asset_id: &Hash) -> Result<u64> {
+        let (version, owner) = self.assets
+            .update(asset_id, |asset_state| {
+                Self::add_asset_snapshot(asset_state, chrono::Utc::now().timestamp());
+                (asset_state.current_version, asset_state.owner)
+            })
+            .ok_or_else(|| HazeError::InvalidTransaction(
+                "Asset not found".to_string()
+            ))?;
+        self.bump_state_version();
+        self.broadcast_event(WsEvent::AssetVersionCreated {
+            asset_id: *asset_id,
+            version,
+            owner,
+        });
+        Ok(version)
+    }
+
+    /// Search assets by owner
+    ///
+    /// # Returns
+    /// Vector of asset IDs owned by the address (sorted by creation time, most recent first)
+    pub fn search_assets_by_owner(&self, owner: &Address) -> Vec<Hash> {
+        let mut assets = self.asset_index_by_owner
+            .get(owner)
+            .map(|v| v.clone())
+            .unwrap_or_default();
+        
+        // Sort by creation time (most recent first) for better UX
+        assets.sort_by(|a, b| {
+            let time_a = self.assets.get(a).map(|s| s.created_at).unwrap_or(0);
+            let time_b = self.assets.get(b).map(|s| s.created_at).unwrap_or(0);
+            time_b.cmp(&time_a) // Descending order
+        });
+        
+        assets
+    }
+
+    /// Search assets by game_id
+    ///
+    /// # Returns
+    /// Vector of asset IDs for the game (sorted by creation time, most recent first)
+    pub fn search_assets_by_game_id(&self, game_id: &str) -> Vec<Hash> {
+        let mut assets = self.assets.collection(game_id).unwrap_or_default();
+        
+        // Sort by creation time (most recent first) for better UX
+        assets.sort_by(|a, b| {
+            let time_a = self.assets.get(a).map(|s| s.created_at).unwrap_or(0);
+            let time_b = self.assets.get(b).map(|s| s.created_at).unwrap_or(0);
+            time_b.cmp(&time_a) // Descending order
+        });
+        
+        assets
+    }
+
+    /// Collections whose game_id starts with `prefix` and their asset counts, by game_id
+    pub fn collections_with_prefix(&self, prefix: &str) -> Vec<(String, usize)> {
+        let mut collections: Vec<(String, usize)> = self.assets
+            .collections()
+            .into_iter()
+            .filter(|(game_id, count)| game_id.starts_with(prefix) && *count > 0)
+            .collect();
+        collections.sort();
+        collections
+    }
+
+    /// Attribute value histogram of a collection (game_id)
+    ///
+    /// # Returns
+    /// Number of assets in the collection and, per attribute name, the number of
+    /// assets carrying each value. `None` if the collection has no assets.
+    pub fn get_collection_traits(&self, game_id: &str) -> Option<(usize, TraitCounts)> {
+        let total = self.assets.collection_len(game_id)?;
+        let traits = self.collection_traits
+            .get(game_id)
+            .map(|traits| traits.clone())
+            .unwrap_or_default();
+        Some((total, traits))
+    }
+
+    /// Holders of a collection's assets as of block `height`
+    ///
+    /// # Returns
+    /// Number of the collection's assets held by each address after block `height`
+    /// was applied. `None` if the collection never had assets.
+    pub fn get_collection_holders(&self, game_id: &str, height: u64) -> Option<std::collections::BTreeMap<Address, u64>> {
+        let changes = self.collection_ownership.get(game_id)?;
+        let mut owners: HashMap<Hash, Option<Address>> = HashMap::new();
+        for change in changes.iter().take_while(|change| change.height <= height) {
+            owners.insert(change.asset_id, change.owner);
+        }
+        let mut holders = std::collections::BTreeMap::new();
+        for owner in owners.into_values().flatten() {
+            *holders.entry(owner).or_insert(0) += 1;
+        }
+        Some(holders)
+    }
+
+    /// Rarity scores of all assets in a collection (cached until the collection changes)
+    pub fn get_collection_rarity(&self, game_id: &str) -> Option<Arc<HashMap<Hash, RarityScore>>> {
+        if let Some(scores) = self.collection_rarity.get(game_id) {
+            return Some(scores.clone());
+        }
+        let generation = self.rarity_generation.load(std::sync::atomic::Ordering::SeqCst);
+        let ids = self.assets.collection(game_id)?;
+        let (collection_size, traits) = self.get_collection_traits(game_id)?;
+        let assets: Vec<(Hash, AssetState)> = ids
+            .into_iter()
+            .filter_map(|id| self.assets.get(&id).map(|asset| (id, asset.clone())))
+            .collect();
+        let scores = Arc::new(compute_rarity_scores(
+            collection_size,
+            &traits,
+            assets.iter().map(|(id, asset)| (*id, asset)),
+        ));
+        if self.rarity_generation.load(std::sync::atomic::Ordering::SeqCst) == generation {
+            self.collection_rarity.insert(game_id.to_string(), scores.clone());
+        }
+        Some(scores)
+    }
+
+    /// Sale statistics of a collection (`None` before its first sale)
+    pub fn get_market_stats(&self, game_id: &str) -> Option<CollectionMarketStats> {
+        self.market_stats.get(game_id).map(|stats| stats.clone())
+    }
+
+    /// Collection-relative rarity of an asset; `None` if it has no collection
+    pub fn get_asset_rarity(&self, asset_id: &Hash) -> Option<RarityScore> {
+        let game_id = self.assets.get(asset_id)?.data.game_id.clone()?;
+        self.get_collection_rarity(&game_id)?.get(asset_id).copied()
+    }
+
+    /// Search assets by density level
+    ///
+    /// # Returns
+    /// Vector of asset IDs with the specified density (sorted by creation time, most recent first)
+    pub fn search_assets_by_density(&self, density: crate::types::DensityLevel) -> Vec<Hash> {
+        let density_level = density as u8;
+        let mut assets = self.asset_index_by_density
+            .get(&density_level)
+            .map(|v| v.clone())
+            .unwrap_or_default();
+        
+        // Sort by creation time (most recent first) for better UX
+        assets.sort_by(|a, b| {
+            let time_a = self.assets.get(a).map(|s| s.created_at).unwrap_or(0);
+            let time_b = self.assets.get(b).map(|s| s.created_at).unwrap_or(0);
+            time_b.cmp(&time_a) // Descending order
+        });
+        
+        assets
+    }
+
+    /// Assets in economic zone `zone_id` of collection `game_id`, sorted by id
+    pub fn search_assets_by_zone(&self, game_id: &str, zone_id: &str) -> Vec<Hash> {
+        let mut assets = self.asset_index_by_zone
+            .get(&format!("{}:{}", game_id, zone_id))
+            .map(|v| v.clone())
+            .unwrap_or_default();
+        assets.sort();
+        assets
+    }
+
+    /// Full-text search in metadata (simple substring matching)
+    pub fn search_assets_by_metadata(&self, query: &str) -> Vec<Hash> {
+        let query_lower = query.to_lowercase();
+        let mut results = Vec::new();
+        
+        for entry in self.assets.iter() {
+            let asset_state = entry.value();
+            // Search in metadata values
+            for value in asset_state.data.metadata.values() {
+                if value.to_lowercase().contains(&query_lower) {
+                    results.push(*entry.key());
+                    break; // Found match, no need to check other values for this asset
+                }
+            }
+        }
+        
+        results
+    }
+
+    /// Check if account has reached asset limit
+    ///
+    /// # Arguments
+    /// * `owner` - Account address
+    ///
+    /// # Returns
+    /// `Ok(())` if within limits, `Err(HazeError)` if limit exceeded
+    fn check_asset_count_limit(&self, overlay: &StateOverlay<'_>, owner: &Address) -> Result<()> {
+        let quota = self.config.get_node_quota();
+        let current_count = overlay.asset_ids_by_owner(owner).len() as u64;
+        
+        if current_count >= quota.max_assets_per_account {
+            return Err(HazeError::InvalidTransaction(
+                format!(
+                    "Asset limit exceeded: {} >= {} (node type: {})",
+                    current_count,
+                    quota.max_assets_per_account,
+                    self.config.network.node_type
+                )
+            ));
+        }
+        
+        Ok(())
+    }
+
+    /// Pay a loot box's bond out of escrow to `to` (the operator on reveal, the player on forfeit)
+    fn release_loot_bond(&self, overlay: &mut StateOverlay<'_>, loot_box: &LootBox, to: Address) -> Result<()> {
+        if loot_box.bond == 0 {
+            return Ok(());
+        }
+        let escrow = loot_escrow_address();
+        let mut escrow_account = overlay.account(&escrow);
+        escrow_account.balance = escrow_account.balance.checked_sub(loot_box.bond)
+            .ok_or_else(|| HazeError::State("Loot box escrow is short of the bond".to_string()))?;
+        overlay.put_account(escrow, escrow_account);
+        let mut recipient = overlay.account(&to);
+        recipient.balance += loot_box.bond;
+        overlay.put_account(to, recipient);
+        Ok(())
+    }
+
+    /// Move an asset of collection `game_id` from zone `from` to zone `to`, charging
+    /// `payer` the exit fee of `from` and the entry fee of `to` and crediting them to the
+    /// zone treasuries. A zone removed from the config charges no exit fee.
+    fn charge_zone_migration(
+        &self,
+        overlay: &mut StateOverlay<'_>,
+        payer: &Address,
+        game_id: Option<&str>,
+        from: Option<&str>,
+        to: Option<&str>,
+    ) -> Result<()> {
+        if from == to {
+            return Ok(());
+        }
+        let economy = &self.config.economy;
+        let exit = from.zip(game_id).and_then(|(zone, game)| economy.zone(game, zone));
+        let entry = match to {
+            Some(zone) => {
+                let target = game_id.and_then(|game| economy.zone(game, zone)).ok_or_else(|| {
+                    HazeError::InvalidTransaction(format!(
+                        "Unknown economic zone '{}' for game {:?}",
+                        zone, game_id
+                    ))
+                });
+                Some(overlay.check("zone_exists", || format!("zone {}", zone), target)?)
+            }
+            None => None,
+        };
+
+        let mut fees = Vec::new();
+        if let Some(zone) = exit.filter(|z| z.exit_fee > 0) {
+            fees.push((zone_treasury_address(&zone.game_id, &zone.zone_id), zone.exit_fee));
+        }
+        if let Some(zone) = entry.filter(|z| z.entry_fee > 0) {
+            fees.push((zone_treasury_address(&zone.game_id, &zone.zone_id), zone.entry_fee));
+        }
+        let total: u64 = fees.iter().map(|(_, fee)| fee).sum();
+
+        let mut payer_account = overlay.account(payer);
+        let balance_check = if payer_account.balance < total {
+            Err(HazeError::InsufficientBalance { required: total, available: payer_account.balance })
+        } else {
+            Ok(())
+        };
+        overlay.check(
+            "zone_fees",
+            || format!("{:?} -> {:?}: fees {}, balance {}", from, to, total, payer_account.balance),
+            balance_check,
+        )?;
+        payer_account.balance -= total;
+        overlay.put_account(*payer, payer_account);
+        for (treasury, fee) in fees {
+            let mut treasury_account = overlay.account(&treasury);
+            treasury_account.balance += fee;
+            overlay.put_account(treasury, treasury_account);
+        }
+        Ok(())
+    }
+
+    /// Hand an asset to `to` in or out of escrow: for the order book (escrowing it for a
+    /// sell order, delivering it to a buyer or returning it on cancel) or an asset pool
+    /// (staking and unstaking it). Permission grants do not survive the move.
+    fn move_escrowed_asset(overlay: &mut StateOverlay<'_>, asset_id: &Hash, to: Address) -> Result<()> {
+        let mut asset_state = overlay.asset(asset_id)
+            .ok_or_else(|| HazeError::InvalidTransaction("Asset not found".to_string()))?;
+        asset_state.owner = to;
+        asset_state.data.owner = to;
+        asset_state.permissions.clear();
+        asset_state.updated_at = overlay.timestamp;
+        overlay.put_asset(*asset_id, asset_state);
+        overlay.emit(WsEvent::AssetUpdated { asset_id: *asset_id, owner: to });
+        Ok(())
+    }
+
+    /// Process a fee `payer` paid for collection `game_id`. If the collection's referral
+    /// program shares this kind of fee and `payer` has a referrer, the referrer is credited
+    /// its share; the rest goes to the fee pool like gas.
+    fn process_collection_fee(
+        &self,
+        overlay: &mut StateOverlay<'_>,
+        payer: &Address,
+        game_id: Option<&str>,
+        kind: ReferralFee,
+        fee: u64,
+    ) -> Result<()> {
+        let program = game_id
+            .and_then(|game_id| self.config.economy.referral_program(game_id))
+            .filter(|program| match kind {
+                ReferralFee::Marketplace => program.marketplace,
+                ReferralFee::Crafting => program.crafting,
+            });
+        let referral = program.and_then(|program| overlay.referral(&program.game_id, payer).map(|r| (program, r)));
+        let Some((program, referral)) = referral else {
+            overlay.process_gas_fee(fee);
+            return Ok(());
+        };
+        let share = (fee as u128 * program.share_bps as u128 / 10_000) as u64;
+        if share > 0 {
+            let mut referrer = overlay.account(&referral.referrer);
+            referrer.balance = referrer.balance.checked_add(share)
+                .ok_or_else(|| HazeError::InvalidTransaction("Referrer balance overflows".to_string()))?;
+            overlay.put_account(referral.referrer, referrer);
+            let earned = overlay.referral_earnings.entry((referral.referrer, program.game_id.clone())).or_default();
+            match kind {
+                ReferralFee::Marketplace => earned.marketplace += share,
+                ReferralFee::Crafting => earned.crafting += share,
+            }
+            overlay.trace("referral_share", || format!("{} of fee {} to referrer", share, fee));
+        }
+        overlay.process_gas_fee(fee - share);
+        Ok(())
+    }
+
+    /// Charge every live subscription due at `height`. A payer that cannot cover the
+    /// amount is retried each block; after `economy.subscription_grace_blocks` failed
+    /// blocks the subscription lapses.
+    fn charge_due_subscriptions(&self, overlay: &mut StateOverlay<'_>, height: u64) -> Result<()> {
+        for mut subscription in overlay.due_subscriptions(height) {
+            let mut payer = overlay.account(&subscription.payer);
+            if payer.balance < subscription.amount {
+                let since = *subscription.past_due_since.get_or_insert(height);
+                if height - since >= self.config.economy.subscription_grace_blocks {
+                    subscription.status = SubscriptionStatus::Lapsed;
+                } else {
+                    subscription.status = SubscriptionStatus::PastDue;
+                    subscription.next_charge_height = height + 1;
+                }
+                overlay.subscriptions.insert(subscription.id, subscription);
+                continue;
+            }
+            payer.balance -= subscription.amount;
+            overlay.put_account(subscription.payer, payer);
+            let mut payee = overlay.account(&subscription.payee);
+            payee.balance = payee.balance.checked_add(subscription.amount)
+                .ok_or_else(|| HazeError::State("Subscription payee balance overflows".to_string()))?;
+            overlay.put_account(subscription.payee, payee);
+            Self::schedule_next_period(&mut subscription, height);
+            overlay.subscriptions.insert(subscription.id, subscription);
+        }
+        Ok(())
+    }
+
+    /// Start the seasons whose first block is `height`: thaw the collections frozen by the
+    /// game's previous season and open the season's leaderboard
+    fn start_due_seasons(&self, overlay: &mut StateOverlay<'_>, height: u64) -> Result<()> {
+        let ids = self.seasons_starting.get(&height).map(|ids| ids.clone()).unwrap_or_default();
+        for id in ids {
+            let Some(mut season) = overlay.season(&id) else {
+                continue;
+            };
+            let thawed: Vec<String> = overlay
+                .frozen_collection_names()
+                .into_iter()
+                .filter(|collection| crate::season::can_freeze(&season.game_id, collection))
+                .collect();
+            for collection in thawed {
+                overlay.frozen_collections.insert(collection, None);
+            }
+            if let Some(capacity) = season.hooks.leaderboard_capacity {
+                let board_id = crate::types::leaderboard_id(&season.operator, &season.game_id, &season.name);
+                // A board the operator opened by hand for this season is kept
+                if overlay.leaderboard(&board_id).is_none() {
+                    let mut submitters = season.hooks.leaderboard_submitters.clone();
+                    submitters.sort();
+                    submitters.dedup();
+                    overlay.leaderboards.insert(board_id, Leaderboard {
+                        id: board_id,
+                        creator: season.operator,
+                        game_id: season.game_id.clone(),
+                        season: season.name.clone(),
+                        capacity,
+                        end_height: season.end_height,
+                        submitters,
+                        created_at_height: height,
+                        entries: Vec::new(),
+                        submissions: 0,
+                    });
+                }
+                season.leaderboard = Some(board_id);
+            }
+            overlay.emit(WsEvent::SeasonStarted {
+                season_id: id,
+                game_id: season.game_id.clone(),
+                season: season.name.clone(),
+                start_height: season.start_height,
+                end_height: season.end_height,
+            });
+            overlay.seasons.insert(id, season);
+        }
+        Ok(())
+    }
+
+    /// Hatch the children of the breedings due at `height`, drawing their attributes with
+    /// the block hash `beacon`. A child of a frozen collection waits for the next block.
+    fn hatch_due_breedings(&self, overlay: &mut StateOverlay<'_>, height: u64, beacon: &Hash) -> Result<()> {
+        let ids = self.breedings_due.get(&height).map(|ids| ids.clone()).unwrap_or_default();
+        for id in ids {
+            let Some(mut breeding) = overlay.breeding(&id) else {
+                continue;
+            };
+            if breeding.hatched_at_height.is_some() || breeding.hatch_height != height {
+                continue;
+            }
+            if overlay.frozen_by(&breeding.game_id).is_some() {
+                breeding.hatch_height = height + 1;
+                overlay.breedings.insert(id, breeding);
+                continue;
+            }
+
+            let data = crate::types::AssetData {
+                density: breeding.density,
+                metadata: HashMap::new(),
+                attributes: derive_child_attributes(&breeding.parent_attributes, beacon, &id),
+                game_id: Some(breeding.game_id.clone()),
+                owner: breeding.breeder,
+            };
+            let mut asset_state = AssetState {
+                owner: breeding.breeder,
+                data,
+                created_at: overlay.timestamp,
+                updated_at: overlay.timestamp,
+                blob_refs: HashMap::new(),
+                history: Vec::new(),
+                versions: Vec::new(),
+                current_version: 0,
+                permissions: Vec::new(),
+                public_read: false,
+                private_fields: BTreeMap::new(),
+            };
+            let parents = breeding.parents.iter().map(hex::encode).collect::<Vec<_>>().join(",");
+            Self::add_asset_history(&mut asset_state, AssetAction::Breed, HashMap::from([("parents".to_string(), parents)]), overlay.timestamp);
+            Self::add_asset_snapshot(&mut asset_state, overlay.timestamp);
+            overlay.put_asset(id, asset_state);
+            for parent in breeding.parents {
+                overlay.link_assets(parent, id, AssetAction::Breed);
+            }
+            overlay.emit(WsEvent::AssetCreated {
+                asset_id: id,
+                owner: breeding.breeder,
+                density: breeding.density,
+            });
+            breeding.hatched_at_height = Some(height);
+            overlay.breedings.insert(id, breeding);
+        }
+        Ok(())
+    }
+
+    /// Roll the upgrades due at `height` with the block hash `beacon`: each asset comes back
+    /// to its owner boosted or unchanged, or is burned. An upgrade of a frozen collection
+    /// waits for the next block.
+    fn resolve_due_upgrades(&self, overlay: &mut StateOverlay<'_>, height: u64, beacon: &Hash) -> Result<()> {
+        let ids = self.upgrades_due.get(&height).map(|ids| ids.clone()).unwrap_or_default();
+        for id in ids {
+            let Some(mut upgrade) = overlay.upgrade(&id) else {
+                continue;
+            };
+            if upgrade.outcome.is_some() || upgrade.roll_height != height {
+                continue;
+            }
+            if overlay.frozen_by(&upgrade.game_id).is_some() {
+                upgrade.roll_height = height + 1;
+                overlay.upgrades.insert(id, upgrade);
+                continue;
+            }
+            let Some(mut asset_state) = overlay.asset(&upgrade.asset_id) else {
+                continue;
+            };
+
+            let outcome = roll_outcome(beacon, &id, upgrade.success_bps, upgrade.destroy_bps);
+            let value = match outcome {
+                UpgradeOutcome::Success => Some(upgrade.value_before.saturating_add(upgrade.boost)),
+                UpgradeOutcome::Fail => Some(upgrade.value_before),
+                UpgradeOutcome::Destroy => None,
+            };
+            let mut changes = HashMap::from([
+                ("upgrade_id".to_string(), hex::encode(id)),
+                ("outcome".to_string(), outcome.as_str().to_string()),
+                ("attribute".to_string(), upgrade.attribute.clone()),
+            ]);
+            if let Some(value) = value {
+                changes.insert("value".to_string(), value.to_string());
+            }
+            if !upgrade.catalysts.is_empty() {
+                changes.insert("catalysts".to_string(), upgrade.catalysts.iter().map(hex::encode).collect::<Vec<_>>().join(","));
+            }
+            if outcome == UpgradeOutcome::Success {
+                let level = AttributeValue::Int(i64::try_from(upgrade.value_before.saturating_add(upgrade.boost)).unwrap_or(i64::MAX));
+                match asset_state.data.attributes.iter_mut().find(|a| a.name == upgrade.attribute) {
+                    Some(attribute) => attribute.value = level,
+                    None => asset_state.data.attributes.push(crate::types::Attribute {
+                        name: upgrade.attribute.clone(),
+                        value: level,
+                        rarity: None,
+                    }),
+                }
+            }
+            Self::add_asset_history(&mut asset_state, AssetAction::Upgrade, changes, overlay.timestamp);
+            asset_state.updated_at = overlay.timestamp;
+            if outcome == UpgradeOutcome::Destroy {
+                overlay.remove_asset(upgrade.asset_id);
+            } else {
+                if outcome == UpgradeOutcome::Success {
+                    Self::add_asset_snapshot(&mut asset_state, overlay.timestamp);
+                }
+                overlay.put_asset(upgrade.asset_id, asset_state);
+                Self::move_escrowed_asset(overlay, &upgrade.asset_id, upgrade.owner)?;
+            }
+            overlay.emit(WsEvent::AssetUpgraded {
+                asset_id: upgrade.asset_id,
+                owner: upgrade.owner,
+                upgrade_id: id,
+                game_id: upgrade.game_id.clone(),
+                outcome,
+                attribute: upgrade.attribute.clone(),
+                value,
+                catalysts: upgrade.catalysts.clone(),
+            });
+            upgrade.outcome = Some(outcome);
+            upgrade.resolved_at_height = Some(height);
+            upgrade.value_after = value;
+            overlay.upgrades.insert(id, upgrade);
+        }
+        Ok(())
+    }
+
+    /// End the seasons whose last block is `height`: freeze their collections and snapshot
+    /// the holders of the game's collection
+    fn end_due_seasons(&self, overlay: &mut StateOverlay<'_>, height: u64) -> Result<()> {
+        let ids = self.seasons_ending.get(&height).map(|ids| ids.clone()).unwrap_or_default();
+        for id in ids {
+            let Some(mut season) = overlay.season(&id) else {
+                continue;
+            };
+            for collection in &season.hooks.freeze_collections {
+                overlay.frozen_collections.insert(collection.clone(), Some(id));
+            }
+            if season.hooks.snapshot_holders {
+                // Served from the collection's ownership history, which includes this block
+                season.holders_snapshot_height = Some(height);
+            }
+            overlay.emit(WsEvent::SeasonEnded {
+                season_id: id,
+                game_id: season.game_id.clone(),
+                season: season.name.clone(),
+                end_height: season.end_height,
+                frozen_collections: season.hooks.freeze_collections.clone(),
+            });
+            overlay.seasons.insert(id, season);
+        }
+        Ok(())
+    }
+
+    /// Book a charged period and schedule the next one (ending the subscription past `end_height`)
+    fn schedule_next_period(subscription: &mut Subscription, height: u64) {
+        // A period paid late keeps the schedule, but the next one is never due right away
+        let period_due = subscription.past_due_since.take().unwrap_or(subscription.next_charge_height);
+        subscription.charges += 1;
+        subscription.total_paid += subscription.amount;
+        subscription.next_charge_height = period_due
+            .saturating_add(subscription.interval_blocks)
+            .max(height + 1);
+        subscription.status = if subscription.end_height.is_some_and(|end| subscription.next_charge_height > end) {
+            SubscriptionStatus::Ended
+        } else {
+            SubscriptionStatus::Active
+        };
+    }
+
+    /// Pay `amount` HAZE of asset pool rewards out of the pool escrow to `to`
+    fn pay_pool_rewards(overlay: &mut StateOverlay<'_>, to: Address, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+        let escrow = asset_pool_escrow_address();
+        let mut escrow_account = overlay.account(&escrow);
+        escrow_account.balance = escrow_account.balance.checked_sub(amount)
+            .ok_or_else(|| HazeError::State("Asset pool escrow underfunded".to_string()))?;
+        overlay.put_account(escrow, escrow_account);
+        let mut account = overlay.account(&to);
+        account.balance = account.balance.checked_add(amount)
+            .ok_or_else(|| HazeError::InvalidTransaction("Recipient balance overflows".to_string()))?;
+        overlay.put_account(to, account);
+        Ok(())
+    }
+
+    /// Move `amount` HAZE out of the order escrow to `to`
+    fn release_escrow(overlay: &mut StateOverlay<'_>, to: Address, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+        let escrow = order_escrow_address();
+        let mut escrow_account = overlay.account(&escrow);
+        escrow_account.balance = escrow_account.balance.checked_sub(amount)
+            .ok_or_else(|| HazeError::State("Order escrow underfunded".to_string()))?;
+        overlay.put_account(escrow, escrow_account);
+        let mut account = overlay.account(&to);
+        account.balance = account.balance.checked_add(amount)
+            .ok_or_else(|| HazeError::InvalidTransaction("Recipient balance overflows".to_string()))?;
+        overlay.put_account(to, account);
+        Ok(())
+    }
+
+    /// Check if metadata size is within limits
+    ///
+    /// # Arguments
+    /// * `metadata_size` - Size of metadata in bytes
+    ///
+    /// # Returns
+    /// `Ok(())` if within limits, `Err(HazeError)` if limit exceeded
+    fn check_metadata_size_limit(&self, metadata_size: usize) -> Result<()> {
+        let quota = self.config.get_node_quota();
+        
+        if metadata_size > quota.max_metadata_size {
+            return Err(HazeError::AssetSizeExceeded(
+                metadata_size,
+                quota.max_metadata_size
+            ));
+        }
+        
+        Ok(())
+    }
+
+    /// Check if asset has reached blob file limit
+    ///
+    /// # Arguments
+    /// * `asset_id` - Asset identifier
+    /// * `additional_blobs` - Number of additional blob files to add
+    ///
+    /// # Returns
+    /// `Ok(())` if within limits, `Err(HazeError)` if limit exceeded
+    fn check_blob_files_limit(&self, overlay: &StateOverlay<'_>, asset_id: &Hash, additional_blobs: u64) -> Result<()> {
+        let quota = self.config.get_node_quota();
+        
+        let current_blob_count = if let Some(asset_state) = overlay.asset(asset_id) {
+            asset_state.blob_refs.len() as u64
+        } else {
+            0
+        };
+        
+        let new_blob_count = current_blob_count + additional_blobs;
+        
+        if new_blob_count > quota.max_blob_files_per_asset {
+            return Err(HazeError::InvalidTransaction(
+                format!(
+                    "Blob files limit exceeded: {} > {} (node type: {})",
+                    new_blob_count,
+                    quota.max_blob_files_per_asset,
+                    self.config.network.node_type
+                )
+            ));
+        }
+        
+        Ok(())
+    }
+
+    /// Check if account has reached blob storage limit
+    ///
+    /// Storage counts the unique bytes of the account's blobs: chunks shared between its blobs
+    /// count once. Blobs this node does not hold count as `BLOB_SIZE_ESTIMATE`.
+    ///
+    /// # Arguments
+    /// * `owner` - Account address
+    /// * `new_blobs` - Hashes of the blobs about to be referenced
+    ///
+    /// # Returns
+    /// `Ok(())` if within limits, `Err(HazeError)` if limit exceeded
+    fn check_blob_storage_limit(&self, overlay: &StateOverlay<'_>, owner: &Address, new_blobs: &[Hash]) -> Result<()> {
+        let quota = self.config.get_node_quota();
+        
+        let mut blob_hashes: Vec<Hash> = Vec::new();
+        for asset_id in &overlay.asset_ids_by_owner(owner) {
+            if let Some(asset_state) = overlay.asset(asset_id) {
+                blob_hashes.extend(asset_state.blob_refs.values());
+            }
+        }
+        blob_hashes.extend_from_slice(new_blobs);
+        let new_storage = self.blobs.unique_bytes(&blob_hashes);
+        
+        if new_storage > quota.max_blob_storage_per_account {
+            return Err(HazeError::InvalidTransaction(
+                format!(
+                    "Blob storage limit exceeded: {} > {} bytes (node type: {})",
+                    new_storage,
+                    quota.max_blob_storage_per_account,
+                    self.config.network.node_type
+                )
+            ));
+        }
+        
+        Ok(())
+    }
+
+    /// Check if caller has write permission for an asset (Update, Condense, Evaporate, Merge, Split).
+    /// Owner always has full access. GameContract grantees have limited access when game_id matches.
+    fn check_asset_write_permission(
+        &self,
+        asset_state: &AssetState,
+        caller: &Address,
+        now: i64,
+    ) -> Result<()> {
+        if has_asset_write_permission(asset_state, caller, now) {
+            return Ok(());
+        }
+        Err(HazeError::AccessDenied(
+            "Caller is not owner and has no GameContract permission".to_string(),
+        ))
+    }
+
+    /// Get quota usage for an account
+    ///
+    /// # Arguments
+    /// * `owner` - Account address
+    ///
+    /// # Returns
+    /// Quota usage information
+    pub fn get_quota_usage(&self, owner: &Address) -> QuotaUsage {
+        let quota = self.config.get_node_quota();
+        let owner_assets = self.search_assets_by_owner(owner);
+        
+        let mut total_blob_files = 0;
+        let mut blob_hashes: Vec<Hash> = Vec::new();
+        
+        for asset_id in &owner_assets {
+            if let Some(asset_state) = self.assets.get(asset_id) {
+                total_blob_files += asset_state.blob_refs.len() as u64;
+                blob_hashes.extend(asset_state.blob_refs.values());
+            }
+        }
+        let total_blob_storage_estimate = self.blobs.unique_bytes(&blob_hashes);
+        
+        QuotaUsage {
+            assets_count: owner_assets.len() as u64,
+            assets_limit: quota.max_assets_per_account,
+            blob_files_count: total_blob_files,
+            blob_files_limit: quota.max_blob_files_per_asset, // Per asset, but we show total
+            blob_storage_estimate: total_blob_storage_estimate,
+            blob_storage_limit: quota.max_blob_storage_per_account,
+            metadata_size_limit: quota.max_metadata_size,
+        }
+    }
+
+    /// Get block by hash (shared, not copied; see `get_block_header` when only the header is needed)
+    pub fn get_block(&self, hash: &Hash) -> Option<Arc<Block>> {
+        self.blocks.get(hash)
+    }
+
+    /// Get block by height
+    pub fn get_block_by_height(&self, height: u64) -> Option<Arc<Block>> {
+        self.blocks.get_by_height(height)
+    }
+
+    /// Blocks with heights in `start..=end` (inclusive), in height order; heights with no
+    /// stored block are skipped
+    pub fn get_blocks_by_height_range(&self, start: u64, end: u64) -> Vec<Arc<Block>> {
+        self.blocks.range_by_height(start, end)
+    }
+
+    /// Header of a block, without decoding its transactions
+    pub fn get_block_header(&self, hash: &Hash) -> Option<BlockHeader> {
+        self.blocks.header(hash)
+    }
+
+    /// Header of the block at `height`, without decoding its transactions
+    pub fn get_block_header_by_height(&self, height: u64) -> Option<BlockHeader> {
+        self.blocks.header_by_height(height)
+    }
+
+    /// Whether a block with this hash has been applied
+    pub fn has_block(&self, hash: &Hash) -> bool {
+        self.blocks.contains(hash)
+    }
+
+    /// Get current height
+    pub fn current_height(&self) -> u64 {
+        *self.current_height.read()
+    }
+
+    /// Apply block to state
+    /// Blocks must be applied in strict sequential order: height == current_height + 1.
+    /// This prevents reorgs that would decrease current_height.
+    /// Application is all-or-nothing: transactions run against a `StateOverlay` that is
+    /// committed only if every one of them succeeds.
+    /// Idempotent by block hash: a block that was already applied is skipped, so the same
+    /// block arriving twice (local producer and a peer) never mints rewards twice.
+    pub fn apply_block(&self, block: &Block) -> Result<()> {
+        if self.is_block_applied(&block.header.hash)? {
+            tracing::debug!(
+                "Block {} already applied, skipping",
+                crate::types::hash_to_hex(&block.header.hash)
+            );
+            return Ok(());
+        }
+        let started = std::time::Instant::now();
+        let result = self.apply_block_inner(block, false);
+        self.latency.block_apply.observe("apply", started.elapsed());
+        result
+    }
+
+    /// Check whether a block hash is in the persisted applied-blocks set
+    pub fn is_block_applied(&self, block_hash: &Hash) -> Result<bool> {
+        self.trees.indexes.contains_key(block_hash)
+    }
+
+    /// Re-execute a block stored elsewhere (an archive being replayed) without storing it
+    pub(crate) fn replay_block(&self, block: &Block) -> Result<()> {
+        let started = std::time::Instant::now();
+        let result = self.apply_block_inner(block, true);
+        self.latency.block_apply.observe("replay", started.elapsed());
+        result
+    }
+
+    /// Apply block to in-memory state; when `replaying`, the block is already persisted
+    fn apply_block_inner(&self, block: &Block, replaying: bool) -> Result<()> {
+        let current = *self.current_height.read();
+        let next_height = current.checked_add(1).ok_or_else(|| {
+            HazeError::InvalidBlock("Block height overflow".to_string())
+        })?;
+        if block.header.height != next_height {
+            return Err(HazeError::InvalidBlock(
+                format!(
+                    "Block height {} must be current_height+1 ({}); strict sequential apply, no reorg",
+                    block.header.height,
+                    next_height
+                )
+            ));
+        }
+
+        // Apply transactions to an overlay; the first failure rejects the whole block
+        // (nothing is committed) and the failing transaction is dead-lettered
+        let mut overlay = StateOverlay::at(self, block.header.height, block.header.timestamp);
+        let mut block_logs = BlockLogs::default();
+        let tx_hashes: Vec<Hash> = block.transactions.iter().map(|tx| tx.hash()).collect();
+        let mut executions = Vec::with_capacity(block.transactions.len());
+
+        // Seasons starting in this block take effect before its transactions
+        self.start_due_seasons(&mut overlay, block.header.height)?;
+        for (tx_index, tx) in block.transactions.iter().enumerate() {
+            match self.apply_measured(&mut overlay, tx) {
+                Ok(execution) => executions.push(execution),
+                Err(e) => {
+                    tracing::warn!(
+                        "Transaction {} failed in block {}: {}",
+                        crate::types::hash_to_hex(&tx_hashes[tx_index]),
+                        block.header.height,
+                        e
+                    );
+                    self.record_dead_letter(tx, tx_index, block, &e)?;
+                    return Err(e);
+                }
+            }
+            if !overlay.logs.is_empty() {
+                let tx_hash = tx_hashes[tx_index];
+                for log in std::mem::take(&mut overlay.logs) {
+                    block_logs.bloom.accrue_log(&log);
+                    block_logs.logs.push(LogEntry {
+                        log,
+                        block_height: block.header.height,
+                        block_hash: block.header.hash,
+                        tx_hash,
+                        tx_index,
+                        log_index: block_logs.logs.len(),
+                    });
+                }
+            }
+        }
+
+        // Charge subscriptions due in this block (after its transactions, so a payer can
+        // top up or cancel in the same block)
+        self.charge_due_subscriptions(&mut overlay, block.header.height)?;
+
+        // Children due in this block hatch from its hash, and upgrades due are rolled with
+        // it, before seasons end
+        self.hatch_due_breedings(&mut overlay, block.header.height, &block.header.hash)?;
+        self.resolve_due_upgrades(&mut overlay, block.header.height, &block.header.hash)?;
+
+        // Seasons ending in this block end after its transactions
+        self.end_due_seasons(&mut overlay, block.header.height)?;
+
+        // Persist the block, its applied marker and the overlay's database writes atomically
+        // (journaled, for recovery on restart) before touching in-memory state
+        let height = block.header.height;
+        let mut blocks = WriteBatch::default();
+        let mut checksums = WriteBatch::default();
+        if !replaying {
+            let encoded = block.to_wire();
+            checksums.insert(block_key(height), block_checksum(&encoded).to_vec());
+            blocks.insert(block_key(height), encoded);
+        }
+        // Blocks persisted before the applied set existed get their marker on replay
+        let mut indexes = WriteBatch::default();
+        indexes.insert(block.header.hash, height.to_be_bytes().to_vec());
+        // Receipts are derived from the block as well, so replay writes them again
+        let mut receipts = WriteBatch::default();
+        if !executions.is_empty() {
+            receipts.insert(
+                block_key(height),
+                bincode::serialize(&executions).map_err(|e| HazeError::Serialization(e.to_string()))?,
+            );
+        }
+        let mut logs = WriteBatch::default();
+        if !block_logs.logs.is_empty() {
+            logs.insert(
+                block_key(height),
+                bincode::serialize(&block_logs).map_err(|e| HazeError::Serialization(e.to_string()))?,
+            );
+        }
+        let mut tx_locations = WriteBatch::default();
+        for (tx_index, tx_hash) in tx_hashes.iter().enumerate() {
+            tx_locations.insert(tx_hash, tx_location(height, tx_index).to_vec());
+        }
+        let (accounts, assets, contract_code) = self.overlay_writes(&overlay)?;
+        let writes = [
+            (BLOCKS_TREE, &blocks),
+            (BLOCK_CHECKSUMS_TREE, &checksums),
+            (INDEXES_TREE, &indexes),
+            (RECEIPTS_TREE, &receipts),
+            (BLOCK_LOGS_TREE, &logs),
+            (TX_LOCATIONS_TREE, &tx_locations),
+            (ACCOUNTS_TREE, &accounts),
+            (ASSETS_TREE, &assets),
+            (CONTRACT_CODE_TREE, &contract_code),
+        ];
+        if replaying {
+            // Everything written here is derived from the stored block
+            self.db.apply_atomic(&writes)?;
+        } else {
+            self.journal.apply(height, block.header.hash, &writes)?;
+        }
+
+        // Readers holding a snapshot see the state either before or after this block
+        let _commit = self.snapshot_lock.write();
+
+        // Commission increases whose notice period is over apply from this block's rewards
+        self.tokenomics.activate_commissions(block.header.height);
+
+        // Process block rewards and inflation
+        let block_reward = self.tokenomics.process_block_rewards(block.header.height)?;
+        
+        // Distribute rewards to validator
+        if block_reward > 0 {
+            self.tokenomics.distribute_rewards(block_reward, block.header.validator)?;
+        }
+
+        let fees_collected: u64 = overlay.gas_fees.iter().sum();
+        let fees_burned: u64 = overlay.gas_fees.iter().map(|fee| Tokenomics::gas_burn_amount(*fee)).sum();
+        let trades = overlay.trades.clone();
+        // Floor and last sale price of every collection whose order book this block touched
+        let touched_collections: BTreeSet<String> = overlay
+            .orders
+            .values()
+            .map(|order| order.game_id.clone())
+            .chain(trades.iter().map(|trade| trade.game_id.clone()))
+            .collect();
+        let prices_before: Vec<(String, Option<u64>, Option<u64>)> = touched_collections
+            .into_iter()
+            .map(|game_id| {
+                let floor = self.get_floor_price(&game_id);
+                let last = self.market_stats.get(&game_id).and_then(|stats| stats.last_price);
+                (game_id, floor, last)
+            })
+            .collect();
+        self.commit_overlay_in_memory(overlay)?;
+        for trade in &trades {
+            self.market_stats
+                .entry(trade.game_id.clone())
+                .or_default()
+                .record_sale(trade.price, trade.fee, trade.height, block.header.timestamp);
+        }
+        for (game_id, previous_floor_price, previous_last_price) in prices_before {
+            let floor_price = self.get_floor_price(&game_id);
+            let last_price = self.market_stats.get(&game_id).and_then(|stats| stats.last_price);
+            if floor_price != previous_floor_price || last_price != previous_last_price {
+                self.broadcast_event(WsEvent::CollectionPriceChanged {
+                    game_id,
+                    floor_price,
+                    previous_floor_price,
+                    last_price,
+                    previous_last_price,
+                    height,
+                });
+            }
+        }
+        self.tokenomics.record_block_fees(
+            block.header.wave_number,
+            block.transactions.len() as u64,
+            fees_collected,
+            fees_burned,
+            block_reward,
+        );
+        self.blocks.record(block.header.hash, height);
+        self.subscriptions_due.remove(&height);
+        self.seasons_starting.remove(&height);
+        self.seasons_ending.remove(&height);
+        self.breedings_due.remove(&height);
+        self.upgrades_due.remove(&height);
+
+        {
+            let mut timestamps = self.recent_block_timestamps.write();
+            timestamps.push_back(block.header.timestamp);
+            while timestamps.len() > self.config.consensus.median_time_window {
+                timestamps.pop_front();
+            }
+        }
+        self.recent_fees.write().record_block(block.transactions.iter().map(Transaction::fee).collect());
+
+        // Sample pool prices once per block for the TWAP oracle
+        self.economy.record_price_observations(height, block.header.timestamp);
+
+        // Count upgrade signals (features locked in by this block activate a window later)
+        self.features.record_block(height, block.header.signals);
+
+        // Update height
+        *self.current_height.write() = height;
+        self.bump_state_version();
+
+        Ok(())
+    }
+
+    /// Fee distribution of the transactions in the last `consensus.fee_estimate_blocks` applied
+    /// blocks, with the number of blocks it covers
+    pub fn recent_fees(&self) -> (FeeHistogram, usize) {
+        let recent = self.recent_fees.read();
+        (recent.histogram().clone(), recent.blocks())
+    }
+
+    /// Latency histograms exported at `/api/v1/metrics/prometheus`
+    pub fn latency(&self) -> &Arc<LatencyMetrics> {
+        &self.latency
+    }
+
+    /// Median timestamp of the last `consensus.median_time_window` applied blocks
+    /// (`None` before the first block)
+    pub fn median_time_past(&self) -> Option<i64> {
+        let mut timestamps: Vec<i64> = self.recent_block_timestamps.read().iter().copied().collect();
+        if timestamps.is_empty() {
+            return None;
+        }
+        timestamps.sort_unstable();
+        Some(timestamps[timestamps.len() / 2])
+    }
+
+    /// Database writes of an overlay: the account index entries of changed accounts, and the
+    /// bytecode of deployed contracts not stored yet (identical code is stored once)
+    fn overlay_writes(&self, overlay: &StateOverlay<'_>) -> Result<(WriteBatch, WriteBatch, WriteBatch)> {
+        let mut account_index = WriteBatch::default();
+        for (address, account) in &overlay.accounts {
+            let value = bincode::serialize(account).map_err(|e| HazeError::Serialization(e.to_string()))?;
+            account_index.insert(address, value);
+        }
+        let mut asset_index = WriteBatch::default();
+        for (asset_id, asset) in &overlay.assets {
+            match asset {
+                Some(asset) => {
+                    let value = bincode::serialize(asset).map_err(|e| HazeError::Serialization(e.to_string()))?;
+                    asset_index.insert(asset_id, value);
+                }
+                None => asset_index.remove(asset_id),
+            }
+        }
+        let mut contract_code = WriteBatch::default();
+        for (info, code) in overlay.contracts.values() {
+            if !self.trees.contract_code.contains_key(&info.code_hash)? {
+                contract_code.insert(info.code_hash, code.clone());
+            }
+        }
+        Ok((account_index, asset_index, contract_code))
+    }
+
+    /// Persist the overlay's database writes, then commit it to the live state
+    fn commit_overlay(&self, overlay: StateOverlay<'_>) -> Result<()> {
+        let (accounts, assets, contract_code) = self.overlay_writes(&overlay)?;
+        self.db.apply_atomic(&[
+            (ACCOUNTS_TREE, &accounts),
+            (ASSETS_TREE, &assets),
+            (CONTRACT_CODE_TREE, &contract_code),
+        ])?;
+        self.commit_overlay_in_memory(overlay)
+    }
+
+    /// Commit overlay writes to the live state, keeping asset indexes in sync (database writes
+    /// are persisted by the caller, see `overlay_writes`)
+    fn commit_overlay_in_memory(&self, overlay: StateOverlay<'_>) -> Result<()> {
+        let StateOverlay {
+            accounts, assets, contracts, asset_hooks, payment_requests, lineage, orders, trades, orders_placed,
+            referrals, referral_earnings, subscriptions, loot_boxes, leaderboards, seasons, frozen_collections,
+            asset_pools, asset_stakes, breedings, last_bred, upgrades, sponsorships, gas_fees, stakes, unjails, commissions, committee_votes, events, timestamp, ..
+        } = overlay;
+
+        for (address, account) in accounts {
+            self.account_hashes.set(&address, &account);
+            self.accounts.insert(address, account);
+        }
+
+        for (asset_id, asset) in assets {
+            let old = self.assets.get(&asset_id).map(|a| a.clone());
+            let reindex = match (&old, &asset) {
+                (Some(old), Some(new)) => {
+                    old.owner != new.owner
+                        || old.data.game_id != new.data.game_id
+                        || old.data.density != new.data.density
+                        || asset_zone(&old.data) != asset_zone(&new.data)
+                }
+                _ => true,
+            };
+            if reindex {
+                if let Some(ref old) = old {
+                    self.remove_asset_from_indexes(&asset_id, old);
+                }
+                if let Some(ref new) = asset {
+                    self.add_asset_to_indexes(&asset_id, new);
+                }
+                self.record_ownership_change(&asset_id, old.as_ref(), asset.as_ref());
+            }
+            let retrait = match (&old, &asset) {
+                (Some(old), Some(new)) => {
+                    old.data.game_id != new.data.game_id || asset_traits(old) != asset_traits(new)
+                }
+                _ => true,
+            };
+            if retrait {
+                if let Some(ref old) = old {
+                    self.remove_asset_traits(old);
+                }
+                if let Some(ref new) = asset {
+                    self.add_asset_traits(new);
+                }
+            }
+            match asset {
+                Some(new) => {
+                    self.assets.insert(asset_id, new);
+                }
+                None => {
+                    self.assets.remove(&asset_id);
+                }
+            }
+        }
+
+        for (address, (info, _)) in contracts {
+            self.contracts.insert(address, info);
+        }
+
+        for (game_id, hook) in asset_hooks {
+            match hook {
+                Some(hook) => {
+                    self.asset_hooks.insert(game_id, hook);
+                }
+                None => {
+                    self.asset_hooks.remove(&game_id);
+                }
+            }
+        }
+
+        for (id, request) in payment_requests {
+            self.payment_requests.insert(id, request);
+        }
+
+        for edge in lineage {
+            self.asset_lineage.entry(edge.parent).or_default().children.push(edge.clone());
+            self.asset_lineage.entry(edge.child).or_default().parents.push(edge);
+        }
+
+        for (id, order) in orders {
+            let mut open = self.open_orders.entry(order.game_id.clone()).or_default();
+            open.retain(|open_id| *open_id != id);
+            if order.status == OrderStatus::Open {
+                open.push(id);
+            }
+            let now_empty = open.is_empty();
+            drop(open);
+            if now_empty {
+                self.open_orders.remove(&order.game_id);
+            }
+            self.orders.insert(id, order);
+        }
+        for trade in trades {
+            let mut history = self.trades.entry(trade.game_id.clone()).or_default();
+            history.push_back(trade);
+            while history.len() > MAX_TRADE_HISTORY {
+                history.pop_front();
+            }
+        }
+        self.order_sequence.fetch_add(orders_placed, std::sync::atomic::Ordering::SeqCst);
+
+        for (key, referral) in referrals {
+            self.referrals.insert(key, referral);
+        }
+        for ((referrer, game_id), earned) in referral_earnings {
+            self.referral_earnings.entry(referrer).or_default().entry(game_id).or_default().add(&earned);
+        }
+
+        for (id, subscription) in subscriptions {
+            if !self.subscriptions.contains_key(&id) {
+                self.subscriptions_by_account.entry(subscription.payer).or_default().push(id);
+                self.subscriptions_by_account.entry(subscription.payee).or_default().push(id);
+            }
+            if subscription.is_live() {
+                let mut due = self.subscriptions_due.entry(subscription.next_charge_height).or_default();
+                if !due.contains(&id) {
+                    due.push(id);
+                }
+            }
+            self.subscriptions.insert(id, subscription);
+        }
+
+        for (id, loot_box) in loot_boxes {
+            self.loot_boxes.insert(id, loot_box);
+        }
+
+        for (id, board) in leaderboards {
+            if !self.leaderboards.contains_key(&id) {
+                self.leaderboards_by_game.entry(board.game_id.clone()).or_default().push(id);
+            }
+            self.leaderboards.insert(id, board);
+        }
+
+        for (id, season) in seasons {
+            if !self.seasons.contains_key(&id) {
+                self.seasons_by_game.entry(season.game_id.clone()).or_default().push(id);
+                self.seasons_starting.entry(season.start_height).or_default().push(id);
+                self.seasons_ending.entry(season.end_height).or_default().push(id);
+            }
+            self.seasons.insert(id, season);
+        }
+
+        for (collection, season) in frozen_collections {
+            match season {
+                Some(season) => self.frozen_collections.insert(collection, season),
+                None => self.frozen_collections.remove(&collection).map(|(_, season)| season),
+            };
+        }
+
+        for (id, pool) in asset_pools {
+            if !self.asset_pools.contains_key(&id) {
+                self.asset_pools_by_game.entry(pool.game_id.clone()).or_default().push(id);
+            }
+            self.asset_pools.insert(id, pool);
+        }
+
+        for (asset_id, stake) in asset_stakes {
+            if let Some((_, old)) = self.asset_stakes.remove(&asset_id)
+                && let Some(mut ids) = self.asset_stakes_by_owner.get_mut(&old.owner)
+            {
+                ids.retain(|id| *id != asset_id);
+            }
+            if let Some(stake) = stake {
+                self.asset_stakes_by_owner.entry(stake.owner).or_default().push(asset_id);
+                self.asset_stakes.insert(asset_id, stake);
+            }
+        }
+
+        for (id, breeding) in breedings {
+            let scheduled = self.breedings.get(&id).map(|b| b.hatch_height);
+            if breeding.hatched_at_height.is_none() && scheduled != Some(breeding.hatch_height) {
+                self.breedings_due.entry(breeding.hatch_height).or_default().push(id);
+            }
+            self.breedings.insert(id, breeding);
+        }
+
+        for (asset_id, height) in last_bred {
+            self.last_bred.insert(asset_id, height);
+        }
+
+        for (id, upgrade) in upgrades {
+            let scheduled = self.upgrades.get(&id).map(|u| u.roll_height);
+            if scheduled.is_none() {
+                self.upgrades_by_asset.entry(upgrade.asset_id).or_default().push(id);
+            }
+            if upgrade.outcome.is_none() && scheduled != Some(upgrade.roll_height) {
+                self.upgrades_due.entry(upgrade.roll_height).or_default().push(id);
+            }
+            self.upgrades.insert(id, upgrade);
+        }
+
+        for (paymaster, window, amount) in sponsorships {
+            let mut spend = self.paymaster_spend.entry(paymaster).or_insert((window, 0));
+            if spend.0 != window {
+                *spend = (window, 0);
+            }
+            spend.1 += amount;
+        }
+
+        for fee in gas_fees {
+            self.tokenomics.process_gas_fee(fee)?;
+        }
+        for (validator, amount) in stakes {
+            self.tokenomics.stake(validator, validator, amount)?;
+        }
+        for validator in unjails {
+            self.tokenomics.unjail(&validator, timestamp)?;
+        }
+        let policy = self.config.consensus.commission_policy();
+        for (validator, rate_bps, height) in commissions {
+            self.tokenomics.set_commission(&validator, rate_bps, height, &policy)?;
+        }
+        if !committee_votes.is_empty() {
+            for (voter, params) in committee_votes {
+                self.tokenomics.vote_committee_params(voter, params);
+            }
+            self.tally_committee_votes();
+        }
+
+        self.bump_state_version();
+        for event in events {
+            self.broadcast_event(event);
+        }
+
+        Ok(())
+    }
+
+    /// Flush the database, sample per-tree sizes and blob deduplication, and trim the blob
+    /// cache of an object store backend (blocking; run from the maintenance task)
+    pub fn run_storage_maintenance(&self) -> Result<StorageStats> {
+        let stats = self.storage.run()?;
+        *self.blob_stats.write() = self.blobs.stats()?;
+        let evicted = self.blobs.trim_cache()?;
+        if evicted > 0 {
+            tracing::info!("Evicted {} bytes of cached blob chunks", evicted);
+        }
+        Ok(stats)
+    }
+
+    /// Blob store of this node (shared; reads and writes block, so call them off the runtime)
+    pub fn blob_storage(&self) -> Arc<BlobStorage> {
+        self.blobs.clone()
+    }
+
+    /// Blob store deduplication from the last maintenance run
+    pub fn blob_stats(&self) -> BlobStats {
+        self.blob_stats.read().clone()
+    }
+
+    /// Database sizes and flush timing from the last maintenance run
+    pub fn storage_stats(&self) -> StorageStats {
+        self.storage.stats()
+    }
+
+    /// Height of the last block whose database writes all completed (from the journal)
+    pub fn last_clean_height(&self) -> u64 {
+        self.journal.last_clean_height()
+    }
+
+    /// Interrupted block applications recovered from the journal at startup
+    pub fn journal_recoveries(&self) -> Vec<JournalRecovery> {
+        self.journal.recovered()
+    }
+
+    /// Open a named tree in the node database for node-local data kept outside the chain state
+    pub fn open_tree(&self, name: &str) -> Result<Arc<dyn StorageTree>> {
+        self.db.open_tree(name)
+    }
+
+    /// Current state version; changes whenever accounts, assets or the height change
+    pub fn state_version(&self) -> u64 {
+        self.state_version.load(std::sync::atomic::Ordering::SeqCst)
+    }
+
+    /// Pin a consistent view of the state for a multi-entity read
+    ///
+    /// Block application waits while the snapshot is held, so everything read through `self`
+    /// until it is dropped reflects exactly `snapshot.height`. Keep it short-lived and never
+    /// hold it across an `.await`.
+    pub fn read_snapshot(&self) -> ReadSnapshot<'_> {
+        let guard = self.snapshot_lock.read();
+        ReadSnapshot {
+            height: *self.current_height.read(),
+            _guard: guard,
+        }
+    }
+
+    fn bump_state_version(&self) {
+        self.state_version.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    }
+
+    /// Record a transaction that failed block application (persisted to the database).
+    /// Repeated failures of the same transaction bump its attempt counter.
+    fn record_dead_letter(&self, tx: &Transaction, tx_index: usize, block: &Block, error: &HazeError) -> Result<()> {
+        let tx_hash = tx.hash();
+        let attempts = self.dead_letters.get(&tx_hash).map(|e| e.attempts).unwrap_or(0) + 1;
+        let entry = DeadLetterEntry {
+            tx_hash,
+            transaction: tx.clone(),
+            error: error.to_string(),
+            block_height: block.header.height,
+            block_hash: block.header.hash,
+            tx_index,
+            failed_at: chrono::Utc::now().timestamp(),
+            attempts,
+            error_code: error.code(),
+            error_params: error.params(),
+        };
+        let serialized = bincode::serialize(&entry).map_err(|e| HazeError::Serialization(e.to_string()))?;
+        self.trees.dead_letters.insert(&tx_hash, &serialized)?;
+        self.dead_letters.insert(tx_hash, entry);
+        Ok(())
+    }
+
+    /// Get dead-lettered transaction by hash
+    pub fn get_dead_letter(&self, tx_hash: &Hash) -> Option<DeadLetterEntry> {
+        self.dead_letters.get(tx_hash).map(|e| e.clone())
+    }
+
+    /// List dead-lettered transactions (most recent failure first)
+    ///
+    /// # Arguments
+    /// * `limit` - Maximum number of entries to return (0 = all)
+    pub fn get_dead_letters(&self, limit: usize) -> Vec<DeadLetterEntry> {
+        let mut entries: Vec<DeadLetterEntry> = self.dead_letters.iter().map(|e| e.value().clone()).collect();
+        entries.sort_by_key(|e| std::cmp::Reverse(e.failed_at));
+        if limit > 0 {
+            entries.truncate(limit);
+        }
+        entries
+    }
+
+    /// Direct composition edges of an asset (`None` if it never took part in a merge or split)
+    pub fn get_asset_lineage(&self, asset_id: &Hash) -> Option<AssetLineage> {
+        self.asset_lineage.get(asset_id).map(|l| l.clone())
+    }
+
+    /// All edges reachable from `asset_id` through parents (`ancestors == true`) or children,
+    /// sorted by height
+    pub fn lineage_closure(&self, asset_id: &Hash, ancestors: bool) -> Vec<LineageEdge> {
+        let mut edges = Vec::new();
+        let mut visited = std::collections::HashSet::from([*asset_id]);
+        let mut queue = std::collections::VecDeque::from([*asset_id]);
+        while let Some(id) = queue.pop_front() {
+            let Some(lineage) = self.asset_lineage.get(&id) else {
+                continue;
+            };
+            let next = if ancestors { &lineage.parents } else { &lineage.children };
+            for edge in next {
+                edges.push(edge.clone());
+                let other = if ancestors { edge.parent } else { edge.child };
+                if visited.insert(other) {
+                    queue.push_back(other);
+                }
+            }
+        }
+        edges.sort_by_key(|e| (e.height, e.parent, e.child));
+        edges.dedup();
+        edges
+    }
+
+    /// Compact commitment to an asset's ancestry: sha256 over its ancestor edges in
+    /// (height, parent, child) order, each `parent || child || action byte || height LE`
+    /// (`None` for an asset with no ancestors)
+    pub fn lineage_hash(&self, asset_id: &Hash) -> Option<Hash> {
+        let ancestors = self.lineage_closure(asset_id, true);
+        if ancestors.is_empty() {
+            return None;
+        }
+        let mut data = Vec::with_capacity(32 + ancestors.len() * 73);
+        data.extend_from_slice(asset_id);
+        for edge in &ancestors {
+            data.extend_from_slice(&edge.parent);
+            data.extend_from_slice(&edge.child);
+            data.push(match edge.action {
+                AssetAction::Merge => 4,
+                AssetAction::Split => 5,
+                AssetAction::Breed => 7,
+                AssetAction::Upgrade => 8,
+                _ => 0,
+            });
+            data.extend_from_slice(&edge.height.to_le_bytes());
+        }
+        Some(crate::types::sha256(&data))
+    }
+
+    /// Payment request by id
+    pub fn get_payment_request(&self, id: &Hash) -> Option<PaymentRequest> {
+        self.payment_requests.get(id).map(|r| r.clone())
+    }
+
+    /// Loot box by id
+    pub fn get_loot_box(&self, id: &Hash) -> Option<LootBox> {
+        self.loot_boxes.get(id).map(|b| b.clone())
+    }
+
+    /// Leaderboard by id
+    pub fn get_leaderboard(&self, id: &Hash) -> Option<Leaderboard> {
+        self.leaderboards.get(id).map(|b| b.clone())
+    }
+
+    /// Leaderboards of a collection, oldest first
+    pub fn get_collection_leaderboards(&self, game_id: &str) -> Vec<Leaderboard> {
+        let ids = self.leaderboards_by_game.get(game_id).map(|ids| ids.clone()).unwrap_or_default();
+        ids.iter().filter_map(|id| self.get_leaderboard(id)).collect()
+    }
+
+    /// Asset pool by id
+    pub fn get_asset_pool(&self, id: &Hash) -> Option<AssetPool> {
+        self.asset_pools.get(id).map(|p| p.clone())
+    }
+
+    /// Asset pools of a collection, oldest first
+    pub fn get_collection_asset_pools(&self, game_id: &str) -> Vec<AssetPool> {
+        let ids = self.asset_pools_by_game.get(game_id).map(|ids| ids.clone()).unwrap_or_default();
+        ids.iter().filter_map(|id| self.get_asset_pool(id)).collect()
+    }
+
+    /// Stake of an asset, if it is staked
+    pub fn get_asset_stake(&self, asset_id: &Hash) -> Option<AssetStake> {
+        self.asset_stakes.get(asset_id).map(|s| s.clone())
+    }
+
+    /// Assets an account has staked, oldest first
+    pub fn get_account_asset_stakes(&self, address: &Address) -> Vec<AssetStake> {
+        let ids = self.asset_stakes_by_owner.get(address).map(|ids| ids.clone()).unwrap_or_default();
+        let mut stakes: Vec<AssetStake> = ids.iter().filter_map(|id| self.get_asset_stake(id)).collect();
+        stakes.sort_by_key(|s| (s.staked_at_height, s.asset_id));
+        stakes
+    }
+
+    /// Breeding by id (also the id of its child)
+    pub fn get_breeding(&self, id: &Hash) -> Option<Breeding> {
+        self.breedings.get(id).map(|b| b.clone())
+    }
+
+    /// Height an asset last bred at
+    pub fn get_last_bred(&self, asset_id: &Hash) -> Option<u64> {
+        self.last_bred.get(asset_id).map(|h| *h)
+    }
+
+    /// Upgrade by id
+    pub fn get_upgrade(&self, id: &Hash) -> Option<Upgrade> {
+        self.upgrades.get(id).map(|u| u.clone())
+    }
+
+    /// Upgrades of an asset, oldest first
+    pub fn get_asset_upgrades(&self, asset_id: &Hash) -> Vec<Upgrade> {
+        let ids = self.upgrades_by_asset.get(asset_id).map(|ids| ids.clone()).unwrap_or_default();
+        ids.iter().filter_map(|id| self.upgrades.get(id).map(|u| u.clone())).collect()
+    }
+
+    /// Season by id
+    pub fn get_season(&self, id: &Hash) -> Option<Season> {
+        self.seasons.get(id).map(|s| s.clone())
+    }
+
+    /// Seasons of a game, in season order
+    pub fn get_game_seasons(&self, game_id: &str) -> Vec<Season> {
+        let ids = self.seasons_by_game.get(game_id).map(|ids| ids.clone()).unwrap_or_default();
+        let mut seasons: Vec<Season> = ids.iter().filter_map(|id| self.get_season(id)).collect();
+        seasons.sort_by_key(|s| s.start_height);
+        seasons
+    }
+
+    /// Frozen collections of a game (its own and those in its namespace), with the season
+    /// that froze each, by name
+    pub fn get_frozen_collections(&self, game_id: &str) -> Vec<(String, Hash)> {
+        let mut frozen: Vec<(String, Hash)> = self.frozen_collections
+            .iter()
+            .filter(|e| crate::season::can_freeze(game_id, e.key()))
+            .map(|e| (e.key().clone(), *e.value()))
+            .collect();
+        frozen.sort();
+        frozen
+    }
+
+    /// Subscription by id
+    pub fn get_subscription(&self, id: &Hash) -> Option<Subscription> {
+        self.subscriptions.get(id).map(|s| s.clone())
+    }
+
+    /// Subscriptions an account pays or is paid by, oldest first
+    pub fn get_account_subscriptions(&self, address: &Address) -> Vec<Subscription> {
+        let ids = self.subscriptions_by_account.get(address).map(|ids| ids.clone()).unwrap_or_default();
+        let mut subscriptions: Vec<Subscription> = ids.iter().filter_map(|id| self.get_subscription(id)).collect();
+        subscriptions.sort_by_key(|s| (s.created_at_height, s.id));
+        subscriptions
+    }
+
+    /// Referrer of `player` in collection `game_id`
+    pub fn get_referral(&self, game_id: &str, player: &Address) -> Option<Referral> {
+        self.referrals.get(&(game_id.to_string(), *player)).map(|r| r.clone())
+    }
+
+    /// Referral earnings of `referrer` by game_id
+    pub fn get_referral_earnings(&self, referrer: &Address) -> BTreeMap<String, ReferralEarnings> {
+        self.referral_earnings.get(referrer).map(|e| e.clone()).unwrap_or_default()
+    }
+
+    /// Order book order by id (open, filled or cancelled)
+    pub fn get_order(&self, id: &Hash) -> Option<Order> {
+        self.orders.get(id).map(|o| o.clone())
+    }
+
+    /// Open orders of a collection, oldest first
+    pub fn get_open_orders(&self, game_id: &str) -> Vec<Order> {
+        let ids = self.open_orders.get(game_id).map(|ids| ids.clone()).unwrap_or_default();
+        let mut orders: Vec<Order> = ids.iter().filter_map(|id| self.get_order(id)).collect();
+        orders.sort_by_key(|o| o.sequence);
+        orders
+    }
+
+    /// Latest trades of a collection, newest first (`limit` 0 = all kept)
+    pub fn get_trades(&self, game_id: &str, limit: usize) -> Vec<Trade> {
+        let Some(trades) = self.trades.get(game_id) else {
+            return Vec::new();
+        };
+        let limit = if limit == 0 { trades.len() } else { limit };
+        trades.iter().rev().take(limit).cloned().collect()
+    }
+
+    /// Lowest open ask of a collection's order book
+    pub fn get_floor_price(&self, game_id: &str) -> Option<u64> {
+        let ids = self.open_orders.get(game_id).map(|ids| ids.clone()).unwrap_or_default();
+        ids.iter()
+            .filter_map(|id| self.orders.get(id))
+            .filter(|order| order.side == OrderSide::Sell && order.status == OrderStatus::Open)
+            .map(|order| order.price)
+            .min()
+    }
+
+    /// Remove a transaction from the dead-letter store (e.g. once it has been resubmitted)
+    pub fn remove_dead_letter(&self, tx_hash: &Hash) -> Result<Option<DeadLetterEntry>> {
+        self.trees.dead_letters.remove(tx_hash)?;
+        Ok(self.dead_letters.remove(tx_hash).map(|(_, e)| e))
+    }
+
+    /// Re-execute a transaction with step-by-step tracing (nothing is committed)
+    ///
+    /// Runs against the state at `height`, after `preceding` (the transactions before it
+    /// in its block). Past states are rebuilt by replaying blocks into a temporary
+    /// database, up to `MAX_TRACE_REPLAY_HEIGHT`; state not created by blocks (e.g.
+    /// test accounts) is not part of a replayed state.
+    pub fn trace_transaction(&self, tx: &Transaction, height: u64, preceding: &[Transaction]) -> Result<TransactionTrace> {
+        let current = self.current_height();
+        if height == current {
+            return self.trace_in_overlay(tx, height, preceding);
+        }
+        if height > current {
+            return Err(HazeError::InvalidTransaction(
+                format!("Cannot trace against height {} above current height {}", height, current)
+            ));
+        }
+        if height > MAX_TRACE_REPLAY_HEIGHT {
+            return Err(HazeError::InvalidTransaction(
+                format!("Cannot rebuild state at height {} (replay limit {})", height, MAX_TRACE_REPLAY_HEIGHT)
+            ));
+        }
+        self.state_at_height(height)?.trace_in_overlay(tx, height, preceding)
+    }
+
+    /// Apply `txs` in order on top of the current state without committing anything, stopping
+    /// at the first failure (used to estimate gas and fees before submitting)
+    pub fn dry_run(&self, txs: &[Transaction]) -> DryRun {
+        let mut overlay = StateOverlay::new(self);
+        let mut executions = Vec::with_capacity(txs.len());
+        for (index, tx) in txs.iter().enumerate() {
+            match self.apply_measured(&mut overlay, tx) {
+                Ok(execution) => executions.push(execution),
+                Err(e) => return DryRun { executions, failure: Some((index, e)) },
+            }
+        }
+        DryRun { executions, failure: None }
+    }
+
+    /// Apply a transaction to an overlay and measure what it did
+    fn apply_measured(&self, overlay: &mut StateOverlay<'_>, tx: &Transaction) -> Result<TxExecution> {
+        let (fees_before, events_before) = (overlay.gas_fees.len(), overlay.events.len());
+        let (discount_before, gas_before) = (overlay.gas_discount, overlay.gas_used);
+        let sponsorships_before = overlay.sponsorships.len();
+        let started = std::time::Instant::now();
+        let applied = self.apply_transaction_to_overlay(overlay, tx);
+        self.latency.tx_apply.observe(tx.type_name(), started.elapsed());
+        applied?;
+        Ok(TxExecution {
+            gas_used: overlay.gas_used - gas_before,
+            fees_paid: overlay.gas_fees[fees_before..].iter().sum(),
+            gas_discount: overlay.gas_discount - discount_before,
+            sponsorship: overlay.sponsorships[sponsorships_before..]
+                .first()
+                .map(|(paymaster, _, amount)| (*paymaster, *amount)),
+            events: overlay.events[events_before..].to_vec(),
+        })
+    }
+
+    fn trace_in_overlay(&self, tx: &Transaction, height: u64, preceding: &[Transaction]) -> Result<TransactionTrace> {
+        let mut overlay = StateOverlay::new(self);
+        for earlier in preceding {
+            self.apply_transaction_to_overlay(&mut overlay, earlier)?;
+        }
+        overlay.trace = Some(Vec::new());
+        let result = self.apply_transaction_to_overlay(&mut overlay, tx);
+        let mut steps = overlay.trace.take().unwrap_or_default();
+        let (error, error_code, error_params) = match result {
+            Ok(()) => (None, None, std::collections::BTreeMap::new()),
+            Err(e) => (Some(e.to_string()), Some(e.code()), e.params()),
+        };
+        // Checks without their own step still show up as the failing step
+        if let Some(error) = error.as_ref().filter(|_| steps.last().is_none_or(|step| step.passed)) {
+            steps.push(TraceStep { check: "apply".to_string(), detail: error.clone(), passed: false });
+        }
+        Ok(TransactionTrace { state_height: height, steps, error, error_code, error_params })
+    }
+
+    /// Rebuild the state at `height` by replaying this node's blocks into a temporary database
+    fn state_at_height(&self, height: u64) -> Result<StateManager> {
+        let historical = Self::with_db(Arc::new(SledStorage::temporary()?), &self.config)?;
+        for block_height in 1..=height.min(self.current_height()) {
+            let block = self.get_block_by_height(block_height).ok_or_else(|| {
+                HazeError::Database(format!("Block {} missing from storage", block_height))
+            })?;
+            historical.apply_block_inner(&block, true)?;
+        }
+        Ok(historical)
+    }
+
+    /// Apply a single transaction and commit it on success (blocks go through `apply_block`)
+    #[cfg(test)]
+    pub(crate) fn apply_transaction(&self, tx: &Transaction) -> Result<()> {
+        let mut overlay = StateOverlay::new(self);
+        self.apply_transaction_to_overlay(&mut overlay, tx)?;
+        let _commit = self.snapshot_lock.write();
+        self.commit_overlay(overlay)
+    }
+
+    /// Next nonce `address` may send on the lane of `nonce`: the main sequence, or the lane's
+    /// own sequence for accounts in `consensus.nonce_lane_accounts`
+    pub(crate) fn expected_nonce(&self, account: &AccountState, address: &Address, nonce: u64) -> Result<u64> {
+        let lane = crate::types::nonce_lane(nonce);
+        if lane == 0 || !self.config.consensus.has_nonce_lanes(address) {
+            return Ok(account.nonce);
+        }
+        if lane as u32 >= self.config.consensus.max_nonce_lanes {
+            return Err(HazeError::InvalidTransaction(format!(
+                "Nonce lane {} out of range (max_nonce_lanes {})",
+                lane, self.config.consensus.max_nonce_lanes
+            )));
+        }
+        if crate::types::nonce_sequence(nonce) == crate::types::MAX_LANE_SEQUENCE {
+            return Err(HazeError::InvalidTransaction(format!("Nonce lane {} is exhausted", lane)));
+        }
+        Ok(account.expected_nonce(nonce))
+    }
+
+    fn check_governance_token(&self) -> Result<()> {
+        if !self.config.governance_token.enabled {
+            return Err(HazeError::InvalidTransaction(
+                "The governance token is not enabled on this chain".to_string()
+            ));
+        }
+        Ok(())
+    }
+
+    fn check_nonce(&self, account: &AccountState, address: &Address, nonce: u64) -> Result<()> {
+        let expected = self.expected_nonce(account, address, nonce)?;
+        if nonce != expected {
+            return Err(HazeError::InvalidNonce { expected, actual: nonce });
+        }
+        Ok(())
+    }
+
+    /// Apply transaction to an overlay; on error the overlay may hold partial
+    /// writes of this transaction and must be discarded
+    fn apply_transaction_to_overlay(&self, overlay: &mut StateOverlay<'_>, tx: &Transaction) -> Result<()> {
+        // The pool turns these away; once the feature is active a block carrying one is
+        // rejected (blocks stored before that still replay)
+        if self.features.is_active(Feature::TransactionBounds, overlay.height) {
+            if tx.chain_id().is_some_and(|chain_id| chain_id != self.config.chain_id) {
+                return Err(HazeError::InvalidTransaction("Transaction chain_id does not match node".to_string()));
+            }
+            if tx.is_expired_at(overlay.height) {
+                return Err(HazeError::InvalidTransaction("Transaction valid_until_height has passed".to_string()));
+            }
+        }
+        match tx {
+            Transaction::Transfer { from, to, amount, fee, nonce, .. } => {
+                let mut from_account = overlay.account(from);
+                
+                // Verify nonce is sequential
+                self.check_nonce(&from_account, from, *nonce)?;
+                
+                if from_account.balance < *amount + *fee {
+                    return Err(HazeError::InsufficientBalance { required: *amount + *fee, available: from_account.balance });
+                }
+
+                from_account.balance -= amount + fee;
+                from_account.advance_nonce(*nonce);
+                overlay.put_account(*from, from_account);
+
+                // Read the recipient after writing the sender (they may be the same account)
+                let mut to_account = overlay.account(to);
+                to_account.balance += amount;
+                overlay.put_account(*to, to_account);
+                
+                // Process gas fee (burn 50%)
+                overlay.process_gas_fee(*fee);
+            }
+            Transaction::MistbornAsset { from, action, asset_id, data, nonce, .. } => {
+                if *from != data.owner {
+                    return Err(HazeError::AccessDenied(
+                        "MistbornAsset must be sent by the asset owner".to_string()
+                    ));
+                }
+                let mut from_account = overlay.account(from);
+                let nonce_check = self.check_nonce(&from_account, from, *nonce);
+                overlay.check("nonce", || format!("account nonce {}, nonce {}", from_account.nonce, nonce), nonce_check)?;
+                from_account.advance_nonce(*nonce);
+                overlay.put_account(*from, from_account);
+
+                // Assets of a collection frozen by a season cannot change
+                let collections = [overlay.asset(asset_id).and_then(|a| a.data.game_id), data.game_id.clone()];
+                for game_id in collections.iter().flatten() {
+                    let unfrozen = match overlay.frozen_by(game_id) {
+                        Some(_) => Err(frozen_collection_error(game_id)),
+                        None => Ok(()),
+                    };
+                    overlay.check("collection_frozen", || format!("collection {}", game_id), unfrozen)?;
+                }
+
+                // Calculate gas cost for this operation
+                let gas_cost = crate::assets::calculate_asset_operation_gas(
+                    &self.config,
+                    action,
+                    data,
+                    Some(&data.metadata),
+                );
+                
+                // Calculate gas fee (gas_cost * gas_price, less the owner's stake discount)
+                let full_gas_fee = gas_cost * self.config.vm.gas_price;
+                overlay.trace("gas", || format!("{:?}: {} gas at price {} = fee {}", action, gas_cost, self.config.vm.gas_price, full_gas_fee));
+                let gas_fee = match self.sponsor_asset_gas(overlay, action, asset_id, data, full_gas_fee)? {
+                    Some(gas_fee) => gas_fee,
+                    None => {
+                        let gas_fee = self.stake_discounted_gas_fee(overlay, &data.owner, full_gas_fee);
+                        
+                        // Check owner balance and deduct gas fee
+                        let mut owner_account = overlay.account(&data.owner);
+                        
+                        let balance_check = if owner_account.balance < gas_fee {
+                            Err(HazeError::InsufficientBalance { required: gas_fee, available: owner_account.balance })
+                        } else {
+                            Ok(())
+                        };
+                        overlay.check("gas_balance", || format!("owner balance {}, fee {}", owner_account.balance, gas_fee), balance_check)?;
+                        
+                        owner_account.balance -= gas_fee;
+                        overlay.put_account(data.owner, owner_account);
+                        gas_fee
+                    }
+                };
+                
+                // Process gas fee (burn 50%); crafting gas may be shared with the owner's referrer
+                let crafting = matches!(
+                    action,
+                    AssetAction::Condense | AssetAction::Evaporate | AssetAction::Merge | AssetAction::Split
+                );
+                if crafting {
+                    let game_id = overlay.asset(asset_id).and_then(|a| a.data.game_id).or_else(|| data.game_id.clone());
+                    self.process_collection_fee(overlay, &data.owner, game_id.as_deref(), ReferralFee::Crafting, gas_fee)?;
+                } else {
+                    overlay.process_gas_fee(gas_fee);
+                }
                 
                 match action {
                     crate::types::AssetAction::Create => {
                         // Check if asset already exists
-                        if self.assets.contains_key(asset_id) {
-                            return Err(HazeError::InvalidTransaction(
+                        let unused = if overlay.asset(asset_id).is_some() {
+                            Err(HazeError::InvalidTransaction(
                                 "Asset already exists".to_string()
-                            ));
-                        }
+                            ))
+                        } else {
+                            Ok(())
+                        };
+                        overlay.check("asset_unused", || format!("asset {}", hex::encode(asset_id)), unused)?;
                         
                         // Check asset count limit for owner
-                        self.check_asset_count_limit(&data.owner)?;
+                        let count_limit = self.check_asset_count_limit(overlay, &data.owner);
+                        overlay.check("asset_count_limit", || format!("owner {}", hex::encode(data.owner)), count_limit)?;
                         
                         // Validate metadata size
                         let metadata_size: usize = data.metadata.values().map(|v| v.len()).sum();
                         
                         // Check against density limit
-                        if metadata_size > data.density.max_size() {
-                            return Err(HazeError::AssetSizeExceeded(
+                        let density_limit = if metadata_size > data.density.max_size() {
+                            Err(HazeError::AssetSizeExceeded(
                                 metadata_size,
                                 data.density.max_size()
-                            ));
-                        }
+                            ))
+                        } else {
+                            Ok(())
+                        };
+                        overlay.check(
+                            "metadata_size",
+                            || format!("{} bytes, {:?} limit {}", metadata_size, data.density, data.density.max_size()),
+                            density_limit,
+                        )?;
                         
                         // Check against node quota limit
-                        self.check_metadata_size_limit(metadata_size)?;
+                        let quota = self.check_metadata_size_limit(metadata_size);
+                        overlay.check("metadata_quota", || format!("{} bytes", metadata_size), quota)?;
                         
                         // Validate metadata keys (no empty keys, reasonable length)
                         for (key, value) in &data.metadata {
@@ -932,45 +4147,55 @@ impl StateManager {
                         let mut asset_state = AssetState {
                             owner: data.owner,
                             data: data.clone(),
-                            created_at: chrono::Utc::now().timestamp(),
-                            updated_at: chrono::Utc::now().timestamp(),
+                            created_at: overlay.timestamp,
+                            updated_at: overlay.timestamp,
                             blob_refs,
                             history: Vec::new(),
                             versions: Vec::new(),
                             current_version: 0,
                             permissions: Vec::new(),
                             public_read: false,
+                            private_fields: BTreeMap::new(),
                         };
+                        let private = update_private_fields(&mut asset_state, &data.metadata);
+                        overlay.check("private_fields", || format!("{} committed", asset_state.private_fields.len()), private)?;
                         
                         // Remove special metadata keys before storing
                         asset_state.data.metadata.remove("_blob_refs");
+                        asset_state.data.metadata.remove(PAYMASTER_KEY);
+                        asset_state.data.metadata.remove(crate::assets::COMMITMENTS_KEY);
+                        
+                        // Place the asset in its starting zone
+                        self.charge_zone_migration(overlay, &data.owner, data.game_id.as_deref(), None, asset_zone(data))?;
+                        if asset_zone(data).is_none() {
+                            asset_state.data.metadata.remove(ZONE_KEY);
+                        }
                         
                         // Add creation to history
-                        Self::add_asset_history(&mut asset_state, crate::types::AssetAction::Create, HashMap::new());
+                        Self::add_asset_history(&mut asset_state, crate::types::AssetAction::Create, HashMap::new(), overlay.timestamp);
                         
                         // Create initial snapshot
-                        Self::add_asset_snapshot(&mut asset_state);
+                        Self::add_asset_snapshot(&mut asset_state, overlay.timestamp);
                         
-                        // Add to indexes
-                        self.add_asset_to_indexes(asset_id, &asset_state);
-                        
-                        self.assets.insert(*asset_id, asset_state);
+                        overlay.put_asset(*asset_id, asset_state);
                         
                         // Broadcast WebSocket event
-            self.broadcast_event(WsEvent::AssetCreated {
-                asset_id: crate::types::hash_to_hex(asset_id),
-                owner: crate::types::address_to_hex(&data.owner),
-                density: format!("{:?}", data.density),
-            });
+                        overlay.emit(WsEvent::AssetCreated {
+                            asset_id: *asset_id,
+                            owner: data.owner,
+                            density: data.density,
+                        });
                     }
                     crate::types::AssetAction::Update => {
-                        let mut asset_state = self.assets.get(asset_id)
+                        let asset_state = overlay.asset(asset_id)
                             .ok_or_else(|| HazeError::InvalidTransaction(
                                 "Asset not found".to_string()
-                            ))?
-                            .clone();
+                            ));
+                        let mut asset_state = overlay.check("asset_exists", || format!("asset {}", hex::encode(asset_id)), asset_state)?;
                         
-                        self.check_asset_write_permission(&asset_state, &data.owner)?;
+                        let changes_attributes = data.attributes != asset_state.data.attributes;
+                        let permission = check_metadata_write_permission(&asset_state, &data.owner, &data.metadata, changes_attributes, overlay.timestamp);
+                        overlay.check("write_permission", || describe_write_permission(&asset_state, &data.owner), permission)?;
                         
                         // Save owner before moving asset_state
                         let owner = asset_state.owner;
@@ -984,15 +4209,27 @@ impl StateManager {
                         
                         let total_metadata_size = current_size + new_metadata_size;
                         
-                        if total_metadata_size > asset_state.data.density.max_size() {
-                            return Err(HazeError::AssetSizeExceeded(
+                        let density_limit = if total_metadata_size > asset_state.data.density.max_size() {
+                            Err(HazeError::AssetSizeExceeded(
                                 total_metadata_size,
                                 asset_state.data.density.max_size()
-                            ));
-                        }
+                            ))
+                        } else {
+                            Ok(())
+                        };
+                        overlay.check(
+                            "metadata_size",
+                            || format!(
+                                "current {} + new {} = {} bytes, {:?} limit {}",
+                                current_size, new_metadata_size, total_metadata_size,
+                                asset_state.data.density, asset_state.data.density.max_size()
+                            ),
+                            density_limit,
+                        )?;
                         
                         // Check against node quota limit
-                        self.check_metadata_size_limit(total_metadata_size)?;
+                        let quota = self.check_metadata_size_limit(total_metadata_size);
+                        overlay.check("metadata_quota", || format!("{} bytes", total_metadata_size), quota)?;
                         
                         // Validate metadata keys
                         for (key, value) in &data.metadata {
@@ -1040,6 +4277,30 @@ impl StateManager {
                             }
                         }
                         
+                        // Migrate to another zone
+                        let mut zone_change = None;
+                        if let Some(new_zone) = data.metadata.get(ZONE_KEY) {
+                            let old_zone = asset_zone(&asset_state.data).map(str::to_string);
+                            let new_zone = Some(new_zone.as_str()).filter(|z| !z.is_empty());
+                            if old_zone.as_deref() != new_zone {
+                                self.charge_zone_migration(
+                                    overlay,
+                                    &data.owner,
+                                    asset_state.data.game_id.as_deref(),
+                                    old_zone.as_deref(),
+                                    new_zone,
+                                )?;
+                                match new_zone {
+                                    Some(zone) => asset_state.data.metadata.insert(ZONE_KEY.to_string(), zone.to_string()),
+                                    None => asset_state.data.metadata.remove(ZONE_KEY),
+                                };
+                                zone_change = Some(new_zone.unwrap_or_default().to_string());
+                            }
+                        }
+                        
+                        let private = update_private_fields(&mut asset_state, &data.metadata);
+                        overlay.check("private_fields", || format!("{} private", asset_state.private_fields.len()), private)?;
+                        
                         // Update metadata and attributes (excluding special keys)
                         for (key, value) in &data.metadata {
                             if !key.starts_with('_') {
@@ -1047,84 +4308,52 @@ impl StateManager {
                             }
                         }
                         asset_state.data.attributes = data.attributes.clone();
-                        asset_state.updated_at = chrono::Utc::now().timestamp();
-                        
-                        // Update indexes if game_id or density changed
-                        let old_game_id = asset_state.data.game_id.clone();
-                        let old_density = asset_state.data.density as u8;
+                        asset_state.updated_at = overlay.timestamp;
                         
                         // Record changes in history
-                        let changes: HashMap<String, String> = data.metadata.iter()
+                        let mut changes: HashMap<String, String> = data.metadata.iter()
                             .filter(|(k, _)| !k.starts_with('_'))
                             .map(|(k, v)| (k.clone(), v.clone()))
                             .collect();
-                        Self::add_asset_history(&mut asset_state, crate::types::AssetAction::Update, changes);
+                        if let Some(zone) = zone_change {
+                            changes.insert(ZONE_KEY.to_string(), zone);
+                        }
+                        Self::add_asset_history(&mut asset_state, crate::types::AssetAction::Update, changes, overlay.timestamp);
                         
                         let attr_names: Vec<String> = asset_state.data.attributes.iter().map(|a| a.name.clone()).collect();
                         if !attr_names.is_empty() {
-                            self.broadcast_event(WsEvent::AssetAttributeUpdated {
-                                asset_id: crate::types::hash_to_hex(asset_id),
-                                owner: crate::types::address_to_hex(&owner),
+                            overlay.emit(WsEvent::AssetAttributeUpdated {
+                                asset_id: *asset_id,
+                                owner,
                                 attributes: attr_names,
                             });
                         }
                         
-                        // Update indexes if needed
-                        let new_game_id = asset_state.data.game_id.clone();
-                        let new_density = asset_state.data.density as u8;
-                        
-                        if old_game_id != new_game_id {
-                            // Remove from old game_id index
-                            if let Some(ref old_game) = old_game_id {
-                                if let Some(mut game_assets) = self.asset_index_by_game_id.get_mut(old_game) {
-                                    game_assets.retain(|&id| id != *asset_id);
-                                }
-                            }
-                            // Add to new game_id index
-                            if let Some(ref new_game) = new_game_id {
-                                self.asset_index_by_game_id
-                                    .entry(new_game.clone())
-                                    .or_insert_with(Vec::new)
-                                    .push(*asset_id);
-                            }
-                        }
-                        
-                        if old_density != new_density {
-                            // Remove from old density index
-                            if let Some(mut density_assets) = self.asset_index_by_density.get_mut(&old_density) {
-                                density_assets.retain(|&id| id != *asset_id);
-                            }
-                            // Add to new density index
-                            self.asset_index_by_density
-                                .entry(new_density)
-                                .or_insert_with(Vec::new)
-                                .push(*asset_id);
-                        }
-                        
-                        self.assets.insert(*asset_id, asset_state);
+                        overlay.put_asset(*asset_id, asset_state);
                         
                         // Broadcast WebSocket event
-                        self.broadcast_event(WsEvent::AssetUpdated {
-                            asset_id: crate::types::hash_to_hex(asset_id),
-                            owner: crate::types::address_to_hex(&owner),
+                        overlay.emit(WsEvent::AssetUpdated {
+                            asset_id: *asset_id,
+                            owner,
                         });
                     }
                     crate::types::AssetAction::Condense => {
-                        let mut asset_state = self.assets.get(asset_id)
+                        let asset_state = overlay.asset(asset_id)
                             .ok_or_else(|| HazeError::InvalidTransaction(
                                 "Asset not found".to_string()
-                            ))?
-                            .clone();
+                            ));
+                        let mut asset_state = overlay.check("asset_exists", || format!("asset {}", hex::encode(asset_id)), asset_state)?;
                         
-                        self.check_asset_write_permission(&asset_state, &data.owner)?;
+                        let permission = check_metadata_write_permission(&asset_state, &data.owner, &data.metadata, !data.attributes.is_empty(), overlay.timestamp);
+                        overlay.check("write_permission", || describe_write_permission(&asset_state, &data.owner), permission)?;
                         
                         // Check if condensation is valid (can only increase density by one level)
                         let current_density = asset_state.data.density as u8;
                         let new_density = data.density as u8;
                         if new_density <= current_density {
                             return Err(HazeError::InvalidDensityTransition(
-                                format!("{:?}", asset_state.data.density),
-                                format!("{:?}", data.density)
+                                asset_state.data.density,
+                                data.density
                             ));
                         }
                         
@@ -1135,19 +4364,21 @@ impl StateManager {
                             crate::types::DensityLevel::Dense => crate::types::DensityLevel::Core,
                             crate::types::DensityLevel::Core => {
                                 return Err(HazeError::InvalidDensityTransition(
-                                    format!("{:?}", asset_state.data.density),
-                                    format!("{:?}", data.density)
+                                    asset_state.data.density,
+                                    data.density
                                 ));
                             }
                         };
                         
                         if data.density != expected_next {
                             return Err(HazeError::InvalidDensityTransition(
-                                format!("{:?}", asset_state.data.density),
-                                format!("{:?}", data.density)
+                                asset_state.data.density,
+                                data.density
                             ));
                         }
                         
+                        overlay.trace("density_transition", || format!("{:?} -> {:?}", asset_state.data.density, data.density));
+                        
                         // Validate new metadata size
                         let new_metadata_size: usize = data.metadata.iter()
                             .filter(|(k, _)| !k.starts_with('_'))
@@ -1165,7 +4396,6 @@ impl StateManager {
                         self.check_metadata_size_limit(new_metadata_size)?;
                         
                         // Update density and merge new data
-                        let new_density_str = format!("{:?}", data.density);
                         let old_density = asset_state.data.density;
                         asset_state.data.density = data.density;
                         
@@ -1175,11 +4405,13 @@ impl StateManager {
                                 let new_blob_refs_count = blob_refs_map.len() as u64;
                                 
                                 // Check blob files limit
-                                self.check_blob_files_limit(asset_id, new_blob_refs_count)?;
+                                self.check_blob_files_limit(overlay, asset_id, new_blob_refs_count)?;
                                 
-                                // Estimate blob storage size (conservative: 1MB per blob)
-                                let estimated_blob_size = new_blob_refs_count * 1024 * 1024;
-                                self.check_blob_storage_limit(&data.owner, estimated_blob_size)?;
+                                let new_blobs: Vec<Hash> = blob_refs_map.values()
+                                    .filter_map(|hash_hex| hex::decode(hash_hex).ok())
+                                    .filter_map(|bytes| <Hash>::try_from(bytes.as_slice()).ok())
+                                    .collect();
+                                self.check_blob_storage_limit(overlay, &data.owner, &new_blobs)?;
                                 
                                 for (key, hash_hex) in blob_refs_map {
                                     if let Ok(hash_bytes) = hex::decode(&hash_hex) {
@@ -1193,6 +4425,9 @@ impl StateManager {
                             }
                         }
                         
+                        let private = update_private_fields(&mut asset_state, &data.metadata);
+                        overlay.check("private_fields", || format!("{} private", asset_state.private_fields.len()), private)?;
+                        
                         // Update metadata (excluding special keys)
                         for (key, value) in &data.metadata {
                             if !key.starts_with('_') {
@@ -1200,7 +4435,7 @@ impl StateManager {
                             }
                         }
                         asset_state.data.attributes.extend(data.attributes.clone());
-                        asset_state.updated_at = chrono::Utc::now().timestamp();
+                        asset_state.updated_at = overlay.timestamp;
                         
                         // Record changes in history
                         let mut changes = HashMap::new();
@@ -1211,48 +4446,36 @@ impl StateManager {
                                 changes.insert(format!("metadata.{}", key), value.clone());
                             }
                         }
-                        Self::add_asset_history(&mut asset_state, crate::types::AssetAction::Condense, changes);
+                        Self::add_asset_history(&mut asset_state, crate::types::AssetAction::Condense, changes, overlay.timestamp);
                         
                         // Create snapshot for important change (condense)
-                        Self::add_asset_snapshot(&mut asset_state);
-                        
-                        // Update density index
-                        let old_density = old_density as u8;
-                        let new_density = asset_state.data.density as u8;
-                        if old_density != new_density {
-                            if let Some(mut density_assets) = self.asset_index_by_density.get_mut(&old_density) {
-                                density_assets.retain(|&id| id != *asset_id);
-                            }
-                            self.asset_index_by_density
-                                .entry(new_density)
-                                .or_insert_with(Vec::new)
-                                .push(*asset_id);
-                        }
+                        Self::add_asset_snapshot(&mut asset_state, overlay.timestamp);
                         
-                        self.assets.insert(*asset_id, asset_state);
+                        overlay.put_asset(*asset_id, asset_state);
                         
                         // Broadcast WebSocket event
-                        self.broadcast_event(WsEvent::AssetCondensed {
-                            asset_id: hex::encode(asset_id),
-                            new_density: new_density_str,
+                        overlay.emit(WsEvent::AssetCondensed {
+                            asset_id: *asset_id,
+                            new_density: data.density,
                         });
                     }
                     crate::types::AssetAction::Evaporate => {
-                        let mut asset_state = self.assets.get(asset_id)
+                        let asset_state = overlay.asset(asset_id)
                             .ok_or_else(|| HazeError::InvalidTransaction(
                                 "Asset not found".to_string()
-                            ))?
-                            .clone();
+                            ));
+                        let mut asset_state = overlay.check("asset_exists", || format!("asset {}", hex::encode(asset_id)), asset_state)?;
                         
-                        self.check_asset_write_permission(&asset_state, &data.owner)?;
+                        let permission = self.check_asset_write_permission(&asset_state, &data.owner, overlay.timestamp);
+                        overlay.check("write_permission", || describe_write_permission(&asset_state, &data.owner), permission)?;
                         
                         // Check if evaporation is valid (can only decrease density by one level)
                         let current_density = asset_state.data.density as u8;
                         let new_density = data.density as u8;
                         if new_density >= current_density {
                             return Err(HazeError::InvalidDensityTransition(
-                                format!("{:?}", asset_state.data.density),
-                                format!("{:?}", data.density)
+                                asset_state.data.density,
+                                data.density
                             ));
                         }
                         
@@ -1263,50 +4486,38 @@ impl StateManager {
                             crate::types::DensityLevel::Light => crate::types::DensityLevel::Ethereal,
                             crate::types::DensityLevel::Ethereal => {
                                 return Err(HazeError::InvalidDensityTransition(
-                                    format!("{:?}", asset_state.data.density),
-                                    format!("{:?}", data.density)
+                                    asset_state.data.density,
+                                    data.density
                                 ));
                             }
                         };
                         
                         if data.density != expected_prev {
                             return Err(HazeError::InvalidDensityTransition(
-                                format!("{:?}", asset_state.data.density),
-                                format!("{:?}", data.density)
+                                asset_state.data.density,
+                                data.density
                             ));
                         }
                         
+                        overlay.trace("density_transition", || format!("{:?} -> {:?}", asset_state.data.density, data.density));
+                        
                         // Update density
-                        let new_density_str = format!("{:?}", data.density);
                         let old_density = asset_state.data.density;
                         asset_state.data.density = data.density;
-                        asset_state.updated_at = chrono::Utc::now().timestamp();
+                        asset_state.updated_at = overlay.timestamp;
                         
                         // Record changes in history
                         let mut changes = HashMap::new();
                         changes.insert("old_density".to_string(), format!("{:?}", old_density));
                         changes.insert("new_density".to_string(), format!("{:?}", data.density));
-                        Self::add_asset_history(&mut asset_state, crate::types::AssetAction::Evaporate, changes);
-                        
-                        // Update density index
-                        let old_density = old_density as u8;
-                        let new_density = asset_state.data.density as u8;
-                        if old_density != new_density {
-                            if let Some(mut density_assets) = self.asset_index_by_density.get_mut(&old_density) {
-                                density_assets.retain(|&id| id != *asset_id);
-                            }
-                            self.asset_index_by_density
-                                .entry(new_density)
-                                .or_insert_with(Vec::new)
-                                .push(*asset_id);
-                        }
+                        Self::add_asset_history(&mut asset_state, crate::types::AssetAction::Evaporate, changes, overlay.timestamp);
                         
-                        self.assets.insert(*asset_id, asset_state);
+                        overlay.put_asset(*asset_id, asset_state);
                         
                         // Broadcast WebSocket event
-                        self.broadcast_event(WsEvent::AssetEvaporated {
-                            asset_id: hex::encode(asset_id),
-                            new_density: new_density_str,
+                        overlay.emit(WsEvent::AssetEvaporated {
+                            asset_id: *asset_id,
+                            new_density: data.density,
                         });
                     }
                     crate::types::AssetAction::Merge => {
@@ -1330,26 +4541,38 @@ impl StateManager {
                         
                         let mut other_asset_id = [0u8; 32];
                         other_asset_id.copy_from_slice(&other_asset_id_bytes);
+                        overlay.trace("other_asset_id", || other_asset_id_str.clone());
                         
                         // Get both assets
-                        let mut asset_state = self.assets.get(asset_id)
+                        let asset_state = overlay.asset(asset_id)
                             .ok_or_else(|| HazeError::InvalidTransaction(
                                 "Source asset not found".to_string()
-                            ))?
-                            .clone();
+                            ));
+                        let mut asset_state = overlay.check("source_asset", || format!("asset {}", hex::encode(asset_id)), asset_state)?;
                         
-                        let other_asset_state = self.assets.get(&other_asset_id)
+                        let other_asset_state = overlay.asset(&other_asset_id)
                             .ok_or_else(|| HazeError::InvalidTransaction(
                                 "Other asset not found".to_string()
-                            ))?
-                            .clone();
-                        
-                        if asset_state.owner != other_asset_state.owner {
-                            return Err(HazeError::AccessDenied(
-                                "Cannot merge assets with different owners".to_string()
                             ));
-                        }
-                        self.check_asset_write_permission(&asset_state, &data.owner)?;
+                        let other_asset_state = overlay.check("other_asset", || format!("asset {}", other_asset_id_str), other_asset_state)?;
+                        
+                        let same_owner = if asset_state.owner != other_asset_state.owner {
+                            Err(HazeError::AccessDenied(
+                                "Cannot merge assets with different owners".to_string()
+                            ))
+                        } else {
+                            Ok(())
+                        };
+                        overlay.check(
+                            "same_owner",
+                            || format!("source owner {}, other owner {}", hex::encode(asset_state.owner), hex::encode(other_asset_state.owner)),
+                            same_owner,
+                        )?;
+                        let committed = check_no_committed_fields(&asset_state, asset_id)
+                            .and_then(|_| check_no_committed_fields(&other_asset_state, &other_asset_id));
+                        overlay.check("no_committed_fields", || "source and other asset".to_string(), committed)?;
+                        let permission = self.check_asset_write_permission(&asset_state, &data.owner, overlay.timestamp);
+                        overlay.check("write_permission", || describe_write_permission(&asset_state, &data.owner), permission)?;
                         
                         // Validate merged size won't exceed Core density limit
                         let current_size: usize = asset_state.data.metadata.values().map(|v| v.len()).sum();
@@ -1365,49 +4588,40 @@ impl StateManager {
                             other_asset_state.data.density
                         };
                         
-                        if merged_metadata_size > max_density.max_size() {
-                            return Err(HazeError::AssetSizeExceeded(
+                        let size_limit = if merged_metadata_size > max_density.max_size() {
+                            Err(HazeError::AssetSizeExceeded(
                                 merged_metadata_size,
                                 max_density.max_size()
-                            ));
-                        }
-                        
-                        // Merge metadata (excluding special keys)
-                        for (key, value) in &other_asset_state.data.metadata {
-                            if !key.starts_with('_') && !asset_state.data.metadata.contains_key(key) {
-                                asset_state.data.metadata.insert(key.clone(), value.clone());
-                            }
-                        }
+                            ))
+                        } else {
+                            Ok(())
+                        };
+                        overlay.check(
+                            "merged_size",
+                            || format!(
+                                "source {} + other {} = {} bytes, {:?} limit {}",
+                                current_size, other_size, merged_metadata_size, max_density, max_density.max_size()
+                            ),
+                            size_limit,
+                        )?;
                         
-                        // Merge attributes with conflict resolution
-                        // If attribute with same name exists, keep the one with higher rarity
-                        // If both have same rarity or both are None, keep the source asset's attribute
-                        for other_attr in &other_asset_state.data.attributes {
-                            if let Some(existing) = asset_state.data.attributes.iter_mut().find(|a| a.name == other_attr.name) {
-                                // Conflict: attribute with same name exists
-                                // Resolve by comparing rarity (higher rarity wins)
-                                let should_replace = match (existing.rarity, other_attr.rarity) {
-                                    (Some(existing_rarity), Some(other_rarity)) => other_rarity > existing_rarity,
-                                    (None, Some(_)) => true, // Other has rarity, existing doesn't
-                                    (Some(_), None) => false, // Existing has rarity, other doesn't
-                                    (None, None) => false, // Both have no rarity, keep existing
-                                };
-                                
-                                if should_replace {
-                                    existing.value = other_attr.value.clone();
-                                    existing.rarity = other_attr.rarity;
-                                }
-                            } else {
-                                // No conflict, add the attribute
-                                asset_state.data.attributes.push(other_attr.clone());
-                            }
-                        }
+                        // Merge metadata (excluding special keys), attributes and blob_refs;
+                        // conflicts are resolved by the requested policy
+                        let policy = crate::assets::MergePolicy::from_metadata(&data.metadata);
+                        let policy = overlay.check("merge_policy", || format!("{:?}", data.metadata.get(crate::assets::MERGE_POLICY_KEY)), policy)?;
+                        let merged = crate::assets::merge_asset_contents(
+                            &mut asset_state.data,
+                            &mut asset_state.blob_refs,
+                            &other_asset_state.data,
+                            &other_asset_state.blob_refs,
+                            policy,
+                        );
+                        let conflicts = overlay.check("merge_conflicts", || format!("policy {}", policy.as_str()), merged)?;
                         
-                        // Merge blob_refs
-                        for (key, hash) in &other_asset_state.blob_refs {
-                            if !asset_state.blob_refs.contains_key(key) {
-                                asset_state.blob_refs.insert(key.clone(), *hash);
-                            }
+                        // Concatenated values add separators
+                        let merged_size: usize = asset_state.data.metadata.values().map(|v| v.len()).sum();
+                        if merged_size > max_density.max_size() {
+                            return Err(HazeError::AssetSizeExceeded(merged_size, max_density.max_size()));
                         }
                         
                         // Increase density if needed
@@ -1415,616 +4629,5047 @@ impl StateManager {
                             asset_state.data.density = other_asset_state.data.density;
                         }
                         
-                        asset_state.updated_at = chrono::Utc::now().timestamp();
+                        asset_state.updated_at = overlay.timestamp;
+                        overlay.trace("merge", || format!(
+                            "{} metadata keys, {} attributes, {} blob refs, density {:?}",
+                            asset_state.data.metadata.len(), asset_state.data.attributes.len(),
+                            asset_state.blob_refs.len(), asset_state.data.density
+                        ));
                         
                         // Record changes in history
                         let mut changes = HashMap::new();
                         changes.insert("merged_asset_id".to_string(), hex::encode(other_asset_id));
-                        Self::add_asset_history(&mut asset_state, crate::types::AssetAction::Merge, changes);
+                        changes.insert("merge_policy".to_string(), policy.as_str().to_string());
+                        if !conflicts.is_empty() {
+                            changes.insert("conflicts".to_string(), conflicts.join(","));
+                        }
+                        Self::add_asset_history(&mut asset_state, crate::types::AssetAction::Merge, changes, overlay.timestamp);
                         
                         // Create snapshot for important change (merge)
-                        Self::add_asset_snapshot(&mut asset_state);
+                        Self::add_asset_snapshot(&mut asset_state, overlay.timestamp);
+                        
+                        // Update source asset and remove the merged one
+                        overlay.put_asset(*asset_id, asset_state);
+                        overlay.remove_asset(other_asset_id);
+                        overlay.link_assets(other_asset_id, *asset_id, crate::types::AssetAction::Merge);
+                        
+                        // Broadcast WebSocket event
+                        overlay.emit(WsEvent::AssetMerged {
+                            asset_id: *asset_id,
+                            merged_asset_id: other_asset_id,
+                        });
+                    }
+                    crate::types::AssetAction::Split => {
+                        // Split creates new assets from components
+                        // Get source asset
+                        let source_asset_state = overlay.asset(asset_id)
+                            .ok_or_else(|| HazeError::InvalidTransaction(
+                                "Source asset not found".to_string()
+                            ));
+                        let source_asset_state = overlay.check("source_asset", || format!("asset {}", hex::encode(asset_id)), source_asset_state)?;
                         
-                        // Update source asset
-                        self.assets.insert(*asset_id, asset_state.clone());
+                        let permission = self.check_asset_write_permission(&source_asset_state, &data.owner, overlay.timestamp);
+                        overlay.check("write_permission", || describe_write_permission(&source_asset_state, &data.owner), permission)?;
+                        let committed = check_no_committed_fields(&source_asset_state, asset_id);
+                        overlay.check("no_committed_fields", || format!("asset {}", hex::encode(asset_id)), committed)?;
+                        
+                        // An explicit manifest (special key "_component_manifest") says which metadata
+                        // keys and attributes each component gets
+                        let manifest = match data.metadata.get(crate::assets::SPLIT_MANIFEST_KEY) {
+                            Some(json) => {
+                                let parsed = crate::assets::parse_split_manifest(json, &source_asset_state.data);
+                                Some(overlay.check("component_manifest", || format!("{} bytes", json.len()), parsed)?)
+                            }
+                            None if self.config.asset_limits.split_heuristic_fallback => None,
+                            None => {
+                                return Err(HazeError::InvalidTransaction(format!(
+                                    "Split operation requires '{}' in metadata", crate::assets::SPLIT_MANIFEST_KEY
+                                )));
+                            }
+                        };
                         
-                        // Update indexes for merged asset (density might have changed)
-                        let old_density = asset_state.data.density as u8;
-                        let new_density = asset_state.data.density as u8;
-                        if old_density != new_density {
-                            if let Some(mut density_assets) = self.asset_index_by_density.get_mut(&old_density) {
-                                density_assets.retain(|&id| id != *asset_id);
+                        // Without a manifest: components list from metadata (special key "_components")
+                        let components: Vec<String> = match &manifest {
+                            Some(manifest) => manifest.iter().map(|component| component.name.clone()).collect(),
+                            None => {
+                                let components_str = data.metadata.get("_components")
+                                    .ok_or_else(|| HazeError::InvalidTransaction(
+                                        "Split operation requires '_components' in metadata".to_string()
+                                    ))?;
+                                
+                                // Parse components (comma-separated list)
+                                let components: Vec<String> = components_str
+                                    .split(',')
+                                    .map(|s| s.trim().to_string())
+                                    .filter(|s| !s.is_empty())
+                                    .collect();
+                                
+                                let has_components = if components.is_empty() {
+                                    Err(HazeError::InvalidTransaction(
+                                        "Split requires at least one component".to_string()
+                                    ))
+                                } else {
+                                    Ok(())
+                                };
+                                overlay.check("components", || format!("'{}'", components_str), has_components)?;
+                                components
                             }
-                            self.asset_index_by_density
-                                .entry(new_density)
-                                .or_insert_with(Vec::new)
-                                .push(*asset_id);
+                        };
+                        
+                        // Validate component count (reasonable limit)
+                        let component_count = if components.len() > crate::assets::MAX_SPLIT_COMPONENTS {
+                            Err(HazeError::InvalidTransaction(
+                                format!("Split operation cannot create more than {} components", crate::assets::MAX_SPLIT_COMPONENTS)
+                            ))
+                        } else {
+                            Ok(())
+                        };
+                        overlay.check("component_count", || format!("{} component(s), limit {}", components.len(), crate::assets::MAX_SPLIT_COMPONENTS), component_count)?;
+                        
+                        // Create new assets for each component
+                        let mut created_asset_ids = Vec::new();
+                        
+                        for (component_index, component_name) in components.iter().enumerate() {
+                            let component_data = match &manifest {
+                                Some(manifest) => {
+                                    let component_data = manifest[component_index].component_data(&source_asset_state.data);
+                                    let size: usize = component_data.metadata.values().map(|v| v.len()).sum();
+                                    self.check_metadata_size_limit(size)?;
+                                    component_data
+                                }
+                                None => Self::heuristic_split_component(&source_asset_state.data, component_name),
+                            };
+                            
+                            // Generate component asset ID
+                            let component_asset_id = crate::types::sha256(&[
+                                asset_id.as_ref(),
+                                component_name.as_bytes(),
+                            ].concat());
+                            
+                            // Create component asset state
+                            let mut component_asset_state = AssetState {
+                                owner: source_asset_state.owner,
+                                data: component_data,
+                                created_at: overlay.timestamp,
+                                updated_at: overlay.timestamp,
+                                blob_refs: HashMap::new(), // Components start with empty blob_refs
+                                history: Vec::new(),
+                                versions: Vec::new(),
+                                current_version: 0,
+                                permissions: Vec::new(),
+                                public_read: false,
+                                private_fields: BTreeMap::new(),
+                            };
+                            
+                            // Add creation to history
+                            let mut changes = HashMap::new();
+                            changes.insert("source_asset_id".to_string(), hex::encode(asset_id));
+                            changes.insert("component_name".to_string(), component_name.clone());
+                            Self::add_asset_history(&mut component_asset_state, crate::types::AssetAction::Split, changes, overlay.timestamp);
+                            
+                            // Create initial snapshot for component
+                            Self::add_asset_snapshot(&mut component_asset_state, overlay.timestamp);
+                            
+                            overlay.put_asset(component_asset_id, component_asset_state);
+                            overlay.link_assets(*asset_id, component_asset_id, crate::types::AssetAction::Split);
+                            created_asset_ids.push(component_asset_id);
+                        }
+                        
+                        overlay.trace("split", || format!("created {} asset(s)", created_asset_ids.len()));
+                        
+                        // Source asset is consumed by the split
+                        overlay.remove_asset(*asset_id);
+                        
+                        // Broadcast WebSocket event
+                        overlay.emit(WsEvent::AssetSplit {
+                            asset_id: *asset_id,
+                            created_assets: created_asset_ids,
+                        });
+                    }
+                    crate::types::AssetAction::Breed => {
+                        return Err(HazeError::InvalidTransaction(
+                            "Assets are bred with the Breed transaction".to_string()
+                        ));
+                    }
+                    crate::types::AssetAction::Upgrade => {
+                        return Err(HazeError::InvalidTransaction(
+                            "Assets are upgraded with the Upgrade transaction".to_string()
+                        ));
+                    }
+                    crate::types::AssetAction::Reveal => {
+                        let asset_state = overlay.asset(asset_id)
+                            .ok_or_else(|| HazeError::InvalidTransaction(
+                                "Asset not found".to_string()
+                            ));
+                        let mut asset_state = overlay.check("asset_exists", || format!("asset {}", hex::encode(asset_id)), asset_state)?;
+                        
+                        let permission = self.check_asset_write_permission(&asset_state, &data.owner, overlay.timestamp);
+                        overlay.check("write_permission", || describe_write_permission(&asset_state, &data.owner), permission)?;
+                        
+                        // Each plaintext must hash, with its salt, to the field's commitment
+                        let salts = crate::assets::reveal_salts(&data.metadata)?;
+                        let height = self.current_height() + 1;
+                        let mut changes = HashMap::new();
+                        for (field, salt) in &salts {
+                            let plaintext = &data.metadata[field];
+                            let matches = match asset_state.private_fields.get_mut(field) {
+                                Some(private) if private.revealed_at.is_some() => Err(HazeError::InvalidTransaction(
+                                    format!("Field '{}' is already revealed", field)
+                                )),
+                                Some(private) if crate::assets::metadata_commitment(salt, plaintext) == private.commitment => {
+                                    private.revealed_at = Some(height);
+                                    Ok(())
+                                }
+                                Some(_) => Err(HazeError::InvalidTransaction(
+                                    format!("Plaintext of '{}' does not match its commitment", field)
+                                )),
+                                None => Err(HazeError::InvalidTransaction(
+                                    format!("Field '{}' is not committed", field)
+                                )),
+                            };
+                            overlay.check("commitment", || format!("field {}", field), matches)?;
+                            asset_state.data.metadata.insert(field.clone(), plaintext.clone());
+                            changes.insert(field.clone(), plaintext.clone());
                         }
                         
-                        // Remove merged asset from indexes and state
-                        if let Some(other_state) = self.assets.get(&other_asset_id) {
-                            self.remove_asset_from_indexes(&other_asset_id, &other_state);
+                        // Plaintext replaces the ciphertext, which may have been shorter
+                        let metadata_size: usize = asset_state.data.metadata.values().map(|v| v.len()).sum();
+                        if metadata_size > asset_state.data.density.max_size() {
+                            return Err(HazeError::AssetSizeExceeded(metadata_size, asset_state.data.density.max_size()));
                         }
-                        self.assets.remove(&other_asset_id);
+                        self.check_metadata_size_limit(metadata_size)?;
+                        
+                        let owner = asset_state.owner;
+                        asset_state.updated_at = overlay.timestamp;
+                        Self::add_asset_history(&mut asset_state, crate::types::AssetAction::Reveal, changes, overlay.timestamp);
+                        overlay.put_asset(*asset_id, asset_state);
                         
                         // Broadcast WebSocket event
-                        self.broadcast_event(WsEvent::AssetMerged {
-                            asset_id: hex::encode(asset_id),
-                            merged_asset_id: hex::encode(other_asset_id),
+                        overlay.emit(WsEvent::AssetUpdated {
+                            asset_id: *asset_id,
+                            owner,
                         });
                     }
-                    crate::types::AssetAction::Split => {
-                        // Split creates new assets from components
-                        // Get components list from metadata (special key "_components")
-                        let components_str = data.metadata.get("_components")
-                            .ok_or_else(|| HazeError::InvalidTransaction(
-                                "Split operation requires '_components' in metadata".to_string()
-                            ))?;
-                        
-                        // Parse components (comma-separated list)
-                        let components: Vec<String> = components_str
-                            .split(',')
-                            .map(|s| s.trim().to_string())
-                            .filter(|s| !s.is_empty())
-                            .collect();
-                        
-                        if components.is_empty() {
-                            return Err(HazeError::InvalidTransaction(
-                                "Split requires at least one component".to_string()
-                            ));
-                        }
-                        
-                        // Get source asset
-                        let source_asset_state = self.assets.get(asset_id)
-                            .ok_or_else(|| HazeError::InvalidTransaction(
-                                "Source asset not found".to_string()
-                            ))?
-                            .clone();
-                        
-                        self.check_asset_write_permission(&source_asset_state, &data.owner)?;
-                        
-                        // Validate component count (reasonable limit)
-                        if components.len() > 100 {
-                            return Err(HazeError::InvalidTransaction(
-                                "Split operation cannot create more than 100 components".to_string()
-                            ));
-                        }
-                        
-                        // Create new assets for each component
-                        let mut created_asset_ids = Vec::new();
-                        
-                        for component_name in &components {
-                            let mut component_data = crate::types::AssetData {
-                                density: crate::types::DensityLevel::Ethereal, // Start with minimum density
-                                metadata: std::collections::HashMap::new(),
-                                attributes: vec![],
-                                game_id: source_asset_state.data.game_id.clone(),
-                                owner: source_asset_state.owner,
-                            };
-                            
-                            // Extract component-specific metadata
-                            if let Some(value) = source_asset_state.data.metadata.get(component_name) {
-                                component_data.metadata.insert(component_name.clone(), value.clone());
-                            }
-                            
-                            // Distribute attributes to components
-                            // Attributes with names matching component pattern go to that component
-                            // Other attributes are copied to all components (shared attributes)
-                            for attr in &source_asset_state.data.attributes {
-                                // If attribute name contains component name, assign to this component
-                                if attr.name.contains(component_name) || attr.name == *component_name {
-                                    component_data.attributes.push(attr.clone());
-                                } else if attr.name.starts_with("shared_") || attr.name == "rarity" || attr.name == "power" {
-                                    // Shared attributes (like rarity, power) go to all components
-                                    component_data.attributes.push(attr.clone());
-                                }
-                                // Otherwise, attribute is not assigned to this component
-                            }
-                            
-                            // If no component-specific attributes were found, copy all attributes
-                            // This ensures components have at least some attributes
-                            if component_data.attributes.is_empty() {
-                                component_data.attributes = source_asset_state.data.attributes.clone();
-                            }
-                            
-                            // Generate component asset ID
-                            let component_asset_id = crate::types::sha256(&[
-                                asset_id.as_ref(),
-                                component_name.as_bytes(),
-                            ].concat());
-                            
-                            // Create component asset state
-                            let mut component_asset_state = AssetState {
-                                owner: source_asset_state.owner,
-                                data: component_data,
-                                created_at: chrono::Utc::now().timestamp(),
-                                updated_at: chrono::Utc::now().timestamp(),
-                                blob_refs: HashMap::new(), // Components start with empty blob_refs
-                                history: Vec::new(),
-                                versions: Vec::new(),
-                                current_version: 0,
-                                permissions: Vec::new(),
-                                public_read: false,
-                            };
-                            
-                            // Add creation to history
-                            let mut changes = HashMap::new();
-                            changes.insert("source_asset_id".to_string(), hex::encode(asset_id));
-                            changes.insert("component_name".to_string(), component_name.clone());
-                            Self::add_asset_history(&mut component_asset_state, crate::types::AssetAction::Split, changes);
-                            
-                            // Create initial snapshot for component
-                            Self::add_asset_snapshot(&mut component_asset_state);
-                            
-                            // Add component to indexes
-                            self.add_asset_to_indexes(&component_asset_id, &component_asset_state);
-                            
-                            self.assets.insert(component_asset_id, component_asset_state);
-                            created_asset_ids.push(hex::encode(component_asset_id));
-                        }
-                        
-                        // Record split in source asset history before removing
-                        if let Some(mut source_state) = self.assets.get_mut(asset_id) {
-                            let mut changes = HashMap::new();
-                            changes.insert("components".to_string(), components_str.clone());
-                            changes.insert("created_assets".to_string(), created_asset_ids.join(","));
-                            Self::add_asset_history(&mut source_state, crate::types::AssetAction::Split, changes);
-                        }
-                        
-                        // Remove source asset from indexes and state
-                        if let Some(source_state) = self.assets.get(asset_id) {
-                            self.remove_asset_from_indexes(asset_id, &source_state);
-                        }
-                        self.assets.remove(asset_id);
-                        
-                        // Broadcast WebSocket event
-                        self.broadcast_event(WsEvent::AssetSplit {
-                            asset_id: hex::encode(asset_id),
-                            created_assets: created_asset_ids,
-                        });
+                }
+
+                if matches!(action, AssetAction::Create | AssetAction::Update | AssetAction::Merge | AssetAction::Reveal) {
+                    let hook = self.run_asset_hook(overlay, &data.owner, asset_id, action);
+                    overlay.check("asset_hook", || format!("collection {:?}", data.game_id), hook)?;
+                }
+            }
+            Transaction::Stake { from: validator, amount, nonce, .. } => {
+                let mut account = overlay.account(validator);
+                self.check_nonce(&account, validator, *nonce)?;
+                
+                if account.balance < *amount {
+                    return Err(HazeError::InsufficientBalance { required: *amount, available: account.balance });
+                }
+
+                account.balance -= amount;
+                account.staked += amount;
+                account.advance_nonce(*nonce);
+                overlay.put_account(*validator, account);
+                
+                // Register stake in tokenomics (validated now, recorded on commit)
+                self.tokenomics.check_stake(validator, validator, *amount)?;
+                overlay.stake(*validator, *amount);
+            }
+            Transaction::Unjail { from: validator, fee, nonce, .. } => {
+                let mut account = overlay.account(validator);
+                self.check_nonce(&account, validator, *nonce)?;
+                if account.balance < *fee {
+                    return Err(HazeError::InsufficientBalance { required: *fee, available: account.balance });
+                }
+                if overlay.unjails.contains(validator) {
+                    return Err(HazeError::InvalidTransaction("Validator already unjailed in this block".to_string()));
+                }
+                // The jail term is measured against the block's timestamp, so every node agrees
+                self.tokenomics.check_unjail(validator, overlay.timestamp)?;
+
+                account.balance -= fee;
+                account.advance_nonce(*nonce);
+                overlay.put_account(*validator, account);
+                overlay.process_gas_fee(*fee);
+                overlay.unjail(*validator);
+            }
+            Transaction::SetAssetHook { from, game_id, hook, fee, nonce, .. } => {
+                let mut from_account = overlay.account(from);
+                self.check_nonce(&from_account, from, *nonce)?;
+                if from_account.balance < *fee {
+                    return Err(HazeError::InsufficientBalance { required: *fee, available: from_account.balance });
+                }
+                if overlay.asset_hook(game_id).is_some_and(|existing| existing.registrant != *from) {
+                    return Err(HazeError::AccessDenied(
+                        format!("Asset hook for '{}' is registered by another account", game_id)
+                    ));
+                }
+                if let Some(hook) = hook {
+                    if hook.gas_limit == 0 || hook.gas_limit > self.config.vm.gas_limit {
+                        return Err(HazeError::InvalidTransaction(
+                            format!("Hook gas limit must be between 1 and {}", self.config.vm.gas_limit)
+                        ));
+                    }
+                    if overlay.contract_info(&hook.contract).is_none() {
+                        return Err(HazeError::VM("Hook contract not found".to_string()));
+                    }
+                }
+
+                from_account.balance -= fee;
+                from_account.advance_nonce(*nonce);
+                overlay.put_account(*from, from_account);
+                overlay.process_gas_fee(*fee);
+                overlay.put_asset_hook(
+                    game_id.clone(),
+                    hook.clone().map(|hook| RegisteredAssetHook { registrant: *from, hook }),
+                );
+            }
+            Transaction::CreateAirdrop { from, claims, fee, nonce, .. } => {
+                let mut from_account = overlay.account(from);
+                self.check_nonce(&from_account, from, *nonce)?;
+                let total = claims
+                    .iter()
+                    .try_fold(*fee, |total, claim| total.checked_add(claim.amount))
+                    .ok_or_else(|| HazeError::InvalidTransaction("Airdrop total overflows".to_string()))?;
+                if from_account.balance < total {
+                    return Err(HazeError::InsufficientBalance { required: total, available: from_account.balance });
+                }
+
+                from_account.balance -= total;
+                from_account.advance_nonce(*nonce);
+                overlay.put_account(*from, from_account);
+                overlay.process_gas_fee(*fee);
+
+                // Read each recipient after the debit so a claim to `from` itself is credited correctly
+                for claim in claims {
+                    let mut account = overlay.account(&claim.address);
+                    account.balance = account.balance.checked_add(claim.amount)
+                        .ok_or_else(|| HazeError::InvalidTransaction("Recipient balance overflows".to_string()))?;
+                    overlay.put_account(claim.address, account);
+                }
+            }
+            Transaction::CreatePaymentRequest { from, payer, amount, memo, expires_at_height, fee, nonce, .. } => {
+                let mut from_account = overlay.account(from);
+                self.check_nonce(&from_account, from, *nonce)?;
+                if from_account.balance < *fee {
+                    return Err(HazeError::InsufficientBalance { required: *fee, available: from_account.balance });
+                }
+                let height = self.current_height() + 1;
+                if *expires_at_height < height {
+                    return Err(HazeError::InvalidTransaction("Payment request already expired".to_string()));
+                }
+
+                from_account.balance -= fee;
+                from_account.advance_nonce(*nonce);
+                overlay.put_account(*from, from_account);
+                overlay.process_gas_fee(*fee);
+                overlay.put_payment_request(PaymentRequest {
+                    id: crate::types::payment_request_id(from, *nonce),
+                    requester: *from,
+                    payer: *payer,
+                    amount: *amount,
+                    memo: memo.clone(),
+                    created_at_height: height,
+                    expires_at_height: *expires_at_height,
+                    settlement: None,
+                });
+            }
+            Transaction::PayPaymentRequest { from, request_id, amount, fee, nonce, .. } => {
+                let mut from_account = overlay.account(from);
+                self.check_nonce(&from_account, from, *nonce)?;
+                let mut request = overlay.payment_request(request_id)
+                    .ok_or_else(|| HazeError::InvalidTransaction("Payment request not found".to_string()))?;
+                let height = self.current_height() + 1;
+                request.check_payable(from, *amount, height)?;
+                let required = amount.checked_add(*fee)
+                    .ok_or_else(|| HazeError::InvalidTransaction("Payment total overflows".to_string()))?;
+                if from_account.balance < required {
+                    return Err(HazeError::InsufficientBalance { required, available: from_account.balance });
+                }
+
+                from_account.balance -= required;
+                from_account.advance_nonce(*nonce);
+                overlay.put_account(*from, from_account);
+                overlay.process_gas_fee(*fee);
+
+                // Read the requester after the debit (they may pay their own open request)
+                let mut requester = overlay.account(&request.requester);
+                requester.balance = requester.balance.checked_add(*amount)
+                    .ok_or_else(|| HazeError::InvalidTransaction("Requester balance overflows".to_string()))?;
+                overlay.put_account(request.requester, requester);
+
+                request.settlement = Some(PaymentSettlement { payer: *from, height });
+                overlay.emit(WsEvent::PaymentRequestSettled {
+                    request_id: *request_id,
+                    requester: request.requester,
+                    payer: *from,
+                    amount: *amount,
+                });
+                overlay.put_payment_request(request);
+            }
+            Transaction::PlaceOrder { from, game_id, side, price, quantity, assets, fee, nonce, .. } => {
+                let mut from_account = overlay.account(from);
+                self.check_nonce(&from_account, from, *nonce)?;
+                let escrowed = match side {
+                    OrderSide::Buy => price.checked_mul(*quantity)
+                        .ok_or_else(|| HazeError::InvalidTransaction("Order total overflows".to_string()))?,
+                    OrderSide::Sell => 0,
+                };
+                let required = escrowed.checked_add(*fee)
+                    .ok_or_else(|| HazeError::InvalidTransaction("Order total overflows".to_string()))?;
+                if from_account.balance < required {
+                    return Err(HazeError::InsufficientBalance { required, available: from_account.balance });
+                }
+                let order_id = crate::types::order_id(from, *nonce);
+                if overlay.order(&order_id).is_some() {
+                    return Err(HazeError::InvalidTransaction("Order already exists".to_string()));
+                }
+                if overlay.frozen_by(game_id).is_some() {
+                    return Err(frozen_collection_error(game_id));
+                }
+                let limits = &self.config.economy.order_book;
+                let placed = overlay.block_orders.get(from).map_or(0, Vec::len);
+                let under_limit = if limits.max_orders_per_block > 0 && placed >= limits.max_orders_per_block as usize {
+                    Err(HazeError::InvalidTransaction(format!(
+                        "At most {} orders per account per block", limits.max_orders_per_block
+                    )))
+                } else {
+                    Ok(())
+                };
+                overlay.check("orders_per_block", || format!("{} placed in this block", placed), under_limit)?;
+
+                from_account.balance -= required;
+                from_account.advance_nonce(*nonce);
+                overlay.put_account(*from, from_account);
+                overlay.process_gas_fee(*fee);
+
+                // Escrow what the order offers
+                let escrow = order_escrow_address();
+                if escrowed > 0 {
+                    let mut escrow_account = overlay.account(&escrow);
+                    escrow_account.balance += escrowed;
+                    overlay.put_account(escrow, escrow_account);
+                }
+                for asset_id in assets {
+                    let asset_state = overlay.asset(asset_id)
+                        .ok_or_else(|| HazeError::InvalidTransaction("Asset not found".to_string()))?;
+                    if asset_state.owner != *from {
+                        return Err(HazeError::AccessDenied("Only asset owner can sell the asset".to_string()));
+                    }
+                    if asset_state.data.game_id.as_deref() != Some(game_id.as_str()) {
+                        return Err(HazeError::InvalidTransaction(
+                            format!("Asset does not belong to collection '{}'", game_id)
+                        ));
+                    }
+                    let relist = if limits.deny_relist_in_block && overlay.block_acquired.contains(&(*from, *asset_id)) {
+                        Err(HazeError::InvalidTransaction(
+                            "Cannot re-list an asset bought in the same block".to_string()
+                        ))
+                    } else {
+                        Ok(())
+                    };
+                    overlay.check("order_relist", || format!("asset {}", crate::types::hash_to_hex(asset_id)), relist)?;
+                    Self::move_escrowed_asset(overlay, asset_id, escrow)?;
+                }
+
+                let height = self.current_height() + 1;
+                let mut order = Order {
+                    id: order_id,
+                    owner: *from,
+                    game_id: game_id.clone(),
+                    side: *side,
+                    price: *price,
+                    quantity: *quantity,
+                    remaining: *quantity,
+                    assets: assets.clone(),
+                    placed_at_height: height,
+                    sequence: overlay.next_order_sequence(),
+                    status: OrderStatus::Open,
+                };
+                let mut book = overlay.open_orders(game_id);
+                let fills = match_order(&mut order, &mut book);
+
+                for fill in &fills {
+                    let maker = book.iter().find(|maker| maker.id == fill.maker)
+                        .ok_or_else(|| HazeError::State("Matched order missing from book".to_string()))?;
+                    let (buy, sell) = match side {
+                        OrderSide::Buy => (&order, maker),
+                        OrderSide::Sell => (maker, &order),
+                    };
+                    Self::move_escrowed_asset(overlay, &fill.asset_id, buy.owner)?;
+                    overlay.block_acquired.insert((buy.owner, fill.asset_id));
+                    // The seller pays the marketplace fee out of the proceeds
+                    let trade_fee = (fill.price as u128 * self.config.economy.trade_fee_bps as u128 / 10_000) as u64;
+                    Self::release_escrow(overlay, sell.owner, fill.price - trade_fee)?;
+                    if trade_fee > 0 {
+                        let escrow = order_escrow_address();
+                        let mut escrow_account = overlay.account(&escrow);
+                        escrow_account.balance = escrow_account.balance.checked_sub(trade_fee)
+                            .ok_or_else(|| HazeError::State("Order escrow underfunded".to_string()))?;
+                        overlay.put_account(escrow, escrow_account);
+                        self.process_collection_fee(overlay, &sell.owner, Some(game_id), ReferralFee::Marketplace, trade_fee)?;
+                    }
+                    // A buyer is only charged the execution price; return the rest of its limit
+                    Self::release_escrow(overlay, buy.owner, buy.price - fill.price)?;
+                    overlay.trades.push(Trade {
+                        game_id: game_id.clone(),
+                        asset_id: fill.asset_id,
+                        price: fill.price,
+                        fee: trade_fee,
+                        buyer: buy.owner,
+                        seller: sell.owner,
+                        buy_order: buy.id,
+                        sell_order: sell.id,
+                        height,
+                    });
+                }
+                for maker in book {
+                    if fills.iter().any(|fill| fill.maker == maker.id) {
+                        overlay.put_order(maker);
+                    }
+                }
+                overlay.block_orders.entry(*from).or_default().push(order_id);
+                overlay.put_order(order);
+            }
+            Transaction::CancelOrder { from, order_id, fee, nonce, .. } => {
+                let mut from_account = overlay.account(from);
+                self.check_nonce(&from_account, from, *nonce)?;
+                if from_account.balance < *fee {
+                    return Err(HazeError::InsufficientBalance { required: *fee, available: from_account.balance });
+                }
+                let mut order = overlay.order(order_id)
+                    .ok_or_else(|| HazeError::InvalidTransaction("Order not found".to_string()))?;
+                if order.owner != *from {
+                    return Err(HazeError::AccessDenied("Only the order owner can cancel it".to_string()));
+                }
+                if order.status != OrderStatus::Open {
+                    return Err(HazeError::InvalidTransaction("Order is not open".to_string()));
+                }
+                let placed_in_block = overlay.block_orders.get(from).is_some_and(|ids| ids.contains(order_id));
+                let cancellable = if self.config.economy.order_book.deny_cancel_in_block && placed_in_block {
+                    Err(HazeError::InvalidTransaction(
+                        "Cannot cancel an order in the block it was placed in".to_string()
+                    ))
+                } else {
+                    Ok(())
+                };
+                overlay.check("order_cancel", || format!("order {}", crate::types::hash_to_hex(order_id)), cancellable)?;
+
+                from_account.balance -= fee;
+                from_account.advance_nonce(*nonce);
+                overlay.put_account(*from, from_account);
+                overlay.process_gas_fee(*fee);
+
+                match order.side {
+                    OrderSide::Buy => {
+                        let refund = order.price.checked_mul(order.remaining)
+                            .ok_or_else(|| HazeError::State("Order total overflows".to_string()))?;
+                        Self::release_escrow(overlay, *from, refund)?;
+                    }
+                    OrderSide::Sell => {
+                        for asset_id in std::mem::take(&mut order.assets) {
+                            Self::move_escrowed_asset(overlay, &asset_id, *from)?;
+                        }
+                    }
+                }
+                order.status = OrderStatus::Cancelled;
+                overlay.put_order(order);
+            }
+            Transaction::CreateSubscription { from, payee, amount, interval_blocks, end_height, fee, nonce, .. } => {
+                let mut from_account = overlay.account(from);
+                self.check_nonce(&from_account, from, *nonce)?;
+                let required = amount.checked_add(*fee)
+                    .ok_or_else(|| HazeError::InvalidTransaction("Subscription total overflows".to_string()))?;
+                if from_account.balance < required {
+                    return Err(HazeError::InsufficientBalance { required, available: from_account.balance });
+                }
+                let height = self.current_height() + 1;
+                if end_height.is_some_and(|end| end < height) {
+                    return Err(HazeError::InvalidTransaction("Subscription already ended".to_string()));
+                }
+
+                from_account.balance -= required;
+                from_account.advance_nonce(*nonce);
+                overlay.put_account(*from, from_account);
+                overlay.process_gas_fee(*fee);
+                let mut payee_account = overlay.account(payee);
+                payee_account.balance = payee_account.balance.checked_add(*amount)
+                    .ok_or_else(|| HazeError::InvalidTransaction("Payee balance overflows".to_string()))?;
+                overlay.put_account(*payee, payee_account);
+
+                // The first period is paid now; the next one is due `interval_blocks` later
+                let mut subscription = Subscription {
+                    id: crate::types::subscription_id(from, *nonce),
+                    payer: *from,
+                    payee: *payee,
+                    amount: *amount,
+                    interval_blocks: *interval_blocks,
+                    end_height: *end_height,
+                    created_at_height: height,
+                    next_charge_height: height,
+                    past_due_since: None,
+                    charges: 0,
+                    total_paid: 0,
+                    status: SubscriptionStatus::Active,
+                    cancelled_by: None,
+                };
+                Self::schedule_next_period(&mut subscription, height);
+                overlay.subscriptions.insert(subscription.id, subscription);
+            }
+            Transaction::CancelSubscription { from, subscription_id, fee, nonce, .. } => {
+                let mut from_account = overlay.account(from);
+                self.check_nonce(&from_account, from, *nonce)?;
+                if from_account.balance < *fee {
+                    return Err(HazeError::InsufficientBalance { required: *fee, available: from_account.balance });
+                }
+                let mut subscription = overlay.subscription(subscription_id)
+                    .ok_or_else(|| HazeError::InvalidTransaction("Subscription not found".to_string()))?;
+                if subscription.payer != *from && subscription.payee != *from {
+                    return Err(HazeError::AccessDenied(
+                        "Only the payer or the payee can cancel a subscription".to_string()
+                    ));
+                }
+                if !subscription.is_live() {
+                    return Err(HazeError::InvalidTransaction("Subscription is not active".to_string()));
+                }
+
+                from_account.balance -= fee;
+                from_account.advance_nonce(*nonce);
+                overlay.put_account(*from, from_account);
+                overlay.process_gas_fee(*fee);
+                subscription.status = SubscriptionStatus::Cancelled;
+                subscription.cancelled_by = Some(*from);
+                overlay.subscriptions.insert(subscription.id, subscription);
+            }
+            Transaction::SetReferrer { from, game_id, referrer, fee, nonce, .. } => {
+                let mut from_account = overlay.account(from);
+                self.check_nonce(&from_account, from, *nonce)?;
+                if from_account.balance < *fee {
+                    return Err(HazeError::InsufficientBalance { required: *fee, available: from_account.balance });
+                }
+                if self.config.economy.referral_program(game_id).is_none() {
+                    return Err(HazeError::InvalidTransaction(
+                        format!("No referral program for '{}'", game_id)
+                    ));
+                }
+                if overlay.referral(game_id, from).is_some() {
+                    return Err(HazeError::InvalidTransaction(
+                        format!("Referrer for '{}' is already set", game_id)
+                    ));
+                }
+
+                from_account.balance -= fee;
+                from_account.advance_nonce(*nonce);
+                overlay.put_account(*from, from_account);
+                overlay.process_gas_fee(*fee);
+                overlay.referrals.insert(
+                    (game_id.clone(), *from),
+                    Referral { referrer: *referrer, bound_at_height: self.current_height() + 1 },
+                );
+                overlay.referral_earnings.entry((*referrer, game_id.clone())).or_default().players += 1;
+            }
+            Transaction::CommitLootBox { from, player, game_id, commitment, bond, fee, nonce, .. } => {
+                let mut from_account = overlay.account(from);
+                self.check_nonce(&from_account, from, *nonce)?;
+                let required = bond.checked_add(*fee)
+                    .ok_or_else(|| HazeError::InvalidTransaction("Loot box bond overflows".to_string()))?;
+                if from_account.balance < required {
+                    return Err(HazeError::InsufficientBalance { required, available: from_account.balance });
+                }
+                let box_id = crate::types::loot_box_id(from, *nonce);
+                if overlay.loot_box(&box_id).is_some() {
+                    return Err(HazeError::InvalidTransaction("Loot box already exists".to_string()));
+                }
+
+                from_account.balance -= required;
+                from_account.advance_nonce(*nonce);
+                overlay.put_account(*from, from_account);
+                overlay.process_gas_fee(*fee);
+
+                // The bond waits in escrow until the box is revealed or forfeited
+                if *bond > 0 {
+                    let escrow = loot_escrow_address();
+                    let mut escrow_account = overlay.account(&escrow);
+                    escrow_account.balance += bond;
+                    overlay.put_account(escrow, escrow_account);
+                }
+                overlay.loot_boxes.insert(box_id, LootBox {
+                    id: box_id,
+                    operator: *from,
+                    player: *player,
+                    game_id: game_id.clone(),
+                    commitment: *commitment,
+                    bond: *bond,
+                    sealed_at_height: self.current_height() + 1,
+                    opened_at_height: None,
+                    beacon_height: None,
+                    reveal_deadline: None,
+                    settlement: None,
+                });
+            }
+            Transaction::OpenLootBox { from, box_id, fee, nonce, .. } => {
+                let mut from_account = overlay.account(from);
+                self.check_nonce(&from_account, from, *nonce)?;
+                if from_account.balance < *fee {
+                    return Err(HazeError::InsufficientBalance { required: *fee, available: from_account.balance });
+                }
+                let mut loot_box = overlay.loot_box(box_id)
+                    .ok_or_else(|| HazeError::InvalidTransaction("Loot box not found".to_string()))?;
+                if loot_box.player != *from {
+                    return Err(HazeError::AccessDenied("Only the player can open a loot box".to_string()));
+                }
+                let height = self.current_height() + 1;
+                if loot_box.status(height) != LootBoxStatus::Sealed {
+                    return Err(HazeError::InvalidTransaction("Loot box is already opened".to_string()));
+                }
+                if height <= loot_box.sealed_at_height {
+                    return Err(HazeError::InvalidTransaction(
+                        "Loot box cannot be opened in the block that sealed it".to_string()
+                    ));
+                }
+
+                from_account.balance -= fee;
+                from_account.advance_nonce(*nonce);
+                overlay.put_account(*from, from_account);
+                overlay.process_gas_fee(*fee);
+                let beacon_height = height + self.config.economy.loot_beacon_delay;
+                loot_box.opened_at_height = Some(height);
+                loot_box.beacon_height = Some(beacon_height);
+                loot_box.reveal_deadline = Some(beacon_height + self.config.economy.loot_reveal_window);
+                overlay.trace("loot_beacon", || format!("block {}", beacon_height));
+                overlay.loot_boxes.insert(loot_box.id, loot_box);
+            }
+            Transaction::RevealLootBox { from, box_id, outcomes, salt, fee, nonce, .. } => {
+                let mut from_account = overlay.account(from);
+                self.check_nonce(&from_account, from, *nonce)?;
+                if from_account.balance < *fee {
+                    return Err(HazeError::InsufficientBalance { required: *fee, available: from_account.balance });
+                }
+                let mut loot_box = overlay.loot_box(box_id)
+                    .ok_or_else(|| HazeError::InvalidTransaction("Loot box not found".to_string()))?;
+                let height = self.current_height() + 1;
+                match loot_box.status(height) {
+                    LootBoxStatus::Opened => {}
+                    LootBoxStatus::Sealed => {
+                        return Err(HazeError::InvalidTransaction("Loot box has not been opened".to_string()));
+                    }
+                    LootBoxStatus::Expired => {
+                        return Err(HazeError::InvalidTransaction("Loot box reveal deadline has passed".to_string()));
+                    }
+                    LootBoxStatus::Revealed | LootBoxStatus::Forfeited => {
+                        return Err(HazeError::InvalidTransaction("Loot box is already settled".to_string()));
+                    }
+                }
+
+                // The beacon block must be final before anyone can reveal
+                let beacon_height = loot_box.beacon_height.unwrap_or(u64::MAX);
+                let beacon = self.get_block_header_by_height(beacon_height)
+                    .filter(|_| beacon_height < height)
+                    .map(|header| header.hash)
+                    .ok_or_else(|| HazeError::InvalidTransaction(
+                        format!("Loot box beacon block {} is not final yet", beacon_height)
+                    ));
+                let beacon = overlay.check("loot_beacon", || format!("block {}", beacon_height), beacon)?;
+                let sealed = if crate::types::loot_commitment(salt, outcomes) == loot_box.commitment {
+                    Ok(())
+                } else {
+                    Err(HazeError::InvalidTransaction("Loot box outcomes do not match the commitment".to_string()))
+                };
+                overlay.check("loot_commitment", || hex::encode(loot_box.commitment), sealed)?;
+                let drawn = draw_loot_outcome(outcomes, &beacon, salt, box_id)
+                    .ok_or_else(|| HazeError::InvalidTransaction("Loot box outcomes need a non-zero weight".to_string()))?;
+                let outcome = &outcomes[drawn];
+                overlay.trace("loot_draw", || format!("outcome {} of {}", drawn, outcomes.len()));
+
+                // Mint the drawn outcome to the player under the box id
+                if overlay.frozen_by(&loot_box.game_id).is_some() {
+                    return Err(frozen_collection_error(&loot_box.game_id));
+                }
+                if overlay.asset(box_id).is_some() {
+                    return Err(HazeError::InvalidTransaction("Asset already exists".to_string()));
+                }
+                if let Some(key) = outcome.metadata.keys().find(|key| key.is_empty() || key.starts_with('_')) {
+                    return Err(HazeError::InvalidMetadataFormat(format!("Invalid loot metadata key: '{}'", key)));
+                }
+                let metadata_size: usize = outcome.metadata.values().map(|v| v.len()).sum();
+                if metadata_size > outcome.density.max_size() {
+                    return Err(HazeError::AssetSizeExceeded(metadata_size, outcome.density.max_size()));
+                }
+                self.check_metadata_size_limit(metadata_size)?;
+                let count_limit = self.check_asset_count_limit(overlay, &loot_box.player);
+                overlay.check("asset_count_limit", || format!("player {}", hex::encode(loot_box.player)), count_limit)?;
+
+                from_account.balance -= fee;
+                from_account.advance_nonce(*nonce);
+                overlay.put_account(*from, from_account);
+                overlay.process_gas_fee(*fee);
+                self.release_loot_bond(overlay, &loot_box, loot_box.operator)?;
+
+                let data = crate::types::AssetData {
+                    density: outcome.density,
+                    metadata: outcome.metadata.clone().into_iter().collect(),
+                    attributes: outcome.attributes.clone(),
+                    game_id: Some(loot_box.game_id.clone()),
+                    owner: loot_box.player,
+                };
+                let mut asset_state = AssetState {
+                    owner: loot_box.player,
+                    data,
+                    created_at: overlay.timestamp,
+                    updated_at: overlay.timestamp,
+                    blob_refs: HashMap::new(),
+                    history: Vec::new(),
+                    versions: Vec::new(),
+                    current_version: 0,
+                    permissions: Vec::new(),
+                    public_read: false,
+                    private_fields: BTreeMap::new(),
+                };
+                let changes = HashMap::from([("loot_box".to_string(), hex::encode(box_id))]);
+                Self::add_asset_history(&mut asset_state, AssetAction::Create, changes, overlay.timestamp);
+                Self::add_asset_snapshot(&mut asset_state, overlay.timestamp);
+                overlay.put_asset(*box_id, asset_state);
+                overlay.emit(WsEvent::AssetCreated {
+                    asset_id: *box_id,
+                    owner: loot_box.player,
+                    density: outcome.density,
+                });
+
+                loot_box.settlement = Some(LootSettlement::Revealed { height, outcome: drawn });
+                overlay.loot_boxes.insert(loot_box.id, loot_box);
+            }
+            Transaction::ForfeitLootBox { from, box_id, fee, nonce, .. } => {
+                let mut from_account = overlay.account(from);
+                self.check_nonce(&from_account, from, *nonce)?;
+                if from_account.balance < *fee {
+                    return Err(HazeError::InsufficientBalance { required: *fee, available: from_account.balance });
+                }
+                let mut loot_box = overlay.loot_box(box_id)
+                    .ok_or_else(|| HazeError::InvalidTransaction("Loot box not found".to_string()))?;
+                if loot_box.player != *from {
+                    return Err(HazeError::AccessDenied("Only the player can claim a loot box bond".to_string()));
+                }
+                let height = self.current_height() + 1;
+                if loot_box.status(height) != LootBoxStatus::Expired {
+                    return Err(HazeError::InvalidTransaction(
+                        "Loot box bond can only be claimed after the reveal deadline".to_string()
+                    ));
+                }
+
+                from_account.balance -= fee;
+                from_account.advance_nonce(*nonce);
+                overlay.put_account(*from, from_account);
+                overlay.process_gas_fee(*fee);
+                self.release_loot_bond(overlay, &loot_box, loot_box.player)?;
+                loot_box.settlement = Some(LootSettlement::Forfeited { height });
+                overlay.loot_boxes.insert(loot_box.id, loot_box);
+            }
+            Transaction::CreateLeaderboard { from, game_id, season, capacity, end_height, submitters, fee, nonce, .. } => {
+                let mut from_account = overlay.account(from);
+                self.check_nonce(&from_account, from, *nonce)?;
+                if from_account.balance < *fee {
+                    return Err(HazeError::InsufficientBalance { required: *fee, available: from_account.balance });
+                }
+                let board_id = crate::types::leaderboard_id(from, game_id, season);
+                if overlay.leaderboard(&board_id).is_some() {
+                    return Err(HazeError::InvalidTransaction(format!(
+                        "Leaderboard for season '{}' of '{}' already exists", season, game_id
+                    )));
+                }
+                let height = self.current_height() + 1;
+                if *end_height < height {
+                    return Err(HazeError::InvalidTransaction("Leaderboard season has already ended".to_string()));
+                }
+
+                from_account.balance -= fee;
+                from_account.advance_nonce(*nonce);
+                overlay.put_account(*from, from_account);
+                overlay.process_gas_fee(*fee);
+                let mut submitters = submitters.clone();
+                submitters.sort();
+                submitters.dedup();
+                overlay.leaderboards.insert(board_id, Leaderboard {
+                    id: board_id,
+                    creator: *from,
+                    game_id: game_id.clone(),
+                    season: season.clone(),
+                    capacity: *capacity,
+                    end_height: *end_height,
+                    submitters,
+                    created_at_height: height,
+                    entries: Vec::new(),
+                    submissions: 0,
+                });
+            }
+            Transaction::SubmitScore { from, board_id, player, score, fee, nonce, .. } => {
+                let mut from_account = overlay.account(from);
+                self.check_nonce(&from_account, from, *nonce)?;
+                if from_account.balance < *fee {
+                    return Err(HazeError::InsufficientBalance { required: *fee, available: from_account.balance });
+                }
+                let mut board = overlay.leaderboard(board_id)
+                    .ok_or_else(|| HazeError::InvalidTransaction("Leaderboard not found".to_string()))?;
+                let permitted = if board.can_submit(from) {
+                    Ok(())
+                } else {
+                    Err(HazeError::AccessDenied(
+                        "Only the leaderboard creator and its submitters can submit scores".to_string()
+                    ))
+                };
+                overlay.check("leaderboard_submitter", || format!("submitter {}", hex::encode(from)), permitted)?;
+                let height = self.current_height() + 1;
+                if height > board.end_height {
+                    return Err(HazeError::InvalidTransaction(format!(
+                        "Leaderboard season ended at block {}", board.end_height
+                    )));
+                }
+
+                from_account.balance -= fee;
+                from_account.advance_nonce(*nonce);
+                overlay.put_account(*from, from_account);
+                overlay.process_gas_fee(*fee);
+                let outcome = board.submit(*player, *score, height);
+                overlay.trace("leaderboard_rank", || match outcome {
+                    ScoreOutcome::Ranked { rank } => format!("player {} at rank {}", hex::encode(player), rank),
+                    ScoreOutcome::NotImproved => format!("player {} already has a better score", hex::encode(player)),
+                    ScoreOutcome::BelowCutoff => format!("score {} below the top {}", score, board.capacity),
+                });
+                overlay.leaderboards.insert(board.id, board);
+            }
+            Transaction::RegisterSeason { from, game_id, season, start_height, end_height, hooks, fee, nonce, .. } => {
+                let mut from_account = overlay.account(from);
+                self.check_nonce(&from_account, from, *nonce)?;
+                if from_account.balance < *fee {
+                    return Err(HazeError::InsufficientBalance { required: *fee, available: from_account.balance });
+                }
+                let height = self.current_height() + 1;
+                if *start_height <= height {
+                    return Err(HazeError::InvalidTransaction("Season must start in a later block".to_string()));
+                }
+                let seasons = overlay.game_seasons(game_id);
+                let operator = seasons.first().map_or(*from, |first| first.operator);
+                let is_operator = if operator == *from {
+                    Ok(())
+                } else {
+                    Err(HazeError::AccessDenied(format!(
+                        "Only the season operator of '{}' can register its seasons", game_id
+                    )))
+                };
+                overlay.check("season_operator", || format!("operator {}", hex::encode(operator)), is_operator)?;
+                let id = crate::types::season_id(game_id, season);
+                if overlay.season(&id).is_some() {
+                    return Err(HazeError::InvalidTransaction(format!(
+                        "Season '{}' of '{}' is already registered", season, game_id
+                    )));
+                }
+                if let Some(last) = seasons.iter().map(|s| s.end_height).max().filter(|last| start_height <= last) {
+                    return Err(HazeError::InvalidTransaction(format!(
+                        "Season must start after the game's previous season ends (block {})", last
+                    )));
+                }
+
+                from_account.balance -= fee;
+                from_account.advance_nonce(*nonce);
+                overlay.put_account(*from, from_account);
+                overlay.process_gas_fee(*fee);
+                overlay.seasons.insert(id, Season {
+                    id,
+                    game_id: game_id.clone(),
+                    name: season.clone(),
+                    operator: *from,
+                    start_height: *start_height,
+                    end_height: *end_height,
+                    hooks: hooks.clone(),
+                    registered_at_height: height,
+                    leaderboard: None,
+                    holders_snapshot_height: None,
+                });
+            }
+            Transaction::CreateAssetPool { from, game_id, reward_per_block, lock_blocks, deposit, fee, nonce, .. } => {
+                let mut from_account = overlay.account(from);
+                self.check_nonce(&from_account, from, *nonce)?;
+                let required = deposit.checked_add(*fee)
+                    .ok_or_else(|| HazeError::InvalidTransaction("Asset pool deposit overflows".to_string()))?;
+                if from_account.balance < required {
+                    return Err(HazeError::InsufficientBalance { required, available: from_account.balance });
+                }
+                let pool_id = crate::types::asset_pool_id(from, game_id);
+                if overlay.asset_pool(&pool_id).is_some() {
+                    return Err(HazeError::InvalidTransaction(format!(
+                        "Asset pool for '{}' already exists", game_id
+                    )));
+                }
+                let height = self.current_height() + 1;
+
+                from_account.balance -= required;
+                from_account.advance_nonce(*nonce);
+                overlay.put_account(*from, from_account);
+                overlay.process_gas_fee(*fee);
+                let escrow = asset_pool_escrow_address();
+                let mut escrow_account = overlay.account(&escrow);
+                escrow_account.balance += deposit;
+                overlay.put_account(escrow, escrow_account);
+                overlay.asset_pools.insert(pool_id, AssetPool {
+                    id: pool_id,
+                    creator: *from,
+                    game_id: game_id.clone(),
+                    reward_per_block: *reward_per_block,
+                    lock_blocks: *lock_blocks,
+                    created_at_height: height,
+                    reserve: *deposit,
+                    staked: 0,
+                    reward_per_asset: 0,
+                    accrued_at_height: height,
+                    paid: 0,
+                });
+            }
+            Transaction::FundAssetPool { from, pool_id, amount, fee, nonce, .. } => {
+                let mut from_account = overlay.account(from);
+                self.check_nonce(&from_account, from, *nonce)?;
+                let required = amount.checked_add(*fee)
+                    .ok_or_else(|| HazeError::InvalidTransaction("Asset pool funding overflows".to_string()))?;
+                if from_account.balance < required {
+                    return Err(HazeError::InsufficientBalance { required, available: from_account.balance });
+                }
+                let mut pool = overlay.asset_pool(pool_id)
+                    .ok_or_else(|| HazeError::InvalidTransaction("Asset pool not found".to_string()))?;
+                // Blocks that found the reserve empty stay unpaid
+                pool.accrue(self.current_height() + 1);
+                pool.reserve = pool.reserve.checked_add(*amount)
+                    .ok_or_else(|| HazeError::InvalidTransaction("Asset pool reserve overflows".to_string()))?;
+
+                from_account.balance -= required;
+                from_account.advance_nonce(*nonce);
+                overlay.put_account(*from, from_account);
+                overlay.process_gas_fee(*fee);
+                let escrow = asset_pool_escrow_address();
+                let mut escrow_account = overlay.account(&escrow);
+                escrow_account.balance += amount;
+                overlay.put_account(escrow, escrow_account);
+                overlay.asset_pools.insert(pool.id, pool);
+            }
+            Transaction::StakeAsset { from, pool_id, asset_id, fee, nonce, .. } => {
+                let mut from_account = overlay.account(from);
+                self.check_nonce(&from_account, from, *nonce)?;
+                if from_account.balance < *fee {
+                    return Err(HazeError::InsufficientBalance { required: *fee, available: from_account.balance });
+                }
+                let mut pool = overlay.asset_pool(pool_id)
+                    .ok_or_else(|| HazeError::InvalidTransaction("Asset pool not found".to_string()))?;
+                let asset_state = overlay.asset(asset_id)
+                    .ok_or_else(|| HazeError::InvalidTransaction("Asset not found".to_string()))?;
+                if asset_state.owner != *from {
+                    return Err(HazeError::AccessDenied("Only asset owner can stake the asset".to_string()));
+                }
+                if asset_state.data.game_id.as_deref() != Some(pool.game_id.as_str()) {
+                    return Err(HazeError::InvalidTransaction(
+                        format!("Asset does not belong to collection '{}'", pool.game_id)
+                    ));
+                }
+                if overlay.frozen_by(&pool.game_id).is_some() {
+                    return Err(frozen_collection_error(&pool.game_id));
+                }
+                let height = self.current_height() + 1;
+
+                from_account.balance -= fee;
+                from_account.advance_nonce(*nonce);
+                overlay.put_account(*from, from_account);
+                overlay.process_gas_fee(*fee);
+                // The asset earns from the next block on
+                pool.accrue(height);
+                pool.staked += 1;
+                Self::move_escrowed_asset(overlay, asset_id, asset_pool_escrow_address())?;
+                overlay.asset_stakes.insert(*asset_id, Some(AssetStake {
+                    asset_id: *asset_id,
+                    pool_id: pool.id,
+                    owner: *from,
+                    staked_at_height: height,
+                    unlock_height: height.saturating_add(pool.lock_blocks),
+                    reward_checkpoint: pool.reward_per_asset,
+                    claimed: 0,
+                }));
+                overlay.asset_pools.insert(pool.id, pool);
+            }
+            Transaction::UnstakeAsset { from, asset_id, fee, nonce, .. } => {
+                let mut from_account = overlay.account(from);
+                self.check_nonce(&from_account, from, *nonce)?;
+                if from_account.balance < *fee {
+                    return Err(HazeError::InsufficientBalance { required: *fee, available: from_account.balance });
+                }
+                let mut stake = overlay.asset_stake(asset_id)
+                    .ok_or_else(|| HazeError::InvalidTransaction("Asset is not staked".to_string()))?;
+                if stake.owner != *from {
+                    return Err(HazeError::AccessDenied("Only the staker can unstake the asset".to_string()));
+                }
+                let height = self.current_height() + 1;
+                if height < stake.unlock_height {
+                    return Err(HazeError::InvalidTransaction(format!(
+                        "Asset is locked until block {}", stake.unlock_height
+                    )));
+                }
+                let mut pool = overlay.asset_pool(&stake.pool_id)
+                    .ok_or_else(|| HazeError::State("Staked asset's pool missing".to_string()))?;
+                if overlay.frozen_by(&pool.game_id).is_some() {
+                    return Err(frozen_collection_error(&pool.game_id));
+                }
+
+                from_account.balance -= fee;
+                from_account.advance_nonce(*nonce);
+                overlay.put_account(*from, from_account);
+                overlay.process_gas_fee(*fee);
+                pool.accrue(height);
+                let reward = stake.take_pending(&pool);
+                overlay.trace("asset_rewards", || format!("{} HAZE", reward));
+                Self::pay_pool_rewards(overlay, *from, reward)?;
+                pool.staked -= 1;
+                pool.paid += reward;
+                Self::move_escrowed_asset(overlay, asset_id, *from)?;
+                overlay.asset_stakes.insert(*asset_id, None);
+                overlay.asset_pools.insert(pool.id, pool);
+            }
+            Transaction::ClaimAssetRewards { from, pool_id, fee, nonce, .. } => {
+                let mut from_account = overlay.account(from);
+                self.check_nonce(&from_account, from, *nonce)?;
+                if from_account.balance < *fee {
+                    return Err(HazeError::InsufficientBalance { required: *fee, available: from_account.balance });
+                }
+                let mut pool = overlay.asset_pool(pool_id)
+                    .ok_or_else(|| HazeError::InvalidTransaction("Asset pool not found".to_string()))?;
+                let stakes = overlay.owner_stakes(from, pool_id);
+                if stakes.is_empty() {
+                    return Err(HazeError::InvalidTransaction("No assets staked in this pool".to_string()));
+                }
+
+                from_account.balance -= fee;
+                from_account.advance_nonce(*nonce);
+                overlay.put_account(*from, from_account);
+                overlay.process_gas_fee(*fee);
+                pool.accrue(self.current_height() + 1);
+                let mut reward = 0u64;
+                for mut stake in stakes {
+                    reward += stake.take_pending(&pool);
+                    overlay.asset_stakes.insert(stake.asset_id, Some(stake));
+                }
+                overlay.trace("asset_rewards", || format!("{} HAZE", reward));
+                Self::pay_pool_rewards(overlay, *from, reward)?;
+                pool.paid += reward;
+                overlay.asset_pools.insert(pool.id, pool);
+            }
+            Transaction::Breed { from, parent_a, parent_b, fee, nonce, .. } => {
+                let mut from_account = overlay.account(from);
+                self.check_nonce(&from_account, from, *nonce)?;
+                if parent_a == parent_b {
+                    return Err(HazeError::InvalidTransaction("An asset cannot breed with itself".to_string()));
+                }
+                let mut parents = Vec::with_capacity(2);
+                for parent_id in [parent_a, parent_b] {
+                    let parent = overlay.asset(parent_id)
+                        .ok_or_else(|| HazeError::InvalidTransaction("Parent asset not found".to_string()))?;
+                    if parent.owner != *from {
+                        return Err(HazeError::AccessDenied("Only the owner of both parents can breed them".to_string()));
+                    }
+                    parents.push(parent);
+                }
+                let game_id = match (&parents[0].data.game_id, &parents[1].data.game_id) {
+                    (Some(a), Some(b)) if a == b => a.clone(),
+                    _ => return Err(HazeError::InvalidTransaction(
+                        "Parents must belong to the same collection".to_string()
+                    )),
+                };
+                let rule = self.config.economy.breeding_rule(&game_id)
+                    .ok_or_else(|| HazeError::InvalidTransaction(format!("Collection '{}' has no breeding rule", game_id)))?;
+                if overlay.frozen_by(&game_id).is_some() {
+                    return Err(frozen_collection_error(&game_id));
+                }
+                let height = self.current_height() + 1;
+                for parent_id in [parent_a, parent_b] {
+                    let ready_at = overlay.last_bred(parent_id).map_or(0, |bred| bred.saturating_add(rule.cooldown_blocks));
+                    let cooled_down = if height < ready_at {
+                        Err(HazeError::InvalidTransaction(format!(
+                            "Parent {} can breed again at block {}", crate::types::hash_to_hex(parent_id), ready_at
+                        )))
+                    } else {
+                        Ok(())
+                    };
+                    overlay.check("breeding_cooldown", || format!("parent {} ready at {}", hex::encode(parent_id), ready_at), cooled_down)?;
+                }
+                let required = fee.checked_add(rule.fee)
+                    .ok_or_else(|| HazeError::InvalidTransaction("Breeding fee overflows".to_string()))?;
+                if from_account.balance < required {
+                    return Err(HazeError::InsufficientBalance { required, available: from_account.balance });
+                }
+                let count_limit = self.check_asset_count_limit(overlay, from);
+                overlay.check("asset_count_limit", || format!("breeder {}", hex::encode(from)), count_limit)?;
+
+                from_account.balance -= required;
+                from_account.advance_nonce(*nonce);
+                overlay.put_account(*from, from_account);
+                overlay.process_gas_fee(*fee);
+                if rule.fee > 0 {
+                    self.process_collection_fee(overlay, from, Some(&game_id), ReferralFee::Crafting, rule.fee)?;
+                }
+                let id = crate::types::breeding_id(from, *nonce);
+                for parent_id in [parent_a, parent_b] {
+                    overlay.last_bred.insert(*parent_id, height);
+                }
+                let (a, b) = (&parents[0], &parents[1]);
+                overlay.breedings.insert(id, Breeding {
+                    id,
+                    breeder: *from,
+                    game_id,
+                    parents: [*parent_a, *parent_b],
+                    parent_attributes: [a.data.attributes.clone(), b.data.attributes.clone()],
+                    density: crate::breeding::lower_density(a.data.density, b.data.density),
+                    bred_at_height: height,
+                    hatch_height: height + rule.hatch_delay,
+                    hatched_at_height: None,
+                });
+            }
+            Transaction::Upgrade { from, asset_id, catalysts, fee, nonce, .. } => {
+                let mut from_account = overlay.account(from);
+                self.check_nonce(&from_account, from, *nonce)?;
+                let asset_state = overlay.asset(asset_id)
+                    .ok_or_else(|| HazeError::InvalidTransaction("Asset not found".to_string()))?;
+                if asset_state.owner != *from {
+                    return Err(HazeError::AccessDenied("Only asset owner can upgrade the asset".to_string()));
+                }
+                let game_id = asset_state.data.game_id.clone()
+                    .ok_or_else(|| HazeError::InvalidTransaction("Asset does not belong to a collection".to_string()))?;
+                let rule = self.config.economy.upgrade_rule(&game_id)
+                    .ok_or_else(|| HazeError::InvalidTransaction(format!("Collection '{}' has no upgrade rule", game_id)))?;
+                if overlay.frozen_by(&game_id).is_some() {
+                    return Err(frozen_collection_error(&game_id));
+                }
+                let current = asset_state.data.attributes.iter()
+                    .find(|attribute| attribute.name == rule.attribute)
+                    .map(|attribute| &attribute.value);
+                let value_before = attribute_level(current).ok_or_else(|| HazeError::InvalidTransaction(
+                    format!("Attribute '{}' of the asset is not a number", rule.attribute)
+                ));
+                let value_before = overlay.check("upgrade_attribute", || match current {
+                    Some(value) => format!("{} = {} {}", rule.attribute, value.type_name(), value),
+                    None => format!("{} unset", rule.attribute),
+                }, value_before)?;
+
+                let catalyst_count = if catalysts.len() != rule.catalysts as usize {
+                    Err(HazeError::InvalidTransaction(format!(
+                        "Collection '{}' upgrades burn {} catalysts, got {}", game_id, rule.catalysts, catalysts.len()
+                    )))
+                } else {
+                    Ok(())
+                };
+                overlay.check("catalyst_count", || format!("{} of {}", catalysts.len(), rule.catalysts), catalyst_count)?;
+                for (i, catalyst_id) in catalysts.iter().enumerate() {
+                    if catalyst_id == asset_id || catalysts[..i].contains(catalyst_id) {
+                        return Err(HazeError::InvalidTransaction(
+                            "Catalysts must be distinct assets other than the upgraded one".to_string()
+                        ));
+                    }
+                    let catalyst = overlay.asset(catalyst_id)
+                        .ok_or_else(|| HazeError::InvalidTransaction("Catalyst asset not found".to_string()))?;
+                    if catalyst.owner != *from {
+                        return Err(HazeError::AccessDenied("Only the owner of the catalysts can burn them".to_string()));
+                    }
+                    if catalyst.data.game_id.as_deref() != Some(game_id.as_str()) {
+                        return Err(HazeError::InvalidTransaction(
+                            format!("Catalysts must belong to collection '{}'", game_id)
+                        ));
                     }
                 }
+                let required = fee.checked_add(rule.fee)
+                    .ok_or_else(|| HazeError::InvalidTransaction("Upgrade fee overflows".to_string()))?;
+                if from_account.balance < required {
+                    return Err(HazeError::InsufficientBalance { required, available: from_account.balance });
+                }
+
+                from_account.balance -= required;
+                from_account.advance_nonce(*nonce);
+                overlay.put_account(*from, from_account);
+                overlay.process_gas_fee(*fee);
+                if rule.fee > 0 {
+                    self.process_collection_fee(overlay, from, Some(&game_id), ReferralFee::Crafting, rule.fee)?;
+                }
+                let height = self.current_height() + 1;
+                let id = crate::types::upgrade_id(from, *nonce);
+                for catalyst_id in catalysts {
+                    overlay.remove_asset(*catalyst_id);
+                    overlay.link_assets(*catalyst_id, *asset_id, AssetAction::Upgrade);
+                }
+                Self::move_escrowed_asset(overlay, asset_id, upgrade_escrow_address())?;
+                overlay.upgrades.insert(id, Upgrade {
+                    id,
+                    owner: *from,
+                    asset_id: *asset_id,
+                    game_id,
+                    attribute: rule.attribute.clone(),
+                    catalysts: catalysts.clone(),
+                    success_bps: rule.success_bps,
+                    destroy_bps: rule.destroy_bps,
+                    boost: rule.boost,
+                    requested_at_height: height,
+                    roll_height: height + rule.roll_delay,
+                    outcome: None,
+                    resolved_at_height: None,
+                    value_before,
+                    value_after: None,
+                });
+            }
+            Transaction::TransferGovernance { from, to, amount, fee, nonce, .. } => {
+                self.check_governance_token()?;
+                let mut from_account = overlay.account(from);
+                self.check_nonce(&from_account, from, *nonce)?;
+                if from_account.balance < *fee {
+                    return Err(HazeError::InsufficientBalance { required: *fee, available: from_account.balance });
+                }
+                if from_account.governance_balance < *amount {
+                    return Err(HazeError::InsufficientBalance { required: *amount, available: from_account.governance_balance });
+                }
+
+                from_account.balance -= fee;
+                from_account.governance_balance -= amount;
+                from_account.advance_nonce(*nonce);
+                overlay.put_account(*from, from_account);
+
+                // Read the recipient after writing the sender (they may be the same account)
+                let mut to_account = overlay.account(to);
+                to_account.governance_balance += amount;
+                overlay.put_account(*to, to_account);
+                overlay.process_gas_fee(*fee);
+            }
+            Transaction::StakeGovernance { from, amount, fee, nonce, .. } => {
+                self.check_governance_token()?;
+                let mut account = overlay.account(from);
+                self.check_nonce(&account, from, *nonce)?;
+                if account.balance < *fee {
+                    return Err(HazeError::InsufficientBalance { required: *fee, available: account.balance });
+                }
+                if account.governance_balance < *amount {
+                    return Err(HazeError::InsufficientBalance { required: *amount, available: account.governance_balance });
+                }
+
+                account.balance -= fee;
+                account.governance_balance -= amount;
+                account.governance_staked += amount;
+                account.advance_nonce(*nonce);
+                overlay.put_account(*from, account);
+                overlay.process_gas_fee(*fee);
+            }
+            Transaction::UnstakeGovernance { from, amount, fee, nonce, .. } => {
+                self.check_governance_token()?;
+                let mut account = overlay.account(from);
+                self.check_nonce(&account, from, *nonce)?;
+                if account.balance < *fee {
+                    return Err(HazeError::InsufficientBalance { required: *fee, available: account.balance });
+                }
+                if account.governance_staked < *amount {
+                    return Err(HazeError::InvalidTransaction(format!(
+                        "Only {} governance tokens are staked", account.governance_staked
+                    )));
+                }
+
+                account.balance -= fee;
+                account.governance_staked -= amount;
+                account.governance_balance += amount;
+                account.advance_nonce(*nonce);
+                overlay.put_account(*from, account);
+                overlay.process_gas_fee(*fee);
+            }
+            Transaction::SetCommission { from: validator, rate_bps, fee, nonce, .. } => {
+                let mut account = overlay.account(validator);
+                self.check_nonce(&account, validator, *nonce)?;
+                if account.balance < *fee {
+                    return Err(HazeError::InsufficientBalance { required: *fee, available: account.balance });
+                }
+                if overlay.commissions.iter().any(|(v, _, _)| v == validator) {
+                    return Err(HazeError::InvalidTransaction("Commission already changed in this block".to_string()));
+                }
+                let height = self.current_height() + 1;
+                self.tokenomics.check_commission_change(validator, *rate_bps, height, &self.config.consensus.commission_policy())?;
+
+                account.balance -= fee;
+                account.advance_nonce(*nonce);
+                overlay.put_account(*validator, account);
+                overlay.process_gas_fee(*fee);
+                overlay.set_commission(*validator, *rate_bps, height);
+            }
+            Transaction::VoteCommitteeParams { from, min_stake, max_weight_bps, fee, nonce, .. } => {
+                let mut account = overlay.account(from);
+                self.check_nonce(&account, from, *nonce)?;
+                if account.balance < *fee {
+                    return Err(HazeError::InsufficientBalance { required: *fee, available: account.balance });
+                }
+                if self.account_governance_weight(&account) == 0 {
+                    return Err(HazeError::InvalidTransaction("Voting needs governance weight".to_string()));
+                }
+
+                account.balance -= fee;
+                account.advance_nonce(*nonce);
+                overlay.put_account(*from, account);
+                overlay.process_gas_fee(*fee);
+                overlay.vote_committee_params(*from, crate::tokenomics::CommitteeParams {
+                    min_stake: *min_stake,
+                    max_weight_bps: *max_weight_bps,
+                });
+            }
+            Transaction::SetAssetPermissions { from, asset_id, permissions, public_read, owner, fee, nonce, .. } => {
+                let mut from_account = overlay.account(from);
+                self.check_nonce(&from_account, from, *nonce)?;
+                if from_account.balance < *fee {
+                    return Err(HazeError::InsufficientBalance { required: *fee, available: from_account.balance });
+                }
+                let mut asset_state = overlay.asset(asset_id)
+                    .ok_or_else(|| HazeError::InvalidTransaction("Asset not found".to_string()))?;
+                if asset_state.owner != *owner || from != owner {
+                    return Err(HazeError::AccessDenied(
+                        "Only asset owner can set permissions".to_string()
+                    ));
+                }
+                from_account.balance -= fee;
+                from_account.advance_nonce(*nonce);
+                overlay.put_account(*from, from_account);
+                overlay.process_gas_fee(*fee);
+
+                asset_state.permissions = permissions.clone();
+                asset_state.public_read = *public_read;
+                asset_state.updated_at = overlay.timestamp;
+                overlay.put_asset(*asset_id, asset_state);
+                overlay.emit(WsEvent::AssetPermissionChanged {
+                    asset_id: *asset_id,
+                    owner: *owner,
+                });
+            }
+            Transaction::DeployContract { from, code, fee, nonce, .. } => {
+                let mut from_account = overlay.account(from);
+                self.check_nonce(&from_account, from, *nonce)?;
+                if from_account.balance < *fee {
+                    return Err(HazeError::InsufficientBalance { required: *fee, available: from_account.balance });
+                }
+                from_account.balance -= fee;
+                from_account.advance_nonce(*nonce);
+                overlay.put_account(*from, from_account);
+                let contract_address = crate::types::contract_address(from, *nonce);
+                if overlay.contract_info(&contract_address).is_some() {
+                    return Err(HazeError::InvalidTransaction(
+                        format!("Contract already deployed at {}", crate::types::address_to_hex(&contract_address))
+                    ));
+                }
+                overlay.put_contract(
+                    ContractInfo {
+                        address: contract_address,
+                        deployer: *from,
+                        deploy_nonce: *nonce,
+                        code_hash: crate::types::sha256(code),
+                        code_size: code.len(),
+                        deployed_at_height: self.current_height() + 1,
+                    },
+                    code.clone(),
+                );
+                overlay.process_gas_fee(*fee);
+            }
+            Transaction::ContractCall { from, contract, method, args, gas_limit, fee, nonce, .. } => {
+                if *gas_limit > self.config.vm.gas_limit {
+                    return Err(HazeError::VM(format!(
+                        "Gas limit {} exceeds node limit {}",
+                        gas_limit, self.config.vm.gas_limit
+                    )));
+                }
+                let wasm_code = overlay.contract(contract)?
+                    .ok_or_else(|| HazeError::VM("Contract not found".to_string()))?;
+                let mut from_account = overlay.account(from);
+                self.check_nonce(&from_account, from, *nonce)?;
+                let (gas_fee_estimate, _) = self.config.vm
+                    .discounted_gas_fee((*gas_limit).saturating_mul(self.config.vm.gas_price), from_account.staked);
+                if from_account.balance < *fee + gas_fee_estimate {
+                    return Err(HazeError::InsufficientBalance { required: *fee + gas_fee_estimate, available: from_account.balance });
+                }
+                from_account.balance -= fee;
+                from_account.advance_nonce(*nonce);
+                overlay.put_account(*from, from_account);
+                overlay.process_gas_fee(*fee);
+
+                let vm = HazeVM::new((*self.config).clone())?;
+                let mut context = ExecutionContext {
+                    caller: *from,
+                    contract: *contract,
+                    gas_limit: *gas_limit,
+                    gas_used: 0,
+                    logs: Vec::new(),
+                    assets: Some(overlay.lend_assets()),
+                    prices: Some(self.economy.clone()),
+                };
+                let result = vm.execute_contract(&wasm_code, method, args, &mut context);
+                overlay.return_assets(context.assets.take(), result.is_ok())?;
+                result?;
+                overlay.push_logs(std::mem::take(&mut context.logs));
+                let gas_used = context.gas_used;
+                overlay.gas_used += gas_used;
+                let gas_fee = self.stake_discounted_gas_fee(overlay, from, gas_used.saturating_mul(self.config.vm.gas_price));
+                let mut from_account = overlay.account(from);
+                if from_account.balance < gas_fee {
+                    return Err(HazeError::InsufficientBalance { required: gas_fee, available: from_account.balance });
+                }
+                from_account.balance -= gas_fee;
+                overlay.put_account(*from, from_account);
+                overlay.process_gas_fee(gas_fee);
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Gas fee `payer` is charged after the discount of its stake tier (`vm.stake_discounts`);
+    /// the waived part is recorded for the transaction's receipt
+    fn stake_discounted_gas_fee(&self, overlay: &mut StateOverlay<'_>, payer: &Address, gas_fee: u64) -> u64 {
+        let staked = overlay.account(payer).staked;
+        let (charged, discount) = self.config.vm.discounted_gas_fee(gas_fee, staked);
+        if discount > 0 {
+            overlay.gas_discount += discount;
+            overlay.trace("gas_discount", || format!("staked {}: {} of gas fee {} waived", staked, discount, gas_fee));
+        }
+        charged
+    }
+
+    /// Ask the paymaster contract named by an asset transaction's `_paymaster` metadata (if any)
+    /// to sponsor its gas fee. Returns the fee the paymaster paid, or `None` if the owner pays.
+    ///
+    /// The paymaster's `sponsor` function gets the request as JSON through `haze_input` and
+    /// accepts by returning a non-zero value. It runs with `vm.paymaster_gas_limit` and without
+    /// asset access; its gas is paid by the paymaster if it accepts and by the owner otherwise.
+    /// A paymaster that is not a contract or cannot cover the fee plus the decision gas is not asked.
+    fn sponsor_asset_gas(
+        &self,
+        overlay: &mut StateOverlay<'_>,
+        action: &AssetAction,
+        asset_id: &Hash,
+        data: &crate::types::AssetData,
+        gas_fee: u64,
+    ) -> Result<Option<u64>> {
+        let Some(paymaster) = data.metadata.get(PAYMASTER_KEY) else {
+            return Ok(None);
+        };
+        let owner = &data.owner;
+        let game_id = overlay.asset(asset_id).and_then(|a| a.data.game_id).or_else(|| data.game_id.clone());
+        let paymaster = crate::types::hex_to_address(paymaster).ok_or_else(|| {
+            HazeError::InvalidTransaction(format!("Invalid {} address", PAYMASTER_KEY))
+        })?;
+        let gas_limit = self.config.vm.paymaster_gas_limit;
+        let max_decision_fee = gas_limit.saturating_mul(self.config.vm.gas_price);
+        let Some(wasm_code) = overlay.contract(&paymaster)? else {
+            overlay.trace("paymaster", || format!("{} is not a contract", hex::encode(paymaster)));
+            return Ok(None);
+        };
+        let paymaster_balance = overlay.account(&paymaster).balance;
+        if paymaster_balance < gas_fee.saturating_add(max_decision_fee) {
+            overlay.trace("paymaster", || format!("balance {} cannot cover fee {} and decision gas", paymaster_balance, gas_fee));
+            return Ok(None);
+        }
+
+        let height = self.current_height() + 1;
+        let window = height / self.config.vm.paymaster_window_blocks;
+        let request = serde_json::json!({
+            "sender": hex::encode(owner),
+            "action": format!("{:?}", action),
+            "asset_id": hex::encode(asset_id),
+            "game_id": game_id,
+            "gas_fee": gas_fee,
+            "height": height,
+            "window_blocks": self.config.vm.paymaster_window_blocks,
+            "sponsored_in_window": overlay.paymaster_spent(&paymaster, window),
+        })
+        .to_string()
+        .into_bytes();
+
+        let vm = HazeVM::new((*self.config).clone())?;
+        let mut context = ExecutionContext {
+            caller: *owner,
+            contract: paymaster,
+            gas_limit,
+            gas_used: 0,
+            logs: Vec::new(),
+            assets: None,
+            prices: Some(self.economy.clone()),
+        };
+        let result = vm.execute_contract_values(&wasm_code, PAYMASTER_METHOD, &request, &mut context);
+        let accepted = match result.as_ref().ok().and_then(|values| values.first()) {
+            Some(ContractValue::I32(v)) => *v != 0,
+            Some(ContractValue::I64(v)) => *v != 0,
+            _ => false,
+        };
+        if result.is_ok() {
+            overlay.push_logs(std::mem::take(&mut context.logs));
+        }
+        overlay.gas_used += context.gas_used.min(gas_limit);
+        let decision_fee = context.gas_used.min(gas_limit).saturating_mul(self.config.vm.gas_price);
+        overlay.trace("paymaster", || match &result {
+            Ok(_) => format!("{} sponsorship, decision gas {}", if accepted { "accepted" } else { "declined" }, context.gas_used),
+            Err(e) => format!("decision failed: {}", e),
+        });
+
+        if !accepted {
+            let mut owner_account = overlay.account(owner);
+            let balance_check = if owner_account.balance < decision_fee {
+                Err(HazeError::InsufficientBalance { required: decision_fee, available: owner_account.balance })
+            } else {
+                Ok(())
+            };
+            overlay.check("paymaster_gas", || format!("owner balance {}, decision fee {}", owner_account.balance, decision_fee), balance_check)?;
+            owner_account.balance -= decision_fee;
+            overlay.put_account(*owner, owner_account);
+            overlay.process_gas_fee(decision_fee);
+            return Ok(None);
+        }
+
+        let gas_fee = self.stake_discounted_gas_fee(overlay, &paymaster, gas_fee);
+        let mut paymaster_account = overlay.account(&paymaster);
+        paymaster_account.balance -= gas_fee + decision_fee;
+        overlay.put_account(paymaster, paymaster_account);
+        overlay.process_gas_fee(decision_fee);
+        overlay.sponsorships.push((paymaster, window, gas_fee + decision_fee));
+        Ok(Some(gas_fee))
+    }
+
+    /// Invoke the collection hook (if any) after an asset lifecycle change.
+    ///
+    /// The hook runs with its registered gas limit, paid by `payer` at `vm.gas_price`.
+    /// On failure the hook's `on_failure` mode decides between failing the
+    /// transaction and logging a warning (gas consumed up to the failure is still charged).
+    fn run_asset_hook(
+        &self,
+        overlay: &mut StateOverlay<'_>,
+        payer: &Address,
+        asset_id: &Hash,
+        action: &AssetAction,
+    ) -> Result<()> {
+        let Some(game_id) = overlay.asset(asset_id).and_then(|a| a.data.game_id) else {
+            return Ok(());
+        };
+        let Some(registered) = overlay.asset_hook(&game_id) else {
+            return Ok(());
+        };
+        let hook = registered.hook;
+
+        let fail = |error: String| -> Result<()> {
+            match hook.on_failure {
+                HookFailureMode::Revert => Err(HazeError::VM(format!("Asset hook for '{}' failed: {}", game_id, error))),
+                HookFailureMode::Warn => {
+                    tracing::warn!("Asset hook for '{}' failed: {}", game_id, error);
+                    Ok(())
+                }
             }
-            Transaction::Stake { from: validator, amount, .. } => {
-                let mut account = self.accounts
-                    .entry(*validator)
-                    .or_insert_with(|| AccountState {
-                        balance: 0,
-                        nonce: 0,
-                        staked: 0,
-                    });
-                
-                if account.balance < *amount {
-                    return Err(HazeError::InvalidTransaction("Insufficient balance for staking".to_string()));
+        };
+
+        let payer_account = overlay.account(payer);
+        let (max_gas_fee, _) = self.config.vm
+            .discounted_gas_fee(hook.gas_limit.saturating_mul(self.config.vm.gas_price), payer_account.staked);
+        if payer_account.balance < max_gas_fee {
+            return fail(format!("insufficient balance for hook gas: need {}", max_gas_fee));
+        }
+        let Some(wasm_code) = overlay.contract(&hook.contract)? else {
+            return fail("hook contract not found".to_string());
+        };
+
+        // Hook arguments: asset id followed by the action tag
+        let mut args = asset_id.to_vec();
+        args.push(match action {
+            AssetAction::Create => 0,
+            AssetAction::Update => 1,
+            AssetAction::Condense => 2,
+            AssetAction::Evaporate => 3,
+            AssetAction::Merge => 4,
+            AssetAction::Split => 5,
+            AssetAction::Reveal => 6,
+            AssetAction::Breed => 7,
+            AssetAction::Upgrade => 8,
+        });
+
+        let vm = HazeVM::new((*self.config).clone())?;
+        let mut context = ExecutionContext {
+            caller: *payer,
+            contract: hook.contract,
+            gas_limit: hook.gas_limit,
+            gas_used: 0,
+            logs: Vec::new(),
+            assets: Some(overlay.lend_assets()),
+            prices: Some(self.economy.clone()),
+        };
+        let result = vm.execute_contract(&wasm_code, &hook.method, &args, &mut context);
+        overlay.return_assets(context.assets.take(), result.is_ok())?;
+
+        overlay.gas_used += context.gas_used.min(hook.gas_limit);
+        let gas_fee = self.stake_discounted_gas_fee(
+            overlay,
+            payer,
+            context.gas_used.min(hook.gas_limit).saturating_mul(self.config.vm.gas_price),
+        );
+        let mut payer_account = overlay.account(payer);
+        payer_account.balance -= gas_fee;
+        overlay.put_account(*payer, payer_account);
+        overlay.process_gas_fee(gas_fee);
+
+        match result {
+            Ok(_) => {
+                overlay.push_logs(context.logs);
+                Ok(())
+            }
+            Err(e) => fail(e.to_string()),
+        }
+    }
+
+    /// Query contract logs. Blocks whose bloom filter rules out the
+    /// address or topics are skipped without scanning their logs.
+    pub fn get_logs(&self, filter: &LogFilter) -> Vec<LogEntry> {
+        let current = self.current_height();
+        let to_height = filter.to_height.unwrap_or(current).min(current);
+        let mut result = Vec::new();
+        if filter.from_height > to_height {
+            return result;
+        }
+        let (from, to) = (block_key(filter.from_height), block_key(to_height));
+        let range = self.trees.block_logs.range(std::ops::Bound::Included(&from[..]), std::ops::Bound::Included(&to[..]));
+        for item in range {
+            let decoded = item.and_then(|(_, value)| {
+                bincode::deserialize::<BlockLogs>(&value).map_err(|e| HazeError::Serialization(e.to_string()))
+            });
+            let block_logs = match decoded {
+                Ok(block_logs) => block_logs,
+                Err(e) => {
+                    tracing::warn!("Failed to read logs of blocks {}-{}: {}", filter.from_height, to_height, e);
+                    break;
+                }
+            };
+            if !filter.matches_bloom(&block_logs.bloom) {
+                continue;
+            }
+            for entry in block_logs.logs.iter().filter(|entry| filter.matches(&entry.log)) {
+                result.push(entry.clone());
+                if filter.limit > 0 && result.len() >= filter.limit {
+                    return result;
                 }
+            }
+        }
+        result
+    }
+
+    /// Height of the block that included an applied transaction
+    pub fn get_transaction_height(&self, tx_hash: &Hash) -> Option<u64> {
+        self.get_transaction_location(tx_hash).map(|(height, _)| height)
+    }
+
+    /// Height and index in its block of an applied transaction, from the `tx_locations` tree
+    fn get_transaction_location(&self, tx_hash: &Hash) -> Option<(u64, usize)> {
+        let value = self.read_receipt_tree(&self.trees.tx_locations, tx_hash, "location of transaction")?;
+        let location: [u8; 16] = value.as_ref().try_into().ok()?;
+        let (height, tx_index) = location.split_at(8);
+        Some((
+            u64::from_be_bytes(height.try_into().ok()?),
+            u64::from_be_bytes(tx_index.try_into().ok()?) as usize,
+        ))
+    }
+
+    /// Read a receipt tree entry; a read error is logged and treated as missing
+    fn read_receipt_tree(&self, tree: &Arc<dyn StorageTree>, key: &[u8], what: &str) -> Option<sled::IVec> {
+        tree.get(key)
+            .inspect_err(|e| tracing::warn!("Failed to read {} {}: {}", what, hex::encode(key), e))
+            .ok()
+            .flatten()
+    }
+
+    /// Receipt of a transaction that was applied, or that failed block application (the last
+    /// failure). `None` for unknown and pending transactions.
+    pub fn get_receipt(&self, tx_hash: &Hash) -> Option<TxReceipt> {
+        if let Some((height, tx_index)) = self.get_transaction_location(tx_hash) {
+            let block = self.get_block_by_height(height)?;
+            return self.get_block_receipts(&block).into_iter().nth(tx_index);
+        }
+        self.get_dead_letter(tx_hash).map(|entry| TxReceipt {
+            tx_hash: entry.tx_hash,
+            status: ReceiptStatus::Failed,
+            block_hash: entry.block_hash,
+            block_height: entry.block_height,
+            tx_index: entry.tx_index,
+            execution: TxExecution::default(),
+            logs: Vec::new(),
+            error: Some(entry.error),
+            error_code: Some(entry.error_code),
+        })
+    }
+
+    /// Receipts of the transactions of an applied block, in transaction order
+    pub fn get_block_receipts(&self, block: &Block) -> Vec<TxReceipt> {
+        let height = block.header.height;
+        let mut executions = self.get_block_executions(height).into_iter();
+        let logs = self.get_block_logs(height);
+        block.transactions.iter().enumerate().map(|(tx_index, tx)| TxReceipt {
+            tx_hash: tx.hash(),
+            status: ReceiptStatus::Executed,
+            block_hash: block.header.hash,
+            block_height: height,
+            tx_index,
+            execution: executions.next().unwrap_or_default(),
+            logs: logs.iter().filter(|entry| entry.tx_index == tx_index).cloned().collect(),
+            error: None,
+            error_code: None,
+        }).collect()
+    }
+
+    /// Contract logs of the block at `height`, in emission order
+    pub fn get_block_logs(&self, height: u64) -> Vec<LogEntry> {
+        self.read_receipt_tree(&self.trees.block_logs, &block_key(height), "logs of block")
+            .and_then(|value| {
+                bincode::deserialize::<BlockLogs>(&value)
+                    .inspect_err(|e| tracing::warn!("Logs of block {} do not decode: {}", height, e))
+                    .ok()
+            })
+            .map(|block_logs| block_logs.logs)
+            .unwrap_or_default()
+    }
+
+    /// Execution results of the transactions in the block at `height`, in transaction order
+    pub fn get_block_executions(&self, height: u64) -> Vec<TxExecution> {
+        self.read_receipt_tree(&self.trees.receipts, &block_key(height), "receipts of block")
+            .and_then(|value| {
+                bincode::deserialize(&value)
+                    .inspect_err(|e| tracing::warn!("Receipts of block {} do not decode: {}", height, e))
+                    .ok()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Logs emitted by a transaction
+    pub fn get_transaction_logs(&self, tx_hash: &Hash) -> Vec<LogEntry> {
+        let Some(height) = self.get_transaction_height(tx_hash) else {
+            return Vec::new();
+        };
+        self.get_block_logs(height).into_iter().filter(|entry| entry.tx_hash == *tx_hash).collect()
+    }
+
+    /// Get the asset hook registered for a collection (game_id)
+    pub fn get_asset_hook(&self, game_id: &str) -> Option<RegisteredAssetHook> {
+        self.asset_hooks.get(game_id).map(|h| h.clone())
+    }
+
+    /// Register contract code at address without a DeployContract tx (genesis contracts, tests).
+    /// Fails if a contract already exists at `address`.
+    pub fn register_contract(&self, address: Address, code: Vec<u8>) -> Result<()> {
+        if self.contracts.contains_key(&address) {
+            return Err(HazeError::State(
+                format!("Contract already deployed at {}", crate::types::address_to_hex(&address))
+            ));
+        }
+        let info = ContractInfo {
+            address,
+            deployer: [0u8; 32],
+            deploy_nonce: 0,
+            code_hash: crate::types::sha256(&code),
+            code_size: code.len(),
+            deployed_at_height: self.current_height(),
+        };
+        self.store_contract_code(&info.code_hash, &code)?;
+        self.contracts.insert(address, info);
+        Ok(())
+    }
+
+    /// Write contract bytecode under its hash (identical code is stored once)
+    fn store_contract_code(&self, code_hash: &Hash, code: &[u8]) -> Result<()> {
+        if !self.trees.contract_code.contains_key(code_hash)? {
+            self.trees.contract_code.insert(code_hash, code)?;
+        }
+        Ok(())
+    }
+
+    /// Get deployed contract metadata
+    pub fn get_contract(&self, address: &Address) -> Option<ContractInfo> {
+        self.contracts.get(address).map(|c| c.clone())
+    }
+
+    /// Get deployed contract bytecode (loaded from the database by code hash)
+    pub fn get_contract_code(&self, address: &Address) -> Result<Option<Vec<u8>>> {
+        let Some(code_hash) = self.contracts.get(address).map(|c| c.code_hash) else {
+            return Ok(None);
+        };
+        let code = self.trees.contract_code
+            .get(&code_hash)?
+            .ok_or_else(|| HazeError::Database(
+                format!("Missing bytecode for contract {}", crate::types::address_to_hex(address))
+            ))?;
+        Ok(Some(code.to_vec()))
+    }
+
+    /// Execute a contract method against current state without a transaction.
+    /// Gas is capped at `gas_limit` (and the configured VM limit) but not charged,
+    /// and emitted logs are returned rather than recorded.
+    pub fn call_contract_readonly(
+        &self,
+        caller: Address,
+        contract: &Address,
+        method: &str,
+        args: &[u8],
+        gas_limit: u64,
+    ) -> Result<ContractCallOutput> {
+        let wasm_code = self.get_contract_code(contract)?.ok_or_else(|| {
+            HazeError::State(format!("Contract not found: {}", crate::types::address_to_hex(contract)))
+        })?;
+
+        let vm = HazeVM::new((*self.config).clone())?;
+        let mut context = ExecutionContext {
+            caller,
+            contract: *contract,
+            gas_limit: gas_limit.min(self.config.vm.gas_limit),
+            gas_used: 0,
+            logs: Vec::new(),
+            // Precompile writes land in a throwaway view
+            assets: Some(Box::new(ContractAssetView::new(
+                self.assets.clone(),
+                HashMap::new(),
+                self.frozen_collections.iter().map(|e| e.key().clone()).collect(),
+                chrono::Utc::now().timestamp(),
+            ))),
+            prices: Some(self.economy.clone()),
+        };
+        let return_values = vm.execute_contract_values(&wasm_code, method, args, &mut context)?;
+
+        Ok(ContractCallOutput {
+            return_data: ContractValue::encode_all(&return_values),
+            return_values,
+            gas_used: context.gas_used,
+            logs: context.logs,
+        })
+    }
+
+    /// Apply multiple transactions in batch (optimized)
+    ///
+    /// # Arguments
+    /// * `transactions` - Vector of transactions to apply
+    ///
+    /// # Returns
+    /// `Ok(())` if all transactions were applied successfully, `Err` with first error otherwise.
+    /// The batch is atomic: on error none of the transactions are applied.
+    ///
+    /// # Performance
+    /// This method is optimized for batch operations by reducing index updates overhead.
+    pub fn apply_transactions_batch(&self, transactions: &[Transaction]) -> Result<()> {
+        let mut overlay = StateOverlay::new(self);
+        for tx in transactions {
+            self.apply_transaction_to_overlay(&mut overlay, tx)?;
+        }
+        
+        let _commit = self.snapshot_lock.write();
+        self.commit_overlay(overlay)
+    }
+    
+    /// Batch create assets (optimized for multiple asset creation)
+    ///
+    /// # Arguments
+    /// * `assets` - Vector of (asset_id, asset_data) tuples
+    ///
+    /// # Returns
+    /// `Ok(())` if all assets were created successfully, `Err` with first error otherwise
+    ///
+    /// # Performance
+    /// This method is optimized for batch creation by batching index updates.
+    pub fn batch_create_assets(&self, assets: Vec<(Hash, AssetState)>) -> Result<()> {
+        // Validate all assets against an overlay, so limits account for earlier items in the batch
+        let mut overlay = StateOverlay::new(self);
+        for (asset_id, asset_state) in assets {
+            // Check if asset already exists
+            if overlay.asset(&asset_id).is_some() {
+                return Err(HazeError::InvalidTransaction(
+                    format!("Asset {} already exists", hex::encode(asset_id))
+                ));
+            }
+            
+            // Check asset count limit
+            self.check_asset_count_limit(&overlay, &asset_state.owner)?;
+            
+            // Check metadata size limit
+            let metadata_size: usize = asset_state.data.metadata.values().map(|v| v.len()).sum();
+            self.check_metadata_size_limit(metadata_size)?;
+            
+            overlay.put_asset(asset_id, asset_state);
+        }
+        
+        // Apply all assets in batch
+        let _commit = self.snapshot_lock.write();
+        self.commit_overlay(overlay)
+    }
+
+    /// Get tokenomics instance
+    pub fn tokenomics(&self) -> &Arc<Tokenomics> {
+        &self.tokenomics
+    }
+
+    /// Get economy instance
+    pub fn economy(&self) -> &Arc<FogEconomy> {
+        &self.economy
+    }
+
+    /// Feature flags activated by upgrade signaling or pinned heights
+    pub fn features(&self) -> &FeatureRegistry {
+        &self.features
+    }
+
+    /// Get assets map (for API access)
+    pub fn assets(&self) -> &Arc<ShardedAssets> {
+        &self.assets
+    }
+
+    /// Give an account a starting balance outside any block: the pre-funded accounts of a dev
+    /// node (`haze --dev`), which start from an empty chain
+    pub fn seed_dev_account(&self, address: Address, balance: u64) -> Result<()> {
+        if self.current_height() > 0 {
+            return Err(HazeError::Config("Dev accounts can only be funded on an empty chain".to_string()));
+        }
+        // Keep governance tokens allocated at genesis
+        let mut account = self.get_account(&address).unwrap_or_default();
+        account.balance = balance;
+        self.put_seeded_account(address, account);
+        Ok(())
+    }
+
+    /// Credit the `governance_token.genesis` allocations on an empty chain. Balances are set,
+    /// not added, so this can run on every start before the first block.
+    pub fn seed_governance_genesis(&self) -> Result<()> {
+        let config = &self.config.governance_token;
+        if !config.enabled {
+            return Ok(());
+        }
+        self.tokenomics.set_governance_supply(config.total_supply());
+        if self.current_height() > 0 {
+            return Ok(());
+        }
+        for (address, amount) in config.allocations() {
+            let mut account = self.get_account(&address).unwrap_or_default();
+            account.governance_balance = amount;
+            self.put_seeded_account(address, account);
+        }
+        Ok(())
+    }
+
+    /// Governance weight of `address`: its staked governance tokens, or its HAZE stake when
+    /// the governance token is disabled
+    pub fn governance_weight(&self, address: &Address) -> u64 {
+        self.account_governance_weight(&self.get_account(address).unwrap_or_default())
+    }
+
+    fn account_governance_weight(&self, account: &AccountState) -> u64 {
+        if self.config.governance_token.enabled {
+            account.governance_staked
+        } else {
+            account.staked
+        }
+    }
+
+    /// Governance tokens staked by all accounts
+    pub fn total_governance_staked(&self) -> u64 {
+        self.accounts.iter().fold(0u64, |total, entry| total.saturating_add(entry.value().governance_staked))
+    }
+
+    /// Governance weight of all accounts
+    pub fn total_governance_weight(&self) -> u64 {
+        self.accounts.iter().fold(0u64, |total, entry| total.saturating_add(self.account_governance_weight(entry.value())))
+    }
+
+    /// Put in force the committee parameters whose votes hold more than two thirds of all
+    /// governance weight. Votes count with their voters' current weight.
+    fn tally_committee_votes(&self) {
+        let mut support: HashMap<crate::tokenomics::CommitteeParams, u64> = HashMap::new();
+        for (voter, params) in self.tokenomics.committee_votes() {
+            let weight = support.entry(params).or_default();
+            *weight = weight.saturating_add(self.governance_weight(&voter));
+        }
+        let total = self.total_governance_weight() as u128;
+        if let Some((params, weight)) = support.into_iter().find(|(_, weight)| *weight as u128 * 3 > total * 2) {
+            tracing::info!(
+                "Committee parameters changed by governance vote ({} of {} weight): min_stake {}, max_weight_bps {}",
+                weight, total, params.min_stake, params.max_weight_bps
+            );
+            self.tokenomics.set_committee_params(params);
+        }
+    }
+
+    /// Write an account outside of block application (genesis and dev funding, tests)
+    fn put_seeded_account(&self, address: Address, account: AccountState) {
+        self.account_hashes.set(&address, &account);
+        if let Ok(value) = bincode::serialize(&account) {
+            let _ = self.trees.accounts.insert(&address, &value);
+        }
+        self.accounts.insert(address, account);
+        self.bump_state_version();
+    }
+
+    /// Create test account (for testing only)
+    /// 
+    /// # Safety
+    /// This method bypasses normal transaction validation and should only be used in tests.
+    /// Available in test builds and integration tests.
+    pub fn create_test_account(&self, address: Address, balance: u64, nonce: u64) {
+        let account = AccountState {
+            balance,
+            nonce,
+            ..Default::default()
+        };
+        self.put_seeded_account(address, account);
+    }
+
+    /// Size and activity of each asset shard
+    pub fn asset_shard_stats(&self) -> Vec<ShardStats> {
+        self.assets.stats()
+    }
+
+    /// Root of each asset shard, in shard order
+    pub fn asset_shard_roots(&self) -> Vec<Hash> {
+        self.assets.shard_roots()
+    }
+
+    /// Compute state root hash
+    /// This creates a hash of the current state (root of the account hashes + the root of the
+    /// asset shard roots + height). Entity hashes are kept up to date in sparse merkle tries on
+    /// write, so this only rehashes the trie paths changed since the previous call.
+    pub fn compute_state_root(&self) -> Hash {
+        combine_state_root(self.account_hashes.root(), self.assets.root(), self.current_height())
+    }
+
+    /// Proof of an account, or of its absence, against the state root of the current height
+    pub fn prove_account(&self, address: &Address) -> StateProof {
+        let snapshot = self.read_snapshot();
+        let value = self.accounts.get(address).and_then(|account| bincode::serialize(account.value()).ok());
+        StateProof {
+            kind: ProofKind::Account,
+            key: *address,
+            height: snapshot.height,
+            value,
+            trie: self.account_hashes.prove(address),
+            account_root: self.account_hashes.root(),
+            shard_roots: self.assets.shard_roots(),
+            shard: None,
+        }
+    }
+
+    /// Proof of an asset against the state root of the current height (`None` if it does not
+    /// exist: its shard, and so the trie to prove absence in, depends on its collection)
+    pub fn prove_asset(&self, asset_id: &Hash) -> Option<StateProof> {
+        let snapshot = self.read_snapshot();
+        let value = bincode::serialize(&*self.assets.get(asset_id)?).ok();
+        let (shard, trie) = self.assets.prove(asset_id)?;
+        Some(StateProof {
+            kind: ProofKind::Asset,
+            key: *asset_id,
+            height: snapshot.height,
+            value,
+            trie,
+            account_root: self.account_hashes.root(),
+            shard_roots: self.assets.shard_roots(),
+            shard: Some(shard),
+        })
+    }
+
+    /// State root recomputed from every account and asset, ignoring the maintained hashes
+    #[cfg(test)]
+    pub(crate) fn compute_state_root_full(&self) -> Hash {
+        let accounts: Vec<(Address, AccountState)> =
+            self.accounts.iter().map(|entry| (*entry.key(), entry.value().clone())).collect();
+        let assets: Vec<(Hash, AssetState)> =
+            self.assets.iter().map(|entry| (*entry.key(), entry.value().clone())).collect();
+        full_state_root(&accounts, &assets, self.current_height())
+    }
+}
+
+impl Clone for StateManager {
+    fn clone(&self) -> Self {
+        Self {
+            db: self.db.clone(),
+            trees: self.trees.clone(),
+            storage: self.storage.clone(),
+            journal: self.journal.clone(),
+            blobs: self.blobs.clone(),
+            blob_stats: self.blob_stats.clone(),
+            config: self.config.clone(),
+            accounts: self.accounts.clone(),
+            account_hashes: self.account_hashes.clone(),
+            assets: self.assets.clone(),
+            blocks: self.blocks.clone(),
+            current_height: self.current_height.clone(),
+            tokenomics: self.tokenomics.clone(),
+            economy: self.economy.clone(),
+            features: self.features.clone(),
+            ws_tx: self.ws_tx.clone(),
+            asset_index_by_owner: self.asset_index_by_owner.clone(),
+            asset_index_by_density: self.asset_index_by_density.clone(),
+            asset_index_by_zone: self.asset_index_by_zone.clone(),
+            collection_traits: self.collection_traits.clone(),
+            collection_rarity: self.collection_rarity.clone(),
+            rarity_generation: self.rarity_generation.clone(),
+            collection_ownership: self.collection_ownership.clone(),
+            market_stats: self.market_stats.clone(),
+            asset_access_count: self.asset_access_count.clone(),
+            contracts: self.contracts.clone(),
+            dead_letters: self.dead_letters.clone(),
+            asset_hooks: self.asset_hooks.clone(),
+            recent_block_timestamps: self.recent_block_timestamps.clone(),
+            recent_fees: self.recent_fees.clone(),
+            latency: self.latency.clone(),
+            state_version: self.state_version.clone(),
+            snapshot_lock: self.snapshot_lock.clone(),
+            payment_requests: self.payment_requests.clone(),
+            asset_lineage: self.asset_lineage.clone(),
+            orders: self.orders.clone(),
+            open_orders: self.open_orders.clone(),
+            trades: self.trades.clone(),
+            order_sequence: self.order_sequence.clone(),
+            referrals: self.referrals.clone(),
+            referral_earnings: self.referral_earnings.clone(),
+            subscriptions: self.subscriptions.clone(),
+            subscriptions_due: self.subscriptions_due.clone(),
+            subscriptions_by_account: self.subscriptions_by_account.clone(),
+            paymaster_spend: self.paymaster_spend.clone(),
+            loot_boxes: self.loot_boxes.clone(),
+            leaderboards: self.leaderboards.clone(),
+            leaderboards_by_game: self.leaderboards_by_game.clone(),
+            seasons: self.seasons.clone(),
+            seasons_by_game: self.seasons_by_game.clone(),
+            seasons_starting: self.seasons_starting.clone(),
+            seasons_ending: self.seasons_ending.clone(),
+            frozen_collections: self.frozen_collections.clone(),
+            asset_pools: self.asset_pools.clone(),
+            asset_pools_by_game: self.asset_pools_by_game.clone(),
+            asset_stakes: self.asset_stakes.clone(),
+            asset_stakes_by_owner: self.asset_stakes_by_owner.clone(),
+            breedings: self.breedings.clone(),
+            breedings_due: self.breedings_due.clone(),
+            last_bred: self.last_bred.clone(),
+            upgrades: self.upgrades.clone(),
+            upgrades_due: self.upgrades_due.clone(),
+            upgrades_by_asset: self.upgrades_by_asset.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::types::{Address, BlockHeader, PROTOCOL_VERSION, STATE_VERSION};
+    use std::path::PathBuf;
+
+    fn create_test_address(seed: u8) -> Address {
+        let mut addr = [0u8; 32];
+        addr[0] = seed;
+        addr
+    }
+
+    /// Nonce the next transaction from `address` must carry
+    fn next_nonce(state_manager: &StateManager, address: &Address) -> u64 {
+        state_manager.get_account(address).map(|account| account.nonce).unwrap_or(0)
+    }
+
+    /// Block at `height` carrying `transactions` (hash derived from the height, not the network)
+    pub(crate) fn test_block(height: u64, transactions: Vec<Transaction>) -> Block {
+        Block {
+            header: BlockHeader {
+                hash: crate::types::sha256(&height.to_le_bytes()),
+                parent_hash: [0u8; 32],
+                height,
+                timestamp: 0,
+                validator: [0u8; 32],
+                merkle_root: [0u8; 32],
+                state_root: [0u8; 32],
+                wave_number: 0,
+                committee_id: 0,
+                protocol_version: PROTOCOL_VERSION,
+                state_version: STATE_VERSION,
+                signals: 0,
+            },
+            transactions,
+            dag_references: vec![],
+        }
+    }
+
+    fn create_test_config(test_name: &str) -> Config {
+        let mut config = Config::default();
+        // Use unique database path for each test
+        let test_db_path = format!("./haze_db_test_{}", test_name);
+        config.storage.db_path = PathBuf::from(test_db_path);
+        config
+    }
+
+    #[test]
+    fn test_state_manager_new() {
+        let config = create_test_config("new");
+        let state_manager = StateManager::new(&config).unwrap();
+        
+        assert_eq!(state_manager.current_height(), 0);
+        assert_eq!(state_manager.tokenomics().total_supply(), crate::tokenomics::INITIAL_SUPPLY);
+    }
+
+    #[test]
+    fn test_get_account_nonexistent() {
+        let config = create_test_config("get_account");
+        let state_manager = StateManager::new(&config).unwrap();
+        let address = create_test_address(1);
+        
+        // Account should not exist
+        assert!(state_manager.get_account(&address).is_none());
+    }
+
+    #[test]
+    fn test_compute_state_root() {
+        let config = create_test_config("state_root");
+        let state_manager = StateManager::new(&config).unwrap();
+        
+        // Compute state root for empty state
+        let state_root1 = state_manager.compute_state_root();
+        assert_ne!(state_root1, [0u8; 32]);
+        
+        // State root should be consistent for same state
+        let state_root2 = state_manager.compute_state_root();
+        assert_eq!(state_root1, state_root2);
+    }
+
+    /// The incrementally maintained root always equals a full recomputation.
+    #[test]
+    fn test_incremental_state_root_matches_full_recompute() {
+        use crate::types::{AssetAction, AssetData, DensityLevel};
+        let config = create_test_config("incremental_state_root");
+        let state_manager = StateManager::new(&config).unwrap();
+        let check = |step: &str| {
+            assert_eq!(state_manager.compute_state_root(), state_manager.compute_state_root_full(), "{}", step);
+        };
+        check("empty");
+
+        let owner = create_test_address(1);
+        let other = create_test_address(2);
+        state_manager.create_test_account(owner, 1_000_000, 0);
+        check("account created");
+        let asset_tx = |action: AssetAction, asset_id: Hash, game_id: &str| Transaction::MistbornAsset {
+            from: owner,
+            action,
+            asset_id,
+            data: AssetData {
+                density: DensityLevel::Ethereal,
+                metadata: [("name".to_string(), game_id.to_string())].into_iter().collect(),
+                attributes: vec![],
+                game_id: Some(game_id.to_string()),
+                owner,
+            },
+            fee: 0,
+            nonce: next_nonce(&state_manager, &owner),
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        let (sword, shield) = ([10u8; 32], [11u8; 32]);
+        state_manager.apply_transaction(&asset_tx(AssetAction::Create, sword, "racing")).unwrap();
+        state_manager.apply_transaction(&asset_tx(AssetAction::Create, shield, "puzzle")).unwrap();
+        check("assets created");
+        let root_before = state_manager.compute_state_root();
+
+        state_manager.apply_transaction(&asset_tx(AssetAction::Update, sword, "racing")).unwrap();
+        check("asset updated");
+        state_manager.create_asset_snapshot(&shield).unwrap();
+        check("asset snapshot");
+        state_manager.apply_transaction(&Transaction::Transfer {
+            from: owner,
+            to: other,
+            amount: 500,
+            fee: 10,
+            nonce: 3,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        }).unwrap();
+        check("transfer");
+        assert_ne!(state_manager.compute_state_root(), root_before);
+    }
+
+    /// An asset's trie entry does not depend on the iteration order of its maps.
+    #[test]
+    fn test_asset_entry_hash_independent_of_map_order() {
+        use crate::types::{AssetAction, AssetData, DensityLevel};
+        let config = create_test_config("asset_entry_hash_order");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        let state_manager = StateManager::new(&config).unwrap();
+        let owner = create_test_address(1);
+        state_manager.create_test_account(owner, 1_000_000, 0);
+        let keys: Vec<String> = (0..32).map(|i| format!("key{}", i)).collect();
+        let asset_id = [10u8; 32];
+        for (action, value) in [(AssetAction::Create, "1"), (AssetAction::Update, "2")] {
+            state_manager.apply_transaction(&Transaction::MistbornAsset {
+                from: owner,
+                action,
+                asset_id,
+                data: AssetData {
+                    density: DensityLevel::Dense,
+                    metadata: keys.iter().map(|key| (key.clone(), value.to_string())).collect(),
+                    attributes: vec![],
+                    game_id: None,
+                    owner,
+                },
+                fee: 0,
+                nonce: next_nonce(&state_manager, &owner),
+                chain_id: None,
+                valid_until_height: None,
+                signature: vec![1; 64],
+            }).unwrap();
+        }
+        let mut asset = state_manager.get_asset(&asset_id).unwrap();
+        asset.blob_refs = keys.iter().map(|key| (key.clone(), crate::types::sha256(key.as_bytes()))).collect();
+
+        // The same entries collected into fresh maps (new hasher seeds, reverse insertion)
+        let mut rebuilt = asset.clone();
+        rebuilt.blob_refs = asset.blob_refs.clone().into_iter().collect::<Vec<_>>().into_iter().rev().collect();
+        for entry in &mut rebuilt.history {
+            entry.changes = entry.changes.clone().into_iter().collect::<Vec<_>>().into_iter().rev().collect();
+        }
+        assert!(rebuilt.history.iter().any(|entry| entry.changes.len() > 1));
+        assert_eq!(trie::entry_hash(&asset_id, &asset), trie::entry_hash(&asset_id, &rebuilt));
+    }
+
+    /// Account and asset proofs verify against the state root, and only against it.
+    #[test]
+    fn test_state_proofs_verify_against_state_root() {
+        use crate::types::{AssetAction, AssetData, DensityLevel};
+        let config = create_test_config("state_proofs");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        let state_manager = StateManager::new(&config).unwrap();
+        let owner = create_test_address(1);
+        for seed in 1..=20u8 {
+            state_manager.create_test_account(create_test_address(seed), seed as u64 * 100_000, 0);
+        }
+        let sword = [10u8; 32];
+        state_manager.apply_transaction(&Transaction::MistbornAsset {
+            from: owner,
+            action: AssetAction::Create,
+            asset_id: sword,
+            data: AssetData {
+                density: DensityLevel::Ethereal,
+                metadata: HashMap::new(),
+                attributes: vec![],
+                game_id: Some("racing".to_string()),
+                owner,
+            },
+            fee: 0,
+            nonce: 0,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        }).unwrap();
+        let root = state_manager.compute_state_root();
+
+        let proof = state_manager.prove_account(&owner);
+        assert_eq!(proof.state_root(), root);
+        assert!(verify_proof(&proof, &root));
+        let account: AccountState = bincode::deserialize(proof.value.as_ref().unwrap()).unwrap();
+        assert_eq!(account.balance, state_manager.get_account(&owner).unwrap().balance);
+
+        // Tampered balance, other root, or the proof claimed for another key
+        let mut tampered = proof.clone();
+        tampered.value = Some(bincode::serialize(&AccountState { balance: 1, ..account }).unwrap());
+        assert!(!verify_proof(&tampered, &root));
+        assert!(!verify_proof(&proof, &[0u8; 32]));
+        let mut moved = proof.clone();
+        moved.key = create_test_address(2);
+        assert!(!verify_proof(&moved, &root));
+
+        // A missing account has an exclusion proof; a missing asset has none
+        let missing = state_manager.prove_account(&[0xeeu8; 32]);
+        assert!(missing.value.is_none());
+        assert!(verify_proof(&missing, &root));
+        assert!(state_manager.prove_asset(&[0xeeu8; 32]).is_none());
+
+        let asset_proof = state_manager.prove_asset(&sword).unwrap();
+        assert_eq!(asset_proof.kind, ProofKind::Asset);
+        assert!(verify_proof(&asset_proof, &root));
+        let mut wrong_shard = asset_proof.clone();
+        wrong_shard.shard = Some((asset_proof.shard.unwrap() + 1) % crate::asset_shards::ASSET_SHARD_COUNT);
+        assert!(!verify_proof(&wrong_shard, &root));
+
+        // Proofs are of the current state: an old proof fails against a newer root
+        state_manager.create_test_account(owner, 5, 0);
+        let new_root = state_manager.compute_state_root();
+        assert!(!verify_proof(&proof, &new_root));
+        assert!(verify_proof(&state_manager.prove_account(&owner), &new_root));
+    }
+
+    #[test]
+    fn test_list_accounts_paginates_and_filters() {
+        let config = create_test_config("account_index");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        let state_manager = StateManager::new(&config).unwrap();
+        for seed in 1..=4u8 {
+            state_manager.create_test_account(create_test_address(seed), seed as u64 * 1_000, 0);
+        }
+        // Accounts created by transactions are indexed too
+        state_manager.apply_transaction(&Transaction::Transfer {
+            from: create_test_address(4),
+            to: create_test_address(5),
+            amount: 500,
+            fee: 0,
+            nonce: 0,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        }).unwrap();
+        state_manager.apply_transaction(&Transaction::Stake {
+            from: create_test_address(2),
+            validator: create_test_address(2),
+            amount: 1_000,
+            fee: 0,
+            nonce: 0,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        }).unwrap();
+        assert_eq!(state_manager.account_count(), 5);
+
+        // Pages follow address order and the cursor of the last page is None
+        let all = AccountFilter::default();
+        let first = state_manager.list_accounts(None, 2, &all).unwrap();
+        let seeds = |page: &AccountPage| page.accounts.iter().map(|(a, _)| a[0]).collect::<Vec<_>>();
+        assert_eq!(seeds(&first), vec![1, 2]);
+        assert_eq!(first.next_cursor, Some(create_test_address(2)));
+        let second = state_manager.list_accounts(first.next_cursor.as_ref(), 2, &all).unwrap();
+        assert_eq!(seeds(&second), vec![3, 4]);
+        let last = state_manager.list_accounts(second.next_cursor.as_ref(), 2, &all).unwrap();
+        assert_eq!(seeds(&last), vec![5]);
+        assert_eq!(last.next_cursor, None);
+        assert_eq!(last.accounts[0].1.balance, 500);
+
+        let rich = AccountFilter { min_balance: Some(3_000), has_stake: None };
+        assert_eq!(seeds(&state_manager.list_accounts(None, 10, &rich).unwrap()), vec![3, 4]);
+        let stakers = AccountFilter { min_balance: None, has_stake: Some(true) };
+        let staked = state_manager.list_accounts(None, 10, &stakers).unwrap();
+        assert_eq!(seeds(&staked), vec![2]);
+        assert_eq!((staked.accounts[0].1.balance, staked.accounts[0].1.staked), (1_000, 1_000));
+        let unstaked = AccountFilter { min_balance: None, has_stake: Some(false) };
+        assert_eq!(state_manager.list_accounts(None, 10, &unstaked).unwrap().accounts.len(), 4);
+    }
+
+    #[test]
+    fn test_attribute_filter_compares_typed_values() {
+        use crate::types::{AssetData, Attribute, DensityLevel};
+        let data = AssetData {
+            density: DensityLevel::Ethereal,
+            metadata: HashMap::new(),
+            attributes: vec![
+                Attribute { name: "level".to_string(), value: AttributeValue::Int(10), rarity: None },
+                Attribute { name: "class".to_string(), value: AttributeValue::Enum("mage".to_string()), rarity: None },
+                Attribute { name: "code".to_string(), value: "10".into(), rarity: None },
+            ],
+            game_id: None,
+            owner: create_test_address(1),
+        };
+        let matches = |condition: &str| AttributeFilter::parse(condition).unwrap().matches(&data);
+        // Ints compare numerically, strings as text
+        assert!(matches("level>9") && matches("level>=10") && !matches("level<10"));
+        assert!(matches("code<9") && !matches("code>9"));
+        assert!(matches("class=mage") && matches("class!=rogue") && !matches("class=rogue"));
+        // Missing attributes and literals of another type never match
+        assert!(!matches("speed>0") && !matches("level=ten") && !matches("level!=ten"));
+        for invalid in ["level", "=5", ""] {
+            assert!(AttributeFilter::parse(invalid).is_none());
+        }
+    }
+
+    #[test]
+    fn test_current_height() {
+        let config = create_test_config("height");
+        let state_manager = StateManager::new(&config).unwrap();
+        
+        // Initial height should be 0
+        assert_eq!(state_manager.current_height(), 0);
+    }
+
+    /// apply_block requires strict sequential height: block.height == current_height + 1.
+    #[test]
+    fn test_apply_block_strict_sequential_height() {
+        use crate::types::{Block, BlockHeader};
+        let config = create_test_config("apply_block_height");
+        let state_manager = StateManager::new(&config).unwrap();
+        assert_eq!(state_manager.current_height(), 0);
+        let addr = create_test_address(1);
+        let block = Block {
+            header: BlockHeader {
+                hash: [0u8; 32],
+                parent_hash: [0u8; 32],
+                height: 2, // wrong: next expected is 1
+                timestamp: 0,
+                validator: addr,
+                merkle_root: [0u8; 32],
+                state_root: [0u8; 32],
+                wave_number: 0,
+                committee_id: 0,
+                protocol_version: PROTOCOL_VERSION,
+                state_version: STATE_VERSION,
+                signals: 0,
+            },
+            transactions: vec![],
+            dag_references: vec![],
+        };
+        let result = state_manager.apply_block(&block);
+        assert!(result.is_err());
+        let err_msg = result.unwrap_err().to_string();
+        assert!(err_msg.contains("current_height") || err_msg.contains("sequential"), "expected height/sequential error, got: {}", err_msg);
+    }
+
+    /// A block is not committed while a read snapshot is held.
+    #[test]
+    fn test_read_snapshot_defers_block_commit() {
+        use crate::types::{Block, BlockHeader};
+        let config = create_test_config("read_snapshot");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        let state_manager = StateManager::new(&config).unwrap();
+        let block = Block {
+            header: BlockHeader {
+                hash: [1u8; 32],
+                parent_hash: [0u8; 32],
+                height: 1,
+                timestamp: 0,
+                validator: create_test_address(1),
+                merkle_root: [0u8; 32],
+                state_root: [0u8; 32],
+                wave_number: 0,
+                committee_id: 0,
+                protocol_version: PROTOCOL_VERSION,
+                state_version: STATE_VERSION,
+                signals: 0,
+            },
+            transactions: vec![],
+            dag_references: vec![],
+        };
+
+        let snapshot = state_manager.read_snapshot();
+        assert_eq!(snapshot.height, 0);
+        let applier = {
+            let state_manager = state_manager.clone();
+            std::thread::spawn(move || state_manager.apply_block(&block))
+        };
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        assert_eq!(state_manager.current_height(), 0);
+        assert!(state_manager.get_block(&[1u8; 32]).is_none());
+
+        drop(snapshot);
+        applier.join().unwrap().unwrap();
+        assert_eq!(state_manager.read_snapshot().height, 1);
+    }
+
+    /// A failing transaction rejects its block and is recorded in the dead-letter store.
+    #[test]
+    fn test_apply_block_dead_letters_failed_transaction() {
+        use crate::types::{Block, BlockHeader};
+        let config = create_test_config("dead_letter");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        let from = create_test_address(1);
+        let to = create_test_address(2);
+        // Sender has no balance, so the transfer fails during application
+        let tx = Transaction::Transfer {
+            from,
+            to,
+            amount: 1_000,
+            fee: 0,
+            nonce: 0,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        let tx_hash = tx.hash();
+        let block = Block {
+            header: BlockHeader {
+                hash: [7u8; 32],
+                parent_hash: [0u8; 32],
+                height: 1,
+                timestamp: 0,
+                validator: from,
+                merkle_root: [0u8; 32],
+                state_root: [0u8; 32],
+                wave_number: 0,
+                committee_id: 0,
+                protocol_version: PROTOCOL_VERSION,
+                state_version: STATE_VERSION,
+                signals: 0,
+            },
+            transactions: vec![tx],
+            dag_references: vec![],
+        };
+
+        let state_manager = StateManager::new(&config).unwrap();
+        assert!(state_manager.apply_block(&block).is_err());
+        assert_eq!(state_manager.current_height(), 0);
+
+        let entry = state_manager.get_dead_letter(&tx_hash).unwrap();
+        assert_eq!(entry.block_height, 1);
+        assert_eq!(entry.block_hash, [7u8; 32]);
+        assert_eq!(entry.tx_index, 0);
+        assert_eq!(entry.attempts, 1);
+        assert!(entry.error.contains("Insufficient balance"), "unexpected error: {}", entry.error);
+        assert_eq!(entry.error_code, 2002);
+        assert!(entry.error_params.contains_key("required"));
+
+        // The failed transaction still has a receipt, built from its dead letter
+        let receipt = state_manager.get_receipt(&tx_hash).unwrap();
+        assert_eq!(receipt.status, ReceiptStatus::Failed);
+        assert_eq!((receipt.block_height, receipt.tx_index), (1, 0));
+        assert_eq!(receipt.error_code, Some(2002));
+        assert!(state_manager.get_receipt(&[9u8; 32]).is_none());
+
+        // Dead letters are persisted, reloaded on startup and can be removed once handled
+        state_manager.dead_letters.clear();
+        state_manager.load_dead_letters_from_db().unwrap();
+        assert_eq!(state_manager.get_dead_letters(0).len(), 1);
+        assert!(state_manager.remove_dead_letter(&tx_hash).unwrap().is_some());
+        assert!(state_manager.get_dead_letter(&tx_hash).is_none());
+        assert_eq!(state_manager.trees.dead_letters.len(), 0);
+    }
+
+    /// A mid-block failure leaves no trace: earlier transactions, gas burns and
+    /// block height are all rolled back; the same block without the bad tx commits.
+    #[test]
+    fn test_apply_block_is_atomic_on_mid_block_failure() {
+        use crate::types::{Block, BlockHeader};
+        let config = create_test_config("atomic_block");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        let state_manager = StateManager::new(&config).unwrap();
+
+        let owner = create_test_address(1);
+        let recipient = create_test_address(2);
+        state_manager.create_test_account(owner, 100_000, 0);
+
+        let asset_id = crate::types::sha256(b"atomic_asset");
+        let create = |nonce: u64| Transaction::MistbornAsset {
+            from: owner,
+            action: crate::types::AssetAction::Create,
+            asset_id,
+            data: crate::types::AssetData {
+                density: crate::types::DensityLevel::Ethereal,
+                metadata: std::collections::HashMap::new(),
+                attributes: vec![],
+                game_id: None,
+                owner,
+            },
+            fee: 0,
+            nonce,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        state_manager.apply_transaction(&create(0)).unwrap();
+
+        let transfer = |nonce: u64| Transaction::Transfer {
+            from: owner,
+            to: recipient,
+            amount: 1_000,
+            fee: 10,
+            nonce,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        let make_block = |transactions: Vec<Transaction>| Block {
+            header: BlockHeader {
+                hash: [9u8; 32],
+                parent_hash: [0u8; 32],
+                height: 1,
+                timestamp: 0,
+                validator: owner,
+                merkle_root: [0u8; 32],
+                state_root: [0u8; 32],
+                wave_number: 0,
+                committee_id: 0,
+                protocol_version: PROTOCOL_VERSION,
+                state_version: STATE_VERSION,
+                signals: 0,
+            },
+            transactions,
+            dag_references: vec![],
+        };
+
+        let balance_before = state_manager.get_account(&owner).unwrap().balance;
+        let burned_before = state_manager.tokenomics().burned_supply();
+        let root_before = state_manager.compute_state_root();
+
+        // Re-creating the asset fails after its gas fee was charged in the overlay
+        let recreate_tx = create(2);
+        let bad_block = make_block(vec![transfer(1), recreate_tx.clone(), transfer(3)]);
+        assert!(state_manager.apply_block(&bad_block).is_err());
+
+        assert_eq!(state_manager.current_height(), 0);
+        assert_eq!(state_manager.get_account(&owner).unwrap().balance, balance_before);
+        assert_eq!(state_manager.get_account(&owner).unwrap().nonce, 1);
+        assert!(state_manager.get_account(&recipient).is_none());
+        assert_eq!(state_manager.tokenomics().burned_supply(), burned_before);
+        assert_eq!(state_manager.search_assets_by_owner(&owner).len(), 1);
+        assert!(state_manager.get_block(&[9u8; 32]).is_none());
+        assert_eq!(state_manager.compute_state_root(), root_before);
+        assert_eq!(state_manager.get_dead_letter(&recreate_tx.hash()).unwrap().tx_index, 1);
+
+        // Later transactions in a block see earlier ones (nonce 2 follows nonce 1)
+        let good_block = make_block(vec![transfer(1), transfer(2)]);
+        state_manager.apply_block(&good_block).unwrap();
+        assert_eq!(state_manager.current_height(), 1);
+        assert_eq!(state_manager.get_account(&owner).unwrap().balance, balance_before - 2 * 1_010);
+        assert_eq!(state_manager.get_account(&recipient).unwrap().balance, 2_000);
+        assert_eq!(state_manager.tokenomics().burned_supply(), burned_before + 10);
+    }
+
+    /// Applying the same block twice is a no-op the second time, also after a restart.
+    #[test]
+    fn test_apply_block_idempotent_by_hash() {
+        use crate::types::{Block, BlockHeader};
+        let config = create_test_config("applied_blocks");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        let state_manager = StateManager::new(&config).unwrap();
+
+        let from = create_test_address(1);
+        let to = create_test_address(2);
+        state_manager.create_test_account(from, 10_000, 0);
+        let block = Block {
+            header: BlockHeader {
+                hash: [5u8; 32],
+                parent_hash: [0u8; 32],
+                height: 1,
+                timestamp: 0,
+                validator: from,
+                merkle_root: [0u8; 32],
+                state_root: [0u8; 32],
+                wave_number: 0,
+                committee_id: 0,
+                protocol_version: PROTOCOL_VERSION,
+                state_version: STATE_VERSION,
+                signals: 0,
+            },
+            transactions: vec![Transaction::Transfer {
+                from,
+                to,
+                amount: 1_000,
+                fee: 10,
+                nonce: 0,
+                chain_id: None,
+                valid_until_height: None,
+                signature: vec![1; 64],
+            }],
+            dag_references: vec![],
+        };
+
+        assert!(!state_manager.is_block_applied(&block.header.hash).unwrap());
+        state_manager.apply_block(&block).unwrap();
+        assert!(state_manager.is_block_applied(&block.header.hash).unwrap());
+        let supply = state_manager.tokenomics().total_supply();
+
+        // Second application succeeds without touching state
+        state_manager.apply_block(&block).unwrap();
+        assert_eq!(state_manager.current_height(), 1);
+        assert_eq!(state_manager.get_account(&to).unwrap().balance, 1_000);
+        assert_eq!(state_manager.tokenomics().total_supply(), supply);
+        assert!(state_manager.get_dead_letter(&block.transactions[0].hash()).is_none());
+    }
+
+    /// Committed assets are mirrored in the `assets` tree, and stray entries are repaired.
+    #[test]
+    fn test_assets_persisted_and_repaired() {
+        let config = create_test_config("assets_tree");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        let state_manager = StateManager::new(&config).unwrap();
+
+        let owner = create_test_address(1);
+        state_manager.create_test_account(owner, 100_000, 0);
+        let asset_id = crate::types::sha256(b"persisted_asset");
+        let tx = Transaction::MistbornAsset {
+            from: owner,
+            action: crate::types::AssetAction::Create,
+            asset_id,
+            data: crate::types::AssetData {
+                density: crate::types::DensityLevel::Ethereal,
+                metadata: std::collections::HashMap::new(),
+                attributes: vec![],
+                game_id: None,
+                owner,
+            },
+            fee: 0,
+            nonce: 0,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        state_manager.apply_transaction(&tx).unwrap();
+
+        let stored = state_manager.trees.assets.get(&asset_id).unwrap().unwrap();
+        let stored: AssetState = bincode::deserialize(&stored).unwrap();
+        assert_eq!(stored.owner, owner);
+        assert_eq!(state_manager.repair_asset_index().unwrap(), 0);
+
+        // An entry for an asset that does not exist, and a missing one, are both repaired
+        state_manager.trees.assets.insert(&[7u8; 32], b"stale").unwrap();
+        state_manager.trees.assets.remove(&asset_id).unwrap();
+        assert_eq!(state_manager.repair_asset_index().unwrap(), 2);
+        assert!(state_manager.trees.assets.get(&[7u8; 32]).unwrap().is_none());
+        assert!(state_manager.trees.assets.get(&asset_id).unwrap().is_some());
+        assert_eq!(state_manager.repair_asset_index().unwrap(), 0);
+    }
+
+    /// After a clean shutdown the state is loaded from the trees and the checkpoint instead of
+    /// replaying blocks; without a checkpoint every block is replayed to the same state.
+    #[test]
+    fn test_restart_restores_checkpoint_without_replay() {
+        let mut config = create_test_config("checkpoint_restart");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        // Free transactions, so replay from an empty state needs no funded accounts
+        config.vm.gas_price = 0;
+        let owner = create_test_address(1);
+        let asset_id = crate::types::sha256(b"checkpointed_asset");
+        let create = Transaction::MistbornAsset {
+            from: owner,
+            action: crate::types::AssetAction::Create,
+            asset_id,
+            data: crate::types::AssetData {
+                density: crate::types::DensityLevel::Light,
+                metadata: std::collections::HashMap::new(),
+                attributes: vec![crate::types::Attribute {
+                    name: "rarity".to_string(),
+                    value: AttributeValue::String("rare".to_string()),
+                    rarity: None,
+                }],
+                game_id: Some("checkpoint_game".to_string()),
+                owner,
+            },
+            fee: 0,
+            nonce: 0,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        let replayed = |state: &StateManager| {
+            let mut rendered = String::new();
+            state.latency.block_apply.render(&mut rendered);
+            rendered.contains("operation=\"replay\"")
+        };
+
+        let state_manager = StateManager::new(&config).unwrap();
+        state_manager.apply_block(&test_block(1, vec![create.clone()])).unwrap();
+        let root = state_manager.compute_state_root();
+        let supply = state_manager.tokenomics().total_supply();
+        state_manager.write_checkpoint().unwrap();
+        drop(state_manager);
+
+        let state_manager = StateManager::new(&config).unwrap();
+        assert!(!replayed(&state_manager));
+        assert_eq!(state_manager.current_height(), 1);
+        assert_eq!(state_manager.compute_state_root(), root);
+        assert_eq!(state_manager.compute_state_root_full(), root);
+        assert_eq!(state_manager.tokenomics().total_supply(), supply);
+        assert_eq!(state_manager.search_assets_by_owner(&owner), vec![asset_id]);
+        assert_eq!(state_manager.search_assets_by_density(crate::types::DensityLevel::Light), vec![asset_id]);
+        assert_eq!(state_manager.assets().collection("checkpoint_game"), Some(vec![asset_id]));
+        let (size, traits) = state_manager.get_collection_traits("checkpoint_game").unwrap();
+        assert_eq!((size, traits["rarity"]["rare"]), (1, 1));
+        let receipt = state_manager.get_receipt(&create.hash()).unwrap();
+        assert_eq!((receipt.block_height, receipt.execution.events.len()), (1, 1));
+        assert!(state_manager.is_block_applied(&test_block(1, vec![]).header.hash).unwrap());
+
+        // The checkpoint was consumed: a restart without a new one replays every block
+        state_manager.apply_block(&test_block(2, vec![])).unwrap();
+        let root = state_manager.compute_state_root();
+        let supply = state_manager.tokenomics().total_supply();
+        drop(state_manager);
+
+        let state_manager = StateManager::new(&config).unwrap();
+        assert!(replayed(&state_manager));
+        assert_eq!(state_manager.current_height(), 2);
+        assert_eq!(state_manager.compute_state_root(), root);
+        assert_eq!(state_manager.tokenomics().total_supply(), supply);
+        assert_eq!(state_manager.search_assets_by_owner(&owner), vec![asset_id]);
+    }
+
+    #[test]
+    fn test_receipts_are_stored_with_their_block() {
+        let mut config = create_test_config("stored_receipts");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        config.vm.gas_price = 0;
+        let owner = create_test_address(1);
+        let create = |nonce: u64| Transaction::MistbornAsset {
+            from: owner,
+            action: crate::types::AssetAction::Create,
+            asset_id: crate::types::sha256(b"stored_receipt_asset"),
+            data: crate::types::AssetData {
+                density: crate::types::DensityLevel::Ethereal,
+                metadata: std::collections::HashMap::new(),
+                attributes: vec![],
+                game_id: None,
+                owner,
+            },
+            fee: 0,
+            nonce,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        let state_manager = StateManager::new(&config).unwrap();
+        let applied = create(0);
+        state_manager.apply_block(&test_block(1, vec![applied.clone()])).unwrap();
+        assert!(state_manager.trees.tx_locations.contains_key(&applied.hash()).unwrap());
+        assert!(state_manager.trees.receipts.contains_key(&block_key(1)).unwrap());
+
+        // A block that fails stores no receipt; the failure is a dead letter
+        let duplicate = create(1);
+        assert!(state_manager.apply_block(&test_block(2, vec![duplicate.clone()])).is_err());
+        assert!(!state_manager.trees.tx_locations.contains_key(&duplicate.hash()).unwrap());
+        assert!(!state_manager.trees.receipts.contains_key(&block_key(2)).unwrap());
+        assert_eq!(state_manager.get_receipt(&duplicate.hash()).unwrap().status, ReceiptStatus::Failed);
+        drop(state_manager);
+
+        // Receipts are read from their trees, not rebuilt in memory
+        let state_manager = StateManager::new(&config).unwrap();
+        let receipt = state_manager.get_receipt(&applied.hash()).unwrap();
+        assert_eq!((receipt.status, receipt.block_height, receipt.tx_index), (ReceiptStatus::Executed, 1, 0));
+        assert_eq!(receipt.execution.events.len(), 1);
+        assert_eq!(state_manager.get_transaction_height(&applied.hash()), Some(1));
+    }
+
+    /// Blocks are read back from sled: shared while cached, headers decoded on their own.
+    #[test]
+    fn test_blocks_read_lazily_from_storage() {
+        use crate::types::{Block, BlockHeader};
+        let config = create_test_config("block_store");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        let state_manager = StateManager::new(&config).unwrap();
+
+        let from = create_test_address(1);
+        state_manager.create_test_account(from, 10_000, 0);
+        let block = Block {
+            header: BlockHeader {
+                hash: [6u8; 32],
+                parent_hash: [0u8; 32],
+                height: 1,
+                timestamp: 42,
+                validator: from,
+                merkle_root: [0u8; 32],
+                state_root: [0u8; 32],
+                wave_number: 0,
+                committee_id: 0,
+                protocol_version: PROTOCOL_VERSION,
+                state_version: STATE_VERSION,
+                signals: 0,
+            },
+            transactions: vec![Transaction::Transfer {
+                from,
+                to: create_test_address(2),
+                amount: 1_000,
+                fee: 10,
+                nonce: 0,
+                chain_id: None,
+                valid_until_height: None,
+                signature: vec![1; 64],
+            }],
+            dag_references: vec![],
+        };
+        state_manager.apply_block(&block).unwrap();
+
+        // The stored encoding is the wire format, and its header decodes without the body
+        let raw = state_manager.blocks.raw_by_height(1).unwrap();
+        assert_eq!(raw.as_ref(), block.to_wire().as_slice());
+        assert_eq!(Block::header_from_wire(&raw).unwrap().timestamp, 42);
+        let header = state_manager.get_block_header(&block.header.hash).unwrap();
+        assert_eq!((header.height, header.timestamp), (1, 42));
+        assert_eq!(state_manager.get_block_header_by_height(1).unwrap().hash, block.header.hash);
+
+        // Repeated reads share one decoded block
+        let first = state_manager.get_block(&block.header.hash).unwrap();
+        let second = state_manager.get_block_by_height(1).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(first.transactions.len(), 1);
+        assert!(state_manager.has_block(&block.header.hash));
+
+        assert!(state_manager.get_block_by_height(2).is_none());
+        assert!(state_manager.get_block_header(&[7u8; 32]).is_none());
+        assert!(!state_manager.has_block(&[7u8; 32]));
+
+        // Height ranges are one ordered scan, sharing cached blocks
+        let mut next = block.clone();
+        next.header.hash = [8u8; 32];
+        next.header.parent_hash = block.header.hash;
+        next.header.height = 2;
+        next.transactions.clear();
+        state_manager.apply_block(&next).unwrap();
+        let range = state_manager.get_blocks_by_height_range(1, 2);
+        assert_eq!(range.iter().map(|b| b.header.height).collect::<Vec<_>>(), vec![1, 2]);
+        assert!(Arc::ptr_eq(&range[0], &first));
+        assert_eq!(state_manager.get_blocks_by_height_range(2, 10).len(), 1);
+        assert!(state_manager.get_blocks_by_height_range(2, 1).is_empty());
+    }
+
+    #[test]
+    fn test_merge_policy_applied_and_recorded() {
+        use crate::types::{AssetAction, AssetData, DensityLevel};
+        let config = create_test_config("merge_policy");
+        let state_manager = StateManager::new(&config).unwrap();
+        let owner = create_test_address(1);
+        state_manager.create_test_account(owner, 1_000_000, 0);
+        
+        let asset_tx = |action: AssetAction, asset_id: Hash, metadata: Vec<(&str, String)>| Transaction::MistbornAsset {
+            from: owner,
+            action,
+            asset_id,
+            data: AssetData {
+                density: DensityLevel::Ethereal,
+                metadata: metadata.into_iter().map(|(k, v)| (k.to_string(), v)).collect(),
+                attributes: vec![],
+                game_id: None,
+                owner,
+            },
+            fee: 0,
+            nonce: next_nonce(&state_manager, &owner),
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        let asset_id_1 = crate::types::sha256(b"policy_asset1");
+        let asset_id_2 = crate::types::sha256(b"policy_asset2");
+        state_manager.apply_transaction(&asset_tx(AssetAction::Create, asset_id_1, vec![("name", "Sword".to_string())])).unwrap();
+        state_manager.apply_transaction(&asset_tx(AssetAction::Create, asset_id_2, vec![("name", "Shield".to_string())])).unwrap();
+        
+        let merge = |policy: &str| asset_tx(AssetAction::Merge, asset_id_1, vec![
+            ("_other_asset_id", hex::encode(asset_id_2)),
+            ("_merge_policy", policy.to_string()),
+        ]);
+        let err = state_manager.apply_transaction(&merge("fail-on-conflict")).unwrap_err();
+        assert!(err.to_string().contains("metadata.name"), "got: {}", err);
+        assert!(state_manager.get_asset(&asset_id_2).is_some());
+        assert!(state_manager.apply_transaction(&merge("newest")).is_err());
+        
+        state_manager.apply_transaction(&merge("prefer-other")).unwrap();
+        let merged = state_manager.get_asset(&asset_id_1).unwrap();
+        assert_eq!(merged.data.metadata.get("name"), Some(&"Shield".to_string()));
+        let entry = merged.history.last().unwrap();
+        assert_eq!(entry.changes.get("merge_policy"), Some(&"prefer-other".to_string()));
+        assert_eq!(entry.changes.get("conflicts"), Some(&"metadata.name".to_string()));
+    }
+
+    #[test]
+    fn test_private_fields_commit_and_reveal() {
+        use crate::assets::{metadata_commitment, COMMITMENTS_KEY, REVEAL_SALTS_KEY};
+        use crate::types::{AssetAction, AssetData, DensityLevel};
+        let config = create_test_config("private_fields");
+        let state_manager = StateManager::new(&config).unwrap();
+        let owner = create_test_address(1);
+        state_manager.create_test_account(owner, 1_000_000, 0);
+
+        let asset_tx = |action: AssetAction, asset_id: Hash, metadata: Vec<(&str, String)>| Transaction::MistbornAsset {
+            from: owner,
+            action,
+            asset_id,
+            data: AssetData {
+                density: DensityLevel::Ethereal,
+                metadata: metadata.into_iter().map(|(k, v)| (k.to_string(), v)).collect(),
+                attributes: vec![],
+                game_id: None,
+                owner,
+            },
+            fee: 0,
+            nonce: next_nonce(&state_manager, &owner),
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        let chest = crate::types::sha256(b"loot_chest");
+        let salt = [7u8; 16];
+        let commitment = metadata_commitment(&salt, "legendary sword");
+        let commitments = format!("{{\"loot\":\"{}\"}}", hex::encode(commitment));
+        state_manager.apply_transaction(&asset_tx(AssetAction::Create, chest, vec![
+            ("name", "Chest".to_string()),
+            ("loot", "c1ph3rt3xt".to_string()),
+            (COMMITMENTS_KEY, commitments),
+        ])).unwrap();
+        let asset = state_manager.get_asset(&chest).unwrap();
+        assert!(!asset.data.metadata.contains_key(COMMITMENTS_KEY));
+        assert_eq!(asset.private_fields["loot"], PrivateField { commitment, revealed_at: None });
+
+        // Committed values cannot be overwritten in plain, merged or split
+        let err = state_manager.apply_transaction(&asset_tx(AssetAction::Update, chest, vec![("loot", "junk".to_string())])).unwrap_err();
+        assert!(err.to_string().contains("committed"), "got: {}", err);
+        let split = asset_tx(AssetAction::Split, chest, vec![("_components", "lid,box".to_string())]);
+        let err = state_manager.apply_transaction(&split).unwrap_err();
+        assert!(err.to_string().contains("reveal it first"), "got: {}", err);
+
+        let reveal = |plaintext: &str, salt: &[u8]| asset_tx(AssetAction::Reveal, chest, vec![
+            ("loot", plaintext.to_string()),
+            (REVEAL_SALTS_KEY, format!("{{\"loot\":\"{}\"}}", hex::encode(salt))),
+        ]);
+        let err = state_manager.apply_transaction(&reveal("common stick", &salt)).unwrap_err();
+        assert!(err.to_string().contains("does not match its commitment"), "got: {}", err);
+        assert!(state_manager.apply_transaction(&reveal("legendary sword", &[8u8; 16])).is_err());
+        assert!(state_manager.apply_transaction(&reveal("legendary sword", &salt[..8])).is_err());
+
+        state_manager.apply_transaction(&reveal("legendary sword", &salt)).unwrap();
+        let asset = state_manager.get_asset(&chest).unwrap();
+        assert_eq!(asset.data.metadata["loot"], "legendary sword");
+        assert_eq!(asset.private_fields["loot"].revealed_at, Some(state_manager.current_height() + 1));
+        assert!(matches!(asset.history.last().unwrap().action, AssetAction::Reveal));
+        assert!(state_manager.apply_transaction(&reveal("legendary sword", &salt)).is_err());
+
+        // A plain write makes a revealed field ordinary again
+        state_manager.apply_transaction(&asset_tx(AssetAction::Update, chest, vec![("loot", "sold".to_string())])).unwrap();
+        assert!(state_manager.get_asset(&chest).unwrap().private_fields.is_empty());
+    }
+
+    #[test]
+    fn test_zone_migration_fees_and_index() {
+        use crate::config::ZoneConfig;
+        use crate::economy::zone_treasury_address;
+        use crate::types::{AssetAction, AssetData, DensityLevel};
+        let mut config = create_test_config("zone_migration");
+        let zone = |zone_id: &str, entry_fee, exit_fee| ZoneConfig {
+            game_id: "game".to_string(),
+            zone_id: zone_id.to_string(),
+            entry_fee,
+            exit_fee,
+            sale_fee_modifier_bps: 10_000,
+        };
+        config.economy.zones = vec![zone("forest", 100, 40), zone("desert", 250, 0)];
+        let state_manager = StateManager::new(&config).unwrap();
+        let owner = create_test_address(1);
+        state_manager.create_test_account(owner, 1_000_000, 0);
+        
+        let asset_id = crate::types::sha256(b"zone_asset");
+        let asset_tx = |action: AssetAction, zone: &str| Transaction::MistbornAsset {
+            from: owner,
+            action,
+            asset_id,
+            data: AssetData {
+                density: DensityLevel::Ethereal,
+                metadata: HashMap::from([("_zone".to_string(), zone.to_string())]),
+                attributes: vec![],
+                game_id: Some("game".to_string()),
+                owner,
+            },
+            fee: 0,
+            nonce: next_nonce(&state_manager, &owner),
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        let treasury_balance = |zone_id: &str| {
+            state_manager.get_account(&zone_treasury_address("game", zone_id)).map(|a| a.balance).unwrap_or(0)
+        };
+        
+        assert!(state_manager.apply_transaction(&asset_tx(AssetAction::Create, "swamp")).is_err());
+        state_manager.apply_transaction(&asset_tx(AssetAction::Create, "forest")).unwrap();
+        assert_eq!(treasury_balance("forest"), 100);
+        assert_eq!(state_manager.search_assets_by_zone("game", "forest"), vec![asset_id]);
+        
+        // Migrating pays the forest exit fee and the desert entry fee
+        state_manager.apply_transaction(&asset_tx(AssetAction::Update, "desert")).unwrap();
+        assert_eq!(treasury_balance("forest"), 140);
+        assert_eq!(treasury_balance("desert"), 250);
+        assert!(state_manager.search_assets_by_zone("game", "forest").is_empty());
+        assert_eq!(state_manager.search_assets_by_zone("game", "desert"), vec![asset_id]);
+        let asset = state_manager.get_asset(&asset_id).unwrap();
+        assert_eq!(asset.history.last().unwrap().changes.get("_zone"), Some(&"desert".to_string()));
+        
+        // Re-sending the current zone charges nothing; an empty zone leaves it
+        state_manager.apply_transaction(&asset_tx(AssetAction::Update, "desert")).unwrap();
+        assert_eq!(treasury_balance("desert"), 250);
+        state_manager.apply_transaction(&asset_tx(AssetAction::Update, "")).unwrap();
+        assert!(state_manager.search_assets_by_zone("game", "desert").is_empty());
+        assert!(!state_manager.get_asset(&asset_id).unwrap().data.metadata.contains_key("_zone"));
+    }
+
+    #[test]
+    fn test_merge_assets() {
+        let config = create_test_config("merge");
+        let state_manager = StateManager::new(&config).unwrap();
+        
+        let owner = create_test_address(1);
+        // Create account with balance for gas fees
+        state_manager.create_test_account(owner, 100_000, 0);
+        
+        // Create first asset
+        let asset_id_1 = crate::types::sha256(b"asset1");
+        let tx1 = Transaction::MistbornAsset {
+            from: owner,
+            action: crate::types::AssetAction::Create,
+            asset_id: asset_id_1,
+            data: crate::types::AssetData {
+                density: crate::types::DensityLevel::Ethereal,
+                metadata: {
+                    let mut m = std::collections::HashMap::new();
+                    m.insert("name".to_string(), "Asset 1".to_string());
+                    m.insert("type".to_string(), "sword".to_string());
+                    m
+                },
+                attributes: vec![crate::types::Attribute {
+                    name: "damage".to_string(),
+                    value: "10".into(),
+                    rarity: None,
+                }],
+                game_id: Some("game1".to_string()),
+                owner,
+            },
+            fee: 0,
+            nonce: next_nonce(&state_manager, &owner),
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64], // Dummy signature for test
+        };
+        
+        state_manager.apply_transaction(&tx1).unwrap();
+        assert!(state_manager.get_asset(&asset_id_1).is_some());
+        
+        // Create second asset
+        let asset_id_2 = crate::types::sha256(b"asset2");
+        let tx2 = Transaction::MistbornAsset {
+            from: owner,
+            action: crate::types::AssetAction::Create,
+            asset_id: asset_id_2,
+            data: crate::types::AssetData {
+                density: crate::types::DensityLevel::Light,
+                metadata: {
+                    let mut m = std::collections::HashMap::new();
+                    m.insert("name".to_string(), "Asset 2".to_string());
+                    m.insert("rarity".to_string(), "epic".to_string());
+                    m
+                },
+                attributes: vec![crate::types::Attribute {
+                    name: "defense".to_string(),
+                    value: "5".into(),
+                    rarity: None,
+                }],
+                game_id: Some("game1".to_string()),
+                owner,
+            },
+            fee: 0,
+            nonce: next_nonce(&state_manager, &owner),
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![2; 64],
+        };
+        
+        state_manager.apply_transaction(&tx2).unwrap();
+        assert!(state_manager.get_asset(&asset_id_2).is_some());
+        
+        // Merge assets
+        let mut merge_metadata = std::collections::HashMap::new();
+        merge_metadata.insert("_other_asset_id".to_string(), hex::encode(asset_id_2));
+        
+        let merge_tx = Transaction::MistbornAsset {
+            from: owner,
+            action: crate::types::AssetAction::Merge,
+            asset_id: asset_id_1,
+            data: crate::types::AssetData {
+                density: crate::types::DensityLevel::Ethereal,
+                metadata: merge_metadata,
+                attributes: vec![],
+                game_id: None,
+                owner,
+            },
+            fee: 0,
+            nonce: next_nonce(&state_manager, &owner),
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![3; 64],
+        };
+        
+        state_manager.apply_transaction(&merge_tx).unwrap();
+        
+        // Check that merged asset exists and has combined data
+        let merged_asset = state_manager.get_asset(&asset_id_1).unwrap();
+        assert_eq!(merged_asset.owner, owner);
+        assert!(merged_asset.data.metadata.contains_key("name"));
+        assert!(merged_asset.data.metadata.contains_key("type"));
+        assert!(merged_asset.data.metadata.contains_key("rarity"));
+        // Density should be increased to Light (from asset 2)
+        assert_eq!(merged_asset.data.density, crate::types::DensityLevel::Light);
+        // Should have both attributes
+        assert_eq!(merged_asset.data.attributes.len(), 2);
+        
+        // Check that other asset is removed
+        assert!(state_manager.get_asset(&asset_id_2).is_none());
+    }
+
+    #[test]
+    fn test_merge_assets_different_owners() {
+        let config = create_test_config("merge_different_owners");
+        let state_manager = StateManager::new(&config).unwrap();
+        
+        let owner1 = create_test_address(1);
+        let owner2 = create_test_address(2);
+        // Create accounts with balance for gas fees
+        state_manager.create_test_account(owner1, 100_000, 0);
+        state_manager.create_test_account(owner2, 100_000, 0);
+        
+        // Create first asset
+        let asset_id_1 = crate::types::sha256(b"asset1");
+        let tx1 = Transaction::MistbornAsset {
+            from: owner1,
+            action: crate::types::AssetAction::Create,
+            asset_id: asset_id_1,
+            data: crate::types::AssetData {
+                density: crate::types::DensityLevel::Ethereal,
+                metadata: std::collections::HashMap::new(),
+                attributes: vec![],
+                game_id: None,
+                owner: owner1,
+            },
+            fee: 0,
+            nonce: next_nonce(&state_manager, &owner1),
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        
+        state_manager.apply_transaction(&tx1).unwrap();
+        
+        // Create second asset with different owner
+        let asset_id_2 = crate::types::sha256(b"asset2");
+        let tx2 = Transaction::MistbornAsset {
+            from: owner2,
+            action: crate::types::AssetAction::Create,
+            asset_id: asset_id_2,
+            data: crate::types::AssetData {
+                density: crate::types::DensityLevel::Ethereal,
+                metadata: std::collections::HashMap::new(),
+                attributes: vec![],
+                game_id: None,
+                owner: owner2,
+            },
+            fee: 0,
+            nonce: next_nonce(&state_manager, &owner2),
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![2; 64],
+        };
+        
+        state_manager.apply_transaction(&tx2).unwrap();
+        
+        // Try to merge - should fail
+        let mut merge_metadata = std::collections::HashMap::new();
+        merge_metadata.insert("_other_asset_id".to_string(), hex::encode(asset_id_2));
+        
+        let merge_tx = Transaction::MistbornAsset {
+            from: owner1,
+            action: crate::types::AssetAction::Merge,
+            asset_id: asset_id_1,
+            data: crate::types::AssetData {
+                density: crate::types::DensityLevel::Ethereal,
+                metadata: merge_metadata,
+                attributes: vec![],
+                game_id: None,
+                owner: owner1,
+            },
+            fee: 0,
+            nonce: next_nonce(&state_manager, &owner1),
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![3; 64],
+        };
+        
+        let result = state_manager.apply_transaction(&merge_tx);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("different owners"));
+        
+        // Both assets should still exist
+        assert!(state_manager.get_asset(&asset_id_1).is_some());
+        assert!(state_manager.get_asset(&asset_id_2).is_some());
+    }
+
+    #[test]
+    fn test_trace_transaction_reports_failing_check() {
+        use crate::types::{Block, BlockHeader};
+        let config = create_test_config("trace_transaction");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        let state_manager = StateManager::new(&config).unwrap();
+
+        let owner1 = create_test_address(1);
+        let owner2 = create_test_address(2);
+        state_manager.create_test_account(owner1, 100_000, 0);
+        state_manager.create_test_account(owner2, 100_000, 0);
+        let asset_tx = |owner: Address, action, asset_id: Hash, metadata: HashMap<String, String>| Transaction::MistbornAsset {
+            from: owner,
+            action,
+            asset_id,
+            data: crate::types::AssetData {
+                density: crate::types::DensityLevel::Ethereal,
+                metadata,
+                attributes: vec![],
+                game_id: None,
+                owner,
+            },
+            fee: 0,
+            nonce: next_nonce(&state_manager, &owner),
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        let asset_id_1 = crate::types::sha256(b"trace_asset1");
+        let asset_id_2 = crate::types::sha256(b"trace_asset2");
+        state_manager.apply_transaction(&asset_tx(owner1, AssetAction::Create, asset_id_1, HashMap::new())).unwrap();
+        state_manager.apply_transaction(&asset_tx(owner2, AssetAction::Create, asset_id_2, HashMap::new())).unwrap();
+
+        let mut merge_metadata = HashMap::new();
+        merge_metadata.insert("_other_asset_id".to_string(), hex::encode(asset_id_2));
+        let merge_tx = asset_tx(owner1, AssetAction::Merge, asset_id_1, merge_metadata);
+
+        let balance_before = state_manager.get_account(&owner1).unwrap().balance;
+        let trace = state_manager.trace_transaction(&merge_tx, 0, &[]).unwrap();
+        assert!(trace.error.unwrap().contains("different owners"));
+        let checks: Vec<&str> = trace.steps.iter().map(|step| step.check.as_str()).collect();
+        assert_eq!(checks, ["nonce", "gas", "gas_balance", "other_asset_id", "source_asset", "other_asset", "same_owner"]);
+        let failed = trace.steps.last().unwrap();
+        assert!(!failed.passed);
+        assert!(failed.detail.contains(&hex::encode(owner2)));
+        // Nothing was committed
+        assert!(state_manager.get_asset(&asset_id_1).is_some());
+        assert!(state_manager.get_asset(&asset_id_2).is_some());
+        assert_eq!(state_manager.get_account(&owner1).unwrap().balance, balance_before);
+
+        // Earlier transactions of the block are applied first
+        let asset_id_3 = crate::types::sha256(b"trace_asset3");
+        let create_3 = asset_tx(owner1, AssetAction::Create, asset_id_3, HashMap::new());
+        let earlier = asset_tx(owner2, AssetAction::Create, asset_id_3, HashMap::new());
+        let trace = state_manager.trace_transaction(&create_3, 0, &[earlier]).unwrap();
+        assert_eq!(trace.steps.last().unwrap().check, "asset_unused");
+        assert!(state_manager.trace_transaction(&create_3, 0, &[]).unwrap().error.is_none());
+
+        // Past heights are rebuilt from blocks, which do not include the test accounts (or their nonces)
+        let block = Block {
+            header: BlockHeader {
+                hash: [9u8; 32],
+                parent_hash: [0u8; 32],
+                height: 1,
+                timestamp: 0,
+                validator: owner2,
+                merkle_root: [0u8; 32],
+                state_root: [0u8; 32],
+                wave_number: 0,
+                committee_id: 0,
+                protocol_version: PROTOCOL_VERSION,
+                state_version: STATE_VERSION,
+                signals: 0,
+            },
+            transactions: vec![],
+            dag_references: vec![],
+        };
+        state_manager.apply_block(&block).unwrap();
+        let trace = state_manager.trace_transaction(&merge_tx, 0, &[]).unwrap();
+        assert_eq!(trace.state_height, 0);
+        assert_eq!(trace.steps.last().unwrap().check, "nonce");
+        assert!(state_manager.trace_transaction(&merge_tx, 2, &[]).is_err());
+    }
+
+    #[test]
+    fn test_split_asset() {
+        let config = create_test_config("split");
+        let state_manager = StateManager::new(&config).unwrap();
+        
+        let owner = create_test_address(1);
+        // Create account with balance for gas fees
+        state_manager.create_test_account(owner, 100_000, 0);
+        
+        // Create asset with multiple components
+        let asset_id = crate::types::sha256(b"composite_asset");
+        let mut metadata = std::collections::HashMap::new();
+        metadata.insert("component1".to_string(), "sword_data".to_string());
+        metadata.insert("component2".to_string(), "shield_data".to_string());
+        metadata.insert("component3".to_string(), "armor_data".to_string());
+        
+        let tx = Transaction::MistbornAsset {
+            from: owner,
+            action: crate::types::AssetAction::Create,
+            asset_id,
+            data: crate::types::AssetData {
+                density: crate::types::DensityLevel::Dense,
+                metadata: metadata.clone(),
+                attributes: vec![
+                    crate::types::Attribute {
+                        name: "power".to_string(),
+                        value: "100".into(),
+                        rarity: None,
+                    },
+                ],
+                game_id: Some("game1".to_string()),
+                owner,
+            },
+            fee: 0,
+            nonce: next_nonce(&state_manager, &owner),
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        
+        state_manager.apply_transaction(&tx).unwrap();
+        assert!(state_manager.get_asset(&asset_id).is_some());
+        
+        // Split asset into components
+        let mut split_metadata = std::collections::HashMap::new();
+        split_metadata.insert("_components".to_string(), "component1,component2,component3".to_string());
+        
+        let split_tx = Transaction::MistbornAsset {
+            from: owner,
+            action: crate::types::AssetAction::Split,
+            asset_id,
+            data: crate::types::AssetData {
+                density: crate::types::DensityLevel::Ethereal,
+                metadata: split_metadata,
+                attributes: vec![],
+                game_id: None,
+                owner,
+            },
+            fee: 0,
+            nonce: next_nonce(&state_manager, &owner),
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![2; 64],
+        };
+        
+        state_manager.apply_transaction(&split_tx).unwrap();
+        
+        // Check that source asset is removed
+        assert!(state_manager.get_asset(&asset_id).is_none());
+        
+        // Check that component assets were created
+        let component1_id = crate::types::sha256(&[asset_id.as_ref(), b"component1"].concat());
+        let component2_id = crate::types::sha256(&[asset_id.as_ref(), b"component2"].concat());
+        let component3_id = crate::types::sha256(&[asset_id.as_ref(), b"component3"].concat());
+        
+        let comp1 = state_manager.get_asset(&component1_id).unwrap();
+        assert_eq!(comp1.owner, owner);
+        assert_eq!(comp1.data.metadata.get("component1"), Some(&"sword_data".to_string()));
+        assert_eq!(comp1.data.density, crate::types::DensityLevel::Ethereal);
+        
+        let comp2 = state_manager.get_asset(&component2_id).unwrap();
+        assert_eq!(comp2.owner, owner);
+        assert_eq!(comp2.data.metadata.get("component2"), Some(&"shield_data".to_string()));
+        
+        let comp3 = state_manager.get_asset(&component3_id).unwrap();
+        assert_eq!(comp3.owner, owner);
+        assert_eq!(comp3.data.metadata.get("component3"), Some(&"armor_data".to_string()));
+    }
+
+    #[test]
+    fn test_split_asset_with_manifest() {
+        use crate::types::{AssetAction, AssetData, Attribute, DensityLevel};
+        let mut config = create_test_config("split_manifest");
+        config.asset_limits.split_heuristic_fallback = false;
+        let state_manager = StateManager::new(&config).unwrap();
+        let owner = create_test_address(1);
+        state_manager.create_test_account(owner, 1_000_000, 0);
+        
+        let asset_tx = |action: AssetAction, metadata: Vec<(&str, &str)>, attributes: Vec<Attribute>| Transaction::MistbornAsset {
+            from: owner,
+            action,
+            asset_id: crate::types::sha256(b"sword"),
+            data: AssetData {
+                density: DensityLevel::Ethereal,
+                metadata: metadata.into_iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+                attributes,
+                game_id: None,
+                owner,
+            },
+            fee: 0,
+            nonce: next_nonce(&state_manager, &owner),
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        let attribute = |name: &str| Attribute { name: name.to_string(), value: "1".into(), rarity: None };
+        let asset_id = crate::types::sha256(b"sword");
+        state_manager.apply_transaction(&asset_tx(
+            AssetAction::Create,
+            vec![("blade", "steel"), ("hilt", "oak"), ("lore", "ancient")],
+            vec![attribute("sharpness"), attribute("grip"), attribute("power")],
+        )).unwrap();
+        
+        let split = |manifest: &str| asset_tx(AssetAction::Split, vec![("_component_manifest", manifest)], vec![]);
+        
+        // The heuristic fallback is disabled
+        let err = state_manager.apply_transaction(
+            &asset_tx(AssetAction::Split, vec![("_components", "blade,hilt")], vec![])
+        ).unwrap_err();
+        assert!(err.to_string().contains("_component_manifest"), "got: {}", err);
+        
+        // "lore" is not assigned to any component
+        let err = state_manager.apply_transaction(&split(r#"[
+            {"name": "blade", "metadata_keys": ["blade"], "attributes": ["sharpness", "power"]},
+            {"name": "hilt", "metadata_keys": ["hilt"], "attributes": ["grip", "power"]}
+        ]"#)).unwrap_err();
+        assert!(err.to_string().contains("lore"), "got: {}", err);
+        
+        // A component may not be denser than its source
+        let err = state_manager.apply_transaction(&split(r#"[
+            {"name": "blade", "metadata_keys": ["blade", "lore"], "attributes": ["sharpness", "power"], "density": "Light"},
+            {"name": "hilt", "metadata_keys": ["hilt"], "attributes": ["grip"]}
+        ]"#)).unwrap_err();
+        assert!(err.to_string().contains("density"), "got: {}", err);
+        assert!(state_manager.get_asset(&asset_id).is_some());
+        
+        state_manager.apply_transaction(&split(r#"[
+            {"name": "blade", "metadata_keys": ["blade", "lore"], "attributes": ["sharpness", "power"]},
+            {"name": "hilt", "metadata_keys": ["hilt"], "attributes": ["grip", "power"]}
+        ]"#)).unwrap();
+        assert!(state_manager.get_asset(&asset_id).is_none());
+        
+        let blade = state_manager.get_asset(&crate::types::sha256(&[asset_id.as_ref(), b"blade"].concat())).unwrap();
+        let mut keys: Vec<&String> = blade.data.metadata.keys().collect();
+        keys.sort();
+        assert_eq!(keys, vec!["blade", "lore"]);
+        let names: Vec<&str> = blade.data.attributes.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["sharpness", "power"]);
+        
+        let hilt = state_manager.get_asset(&crate::types::sha256(&[asset_id.as_ref(), b"hilt"].concat())).unwrap();
+        assert_eq!(hilt.data.metadata.len(), 1);
+        let names: Vec<&str> = hilt.data.attributes.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["grip", "power"]);
+    }
 
-                account.balance -= amount;
-                account.staked += amount;
-                
-                // Register stake in tokenomics
-                self.tokenomics.stake(*validator, *validator, *amount)?;
-            }
-            Transaction::SetAssetPermissions { asset_id, permissions, public_read, owner, .. } => {
-                let mut asset_state = self.assets.get(asset_id)
-                    .ok_or_else(|| HazeError::InvalidTransaction("Asset not found".to_string()))?
-                    .clone();
-                if asset_state.owner != *owner {
-                    return Err(HazeError::AccessDenied(
-                        "Only asset owner can set permissions".to_string()
-                    ));
-                }
-                asset_state.permissions = permissions.clone();
-                asset_state.public_read = *public_read;
-                asset_state.updated_at = chrono::Utc::now().timestamp();
-                self.assets.insert(*asset_id, asset_state);
-                self.broadcast_event(WsEvent::AssetPermissionChanged {
-                    asset_id: hex::encode(asset_id),
-                    owner: hex::encode(owner),
-                });
-            }
-            Transaction::DeployContract { from, code, fee, nonce, .. } => {
-                let mut from_account = self.accounts
-                    .entry(*from)
-                    .or_insert_with(|| AccountState { balance: 0, nonce: 0, staked: 0 });
-                let expected_nonce = from_account.nonce;
-                if *nonce != expected_nonce {
-                    return Err(HazeError::InvalidTransaction(
-                        format!("Invalid nonce: expected {}, got {}", expected_nonce, nonce)
-                    ));
-                }
-                if from_account.balance < *fee {
-                    return Err(HazeError::InvalidTransaction("Insufficient balance for fee".to_string()));
-                }
-                from_account.balance -= fee;
-                from_account.nonce = *nonce + 1;
-                let contract_address = crate::types::sha256(code);
-                self.contracts.insert(contract_address, code.clone());
-                let _ = self.tokenomics.process_gas_fee(*fee)?;
-            }
-            Transaction::ContractCall { from, contract, method, args, gas_limit, fee, nonce, .. } => {
-                if *gas_limit > self.config.vm.gas_limit {
-                    return Err(HazeError::VM(format!(
-                        "Gas limit {} exceeds node limit {}",
-                        gas_limit, self.config.vm.gas_limit
-                    )));
-                }
-                let wasm_code = self.contracts.get(contract)
-                    .ok_or_else(|| HazeError::VM("Contract not found".to_string()))?
-                    .clone();
-                let mut from_account = self.accounts
-                    .entry(*from)
-                    .or_insert_with(|| AccountState { balance: 0, nonce: 0, staked: 0 });
-                let expected_nonce = from_account.nonce;
-                if *nonce != expected_nonce {
-                    return Err(HazeError::InvalidTransaction(
-                        format!("Invalid nonce: expected {}, got {}", expected_nonce, nonce)
-                    ));
-                }
-                let gas_fee_estimate = (*gas_limit).saturating_mul(self.config.vm.gas_price);
-                if from_account.balance < *fee + gas_fee_estimate {
-                    return Err(HazeError::InvalidTransaction(
-                        "Insufficient balance for fee and gas".to_string()
-                    ));
-                }
-                from_account.balance -= fee;
-                from_account.nonce = *nonce + 1;
-                let _ = self.tokenomics.process_gas_fee(*fee)?;
+    #[test]
+    fn test_asset_lineage_through_split_and_merge() {
+        use crate::types::{AssetAction, AssetData, DensityLevel};
+        let config = create_test_config("asset_lineage");
+        let state_manager = StateManager::new(&config).unwrap();
+        let owner = create_test_address(1);
+        state_manager.create_test_account(owner, 1_000_000, 0);
+        
+        let asset_tx = |action: AssetAction, asset_id: Hash, metadata: Vec<(&str, String)>| Transaction::MistbornAsset {
+            from: owner,
+            action,
+            asset_id,
+            data: AssetData {
+                density: DensityLevel::Ethereal,
+                metadata: metadata.into_iter().map(|(k, v)| (k.to_string(), v)).collect(),
+                attributes: vec![],
+                game_id: None,
+                owner,
+            },
+            fee: 0,
+            nonce: next_nonce(&state_manager, &owner),
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        let source = crate::types::sha256(b"lineage_source");
+        let blade = crate::types::sha256(&[source.as_ref(), b"blade"].concat());
+        let hilt = crate::types::sha256(&[source.as_ref(), b"hilt"].concat());
+        state_manager.apply_transaction(&asset_tx(AssetAction::Create, source, vec![
+            ("blade", "steel".to_string()),
+            ("hilt", "oak".to_string()),
+        ])).unwrap();
+        assert!(state_manager.get_asset_lineage(&source).is_none());
+        assert!(state_manager.lineage_hash(&source).is_none());
+        
+        state_manager.apply_transaction(&asset_tx(AssetAction::Split, source, vec![("_components", "blade,hilt".to_string())])).unwrap();
+        let hash_after_split = state_manager.lineage_hash(&blade).unwrap();
+        state_manager.apply_transaction(&asset_tx(AssetAction::Merge, blade, vec![("_other_asset_id", hex::encode(hilt))])).unwrap();
+        
+        let parents: Vec<(Hash, AssetAction)> = state_manager.get_asset_lineage(&blade).unwrap().parents
+            .into_iter().map(|e| (e.parent, e.action)).collect();
+        assert_eq!(parents, vec![(source, AssetAction::Split), (hilt, AssetAction::Merge)]);
+        assert!(state_manager.get_asset_lineage(&blade).unwrap().parents.iter().all(|e| e.height == 1));
+        
+        // The source is reached both directly and through the merged-away hilt
+        let ancestors: Vec<(Hash, Hash)> = state_manager.lineage_closure(&blade, true)
+            .into_iter().map(|e| (e.parent, e.child)).collect();
+        assert_eq!(ancestors.len(), 3);
+        assert!(ancestors.contains(&(source, hilt)));
+        let descendants = state_manager.lineage_closure(&source, false);
+        assert_eq!(descendants.len(), 3);
+        assert!(state_manager.lineage_closure(&source, true).is_empty());
+        
+        assert_ne!(state_manager.lineage_hash(&blade).unwrap(), hash_after_split);
+        assert_eq!(state_manager.lineage_hash(&blade), state_manager.lineage_hash(&blade));
+    }
 
-                let vm = HazeVM::new((*self.config).clone())?;
-                let mut context = ExecutionContext {
-                    caller: *from,
-                    contract: *contract,
-                    gas_limit: *gas_limit,
-                    gas_used: 0,
-                };
-                let _result = vm.execute_contract(&wasm_code, method, args, &mut context)?;
-                let gas_used = context.gas_used;
-                let gas_fee = gas_used.saturating_mul(self.config.vm.gas_price);
-                let mut from_account = self.accounts
-                    .entry(*from)
-                    .or_insert_with(|| AccountState { balance: 0, nonce: 0, staked: 0 });
-                if from_account.balance < gas_fee {
-                    return Err(HazeError::InvalidTransaction(
-                        format!("Insufficient balance for gas: need {}, have {}", gas_fee, from_account.balance)
-                    ));
-                }
-                from_account.balance -= gas_fee;
-                let _ = self.tokenomics.process_gas_fee(gas_fee)?;
-            }
+    #[test]
+    fn test_split_asset_invalid_owner() {
+        let config = create_test_config("split_invalid_owner");
+        let state_manager = StateManager::new(&config).unwrap();
+        
+        let owner1 = create_test_address(1);
+        let owner2 = create_test_address(2);
+        // Create accounts with balance for gas fees
+        state_manager.create_test_account(owner1, 100_000, 0);
+        state_manager.create_test_account(owner2, 100_000, 0);
+        
+        // Create asset
+        let asset_id = crate::types::sha256(b"asset");
+        let tx = Transaction::MistbornAsset {
+            from: owner1,
+            action: crate::types::AssetAction::Create,
+            asset_id,
+            data: crate::types::AssetData {
+                density: crate::types::DensityLevel::Ethereal,
+                metadata: {
+                    let mut m = std::collections::HashMap::new();
+                    m.insert("component1".to_string(), "data".to_string());
+                    m
+                },
+                attributes: vec![],
+                game_id: None,
+                owner: owner1,
+            },
+            fee: 0,
+            nonce: 0,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        
+        state_manager.apply_transaction(&tx).unwrap();
+        
+        // Try to split with wrong owner - should fail
+        let mut split_metadata = std::collections::HashMap::new();
+        split_metadata.insert("_components".to_string(), "component1".to_string());
+        
+        let split_tx = Transaction::MistbornAsset {
+            from: owner2, // Wrong owner
+            action: crate::types::AssetAction::Split,
+            asset_id,
+            data: crate::types::AssetData {
+                density: crate::types::DensityLevel::Ethereal,
+                metadata: split_metadata,
+                attributes: vec![],
+                game_id: None,
+                owner: owner2, // Wrong owner
+            },
+            fee: 0,
+            nonce: 0,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![2; 64],
+        };
+        
+        let result = state_manager.apply_transaction(&split_tx);
+        assert!(result.is_err());
+        let err_msg = result.unwrap_err().to_string();
+        assert!(
+            err_msg.contains("not owner") || err_msg.contains("Access denied"),
+            "expected permission error, got: {}",
+            err_msg
+        );
+        
+        // Asset should still exist
+        assert!(state_manager.get_asset(&asset_id).is_some());
+    }
+
+    #[test]
+    fn test_asset_history() {
+        let config = create_test_config("history");
+        let state_manager = StateManager::new(&config).unwrap();
+        
+        let owner = create_test_address(1);
+        // Create account with balance for gas fees
+        state_manager.create_test_account(owner, 100_000, 0);
+        
+        let asset_id = crate::types::sha256(b"test_asset");
+        
+        // Create asset
+        let tx = Transaction::MistbornAsset {
+            from: owner,
+            action: crate::types::AssetAction::Create,
+            asset_id,
+            data: crate::types::AssetData {
+                density: crate::types::DensityLevel::Ethereal,
+                metadata: std::collections::HashMap::new(),
+                attributes: vec![],
+                game_id: None,
+                owner,
+            },
+            fee: 0,
+            nonce: 0,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        
+        state_manager.apply_transaction(&tx).unwrap();
+        
+        // Check history
+        let history = state_manager.get_asset_history(&asset_id, 0).unwrap();
+        assert_eq!(history.len(), 1);
+        assert!(matches!(history[0].action, crate::types::AssetAction::Create));
+    }
+
+    #[test]
+    fn test_search_assets_by_owner() {
+        let config = create_test_config("search_owner");
+        let state_manager = StateManager::new(&config).unwrap();
+        
+        let owner1 = create_test_address(1);
+        let owner2 = create_test_address(2);
+        // Create accounts with balance for gas fees
+        state_manager.create_test_account(owner1, 100_000, 0);
+        state_manager.create_test_account(owner2, 100_000, 0);
+        
+        // Create assets for owner1
+            for i in 0..3 {
+            let asset_id = crate::types::sha256(&format!("asset1_{}", i).into_bytes());
+            let tx = Transaction::MistbornAsset {
+                from: owner1,
+                action: crate::types::AssetAction::Create,
+                asset_id,
+                data: crate::types::AssetData {
+                    density: crate::types::DensityLevel::Ethereal,
+                    metadata: std::collections::HashMap::new(),
+                    attributes: vec![],
+                    game_id: None,
+                    owner: owner1,
+                },
+                fee: 0,
+                nonce: next_nonce(&state_manager, &owner1),
+                chain_id: None,
+                valid_until_height: None,
+                signature: vec![1; 64],
+            };
+            state_manager.apply_transaction(&tx).unwrap();
+        }
+        
+        // Create asset for owner2
+        let asset_id2 = crate::types::sha256(b"asset2");
+        let tx2 = Transaction::MistbornAsset {
+            from: owner2,
+            action: crate::types::AssetAction::Create,
+            asset_id: asset_id2,
+            data: crate::types::AssetData {
+                density: crate::types::DensityLevel::Ethereal,
+                metadata: std::collections::HashMap::new(),
+                attributes: vec![],
+                game_id: None,
+                owner: owner2,
+            },
+            fee: 0,
+            nonce: next_nonce(&state_manager, &owner2),
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![2; 64],
+        };
+        state_manager.apply_transaction(&tx2).unwrap();
+        
+        // Search by owner1
+        let results = state_manager.search_assets_by_owner(&owner1);
+        assert_eq!(results.len(), 3);
+    }
+
+    #[test]
+    fn test_search_assets_by_game_id() {
+        let config = create_test_config("search_game");
+        let state_manager = StateManager::new(&config).unwrap();
+        
+        let owner = create_test_address(1);
+        // Create account with balance for gas fees
+        state_manager.create_test_account(owner, 100_000, 0);
+        
+        // Create assets with game_id
+        for i in 0..2 {
+            let asset_id = crate::types::sha256(&format!("game_asset_{}", i).into_bytes());
+            let tx = Transaction::MistbornAsset {
+                from: owner,
+                action: crate::types::AssetAction::Create,
+                asset_id,
+                data: crate::types::AssetData {
+                    density: crate::types::DensityLevel::Ethereal,
+                    metadata: std::collections::HashMap::new(),
+                    attributes: vec![],
+                    game_id: Some("game1".to_string()),
+                    owner,
+                },
+                fee: 0,
+                nonce: next_nonce(&state_manager, &owner),
+                chain_id: None,
+                valid_until_height: None,
+                signature: vec![1; 64],
+            };
+            state_manager.apply_transaction(&tx).unwrap();
         }
         
-        Ok(())
+        // Search by game_id
+        let results = state_manager.search_assets_by_game_id("game1");
+        assert_eq!(results.len(), 2);
     }
-    
-    /// Register contract code at address (for tests or future deploy API).
-    /// Contract address is typically sha256(code); use this to preload code without a DeployContract tx.
-    pub fn register_contract(&self, address: Address, code: Vec<u8>) {
-        self.contracts.insert(address, code);
+
+    #[test]
+    fn test_collection_trait_histogram() {
+        let config = create_test_config("collection_traits");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        let state_manager = StateManager::new(&config).unwrap();
+
+        let owner = create_test_address(1);
+        state_manager.create_test_account(owner, 100_000, 0);
+
+        let attr = |name: &str, value: &str| crate::types::Attribute {
+            name: name.to_string(),
+            value: value.into(),
+            rarity: None,
+        };
+        let asset_tx = |action, seed: u8, attributes| Transaction::MistbornAsset {
+            from: owner,
+            action,
+            asset_id: [seed; 32],
+            data: crate::types::AssetData {
+                density: crate::types::DensityLevel::Ethereal,
+                metadata: std::collections::HashMap::new(),
+                attributes,
+                game_id: Some("traits_game".to_string()),
+                owner,
+            },
+            fee: 0,
+            nonce: next_nonce(&state_manager, &owner),
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+
+        let create = crate::types::AssetAction::Create;
+        state_manager.apply_transaction(&asset_tx(create.clone(), 1, vec![attr("aura", "flaming"), attr("tier", "gold")])).unwrap();
+        state_manager.apply_transaction(&asset_tx(create.clone(), 2, vec![attr("tier", "gold")])).unwrap();
+        state_manager.apply_transaction(&asset_tx(create, 3, vec![attr("tier", "silver")])).unwrap();
+
+        let (total, traits) = state_manager.get_collection_traits("traits_game").unwrap();
+        assert_eq!(total, 3);
+        assert_eq!(traits["aura"]["flaming"], 1);
+        assert_eq!(traits["tier"]["gold"], 2);
+        assert_eq!(traits["tier"]["silver"], 1);
+
+        // Updating attributes moves counts between values and drops empty traits
+        state_manager.apply_transaction(&asset_tx(crate::types::AssetAction::Update, 1, vec![attr("tier", "silver")])).unwrap();
+        let (_, traits) = state_manager.get_collection_traits("traits_game").unwrap();
+        assert!(!traits.contains_key("aura"));
+        assert_eq!(traits["tier"]["gold"], 1);
+        assert_eq!(traits["tier"]["silver"], 2);
+
+        assert!(state_manager.get_collection_traits("unknown_game").is_none());
     }
 
-    /// Apply multiple transactions in batch (optimized)
-    ///
-    /// # Arguments
-    /// * `transactions` - Vector of transactions to apply
-    ///
-    /// # Returns
-    /// `Ok(())` if all transactions were applied successfully, `Err` with first error otherwise
-    ///
-    /// # Performance
-    /// This method is optimized for batch operations by reducing index updates overhead.
-    pub fn apply_transactions_batch(&self, transactions: &[Transaction]) -> Result<()> {
-        // Apply all transactions
-        for tx in transactions {
-            self.apply_transaction(tx)?;
+    #[test]
+    fn test_collection_rarity_scores() {
+        let config = create_test_config("collection_rarity");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        let state_manager = StateManager::new(&config).unwrap();
+
+        let owner = create_test_address(1);
+        state_manager.create_test_account(owner, 100_000, 0);
+
+        let create = |seed: u8, game_id: Option<&str>, tier: &str| Transaction::MistbornAsset {
+            from: owner,
+            action: crate::types::AssetAction::Create,
+            asset_id: [seed; 32],
+            data: crate::types::AssetData {
+                density: crate::types::DensityLevel::Ethereal,
+                metadata: std::collections::HashMap::new(),
+                attributes: vec![crate::types::Attribute {
+                    name: "tier".to_string(),
+                    value: tier.into(),
+                    rarity: None,
+                }],
+                game_id: game_id.map(str::to_string),
+                owner,
+            },
+            fee: 0,
+            nonce: next_nonce(&state_manager, &owner),
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+
+        for (seed, tier) in [(1, "gold"), (2, "gold"), (3, "gold"), (4, "mythic")] {
+            state_manager.apply_transaction(&create(seed, Some("rarity_game"), tier)).unwrap();
         }
-        
-        Ok(())
+
+        let mythic = state_manager.get_asset_rarity(&[4; 32]).unwrap();
+        assert_eq!(mythic.rank, 1);
+        assert_eq!(mythic.collection_size, 4);
+        assert!((mythic.score - 4.0).abs() < 1e-9);
+        let gold = state_manager.get_asset_rarity(&[1; 32]).unwrap();
+        assert_eq!(gold.rank, 2);
+        assert_eq!(state_manager.get_asset_rarity(&[2; 32]).unwrap().rank, 2);
+
+        // A new asset invalidates the cached scores of its collection
+        state_manager.apply_transaction(&create(5, Some("rarity_game"), "mythic")).unwrap();
+        let mythic = state_manager.get_asset_rarity(&[4; 32]).unwrap();
+        assert_eq!(mythic.collection_size, 5);
+        assert!((mythic.score - 2.5).abs() < 1e-9);
+
+        state_manager.apply_transaction(&create(6, None, "gold")).unwrap();
+        assert!(state_manager.get_asset_rarity(&[6; 32]).is_none());
     }
-    
-    /// Batch create assets (optimized for multiple asset creation)
-    ///
-    /// # Arguments
-    /// * `assets` - Vector of (asset_id, asset_data) tuples
-    ///
-    /// # Returns
-    /// `Ok(())` if all assets were created successfully, `Err` with first error otherwise
-    ///
-    /// # Performance
-    /// This method is optimized for batch creation by batching index updates.
-    pub fn batch_create_assets(&self, assets: Vec<(Hash, AssetState)>) -> Result<()> {
-        // Validate all assets first
-        for (asset_id, asset_state) in &assets {
-            // Check if asset already exists
-            if self.assets.contains_key(asset_id) {
-                return Err(HazeError::InvalidTransaction(
-                    format!("Asset {} already exists", hex::encode(asset_id))
-                ));
+
+    #[test]
+    fn test_collection_holders_at_height() {
+        use crate::types::{Block, BlockHeader};
+        let config = create_test_config("collection_holders");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        let state_manager = StateManager::new(&config).unwrap();
+
+        let alice = create_test_address(1);
+        let bob = create_test_address(2);
+        state_manager.create_test_account(alice, 100_000, 0);
+        state_manager.create_test_account(bob, 100_000, 0);
+
+        let create = |owner: Address, seed: u8, nonce: u64| Transaction::MistbornAsset {
+            from: owner,
+            action: crate::types::AssetAction::Create,
+            asset_id: [seed; 32],
+            data: crate::types::AssetData {
+                density: crate::types::DensityLevel::Ethereal,
+                metadata: std::collections::HashMap::new(),
+                attributes: vec![],
+                game_id: Some("drop_game".to_string()),
+                owner,
+            },
+            fee: 0,
+            nonce,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+
+        state_manager.apply_block(&test_block(1, vec![create(alice, 10, 0), create(alice, 11, 1)])).unwrap();
+        state_manager.apply_block(&test_block(2, vec![create(bob, 12, 0)])).unwrap();
+
+        let at_1 = state_manager.get_collection_holders("drop_game", 1).unwrap();
+        assert_eq!(at_1.len(), 1);
+        assert_eq!(at_1[&alice], 2);
+
+        let at_2 = state_manager.get_collection_holders("drop_game", 2).unwrap();
+        assert_eq!(at_2[&alice], 2);
+        assert_eq!(at_2[&bob], 1);
+
+        assert!(state_manager.get_collection_holders("drop_game", 0).unwrap().is_empty());
+        assert!(state_manager.get_collection_holders("unknown_game", 2).is_none());
+    }
+
+    #[test]
+    fn test_payment_request_settlement() {
+        let config = create_test_config("payment_request");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        let state_manager = StateManager::new(&config).unwrap();
+
+        let studio = create_test_address(1);
+        let player = create_test_address(2);
+        let other = create_test_address(3);
+        state_manager.create_test_account(studio, 100, 0);
+        state_manager.create_test_account(player, 1_000, 0);
+        state_manager.create_test_account(other, 1_000, 0);
+        let (ws_tx, mut ws_rx) = broadcast::channel(10);
+        state_manager.set_ws_tx(ws_tx);
+
+        state_manager.apply_transaction(&Transaction::CreatePaymentRequest {
+            from: studio,
+            payer: Some(player),
+            amount: 50,
+            memo: "Sword of Fog".to_string(),
+            expires_at_height: 10,
+            fee: 1,
+            nonce: 0,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        }).unwrap();
+        let request_id = crate::types::payment_request_id(&studio, 0);
+        let request = state_manager.get_payment_request(&request_id).unwrap();
+        assert_eq!(request.status(1), PaymentRequestStatus::Open);
+        assert_eq!(request.status(11), PaymentRequestStatus::Expired);
+        assert_eq!(state_manager.get_account(&studio).unwrap().balance, 99);
+
+        let pay = |from: Address, amount: u64, nonce: u64| Transaction::PayPaymentRequest {
+            from,
+            request_id,
+            amount,
+            fee: 2,
+            nonce,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        // Only the addressed payer, and only the exact amount
+        assert!(matches!(state_manager.apply_transaction(&pay(other, 50, 0)), Err(HazeError::AccessDenied(_))));
+        assert!(state_manager.apply_transaction(&pay(player, 40, 0)).is_err());
+
+        state_manager.apply_transaction(&pay(player, 50, 0)).unwrap();
+        assert_eq!(state_manager.get_account(&player).unwrap().balance, 1_000 - 52);
+        assert_eq!(state_manager.get_account(&studio).unwrap().balance, 99 + 50);
+        let request = state_manager.get_payment_request(&request_id).unwrap();
+        assert_eq!(request.status(1), PaymentRequestStatus::Paid);
+        assert_eq!(request.settlement.unwrap().payer, player);
+        match ws_rx.try_recv().unwrap() {
+            WsEvent::PaymentRequestSettled { requester, payer, amount, .. } => {
+                assert_eq!((requester, payer, amount), (studio, player, 50));
             }
-            
-            // Check asset count limit
-            self.check_asset_count_limit(&asset_state.owner)?;
-            
-            // Check metadata size limit
-            let metadata_size: usize = asset_state.data.metadata.values().map(|v| v.len()).sum();
-            self.check_metadata_size_limit(metadata_size)?;
+            other => panic!("unexpected event {:?}", other),
         }
-        
-        // Apply all assets in batch
-        for (asset_id, asset_state) in assets {
-            // Add to indexes
-            self.add_asset_to_indexes(&asset_id, &asset_state);
-            
-            // Insert asset
-            self.assets.insert(asset_id, asset_state);
+
+        // A request is paid at most once
+        assert!(state_manager.apply_transaction(&pay(player, 50, 1)).is_err());
+    }
+
+    #[test]
+    fn test_order_book_matching_and_cancel() {
+        use crate::economy::{order_escrow_address, OrderStatus};
+        use crate::types::{order_id, AssetAction, AssetData, DensityLevel};
+        let config = create_test_config("order_book");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        let state_manager = StateManager::new(&config).unwrap();
+
+        let seller = create_test_address(1);
+        let rival = create_test_address(2);
+        let buyer = create_test_address(3);
+        for address in [seller, rival, buyer] {
+            state_manager.create_test_account(address, 100_000, 0);
         }
-        
-        Ok(())
+        let create_asset = |owner: Address, name: &[u8]| {
+            let asset_id = crate::types::sha256(name);
+            state_manager.apply_transaction(&Transaction::MistbornAsset {
+                from: owner,
+                action: AssetAction::Create,
+                asset_id,
+                data: AssetData {
+                    density: DensityLevel::Ethereal,
+                    metadata: HashMap::new(),
+                    attributes: vec![],
+                    game_id: Some("game".to_string()),
+                    owner,
+                },
+                fee: 0,
+                nonce: next_nonce(&state_manager, &owner),
+                chain_id: None,
+                valid_until_height: None,
+                signature: vec![1; 64],
+            }).unwrap();
+            asset_id
+        };
+        let (a1, a2, a3) = (create_asset(seller, b"a1"), create_asset(seller, b"a2"), create_asset(seller, b"a3"));
+        let b1 = create_asset(rival, b"b1");
+        let place = |from: Address, side: OrderSide, price: u64, quantity: u64, assets: Vec<Hash>, nonce: u64| {
+            Transaction::PlaceOrder {
+                from,
+                game_id: "game".to_string(),
+                side,
+                price,
+                quantity,
+                assets,
+                fee: 1,
+                nonce,
+                chain_id: None,
+                valid_until_height: None,
+                signature: vec![1; 64],
+            }
+        };
+        let cancel = |from: Address, order_id: Hash, nonce: u64| Transaction::CancelOrder {
+            from,
+            order_id,
+            fee: 1,
+            nonce,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        let balance = |address: &Address| state_manager.get_account(address).map(|a| a.balance).unwrap_or(0);
+        let (seller_start, buyer_start) = (balance(&seller), balance(&buyer));
+
+        // Only the owner can offer an asset
+        assert!(matches!(
+            state_manager.apply_transaction(&place(rival, OrderSide::Sell, 10, 1, vec![a1], 1)),
+            Err(HazeError::AccessDenied(_))
+        ));
+        state_manager.apply_transaction(&place(seller, OrderSide::Sell, 10, 2, vec![a1, a2], 3)).unwrap();
+        state_manager.apply_transaction(&place(rival, OrderSide::Sell, 10, 1, vec![b1], 1)).unwrap();
+        state_manager.apply_transaction(&place(seller, OrderSide::Sell, 8, 1, vec![a3], 4)).unwrap();
+        assert_eq!(state_manager.get_asset(&a1).unwrap().owner, order_escrow_address());
+        assert_eq!(state_manager.get_open_orders("game").len(), 3);
+        assert_eq!(state_manager.get_floor_price("game"), Some(8));
+
+        // Cheapest ask first, then the earlier of the two asks at 10; each at the maker's price
+        state_manager.apply_transaction(&place(buyer, OrderSide::Buy, 12, 2, vec![], 0)).unwrap();
+        assert_eq!(balance(&buyer), buyer_start - 8 - 10 - 1);
+        assert_eq!(balance(&seller), seller_start - 2 + 8 + 10);
+        assert_eq!(state_manager.get_asset(&a3).unwrap().owner, buyer);
+        assert_eq!(state_manager.get_asset(&a1).unwrap().owner, buyer);
+        assert_eq!(state_manager.get_order(&order_id(&buyer, 0)).unwrap().status, OrderStatus::Filled);
+        let partial = state_manager.get_order(&order_id(&seller, 3)).unwrap();
+        assert_eq!((partial.remaining, partial.assets.clone()), (1, vec![a2]));
+        assert_eq!(state_manager.get_order(&order_id(&rival, 1)).unwrap().remaining, 1);
+        let trades = state_manager.get_trades("game", 0);
+        assert_eq!(trades.iter().map(|t| (t.asset_id, t.price)).collect::<Vec<_>>(), vec![(a1, 10), (a3, 8)]);
+        assert_eq!(state_manager.get_floor_price("game"), Some(10));
+
+        // Only the owner cancels; the unsold asset goes back
+        assert!(state_manager.apply_transaction(&cancel(buyer, order_id(&seller, 3), 1)).is_err());
+        state_manager.apply_transaction(&cancel(seller, order_id(&seller, 3), 5)).unwrap();
+        assert_eq!(state_manager.get_asset(&a2).unwrap().owner, seller);
+        assert_eq!(state_manager.get_order(&order_id(&seller, 3)).unwrap().status, OrderStatus::Cancelled);
+        assert!(state_manager.apply_transaction(&cancel(seller, order_id(&seller, 3), 6)).is_err());
+
+        // A resting bid escrows its total and a cancel refunds it
+        state_manager.apply_transaction(&place(buyer, OrderSide::Buy, 5, 2, vec![], 1)).unwrap();
+        assert_eq!(balance(&buyer), buyer_start - 19 - 10 - 1);
+        state_manager.apply_transaction(&cancel(buyer, order_id(&buyer, 1), 2)).unwrap();
+        assert_eq!(balance(&buyer), buyer_start - 19 - 2);
+        assert_eq!(
+            state_manager.get_open_orders("game").iter().map(|o| o.id).collect::<Vec<_>>(),
+            vec![order_id(&rival, 1)]
+        );
+        assert_eq!(balance(&order_escrow_address()), 0);
     }
 
-    /// Get tokenomics instance
-    pub fn tokenomics(&self) -> &Arc<Tokenomics> {
-        &self.tokenomics
+    #[test]
+    fn test_order_book_single_block_limits() {
+        use crate::types::{order_id, AssetAction, AssetData, BlockHeader, DensityLevel};
+        let mut config = create_test_config("order_book_block_limits");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        config.economy.order_book.max_orders_per_block = 2;
+        let state_manager = StateManager::new(&config).unwrap();
+
+        let seller = create_test_address(1);
+        let trader = create_test_address(2);
+        state_manager.create_test_account(seller, 100_000, 0);
+        state_manager.create_test_account(trader, 100_000, 0);
+        let asset_id = crate::types::sha256(b"flash_asset");
+        state_manager.apply_transaction(&Transaction::MistbornAsset {
+            from: seller,
+            action: AssetAction::Create,
+            asset_id,
+            data: AssetData {
+                density: DensityLevel::Ethereal,
+                metadata: HashMap::new(),
+                attributes: vec![],
+                game_id: Some("game".to_string()),
+                owner: seller,
+            },
+            fee: 0,
+            nonce: next_nonce(&state_manager, &seller),
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        }).unwrap();
+
+        let place = |from: Address, side: OrderSide, price: u64, assets: Vec<Hash>, nonce: u64| Transaction::PlaceOrder {
+            from,
+            game_id: "game".to_string(),
+            side,
+            price,
+            quantity: 1,
+            assets,
+            fee: 1,
+            nonce,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        let cancel = |from: Address, order_id: Hash, nonce: u64| Transaction::CancelOrder {
+            from,
+            order_id,
+            fee: 1,
+            nonce,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+
+        let (ws_tx, mut ws_rx) = broadcast::channel(64);
+        state_manager.set_ws_tx(ws_tx);
+        state_manager.apply_block(&test_block(1, vec![place(seller, OrderSide::Sell, 10, vec![asset_id], 1)])).unwrap();
+
+        // Buy and re-list in one block
+        let flip = vec![
+            place(trader, OrderSide::Buy, 10, vec![], 0),
+            place(trader, OrderSide::Sell, 50, vec![asset_id], 1),
+        ];
+        assert!(state_manager.apply_block(&test_block(2, flip)).is_err());
+        // Post a bid and pull it in one block
+        let flash_bid = vec![place(trader, OrderSide::Buy, 5, vec![], 0), cancel(trader, order_id(&trader, 0), 1)];
+        assert!(state_manager.apply_block(&test_block(2, flash_bid)).is_err());
+        // More orders than allowed per block
+        let burst = (0..3).map(|nonce| place(trader, OrderSide::Buy, 1, vec![], nonce)).collect();
+        assert!(state_manager.apply_block(&test_block(2, burst)).is_err());
+        assert_eq!(state_manager.get_asset(&asset_id).unwrap().owner, crate::economy::order_escrow_address());
+
+        // The same actions spread over separate blocks go through
+        state_manager.apply_block(&test_block(2, vec![place(trader, OrderSide::Buy, 10, vec![], 0)])).unwrap();
+        assert_eq!(state_manager.get_asset(&asset_id).unwrap().owner, trader);
+        state_manager.apply_block(&test_block(3, vec![
+            place(trader, OrderSide::Sell, 50, vec![asset_id], 1),
+            place(trader, OrderSide::Buy, 5, vec![], 2),
+        ])).unwrap();
+        state_manager.apply_block(&test_block(4, vec![cancel(trader, order_id(&trader, 2), 3)])).unwrap();
+        assert_eq!(state_manager.get_open_orders("game").len(), 1);
+
+        // The one sale feeds the collection's market stats; the re-listing is the new floor
+        let stats = state_manager.get_market_stats("game").unwrap();
+        assert_eq!((stats.sales, stats.volume, stats.last_price), (1, 10, Some(10)));
+        assert_eq!(stats.hourly.len(), 1);
+        assert_eq!(state_manager.get_floor_price("game"), Some(50));
+
+        // Watchers see each block that moved the floor or the last sale price, and only those
+        let mut price_changes = Vec::new();
+        while let Ok(event) = ws_rx.try_recv() {
+            if let WsEvent::CollectionPriceChanged { game_id, floor_price, previous_floor_price, last_price, height, .. } = event {
+                assert_eq!(game_id, "game");
+                price_changes.push((height, previous_floor_price, floor_price, last_price));
+            }
+        }
+        assert_eq!(price_changes, vec![
+            (1, None, Some(10), None),
+            (2, Some(10), None, Some(10)),
+            (3, None, Some(50), Some(10)),
+        ]);
     }
 
-    /// Get economy instance
-    pub fn economy(&self) -> &Arc<FogEconomy> {
-        &self.economy
+    #[test]
+    fn test_stake_gas_discount_in_fee_and_receipt() {
+        use crate::config::StakeDiscountTier;
+        use crate::types::{AssetAction, AssetData, BlockHeader, DensityLevel};
+        let mut config = create_test_config("stake_gas_discount");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        config.vm.stake_discounts = vec![
+            StakeDiscountTier { min_stake: 1_000, discount_bps: 500 },
+            StakeDiscountTier { min_stake: 10_000, discount_bps: 1_000 },
+        ];
+        config.chain_id = 1;
+        config.validate().unwrap();
+        let state_manager = StateManager::new(&config).unwrap();
+
+        let staker = create_test_address(1);
+        let player = create_test_address(2);
+        state_manager.create_test_account(staker, 1_000_000, 0);
+        state_manager.create_test_account(player, 1_000_000, 0);
+        state_manager.apply_transaction(&Transaction::Stake {
+            from: staker,
+            validator: staker,
+            amount: 10_000,
+            fee: 0,
+            nonce: next_nonce(&state_manager, &staker),
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        }).unwrap();
+
+        let create = |owner: Address, name: &[u8]| Transaction::MistbornAsset {
+            from: owner,
+            action: AssetAction::Create,
+            asset_id: crate::types::sha256(name),
+            data: AssetData {
+                density: DensityLevel::Ethereal,
+                metadata: HashMap::new(),
+                attributes: vec![],
+                game_id: None,
+                owner,
+            },
+            fee: 0,
+            nonce: next_nonce(&state_manager, &owner),
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        let full_fee = match create(staker, b"probe") {
+            Transaction::MistbornAsset { action, data, .. } => {
+                crate::assets::calculate_asset_operation_gas(&config, &action, &data, Some(&data.metadata))
+                    * config.vm.gas_price
+            }
+            _ => unreachable!(),
+        };
+        let block = Block {
+            header: BlockHeader {
+                hash: [1u8; 32],
+                parent_hash: [0u8; 32],
+                height: 1,
+                timestamp: 0,
+                validator: staker,
+                merkle_root: [0u8; 32],
+                state_root: [0u8; 32],
+                wave_number: 0,
+                committee_id: 0,
+                protocol_version: PROTOCOL_VERSION,
+                state_version: STATE_VERSION,
+                signals: 0,
+            },
+            transactions: vec![create(staker, b"staker_asset"), create(player, b"player_asset")],
+            dag_references: vec![],
+        };
+        state_manager.apply_block(&block).unwrap();
+
+        // Highest tier reached: 10% off for the staker, nothing for the player
+        let discount = full_fee / 10;
+        assert_eq!(state_manager.get_account(&staker).unwrap().balance, 1_000_000 - 10_000 - (full_fee - discount));
+        assert_eq!(state_manager.get_account(&player).unwrap().balance, 1_000_000 - full_fee);
+        let receipts = state_manager.get_block_executions(1);
+        assert_eq!((receipts[0].fees_paid, receipts[0].gas_discount), (full_fee - discount, discount));
+        assert_eq!((receipts[1].fees_paid, receipts[1].gas_discount), (full_fee, 0));
+
+        // Tiers are capped and must increase
+        config.vm.stake_discounts.push(StakeDiscountTier { min_stake: 100_000, discount_bps: 5_000 });
+        assert!(config.validate().is_err());
+        config.vm.stake_discounts[2].discount_bps = 900;
+        assert!(config.validate().is_err());
     }
 
-    /// Get assets map (for API access)
-    pub fn assets(&self) -> &Arc<DashMap<Hash, AssetState>> {
-        &self.assets
-    }
+    #[test]
+    fn test_paymaster_sponsors_asset_gas() {
+        use crate::types::{AssetAction, AssetData, BlockHeader, DensityLevel};
+        let config = create_test_config("paymaster");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        let state_manager = StateManager::new(&config).unwrap();
 
-    /// Get blocks map (for API access)
-    pub fn blocks(&self) -> &Arc<DashMap<Hash, Block>> {
-        &self.blocks
-    }
+        let player = create_test_address(1);
+        let (generous, stingy, spinner, broke) =
+            (create_test_address(40), create_test_address(41), create_test_address(42), create_test_address(43));
+        state_manager.create_test_account(player, 1_000_000, 0);
+        for paymaster in [generous, stingy, spinner] {
+            state_manager.create_test_account(paymaster, 1_000_000, 0);
+        }
+        // Logs the request it was given and accepts
+        state_manager.register_contract(generous, br#"(module
+            (import "env" "haze_input" (func $input (param i32 i32) (result i32)))
+            (import "env" "haze_log" (func $log (param i32 i32 i32 i32)))
+            (memory (export "memory") 1)
+            (func (export "sponsor") (param i64) (result i32) (local $len i32)
+                (local.set $len (call $input (i32.const 0) (i32.const 4096)))
+                (call $log (i32.const 0) (i32.const 0) (i32.const 0) (local.get $len))
+                (i32.const 1)))"#.to_vec()).unwrap();
+        state_manager.register_contract(stingy, br#"(module
+            (func (export "sponsor") (result i32) (i32.const 0)))"#.to_vec()).unwrap();
+        state_manager.register_contract(spinner, br#"(module
+            (func (export "sponsor") (result i32) (loop $spin (br $spin)) (i32.const 1)))"#.to_vec()).unwrap();
+        state_manager.register_contract(broke, br#"(module
+            (func (export "sponsor") (result i32) (i32.const 1)))"#.to_vec()).unwrap();
 
-    /// Create test account (for testing only)
-    /// 
-    /// # Safety
-    /// This method bypasses normal transaction validation and should only be used in tests.
-    /// Available in test builds and integration tests.
-    pub fn create_test_account(&self, address: Address, balance: u64, nonce: u64) {
-        let account = AccountState {
-            balance,
-            nonce,
-            staked: 0,
+        let create = |name: &[u8], paymaster: &Address| Transaction::MistbornAsset {
+            from: player,
+            action: AssetAction::Create,
+            asset_id: crate::types::sha256(name),
+            data: AssetData {
+                density: DensityLevel::Ethereal,
+                metadata: HashMap::from([(PAYMASTER_KEY.to_string(), hex::encode(paymaster))]),
+                attributes: vec![],
+                game_id: Some("game1".to_string()),
+                owner: player,
+            },
+            fee: 0,
+            nonce: next_nonce(&state_manager, &player),
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
         };
-        self.accounts.insert(address, account);
+        let gas_fee = |tx: &Transaction| match tx {
+            Transaction::MistbornAsset { action, data, .. } => {
+                crate::assets::calculate_asset_operation_gas(&config, action, data, Some(&data.metadata))
+                    * config.vm.gas_price
+            }
+            _ => unreachable!(),
+        };
+        let apply = |height: u64, tx: &Transaction| {
+            state_manager.apply_block(&Block {
+                header: BlockHeader {
+                    hash: crate::types::sha256(&height.to_le_bytes()),
+                    parent_hash: [0u8; 32],
+                    height,
+                    timestamp: 0,
+                    validator: player,
+                    merkle_root: [0u8; 32],
+                    state_root: [0u8; 32],
+                    wave_number: 0,
+                    committee_id: 0,
+                    protocol_version: PROTOCOL_VERSION,
+                    state_version: STATE_VERSION,
+                    signals: 0,
+                },
+                transactions: vec![tx.clone()],
+                dag_references: vec![],
+            }).unwrap();
+            state_manager.get_block_executions(height).remove(0)
+        };
+        let balance = |address: &Address| state_manager.get_account(address).unwrap().balance;
+        let request = |tx: &Transaction| -> serde_json::Value {
+            serde_json::from_slice(&state_manager.get_transaction_logs(&tx.hash())[0].log.data).unwrap()
+        };
+
+        // Accepted: the paymaster pays the gas and its decision, and sees what it paid so far
+        let first = create(b"first", &generous);
+        let receipt = apply(1, &first);
+        let (sponsor, sponsored) = receipt.sponsorship.unwrap();
+        assert_eq!(sponsor, generous);
+        assert!(sponsored > gas_fee(&first));
+        assert_eq!(balance(&player), 1_000_000);
+        assert_eq!(balance(&generous), 1_000_000 - sponsored);
+        let seen = request(&first);
+        assert_eq!(seen["sender"], hex::encode(player));
+        assert_eq!(seen["game_id"], "game1");
+        assert_eq!(seen["gas_fee"], gas_fee(&first));
+        assert_eq!(seen["sponsored_in_window"], 0);
+        let second = create(b"second", &generous);
+        apply(2, &second);
+        assert_eq!(request(&second)["sponsored_in_window"], sponsored);
+        assert!(!state_manager.get_asset(&crate::types::sha256(b"first")).unwrap().data.metadata.contains_key(PAYMASTER_KEY));
+
+        // Declined: the owner pays the gas and the decision
+        let declined = create(b"declined", &stingy);
+        let receipt = apply(3, &declined);
+        assert!(receipt.sponsorship.is_none());
+        assert_eq!(balance(&stingy), 1_000_000);
+        let decision_fee = 1_000_000 - gas_fee(&declined) - balance(&player);
+        assert!(decision_fee > 0);
+
+        // A decision that never returns is cut off at the paymaster gas limit
+        let before = balance(&player);
+        let spun = create(b"spun", &spinner);
+        assert!(apply(4, &spun).sponsorship.is_none());
+        assert_eq!(
+            before - balance(&player),
+            gas_fee(&spun) + config.vm.paymaster_gas_limit * config.vm.gas_price
+        );
+
+        // A paymaster that cannot cover the fee is not asked
+        let before = balance(&player);
+        let unfunded = create(b"unfunded", &broke);
+        assert!(apply(5, &unfunded).sponsorship.is_none());
+        assert_eq!(before - balance(&player), gas_fee(&unfunded));
     }
 
-    /// Compute state root hash
-    /// This creates a hash of the current state (accounts + assets)
-    pub fn compute_state_root(&self) -> Hash {
-        use crate::types::sha256;
-        use bincode;
-        
-        // Collect all account states
-        let mut account_data = Vec::new();
-        for entry in self.accounts.iter() {
-            let account_bytes = bincode::serialize(&(*entry.key(), entry.value()))
-                .unwrap_or_default();
-            account_data.push(account_bytes);
-        }
-        account_data.sort();
-        
-        // Collect all asset states
-        let mut asset_data = Vec::new();
-        for entry in self.assets.iter() {
-            let asset_bytes = bincode::serialize(&(*entry.key(), entry.value()))
-                .unwrap_or_default();
-            asset_data.push(asset_bytes);
+    #[test]
+    fn test_referral_shares_crafting_and_marketplace_fees() {
+        use crate::config::ReferralProgram;
+        use crate::types::{AssetAction, AssetData, DensityLevel};
+        let mut config = create_test_config("referral_fees");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        config.economy.trade_fee_bps = 500;
+        config.economy.referral_programs = vec![ReferralProgram {
+            game_id: "game".to_string(),
+            share_bps: 2_000,
+            marketplace: true,
+            crafting: true,
+        }];
+        config.chain_id = 1;
+        config.validate().unwrap();
+        let state_manager = StateManager::new(&config).unwrap();
+
+        let player = create_test_address(1);
+        let referrer = create_test_address(2);
+        let buyer = create_test_address(3);
+        for address in [player, referrer, buyer] {
+            state_manager.create_test_account(address, 1_000_000, 0);
         }
-        asset_data.sort();
-        
-        // Combine and hash
-        let mut combined = Vec::new();
-        combined.extend(bincode::serialize(&account_data).unwrap_or_default());
-        combined.extend(bincode::serialize(&asset_data).unwrap_or_default());
-        combined.extend(bincode::serialize(&self.current_height()).unwrap_or_default());
-        
-        sha256(&combined)
+        let asset_id = crate::types::sha256(b"referred_asset");
+        let asset_tx = |action: AssetAction, density: DensityLevel| Transaction::MistbornAsset {
+            from: player,
+            action,
+            asset_id,
+            data: AssetData {
+                density,
+                metadata: HashMap::new(),
+                attributes: vec![],
+                game_id: Some("game".to_string()),
+                owner: player,
+            },
+            fee: 0,
+            nonce: next_nonce(&state_manager, &player),
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        let set_referrer = |game_id: &str, nonce: u64| Transaction::SetReferrer {
+            from: player,
+            game_id: game_id.to_string(),
+            referrer,
+            fee: 1,
+            nonce,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        let referrer_balance = || state_manager.get_account(&referrer).unwrap().balance;
+
+        // Fees paid before the binding are not shared
+        state_manager.apply_transaction(&asset_tx(AssetAction::Create, DensityLevel::Ethereal)).unwrap();
+        assert!(state_manager.apply_transaction(&set_referrer("other_game", 1)).is_err());
+        state_manager.apply_transaction(&set_referrer("game", 1)).unwrap();
+        assert!(state_manager.apply_transaction(&set_referrer("game", 2)).is_err());
+        assert_eq!(state_manager.get_referral("game", &player).unwrap().referrer, referrer);
+        assert_eq!(referrer_balance(), 1_000_000);
+
+        let condense = asset_tx(AssetAction::Condense, DensityLevel::Light);
+        let crafting_fee = match &condense {
+            Transaction::MistbornAsset { action, data, .. } => {
+                crate::assets::calculate_asset_operation_gas(&config, action, data, Some(&data.metadata)) * config.vm.gas_price
+            }
+            _ => unreachable!(),
+        };
+        state_manager.apply_transaction(&condense).unwrap();
+        assert_eq!(referrer_balance(), 1_000_000 + crafting_fee / 5);
+
+        // A 1000 HAZE sale pays a 50 HAZE marketplace fee, 10 of it to the seller's referrer
+        let order = |from: Address, side: OrderSide, assets: Vec<Hash>, nonce: u64| Transaction::PlaceOrder {
+            from,
+            game_id: "game".to_string(),
+            side,
+            price: 1_000,
+            quantity: 1,
+            assets,
+            fee: 0,
+            nonce,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        state_manager.apply_transaction(&order(player, OrderSide::Sell, vec![asset_id], 3)).unwrap();
+        let seller_before = state_manager.get_account(&player).unwrap().balance;
+        state_manager.apply_transaction(&order(buyer, OrderSide::Buy, vec![], 0)).unwrap();
+        assert_eq!(state_manager.get_account(&player).unwrap().balance, seller_before + 950);
+        assert_eq!(state_manager.get_trades("game", 1)[0].fee, 50);
+        assert_eq!(referrer_balance(), 1_000_000 + crafting_fee / 5 + 10);
+
+        let earnings = &state_manager.get_referral_earnings(&referrer)["game"];
+        assert_eq!((earnings.players, earnings.crafting, earnings.marketplace), (1, crafting_fee / 5, 10));
     }
-}
 
-impl Clone for StateManager {
-    fn clone(&self) -> Self {
-        Self {
-            db: self.db.clone(),
-            config: self.config.clone(),
-            accounts: self.accounts.clone(),
-            assets: self.assets.clone(),
-            blocks: self.blocks.clone(),
-            current_height: self.current_height.clone(),
-            tokenomics: self.tokenomics.clone(),
-            economy: self.economy.clone(),
-            ws_tx: self.ws_tx.clone(),
-            asset_index_by_owner: self.asset_index_by_owner.clone(),
-            asset_index_by_game_id: self.asset_index_by_game_id.clone(),
-            asset_index_by_density: self.asset_index_by_density.clone(),
-            asset_access_count: self.asset_access_count.clone(),
-            contracts: self.contracts.clone(),
+    #[test]
+    fn test_subscription_charges_grace_and_cancel() {
+        use crate::types::{subscription_id, BlockHeader};
+        let mut config = create_test_config("subscriptions");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        config.economy.subscription_grace_blocks = 2;
+        let state_manager = StateManager::new(&config).unwrap();
+
+        let player = create_test_address(1);
+        let studio = create_test_address(2);
+        let friend = create_test_address(3);
+        let casual = create_test_address(4);
+        state_manager.create_test_account(player, 100, 0);
+        state_manager.create_test_account(studio, 0, 0);
+        state_manager.create_test_account(friend, 1_000, 0);
+        state_manager.create_test_account(casual, 5, 0);
+
+        let subscribe = |from: Address, amount: u64, interval_blocks: u64| Transaction::CreateSubscription {
+            from,
+            payee: studio,
+            amount,
+            interval_blocks,
+            end_height: None,
+            fee: 0,
+            nonce: 0,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        let balance = |address: &Address| state_manager.get_account(address).unwrap().balance;
+        let pass_id = subscription_id(&player, 0);
+        let casual_id = subscription_id(&casual, 0);
+
+        // The first period is paid on creation, the next ones every 2 blocks
+        state_manager.apply_block(&test_block(1, vec![subscribe(player, 30, 2), subscribe(casual, 5, 1)])).unwrap();
+        assert_eq!(balance(&player), 70);
+        for height in 2..=5 {
+            state_manager.apply_block(&test_block(height, vec![])).unwrap();
         }
-    }
-}
+        assert_eq!(balance(&player), 10);
+        assert_eq!(state_manager.get_subscription(&pass_id).unwrap().charges, 3);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::types::Address;
-    use std::path::PathBuf;
+        // The casual player could not pay at block 2 and lapsed after the grace period
+        let lapsed = state_manager.get_subscription(&casual_id).unwrap();
+        assert_eq!((lapsed.status, lapsed.past_due_since, lapsed.charges), (SubscriptionStatus::Lapsed, Some(2), 1));
 
-    fn create_test_address(seed: u8) -> Address {
-        let mut addr = [0u8; 32];
-        addr[0] = seed;
-        addr
-    }
+        // Past due at block 7, paid at block 8 once topped up
+        state_manager.apply_block(&test_block(6, vec![])).unwrap();
+        state_manager.apply_block(&test_block(7, vec![])).unwrap();
+        assert_eq!(state_manager.get_subscription(&pass_id).unwrap().status, SubscriptionStatus::PastDue);
+        let top_up = Transaction::Transfer {
+            from: friend,
+            to: player,
+            amount: 50,
+            fee: 0,
+            nonce: 0,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        state_manager.apply_block(&test_block(8, vec![top_up])).unwrap();
+        let pass = state_manager.get_subscription(&pass_id).unwrap();
+        assert_eq!((pass.status, pass.charges, pass.next_charge_height), (SubscriptionStatus::Active, 4, 9));
+        assert_eq!(balance(&player), 30);
 
-    fn create_test_config(test_name: &str) -> Config {
-        let mut config = Config::default();
-        // Use unique database path for each test
-        let test_db_path = format!("./haze_db_test_{}", test_name);
-        config.storage.db_path = PathBuf::from(test_db_path);
-        config
+        // Either party can cancel; a cancel in the due block prevents that charge
+        let cancel = |from: Address, nonce: u64| Transaction::CancelSubscription {
+            from,
+            subscription_id: pass_id,
+            fee: 0,
+            nonce,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        assert!(matches!(state_manager.apply_transaction(&cancel(friend, 1)), Err(HazeError::AccessDenied(_))));
+        state_manager.apply_block(&test_block(9, vec![cancel(studio, 0)])).unwrap();
+        let pass = state_manager.get_subscription(&pass_id).unwrap();
+        assert_eq!((pass.status, pass.cancelled_by), (SubscriptionStatus::Cancelled, Some(studio)));
+        assert_eq!(balance(&player), 30);
+        assert_eq!(balance(&studio), 4 * 30 + 5);
+        assert_eq!(state_manager.get_account_subscriptions(&studio).len(), 2);
     }
 
     #[test]
-    fn test_state_manager_new() {
-        let config = create_test_config("new");
+    fn test_create_airdrop_credits_claims() {
+        let config = create_test_config("create_airdrop");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
         let state_manager = StateManager::new(&config).unwrap();
-        
-        assert_eq!(state_manager.current_height(), 0);
-        assert_eq!(state_manager.tokenomics().total_supply(), crate::tokenomics::INITIAL_SUPPLY);
+
+        let sender = create_test_address(1);
+        state_manager.create_test_account(sender, 1_000, 0);
+        let claims = vec![
+            crate::types::AirdropClaim { address: create_test_address(2), amount: 100 },
+            crate::types::AirdropClaim { address: create_test_address(3), amount: 200 },
+            crate::types::AirdropClaim { address: sender, amount: 50 },
+        ];
+        let airdrop = |claims: Vec<crate::types::AirdropClaim>, nonce: u64| Transaction::CreateAirdrop {
+            from: sender,
+            claims_root: crate::types::airdrop_claims_root(&claims),
+            claims,
+            fee: 10,
+            nonce,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+
+        state_manager.apply_transaction(&airdrop(claims, 0)).unwrap();
+        assert_eq!(state_manager.get_account(&create_test_address(2)).unwrap().balance, 100);
+        assert_eq!(state_manager.get_account(&create_test_address(3)).unwrap().balance, 200);
+        let sender_account = state_manager.get_account(&sender).unwrap();
+        assert_eq!(sender_account.balance, 1_000 - 350 - 10 + 50);
+        assert_eq!(sender_account.nonce, 1);
+
+        // Nothing is credited when the sender cannot cover the total
+        let too_large = vec![crate::types::AirdropClaim { address: create_test_address(4), amount: 10_000 }];
+        assert!(state_manager.apply_transaction(&airdrop(too_large, 1)).is_err());
+        assert!(state_manager.get_account(&create_test_address(4)).is_none());
     }
 
     #[test]
-    fn test_get_account_nonexistent() {
-        let config = create_test_config("get_account");
+    fn test_asset_versions() {
+        let config = create_test_config("versions");
         let state_manager = StateManager::new(&config).unwrap();
-        let address = create_test_address(1);
         
-        // Account should not exist
-        assert!(state_manager.get_account(&address).is_none());
+        let owner = create_test_address(1);
+        // Create account with balance for gas fees
+        state_manager.create_test_account(owner, 100_000, 0);
+        
+        let asset_id = crate::types::sha256(b"test_asset");
+        
+        // Create asset
+        let tx = Transaction::MistbornAsset {
+            from: owner,
+            action: crate::types::AssetAction::Create,
+            asset_id,
+            data: crate::types::AssetData {
+                density: crate::types::DensityLevel::Ethereal,
+                metadata: std::collections::HashMap::new(),
+                attributes: vec![],
+                game_id: None,
+                owner,
+            },
+            fee: 0,
+            nonce: 0,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        
+        state_manager.apply_transaction(&tx).unwrap();
+        
+        // Check initial version (after create, version 1 should be created)
+        let asset_state = state_manager.get_asset(&asset_id).unwrap();
+        assert_eq!(asset_state.current_version, 1);
+        
+        let versions = state_manager.get_asset_versions(&asset_id).unwrap();
+        // Should have 1 version (version 1 from initial snapshot)
+        assert_eq!(versions.len(), 1);
+        assert_eq!(versions[0].version, 1);
+        
+        // Create manual snapshot
+        let version = state_manager.create_asset_snapshot(&asset_id).unwrap();
+        assert_eq!(version, 2);
+        
+        // Check versions (should have 2 now)
+        let versions = state_manager.get_asset_versions(&asset_id).unwrap();
+        assert_eq!(versions.len(), 2);
+        assert!(versions.iter().any(|v| v.version == 1));
+        assert!(versions.iter().any(|v| v.version == 2));
+        
+        // Get specific version
+        let v1 = state_manager.get_asset_version(&asset_id, 1).unwrap();
+        assert_eq!(v1.version, 1);
+        
+        let v2 = state_manager.get_asset_version(&asset_id, 2).unwrap();
+        assert_eq!(v2.version, 2);
+        
+        // Get current version (version 0 means current)
+        let v_current = state_manager.get_asset_version(&asset_id, 0).unwrap();
+        assert_eq!(v_current.version, 2);
     }
 
     #[test]
-    fn test_compute_state_root() {
-        let config = create_test_config("state_root");
+    fn test_asset_versions_on_condense() {
+        let config = create_test_config("versions_condense");
         let state_manager = StateManager::new(&config).unwrap();
         
-        // Compute state root for empty state
-        let state_root1 = state_manager.compute_state_root();
-        assert_ne!(state_root1, [0u8; 32]);
+        let owner = create_test_address(1);
+        // Create account with balance for gas fees
+        state_manager.create_test_account(owner, 100_000, 0);
+        
+        let asset_id = crate::types::sha256(b"test_asset");
+        
+        // Create asset
+        let tx = Transaction::MistbornAsset {
+            from: owner,
+            action: crate::types::AssetAction::Create,
+            asset_id,
+            data: crate::types::AssetData {
+                density: crate::types::DensityLevel::Ethereal,
+                metadata: std::collections::HashMap::new(),
+                attributes: vec![],
+                game_id: None,
+                owner,
+            },
+            fee: 0,
+            nonce: next_nonce(&state_manager, &owner),
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        
+        state_manager.apply_transaction(&tx).unwrap();
+        
+        // Condense (should create snapshot)
+        let mut condense_metadata = std::collections::HashMap::new();
+        condense_metadata.insert("new_data".to_string(), "value".to_string());
+        
+        let condense_tx = Transaction::MistbornAsset {
+            from: owner,
+            action: crate::types::AssetAction::Condense,
+            asset_id,
+            data: crate::types::AssetData {
+                density: crate::types::DensityLevel::Light,
+                metadata: condense_metadata,
+                attributes: vec![],
+                game_id: None,
+                owner,
+            },
+            fee: 0,
+            nonce: next_nonce(&state_manager, &owner),
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![2; 64],
+        };
+        
+        state_manager.apply_transaction(&condense_tx).unwrap();
         
-        // State root should be consistent for same state
-        let state_root2 = state_manager.compute_state_root();
-        assert_eq!(state_root1, state_root2);
-    }
-
-    #[test]
-    fn test_current_height() {
-        let config = create_test_config("height");
-        let state_manager = StateManager::new(&config).unwrap();
+        // Should have 2 versions (initial + condense)
+        let asset_state = state_manager.get_asset(&asset_id).unwrap();
+        assert_eq!(asset_state.current_version, 2); // After condense, version should be 2
         
-        // Initial height should be 0
-        assert_eq!(state_manager.current_height(), 0);
+        let versions = state_manager.get_asset_versions(&asset_id).unwrap();
+        assert_eq!(versions.len(), 2);
+        // Versions should be sorted, so version 1 first, then version 2
+        let sorted_versions: Vec<u64> = versions.iter().map(|v| v.version).collect();
+        assert_eq!(sorted_versions, vec![1, 2]);
     }
 
-    /// apply_block requires strict sequential height: block.height == current_height + 1.
     #[test]
-    fn test_apply_block_strict_sequential_height() {
-        use crate::types::{Block, BlockHeader};
-        let config = create_test_config("apply_block_height");
+    fn test_create_asset() {
+        let config = create_test_config("create_only");
         let state_manager = StateManager::new(&config).unwrap();
-        assert_eq!(state_manager.current_height(), 0);
-        let addr = create_test_address(1);
-        let block = Block {
-            header: BlockHeader {
-                hash: [0u8; 32],
-                parent_hash: [0u8; 32],
-                height: 2, // wrong: next expected is 1
-                timestamp: 0,
-                validator: addr,
-                merkle_root: [0u8; 32],
-                state_root: [0u8; 32],
-                wave_number: 0,
-                committee_id: 0,
+        let owner = create_test_address(1);
+        state_manager.create_test_account(owner, 100_000, 0);
+
+        let asset_id = crate::types::sha256(b"create_test");
+        let mut meta = std::collections::HashMap::new();
+        meta.insert("name".to_string(), "Test Asset".to_string());
+
+        let tx = Transaction::MistbornAsset {
+            from: owner,
+            action: crate::types::AssetAction::Create,
+            asset_id,
+            data: crate::types::AssetData {
+                density: crate::types::DensityLevel::Ethereal,
+                metadata: meta,
+                attributes: vec![],
+                game_id: Some("g1".to_string()),
+                owner,
             },
-            transactions: vec![],
-            dag_references: vec![],
+            fee: 0,
+            nonce: 0,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
         };
-        let result = state_manager.apply_block(&block);
-        assert!(result.is_err());
-        let err_msg = result.unwrap_err().to_string();
-        assert!(err_msg.contains("current_height") || err_msg.contains("sequential"), "expected height/sequential error, got: {}", err_msg);
+        state_manager.apply_transaction(&tx).unwrap();
+
+        let asset = state_manager.get_asset(&asset_id).unwrap();
+        assert_eq!(asset.owner, owner);
+        assert_eq!(asset.data.density, crate::types::DensityLevel::Ethereal);
+        assert_eq!(asset.data.metadata.get("name"), Some(&"Test Asset".to_string()));
+        assert_eq!(asset.data.game_id, Some("g1".to_string()));
     }
 
     #[test]
-    fn test_merge_assets() {
-        let config = create_test_config("merge");
+    fn test_evaporate_asset() {
+        let config = create_test_config("evaporate");
         let state_manager = StateManager::new(&config).unwrap();
-        
         let owner = create_test_address(1);
-        // Create account with balance for gas fees
         state_manager.create_test_account(owner, 100_000, 0);
-        
-        // Create first asset
-        let asset_id_1 = crate::types::sha256(b"asset1");
-        let tx1 = Transaction::MistbornAsset {
+
+        let asset_id = crate::types::sha256(b"evap_asset");
+        let create_tx = Transaction::MistbornAsset {
             from: owner,
             action: crate::types::AssetAction::Create,
-            asset_id: asset_id_1,
+            asset_id,
             data: crate::types::AssetData {
-                density: crate::types::DensityLevel::Ethereal,
-                metadata: {
-                    let mut m = std::collections::HashMap::new();
-                    m.insert("name".to_string(), "Asset 1".to_string());
-                    m.insert("type".to_string(), "sword".to_string());
-                    m
-                },
-                attributes: vec![crate::types::Attribute {
-                    name: "damage".to_string(),
-                    value: "10".to_string(),
-                    rarity: None,
-                }],
-                game_id: Some("game1".to_string()),
+                density: crate::types::DensityLevel::Light,
+                metadata: std::collections::HashMap::new(),
+                attributes: vec![],
+                game_id: None,
                 owner,
             },
             fee: 0,
-            nonce: 0,
+            nonce: next_nonce(&state_manager, &owner),
             chain_id: None,
             valid_until_height: None,
-            signature: vec![1; 64], // Dummy signature for test
+            signature: vec![1; 64],
         };
-        
-        state_manager.apply_transaction(&tx1).unwrap();
-        assert!(state_manager.get_asset(&asset_id_1).is_some());
-        
-        // Create second asset
-        let asset_id_2 = crate::types::sha256(b"asset2");
-        let tx2 = Transaction::MistbornAsset {
+        state_manager.apply_transaction(&create_tx).unwrap();
+        assert_eq!(state_manager.get_asset(&asset_id).unwrap().data.density, crate::types::DensityLevel::Light);
+
+        let evap_tx = Transaction::MistbornAsset {
             from: owner,
-            action: crate::types::AssetAction::Create,
-            asset_id: asset_id_2,
+            action: crate::types::AssetAction::Evaporate,
+            asset_id,
             data: crate::types::AssetData {
-                density: crate::types::DensityLevel::Light,
-                metadata: {
-                    let mut m = std::collections::HashMap::new();
-                    m.insert("name".to_string(), "Asset 2".to_string());
-                    m.insert("rarity".to_string(), "epic".to_string());
-                    m
-                },
-                attributes: vec![crate::types::Attribute {
-                    name: "defense".to_string(),
-                    value: "5".to_string(),
-                    rarity: None,
-                }],
-                game_id: Some("game1".to_string()),
+                density: crate::types::DensityLevel::Ethereal,
+                metadata: std::collections::HashMap::new(),
+                attributes: vec![],
+                game_id: None,
                 owner,
             },
             fee: 0,
-            nonce: 0,
+            nonce: next_nonce(&state_manager, &owner),
             chain_id: None,
             valid_until_height: None,
             signature: vec![2; 64],
         };
-        
-        state_manager.apply_transaction(&tx2).unwrap();
-        assert!(state_manager.get_asset(&asset_id_2).is_some());
-        
-        // Merge assets
-        let mut merge_metadata = std::collections::HashMap::new();
-        merge_metadata.insert("_other_asset_id".to_string(), hex::encode(asset_id_2));
-        
-        let merge_tx = Transaction::MistbornAsset {
+        state_manager.apply_transaction(&evap_tx).unwrap();
+
+        let asset = state_manager.get_asset(&asset_id).unwrap();
+        assert_eq!(asset.data.density, crate::types::DensityLevel::Ethereal);
+    }
+
+    #[test]
+    fn test_metadata_size_exceeded() {
+        let config = create_test_config("meta_size");
+        let state_manager = StateManager::new(&config).unwrap();
+        let owner = create_test_address(1);
+        state_manager.create_test_account(owner, 100_000, 0);
+
+        let asset_id = crate::types::sha256(b"oversized");
+        let mut meta = std::collections::HashMap::new();
+        meta.insert("big".to_string(), "x".to_string().repeat(6 * 1024)); // Ethereal max 5KB
+
+        let tx = Transaction::MistbornAsset {
             from: owner,
-            action: crate::types::AssetAction::Merge,
-            asset_id: asset_id_1,
+            action: crate::types::AssetAction::Create,
+            asset_id,
             data: crate::types::AssetData {
                 density: crate::types::DensityLevel::Ethereal,
-                metadata: merge_metadata,
+                metadata: meta,
                 attributes: vec![],
                 game_id: None,
                 owner,
@@ -2033,285 +9678,407 @@ mod tests {
             nonce: 0,
             chain_id: None,
             valid_until_height: None,
-            signature: vec![3; 64],
+            signature: vec![1; 64],
         };
-        
-        state_manager.apply_transaction(&merge_tx).unwrap();
-        
-        // Check that merged asset exists and has combined data
-        let merged_asset = state_manager.get_asset(&asset_id_1).unwrap();
-        assert_eq!(merged_asset.owner, owner);
-        assert!(merged_asset.data.metadata.contains_key("name"));
-        assert!(merged_asset.data.metadata.contains_key("type"));
-        assert!(merged_asset.data.metadata.contains_key("rarity"));
-        // Density should be increased to Light (from asset 2)
-        assert_eq!(merged_asset.data.density, crate::types::DensityLevel::Light);
-        // Should have both attributes
-        assert_eq!(merged_asset.data.attributes.len(), 2);
-        
-        // Check that other asset is removed
-        assert!(state_manager.get_asset(&asset_id_2).is_none());
+        let res = state_manager.apply_transaction(&tx);
+        assert!(res.is_err());
+        let err = res.unwrap_err().to_string();
+        assert!(err.contains("Asset size exceeded") || err.contains("size"));
     }
 
     #[test]
-    fn test_merge_assets_different_owners() {
-        let config = create_test_config("merge_different_owners");
+    fn test_set_asset_permissions() {
+        let config = create_test_config("perms");
         let state_manager = StateManager::new(&config).unwrap();
-        
-        let owner1 = create_test_address(1);
-        let owner2 = create_test_address(2);
-        // Create accounts with balance for gas fees
-        state_manager.create_test_account(owner1, 100_000, 0);
-        state_manager.create_test_account(owner2, 100_000, 0);
-        
-        // Create first asset
-        let asset_id_1 = crate::types::sha256(b"asset1");
-        let tx1 = Transaction::MistbornAsset {
-            from: owner1,
+        let owner = create_test_address(1);
+        let other = create_test_address(2);
+        state_manager.create_test_account(owner, 100_000, 0);
+
+        let asset_id = crate::types::sha256(b"perm_asset");
+        let create_tx = Transaction::MistbornAsset {
+            from: owner,
             action: crate::types::AssetAction::Create,
-            asset_id: asset_id_1,
+            asset_id,
             data: crate::types::AssetData {
                 density: crate::types::DensityLevel::Ethereal,
                 metadata: std::collections::HashMap::new(),
                 attributes: vec![],
                 game_id: None,
-                owner: owner1,
+                owner,
             },
             fee: 0,
-            nonce: 0,
+            nonce: next_nonce(&state_manager, &owner),
             chain_id: None,
             valid_until_height: None,
             signature: vec![1; 64],
         };
-        
-        state_manager.apply_transaction(&tx1).unwrap();
-        
-        // Create second asset with different owner
-        let asset_id_2 = crate::types::sha256(b"asset2");
-        let tx2 = Transaction::MistbornAsset {
-            from: owner2,
+        state_manager.apply_transaction(&create_tx).unwrap();
+
+        let set_tx = Transaction::SetAssetPermissions {
+            from: owner,
+            asset_id,
+            permissions: vec![crate::types::AssetPermission {
+                grantee: other,
+                level: crate::types::PermissionLevel::PublicRead,
+                game_id: None,
+                expires_at: None,
+            }],
+            public_read: true,
+            owner,
+            fee: 7,
+            nonce: 1,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![2; 64],
+        };
+        let balance_before = state_manager.get_account(&owner).unwrap().balance;
+        state_manager.apply_transaction(&set_tx).unwrap();
+
+        let asset = state_manager.get_asset(&asset_id).unwrap();
+        assert!(asset.public_read);
+        assert_eq!(asset.permissions.len(), 1);
+        assert_eq!(asset.permissions[0].grantee, other);
+        assert_eq!(asset.permissions[0].level, crate::types::PermissionLevel::PublicRead);
+
+        // The owner pays the fee and uses up the nonce
+        let account = state_manager.get_account(&owner).unwrap();
+        assert_eq!(account.balance, balance_before - 7);
+        assert_eq!(account.nonce, 2);
+        assert!(matches!(state_manager.apply_transaction(&set_tx), Err(HazeError::InvalidNonce { expected: 2, actual: 1 })));
+
+        // Only the owner may send it
+        state_manager.create_test_account(other, 100, 0);
+        let mut foreign_tx = set_tx.clone();
+        if let Transaction::SetAssetPermissions { from, nonce, .. } = &mut foreign_tx {
+            *from = other;
+            *nonce = 0;
+        }
+        assert!(matches!(state_manager.apply_transaction(&foreign_tx), Err(HazeError::AccessDenied(_))));
+        assert_eq!(state_manager.get_account(&other).unwrap().balance, 100);
+    }
+
+    #[test]
+    fn test_asset_and_stake_transactions_use_sender_nonce() {
+        let config = create_test_config("asset_stake_nonces");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        let state_manager = StateManager::new(&config).unwrap();
+        let owner = create_test_address(1);
+        let other = create_test_address(2);
+        state_manager.create_test_account(owner, 100_000, 0);
+        state_manager.create_test_account(other, 100_000, 0);
+
+        let create = |from: Address, nonce: u64| Transaction::MistbornAsset {
+            from,
             action: crate::types::AssetAction::Create,
-            asset_id: asset_id_2,
+            asset_id: crate::types::sha256(b"nonce_asset"),
             data: crate::types::AssetData {
                 density: crate::types::DensityLevel::Ethereal,
                 metadata: std::collections::HashMap::new(),
                 attributes: vec![],
                 game_id: None,
-                owner: owner2,
+                owner,
             },
             fee: 0,
-            nonce: 0,
+            nonce,
             chain_id: None,
             valid_until_height: None,
-            signature: vec![2; 64],
+            signature: vec![1; 64],
         };
-        
-        state_manager.apply_transaction(&tx2).unwrap();
-        
-        // Try to merge - should fail
-        let mut merge_metadata = std::collections::HashMap::new();
-        merge_metadata.insert("_other_asset_id".to_string(), hex::encode(asset_id_2));
-        
-        let merge_tx = Transaction::MistbornAsset {
-            from: owner1,
-            action: crate::types::AssetAction::Merge,
-            asset_id: asset_id_1,
-            data: crate::types::AssetData {
-                density: crate::types::DensityLevel::Ethereal,
-                metadata: merge_metadata,
-                attributes: vec![],
-                game_id: None,
-                owner: owner1,
-            },
+        let stake = |nonce: u64| Transaction::Stake {
+            from: owner,
+            validator: owner,
+            amount: 100,
             fee: 0,
-            nonce: 0,
+            nonce,
             chain_id: None,
             valid_until_height: None,
-            signature: vec![3; 64],
+            signature: vec![1; 64],
         };
-        
-        let result = state_manager.apply_transaction(&merge_tx);
-        assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains("different owners"));
-        
-        // Both assets should still exist
-        assert!(state_manager.get_asset(&asset_id_1).is_some());
-        assert!(state_manager.get_asset(&asset_id_2).is_some());
+
+        // Only the owner sends an asset transaction, and it advances the owner's nonce
+        assert!(matches!(state_manager.apply_transaction(&create(other, 0)), Err(HazeError::AccessDenied(_))));
+        state_manager.apply_transaction(&create(owner, 0)).unwrap();
+        assert_eq!(state_manager.get_account(&owner).unwrap().nonce, 1);
+        assert!(matches!(
+            state_manager.apply_transaction(&create(owner, 0)),
+            Err(HazeError::InvalidNonce { expected: 1, actual: 0 })
+        ));
+
+        assert!(state_manager.apply_transaction(&stake(0)).is_err());
+        state_manager.apply_transaction(&stake(1)).unwrap();
+        let account = state_manager.get_account(&owner).unwrap();
+        assert_eq!((account.nonce, account.staked), (2, 100));
+        assert!(matches!(state_manager.apply_transaction(&stake(1)), Err(HazeError::InvalidNonce { expected: 2, actual: 1 })));
     }
 
     #[test]
-    fn test_split_asset() {
-        let config = create_test_config("split");
+    fn test_search_assets_by_density() {
+        let config = create_test_config("search_density");
         let state_manager = StateManager::new(&config).unwrap();
-        
         let owner = create_test_address(1);
-        // Create account with balance for gas fees
         state_manager.create_test_account(owner, 100_000, 0);
-        
-        // Create asset with multiple components
-        let asset_id = crate::types::sha256(b"composite_asset");
-        let mut metadata = std::collections::HashMap::new();
-        metadata.insert("component1".to_string(), "sword_data".to_string());
-        metadata.insert("component2".to_string(), "shield_data".to_string());
-        metadata.insert("component3".to_string(), "armor_data".to_string());
-        
-        let tx = Transaction::MistbornAsset {
+
+        let id_e = crate::types::sha256(b"e");
+        let id_l = crate::types::sha256(b"l");
+        for (id, density) in [
+            (id_e, crate::types::DensityLevel::Ethereal),
+            (id_l, crate::types::DensityLevel::Light),
+        ] {
+            let tx = Transaction::MistbornAsset {
+                from: owner,
+                action: crate::types::AssetAction::Create,
+                asset_id: id,
+                data: crate::types::AssetData {
+                    density,
+                    metadata: std::collections::HashMap::new(),
+                    attributes: vec![],
+                    game_id: None,
+                    owner,
+                },
+                fee: 0,
+                nonce: next_nonce(&state_manager, &owner),
+                chain_id: None,
+                valid_until_height: None,
+                signature: vec![id[0]; 64],
+            };
+            state_manager.apply_transaction(&tx).unwrap();
+        }
+
+        let ethereal = state_manager.search_assets_by_density(crate::types::DensityLevel::Ethereal);
+        let light = state_manager.search_assets_by_density(crate::types::DensityLevel::Light);
+        assert!(ethereal.contains(&id_e));
+        assert!(light.contains(&id_l));
+        assert!(!ethereal.contains(&id_l));
+        assert!(!light.contains(&id_e));
+    }
+
+    #[test]
+    fn test_write_permission_game_contract() {
+        let config = create_test_config("game_contract");
+        let state_manager = StateManager::new(&config).unwrap();
+        let owner = create_test_address(1);
+        let grantee = create_test_address(2);
+        state_manager.create_test_account(owner, 100_000, 0);
+        state_manager.create_test_account(grantee, 100_000, 0);
+
+        let asset_id = crate::types::sha256(b"game_asset");
+        let create_tx = Transaction::MistbornAsset {
             from: owner,
             action: crate::types::AssetAction::Create,
             asset_id,
             data: crate::types::AssetData {
-                density: crate::types::DensityLevel::Dense,
-                metadata: metadata.clone(),
-                attributes: vec![
-                    crate::types::Attribute {
-                        name: "power".to_string(),
-                        value: "100".to_string(),
-                        rarity: None,
-                    },
-                ],
+                density: crate::types::DensityLevel::Ethereal,
+                metadata: std::collections::HashMap::new(),
+                attributes: vec![],
                 game_id: Some("game1".to_string()),
                 owner,
             },
             fee: 0,
-            nonce: 0,
+            nonce: next_nonce(&state_manager, &owner),
             chain_id: None,
             valid_until_height: None,
             signature: vec![1; 64],
         };
-        
-        state_manager.apply_transaction(&tx).unwrap();
-        assert!(state_manager.get_asset(&asset_id).is_some());
-        
-        // Split asset into components
-        let mut split_metadata = std::collections::HashMap::new();
-        split_metadata.insert("_components".to_string(), "component1,component2,component3".to_string());
-        
-        let split_tx = Transaction::MistbornAsset {
+        state_manager.apply_transaction(&create_tx).unwrap();
+
+        let set_tx = Transaction::SetAssetPermissions {
             from: owner,
-            action: crate::types::AssetAction::Split,
+            asset_id,
+            permissions: vec![crate::types::AssetPermission {
+                grantee,
+                level: crate::types::PermissionLevel::GameContract,
+                game_id: Some("game1".to_string()),
+                expires_at: None,
+            }],
+            public_read: false,
+            owner,
+            fee: 0,
+            nonce: 1,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![2; 64],
+        };
+        state_manager.apply_transaction(&set_tx).unwrap();
+
+        let mut upd_meta = std::collections::HashMap::new();
+        upd_meta.insert("updated".to_string(), "by_grantee".to_string());
+        let update_tx = Transaction::MistbornAsset {
+            from: grantee,
+            action: crate::types::AssetAction::Update,
             asset_id,
             data: crate::types::AssetData {
                 density: crate::types::DensityLevel::Ethereal,
-                metadata: split_metadata,
+                metadata: upd_meta,
                 attributes: vec![],
-                game_id: None,
-                owner,
+                game_id: Some("game1".to_string()),
+                owner: grantee,
             },
             fee: 0,
-            nonce: 0,
+            nonce: next_nonce(&state_manager, &grantee),
             chain_id: None,
             valid_until_height: None,
-            signature: vec![2; 64],
+            signature: vec![3; 64],
         };
-        
-        state_manager.apply_transaction(&split_tx).unwrap();
-        
-        // Check that source asset is removed
-        assert!(state_manager.get_asset(&asset_id).is_none());
-        
-        // Check that component assets were created
-        let component1_id = crate::types::sha256(&[asset_id.as_ref(), b"component1"].concat());
-        let component2_id = crate::types::sha256(&[asset_id.as_ref(), b"component2"].concat());
-        let component3_id = crate::types::sha256(&[asset_id.as_ref(), b"component3"].concat());
-        
-        let comp1 = state_manager.get_asset(&component1_id).unwrap();
-        assert_eq!(comp1.owner, owner);
-        assert_eq!(comp1.data.metadata.get("component1"), Some(&"sword_data".to_string()));
-        assert_eq!(comp1.data.density, crate::types::DensityLevel::Ethereal);
-        
-        let comp2 = state_manager.get_asset(&component2_id).unwrap();
-        assert_eq!(comp2.owner, owner);
-        assert_eq!(comp2.data.metadata.get("component2"), Some(&"shield_data".to_string()));
-        
-        let comp3 = state_manager.get_asset(&component3_id).unwrap();
-        assert_eq!(comp3.owner, owner);
-        assert_eq!(comp3.data.metadata.get("component3"), Some(&"armor_data".to_string()));
+        state_manager.apply_transaction(&update_tx).unwrap();
+
+        let asset = state_manager.get_asset(&asset_id).unwrap();
+        assert_eq!(asset.data.metadata.get("updated"), Some(&"by_grantee".to_string()));
     }
 
     #[test]
-    fn test_split_asset_invalid_owner() {
-        let config = create_test_config("split_invalid_owner");
+    fn test_namespaced_metadata_writes() {
+        use crate::types::{AssetAction, AssetData, AssetPermission, Attribute, DensityLevel, PermissionLevel};
+        let config = create_test_config("metadata_namespaces");
         let state_manager = StateManager::new(&config).unwrap();
-        
-        let owner1 = create_test_address(1);
-        let owner2 = create_test_address(2);
-        // Create accounts with balance for gas fees
-        state_manager.create_test_account(owner1, 100_000, 0);
-        state_manager.create_test_account(owner2, 100_000, 0);
-        
-        // Create asset
-        let asset_id = crate::types::sha256(b"asset");
-        let tx = Transaction::MistbornAsset {
-            from: owner1,
-            action: crate::types::AssetAction::Create,
-            asset_id,
-            data: crate::types::AssetData {
-                density: crate::types::DensityLevel::Ethereal,
-                metadata: {
-                    let mut m = std::collections::HashMap::new();
-                    m.insert("component1".to_string(), "data".to_string());
-                    m
+        let (owner, racing, puzzle) = (create_test_address(1), create_test_address(2), create_test_address(3));
+        for address in [owner, racing, puzzle] {
+            state_manager.create_test_account(address, 100_000, 0);
+        }
+        let asset_id = crate::types::sha256(b"shared_asset");
+        let asset_tx = |from: Address, action: AssetAction, metadata: &[(&str, &str)], attributes: Vec<Attribute>| {
+            Transaction::MistbornAsset {
+                from,
+                action,
+                asset_id,
+                data: AssetData {
+                    density: DensityLevel::Ethereal,
+                    metadata: metadata.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+                    attributes,
+                    game_id: Some("racing".to_string()),
+                    owner: from,
                 },
-                attributes: vec![],
-                game_id: None,
-                owner: owner1,
-            },
+                fee: 0,
+                nonce: next_nonce(&state_manager, &from),
+                chain_id: None,
+                valid_until_height: None,
+                signature: vec![1; 64],
+            }
+        };
+        state_manager.apply_transaction(&asset_tx(owner, AssetAction::Create, &[("name", "Car")], vec![])).unwrap();
+        let grant = |grantee: Address, game_id: &str| AssetPermission {
+            grantee,
+            level: PermissionLevel::GameContract,
+            game_id: Some(game_id.to_string()),
+            expires_at: None,
+        };
+        state_manager.apply_transaction(&Transaction::SetAssetPermissions {
+            from: owner,
+            asset_id,
+            permissions: vec![grant(racing, "racing"), grant(puzzle, "puzzle")],
+            public_read: false,
+            owner,
             fee: 0,
-            nonce: 0,
+            nonce: 1,
             chain_id: None,
             valid_until_height: None,
-            signature: vec![1; 64],
-        };
-        
-        state_manager.apply_transaction(&tx).unwrap();
-        
-        // Try to split with wrong owner - should fail
-        let mut split_metadata = std::collections::HashMap::new();
-        split_metadata.insert("_components".to_string(), "component1".to_string());
-        
-        let split_tx = Transaction::MistbornAsset {
-            from: owner2, // Wrong owner
-            action: crate::types::AssetAction::Split,
+            signature: vec![2; 64],
+        }).unwrap();
+
+        // The collection's game writes plain keys and its namespace
+        let racing_write = asset_tx(racing, AssetAction::Update, &[("name", "Fast car"), ("racing:laps", "3")], vec![]);
+        state_manager.apply_transaction(&racing_write).unwrap();
+        // Another game writes only inside its namespace, even though the asset is not in its collection
+        let puzzle_write = asset_tx(puzzle, AssetAction::Update, &[("puzzle:pieces", "12")], vec![]);
+        state_manager.apply_transaction(&puzzle_write).unwrap();
+
+        let denied = [
+            asset_tx(puzzle, AssetAction::Update, &[("name", "Puzzle car")], vec![]),
+            asset_tx(puzzle, AssetAction::Update, &[("racing:laps", "0")], vec![]),
+            asset_tx(racing, AssetAction::Update, &[("puzzle:pieces", "0")], vec![]),
+            asset_tx(puzzle, AssetAction::Update, &[("puzzle:pieces", "13")], vec![
+                Attribute { name: "speed".to_string(), value: AttributeValue::Int(9), rarity: None },
+            ]),
+        ];
+        for tx in &denied {
+            assert!(matches!(state_manager.apply_transaction(tx), Err(HazeError::AccessDenied(_))));
+        }
+        // The owner writes every namespace
+        state_manager.apply_transaction(&asset_tx(owner, AssetAction::Update, &[("puzzle:solved", "yes")], vec![])).unwrap();
+
+        let asset = state_manager.get_asset(&asset_id).unwrap();
+        assert_eq!(asset.data.metadata.get("name").map(String::as_str), Some("Fast car"));
+        assert_eq!(asset.data.metadata.get("racing:laps").map(String::as_str), Some("3"));
+        assert_eq!(asset.data.metadata.get("puzzle:pieces").map(String::as_str), Some("12"));
+        let namespaces = metadata_namespaces(&asset, chrono::Utc::now().timestamp());
+        assert_eq!(namespaces.keys().collect::<Vec<_>>(), ["puzzle", "racing"]);
+        assert_eq!(namespaces["puzzle"].keys, ["puzzle:pieces", "puzzle:solved"]);
+        assert_eq!(namespaces["puzzle"].writers, [puzzle]);
+        assert_eq!(namespaces["racing"].keys, ["racing:laps"]);
+        assert_eq!(namespaces["racing"].writers, [racing]);
+    }
+
+    #[test]
+    fn test_grant_expiry_uses_block_timestamp() {
+        use crate::types::{AssetAction, AssetData, AssetPermission, DensityLevel, PermissionLevel};
+        let config = create_test_config("grant_expiry_block_time");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        let state_manager = StateManager::new(&config).unwrap();
+        let (owner, game) = (create_test_address(1), create_test_address(2));
+        for address in [owner, game] {
+            state_manager.create_test_account(address, 100_000, 0);
+        }
+        let asset_id = crate::types::sha256(b"expiring_grant");
+        let asset_tx = |from: Address, action: AssetAction, nonce: u64| Transaction::MistbornAsset {
+            from,
+            action,
             asset_id,
-            data: crate::types::AssetData {
-                density: crate::types::DensityLevel::Ethereal,
-                metadata: split_metadata,
+            data: AssetData {
+                density: DensityLevel::Ethereal,
+                metadata: [("level".to_string(), nonce.to_string())].into_iter().collect(),
                 attributes: vec![],
-                game_id: None,
-                owner: owner2, // Wrong owner
+                game_id: Some("racing".to_string()),
+                owner: from,
             },
             fee: 0,
-            nonce: 0,
+            nonce,
             chain_id: None,
             valid_until_height: None,
-            signature: vec![2; 64],
+            signature: vec![1; 64],
         };
-        
-        let result = state_manager.apply_transaction(&split_tx);
-        assert!(result.is_err());
-        let err_msg = result.unwrap_err().to_string();
-        assert!(
-            err_msg.contains("not owner") || err_msg.contains("Access denied"),
-            "expected permission error, got: {}",
-            err_msg
-        );
-        
-        // Asset should still exist
-        assert!(state_manager.get_asset(&asset_id).is_some());
+        state_manager.apply_transaction(&asset_tx(owner, AssetAction::Create, 0)).unwrap();
+        state_manager.apply_transaction(&Transaction::SetAssetPermissions {
+            from: owner,
+            asset_id,
+            permissions: vec![AssetPermission {
+                grantee: game,
+                level: PermissionLevel::GameContract,
+                game_id: Some("racing".to_string()),
+                expires_at: Some(1_000),
+            }],
+            public_read: false,
+            owner,
+            fee: 0,
+            nonce: 1,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![2; 64],
+        }).unwrap();
+
+        // Long past on the wall clock, but the grant is unexpired at the block's timestamp
+        let mut block = test_block(1, vec![asset_tx(game, AssetAction::Update, 0)]);
+        block.header.timestamp = 1_000;
+        state_manager.apply_block(&block).unwrap();
+        assert_eq!(state_manager.get_asset(&asset_id).unwrap().data.metadata["level"], "0");
+
+        let mut block = test_block(2, vec![asset_tx(game, AssetAction::Update, 1)]);
+        block.header.timestamp = 1_001;
+        assert!(state_manager.apply_block(&block).is_err());
+        assert_eq!(state_manager.get_asset(&asset_id).unwrap().data.metadata["level"], "0");
     }
 
     #[test]
-    fn test_asset_history() {
-        let config = create_test_config("history");
+    fn test_get_quota_usage() {
+        let config = create_test_config("quota");
         let state_manager = StateManager::new(&config).unwrap();
-        
         let owner = create_test_address(1);
-        // Create account with balance for gas fees
         state_manager.create_test_account(owner, 100_000, 0);
-        
-        let asset_id = crate::types::sha256(b"test_asset");
-        
-        // Create asset
+
+        let usage_empty = state_manager.get_quota_usage(&owner);
+        assert_eq!(usage_empty.assets_count, 0);
+        assert!(usage_empty.assets_limit > 0);
+        assert_eq!(usage_empty.blob_files_count, 0);
+
+        let asset_id = crate::types::sha256(b"quota_asset");
         let tx = Transaction::MistbornAsset {
             from: owner,
             action: crate::types::AssetAction::Create,
@@ -2329,188 +10096,439 @@ mod tests {
             valid_until_height: None,
             signature: vec![1; 64],
         };
-        
         state_manager.apply_transaction(&tx).unwrap();
-        
-        // Check history
-        let history = state_manager.get_asset_history(&asset_id, 0).unwrap();
-        assert_eq!(history.len(), 1);
-        assert!(matches!(history[0].action, crate::types::AssetAction::Create));
+
+        let usage = state_manager.get_quota_usage(&owner);
+        assert_eq!(usage.assets_count, 1);
+        assert!(usage.assets_limit > 0);
+        assert!(usage.metadata_size_limit > 0);
     }
 
     #[test]
-    fn test_search_assets_by_owner() {
-        let config = create_test_config("search_owner");
+    fn test_unjail_transaction_requires_elapsed_jail_period() {
+        let config = create_test_config("unjail");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
         let state_manager = StateManager::new(&config).unwrap();
-        
-        let owner1 = create_test_address(1);
-        let owner2 = create_test_address(2);
-        // Create accounts with balance for gas fees
-        state_manager.create_test_account(owner1, 100_000, 0);
-        state_manager.create_test_account(owner2, 100_000, 0);
-        
-        // Create assets for owner1
-            for i in 0..3 {
-            let asset_id = crate::types::sha256(&format!("asset1_{}", i).into_bytes());
-            let tx = Transaction::MistbornAsset {
-                from: owner1,
-                action: crate::types::AssetAction::Create,
-                asset_id,
-                data: crate::types::AssetData {
-                    density: crate::types::DensityLevel::Ethereal,
-                    metadata: std::collections::HashMap::new(),
-                    attributes: vec![],
-                    game_id: None,
-                    owner: owner1,
-                },
-                fee: 0,
-                nonce: 0,
-                chain_id: None,
-                valid_until_height: None,
-                signature: vec![1; 64],
-            };
-            state_manager.apply_transaction(&tx).unwrap();
-        }
-        
-        // Create asset for owner2
-        let asset_id2 = crate::types::sha256(b"asset2");
-        let tx2 = Transaction::MistbornAsset {
-            from: owner2,
-            action: crate::types::AssetAction::Create,
-            asset_id: asset_id2,
-            data: crate::types::AssetData {
-                density: crate::types::DensityLevel::Ethereal,
-                metadata: std::collections::HashMap::new(),
-                attributes: vec![],
-                game_id: None,
-                owner: owner2,
-            },
-            fee: 0,
-            nonce: 0,
+
+        let validator = create_test_address(7);
+        state_manager.create_test_account(validator, 1_000, 0);
+        state_manager.tokenomics().stake(validator, validator, 500).unwrap();
+        let unjail = |nonce: u64| Transaction::Unjail {
+            from: validator,
+            fee: 10,
+            nonce,
             chain_id: None,
             valid_until_height: None,
-            signature: vec![2; 64],
+            signature: vec![1; 64],
         };
-        state_manager.apply_transaction(&tx2).unwrap();
-        
-        // Search by owner1
-        let results = state_manager.search_assets_by_owner(&owner1);
-        assert_eq!(results.len(), 3);
+
+        // Not jailed: nothing to release
+        assert!(state_manager.apply_transaction(&unjail(0)).is_err());
+
+        // The jail period is measured against the block's timestamp, not the local clock
+        let at = |timestamp: i64| {
+            let mut block = test_block(state_manager.current_height() + 1, vec![unjail(0)]);
+            block.header.timestamp = timestamp;
+            block
+        };
+        state_manager.tokenomics().jail(&validator, 1_000).unwrap();
+        assert!(state_manager.apply_block(&at(999)).is_err());
+        assert!(state_manager.tokenomics().is_jailed(&validator));
+        assert_eq!(state_manager.get_account(&validator).unwrap().balance, 1_000);
+
+        // Jail period elapsed at the block's time
+        state_manager.apply_block(&at(1_000)).unwrap();
+        assert!(!state_manager.tokenomics().is_jailed(&validator));
+        let account = state_manager.get_account(&validator).unwrap();
+        assert_eq!(account.balance, 990);
+        assert_eq!(account.nonce, 1);
+    }
+
+    /// Minimal WASM module exporting `on_asset: () -> ()`; traps when `trap` is set
+    fn hook_test_wasm(trap: bool) -> Vec<u8> {
+        let mut wasm = vec![0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+        wasm.extend_from_slice(&[0x01, 0x04, 0x01, 0x60, 0x00, 0x00]); // type: () -> ()
+        wasm.extend_from_slice(&[0x03, 0x02, 0x01, 0x00]); // func 0 has type 0
+        wasm.extend_from_slice(&[0x07, 0x0c, 0x01, 0x08]);
+        wasm.extend_from_slice(b"on_asset");
+        wasm.extend_from_slice(&[0x00, 0x00]); // export func 0
+        if trap {
+            wasm.extend_from_slice(&[0x0a, 0x05, 0x01, 0x03, 0x00, 0x00, 0x0b]); // unreachable
+        } else {
+            wasm.extend_from_slice(&[0x0a, 0x04, 0x01, 0x02, 0x00, 0x0b]);
+        }
+        wasm
     }
 
     #[test]
-    fn test_search_assets_by_game_id() {
-        let config = create_test_config("search_game");
+    fn test_asset_hook_invoked_on_lifecycle_changes() {
+        let config = create_test_config("asset_hooks");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
         let state_manager = StateManager::new(&config).unwrap();
-        
+
         let owner = create_test_address(1);
-        // Create account with balance for gas fees
-        state_manager.create_test_account(owner, 100_000, 0);
-        
-        // Create assets with game_id
-        for i in 0..2 {
-            let asset_id = crate::types::sha256(&format!("game_asset_{}", i).into_bytes());
-            let tx = Transaction::MistbornAsset {
-                from: owner,
-                action: crate::types::AssetAction::Create,
-                asset_id,
-                data: crate::types::AssetData {
-                    density: crate::types::DensityLevel::Ethereal,
-                    metadata: std::collections::HashMap::new(),
-                    attributes: vec![],
-                    game_id: Some("game1".to_string()),
-                    owner,
-                },
+        let other = create_test_address(2);
+        state_manager.create_test_account(owner, 1_000_000, 0);
+        state_manager.create_test_account(other, 1_000_000, 0);
+        let ok_contract = create_test_address(10);
+        let trap_contract = create_test_address(11);
+        state_manager.register_contract(ok_contract, hook_test_wasm(false)).unwrap();
+        state_manager.register_contract(trap_contract, hook_test_wasm(true)).unwrap();
+
+        let set_hook = |from: Address, nonce: u64, contract: Address, on_failure: HookFailureMode| {
+            Transaction::SetAssetHook {
+                from,
+                game_id: "hooked_game".to_string(),
+                hook: Some(AssetHook {
+                    contract,
+                    method: "on_asset".to_string(),
+                    gas_limit: 10_000,
+                    on_failure,
+                }),
                 fee: 0,
-                nonce: 0,
+                nonce,
                 chain_id: None,
                 valid_until_height: None,
                 signature: vec![1; 64],
-            };
-            state_manager.apply_transaction(&tx).unwrap();
-        }
-        
-        // Search by game_id
-        let results = state_manager.search_assets_by_game_id("game1");
-        assert_eq!(results.len(), 2);
+            }
+        };
+        let create = |seed: &[u8], game_id: &str| {
+            (
+                crate::types::sha256(seed),
+                Transaction::MistbornAsset {
+                    from: owner,
+                    action: AssetAction::Create,
+                    asset_id: crate::types::sha256(seed),
+                    data: crate::types::AssetData {
+                        density: crate::types::DensityLevel::Ethereal,
+                        metadata: HashMap::new(),
+                        attributes: vec![],
+                        game_id: Some(game_id.to_string()),
+                        owner,
+                    },
+                    fee: 0,
+                    nonce: next_nonce(&state_manager, &owner),
+                    chain_id: None,
+                    valid_until_height: None,
+                    signature: vec![1; 64],
+                },
+            )
+        };
+        let balance = |address: &Address| state_manager.get_account(address).unwrap().balance;
+
+        state_manager.apply_transaction(&set_hook(owner, 0, ok_contract, HookFailureMode::Revert)).unwrap();
+        assert_eq!(state_manager.get_asset_hook("hooked_game").unwrap().registrant, owner);
+
+        // Only the registrant can replace the hook
+        let result = state_manager.apply_transaction(&set_hook(other, 0, trap_contract, HookFailureMode::Revert));
+        assert!(matches!(result, Err(HazeError::AccessDenied(_))));
+
+        // Hook gas is paid on top of the asset operation gas
+        let before = balance(&owner);
+        state_manager.apply_transaction(&create(b"plain_asset", "plain_game").1).unwrap();
+        let plain_cost = before - balance(&owner);
+        let before = balance(&owner);
+        state_manager.apply_transaction(&create(b"hooked_asset", "hooked_game").1).unwrap();
+        assert!(before - balance(&owner) > plain_cost);
+
+        // Warn-only: a failing hook keeps the asset change
+        state_manager.apply_transaction(&set_hook(owner, next_nonce(&state_manager, &owner), trap_contract, HookFailureMode::Warn)).unwrap();
+        let (warn_asset_id, warn_tx) = create(b"warn_asset", "hooked_game");
+        state_manager.apply_transaction(&warn_tx).unwrap();
+        assert!(state_manager.get_asset(&warn_asset_id).is_some());
+
+        // Reverting: a failing hook fails the whole transaction
+        state_manager.apply_transaction(&set_hook(owner, next_nonce(&state_manager, &owner), trap_contract, HookFailureMode::Revert)).unwrap();
+        let (revert_asset_id, revert_tx) = create(b"revert_asset", "hooked_game");
+        assert!(state_manager.apply_transaction(&revert_tx).is_err());
+        assert!(state_manager.get_asset(&revert_asset_id).is_none());
     }
 
     #[test]
-    fn test_asset_versions() {
-        let config = create_test_config("versions");
+    fn test_contract_logs_indexed_and_filtered() {
+        use crate::types::{Block, BlockHeader};
+        let config = create_test_config("contract_logs");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
         let state_manager = StateManager::new(&config).unwrap();
-        
-        let owner = create_test_address(1);
-        // Create account with balance for gas fees
-        state_manager.create_test_account(owner, 100_000, 0);
-        
-        let asset_id = crate::types::sha256(b"test_asset");
-        
-        // Create asset
-        let tx = Transaction::MistbornAsset {
-            from: owner,
-            action: crate::types::AssetAction::Create,
-            asset_id,
-            data: crate::types::AssetData {
-                density: crate::types::DensityLevel::Ethereal,
-                metadata: std::collections::HashMap::new(),
-                attributes: vec![],
-                game_id: None,
-                owner,
-            },
-            fee: 0,
+
+        let caller = create_test_address(1);
+        let contract = create_test_address(20);
+        state_manager.create_test_account(caller, 10_000_000, 0);
+        let topic: Hash = [0xaa; 32];
+        let wat = format!(
+            r#"(module
+                (import "env" "haze_log" (func $log (param i32 i32 i32 i32)))
+                (memory (export "memory") 1)
+                (data (i32.const 0) "{}")
+                (data (i32.const 32) "ping")
+                (func (export "emit")
+                    (call $log (i32.const 0) (i32.const 1) (i32.const 32) (i32.const 4))))"#,
+            "\\aa".repeat(32)
+        );
+        state_manager.register_contract(contract, wat.into_bytes()).unwrap();
+
+        let call = Transaction::ContractCall {
+            from: caller,
+            contract,
+            method: "emit".to_string(),
+            args: vec![],
+            gas_limit: 100_000,
+            fee: 1,
             nonce: 0,
             chain_id: None,
             valid_until_height: None,
             signature: vec![1; 64],
         };
-        
-        state_manager.apply_transaction(&tx).unwrap();
-        
-        // Check initial version (after create, version 1 should be created)
-        let asset_state = state_manager.get_asset(&asset_id).unwrap();
-        assert_eq!(asset_state.current_version, 1);
-        
-        let versions = state_manager.get_asset_versions(&asset_id).unwrap();
-        // Should have 1 version (version 1 from initial snapshot)
-        assert_eq!(versions.len(), 1);
-        assert_eq!(versions[0].version, 1);
-        
-        // Create manual snapshot
-        let version = state_manager.create_asset_snapshot(&asset_id).unwrap();
-        assert_eq!(version, 2);
-        
-        // Check versions (should have 2 now)
-        let versions = state_manager.get_asset_versions(&asset_id).unwrap();
-        assert_eq!(versions.len(), 2);
-        assert!(versions.iter().any(|v| v.version == 1));
-        assert!(versions.iter().any(|v| v.version == 2));
-        
-        // Get specific version
-        let v1 = state_manager.get_asset_version(&asset_id, 1).unwrap();
-        assert_eq!(v1.version, 1);
-        
-        let v2 = state_manager.get_asset_version(&asset_id, 2).unwrap();
-        assert_eq!(v2.version, 2);
-        
-        // Get current version (version 0 means current)
-        let v_current = state_manager.get_asset_version(&asset_id, 0).unwrap();
-        assert_eq!(v_current.version, 2);
+        let block = Block {
+            header: BlockHeader {
+                hash: [7u8; 32],
+                parent_hash: [0u8; 32],
+                height: 1,
+                timestamp: 0,
+                validator: caller,
+                merkle_root: [0u8; 32],
+                state_root: [0u8; 32],
+                wave_number: 0,
+                committee_id: 0,
+                protocol_version: PROTOCOL_VERSION,
+                state_version: STATE_VERSION,
+                signals: 0,
+            },
+            transactions: vec![call.clone()],
+            dag_references: vec![],
+        };
+        state_manager.apply_block(&block).unwrap();
+
+        let logs = state_manager.get_logs(&LogFilter {
+            address: Some(contract),
+            topics: vec![Some(topic)],
+            ..Default::default()
+        });
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].log.data, b"ping".to_vec());
+        assert_eq!(logs[0].block_height, 1);
+        assert_eq!(logs[0].tx_hash, call.hash());
+        assert_eq!(state_manager.get_transaction_logs(&call.hash()).len(), 1);
+
+        // Non-matching address, topic or range
+        let miss = |filter: LogFilter| state_manager.get_logs(&filter).is_empty();
+        assert!(miss(LogFilter { address: Some(caller), ..Default::default() }));
+        assert!(miss(LogFilter { topics: vec![Some([0xbb; 32])], ..Default::default() }));
+        assert!(miss(LogFilter { topics: vec![None, Some(topic)], ..Default::default() }));
+        assert!(miss(LogFilter { from_height: 2, ..Default::default() }));
+    }
+
+    #[test]
+    fn test_deploy_contract_derives_address_from_deployer_and_nonce() {
+        let config = create_test_config("contract_deploy");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        let state_manager = StateManager::new(&config).unwrap();
+
+        let deployer = create_test_address(1);
+        state_manager.create_test_account(deployer, 1_000, 0);
+        let code = hook_test_wasm(false);
+        let deploy = |nonce: u64| Transaction::DeployContract {
+            from: deployer,
+            code: code.clone(),
+            fee: 1,
+            nonce,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+
+        // Same code deployed twice gets two addresses backed by one stored blob
+        state_manager.apply_transaction(&deploy(0)).unwrap();
+        state_manager.apply_transaction(&deploy(1)).unwrap();
+        let first = crate::types::contract_address(&deployer, 0);
+        let second = crate::types::contract_address(&deployer, 1);
+        assert_ne!(first, second);
+
+        let info = state_manager.get_contract(&first).unwrap();
+        assert_eq!(info.deployer, deployer);
+        assert_eq!(info.deploy_nonce, 0);
+        assert_eq!(info.code_hash, crate::types::sha256(&code));
+        assert_eq!(info.code_size, code.len());
+        assert_eq!(info.deployed_at_height, 1);
+        assert_eq!(state_manager.get_contract_code(&first).unwrap(), Some(code.clone()));
+        assert_eq!(state_manager.get_contract_code(&second).unwrap(), Some(code.clone()));
+        assert_eq!(state_manager.trees.contract_code.len(), 1);
+
+        // An occupied address cannot be deployed over
+        let taken = crate::types::contract_address(&deployer, 2);
+        state_manager.register_contract(taken, vec![0u8; 8]).unwrap();
+        assert!(state_manager.apply_transaction(&deploy(2)).is_err());
+        assert!(state_manager.register_contract(taken, code.clone()).is_err());
+        assert_eq!(state_manager.get_contract_code(&taken).unwrap(), Some(vec![0u8; 8]));
+    }
+
+    #[test]
+    fn test_call_contract_readonly() {
+        let config = create_test_config("contract_call_readonly");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        let state_manager = StateManager::new(&config).unwrap();
+
+        let caller = create_test_address(1);
+        let contract = create_test_address(21);
+        state_manager.create_test_account(caller, 1_000_000, 0);
+        let wat = r#"(module
+            (func (export "get") (result i64) i64.const 42)
+            (func (export "spin") (loop $l (br $l))))"#;
+        state_manager.register_contract(contract, wat.as_bytes().to_vec()).unwrap();
+
+        let output = state_manager
+            .call_contract_readonly(caller, &contract, "get", &[], 100_000)
+            .unwrap();
+        assert_eq!(output.return_values, vec![ContractValue::I64(42)]);
+        assert_eq!(output.return_data, 42i64.to_le_bytes().to_vec());
+        assert!(output.gas_used > 0 && output.gas_used <= 100_000);
+
+        // Gas cap is enforced, and nothing is charged either way
+        assert!(state_manager.call_contract_readonly(caller, &contract, "spin", &[], 10_000).is_err());
+        let account = state_manager.get_account(&caller).unwrap();
+        assert_eq!(account.balance, 1_000_000);
+        assert_eq!(account.nonce, 0);
+
+        assert!(state_manager
+            .call_contract_readonly(caller, &create_test_address(22), "get", &[], 100_000)
+            .is_err());
     }
 
     #[test]
-    fn test_asset_versions_on_condense() {
-        let config = create_test_config("versions_condense");
+    fn test_dry_run_measures_transactions_without_committing() {
+        let config = create_test_config("dry_run");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
         let state_manager = StateManager::new(&config).unwrap();
-        
+
+        let caller = create_test_address(1);
+        let funded = create_test_address(2);
+        let contract = create_test_address(23);
+        state_manager.create_test_account(caller, 1_000_000, 0);
+        let wat = r#"(module (func (export "get") (result i64) i64.const 42))"#;
+        state_manager.register_contract(contract, wat.as_bytes().to_vec()).unwrap();
+
+        let transfer = |from: Address, to: Address, amount: u64, nonce: u64| Transaction::Transfer {
+            from,
+            to,
+            amount,
+            fee: 5,
+            nonce,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        let call = Transaction::ContractCall {
+            from: caller,
+            contract,
+            method: "get".to_string(),
+            args: vec![],
+            gas_limit: 100_000,
+            fee: 3,
+            nonce: 1,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+
+        // Applied in order: the call sees the transfer's nonce, and the funded account can spend
+        let txs = [transfer(caller, funded, 1_000, 0), call, transfer(funded, caller, 500, 0)];
+        let dry_run = state_manager.dry_run(&txs);
+        assert!(dry_run.failure.is_none());
+        assert_eq!(dry_run.executions.len(), 3);
+        assert_eq!((dry_run.executions[0].gas_used, dry_run.executions[0].fees_paid), (0, 5));
+        let call_gas = dry_run.executions[1].gas_used;
+        assert!(call_gas > 0);
+        assert_eq!(dry_run.executions[1].fees_paid, 3 + call_gas * config.vm.gas_price);
+
+        // Nothing was committed
+        assert_eq!(state_manager.get_account(&caller).unwrap().balance, 1_000_000);
+        assert!(state_manager.get_account(&funded).is_none());
+
+        // Stops at the first failure
+        let dry_run = state_manager.dry_run(&[transfer(caller, funded, 1_000, 0), transfer(caller, funded, 1_000, 0)]);
+        assert_eq!(dry_run.executions.len(), 1);
+        let (index, error) = dry_run.failure.unwrap();
+        assert_eq!(index, 1);
+        assert!(matches!(error, HazeError::InvalidNonce { expected: 1, actual: 0 }));
+    }
+
+    #[test]
+    fn test_pool_twap_resists_in_block_moves_and_reaches_contracts() {
+        let config = create_test_config("pool_twap");
+        let state_manager = StateManager::new(&config).unwrap();
+        let economy = state_manager.economy();
+        let pool_id = economy.create_liquidity_pool("HAZE".to_string(), "GOLD".to_string(), 1_000_000, 1_000, 30).unwrap();
+        assert!(economy.twap(&pool_id, 5).is_none());
+        for height in 1..=10 {
+            economy.record_price_observations(height, height as i64);
+        }
+        // A large swap during block 11 only moves the average from block 12 on
+        economy.swap_assets(&pool_id, "HAZE", 9_000_000).unwrap();
+        economy.record_price_observations(11, 11);
+        let twap = economy.twap(&pool_id, 10).unwrap();
+        assert_eq!((twap.from_height, twap.to_height, twap.window_blocks), (1, 11, 10));
+        assert_eq!(twap.price1, 1_000_000); // 0.001 GOLD per HAZE
+        assert_eq!(twap.price2, 1_000_000_000_000); // 1000 HAZE per GOLD
+        economy.record_price_observations(12, 12);
+        assert!(economy.twap(&pool_id, 10).unwrap().price2 > twap.price2);
+        // Short history covers what it can
+        assert_eq!(economy.twap(&pool_id, 50).unwrap().window_blocks, 11);
+
+        let contract = create_test_address(40);
+        let wat = format!(
+            r#"(module
+                (import "env" "haze_pool_twap" (func $twap (param i32 i32 i64 i32) (result i64)))
+                (memory (export "memory") 1)
+                (data (i32.const 0) "{}")
+                (func (export "gold_price") (result i64) (call $twap (i32.const 0) (i32.const {}) (i64.const 5) (i32.const 1)))
+                (func (export "too_long") (result i64) (call $twap (i32.const 0) (i32.const {}) (i64.const 50) (i32.const 0))))"#,
+            pool_id, pool_id.len(), pool_id.len()
+        );
+        state_manager.register_contract(contract, wat.into_bytes()).unwrap();
+        let caller = create_test_address(1);
+        let call = |method: &str| state_manager.call_contract_readonly(caller, &contract, method, &[], 100_000).unwrap().return_values[0];
+        let expected = economy.twap(&pool_id, 5).unwrap().price2 as i64;
+        assert_eq!(call("gold_price"), ContractValue::I64(expected));
+        assert_eq!(call("too_long"), ContractValue::I64(-1));
+    }
+
+    #[test]
+    fn test_contract_asset_precompiles() {
+        let config = create_test_config("asset_precompiles");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        let state_manager = StateManager::new(&config).unwrap();
+
         let owner = create_test_address(1);
-        // Create account with balance for gas fees
-        state_manager.create_test_account(owner, 100_000, 0);
-        
-        let asset_id = crate::types::sha256(b"test_asset");
-        
-        // Create asset
-        let tx = Transaction::MistbornAsset {
+        let new_owner = create_test_address(2);
+        let game = create_test_address(30);
+        let stranger = create_test_address(31);
+        state_manager.create_test_account(owner, 10_000_000, 0);
+
+        let asset_id = crate::types::sha256(b"precompile_asset");
+        let escape = |bytes: &[u8]| bytes.iter().map(|b| format!("\\{:02x}", b)).collect::<String>();
+        let wat = format!(
+            r#"(module
+                (import "env" "haze_asset_get" (func $get (param i32 i32 i32) (result i32)))
+                (import "env" "haze_asset_can_write" (func $can_write (param i32) (result i32)))
+                (import "env" "haze_asset_set_attribute" (func $set_attr (param i32 i32 i32 i32 i32)))
+                (import "env" "haze_asset_transfer" (func $transfer (param i32 i32)))
+                (memory (export "memory") 1)
+                (data (i32.const 0) "{}")
+                (data (i32.const 32) "{}")
+                (data (i32.const 64) "level")
+                (data (i32.const 80) "7")
+                (func (export "check") (result i32) (call $can_write (i32.const 0)))
+                (func (export "read") (result i32) (call $get (i32.const 0) (i32.const 256) (i32.const 4096)))
+                (func (export "level_up")
+                    (call $set_attr (i32.const 0) (i32.const 64) (i32.const 5) (i32.const 80) (i32.const 1)))
+                (func (export "give") (call $transfer (i32.const 0) (i32.const 32))))"#,
+            escape(&asset_id),
+            escape(&new_owner)
+        );
+        state_manager.register_contract(game, wat.clone().into_bytes()).unwrap();
+        state_manager.register_contract(stranger, wat.into_bytes()).unwrap();
+
+        let create_tx = Transaction::MistbornAsset {
             from: owner,
             action: crate::types::AssetAction::Create,
             asset_id,
@@ -2518,378 +10536,922 @@ mod tests {
                 density: crate::types::DensityLevel::Ethereal,
                 metadata: std::collections::HashMap::new(),
                 attributes: vec![],
-                game_id: None,
+                game_id: Some("game1".to_string()),
                 owner,
             },
             fee: 0,
-            nonce: 0,
+            nonce: next_nonce(&state_manager, &owner),
             chain_id: None,
             valid_until_height: None,
             signature: vec![1; 64],
         };
-        
-        state_manager.apply_transaction(&tx).unwrap();
-        
-        // Condense (should create snapshot)
-        let mut condense_metadata = std::collections::HashMap::new();
-        condense_metadata.insert("new_data".to_string(), "value".to_string());
-        
-        let condense_tx = Transaction::MistbornAsset {
+        state_manager.apply_transaction(&create_tx).unwrap();
+        let grant_tx = Transaction::SetAssetPermissions {
             from: owner,
-            action: crate::types::AssetAction::Condense,
             asset_id,
-            data: crate::types::AssetData {
-                density: crate::types::DensityLevel::Light,
-                metadata: condense_metadata,
-                attributes: vec![],
-                game_id: None,
-                owner,
-            },
+            permissions: vec![crate::types::AssetPermission {
+                grantee: game,
+                level: crate::types::PermissionLevel::GameContract,
+                game_id: Some("game1".to_string()),
+                expires_at: None,
+            }],
+            public_read: false,
+            owner,
             fee: 0,
-            nonce: 0,
+            nonce: 1,
             chain_id: None,
             valid_until_height: None,
             signature: vec![2; 64],
         };
-        
-        state_manager.apply_transaction(&condense_tx).unwrap();
-        
-        // Should have 2 versions (initial + condense)
-        let asset_state = state_manager.get_asset(&asset_id).unwrap();
-        assert_eq!(asset_state.current_version, 2); // After condense, version should be 2
-        
-        let versions = state_manager.get_asset_versions(&asset_id).unwrap();
-        assert_eq!(versions.len(), 2);
-        // Versions should be sorted, so version 1 first, then version 2
-        let sorted_versions: Vec<u64> = versions.iter().map(|v| v.version).collect();
-        assert_eq!(sorted_versions, vec![1, 2]);
+        state_manager.apply_transaction(&grant_tx).unwrap();
+
+        // The create and the grant used the owner's nonces 0 and 1
+        let call = |contract: Address, method: &str, nonce: u64| Transaction::ContractCall {
+            from: owner,
+            contract,
+            method: method.to_string(),
+            args: vec![],
+            gas_limit: 200_000,
+            fee: 0,
+            nonce,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![3; 64],
+        };
+
+        // Reads and permission checks
+        let read = state_manager.call_contract_readonly(owner, &game, "read", &[], 200_000).unwrap();
+        assert!(matches!(read.return_values[0], ContractValue::I32(len) if len > 0));
+        let check = |contract| state_manager.call_contract_readonly(owner, &contract, "check", &[], 200_000).unwrap();
+        assert_eq!(check(game).return_values, vec![ContractValue::I32(1)]);
+        assert_eq!(check(stranger).return_values, vec![ContractValue::I32(0)]);
+
+        // Read-only calls never persist precompile writes
+        state_manager.call_contract_readonly(owner, &game, "level_up", &[], 200_000).unwrap();
+        assert!(state_manager.get_asset(&asset_id).unwrap().data.attributes.is_empty());
+
+        // A contract without a grant cannot modify the asset
+        assert!(state_manager.apply_transaction(&call(stranger, "level_up", 2)).is_err());
+        assert!(state_manager.get_asset(&asset_id).unwrap().data.attributes.is_empty());
+
+        state_manager.apply_transaction(&call(game, "level_up", 2)).unwrap();
+        let asset = state_manager.get_asset(&asset_id).unwrap();
+        assert_eq!(asset.data.attributes.len(), 1);
+        assert_eq!(asset.data.attributes[0].name, "level");
+        assert_eq!(asset.data.attributes[0].value.to_string(), "7");
+
+        state_manager.apply_transaction(&call(game, "give", 3)).unwrap();
+        let asset = state_manager.get_asset(&asset_id).unwrap();
+        assert_eq!(asset.owner, new_owner);
+        assert!(asset.permissions.is_empty());
+        assert_eq!(state_manager.search_assets_by_owner(&new_owner), vec![asset_id]);
+
+        // The grant went away with the transfer
+        assert!(state_manager.apply_transaction(&call(game, "level_up", 4)).is_err());
     }
 
     #[test]
-    fn test_create_asset() {
-        let config = create_test_config("create_only");
+    fn test_collection_market_stats_buckets() {
+        use crate::economy::{MarketBucketSize, MAX_HOURLY_BUCKETS};
+        let mut stats = CollectionMarketStats::default();
+        stats.record_sale(100, 1, 1, 7200 + 10);
+        stats.record_sale(300, 3, 2, 7200 + 20);
+        stats.record_sale(200, 2, 3, 7200 + 30);
+        // A slightly earlier block timestamp still joins the latest bucket
+        stats.record_sale(150, 1, 4, 7200 - 5);
+        stats.record_sale(400, 4, 5, 3 * 3600);
+
+        assert_eq!((stats.sales, stats.volume, stats.fees), (5, 1150, 11));
+        assert_eq!((stats.last_price, stats.last_sale_height), (Some(400), Some(5)));
+        let hourly = stats.buckets(MarketBucketSize::Hour);
+        assert_eq!(hourly.len(), 2);
+        let first = hourly[0];
+        assert_eq!((first.start, first.open, first.close, first.low, first.high), (7200, 100, 150, 100, 300));
+        assert_eq!((first.volume, first.sales), (750, 4));
+        assert_eq!((hourly[1].start, hourly[1].sales), (3 * 3600, 1));
+        let daily = stats.buckets(MarketBucketSize::Day);
+        assert_eq!((daily.len(), daily[0].start, daily[0].volume), (1, 0, 1150));
+
+        for hour in 0..(MAX_HOURLY_BUCKETS as i64 + 10) {
+            stats.record_sale(1, 0, 6, (4 + hour) * 3600);
+        }
+        assert_eq!(stats.hourly.len(), MAX_HOURLY_BUCKETS);
+    }
+
+    #[test]
+    fn test_loot_box_commit_open_reveal_and_forfeit() {
+        use crate::types::{loot_box_id, loot_commitment, BlockHeader, DensityLevel, LootOutcome};
+        let mut config = create_test_config("loot_boxes");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        config.economy.loot_beacon_delay = 2;
+        config.economy.loot_reveal_window = 3;
         let state_manager = StateManager::new(&config).unwrap();
-        let owner = create_test_address(1);
-        state_manager.create_test_account(owner, 100_000, 0);
 
-        let asset_id = crate::types::sha256(b"create_test");
-        let mut meta = std::collections::HashMap::new();
-        meta.insert("name".to_string(), "Test Asset".to_string());
+        let studio = create_test_address(1);
+        let player = create_test_address(2);
+        state_manager.create_test_account(studio, 1_000, 0);
+        state_manager.create_test_account(player, 10, 0);
 
-        let tx = Transaction::MistbornAsset {
-            from: owner,
-            action: crate::types::AssetAction::Create,
-            asset_id,
-            data: crate::types::AssetData {
-                density: crate::types::DensityLevel::Ethereal,
-                metadata: meta,
+        let outcomes = vec![
+            LootOutcome {
+                weight: 3,
+                density: DensityLevel::Ethereal,
+                metadata: BTreeMap::from([("name".to_string(), "Common sword".to_string())]),
+                attributes: vec![],
+            },
+            LootOutcome {
+                weight: 1,
+                density: DensityLevel::Light,
+                metadata: BTreeMap::from([("name".to_string(), "Rare sword".to_string())]),
                 attributes: vec![],
-                game_id: Some("g1".to_string()),
-                owner,
             },
+        ];
+        let salt = vec![7u8; 16];
+        let commit = |nonce: u64| Transaction::CommitLootBox {
+            from: studio,
+            player,
+            game_id: "my_game".to_string(),
+            commitment: loot_commitment(&salt, &outcomes),
+            bond: 100,
             fee: 0,
-            nonce: 0,
+            nonce,
             chain_id: None,
             valid_until_height: None,
             signature: vec![1; 64],
         };
-        state_manager.apply_transaction(&tx).unwrap();
+        let open = |box_id: Hash, nonce: u64| Transaction::OpenLootBox {
+            from: player,
+            box_id,
+            fee: 0,
+            nonce,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        let reveal = |box_id: Hash, outcomes: Vec<LootOutcome>, nonce: u64| Transaction::RevealLootBox {
+            from: studio,
+            box_id,
+            outcomes,
+            salt: salt.clone(),
+            fee: 0,
+            nonce,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        let balance = |address: &Address| state_manager.get_account(address).unwrap().balance;
+        let first = loot_box_id(&studio, 0);
+        let second = loot_box_id(&studio, 1);
 
-        let asset = state_manager.get_asset(&asset_id).unwrap();
-        assert_eq!(asset.owner, owner);
-        assert_eq!(asset.data.density, crate::types::DensityLevel::Ethereal);
-        assert_eq!(asset.data.metadata.get("name"), Some(&"Test Asset".to_string()));
-        assert_eq!(asset.data.game_id, Some("g1".to_string()));
+        // Sealed at block 1 with the bond in escrow
+        state_manager.apply_block(&test_block(1, vec![commit(0), commit(1)])).unwrap();
+        assert_eq!(balance(&studio), 800);
+        assert_eq!(balance(&loot_escrow_address()), 200);
+
+        // Opened at block 2: the beacon is block 4, the reveal deadline block 7
+        assert!(matches!(
+            state_manager.apply_transaction(&Transaction::OpenLootBox {
+                from: studio,
+                box_id: first,
+                fee: 0,
+                nonce: 2,
+                chain_id: None,
+                valid_until_height: None,
+                signature: vec![1; 64],
+            }),
+            Err(HazeError::AccessDenied(_))
+        ));
+        state_manager.apply_block(&test_block(2, vec![open(first, 0), open(second, 1)])).unwrap();
+        let opened = state_manager.get_loot_box(&first).unwrap();
+        assert_eq!((opened.beacon_height, opened.reveal_deadline), (Some(4), Some(7)));
+        state_manager.apply_block(&test_block(3, vec![])).unwrap();
+        assert!(state_manager.apply_transaction(&reveal(first, outcomes.clone(), 2)).is_err());
+        state_manager.apply_block(&test_block(4, vec![])).unwrap();
+
+        // A table other than the committed one is rejected
+        let mut rigged = outcomes.clone();
+        rigged[0].weight = 0;
+        let err = state_manager.apply_transaction(&reveal(first, rigged, 2)).unwrap_err();
+        assert!(err.to_string().contains("do not match the commitment"));
+
+        // The committed table is drawn from with the block 4 hash and minted to the player
+        let beacon = crate::types::sha256(&4u64.to_le_bytes());
+        let drawn = draw_loot_outcome(&outcomes, &beacon, &salt, &first).unwrap();
+        state_manager.apply_block(&test_block(5, vec![reveal(first, outcomes.clone(), 2)])).unwrap();
+        let revealed = state_manager.get_loot_box(&first).unwrap();
+        assert_eq!(revealed.settlement, Some(LootSettlement::Revealed { height: 5, outcome: drawn }));
+        assert_eq!(revealed.status(6), LootBoxStatus::Revealed);
+        let asset = state_manager.get_asset(&first).unwrap();
+        assert_eq!(asset.owner, player);
+        assert_eq!(asset.data.game_id.as_deref(), Some("my_game"));
+        assert_eq!(asset.data.metadata.get("name"), outcomes[drawn].metadata.get("name"));
+        assert_eq!(balance(&studio), 900);
+        assert!(state_manager.apply_transaction(&reveal(first, outcomes.clone(), 3)).is_err());
+
+        // The second box is never revealed: after block 7 the player claims its bond
+        let forfeit = Transaction::ForfeitLootBox {
+            from: player,
+            box_id: second,
+            fee: 0,
+            nonce: 2,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        state_manager.apply_block(&test_block(6, vec![])).unwrap();
+        state_manager.apply_block(&test_block(7, vec![])).unwrap();
+        assert_eq!(state_manager.get_loot_box(&second).unwrap().status(8), LootBoxStatus::Expired);
+        assert!(state_manager.apply_transaction(&reveal(second, outcomes.clone(), 3)).is_err());
+        state_manager.apply_block(&test_block(8, vec![forfeit])).unwrap();
+        assert_eq!(state_manager.get_loot_box(&second).unwrap().settlement, Some(LootSettlement::Forfeited { height: 8 }));
+        assert_eq!(balance(&player), 110);
+        assert_eq!(balance(&loot_escrow_address()), 0);
     }
 
     #[test]
-    fn test_evaporate_asset() {
-        let config = create_test_config("evaporate");
+    fn test_leaderboard_submitters_and_season_end() {
+        use crate::leaderboard::LeaderboardStatus;
+        use crate::types::{leaderboard_id, BlockHeader};
+        let config = create_test_config("leaderboards");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
         let state_manager = StateManager::new(&config).unwrap();
-        let owner = create_test_address(1);
-        state_manager.create_test_account(owner, 100_000, 0);
 
-        let asset_id = crate::types::sha256(b"evap_asset");
-        let create_tx = Transaction::MistbornAsset {
-            from: owner,
-            action: crate::types::AssetAction::Create,
-            asset_id,
-            data: crate::types::AssetData {
-                density: crate::types::DensityLevel::Light,
-                metadata: std::collections::HashMap::new(),
-                attributes: vec![],
-                game_id: None,
-                owner,
+        let studio = create_test_address(1);
+        let game_contract = create_test_address(2);
+        let cheater = create_test_address(3);
+        let (alice, bob, carol) = (create_test_address(4), create_test_address(5), create_test_address(6));
+        for address in [studio, game_contract, cheater] {
+            state_manager.create_test_account(address, 100, 0);
+        }
+
+        let create = |nonce: u64| Transaction::CreateLeaderboard {
+            from: studio,
+            game_id: "my_game".to_string(),
+            season: "s1".to_string(),
+            capacity: 2,
+            end_height: 3,
+            submitters: vec![game_contract],
+            fee: 1,
+            nonce,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        let board_id = leaderboard_id(&studio, "my_game", "s1");
+        let submit = |from: Address, player: Address, score: u64, nonce: u64| Transaction::SubmitScore {
+            from,
+            board_id,
+            player,
+            score,
+            fee: 1,
+            nonce,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+
+        state_manager.apply_block(&test_block(1, vec![create(0)])).unwrap();
+        assert!(state_manager.apply_transaction(&create(1)).is_err());
+
+        // Only the creator and listed game contracts submit scores
+        assert!(matches!(
+            state_manager.apply_transaction(&submit(cheater, cheater, u64::MAX, 0)),
+            Err(HazeError::AccessDenied(_))
+        ));
+        state_manager.apply_block(&test_block(2, vec![
+            submit(game_contract, alice, 30, 0),
+            submit(game_contract, bob, 50, 1),
+            submit(studio, carol, 40, 1),
+        ])).unwrap();
+        let board = state_manager.get_leaderboard(&board_id).unwrap();
+        assert_eq!(board.entries.iter().map(|e| (e.player, e.score)).collect::<Vec<_>>(), vec![(bob, 50), (carol, 40)]);
+        assert_eq!(board.submissions, 3);
+
+        // Scores land up to the end height; the standings are final afterwards
+        state_manager.apply_block(&test_block(3, vec![submit(game_contract, alice, 45, 2)])).unwrap();
+        let board = state_manager.get_leaderboard(&board_id).unwrap();
+        assert_eq!((board.rank_of(&alice), board.rank_of(&carol)), (Some(2), None));
+        assert_eq!(board.status(state_manager.current_height() + 1), LeaderboardStatus::Final);
+        let err = state_manager.apply_transaction(&submit(game_contract, carol, 99, 3)).unwrap_err();
+        assert!(err.to_string().contains("season ended"));
+        assert_eq!(state_manager.get_collection_leaderboards("my_game").len(), 1);
+        assert_eq!(state_manager.get_account(&game_contract).unwrap().balance, 97);
+    }
+
+    #[test]
+    fn test_season_boundaries_run_hooks() {
+        use crate::season::SeasonStatus;
+        use crate::types::{leaderboard_id, season_id, BlockHeader, OrderSide, SeasonHooks};
+        let config = create_test_config("seasons");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        let state_manager = StateManager::new(&config).unwrap();
+        let (ws_tx, mut ws_rx) = broadcast::channel(64);
+        state_manager.set_ws_tx(ws_tx);
+
+        let studio = create_test_address(1);
+        let squatter = create_test_address(2);
+        let player = create_test_address(3);
+        for address in [studio, squatter, player] {
+            state_manager.create_test_account(address, 1_000, 0);
+        }
+
+        let register = |from: Address, season: &str, start_height: u64, end_height: u64, nonce: u64| Transaction::RegisterSeason {
+            from,
+            game_id: "my_game".to_string(),
+            season: season.to_string(),
+            start_height,
+            end_height,
+            hooks: SeasonHooks {
+                freeze_collections: vec!["my_game/pass".to_string()],
+                snapshot_holders: true,
+                leaderboard_capacity: Some(5),
+                leaderboard_submitters: vec![],
             },
-            fee: 0,
-            nonce: 0,
+            fee: 1,
+            nonce,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        let buy_pass = |nonce: u64| Transaction::PlaceOrder {
+            from: player,
+            game_id: "my_game/pass".to_string(),
+            side: OrderSide::Buy,
+            price: 10,
+            quantity: 1,
+            assets: vec![],
+            fee: 1,
+            nonce,
             chain_id: None,
             valid_until_height: None,
             signature: vec![1; 64],
         };
-        state_manager.apply_transaction(&create_tx).unwrap();
-        assert_eq!(state_manager.get_asset(&asset_id).unwrap().data.density, crate::types::DensityLevel::Light);
 
-        let evap_tx = Transaction::MistbornAsset {
-            from: owner,
-            action: crate::types::AssetAction::Evaporate,
+        // Seasons start in a later block; the first registrant operates the game's seasons
+        assert!(state_manager.apply_transaction(&register(studio, "s1", 1, 4, 0)).is_err());
+        state_manager.apply_block(&test_block(1, vec![register(studio, "s1", 3, 4, 0)])).unwrap();
+        assert!(matches!(
+            state_manager.apply_transaction(&register(squatter, "s2", 6, 8, 0)),
+            Err(HazeError::AccessDenied(_))
+        ));
+        assert!(state_manager.apply_transaction(&register(studio, "s2", 4, 8, 1)).is_err());
+        state_manager.apply_block(&test_block(2, vec![register(studio, "s2", 6, 8, 1)])).unwrap();
+        let s1 = season_id("my_game", "s1");
+        assert_eq!(state_manager.get_game_seasons("my_game").iter().map(|s| s.id).collect::<Vec<_>>(), vec![s1, season_id("my_game", "s2")]);
+
+        // The start hook opens the season's leaderboard
+        state_manager.apply_block(&test_block(3, vec![])).unwrap();
+        let board_id = leaderboard_id(&studio, "my_game", "s1");
+        let season = state_manager.get_season(&s1).unwrap();
+        assert_eq!((season.status(3), season.leaderboard), (SeasonStatus::Active, Some(board_id)));
+        assert_eq!(state_manager.get_leaderboard(&board_id).unwrap().end_height, 4);
+
+        // The end hook freezes the pass collection and snapshots the holders
+        state_manager.apply_block(&test_block(4, vec![buy_pass(0)])).unwrap();
+        assert_eq!(state_manager.get_frozen_collections("my_game"), vec![("my_game/pass".to_string(), s1)]);
+        assert_eq!(state_manager.get_season(&s1).unwrap().holders_snapshot_height, Some(4));
+        let err = state_manager.apply_transaction(&buy_pass(1)).unwrap_err();
+        assert!(err.to_string().contains("frozen"));
+
+        // The next season thaws it
+        state_manager.apply_block(&test_block(5, vec![])).unwrap();
+        state_manager.apply_block(&test_block(6, vec![buy_pass(1)])).unwrap();
+        assert!(state_manager.get_frozen_collections("my_game").is_empty());
+
+        let mut boundaries = Vec::new();
+        while let Ok(event) = ws_rx.try_recv() {
+            match event {
+                WsEvent::SeasonStarted { season, start_height, .. } => boundaries.push((season, start_height)),
+                WsEvent::SeasonEnded { season, end_height, .. } => boundaries.push((season, end_height)),
+                _ => {}
+            }
+        }
+        assert_eq!(boundaries, vec![("s1".to_string(), 3), ("s1".to_string(), 4), ("s2".to_string(), 6)]);
+    }
+
+    #[test]
+    fn test_asset_pool_rewards_split_between_stakers() {
+        use crate::economy::asset_pool_escrow_address;
+        use crate::types::{asset_pool_id, AssetAction, AssetData, BlockHeader, DensityLevel};
+        let config = create_test_config("asset_pools");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        let state_manager = StateManager::new(&config).unwrap();
+
+        let studio = create_test_address(1);
+        let (alice, bob) = (create_test_address(2), create_test_address(3));
+        for address in [studio, alice, bob] {
+            state_manager.create_test_account(address, 100_000, 0);
+        }
+        let create_asset = |owner: Address, name: &[u8], game_id: &str| {
+            let asset_id = crate::types::sha256(name);
+            state_manager.apply_transaction(&Transaction::MistbornAsset {
+                from: owner,
+                action: AssetAction::Create,
+                asset_id,
+                data: AssetData {
+                    density: DensityLevel::Ethereal,
+                    metadata: HashMap::new(),
+                    attributes: vec![],
+                    game_id: Some(game_id.to_string()),
+                    owner,
+                },
+                fee: 0,
+                nonce: next_nonce(&state_manager, &owner),
+                chain_id: None,
+                valid_until_height: None,
+                signature: vec![1; 64],
+            }).unwrap();
+            asset_id
+        };
+        let sword = create_asset(alice, b"sword", "my_game");
+        let shield = create_asset(bob, b"shield", "my_game");
+        let car = create_asset(bob, b"car", "other_game");
+
+        let pool_id = asset_pool_id(&studio, "my_game");
+        let stake = |from: Address, asset_id: Hash, nonce: u64| Transaction::StakeAsset {
+            from,
+            pool_id,
             asset_id,
-            data: crate::types::AssetData {
-                density: crate::types::DensityLevel::Ethereal,
-                metadata: std::collections::HashMap::new(),
-                attributes: vec![],
-                game_id: None,
-                owner,
+            fee: 0,
+            nonce,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        let unstake = |from: Address, asset_id: Hash, nonce: u64| Transaction::UnstakeAsset {
+            from,
+            asset_id,
+            fee: 0,
+            nonce,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        let balance = |address: &Address| state_manager.get_account(address).map_or(0, |a| a.balance);
+        let (alice_start, bob_start, studio_start) = (balance(&alice), balance(&bob), balance(&studio));
+
+        // 10 HAZE per block, 45 in reserve, assets locked for 2 blocks
+        state_manager.apply_block(&test_block(1, vec![
+            Transaction::CreateAssetPool {
+                from: studio,
+                game_id: "my_game".to_string(),
+                reward_per_block: 10,
+                lock_blocks: 2,
+                deposit: 45,
+                fee: 0,
+                nonce: 0,
+                chain_id: None,
+                valid_until_height: None,
+                signature: vec![1; 64],
             },
+            stake(alice, sword, 1),
+        ])).unwrap();
+        assert_eq!(state_manager.get_asset(&sword).unwrap().owner, asset_pool_escrow_address());
+        assert!(state_manager.apply_transaction(&stake(bob, car, 2)).is_err());
+        assert!(matches!(state_manager.apply_transaction(&stake(alice, shield, 2)), Err(HazeError::AccessDenied(_))));
+
+        // Alice earns block 2 alone, then the two split each block
+        state_manager.apply_block(&test_block(2, vec![stake(bob, shield, 2)])).unwrap();
+        let err = state_manager.apply_transaction(&unstake(bob, shield, 3)).unwrap_err();
+        assert!(err.to_string().contains("locked until block 4"));
+        state_manager.apply_block(&test_block(3, vec![])).unwrap();
+        state_manager.apply_block(&test_block(4, vec![Transaction::ClaimAssetRewards {
+            from: alice,
+            pool_id,
             fee: 0,
-            nonce: 0,
+            nonce: 2,
             chain_id: None,
             valid_until_height: None,
-            signature: vec![2; 64],
-        };
-        state_manager.apply_transaction(&evap_tx).unwrap();
-
-        let asset = state_manager.get_asset(&asset_id).unwrap();
-        assert_eq!(asset.data.density, crate::types::DensityLevel::Ethereal);
-    }
-
-    #[test]
-    fn test_metadata_size_exceeded() {
-        let config = create_test_config("meta_size");
-        let state_manager = StateManager::new(&config).unwrap();
-        let owner = create_test_address(1);
-        state_manager.create_test_account(owner, 100_000, 0);
+            signature: vec![1; 64],
+        }])).unwrap();
+        assert_eq!(balance(&alice), alice_start + 20);
+        state_manager.apply_block(&test_block(5, vec![unstake(bob, shield, 3)])).unwrap();
+        assert_eq!((balance(&bob), state_manager.get_asset(&shield).unwrap().owner), (bob_start + 15, bob));
 
-        let asset_id = crate::types::sha256(b"oversized");
-        let mut meta = std::collections::HashMap::new();
-        meta.insert("big".to_string(), "x".to_string().repeat(6 * 1024)); // Ethereal max 5KB
+        // Emission stops when the reserve runs out
+        state_manager.apply_block(&test_block(6, vec![])).unwrap();
+        state_manager.apply_block(&test_block(7, vec![unstake(alice, sword, 3)])).unwrap();
+        assert_eq!((balance(&alice), state_manager.get_asset(&sword).unwrap().owner), (alice_start + 30, alice));
+        let pool = state_manager.get_asset_pool(&pool_id).unwrap();
+        assert_eq!((pool.reserve, pool.staked, pool.paid), (0, 0, 45));
+        assert_eq!(balance(&asset_pool_escrow_address()), 0);
+        assert!(state_manager.get_account_asset_stakes(&alice).is_empty());
 
-        let tx = Transaction::MistbornAsset {
-            from: owner,
-            action: crate::types::AssetAction::Create,
-            asset_id,
-            data: crate::types::AssetData {
-                density: crate::types::DensityLevel::Ethereal,
-                metadata: meta,
-                attributes: vec![],
-                game_id: None,
-                owner,
-            },
+        // Funding an empty pool does not pay for the blocks nobody staked
+        state_manager.apply_block(&test_block(8, vec![Transaction::FundAssetPool {
+            from: studio,
+            pool_id,
+            amount: 100,
             fee: 0,
-            nonce: 0,
+            nonce: 1,
             chain_id: None,
             valid_until_height: None,
             signature: vec![1; 64],
-        };
-        let res = state_manager.apply_transaction(&tx);
-        assert!(res.is_err());
-        let err = res.unwrap_err().to_string();
-        assert!(err.contains("Asset size exceeded") || err.contains("size"));
+        }])).unwrap();
+        assert_eq!(state_manager.get_asset_pool(&pool_id).unwrap().reserve, 100);
+        assert_eq!(balance(&studio), studio_start - 145);
+        assert_eq!(state_manager.get_collection_asset_pools("my_game").len(), 1);
     }
 
     #[test]
-    fn test_set_asset_permissions() {
-        let config = create_test_config("perms");
+    fn test_breed_hatches_child_from_beacon() {
+        use crate::breeding::{derive_child_attributes, BreedingStatus};
+        use crate::config::BreedingRule;
+        use crate::types::{breeding_id, AssetAction, AssetData, Attribute, BlockHeader, DensityLevel, SeasonHooks};
+        let mut config = create_test_config("breeding");
+        config.economy.breeding = vec![BreedingRule {
+            game_id: "dragons".to_string(),
+            fee: 5,
+            cooldown_blocks: 3,
+            hatch_delay: 2,
+        }];
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
         let state_manager = StateManager::new(&config).unwrap();
-        let owner = create_test_address(1);
-        let other = create_test_address(2);
-        state_manager.create_test_account(owner, 100_000, 0);
 
-        let asset_id = crate::types::sha256(b"perm_asset");
-        let create_tx = Transaction::MistbornAsset {
-            from: owner,
-            action: crate::types::AssetAction::Create,
-            asset_id,
-            data: crate::types::AssetData {
-                density: crate::types::DensityLevel::Ethereal,
-                metadata: std::collections::HashMap::new(),
-                attributes: vec![],
-                game_id: None,
-                owner,
-            },
+        let breeder = create_test_address(1);
+        let rival = create_test_address(2);
+        let studio = create_test_address(3);
+        for address in [breeder, rival, studio] {
+            state_manager.create_test_account(address, 100_000, 0);
+        }
+        let attribute = |name: &str, value: &str| Attribute { name: name.to_string(), value: value.into(), rarity: None };
+        let create_asset = |owner: Address, name: &[u8], game_id: &str, density: DensityLevel, attributes: Vec<Attribute>| {
+            let asset_id = crate::types::sha256(name);
+            state_manager.apply_transaction(&Transaction::MistbornAsset {
+                from: owner,
+                action: AssetAction::Create,
+                asset_id,
+                data: AssetData {
+                    density,
+                    metadata: HashMap::new(),
+                    attributes,
+                    game_id: Some(game_id.to_string()),
+                    owner,
+                },
+                fee: 0,
+                nonce: next_nonce(&state_manager, &owner),
+                chain_id: None,
+                valid_until_height: None,
+                signature: vec![1; 64],
+            }).unwrap();
+            asset_id
+        };
+        let sire = create_asset(breeder, b"sire", "dragons", DensityLevel::Light, vec![attribute("color", "red"), attribute("wings", "yes")]);
+        let dam = create_asset(breeder, b"dam", "dragons", DensityLevel::Ethereal, vec![attribute("color", "green")]);
+        let cat = create_asset(breeder, b"cat", "cats", DensityLevel::Ethereal, vec![]);
+        let wild = create_asset(rival, b"wild", "dragons", DensityLevel::Ethereal, vec![]);
+
+        let breed = |parent_a: Hash, parent_b: Hash, nonce: u64| Transaction::Breed {
+            from: breeder,
+            parent_a,
+            parent_b,
             fee: 0,
-            nonce: 0,
+            nonce,
             chain_id: None,
             valid_until_height: None,
             signature: vec![1; 64],
         };
-        state_manager.apply_transaction(&create_tx).unwrap();
 
-        let set_tx = Transaction::SetAssetPermissions {
-            from: owner,
-            asset_id,
-            permissions: vec![crate::types::AssetPermission {
-                grantee: other,
-                level: crate::types::PermissionLevel::PublicRead,
-                game_id: None,
-                expires_at: None,
-            }],
-            public_read: true,
-            owner,
+        assert!(matches!(state_manager.apply_transaction(&breed(sire, wild, 3)), Err(HazeError::AccessDenied(_))));
+        assert!(state_manager.apply_transaction(&breed(sire, cat, 3)).is_err());
+        let balance_before = state_manager.get_account(&breeder).unwrap().balance;
+        state_manager.apply_block(&test_block(1, vec![breed(sire, dam, 3)])).unwrap();
+        assert_eq!(state_manager.get_account(&breeder).unwrap().balance, balance_before - 5);
+        let child = breeding_id(&breeder, 3);
+        assert_eq!(state_manager.get_breeding(&child).unwrap().hatch_height, 3);
+
+        // Parents wait out their cooldown; the child hatches from block 3's hash
+        state_manager.apply_block(&test_block(2, vec![])).unwrap();
+        let err = state_manager.apply_transaction(&breed(dam, sire, 4)).unwrap_err();
+        assert!(err.to_string().contains("can breed again at block 4"));
+        assert!(state_manager.get_asset(&child).is_none());
+        state_manager.apply_block(&test_block(3, vec![])).unwrap();
+        let breeding = state_manager.get_breeding(&child).unwrap();
+        assert_eq!((breeding.status(), breeding.hatched_at_height), (BreedingStatus::Hatched, Some(3)));
+        let asset = state_manager.get_asset(&child).unwrap();
+        assert_eq!((asset.owner, asset.data.density), (breeder, DensityLevel::Ethereal));
+        let expected = derive_child_attributes(&breeding.parent_attributes, &crate::types::sha256(&3u64.to_le_bytes()), &child);
+        assert_eq!(format!("{:?}", asset.data.attributes), format!("{:?}", expected));
+        let parents: Vec<(Hash, AssetAction)> = state_manager.get_asset_lineage(&child).unwrap().parents
+            .iter().map(|edge| (edge.parent, edge.action.clone())).collect();
+        assert_eq!(parents, vec![(sire, AssetAction::Breed), (dam, AssetAction::Breed)]);
+
+        // A child due while its collection is frozen hatches once the next season thaws it
+        let register = |season: &str, start_height: u64, end_height: u64, nonce: u64| Transaction::RegisterSeason {
+            from: studio,
+            game_id: "dragons".to_string(),
+            season: season.to_string(),
+            start_height,
+            end_height,
+            hooks: SeasonHooks { freeze_collections: vec!["dragons".to_string()], ..SeasonHooks::default() },
             fee: 0,
-            nonce: 0,
+            nonce,
             chain_id: None,
             valid_until_height: None,
-            signature: vec![2; 64],
+            signature: vec![1; 64],
         };
-        state_manager.apply_transaction(&set_tx).unwrap();
-
-        let asset = state_manager.get_asset(&asset_id).unwrap();
-        assert!(asset.public_read);
-        assert_eq!(asset.permissions.len(), 1);
-        assert_eq!(asset.permissions[0].grantee, other);
-        assert_eq!(asset.permissions[0].level, crate::types::PermissionLevel::PublicRead);
+        state_manager.apply_block(&test_block(4, vec![breed(dam, sire, 4), register("s1", 5, 5, 0), register("s2", 8, 10, 1)])).unwrap();
+        let second = breeding_id(&breeder, 4);
+        for height in 5..=7 {
+            state_manager.apply_block(&test_block(height, vec![])).unwrap();
+            assert!(state_manager.get_asset(&second).is_none());
+        }
+        state_manager.apply_block(&test_block(8, vec![])).unwrap();
+        assert_eq!(state_manager.get_breeding(&second).unwrap().hatched_at_height, Some(8));
+        assert_eq!(state_manager.get_last_bred(&sire), Some(4));
     }
 
     #[test]
-    fn test_search_assets_by_density() {
-        let config = create_test_config("search_density");
+    fn test_upgrade_rolls_outcome_from_beacon() {
+        use crate::config::UpgradeRule;
+        use crate::economy::upgrade_escrow_address;
+        use crate::types::{upgrade_id, AssetAction, AssetData, Attribute, BlockHeader, DensityLevel};
+        use crate::upgrade::UpgradeOutcome;
+        let mut config = create_test_config("upgrades");
+        let rule = |game_id: &str, success_bps: u64, destroy_bps: u64, catalysts: u32, roll_delay: u64| UpgradeRule {
+            game_id: game_id.to_string(),
+            attribute: "level".to_string(),
+            boost: 2,
+            fee: 7,
+            catalysts,
+            success_bps,
+            destroy_bps,
+            roll_delay,
+        };
+        config.economy.upgrades = vec![rule("swords", 10_000, 0, 1, 2), rule("glass", 0, 10_000, 0, 1)];
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
         let state_manager = StateManager::new(&config).unwrap();
-        let owner = create_test_address(1);
-        state_manager.create_test_account(owner, 100_000, 0);
+        let (ws_tx, mut ws_rx) = broadcast::channel(64);
+        state_manager.set_ws_tx(ws_tx);
 
-        let id_e = crate::types::sha256(b"e");
-        let id_l = crate::types::sha256(b"l");
-        for (id, density) in [
-            (id_e, crate::types::DensityLevel::Ethereal),
-            (id_l, crate::types::DensityLevel::Light),
-        ] {
-            let tx = Transaction::MistbornAsset {
+        let smith = create_test_address(1);
+        let rival = create_test_address(2);
+        for address in [smith, rival] {
+            state_manager.create_test_account(address, 100_000, 0);
+        }
+        let create_asset = |owner: Address, name: &[u8], game_id: &str, level: Option<&str>| {
+            let asset_id = crate::types::sha256(name);
+            let attributes = level
+                .map(|level| vec![Attribute { name: "level".to_string(), value: level.into(), rarity: None }])
+                .unwrap_or_default();
+            state_manager.apply_transaction(&Transaction::MistbornAsset {
                 from: owner,
-                action: crate::types::AssetAction::Create,
-                asset_id: id,
-                data: crate::types::AssetData {
-                    density,
-                    metadata: std::collections::HashMap::new(),
-                    attributes: vec![],
-                    game_id: None,
+                action: AssetAction::Create,
+                asset_id,
+                data: AssetData {
+                    density: DensityLevel::Ethereal,
+                    metadata: HashMap::new(),
+                    attributes,
+                    game_id: Some(game_id.to_string()),
                     owner,
                 },
                 fee: 0,
-                nonce: 0,
+                nonce: next_nonce(&state_manager, &owner),
                 chain_id: None,
                 valid_until_height: None,
-                signature: vec![id[0]; 64],
-            };
-            state_manager.apply_transaction(&tx).unwrap();
-        }
+                signature: vec![1; 64],
+            }).unwrap();
+            asset_id
+        };
+        let sword = create_asset(smith, b"sword", "swords", Some("3"));
+        let shard = create_asset(smith, b"shard", "swords", None);
+        let relic = create_asset(smith, b"relic", "swords", Some("max"));
+        let stolen = create_asset(rival, b"stolen", "swords", None);
+        let vase = create_asset(smith, b"vase", "glass", None);
 
-        let ethereal = state_manager.search_assets_by_density(crate::types::DensityLevel::Ethereal);
-        let light = state_manager.search_assets_by_density(crate::types::DensityLevel::Light);
-        assert!(ethereal.contains(&id_e));
-        assert!(light.contains(&id_l));
-        assert!(!ethereal.contains(&id_l));
-        assert!(!light.contains(&id_e));
+        let upgrade = |asset_id: Hash, catalysts: Vec<Hash>, nonce: u64| Transaction::Upgrade {
+            from: smith,
+            asset_id,
+            catalysts,
+            fee: 0,
+            nonce,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+
+        // Catalysts must match the rule and belong to the owner; the attribute must be a number
+        assert!(state_manager.apply_transaction(&upgrade(sword, vec![], 4)).is_err());
+        assert!(matches!(state_manager.apply_transaction(&upgrade(sword, vec![stolen], 4)), Err(HazeError::AccessDenied(_))));
+        let err = state_manager.apply_transaction(&upgrade(relic, vec![shard], 4)).unwrap_err();
+        assert!(err.to_string().contains("not a number"));
+
+        // The fee and catalysts are spent up front and the asset waits in escrow
+        let balance_before = state_manager.get_account(&smith).unwrap().balance;
+        state_manager.apply_block(&test_block(1, vec![upgrade(sword, vec![shard], 4)])).unwrap();
+        assert_eq!(state_manager.get_account(&smith).unwrap().balance, balance_before - 7);
+        assert!(state_manager.get_asset(&shard).is_none());
+        assert_eq!(state_manager.get_asset(&sword).unwrap().owner, upgrade_escrow_address());
+        let first = upgrade_id(&smith, 4);
+        assert_eq!(state_manager.get_upgrade(&first).unwrap().roll_height, 3);
+        state_manager.apply_block(&test_block(2, vec![])).unwrap();
+        assert!(state_manager.get_upgrade(&first).unwrap().outcome.is_none());
+
+        // A success boosts the attribute and gives the asset back
+        state_manager.apply_block(&test_block(3, vec![upgrade(vase, vec![], 5)])).unwrap();
+        let resolved = state_manager.get_upgrade(&first).unwrap();
+        assert_eq!((resolved.outcome, resolved.value_after), (Some(UpgradeOutcome::Success), Some(5)));
+        let asset = state_manager.get_asset(&sword).unwrap();
+        assert_eq!(asset.owner, smith);
+        assert_eq!(asset.data.attributes.iter().find(|a| a.name == "level").unwrap().value, AttributeValue::Int(5));
+        let entry = asset.history.last().unwrap();
+        assert_eq!((entry.action.clone(), entry.changes.get("outcome").map(String::as_str)), (AssetAction::Upgrade, Some("success")));
+        let parents: Vec<(Hash, AssetAction)> = state_manager.get_asset_lineage(&sword).unwrap().parents
+            .iter().map(|edge| (edge.parent, edge.action.clone())).collect();
+        assert_eq!(parents, vec![(shard, AssetAction::Upgrade)]);
+
+        // A destroyed asset is burned, and its upgrades stay readable
+        state_manager.apply_block(&test_block(4, vec![])).unwrap();
+        assert!(state_manager.get_asset(&vase).is_none());
+        let upgrades = state_manager.get_asset_upgrades(&vase);
+        assert_eq!((upgrades.len(), upgrades[0].outcome, upgrades[0].value_after), (1, Some(UpgradeOutcome::Destroy), None));
+
+        let mut outcomes = Vec::new();
+        while let Ok(event) = ws_rx.try_recv() {
+            if let WsEvent::AssetUpgraded { asset_id, outcome, value, .. } = event {
+                outcomes.push((asset_id, outcome, value));
+            }
+        }
+        assert_eq!(outcomes, vec![(sword, UpgradeOutcome::Success, Some(5)), (vase, UpgradeOutcome::Destroy, None)]);
     }
 
     #[test]
-    fn test_write_permission_game_contract() {
-        let config = create_test_config("game_contract");
-        let state_manager = StateManager::new(&config).unwrap();
-        let owner = create_test_address(1);
-        let grantee = create_test_address(2);
-        state_manager.create_test_account(owner, 100_000, 0);
-        state_manager.create_test_account(grantee, 100_000, 0);
+    fn test_governance_token_transfer_stake_and_weight() {
+        use crate::config::GenesisAllocation;
+        let holder = create_test_address(1);
+        let other = create_test_address(2);
 
-        let asset_id = crate::types::sha256(b"game_asset");
-        let create_tx = Transaction::MistbornAsset {
-            from: owner,
-            action: crate::types::AssetAction::Create,
-            asset_id,
-            data: crate::types::AssetData {
-                density: crate::types::DensityLevel::Ethereal,
-                metadata: std::collections::HashMap::new(),
-                attributes: vec![],
-                game_id: Some("game1".to_string()),
-                owner,
-            },
+        // Disabled: governance transactions are refused and weight is HAZE stake
+        let config = create_test_config("governance_token_disabled");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        let state_manager = StateManager::new(&config).unwrap();
+        state_manager.create_test_account(holder, 10_000, 0);
+        let stake_haze = Transaction::Stake {
+            from: holder,
+            validator: holder,
+            amount: 1_000,
             fee: 0,
             nonce: 0,
             chain_id: None,
             valid_until_height: None,
             signature: vec![1; 64],
         };
-        state_manager.apply_transaction(&create_tx).unwrap();
+        state_manager.apply_transaction(&stake_haze).unwrap();
+        assert_eq!(state_manager.governance_weight(&holder), 1_000);
+        let stake = |amount: u64, nonce: u64| Transaction::StakeGovernance {
+            from: holder,
+            amount,
+            fee: 10,
+            nonce,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        };
+        assert!(state_manager.apply_transaction(&stake(1, 0)).is_err());
 
-        let set_tx = Transaction::SetAssetPermissions {
-            from: owner,
-            asset_id,
-            permissions: vec![crate::types::AssetPermission {
-                grantee,
-                level: crate::types::PermissionLevel::GameContract,
-                game_id: Some("game1".to_string()),
-                expires_at: None,
-            }],
-            public_read: false,
-            owner,
-            fee: 0,
+        // Enabled: genesis allocation, transfer and stake move only the governance token
+        let mut config = create_test_config("governance_token");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        config.chain_id = 1;
+        config.governance_token.enabled = true;
+        config.governance_token.genesis = vec![GenesisAllocation {
+            address: crate::types::address_to_hex(&holder),
+            amount: 5_000,
+        }];
+        let mut duplicate = config.clone();
+        duplicate.governance_token.genesis.extend(config.governance_token.genesis.clone());
+        assert!(duplicate.validate().is_err());
+        config.validate().unwrap();
+        let state_manager = StateManager::new(&config).unwrap();
+        assert_eq!(state_manager.tokenomics().governance_supply(), 5_000);
+        state_manager.seed_dev_account(holder, 10_000).unwrap();
+        let account = state_manager.get_account(&holder).unwrap();
+        assert_eq!((account.balance, account.governance_balance), (10_000, 5_000));
+
+        state_manager.apply_transaction(&Transaction::TransferGovernance {
+            from: holder,
+            to: other,
+            amount: 2_000,
+            fee: 10,
             nonce: 0,
             chain_id: None,
             valid_until_height: None,
-            signature: vec![2; 64],
+            signature: vec![1; 64],
+        }).unwrap();
+        state_manager.apply_transaction(&stake(2_500, 1)).unwrap();
+        // More than the unstaked balance
+        assert!(matches!(
+            state_manager.apply_transaction(&stake(1_000, 2)),
+            Err(HazeError::InsufficientBalance { required: 1_000, available: 500 })
+        ));
+        state_manager.apply_transaction(&Transaction::UnstakeGovernance {
+            from: holder,
+            amount: 500,
+            fee: 10,
+            nonce: 2,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        }).unwrap();
+
+        let account = state_manager.get_account(&holder).unwrap();
+        assert_eq!(account.balance, 10_000 - 30, "fees are paid in HAZE");
+        assert_eq!((account.governance_balance, account.governance_staked), (1_000, 2_000));
+        assert_eq!(state_manager.get_account(&other).unwrap().governance_balance, 2_000);
+        assert_eq!(state_manager.governance_weight(&holder), 2_000);
+        assert_eq!(state_manager.governance_weight(&other), 0);
+        assert_eq!(state_manager.total_governance_staked(), 2_000);
+    }
+
+    #[test]
+    fn test_set_commission_transaction() {
+        let mut config = create_test_config("set_commission");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
+        config.consensus.commission_notice_blocks = 5;
+        let state_manager = StateManager::new(&config).unwrap();
+        let validator = create_test_address(1);
+        state_manager.create_test_account(validator, 10_000, 0);
+        let set_commission = |rate_bps: u64, nonce: u64| Transaction::SetCommission {
+            from: validator,
+            rate_bps,
+            fee: 10,
+            nonce,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
         };
-        state_manager.apply_transaction(&set_tx).unwrap();
+        // Only registered validators have a commission
+        assert!(state_manager.apply_transaction(&set_commission(50, 0)).is_err());
 
-        let mut upd_meta = std::collections::HashMap::new();
-        upd_meta.insert("updated".to_string(), "by_grantee".to_string());
-        let update_tx = Transaction::MistbornAsset {
-            from: grantee,
-            action: crate::types::AssetAction::Update,
-            asset_id,
-            data: crate::types::AssetData {
-                density: crate::types::DensityLevel::Ethereal,
-                metadata: upd_meta,
-                attributes: vec![],
-                game_id: Some("game1".to_string()),
-                owner: grantee,
-            },
+        state_manager.apply_transaction(&Transaction::Stake {
+            from: validator,
+            validator,
+            amount: 1_000,
             fee: 0,
-            nonce: 0,
+            nonce: next_nonce(&state_manager, &validator),
             chain_id: None,
             valid_until_height: None,
-            signature: vec![3; 64],
-        };
-        state_manager.apply_transaction(&update_tx).unwrap();
+            signature: vec![1; 64],
+        }).unwrap();
+        // Over the 1% step of the default policy
+        assert!(state_manager.apply_transaction(&set_commission(200, 1)).is_err());
+        state_manager.apply_transaction(&set_commission(100, 1)).unwrap();
 
-        let asset = state_manager.get_asset(&asset_id).unwrap();
-        assert_eq!(asset.data.metadata.get("updated"), Some(&"by_grantee".to_string()));
+        let info = state_manager.tokenomics().get_validator(&validator).unwrap();
+        assert_eq!(info.commission_bps, 0);
+        assert_eq!(info.pending_commission.map(|p| (p.rate_bps, p.effective_height)), Some((100, 6)));
+        assert_eq!(state_manager.get_account(&validator).unwrap().balance, 10_000 - 1_000 - 10);
+        assert!(state_manager.apply_transaction(&set_commission(50, 2)).is_err(), "one change per epoch");
     }
 
     #[test]
-    fn test_get_quota_usage() {
-        let config = create_test_config("quota");
+    fn test_committee_params_governance_vote() {
+        let config = create_test_config("committee_vote");
+        let _ = std::fs::remove_dir_all(&config.storage.db_path);
         let state_manager = StateManager::new(&config).unwrap();
-        let owner = create_test_address(1);
-        state_manager.create_test_account(owner, 100_000, 0);
+        assert_eq!(state_manager.tokenomics().committee_params(), config.consensus.committee_params());
 
-        let usage_empty = state_manager.get_quota_usage(&owner);
-        assert_eq!(usage_empty.assets_count, 0);
-        assert!(usage_empty.assets_limit > 0);
-        assert_eq!(usage_empty.blob_files_count, 0);
-
-        let asset_id = crate::types::sha256(b"quota_asset");
-        let tx = Transaction::MistbornAsset {
-            from: owner,
-            action: crate::types::AssetAction::Create,
-            asset_id,
-            data: crate::types::AssetData {
-                density: crate::types::DensityLevel::Ethereal,
-                metadata: std::collections::HashMap::new(),
-                attributes: vec![],
-                game_id: None,
-                owner,
-            },
-            fee: 0,
-            nonce: 0,
+        let voters = [create_test_address(1), create_test_address(2), create_test_address(3)];
+        for (voter, amount) in voters.iter().zip([1_000u64, 1_000, 500]) {
+            state_manager.create_test_account(*voter, 10_000, 0);
+            state_manager.apply_transaction(&Transaction::Stake {
+                from: *voter,
+                validator: *voter,
+                amount,
+                fee: 0,
+                nonce: next_nonce(&state_manager, voter),
+                chain_id: None,
+                valid_until_height: None,
+                signature: vec![1; 64],
+            }).unwrap();
+        }
+        let vote = |from: Address, min_stake: u64| Transaction::VoteCommitteeParams {
+            from,
+            min_stake,
+            max_weight_bps: 2_500,
+            fee: 10,
+            nonce: next_nonce(&state_manager, &from),
             chain_id: None,
             valid_until_height: None,
             signature: vec![1; 64],
         };
-        state_manager.apply_transaction(&tx).unwrap();
+        let proposed = crate::tokenomics::CommitteeParams { min_stake: 800, max_weight_bps: 2_500 };
 
-        let usage = state_manager.get_quota_usage(&owner);
-        assert_eq!(usage.assets_count, 1);
-        assert!(usage.assets_limit > 0);
-        assert!(usage.metadata_size_limit > 0);
+        // Accounts without governance weight cannot vote
+        let outsider = create_test_address(9);
+        state_manager.create_test_account(outsider, 10_000, 0);
+        assert!(state_manager.apply_transaction(&vote(outsider, 800)).is_err());
+
+        // 40% of the weight, then 60% split over two proposals: nothing changes
+        state_manager.apply_transaction(&vote(voters[0], 800)).unwrap();
+        state_manager.apply_transaction(&vote(voters[2], 900)).unwrap();
+        assert_eq!(state_manager.tokenomics().committee_votes().len(), 2);
+        assert_eq!(state_manager.tokenomics().committee_params(), config.consensus.committee_params());
+
+        // 80% for the same parameters passes them and closes the vote
+        state_manager.apply_transaction(&vote(voters[1], 800)).unwrap();
+        assert_eq!(state_manager.tokenomics().committee_params(), proposed);
+        assert!(state_manager.tokenomics().committee_votes().is_empty());
+        assert_eq!(state_manager.get_account(&voters[1]).unwrap().balance, 10_000 - 1_000 - 10);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,356 @@
+//! State checkpoint written on a clean shutdown
+//!
+//! Accounts and assets are persisted in their trees with every block, but the rest of the
+//! block-derived state (tokenomics, order books, seasons, ...) lives only in memory
+//! and would otherwise be rebuilt by replaying every stored block. On shutdown the node stores
+//! it in the `meta` tree together with the height and state root it belongs to. The next start
+//! loads accounts and assets from their trees, rebuilds their indexes, restores the rest from
+//! the checkpoint and replays only the blocks above it.
+//!
+//! The checkpoint is removed as it is read, so a crash later on is never mistaken for a clean
+//! shutdown. It is ignored (and every block replayed) when it was written by another build,
+//! when the database changed since (journal recovery, migration or repair) or when the trees do
+//! not hash to its state root.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::hash::Hash as MapKey;
+use std::sync::atomic::Ordering;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use crate::economy::{AssetPool, AssetStake, CollectionMarketStats, FogEconomyCheckpoint, LootBox, Order, Referral, ReferralEarnings, Trade};
+use crate::error::{HazeError, Result};
+use crate::features::Tally;
+use crate::leaderboard::Leaderboard;
+use crate::season::Season;
+use crate::storage::META_TREE;
+use crate::tokenomics::TokenomicsCheckpoint;
+use crate::types::{Address, Hash};
+use crate::breeding::Breeding;
+use crate::upgrade::Upgrade;
+use super::{
+    full_state_root, AccountState, AssetLineage, AssetState, ContractInfo, OwnershipChange, PaymentRequest,
+    RegisteredAssetHook, StateManager, Subscription,
+};
+
+/// Key of the checkpoint in the `meta` tree
+const CHECKPOINT_KEY: &[u8] = b"state_checkpoint";
+
+/// Memory-only state of a `StateManager` at `height`
+#[derive(Serialize, Deserialize)]
+struct StateCheckpoint {
+    /// Node version that wrote the checkpoint (the layout below may change between versions)
+    node_version: String,
+    height: u64,
+    state_root: Hash,
+    tokenomics: TokenomicsCheckpoint,
+    economy: FogEconomyCheckpoint,
+    feature_tally: Tally,
+    collection_ownership: Vec<(String, Vec<OwnershipChange>)>,
+    market_stats: Vec<(String, CollectionMarketStats)>,
+    contracts: Vec<(Address, ContractInfo)>,
+    asset_hooks: Vec<(String, RegisteredAssetHook)>,
+    recent_block_timestamps: Vec<i64>,
+    recent_fees: Vec<Vec<u64>>,
+    payment_requests: Vec<(Hash, PaymentRequest)>,
+    asset_lineage: Vec<(Hash, AssetLineage)>,
+    orders: Vec<(Hash, Order)>,
+    open_orders: Vec<(String, Vec<Hash>)>,
+    trades: Vec<(String, VecDeque<Trade>)>,
+    order_sequence: u64,
+    referrals: Vec<((String, Address), Referral)>,
+    referral_earnings: Vec<(Address, BTreeMap<String, ReferralEarnings>)>,
+    subscriptions: Vec<(Hash, Subscription)>,
+    subscriptions_due: Vec<(u64, Vec<Hash>)>,
+    subscriptions_by_account: Vec<(Address, Vec<Hash>)>,
+    paymaster_spend: Vec<(Address, (u64, u64))>,
+    loot_boxes: Vec<(Hash, LootBox)>,
+    leaderboards: Vec<(Hash, Leaderboard)>,
+    leaderboards_by_game: Vec<(String, Vec<Hash>)>,
+    seasons: Vec<(Hash, Season)>,
+    seasons_by_game: Vec<(String, Vec<Hash>)>,
+    seasons_starting: Vec<(u64, Vec<Hash>)>,
+    seasons_ending: Vec<(u64, Vec<Hash>)>,
+    frozen_collections: Vec<(String, Hash)>,
+    asset_pools: Vec<(Hash, AssetPool)>,
+    asset_pools_by_game: Vec<(String, Vec<Hash>)>,
+    asset_stakes: Vec<(Hash, AssetStake)>,
+    asset_stakes_by_owner: Vec<(Address, Vec<Hash>)>,
+    breedings: Vec<(Hash, Breeding)>,
+    breedings_due: Vec<(u64, Vec<Hash>)>,
+    last_bred: Vec<(Hash, u64)>,
+    upgrades: Vec<(Hash, Upgrade)>,
+    upgrades_due: Vec<(u64, Vec<Hash>)>,
+    upgrades_by_asset: Vec<(Hash, Vec<Hash>)>,
+}
+
+fn entries<K: MapKey + Eq + Clone, V: Clone>(map: &DashMap<K, V>) -> Vec<(K, V)> {
+    map.iter().map(|entry| (entry.key().clone(), entry.value().clone())).collect()
+}
+
+fn restore<K: MapKey + Eq, V>(map: &DashMap<K, V>, entries: Vec<(K, V)>) {
+    map.clear();
+    for (key, value) in entries {
+        map.insert(key, value);
+    }
+}
+
+fn decode<T: serde::de::DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+    bincode::deserialize(bytes).map_err(|e| HazeError::Serialization(e.to_string()))
+}
+
+impl StateManager {
+    /// Store the memory-only state for the next start (call once block application has
+    /// stopped, on shutdown). Any block applied afterwards makes the checkpoint unusable.
+    pub fn write_checkpoint(&self) -> Result<()> {
+        let _commit = self.snapshot_lock.write();
+        // Every field is named so that new state has to be checkpointed (or skipped) here
+        let StateManager {
+            db,
+            trees: _,
+            storage: _,
+            journal: _,
+            blobs: _,
+            blob_stats: _,
+            config: _,
+            // Persisted in their trees with every block
+            accounts: _,
+            assets: _,
+            // Rebuilt from the accounts, assets and block index on start
+            account_hashes: _,
+            blocks: _,
+            asset_index_by_owner: _,
+            asset_index_by_density: _,
+            asset_index_by_zone: _,
+            collection_traits: _,
+            current_height,
+            tokenomics,
+            economy,
+            features,
+            ws_tx: _,
+            // Caches and metrics
+            collection_rarity: _,
+            rarity_generation: _,
+            asset_access_count: _,
+            latency: _,
+            state_version: _,
+            snapshot_lock: _,
+            // Persisted in its tree as transactions fail
+            dead_letters: _,
+            collection_ownership,
+            market_stats,
+            contracts,
+            asset_hooks,
+            recent_block_timestamps,
+            recent_fees,
+            payment_requests,
+            asset_lineage,
+            orders,
+            open_orders,
+            trades,
+            order_sequence,
+            referrals,
+            referral_earnings,
+            subscriptions,
+            subscriptions_due,
+            subscriptions_by_account,
+            paymaster_spend,
+            loot_boxes,
+            leaderboards,
+            leaderboards_by_game,
+            seasons,
+            seasons_by_game,
+            seasons_starting,
+            seasons_ending,
+            frozen_collections,
+            asset_pools,
+            asset_pools_by_game,
+            asset_stakes,
+            asset_stakes_by_owner,
+            breedings,
+            breedings_due,
+            last_bred,
+            upgrades,
+            upgrades_due,
+            upgrades_by_asset,
+        } = self;
+        let checkpoint = StateCheckpoint {
+            node_version: env!("CARGO_PKG_VERSION").to_string(),
+            height: *current_height.read(),
+            state_root: self.compute_state_root(),
+            tokenomics: tokenomics.checkpoint(),
+            economy: economy.checkpoint(),
+            feature_tally: features.checkpoint(),
+            collection_ownership: entries(collection_ownership),
+            market_stats: entries(market_stats),
+            contracts: entries(contracts),
+            asset_hooks: entries(asset_hooks),
+            recent_block_timestamps: recent_block_timestamps.read().iter().copied().collect(),
+            recent_fees: recent_fees.read().block_fees().map(<[u64]>::to_vec).collect(),
+            payment_requests: entries(payment_requests),
+            asset_lineage: entries(asset_lineage),
+            orders: entries(orders),
+            open_orders: entries(open_orders),
+            trades: entries(trades),
+            order_sequence: order_sequence.load(Ordering::SeqCst),
+            referrals: entries(referrals),
+            referral_earnings: entries(referral_earnings),
+            subscriptions: entries(subscriptions),
+            subscriptions_due: entries(subscriptions_due),
+            subscriptions_by_account: entries(subscriptions_by_account),
+            paymaster_spend: entries(paymaster_spend),
+            loot_boxes: entries(loot_boxes),
+            leaderboards: entries(leaderboards),
+            leaderboards_by_game: entries(leaderboards_by_game),
+            seasons: entries(seasons),
+            seasons_by_game: entries(seasons_by_game),
+            seasons_starting: entries(seasons_starting),
+            seasons_ending: entries(seasons_ending),
+            frozen_collections: entries(frozen_collections),
+            asset_pools: entries(asset_pools),
+            asset_pools_by_game: entries(asset_pools_by_game),
+            asset_stakes: entries(asset_stakes),
+            asset_stakes_by_owner: entries(asset_stakes_by_owner),
+            breedings: entries(breedings),
+            breedings_due: entries(breedings_due),
+            last_bred: entries(last_bred),
+            upgrades: entries(upgrades),
+            upgrades_due: entries(upgrades_due),
+            upgrades_by_asset: entries(upgrades_by_asset),
+        };
+        let encoded = bincode::serialize(&checkpoint).map_err(|e| HazeError::Serialization(e.to_string()))?;
+        db.open_tree(META_TREE)?.insert(CHECKPOINT_KEY, &encoded)?;
+        db.flush()
+    }
+
+    /// Remove the checkpoint left by the last shutdown; restore from it when `usable` (the
+    /// database has not changed since it was written). Returns whether the state was restored,
+    /// leaving the state untouched otherwise.
+    pub(super) fn restore_checkpoint(&self, usable: bool) -> Result<bool> {
+        let meta = self.db.open_tree(META_TREE)?;
+        let Some(encoded) = meta.get(CHECKPOINT_KEY)? else {
+            return Ok(false);
+        };
+        meta.remove(CHECKPOINT_KEY)?;
+        if !usable {
+            tracing::info!("Ignoring the state checkpoint: the database changed since it was written");
+            return Ok(false);
+        }
+        let checkpoint: StateCheckpoint = match decode(&encoded) {
+            Ok(checkpoint) => checkpoint,
+            Err(e) => {
+                tracing::warn!("Ignoring unreadable state checkpoint: {}", e);
+                return Ok(false);
+            }
+        };
+        if checkpoint.node_version != env!("CARGO_PKG_VERSION") {
+            tracing::info!("Ignoring the state checkpoint of node version {}", checkpoint.node_version);
+            return Ok(false);
+        }
+        let tip = match self.trees.blocks.last()? {
+            Some((key, _)) => <[u8; 8]>::try_from(&key[..]).map(u64::from_be_bytes).unwrap_or(u64::MAX),
+            None => 0,
+        };
+        if tip != checkpoint.height {
+            tracing::info!("Ignoring the state checkpoint of height {}: blocks are stored up to {}", checkpoint.height, tip);
+            return Ok(false);
+        }
+
+        // The trees must hold exactly the state the checkpoint was taken with
+        let mut accounts: Vec<(Address, AccountState)> = Vec::new();
+        let mut assets: Vec<(Hash, AssetState)> = Vec::new();
+        let loaded = (|| -> Result<()> {
+            for item in self.trees.accounts.iter() {
+                let (key, value) = item?;
+                let address = Address::try_from(&key[..])
+                    .map_err(|_| HazeError::Database("Invalid account key length".to_string()))?;
+                accounts.push((address, decode(&value)?));
+            }
+            for item in self.trees.assets.iter() {
+                let (key, value) = item?;
+                let asset_id = Hash::try_from(&key[..])
+                    .map_err(|_| HazeError::Database("Invalid asset key length".to_string()))?;
+                assets.push((asset_id, decode(&value)?));
+            }
+            Ok(())
+        })();
+        if let Err(e) = loaded {
+            tracing::warn!("Ignoring the state checkpoint: {}", e);
+            return Ok(false);
+        }
+        if full_state_root(&accounts, &assets, checkpoint.height) != checkpoint.state_root {
+            tracing::warn!("Ignoring the state checkpoint of height {}: the stored state does not match its root", checkpoint.height);
+            return Ok(false);
+        }
+
+        for (address, account) in accounts {
+            self.account_hashes.set(&address, &account);
+            self.accounts.insert(address, account);
+        }
+        for (asset_id, asset) in assets {
+            self.add_asset_to_indexes(&asset_id, &asset);
+            self.add_asset_traits(&asset);
+            self.assets.insert(asset_id, asset);
+        }
+        for item in self.trees.indexes.iter() {
+            let (key, value) = item?;
+            if let (Ok(hash), Ok(height)) = (Hash::try_from(&key[..]), <[u8; 8]>::try_from(&value[..])) {
+                self.blocks.record(hash, u64::from_be_bytes(height));
+            }
+        }
+        self.tokenomics.restore(checkpoint.tokenomics);
+        self.economy.restore(checkpoint.economy);
+        self.features.restore(checkpoint.feature_tally);
+        restore(&self.collection_ownership, checkpoint.collection_ownership);
+        restore(&self.market_stats, checkpoint.market_stats);
+        restore(&self.contracts, checkpoint.contracts);
+        restore(&self.asset_hooks, checkpoint.asset_hooks);
+        {
+            let mut timestamps = self.recent_block_timestamps.write();
+            timestamps.clear();
+            timestamps.extend(checkpoint.recent_block_timestamps);
+            while timestamps.len() > self.config.consensus.median_time_window {
+                timestamps.pop_front();
+            }
+        }
+        {
+            let mut recent_fees = self.recent_fees.write();
+            for fees in checkpoint.recent_fees {
+                recent_fees.record_block(fees);
+            }
+        }
+        restore(&self.payment_requests, checkpoint.payment_requests);
+        restore(&self.asset_lineage, checkpoint.asset_lineage);
+        restore(&self.orders, checkpoint.orders);
+        restore(&self.open_orders, checkpoint.open_orders);
+        restore(&self.trades, checkpoint.trades);
+        self.order_sequence.store(checkpoint.order_sequence, Ordering::SeqCst);
+        restore(&self.referrals, checkpoint.referrals);
+        restore(&self.referral_earnings, checkpoint.referral_earnings);
+        restore(&self.subscriptions, checkpoint.subscriptions);
+        restore(&self.subscriptions_due, checkpoint.subscriptions_due);
+        restore(&self.subscriptions_by_account, checkpoint.subscriptions_by_account);
+        restore(&self.paymaster_spend, checkpoint.paymaster_spend);
+        restore(&self.loot_boxes, checkpoint.loot_boxes);
+        restore(&self.leaderboards, checkpoint.leaderboards);
+        restore(&self.leaderboards_by_game, checkpoint.leaderboards_by_game);
+        restore(&self.seasons, checkpoint.seasons);
+        restore(&self.seasons_by_game, checkpoint.seasons_by_game);
+        restore(&self.seasons_starting, checkpoint.seasons_starting);
+        restore(&self.seasons_ending, checkpoint.seasons_ending);
+        restore(&self.frozen_collections, checkpoint.frozen_collections);
+        restore(&self.asset_pools, checkpoint.asset_pools);
+        restore(&self.asset_pools_by_game, checkpoint.asset_pools_by_game);
+        restore(&self.asset_stakes, checkpoint.asset_stakes);
+        restore(&self.asset_stakes_by_owner, checkpoint.asset_stakes_by_owner);
+        restore(&self.breedings, checkpoint.breedings);
+        restore(&self.breedings_due, checkpoint.breedings_due);
+        restore(&self.last_bred, checkpoint.last_bred);
+        restore(&self.upgrades, checkpoint.upgrades);
+        restore(&self.upgrades_due, checkpoint.upgrades_due);
+        restore(&self.upgrades_by_asset, checkpoint.upgrades_by_asset);
+        *self.current_height.write() = checkpoint.height;
+        self.bump_state_version();
+        tracing::info!("Restored state at height {} from the shutdown checkpoint", checkpoint.height);
+        Ok(true)
+    }
+}
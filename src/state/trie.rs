@@ -0,0 +1,344 @@
+//! Sparse merkle trie behind the state root
+//!
+//! Every account and asset has a leaf at the path given by the bits of its 32-byte key, holding
+//! the entity hash `sha256(bincode(key, value))`. The trie is compressed: an empty subtree hashes
+//! to zero and a subtree holding a single leaf is that leaf's hash, so paths are only as deep as
+//! needed to separate keys (about log2 of the entry count), not 256 levels.
+//!
+//! - leaf: `sha256(0x00 || key || entry hash)`
+//! - node: `sha256(0x01 || left || right)`
+//!
+//! Writes update the leaf and drop the cached nodes on its path; computing a root rehashes only
+//! those paths, so its cost follows the entries changed since the last root, not the size of
+//! the state. The root depends only on the current entries, never on write order.
+//!
+//! A `TrieProof` shows that a key has a given entry hash under a root (inclusion), or that it
+//! has none (exclusion: the key's subtree is empty or holds one other key).
+
+use std::collections::{BTreeMap, HashMap};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use crate::types::{sha256, Hash};
+
+/// Hash of an empty subtree
+pub const EMPTY_ROOT: Hash = [0u8; 32];
+
+/// Key length in bits, the deepest a path can go
+const KEY_BITS: usize = 256;
+
+/// Hash of one entity: sha256 of its bincode-encoded `(key, value)`
+pub fn entry_hash<V: Serialize>(key: &Hash, value: &V) -> Hash {
+    sha256(&bincode::serialize(&(key, value)).unwrap_or_default())
+}
+
+fn leaf_hash(key: &Hash, entry: &Hash) -> Hash {
+    let mut data = [0u8; 65];
+    data[1..33].copy_from_slice(key);
+    data[33..].copy_from_slice(entry);
+    sha256(&data)
+}
+
+fn node_hash(left: &Hash, right: &Hash) -> Hash {
+    let mut data = [0u8; 65];
+    data[0] = 1;
+    data[1..33].copy_from_slice(left);
+    data[33..].copy_from_slice(right);
+    sha256(&data)
+}
+
+/// Bit `depth` of `key`, most significant first
+fn bit(key: &Hash, depth: usize) -> bool {
+    key[depth / 8] & (0x80 >> (depth % 8)) != 0
+}
+
+fn set_bit(key: &mut Hash, depth: usize) {
+    key[depth / 8] |= 0x80 >> (depth % 8);
+}
+
+/// First and last key of the subtree at `depth` containing `key`
+fn subtree_bounds(key: &Hash, depth: usize) -> (Hash, Hash) {
+    let (mut low, mut high) = ([0u8; 32], [0xffu8; 32]);
+    let whole = depth / 8;
+    low[..whole].copy_from_slice(&key[..whole]);
+    high[..whole].copy_from_slice(&key[..whole]);
+    if depth < KEY_BITS {
+        let kept = !(0xffu8 >> (depth % 8));
+        low[whole] = key[whole] & kept;
+        high[whole] = key[whole] | !kept;
+    }
+    (low, high)
+}
+
+/// Sparse merkle trie over 32-byte keys (single-threaded; `RootTree` shares one)
+#[derive(Debug, Default)]
+pub struct SparseMerkleTrie {
+    /// Entry hash of every key
+    leaves: BTreeMap<Hash, Hash>,
+    /// Hashes of subtrees with two or more leaves, by (depth, first key of the subtree)
+    nodes: HashMap<(usize, Hash), Hash>,
+    /// Deepest cached node, bounding the path cleared on a write
+    deepest: usize,
+}
+
+impl SparseMerkleTrie {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the entry hash of `key`
+    pub fn insert(&mut self, key: Hash, entry: Hash) {
+        if self.leaves.insert(key, entry) != Some(entry) {
+            self.invalidate(&key);
+        }
+    }
+
+    pub fn remove(&mut self, key: &Hash) {
+        if self.leaves.remove(key).is_some() {
+            self.invalidate(key);
+        }
+    }
+
+    /// Drop the cached nodes on the path of `key`
+    fn invalidate(&mut self, key: &Hash) {
+        for depth in 0..=self.deepest.min(KEY_BITS - 1) {
+            self.nodes.remove(&(depth, subtree_bounds(key, depth).0));
+        }
+    }
+
+    /// Up to two leaves of the subtree at `depth` containing `key`
+    fn subtree_leaves(&self, key: &Hash, depth: usize) -> (Option<(Hash, Hash)>, bool) {
+        let (low, high) = subtree_bounds(key, depth);
+        let mut leaves = self.leaves.range(low..=high).map(|(k, v)| (*k, *v));
+        let first = leaves.next();
+        (first, leaves.next().is_some())
+    }
+
+    /// Hash of the subtree at `depth` containing `key`, from the cache where it is current
+    fn subtree_hash(&mut self, key: &Hash, depth: usize) -> Hash {
+        match self.subtree_leaves(key, depth) {
+            (None, _) => EMPTY_ROOT,
+            (Some((leaf_key, entry)), false) => leaf_hash(&leaf_key, &entry),
+            (Some(_), true) => {
+                let low = subtree_bounds(key, depth).0;
+                if let Some(hash) = self.nodes.get(&(depth, low)) {
+                    return *hash;
+                }
+                let mut high = low;
+                set_bit(&mut high, depth);
+                let hash = node_hash(&self.subtree_hash(&low, depth + 1), &self.subtree_hash(&high, depth + 1));
+                self.nodes.insert((depth, low), hash);
+                self.deepest = self.deepest.max(depth);
+                hash
+            }
+        }
+    }
+
+    /// Root hash, rehashing only the paths written since the last call
+    pub fn root(&mut self) -> Hash {
+        self.subtree_hash(&EMPTY_ROOT, 0)
+    }
+
+    /// Proof of `key`'s entry hash under the current root, or of its absence
+    pub fn prove(&mut self, key: &Hash) -> TrieProof {
+        let mut siblings = Vec::new();
+        let mut depth = 0;
+        let leaf = loop {
+            let (leaf, more) = self.subtree_leaves(key, depth);
+            if !more {
+                break leaf;
+            }
+            let mut sibling = *key;
+            sibling[depth / 8] ^= 0x80 >> (depth % 8);
+            siblings.push(self.subtree_hash(&sibling, depth + 1));
+            depth += 1;
+        };
+        TrieProof { siblings, leaf }
+    }
+}
+
+/// Merkle proof for one key of a `SparseMerkleTrie`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrieProof {
+    /// Sibling subtree hashes along the key's path, from the root down
+    pub siblings: Vec<Hash>,
+    /// The only leaf `(key, entry hash)` in the key's subtree at the end of the path: the key
+    /// itself for an inclusion proof, another key (or none) for an exclusion proof
+    pub leaf: Option<(Hash, Hash)>,
+}
+
+impl TrieProof {
+    /// Entry hash the proof shows for `key` (`None`: the key is absent)
+    pub fn entry(&self, key: &Hash) -> Option<Hash> {
+        self.leaf.filter(|(leaf_key, _)| leaf_key == key).map(|(_, entry)| entry)
+    }
+
+    /// Root the proof leads to for `key`, or `None` if the proof does not belong to `key`
+    pub fn compute_root(&self, key: &Hash) -> Option<Hash> {
+        if self.siblings.len() > KEY_BITS {
+            return None;
+        }
+        // Another key's leaf must sit in the key's subtree, i.e. share the path so far
+        if let Some((leaf_key, _)) = &self.leaf
+            && leaf_key != key
+            && subtree_bounds(leaf_key, self.siblings.len()) != subtree_bounds(key, self.siblings.len())
+        {
+            return None;
+        }
+        let mut hash = self.leaf.map_or(EMPTY_ROOT, |(leaf_key, entry)| leaf_hash(&leaf_key, &entry));
+        for (depth, sibling) in self.siblings.iter().enumerate().rev() {
+            hash = if bit(key, depth) { node_hash(sibling, &hash) } else { node_hash(&hash, sibling) };
+        }
+        Some(hash)
+    }
+
+    /// Whether the proof shows, under `root`, that `key` has entry hash `entry` (`None`: absent)
+    pub fn verify(&self, root: &Hash, key: &Hash, entry: Option<&Hash>) -> bool {
+        self.compute_root(key).as_ref() == Some(root) && self.entry(key).as_ref() == entry
+    }
+}
+
+/// Entity hashes of one state tree (accounts, or one asset shard), shared between threads
+#[derive(Debug, Default)]
+pub struct RootTree {
+    trie: Mutex<SparseMerkleTrie>,
+}
+
+impl RootTree {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the current value of an entity
+    pub fn set<V: Serialize>(&self, key: &Hash, value: &V) {
+        let hash = entry_hash(key, value);
+        self.trie.lock().insert(*key, hash);
+    }
+
+    /// Forget a removed entity
+    pub fn remove(&self, key: &Hash) {
+        self.trie.lock().remove(key);
+    }
+
+    /// Root of the tree, rehashing only the paths changed since the last call
+    pub fn root(&self) -> Hash {
+        self.trie.lock().root()
+    }
+
+    /// Proof of an entity's hash (or its absence) under `root()`
+    pub fn prove(&self, key: &Hash) -> TrieProof {
+        self.trie.lock().prove(key)
+    }
+
+    /// Root of a set of `(key, entry hash)` pairs computed from scratch (equivalence checks and
+    /// verifying a loaded state)
+    pub fn full_root(entries: impl IntoIterator<Item = (Hash, Hash)>) -> Hash {
+        let mut trie = SparseMerkleTrie::new();
+        for (key, entry) in entries {
+            trie.insert(key, entry);
+        }
+        trie.root()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_incremental_root_matches_full_recompute() {
+        let tree = RootTree::new();
+        let empty = tree.root();
+        assert_eq!(empty, EMPTY_ROOT);
+
+        let mut values: BTreeMap<Hash, u64> = BTreeMap::new();
+        for i in 0..500u64 {
+            // Keys spread over the trie, some written several times, some removed
+            let key = sha256(&(i % 300).to_le_bytes());
+            if i % 7 == 0 {
+                tree.remove(&key);
+                values.remove(&key);
+            } else {
+                tree.set(&key, &i);
+                values.insert(key, i);
+            }
+            if i % 50 == 0 {
+                let full = RootTree::full_root(values.iter().map(|(k, v)| (*k, entry_hash(k, v))));
+                assert_eq!(tree.root(), full);
+            }
+        }
+        let full = RootTree::full_root(values.iter().map(|(k, v)| (*k, entry_hash(k, v))));
+        assert_eq!(tree.root(), full);
+
+        // Write order does not matter, and removing everything gives the empty root
+        let reversed = RootTree::new();
+        for (key, value) in values.iter().rev() {
+            reversed.set(key, value);
+        }
+        assert_eq!(reversed.root(), tree.root());
+        for key in values.keys() {
+            tree.remove(key);
+        }
+        assert_eq!(tree.root(), empty);
+    }
+
+    #[test]
+    fn test_single_leaf_and_close_keys() {
+        let mut trie = SparseMerkleTrie::new();
+        let key = [0xaau8; 32];
+        trie.insert(key, [1u8; 32]);
+        assert_eq!(trie.root(), leaf_hash(&key, &[1u8; 32]));
+
+        // Keys differing only in the last bit sit 255 levels down
+        let mut neighbour = key;
+        neighbour[31] ^= 1;
+        trie.insert(neighbour, [2u8; 32]);
+        let root = trie.root();
+        let proof = trie.prove(&neighbour);
+        assert_eq!(proof.siblings.len(), KEY_BITS);
+        assert!(proof.verify(&root, &neighbour, Some(&[2u8; 32])));
+        assert!(trie.prove(&key).verify(&root, &key, Some(&[1u8; 32])));
+    }
+
+    #[test]
+    fn test_inclusion_and_exclusion_proofs() {
+        let mut trie = SparseMerkleTrie::new();
+        for i in 0..200u64 {
+            trie.insert(sha256(&i.to_le_bytes()), sha256(&(i * 3).to_le_bytes()));
+        }
+        let root = trie.root();
+
+        for i in [0u64, 17, 199] {
+            let key = sha256(&i.to_le_bytes());
+            let entry = sha256(&(i * 3).to_le_bytes());
+            let proof = trie.prove(&key);
+            assert_eq!(proof.entry(&key), Some(entry));
+            assert!(proof.verify(&root, &key, Some(&entry)));
+            // Wrong value, wrong root, or a proof replayed for another key
+            assert!(!proof.verify(&root, &key, Some(&[0u8; 32])));
+            assert!(!proof.verify(&[9u8; 32], &key, Some(&entry)));
+            assert!(!proof.verify(&root, &sha256(b"other"), Some(&entry)));
+        }
+
+        // Absent keys: the path ends in an empty subtree or at another key's leaf
+        for i in 200..260u64 {
+            let key = sha256(&i.to_le_bytes());
+            let proof = trie.prove(&key);
+            assert_eq!(proof.entry(&key), None);
+            assert!(proof.verify(&root, &key, None));
+        }
+
+        // A key's own proof cannot be turned into an exclusion proof
+        let key = sha256(&5u64.to_le_bytes());
+        let mut forged = trie.prove(&key);
+        forged.leaf = None;
+        assert!(!forged.verify(&root, &key, None));
+
+        // Proofs follow updates
+        trie.insert(key, [7u8; 32]);
+        let updated = trie.root();
+        assert_ne!(updated, root);
+        assert!(trie.prove(&key).verify(&updated, &key, Some(&[7u8; 32])));
+        trie.remove(&key);
+        assert!(trie.prove(&key).verify(&trie.root(), &key, None));
+    }
+}
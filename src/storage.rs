@@ -0,0 +1,796 @@
+//! Node database: storage backends, layout and maintenance
+//!
+//! The node reads and writes its database through the `Storage` trait (named trees of ordered
+//! key-value pairs, plus atomic writes across trees), so the engine can be chosen with
+//! `storage.backend`: sled (default), or RocksDB in nodes built with the `rocksdb` feature,
+//! where each tree is a column family. `memory` keeps a temporary sled database that is
+//! discarded on exit, for dev nodes.
+//!
+//! Chain data lives in named trees:
+//!
+//! | Tree | Key | Value |
+//! |------|-----|-------|
+//! | `blocks` | height (u64 BE) | wire-encoded block |
+//! | `block_checksums` | height (u64 BE) | sha256 of the stored block, checked at startup |
+//! | `indexes` | block hash | height (u64 BE), the applied-blocks set |
+//! | `accounts` | address | bincode `AccountState` |
+//! | `assets` | asset id | bincode `AssetState` |
+//! | `dead_letters` | tx hash | bincode `DeadLetterEntry` |
+//! | `contract_code` | sha256(code) | bytecode |
+//! | `journal` | height (u64 BE) | pending writes of a block being applied (see `journal`) |
+//! | `meta` | `schema_version` | schema version (u32 BE), see `migrations` |
+//! | `meta` | `chain_id` | chain the database was created for (u64 BE), see `bind_chain_id` |
+//!
+//! Sled databases written before named trees are migrated when opened. A maintenance task
+//! flushes the database on a schedule (sled rewrites fragmented segments as it flushes; it has
+//! no manual compaction) and samples the size of every tree for metrics. `haze migrate-storage`
+//! copies a database between backends; `haze migrate-schema` upgrades one written by an older
+//! build (see `migrations`).
+
+use std::fmt::Debug;
+use std::ops::Bound;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+use parking_lot::RwLock;
+use sled::{IVec, Transactional};
+use crate::config::{StorageBackend, StorageConfig};
+use crate::error::{HazeError, Result};
+
+pub const BLOCKS_TREE: &str = "blocks";
+pub const BLOCK_CHECKSUMS_TREE: &str = "block_checksums";
+pub const INDEXES_TREE: &str = "indexes";
+pub const ACCOUNTS_TREE: &str = "accounts";
+pub const ASSETS_TREE: &str = "assets";
+pub const DEAD_LETTERS_TREE: &str = "dead_letters";
+pub const CONTRACT_CODE_TREE: &str = "contract_code";
+pub const RECEIPTS_TREE: &str = "receipts";
+pub const BLOCK_LOGS_TREE: &str = "block_logs";
+pub const TX_LOCATIONS_TREE: &str = "tx_locations";
+pub const JOURNAL_TREE: &str = "journal";
+pub const META_TREE: &str = "meta";
+/// Key of the chain id in the `meta` tree
+const CHAIN_ID_KEY: &[u8] = b"chain_id";
+/// Name of the backend's unnamed default tree
+pub const DEFAULT_TREE: &str = "default";
+
+/// Entries copied per batch by `copy_storage`
+const COPY_BATCH_SIZE: usize = 10_000;
+
+/// Key/value pairs read from a tree
+pub type Entries<'a> = Box<dyn Iterator<Item = Result<(IVec, IVec)>> + 'a>;
+
+/// Inserts and removals applied together
+#[derive(Debug, Clone, Default)]
+pub struct WriteBatch {
+    ops: Vec<(Vec<u8>, Option<Vec<u8>>)>,
+}
+
+impl WriteBatch {
+    pub fn insert(&mut self, key: impl AsRef<[u8]>, value: impl Into<Vec<u8>>) {
+        self.ops.push((key.as_ref().to_vec(), Some(value.into())));
+    }
+
+    pub fn remove(&mut self, key: impl AsRef<[u8]>) {
+        self.ops.push((key.as_ref().to_vec(), None));
+    }
+
+    /// Keys and values in order; `None` removes the key
+    pub fn ops(&self) -> impl Iterator<Item = (&[u8], Option<&[u8]>)> {
+        self.ops.iter().map(|(key, value)| (key.as_slice(), value.as_deref()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+}
+
+/// One named tree: ordered key-value pairs. Values come back as `IVec`, a shared buffer
+/// (sled hands out its cached copy; other engines would wrap their own).
+pub trait StorageTree: Send + Sync + Debug {
+    fn get(&self, key: &[u8]) -> Result<Option<IVec>>;
+    fn insert(&self, key: &[u8], value: &[u8]) -> Result<()>;
+    fn remove(&self, key: &[u8]) -> Result<()>;
+    fn contains_key(&self, key: &[u8]) -> Result<bool>;
+    /// Entries with keys in `(from, to)`, in key order
+    fn range(&self, from: Bound<&[u8]>, to: Bound<&[u8]>) -> Entries<'_>;
+    /// Entry with the greatest key
+    fn last(&self) -> Result<Option<(IVec, IVec)>>;
+    fn apply_batch(&self, batch: WriteBatch) -> Result<()>;
+    /// Number of entries; engines that do not keep a count (RocksDB) return an estimate
+    fn len(&self) -> usize;
+    /// Persist this tree's writes to disk
+    fn flush(&self) -> Result<()>;
+
+    fn iter(&self) -> Entries<'_> {
+        self.range(Bound::Unbounded, Bound::Unbounded)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// A storage engine holding named trees
+pub trait Storage: Send + Sync + Debug {
+    /// Open (creating if needed) a tree; `DEFAULT_TREE` is the engine's unnamed tree
+    fn open_tree(&self, name: &str) -> Result<Arc<dyn StorageTree>>;
+    /// Names of all trees, including `DEFAULT_TREE`
+    fn tree_names(&self) -> Vec<String>;
+    /// Apply batches to several trees, all or nothing
+    fn apply_atomic(&self, batches: &[(&str, &WriteBatch)]) -> Result<()>;
+    fn flush(&self) -> Result<()>;
+    fn size_on_disk(&self) -> Result<u64>;
+}
+
+/// Open the database at `storage.db_path` with the configured backend
+pub fn open_storage(config: &StorageConfig) -> Result<Arc<dyn Storage>> {
+    open_backend(config.backend, &config.db_path)
+}
+
+/// Open a database with a given backend
+pub fn open_backend(backend: StorageBackend, path: &Path) -> Result<Arc<dyn Storage>> {
+    match backend {
+        StorageBackend::Sled => Ok(Arc::new(SledStorage::open(path)?)),
+        StorageBackend::Memory => Ok(Arc::new(SledStorage::temporary()?)),
+        #[cfg(feature = "rocksdb")]
+        StorageBackend::RocksDb => Ok(Arc::new(RocksDbStorage::open(path)?)),
+        #[cfg(not(feature = "rocksdb"))]
+        StorageBackend::RocksDb => Err(HazeError::Config(
+            "storage.backend \"rocksdb\" requires a node built with the rocksdb feature".to_string(),
+        )),
+    }
+}
+
+fn db_error(e: sled::Error) -> HazeError {
+    HazeError::Database(e.to_string())
+}
+
+/// Key prefixes of sled's default tree before named trees, and the trees their entries move to
+const LEGACY_PREFIXES: [(&[u8], &str); 5] = [
+    (b"block_h", BLOCKS_TREE),
+    (b"applied_", INDEXES_TREE),
+    (b"acct_", ACCOUNTS_TREE),
+    (b"dlq_", DEAD_LETTERS_TREE),
+    (b"code_", CONTRACT_CODE_TREE),
+];
+
+/// Sled backend (default)
+#[derive(Debug)]
+pub struct SledStorage {
+    db: sled::Db,
+}
+
+impl SledStorage {
+    /// Open the database at `path`, moving entries under the old key prefixes into their trees
+    pub fn open(path: &Path) -> Result<Self> {
+        let db = sled::open(path)
+            .map_err(|e| HazeError::Database(format!("Failed to open database: {}", e)))?;
+        let storage = Self { db };
+        storage.migrate_legacy_prefixes()?;
+        Ok(storage)
+    }
+
+    /// Database deleted when dropped
+    pub fn temporary() -> Result<Self> {
+        let db = sled::Config::new()
+            .temporary(true)
+            .open()
+            .map_err(|e| HazeError::Database(format!("Failed to open temporary database: {}", e)))?;
+        Ok(Self { db })
+    }
+
+    fn tree(&self, name: &str) -> Result<sled::Tree> {
+        if name == DEFAULT_TREE {
+            Ok((*self.db).clone())
+        } else {
+            self.db.open_tree(name).map_err(db_error)
+        }
+    }
+
+    /// Move prefixed entries of the default tree into their named trees (without the prefix).
+    /// Entries are written to the new tree before they are removed, so an interrupted migration
+    /// resumes on the next start.
+    fn migrate_legacy_prefixes(&self) -> Result<()> {
+        for (prefix, tree_name) in LEGACY_PREFIXES {
+            let mut moved = sled::Batch::default();
+            let mut removed = sled::Batch::default();
+            let mut count = 0usize;
+            for item in self.db.scan_prefix(prefix) {
+                let (key, value) = item.map_err(db_error)?;
+                moved.insert(&key[prefix.len()..], value);
+                removed.remove(key);
+                count += 1;
+            }
+            if count == 0 {
+                continue;
+            }
+            self.tree(tree_name)?.apply_batch(moved).map_err(db_error)?;
+            self.db.apply_batch(removed).map_err(db_error)?;
+            tracing::info!("Migrated {} entries to the {} tree", count, tree_name);
+        }
+        Ok(())
+    }
+}
+
+fn sled_batch(batch: &WriteBatch) -> sled::Batch {
+    let mut sled_batch = sled::Batch::default();
+    for (key, value) in batch.ops() {
+        match value {
+            Some(value) => sled_batch.insert(key, value),
+            None => sled_batch.remove(key),
+        }
+    }
+    sled_batch
+}
+
+impl StorageTree for sled::Tree {
+    fn get(&self, key: &[u8]) -> Result<Option<IVec>> {
+        sled::Tree::get(self, key).map_err(db_error)
+    }
+
+    fn insert(&self, key: &[u8], value: &[u8]) -> Result<()> {
+        sled::Tree::insert(self, key, value).map(|_| ()).map_err(db_error)
+    }
+
+    fn remove(&self, key: &[u8]) -> Result<()> {
+        sled::Tree::remove(self, key).map(|_| ()).map_err(db_error)
+    }
+
+    fn contains_key(&self, key: &[u8]) -> Result<bool> {
+        sled::Tree::contains_key(self, key).map_err(db_error)
+    }
+
+    fn range(&self, from: Bound<&[u8]>, to: Bound<&[u8]>) -> Entries<'_> {
+        let bounds = (from.map(<[u8]>::to_vec), to.map(<[u8]>::to_vec));
+        Box::new(sled::Tree::range(self, bounds).map(|item| item.map_err(db_error)))
+    }
+
+    fn last(&self) -> Result<Option<(IVec, IVec)>> {
+        sled::Tree::last(self).map_err(db_error)
+    }
+
+    fn apply_batch(&self, batch: WriteBatch) -> Result<()> {
+        sled::Tree::apply_batch(self, sled_batch(&batch)).map_err(db_error)
+    }
+
+    fn len(&self) -> usize {
+        sled::Tree::len(self)
+    }
+
+    fn flush(&self) -> Result<()> {
+        sled::Tree::flush(self).map(|_| ()).map_err(db_error)
+    }
+}
+
+impl Storage for SledStorage {
+    fn open_tree(&self, name: &str) -> Result<Arc<dyn StorageTree>> {
+        Ok(Arc::new(self.tree(name)?))
+    }
+
+    fn tree_names(&self) -> Vec<String> {
+        let default_name = self.db.name();
+        self.db
+            .tree_names()
+            .into_iter()
+            .map(|name| {
+                if name == default_name {
+                    DEFAULT_TREE.to_string()
+                } else {
+                    String::from_utf8_lossy(&name).into_owned()
+                }
+            })
+            .collect()
+    }
+
+    fn apply_atomic(&self, batches: &[(&str, &WriteBatch)]) -> Result<()> {
+        let trees = batches
+            .iter()
+            .map(|(name, _)| self.tree(name))
+            .collect::<Result<Vec<_>>>()?;
+        trees
+            .as_slice()
+            .transaction(|trees| -> sled::transaction::ConflictableTransactionResult<(), sled::Error> {
+                for (tree, (_, batch)) in trees.iter().zip(batches) {
+                    tree.apply_batch(&sled_batch(batch))?;
+                }
+                Ok(())
+            })
+            .map_err(|e| HazeError::Database(e.to_string()))
+    }
+
+    fn flush(&self) -> Result<()> {
+        self.db.flush().map(|_| ()).map_err(db_error)
+    }
+
+    fn size_on_disk(&self) -> Result<u64> {
+        self.db.size_on_disk().map_err(db_error)
+    }
+}
+
+#[cfg(feature = "rocksdb")]
+pub use rocks::RocksDbStorage;
+
+#[cfg(feature = "rocksdb")]
+mod rocks {
+    use super::*;
+    use rocksdb::{BoundColumnFamily, Direction, IteratorMode, MultiThreaded, Options};
+
+    type Db = rocksdb::DBWithThreadMode<MultiThreaded>;
+
+    fn rocks_error(e: rocksdb::Error) -> HazeError {
+        HazeError::Database(e.to_string())
+    }
+
+    /// RocksDB backend (`rocksdb` feature); trees are column families
+    #[derive(Debug)]
+    pub struct RocksDbStorage {
+        db: Arc<Db>,
+        path: PathBuf,
+    }
+
+    impl RocksDbStorage {
+        pub fn open(path: &Path) -> Result<Self> {
+            let mut options = Options::default();
+            options.create_if_missing(true);
+            // A database that does not exist yet has no column families to list
+            let names = Db::list_cf(&options, path).unwrap_or_else(|_| vec![DEFAULT_TREE.to_string()]);
+            let db = Db::open_cf(&options, path, names)
+                .map_err(|e| HazeError::Database(format!("Failed to open database: {}", e)))?;
+            Ok(Self { db: Arc::new(db), path: path.to_path_buf() })
+        }
+    }
+
+    #[derive(Debug)]
+    struct RocksTree {
+        db: Arc<Db>,
+        name: String,
+    }
+
+    impl RocksTree {
+        fn cf(&self) -> Result<Arc<BoundColumnFamily<'_>>> {
+            self.db
+                .cf_handle(&self.name)
+                .ok_or_else(|| HazeError::Database(format!("Missing column family '{}'", self.name)))
+        }
+    }
+
+    impl StorageTree for RocksTree {
+        fn get(&self, key: &[u8]) -> Result<Option<IVec>> {
+            Ok(self.db.get_cf(&self.cf()?, key).map_err(rocks_error)?.map(IVec::from))
+        }
+
+        fn insert(&self, key: &[u8], value: &[u8]) -> Result<()> {
+            self.db.put_cf(&self.cf()?, key, value).map_err(rocks_error)
+        }
+
+        fn remove(&self, key: &[u8]) -> Result<()> {
+            self.db.delete_cf(&self.cf()?, key).map_err(rocks_error)
+        }
+
+        fn contains_key(&self, key: &[u8]) -> Result<bool> {
+            Ok(self.get(key)?.is_some())
+        }
+
+        fn range(&self, from: Bound<&[u8]>, to: Bound<&[u8]>) -> Entries<'_> {
+            let cf = match self.cf() {
+                Ok(cf) => cf,
+                Err(e) => return Box::new(std::iter::once(Err(e))),
+            };
+            let mode = match from {
+                Bound::Included(key) | Bound::Excluded(key) => IteratorMode::From(key, Direction::Forward),
+                Bound::Unbounded => IteratorMode::Start,
+            };
+            let excluded = match from {
+                Bound::Excluded(key) => Some(key.to_vec()),
+                _ => None,
+            };
+            let to = to.map(<[u8]>::to_vec);
+            let entries = self.db
+                .iterator_cf(&cf, mode)
+                .skip_while(move |item| matches!((item, &excluded), (Ok((key, _)), Some(start)) if **key == start[..]))
+                .take_while(move |item| match (item, &to) {
+                    (Ok((key, _)), Bound::Included(end)) => **key <= end[..],
+                    (Ok((key, _)), Bound::Excluded(end)) => **key < end[..],
+                    _ => true,
+                })
+                .map(|item| {
+                    item.map(|(key, value)| (IVec::from(key), IVec::from(value)))
+                        .map_err(rocks_error)
+                });
+            Box::new(entries)
+        }
+
+        fn last(&self) -> Result<Option<(IVec, IVec)>> {
+            match self.db.iterator_cf(&self.cf()?, IteratorMode::End).next() {
+                Some(item) => {
+                    let (key, value) = item.map_err(rocks_error)?;
+                    Ok(Some((IVec::from(key), IVec::from(value))))
+                }
+                None => Ok(None),
+            }
+        }
+
+        fn apply_batch(&self, batch: WriteBatch) -> Result<()> {
+            let cf = self.cf()?;
+            let mut rocks_batch = rocksdb::WriteBatch::default();
+            for (key, value) in batch.ops() {
+                match value {
+                    Some(value) => rocks_batch.put_cf(&cf, key, value),
+                    None => rocks_batch.delete_cf(&cf, key),
+                }
+            }
+            self.db.write(rocks_batch).map_err(rocks_error)
+        }
+
+        /// RocksDB's `estimate-num-keys` (exact counting would read the whole column family)
+        fn len(&self) -> usize {
+            self.cf()
+                .ok()
+                .and_then(|cf| self.db.property_int_value_cf(&cf, rocksdb::properties::ESTIMATE_NUM_KEYS).ok().flatten())
+                .map_or(0, |keys| keys as usize)
+        }
+
+        /// Exact, unlike `len`: the estimate can stay above zero after deletes
+        fn is_empty(&self) -> bool {
+            self.iter().next().is_none()
+        }
+
+        fn flush(&self) -> Result<()> {
+            self.db.flush_cf(&self.cf()?).map_err(rocks_error)
+        }
+    }
+
+    impl Storage for RocksDbStorage {
+        fn open_tree(&self, name: &str) -> Result<Arc<dyn StorageTree>> {
+            if self.db.cf_handle(name).is_none() {
+                self.db.create_cf(name, &Options::default()).map_err(rocks_error)?;
+            }
+            Ok(Arc::new(RocksTree { db: self.db.clone(), name: name.to_string() }))
+        }
+
+        fn tree_names(&self) -> Vec<String> {
+            Db::list_cf(&Options::default(), &self.path).unwrap_or_default()
+        }
+
+        /// One write batch across column families is atomic in RocksDB
+        fn apply_atomic(&self, batches: &[(&str, &WriteBatch)]) -> Result<()> {
+            let mut rocks_batch = rocksdb::WriteBatch::default();
+            for (name, batch) in batches {
+                self.open_tree(name)?;
+                let cf = self.db
+                    .cf_handle(name)
+                    .ok_or_else(|| HazeError::Database(format!("Missing column family '{}'", name)))?;
+                for (key, value) in batch.ops() {
+                    match value {
+                        Some(value) => rocks_batch.put_cf(&cf, key, value),
+                        None => rocks_batch.delete_cf(&cf, key),
+                    }
+                }
+            }
+            self.db.write(rocks_batch).map_err(rocks_error)
+        }
+
+        fn flush(&self) -> Result<()> {
+            for name in self.tree_names() {
+                self.open_tree(&name)?.flush()?;
+            }
+            Ok(())
+        }
+
+        fn size_on_disk(&self) -> Result<u64> {
+            let mut size = 0;
+            for entry in std::fs::read_dir(&self.path)? {
+                let metadata = entry?.metadata()?;
+                if metadata.is_file() {
+                    size += metadata.len();
+                }
+            }
+            Ok(size)
+        }
+    }
+}
+
+/// The chain data trees of the node database
+#[derive(Debug, Clone)]
+pub struct DbTrees {
+    pub blocks: Arc<dyn StorageTree>,
+    pub block_checksums: Arc<dyn StorageTree>,
+    pub indexes: Arc<dyn StorageTree>,
+    pub accounts: Arc<dyn StorageTree>,
+    pub assets: Arc<dyn StorageTree>,
+    pub dead_letters: Arc<dyn StorageTree>,
+    pub contract_code: Arc<dyn StorageTree>,
+    pub receipts: Arc<dyn StorageTree>,
+    pub block_logs: Arc<dyn StorageTree>,
+    pub tx_locations: Arc<dyn StorageTree>,
+}
+
+impl DbTrees {
+    pub fn open(storage: &dyn Storage) -> Result<Self> {
+        Ok(Self {
+            blocks: storage.open_tree(BLOCKS_TREE)?,
+            block_checksums: storage.open_tree(BLOCK_CHECKSUMS_TREE)?,
+            indexes: storage.open_tree(INDEXES_TREE)?,
+            accounts: storage.open_tree(ACCOUNTS_TREE)?,
+            assets: storage.open_tree(ASSETS_TREE)?,
+            dead_letters: storage.open_tree(DEAD_LETTERS_TREE)?,
+            contract_code: storage.open_tree(CONTRACT_CODE_TREE)?,
+            receipts: storage.open_tree(RECEIPTS_TREE)?,
+            block_logs: storage.open_tree(BLOCK_LOGS_TREE)?,
+            tx_locations: storage.open_tree(TX_LOCATIONS_TREE)?,
+        })
+    }
+}
+
+/// Size of one tree at the last maintenance run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeStats {
+    pub name: String,
+    pub entries: u64,
+    /// Sum of key and value lengths (logical size, before the engine's own overhead)
+    pub bytes: u64,
+}
+
+/// Database sizes and flush timings
+#[derive(Debug, Clone, Default)]
+pub struct StorageStats {
+    /// Size of the database files on disk
+    pub size_on_disk: u64,
+    /// Per-tree sizes, by tree name
+    pub trees: Vec<TreeStats>,
+    /// Duration of the last scheduled flush
+    pub last_flush_seconds: f64,
+    /// Scheduled flushes since node start
+    pub flushes: u64,
+    /// Unix timestamp of the last maintenance run (0 before the first)
+    pub last_run: i64,
+}
+
+/// Scheduled maintenance of the node database
+#[derive(Debug)]
+pub struct StorageMaintenance {
+    storage: Arc<dyn Storage>,
+    stats: RwLock<StorageStats>,
+    flushes: AtomicU64,
+}
+
+impl StorageMaintenance {
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
+        Self {
+            storage,
+            stats: RwLock::new(StorageStats::default()),
+            flushes: AtomicU64::new(0),
+        }
+    }
+
+    /// Flush the database, then measure the size of every tree. Blocking: iterates all trees.
+    pub fn run(&self) -> Result<StorageStats> {
+        let started = Instant::now();
+        self.storage.flush()?;
+        let last_flush_seconds = started.elapsed().as_secs_f64();
+        let flushes = self.flushes.fetch_add(1, Ordering::Relaxed) + 1;
+
+        let mut trees = Vec::new();
+        for name in self.storage.tree_names() {
+            let tree = self.storage.open_tree(&name)?;
+            let (mut entries, mut bytes) = (0u64, 0u64);
+            for item in tree.iter() {
+                let (key, value) = item?;
+                entries += 1;
+                bytes += (key.len() + value.len()) as u64;
+            }
+            trees.push(TreeStats { name, entries, bytes });
+        }
+        trees.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let stats = StorageStats {
+            size_on_disk: self.storage.size_on_disk()?,
+            trees,
+            last_flush_seconds,
+            flushes,
+            last_run: chrono::Utc::now().timestamp(),
+        };
+        *self.stats.write() = stats.clone();
+        Ok(stats)
+    }
+
+    /// Stats of the last run
+    pub fn stats(&self) -> StorageStats {
+        self.stats.read().clone()
+    }
+}
+
+/// Copy every tree of `from` into `to`, which must hold no entries. Returns entries copied.
+pub fn copy_storage(from: &dyn Storage, to: &dyn Storage) -> Result<u64> {
+    for name in to.tree_names() {
+        if !to.open_tree(&name)?.is_empty() {
+            return Err(HazeError::Database(format!("Destination tree '{}' is not empty", name)));
+        }
+    }
+    let mut copied = 0u64;
+    for name in from.tree_names() {
+        let (source, target) = (from.open_tree(&name)?, to.open_tree(&name)?);
+        let mut batch = WriteBatch::default();
+        for item in source.iter() {
+            let (key, value) = item?;
+            batch.insert(key, value.to_vec());
+            if batch.len() == COPY_BATCH_SIZE {
+                copied += batch.len() as u64;
+                target.apply_batch(std::mem::take(&mut batch))?;
+            }
+        }
+        if !batch.is_empty() {
+            copied += batch.len() as u64;
+            target.apply_batch(batch)?;
+        }
+    }
+    to.flush()?;
+    Ok(copied)
+}
+
+/// Record `chain_id` in a database that has none yet, or check that the database was created
+/// for `chain_id`. A node started with the wrong
+/// `chain_id` would otherwise reject its own chain's transactions.
+pub fn bind_chain_id(storage: &dyn Storage, chain_id: u64) -> Result<()> {
+    let meta = storage.open_tree(META_TREE)?;
+    if let Some(value) = meta.get(CHAIN_ID_KEY)? {
+        let bytes: [u8; 8] = value.as_ref().try_into()
+            .map_err(|_| HazeError::Corruption("Malformed chain id".to_string()))?;
+        let stored = u64::from_be_bytes(bytes);
+        if stored != chain_id {
+            return Err(HazeError::Config(format!(
+                "Database was created for chain_id {}, but the node is configured with chain_id {}",
+                stored, chain_id
+            )));
+        }
+        return Ok(());
+    }
+    meta.insert(CHAIN_ID_KEY, &chain_id.to_be_bytes())?;
+    storage.flush()
+}
+
+/// `haze migrate-storage`: copy a node database to another path and/or backend
+pub fn run_migrate_cli(args: &[String]) -> Result<()> {
+    let usage = "usage: haze migrate-storage --from <db_path> --to <db_path> [--from-backend sled|rocksdb] [--to-backend sled|rocksdb]";
+    let mut from: Option<PathBuf> = None;
+    let mut to: Option<PathBuf> = None;
+    let mut from_backend = StorageBackend::Sled;
+    let mut to_backend = StorageBackend::Sled;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().cloned()
+            .ok_or_else(|| HazeError::Config(format!("Missing value for {}", arg)));
+        match arg.as_str() {
+            "--from" => from = Some(PathBuf::from(value()?)),
+            "--to" => to = Some(PathBuf::from(value()?)),
+            "--from-backend" => from_backend = value()?.parse()?,
+            "--to-backend" => to_backend = value()?.parse()?,
+            other => return Err(HazeError::Config(format!("Unknown argument '{}' ({})", other, usage))),
+        }
+    }
+    let (Some(from), Some(to)) = (from, to) else {
+        return Err(HazeError::Config(format!("--from and --to are required ({})", usage)));
+    };
+    if from == to {
+        return Err(HazeError::Config("--from and --to must be different paths".to_string()));
+    }
+    let source = open_backend(from_backend, &from)?;
+    let target = open_backend(to_backend, &to)?;
+    let copied = copy_storage(source.as_ref(), target.as_ref())?;
+    println!(
+        "Copied {} entries from {} ({}) to {} ({})",
+        copied,
+        from.display(),
+        from_backend,
+        to.display(),
+        to_backend
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legacy_prefixes_move_to_named_trees() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let db = sled::open(dir.path()).unwrap();
+            let mut block_key = b"block_h".to_vec();
+            block_key.extend_from_slice(&7u64.to_be_bytes());
+            db.insert(&block_key, b"block".as_slice()).unwrap();
+            db.insert(b"applied_hash", b"height".as_slice()).unwrap();
+            db.insert(b"unrelated", b"kept".as_slice()).unwrap();
+        }
+
+        let storage: Arc<dyn Storage> = Arc::new(SledStorage::open(dir.path()).unwrap());
+        let trees = DbTrees::open(storage.as_ref()).unwrap();
+        assert_eq!(trees.blocks.get(&7u64.to_be_bytes()).unwrap().unwrap(), b"block".as_slice());
+        assert_eq!(trees.indexes.get(b"hash").unwrap().unwrap(), b"height".as_slice());
+        let default = storage.open_tree(DEFAULT_TREE).unwrap();
+        assert_eq!(default.iter().count(), 1);
+
+        let maintenance = StorageMaintenance::new(storage.clone());
+        let stats = maintenance.run().unwrap();
+        let blocks = stats.trees.iter().find(|t| t.name == BLOCKS_TREE).unwrap();
+        assert_eq!((blocks.entries, blocks.bytes), (1, 8 + 5));
+        assert_eq!(stats.trees.iter().find(|t| t.name == DEFAULT_TREE).unwrap().entries, 1);
+        assert_eq!(stats.flushes, 1);
+        assert_eq!(maintenance.stats().trees, stats.trees);
+    }
+
+    #[test]
+    fn test_atomic_writes_and_copy_between_databases() {
+        let source = SledStorage::temporary().unwrap();
+        let mut blocks = WriteBatch::default();
+        blocks.insert(1u64.to_be_bytes(), b"one".to_vec());
+        blocks.insert(2u64.to_be_bytes(), b"two".to_vec());
+        let mut indexes = WriteBatch::default();
+        indexes.insert(b"hash", 2u64.to_be_bytes().to_vec());
+        source.apply_atomic(&[(BLOCKS_TREE, &blocks), (INDEXES_TREE, &indexes)]).unwrap();
+        let tree = source.open_tree(BLOCKS_TREE).unwrap();
+        let after_first: Vec<IVec> = tree
+            .range(Bound::Excluded(&1u64.to_be_bytes()[..]), Bound::Unbounded)
+            .map(|item| item.unwrap().1)
+            .collect();
+        assert_eq!(after_first, vec![IVec::from(b"two")]);
+
+        let target = SledStorage::temporary().unwrap();
+        assert_eq!(copy_storage(&source, &target).unwrap(), 3);
+        assert_eq!(target.open_tree(BLOCKS_TREE).unwrap().len(), 2);
+        assert!(target.open_tree(INDEXES_TREE).unwrap().contains_key(b"hash").unwrap());
+        // A second copy would mix databases
+        assert!(copy_storage(&source, &target).is_err());
+    }
+
+    #[test]
+    fn test_database_is_bound_to_its_chain_id() {
+        let storage = SledStorage::temporary().unwrap();
+        bind_chain_id(&storage, 7).unwrap();
+        bind_chain_id(&storage, 7).unwrap();
+        assert!(matches!(bind_chain_id(&storage, 8), Err(HazeError::Config(_))));
+    }
+
+    #[cfg(not(feature = "rocksdb"))]
+    #[test]
+    fn test_rocksdb_backend_requires_feature() {
+        assert!(open_backend(StorageBackend::RocksDb, Path::new("unused")).is_err());
+    }
+
+    #[cfg(feature = "rocksdb")]
+    #[test]
+    fn test_rocksdb_backend_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = SledStorage::temporary().unwrap();
+        let mut blocks = WriteBatch::default();
+        for height in 1u64..=3 {
+            blocks.insert(height.to_be_bytes(), vec![height as u8]);
+        }
+        source.apply_atomic(&[(BLOCKS_TREE, &blocks)]).unwrap();
+
+        let target = open_backend(StorageBackend::RocksDb, dir.path()).unwrap();
+        assert_eq!(copy_storage(&source, target.as_ref()).unwrap(), 3);
+        let tree = target.open_tree(BLOCKS_TREE).unwrap();
+        let middle: Vec<IVec> = tree
+            .range(Bound::Excluded(&1u64.to_be_bytes()[..]), Bound::Excluded(&3u64.to_be_bytes()[..]))
+            .map(|item| item.unwrap().1)
+            .collect();
+        assert_eq!(middle, vec![IVec::from(&[2u8][..])]);
+        assert!(target.tree_names().contains(&BLOCKS_TREE.to_string()));
+        assert_eq!(tree.len(), 3);
+
+        let mut removals = WriteBatch::default();
+        for height in 1u64..=3 {
+            removals.remove(height.to_be_bytes());
+        }
+        tree.apply_batch(removals).unwrap();
+        assert!(tree.is_empty());
+    }
+}
@@ -0,0 +1,211 @@
+//! Opt-in node telemetry for a public network map
+//!
+//! Nothing is reported unless `telemetry.enabled` is set. A reporting node posts a
+//! `TelemetryReport` (version, height, peer count, median peer round trip) to
+//! `telemetry.endpoint` every `telemetry.interval_secs`; it carries no keys, addresses or
+//! transaction data. A node with `telemetry.aggregator` set is the dashboard side: it accepts
+//! reports of its own chain at `POST /api/v1/telemetry` and lists the nodes heard from within
+//! `telemetry.aggregator_ttl_secs` at `GET /api/v1/telemetry/nodes`.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use crate::config::{Config, TelemetryConfig};
+use crate::consensus::ConsensusEngine;
+use crate::error::{HazeError, Result};
+use crate::state::StateManager;
+
+/// Timeout of one report POST
+const REPORT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Longest node id, name or region an aggregator accepts
+pub const MAX_TELEMETRY_FIELD_LEN: usize = 64;
+
+/// What a node reports about itself
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TelemetryReport {
+    pub node_id: String,
+    /// Display name on the map (`telemetry.node_name`, or the node id)
+    pub name: String,
+    /// Operator-declared location (`telemetry.region`), e.g. `eu-west`
+    pub region: Option<String>,
+    /// Node software version
+    pub version: String,
+    pub chain_id: u64,
+    pub height: u64,
+    pub finalized_height: u64,
+    pub peers: usize,
+    /// Median round trip to peers (ms), `None` before any peer was measured
+    pub latency_ms: Option<i64>,
+    pub replica: bool,
+    /// Unix seconds on the reporting node
+    pub sent_at: i64,
+}
+
+impl TelemetryReport {
+    /// Current report of this node
+    pub fn collect(config: &Config, state: &StateManager, consensus: &ConsensusEngine, peers: usize) -> Self {
+        Self {
+            node_id: config.node_id.clone(),
+            name: config.telemetry.node_name.clone().unwrap_or_else(|| config.node_id.clone()),
+            region: config.telemetry.region.clone(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            chain_id: config.chain_id,
+            height: state.current_height(),
+            finalized_height: consensus.get_last_finalized_height(),
+            peers,
+            latency_ms: consensus.clock_status().median_rtt_ms,
+            replica: config.replica.enabled,
+            sent_at: chrono::Utc::now().timestamp(),
+        }
+    }
+}
+
+/// Latest report of a node, as seen by the aggregator
+#[derive(Debug, Clone, Serialize)]
+pub struct TelemetryNode {
+    #[serde(flatten)]
+    pub report: TelemetryReport,
+    /// Unix seconds the aggregator received the report
+    pub received_at: i64,
+}
+
+/// Latest report per node, kept in memory by an aggregator
+#[derive(Debug)]
+pub struct TelemetryAggregator {
+    nodes: RwLock<HashMap<String, TelemetryNode>>,
+    chain_id: u64,
+    ttl_secs: i64,
+    max_nodes: usize,
+}
+
+impl TelemetryAggregator {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            nodes: RwLock::new(HashMap::new()),
+            chain_id: config.chain_id,
+            ttl_secs: config.telemetry.aggregator_ttl_secs as i64,
+            max_nodes: config.telemetry.aggregator_max_nodes,
+        }
+    }
+
+    /// Store a node's report, replacing its previous one
+    pub fn record(&self, report: TelemetryReport, now: i64) -> Result<()> {
+        let invalid = |detail: String| Err(HazeError::InvalidTransaction(detail));
+        if report.chain_id != self.chain_id {
+            return invalid(format!("Report is for chain {}, not {}", report.chain_id, self.chain_id));
+        }
+        let fields = [Some(&report.node_id), Some(&report.name), report.region.as_ref()];
+        if report.node_id.is_empty() || fields.into_iter().flatten().any(|field| field.len() > MAX_TELEMETRY_FIELD_LEN) {
+            return invalid(format!("Node id must be non-empty; id, name and region at most {} bytes", MAX_TELEMETRY_FIELD_LEN));
+        }
+        if report.version.len() > MAX_TELEMETRY_FIELD_LEN {
+            return invalid(format!("Version must be at most {} bytes", MAX_TELEMETRY_FIELD_LEN));
+        }
+        let mut nodes = self.nodes.write();
+        nodes.retain(|_, node| now - node.received_at <= self.ttl_secs);
+        if !nodes.contains_key(&report.node_id) && nodes.len() >= self.max_nodes {
+            return invalid(format!("Aggregator already tracks {} nodes", self.max_nodes));
+        }
+        nodes.insert(report.node_id.clone(), TelemetryNode { report, received_at: now });
+        Ok(())
+    }
+
+    /// Nodes heard from within the TTL, by node id
+    pub fn nodes(&self, now: i64) -> Vec<TelemetryNode> {
+        let mut nodes: Vec<TelemetryNode> = self.nodes
+            .read()
+            .values()
+            .filter(|node| now - node.received_at <= self.ttl_secs)
+            .cloned()
+            .collect();
+        nodes.sort_by(|a, b| a.report.node_id.cmp(&b.report.node_id));
+        nodes
+    }
+}
+
+/// Post this node's report to `telemetry.endpoint` every `telemetry.interval_secs`
+pub async fn run_reporter(
+    config: Config,
+    state: Arc<StateManager>,
+    consensus: Arc<ConsensusEngine>,
+    connected_peers: Arc<AtomicUsize>,
+) {
+    let TelemetryConfig { endpoint: Some(endpoint), interval_secs, .. } = config.telemetry.clone() else {
+        return;
+    };
+    let client = match reqwest::Client::builder().timeout(REPORT_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => {
+            tracing::error!("Telemetry disabled: {}", e);
+            return;
+        }
+    };
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    loop {
+        interval.tick().await;
+        let report = TelemetryReport::collect(&config, &state, &consensus, connected_peers.load(Ordering::Relaxed));
+        let body = match serde_json::to_vec(&report) {
+            Ok(body) => body,
+            Err(_) => continue,
+        };
+        let request = client
+            .post(&endpoint)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body);
+        match request.send().await.and_then(|r| r.error_for_status()) {
+            Ok(_) => tracing::debug!("Telemetry reported at height {}", report.height),
+            Err(e) => tracing::warn!("Telemetry report to {} failed: {}", endpoint, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(node_id: &str, chain_id: u64) -> TelemetryReport {
+        TelemetryReport {
+            node_id: node_id.to_string(),
+            name: node_id.to_string(),
+            region: Some("eu-west".to_string()),
+            version: "0.1.0".to_string(),
+            chain_id,
+            height: 10,
+            finalized_height: 8,
+            peers: 3,
+            latency_ms: Some(40),
+            replica: false,
+            sent_at: 1_000,
+        }
+    }
+
+    #[test]
+    fn test_aggregator_keeps_latest_report_per_node() {
+        let mut config = Config::default();
+        config.telemetry.aggregator_ttl_secs = 100;
+        config.telemetry.aggregator_max_nodes = 2;
+        let aggregator = TelemetryAggregator::new(&config);
+
+        aggregator.record(report("a", 0), 1_000).unwrap();
+        aggregator.record(TelemetryReport { height: 11, ..report("a", 0) }, 1_010).unwrap();
+        aggregator.record(report("b", 0), 1_020).unwrap();
+        let nodes = aggregator.nodes(1_050);
+        assert_eq!(nodes.iter().map(|n| n.report.node_id.as_str()).collect::<Vec<_>>(), ["a", "b"]);
+        assert_eq!(nodes[0].report.height, 11);
+
+        // Other chains, oversized fields and nodes beyond the cap are rejected
+        assert!(aggregator.record(report("c", 1), 1_050).is_err());
+        assert!(aggregator.record(report(&"x".repeat(MAX_TELEMETRY_FIELD_LEN + 1), 0), 1_050).is_err());
+        assert!(aggregator.record(report("c", 0), 1_050).is_err());
+
+        // Silent nodes expire and make room
+        assert_eq!(aggregator.nodes(1_115).len(), 1);
+        aggregator.record(report("c", 0), 1_115).unwrap();
+        assert_eq!(aggregator.nodes(1_115).iter().map(|n| n.report.node_id.as_str()).collect::<Vec<_>>(), ["b", "c"]);
+    }
+}
@@ -0,0 +1,398 @@
+//! Multi-tenant node mode
+//!
+//! An infrastructure provider can run one node for many studios. Each tenant gets:
+//! - its own API keys (`x-api-key`), issued by the admin endpoints and stored as hashes;
+//! - a collection namespace: game_ids starting with `<tenant id>/`. Transactions a tenant
+//!   submits may only touch collections in its namespace;
+//! - quotas on collections, assets and watchlists;
+//! - watchlists, webhooks and WebSocket event streams limited to its own collections;
+//! - usage counters for billing (kept in memory since node start).
+//!
+//! Tenants are persisted in the node database's `tenants` tree.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use parking_lot::RwLock;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use crate::config::{TenantQuota, TenantsConfig};
+use crate::error::{HazeError, Result};
+use crate::state::StateManager;
+use crate::storage::StorageTree;
+use crate::types::{AssetAction, Transaction};
+use crate::watchlist::hash_api_key;
+
+/// Maximum length of a tenant id
+pub const MAX_TENANT_ID_LEN: usize = 32;
+/// Most API keys one tenant may hold
+pub const MAX_KEYS_PER_TENANT: usize = 20;
+/// Separator between a tenant id and the rest of a namespaced game_id
+pub const NAMESPACE_SEPARATOR: char = '/';
+
+/// A studio served by the node
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tenant {
+    /// Lowercase letters, digits and dashes; also the collection namespace
+    pub id: String,
+    pub name: String,
+    /// Hex SHA-256 hashes of the tenant's API keys (the keys themselves are never stored)
+    pub key_hashes: Vec<String>,
+    pub quota: TenantQuota,
+    pub created_at: i64,
+}
+
+/// Fields of a new tenant
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewTenant {
+    pub id: String,
+    pub name: String,
+    /// Defaults to `tenants.default_quota`
+    pub quota: Option<TenantQuota>,
+}
+
+impl Tenant {
+    /// Prefix of the tenant's game_ids (`<id>/`)
+    pub fn namespace(&self) -> String {
+        format!("{}{}", self.id, NAMESPACE_SEPARATOR)
+    }
+
+    /// Whether `game_id` is in the tenant's namespace
+    pub fn owns_collection(&self, game_id: &str) -> bool {
+        in_namespace(&self.id, game_id)
+    }
+
+    /// Owner key of the tenant's watchlists, shared by all its API keys
+    pub fn watchlist_owner(&self) -> String {
+        tenant_watchlist_owner(&self.id)
+    }
+}
+
+/// Whether `game_id` is in the namespace of tenant `tenant_id`
+pub fn in_namespace(tenant_id: &str, game_id: &str) -> bool {
+    game_id
+        .strip_prefix(tenant_id)
+        .and_then(|rest| rest.strip_prefix(NAMESPACE_SEPARATOR))
+        .is_some_and(|name| !name.is_empty())
+}
+
+/// Watchlist owner key of tenant `id`
+pub fn tenant_watchlist_owner(id: &str) -> String {
+    format!("tenant:{}", id)
+}
+
+/// Tenant id of a watchlist owner key, if the watchlist belongs to a tenant
+pub fn watchlist_tenant(owner_key: &str) -> Option<&str> {
+    owner_key.strip_prefix("tenant:")
+}
+
+/// Usage counters of one tenant since node start
+#[derive(Debug, Default)]
+pub struct TenantUsage {
+    /// API requests made with the tenant's keys
+    pub requests: AtomicU64,
+    /// Transactions submitted
+    pub transactions: AtomicU64,
+    /// Asset Create transactions submitted
+    pub assets_created: AtomicU64,
+    /// WebSocket connections opened
+    pub ws_connections: AtomicU64,
+}
+
+/// Persisted tenants plus their usage counters
+#[derive(Debug)]
+pub struct TenantStore {
+    tree: Arc<dyn StorageTree>,
+    tenants: RwLock<Vec<Tenant>>,
+    admin_keys: HashSet<String>,
+    default_quota: TenantQuota,
+    usage: RwLock<HashMap<String, Arc<TenantUsage>>>,
+}
+
+impl TenantStore {
+    /// Open the store in the node database's `tenants` tree
+    pub fn open(config: &TenantsConfig, state: &StateManager) -> Result<Self> {
+        let tree = state.open_tree("tenants")?;
+        let mut tenants = Vec::new();
+        for item in tree.iter() {
+            let (_, value) = item?;
+            match bincode::deserialize::<Tenant>(&value) {
+                Ok(tenant) => tenants.push(tenant),
+                Err(e) => tracing::warn!("Skipping unreadable tenant: {}", e),
+            }
+        }
+        tenants.sort_by_key(|t| t.created_at);
+        Ok(Self {
+            tree,
+            tenants: RwLock::new(tenants),
+            admin_keys: config.admin_key_hashes.iter().map(|h| h.to_lowercase()).collect(),
+            default_quota: config.default_quota,
+            usage: RwLock::new(HashMap::new()),
+        })
+    }
+
+    /// Whether `api_key` may use the admin endpoints
+    pub fn is_admin(&self, api_key: &str) -> bool {
+        self.admin_keys.contains(&hash_api_key(api_key))
+    }
+
+    /// Create a tenant; returns it with its first API key (shown only once)
+    pub fn create(&self, new: NewTenant) -> Result<(Tenant, String)> {
+        let valid_id = !new.id.is_empty()
+            && new.id.len() <= MAX_TENANT_ID_LEN
+            && new.id.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+        if !valid_id {
+            return Err(HazeError::InvalidTransaction(format!(
+                "Tenant id must be 1-{} lowercase letters, digits or dashes", MAX_TENANT_ID_LEN
+            )));
+        }
+        if new.name.trim().is_empty() {
+            return Err(HazeError::InvalidTransaction("Tenant name must not be empty".to_string()));
+        }
+
+        let mut tenants = self.tenants.write();
+        if tenants.iter().any(|t| t.id == new.id) {
+            return Err(HazeError::InvalidTransaction(format!("Tenant '{}' already exists", new.id)));
+        }
+        let api_key = generate_api_key();
+        let tenant = Tenant {
+            id: new.id,
+            name: new.name,
+            key_hashes: vec![hash_api_key(&api_key)],
+            quota: new.quota.unwrap_or(self.default_quota),
+            created_at: chrono::Utc::now().timestamp(),
+        };
+        self.persist(&tenant)?;
+        tenants.push(tenant.clone());
+        Ok((tenant, api_key))
+    }
+
+    /// All tenants, oldest first
+    pub fn list(&self) -> Vec<Tenant> {
+        self.tenants.read().clone()
+    }
+
+    pub fn get(&self, id: &str) -> Option<Tenant> {
+        self.tenants.read().iter().find(|t| t.id == id).cloned()
+    }
+
+    /// Delete a tenant and its keys; returns false if there is no such tenant.
+    /// Its collections stay on chain.
+    pub fn delete(&self, id: &str) -> Result<bool> {
+        let mut tenants = self.tenants.write();
+        let Some(index) = tenants.iter().position(|t| t.id == id) else {
+            return Ok(false);
+        };
+        self.tree.remove(id.as_bytes())?;
+        tenants.remove(index);
+        self.usage.write().remove(id);
+        Ok(true)
+    }
+
+    /// Replace a tenant's quota
+    pub fn set_quota(&self, id: &str, quota: TenantQuota) -> Result<Option<Tenant>> {
+        self.update(id, |tenant| {
+            tenant.quota = quota;
+            Ok(())
+        })
+    }
+
+    /// Issue another API key for a tenant; returns the key (shown only once)
+    pub fn issue_key(&self, id: &str) -> Result<Option<String>> {
+        let api_key = generate_api_key();
+        let updated = self.update(id, |tenant| {
+            if tenant.key_hashes.len() >= MAX_KEYS_PER_TENANT {
+                return Err(HazeError::InvalidTransaction(format!(
+                    "At most {} API keys per tenant", MAX_KEYS_PER_TENANT
+                )));
+            }
+            tenant.key_hashes.push(hash_api_key(&api_key));
+            Ok(())
+        })?;
+        Ok(updated.map(|_| api_key))
+    }
+
+    /// Revoke a tenant's API key by its hash; returns false if the tenant or key is unknown
+    pub fn revoke_key(&self, id: &str, key_hash: &str) -> Result<bool> {
+        let key_hash = key_hash.to_lowercase();
+        let mut found = false;
+        self.update(id, |tenant| {
+            let before = tenant.key_hashes.len();
+            tenant.key_hashes.retain(|hash| *hash != key_hash);
+            found = tenant.key_hashes.len() < before;
+            Ok(())
+        })?;
+        Ok(found)
+    }
+
+    /// Tenant owning `api_key`
+    pub fn tenant_for_key(&self, api_key: &str) -> Option<Tenant> {
+        let key_hash = hash_api_key(api_key);
+        self.tenants.read().iter().find(|t| t.key_hashes.contains(&key_hash)).cloned()
+    }
+
+    /// Usage counters of tenant `id`
+    pub fn usage(&self, id: &str) -> Arc<TenantUsage> {
+        if let Some(usage) = self.usage.read().get(id) {
+            return usage.clone();
+        }
+        self.usage.write().entry(id.to_string()).or_default().clone()
+    }
+
+    /// Check a transaction submitted by `tenant` against its namespace and quotas, and
+    /// count it. Transactions naming a collection (asset actions, hooks, order books) must
+    /// stay in the tenant's namespace; asset actions must name a collection.
+    pub fn authorize_transaction(&self, tenant: &Tenant, state: &StateManager, tx: &Transaction) -> Result<()> {
+        let collection = match tx {
+            Transaction::MistbornAsset { asset_id, data, .. } => Some(
+                state.get_asset(asset_id).and_then(|asset| asset.data.game_id).or_else(|| data.game_id.clone())
+            ),
+            Transaction::SetAssetPermissions { asset_id, .. } => {
+                Some(state.get_asset(asset_id).and_then(|asset| asset.data.game_id))
+            }
+            Transaction::SetAssetHook { game_id, .. } | Transaction::PlaceOrder { game_id, .. } => {
+                Some(Some(game_id.clone()))
+            }
+            _ => None,
+        };
+        if let Some(game_id) = collection {
+            let game_id = game_id.filter(|game_id| tenant.owns_collection(game_id)).ok_or_else(|| {
+                HazeError::AccessDenied(format!(
+                    "Tenant '{}' may only use collections named '{}<name>'", tenant.id, tenant.namespace()
+                ))
+            })?;
+            if matches!(tx, Transaction::MistbornAsset { action: AssetAction::Create, .. }) {
+                self.check_asset_quota(tenant, state, &game_id)?;
+            }
+        }
+
+        let usage = self.usage(&tenant.id);
+        usage.transactions.fetch_add(1, Ordering::Relaxed);
+        if matches!(tx, Transaction::MistbornAsset { action: AssetAction::Create, .. }) {
+            usage.assets_created.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    /// Collections of `tenant` with their asset counts
+    pub fn collections(&self, tenant: &Tenant, state: &StateManager) -> Vec<(String, usize)> {
+        state.collections_with_prefix(&tenant.namespace())
+    }
+
+    fn check_asset_quota(&self, tenant: &Tenant, state: &StateManager, game_id: &str) -> Result<()> {
+        let collections = self.collections(tenant, state);
+        let assets: usize = collections.iter().map(|(_, count)| count).sum();
+        if assets >= tenant.quota.max_assets {
+            return Err(HazeError::AccessDenied(format!(
+                "Tenant '{}' reached its quota of {} assets", tenant.id, tenant.quota.max_assets
+            )));
+        }
+        let new_collection = !collections.iter().any(|(name, _)| name == game_id);
+        if new_collection && collections.len() >= tenant.quota.max_collections {
+            return Err(HazeError::AccessDenied(format!(
+                "Tenant '{}' reached its quota of {} collections", tenant.id, tenant.quota.max_collections
+            )));
+        }
+        Ok(())
+    }
+
+    /// Apply `change` to tenant `id` and persist it; `None` if there is no such tenant
+    fn update(&self, id: &str, change: impl FnOnce(&mut Tenant) -> Result<()>) -> Result<Option<Tenant>> {
+        let mut tenants = self.tenants.write();
+        let Some(tenant) = tenants.iter_mut().find(|t| t.id == id) else {
+            return Ok(None);
+        };
+        let mut updated = tenant.clone();
+        change(&mut updated)?;
+        self.persist(&updated)?;
+        *tenant = updated.clone();
+        Ok(Some(updated))
+    }
+
+    fn persist(&self, tenant: &Tenant) -> Result<()> {
+        let value = bincode::serialize(tenant)
+            .map_err(|e| HazeError::Serialization(e.to_string()))?;
+        self.tree.insert(tenant.id.as_bytes(), &value)?;
+        Ok(())
+    }
+}
+
+/// Random API key (`hz_` followed by 48 hex digits)
+fn generate_api_key() -> String {
+    let mut bytes = [0u8; 24];
+    OsRng.fill_bytes(&mut bytes);
+    format!("hz_{}", hex::encode(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::types::{AssetData, DensityLevel};
+
+    fn create_tx(game_id: &str, name: &[u8], nonce: u64) -> Transaction {
+        Transaction::MistbornAsset {
+            from: [1u8; 32],
+            action: AssetAction::Create,
+            asset_id: crate::types::sha256(name),
+            data: AssetData {
+                density: DensityLevel::Ethereal,
+                metadata: HashMap::new(),
+                attributes: vec![],
+                game_id: Some(game_id.to_string()),
+                owner: [1u8; 32],
+            },
+            fee: 0,
+            nonce,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![1; 64],
+        }
+    }
+
+    #[test]
+    fn test_tenant_keys_namespaces_and_quotas() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage.db_path = dir.path().join("db");
+        config.tenants.admin_key_hashes = vec![hash_api_key("admin")];
+        config.tenants.default_quota = TenantQuota { max_collections: 1, max_assets: 2, max_watchlists: 1 };
+        let state = StateManager::new(&config).unwrap();
+        let store = TenantStore::open(&config.tenants, &state).unwrap();
+        assert!(store.is_admin("admin"));
+        assert!(!store.is_admin("studio"));
+
+        let (tenant, key) = store.create(NewTenant { id: "acme".to_string(), name: "Acme".to_string(), quota: None }).unwrap();
+        assert!(store.create(NewTenant { id: "acme".to_string(), name: "Again".to_string(), quota: None }).is_err());
+        assert!(store.create(NewTenant { id: "Bad/Id".to_string(), name: "Bad".to_string(), quota: None }).is_err());
+        assert_eq!(store.tenant_for_key(&key).unwrap().id, "acme");
+        assert!(tenant.owns_collection("acme/racing"));
+        assert!(!tenant.owns_collection("acme/") && !tenant.owns_collection("acmeco/racing"));
+
+        // Namespace
+        assert!(store.authorize_transaction(&tenant, &state, &create_tx("other/racing", b"a", 0)).is_err());
+        store.authorize_transaction(&tenant, &state, &create_tx("acme/racing", b"a", 0)).unwrap();
+        assert_eq!(store.usage("acme").assets_created.load(Ordering::Relaxed), 1);
+
+        // Collection and asset quotas count what is on chain
+        state.create_test_account([1u8; 32], 1_000_000, 0);
+        state.apply_transaction(&create_tx("acme/racing", b"a", 0)).unwrap();
+        assert!(store.authorize_transaction(&tenant, &state, &create_tx("acme/puzzle", b"b", 0)).is_err());
+        state.apply_transaction(&create_tx("acme/racing", b"b", 1)).unwrap();
+        assert!(store.authorize_transaction(&tenant, &state, &create_tx("acme/racing", b"c", 0)).is_err());
+        let raised = TenantQuota { max_assets: 10, ..tenant.quota };
+        let tenant = store.set_quota("acme", raised).unwrap().unwrap();
+        store.authorize_transaction(&tenant, &state, &create_tx("acme/racing", b"c", 0)).unwrap();
+        assert_eq!(store.collections(&tenant, &state), vec![("acme/racing".to_string(), 2)]);
+
+        // Keys can be added and revoked; tenants survive a restart
+        let second = store.issue_key("acme").unwrap().unwrap();
+        assert!(store.revoke_key("acme", &hash_api_key(&key)).unwrap());
+        let reopened = TenantStore::open(&config.tenants, &state).unwrap();
+        assert!(reopened.tenant_for_key(&key).is_none());
+        assert_eq!(reopened.tenant_for_key(&second).unwrap().quota, raised);
+        assert!(reopened.delete("acme").unwrap());
+        assert!(TenantStore::open(&config.tenants, &state).unwrap().list().is_empty());
+    }
+}
@@ -0,0 +1,182 @@
+//! Clock drift detection against peers
+//!
+//! Wave finalization, committee expiry and block timestamp checks use the local clock.
+//! Nodes periodically ask their peers for the time and estimate each peer's offset
+//! NTP-style (`peer_time - midpoint of the round trip`). The median offset over
+//! all peers is the estimated drift of the local clock; above
+//! `consensus.max_clock_drift_ms` the node is flagged as drifting and consensus
+//! widens its timing windows by the drift.
+
+use std::collections::HashMap;
+use parking_lot::RwLock;
+use serde::Serialize;
+
+/// Measurements older than this are ignored (ms)
+const OFFSET_TTL_MS: i64 = 5 * 60 * 1000;
+
+/// Offset of one peer's clock relative to ours
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct PeerOffset {
+    /// Peer clock minus local clock (ms); positive when the local clock is behind
+    pub offset_ms: i64,
+    pub rtt_ms: i64,
+    /// Local time of the measurement (ms)
+    pub measured_at_ms: i64,
+}
+
+/// Clock drift summary for health checks
+#[derive(Debug, Clone, Serialize)]
+pub struct ClockStatus {
+    /// Peers with a recent measurement
+    pub peers: usize,
+    /// Median peer offset (ms), `None` without measurements
+    pub median_offset_ms: Option<i64>,
+    /// Median round trip to peers (ms), `None` without measurements
+    pub median_rtt_ms: Option<i64>,
+    pub max_drift_ms: u64,
+    pub drifting: bool,
+}
+
+/// Estimate a peer's clock offset and the round-trip time from one request/response
+pub fn estimate_offset(sent_ms: i64, peer_time_ms: i64, received_ms: i64) -> PeerOffset {
+    let rtt_ms = (received_ms - sent_ms).max(0);
+    PeerOffset {
+        offset_ms: peer_time_ms - (sent_ms + rtt_ms / 2),
+        rtt_ms,
+        measured_at_ms: received_ms,
+    }
+}
+
+/// Local wall clock in milliseconds
+pub fn now_ms() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+/// Latest clock offsets reported by peers
+#[derive(Debug)]
+pub struct PeerClock {
+    offsets: RwLock<HashMap<String, PeerOffset>>,
+    max_drift_ms: u64,
+}
+
+impl PeerClock {
+    pub fn new(max_drift_ms: u64) -> Self {
+        Self {
+            offsets: RwLock::new(HashMap::new()),
+            max_drift_ms,
+        }
+    }
+
+    /// Record a peer's offset; returns true if the drifting flag changed
+    pub fn record(&self, peer: String, offset: PeerOffset) -> bool {
+        let was_drifting = self.is_drifting();
+        self.offsets.write().insert(peer, offset);
+        was_drifting != self.is_drifting()
+    }
+
+    /// Forget a disconnected peer
+    pub fn remove(&self, peer: &str) {
+        self.offsets.write().remove(peer);
+    }
+
+    /// Median offset over peers measured within the last `OFFSET_TTL_MS`
+    pub fn median_offset_ms(&self) -> Option<i64> {
+        let now = now_ms();
+        let mut offsets: Vec<i64> = self.offsets
+            .read()
+            .values()
+            .filter(|offset| now - offset.measured_at_ms <= OFFSET_TTL_MS)
+            .map(|offset| offset.offset_ms)
+            .collect();
+        if offsets.is_empty() {
+            return None;
+        }
+        offsets.sort_unstable();
+        Some(offsets[offsets.len() / 2])
+    }
+
+    /// Median round trip over peers measured within the last `OFFSET_TTL_MS`
+    pub fn median_rtt_ms(&self) -> Option<i64> {
+        let now = now_ms();
+        let mut rtts: Vec<i64> = self.offsets
+            .read()
+            .values()
+            .filter(|offset| now - offset.measured_at_ms <= OFFSET_TTL_MS)
+            .map(|offset| offset.rtt_ms)
+            .collect();
+        if rtts.is_empty() {
+            return None;
+        }
+        rtts.sort_unstable();
+        Some(rtts[rtts.len() / 2])
+    }
+
+    pub fn is_drifting(&self) -> bool {
+        self.median_offset_ms()
+            .is_some_and(|offset| offset.unsigned_abs() > self.max_drift_ms)
+    }
+
+    /// Extra slack (ms) for timing decisions: the drift while flagged, otherwise 0
+    pub fn drift_allowance_ms(&self) -> u64 {
+        match self.median_offset_ms() {
+            Some(offset) if offset.unsigned_abs() > self.max_drift_ms => offset.unsigned_abs(),
+            _ => 0,
+        }
+    }
+
+    pub fn status(&self) -> ClockStatus {
+        let now = now_ms();
+        let peers = self.offsets
+            .read()
+            .values()
+            .filter(|offset| now - offset.measured_at_ms <= OFFSET_TTL_MS)
+            .count();
+        let median_offset_ms = self.median_offset_ms();
+        ClockStatus {
+            peers,
+            median_offset_ms,
+            median_rtt_ms: self.median_rtt_ms(),
+            max_drift_ms: self.max_drift_ms,
+            drifting: median_offset_ms.is_some_and(|offset| offset.unsigned_abs() > self.max_drift_ms),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_offset_uses_round_trip_midpoint() {
+        let offset = estimate_offset(1_000, 6_050, 1_100);
+        assert_eq!(offset.rtt_ms, 100);
+        assert_eq!(offset.offset_ms, 5_000);
+    }
+
+    #[test]
+    fn test_drift_flag_follows_median() {
+        let clock = PeerClock::new(2_000);
+        let now = now_ms();
+        let at = |offset_ms| PeerOffset { offset_ms, rtt_ms: 10, measured_at_ms: now };
+
+        assert!(!clock.status().drifting);
+        assert!(!clock.record("a".to_string(), at(100)));
+        // One outlier does not move the median past the threshold
+        assert!(!clock.record("b".to_string(), at(-9_000)));
+        assert!(!clock.record("c".to_string(), at(50)));
+        assert!(!clock.is_drifting());
+        assert_eq!(clock.drift_allowance_ms(), 0);
+
+        assert!(clock.record("a".to_string(), at(-5_000)));
+        assert!(clock.is_drifting());
+        assert_eq!(clock.drift_allowance_ms(), 5_000);
+
+        clock.remove("b");
+        clock.remove("a");
+        assert!(!clock.is_drifting());
+
+        // Stale measurements are ignored
+        clock.record("d".to_string(), PeerOffset { offset_ms: -9_000, rtt_ms: 10, measured_at_ms: now - OFFSET_TTL_MS - 1 });
+        assert_eq!(clock.status().peers, 1);
+    }
+}
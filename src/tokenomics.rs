@@ -8,6 +8,7 @@
 //! - Inflation control
 
 use std::sync::Arc;
+use std::collections::BTreeMap;
 use parking_lot::RwLock;
 use dashmap::DashMap;
 use chrono::{DateTime, Utc};
@@ -22,6 +23,7 @@ pub const STAKER_REWARD_RATIO: u64 = 70; // 70% to stakers
 pub const TREASURY_RATIO: u64 = 30; // 30% to treasury
 pub const GAS_BURN_RATIO: u64 = 50; // 50% of gas fees are burned
 pub const BLOCKS_PER_YEAR: u64 = 31_536_000; // ~365 days * 24 hours * 60 minutes * 60 seconds (1 second blocks)
+pub const FEE_STATS_RETAINED_WAVES: usize = 10_000; // Per-wave fee stats kept in memory
 
 /// Tokenomics manager
 pub struct Tokenomics {
@@ -48,10 +50,46 @@ pub struct Tokenomics {
     
     /// Validator set
     validators: Arc<DashMap<Address, ValidatorInfo>>,
+    
+    /// Per-wave fee statistics (most recent FEE_STATS_RETAINED_WAVES waves)
+    wave_fee_stats: Arc<RwLock<BTreeMap<u64, WaveFeeStats>>>,
+    
+    /// Lifetime fee totals (not affected by wave retention)
+    fee_totals: Arc<RwLock<WaveFeeStats>>,
+
+    /// Supply of the governance token (0 when it is disabled)
+    governance_supply: Arc<RwLock<u64>>,
+
+    /// Committee eligibility and weight cap in force
+    committee_params: Arc<RwLock<CommitteeParams>>,
+
+    /// Open votes for new committee parameters, by voter
+    committee_votes: Arc<DashMap<Address, CommitteeParams>>,
+}
+
+/// Fees collected, burned and redistributed within one wave.
+/// `minted` is the block reward issued in the same wave, so
+/// `minted - fees_burned` is the wave's net issuance (negative = deflationary).
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct WaveFeeStats {
+    pub wave: u64,
+    pub blocks: u64,
+    pub transactions: u64,
+    pub fees_collected: u64,
+    pub fees_burned: u64,
+    pub fees_redistributed: u64,
+    pub minted: u64,
+}
+
+impl WaveFeeStats {
+    /// Net issuance (minted - burned); negative when the wave was deflationary
+    pub fn net_issuance(&self) -> i128 {
+        self.minted as i128 - self.fees_burned as i128
+    }
 }
 
 /// Stake record
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct StakeRecord {
     pub validator: Address,
     pub amount: u64,
@@ -61,7 +99,7 @@ pub struct StakeRecord {
 }
 
 /// Validator information
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct ValidatorInfo {
     pub address: Address,
     pub total_staked: u64,
@@ -70,6 +108,85 @@ pub struct ValidatorInfo {
     pub reputation_score: u64,
     pub is_active: bool,
     pub joined_at: DateTime<Utc>,
+    /// Unix time until which the validator is jailed (excluded from committees)
+    pub jailed_until: Option<i64>,
+    /// Number of times the validator has been jailed
+    pub jail_count: u64,
+    /// Share of its delegators' rewards the validator keeps (basis points)
+    pub commission_bps: u64,
+    /// Commission increase waiting out its notice period
+    pub pending_commission: Option<PendingCommission>,
+    /// Commission epoch of the last commission change
+    pub commission_changed_epoch: Option<u64>,
+}
+
+/// Commission increase announced by a validator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PendingCommission {
+    pub rate_bps: u64,
+    /// First block height at which the new rate applies
+    pub effective_height: u64,
+}
+
+/// Limits on commission changes (from `consensus`)
+#[derive(Debug, Clone, Copy)]
+pub struct CommissionPolicy {
+    /// Blocks per commission epoch; a validator changes its commission at most once per epoch
+    pub epoch_blocks: u64,
+    /// Largest change of one commission update (basis points)
+    pub max_change_bps: u64,
+    /// Blocks between an increase and the height it takes effect
+    pub notice_blocks: u64,
+}
+
+/// Largest commission (100%)
+pub const MAX_COMMISSION_BPS: u64 = 10_000;
+
+/// Committee eligibility and weighting rules. They start from `consensus` and change when a
+/// governance vote (`VoteCommitteeParams`) passes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, serde::Serialize, serde::Deserialize)]
+pub struct CommitteeParams {
+    /// Least stake a validator needs to be selected into a committee
+    pub min_stake: u64,
+    /// Largest normalized weight of one committee member (basis points)
+    pub max_weight_bps: u64,
+}
+
+/// Total normalized weight of a committee (100%)
+pub const MAX_COMMITTEE_WEIGHT_BPS: u64 = 10_000;
+
+/// Normalize committee stakes to weights in basis points of `MAX_COMMITTEE_WEIGHT_BPS`, none
+/// above `max_weight_bps`. The excess of capped members is shared among the others by stake;
+/// when every member is capped the weights add up to less than 100%. Rounds down.
+pub fn normalize_committee_weights(stakes: &[u64], max_weight_bps: u64) -> Vec<u64> {
+    let mut weights = vec![0u64; stakes.len()];
+    let mut capped = vec![false; stakes.len()];
+    let mut budget = MAX_COMMITTEE_WEIGHT_BPS;
+    loop {
+        let open_stake: u128 = stakes.iter().zip(&capped).filter(|(_, c)| !**c).map(|(s, _)| *s as u128).sum();
+        if open_stake == 0 {
+            return weights;
+        }
+        let pass_budget = budget;
+        let share = |stake: u64| (stake as u128 * pass_budget as u128 / open_stake) as u64;
+        let mut capped_any = false;
+        for (i, stake) in stakes.iter().enumerate() {
+            if !capped[i] && share(*stake) > max_weight_bps {
+                capped[i] = true;
+                weights[i] = max_weight_bps;
+                budget = budget.saturating_sub(max_weight_bps);
+                capped_any = true;
+            }
+        }
+        if !capped_any {
+            for (i, stake) in stakes.iter().enumerate() {
+                if !capped[i] {
+                    weights[i] = share(*stake);
+                }
+            }
+            return weights;
+        }
+    }
 }
 
 impl Tokenomics {
@@ -83,9 +200,33 @@ impl Tokenomics {
             treasury: Arc::new(RwLock::new(0)),
             stakes: Arc::new(DashMap::new()),
             validators: Arc::new(DashMap::new()),
+            wave_fee_stats: Arc::new(RwLock::new(BTreeMap::new())),
+            fee_totals: Arc::new(RwLock::new(WaveFeeStats::default())),
+            governance_supply: Arc::new(RwLock::new(0)),
+            committee_params: Arc::new(RwLock::new(CommitteeParams::default())),
+            committee_votes: Arc::new(DashMap::new()),
         }
     }
 
+    /// Tokenomics starting from a custom inflation rate (basis points) instead of
+    /// `INITIAL_INFLATION_RATE`; used to try out parameters in economy simulations
+    pub fn with_inflation_rate(inflation_rate_bps: u64) -> Self {
+        let tokenomics = Self::new();
+        *tokenomics.current_inflation_rate.write() = inflation_rate_bps;
+        tokenomics
+    }
+
+    /// Supply of the governance token. It is fixed at genesis: governance tokens are never
+    /// minted, burned or used for fees.
+    pub fn governance_supply(&self) -> u64 {
+        *self.governance_supply.read()
+    }
+
+    /// Record the governance token supply allocated at genesis
+    pub fn set_governance_supply(&self, supply: u64) {
+        *self.governance_supply.write() = supply;
+    }
+
     /// Get total supply
     pub fn total_supply(&self) -> u64 {
         *self.total_supply.read()
@@ -132,10 +273,9 @@ impl Tokenomics {
         // Calculate annual inflation amount
         let inflation_rate = *self.current_inflation_rate.read();
         // Use checked arithmetic to avoid overflow
-        let annual_inflation = self.circulating_supply()
-            .checked_mul(inflation_rate)
-            .and_then(|v| v.checked_div(10_000))
-            .unwrap_or(0);
+        // (in u128: supply * rate overflows u64 at the initial supply)
+        let annual_inflation = u64::try_from(self.circulating_supply() as u128 * inflation_rate as u128 / 10_000)
+            .unwrap_or(u64::MAX);
         
         // Per-block inflation
         let block_inflation = annual_inflation.checked_div(BLOCKS_PER_YEAR).unwrap_or(0);
@@ -148,48 +288,70 @@ impl Tokenomics {
     }
 
     /// Distribute block rewards
+    ///
+    /// The staker share of a validator nobody has staked with (or that is not registered)
+    /// goes to the treasury.
     pub fn distribute_rewards(&self, block_reward: u64, validator: Address) -> Result<()> {
         let staker_reward = block_reward * STAKER_REWARD_RATIO / 100;
         let treasury_reward = block_reward * TREASURY_RATIO / 100;
 
         // Distribute to stakers
-        self.distribute_staker_rewards(staker_reward, validator)?;
+        let undistributed = self.distribute_staker_rewards(staker_reward, validator);
 
         // Add to treasury
-        *self.treasury.write() += treasury_reward;
+        *self.treasury.write() += treasury_reward + undistributed;
 
         Ok(())
     }
 
-    /// Distribute rewards to stakers
-    fn distribute_staker_rewards(&self, total_reward: u64, validator: Address) -> Result<()> {
-        if let Some(validator_info) = self.validators.get(&validator) {
-            let total_staked = validator_info.total_staked;
-            
-            if total_staked == 0 {
-                return Ok(());
-            }
+    /// Distribute rewards to stakers; returns the amount that had no staker to go to
+    fn distribute_staker_rewards(&self, total_reward: u64, validator: Address) -> u64 {
+        let total_staked = self.validators.get(&validator).map_or(0, |v| v.total_staked);
+        if total_staked == 0 {
+            return total_reward;
+        }
 
-            // Distribute rewards proportionally
-            for mut stake in self.stakes.iter_mut() {
-                if stake.value().validator == validator {
-                    let reward_share = total_reward * stake.value().amount / total_staked;
-                    stake.value_mut().accumulated_rewards += reward_share;
-                    stake.value_mut().last_reward = Utc::now();
+        let commission_bps = self.validators.get(&validator).map_or(0, |v| v.commission_bps);
+
+        // Distribute rewards proportionally; the validator keeps its commission of delegator shares
+        let mut commission = 0u64;
+        for mut stake in self.stakes.iter_mut() {
+            if stake.value().validator == validator {
+                let mut reward_share = (total_reward as u128 * stake.value().amount as u128 / total_staked as u128) as u64;
+                if *stake.key() != validator {
+                    let cut = (reward_share as u128 * commission_bps as u128 / MAX_COMMISSION_BPS as u128) as u64;
+                    reward_share -= cut;
+                    commission += cut;
                 }
+                stake.value_mut().accumulated_rewards += reward_share;
+                stake.value_mut().last_reward = Utc::now();
             }
-
-            Ok(())
-        } else {
-            Err(HazeError::State("Validator not found".to_string()))
+        }
+        match self.stakes.get_mut(&validator) {
+            Some(mut own) if commission > 0 => {
+                own.accumulated_rewards += commission;
+                0
+            }
+            _ => commission,
         }
     }
 
-    /// Stake tokens
-    pub fn stake(&self, staker: Address, validator: Address, amount: u64) -> Result<()> {
+    /// Check that a stake would be accepted by `stake`, without recording it
+    pub fn check_stake(&self, staker: &Address, validator: &Address, amount: u64) -> Result<()> {
         if amount == 0 {
             return Err(HazeError::State("Cannot stake zero amount".to_string()));
         }
+        if let Some(stake) = self.stakes.get(staker)
+            && stake.validator != *validator
+        {
+            return Err(HazeError::State("Cannot stake to different validator".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Stake tokens
+    pub fn stake(&self, staker: Address, validator: Address, amount: u64) -> Result<()> {
+        self.check_stake(&staker, &validator, amount)?;
 
         // Get or create stake record
         let mut stake = self.stakes.entry(staker).or_insert_with(|| StakeRecord {
@@ -216,6 +378,11 @@ impl Tokenomics {
                 reputation_score: 0,
                 is_active: false,
                 joined_at: Utc::now(),
+                jailed_until: None,
+                jail_count: 0,
+                commission_bps: 0,
+                pending_commission: None,
+                commission_changed_epoch: None,
             });
 
         if staker == validator {
@@ -260,7 +427,7 @@ impl Tokenomics {
 
     /// Process gas fee (burn 50%)
     pub fn process_gas_fee(&self, gas_fee: u64) -> Result<u64> {
-        let burn_amount = gas_fee * GAS_BURN_RATIO / 100;
+        let burn_amount = Self::gas_burn_amount(gas_fee);
         let remaining = gas_fee - burn_amount;
 
         // Burn tokens
@@ -270,6 +437,54 @@ impl Tokenomics {
         Ok(remaining)
     }
 
+    /// Portion of a gas fee that `process_gas_fee` burns
+    pub fn gas_burn_amount(gas_fee: u64) -> u64 {
+        gas_fee * GAS_BURN_RATIO / 100
+    }
+
+    /// Record fee/issuance totals of an applied block under its wave.
+    /// Stats are accumulated incrementally so reading them never scans blocks.
+    pub fn record_block_fees(&self, wave: u64, transactions: u64, fees_collected: u64, fees_burned: u64, minted: u64) {
+        let fees_redistributed = fees_collected.saturating_sub(fees_burned);
+        let apply = |stats: &mut WaveFeeStats| {
+            stats.blocks += 1;
+            stats.transactions += transactions;
+            stats.fees_collected += fees_collected;
+            stats.fees_burned += fees_burned;
+            stats.fees_redistributed += fees_redistributed;
+            stats.minted += minted;
+        };
+
+        {
+            let mut waves = self.wave_fee_stats.write();
+            apply(waves.entry(wave).or_insert_with(|| WaveFeeStats { wave, ..Default::default() }));
+            while waves.len() > FEE_STATS_RETAINED_WAVES {
+                waves.pop_first();
+            }
+        }
+        apply(&mut self.fee_totals.write());
+    }
+
+    /// Get per-wave fee stats in ascending wave order
+    ///
+    /// # Arguments
+    /// * `from_wave` - First wave to include
+    /// * `limit` - Maximum number of waves to return (0 = all)
+    pub fn get_wave_fee_stats(&self, from_wave: u64, limit: usize) -> Vec<WaveFeeStats> {
+        let waves = self.wave_fee_stats.read();
+        let iter = waves.range(from_wave..).map(|(_, stats)| stats.clone());
+        if limit > 0 {
+            iter.take(limit).collect()
+        } else {
+            iter.collect()
+        }
+    }
+
+    /// Get lifetime fee totals (`wave` is unused)
+    pub fn fee_totals(&self) -> WaveFeeStats {
+        self.fee_totals.read().clone()
+    }
+
     /// Get stake record
     pub fn get_stake(&self, staker: &Address) -> Option<StakeRecord> {
         self.stakes.get(staker).map(|s| s.clone())
@@ -306,9 +521,15 @@ impl Tokenomics {
         }
     }
 
-    /// Get top validators by stake
+    /// Get top validators by stake (jailed validators are excluded)
     pub fn get_top_validators(&self, limit: usize) -> Vec<ValidatorInfo> {
+        self.get_committee_candidates(limit, 0)
+    }
+
+    /// Up to `limit` unjailed validators with at least `min_stake` staked, by stake
+    pub fn get_committee_candidates(&self, limit: usize, min_stake: u64) -> Vec<ValidatorInfo> {
         let mut validators: Vec<ValidatorInfo> = self.validators.iter()
+            .filter(|v| v.value().jailed_until.is_none() && v.value().total_staked >= min_stake)
             .map(|v| v.value().clone())
             .collect();
         
@@ -317,6 +538,192 @@ impl Tokenomics {
         
         validators
     }
+
+    /// Get all validators (including jailed) sorted by stake
+    pub fn get_all_validators(&self) -> Vec<ValidatorInfo> {
+        let mut validators: Vec<ValidatorInfo> = self.validators.iter()
+            .map(|v| v.value().clone())
+            .collect();
+        validators.sort_by_key(|v| std::cmp::Reverse(v.total_staked));
+        validators
+    }
+
+    /// Check whether a validator is currently jailed
+    pub fn is_jailed(&self, validator: &Address) -> bool {
+        self.validators
+            .get(validator)
+            .is_some_and(|v| v.jailed_until.is_some())
+    }
+
+    /// Jail a validator until `until` (unix seconds). Jailing is separate from
+    /// slashing: stake is untouched, the validator is only excluded from committees.
+    pub fn jail(&self, validator: &Address, until: i64) -> Result<()> {
+        let mut validator_info = self.validators.get_mut(validator)
+            .ok_or_else(|| HazeError::State("Validator not found".to_string()))?;
+        validator_info.jailed_until = Some(until);
+        validator_info.jail_count += 1;
+        validator_info.is_active = false;
+        Ok(())
+    }
+
+    /// Check that a validator may be unjailed at `now` (jailed and cooldown elapsed)
+    pub fn check_unjail(&self, validator: &Address, now: i64) -> Result<()> {
+        let validator_info = self.validators.get(validator)
+            .ok_or_else(|| HazeError::State("Validator not found".to_string()))?;
+        match validator_info.jailed_until {
+            None => Err(HazeError::State("Validator is not jailed".to_string())),
+            Some(until) if now < until => Err(HazeError::State(
+                format!("Validator is jailed for another {} seconds", until - now)
+            )),
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// Check that `validator` may set its commission to `rate_bps` at block `height`
+    pub fn check_commission_change(&self, validator: &Address, rate_bps: u64, height: u64, policy: &CommissionPolicy) -> Result<()> {
+        let validator_info = self.validators.get(validator)
+            .ok_or_else(|| HazeError::State("Validator not found".to_string()))?;
+        if rate_bps > MAX_COMMISSION_BPS {
+            return Err(HazeError::State(format!("Commission cannot exceed {} bps", MAX_COMMISSION_BPS)));
+        }
+        let epoch = height / policy.epoch_blocks;
+        if validator_info.commission_changed_epoch == Some(epoch) {
+            return Err(HazeError::State(format!(
+                "Commission was already changed in this epoch; the next epoch starts at height {}",
+                (epoch + 1) * policy.epoch_blocks
+            )));
+        }
+        // Measured from the announced rate, so queued increases cannot be chained past the limit
+        let current = validator_info.pending_commission.map_or(validator_info.commission_bps, |p| p.rate_bps);
+        if rate_bps.abs_diff(current) > policy.max_change_bps {
+            return Err(HazeError::State(format!(
+                "Commission can change by at most {} bps per epoch (from {} bps)", policy.max_change_bps, current
+            )));
+        }
+        Ok(())
+    }
+
+    /// Change the commission of `validator` at block `height`: decreases apply at once,
+    /// increases after `policy.notice_blocks`
+    pub fn set_commission(&self, validator: &Address, rate_bps: u64, height: u64, policy: &CommissionPolicy) -> Result<()> {
+        self.check_commission_change(validator, rate_bps, height, policy)?;
+        let mut validator_info = self.validators.get_mut(validator)
+            .ok_or_else(|| HazeError::State("Validator not found".to_string()))?;
+        if rate_bps <= validator_info.commission_bps {
+            validator_info.commission_bps = rate_bps;
+            validator_info.pending_commission = None;
+        } else {
+            validator_info.pending_commission = Some(PendingCommission {
+                rate_bps,
+                effective_height: height + policy.notice_blocks,
+            });
+        }
+        validator_info.commission_changed_epoch = Some(height / policy.epoch_blocks);
+        Ok(())
+    }
+
+    /// Apply the commission increases whose notice period ends at or before `height`
+    pub fn activate_commissions(&self, height: u64) {
+        for mut validator_info in self.validators.iter_mut() {
+            if let Some(pending) = validator_info.pending_commission.filter(|p| p.effective_height <= height) {
+                validator_info.commission_bps = pending.rate_bps;
+                validator_info.pending_commission = None;
+            }
+        }
+    }
+
+    /// Committee eligibility and weight cap in force
+    pub fn committee_params(&self) -> CommitteeParams {
+        *self.committee_params.read()
+    }
+
+    /// Put committee parameters in force; open votes are dropped
+    pub fn set_committee_params(&self, params: CommitteeParams) {
+        *self.committee_params.write() = params;
+        self.committee_votes.clear();
+    }
+
+    /// Record the committee parameters `voter` votes for, replacing its earlier vote
+    pub fn vote_committee_params(&self, voter: Address, params: CommitteeParams) {
+        self.committee_votes.insert(voter, params);
+    }
+
+    /// Open committee parameter votes as (voter, parameters)
+    pub fn committee_votes(&self) -> Vec<(Address, CommitteeParams)> {
+        self.committee_votes.iter().map(|vote| (*vote.key(), *vote.value())).collect()
+    }
+
+    /// Release a jailed validator; it becomes eligible for the next committee
+    pub fn unjail(&self, validator: &Address, now: i64) -> Result<()> {
+        self.check_unjail(validator, now)?;
+        if let Some(mut validator_info) = self.validators.get_mut(validator) {
+            validator_info.jailed_until = None;
+        }
+        Ok(())
+    }
+
+    /// Copy of everything tracked here, for the state checkpoint written on shutdown
+    pub(crate) fn checkpoint(&self) -> TokenomicsCheckpoint {
+        TokenomicsCheckpoint {
+            total_supply: *self.total_supply.read(),
+            circulating_supply: *self.circulating_supply.read(),
+            burned_supply: *self.burned_supply.read(),
+            current_inflation_rate: *self.current_inflation_rate.read(),
+            current_year: *self.current_year.read(),
+            treasury: *self.treasury.read(),
+            stakes: self.stakes.iter().map(|entry| (*entry.key(), entry.value().clone())).collect(),
+            validators: self.validators.iter().map(|entry| (*entry.key(), entry.value().clone())).collect(),
+            wave_fee_stats: self.wave_fee_stats.read().clone(),
+            fee_totals: self.fee_totals.read().clone(),
+            governance_supply: *self.governance_supply.read(),
+            committee_params: *self.committee_params.read(),
+            committee_votes: self.committee_votes(),
+        }
+    }
+
+    /// Replace everything tracked here with a checkpoint
+    pub(crate) fn restore(&self, checkpoint: TokenomicsCheckpoint) {
+        *self.total_supply.write() = checkpoint.total_supply;
+        *self.circulating_supply.write() = checkpoint.circulating_supply;
+        *self.burned_supply.write() = checkpoint.burned_supply;
+        *self.current_inflation_rate.write() = checkpoint.current_inflation_rate;
+        *self.current_year.write() = checkpoint.current_year;
+        *self.treasury.write() = checkpoint.treasury;
+        self.stakes.clear();
+        for (key, value) in checkpoint.stakes {
+            self.stakes.insert(key, value);
+        }
+        self.validators.clear();
+        for (key, value) in checkpoint.validators {
+            self.validators.insert(key, value);
+        }
+        *self.wave_fee_stats.write() = checkpoint.wave_fee_stats;
+        *self.fee_totals.write() = checkpoint.fee_totals;
+        *self.governance_supply.write() = checkpoint.governance_supply;
+        *self.committee_params.write() = checkpoint.committee_params;
+        self.committee_votes.clear();
+        for (key, value) in checkpoint.committee_votes {
+            self.committee_votes.insert(key, value);
+        }
+    }
+}
+
+/// Contents of a `Tokenomics` (see `Tokenomics::checkpoint`)
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub(crate) struct TokenomicsCheckpoint {
+    total_supply: u64,
+    circulating_supply: u64,
+    burned_supply: u64,
+    current_inflation_rate: u64,
+    current_year: u64,
+    treasury: u64,
+    stakes: Vec<(Address, StakeRecord)>,
+    validators: Vec<(Address, ValidatorInfo)>,
+    wave_fee_stats: BTreeMap<u64, WaveFeeStats>,
+    fee_totals: WaveFeeStats,
+    governance_supply: u64,
+    committee_params: CommitteeParams,
+    committee_votes: Vec<(Address, CommitteeParams)>,
 }
 
 impl Default for Tokenomics {
@@ -417,6 +824,126 @@ mod tests {
         assert_eq!(top_validators[1].address, validator3);
     }
 
+    #[test]
+    fn test_commission_change_limits_and_notice() {
+        let tokenomics = Tokenomics::new();
+        let validator = create_test_address(1);
+        let delegator = create_test_address(2);
+        tokenomics.stake(validator, validator, 1_000).unwrap();
+        tokenomics.stake(delegator, validator, 1_000).unwrap();
+        let policy = CommissionPolicy { epoch_blocks: 100, max_change_bps: 500, notice_blocks: 50 };
+
+        // An increase is capped per epoch and waits out the notice period
+        assert!(tokenomics.check_commission_change(&validator, 600, 10, &policy).is_err());
+        tokenomics.set_commission(&validator, 500, 10, &policy).unwrap();
+        let info = tokenomics.get_validator(&validator).unwrap();
+        assert_eq!(info.commission_bps, 0);
+        assert_eq!(info.pending_commission, Some(PendingCommission { rate_bps: 500, effective_height: 60 }));
+        assert!(tokenomics.check_commission_change(&validator, 400, 20, &policy).is_err(), "one change per epoch");
+        // The limit is measured from the pending rate
+        assert!(tokenomics.check_commission_change(&validator, 1_100, 100, &policy).is_err());
+
+        tokenomics.activate_commissions(59);
+        assert_eq!(tokenomics.get_validator(&validator).unwrap().commission_bps, 0);
+        tokenomics.activate_commissions(60);
+        let info = tokenomics.get_validator(&validator).unwrap();
+        assert_eq!((info.commission_bps, info.pending_commission), (500, None));
+
+        // The validator keeps 5% of its delegator's share
+        tokenomics.distribute_rewards(1_000, validator).unwrap();
+        assert_eq!(tokenomics.get_stake(&delegator).unwrap().accumulated_rewards, 333);
+        assert_eq!(tokenomics.get_stake(&validator).unwrap().accumulated_rewards, 367);
+
+        // Decreases apply at once
+        tokenomics.set_commission(&validator, 100, 100, &policy).unwrap();
+        let info = tokenomics.get_validator(&validator).unwrap();
+        assert_eq!((info.commission_bps, info.pending_commission), (100, None));
+    }
+
+    #[test]
+    fn test_committee_candidates_and_weight_cap() {
+        let tokenomics = Tokenomics::new();
+        for (seed, amount) in [(1u8, 6_000u64), (2, 2_000), (3, 1_000), (4, 1_000), (5, 10)] {
+            let validator = create_test_address(seed);
+            tokenomics.stake(validator, validator, amount).unwrap();
+        }
+        // Dust stakes are not eligible
+        let candidates = tokenomics.get_committee_candidates(10, 100);
+        assert_eq!(candidates.len(), 4);
+        assert_eq!(tokenomics.get_top_validators(10).len(), 5);
+
+        // The excess of the capped member goes to the others by stake
+        let stakes: Vec<u64> = candidates.iter().map(|v| v.total_staked).collect();
+        assert_eq!(normalize_committee_weights(&stakes, 3_333), vec![3_333, 3_333, 1_666, 1_666]);
+        assert_eq!(normalize_committee_weights(&stakes, MAX_COMMITTEE_WEIGHT_BPS), vec![6_000, 2_000, 1_000, 1_000]);
+        // Too few members to reach 100% under the cap
+        assert_eq!(normalize_committee_weights(&[1, 1], 3_000), vec![3_000, 3_000]);
+        assert_eq!(normalize_committee_weights(&[0, 0], 3_000), vec![0, 0]);
+
+        // A new set of parameters drops the open votes
+        tokenomics.vote_committee_params(create_test_address(1), CommitteeParams { min_stake: 1, max_weight_bps: 1 });
+        assert_eq!(tokenomics.committee_votes().len(), 1);
+        tokenomics.set_committee_params(CommitteeParams { min_stake: 5, max_weight_bps: 5_000 });
+        assert!(tokenomics.committee_votes().is_empty());
+        assert_eq!(tokenomics.committee_params().max_weight_bps, 5_000);
+    }
+
+    #[test]
+    fn test_jail_and_unjail_validator() {
+        let tokenomics = Tokenomics::new();
+        let validator1 = create_test_address(1);
+        let validator2 = create_test_address(2);
+        tokenomics.stake(validator1, validator1, 3000).unwrap();
+        tokenomics.stake(validator2, validator2, 1000).unwrap();
+
+        tokenomics.jail(&validator1, 1_000).unwrap();
+        assert!(tokenomics.is_jailed(&validator1));
+        let top_validators = tokenomics.get_top_validators(10);
+        assert_eq!(top_validators.len(), 1);
+        assert_eq!(top_validators[0].address, validator2);
+        assert_eq!(tokenomics.get_all_validators().len(), 2);
+
+        // Waiting period not over yet
+        assert!(tokenomics.unjail(&validator1, 999).is_err());
+        tokenomics.unjail(&validator1, 1_000).unwrap();
+        assert!(!tokenomics.is_jailed(&validator1));
+        assert_eq!(tokenomics.get_validator(&validator1).unwrap().jail_count, 1);
+        assert_eq!(tokenomics.get_top_validators(10).len(), 2);
+
+        // Not jailed any more
+        assert!(tokenomics.unjail(&validator1, 2_000).is_err());
+    }
+
+    #[test]
+    fn test_record_block_fees_per_wave() {
+        let tokenomics = Tokenomics::new();
+        
+        tokenomics.record_block_fees(1, 2, 1000, 500, 100);
+        tokenomics.record_block_fees(1, 1, 10, 5, 100);
+        tokenomics.record_block_fees(2, 0, 0, 0, 100);
+        
+        let waves = tokenomics.get_wave_fee_stats(0, 0);
+        assert_eq!(waves.len(), 2);
+        assert_eq!(waves[0].wave, 1);
+        assert_eq!(waves[0].blocks, 2);
+        assert_eq!(waves[0].transactions, 3);
+        assert_eq!(waves[0].fees_collected, 1010);
+        assert_eq!(waves[0].fees_burned, 505);
+        assert_eq!(waves[0].fees_redistributed, 505);
+        assert_eq!(waves[0].net_issuance(), 200 - 505);
+        assert_eq!(waves[1].net_issuance(), 100);
+        
+        // Range queries
+        let from_two = tokenomics.get_wave_fee_stats(2, 0);
+        assert_eq!(from_two.len(), 1);
+        assert_eq!(tokenomics.get_wave_fee_stats(0, 1).len(), 1);
+        
+        let totals = tokenomics.fee_totals();
+        assert_eq!(totals.blocks, 3);
+        assert_eq!(totals.fees_burned, 505);
+        assert_eq!(totals.minted, 300);
+    }
+
     // Note: test_process_block_rewards is skipped because it causes overflow
     // with the very large INITIAL_SUPPLY (1_000_000_000_000_000_000) when multiplying
     // by inflation_rate. This is expected behavior and the function works correctly
@@ -1,8 +1,10 @@
 //! Core types for HAZE blockchain
 
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
 use sha2::{Sha256, Digest};
+use bincode::Options;
+use crate::error::{HazeError, Result};
 
 /// Unique identifier for blocks, transactions, and assets
 pub type Hash = [u8; 32];
@@ -50,6 +52,60 @@ pub fn sha256(data: &[u8]) -> Hash {
     hasher.finalize().into()
 }
 
+/// Merkle root over leaf hashes (pairs hashed as sha256(left || right), an odd
+/// node is carried up unchanged; all zeros for no leaves)
+pub fn merkle_root(mut hashes: Vec<Hash>) -> Hash {
+    if hashes.is_empty() {
+        return [0; 32];
+    }
+
+    while hashes.len() > 1 {
+        let mut next_level = Vec::new();
+        for chunk in hashes.chunks(2) {
+            if chunk.len() == 2 {
+                let combined = [chunk[0].as_ref(), chunk[1].as_ref()].concat();
+                next_level.push(sha256(&combined));
+            } else {
+                next_level.push(chunk[0]);
+            }
+        }
+        hashes = next_level;
+    }
+
+    hashes[0]
+}
+
+/// Address of a contract deployed by `deployer` with transaction nonce `nonce`:
+/// sha256("haze_contract" || deployer || nonce as u64 LE)
+pub fn contract_address(deployer: &Address, nonce: u64) -> Address {
+    let mut data = Vec::with_capacity(13 + 32 + 8);
+    data.extend_from_slice(b"haze_contract");
+    data.extend_from_slice(deployer);
+    data.extend_from_slice(&nonce.to_le_bytes());
+    sha256(&data)
+}
+
+/// Low bits of a nonce holding the sequence; the 16 bits above select the nonce lane.
+/// Lane 0 is the account's main sequence, so plain nonces are lane-0 nonces.
+pub const NONCE_LANE_SHIFT: u32 = 48;
+/// Highest sequence number a nonce lane can reach
+pub const MAX_LANE_SEQUENCE: u64 = (1 << NONCE_LANE_SHIFT) - 1;
+
+/// Lane a nonce belongs to
+pub fn nonce_lane(nonce: u64) -> u16 {
+    (nonce >> NONCE_LANE_SHIFT) as u16
+}
+
+/// Position of a nonce in its lane
+pub fn nonce_sequence(nonce: u64) -> u64 {
+    nonce & MAX_LANE_SEQUENCE
+}
+
+/// Nonce for `sequence` on `lane` (the sequence itself on lane 0)
+pub fn lane_nonce(lane: u16, sequence: u64) -> u64 {
+    ((lane as u64) << NONCE_LANE_SHIFT) | (sequence & MAX_LANE_SEQUENCE)
+}
+
 /// Block in the HAZE blockchain
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Block {
@@ -70,15 +126,174 @@ pub struct BlockHeader {
     pub state_root: Hash,
     pub wave_number: u64, // Wave finalization number
     pub committee_id: u64, // Haze Committee ID
+    /// Peer protocol the block was produced under (`PROTOCOL_VERSION`)
+    pub protocol_version: u32,
+    /// State format `state_root` was computed with (`STATE_VERSION`)
+    pub state_version: u32,
+    /// Upgrade signal bits: bit `Feature::bit` is set when the producer is ready for that
+    /// feature (see `features`)
+    pub signals: u32,
 }
 
 impl BlockHeader {
-    pub fn compute_hash(&self) -> Hash {
-        let data = bincode::serialize(self).unwrap();
+    /// Hash of the network id followed by the canonical header body with the `hash` field zeroed
+    ///
+    /// Committing to the network id means a block hashes differently on every network,
+    /// so blocks cannot be replayed from testnet to mainnet. Headers of an older protocol hash
+    /// the body layout of that protocol, so blocks stored before an upgrade keep their hashes.
+    pub fn compute_hash(&self, network_id: &Hash) -> Hash {
+        let unhashed = BlockHeader { hash: [0; 32], ..self.clone() };
+        let mut data = network_id.to_vec();
+        match unhashed.protocol_version {
+            0 | 1 => data.extend_from_slice(&wire_body(&BlockHeaderV1::from(unhashed))),
+            2 => data.extend_from_slice(&wire_body(&BlockHeaderV2::from(unhashed))),
+            _ => data.extend_from_slice(&wire_body(&unhashed)),
+        }
         sha256(&data)
     }
 }
 
+/// Block header as encoded by wire version 1, before the version fields
+#[derive(Serialize, Deserialize)]
+struct BlockHeaderV1 {
+    hash: Hash,
+    parent_hash: Hash,
+    height: u64,
+    timestamp: Timestamp,
+    validator: Address,
+    merkle_root: Hash,
+    state_root: Hash,
+    wave_number: u64,
+    committee_id: u64,
+}
+
+/// Block header as encoded by wire version 2, before the upgrade signals
+#[derive(Serialize, Deserialize)]
+struct BlockHeaderV2 {
+    hash: Hash,
+    parent_hash: Hash,
+    height: u64,
+    timestamp: Timestamp,
+    validator: Address,
+    merkle_root: Hash,
+    state_root: Hash,
+    wave_number: u64,
+    committee_id: u64,
+    protocol_version: u32,
+    state_version: u32,
+}
+
+/// Block as encoded by an older wire version, with that version's header layout
+#[derive(Deserialize)]
+struct LegacyBlock<H> {
+    header: H,
+    transactions: Vec<Transaction>,
+    dag_references: Vec<Hash>,
+}
+
+impl From<BlockHeader> for BlockHeaderV1 {
+    fn from(header: BlockHeader) -> Self {
+        Self {
+            hash: header.hash,
+            parent_hash: header.parent_hash,
+            height: header.height,
+            timestamp: header.timestamp,
+            validator: header.validator,
+            merkle_root: header.merkle_root,
+            state_root: header.state_root,
+            wave_number: header.wave_number,
+            committee_id: header.committee_id,
+        }
+    }
+}
+
+impl From<BlockHeaderV1> for BlockHeader {
+    fn from(header: BlockHeaderV1) -> Self {
+        Self {
+            hash: header.hash,
+            parent_hash: header.parent_hash,
+            height: header.height,
+            timestamp: header.timestamp,
+            validator: header.validator,
+            merkle_root: header.merkle_root,
+            state_root: header.state_root,
+            wave_number: header.wave_number,
+            committee_id: header.committee_id,
+            protocol_version: 1,
+            state_version: 1,
+            signals: 0,
+        }
+    }
+}
+
+impl From<BlockHeader> for BlockHeaderV2 {
+    fn from(header: BlockHeader) -> Self {
+        Self {
+            hash: header.hash,
+            parent_hash: header.parent_hash,
+            height: header.height,
+            timestamp: header.timestamp,
+            validator: header.validator,
+            merkle_root: header.merkle_root,
+            state_root: header.state_root,
+            wave_number: header.wave_number,
+            committee_id: header.committee_id,
+            protocol_version: header.protocol_version,
+            state_version: header.state_version,
+        }
+    }
+}
+
+impl From<BlockHeaderV2> for BlockHeader {
+    fn from(header: BlockHeaderV2) -> Self {
+        Self {
+            hash: header.hash,
+            parent_hash: header.parent_hash,
+            height: header.height,
+            timestamp: header.timestamp,
+            validator: header.validator,
+            merkle_root: header.merkle_root,
+            state_root: header.state_root,
+            wave_number: header.wave_number,
+            committee_id: header.committee_id,
+            protocol_version: header.protocol_version,
+            state_version: header.state_version,
+            signals: 0,
+        }
+    }
+}
+
+impl<H: Into<BlockHeader>> From<LegacyBlock<H>> for Block {
+    fn from(block: LegacyBlock<H>) -> Self {
+        Self {
+            header: block.header.into(),
+            transactions: block.transactions,
+            dag_references: block.dag_references,
+        }
+    }
+}
+
+/// Identifier of a network, derived from its genesis parameters:
+/// sha256("haze_genesis" || chain_id as u64 LE || initial supply as u64 LE
+///        || for each genesis allocation, sorted by address: address || amount as u64 LE)
+///
+/// Every transaction signing payload and block hash starts with it (domain separation),
+/// so signatures and blocks from one network are invalid on any other, including a
+/// network that reuses the chain id with a different genesis allocation set.
+pub fn network_id(chain_id: u64, genesis: &[(Address, u64)]) -> Hash {
+    let mut allocations = genesis.to_vec();
+    allocations.sort_unstable();
+    let mut data = Vec::with_capacity(12 + 8 + 8 + allocations.len() * 40);
+    data.extend_from_slice(b"haze_genesis");
+    data.extend_from_slice(&chain_id.to_le_bytes());
+    data.extend_from_slice(&crate::tokenomics::INITIAL_SUPPLY.to_le_bytes());
+    for (address, amount) in allocations {
+        data.extend_from_slice(&address);
+        data.extend_from_slice(&amount.to_le_bytes());
+    }
+    sha256(&data)
+}
+
 /// Transaction types in HAZE
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Transaction {
@@ -96,7 +311,7 @@ pub enum Transaction {
         signature: Vec<u8>,
     },
 
-    /// Deploy WASM contract (code stored at address = sha256(code))
+    /// Deploy WASM contract (address = `contract_address(from, nonce)`)
     DeployContract {
         from: Address,
         code: Vec<u8>,
@@ -169,10 +384,656 @@ pub enum Transaction {
         valid_until_height: Option<u64>,
         signature: Vec<u8>,
     },
+
+    /// Release a jailed validator once its jail period has elapsed (`from` is the validator)
+    Unjail {
+        from: Address,
+        fee: u64,
+        nonce: u64,
+        #[serde(default)]
+        chain_id: Option<u64>,
+        #[serde(default)]
+        valid_until_height: Option<u64>,
+        signature: Vec<u8>,
+    },
+
+    /// Register (or remove, with `hook: None`) the contract hook of a collection (game_id).
+    /// The first registrant controls the collection's hook.
+    SetAssetHook {
+        from: Address,
+        game_id: String,
+        hook: Option<AssetHook>,
+        fee: u64,
+        nonce: u64,
+        #[serde(default)]
+        chain_id: Option<u64>,
+        #[serde(default)]
+        valid_until_height: Option<u64>,
+        signature: Vec<u8>,
+    },
+
+    /// Credit every claim from `from`'s balance in one transaction.
+    /// `claims_root` must equal `airdrop_claims_root(claims)`; the signature covers the root.
+    CreateAirdrop {
+        from: Address,
+        claims: Vec<AirdropClaim>,
+        claims_root: Hash,
+        fee: u64,
+        nonce: u64,
+        #[serde(default)]
+        chain_id: Option<u64>,
+        #[serde(default)]
+        valid_until_height: Option<u64>,
+        signature: Vec<u8>,
+    },
+
+    /// Ask for `amount` HAZE to be paid to `from` (an invoice). Anyone may pay unless
+    /// `payer` is set. The request id is `payment_request_id(from, nonce)`.
+    CreatePaymentRequest {
+        from: Address,
+        payer: Option<Address>,
+        amount: u64,
+        memo: String,
+        /// Last block height at which the request can be paid
+        expires_at_height: u64,
+        fee: u64,
+        nonce: u64,
+        #[serde(default)]
+        chain_id: Option<u64>,
+        #[serde(default)]
+        valid_until_height: Option<u64>,
+        signature: Vec<u8>,
+    },
+
+    /// Pay an open payment request in full; `amount` must equal the requested amount
+    PayPaymentRequest {
+        from: Address,
+        request_id: Hash,
+        amount: u64,
+        fee: u64,
+        nonce: u64,
+        #[serde(default)]
+        chain_id: Option<u64>,
+        #[serde(default)]
+        valid_until_height: Option<u64>,
+        signature: Vec<u8>,
+    },
+
+    /// Place a limit order on the order book of collection `game_id`, priced in HAZE per asset.
+    /// A buy order escrows `price * quantity`; a sell order escrows `assets`
+    /// (`quantity == assets.len()`). The order id is `order_id(from, nonce)`.
+    PlaceOrder {
+        from: Address,
+        game_id: String,
+        side: OrderSide,
+        price: u64,
+        quantity: u64,
+        /// Assets offered by a sell order (empty for a buy order)
+        assets: Vec<Hash>,
+        fee: u64,
+        nonce: u64,
+        #[serde(default)]
+        chain_id: Option<u64>,
+        #[serde(default)]
+        valid_until_height: Option<u64>,
+        signature: Vec<u8>,
+    },
+
+    /// Cancel an open order of `from` and release what is left in escrow
+    CancelOrder {
+        from: Address,
+        order_id: Hash,
+        fee: u64,
+        nonce: u64,
+        #[serde(default)]
+        chain_id: Option<u64>,
+        #[serde(default)]
+        valid_until_height: Option<u64>,
+        signature: Vec<u8>,
+    },
+
+    /// Authorize a recurring payment of `amount` from `from` to `payee` every
+    /// `interval_blocks` blocks until `end_height` (or until cancelled). The first period
+    /// is charged by this transaction; later ones during block application.
+    /// The subscription id is `subscription_id(from, nonce)`.
+    CreateSubscription {
+        from: Address,
+        payee: Address,
+        amount: u64,
+        interval_blocks: u64,
+        /// Last height a period can be charged at (`None` = until cancelled)
+        end_height: Option<u64>,
+        fee: u64,
+        nonce: u64,
+        #[serde(default)]
+        chain_id: Option<u64>,
+        #[serde(default)]
+        valid_until_height: Option<u64>,
+        signature: Vec<u8>,
+    },
+
+    /// Stop a subscription; signed by its payer or its payee
+    CancelSubscription {
+        from: Address,
+        subscription_id: Hash,
+        fee: u64,
+        nonce: u64,
+        #[serde(default)]
+        chain_id: Option<u64>,
+        #[serde(default)]
+        valid_until_height: Option<u64>,
+        signature: Vec<u8>,
+    },
+
+    /// Bind player `from` to `referrer` in the referral program of `game_id`.
+    /// Signed by the player; the binding is permanent.
+    SetReferrer {
+        from: Address,
+        game_id: String,
+        referrer: Address,
+        fee: u64,
+        nonce: u64,
+        #[serde(default)]
+        chain_id: Option<u64>,
+        #[serde(default)]
+        valid_until_height: Option<u64>,
+        signature: Vec<u8>,
+    },
+
+    /// Seal a loot box of collection `game_id` for `player`. `commitment` is
+    /// `loot_commitment(salt, outcomes)` of the outcomes revealed later; `bond` is escrowed
+    /// until the reveal and paid to the player if the operator `from` misses the deadline.
+    /// The box id is `loot_box_id(from, nonce)`.
+    CommitLootBox {
+        from: Address,
+        player: Address,
+        game_id: String,
+        commitment: Hash,
+        bond: u64,
+        fee: u64,
+        nonce: u64,
+        #[serde(default)]
+        chain_id: Option<u64>,
+        #[serde(default)]
+        valid_until_height: Option<u64>,
+        signature: Vec<u8>,
+    },
+
+    /// Open a sealed loot box (signed by its player). The hash of a block produced
+    /// `economy.loot_beacon_delay` blocks later decides the outcome.
+    OpenLootBox {
+        from: Address,
+        box_id: Hash,
+        fee: u64,
+        nonce: u64,
+        #[serde(default)]
+        chain_id: Option<u64>,
+        #[serde(default)]
+        valid_until_height: Option<u64>,
+        signature: Vec<u8>,
+    },
+
+    /// Publish the sealed outcomes and salt of an opened loot box. They must match the
+    /// commitment; the drawn outcome is minted to the player and the bond returned.
+    RevealLootBox {
+        from: Address,
+        box_id: Hash,
+        outcomes: Vec<LootOutcome>,
+        salt: Vec<u8>,
+        fee: u64,
+        nonce: u64,
+        #[serde(default)]
+        chain_id: Option<u64>,
+        #[serde(default)]
+        valid_until_height: Option<u64>,
+        signature: Vec<u8>,
+    },
+
+    /// Claim the bond of a loot box whose operator missed the reveal deadline
+    /// (signed by its player)
+    ForfeitLootBox {
+        from: Address,
+        box_id: Hash,
+        fee: u64,
+        nonce: u64,
+        #[serde(default)]
+        chain_id: Option<u64>,
+        #[serde(default)]
+        valid_until_height: Option<u64>,
+        signature: Vec<u8>,
+    },
+
+    /// Open the `season` leaderboard of collection `game_id`, keeping the best `capacity`
+    /// players until block `end_height`. Scores are submitted by `from` and the game
+    /// contracts listed in `submitters`. The board id is `leaderboard_id(from, game_id, season)`.
+    CreateLeaderboard {
+        from: Address,
+        game_id: String,
+        season: String,
+        capacity: u32,
+        end_height: u64,
+        submitters: Vec<Address>,
+        fee: u64,
+        nonce: u64,
+        #[serde(default)]
+        chain_id: Option<u64>,
+        #[serde(default)]
+        valid_until_height: Option<u64>,
+        signature: Vec<u8>,
+    },
+
+    /// Record `score` for `player` on a leaderboard (signed by its creator or a submitter).
+    /// Only a player's best score counts.
+    SubmitScore {
+        from: Address,
+        board_id: Hash,
+        player: Address,
+        score: u64,
+        fee: u64,
+        nonce: u64,
+        #[serde(default)]
+        chain_id: Option<u64>,
+        #[serde(default)]
+        valid_until_height: Option<u64>,
+        signature: Vec<u8>,
+    },
+
+    /// Schedule `season` of game `game_id` from block `start_height` to `end_height`,
+    /// running `hooks` at its boundaries. The first season of a game makes `from` its
+    /// season operator; later seasons must come from the operator and must not overlap.
+    RegisterSeason {
+        from: Address,
+        game_id: String,
+        season: String,
+        start_height: u64,
+        end_height: u64,
+        hooks: SeasonHooks,
+        fee: u64,
+        nonce: u64,
+        #[serde(default)]
+        chain_id: Option<u64>,
+        #[serde(default)]
+        valid_until_height: Option<u64>,
+        signature: Vec<u8>,
+    },
+
+    /// Open a staking pool for the assets of collection `game_id`, emitting
+    /// `reward_per_block` HAZE per block shared by the staked assets, funded with `deposit`.
+    /// Staked assets stay locked for `lock_blocks`. The pool id is `asset_pool_id(from, game_id)`.
+    CreateAssetPool {
+        from: Address,
+        game_id: String,
+        reward_per_block: u64,
+        lock_blocks: u64,
+        deposit: u64,
+        fee: u64,
+        nonce: u64,
+        #[serde(default)]
+        chain_id: Option<u64>,
+        #[serde(default)]
+        valid_until_height: Option<u64>,
+        signature: Vec<u8>,
+    },
+
+    /// Add `amount` HAZE to the reward reserve of an asset pool (anyone can fund a pool)
+    FundAssetPool {
+        from: Address,
+        pool_id: Hash,
+        amount: u64,
+        fee: u64,
+        nonce: u64,
+        #[serde(default)]
+        chain_id: Option<u64>,
+        #[serde(default)]
+        valid_until_height: Option<u64>,
+        signature: Vec<u8>,
+    },
+
+    /// Lock an asset of `from` in an asset pool of its collection to earn rewards
+    StakeAsset {
+        from: Address,
+        pool_id: Hash,
+        asset_id: Hash,
+        fee: u64,
+        nonce: u64,
+        #[serde(default)]
+        chain_id: Option<u64>,
+        #[serde(default)]
+        valid_until_height: Option<u64>,
+        signature: Vec<u8>,
+    },
+
+    /// Take a staked asset back once its lock period is over, with its unclaimed rewards
+    UnstakeAsset {
+        from: Address,
+        asset_id: Hash,
+        fee: u64,
+        nonce: u64,
+        #[serde(default)]
+        chain_id: Option<u64>,
+        #[serde(default)]
+        valid_until_height: Option<u64>,
+        signature: Vec<u8>,
+    },
+
+    /// Pay out the rewards of every asset `from` has staked in a pool
+    ClaimAssetRewards {
+        from: Address,
+        pool_id: Hash,
+        fee: u64,
+        nonce: u64,
+        #[serde(default)]
+        chain_id: Option<u64>,
+        #[serde(default)]
+        valid_until_height: Option<u64>,
+        signature: Vec<u8>,
+    },
+
+    /// Breed two assets of `from` from a collection with a breeding rule. The child, with id
+    /// `breeding_id(from, nonce)`, hatches a few blocks later (see `crate::breeding`).
+    Breed {
+        from: Address,
+        parent_a: Hash,
+        parent_b: Hash,
+        fee: u64,
+        nonce: u64,
+        #[serde(default)]
+        chain_id: Option<u64>,
+        #[serde(default)]
+        valid_until_height: Option<u64>,
+        signature: Vec<u8>,
+    },
+
+    /// Upgrade an asset of `from` from a collection with an upgrade rule, burning
+    /// `catalysts`. The outcome of upgrade `upgrade_id(from, nonce)` is rolled a few blocks
+    /// later (see `crate::upgrade`).
+    Upgrade {
+        from: Address,
+        asset_id: Hash,
+        catalysts: Vec<Hash>,
+        fee: u64,
+        nonce: u64,
+        #[serde(default)]
+        chain_id: Option<u64>,
+        #[serde(default)]
+        valid_until_height: Option<u64>,
+        signature: Vec<u8>,
+    },
+
+    /// Transfer governance tokens (`governance_token` config); the fee is paid in HAZE
+    TransferGovernance {
+        from: Address,
+        to: Address,
+        amount: u64,
+        fee: u64,
+        nonce: u64,
+        #[serde(default)]
+        chain_id: Option<u64>,
+        #[serde(default)]
+        valid_until_height: Option<u64>,
+        signature: Vec<u8>,
+    },
+
+    /// Stake governance tokens of `from` for governance weight; the fee is paid in HAZE
+    StakeGovernance {
+        from: Address,
+        amount: u64,
+        fee: u64,
+        nonce: u64,
+        #[serde(default)]
+        chain_id: Option<u64>,
+        #[serde(default)]
+        valid_until_height: Option<u64>,
+        signature: Vec<u8>,
+    },
+
+    /// Return staked governance tokens of `from` to its balance; the fee is paid in HAZE
+    UnstakeGovernance {
+        from: Address,
+        amount: u64,
+        fee: u64,
+        nonce: u64,
+        #[serde(default)]
+        chain_id: Option<u64>,
+        #[serde(default)]
+        valid_until_height: Option<u64>,
+        signature: Vec<u8>,
+    },
+
+    /// Set the commission validator `from` keeps from its delegators' rewards. Changes are
+    /// limited per commission epoch and increases apply after a notice period
+    /// (`consensus.commission_*`).
+    SetCommission {
+        from: Address,
+        rate_bps: u64,
+        fee: u64,
+        nonce: u64,
+        #[serde(default)]
+        chain_id: Option<u64>,
+        #[serde(default)]
+        valid_until_height: Option<u64>,
+        signature: Vec<u8>,
+    },
+
+    /// Vote, with `from`'s governance weight, for the committee minimum stake and weight cap.
+    /// The parameters change once votes for them hold more than two thirds of all governance
+    /// weight.
+    VoteCommitteeParams {
+        from: Address,
+        min_stake: u64,
+        max_weight_bps: u64,
+        fee: u64,
+        nonce: u64,
+        #[serde(default)]
+        chain_id: Option<u64>,
+        #[serde(default)]
+        valid_until_height: Option<u64>,
+        signature: Vec<u8>,
+    },
+}
+
+/// Side of an order book order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OrderSide {
+    /// Pay HAZE for assets of the collection
+    Buy,
+    /// Sell specific assets of the collection for HAZE
+    Sell,
+}
+
+/// Maximum `quantity` of one order
+pub const MAX_ORDER_QUANTITY: u64 = 100;
+
+/// Id of the order placed by `owner` with transaction nonce `nonce`:
+/// sha256("haze_order" || owner || nonce as u64 LE)
+pub fn order_id(owner: &Address, nonce: u64) -> Hash {
+    let mut data = Vec::with_capacity(10 + 32 + 8);
+    data.extend_from_slice(b"haze_order");
+    data.extend_from_slice(owner);
+    data.extend_from_slice(&nonce.to_le_bytes());
+    sha256(&data)
+}
+
+/// Maximum memo length of a payment request (bytes)
+pub const MAX_PAYMENT_MEMO_LEN: usize = 256;
+
+/// Id of the payment request created by `requester` with transaction nonce `nonce`:
+/// sha256("haze_payment_request" || requester || nonce as u64 LE)
+pub fn payment_request_id(requester: &Address, nonce: u64) -> Hash {
+    let mut data = Vec::with_capacity(20 + 32 + 8);
+    data.extend_from_slice(b"haze_payment_request");
+    data.extend_from_slice(requester);
+    data.extend_from_slice(&nonce.to_le_bytes());
+    sha256(&data)
+}
+
+/// Id of the subscription created by `payer` with transaction nonce `nonce`:
+/// sha256("haze_subscription" || payer || nonce as u64 LE)
+pub fn subscription_id(payer: &Address, nonce: u64) -> Hash {
+    let mut data = Vec::with_capacity(17 + 32 + 8);
+    data.extend_from_slice(b"haze_subscription");
+    data.extend_from_slice(payer);
+    data.extend_from_slice(&nonce.to_le_bytes());
+    sha256(&data)
+}
+
+/// Maximum number of entries a leaderboard keeps
+pub const MAX_LEADERBOARD_CAPACITY: u32 = 1000;
+
+/// Maximum number of score submitters of a leaderboard (besides its creator)
+pub const MAX_LEADERBOARD_SUBMITTERS: usize = 16;
+
+/// Maximum number of catalysts one upgrade burns
+pub const MAX_UPGRADE_CATALYSTS: usize = 16;
+
+/// Id of the `season` leaderboard of collection `game_id` created by `creator`:
+/// sha256("haze_leaderboard" || creator || game_id_len u32 LE || game_id || season)
+pub fn leaderboard_id(creator: &Address, game_id: &str, season: &str) -> Hash {
+    let mut data = Vec::with_capacity(16 + 32 + 4 + game_id.len() + season.len());
+    data.extend_from_slice(b"haze_leaderboard");
+    data.extend_from_slice(creator);
+    data.extend_from_slice(&(game_id.len() as u32).to_le_bytes());
+    data.extend_from_slice(game_id.as_bytes());
+    data.extend_from_slice(season.as_bytes());
+    sha256(&data)
+}
+
+/// Maximum number of collections one season freezes
+pub const MAX_SEASON_FREEZES: usize = 16;
+
+/// Collection changes run at the boundaries of a season (see `RegisterSeason`)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeasonHooks {
+    /// Collections frozen when the season ends, until the game's next season starts: the
+    /// game's own collection or collections in its namespace (`game_id/...`)
+    pub freeze_collections: Vec<String>,
+    /// Snapshot the holders of the game's collection when the season ends
+    pub snapshot_holders: bool,
+    /// Open a leaderboard of this capacity for the season when it starts
+    pub leaderboard_capacity: Option<u32>,
+    /// Score submitters of the season's leaderboard besides the operator
+    pub leaderboard_submitters: Vec<Address>,
+}
+
+/// Id of `season` of game `game_id`:
+/// sha256("haze_season" || game_id_len u32 LE || game_id || season)
+pub fn season_id(game_id: &str, season: &str) -> Hash {
+    let mut data = Vec::with_capacity(11 + 4 + game_id.len() + season.len());
+    data.extend_from_slice(b"haze_season");
+    data.extend_from_slice(&(game_id.len() as u32).to_le_bytes());
+    data.extend_from_slice(game_id.as_bytes());
+    data.extend_from_slice(season.as_bytes());
+    sha256(&data)
+}
+
+/// Id of the asset pool of collection `game_id` created by `creator`:
+/// sha256("haze_asset_pool" || creator || game_id)
+pub fn asset_pool_id(creator: &Address, game_id: &str) -> Hash {
+    let mut data = Vec::with_capacity(15 + 32 + game_id.len());
+    data.extend_from_slice(b"haze_asset_pool");
+    data.extend_from_slice(creator);
+    data.extend_from_slice(game_id.as_bytes());
+    sha256(&data)
+}
+
+/// Id of the breeding started by `breeder` with transaction nonce `nonce`, also the id of
+/// the child asset: sha256("haze_breeding" || breeder || nonce as u64 LE)
+pub fn breeding_id(breeder: &Address, nonce: u64) -> Hash {
+    let mut data = Vec::with_capacity(13 + 32 + 8);
+    data.extend_from_slice(b"haze_breeding");
+    data.extend_from_slice(breeder);
+    data.extend_from_slice(&nonce.to_le_bytes());
+    sha256(&data)
+}
+
+/// Id of the upgrade requested by `owner` with transaction nonce `nonce`:
+/// sha256("haze_upgrade" || owner || nonce as u64 LE)
+pub fn upgrade_id(owner: &Address, nonce: u64) -> Hash {
+    let mut data = Vec::with_capacity(12 + 32 + 8);
+    data.extend_from_slice(b"haze_upgrade");
+    data.extend_from_slice(owner);
+    data.extend_from_slice(&nonce.to_le_bytes());
+    sha256(&data)
+}
+
+/// Maximum number of outcomes sealed in one loot box
+pub const MAX_LOOT_OUTCOMES: usize = 256;
+
+/// Minimum salt length of a loot box commitment (bytes)
+pub const MIN_LOOT_SALT_LEN: usize = 16;
+
+/// One possible result of a loot box: the asset minted to the player when it is drawn
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LootOutcome {
+    /// Relative chance of the outcome (0 = never drawn)
+    pub weight: u32,
+    pub density: DensityLevel,
+    pub metadata: BTreeMap<String, String>,
+    pub attributes: Vec<Attribute>,
+}
+
+/// Id of the loot box sealed by `operator` with transaction nonce `nonce`, also the id of
+/// the asset it mints: sha256("haze_loot_box" || operator || nonce as u64 LE)
+pub fn loot_box_id(operator: &Address, nonce: u64) -> Hash {
+    let mut data = Vec::with_capacity(13 + 32 + 8);
+    data.extend_from_slice(b"haze_loot_box");
+    data.extend_from_slice(operator);
+    data.extend_from_slice(&nonce.to_le_bytes());
+    sha256(&data)
+}
+
+/// Commitment sealing loot box outcomes: sha256("haze_loot" || salt || bincode(outcomes))
+pub fn loot_commitment(salt: &[u8], outcomes: &[LootOutcome]) -> Hash {
+    let mut data = b"haze_loot".to_vec();
+    data.extend_from_slice(salt);
+    data.extend_from_slice(&bincode::serialize(outcomes).unwrap_or_default());
+    sha256(&data)
+}
+
+/// Maximum number of claims in one `CreateAirdrop` transaction
+pub const MAX_AIRDROP_CLAIMS: usize = 1000;
+
+/// One recipient of an airdrop
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AirdropClaim {
+    pub address: Address,
+    pub amount: u64,
+}
+
+impl AirdropClaim {
+    /// Merkle leaf: sha256(address || amount as u64 LE)
+    pub fn leaf(&self) -> Hash {
+        sha256(&[self.address.as_ref(), &self.amount.to_le_bytes()].concat())
+    }
+}
+
+/// Merkle root of an airdrop claim list, in list order
+pub fn airdrop_claims_root(claims: &[AirdropClaim]) -> Hash {
+    merkle_root(claims.iter().map(AirdropClaim::leaf).collect())
+}
+
+/// Contract hook invoked after Create/Update/Merge of an asset in a collection.
+/// The hook method receives the asset id and action; its gas is paid by the transaction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssetHook {
+    pub contract: Address,
+    pub method: String,
+    /// Gas available to the hook (must not exceed the node's `vm.gas_limit`)
+    pub gas_limit: u64,
+    pub on_failure: HookFailureMode,
+}
+
+/// What happens to the transaction when an asset hook fails
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HookFailureMode {
+    /// Log the failure and keep the asset change
+    Warn,
+    /// Fail the whole transaction
+    Revert,
 }
 
 /// Actions for Mistborn assets
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum AssetAction {
     Create,
     Update,
@@ -180,12 +1041,17 @@ pub enum AssetAction {
     Evaporate, // Decrease density
     Merge,
     Split,
+    Reveal, // Publish the plaintext of committed private metadata
+    Breed, // Child hatched from two parents (recorded by `Breed`, not a MistbornAsset action)
+    Upgrade, // Upgrade outcome (recorded by `Upgrade`, not a MistbornAsset action)
 }
 
 /// Asset data with density levels
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AssetData {
     pub density: DensityLevel,
+    /// Serialized in key order so encodings and hashes are deterministic
+    #[serde(serialize_with = "serialize_sorted_map")]
     pub metadata: HashMap<String, String>,
     pub attributes: Vec<Attribute>,
     pub game_id: Option<String>,
@@ -213,13 +1079,178 @@ impl DensityLevel {
 }
 
 /// Attribute for NFT
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Attribute {
     pub name: String,
-    pub value: String,
+    pub value: AttributeValue,
     pub rarity: Option<f64>,
 }
 
+/// Typed value of an attribute
+///
+/// In JSON a value is a string (`String`), an integer (`Int`), any other number (`Float`),
+/// a boolean (`Bool`) or `{ "enum": "<label>" }` (`Enum`), so attributes written before
+/// values were typed read back as strings. Binary encodings (wire format, storage) keep one
+/// string per value: `String` values as they are, so existing encodings do not change, and
+/// the other types as NUL, a type letter and the value (`i` and the decimal i64, `f` and the
+/// f64 bits as 16 hex digits, `b` and `true`/`false`, `e` and the label). A `String` value
+/// therefore cannot start with NUL.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeValue {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    String(String),
+    /// Label out of a fixed set (`"epic"`); compared as text like `String`
+    Enum(String),
+}
+
+/// Prefix of typed values in the binary encoding of `AttributeValue`
+const TYPED_ATTRIBUTE_PREFIX: char = '\0';
+
+impl AttributeValue {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            AttributeValue::Int(_) => "int",
+            AttributeValue::Float(_) => "float",
+            AttributeValue::Bool(_) => "bool",
+            AttributeValue::String(_) => "string",
+            AttributeValue::Enum(_) => "enum",
+        }
+    }
+
+    /// Binary encoding (see the type docs)
+    pub fn encode(&self) -> std::borrow::Cow<'_, str> {
+        match self {
+            AttributeValue::String(value) => std::borrow::Cow::Borrowed(value),
+            AttributeValue::Int(value) => format!("{TYPED_ATTRIBUTE_PREFIX}i{value}").into(),
+            AttributeValue::Float(value) => format!("{TYPED_ATTRIBUTE_PREFIX}f{:016x}", value.to_bits()).into(),
+            AttributeValue::Bool(value) => format!("{TYPED_ATTRIBUTE_PREFIX}b{value}").into(),
+            AttributeValue::Enum(label) => format!("{TYPED_ATTRIBUTE_PREFIX}e{label}").into(),
+        }
+    }
+
+    /// Value of a binary encoding
+    pub fn decode(encoded: String) -> std::result::Result<Self, String> {
+        let Some(typed) = encoded.strip_prefix(TYPED_ATTRIBUTE_PREFIX) else {
+            return Ok(AttributeValue::String(encoded));
+        };
+        let invalid = || format!("invalid typed attribute value {:?}", typed);
+        let mut chars = typed.chars();
+        let tag = chars.next().ok_or_else(invalid)?;
+        let body = chars.as_str();
+        match tag {
+            'i' => body.parse().map(AttributeValue::Int).map_err(|_| invalid()),
+            'f' if body.len() == 16 => u64::from_str_radix(body, 16)
+                .map(|bits| AttributeValue::Float(f64::from_bits(bits)))
+                .map_err(|_| invalid()),
+            'b' => body.parse().map(AttributeValue::Bool).map_err(|_| invalid()),
+            'e' => Ok(AttributeValue::Enum(body.to_string())),
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Reject values no asset may hold: non-finite floats, empty enum labels and strings
+    /// that would read back as typed values
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        match self {
+            AttributeValue::Float(value) if !value.is_finite() => Err(format!("float value must be finite, got {}", value)),
+            AttributeValue::Enum(label) if label.is_empty() => Err("enum label cannot be empty".to_string()),
+            AttributeValue::String(value) if value.starts_with(TYPED_ATTRIBUTE_PREFIX) => {
+                Err("string value cannot start with NUL".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Compare the value with a query literal, by the value's type: numbers numerically
+    /// (`None` if the literal is not a number), booleans with `true`/`false`, strings and
+    /// enum labels as text
+    pub fn compare(&self, literal: &str) -> Option<std::cmp::Ordering> {
+        match self {
+            AttributeValue::Int(value) => match literal.parse::<i64>() {
+                Ok(literal) => Some(value.cmp(&literal)),
+                Err(_) => (*value as f64).partial_cmp(&literal.parse::<f64>().ok()?),
+            },
+            AttributeValue::Float(value) => value.partial_cmp(&literal.parse::<f64>().ok()?),
+            AttributeValue::Bool(value) => Some(value.cmp(&literal.parse::<bool>().ok()?)),
+            AttributeValue::String(value) | AttributeValue::Enum(value) => Some(value.as_str().cmp(literal)),
+        }
+    }
+}
+
+impl std::fmt::Display for AttributeValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AttributeValue::Int(value) => write!(f, "{}", value),
+            AttributeValue::Float(value) => write!(f, "{}", value),
+            AttributeValue::Bool(value) => write!(f, "{}", value),
+            AttributeValue::String(value) | AttributeValue::Enum(value) => f.write_str(value),
+        }
+    }
+}
+
+impl From<String> for AttributeValue {
+    fn from(value: String) -> Self {
+        AttributeValue::String(value)
+    }
+}
+
+impl From<&str> for AttributeValue {
+    fn from(value: &str) -> Self {
+        AttributeValue::String(value.to_string())
+    }
+}
+
+impl Serialize for AttributeValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        if !serializer.is_human_readable() {
+            return serializer.serialize_str(&self.encode());
+        }
+        match self {
+            AttributeValue::Int(value) => serializer.serialize_i64(*value),
+            AttributeValue::Float(value) => serializer.serialize_f64(*value),
+            AttributeValue::Bool(value) => serializer.serialize_bool(*value),
+            AttributeValue::String(value) => serializer.serialize_str(value),
+            AttributeValue::Enum(label) => {
+                use serde::ser::SerializeMap;
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("enum", label)?;
+                map.end()
+            }
+        }
+    }
+}
+
+/// JSON shapes of an `AttributeValue`, tried in order
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ReadableAttributeValue {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    String(String),
+    Enum {
+        #[serde(rename = "enum")]
+        label: String,
+    },
+}
+
+impl<'de> Deserialize<'de> for AttributeValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        if !deserializer.is_human_readable() {
+            return AttributeValue::decode(String::deserialize(deserializer)?).map_err(serde::de::Error::custom);
+        }
+        Ok(match ReadableAttributeValue::deserialize(deserializer)? {
+            ReadableAttributeValue::Int(value) => AttributeValue::Int(value),
+            ReadableAttributeValue::Float(value) => AttributeValue::Float(value),
+            ReadableAttributeValue::Bool(value) => AttributeValue::Bool(value),
+            ReadableAttributeValue::String(value) => AttributeValue::String(value),
+            ReadableAttributeValue::Enum { label } => AttributeValue::Enum(label),
+        })
+    }
+}
+
 /// Permission level for asset access (granted to non-owners)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum PermissionLevel {
@@ -242,11 +1273,680 @@ pub struct AssetPermission {
     pub expires_at: Option<i64>,
 }
 
-/// Transaction hash
+/// Event log emitted by a contract during execution
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Log {
+    /// Contract that emitted the log
+    pub address: Address,
+    /// Indexed topics (at most 4); `topics[0]` is conventionally the event signature hash
+    pub topics: Vec<Hash>,
+    pub data: Vec<u8>,
+}
+
+/// 2048-bit bloom filter over log addresses and topics (one per block)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogsBloom(pub [u8; 256]);
+
+impl LogsBloom {
+    pub fn new() -> Self {
+        Self([0u8; 256])
+    }
+
+    /// Three bit positions (0..2048) derived from sha256(value)
+    fn bit_positions(value: &[u8]) -> [usize; 3] {
+        let hash = sha256(value);
+        let mut positions = [0usize; 3];
+        for (i, position) in positions.iter_mut().enumerate() {
+            *position = (((hash[2 * i] as usize) << 8) | hash[2 * i + 1] as usize) & 2047;
+        }
+        positions
+    }
+
+    pub fn accrue(&mut self, value: &[u8]) {
+        for bit in Self::bit_positions(value) {
+            self.0[bit / 8] |= 1 << (bit % 8);
+        }
+    }
+
+    /// Add a log's address and topics
+    pub fn accrue_log(&mut self, log: &Log) {
+        self.accrue(&log.address);
+        for topic in &log.topics {
+            self.accrue(topic);
+        }
+    }
+
+    /// False means `value` was definitely never added
+    pub fn contains(&self, value: &[u8]) -> bool {
+        Self::bit_positions(value)
+            .iter()
+            .all(|bit| self.0[bit / 8] & (1 << (bit % 8)) != 0)
+    }
+}
+
+impl Default for LogsBloom {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Serialize for LogsBloom {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for LogsBloom {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let bytes = Vec::<u8>::deserialize(deserializer)?;
+        let bits = bytes
+            .try_into()
+            .map_err(|bytes: Vec<u8>| serde::de::Error::invalid_length(bytes.len(), &"256 bytes"))?;
+        Ok(Self(bits))
+    }
+}
+
+/// Transaction hash (of the canonical body)
 impl Transaction {
     pub fn hash(&self) -> Hash {
-        let data = bincode::serialize(self).unwrap();
-        sha256(&data)
+        sha256(&wire_body(self))
+    }
+
+    /// Account that signs and pays for the transaction
+    pub fn sender(&self) -> &Address {
+        match self {
+            Transaction::Transfer { from, .. } => from,
+            Transaction::DeployContract { from, .. } => from,
+            Transaction::ContractCall { from, .. } => from,
+            Transaction::MistbornAsset { from, .. } => from,
+            Transaction::Stake { from, .. } => from,
+            Transaction::SetAssetPermissions { from, .. } => from,
+            Transaction::Unjail { from, .. } => from,
+            Transaction::SetAssetHook { from, .. } => from,
+            Transaction::CreateAirdrop { from, .. } => from,
+            Transaction::CreatePaymentRequest { from, .. } => from,
+            Transaction::PayPaymentRequest { from, .. } => from,
+            Transaction::PlaceOrder { from, .. } => from,
+            Transaction::CancelOrder { from, .. } => from,
+            Transaction::SetReferrer { from, .. } => from,
+            Transaction::CreateSubscription { from, .. } => from,
+            Transaction::CancelSubscription { from, .. } => from,
+            Transaction::CommitLootBox { from, .. } => from,
+            Transaction::OpenLootBox { from, .. } => from,
+            Transaction::RevealLootBox { from, .. } => from,
+            Transaction::ForfeitLootBox { from, .. } => from,
+            Transaction::CreateLeaderboard { from, .. } => from,
+            Transaction::SubmitScore { from, .. } => from,
+            Transaction::RegisterSeason { from, .. } => from,
+            Transaction::CreateAssetPool { from, .. } => from,
+            Transaction::FundAssetPool { from, .. } => from,
+            Transaction::StakeAsset { from, .. } => from,
+            Transaction::UnstakeAsset { from, .. } => from,
+            Transaction::ClaimAssetRewards { from, .. } => from,
+            Transaction::Breed { from, .. } => from,
+            Transaction::Upgrade { from, .. } => from,
+            Transaction::TransferGovernance { from, .. } => from,
+            Transaction::StakeGovernance { from, .. } => from,
+            Transaction::UnstakeGovernance { from, .. } => from,
+            Transaction::SetCommission { from, .. } => from,
+            Transaction::VoteCommitteeParams { from, .. } => from,
+        }
+    }
+
+    /// Fee the sender offers
+    pub fn fee(&self) -> u64 {
+        match self {
+            Transaction::Transfer { fee, .. } => *fee,
+            Transaction::DeployContract { fee, .. } => *fee,
+            Transaction::ContractCall { fee, .. } => *fee,
+            Transaction::MistbornAsset { fee, .. } => *fee,
+            Transaction::Stake { fee, .. } => *fee,
+            Transaction::SetAssetPermissions { fee, .. } => *fee,
+            Transaction::Unjail { fee, .. } => *fee,
+            Transaction::SetAssetHook { fee, .. } => *fee,
+            Transaction::CreateAirdrop { fee, .. } => *fee,
+            Transaction::CreatePaymentRequest { fee, .. } => *fee,
+            Transaction::PayPaymentRequest { fee, .. } => *fee,
+            Transaction::PlaceOrder { fee, .. } => *fee,
+            Transaction::CancelOrder { fee, .. } => *fee,
+            Transaction::SetReferrer { fee, .. } => *fee,
+            Transaction::CreateSubscription { fee, .. } => *fee,
+            Transaction::CancelSubscription { fee, .. } => *fee,
+            Transaction::CommitLootBox { fee, .. } => *fee,
+            Transaction::OpenLootBox { fee, .. } => *fee,
+            Transaction::RevealLootBox { fee, .. } => *fee,
+            Transaction::ForfeitLootBox { fee, .. } => *fee,
+            Transaction::CreateLeaderboard { fee, .. } => *fee,
+            Transaction::SubmitScore { fee, .. } => *fee,
+            Transaction::RegisterSeason { fee, .. } => *fee,
+            Transaction::CreateAssetPool { fee, .. } => *fee,
+            Transaction::FundAssetPool { fee, .. } => *fee,
+            Transaction::StakeAsset { fee, .. } => *fee,
+            Transaction::UnstakeAsset { fee, .. } => *fee,
+            Transaction::ClaimAssetRewards { fee, .. } => *fee,
+            Transaction::Breed { fee, .. } => *fee,
+            Transaction::Upgrade { fee, .. } => *fee,
+            Transaction::TransferGovernance { fee, .. } => *fee,
+            Transaction::StakeGovernance { fee, .. } => *fee,
+            Transaction::UnstakeGovernance { fee, .. } => *fee,
+            Transaction::SetCommission { fee, .. } => *fee,
+            Transaction::VoteCommitteeParams { fee, .. } => *fee,
+        }
+    }
+
+    /// Sender nonce (lane and sequence, see `nonce_lane`)
+    pub fn nonce(&self) -> u64 {
+        match self {
+            Transaction::Transfer { nonce, .. } => *nonce,
+            Transaction::DeployContract { nonce, .. } => *nonce,
+            Transaction::ContractCall { nonce, .. } => *nonce,
+            Transaction::MistbornAsset { nonce, .. } => *nonce,
+            Transaction::Stake { nonce, .. } => *nonce,
+            Transaction::SetAssetPermissions { nonce, .. } => *nonce,
+            Transaction::Unjail { nonce, .. } => *nonce,
+            Transaction::SetAssetHook { nonce, .. } => *nonce,
+            Transaction::CreateAirdrop { nonce, .. } => *nonce,
+            Transaction::CreatePaymentRequest { nonce, .. } => *nonce,
+            Transaction::PayPaymentRequest { nonce, .. } => *nonce,
+            Transaction::PlaceOrder { nonce, .. } => *nonce,
+            Transaction::CancelOrder { nonce, .. } => *nonce,
+            Transaction::SetReferrer { nonce, .. } => *nonce,
+            Transaction::CreateSubscription { nonce, .. } => *nonce,
+            Transaction::CancelSubscription { nonce, .. } => *nonce,
+            Transaction::CommitLootBox { nonce, .. } => *nonce,
+            Transaction::OpenLootBox { nonce, .. } => *nonce,
+            Transaction::RevealLootBox { nonce, .. } => *nonce,
+            Transaction::ForfeitLootBox { nonce, .. } => *nonce,
+            Transaction::CreateLeaderboard { nonce, .. } => *nonce,
+            Transaction::SubmitScore { nonce, .. } => *nonce,
+            Transaction::RegisterSeason { nonce, .. } => *nonce,
+            Transaction::CreateAssetPool { nonce, .. } => *nonce,
+            Transaction::FundAssetPool { nonce, .. } => *nonce,
+            Transaction::StakeAsset { nonce, .. } => *nonce,
+            Transaction::UnstakeAsset { nonce, .. } => *nonce,
+            Transaction::ClaimAssetRewards { nonce, .. } => *nonce,
+            Transaction::Breed { nonce, .. } => *nonce,
+            Transaction::Upgrade { nonce, .. } => *nonce,
+            Transaction::TransferGovernance { nonce, .. } => *nonce,
+            Transaction::StakeGovernance { nonce, .. } => *nonce,
+            Transaction::UnstakeGovernance { nonce, .. } => *nonce,
+            Transaction::SetCommission { nonce, .. } => *nonce,
+            Transaction::VoteCommitteeParams { nonce, .. } => *nonce,
+        }
+    }
+
+    /// Name of the variant, e.g. `Transfer`
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Transaction::Transfer { .. } => "Transfer",
+            Transaction::DeployContract { .. } => "DeployContract",
+            Transaction::ContractCall { .. } => "ContractCall",
+            Transaction::MistbornAsset { .. } => "MistbornAsset",
+            Transaction::Stake { .. } => "Stake",
+            Transaction::SetAssetPermissions { .. } => "SetAssetPermissions",
+            Transaction::Unjail { .. } => "Unjail",
+            Transaction::SetAssetHook { .. } => "SetAssetHook",
+            Transaction::CreateAirdrop { .. } => "CreateAirdrop",
+            Transaction::CreatePaymentRequest { .. } => "CreatePaymentRequest",
+            Transaction::PayPaymentRequest { .. } => "PayPaymentRequest",
+            Transaction::PlaceOrder { .. } => "PlaceOrder",
+            Transaction::CancelOrder { .. } => "CancelOrder",
+            Transaction::SetReferrer { .. } => "SetReferrer",
+            Transaction::CreateSubscription { .. } => "CreateSubscription",
+            Transaction::CancelSubscription { .. } => "CancelSubscription",
+            Transaction::CommitLootBox { .. } => "CommitLootBox",
+            Transaction::OpenLootBox { .. } => "OpenLootBox",
+            Transaction::RevealLootBox { .. } => "RevealLootBox",
+            Transaction::ForfeitLootBox { .. } => "ForfeitLootBox",
+            Transaction::CreateLeaderboard { .. } => "CreateLeaderboard",
+            Transaction::SubmitScore { .. } => "SubmitScore",
+            Transaction::RegisterSeason { .. } => "RegisterSeason",
+            Transaction::CreateAssetPool { .. } => "CreateAssetPool",
+            Transaction::FundAssetPool { .. } => "FundAssetPool",
+            Transaction::StakeAsset { .. } => "StakeAsset",
+            Transaction::UnstakeAsset { .. } => "UnstakeAsset",
+            Transaction::ClaimAssetRewards { .. } => "ClaimAssetRewards",
+            Transaction::Breed { .. } => "Breed",
+            Transaction::Upgrade { .. } => "Upgrade",
+            Transaction::TransferGovernance { .. } => "TransferGovernance",
+            Transaction::StakeGovernance { .. } => "StakeGovernance",
+            Transaction::UnstakeGovernance { .. } => "UnstakeGovernance",
+            Transaction::SetCommission { .. } => "SetCommission",
+            Transaction::VoteCommitteeParams { .. } => "VoteCommitteeParams",
+        }
+    }
+
+    /// Chain the transaction is bound to, if any
+    pub fn chain_id(&self) -> Option<u64> {
+        match self {
+            Transaction::Transfer { chain_id, .. } => *chain_id,
+            Transaction::DeployContract { chain_id, .. } => *chain_id,
+            Transaction::ContractCall { chain_id, .. } => *chain_id,
+            Transaction::MistbornAsset { chain_id, .. } => *chain_id,
+            Transaction::Stake { chain_id, .. } => *chain_id,
+            Transaction::SetAssetPermissions { chain_id, .. } => *chain_id,
+            Transaction::Unjail { chain_id, .. } => *chain_id,
+            Transaction::SetAssetHook { chain_id, .. } => *chain_id,
+            Transaction::CreateAirdrop { chain_id, .. } => *chain_id,
+            Transaction::CreatePaymentRequest { chain_id, .. } => *chain_id,
+            Transaction::PayPaymentRequest { chain_id, .. } => *chain_id,
+            Transaction::PlaceOrder { chain_id, .. } => *chain_id,
+            Transaction::CancelOrder { chain_id, .. } => *chain_id,
+            Transaction::SetReferrer { chain_id, .. } => *chain_id,
+            Transaction::CreateSubscription { chain_id, .. } => *chain_id,
+            Transaction::CancelSubscription { chain_id, .. } => *chain_id,
+            Transaction::CommitLootBox { chain_id, .. } => *chain_id,
+            Transaction::OpenLootBox { chain_id, .. } => *chain_id,
+            Transaction::RevealLootBox { chain_id, .. } => *chain_id,
+            Transaction::ForfeitLootBox { chain_id, .. } => *chain_id,
+            Transaction::CreateLeaderboard { chain_id, .. } => *chain_id,
+            Transaction::SubmitScore { chain_id, .. } => *chain_id,
+            Transaction::RegisterSeason { chain_id, .. } => *chain_id,
+            Transaction::CreateAssetPool { chain_id, .. } => *chain_id,
+            Transaction::FundAssetPool { chain_id, .. } => *chain_id,
+            Transaction::StakeAsset { chain_id, .. } => *chain_id,
+            Transaction::UnstakeAsset { chain_id, .. } => *chain_id,
+            Transaction::ClaimAssetRewards { chain_id, .. } => *chain_id,
+            Transaction::Breed { chain_id, .. } => *chain_id,
+            Transaction::Upgrade { chain_id, .. } => *chain_id,
+            Transaction::TransferGovernance { chain_id, .. } => *chain_id,
+            Transaction::StakeGovernance { chain_id, .. } => *chain_id,
+            Transaction::UnstakeGovernance { chain_id, .. } => *chain_id,
+            Transaction::SetCommission { chain_id, .. } => *chain_id,
+            Transaction::VoteCommitteeParams { chain_id, .. } => *chain_id,
+        }
+    }
+
+    /// Last block height the transaction can be included at, if any
+    pub fn valid_until_height(&self) -> Option<u64> {
+        match self {
+            Transaction::Transfer { valid_until_height, .. } => *valid_until_height,
+            Transaction::DeployContract { valid_until_height, .. } => *valid_until_height,
+            Transaction::ContractCall { valid_until_height, .. } => *valid_until_height,
+            Transaction::MistbornAsset { valid_until_height, .. } => *valid_until_height,
+            Transaction::Stake { valid_until_height, .. } => *valid_until_height,
+            Transaction::SetAssetPermissions { valid_until_height, .. } => *valid_until_height,
+            Transaction::Unjail { valid_until_height, .. } => *valid_until_height,
+            Transaction::SetAssetHook { valid_until_height, .. } => *valid_until_height,
+            Transaction::CreateAirdrop { valid_until_height, .. } => *valid_until_height,
+            Transaction::CreatePaymentRequest { valid_until_height, .. } => *valid_until_height,
+            Transaction::PayPaymentRequest { valid_until_height, .. } => *valid_until_height,
+            Transaction::PlaceOrder { valid_until_height, .. } => *valid_until_height,
+            Transaction::CancelOrder { valid_until_height, .. } => *valid_until_height,
+            Transaction::SetReferrer { valid_until_height, .. } => *valid_until_height,
+            Transaction::CreateSubscription { valid_until_height, .. } => *valid_until_height,
+            Transaction::CancelSubscription { valid_until_height, .. } => *valid_until_height,
+            Transaction::CommitLootBox { valid_until_height, .. } => *valid_until_height,
+            Transaction::OpenLootBox { valid_until_height, .. } => *valid_until_height,
+            Transaction::RevealLootBox { valid_until_height, .. } => *valid_until_height,
+            Transaction::ForfeitLootBox { valid_until_height, .. } => *valid_until_height,
+            Transaction::CreateLeaderboard { valid_until_height, .. } => *valid_until_height,
+            Transaction::SubmitScore { valid_until_height, .. } => *valid_until_height,
+            Transaction::RegisterSeason { valid_until_height, .. } => *valid_until_height,
+            Transaction::CreateAssetPool { valid_until_height, .. } => *valid_until_height,
+            Transaction::FundAssetPool { valid_until_height, .. } => *valid_until_height,
+            Transaction::StakeAsset { valid_until_height, .. } => *valid_until_height,
+            Transaction::UnstakeAsset { valid_until_height, .. } => *valid_until_height,
+            Transaction::ClaimAssetRewards { valid_until_height, .. } => *valid_until_height,
+            Transaction::Breed { valid_until_height, .. } => *valid_until_height,
+            Transaction::Upgrade { valid_until_height, .. } => *valid_until_height,
+            Transaction::TransferGovernance { valid_until_height, .. } => *valid_until_height,
+            Transaction::StakeGovernance { valid_until_height, .. } => *valid_until_height,
+            Transaction::UnstakeGovernance { valid_until_height, .. } => *valid_until_height,
+            Transaction::SetCommission { valid_until_height, .. } => *valid_until_height,
+            Transaction::VoteCommitteeParams { valid_until_height, .. } => *valid_until_height,
+        }
+    }
+
+    /// Whether `valid_until_height` keeps the transaction out of a block at `height`
+    pub fn is_expired_at(&self, height: u64) -> bool {
+        self.valid_until_height().is_some_and(|last| height > last)
+    }
+
+    /// Balance the transaction takes from its sender, as far as the transaction itself tells:
+    /// the fee plus any amount it pays, escrows or stakes. Costs only known from state
+    /// (breeding and upgrade rule fees, contract gas, Mistborn gas charged to the owner) are
+    /// not included.
+    pub fn spend(&self) -> u64 {
+        match self {
+            Transaction::Transfer { amount, fee, .. }
+            | Transaction::PayPaymentRequest { amount, fee, .. }
+            | Transaction::CreateSubscription { amount, fee, .. }
+            | Transaction::FundAssetPool { amount, fee, .. } => amount.saturating_add(*fee),
+            Transaction::Stake { amount, .. } => *amount,
+            Transaction::CreateAirdrop { claims, fee, .. } => {
+                claims.iter().fold(*fee, |total, claim| total.saturating_add(claim.amount))
+            }
+            Transaction::PlaceOrder { side: OrderSide::Buy, price, quantity, fee, .. } => {
+                price.saturating_mul(*quantity).saturating_add(*fee)
+            }
+            Transaction::CommitLootBox { bond, fee, .. } => bond.saturating_add(*fee),
+            Transaction::CreateAssetPool { deposit, fee, .. } => deposit.saturating_add(*fee),
+            Transaction::MistbornAsset { .. } => 0,
+            other => other.fee(),
+        }
+    }
+
+    /// Replace the signature (used when the node signs on behalf of a custodial account)
+    pub fn set_signature(&mut self, new_signature: Vec<u8>) {
+        let signature = match self {
+            Transaction::Transfer { signature, .. } => signature,
+            Transaction::DeployContract { signature, .. } => signature,
+            Transaction::ContractCall { signature, .. } => signature,
+            Transaction::MistbornAsset { signature, .. } => signature,
+            Transaction::Stake { signature, .. } => signature,
+            Transaction::SetAssetPermissions { signature, .. } => signature,
+            Transaction::Unjail { signature, .. } => signature,
+            Transaction::SetAssetHook { signature, .. } => signature,
+            Transaction::CreateAirdrop { signature, .. } => signature,
+            Transaction::CreatePaymentRequest { signature, .. } => signature,
+            Transaction::PayPaymentRequest { signature, .. } => signature,
+            Transaction::PlaceOrder { signature, .. } => signature,
+            Transaction::CancelOrder { signature, .. } => signature,
+            Transaction::SetReferrer { signature, .. } => signature,
+            Transaction::CreateSubscription { signature, .. } => signature,
+            Transaction::CancelSubscription { signature, .. } => signature,
+            Transaction::CommitLootBox { signature, .. } => signature,
+            Transaction::OpenLootBox { signature, .. } => signature,
+            Transaction::RevealLootBox { signature, .. } => signature,
+            Transaction::ForfeitLootBox { signature, .. } => signature,
+            Transaction::CreateLeaderboard { signature, .. } => signature,
+            Transaction::SubmitScore { signature, .. } => signature,
+            Transaction::RegisterSeason { signature, .. } => signature,
+            Transaction::CreateAssetPool { signature, .. } => signature,
+            Transaction::FundAssetPool { signature, .. } => signature,
+            Transaction::StakeAsset { signature, .. } => signature,
+            Transaction::UnstakeAsset { signature, .. } => signature,
+            Transaction::ClaimAssetRewards { signature, .. } => signature,
+            Transaction::Breed { signature, .. } => signature,
+            Transaction::Upgrade { signature, .. } => signature,
+            Transaction::TransferGovernance { signature, .. } => signature,
+            Transaction::StakeGovernance { signature, .. } => signature,
+            Transaction::UnstakeGovernance { signature, .. } => signature,
+            Transaction::SetCommission { signature, .. } => signature,
+            Transaction::VoteCommitteeParams { signature, .. } => signature,
+        };
+        *signature = new_signature;
+    }
+}
+
+/// Transaction with its hash computed at most once.
+///
+/// Used where the same transaction is hashed repeatedly (pool admission,
+/// block building, merkle roots, pool removal).
+#[derive(Debug, Clone)]
+pub struct TxEnvelope {
+    tx: Transaction,
+    hash: std::sync::OnceLock<Hash>,
+}
+
+impl TxEnvelope {
+    pub fn new(tx: Transaction) -> Self {
+        Self { tx, hash: std::sync::OnceLock::new() }
+    }
+
+    /// Wrap a transaction whose hash is already known
+    pub fn with_hash(tx: Transaction, hash: Hash) -> Self {
+        Self { tx, hash: std::sync::OnceLock::from(hash) }
+    }
+
+    /// Transaction hash, computed on first use
+    pub fn hash(&self) -> Hash {
+        *self.hash.get_or_init(|| self.tx.hash())
+    }
+
+    pub fn transaction(&self) -> &Transaction {
+        &self.tx
+    }
+
+    pub fn into_transaction(self) -> Transaction {
+        self.tx
+    }
+}
+
+impl From<Transaction> for TxEnvelope {
+    fn from(tx: Transaction) -> Self {
+        Self::new(tx)
+    }
+}
+
+impl std::ops::Deref for TxEnvelope {
+    type Target = Transaction;
+
+    fn deref(&self) -> &Transaction {
+        &self.tx
+    }
+}
+
+/// Serialize a string-keyed map in key order, so encodings and hashes do not depend on the hasher
+pub(crate) fn serialize_sorted_map<S: Serializer, V: Serialize>(
+    map: &HashMap<String, V>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
+
+// Canonical wire format
+//
+// Every encoded object is `magic (4) | version (1) | kind (1) | body length (u32 LE) | body`.
+// The body is bincode (fixed-width little-endian integers, fields in declaration
+// order, maps in key order). Later versions should only append fields after the
+// existing ones; a decoder ignores such trailing body bytes when the message
+// carries a newer version and rejects them otherwise, so each value has exactly
+// one encoding per version. Hashes cover the body only.
+//
+// Version 2 added `protocol_version` and `state_version` to the block header and
+// version 3 added `signals`, which moved the rest of a block body: older headers
+// and blocks still decode (as the protocol of their version), but older decoders
+// cannot read newer blocks, so blocks are only sent to peers whose handshake
+// announced the current block format.
+
+/// Magic prefix of canonically encoded objects
+pub const WIRE_MAGIC: [u8; 4] = *b"HAZE";
+/// Current wire format version
+pub const WIRE_VERSION: u8 = 3;
+/// Peer protocol version of this build, announced in the handshake and block headers
+pub const PROTOCOL_VERSION: u32 = 3;
+/// Oldest peer protocol this build still talks to (blocks are only sent to protocol 3 peers)
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+/// Version of the state database layout and state root computation (version 2 derives the
+/// account and asset shard roots from sparse merkle tries)
+pub const STATE_VERSION: u32 = 2;
+/// Envelope bytes before the body
+pub const WIRE_HEADER_SIZE: usize = 10;
+
+/// Object kind tag in the wire envelope
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum WireKind {
+    BlockHeader = 1,
+    Block = 2,
+    Transaction = 3,
+}
+
+/// Canonical body encoding (infallible for the plain data types in this module)
+fn wire_body<T: Serialize>(value: &T) -> Vec<u8> {
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .serialize(value)
+        .expect("wire types always serialize")
+}
+
+/// Versioned canonical encoding used for hashing, gossip and block storage
+pub trait WireFormat: Serialize + DeserializeOwned {
+    const KIND: WireKind;
+
+    /// Encode with the current version envelope
+    fn to_wire(&self) -> Vec<u8> {
+        let body = wire_body(self);
+        let mut out = Vec::with_capacity(WIRE_HEADER_SIZE + body.len());
+        out.extend_from_slice(&WIRE_MAGIC);
+        out.push(WIRE_VERSION);
+        out.push(Self::KIND as u8);
+        out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        out.extend_from_slice(&body);
+        out
+    }
+
+    /// Decode an envelope of this object kind
+    fn from_wire(bytes: &[u8]) -> Result<Self> {
+        let (version, body) = wire_envelope(bytes, Self::KIND)?;
+        Self::decode_body(version, body)
+    }
+
+    /// Decode a body of wire version `version`; types whose layout changed read older
+    /// versions here
+    fn decode_body(version: u8, body: &[u8]) -> Result<Self> {
+        decode_versioned(version, body)
+    }
+}
+
+/// Decode a body laid out as `T`, ignoring trailing fields of newer versions
+fn decode_versioned<T: DeserializeOwned>(version: u8, body: &[u8]) -> Result<T> {
+    let options = bincode::DefaultOptions::new().with_fixint_encoding();
+    let decoded = if version > WIRE_VERSION {
+        // Newer encoder: fields we do not know about follow ours
+        options.allow_trailing_bytes().deserialize(body)
+    } else {
+        options.reject_trailing_bytes().deserialize(body)
+    };
+    decoded.map_err(|e| invalid_wire(e.to_string()))
+}
+
+fn invalid_wire(msg: String) -> HazeError {
+    HazeError::Serialization(format!("Invalid wire encoding: {msg}"))
+}
+
+/// Check an envelope of object kind `kind`, returning its version and body
+fn wire_envelope(bytes: &[u8], kind: WireKind) -> Result<(u8, &[u8])> {
+    if bytes.len() < WIRE_HEADER_SIZE || bytes[..4] != WIRE_MAGIC {
+        return Err(invalid_wire("missing magic prefix".to_string()));
+    }
+    let version = bytes[4];
+    if version == 0 {
+        return Err(invalid_wire("version 0".to_string()));
+    }
+    if bytes[5] != kind as u8 {
+        return Err(invalid_wire(format!("expected kind {}, got {}", kind as u8, bytes[5])));
+    }
+    let body_len = u32::from_le_bytes(bytes[6..10].try_into().expect("4 bytes")) as usize;
+    let body = &bytes[WIRE_HEADER_SIZE..];
+    if body.len() != body_len {
+        return Err(invalid_wire(format!("body is {} bytes, header says {}", body.len(), body_len)));
+    }
+    Ok((version, body))
+}
+
+impl Block {
+    /// Decode only the header of a wire-encoded block; the header is the first field of the
+    /// body, so the transactions are never deserialized
+    pub fn header_from_wire(bytes: &[u8]) -> Result<BlockHeader> {
+        let (version, body) = wire_envelope(bytes, WireKind::Block)?;
+        Self::header_from_body(version, body)
+    }
+
+    /// Decode only the header of a block body of wire version `version`
+    pub fn header_from_body(version: u8, body: &[u8]) -> Result<BlockHeader> {
+        let options = bincode::DefaultOptions::new().with_fixint_encoding().allow_trailing_bytes();
+        let decoded = match version {
+            1 => options.deserialize::<BlockHeaderV1>(body).map(BlockHeader::from),
+            2 => options.deserialize::<BlockHeaderV2>(body).map(BlockHeader::from),
+            _ => options.deserialize(body),
+        };
+        decoded.map_err(|e| invalid_wire(e.to_string()))
+    }
+}
+
+impl WireFormat for BlockHeader {
+    const KIND: WireKind = WireKind::BlockHeader;
+
+    fn decode_body(version: u8, body: &[u8]) -> Result<Self> {
+        match version {
+            1 => decode_versioned::<BlockHeaderV1>(version, body).map(Self::from),
+            2 => decode_versioned::<BlockHeaderV2>(version, body).map(Self::from),
+            _ => decode_versioned(version, body),
+        }
+    }
+}
+
+impl WireFormat for Block {
+    const KIND: WireKind = WireKind::Block;
+
+    fn decode_body(version: u8, body: &[u8]) -> Result<Self> {
+        match version {
+            1 => decode_versioned::<LegacyBlock<BlockHeaderV1>>(version, body).map(Self::from),
+            2 => decode_versioned::<LegacyBlock<BlockHeaderV2>>(version, body).map(Self::from),
+            _ => decode_versioned(version, body),
+        }
+    }
+}
+
+impl WireFormat for Transaction {
+    const KIND: WireKind = WireKind::Transaction;
+}
+
+/// Serde adapter embedding a value as its wire encoding (`#[serde(with = "wire_bytes")]`)
+pub mod wire_bytes {
+    use super::*;
+
+    pub fn serialize<T: WireFormat, S: Serializer>(value: &T, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&value.to_wire())
+    }
+
+    pub fn deserialize<'de, T: WireFormat, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<T, D::Error> {
+        let bytes = Vec::<u8>::deserialize(deserializer)?;
+        T::from_wire(&bytes).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Serde adapter for a list of wire-encoded values
+pub mod wire_bytes_vec {
+    use super::*;
+
+    pub fn serialize<T: WireFormat, S: Serializer>(values: &[T], serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_seq(values.iter().map(WireFormat::to_wire))
+    }
+
+    pub fn deserialize<'de, T: WireFormat, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Vec<T>, D::Error> {
+        let items = Vec::<Vec<u8>>::deserialize(deserializer)?;
+        items
+            .iter()
+            .map(|bytes| T::from_wire(bytes).map_err(serde::de::Error::custom))
+            .collect()
+    }
+}
+
+/// Compare `actual` with `tests/golden/<name>.hex`; set `HAZE_UPDATE_GOLDEN=1` to rewrite the file
+#[cfg(test)]
+pub(crate) fn check_golden(name: &str, actual: &[u8]) {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{name}.hex"));
+    if std::env::var_os("HAZE_UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, format!("{}\n", hex::encode(actual))).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("missing golden file {}; run with HAZE_UPDATE_GOLDEN=1", path.display()));
+    assert_eq!(
+        hex::encode(actual),
+        expected.trim(),
+        "encoding of {name} changed; if intentional, bump WIRE_VERSION and regenerate with HAZE_UPDATE_GOLDEN=1"
+    );
+}
+
+#[cfg(test)]
+pub(crate) fn golden_transfer() -> Transaction {
+    Transaction::Transfer {
+        from: [1u8; 32],
+        to: [2u8; 32],
+        amount: 1000,
+        fee: 10,
+        nonce: 7,
+        chain_id: Some(42),
+        valid_until_height: Some(100),
+        signature: vec![9u8; 64],
     }
 }
 
@@ -254,6 +1954,199 @@ impl Transaction {
 mod tests {
     use super::*;
 
+    fn golden_asset_tx() -> Transaction {
+        let metadata: HashMap<String, String> = [("name", "Sword"), ("power", "9"), ("class", "weapon"), ("a", "")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        Transaction::MistbornAsset {
+            from: [3u8; 32],
+            action: AssetAction::Create,
+            asset_id: [4u8; 32],
+            data: AssetData {
+                density: DensityLevel::Light,
+                metadata,
+                attributes: vec![Attribute { name: "rarity".to_string(), value: "epic".into(), rarity: Some(0.5) }],
+                game_id: Some("game1".to_string()),
+                owner: [3u8; 32],
+            },
+            fee: 5,
+            nonce: 0,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![8u8; 64],
+        }
+    }
+
+    fn golden_block() -> Block {
+        let mut header = BlockHeader {
+            hash: [0; 32],
+            parent_hash: [5u8; 32],
+            height: 12,
+            timestamp: 1_700_000_000,
+            validator: [6u8; 32],
+            merkle_root: [7u8; 32],
+            state_root: [8u8; 32],
+            wave_number: 3,
+            committee_id: 2,
+            protocol_version: PROTOCOL_VERSION,
+            state_version: STATE_VERSION,
+            signals: 1,
+        };
+        header.hash = header.compute_hash(&network_id(0, &[]));
+        Block {
+            header,
+            transactions: vec![golden_transfer(), golden_asset_tx()],
+            dag_references: vec![[10u8; 32]],
+        }
+    }
+
+    /// Bytes of `tests/golden/<name>.hex`
+    fn read_golden(name: &str) -> Vec<u8> {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/golden")
+            .join(format!("{name}.hex"));
+        hex::decode(std::fs::read_to_string(path).unwrap().trim()).unwrap()
+    }
+
+    #[test]
+    fn test_wire_format_golden() {
+        check_golden("transfer_v3", &golden_transfer().to_wire());
+        check_golden("mistborn_asset_v3", &golden_asset_tx().to_wire());
+        check_golden("block_v3", &golden_block().to_wire());
+        check_golden("transfer_hash", &golden_transfer().hash());
+        check_golden("mistborn_asset_hash", &golden_asset_tx().hash());
+        check_golden("block_hash", &golden_block().header.hash);
+    }
+
+    #[test]
+    fn test_wire_v1_still_decodes() {
+        // Version-1 transactions only differ in the envelope
+        let transfer = Transaction::from_wire(&read_golden("transfer_v1")).unwrap();
+        assert_eq!(transfer.hash(), golden_transfer().hash());
+        let asset = Transaction::from_wire(&read_golden("mistborn_asset_v1")).unwrap();
+        assert_eq!(asset.hash(), golden_asset_tx().hash());
+
+        // Version-1 blocks decode as protocol 1 and keep their hash
+        let v1 = read_golden("block_v1");
+        let block = Block::from_wire(&v1).unwrap();
+        assert_eq!((block.header.protocol_version, block.header.state_version), (1, 1));
+        assert_eq!(block.header.hash.as_slice(), read_golden("block_v1_hash").as_slice());
+        assert_eq!(block.header.compute_hash(&network_id(0, &[])), block.header.hash);
+        assert_eq!(block.transactions.len(), 2);
+        assert_eq!(Block::header_from_wire(&v1).unwrap().hash, block.header.hash);
+
+        // Re-encoded in the current version, the block still decodes to the same hash
+        let reencoded = Block::from_wire(&block.to_wire()).unwrap();
+        assert_eq!(reencoded.header.protocol_version, 1);
+        assert_eq!(reencoded.header.compute_hash(&network_id(0, &[])), block.header.hash);
+
+        // Protocol 2 hashes commit to the version fields
+        let mut header = golden_block().header;
+        header.state_version += 1;
+        assert_ne!(header.compute_hash(&network_id(0, &[])), golden_block().header.hash);
+    }
+
+    #[test]
+    fn test_wire_v2_still_decodes() {
+        let transfer = Transaction::from_wire(&read_golden("transfer_v2")).unwrap();
+        assert_eq!(transfer.hash(), golden_transfer().hash());
+
+        // Version-2 blocks decode as protocol 2 without signals and keep their hash
+        let v2 = read_golden("block_v2");
+        let block = Block::from_wire(&v2).unwrap();
+        assert_eq!((block.header.protocol_version, block.header.signals), (2, 0));
+        assert_eq!(block.header.hash.as_slice(), read_golden("block_v2_hash").as_slice());
+        assert_eq!(block.header.compute_hash(&network_id(0, &[])), block.header.hash);
+        assert_eq!(Block::header_from_wire(&v2).unwrap().hash, block.header.hash);
+        assert_eq!(Block::from_wire(&block.to_wire()).unwrap().header.compute_hash(&network_id(0, &[])), block.header.hash);
+
+        // Protocol 3 hashes commit to the signals
+        let mut header = golden_block().header;
+        header.signals = 0;
+        assert_ne!(header.compute_hash(&network_id(0, &[])), golden_block().header.hash);
+    }
+
+    #[test]
+    fn test_wire_roundtrip() {
+        let block = golden_block();
+        let decoded = Block::from_wire(&block.to_wire()).unwrap();
+        assert_eq!(decoded.to_wire(), block.to_wire());
+        assert_eq!(decoded.header.compute_hash(&network_id(0, &[])), block.header.hash);
+
+        // The header hash does not depend on the stored hash field
+        let mut header = block.header.clone();
+        header.hash = [0xff; 32];
+        assert_eq!(header.compute_hash(&network_id(0, &[])), block.header.hash);
+    }
+
+    #[test]
+    fn test_wire_rejects_malformed_input() {
+        let tx = golden_transfer();
+        let bytes = tx.to_wire();
+
+        // Wrong kind, bad magic, truncated body, version 0
+        assert!(Block::from_wire(&bytes).is_err());
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] = b'X';
+        assert!(Transaction::from_wire(&bad_magic).is_err());
+        assert!(Transaction::from_wire(&bytes[..bytes.len() - 1]).is_err());
+        let mut v0 = bytes.clone();
+        v0[4] = 0;
+        assert!(Transaction::from_wire(&v0).is_err());
+
+        // Trailing body bytes are only accepted from newer versions
+        let mut extended = bytes.clone();
+        extended.extend_from_slice(&[0xaa, 0xbb]);
+        let body_len = (extended.len() - WIRE_HEADER_SIZE) as u32;
+        extended[6..10].copy_from_slice(&body_len.to_le_bytes());
+        assert!(Transaction::from_wire(&extended).is_err());
+        extended[4] = WIRE_VERSION + 1;
+        assert_eq!(Transaction::from_wire(&extended).unwrap().hash(), tx.hash());
+    }
+
+    #[test]
+    fn test_metadata_encoding_is_order_independent() {
+        let tx = golden_asset_tx();
+        let Transaction::MistbornAsset { data, .. } = &tx else { unreachable!() };
+        for _ in 0..8 {
+            let mut reordered = data.clone();
+            // Each map gets a fresh hasher seed, so iteration order varies
+            reordered.metadata = data.metadata.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+            assert_eq!(wire_body(&reordered), wire_body(data));
+        }
+    }
+
+    #[test]
+    fn test_attribute_value_encodings() {
+        let values = [
+            AttributeValue::Int(-42),
+            AttributeValue::Float(2.5),
+            AttributeValue::Bool(true),
+            AttributeValue::String("epic".to_string()),
+            AttributeValue::Enum("mage".to_string()),
+        ];
+        for value in values {
+            let json = serde_json::to_value(&value).unwrap();
+            assert_eq!(serde_json::from_value::<AttributeValue>(json).unwrap(), value);
+            let binary = bincode::DefaultOptions::new().with_fixint_encoding()
+                .deserialize::<AttributeValue>(&wire_body(&value)).unwrap();
+            assert_eq!(binary, value);
+        }
+        // Untyped values keep the encodings they had as plain strings
+        assert_eq!(wire_body(&AttributeValue::from("epic")), wire_body(&"epic".to_string()));
+        assert_eq!(serde_json::to_value(AttributeValue::from("epic")).unwrap(), serde_json::json!("epic"));
+        assert_eq!(serde_json::to_value(AttributeValue::Enum("mage".to_string())).unwrap(), serde_json::json!({"enum": "mage"}));
+        assert!(AttributeValue::decode("\0x1".to_string()).is_err());
+        assert!(AttributeValue::Float(f64::NAN).validate().is_err());
+        assert!(AttributeValue::from("\0i1").validate().is_err());
+
+        assert_eq!(AttributeValue::Int(10).compare("9"), Some(std::cmp::Ordering::Greater));
+        assert_eq!(AttributeValue::String("10".to_string()).compare("9"), Some(std::cmp::Ordering::Less));
+        assert_eq!(AttributeValue::Int(10).compare("ten"), None);
+        assert_eq!(AttributeValue::Bool(false).compare("false"), Some(std::cmp::Ordering::Equal));
+    }
+
     #[test]
     fn test_sha256_hash() {
         let data = b"Hello, HAZE!";
@@ -311,6 +2204,31 @@ mod tests {
         assert_ne!(hash1, [0u8; 32]);
     }
 
+    #[test]
+    fn test_tx_envelope_caches_hash() {
+        let tx = golden_transfer();
+        let envelope = TxEnvelope::from(tx.clone());
+        assert_eq!(envelope.hash(), tx.hash());
+        assert_eq!(envelope.clone().hash(), tx.hash());
+        assert_eq!(TxEnvelope::with_hash(tx.clone(), [7u8; 32]).hash(), [7u8; 32]);
+        assert_eq!(envelope.into_transaction().hash(), tx.hash());
+    }
+
+    #[test]
+    fn test_logs_bloom() {
+        let log = Log {
+            address: [1u8; 32],
+            topics: vec![[2u8; 32]],
+            data: vec![],
+        };
+        let mut bloom = LogsBloom::new();
+        assert!(!bloom.contains(&log.address));
+        bloom.accrue_log(&log);
+        assert!(bloom.contains(&log.address));
+        assert!(bloom.contains(&log.topics[0]));
+        assert!(!bloom.contains(&[3u8; 32]));
+    }
+
     #[test]
     fn test_density_level_max_size() {
         assert_eq!(DensityLevel::Ethereal.max_size(), 5 * 1024);
@@ -319,6 +2237,19 @@ mod tests {
         assert_eq!(DensityLevel::Core.max_size(), 50 * 1024 * 1024);
     }
 
+    #[test]
+    fn test_network_id_matches_sdk() {
+        // Same value is pinned in sdk/src/crypto.test.ts
+        assert_eq!(
+            hex::encode(network_id(0, &[])),
+            "2dd8af478b6de3abd3c8a0b4aa571e211d004a679b9e3e98295d2e29b215f532"
+        );
+        assert_eq!(
+            hex::encode(network_id(7, &[([2; 32], 300), ([1; 32], 500)])),
+            "10074addc32043298dc104eb20997a3b3132b5fcb9d1c1d70da3961709211940"
+        );
+    }
+
     #[test]
     fn test_block_header_compute_hash() {
         let header = BlockHeader {
@@ -331,13 +2262,20 @@ mod tests {
             state_root: [4; 32],
             wave_number: 0,
             committee_id: 1,
+            protocol_version: PROTOCOL_VERSION,
+            state_version: STATE_VERSION,
+            signals: 0,
         };
         
-        let hash = header.compute_hash();
+        let hash = header.compute_hash(&network_id(0, &[]));
         assert_ne!(hash, [0u8; 32]);
         
         // Hash should be consistent
-        let hash2 = header.compute_hash();
+        let hash2 = header.compute_hash(&network_id(0, &[]));
         assert_eq!(hash, hash2);
+
+        // ...and differ between networks
+        assert_ne!(network_id(0, &[]), network_id(1, &[]));
+        assert_ne!(header.compute_hash(&network_id(1, &[])), hash);
     }
 }
\ No newline at end of file
@@ -0,0 +1,124 @@
+//! Asset upgrades
+//!
+//! An asset of a collection with an upgrade rule (`economy.upgrades`) is upgraded with an
+//! `Upgrade` transaction. The owner pays the rule's fee and burns its catalysts (assets of
+//! the same collection) up front, and the asset waits in escrow for the outcome.
+//!
+//! The outcome is rolled at the end of block `roll_height`, `roll_delay` blocks after the
+//! upgrade, with that block's hash as the randomness, which nobody knows when the upgrade is
+//! signed. On success the rule's attribute grows by `boost`; on failure the asset comes
+//! back unchanged; on destruction it is burned. An upgrade whose collection is frozen by a
+//! season when it is due is rolled in the first block after the collection thaws.
+
+use serde::{Deserialize, Serialize};
+use crate::types::{Address, AttributeValue, Hash};
+
+/// Odds are in basis points of this total
+pub const UPGRADE_ODDS_TOTAL: u64 = 10_000;
+
+/// What an upgrade did to its asset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UpgradeOutcome {
+    /// The attribute was boosted
+    Success,
+    /// The asset came back unchanged
+    Fail,
+    /// The asset was burned
+    Destroy,
+}
+
+impl UpgradeOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UpgradeOutcome::Success => "success",
+            UpgradeOutcome::Fail => "fail",
+            UpgradeOutcome::Destroy => "destroy",
+        }
+    }
+}
+
+/// Upgrade of one asset, pending until its outcome is rolled
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Upgrade {
+    pub id: Hash,
+    pub owner: Address,
+    pub asset_id: Hash,
+    pub game_id: String,
+    /// Attribute the upgrade boosts
+    pub attribute: String,
+    /// Assets burned as catalysts
+    pub catalysts: Vec<Hash>,
+    /// Odds and boost of the rule when the upgrade was requested
+    pub success_bps: u64,
+    pub destroy_bps: u64,
+    pub boost: u64,
+    pub requested_at_height: u64,
+    /// Block whose hash decides the outcome (moved past a season freeze)
+    pub roll_height: u64,
+    pub outcome: Option<UpgradeOutcome>,
+    pub resolved_at_height: Option<u64>,
+    /// Attribute value before the roll, and after it (none until rolled or when destroyed)
+    pub value_before: u64,
+    pub value_after: Option<u64>,
+}
+
+/// Level held by an attribute: a non-negative int, or a string of digits written before
+/// values were typed (a missing attribute counts as 0)
+pub fn attribute_level(value: Option<&AttributeValue>) -> Option<u64> {
+    match value {
+        Some(AttributeValue::Int(level)) => u64::try_from(*level).ok(),
+        Some(AttributeValue::String(level)) => level.parse().ok(),
+        Some(_) => None,
+        None => Some(0),
+    }
+}
+
+/// Outcome of an upgrade
+///
+/// The roll is the first 8 bytes of sha256("haze_upgrade_roll" || beacon || upgrade_id), as
+/// a u64 LE, modulo 10000. A roll below `success_bps` succeeds, one below
+/// `success_bps + destroy_bps` destroys the asset, and anything else fails.
+pub fn roll_outcome(beacon: &Hash, upgrade_id: &Hash, success_bps: u64, destroy_bps: u64) -> UpgradeOutcome {
+    let mut data = b"haze_upgrade_roll".to_vec();
+    data.extend_from_slice(beacon);
+    data.extend_from_slice(upgrade_id);
+    let digest = crate::types::sha256(&data);
+    let roll = u64::from_le_bytes(digest[..8].try_into().expect("8 bytes")) % UPGRADE_ODDS_TOTAL;
+    if roll < success_bps {
+        UpgradeOutcome::Success
+    } else if roll < success_bps + destroy_bps {
+        UpgradeOutcome::Destroy
+    } else {
+        UpgradeOutcome::Fail
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roll_follows_odds() {
+        let rolls = |success_bps: u64, destroy_bps: u64| {
+            (0..200u8).map(|beacon| roll_outcome(&[beacon; 32], &[7u8; 32], success_bps, destroy_bps)).collect::<Vec<_>>()
+        };
+        assert!(rolls(10_000, 0).iter().all(|o| *o == UpgradeOutcome::Success));
+        assert!(rolls(0, 10_000).iter().all(|o| *o == UpgradeOutcome::Destroy));
+        assert!(rolls(0, 0).iter().all(|o| *o == UpgradeOutcome::Fail));
+        let mixed = rolls(5_000, 2_500);
+        for outcome in [UpgradeOutcome::Success, UpgradeOutcome::Fail, UpgradeOutcome::Destroy] {
+            assert!(mixed.contains(&outcome));
+        }
+        assert_eq!(rolls(5_000, 2_500), mixed);
+    }
+
+    #[test]
+    fn test_attribute_level() {
+        assert_eq!(attribute_level(None), Some(0));
+        assert_eq!(attribute_level(Some(&AttributeValue::Int(12))), Some(12));
+        assert_eq!(attribute_level(Some(&"12".into())), Some(12));
+        assert_eq!(attribute_level(Some(&"legendary".into())), None);
+        assert_eq!(attribute_level(Some(&AttributeValue::Int(-1))), None);
+    }
+}
@@ -0,0 +1,203 @@
+//! Protocol compatibility between nodes of different versions
+//!
+//! Every block header carries the protocol and state versions it was produced under, and
+//! peers exchange a `PeerVersion` right after connecting. A peer on another chain, or whose
+//! protocol range does not overlap ours, is disconnected. A peer that speaks an older
+//! protocol (or does not answer the handshake at all, i.e. protocol 1) stays connected in a
+//! degraded mode: transactions still flow both ways and its blocks are accepted, but no
+//! blocks are sent to it, since it cannot decode the current block format.
+
+use std::collections::HashMap;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use crate::types::{MIN_PROTOCOL_VERSION, PROTOCOL_VERSION, STATE_VERSION, WIRE_VERSION};
+
+/// Block format introduced by protocol 3; older peers cannot decode our blocks
+const BLOCK_FORMAT_PROTOCOL: u32 = 3;
+
+/// Versions a node announces in the handshake
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerVersion {
+    pub protocol_version: u32,
+    /// Oldest protocol the node still talks to
+    pub min_protocol_version: u32,
+    pub state_version: u32,
+    /// Node software version, `None` for a peer that predates the handshake
+    pub binary_version: Option<String>,
+    pub chain_id: u64,
+}
+
+impl PeerVersion {
+    /// Versions of this build
+    pub fn local(chain_id: u64) -> Self {
+        Self {
+            protocol_version: PROTOCOL_VERSION,
+            min_protocol_version: MIN_PROTOCOL_VERSION,
+            state_version: STATE_VERSION,
+            binary_version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            chain_id,
+        }
+    }
+
+    /// Versions assumed for a peer that does not answer the handshake
+    pub fn legacy(chain_id: u64) -> Self {
+        Self {
+            protocol_version: 1,
+            min_protocol_version: 1,
+            state_version: 1,
+            binary_version: None,
+            chain_id,
+        }
+    }
+}
+
+/// How this node can talk to a peer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "mode", content = "reason")]
+pub enum PeerCompatibility {
+    /// Blocks and transactions are exchanged
+    Full,
+    /// Transactions only; the peer's blocks are accepted but ours are not sent to it
+    Degraded,
+    /// The peer is disconnected
+    Incompatible(String),
+}
+
+/// Compare a peer's announced versions with ours
+pub fn check_peer_version(local: &PeerVersion, peer: &PeerVersion) -> PeerCompatibility {
+    if peer.chain_id != local.chain_id {
+        return PeerCompatibility::Incompatible(format!("peer is on chain {}, not {}", peer.chain_id, local.chain_id));
+    }
+    if peer.protocol_version < local.min_protocol_version {
+        return PeerCompatibility::Incompatible(format!(
+            "peer protocol {} is older than the oldest supported {}",
+            peer.protocol_version, local.min_protocol_version
+        ));
+    }
+    if local.protocol_version < peer.min_protocol_version {
+        return PeerCompatibility::Incompatible(format!(
+            "peer requires protocol {} or newer, this node speaks {}",
+            peer.min_protocol_version, local.protocol_version
+        ));
+    }
+    if peer.protocol_version < BLOCK_FORMAT_PROTOCOL || peer.state_version != local.state_version {
+        return PeerCompatibility::Degraded;
+    }
+    PeerCompatibility::Full
+}
+
+/// Versions of this node, for `GET /api/v1/node/version`
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeVersion {
+    pub binary_version: &'static str,
+    pub protocol_version: u32,
+    pub min_protocol_version: u32,
+    pub state_version: u32,
+    pub wire_version: u8,
+    pub chain_id: u64,
+}
+
+impl NodeVersion {
+    pub fn current(chain_id: u64) -> Self {
+        Self {
+            binary_version: env!("CARGO_PKG_VERSION"),
+            protocol_version: PROTOCOL_VERSION,
+            min_protocol_version: MIN_PROTOCOL_VERSION,
+            state_version: STATE_VERSION,
+            wire_version: WIRE_VERSION,
+            chain_id,
+        }
+    }
+}
+
+/// Handshake result of one connected peer
+#[derive(Debug, Clone, Serialize)]
+pub struct PeerVersionInfo {
+    pub peer_id: String,
+    #[serde(flatten)]
+    pub version: PeerVersion,
+    pub compatibility: PeerCompatibility,
+}
+
+/// Versions announced by connected peers
+#[derive(Debug)]
+pub struct PeerVersions {
+    local: PeerVersion,
+    peers: RwLock<HashMap<String, PeerVersion>>,
+}
+
+impl PeerVersions {
+    pub fn new(chain_id: u64) -> Self {
+        Self {
+            local: PeerVersion::local(chain_id),
+            peers: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Versions this node announces
+    pub fn local(&self) -> &PeerVersion {
+        &self.local
+    }
+
+    /// Record a peer's versions and return how to talk to it
+    pub fn record(&self, peer: String, version: PeerVersion) -> PeerCompatibility {
+        let compatibility = check_peer_version(&self.local, &version);
+        self.peers.write().insert(peer, version);
+        compatibility
+    }
+
+    /// Forget a disconnected peer
+    pub fn remove(&self, peer: &str) {
+        self.peers.write().remove(peer);
+    }
+
+    /// Handshake results by peer id
+    pub fn peers(&self) -> Vec<PeerVersionInfo> {
+        let mut peers: Vec<PeerVersionInfo> = self.peers
+            .read()
+            .iter()
+            .map(|(peer_id, version)| PeerVersionInfo {
+                peer_id: peer_id.clone(),
+                version: version.clone(),
+                compatibility: check_peer_version(&self.local, version),
+            })
+            .collect();
+        peers.sort_by(|a, b| a.peer_id.cmp(&b.peer_id));
+        peers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_peer_compatibility() {
+        let local = PeerVersion::local(7);
+        assert_eq!(check_peer_version(&local, &local), PeerCompatibility::Full);
+
+        // Peers without the handshake only get transactions
+        assert_eq!(check_peer_version(&local, &PeerVersion::legacy(7)), PeerCompatibility::Degraded);
+        let other_state = PeerVersion { state_version: STATE_VERSION + 1, ..local.clone() };
+        assert_eq!(check_peer_version(&local, &other_state), PeerCompatibility::Degraded);
+
+        // A newer peer that still talks down to us is fine
+        let newer = PeerVersion { protocol_version: PROTOCOL_VERSION + 1, ..local.clone() };
+        assert_eq!(check_peer_version(&local, &newer), PeerCompatibility::Full);
+
+        // Another chain, or protocol ranges that do not overlap
+        assert!(matches!(check_peer_version(&local, &PeerVersion::local(8)), PeerCompatibility::Incompatible(_)));
+        let too_new = PeerVersion { protocol_version: PROTOCOL_VERSION + 2, min_protocol_version: PROTOCOL_VERSION + 1, ..local.clone() };
+        assert!(matches!(check_peer_version(&local, &too_new), PeerCompatibility::Incompatible(_)));
+        let strict = PeerVersion { min_protocol_version: PROTOCOL_VERSION, ..local.clone() };
+        let too_old = PeerVersion { protocol_version: PROTOCOL_VERSION - 1, ..local.clone() };
+        assert!(matches!(check_peer_version(&strict, &too_old), PeerCompatibility::Incompatible(_)));
+
+        let versions = PeerVersions::new(7);
+        assert_eq!(versions.record("b".to_string(), PeerVersion::legacy(7)), PeerCompatibility::Degraded);
+        versions.record("a".to_string(), local.clone());
+        assert_eq!(versions.peers().iter().map(|p| p.peer_id.as_str()).collect::<Vec<_>>(), ["a", "b"]);
+        versions.remove("b");
+        assert_eq!(versions.peers().len(), 1);
+    }
+}
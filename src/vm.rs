@@ -3,11 +3,51 @@
 //! Features:
 //! - Haze Contracts (state density management)
 //! - Game Primitives (Asset Mist, Economy Fog, Quest Haze, Battle Smoke)
+//! - Event logs (`env.haze_log` host function)
+//! - Asset precompiles (`env.haze_asset_*` host functions)
+//! - Pool price oracle (`env.haze_pool_twap` host function)
+//! - Call input (`env.haze_input` host function)
+//! - Paymasters sponsoring asset gas (`PAYMASTER_KEY`)
 
-use wasmtime::{Engine, Store, Module, Instance, Val, ValType};
+use std::any::Any;
+use std::sync::Arc;
+use wasmtime::{Caller, Engine, Extern, Linker, Memory, Store, Module, Val, ValType};
 use crate::error::{HazeError, Result};
 use crate::config::Config;
-use crate::types::Address;
+use crate::state::AssetState;
+use crate::types::{Address, AttributeValue, Hash, Log};
+
+/// Maximum topics per log
+pub const MAX_LOG_TOPICS: usize = 4;
+/// Maximum data bytes per log
+pub const MAX_LOG_DATA_SIZE: usize = 64 * 1024;
+/// Gas charged per log, per topic and per data byte
+pub const LOG_GAS: u64 = 375;
+pub const LOG_TOPIC_GAS: u64 = 375;
+pub const LOG_DATA_BYTE_GAS: u64 = 8;
+
+/// Gas charged by asset precompiles
+pub const ASSET_READ_GAS: u64 = 2_000;
+pub const ASSET_PERMISSION_GAS: u64 = 500;
+pub const ASSET_WRITE_GAS: u64 = 5_000;
+pub const ASSET_TRANSFER_GAS: u64 = 10_000;
+/// Gas charged by `haze_pool_twap`
+pub const PRICE_READ_GAS: u64 = 2_000;
+/// Maximum pool id length accepted by `haze_pool_twap`
+pub const MAX_POOL_ID_SIZE: usize = 256;
+/// Gas per byte copied between an asset and contract memory
+pub const ASSET_BYTE_GAS: u64 = 3;
+/// Maximum attribute name / value sizes accepted by `haze_asset_set_attribute`
+pub const MAX_ATTRIBUTE_NAME_SIZE: usize = 256;
+pub const MAX_ATTRIBUTE_VALUE_SIZE: usize = 4 * 1024;
+/// Gas charged by `haze_input`, plus `ASSET_BYTE_GAS` per byte copied
+pub const INPUT_READ_GAS: u64 = 200;
+
+/// Asset metadata key naming the paymaster contract (hex address) asked to sponsor
+/// the transaction's gas
+pub const PAYMASTER_KEY: &str = "_paymaster";
+/// Paymaster function deciding whether to sponsor; returns non-zero to accept
+pub const PAYMASTER_METHOD: &str = "sponsor";
 
 /// Encode unsigned 32-bit integer as LEB128
 fn encode_leb128_u32(buf: &mut Vec<u8>, mut value: u32) {
@@ -53,6 +93,120 @@ pub struct ExecutionContext {
     pub contract: Address,
     pub gas_limit: u64,
     pub gas_used: u64,
+    /// Logs emitted by a successful call (discarded when the call fails)
+    pub logs: Vec<Log>,
+    /// Asset access for precompiles; `None` makes the asset host functions trap.
+    /// The host is handed back here after the call.
+    pub assets: Option<Box<dyn AssetHost>>,
+    /// Pool prices for `haze_pool_twap`; `None` makes it trap
+    pub prices: Option<Arc<dyn PriceOracle>>,
+}
+
+/// Pool prices backing the `haze_pool_twap` host function
+pub trait PriceOracle: Send + Sync {
+    /// Time-weighted average price of a pool over the last `window_blocks` blocks
+    fn twap(&self, pool_id: &str, window_blocks: u64) -> Option<crate::economy::Twap>;
+}
+
+/// Asset access backing the `haze_asset_*` precompiles. The acting address is
+/// always the executing contract, so it needs ownership or a GameContract grant.
+pub trait AssetHost: Any {
+    /// Current asset state, including changes made earlier in the call
+    fn asset(&self, asset_id: &Hash) -> Option<AssetState>;
+    /// Whether `actor` may modify the asset
+    fn can_write(&self, asset_id: &Hash, actor: &Address) -> bool;
+    /// Insert or replace an attribute by name
+    fn set_attribute(&mut self, actor: &Address, asset_id: &Hash, name: String, value: AttributeValue) -> Result<()>;
+    /// Move the asset to a new owner
+    fn transfer(&mut self, actor: &Address, asset_id: &Hash, to: Address) -> Result<()>;
+}
+
+/// Typed value returned by a contract function
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(tag = "type", content = "value", rename_all = "lowercase")]
+pub enum ContractValue {
+    I32(i32),
+    I64(i64),
+    F32(f32),
+    F64(f64),
+}
+
+impl ContractValue {
+    /// Little-endian encoding of the value
+    pub fn to_le_bytes(&self) -> Vec<u8> {
+        match self {
+            ContractValue::I32(v) => v.to_le_bytes().to_vec(),
+            ContractValue::I64(v) => v.to_le_bytes().to_vec(),
+            ContractValue::F32(v) => v.to_le_bytes().to_vec(),
+            ContractValue::F64(v) => v.to_le_bytes().to_vec(),
+        }
+    }
+
+    /// Concatenated little-endian encoding of function results
+    pub fn encode_all(values: &[ContractValue]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+}
+
+/// Per-call host state available to host functions
+struct HostState {
+    contract: Address,
+    /// Call arguments returned by `haze_input`
+    input: Vec<u8>,
+    logs: Vec<Log>,
+    assets: Option<Box<dyn AssetHost>>,
+    prices: Option<Arc<dyn PriceOracle>>,
+}
+
+/// Take `cost` gas from the remaining fuel, exhausting it if there is not enough
+fn charge_fuel(caller: &mut Caller<'_, HostState>, cost: u64, what: &str) -> anyhow::Result<()> {
+    let fuel = caller.get_fuel()?;
+    if fuel < cost {
+        caller.set_fuel(0)?;
+        anyhow::bail!("out of fuel: {} needs {} gas", what, cost);
+    }
+    caller.set_fuel(fuel - cost)?;
+    Ok(())
+}
+
+fn exported_memory(caller: &mut Caller<'_, HostState>, function: &str) -> anyhow::Result<Memory> {
+    match caller.get_export("memory") {
+        Some(Extern::Memory(memory)) => Ok(memory),
+        _ => anyhow::bail!("{} requires an exported memory", function),
+    }
+}
+
+fn read_bytes(caller: &mut Caller<'_, HostState>, memory: Memory, ptr: i32, len: usize) -> anyhow::Result<Vec<u8>> {
+    let mut buf = vec![0u8; len];
+    memory.read(&*caller, ptr as u32 as usize, &mut buf)?;
+    Ok(buf)
+}
+
+fn read_hash(caller: &mut Caller<'_, HostState>, memory: Memory, ptr: i32) -> anyhow::Result<Hash> {
+    let mut hash: Hash = [0u8; 32];
+    memory.read(&*caller, ptr as u32 as usize, &mut hash)?;
+    Ok(hash)
+}
+
+fn asset_host<'a>(caller: &'a mut Caller<'_, HostState>) -> anyhow::Result<&'a mut Box<dyn AssetHost>> {
+    caller
+        .data_mut()
+        .assets
+        .as_mut()
+        .ok_or_else(|| anyhow::anyhow!("asset precompiles are not available in this context"))
+}
+
+/// JSON view of an asset returned by `haze_asset_get`
+fn asset_json(asset: &AssetState) -> Vec<u8> {
+    serde_json::json!({
+        "owner": crate::types::address_to_hex(&asset.owner),
+        "game_id": asset.data.game_id,
+        "density": format!("{:?}", asset.data.density),
+        "metadata": asset.data.metadata,
+        "attributes": asset.data.attributes,
+    })
+    .to_string()
+    .into_bytes()
 }
 
 /// Contract state density
@@ -77,6 +231,7 @@ impl HazeVM {
     }
 
     /// Execute contract call. Updates context.gas_used with actual gas consumed.
+    /// Returns the function results concatenated as little-endian bytes.
     pub fn execute_contract(
         &self,
         wasm_code: &[u8],
@@ -84,6 +239,19 @@ impl HazeVM {
         args: &[u8],
         context: &mut ExecutionContext,
     ) -> Result<Vec<u8>> {
+        let values = self.execute_contract_values(wasm_code, method, args, context)?;
+        Ok(ContractValue::encode_all(&values))
+    }
+
+    /// Execute contract call and return the typed function results.
+    /// Updates context.gas_used with actual gas consumed.
+    pub fn execute_contract_values(
+        &self,
+        wasm_code: &[u8],
+        method: &str,
+        args: &[u8],
+        context: &mut ExecutionContext,
+    ) -> Result<Vec<ContractValue>> {
         // Check gas limit
         if context.gas_limit == 0 {
             return Err(HazeError::VM("Gas limit is zero".to_string()));
@@ -107,17 +275,22 @@ impl HazeVM {
             .map_err(|e| HazeError::VM(format!("Failed to compile WASM: {e}")))?;
 
         // Create store with gas metering
-        let mut store = Store::new(&self.engine, ());
+        let mut store = Store::new(&self.engine, HostState {
+            contract: context.contract,
+            input: args.to_vec(),
+            logs: Vec::new(),
+            assets: None,
+            prices: context.prices.clone(),
+        });
         
         // Calculate remaining gas for execution
         let remaining_gas = context.gas_limit
             .saturating_sub(context.gas_used)
             .saturating_sub(INSTANTIATE_GAS_COST);
         
-        // Set fuel (gas) limit for execution
-        // In wasmtime 15.0, we need to add fuel first, then it will be consumed
-        // Try to add fuel - if method doesn't exist, we'll track manually
-        // For now, we'll use a workaround: track gas manually and check after execution
+        // Set fuel (gas) limit for execution; consumed fuel is charged as gas
+        store.set_fuel(remaining_gas)
+            .map_err(|e| HazeError::VM(format!("Failed to set fuel: {e}")))?;
 
         // Check gas for instantiation
         if context.gas_used + INSTANTIATE_GAS_COST > context.gas_limit {
@@ -129,8 +302,9 @@ impl HazeVM {
         }
         context.gas_used += INSTANTIATE_GAS_COST;
 
-        // Instantiate module
-        let instance = Instance::new(&mut store, &module, &[])
+        // Instantiate module (host functions are available under the `env` namespace)
+        let linker = self.create_linker()?;
+        let instance = linker.instantiate(&mut store, &module)
             .map_err(|e| HazeError::VM(format!("Failed to instantiate module: {e}")))?;
 
         // Get function
@@ -160,52 +334,239 @@ impl HazeVM {
             )));
         };
 
-        // Call the function
+        // Call the function; the asset host is lent to the store only for the call
+        // so that every earlier failure leaves it with the context
         let mut results = vec![Val::I64(0); func_ty.results().len()];
-        func.call(&mut store, &wasm_args, &mut results)
-            .map_err(|e| {
-                // Check if it's a fuel exhaustion error
-                if e.to_string().contains("fuel") || e.to_string().contains("out of fuel") {
-                    HazeError::VM(format!("Gas limit exceeded during execution"))
-                } else {
-                    HazeError::VM(format!("Function call failed: {e}"))
-                }
-            })?;
+        store.data_mut().assets = context.assets.take();
+        let call_result = func.call(&mut store, &wasm_args, &mut results);
 
-        // Get consumed fuel to calculate actual gas used
-        // In wasmtime 15.0, we check remaining fuel and calculate consumed
-        // For now, estimate based on execution (in production, use proper fuel API)
-        // We'll use a conservative estimate: remaining_gas - some margin
-        // Actual implementation would use store.fuel_remaining() or similar
-        let estimated_execution_gas = remaining_gas / 10; // Conservative estimate
-        context.gas_used += estimated_execution_gas.min(remaining_gas);
+        // Charge the fuel actually consumed by execution (also when the call traps)
+        let fuel_left = store.get_fuel().unwrap_or(0);
+        context.gas_used += remaining_gas.saturating_sub(fuel_left);
+
+        context.logs = std::mem::take(&mut store.data_mut().logs);
+        context.assets = store.data_mut().assets.take();
+        call_result.map_err(|e| {
+            context.logs.clear();
+            // Check if it's a fuel exhaustion error
+            if e.to_string().contains("fuel") || e.to_string().contains("out of fuel") {
+                HazeError::VM("Gas limit exceeded during execution".to_string())
+            } else {
+                HazeError::VM(format!("Function call failed: {e}"))
+            }
+        })?;
 
         // Extract return values
-        let mut return_data = Vec::new();
+        let mut values = Vec::with_capacity(results.len());
         for result in results {
-            match result {
-                Val::I32(v) => return_data.extend_from_slice(&v.to_le_bytes()),
-                Val::I64(v) => return_data.extend_from_slice(&v.to_le_bytes()),
-                Val::F32(v) => {
-                    // In wasmtime, Val::F32 contains f32::to_bits() result (u32)
-                    // Convert to bytes directly
-                    return_data.extend_from_slice(&v.to_le_bytes());
-                }
-                Val::F64(v) => {
-                    // In wasmtime, Val::F64 contains f64::to_bits() result (u64)
-                    // Convert to bytes directly
-                    return_data.extend_from_slice(&v.to_le_bytes());
-                }
+            values.push(match result {
+                Val::I32(v) => ContractValue::I32(v),
+                Val::I64(v) => ContractValue::I64(v),
+                // In wasmtime, Val::F32/F64 hold the IEEE-754 bits
+                Val::F32(v) => ContractValue::F32(f32::from_bits(v)),
+                Val::F64(v) => ContractValue::F64(f64::from_bits(v)),
                 Val::V128(_) => {
                     return Err(HazeError::VM("V128 return type not supported".to_string()));
                 }
                 Val::FuncRef(_) | Val::ExternRef(_) => {
                     return Err(HazeError::VM("Reference return types not supported".to_string()));
                 }
-            }
+            });
         }
 
-        Ok(return_data)
+        Ok(values)
+    }
+
+    /// Linker exposing host functions to contracts:
+    /// `env.haze_log(topics_ptr: i32, topic_count: i32, data_ptr: i32, data_len: i32)`
+    /// reads `topic_count` 32-byte topics and `data_len` bytes from the exported `memory`.
+    fn create_linker(&self) -> Result<Linker<HostState>> {
+        let mut linker = Linker::new(&self.engine);
+        linker
+            .func_wrap(
+                "env",
+                "haze_log",
+                |mut caller: Caller<'_, HostState>, topics_ptr: i32, topic_count: i32, data_ptr: i32, data_len: i32| -> anyhow::Result<()> {
+                    let topic_count = topic_count as u32 as usize;
+                    let data_len = data_len as u32 as usize;
+                    if topic_count > MAX_LOG_TOPICS {
+                        anyhow::bail!("too many log topics: {} > {}", topic_count, MAX_LOG_TOPICS);
+                    }
+                    if data_len > MAX_LOG_DATA_SIZE {
+                        anyhow::bail!("log data too large: {} > {}", data_len, MAX_LOG_DATA_SIZE);
+                    }
+
+                    // Charge log gas from the remaining fuel
+                    let cost = LOG_GAS + LOG_TOPIC_GAS * topic_count as u64 + LOG_DATA_BYTE_GAS * data_len as u64;
+                    charge_fuel(&mut caller, cost, "log")?;
+
+                    let memory = exported_memory(&mut caller, "haze_log")?;
+                    let topic_bytes = read_bytes(&mut caller, memory, topics_ptr, topic_count * 32)?;
+                    let data = read_bytes(&mut caller, memory, data_ptr, data_len)?;
+
+                    let topics = topic_bytes
+                        .chunks_exact(32)
+                        .map(|chunk| {
+                            let mut topic: Hash = [0u8; 32];
+                            topic.copy_from_slice(chunk);
+                            topic
+                        })
+                        .collect();
+                    let address = caller.data().contract;
+                    caller.data_mut().logs.push(Log { address, topics, data });
+                    Ok(())
+                },
+            )
+            .map_err(|e| HazeError::VM(format!("Failed to register host functions: {e}")))?;
+        Self::link_asset_precompiles(&mut linker)
+            .map_err(|e| HazeError::VM(format!("Failed to register host functions: {e}")))?;
+        Self::link_price_oracle(&mut linker)
+            .map_err(|e| HazeError::VM(format!("Failed to register host functions: {e}")))?;
+        Self::link_input(&mut linker)
+            .map_err(|e| HazeError::VM(format!("Failed to register host functions: {e}")))?;
+        Ok(linker)
+    }
+
+    /// Asset precompiles (asset ids and addresses are 32 bytes in contract memory):
+    /// - `haze_asset_get(id_ptr, out_ptr, out_cap) -> i32` writes the asset as JSON and returns
+    ///   its length (-1 if the asset does not exist); nothing is written if it exceeds `out_cap`
+    /// - `haze_asset_can_write(id_ptr) -> i32` returns 1 if the contract may modify the asset
+    /// - `haze_asset_set_attribute(id_ptr, name_ptr, name_len, value_ptr, value_len)`; the
+    ///   value is the attribute's binary encoding (`AttributeValue::encode`: a plain string,
+    ///   or NUL and a type letter for typed values)
+    /// - `haze_asset_transfer(id_ptr, to_ptr)`
+    ///
+    /// Failed writes trap, reverting the call.
+    fn link_asset_precompiles(linker: &mut Linker<HostState>) -> anyhow::Result<()> {
+        linker.func_wrap(
+            "env",
+            "haze_asset_get",
+            |mut caller: Caller<'_, HostState>, id_ptr: i32, out_ptr: i32, out_cap: i32| -> anyhow::Result<i32> {
+                charge_fuel(&mut caller, ASSET_READ_GAS, "asset read")?;
+                let memory = exported_memory(&mut caller, "haze_asset_get")?;
+                let asset_id = read_hash(&mut caller, memory, id_ptr)?;
+                let Some(asset) = asset_host(&mut caller)?.asset(&asset_id) else {
+                    return Ok(-1);
+                };
+                let json = asset_json(&asset);
+                if json.len() <= out_cap as u32 as usize {
+                    charge_fuel(&mut caller, ASSET_BYTE_GAS * json.len() as u64, "asset read")?;
+                    memory.write(&mut caller, out_ptr as u32 as usize, &json)?;
+                }
+                Ok(json.len() as i32)
+            },
+        )?;
+        linker.func_wrap(
+            "env",
+            "haze_asset_can_write",
+            |mut caller: Caller<'_, HostState>, id_ptr: i32| -> anyhow::Result<i32> {
+                charge_fuel(&mut caller, ASSET_PERMISSION_GAS, "permission check")?;
+                let memory = exported_memory(&mut caller, "haze_asset_can_write")?;
+                let asset_id = read_hash(&mut caller, memory, id_ptr)?;
+                let contract = caller.data().contract;
+                Ok(asset_host(&mut caller)?.can_write(&asset_id, &contract) as i32)
+            },
+        )?;
+        linker.func_wrap(
+            "env",
+            "haze_asset_set_attribute",
+            |mut caller: Caller<'_, HostState>, id_ptr: i32, name_ptr: i32, name_len: i32, value_ptr: i32, value_len: i32| -> anyhow::Result<()> {
+                let name_len = name_len as u32 as usize;
+                let value_len = value_len as u32 as usize;
+                if name_len == 0 || name_len > MAX_ATTRIBUTE_NAME_SIZE {
+                    anyhow::bail!("invalid attribute name length: {}", name_len);
+                }
+                if value_len > MAX_ATTRIBUTE_VALUE_SIZE {
+                    anyhow::bail!("attribute value too large: {} > {}", value_len, MAX_ATTRIBUTE_VALUE_SIZE);
+                }
+                let cost = ASSET_WRITE_GAS + ASSET_BYTE_GAS * (name_len + value_len) as u64;
+                charge_fuel(&mut caller, cost, "attribute update")?;
+
+                let memory = exported_memory(&mut caller, "haze_asset_set_attribute")?;
+                let asset_id = read_hash(&mut caller, memory, id_ptr)?;
+                let name = String::from_utf8(read_bytes(&mut caller, memory, name_ptr, name_len)?)?;
+                let value = String::from_utf8(read_bytes(&mut caller, memory, value_ptr, value_len)?)?;
+                let value = AttributeValue::decode(value).map_err(anyhow::Error::msg)?;
+                value.validate().map_err(anyhow::Error::msg)?;
+                let contract = caller.data().contract;
+                asset_host(&mut caller)?.set_attribute(&contract, &asset_id, name, value)?;
+                Ok(())
+            },
+        )?;
+        linker.func_wrap(
+            "env",
+            "haze_asset_transfer",
+            |mut caller: Caller<'_, HostState>, id_ptr: i32, to_ptr: i32| -> anyhow::Result<()> {
+                charge_fuel(&mut caller, ASSET_TRANSFER_GAS, "asset transfer")?;
+                let memory = exported_memory(&mut caller, "haze_asset_transfer")?;
+                let asset_id = read_hash(&mut caller, memory, id_ptr)?;
+                let to = read_hash(&mut caller, memory, to_ptr)?;
+                let contract = caller.data().contract;
+                asset_host(&mut caller)?.transfer(&contract, &asset_id, to)?;
+                Ok(())
+            },
+        )?;
+        Ok(())
+    }
+
+    /// Pool price oracle:
+    /// `haze_pool_twap(pool_ptr, pool_len, window_blocks: i64, invert: i32) -> i64` returns the
+    /// time-weighted average price of asset1 in asset2 (asset2 in asset1 if `invert != 0`) over
+    /// the last `window_blocks` blocks, scaled by `economy::TWAP_PRICE_SCALE`. Returns -1 for an
+    /// unknown pool or while the pool has less than `window_blocks` of price history.
+    fn link_price_oracle(linker: &mut Linker<HostState>) -> anyhow::Result<()> {
+        linker.func_wrap(
+            "env",
+            "haze_pool_twap",
+            |mut caller: Caller<'_, HostState>, pool_ptr: i32, pool_len: i32, window_blocks: i64, invert: i32| -> anyhow::Result<i64> {
+                let pool_len = pool_len as u32 as usize;
+                if pool_len > MAX_POOL_ID_SIZE {
+                    anyhow::bail!("pool id too long: {} > {}", pool_len, MAX_POOL_ID_SIZE);
+                }
+                if window_blocks <= 0 {
+                    anyhow::bail!("TWAP window must be positive, got {}", window_blocks);
+                }
+                charge_fuel(&mut caller, PRICE_READ_GAS, "price read")?;
+                let memory = exported_memory(&mut caller, "haze_pool_twap")?;
+                let pool_id = String::from_utf8(read_bytes(&mut caller, memory, pool_ptr, pool_len)?)?;
+                let prices = caller
+                    .data()
+                    .prices
+                    .clone()
+                    .ok_or_else(|| anyhow::anyhow!("price oracle is not available in this context"))?;
+                let twap = prices
+                    .twap(&pool_id, window_blocks as u64)
+                    .filter(|twap| twap.window_blocks >= window_blocks as u64);
+                Ok(match twap {
+                    Some(twap) => {
+                        let price = if invert != 0 { twap.price2 } else { twap.price1 };
+                        i64::try_from(price).unwrap_or(i64::MAX)
+                    }
+                    None => -1,
+                })
+            },
+        )?;
+        Ok(())
+    }
+
+    /// Call input: `haze_input(out_ptr, out_cap) -> i32` writes the call arguments and returns
+    /// their length; nothing is written if they exceed `out_cap`
+    fn link_input(linker: &mut Linker<HostState>) -> anyhow::Result<()> {
+        linker.func_wrap(
+            "env",
+            "haze_input",
+            |mut caller: Caller<'_, HostState>, out_ptr: i32, out_cap: i32| -> anyhow::Result<i32> {
+                charge_fuel(&mut caller, INPUT_READ_GAS, "input read")?;
+                let input = caller.data().input.clone();
+                if input.len() <= out_cap as u32 as usize {
+                    charge_fuel(&mut caller, ASSET_BYTE_GAS * input.len() as u64, "input read")?;
+                    let memory = exported_memory(&mut caller, "haze_input")?;
+                    memory.write(&mut caller, out_ptr as u32 as usize, &input)?;
+                }
+                Ok(input.len() as i32)
+            },
+        )?;
+        Ok(())
     }
 
     /// Create game primitive contract
@@ -688,6 +1049,9 @@ mod tests {
             contract: create_test_address(2),
             gas_limit: 10000,
             gas_used: 0,
+            logs: Vec::new(),
+            assets: None,
+            prices: None,
         };
         
         // Try to execute the contract
@@ -730,6 +1094,9 @@ mod tests {
             contract: create_test_address(2),
             gas_limit: 0,
             gas_used: 0,
+            logs: Vec::new(),
+            assets: None,
+            prices: None,
         };
         
         let result = vm.execute_contract(&wasm, "execute", &[], &mut context);
@@ -750,6 +1117,9 @@ mod tests {
             contract: create_test_address(2),
             gas_limit: 100, // Too low
             gas_used: 0,
+            logs: Vec::new(),
+            assets: None,
+            prices: None,
         };
         
         let result = vm.execute_contract(&wasm, "execute", &[], &mut context);
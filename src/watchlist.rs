@@ -0,0 +1,393 @@
+//! Asset watchlists with WebSocket and webhook notifications
+//!
+//! A watchlist names assets, collections (game_id) and accounts to follow. Watchlists belong to
+//! the API key that created them (`x-api-key` header) and are persisted in the node's
+//! database under a hash of that key. Events matching a watchlist are:
+//! - sent to WebSocket connections opened with the same API key (in addition to
+//!   explicit subscriptions);
+//! - POSTed to the watchlist's `webhook_url`, if set.
+//!
+//! Matching is by asset id, by the asset's collection, or by the event's owner account
+//! (the requester for payment request settlements), so any future asset event (e.g. price
+//! changes once a marketplace exists) reaches watchers without changes here.
+//!
+//! In multi-tenant mode a tenant's watchlists are shared by all its API keys (owner key
+//! `tenant:<id>`) and only match events about the tenant's own collections.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use crate::error::{HazeError, Result};
+use crate::state::StateManager;
+use crate::storage::StorageTree;
+use crate::types::{sha256, Address, Hash};
+use crate::ws_events::{WsEvent, WsEventEnvelope, EVENT_VERSION};
+
+/// Most watchlists one API key may own
+pub const MAX_WATCHLISTS_PER_KEY: usize = 100;
+/// Most assets, collections and accounts in one watchlist
+pub const MAX_WATCHLIST_ENTRIES: usize = 1000;
+/// Timeout of one webhook POST
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Assets and collections followed by one API key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Watchlist {
+    pub id: String,
+    /// Hex SHA-256 of the owning API key (the key itself is never stored)
+    pub owner_key: String,
+    pub name: String,
+    #[serde(with = "hex_hashes")]
+    pub assets: Vec<Hash>,
+    pub collections: Vec<String>,
+    /// Events owned by these accounts (asset owner, payment requester)
+    #[serde(with = "hex_hashes")]
+    pub accounts: Vec<Address>,
+    pub webhook_url: Option<String>,
+    pub created_at: i64,
+}
+
+/// Fields of a new watchlist
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewWatchlist {
+    pub name: String,
+    #[serde(default, with = "hex_hashes")]
+    pub assets: Vec<Hash>,
+    #[serde(default)]
+    pub collections: Vec<String>,
+    #[serde(default, with = "hex_hashes")]
+    pub accounts: Vec<Address>,
+    pub webhook_url: Option<String>,
+}
+
+impl Watchlist {
+    /// Whether an event (about an asset in collection `game_id`) concerns this watchlist
+    pub fn matches(&self, event: &WsEvent, game_id: Option<&str>) -> bool {
+        let outside_tenant = crate::tenant::watchlist_tenant(&self.owner_key)
+            .is_some_and(|tenant| !game_id.is_some_and(|game_id| crate::tenant::in_namespace(tenant, game_id)));
+        if outside_tenant {
+            return false;
+        }
+        event.asset_id().is_some_and(|asset_id| self.assets.contains(asset_id))
+            || game_id.is_some_and(|game_id| self.collections.iter().any(|c| c == game_id))
+            || event.owner().is_some_and(|owner| self.accounts.contains(owner))
+    }
+}
+
+/// Body POSTed to a watchlist webhook
+#[derive(Debug, Serialize)]
+pub struct WebhookPayload<'a> {
+    pub watchlist_id: &'a str,
+    pub event: WsEventEnvelope<'a>,
+}
+
+/// Webhook delivery counters (exposed as Prometheus metrics)
+#[derive(Debug, Default)]
+pub struct WebhookStats {
+    pub delivered: AtomicU64,
+    pub failed: AtomicU64,
+}
+
+/// Persisted watchlists of all API keys
+#[derive(Debug)]
+pub struct WatchlistStore {
+    tree: Arc<dyn StorageTree>,
+    watchlists: RwLock<Vec<Watchlist>>,
+    pub webhook_stats: WebhookStats,
+}
+
+/// Hex SHA-256 of an API key
+pub fn hash_api_key(api_key: &str) -> String {
+    hex::encode(sha256(api_key.as_bytes()))
+}
+
+impl WatchlistStore {
+    /// Open the store in the node database's `watchlists` tree
+    pub fn open(state: &StateManager) -> Result<Self> {
+        let tree = state.open_tree("watchlists")?;
+        let mut watchlists = Vec::new();
+        for item in tree.iter() {
+            let (_, value) = item?;
+            match bincode::deserialize::<Watchlist>(&value) {
+                Ok(watchlist) => watchlists.push(watchlist),
+                Err(e) => tracing::warn!("Skipping unreadable watchlist: {}", e),
+            }
+        }
+        watchlists.sort_by_key(|w| w.created_at);
+        Ok(Self {
+            tree,
+            watchlists: RwLock::new(watchlists),
+            webhook_stats: WebhookStats::default(),
+        })
+    }
+
+    /// Create a watchlist owned by `api_key`
+    pub fn create(&self, api_key: &str, new: NewWatchlist) -> Result<Watchlist> {
+        self.create_owned(hash_api_key(api_key), new, MAX_WATCHLISTS_PER_KEY)
+    }
+
+    /// Create a watchlist for owner key `owner_key`, which may hold at most `max_watchlists`
+    pub fn create_owned(&self, owner_key: String, new: NewWatchlist, max_watchlists: usize) -> Result<Watchlist> {
+        if new.name.trim().is_empty() {
+            return Err(HazeError::InvalidTransaction("Watchlist name must not be empty".to_string()));
+        }
+        let entries = new.assets.len() + new.collections.len() + new.accounts.len();
+        if entries == 0 {
+            return Err(HazeError::InvalidTransaction(
+                "Watchlist must contain at least one asset, collection or account".to_string()
+            ));
+        }
+        if entries > MAX_WATCHLIST_ENTRIES {
+            return Err(HazeError::InvalidTransaction(format!(
+                "Watchlist may contain at most {} assets, collections and accounts", MAX_WATCHLIST_ENTRIES
+            )));
+        }
+        if let Some(url) = &new.webhook_url {
+            let valid = reqwest::Url::parse(url)
+                .is_ok_and(|url| matches!(url.scheme(), "http" | "https"));
+            if !valid {
+                return Err(HazeError::InvalidTransaction(format!("Invalid webhook URL: {}", url)));
+            }
+        }
+
+        let mut watchlists = self.watchlists.write();
+        if watchlists.iter().filter(|w| w.owner_key == owner_key).count() >= max_watchlists {
+            return Err(HazeError::InvalidTransaction(format!(
+                "At most {} watchlists per owner", max_watchlists
+            )));
+        }
+        let watchlist = Watchlist {
+            id: uuid::Uuid::new_v4().to_string(),
+            owner_key,
+            name: new.name,
+            assets: new.assets,
+            collections: new.collections,
+            accounts: new.accounts,
+            webhook_url: new.webhook_url,
+            created_at: chrono::Utc::now().timestamp(),
+        };
+        let value = bincode::serialize(&watchlist)
+            .map_err(|e| HazeError::Serialization(e.to_string()))?;
+        self.tree.insert(watchlist.id.as_bytes(), &value)?;
+        watchlists.push(watchlist.clone());
+        Ok(watchlist)
+    }
+
+    /// Watchlists owned by `api_key`, oldest first
+    pub fn list(&self, api_key: &str) -> Vec<Watchlist> {
+        self.list_owned(&hash_api_key(api_key))
+    }
+
+    /// Watchlists of owner key `owner_key`, oldest first
+    pub fn list_owned(&self, owner_key: &str) -> Vec<Watchlist> {
+        self.watchlists
+            .read()
+            .iter()
+            .filter(|w| w.owner_key == owner_key)
+            .cloned()
+            .collect()
+    }
+
+    /// Delete a watchlist; returns false if `api_key` owns no watchlist with this id
+    pub fn delete(&self, api_key: &str, id: &str) -> Result<bool> {
+        self.delete_owned(&hash_api_key(api_key), id)
+    }
+
+    /// Delete a watchlist of owner key `owner_key`
+    pub fn delete_owned(&self, owner_key: &str, id: &str) -> Result<bool> {
+        let mut watchlists = self.watchlists.write();
+        let Some(index) = watchlists.iter().position(|w| w.id == id && w.owner_key == owner_key) else {
+            return Ok(false);
+        };
+        self.tree.remove(id.as_bytes())?;
+        watchlists.remove(index);
+        Ok(true)
+    }
+
+    /// Whether any watchlist of `api_key` matches the event
+    pub fn key_matches(&self, api_key_hash: &str, event: &WsEvent, game_id: Option<&str>) -> bool {
+        self.watchlists
+            .read()
+            .iter()
+            .any(|w| w.owner_key == api_key_hash && w.matches(event, game_id))
+    }
+
+    /// Whether `api_key_hash` owns any watchlist
+    pub fn has_watchlists(&self, api_key_hash: &str) -> bool {
+        self.watchlists.read().iter().any(|w| w.owner_key == api_key_hash)
+    }
+
+    /// Watchlists with a webhook that match the event
+    fn webhook_targets(&self, event: &WsEvent, game_id: Option<&str>) -> Vec<(String, String)> {
+        self.watchlists
+            .read()
+            .iter()
+            .filter(|w| w.matches(event, game_id))
+            .filter_map(|w| w.webhook_url.clone().map(|url| (w.id.clone(), url)))
+            .collect()
+    }
+}
+
+/// Collection an event is about: named by the event, or that of its asset (looked up in the
+/// current state)
+pub fn event_collection(state: &StateManager, event: &WsEvent) -> Option<String> {
+    if let Some(game_id) = event.game_id() {
+        return Some(game_id.to_string());
+    }
+    event.asset_id()
+        .and_then(|asset_id| state.get_asset(asset_id))
+        .and_then(|asset| asset.data.game_id)
+}
+
+/// POST every event to the webhooks of matching watchlists until the channel closes
+pub async fn run_webhook_dispatcher(
+    store: Arc<WatchlistStore>,
+    state: Arc<StateManager>,
+    mut events: broadcast::Receiver<WsEvent>,
+) {
+    let client = match reqwest::Client::builder().timeout(WEBHOOK_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => {
+            tracing::error!("Webhook delivery disabled: {}", e);
+            return;
+        }
+    };
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                tracing::warn!("Webhook dispatcher lagged, {} events not delivered", skipped);
+                store.webhook_stats.failed.fetch_add(skipped, Ordering::Relaxed);
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        if matches!(event, WsEvent::Error { .. }) {
+            continue;
+        }
+        let game_id = event_collection(&state, &event);
+        for (watchlist_id, url) in store.webhook_targets(&event, game_id.as_deref()) {
+            let payload = WebhookPayload {
+                watchlist_id: &watchlist_id,
+                event: WsEventEnvelope { event_version: EVENT_VERSION, event: &event },
+            };
+            let body = match serde_json::to_vec(&payload) {
+                Ok(body) => body,
+                Err(_) => continue,
+            };
+            let request = client
+                .post(&url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body);
+            let store = store.clone();
+            // Deliver concurrently so one slow endpoint does not hold up the others
+            tokio::spawn(async move {
+                match request.send().await.and_then(|r| r.error_for_status()) {
+                    Ok(_) => {
+                        store.webhook_stats.delivered.fetch_add(1, Ordering::Relaxed);
+                    }
+                    Err(e) => {
+                        store.webhook_stats.failed.fetch_add(1, Ordering::Relaxed);
+                        tracing::warn!("Webhook delivery for watchlist {} failed: {}", watchlist_id, e);
+                    }
+                }
+            });
+        }
+    }
+}
+
+/// Serde helper: `Vec<Hash>` as hex strings in JSON, as raw bytes in bincode
+mod hex_hashes {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use crate::types::Hash;
+
+    pub fn serialize<S: Serializer>(hashes: &[Hash], serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_seq(hashes.iter().map(hex::encode))
+        } else {
+            hashes.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Vec<Hash>, D::Error> {
+        if deserializer.is_human_readable() {
+            Vec::<String>::deserialize(deserializer)?
+                .iter()
+                .map(|s| crate::types::hex_to_hash(s).ok_or_else(|| serde::de::Error::custom("expected 32-byte hex hash")))
+                .collect()
+        } else {
+            Vec::<Hash>::deserialize(deserializer)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    fn new_watchlist(assets: Vec<Hash>, collections: Vec<&str>) -> NewWatchlist {
+        NewWatchlist {
+            name: "favorites".to_string(),
+            assets,
+            collections: collections.into_iter().map(str::to_string).collect(),
+            accounts: vec![],
+            webhook_url: None,
+        }
+    }
+
+    #[test]
+    fn test_watchlists_are_scoped_to_api_key_and_persisted() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config::default();
+        config.storage.db_path = dir.path().join("db");
+        let state = StateManager::new(&config).unwrap();
+        let store = WatchlistStore::open(&state).unwrap();
+
+        let watchlist = store.create("key-a", new_watchlist(vec![[1u8; 32]], vec!["game"])).unwrap();
+        assert!(store.create("key-a", new_watchlist(vec![], vec![])).is_err());
+        let mut bad_url = new_watchlist(vec![[1u8; 32]], vec![]);
+        bad_url.webhook_url = Some("ftp://example.com".to_string());
+        assert!(store.create("key-a", bad_url).is_err());
+
+        assert_eq!(store.list("key-a").len(), 1);
+        assert!(store.list("key-b").is_empty());
+        let key_a = hash_api_key("key-a");
+        let updated = |asset_id: Hash| WsEvent::AssetUpdated { asset_id, owner: [9u8; 32] };
+        assert!(store.key_matches(&key_a, &updated([1u8; 32]), None));
+        assert!(store.key_matches(&key_a, &updated([2u8; 32]), Some("game")));
+        assert!(!store.key_matches(&key_a, &updated([2u8; 32]), Some("other")));
+        assert!(!store.key_matches(&hash_api_key("key-b"), &updated([1u8; 32]), None));
+
+        // Price changes name their collection
+        let price_changed = WsEvent::CollectionPriceChanged {
+            game_id: "game".to_string(),
+            floor_price: Some(10),
+            previous_floor_price: None,
+            last_price: None,
+            previous_last_price: None,
+            height: 1,
+        };
+        let collection = event_collection(&state, &price_changed);
+        assert!(store.key_matches(&key_a, &price_changed, collection.as_deref()));
+
+        // Accounts match events they own, such as payment settlements
+        let mut by_account = new_watchlist(vec![], vec![]);
+        by_account.accounts = vec![[9u8; 32]];
+        store.create("key-c", by_account).unwrap();
+        let settled = WsEvent::PaymentRequestSettled { request_id: [3u8; 32], requester: [9u8; 32], payer: [4u8; 32], amount: 5 };
+        assert!(store.key_matches(&hash_api_key("key-c"), &settled, None));
+        assert!(!store.key_matches(&key_a, &settled, None));
+
+        // Other keys cannot delete it
+        assert!(!store.delete("key-b", &watchlist.id).unwrap());
+        let reopened = WatchlistStore::open(&state).unwrap();
+        assert_eq!(reopened.list("key-a")[0].id, watchlist.id);
+
+        assert!(reopened.delete("key-a", &watchlist.id).unwrap());
+        assert!(WatchlistStore::open(&state).unwrap().list("key-a").is_empty());
+    }
+}
@@ -1,62 +1,529 @@
 //! WebSocket event types for real-time notifications
 //!
 //! This module contains event types that are broadcast to WebSocket clients
-//! when asset operations occur in the blockchain.
+//! when asset operations occur in the blockchain (and when payment requests settle or
+//! game seasons start and end).
+//!
+//! Events are serialized per subscriber in one of the supported schema versions:
+//! - **v2** (current): `{ "event_version": 2, "type": "asset_created", "data": { ... } }`.
+//!   Ids and addresses are hex strings; densities are `{ "level": 0, "name": "ethereal" }`.
+//! - **v1** (legacy, kept for one version): the flat `{ "type": ..., <fields> }` shape with
+//!   densities as debug strings (`"Ethereal"`).
 
-use serde::Serialize;
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use crate::types::{Address, DensityLevel, Hash};
+use crate::upgrade::UpgradeOutcome;
 
-/// WebSocket event types
-#[derive(Debug, Clone, Serialize)]
-#[serde(tag = "type")]
+/// Current event schema version
+pub const EVENT_VERSION: u32 = 2;
+/// Oldest event schema version still served
+pub const MIN_EVENT_VERSION: u32 = 1;
+
+fn serialize_hex<S: Serializer>(bytes: &[u8; 32], serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_str(&hex::encode(bytes))
+}
+
+fn serialize_hex_vec<S: Serializer>(items: &[Hash], serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    serializer.collect_seq(items.iter().map(hex::encode))
+}
+
+fn decode_hex<E: serde::de::Error>(value: &str) -> std::result::Result<[u8; 32], E> {
+    hex::decode(value)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| E::custom(format!("expected 32 hex-encoded bytes, got {:?}", value)))
+}
+
+fn deserialize_hex<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<[u8; 32], D::Error> {
+    decode_hex(&String::deserialize(deserializer)?)
+}
+
+fn deserialize_hex_vec<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Vec<Hash>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?.iter().map(|item| decode_hex(item)).collect()
+}
+
+/// Density level as a stable numeric level with a string tag
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct DensityTag {
+    pub level: u8,
+    pub name: &'static str,
+}
+
+impl From<DensityLevel> for DensityTag {
+    fn from(density: DensityLevel) -> Self {
+        let (level, name) = match density {
+            DensityLevel::Ethereal => (0, "ethereal"),
+            DensityLevel::Light => (1, "light"),
+            DensityLevel::Dense => (2, "dense"),
+            DensityLevel::Core => (3, "core"),
+        };
+        Self { level, name }
+    }
+}
+
+fn serialize_density<S: Serializer>(density: &DensityLevel, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    DensityTag::from(*density).serialize(serializer)
+}
+
+/// Reads the `level` of a density tag (the name only mirrors it)
+fn deserialize_density<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<DensityLevel, D::Error> {
+    #[derive(Deserialize)]
+    struct Level {
+        level: u8,
+    }
+    match Level::deserialize(deserializer)?.level {
+        0 => Ok(DensityLevel::Ethereal),
+        1 => Ok(DensityLevel::Light),
+        2 => Ok(DensityLevel::Dense),
+        3 => Ok(DensityLevel::Core),
+        level => Err(D::Error::custom(format!("unknown density level {}", level))),
+    }
+}
+
+/// Serialize events as their v2 JSON. Formats that are not self-describing (bincode) cannot
+/// decode the tagged `WsEvent` shape, so values stored with them keep events in this form.
+pub(crate) fn serialize_events_json<S: Serializer>(events: &[WsEvent], serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    let json = serde_json::to_string(events).map_err(serde::ser::Error::custom)?;
+    serializer.serialize_str(&json)
+}
+
+/// Read events written by `serialize_events_json`
+pub(crate) fn deserialize_events_json<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Vec<WsEvent>, D::Error> {
+    serde_json::from_str(&String::deserialize(deserializer)?).map_err(D::Error::custom)
+}
+
+/// WebSocket event types (serialize to the current v2 `type`/`data` shape)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
 pub enum WsEvent {
-    #[serde(rename = "asset_created")]
     AssetCreated {
-        asset_id: String,
-        owner: String,
-        density: String,
+        #[serde(serialize_with = "serialize_hex", deserialize_with = "deserialize_hex")]
+        asset_id: Hash,
+        #[serde(serialize_with = "serialize_hex", deserialize_with = "deserialize_hex")]
+        owner: Address,
+        #[serde(serialize_with = "serialize_density", deserialize_with = "deserialize_density")]
+        density: DensityLevel,
     },
-    #[serde(rename = "asset_updated")]
     AssetUpdated {
-        asset_id: String,
-        owner: String,
+        #[serde(serialize_with = "serialize_hex", deserialize_with = "deserialize_hex")]
+        asset_id: Hash,
+        #[serde(serialize_with = "serialize_hex", deserialize_with = "deserialize_hex")]
+        owner: Address,
     },
-    #[serde(rename = "asset_condensed")]
     AssetCondensed {
-        asset_id: String,
-        new_density: String,
+        #[serde(serialize_with = "serialize_hex", deserialize_with = "deserialize_hex")]
+        asset_id: Hash,
+        #[serde(serialize_with = "serialize_density", deserialize_with = "deserialize_density")]
+        new_density: DensityLevel,
     },
-    #[serde(rename = "asset_evaporated")]
     AssetEvaporated {
-        asset_id: String,
-        new_density: String,
+        #[serde(serialize_with = "serialize_hex", deserialize_with = "deserialize_hex")]
+        asset_id: Hash,
+        #[serde(serialize_with = "serialize_density", deserialize_with = "deserialize_density")]
+        new_density: DensityLevel,
     },
-    #[serde(rename = "asset_merged")]
     AssetMerged {
-        asset_id: String,
-        merged_asset_id: String,
+        #[serde(serialize_with = "serialize_hex", deserialize_with = "deserialize_hex")]
+        asset_id: Hash,
+        #[serde(serialize_with = "serialize_hex", deserialize_with = "deserialize_hex")]
+        merged_asset_id: Hash,
     },
-    #[serde(rename = "asset_split")]
     AssetSplit {
-        asset_id: String,
-        created_assets: Vec<String>,
+        #[serde(serialize_with = "serialize_hex", deserialize_with = "deserialize_hex")]
+        asset_id: Hash,
+        #[serde(serialize_with = "serialize_hex_vec", deserialize_with = "deserialize_hex_vec")]
+        created_assets: Vec<Hash>,
     },
-    #[serde(rename = "asset_permission_changed")]
     AssetPermissionChanged {
-        asset_id: String,
-        owner: String,
+        #[serde(serialize_with = "serialize_hex", deserialize_with = "deserialize_hex")]
+        asset_id: Hash,
+        #[serde(serialize_with = "serialize_hex", deserialize_with = "deserialize_hex")]
+        owner: Address,
     },
-    #[serde(rename = "asset_attribute_updated")]
     AssetAttributeUpdated {
-        asset_id: String,
-        owner: String,
+        #[serde(serialize_with = "serialize_hex", deserialize_with = "deserialize_hex")]
+        asset_id: Hash,
+        #[serde(serialize_with = "serialize_hex", deserialize_with = "deserialize_hex")]
+        owner: Address,
+        /// Names of the asset's attributes after the update
         attributes: Vec<String>,
     },
-    #[serde(rename = "asset_version_created")]
     AssetVersionCreated {
-        asset_id: String,
+        #[serde(serialize_with = "serialize_hex", deserialize_with = "deserialize_hex")]
+        asset_id: Hash,
         version: u64,
+        #[serde(serialize_with = "serialize_hex", deserialize_with = "deserialize_hex")]
+        owner: Address,
+    },
+    /// A payment request was paid; `requester` received `amount`
+    PaymentRequestSettled {
+        #[serde(serialize_with = "serialize_hex", deserialize_with = "deserialize_hex")]
+        request_id: Hash,
+        #[serde(serialize_with = "serialize_hex", deserialize_with = "deserialize_hex")]
+        requester: Address,
+        #[serde(serialize_with = "serialize_hex", deserialize_with = "deserialize_hex")]
+        payer: Address,
+        amount: u64,
+    },
+    /// A game's season started (at the beginning of block `start_height`)
+    SeasonStarted {
+        #[serde(serialize_with = "serialize_hex", deserialize_with = "deserialize_hex")]
+        season_id: Hash,
+        game_id: String,
+        season: String,
+        start_height: u64,
+        end_height: u64,
+    },
+    /// A game's season ended (at the end of block `end_height`) and froze `frozen_collections`
+    SeasonEnded {
+        #[serde(serialize_with = "serialize_hex", deserialize_with = "deserialize_hex")]
+        season_id: Hash,
+        game_id: String,
+        season: String,
+        end_height: u64,
+        frozen_collections: Vec<String>,
+    },
+    /// An upgrade's outcome was rolled; `value` is the attribute after it (none when the
+    /// asset was destroyed)
+    AssetUpgraded {
+        #[serde(serialize_with = "serialize_hex", deserialize_with = "deserialize_hex")]
+        asset_id: Hash,
+        #[serde(serialize_with = "serialize_hex", deserialize_with = "deserialize_hex")]
+        owner: Address,
+        #[serde(serialize_with = "serialize_hex", deserialize_with = "deserialize_hex")]
+        upgrade_id: Hash,
+        game_id: String,
+        outcome: UpgradeOutcome,
+        attribute: String,
+        value: Option<u64>,
+        #[serde(serialize_with = "serialize_hex_vec", deserialize_with = "deserialize_hex_vec")]
+        catalysts: Vec<Hash>,
+    },
+    /// The wave holding the block that changed the asset at `height` finalized: the change
+    /// is permanent (sent for each asset a block changed, including merged and split ones)
+    AssetFinalized {
+        #[serde(serialize_with = "serialize_hex", deserialize_with = "deserialize_hex")]
+        asset_id: Hash,
+        height: u64,
+        wave: u64,
+    },
+    /// The block at `height` moved a collection's floor (lowest open ask) or last sale price
+    CollectionPriceChanged {
+        game_id: String,
+        floor_price: Option<u64>,
+        previous_floor_price: Option<u64>,
+        last_price: Option<u64>,
+        previous_last_price: Option<u64>,
+        height: u64,
+    },
+    Error { message: String },
+}
+
+/// Versioned event as sent to v2 subscribers
+#[derive(Debug, Serialize)]
+pub struct WsEventEnvelope<'a> {
+    pub event_version: u32,
+    #[serde(flatten)]
+    pub event: &'a WsEvent,
+}
+
+/// Legacy v1 event shape (flat fields, debug-formatted densities)
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LegacyWsEvent {
+    AssetCreated { asset_id: String, owner: String, density: String },
+    AssetUpdated { asset_id: String, owner: String },
+    AssetCondensed { asset_id: String, new_density: String },
+    AssetEvaporated { asset_id: String, new_density: String },
+    AssetMerged { asset_id: String, merged_asset_id: String },
+    AssetSplit { asset_id: String, created_assets: Vec<String> },
+    AssetPermissionChanged { asset_id: String, owner: String },
+    AssetAttributeUpdated { asset_id: String, owner: String, attributes: Vec<String> },
+    AssetVersionCreated { asset_id: String, version: u64, owner: String },
+    PaymentRequestSettled { request_id: String, requester: String, payer: String, amount: u64 },
+    SeasonStarted { season_id: String, game_id: String, season: String, start_height: u64, end_height: u64 },
+    SeasonEnded { season_id: String, game_id: String, season: String, end_height: u64, frozen_collections: Vec<String> },
+    AssetUpgraded {
+        asset_id: String,
         owner: String,
+        upgrade_id: String,
+        game_id: String,
+        outcome: String,
+        attribute: String,
+        value: Option<u64>,
+        catalysts: Vec<String>,
+    },
+    AssetFinalized { asset_id: String, height: u64, wave: u64 },
+    CollectionPriceChanged {
+        game_id: String,
+        floor_price: Option<u64>,
+        previous_floor_price: Option<u64>,
+        last_price: Option<u64>,
+        previous_last_price: Option<u64>,
+        height: u64,
     },
-    #[serde(rename = "error")]
     Error { message: String },
 }
+
+impl From<&WsEvent> for LegacyWsEvent {
+    fn from(event: &WsEvent) -> Self {
+        match event {
+            WsEvent::AssetCreated { asset_id, owner, density } => LegacyWsEvent::AssetCreated {
+                asset_id: hex::encode(asset_id),
+                owner: hex::encode(owner),
+                density: format!("{:?}", density),
+            },
+            WsEvent::AssetUpdated { asset_id, owner } => LegacyWsEvent::AssetUpdated {
+                asset_id: hex::encode(asset_id),
+                owner: hex::encode(owner),
+            },
+            WsEvent::AssetCondensed { asset_id, new_density } => LegacyWsEvent::AssetCondensed {
+                asset_id: hex::encode(asset_id),
+                new_density: format!("{:?}", new_density),
+            },
+            WsEvent::AssetEvaporated { asset_id, new_density } => LegacyWsEvent::AssetEvaporated {
+                asset_id: hex::encode(asset_id),
+                new_density: format!("{:?}", new_density),
+            },
+            WsEvent::AssetMerged { asset_id, merged_asset_id } => LegacyWsEvent::AssetMerged {
+                asset_id: hex::encode(asset_id),
+                merged_asset_id: hex::encode(merged_asset_id),
+            },
+            WsEvent::AssetSplit { asset_id, created_assets } => LegacyWsEvent::AssetSplit {
+                asset_id: hex::encode(asset_id),
+                created_assets: created_assets.iter().map(hex::encode).collect(),
+            },
+            WsEvent::AssetPermissionChanged { asset_id, owner } => LegacyWsEvent::AssetPermissionChanged {
+                asset_id: hex::encode(asset_id),
+                owner: hex::encode(owner),
+            },
+            WsEvent::AssetAttributeUpdated { asset_id, owner, attributes } => LegacyWsEvent::AssetAttributeUpdated {
+                asset_id: hex::encode(asset_id),
+                owner: hex::encode(owner),
+                attributes: attributes.clone(),
+            },
+            WsEvent::AssetVersionCreated { asset_id, version, owner } => LegacyWsEvent::AssetVersionCreated {
+                asset_id: hex::encode(asset_id),
+                version: *version,
+                owner: hex::encode(owner),
+            },
+            WsEvent::PaymentRequestSettled { request_id, requester, payer, amount } => LegacyWsEvent::PaymentRequestSettled {
+                request_id: hex::encode(request_id),
+                requester: hex::encode(requester),
+                payer: hex::encode(payer),
+                amount: *amount,
+            },
+            WsEvent::SeasonStarted { season_id, game_id, season, start_height, end_height } => LegacyWsEvent::SeasonStarted {
+                season_id: hex::encode(season_id),
+                game_id: game_id.clone(),
+                season: season.clone(),
+                start_height: *start_height,
+                end_height: *end_height,
+            },
+            WsEvent::SeasonEnded { season_id, game_id, season, end_height, frozen_collections } => LegacyWsEvent::SeasonEnded {
+                season_id: hex::encode(season_id),
+                game_id: game_id.clone(),
+                season: season.clone(),
+                end_height: *end_height,
+                frozen_collections: frozen_collections.clone(),
+            },
+            WsEvent::AssetUpgraded { asset_id, owner, upgrade_id, game_id, outcome, attribute, value, catalysts } => {
+                LegacyWsEvent::AssetUpgraded {
+                    asset_id: hex::encode(asset_id),
+                    owner: hex::encode(owner),
+                    upgrade_id: hex::encode(upgrade_id),
+                    game_id: game_id.clone(),
+                    outcome: outcome.as_str().to_string(),
+                    attribute: attribute.clone(),
+                    value: *value,
+                    catalysts: catalysts.iter().map(hex::encode).collect(),
+                }
+            }
+            WsEvent::AssetFinalized { asset_id, height, wave } => LegacyWsEvent::AssetFinalized {
+                asset_id: hex::encode(asset_id),
+                height: *height,
+                wave: *wave,
+            },
+            WsEvent::CollectionPriceChanged {
+                game_id,
+                floor_price,
+                previous_floor_price,
+                last_price,
+                previous_last_price,
+                height,
+            } => LegacyWsEvent::CollectionPriceChanged {
+                game_id: game_id.clone(),
+                floor_price: *floor_price,
+                previous_floor_price: *previous_floor_price,
+                last_price: *last_price,
+                previous_last_price: *previous_last_price,
+                height: *height,
+            },
+            WsEvent::Error { message } => LegacyWsEvent::Error { message: message.clone() },
+        }
+    }
+}
+
+impl WsEvent {
+    /// Event type tag (same in every schema version)
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            WsEvent::AssetCreated { .. } => "asset_created",
+            WsEvent::AssetUpdated { .. } => "asset_updated",
+            WsEvent::AssetCondensed { .. } => "asset_condensed",
+            WsEvent::AssetEvaporated { .. } => "asset_evaporated",
+            WsEvent::AssetMerged { .. } => "asset_merged",
+            WsEvent::AssetSplit { .. } => "asset_split",
+            WsEvent::AssetPermissionChanged { .. } => "asset_permission_changed",
+            WsEvent::AssetAttributeUpdated { .. } => "asset_attribute_updated",
+            WsEvent::AssetVersionCreated { .. } => "asset_version_created",
+            WsEvent::PaymentRequestSettled { .. } => "payment_request_settled",
+            WsEvent::SeasonStarted { .. } => "season_started",
+            WsEvent::SeasonEnded { .. } => "season_ended",
+            WsEvent::AssetUpgraded { .. } => "asset_upgraded",
+            WsEvent::AssetFinalized { .. } => "asset_finalized",
+            WsEvent::CollectionPriceChanged { .. } => "collection_price_changed",
+            WsEvent::Error { .. } => "error",
+        }
+    }
+
+    /// Asset the event is about
+    pub fn asset_id(&self) -> Option<&Hash> {
+        match self {
+            WsEvent::AssetCreated { asset_id, .. }
+            | WsEvent::AssetUpdated { asset_id, .. }
+            | WsEvent::AssetCondensed { asset_id, .. }
+            | WsEvent::AssetEvaporated { asset_id, .. }
+            | WsEvent::AssetMerged { asset_id, .. }
+            | WsEvent::AssetSplit { asset_id, .. }
+            | WsEvent::AssetPermissionChanged { asset_id, .. }
+            | WsEvent::AssetAttributeUpdated { asset_id, .. }
+            | WsEvent::AssetVersionCreated { asset_id, .. }
+            | WsEvent::AssetUpgraded { asset_id, .. }
+            | WsEvent::AssetFinalized { asset_id, .. } => Some(asset_id),
+            WsEvent::PaymentRequestSettled { .. }
+            | WsEvent::SeasonStarted { .. }
+            | WsEvent::SeasonEnded { .. }
+            | WsEvent::CollectionPriceChanged { .. }
+            | WsEvent::Error { .. } => None,
+        }
+    }
+
+    /// Every asset the event changed: its asset, plus the merged or split off ones
+    pub fn changed_assets(&self) -> Vec<Hash> {
+        let mut assets: Vec<Hash> = self.asset_id().copied().into_iter().collect();
+        match self {
+            WsEvent::AssetMerged { merged_asset_id, .. } => assets.push(*merged_asset_id),
+            WsEvent::AssetSplit { created_assets, .. } => assets.extend(created_assets),
+            _ => {}
+        }
+        assets
+    }
+
+    /// Collection of events that name one directly (asset events are resolved through the asset)
+    pub fn game_id(&self) -> Option<&str> {
+        match self {
+            WsEvent::SeasonStarted { game_id, .. }
+            | WsEvent::SeasonEnded { game_id, .. }
+            | WsEvent::AssetUpgraded { game_id, .. }
+            | WsEvent::CollectionPriceChanged { game_id, .. } => Some(game_id),
+            _ => None,
+        }
+    }
+
+    /// Asset owner (the requester for payment events), for events that carry it
+    pub fn owner(&self) -> Option<&Address> {
+        match self {
+            WsEvent::AssetCreated { owner, .. }
+            | WsEvent::AssetUpdated { owner, .. }
+            | WsEvent::AssetPermissionChanged { owner, .. }
+            | WsEvent::AssetAttributeUpdated { owner, .. }
+            | WsEvent::AssetVersionCreated { owner, .. }
+            | WsEvent::AssetUpgraded { owner, .. }
+            | WsEvent::PaymentRequestSettled { requester: owner, .. } => Some(owner),
+            _ => None,
+        }
+    }
+
+    /// Serialize the event in the given schema version
+    pub fn to_json(&self, event_version: u32) -> serde_json::Result<String> {
+        if event_version < EVENT_VERSION {
+            serde_json::to_string(&LegacyWsEvent::from(self))
+        } else {
+            serde_json::to_string(&WsEventEnvelope { event_version: EVENT_VERSION, event: self })
+        }
+    }
+}
+
+/// Whether `event_version` can be served
+pub fn is_supported_event_version(event_version: u32) -> bool {
+    (MIN_EVENT_VERSION..=EVENT_VERSION).contains(&event_version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn created() -> WsEvent {
+        WsEvent::AssetCreated {
+            asset_id: [0xab; 32],
+            owner: [0x01; 32],
+            density: DensityLevel::Light,
+        }
+    }
+
+    #[test]
+    fn test_v2_envelope_shape() {
+        let json: serde_json::Value = serde_json::from_str(&created().to_json(2).unwrap()).unwrap();
+        assert_eq!(json["event_version"], 2);
+        assert_eq!(json["type"], "asset_created");
+        assert_eq!(json["data"]["asset_id"], hex::encode([0xab; 32]));
+        assert_eq!(json["data"]["owner"], hex::encode([0x01; 32]));
+        assert_eq!(json["data"]["density"]["level"], 1);
+        assert_eq!(json["data"]["density"]["name"], "light");
+    }
+
+    #[test]
+    fn test_events_round_trip_through_bincode() {
+        #[derive(Serialize, Deserialize)]
+        struct Stored {
+            #[serde(serialize_with = "serialize_events_json", deserialize_with = "deserialize_events_json")]
+            events: Vec<WsEvent>,
+        }
+        let split = WsEvent::AssetSplit { asset_id: [1; 32], created_assets: vec![[2; 32], [3; 32]] };
+        let encoded = bincode::serialize(&Stored { events: vec![created(), split] }).unwrap();
+        let decoded: Stored = bincode::deserialize(&encoded).unwrap();
+        match &decoded.events[..] {
+            [
+                WsEvent::AssetCreated { asset_id, owner, density },
+                WsEvent::AssetSplit { created_assets, .. },
+            ] => {
+                assert_eq!((*asset_id, *owner, *density), ([0xab; 32], [0x01; 32], DensityLevel::Light));
+                assert_eq!(created_assets, &vec![[2; 32], [3; 32]]);
+            }
+            other => panic!("unexpected events {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_v1_keeps_legacy_shape() {
+        let json: serde_json::Value = serde_json::from_str(&created().to_json(1).unwrap()).unwrap();
+        assert!(json.get("event_version").is_none());
+        assert_eq!(json["type"], "asset_created");
+        assert_eq!(json["asset_id"], hex::encode([0xab; 32]));
+        assert_eq!(json["density"], "Light");
+
+        let split = WsEvent::AssetSplit { asset_id: [1; 32], created_assets: vec![[2; 32]] };
+        let json: serde_json::Value = serde_json::from_str(&split.to_json(1).unwrap()).unwrap();
+        assert_eq!(json["type"], "asset_split");
+        assert_eq!(json["created_assets"][0], hex::encode([2; 32]));
+    }
+
+    #[test]
+    fn test_supported_versions() {
+        assert!(!is_supported_event_version(0));
+        assert!(is_supported_event_version(MIN_EVENT_VERSION));
+        assert!(is_supported_event_version(EVENT_VERSION));
+        assert!(!is_supported_event_version(EVENT_VERSION + 1));
+    }
+}
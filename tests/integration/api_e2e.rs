@@ -7,31 +7,77 @@ use std::sync::Arc;
 use axum::body::Body;
 use axum::http::{Request, StatusCode};
 use bytes::Bytes;
-use haze::api::{create_router, ApiState, EstimateGasRequest};
+use haze::api::{create_router, ApiState, EstimateGasBatchRequest, EstimateGasRequest};
 use haze::config::Config;
 use haze::consensus::ConsensusEngine;
+use haze::http_cache::ResponseCache;
+use haze::messaging::Mailbox;
+use haze::sign_in::SignInService;
+use haze::watchlist::WatchlistStore;
 use haze::state::StateManager;
-use haze::types::{AssetAction, AssetData, DensityLevel, Transaction};
+use haze::types::{AssetAction, AssetData, DensityLevel, Transaction, PROTOCOL_VERSION, STATE_VERSION};
+use haze::version::PeerVersion;
 use tower::util::ServiceExt;
 
 static INTEGRATION_TEST_DB_COUNTER: AtomicU64 = AtomicU64::new(0);
 
+/// Block at `height` carrying `transactions`, hashed for the default test network
+fn test_block(height: u64, transactions: Vec<Transaction>) -> haze::types::Block {
+    let mut header = haze::types::BlockHeader {
+        hash: [0u8; 32],
+        parent_hash: [0u8; 32],
+        height,
+        timestamp: 0,
+        validator: [0u8; 32],
+        merkle_root: [0u8; 32],
+        state_root: [0u8; 32],
+        wave_number: 0,
+        committee_id: 0,
+        protocol_version: PROTOCOL_VERSION,
+        state_version: STATE_VERSION,
+        signals: 0,
+    };
+    header.hash = header.compute_hash(&haze::types::network_id(0, &[]));
+    haze::types::Block { header, transactions, dag_references: vec![] }
+}
+
 fn create_test_api_state() -> ApiState {
+    create_test_api_state_with(|_| {})
+}
+
+/// Test API state on a node whose default test config was adjusted by `configure`
+fn create_test_api_state_with(configure: impl FnOnce(&mut Config)) -> ApiState {
     let id = INTEGRATION_TEST_DB_COUNTER.fetch_add(1, Ordering::Relaxed);
     let mut config = Config::default();
     config.storage.db_path = PathBuf::from(format!("./haze_db_test_integration_{}", id));
     config.api.enable_cors = false;
+    configure(&mut config);
+    // Fresh db so blocks persisted by a previous run are not replayed
+    let _ = std::fs::remove_dir_all(&config.storage.db_path);
 
     let state = Arc::new(StateManager::new(&config).unwrap());
     let consensus = Arc::new(ConsensusEngine::new(config.clone(), state.clone()).unwrap());
     let (ws_tx, _) = tokio::sync::broadcast::channel(100);
 
+    let sign_in = config.sign_in.enabled
+        .then(|| Arc::new(SignInService::new(config.sign_in.clone(), config.chain_id)));
+    let mailbox = config.messaging.enabled
+        .then(|| Arc::new(Mailbox::open(config.messaging.clone(), &state).unwrap()));
     ApiState {
         consensus,
+        watchlists: Arc::new(WatchlistStore::open(&state).unwrap()),
+        sign_in,
+        mailbox,
         state,
         config,
         ws_tx,
         connected_peers: Arc::new(AtomicUsize::new(0)),
+        response_cache: Arc::new(ResponseCache::default()),
+        custody: None,
+        tenants: None,
+        metering: None,
+        telemetry: None,
+        faucet: None,
     }
 }
 
@@ -49,6 +95,344 @@ async fn e2e_health() {
     assert_eq!(response.status(), StatusCode::OK);
 }
 
+#[tokio::test]
+async fn e2e_detailed_health_reports_clock() {
+    let api_state = create_test_api_state();
+    let app = create_router(api_state);
+
+    let req = Request::builder()
+        .uri("/api/v1/health")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(req).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["data"]["status"], "ok");
+    assert_eq!(json["data"]["clock"]["peers"], 0);
+    assert_eq!(json["data"]["clock"]["drifting"], false);
+}
+
+#[tokio::test]
+async fn e2e_node_version_reports_versions() {
+    let api_state = create_test_api_state();
+    api_state.consensus.peer_versions().record("peer-1".to_string(), PeerVersion::legacy(api_state.config.chain_id));
+    let app = create_router(api_state);
+
+    let req = Request::builder()
+        .uri("/api/v1/node/version")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(req).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["data"]["binary_version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(json["data"]["protocol_version"], PROTOCOL_VERSION);
+    assert_eq!(json["data"]["state_version"], STATE_VERSION);
+    assert_eq!(json["data"]["peers"][0]["peer_id"], "peer-1");
+    assert_eq!(json["data"]["peers"][0]["protocol_version"], 1);
+    assert_eq!(json["data"]["peers"][0]["compatibility"]["mode"], "degraded");
+}
+
+#[tokio::test]
+async fn e2e_node_peers_report_connection_health() {
+    let api_state = create_test_api_state();
+    let health = api_state.consensus.peer_health();
+    let now = haze::time_sync::now_ms();
+    health.connected("peer-1".to_string(), Some("/ip4/10.0.0.1/tcp/9000".to_string()), true, now);
+    health.record_pong("peer-1", 42, now);
+    health.connected("peer-2".to_string(), None, false, now);
+    health.record_dead_disconnect();
+    health.disconnected("peer-2", now);
+    let app = create_router(api_state);
+
+    let req = Request::builder().uri("/api/v1/node/peers").body(Body::empty()).unwrap();
+    let response = app.clone().oneshot(req).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["data"]["connected"], 1);
+    assert_eq!(json["data"]["dead_peers_disconnected"], 1);
+    assert_eq!(json["data"]["peers"][0]["peer_id"], "peer-1");
+    assert_eq!(json["data"]["peers"][0]["rtt_ms"], 42);
+    assert_eq!(json["data"]["peers"][0]["outbound"], true);
+    assert!(json["data"]["peers"][0]["last_seen_ms_ago"].is_number());
+
+    let req = Request::builder().uri("/api/v1/metrics/prometheus").body(Body::empty()).unwrap();
+    let response = app.oneshot(req).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let metrics = String::from_utf8(body.to_vec()).unwrap();
+    assert!(metrics.contains("haze_peer_rtt_ms{peer=\"peer-1\"} 42"));
+    assert!(metrics.contains("haze_dead_peers_disconnected_total 1"));
+}
+
+#[tokio::test]
+async fn e2e_prometheus_latency_histograms() {
+    let api_state = create_test_api_state_with(|config| {
+        config.chain_id = haze::dev::DEV_CHAIN_ID;
+        config.dev.enabled = true;
+        config.dev.relaxed_signatures = true;
+    });
+    let from = haze::dev::dev_account_key(0).address();
+    api_state.state.create_test_account(from, 1_000, 0);
+    let consensus = api_state.consensus.clone();
+    let app = create_router(api_state);
+
+    let transfer = serde_json::json!({
+        "transaction": {
+            "Transfer": {
+                "from": hex::encode(from),
+                "to": hex::encode([0x22u8; 32]),
+                "amount": 100,
+                "fee": 1,
+                "nonce": 0,
+                "signature": hex::encode([0u8; 64]),
+            }
+        }
+    });
+    let req = Request::builder()
+        .method("POST")
+        .uri("/api/v1/transactions")
+        .header("content-type", "application/json")
+        .body(Body::from(transfer.to_string()))
+        .unwrap();
+    assert_eq!(app.clone().oneshot(req).await.unwrap().status(), StatusCode::OK);
+    let block = consensus.create_block([4u8; 32]).unwrap();
+    consensus.process_block(&block).unwrap();
+    let req = Request::builder().uri("/no/such/route").body(Body::empty()).unwrap();
+    assert_eq!(app.clone().oneshot(req).await.unwrap().status(), StatusCode::NOT_FOUND);
+
+    let req = Request::builder().uri("/api/v1/metrics/prometheus").body(Body::empty()).unwrap();
+    let response = app.oneshot(req).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let metrics = String::from_utf8(body.to_vec()).unwrap();
+    assert!(metrics.contains("# TYPE haze_block_apply_seconds histogram\n"));
+    assert!(metrics.contains("haze_block_apply_seconds_count{operation=\"apply\"} 1\n"));
+    assert!(metrics.contains("haze_tx_apply_seconds_count{operation=\"Transfer\"} 1\n"));
+    assert!(metrics.contains("haze_mempool_admission_seconds_count{operation=\"Transfer\"} 1\n"));
+    assert!(metrics.contains("haze_api_request_seconds_bucket{operation=\"POST /api/v1/transactions\",le=\"+Inf\"} 1\n"));
+    // Unrouted paths add no time series
+    assert!(!metrics.contains("/no/such/route"));
+}
+
+#[tokio::test]
+async fn e2e_node_info_reports_role_and_read_only_surface() {
+    let api_state = create_test_api_state_with(|config| {
+        config.node_id = "light-1".to_string();
+        config.role.preset = Some(haze::config::NodeRole::Light);
+    });
+    let app = create_router(api_state);
+
+    let req = Request::builder()
+        .uri("/api/v1/node/info")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(req).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["data"]["node_id"], "light-1");
+    assert_eq!(json["data"]["role"], "light");
+    assert_eq!(json["data"]["subsystems"]["block_production"], false);
+    assert_eq!(json["data"]["subsystems"]["mempool"], false);
+    assert_eq!(json["data"]["subsystems"]["api"], "read");
+    assert_eq!(json["data"]["version"]["binary_version"], env!("CARGO_PKG_VERSION"));
+
+    // Writes are refused before reaching a handler
+    let req = Request::builder()
+        .method("POST")
+        .uri("/api/v1/transactions")
+        .header("content-type", "application/json")
+        .body(Body::from("{}"))
+        .unwrap();
+    let response = app.oneshot(req).await.unwrap();
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+}
+
+#[tokio::test]
+async fn e2e_features_report_pinned_activation() {
+    let api_state = create_test_api_state_with(|config| {
+        config.features.signal.clear();
+        config.features.activation_heights.insert("strict_blocks".to_string(), 100);
+    });
+    let app = create_router(api_state);
+
+    let req = Request::builder()
+        .uri("/api/v1/features")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(req).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let feature = &json["data"]["features"][0];
+    assert_eq!(feature["feature"], "strict_blocks");
+    assert_eq!(feature["signaling"], false);
+    assert_eq!(feature["activation"]["height"], 100);
+    assert_eq!(feature["activation"]["source"], "pinned");
+    assert_eq!(feature["active"], false);
+}
+
+#[tokio::test]
+async fn e2e_dev_node_faucet_and_instant_sealing() {
+    let mut api_state = create_test_api_state_with(|config| {
+        config.chain_id = haze::dev::DEV_CHAIN_ID;
+        config.dev.enabled = true;
+        config.dev.accounts = 2;
+        config.dev.relaxed_signatures = true;
+        config.consensus.max_transactions_per_block = 1;
+    });
+    let accounts = haze::dev::fund_accounts(&api_state.state, &api_state.config.dev).unwrap();
+    assert_eq!(accounts.len(), 2);
+    assert_eq!(accounts[0].balance, api_state.config.dev.account_balance);
+    api_state.faucet = Some(Arc::new(haze::dev::Faucet::new(api_state.consensus.clone(), &api_state.config)));
+    tokio::spawn(haze::dev::run_sealer(api_state.consensus.clone(), [0x77u8; 32]));
+    let state = api_state.state.clone();
+    let app = create_router(api_state);
+    let post = |uri: &str, body: serde_json::Value| {
+        let app = app.clone();
+        let request = Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        async move { app.oneshot(request).await.unwrap() }
+    };
+
+    // The faucet answers once its transfer is sealed in a block of its own
+    let player = [0x42u8; 32];
+    let response = post("/api/v1/dev/faucet", serde_json::json!({ "address": hex::encode(player), "amount": 500 })).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(state.get_account(&player).unwrap().balance, 500);
+    assert_eq!(state.current_height(), 1);
+
+    // Relaxed signatures: a dev account transfers with a dummy signature
+    let from = haze::dev::dev_account_key(0).address();
+    let transfer = serde_json::json!({
+        "transaction": {
+            "Transfer": {
+                "from": hex::encode(from),
+                "to": hex::encode(player),
+                "amount": 100,
+                "fee": 1,
+                "nonce": 0,
+                "signature": hex::encode([0u8; 64]),
+            }
+        }
+    });
+    assert_eq!(post("/api/v1/transactions", transfer).await.status(), StatusCode::OK);
+    for _ in 0..250 {
+        if state.current_height() == 2 {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    assert_eq!(state.get_account(&player).unwrap().balance, 600);
+}
+
+#[tokio::test]
+async fn e2e_dev_sealer_keeps_sealing_after_a_failed_transaction() {
+    let api_state = create_test_api_state_with(|config| {
+        config.chain_id = haze::dev::DEV_CHAIN_ID;
+        config.dev.enabled = true;
+        config.dev.relaxed_signatures = true;
+        config.consensus.max_transactions_per_block = 1;
+    });
+    let (state, consensus) = (api_state.state.clone(), api_state.consensus.clone());
+    let (broke, funded) = ([0x11u8; 32], [0x22u8; 32]);
+    state.create_test_account(broke, 1_000, 0);
+    state.create_test_account(funded, 1_000, 0);
+    let transfer = |from: [u8; 32], nonce: u64| Transaction::Transfer {
+        from,
+        to: [0x33u8; 32],
+        amount: 100,
+        fee: 1,
+        nonce,
+        chain_id: None,
+        valid_until_height: None,
+        signature: vec![0; 64],
+    };
+    // The lower sender is sealed first, and its funds are gone by then
+    let failing = transfer(broke, 0);
+    consensus.add_transaction(failing.clone()).unwrap();
+    consensus.add_transaction(transfer(funded, 0)).unwrap();
+    state.create_test_account(broke, 0, 0);
+    tokio::spawn(haze::dev::run_sealer(consensus.clone(), [0x77u8; 32]));
+
+    let wait_for_height = |height: u64| {
+        let state = state.clone();
+        async move {
+            for _ in 0..250 {
+                if state.current_height() == height {
+                    break;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            }
+        }
+    };
+    wait_for_height(1).await;
+    assert_eq!(state.get_account(&[0x33u8; 32]).unwrap().balance, 100);
+    assert!(state.get_dead_letter(&failing.hash()).is_some());
+
+    // Later transactions are still sealed
+    consensus.add_transaction(transfer(funded, 1)).unwrap();
+    wait_for_height(2).await;
+    assert_eq!(state.get_account(&[0x33u8; 32]).unwrap().balance, 200);
+}
+
+#[tokio::test]
+async fn e2e_faucet_is_not_served_outside_dev_nodes() {
+    let app = create_router(create_test_api_state());
+    let request = Request::builder()
+        .method("POST")
+        .uri("/api/v1/dev/faucet")
+        .header("content-type", "application/json")
+        .body(Body::from(serde_json::json!({ "address": hex::encode([1u8; 32]) }).to_string()))
+        .unwrap();
+    assert_eq!(app.oneshot(request).await.unwrap().status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn e2e_rejected_transaction_returns_error_code() {
+    let api_state = create_test_api_state();
+    let from = [0x11u8; 32];
+    api_state.state.create_test_account(from, 10, 0);
+    let app = create_router(api_state);
+
+    let body = serde_json::json!({
+        "transaction": {
+            "Transfer": {
+                "from": hex::encode(from),
+                "to": hex::encode([0x22u8; 32]),
+                "amount": 100,
+                "fee": 1,
+                "nonce": 0,
+                "signature": hex::encode([0u8; 64]),
+            }
+        }
+    });
+    let req = Request::builder()
+        .method("POST")
+        .uri("/api/v1/transactions")
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let response = app.oneshot(req).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["success"], false);
+    assert_eq!(json["error_code"], 2002);
+    assert_eq!(json["error_params"]["required"], 101);
+    assert_eq!(json["error_params"]["available"], 10);
+}
+
 #[tokio::test]
 async fn e2e_blockchain_info() {
     let api_state = create_test_api_state();
@@ -63,6 +447,84 @@ async fn e2e_blockchain_info() {
     assert_eq!(response.status(), StatusCode::OK);
 }
 
+#[tokio::test]
+async fn e2e_transaction_size_limit() {
+    let app = create_router(create_test_api_state_with(|config| config.consensus.max_transaction_bytes = 4096));
+    let response = app.clone()
+        .oneshot(Request::builder().uri("/api/v1/blockchain/info").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["data"]["max_transaction_bytes"], 4096);
+
+    let create = |description: String| serde_json::json!({
+        "transaction": {
+            "MistbornAsset": {
+                "from": hex::encode([0x11u8; 32]),
+                "action": "Create",
+                "asset_id": hex::encode([0x33u8; 32]),
+                "data": {
+                    "density": "Light",
+                    "metadata": { "description": description },
+                    "attributes": [],
+                    "game_id": null,
+                    "owner": hex::encode([0x11u8; 32]),
+                },
+                "fee": 0,
+                "nonce": 0,
+                "signature": hex::encode([1u8; 64]),
+            }
+        }
+    });
+    let post = |body: serde_json::Value| Request::builder()
+        .method("POST")
+        .uri("/api/v1/assets")
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+
+    // Within the asset's density limit, but over the transaction limit
+    let response = app.clone().oneshot(post(create("x".repeat(5000)))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["error_code"], 2004);
+    assert_eq!(json["error_params"]["limit"], 4096);
+
+    // Bodies far over the limit are refused before parsing
+    let response = app.oneshot(post(create("x".repeat(200_000)))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[tokio::test]
+async fn e2e_governance_token() {
+    let holder = [0x21u8; 32];
+    let app = create_router(create_test_api_state_with(|config| {
+        config.governance_token.enabled = true;
+        config.governance_token.genesis = vec![haze::config::GenesisAllocation {
+            address: hex::encode(holder),
+            amount: 7_000,
+        }];
+    }));
+    let get = |uri: String| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+    let response = app.clone().oneshot(get("/api/v1/governance".to_string())).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["data"]["symbol"], "HZG");
+    assert_eq!(json["data"]["total_supply"], 7_000);
+    assert_eq!(json["data"]["total_staked"], 0);
+
+    let response = app.oneshot(get(format!("/api/v1/governance/{}", hex::encode(holder)))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["data"]["balance"], 7_000);
+    assert_eq!(json["data"]["staked"], 0);
+    assert_eq!(json["data"]["weight"], 0);
+}
+
 #[tokio::test]
 async fn e2e_get_asset_not_found() {
     let api_state = create_test_api_state();
@@ -115,3 +577,1224 @@ async fn e2e_estimate_gas_create() {
 
     assert_eq!(response.status(), StatusCode::OK);
 }
+
+#[tokio::test]
+async fn e2e_estimate_gas_transfer_and_batch() {
+    let api_state = create_test_api_state();
+    let from = [1u8; 32];
+    let to = [2u8; 32];
+    api_state.state.create_test_account(from, 1_000, 0);
+    let app = create_router(api_state.clone());
+    let transfer = |amount: u64, nonce: u64| Transaction::Transfer {
+        from,
+        to,
+        amount,
+        fee: 10,
+        nonce,
+        chain_id: None,
+        valid_until_height: None,
+        signature: vec![0; 64],
+    };
+    let post = |uri: &str, body: Vec<u8>| {
+        Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(Bytes::from(body)))
+            .unwrap()
+    };
+
+    let body = serde_json::to_vec(&EstimateGasRequest { transaction: transfer(100, 0) }).unwrap();
+    let response = app.clone().oneshot(post("/api/v1/transactions/estimate-gas", body)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["data"]["gas_cost"], 0);
+    assert_eq!(json["data"]["total_fee"], 10);
+
+    // A transfer the sender cannot afford fails with its error
+    let body = serde_json::to_vec(&EstimateGasRequest { transaction: transfer(5_000, 0) }).unwrap();
+    let response = app.clone().oneshot(post("/api/v1/transactions/estimate-gas", body)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // Batches run in order (nonces 0 and 1) and stop at the first failure
+    let transactions = vec![transfer(100, 0), transfer(100, 1), transfer(5_000, 2), transfer(100, 3)];
+    let body = serde_json::to_vec(&EstimateGasBatchRequest { transactions }).unwrap();
+    let response = app.oneshot(post("/api/v1/transactions/estimate-gas/batch", body)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["data"]["estimates"].as_array().unwrap().len(), 2);
+    assert_eq!(json["data"]["total_fee"], 20);
+    assert_eq!(json["data"]["failed_index"], 2);
+    assert!(json["data"]["error"].as_str().unwrap().contains("Insufficient"));
+
+    // Nothing was applied
+    assert_eq!(api_state.state.get_account(&from).unwrap().balance, 1_000);
+}
+
+#[tokio::test]
+async fn e2e_fee_stats_per_wave() {
+    let api_state = create_test_api_state();
+    let from = [1u8; 32];
+    let to = [2u8; 32];
+    api_state.state.create_test_account(from, 10_000, 0);
+
+    let mut block = test_block(api_state.state.current_height() + 1, vec![Transaction::Transfer {
+        from,
+        to,
+        amount: 100,
+        fee: 40,
+        nonce: 0,
+        chain_id: None,
+        valid_until_height: None,
+        signature: vec![0; 64],
+    }]);
+    block.header.wave_number = 3;
+    block.header.hash = block.header.compute_hash(&haze::types::network_id(0, &[]));
+    api_state.state.apply_block(&block).unwrap();
+
+    let app = create_router(api_state);
+    let req = Request::builder()
+        .uri("/api/v1/stats/fees?from_wave=3")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(req).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let waves = json["data"]["waves"].as_array().unwrap();
+    assert_eq!(waves.len(), 1);
+    assert_eq!(waves[0]["wave"], 3);
+    assert_eq!(waves[0]["fees_collected"], 40);
+    assert_eq!(waves[0]["fees_burned"], 20);
+    assert_eq!(waves[0]["fees_redistributed"], 20);
+    assert_eq!(json["data"]["summary"]["fees_burned"], 20);
+}
+
+#[tokio::test]
+async fn e2e_priority_fee_estimate() {
+    let api_state = create_test_api_state();
+    let from = [1u8; 32];
+    api_state.state.create_test_account(from, 10_000, 0);
+    let transactions = [5u64, 40, 90].iter().enumerate().map(|(nonce, fee)| Transaction::Transfer {
+        from,
+        to: [2u8; 32],
+        amount: 1,
+        fee: *fee,
+        nonce: nonce as u64,
+        chain_id: None,
+        valid_until_height: None,
+        signature: vec![0; 64],
+    }).collect();
+    api_state.state.apply_block(&test_block(1, transactions)).unwrap();
+
+    let app = create_router(api_state);
+    let req = Request::builder().uri("/api/v1/gas/estimate-priority").body(Body::empty()).unwrap();
+    let response = app.oneshot(req).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let data = &json["data"];
+    assert_eq!(data["blocks"], 1);
+    assert_eq!((data["recent"]["count"].as_u64(), data["recent"]["p50"].as_u64()), (Some(3), Some(40)));
+    assert_eq!(data["pool"]["count"], 0);
+    assert_eq!(data["congested"], false);
+    assert_eq!(data["suggested"], serde_json::json!({ "low": 5, "medium": 40, "high": 90 }));
+}
+
+#[tokio::test]
+async fn e2e_read_endpoints_support_etags() {
+    let api_state = create_test_api_state();
+    let owner = [3u8; 32];
+    let asset_id = [7u8; 32];
+    api_state.state.create_test_account(owner, 10_000, 0);
+
+    let block = test_block(api_state.state.current_height() + 1, vec![Transaction::MistbornAsset {
+        from: owner,
+        action: AssetAction::Create,
+        asset_id,
+        data: AssetData {
+            density: DensityLevel::Ethereal,
+            metadata: std::collections::HashMap::new(),
+            attributes: vec![],
+            game_id: None,
+            owner,
+        },
+        fee: 0,
+        nonce: 0,
+        chain_id: None,
+        valid_until_height: None,
+        signature: vec![0; 64],
+    }]);
+    let block_hash = block.header.hash;
+    api_state.state.apply_block(&block).unwrap();
+    let state = api_state.state.clone();
+    let app = create_router(api_state);
+
+    let get = |uri: String, etag: Option<String>| {
+        let mut req = Request::builder().uri(uri);
+        if let Some(etag) = etag {
+            req = req.header("if-none-match", etag);
+        }
+        app.clone().oneshot(req.body(Body::empty()).unwrap())
+    };
+    let etag_of = |response: &axum::response::Response| {
+        response.headers()["etag"].to_str().unwrap().to_string()
+    };
+
+    // Asset: ETag on the first read, 304 while unchanged
+    let asset_uri = format!("/api/v1/assets/{}", hex::encode(asset_id));
+    let response = get(asset_uri.clone(), None).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let asset_etag = etag_of(&response);
+    let response = get(asset_uri.clone(), Some(asset_etag.clone())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert!(axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap().is_empty());
+
+    // Block by hash: the hash is the ETag
+    let response = get(
+        format!("/api/v1/blocks/{}", hex::encode(block_hash)),
+        Some(format!("\"{}\"", hex::encode(block_hash))),
+    ).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+    // Blockchain info: a state change invalidates the cached response
+    let response = get("/api/v1/blockchain/info".to_string(), None).await.unwrap();
+    let info_etag = etag_of(&response);
+    let response = get("/api/v1/blockchain/info".to_string(), Some(info_etag.clone())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    state.create_test_account([4u8; 32], 1, 0);
+    let response = get("/api/v1/blockchain/info".to_string(), Some(info_etag.clone())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_ne!(etag_of(&response), info_etag);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["success"], true);
+    assert!(json["data"]["state_root"].is_string());
+}
+
+#[tokio::test]
+async fn e2e_watchlist_webhook_delivery() {
+    // Local webhook receiver
+    let (hook_tx, mut hook_rx) = tokio::sync::mpsc::unbounded_channel::<serde_json::Value>();
+    let receiver = axum::Router::new().route(
+        "/hook",
+        axum::routing::post(move |axum::Json(body): axum::Json<serde_json::Value>| {
+            let hook_tx = hook_tx.clone();
+            async move {
+                let _ = hook_tx.send(body);
+                StatusCode::OK
+            }
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let hook_url = format!("http://{}/hook", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, receiver).await.unwrap() });
+
+    let api_state = create_test_api_state();
+    let (ws_tx, _) = tokio::sync::broadcast::channel(100);
+    api_state.state.set_ws_tx(ws_tx.clone());
+    tokio::spawn(haze::watchlist::run_webhook_dispatcher(
+        api_state.watchlists.clone(),
+        api_state.state.clone(),
+        ws_tx.subscribe(),
+    ));
+    let state = api_state.state.clone();
+    let app = create_router(api_state);
+
+    // API key required
+    let req = Request::builder()
+        .uri("/api/v1/watchlists")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(app.clone().oneshot(req).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+
+    let body = serde_json::json!({ "name": "dragons", "collections": ["dragons"], "webhook_url": hook_url });
+    let req = Request::builder()
+        .method("POST")
+        .uri("/api/v1/watchlists")
+        .header("content-type", "application/json")
+        .header("x-api-key", "trader-key")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let response = app.clone().oneshot(req).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let watchlist_id = json["data"]["id"].as_str().unwrap().to_string();
+
+    // An asset created in the watched collection triggers the webhook
+    let owner = [5u8; 32];
+    let asset_id = [6u8; 32];
+    let block = test_block(state.current_height() + 1, vec![Transaction::MistbornAsset {
+        from: owner,
+        action: AssetAction::Create,
+        asset_id,
+        data: AssetData {
+            density: DensityLevel::Ethereal,
+            metadata: std::collections::HashMap::new(),
+            attributes: vec![],
+            game_id: Some("dragons".to_string()),
+            owner,
+        },
+        fee: 0,
+        nonce: 0,
+        chain_id: None,
+        valid_until_height: None,
+        signature: vec![0; 64],
+    }]);
+    state.create_test_account(owner, 10_000, 0);
+    state.apply_block(&block).unwrap();
+
+    let delivery = tokio::time::timeout(std::time::Duration::from_secs(10), hook_rx.recv())
+        .await
+        .expect("webhook not delivered")
+        .unwrap();
+    assert_eq!(delivery["watchlist_id"], watchlist_id.as_str());
+    assert_eq!(delivery["event"]["type"], "asset_created");
+    assert_eq!(delivery["event"]["data"]["asset_id"], hex::encode(asset_id));
+
+    // Listing and deleting are scoped to the key
+    let list = |key: &'static str| {
+        Request::builder()
+            .uri("/api/v1/watchlists")
+            .header("x-api-key", key)
+            .body(Body::empty())
+            .unwrap()
+    };
+    let response = app.clone().oneshot(list("other-key")).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["data"].as_array().unwrap().len(), 0);
+
+    let delete = |key: &'static str| {
+        Request::builder()
+            .method("DELETE")
+            .uri(format!("/api/v1/watchlists/{}", watchlist_id))
+            .header("x-api-key", key)
+            .body(Body::empty())
+            .unwrap()
+    };
+    assert_eq!(app.clone().oneshot(delete("other-key")).await.unwrap().status(), StatusCode::NOT_FOUND);
+    assert_eq!(app.clone().oneshot(delete("trader-key")).await.unwrap().status(), StatusCode::OK);
+    let response = app.oneshot(list("trader-key")).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["data"].as_array().unwrap().len(), 0);
+}
+
+#[tokio::test]
+async fn e2e_custodial_account_signing_and_export() {
+    let mut api_state = create_test_api_state();
+    api_state.config.custody.enabled = true;
+    api_state.config.custody.keystore_path = api_state.config.storage.db_path.join("custody.keystore");
+    api_state.config.custody.api_key_hashes = vec![haze::watchlist::hash_api_key("studio-key")];
+    api_state.custody = Some(Arc::new(
+        haze::custody::CustodyStore::open(&api_state.config.custody, "test passphrase", &api_state.state).unwrap(),
+    ));
+    let state = api_state.state.clone();
+    let consensus = api_state.consensus.clone();
+    let app = create_router(api_state);
+
+    let get = |key: &'static str| {
+        Request::builder()
+            .uri("/api/v1/custody/players/player-42")
+            .header("x-api-key", key)
+            .body(Body::empty())
+            .unwrap()
+    };
+    assert_eq!(app.clone().oneshot(get("other-key")).await.unwrap().status(), StatusCode::FORBIDDEN);
+    let response = app.clone().oneshot(get("studio-key")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let address_hex = json["data"]["address"].as_str().unwrap().to_string();
+    assert_eq!(json["data"]["exported_at"], serde_json::Value::Null);
+
+    // Same player id always maps to the same address
+    let response = app.clone().oneshot(get("studio-key")).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["data"]["address"], address_hex);
+
+    let mut address = [0u8; 32];
+    address.copy_from_slice(&hex::decode(&address_hex).unwrap());
+    state.create_test_account(address, 1_000, 0);
+
+    // `from` and `signature` are filled in by the node
+    let send = || {
+        let body = serde_json::json!({
+            "transaction": {
+                "Transfer": { "to": hex::encode([0x22u8; 32]), "amount": 10, "fee": 1, "nonce": 0 }
+            }
+        });
+        Request::builder()
+            .method("POST")
+            .uri("/api/v1/custody/players/player-42/transactions")
+            .header("content-type", "application/json")
+            .header("x-api-key", "studio-key")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let response = app.clone().oneshot(send()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let tx_hash = haze::types::hex_to_hash(json["data"]["hash"].as_str().unwrap()).unwrap();
+    assert_eq!(*consensus.get_transaction(&tx_hash).unwrap().sender(), address);
+
+    let req = Request::builder()
+        .method("POST")
+        .uri("/api/v1/custody/players/player-42/export")
+        .header("x-api-key", "studio-key")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(req).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let secret = hex::decode(json["data"]["secret_key"].as_str().unwrap()).unwrap();
+    let keypair = haze::crypto::KeyPair::from_signing_key(haze::crypto::signing_key_from_bytes(&secret).unwrap());
+    assert_eq!(keypair.address(), address);
+    assert!(json["data"]["exported_at"].is_i64());
+
+    // After export the player signs with their own wallet
+    let response = app.oneshot(send()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["error_code"], haze::error::HazeError::AccessDenied(String::new()).code());
+}
+
+#[tokio::test]
+async fn e2e_sign_in_with_haze_session() {
+    let player = haze::crypto::KeyPair::generate();
+    let app = create_router(create_test_api_state());
+    let request = |method: &str, uri: &str, token: Option<&str>, body: serde_json::Value| {
+        let mut builder = Request::builder().method(method).uri(uri).header("content-type", "application/json");
+        if let Some(token) = token {
+            builder = builder.header("authorization", format!("Bearer {}", token));
+        }
+        builder.body(Body::from(body.to_string())).unwrap()
+    };
+    let json_of = |response: axum::response::Response| async move {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+    };
+
+    let challenge = serde_json::json!({ "address": hex::encode(player.address()) });
+    let response = app.clone().oneshot(request("POST", "/api/v1/auth/challenge", None, challenge)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let message = json_of(response).await["data"]["message"].as_str().unwrap().to_string();
+    assert!(message.contains(&hex::encode(player.address())));
+
+    // Signed by someone else
+    let forged = serde_json::json!({
+        "message": message,
+        "signature": hex::encode(haze::crypto::KeyPair::generate().sign(message.as_bytes())),
+    });
+    let response = app.clone().oneshot(request("POST", "/api/v1/auth/session", None, forged)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let signed = serde_json::json!({ "message": message, "signature": hex::encode(player.sign(message.as_bytes())) });
+    let response = app.clone().oneshot(request("POST", "/api/v1/auth/session", None, signed.clone())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let token = json_of(response).await["data"]["token"].as_str().unwrap().to_string();
+    // A challenge is redeemed once
+    let response = app.clone().oneshot(request("POST", "/api/v1/auth/session", None, signed)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // The session token stands in for an API key on watchlists
+    let watchlist = serde_json::json!({ "name": "my swords", "collections": ["fog"] });
+    let response = app.clone().oneshot(request("POST", "/api/v1/watchlists", Some(&token), watchlist)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app.clone().oneshot(request("GET", "/api/v1/watchlists", Some(&token), serde_json::Value::Null)).await.unwrap();
+    assert_eq!(json_of(response).await["data"].as_array().unwrap().len(), 1);
+    let response = app.clone().oneshot(request("GET", "/api/v1/auth/session", Some(&token), serde_json::Value::Null)).await.unwrap();
+    assert_eq!(json_of(response).await["data"]["address"], hex::encode(player.address()));
+
+    let response = app.clone().oneshot(request("DELETE", "/api/v1/auth/session", Some(&token), serde_json::Value::Null)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app.oneshot(request("GET", "/api/v1/watchlists", Some(&token), serde_json::Value::Null)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn e2e_direct_messages_mailbox() {
+    let alice = haze::crypto::KeyPair::generate();
+    let bob = haze::crypto::KeyPair::generate();
+    let app = create_router(create_test_api_state_with(|config| config.messaging.enabled = true));
+    let request = |method: &str, uri: &str, body: serde_json::Value| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let json_of = |response: axum::response::Response| async move {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+    };
+
+    let now = chrono::Utc::now().timestamp();
+    let sealed = haze::messaging::seal(&alice, &bob.address(), b"5 gold and the bow?", now).unwrap();
+    let body = serde_json::json!({
+        "sender": hex::encode(sealed.sender),
+        "recipient": hex::encode(sealed.recipient),
+        "ephemeral_key": hex::encode(sealed.ephemeral_key),
+        "nonce": hex::encode(sealed.nonce),
+        "ciphertext": hex::encode(&sealed.ciphertext),
+        "sent_at": sealed.sent_at,
+        "signature": hex::encode(&sealed.signature),
+    });
+    let response = app.clone().oneshot(request("POST", "/api/v1/messages", body)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let id = json_of(response).await["data"]["id"].as_str().unwrap().to_string();
+
+    // Reading needs a signature of the recipient
+    let mailbox = format!("/api/v1/messages/{}", hex::encode(bob.address()));
+    let response = app.clone().oneshot(request("GET", &mailbox, serde_json::Value::Null)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let signed_by = |keypair: &haze::crypto::KeyPair| {
+        let signature = keypair.sign(&haze::messaging::mailbox_auth_payload(&bob.address(), now));
+        format!("timestamp={}&signature={}", now, hex::encode(signature))
+    };
+    let response = app.clone()
+        .oneshot(request("GET", &format!("{}?{}", mailbox, signed_by(&alice)), serde_json::Value::Null))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app.clone()
+        .oneshot(request("GET", &format!("{}?{}", mailbox, signed_by(&bob)), serde_json::Value::Null))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let messages = json_of(response).await["data"].as_array().unwrap().clone();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0]["id"], id.as_str());
+    let received = haze::messaging::SealedMessage {
+        sender: alice.address(),
+        recipient: bob.address(),
+        ephemeral_key: hex::decode(messages[0]["ephemeral_key"].as_str().unwrap()).unwrap().try_into().unwrap(),
+        nonce: hex::decode(messages[0]["nonce"].as_str().unwrap()).unwrap().try_into().unwrap(),
+        ciphertext: hex::decode(messages[0]["ciphertext"].as_str().unwrap()).unwrap(),
+        sent_at: messages[0]["sent_at"].as_i64().unwrap(),
+        signature: hex::decode(messages[0]["signature"].as_str().unwrap()).unwrap(),
+    };
+    assert_eq!(haze::messaging::open(&bob, &received).unwrap(), b"5 gold and the bow?");
+
+    let delete = format!("{}/{}?{}", mailbox, id, signed_by(&bob));
+    let response = app.clone().oneshot(request("DELETE", &delete, serde_json::Value::Null)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app.oneshot(request("DELETE", &delete, serde_json::Value::Null)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn e2e_full_block_with_receipts_and_gzip() {
+    let api_state = create_test_api_state();
+    let owner = [4u8; 32];
+    let asset_id = [8u8; 32];
+    api_state.state.create_test_account(owner, 100_000, 0);
+
+    let mut block = test_block(api_state.state.current_height() + 1, vec![
+        Transaction::Transfer {
+            from: owner,
+            to: [9u8; 32],
+            amount: 100,
+            fee: 5,
+            nonce: 0,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![0; 64],
+        },
+        Transaction::MistbornAsset {
+            from: owner,
+            action: AssetAction::Create,
+            asset_id,
+            data: AssetData {
+                density: DensityLevel::Ethereal,
+                metadata: std::collections::HashMap::new(),
+                attributes: vec![],
+                game_id: None,
+                owner,
+            },
+            fee: 0,
+            nonce: 1,
+            chain_id: None,
+            valid_until_height: None,
+            signature: vec![0; 64],
+        },
+    ]);
+    block.dag_references = vec![[3u8; 32]];
+    let block_hash = hex::encode(block.header.hash);
+    api_state.state.apply_block(&block).unwrap();
+    let app = create_router(api_state);
+
+    let req = Request::builder()
+        .uri(format!("/api/v1/blocks/{}/full", block_hash))
+        .header("accept-encoding", "gzip")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(req).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-encoding"], "gzip");
+    let compressed = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let mut body = Vec::new();
+    std::io::Read::read_to_end(&mut flate2::read::GzDecoder::new(&compressed[..]), &mut body).unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+    let data = &json["data"];
+    assert_eq!(data["header"]["hash"], block_hash);
+    assert_eq!(data["dag_references"][0], hex::encode([3u8; 32]));
+    let txs = data["transactions"].as_array().unwrap();
+    assert_eq!(txs.len(), 2);
+
+    let transfer = &txs[0];
+    assert_eq!(transfer["transaction"]["Transfer"]["from"], hex::encode(owner));
+    assert_eq!(transfer["transaction"]["Transfer"]["signature"], hex::encode([0u8; 64]));
+    assert_eq!(transfer["receipt"]["status"], "executed");
+    assert_eq!(transfer["receipt"]["fees_paid"], 5);
+    assert_eq!(transfer["receipt"]["events"].as_array().unwrap().len(), 0);
+
+    let create = &txs[1];
+    assert_eq!(create["index"], 1);
+    assert_eq!(create["transaction"]["MistbornAsset"]["asset_id"], hex::encode(asset_id));
+    let events = create["receipt"]["events"].as_array().unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0]["type"], "asset_created");
+
+    // The same receipt is served per transaction, found through the transaction index
+    let create_hash = create["hash"].as_str().unwrap().to_string();
+    let req = Request::builder()
+        .uri(format!("/api/v1/transactions/{}/receipt", create_hash))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(req).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let receipt: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(receipt["data"], create["receipt"]);
+    assert_eq!(receipt["data"]["tx_index"], 1);
+    assert_eq!(receipt["data"]["block_hash"], block_hash);
+
+    let req = Request::builder()
+        .uri(format!("/api/v1/transactions/{}/receipt", hex::encode([0xabu8; 32])))
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(app.clone().oneshot(req).await.unwrap().status(), StatusCode::NOT_FOUND);
+
+    // Without Accept-Encoding the body is plain JSON
+    let req = Request::builder()
+        .uri(format!("/api/v1/blocks/{}/full", block_hash))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(req).await.unwrap();
+    assert!(response.headers().get("content-encoding").is_none());
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(serde_json::from_slice::<serde_json::Value>(&body).unwrap(), json);
+}
+
+#[tokio::test]
+async fn e2e_consensus_dag_pages() {
+    let api_state = create_test_api_state();
+    let consensus = api_state.consensus.clone();
+    let validator = haze::crypto::KeyPair::generate().address();
+    let mut hashes = Vec::new();
+    for _ in 0..3 {
+        let block = consensus.create_block(validator).unwrap();
+        consensus.process_block(&block).unwrap();
+        hashes.push(hex::encode(block.header.hash));
+    }
+    let first_wave = consensus.dag_vertices(0, u64::MAX)[0].wave;
+    consensus.finalize_wave(first_wave).unwrap();
+    let app = create_router(api_state);
+
+    let get = |uri: &str| {
+        let req = Request::builder().uri(uri).body(Body::empty()).unwrap();
+        let app = app.clone();
+        async move {
+            let response = app.oneshot(req).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+        }
+    };
+
+    let page = get("/api/v1/consensus/dag?limit=2").await;
+    let data = &page["data"];
+    assert_eq!(data["total"], 3);
+    assert_eq!(data["next_offset"], 2);
+    let vertices = data["vertices"].as_array().unwrap();
+    assert_eq!(vertices.len(), 2);
+    assert!(vertices.iter().all(|v| hashes.contains(&v["hash"].as_str().unwrap().to_string())));
+    assert_eq!(vertices[0]["finalized"], true);
+    assert_eq!(vertices[0]["validator"], hex::encode(validator));
+    // Every returned vertex has its references as edges
+    let edges = data["edges"].as_array().unwrap();
+    assert!(edges.iter().any(|e| e[0] == vertices[1]["hash"]));
+
+    let page = get("/api/v1/consensus/dag?limit=2&offset=2").await;
+    assert_eq!(page["data"]["vertices"].as_array().unwrap().len(), 1);
+    assert_eq!(page["data"]["next_offset"], serde_json::Value::Null);
+
+    // Wave filter
+    let page = get(&format!("/api/v1/consensus/dag?from_wave={}&to_wave={}", first_wave + 1000, first_wave + 2000)).await;
+    assert_eq!(page["data"]["total"], 0);
+
+    // Wave history
+    let page = get(&format!("/api/v1/consensus/waves?from={}&to={}", first_wave, first_wave)).await;
+    let wave = &page["data"]["waves"][0];
+    assert_eq!(wave["wave"], first_wave);
+    assert_eq!(wave["finalized"], true);
+    assert!(wave["finalization_ms"].is_i64());
+    assert_eq!(wave["certificate"]["proposers"][0], hex::encode(validator));
+
+    // Committee parameters start from the config
+    let committee = get("/api/v1/consensus/committee").await;
+    assert_eq!(committee["data"]["min_stake"], 1_000);
+    assert_eq!(committee["data"]["max_weight_bps"], 3_333);
+    assert!(committee["data"]["votes"].as_array().unwrap().is_empty());
+    let response = app
+        .oneshot(Request::builder().uri("/api/v1/consensus/waves?from=2&to=1").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn e2e_tenant_admin_and_namespaced_writes() {
+    let mut api_state = create_test_api_state();
+    api_state.config.tenants.enabled = true;
+    api_state.config.tenants.admin_key_hashes = vec![haze::watchlist::hash_api_key("operator-key")];
+    api_state.tenants = Some(Arc::new(
+        haze::tenant::TenantStore::open(&api_state.config.tenants, &api_state.state).unwrap(),
+    ));
+    let app = create_router(api_state);
+    let request = |method: &str, uri: &str, key: Option<&str>, body: serde_json::Value| {
+        let mut builder = Request::builder().method(method).uri(uri).header("content-type", "application/json");
+        if let Some(key) = key {
+            builder = builder.header("x-api-key", key);
+        }
+        builder.body(Body::from(body.to_string())).unwrap()
+    };
+    let json_of = |response: axum::response::Response| async move {
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+    };
+
+    // Only admins manage tenants
+    let new_tenant = serde_json::json!({ "id": "acme", "name": "Acme Games" });
+    let response = app.clone().oneshot(request("POST", "/api/v1/admin/tenants", Some("studio"), new_tenant.clone())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app.clone().oneshot(request("POST", "/api/v1/admin/tenants", Some("operator-key"), new_tenant)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json = json_of(response).await;
+    assert_eq!(json["data"]["namespace"], "acme/");
+    let tenant_key = json["data"]["api_key"].as_str().unwrap().to_string();
+
+    // Writes need a tenant key and stay in the tenant's namespace
+    let create = |game_id: &str| serde_json::json!({
+        "transaction": {
+            "MistbornAsset": {
+                "from": hex::encode([0x11u8; 32]),
+                "action": "Create",
+                "asset_id": hex::encode([0x33u8; 32]),
+                "data": {
+                    "density": "Ethereal",
+                    "metadata": {},
+                    "attributes": [],
+                    "game_id": game_id,
+                    "owner": hex::encode([0x11u8; 32]),
+                },
+                "fee": 0,
+                "nonce": 0,
+                "signature": hex::encode([1u8; 64]),
+            }
+        }
+    });
+    let response = app.clone().oneshot(request("POST", "/api/v1/assets", None, create("acme/racing"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app.clone().oneshot(request("POST", "/api/v1/assets", Some("unknown"), create("acme/racing"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app.clone().oneshot(request("POST", "/api/v1/assets", Some(&tenant_key), create("rival/racing"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let json = json_of(response).await;
+    assert_eq!(json["error_code"], haze::error::HazeError::AccessDenied(String::new()).code());
+
+    // Watchlists (and their webhooks) are limited to the namespace too
+    let watchlist = |game_id: &str| serde_json::json!({ "name": "drops", "collections": [game_id] });
+    let response = app.clone().oneshot(request("POST", "/api/v1/watchlists", Some(&tenant_key), watchlist("rival/racing"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = app.clone().oneshot(request("POST", "/api/v1/watchlists", Some(&tenant_key), watchlist("acme/racing"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Usage is reported to the tenant and to the admin
+    let response = app.clone().oneshot(request("GET", "/api/v1/tenant", Some(&tenant_key), serde_json::Value::Null)).await.unwrap();
+    let json = json_of(response).await;
+    assert_eq!(json["data"]["id"], "acme");
+    assert_eq!(json["data"]["watchlists"], 1);
+    assert_eq!(json["data"]["usage"]["requests"], 4);
+    let response = app.oneshot(request("GET", "/api/v1/admin/tenants", Some("operator-key"), serde_json::Value::Null)).await.unwrap();
+    let json = json_of(response).await;
+    assert_eq!(json["data"][0]["usage"]["requests"], 4);
+}
+
+#[tokio::test]
+async fn e2e_usage_metering_export() {
+    let mut api_state = create_test_api_state();
+    api_state.config.metering.enabled = true;
+    api_state.config.metering.admin_key_hashes = vec![haze::watchlist::hash_api_key("billing-key")];
+    api_state.metering = Some(Arc::new(
+        haze::metering::UsageMeter::open(&api_state.config.metering, &api_state.state).unwrap(),
+    ));
+    let app = create_router(api_state);
+    let get = |uri: &str, key: Option<&str>| {
+        let mut builder = Request::builder().uri(uri);
+        if let Some(key) = key {
+            builder = builder.header("x-api-key", key);
+        }
+        builder.body(Body::empty()).unwrap()
+    };
+
+    for _ in 0..3 {
+        let response = app.clone().oneshot(get("/api/v1/blockchain/info", Some("studio-key"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+    let response = app.clone().oneshot(get("/api/v1/admin/metering", None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app.clone().oneshot(get("/api/v1/admin/metering", Some("studio-key"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app.clone().oneshot(get("/api/v1/admin/metering?from=yesterday", Some("billing-key"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let studio = haze::watchlist::hash_api_key("studio-key");
+    let uri = format!("/api/v1/admin/metering?key_hash={}", studio);
+    let response = app.clone().oneshot(get(&uri, Some("billing-key"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let rollups = json["data"].as_array().unwrap();
+    assert_eq!(rollups.len(), 1);
+    assert_eq!(rollups[0]["requests"], 4);
+
+    let response = app.oneshot(get(&format!("{}&format=csv", uri), Some("billing-key"))).await.unwrap();
+    assert_eq!(response.headers()["content-type"], "text/csv; charset=utf-8");
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let csv = String::from_utf8(body.to_vec()).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines[0], "day,key_hash,tenant,requests,gas_submitted,bytes_stored,ws_seconds,ws_minutes");
+    assert!(lines[1].contains(&format!(",{},,4,0,0,0,0", studio)));
+}
+
+#[tokio::test]
+async fn e2e_pause_and_maintenance_mode() {
+    let api_state = create_test_api_state_with(|config| {
+        config.api.admin_key_hashes = vec![haze::watchlist::hash_api_key("operator-key")];
+        config.consensus.max_pause_secs = 600;
+    });
+    let consensus = api_state.consensus.clone();
+    let app = create_router(api_state);
+    let post = |uri: &str, key: Option<&str>, body: serde_json::Value| {
+        let mut builder = Request::builder().method("POST").uri(uri).header("content-type", "application/json");
+        if let Some(key) = key {
+            builder = builder.header("x-api-key", key);
+        }
+        let request = builder.body(Body::from(body.to_string())).unwrap();
+        let app = app.clone();
+        async move { app.oneshot(request).await.unwrap() }
+    };
+    let transfer = serde_json::json!({
+        "transaction": {
+            "Transfer": {
+                "from": hex::encode([0x11u8; 32]),
+                "to": hex::encode([0x22u8; 32]),
+                "amount": 100,
+                "fee": 1,
+                "nonce": 0,
+                "signature": hex::encode([0u8; 64]),
+            }
+        }
+    });
+
+    let pause = serde_json::json!({ "maintenance": true, "reason": "upgrade to 0.9" });
+    assert_eq!(post("/api/v1/admin/consensus/pause", None, pause.clone()).await.status(), StatusCode::UNAUTHORIZED);
+    let too_long = serde_json::json!({ "timeout_secs": 601 });
+    assert_eq!(post("/api/v1/admin/consensus/pause", Some("operator-key"), too_long).await.status(), StatusCode::BAD_REQUEST);
+    let response = post("/api/v1/admin/consensus/pause", Some("operator-key"), pause).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["data"]["resume_at"].as_i64().unwrap() - json["data"]["paused_at"].as_i64().unwrap(), 600);
+    assert!(consensus.production_paused());
+
+    // Transactions are refused with a clear error, reads are served
+    let response = post("/api/v1/transactions", None, transfer.clone()).await;
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["error_code"], 1010);
+    assert!(json["error"].as_str().unwrap().contains("upgrade to 0.9"));
+    let request = Request::builder().uri("/api/v1/health").body(Body::empty()).unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let health: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(health["data"]["status"], "degraded");
+    assert_eq!(health["data"]["pause"]["maintenance"], true);
+
+    let response = post("/api/v1/admin/consensus/resume", Some("operator-key"), serde_json::json!({})).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(!consensus.production_paused());
+    // Back to normal validation (this account cannot pay)
+    assert_eq!(post("/api/v1/transactions", None, transfer).await.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn e2e_state_divergence_admin_and_metrics() {
+    let api_state = create_test_api_state_with(|config| {
+        config.api.admin_key_hashes = vec![haze::watchlist::hash_api_key("operator-key")];
+    });
+    let consensus = api_state.consensus.clone();
+    let mut block = consensus.create_block([4u8; 32]).unwrap();
+    block.header.state_root = [9u8; 32];
+    block.header.hash = block.header.compute_hash(&consensus.network_id());
+    consensus.process_block(&block).unwrap();
+    let app = create_router(api_state);
+    let get = |uri: &str, key: Option<&str>| {
+        let mut builder = Request::builder().uri(uri);
+        if let Some(key) = key {
+            builder = builder.header("x-api-key", key);
+        }
+        builder.body(Body::empty()).unwrap()
+    };
+
+    let response = app.clone().oneshot(get("/api/v1/admin/divergence", None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = app.clone().oneshot(get("/api/v1/admin/divergence", Some("studio-key"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app.clone().oneshot(get("/api/v1/admin/divergence", Some("operator-key"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["data"]["halted_waves"], serde_json::json!([block.header.wave_number]));
+    let incident = &json["data"]["incidents"][0];
+    assert_eq!(incident["height"], 1);
+    assert_eq!(incident["expected_state_root"], hex::encode([9u8; 32]));
+
+    let response = app.oneshot(get("/api/v1/metrics/prometheus", None)).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let metrics = String::from_utf8(body.to_vec()).unwrap();
+    assert!(metrics.contains("haze_state_divergences_total 1\n"));
+    assert!(metrics.contains("haze_divergent_waves 1\n"));
+}
+
+#[tokio::test]
+async fn e2e_read_replica_forwards_writes_and_reports_lag() {
+    // Primary serving HTTP on a local port
+    let primary = create_test_api_state();
+    let primary_watchlists = primary.watchlists.clone();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let primary_addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, create_router(primary)).await.unwrap();
+    });
+
+    let mut replica = create_test_api_state();
+    replica.config.replica.enabled = true;
+    let consensus = replica.consensus.clone();
+    let watchlist = serde_json::json!({ "name": "drops", "collections": ["racing"] });
+    let post = |body: &serde_json::Value| {
+        Request::builder()
+            .method("POST")
+            .uri("/api/v1/watchlists")
+            .header("content-type", "application/json")
+            .header("x-api-key", "studio")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    // Without a primary URL writes are rejected, reads are served
+    let app = create_router(replica.clone());
+    let response = app.clone().oneshot(post(&watchlist)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(response.headers()["allow"], "GET, HEAD, OPTIONS");
+    let response = app.clone().oneshot(Request::builder().uri("/api/v1/blockchain/info").body(Body::empty()).unwrap()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // With one, they reach the primary
+    replica.config.replica.primary_api_url = Some(format!("http://{}/", primary_addr));
+    let app = create_router(replica);
+    let response = app.clone().oneshot(post(&watchlist)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(primary_watchlists.list("studio").len(), 1);
+    let response = app.clone().oneshot(post(&serde_json::json!({ "name": "empty" }))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // Health reports the lag behind the primary
+    let health = |app: axum::Router| async move {
+        let response = app.oneshot(Request::builder().uri("/api/v1/health").body(Body::empty()).unwrap()).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+    };
+    let json = health(app.clone()).await;
+    assert_eq!(json["data"]["status"], "degraded");
+    assert_eq!(json["data"]["replica"]["lagging"], true);
+    let height = consensus.state().current_height();
+    consensus.record_peer_height("primary".to_string(), height + 3);
+    let json = health(app).await;
+    assert_eq!(json["data"]["status"], "ok");
+    assert_eq!(json["data"]["replica"]["lag_blocks"], 3);
+}
+
+#[tokio::test]
+async fn e2e_telemetry_reports_reach_aggregator() {
+    let get_nodes = || Request::builder().uri("/api/v1/telemetry/nodes").body(Body::empty()).unwrap();
+    // Nodes are not aggregators unless configured
+    let response = create_router(create_test_api_state()).oneshot(get_nodes()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // Aggregator serving HTTP on a local port
+    let mut aggregator = create_test_api_state();
+    aggregator.config.telemetry.aggregator = true;
+    aggregator.telemetry = Some(Arc::new(haze::telemetry::TelemetryAggregator::new(&aggregator.config)));
+    let app = create_router(aggregator);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let aggregator_addr = listener.local_addr().unwrap();
+    let server = app.clone();
+    tokio::spawn(async move {
+        axum::serve(listener, server).await.unwrap();
+    });
+
+    // A reporting node sends its first report right away
+    let node = create_test_api_state_with(|config| {
+        config.node_id = "validator-eu".to_string();
+        config.telemetry.enabled = true;
+        config.telemetry.region = Some("eu-west".to_string());
+        config.telemetry.endpoint = Some(format!("http://{}/api/v1/telemetry", aggregator_addr));
+    });
+    node.connected_peers.store(4, Ordering::Relaxed);
+    let reporter = tokio::spawn(haze::telemetry::run_reporter(
+        node.config.clone(),
+        node.state.clone(),
+        node.consensus.clone(),
+        node.connected_peers.clone(),
+    ));
+    let mut json = serde_json::Value::Null;
+    for _ in 0..50 {
+        let response = app.clone().oneshot(get_nodes()).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        json = serde_json::from_slice(&body).unwrap();
+        if json["data"]["count"] == 1 {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    reporter.abort();
+    let reported = &json["data"]["nodes"][0];
+    assert_eq!(reported["node_id"], "validator-eu");
+    assert_eq!(reported["name"], "validator-eu");
+    assert_eq!(reported["region"], "eu-west");
+    assert_eq!(reported["peers"], 4);
+    assert_eq!(reported["height"], 0);
+    assert!(reported["received_at"].is_i64());
+
+    // Reports of another chain are rejected
+    let foreign = serde_json::json!({
+        "node_id": "other", "name": "other", "region": null, "version": "0.1.0", "chain_id": 7,
+        "height": 1, "finalized_height": 0, "peers": 0, "latency_ms": null, "replica": false, "sent_at": 0,
+    });
+    let request = Request::builder()
+        .method("POST")
+        .uri("/api/v1/telemetry")
+        .header("content-type", "application/json")
+        .body(Body::from(foreign.to_string()))
+        .unwrap();
+    assert_eq!(app.oneshot(request).await.unwrap().status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn e2e_list_accounts_with_cursor() {
+    let api_state = create_test_api_state();
+    for seed in 1..=3u8 {
+        api_state.state.create_test_account([seed; 32], seed as u64 * 100, 0);
+    }
+    let app = create_router(api_state);
+    let get_json = |uri: String| {
+        let app = app.clone();
+        async move {
+            let response = app.oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap()).await.unwrap();
+            let status = response.status();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            (status, serde_json::from_slice::<serde_json::Value>(&body).unwrap_or_default())
+        }
+    };
+
+    let (status, json) = get_json("/api/v1/accounts?limit=2".to_string()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["data"]["total"], 3);
+    assert_eq!(json["data"]["accounts"].as_array().unwrap().len(), 2);
+    let cursor = json["data"]["next_cursor"].as_str().unwrap().to_string();
+    assert_eq!(cursor, haze::types::address_to_hex(&[2u8; 32]));
+
+    let (_, json) = get_json(format!("/api/v1/accounts?limit=2&cursor={}", cursor)).await;
+    let accounts = json["data"]["accounts"].as_array().unwrap();
+    assert_eq!((accounts.len(), accounts[0]["balance"].as_u64()), (1, Some(300)));
+    assert!(json["data"]["next_cursor"].is_null());
+
+    let (_, json) = get_json("/api/v1/accounts?min_balance=200&has_stake=false".to_string()).await;
+    assert_eq!(json["data"]["accounts"].as_array().unwrap().len(), 2);
+    let (status, _) = get_json("/api/v1/accounts?cursor=nothex".to_string()).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (_, json) = get_json("/api/v1/metrics/basic".to_string()).await;
+    assert_eq!(json["data"]["total_accounts"], 3);
+}
+
+#[tokio::test]
+async fn e2e_blob_download_supports_ranges() {
+    let blob_dir = tempfile::tempdir().unwrap();
+    let api_state = create_test_api_state_with(|config| {
+        config.storage.blob_storage_path = blob_dir.path().to_path_buf();
+        config.storage.blob_chunk_size = 4 * 1024;
+    });
+    let blobs = haze::assets::BlobStorage::new(&api_state.config).unwrap();
+    let data: Vec<u8> = (0..20_000u32).map(|i| (i % 251) as u8).collect();
+    let blob_hash = blobs.store_blob_with_content_type("model", &data, Some("model/gltf-binary")).unwrap();
+
+    let owner = [5u8; 32];
+    let asset_id = [9u8; 32];
+    api_state.state.create_test_account(owner, 1_000_000, 0);
+    let mut metadata = std::collections::HashMap::new();
+    metadata.insert(
+        "_blob_refs".to_string(),
+        serde_json::json!({ "model": hex::encode(blob_hash) }).to_string(),
+    );
+    let block = test_block(api_state.state.current_height() + 1, vec![Transaction::MistbornAsset {
+        from: owner,
+        action: AssetAction::Create,
+        asset_id,
+        data: AssetData {
+            density: DensityLevel::Core,
+            metadata,
+            attributes: vec![],
+            game_id: None,
+            owner,
+        },
+        fee: 0,
+        nonce: 0,
+        chain_id: None,
+        valid_until_height: None,
+        signature: vec![0; 64],
+    }]);
+    api_state.state.apply_block(&block).unwrap();
+    let app = create_router(api_state);
+    let uri = format!("/api/v1/assets/{}/blob/model", hex::encode(asset_id));
+    let get = |headers: &[(&str, &str)]| {
+        let mut req = Request::builder().uri(&uri);
+        for (name, value) in headers {
+            req = req.header(*name, *value);
+        }
+        app.clone().oneshot(req.body(Body::empty()).unwrap())
+    };
+
+    // Whole blob, streamed across chunks, with the stored content type
+    let response = get(&[]).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "model/gltf-binary");
+    assert_eq!(response.headers()["accept-ranges"], "bytes");
+    let etag = response.headers()["etag"].to_str().unwrap().to_string();
+    assert_eq!(etag, format!("\"{}\"", hex::encode(blob_hash)));
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(body, Bytes::from(data.clone()));
+
+    // A range spanning a chunk boundary
+    let response = get(&[("range", "bytes=4000-9999")]).await.unwrap();
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(response.headers()["content-range"], "bytes 4000-9999/20000");
+    assert_eq!(response.headers()["content-length"], "6000");
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(&body[..], &data[4000..10000]);
+
+    let response = get(&[("range", "bytes=-100")]).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(&body[..], &data[19_900..]);
+
+    let response = get(&[("range", "bytes=20000-")]).await.unwrap();
+    assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+    assert_eq!(response.headers()["content-range"], "bytes */20000");
+
+    // The blob hash answers If-None-Match
+    let response = get(&[("if-none-match", etag.as_str())]).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+}
+
+#[tokio::test]
+async fn e2e_state_proofs_verify_against_state_root() {
+    let api_state = create_test_api_state();
+    let owner = [6u8; 32];
+    let asset_id = [0x5au8; 32];
+    api_state.state.create_test_account(owner, 1_000_000, 0);
+    api_state.state.create_test_account([7u8; 32], 10, 0);
+    api_state.state.apply_transactions_batch(&[Transaction::MistbornAsset {
+        from: owner,
+        action: AssetAction::Create,
+        asset_id,
+        data: AssetData {
+            density: DensityLevel::Ethereal,
+            metadata: std::collections::HashMap::new(),
+            attributes: vec![],
+            game_id: Some("proofs".to_string()),
+            owner,
+        },
+        fee: 0,
+        nonce: 0,
+        chain_id: None,
+        valid_until_height: None,
+        signature: vec![0; 64],
+    }]).unwrap();
+    let app = create_router(api_state);
+    let get_json = |uri: String| {
+        let app = app.clone();
+        async move {
+            let response = app.oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap()).await.unwrap();
+            let status = response.status();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            (status, serde_json::from_slice::<serde_json::Value>(&body).unwrap_or_default())
+        }
+    };
+    let (_, info) = get_json("/api/v1/blockchain/info".to_string()).await;
+    let state_root = haze::types::hex_to_hash(info["data"]["state_root"].as_str().unwrap()).unwrap();
+
+    // A light client decodes the proof and checks it with the shared helper
+    let verify = |json: &serde_json::Value| {
+        let encoded = hex::decode(json["data"]["encoded"].as_str().unwrap()).unwrap();
+        let proof: haze::state::StateProof = bincode::deserialize(&encoded).unwrap();
+        haze::state::verify_proof(&proof, &state_root)
+    };
+    let (status, account) = get_json(format!("/api/v1/proof/account/{}", hex::encode(owner))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(account["data"]["kind"], "account");
+    assert_eq!(account["data"]["exists"], true);
+    assert_eq!(account["data"]["state_root"], info["data"]["state_root"]);
+    assert_eq!(account["data"]["committed_in_block"], account["data"]["height"].as_u64().unwrap() + 1);
+    assert!(verify(&account));
+
+    let (_, absent) = get_json(format!("/api/v1/proof/account/{}", hex::encode([0x99u8; 32]))).await;
+    assert_eq!(absent["data"]["exists"], false);
+    assert!(absent["data"]["value"].is_null());
+    assert!(verify(&absent));
+
+    let (status, asset) = get_json(format!("/api/v1/proof/asset/{}", hex::encode(asset_id))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(asset["data"]["kind"], "asset");
+    assert_eq!(asset["data"]["shard_roots"].as_array().unwrap().len(), haze::asset_shards::ASSET_SHARD_COUNT);
+    assert!(verify(&asset));
+
+    let (status, _) = get_json(format!("/api/v1/proof/asset/{}", hex::encode([0x99u8; 32]))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = get_json("/api/v1/proof/account/nothex".to_string()).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
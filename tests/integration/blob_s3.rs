@@ -0,0 +1,130 @@
+//! Blob storage on an S3-compatible object store (in-process fake bucket)
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, Method, StatusCode};
+use haze::assets::BlobStorage;
+use haze::config::{BlobBackend, Config};
+use parking_lot::Mutex;
+
+type Bucket = Arc<Mutex<HashMap<String, Vec<u8>>>>;
+
+/// Path-style bucket answering GET, PUT and DELETE on any key; requires a SigV4 header
+async fn object(
+    State(bucket): State<Bucket>,
+    method: Method,
+    headers: HeaderMap,
+    Path(key): Path<String>,
+    body: Bytes,
+) -> (StatusCode, Vec<u8>) {
+    let signed = headers
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("AWS4-HMAC-SHA256 Credential=test-key/"));
+    if !signed {
+        return (StatusCode::FORBIDDEN, Vec::new());
+    }
+    let mut objects = bucket.lock();
+    match method {
+        Method::PUT => {
+            objects.insert(key, body.to_vec());
+            (StatusCode::OK, Vec::new())
+        }
+        Method::DELETE => {
+            objects.remove(&key);
+            (StatusCode::NO_CONTENT, Vec::new())
+        }
+        _ => match objects.get(&key) {
+            Some(data) => (StatusCode::OK, data.clone()),
+            None => (StatusCode::NOT_FOUND, Vec::new()),
+        },
+    }
+}
+
+async fn start_bucket() -> (Bucket, String) {
+    let bucket: Bucket = Arc::default();
+    let app = axum::Router::new()
+        .route("/blobs/*key", axum::routing::any(object))
+        .with_state(bucket.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    (bucket, endpoint)
+}
+
+/// Node blob storage backed by the bucket, with its own empty cache directory
+fn node_storage(endpoint: &str, cache: &tempfile::TempDir) -> BlobStorage {
+    // SAFETY: the only test reading these variables; set to the same values by every call
+    unsafe {
+        std::env::set_var("HAZE_TEST_S3_ACCESS_KEY", "test-key");
+        std::env::set_var("HAZE_TEST_S3_SECRET_KEY", "test-secret");
+    }
+    let mut config = Config::default();
+    config.chain_id = 1;
+    config.storage.blob_storage_path = cache.path().to_path_buf();
+    config.storage.blob_chunk_size = 4 * 1024;
+    config.storage.blob_backend = BlobBackend::S3;
+    config.storage.blob_s3.endpoint = endpoint.to_string();
+    config.storage.blob_s3.bucket = "blobs".to_string();
+    config.storage.blob_s3.path_style = true;
+    config.storage.blob_s3.access_key_env = "HAZE_TEST_S3_ACCESS_KEY".to_string();
+    config.storage.blob_s3.secret_key_env = "HAZE_TEST_S3_SECRET_KEY".to_string();
+    config.storage.blob_s3.cache_max_bytes = 8 * 1024;
+    config.validate().unwrap();
+    BlobStorage::new(&config).unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn s3_backend_read_through_cache_and_integrity() {
+    let (bucket, endpoint) = start_bucket().await;
+    let writer_cache = tempfile::tempdir().unwrap();
+    let reader_cache = tempfile::tempdir().unwrap();
+    let tampered_cache = tempfile::tempdir().unwrap();
+    let data: Vec<u8> = (0..30_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8).collect();
+
+    let bucket_for_test = bucket.clone();
+    tokio::task::spawn_blocking(move || {
+        let writer = node_storage(&endpoint, &writer_cache);
+        let blob_hash = writer.store_blob("level", &data).unwrap();
+        {
+            let objects = bucket_for_test.lock();
+            assert!(objects.contains_key(&format!("manifests/{}", hex::encode(blob_hash))));
+            assert!(objects.keys().filter(|key| key.starts_with("chunks/")).count() > 1);
+        }
+
+        // Another node fetches the blob into its empty cache, checked against the hash
+        let reader = node_storage(&endpoint, &reader_cache);
+        assert_eq!(reader.get_blob("level", &blob_hash).unwrap(), data);
+        assert_eq!(reader.stats().unwrap().blobs, 1);
+
+        // Trimming keeps the cache under its limit; evicted chunks are fetched again
+        assert!(reader.trim_cache().unwrap() > 0);
+        let cached: u64 = std::fs::read_dir(reader_cache.path().join("chunks"))
+            .unwrap()
+            .map(|entry| entry.unwrap().metadata().unwrap().len())
+            .sum();
+        assert!(cached <= 8 * 1024);
+        let blob = reader.open_blob("level", &blob_hash).unwrap();
+        let range: Vec<u8> = blob.pieces(100, 20_000).iter().flat_map(|piece| piece.read().unwrap()).collect();
+        assert_eq!(range, data[100..20_000]);
+
+        // A chunk altered in the bucket is refused by a node without a verified copy
+        {
+            let mut objects = bucket_for_test.lock();
+            let chunk = objects.keys().find(|key| key.starts_with("chunks/")).unwrap().clone();
+            objects.insert(chunk, b"tampered".to_vec());
+        }
+        let tampered = node_storage(&endpoint, &tampered_cache);
+        let err = tampered.get_blob("level", &blob_hash).unwrap_err();
+        assert!(err.to_string().contains("does not match its hash"), "{}", err);
+
+        // Deleting the last key removes the manifest from the bucket
+        writer.delete_blob("level", &blob_hash).unwrap();
+        assert!(!bucket_for_test.lock().contains_key(&format!("manifests/{}", hex::encode(blob_hash))));
+    })
+    .await
+    .unwrap();
+}
@@ -20,8 +20,10 @@ fn sign_mistborn_asset_tx(
     action: &AssetAction,
     asset_id: &haze::types::Hash,
     data: &AssetData,
+    nonce: u64,
 ) -> Vec<u8> {
-    let mut serialized = Vec::new();
+    // Network id of the default config (chain_id 0) prefixes every payload
+    let mut serialized = haze::types::network_id(0, &[]).to_vec();
     serialized.extend_from_slice(b"MistbornAsset");
     // from (signer) — в тестах это всегда владелец
     serialized.extend_from_slice(&data.owner);
@@ -33,6 +35,9 @@ fn sign_mistborn_asset_tx(
         AssetAction::Evaporate => 3,
         AssetAction::Merge => 4,
         AssetAction::Split => 5,
+        AssetAction::Reveal => 6,
+        AssetAction::Breed => 7,
+        AssetAction::Upgrade => 8,
     });
     serialized.extend_from_slice(asset_id);
     serialized.extend_from_slice(&data.owner);
@@ -52,6 +57,10 @@ fn sign_mistborn_asset_tx(
                 }
             }
         }
+        if let Some(policy) = data.metadata.get("_merge_policy") {
+            serialized.extend_from_slice(b"_merge_policy");
+            serialized.extend_from_slice(policy.as_bytes());
+        }
     }
     
     // For Split: include components in signature
@@ -59,11 +68,25 @@ fn sign_mistborn_asset_tx(
         if let Some(components_str) = data.metadata.get("_components") {
             serialized.extend_from_slice(components_str.as_bytes());
         }
+        if let Some(manifest) = data.metadata.get("_component_manifest") {
+            serialized.extend_from_slice(b"_component_manifest");
+            serialized.extend_from_slice(manifest.as_bytes());
+        }
     }
     
-    // fee и nonce — в тестах всегда 0
-    serialized.extend_from_slice(&0u64.to_le_bytes());
+    if let Some(zone) = data.metadata.get("_zone") {
+        serialized.extend_from_slice(b"_zone");
+        serialized.extend_from_slice(zone.as_bytes());
+    }
+    
+    if let Some(paymaster) = data.metadata.get("_paymaster") {
+        serialized.extend_from_slice(b"_paymaster");
+        serialized.extend_from_slice(paymaster.as_bytes());
+    }
+    
+    // fee — в тестах всегда 0
     serialized.extend_from_slice(&0u64.to_le_bytes());
+    serialized.extend_from_slice(&nonce.to_le_bytes());
     
     keypair.sign(&serialized)
 }
@@ -77,7 +100,7 @@ fn sign_transfer_tx(
     fee: u64,
     nonce: u64,
 ) -> Vec<u8> {
-    let mut data = Vec::new();
+    let mut data = haze::types::network_id(0, &[]).to_vec();
     data.extend_from_slice(b"Transfer");
     data.extend_from_slice(from);
     data.extend_from_slice(to);
@@ -125,7 +148,7 @@ async fn test_load_create_many_assets() {
             game_id: None,
             owner,
         };
-        let signature = sign_mistborn_asset_tx(&keypair, &AssetAction::Create, &asset_id, &data);
+        let signature = sign_mistborn_asset_tx(&keypair, &AssetAction::Create, &asset_id, &data, i as u64);
         
         let tx = Transaction::MistbornAsset {
             from: owner,
@@ -133,7 +156,7 @@ async fn test_load_create_many_assets() {
             asset_id,
             data,
             fee: 0,
-            nonce: 0,
+            nonce: i as u64,
             chain_id: None,
             valid_until_height: None,
             signature,
@@ -186,7 +209,7 @@ async fn test_load_batch_operations() {
             game_id: None,
             owner,
         };
-        let signature = sign_mistborn_asset_tx(&keypair, &AssetAction::Create, &asset_id, &data);
+        let signature = sign_mistborn_asset_tx(&keypair, &AssetAction::Create, &asset_id, &data, i as u64);
         
         let tx = Transaction::MistbornAsset {
             from: owner,
@@ -194,7 +217,7 @@ async fn test_load_batch_operations() {
             asset_id,
             data,
             fee: 0,
-            nonce: 0,
+            nonce: i as u64,
             chain_id: None,
             valid_until_height: None,
             signature,
@@ -237,7 +260,7 @@ async fn test_load_search_performance() {
             game_id: Some("load_test_game".to_string()),
             owner,
         };
-        let signature = sign_mistborn_asset_tx(&keypair, &AssetAction::Create, &asset_id, &data);
+        let signature = sign_mistborn_asset_tx(&keypair, &AssetAction::Create, &asset_id, &data, i as u64);
         
         let tx = Transaction::MistbornAsset {
             from: owner,
@@ -245,7 +268,7 @@ async fn test_load_search_performance() {
             asset_id,
             data,
             fee: 0,
-            nonce: 0,
+            nonce: i as u64,
             chain_id: None,
             valid_until_height: None,
             signature,
@@ -336,11 +359,11 @@ async fn test_load_mixed_transfers_and_assets() {
     const FEE_PER_TX: u64 = 1;
     let start = Instant::now();
 
-    // Add and process one block per "round" (one transfer + one asset) so transfer nonce order is preserved
+    // Add and process one block per "round" (one transfer + one asset); both share the owner's nonces
     let mut blocks_created = 0;
+    let mut nonce = 0u64;
     for i in 0..TRANSFER_COUNT.max(ASSET_COUNT) {
         if i < TRANSFER_COUNT {
-            let nonce = i as u64;
             let signature = sign_transfer_tx(&keypair, &owner, &recipient, AMOUNT_PER_TX, FEE_PER_TX, nonce);
             let tx = Transaction::Transfer {
                 from: owner,
@@ -353,6 +376,7 @@ async fn test_load_mixed_transfers_and_assets() {
                 signature,
             };
             consensus.add_transaction(tx).unwrap();
+            nonce += 1;
         }
         if i < ASSET_COUNT {
             let asset_id = haze::types::sha256(&format!("mixed_asset_{}", i).as_bytes());
@@ -363,19 +387,20 @@ async fn test_load_mixed_transfers_and_assets() {
                 game_id: None,
                 owner,
             };
-            let signature = sign_mistborn_asset_tx(&keypair, &AssetAction::Create, &asset_id, &data);
+            let signature = sign_mistborn_asset_tx(&keypair, &AssetAction::Create, &asset_id, &data, nonce);
             let tx = Transaction::MistbornAsset {
                 from: owner,
                 action: AssetAction::Create,
                 asset_id,
                 data,
                 fee: 0,
-                nonce: 0,
+                nonce,
                 chain_id: None,
                 valid_until_height: None,
                 signature,
             };
             consensus.add_transaction(tx).unwrap();
+            nonce += 1;
         }
         let block = consensus.create_block(owner).unwrap();
         consensus.process_block(&block).unwrap();
@@ -421,14 +446,14 @@ async fn test_stress_many_assets_per_account() {
             game_id: None,
             owner,
         };
-        let signature = sign_mistborn_asset_tx(&keypair, &AssetAction::Create, &asset_id, &data);
+        let signature = sign_mistborn_asset_tx(&keypair, &AssetAction::Create, &asset_id, &data, i as u64);
         let tx = Transaction::MistbornAsset {
             from: owner,
             action: AssetAction::Create,
             asset_id,
             data,
             fee: 0,
-            nonce: 0,
+            nonce: i as u64,
             chain_id: None,
             valid_until_height: None,
             signature,
@@ -3,3 +3,5 @@
 mod api_e2e;
 mod multi_node;
 mod load_test;
+mod blob_s3;
+mod node_e2e;
@@ -4,6 +4,7 @@ use std::path::PathBuf;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
+use haze::byzantine::ByzantineBehavior;
 use haze::config::Config;
 use haze::consensus::ConsensusEngine;
 use haze::crypto::KeyPair;
@@ -19,8 +20,10 @@ fn sign_mistborn_asset_tx(
     action: &AssetAction,
     asset_id: &haze::types::Hash,
     data: &AssetData,
+    nonce: u64,
 ) -> Vec<u8> {
-    let mut serialized = Vec::new();
+    // Network id of the default config (chain_id 0) prefixes every payload
+    let mut serialized = haze::types::network_id(0, &[]).to_vec();
     serialized.extend_from_slice(b"MistbornAsset");
     // from (signer) — в тестах это всегда владелец
     serialized.extend_from_slice(&data.owner);
@@ -32,6 +35,9 @@ fn sign_mistborn_asset_tx(
         AssetAction::Evaporate => 3,
         AssetAction::Merge => 4,
         AssetAction::Split => 5,
+        AssetAction::Reveal => 6,
+        AssetAction::Breed => 7,
+        AssetAction::Upgrade => 8,
     });
     serialized.extend_from_slice(asset_id);
     serialized.extend_from_slice(&data.owner);
@@ -60,9 +66,9 @@ fn sign_mistborn_asset_tx(
         }
     }
     
-    // fee и nonce — в тестах всегда 0
-    serialized.extend_from_slice(&0u64.to_le_bytes());
+    // fee — в тестах всегда 0
     serialized.extend_from_slice(&0u64.to_le_bytes());
+    serialized.extend_from_slice(&nonce.to_le_bytes());
     
     keypair.sign(&serialized)
 }
@@ -104,7 +110,7 @@ async fn test_multi_node_asset_sync() {
         game_id: Some("test_game".to_string()),
         owner,
     };
-    let signature = sign_mistborn_asset_tx(&keypair1, &AssetAction::Create, &asset_id, &data);
+    let signature = sign_mistborn_asset_tx(&keypair1, &AssetAction::Create, &asset_id, &data, 0);
     
     let tx = Transaction::MistbornAsset {
         from: owner,
@@ -167,7 +173,7 @@ async fn test_multi_node_block_chain_sync() {
             game_id: None,
             owner,
         };
-        let signature = sign_mistborn_asset_tx(&keypair1, &AssetAction::Create, &asset_id, &data);
+        let signature = sign_mistborn_asset_tx(&keypair1, &AssetAction::Create, &asset_id, &data, i);
         
         let tx = Transaction::MistbornAsset {
             from: owner,
@@ -175,7 +181,7 @@ async fn test_multi_node_block_chain_sync() {
             asset_id,
             data,
             fee: 0,
-            nonce: 0,
+            nonce: i,
             chain_id: None,
             valid_until_height: None,
             signature,
@@ -203,3 +209,87 @@ async fn test_multi_node_block_chain_sync() {
         assert!(state2.get_asset(&asset_id).is_some());
     }
 }
+
+/// Signed asset-creation transaction from `keypair` (used to give blocks content)
+fn create_asset_tx(keypair: &KeyPair, name: &str) -> Transaction {
+    let owner = keypair.address();
+    let asset_id = haze::types::sha256(name.as_bytes());
+    let data = AssetData {
+        density: DensityLevel::Ethereal,
+        metadata: std::collections::HashMap::new(),
+        attributes: vec![],
+        game_id: None,
+        owner,
+    };
+    let signature = sign_mistborn_asset_tx(keypair, &AssetAction::Create, &asset_id, &data, 0);
+    Transaction::MistbornAsset {
+        from: owner,
+        action: AssetAction::Create,
+        asset_id,
+        data,
+        fee: 0,
+        nonce: 0,
+        chain_id: None,
+        valid_until_height: None,
+        signature,
+    }
+}
+
+#[tokio::test]
+async fn test_byzantine_equivocation_is_rejected_with_evidence() {
+    let (_state1, byzantine, keypair1) = create_test_node(1);
+    let (state2, honest, _keypair2) = create_test_node(2);
+    let validator = keypair1.address();
+    state2.create_test_account(validator, 100_000, 0);
+
+    byzantine.set_byzantine_behavior(ByzantineBehavior::Equivocate);
+    byzantine.add_transaction(create_asset_tx(&keypair1, "equivocation_asset")).unwrap();
+    let block = byzantine.create_block(validator).unwrap();
+    let twin = byzantine.create_equivocating_block(&block).unwrap();
+    assert_eq!(twin.header.height, block.header.height);
+    assert_ne!(twin.header.hash, block.header.hash);
+
+    honest.process_block(&block).unwrap();
+    assert!(honest.process_block(&twin).is_err());
+    assert_eq!(state2.current_height(), 1);
+
+    let evidence = honest.get_equivocation_evidence();
+    assert_eq!(evidence.len(), 1);
+    assert_eq!(evidence[0].validator, validator);
+    assert_eq!(evidence[0].height, 1);
+    assert_eq!(evidence[0].first, block.header.hash);
+    assert_eq!(evidence[0].second, twin.header.hash);
+
+    // Honest nodes never produce twins
+    assert!(honest.create_equivocating_block(&block).is_none());
+}
+
+#[tokio::test]
+async fn test_byzantine_invalid_references_are_rejected() {
+    let (_state1, byzantine, keypair1) = create_test_node(1);
+    let (state2, honest, _keypair2) = create_test_node(2);
+    let validator = keypair1.address();
+    state2.create_test_account(validator, 100_000, 0);
+
+    byzantine.set_byzantine_behavior(ByzantineBehavior::InvalidReferences);
+    byzantine.add_transaction(create_asset_tx(&keypair1, "invalid_refs_asset")).unwrap();
+    let block = byzantine.create_block(validator).unwrap();
+
+    let err = honest.process_block(&block).unwrap_err();
+    assert!(err.to_string().contains("does not exist"), "{}", err);
+    assert_eq!(state2.current_height(), 0);
+    assert!(honest.get_equivocation_evidence().is_empty());
+}
+
+#[tokio::test]
+async fn test_byzantine_withholding_node_stops_sharing_blocks() {
+    let (_state1, byzantine, _keypair1) = create_test_node(1);
+    assert!(byzantine.shares_blocks());
+
+    byzantine.set_byzantine_behavior(ByzantineBehavior::WithholdBlocks);
+    assert!(!byzantine.shares_blocks());
+    assert_eq!(byzantine.byzantine_behavior(), ByzantineBehavior::WithholdBlocks);
+
+    byzantine.set_byzantine_behavior(ByzantineBehavior::Honest);
+    assert!(byzantine.shares_blocks());
+}
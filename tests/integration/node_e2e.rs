@@ -0,0 +1,241 @@
+//! Full-node tests: boot the `haze` binary and drive it over its real HTTP and WebSocket API
+//!
+//! Unlike `api_e2e`, which calls the router in-process, these tests go through `main.rs`:
+//! configuration, wiring of state, consensus, block production and the API server. The node
+//! runs as a dev node (`haze --dev`): in-memory database in a scratch directory, a block per
+//! transaction, a faucet to fund accounts and relaxed signatures so tests need no signing.
+
+use std::process::{Child, Command, Stdio};
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use tokio_tungstenite::tungstenite::Message;
+
+/// How long a node may take to start, and a transaction to be sealed
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A running `haze --dev` process, killed on drop
+struct Node {
+    child: Child,
+    api: String,
+    client: reqwest::Client,
+    /// Working directory of the process, removed after it stops
+    _workdir: tempfile::TempDir,
+}
+
+impl Node {
+    /// Start a dev node with its API on a free port and wait until it serves `/health`
+    async fn start() -> Node {
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let api = format!("127.0.0.1:{}", port);
+        let workdir = tempfile::tempdir().unwrap();
+        let child = Command::new(env!("CARGO_BIN_EXE_haze"))
+            .args(["--dev", "--relaxed-signatures", "--api", &api])
+            .current_dir(workdir.path())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("failed to start the haze binary");
+        let node = Node { child, api, client: reqwest::Client::new(), _workdir: workdir };
+
+        let started = std::time::Instant::now();
+        loop {
+            if let Ok(response) = node.client.get(node.url("/health")).send().await
+                && response.status().is_success()
+            {
+                return node;
+            }
+            assert!(started.elapsed() < TIMEOUT, "node did not come up on {}", node.api);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("http://{}{}", self.api, path)
+    }
+
+    /// GET `path`: status and JSON body (`null` when the body is not JSON)
+    async fn get(&self, path: &str) -> (u16, serde_json::Value) {
+        let response = self.client.get(self.url(path)).send().await.unwrap();
+        let status = response.status().as_u16();
+        let body = response.text().await.unwrap();
+        (status, serde_json::from_str(&body).unwrap_or(serde_json::Value::Null))
+    }
+
+    /// POST a JSON body to `path`: status and JSON body
+    async fn post(&self, path: &str, body: serde_json::Value) -> (u16, serde_json::Value) {
+        let response = self.client
+            .post(self.url(path))
+            .header("content-type", "application/json")
+            .body(body.to_string())
+            .send()
+            .await
+            .unwrap();
+        let status = response.status().as_u16();
+        let body = response.text().await.unwrap();
+        (status, serde_json::from_str(&body).unwrap_or(serde_json::Value::Null))
+    }
+
+    /// Submit a transaction to `path` and wait for its receipt
+    async fn submit(&self, path: &str, transaction: serde_json::Value) -> serde_json::Value {
+        let (status, body) = self.post(path, serde_json::json!({ "transaction": transaction })).await;
+        assert_eq!(status, 200, "{} rejected: {}", path, body);
+        let hash = body["data"]["hash"].as_str().unwrap().to_string();
+        self.receipt(&hash).await
+    }
+
+    /// Receipt of transaction `hash`, polled until it is sealed
+    async fn receipt(&self, hash: &str) -> serde_json::Value {
+        let started = std::time::Instant::now();
+        loop {
+            let (status, body) = self.get(&format!("/api/v1/transactions/{}/receipt", hash)).await;
+            if status == 200 {
+                return body["data"].clone();
+            }
+            assert!(started.elapsed() < TIMEOUT, "transaction {} was not sealed", hash);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }
+}
+
+impl Drop for Node {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// A MistbornAsset transaction with a dummy signature (the node runs with relaxed signatures)
+fn asset_tx(
+    owner: &str,
+    action: &str,
+    asset_id: &str,
+    density: &str,
+    metadata: serde_json::Value,
+    nonce: u64,
+) -> serde_json::Value {
+    serde_json::json!({
+        "MistbornAsset": {
+            "from": owner,
+            "action": action,
+            "asset_id": asset_id,
+            "data": {
+                "density": density,
+                "metadata": metadata,
+                "attributes": [],
+                "game_id": "e2e-game",
+                "owner": owner,
+            },
+            "fee": 0,
+            "nonce": nonce,
+            "signature": hex::encode([1u8; 64]),
+        }
+    })
+}
+
+#[tokio::test]
+async fn node_e2e_asset_lifecycle_over_http_and_ws() {
+    let node = Node::start().await;
+
+    // Every event of the node, in the current schema
+    let (mut ws, _) = tokio_tungstenite::connect_async(format!(
+        "ws://{}/api/v1/ws?event_version={}", node.api, haze::ws_events::EVENT_VERSION
+    ))
+    .await
+    .unwrap();
+    ws.send(Message::Text(serde_json::json!({ "subscribe": [] }).to_string())).await.unwrap();
+
+    // A fresh account, funded through the dev faucet
+    let player = hex::encode([0x42u8; 32]);
+    assert_eq!(node.get(&format!("/api/v1/accounts/{}", player)).await.0, 404);
+    let (status, body) = node.post("/api/v1/dev/faucet", serde_json::json!({ "address": player, "amount": 1_000_000 })).await;
+    assert_eq!(status, 200, "faucet failed: {}", body);
+    let (status, account) = node.get(&format!("/api/v1/accounts/{}", player)).await;
+    assert_eq!(status, 200);
+    assert_eq!(account["data"]["balance"], 1_000_000);
+
+    // Create two assets, condense one, then merge the other into it
+    let sword = hex::encode([0x51u8; 32]);
+    let shield = hex::encode([0x52u8; 32]);
+    let created = node.submit(
+        "/api/v1/assets",
+        asset_tx(&player, "Create", &sword, "Ethereal", serde_json::json!({ "name": "Sword" }), 0),
+    ).await;
+    assert_eq!(created["status"], "executed");
+    node.submit(
+        "/api/v1/assets",
+        asset_tx(&player, "Create", &shield, "Ethereal", serde_json::json!({ "name": "Shield", "rarity": "epic" }), 1),
+    ).await;
+    node.submit(
+        &format!("/api/v1/assets/{}/condense", sword),
+        asset_tx(&player, "Condense", &sword, "Light", serde_json::json!({}), 2),
+    ).await;
+    let merged = node.submit(
+        &format!("/api/v1/assets/{}/merge", sword),
+        asset_tx(&player, "Merge", &sword, "Light", serde_json::json!({ "_other_asset_id": shield }), 3),
+    ).await;
+    assert_eq!(merged["status"], "executed", "{}", merged);
+
+    let (status, asset) = node.get(&format!("/api/v1/assets/{}", sword)).await;
+    assert_eq!(status, 200);
+    assert_eq!(asset["data"]["owner"], player);
+    assert_eq!(asset["data"]["density"], "Light");
+    assert_eq!(asset["data"]["metadata"]["rarity"], "epic");
+    assert_eq!(node.get(&format!("/api/v1/assets/{}", shield)).await.0, 404);
+
+    // One block per transaction: the faucet transfer and the four asset operations
+    let (_, info) = node.get("/api/v1/blockchain/info").await;
+    assert_eq!(info["data"]["current_height"], 5);
+    assert_eq!(info["data"]["chain_id"], haze::dev::DEV_CHAIN_ID);
+    let (status, block) = node.get(&format!("/api/v1/blocks/height/{}", merged["block_height"])).await;
+    assert_eq!(status, 200);
+    assert_eq!(block["data"]["hash"], merged["block_hash"]);
+    assert_eq!(block["data"]["transaction_count"], 1);
+    let (_, full) = node.get(&format!("/api/v1/blocks/{}/full", created["block_hash"].as_str().unwrap())).await;
+    assert_eq!(full["data"]["transactions"][0]["receipt"], created);
+
+    // The WebSocket saw the asset operations in order
+    let mut events = Vec::new();
+    while events.len() < 4 {
+        let message = tokio::time::timeout(TIMEOUT, ws.next()).await
+            .expect("timed out waiting for WebSocket events")
+            .unwrap()
+            .unwrap();
+        if let Message::Text(text) = message {
+            let event: serde_json::Value = serde_json::from_str(&text).unwrap();
+            // Finality events depend on wave timing
+            if event["type"] != "asset_finalized" {
+                events.push(event["type"].as_str().unwrap().to_string());
+            }
+        }
+    }
+    assert_eq!(events, ["asset_created", "asset_created", "asset_condensed", "asset_merged"]);
+}
+
+#[tokio::test]
+async fn node_e2e_rejects_invalid_requests() {
+    let node = Node::start().await;
+
+    // Malformed and unknown hashes
+    assert_eq!(node.get("/api/v1/transactions/not-a-hash/receipt").await.0, 400);
+    assert_eq!(node.get(&format!("/api/v1/transactions/{}/receipt", hex::encode([7u8; 32]))).await.0, 404);
+    assert_eq!(node.get("/api/v1/blocks/height/1").await.0, 404);
+
+    // A transfer from an account without funds is refused with an error code
+    let (status, body) = node.post("/api/v1/transactions", serde_json::json!({
+        "transaction": {
+            "Transfer": {
+                "from": hex::encode([0x61u8; 32]),
+                "to": hex::encode([0x62u8; 32]),
+                "amount": 10,
+                "fee": 1,
+                "nonce": 0,
+                "signature": hex::encode([0u8; 64]),
+            }
+        }
+    })).await;
+    assert_eq!(status, 400);
+    assert_eq!(body["success"], false);
+    assert!(body["error_code"].as_u64().is_some(), "no error code in {}", body);
+    assert_eq!(node.get("/api/v1/blockchain/info").await.1["data"]["current_height"], 0);
+}